@@ -0,0 +1,102 @@
+//! Dose logging from the command line
+
+use anyhow::{bail, Context, Result};
+use peptrack_core::{DoseLog, PeptideProtocol};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// The protocol `query` names: its id, or a unique protocol or peptide name
+/// (case-insensitive)
+pub fn find_protocol<'a>(protocols: &'a [PeptideProtocol], query: &str) -> Result<&'a PeptideProtocol> {
+    let query = query.trim();
+    if let Some(protocol) = protocols.iter().find(|protocol| protocol.id == query) {
+        return Ok(protocol);
+    }
+
+    let by_name: Vec<_> = protocols
+        .iter()
+        .filter(|protocol| protocol.name.eq_ignore_ascii_case(query))
+        .collect();
+    let matches = if by_name.is_empty() {
+        protocols
+            .iter()
+            .filter(|protocol| protocol.peptide_name.eq_ignore_ascii_case(query))
+            .collect()
+    } else {
+        by_name
+    };
+
+    match matches.as_slice() {
+        [protocol] => Ok(protocol),
+        [] => bail!("No protocol named \"{}\"; list them with `peptrack-cli protocols`", query),
+        _ => bail!(
+            "\"{}\" matches {} protocols; use the protocol id instead",
+            query,
+            matches.len()
+        ),
+    }
+}
+
+/// A dose of `protocol`, taken `at` (RFC 3339) or now
+pub fn new_dose(
+    protocol: &PeptideProtocol,
+    amount_mg: f32,
+    site: &str,
+    notes: Option<String>,
+    at: Option<&str>,
+) -> Result<DoseLog> {
+    if !amount_mg.is_finite() || amount_mg <= 0.0 {
+        bail!("Dose amount must be greater than zero");
+    }
+    if site.trim().is_empty() {
+        bail!("Injection site is required");
+    }
+
+    let mut dose = DoseLog::new(protocol.id.as_str(), site.trim(), amount_mg);
+    dose.notes = notes.filter(|notes| !notes.trim().is_empty());
+    if let Some(at) = at {
+        dose.logged_at = OffsetDateTime::parse(at, &Rfc3339)
+            .with_context(|| format!("Invalid time \"{}\"; use RFC 3339, e.g. 2025-03-01T08:00:00Z", at))?;
+    }
+    Ok(dose)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn finds_protocol_by_id_name_or_peptide() {
+        let healing = PeptideProtocol::new("Healing", "BPC-157");
+        let recovery = PeptideProtocol::new("Recovery", "TB-500");
+        let protocols = vec![healing.clone(), recovery.clone()];
+
+        assert_eq!(find_protocol(&protocols, &recovery.id).unwrap().id, recovery.id);
+        assert_eq!(find_protocol(&protocols, "healing").unwrap().id, healing.id);
+        assert_eq!(find_protocol(&protocols, "tb-500").unwrap().id, recovery.id);
+        assert!(find_protocol(&protocols, "GHK-Cu").is_err());
+    }
+
+    #[test]
+    fn ambiguous_peptide_name_is_an_error() {
+        let protocols = vec![
+            PeptideProtocol::new("Morning", "BPC-157"),
+            PeptideProtocol::new("Evening", "BPC-157"),
+        ];
+        let err = find_protocol(&protocols, "BPC-157").unwrap_err();
+        assert!(err.to_string().contains("matches 2 protocols"));
+    }
+
+    #[test]
+    fn new_dose_parses_time_and_validates_amount() {
+        let protocol = PeptideProtocol::new("Healing", "BPC-157");
+        let dose = new_dose(&protocol, 0.25, " abdomen ", None, Some("2025-03-01T08:00:00Z")).unwrap();
+        assert_eq!(dose.site, "abdomen");
+        assert_eq!(dose.logged_at, datetime!(2025-03-01 08:00 UTC));
+
+        assert!(new_dose(&protocol, 0.0, "abdomen", None, None).is_err());
+        assert!(new_dose(&protocol, 0.25, "abdomen", None, Some("yesterday")).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+//! Backups and CSV exports
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use peptrack_core::backup::{AttachmentBackupOptions, BackupData};
+use peptrack_core::{DoseLog, InventoryItem, PeptideProtocol, StorageManager};
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+use crate::unlock::prompt;
+
+/// Read instead of prompting when encrypting a backup
+pub const BACKUP_PASSWORD_ENV: &str = "PEPTRACK_BACKUP_PASSWORD";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CsvTable {
+    Doses,
+    Protocols,
+    Inventory,
+}
+
+pub struct BackupOptions {
+    pub output: Option<PathBuf>,
+    pub encrypt: bool,
+    pub attachments: AttachmentBackupOptions,
+}
+
+/// Write a backup file the app can restore; returns its path
+pub fn write_backup(storage: &StorageManager, options: &BackupOptions) -> Result<PathBuf> {
+    storage
+        .verify_integrity()
+        .context("Cannot back up a corrupted database")?;
+
+    let backup = BackupData::collect(storage, &options.attachments)?;
+    let json = serde_json::to_string_pretty(&backup).context("Failed to serialize backup")?;
+    let contents = if options.encrypt {
+        let password = match std::env::var(BACKUP_PASSWORD_ENV) {
+            Ok(password) => password,
+            Err(_) => prompt("Backup password: ")?,
+        };
+        if password.is_empty() {
+            bail!("Backup password is empty");
+        }
+        peptrack_core::encrypt_backup(&json, &password)?
+    } else {
+        json
+    };
+
+    let path = match &options.output {
+        Some(path) => path.clone(),
+        None => default_backup_path()?,
+    };
+    std::fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// `peptrack_backup_<timestamp>.json` in Downloads, like the app
+fn default_backup_path() -> Result<PathBuf> {
+    let timestamp = OffsetDateTime::now_utc()
+        .format(format_description!("[year]-[month]-[day]_[hour]-[minute]"))
+        .unwrap_or_else(|_| "backup".to_string());
+    let dir = dirs::download_dir()
+        .or_else(dirs::document_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+    Ok(dir.join(format!("peptrack_backup_{}.json", timestamp)))
+}
+
+/// Render `table` as CSV
+pub fn export_csv(storage: &StorageManager, table: CsvTable) -> Result<String> {
+    let protocols = storage.list_protocols()?;
+    match table {
+        CsvTable::Doses => doses_csv(&storage.list_dose_logs()?, &protocols),
+        CsvTable::Protocols => protocols_csv(&protocols),
+        CsvTable::Inventory => inventory_csv(&storage.list_inventory()?, &protocols),
+    }
+}
+
+fn timestamp(value: OffsetDateTime) -> String {
+    value.format(&Rfc3339).unwrap_or_else(|_| value.to_string())
+}
+
+fn protocol_names(protocols: &[PeptideProtocol]) -> HashMap<&str, &str> {
+    protocols
+        .iter()
+        .map(|protocol| (protocol.id.as_str(), protocol.name.as_str()))
+        .collect()
+}
+
+fn finish(writer: csv::Writer<Vec<u8>>) -> Result<String> {
+    let bytes = writer.into_inner().context("Failed to write CSV")?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+pub(crate) fn doses_csv(doses: &[DoseLog], protocols: &[PeptideProtocol]) -> Result<String> {
+    let names = protocol_names(protocols);
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["logged_at", "protocol", "site", "amount_mg", "notes", "id"])?;
+    for dose in doses {
+        writer.write_record([
+            timestamp(dose.logged_at),
+            names.get(dose.protocol_id.as_str()).unwrap_or(&"").to_string(),
+            dose.site.clone(),
+            dose.amount_mg.to_string(),
+            dose.notes.clone().unwrap_or_default(),
+            dose.id.clone(),
+        ])?;
+    }
+    finish(writer)
+}
+
+pub(crate) fn protocols_csv(protocols: &[PeptideProtocol]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record([
+        "name",
+        "peptide",
+        "target_concentration_mg_ml",
+        "tags",
+        "notes",
+        "created_at",
+        "id",
+    ])?;
+    for protocol in protocols {
+        writer.write_record([
+            protocol.name.clone(),
+            protocol.peptide_name.clone(),
+            protocol
+                .target_concentration_mg_ml
+                .map(|value| value.to_string())
+                .unwrap_or_default(),
+            protocol.tags.join(";"),
+            protocol.notes.clone().unwrap_or_default(),
+            timestamp(protocol.created_at),
+            protocol.id.clone(),
+        ])?;
+    }
+    finish(writer)
+}
+
+pub(crate) fn inventory_csv(items: &[InventoryItem], protocols: &[PeptideProtocol]) -> Result<String> {
+    let names = protocol_names(protocols);
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record([
+        "protocol",
+        "vial_number",
+        "vial_status",
+        "quantity_mg",
+        "remaining_mg",
+        "cost_per_mg",
+        "currency",
+        "expiry_date",
+        "id",
+    ])?;
+    let optional = |value: Option<f32>| value.map(|value| value.to_string()).unwrap_or_default();
+    for item in items {
+        writer.write_record([
+            names.get(item.protocol_id.as_str()).unwrap_or(&"").to_string(),
+            item.vial_number.clone().unwrap_or_default(),
+            format!("{:?}", item.vial_status),
+            optional(item.quantity_mg),
+            optional(item.quantity_remaining_mg),
+            optional(item.cost_per_mg),
+            item.currency.clone(),
+            item.expiry_date.map(timestamp).unwrap_or_default(),
+            item.id.clone(),
+        ])?;
+    }
+    finish(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn doses_csv_names_protocols_and_quotes_notes() {
+        let protocol = PeptideProtocol::new("Healing", "BPC-157");
+        let mut dose = DoseLog::new(protocol.id.as_str(), "abdomen", 0.25);
+        dose.logged_at = datetime!(2025-03-01 08:00 UTC);
+        dose.notes = Some("felt fine, no redness".into());
+
+        let csv = doses_csv(&[dose.clone()], &[protocol]).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("logged_at,protocol,site,amount_mg,notes,id"));
+        assert_eq!(
+            lines.next().unwrap(),
+            format!("2025-03-01T08:00:00Z,Healing,abdomen,0.25,\"felt fine, no redness\",{}", dose.id)
+        );
+    }
+
+    #[test]
+    fn protocols_csv_joins_tags() {
+        let mut protocol = PeptideProtocol::new("Healing", "BPC-157");
+        protocol.tags = vec!["injury".into(), "morning".into()];
+
+        let csv = protocols_csv(&[protocol]).unwrap();
+        assert!(csv.lines().nth(1).unwrap().starts_with("Healing,BPC-157,,injury;morning,"));
+    }
+}
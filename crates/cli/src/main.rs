@@ -0,0 +1,358 @@
+//! PepTrack CLI - Headless access to the PepTrack database
+//!
+//! Runs the non-GUI operations of the desktop app against the same
+//! encrypted database: backups, CSV exports, health checks, literature
+//! search and dose logging. Useful for scripting, and for getting at the
+//! data when the app won't start.
+//!
+//! ```text
+//! peptrack-cli backup --output ~/peptrack.json --encrypt
+//! peptrack-cli export-csv doses > doses.csv
+//! peptrack-cli log-dose --protocol "BPC-157" --amount-mg 0.25 --site abdomen
+//! ```
+
+mod doses;
+mod export;
+mod unlock;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use peptrack_core::backup::AttachmentBackupOptions;
+use peptrack_core::{AsyncStorage, LiteratureEntry, ResponseCacheSettings, StorageManager};
+use peptrack_literature::{
+    search_all, CachedFetcher, CrossrefFetcher, EuropePmcFetcher, LiteratureFetcher,
+    OpenAlexFetcher, PubMedFetcher, RelevanceContext,
+};
+use peptrack_local_ai::{AiClientConfig, LocalAiOrchestrator};
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use crate::export::{BackupOptions, CsvTable};
+
+#[derive(Debug, Parser)]
+#[command(name = "peptrack-cli", version, about = "Headless access to the PepTrack database")]
+struct Cli {
+    /// PepTrack data directory; defaults to the one the app uses
+    #[arg(long, global = true, env = "PEPTRACK_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Print results as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Write a backup file the app can restore
+    Backup {
+        /// Defaults to peptrack_backup_<timestamp>.json in Downloads
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Encrypt with a password from PEPTRACK_BACKUP_PASSWORD or stdin
+        #[arg(long)]
+        encrypt: bool,
+        /// Include photo attachments
+        #[arg(long)]
+        include_photos: bool,
+        /// Leave out document attachments such as COAs
+        #[arg(long)]
+        no_documents: bool,
+    },
+    /// Export a table as CSV
+    ExportCsv {
+        table: CsvTable,
+        /// Defaults to stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Check the database, and optionally the local AI providers
+    Health {
+        /// Also send each AI CLI a test prompt
+        #[arg(long)]
+        ai: bool,
+    },
+    /// Search literature and cache the results
+    SearchLiterature {
+        query: String,
+        /// pubmed, openalex, crossref or europepmc; repeat for several
+        #[arg(long = "source", default_values = ["pubmed", "openalex"])]
+        sources: Vec<String>,
+        /// Results per source; large limits are fetched page by page
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        /// Search the local cache instead of the online sources
+        #[arg(long)]
+        cached: bool,
+        /// Call the online sources even if the same search was cached recently
+        #[arg(long, conflicts_with = "cached")]
+        refresh: bool,
+    },
+    /// List protocols with their ids
+    Protocols,
+    /// Log a dose
+    LogDose {
+        /// Protocol id, name or peptide name
+        #[arg(long)]
+        protocol: String,
+        #[arg(long)]
+        amount_mg: f32,
+        #[arg(long)]
+        site: String,
+        #[arg(long)]
+        notes: Option<String>,
+        /// When the dose was taken (RFC 3339); defaults to now
+        #[arg(long)]
+        at: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {:#}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let json = cli.json;
+    let open = || -> Result<StorageManager> {
+        let data_dir = unlock::resolve_data_dir(cli.data_dir.clone())?;
+        unlock::open_storage(&data_dir)
+    };
+
+    match cli.command {
+        Command::Backup {
+            output,
+            encrypt,
+            include_photos,
+            no_documents,
+        } => {
+            let storage = open()?;
+            let path = export::write_backup(
+                &storage,
+                &BackupOptions {
+                    output,
+                    encrypt,
+                    attachments: AttachmentBackupOptions {
+                        include_documents: !no_documents,
+                        include_photos,
+                    },
+                },
+            )?;
+            print(json, &path, |path| format!("Backup written to {}", path.display()))
+        }
+        Command::ExportCsv { table, output } => {
+            let csv = export::export_csv(&open()?, table)?;
+            match output {
+                Some(path) => std::fs::write(&path, csv)
+                    .with_context(|| format!("Failed to write {}", path.display())),
+                None => {
+                    print!("{}", csv);
+                    Ok(())
+                }
+            }
+        }
+        Command::Health { ai } => health(&open()?, ai, json).await,
+        Command::SearchLiterature {
+            query,
+            sources,
+            limit,
+            cached,
+            refresh,
+        } => {
+            let storage = Arc::new(open()?);
+            let entries = if cached {
+                let mut entries = storage.search_literature(&query)?;
+                entries.truncate(limit);
+                entries
+            } else {
+                search_online(&storage, &query, &sources, limit, refresh).await?
+            };
+            print(json, &entries, |entries| {
+                entries
+                    .iter()
+                    .map(|entry| {
+                        format!(
+                            "[{}] {}{}",
+                            entry.source,
+                            entry.title,
+                            entry.url.as_deref().map(|url| format!("\n    {}", url)).unwrap_or_default()
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+        }
+        Command::Protocols => {
+            let protocols = open()?.list_protocols()?;
+            print(json, &protocols, |protocols| {
+                protocols
+                    .iter()
+                    .map(|p| format!("{}  {} ({})", p.id, p.name, p.peptide_name))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+        }
+        Command::LogDose {
+            protocol,
+            amount_mg,
+            site,
+            notes,
+            at,
+        } => {
+            let storage = open()?;
+            let protocols = storage.list_protocols()?;
+            let protocol = doses::find_protocol(&protocols, &protocol)?;
+            let dose = doses::new_dose(protocol, amount_mg, &site, notes, at.as_deref())?;
+            storage.append_dose_log(&dose)?;
+            print(json, &dose, |dose| {
+                format!("Logged {} mg of {} at {} ({})", dose.amount_mg, protocol.name, dose.site, dose.id)
+            })
+        }
+    }
+}
+
+/// Print `value` as JSON, or as the text `describe` makes of it
+fn print<T: Serialize>(json: bool, value: &T, describe: impl FnOnce(&T) -> String) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(value)?);
+    } else {
+        let text = describe(value);
+        if !text.is_empty() {
+            println!("{}", text);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AiProviderStatus {
+    provider: String,
+    model: String,
+    healthy: bool,
+    latency_ms: Option<u64>,
+    error: Option<String>,
+}
+
+async fn health(storage: &StorageManager, ai: bool, json: bool) -> Result<()> {
+    let report = storage.health_check()?;
+    let providers: Vec<AiProviderStatus> = if ai {
+        LocalAiOrchestrator::detect(AiClientConfig::default())
+            .health_check(peptrack_local_ai::health::DEFAULT_HEALTH_TIMEOUT)
+            .await
+            .providers
+            .into_iter()
+            .map(|health| AiProviderStatus {
+                provider: format!("{:?}", health.provider),
+                model: health.model,
+                healthy: health.healthy,
+                latency_ms: health.latency.map(|latency| latency.as_millis() as u64),
+                error: health.error,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "database": report,
+                "aiProviders": providers,
+            }))?
+        );
+    } else {
+        println!(
+            "Database: {} ({:.2} MB, WAL {}, foreign keys {})",
+            report.integrity_result,
+            report.size_mb,
+            if report.wal_mode { "on" } else { "off" },
+            if report.foreign_keys_enabled { "on" } else { "off" },
+        );
+        for provider in &providers {
+            let status = match (&provider.error, provider.latency_ms) {
+                (None, Some(ms)) => format!("ok in {}", humanize_ms(ms)),
+                (None, None) => "ok".to_string(),
+                (Some(err), _) => err.clone(),
+            };
+            println!("AI {} ({}): {}", provider.provider, provider.model, status);
+        }
+    }
+
+    if !report.is_healthy {
+        bail!("Database integrity check failed");
+    }
+    Ok(())
+}
+
+fn humanize_ms(ms: u64) -> String {
+    let duration = Duration::from_millis(ms);
+    if duration.as_secs() > 0 {
+        format!("{:.1}s", duration.as_secs_f32())
+    } else {
+        format!("{}ms", ms)
+    }
+}
+
+/// Search the online sources, then score and cache the results like the app does
+async fn search_online(
+    storage: &Arc<StorageManager>,
+    query: &str,
+    sources: &[String],
+    limit: usize,
+    refresh: bool,
+) -> Result<Vec<LiteratureEntry>> {
+    let peptides: Vec<String> = storage
+        .list_protocols()?
+        .into_iter()
+        .map(|protocol| protocol.peptide_name)
+        .collect();
+    let relevance = RelevanceContext::new(query, &peptides, OffsetDateTime::now_utc());
+    let cache: ResponseCacheSettings = storage.load_setting_or_default()?;
+
+    let mut entries = Vec::new();
+    for source in sources {
+        let fetcher: Box<dyn LiteratureFetcher> = match source.to_lowercase().as_str() {
+            "pubmed" => Box::new(PubMedFetcher::new()),
+            "openalex" => Box::new(OpenAlexFetcher::new()),
+            "crossref" => Box::new(CrossrefFetcher::new()),
+            "europepmc" => Box::new(EuropePmcFetcher::new()),
+            other => bail!("Unknown literature source: {}", other),
+        };
+        let fetcher: Box<dyn LiteratureFetcher> = if cache.enabled {
+            let cached = AsyncStorage::new(storage.clone());
+            Box::new(CachedFetcher::new(fetcher, cached, cache.ttl()).bypass_cache(refresh))
+        } else {
+            fetcher
+        };
+        // Cache page by page so an interrupted search keeps what it found
+        let mut pages = search_all(fetcher.as_ref(), query, limit);
+        while let Some(results) = pages
+            .next_page()
+            .await
+            .with_context(|| format!("{} search failed", source))?
+        {
+            for result in &results {
+                let entry = relevance.to_entry(result);
+                storage.cache_literature(&entry)?;
+                entries.push(entry);
+            }
+        }
+    }
+    Ok(entries)
+}
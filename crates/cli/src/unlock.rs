@@ -0,0 +1,171 @@
+//! Opening the app's encrypted database
+//!
+//! The key is found the same way the desktop app finds it: derived from
+//! the passphrase when one is set, otherwise the macOS Keychain or the key
+//! file in the data directory. When biometric unlock is enabled the stored
+//! key is only released after Touch ID / Windows Hello. The CLI never
+//! creates a key, so it can't start a second, unreadable database.
+
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use peptrack_core::{
+    unlock_storage, BiometricKeyProvider, KeyMaterial, KeyProvider, PassphraseKeyProvider,
+    StaticKeyProvider, StorageConfig, StorageManager,
+};
+use serde::Deserialize;
+
+/// Same file names as the desktop app
+const KEY_FILE_NAME: &str = "peptrack.key";
+const PENDING_KEY_FILE_NAME: &str = "peptrack.key.pending";
+const BIOMETRIC_SETTINGS_FILENAME: &str = "biometric.json";
+
+/// Read instead of prompting when set
+pub const PASSPHRASE_ENV: &str = "PEPTRACK_PASSPHRASE";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct BiometricSettings {
+    enabled: bool,
+}
+
+/// The app's data directory: `data_dir` when given, otherwise the OS
+/// default the app uses
+pub fn resolve_data_dir(data_dir: Option<PathBuf>) -> Result<PathBuf> {
+    let dir = match data_dir {
+        Some(dir) => dir,
+        None => dirs::data_dir()
+            .context("Unable to determine OS data directory")?
+            .join("PepTrack"),
+    };
+    if !dir.exists() {
+        bail!(
+            "No PepTrack data found in {}; open the app once or pass --data-dir",
+            dir.display()
+        );
+    }
+    Ok(dir)
+}
+
+/// Open and unlock the database in `data_dir`
+pub fn open_storage(data_dir: &Path) -> Result<StorageManager> {
+    if data_dir.join(PENDING_KEY_FILE_NAME).exists() {
+        bail!("A key rotation was interrupted; open the PepTrack app to finish it first");
+    }
+
+    let key_provider = Arc::new(PassphraseKeyProvider::new(data_dir));
+    let storage = StorageManager::new(StorageConfig {
+        data_dir: Some(data_dir.to_path_buf()),
+        db_file_name: None,
+        key_provider: key_provider.clone(),
+    })?;
+
+    if key_provider.is_configured() {
+        let passphrase = read_passphrase()?;
+        unlock_storage(&storage, &key_provider, &passphrase)?;
+        return Ok(storage);
+    }
+
+    key_provider.unlock_with_key(stored_key(data_dir)?);
+    if !storage.verify_key()? {
+        bail!("The stored encryption key does not open this database");
+    }
+    storage.initialize()?;
+    Ok(storage)
+}
+
+/// The stored key, behind a biometric prompt when that is enabled
+fn stored_key(data_dir: &Path) -> Result<KeyMaterial> {
+    let stored: Arc<dyn KeyProvider> = select_stored_key(data_dir)?;
+
+    let settings: BiometricSettings = std::fs::read_to_string(data_dir.join(BIOMETRIC_SETTINGS_FILENAME))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    if settings.enabled && BiometricKeyProvider::is_available() {
+        return BiometricKeyProvider::new(stored, None)
+            .with_reason("unlock PepTrack from the command line")
+            .key_material();
+    }
+    stored.key_material()
+}
+
+fn select_stored_key(data_dir: &Path) -> Result<Arc<dyn KeyProvider>> {
+    #[cfg(target_os = "macos")]
+    if let Ok(provider) = peptrack_core::KeychainKeyProvider::new() {
+        if provider.key_material().is_ok() {
+            return Ok(Arc::new(provider));
+        }
+    }
+
+    let key_path = data_dir.join(KEY_FILE_NAME);
+    let raw = std::fs::read_to_string(&key_path)
+        .map_err(|_| anyhow!("No encryption key found in {}", data_dir.display()))?;
+    let bytes = hex::decode(raw.trim()).context("Stored encryption key is corrupted")?;
+    Ok(Arc::new(StaticKeyProvider::new(bytes)?))
+}
+
+/// The passphrase from [`PASSPHRASE_ENV`], or typed on stdin
+fn read_passphrase() -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    prompt("Passphrase: ")
+}
+
+/// Read one line from stdin after printing `label` to stderr
+///
+/// Input is echoed; use the environment variables when that matters.
+pub fn prompt(label: &str) -> Result<String> {
+    eprint!("{}", label);
+    std::io::stderr().flush().ok();
+    let mut line = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .context("Failed to read from stdin")?;
+    let line = line.trim_end_matches(['\r', '\n']).to_string();
+    if line.is_empty() {
+        bail!("No input given");
+    }
+    Ok(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn opens_database_with_key_file() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join(KEY_FILE_NAME), hex::encode([9u8; 32])).unwrap();
+
+        let storage = open_storage(dir.path()).unwrap();
+        storage
+            .upsert_protocol(&peptrack_core::PeptideProtocol::new("Test", "BPC-157"))
+            .unwrap();
+        drop(storage);
+
+        let reopened = open_storage(dir.path()).unwrap();
+        assert_eq!(reopened.list_protocols().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn refuses_to_create_a_key() {
+        let dir = tempdir().unwrap();
+        let err = open_storage(dir.path()).err().unwrap();
+        assert!(err.to_string().contains("No encryption key found"));
+        assert!(!dir.path().join(KEY_FILE_NAME).exists());
+    }
+
+    #[test]
+    fn refuses_during_interrupted_rotation() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join(PENDING_KEY_FILE_NAME), "00").unwrap();
+        assert!(open_storage(dir.path()).is_err());
+    }
+}
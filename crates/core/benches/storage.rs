@@ -0,0 +1,84 @@
+//! Storage read benchmarks on a database with 10,000 dose logs
+//!
+//! Run with `cargo bench -p peptrack-core`.
+
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use peptrack_core::{
+    DoseLog, ListOptions, PeptideProtocol, StaticKeyProvider, StorageConfig, StorageManager,
+};
+use tempfile::TempDir;
+use time::{Duration, OffsetDateTime};
+
+const DOSE_COUNT: i64 = 10_000;
+
+fn seeded_storage() -> (TempDir, StorageManager, Vec<String>) {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let key_provider = Arc::new(StaticKeyProvider::new(vec![7u8; 32]).expect("key provider"));
+    let storage = StorageManager::new(StorageConfig {
+        data_dir: Some(dir.path().to_path_buf()),
+        db_file_name: Some("bench.sqlite".into()),
+        key_provider,
+    })
+    .expect("storage manager");
+    storage.initialize().expect("initialize");
+
+    let protocol = PeptideProtocol::new("Bench Protocol", "BPC-157");
+    storage.upsert_protocol(&protocol).expect("upsert protocol");
+
+    let start = OffsetDateTime::now_utc() - Duration::hours(DOSE_COUNT);
+    let mut ids = Vec::new();
+    for hour in 0..DOSE_COUNT {
+        let mut dose = DoseLog::new(protocol.id.as_str(), "Abdomen", 0.25);
+        dose.logged_at = start + Duration::hours(hour);
+        storage.append_dose_log(&dose).expect("append dose");
+        ids.push(dose.id);
+    }
+
+    (dir, storage, ids)
+}
+
+fn list_benches(c: &mut Criterion) {
+    let (dir, storage, ids) = seeded_storage();
+    let last_week = OffsetDateTime::now_utc() - Duration::days(7);
+
+    let mut group = c.benchmark_group("list_dose_logs");
+    group.sample_size(10);
+    group.bench_function("all", |b| {
+        b.iter(|| storage.list_dose_logs().expect("list"))
+    });
+    for (name, options) in [
+        ("first_page_50", ListOptions::page(50, 0)),
+        ("deep_page_50", ListOptions::page(50, 5_000)),
+        ("since_7_days", ListOptions::since(last_week)),
+    ] {
+        group.bench_with_input(BenchmarkId::new("page", name), &options, |b, options| {
+            b.iter(|| storage.list_dose_logs_page(options).expect("list page"))
+        });
+    }
+    group.finish();
+
+    // Reused connection with a cached statement versus what every call paid
+    // before: a new connection and a fresh prepare
+    let mut group = c.benchmark_group("get_dose_log");
+    group.bench_function("pooled", |b| {
+        b.iter(|| storage.get_dose_log(&ids[ids.len() / 2]).expect("get"))
+    });
+    group.bench_function("new_connection", |b| {
+        b.iter(|| {
+            let conn = rusqlite::Connection::open(dir.path().join("bench.sqlite")).expect("connection");
+            let mut stmt = conn
+                .prepare("SELECT payload FROM dose_logs WHERE id = ?1 AND deleted_at IS NULL")
+                .expect("prepare");
+            let blob: Vec<u8> = stmt
+                .query_row([&ids[ids.len() / 2]], |row| row.get(0))
+                .expect("query");
+            blob
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, list_benches);
+criterion_main!(benches);
@@ -0,0 +1,171 @@
+//! AI usage totals from the summary history
+//!
+//! Each saved summary records the tokens its AI calls used and what they
+//! cost. These are summed per provider and per day so spending can be
+//! followed over time. Summaries saved before usage was recorded are only
+//! counted.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use time::Date;
+
+use crate::models::SummaryHistory;
+
+/// Usage of one provider
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderUsage {
+    pub provider: String,
+    /// Summaries with recorded usage
+    pub summaries: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    /// Summaries whose token counts were estimated
+    pub estimated_summaries: usize,
+}
+
+/// Usage of one provider on one day
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyAiUsage {
+    /// Day as `YYYY-MM-DD`
+    pub date: String,
+    pub provider: String,
+    pub summaries: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AiUsageStats {
+    /// Most expensive first
+    pub by_provider: Vec<ProviderUsage>,
+    /// Oldest first
+    pub daily: Vec<DailyAiUsage>,
+    pub total_cost_usd: f64,
+    /// Summaries saved without usage information
+    pub untracked_summaries: usize,
+}
+
+/// Usage of `summaries` created on or after `since`
+pub fn ai_usage_stats(summaries: &[SummaryHistory], since: Option<Date>) -> AiUsageStats {
+    let mut providers: BTreeMap<&str, ProviderUsage> = BTreeMap::new();
+    let mut daily: BTreeMap<(Date, &str), DailyAiUsage> = BTreeMap::new();
+    let mut stats = AiUsageStats::default();
+
+    for summary in summaries {
+        let date = summary.created_at.date();
+        if since.is_some_and(|since| date < since) {
+            continue;
+        }
+        let Some(usage) = summary.usage else {
+            stats.untracked_summaries += 1;
+            continue;
+        };
+
+        let provider = providers
+            .entry(summary.provider.as_str())
+            .or_insert_with(|| ProviderUsage {
+                provider: summary.provider.clone(),
+                ..Default::default()
+            });
+        provider.summaries += 1;
+        provider.input_tokens += usage.input_tokens;
+        provider.output_tokens += usage.output_tokens;
+        provider.cost_usd += usage.cost_usd;
+        provider.estimated_summaries += usize::from(usage.estimated);
+
+        let day = daily
+            .entry((date, summary.provider.as_str()))
+            .or_insert_with(|| DailyAiUsage {
+                date: date.to_string(),
+                provider: summary.provider.clone(),
+                summaries: 0,
+                input_tokens: 0,
+                output_tokens: 0,
+                cost_usd: 0.0,
+            });
+        day.summaries += 1;
+        day.input_tokens += usage.input_tokens;
+        day.output_tokens += usage.output_tokens;
+        day.cost_usd += usage.cost_usd;
+
+        stats.total_cost_usd += usage.cost_usd;
+    }
+
+    stats.by_provider = providers.into_values().collect();
+    stats
+        .by_provider
+        .sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+    stats.daily = daily.into_values().collect();
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, datetime};
+
+    use super::*;
+    use crate::models::AiUsage;
+
+    fn summary(provider: &str, day: u8, usage: Option<AiUsage>) -> SummaryHistory {
+        let mut summary = SummaryHistory::new("Title", "Content", "Output", "Markdown", provider);
+        summary.created_at = datetime!(2025-03-01 12:00 UTC).replace_day(day).unwrap();
+        summary.usage = usage;
+        summary
+    }
+
+    fn usage(tokens: u64, cost_usd: f64, estimated: bool) -> Option<AiUsage> {
+        Some(AiUsage {
+            input_tokens: tokens,
+            output_tokens: tokens / 10,
+            cost_usd,
+            estimated,
+        })
+    }
+
+    #[test]
+    fn totals_per_provider_and_day() {
+        let summaries = vec![
+            summary("Codex", 1, usage(1_000, 0.02, false)),
+            summary("Codex", 1, usage(500, 0.01, true)),
+            summary("Claude", 2, usage(2_000, 0.05, false)),
+            summary("Claude", 3, None),
+        ];
+        let stats = ai_usage_stats(&summaries, None);
+
+        assert_eq!(stats.untracked_summaries, 1);
+        assert!((stats.total_cost_usd - 0.08).abs() < 1e-9);
+        assert_eq!(stats.by_provider[0].provider, "Claude");
+        let codex = &stats.by_provider[1];
+        assert_eq!(
+            (
+                codex.summaries,
+                codex.input_tokens,
+                codex.estimated_summaries
+            ),
+            (2, 1_500, 1)
+        );
+
+        assert_eq!(stats.daily.len(), 2);
+        assert_eq!(stats.daily[0].date, "2025-03-01");
+        assert_eq!(stats.daily[0].summaries, 2);
+        assert_eq!(stats.daily[1].provider, "Claude");
+    }
+
+    #[test]
+    fn ignores_summaries_before_since() {
+        let summaries = vec![
+            summary("Codex", 1, usage(1_000, 0.02, false)),
+            summary("Codex", 5, usage(1_000, 0.03, false)),
+        ];
+        let stats = ai_usage_stats(&summaries, Some(date!(2025 - 03 - 02)));
+
+        assert_eq!(stats.by_provider[0].summaries, 1);
+        assert!((stats.total_cost_usd - 0.03).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,290 @@
+//! Alert muting, notification routing and threads
+//!
+//! [`AlertPreferences`] holds the mutes and how each severity is delivered.
+//! A mute covers an alert type, a related item (a vial, supplier, paper) or
+//! both until it expires; muted alerts aren't saved at all. Alerts that get
+//! through are either shown as an OS notification or only added to the
+//! in-app list, depending on their severity.
+//!
+//! [`group_alerts`] folds repeats of the same alert into an [`AlertThread`]
+//! so a vial that ran low three times reads as one entry with a count.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+
+use crate::models::{Alert, AlertSeverity, AlertType};
+use crate::settings::Setting;
+
+/// Longest an alert can be muted for
+pub const MAX_MUTE_DAYS: u32 = 365;
+
+/// How an alert reaches the user
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertDelivery {
+    /// OS notification as well as the in-app list
+    Notification,
+    /// Only the in-app list
+    InApp,
+}
+
+/// Delivery for each severity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertRouting {
+    pub info: AlertDelivery,
+    pub warning: AlertDelivery,
+    pub critical: AlertDelivery,
+}
+
+impl Default for AlertRouting {
+    fn default() -> Self {
+        Self {
+            info: AlertDelivery::InApp,
+            warning: AlertDelivery::Notification,
+            critical: AlertDelivery::Notification,
+        }
+    }
+}
+
+impl AlertRouting {
+    pub fn delivery(&self, severity: &AlertSeverity) -> AlertDelivery {
+        match severity {
+            AlertSeverity::Info => self.info,
+            AlertSeverity::Warning => self.warning,
+            AlertSeverity::Critical => self.critical,
+        }
+    }
+}
+
+/// Alerts of `alert_type`, about `related_id`, or both, muted until `until`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertMute {
+    #[serde(default)]
+    pub alert_type: Option<AlertType>,
+    #[serde(default)]
+    pub related_id: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub until: OffsetDateTime,
+}
+
+impl AlertMute {
+    pub fn covers(&self, alert: &Alert, now: OffsetDateTime) -> bool {
+        self.until > now
+            && self.alert_type.as_ref().is_none_or(|t| *t == alert.alert_type)
+            && self
+                .related_id
+                .as_deref()
+                .is_none_or(|id| alert.related_id.as_deref() == Some(id))
+    }
+
+    fn same_target(&self, alert_type: Option<&AlertType>, related_id: Option<&str>) -> bool {
+        self.alert_type.as_ref() == alert_type && self.related_id.as_deref() == related_id
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AlertPreferences {
+    pub mutes: Vec<AlertMute>,
+    pub routing: AlertRouting,
+}
+
+impl AlertPreferences {
+    pub fn is_muted(&self, alert: &Alert, now: OffsetDateTime) -> bool {
+        self.mutes.iter().any(|mute| mute.covers(alert, now))
+    }
+
+    /// How `alert` should be delivered, or `None` when it's muted
+    pub fn route(&self, alert: &Alert, now: OffsetDateTime) -> Option<AlertDelivery> {
+        if self.is_muted(alert, now) {
+            return None;
+        }
+        Some(self.routing.delivery(&alert.severity))
+    }
+
+    /// Mute `alert_type` and/or `related_id` for `days`, replacing an
+    /// existing mute of the same target, and drop expired mutes
+    pub fn mute(
+        &mut self,
+        alert_type: Option<AlertType>,
+        related_id: Option<String>,
+        days: u32,
+        now: OffsetDateTime,
+    ) -> Result<(), String> {
+        let related_id = related_id.map(|id| id.trim().to_string()).filter(|id| !id.is_empty());
+        if alert_type.is_none() && related_id.is_none() {
+            return Err("Choose an alert type or an item to mute".to_string());
+        }
+        if days == 0 || days > MAX_MUTE_DAYS {
+            return Err(format!("Mute alerts for 1 to {} days", MAX_MUTE_DAYS));
+        }
+
+        self.unmute(alert_type.as_ref(), related_id.as_deref());
+        self.prune(now);
+        self.mutes.push(AlertMute {
+            alert_type,
+            related_id,
+            until: now + Duration::days(i64::from(days)),
+        });
+        Ok(())
+    }
+
+    /// Remove the mute of exactly this target; false if there was none
+    pub fn unmute(&mut self, alert_type: Option<&AlertType>, related_id: Option<&str>) -> bool {
+        let before = self.mutes.len();
+        self.mutes.retain(|mute| !mute.same_target(alert_type, related_id));
+        self.mutes.len() != before
+    }
+
+    /// Drop mutes that have expired
+    pub fn prune(&mut self, now: OffsetDateTime) {
+        self.mutes.retain(|mute| mute.until > now);
+    }
+}
+
+impl Setting for AlertPreferences {
+    const KEY: &'static str = "alerts.preferences";
+
+    fn validate(&self) -> Result<(), String> {
+        if self
+            .mutes
+            .iter()
+            .any(|mute| mute.alert_type.is_none() && mute.related_id.is_none())
+        {
+            return Err("A mute needs an alert type or an item".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Repeats of one alert: the same type about the same item
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertThread {
+    pub alert_type: AlertType,
+    pub related_id: Option<String>,
+    /// Most recent alert in the thread
+    pub latest: Alert,
+    pub occurrences: usize,
+    #[serde(with = "time::serde::rfc3339")]
+    pub first_seen: OffsetDateTime,
+    /// Every alert in the thread, newest first
+    pub alert_ids: Vec<String>,
+}
+
+/// Group `alerts` into threads, most recently active first
+///
+/// Alerts are repeats when they have the same type and related item;
+/// alerts without a related item are repeats when their titles match too.
+pub fn group_alerts(mut alerts: Vec<Alert>) -> Vec<AlertThread> {
+    alerts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    let mut threads: Vec<AlertThread> = Vec::new();
+    let mut index: HashMap<(AlertType, String), usize> = HashMap::new();
+    for alert in alerts {
+        let key = (
+            alert.alert_type.clone(),
+            alert.related_id.clone().unwrap_or_else(|| format!("title:{}", alert.title)),
+        );
+        match index.get(&key) {
+            Some(&i) => {
+                let thread = &mut threads[i];
+                thread.occurrences += 1;
+                thread.first_seen = alert.created_at;
+                thread.alert_ids.push(alert.id);
+            }
+            None => {
+                index.insert(key, threads.len());
+                threads.push(AlertThread {
+                    alert_type: alert.alert_type.clone(),
+                    related_id: alert.related_id.clone(),
+                    occurrences: 1,
+                    first_seen: alert.created_at,
+                    alert_ids: vec![alert.id.clone()],
+                    latest: alert,
+                });
+            }
+        }
+    }
+    threads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(alert_type: AlertType, related_id: Option<&str>, minutes_ago: i64) -> Alert {
+        let mut alert = Alert::new(alert_type, AlertSeverity::Warning, "Low stock", "Running low");
+        alert.related_id = related_id.map(str::to_string);
+        alert.created_at -= Duration::minutes(minutes_ago);
+        alert
+    }
+
+    #[test]
+    fn mutes_cover_type_item_or_both_until_they_expire() {
+        let now = OffsetDateTime::now_utc();
+        let mut prefs = AlertPreferences::default();
+        let low = alert(AlertType::LowStock, Some("vial-1"), 0);
+        let expired = alert(AlertType::Expired, Some("vial-1"), 0);
+        let other = alert(AlertType::LowStock, Some("vial-2"), 0);
+
+        assert_eq!(prefs.route(&low, now), Some(AlertDelivery::Notification));
+        assert!(prefs.mute(None, None, 7, now).is_err());
+        assert!(prefs.mute(Some(AlertType::LowStock), None, 0, now).is_err());
+
+        prefs.mute(None, Some("vial-1".to_string()), 7, now).unwrap();
+        assert!(prefs.is_muted(&low, now));
+        assert!(prefs.is_muted(&expired, now));
+        assert!(!prefs.is_muted(&other, now));
+        assert!(!prefs.is_muted(&low, now + Duration::days(8)));
+
+        prefs.mute(Some(AlertType::LowStock), None, 1, now).unwrap();
+        assert!(prefs.is_muted(&other, now));
+        prefs.mute(Some(AlertType::LowStock), None, 3, now).unwrap();
+        assert_eq!(prefs.mutes.len(), 2);
+
+        assert!(prefs.unmute(None, Some("vial-1")));
+        assert!(!prefs.is_muted(&expired, now));
+        assert!(Setting::validate(&prefs).is_ok());
+    }
+
+    #[test]
+    fn routing_follows_severity() {
+        let now = OffsetDateTime::now_utc();
+        let mut prefs = AlertPreferences::default();
+        let mut info = alert(AlertType::NewLiterature, None, 0);
+        info.severity = AlertSeverity::Info;
+        assert_eq!(prefs.route(&info, now), Some(AlertDelivery::InApp));
+
+        prefs.routing.critical = AlertDelivery::InApp;
+        let mut critical = info.clone();
+        critical.severity = AlertSeverity::Critical;
+        assert_eq!(prefs.route(&critical, now), Some(AlertDelivery::InApp));
+    }
+
+    #[test]
+    fn repeats_are_grouped_into_threads() {
+        let first = alert(AlertType::LowStock, Some("vial-1"), 60);
+        let alerts = vec![
+            first.clone(),
+            alert(AlertType::LowStock, Some("vial-2"), 30),
+            alert(AlertType::LowStock, Some("vial-1"), 10),
+            alert(AlertType::Expired, Some("vial-1"), 20),
+            alert(AlertType::BackupOverdue, None, 5),
+        ];
+
+        let threads = group_alerts(alerts);
+        assert_eq!(threads.len(), 4);
+        assert_eq!(threads[0].alert_type, AlertType::BackupOverdue);
+
+        let vial = &threads[1];
+        assert_eq!(vial.related_id.as_deref(), Some("vial-1"));
+        assert_eq!(vial.occurrences, 2);
+        assert_eq!(vial.first_seen, first.created_at);
+        assert_eq!(vial.alert_ids.last(), Some(&first.id));
+    }
+}
@@ -0,0 +1,137 @@
+//! Plaintext copy of the data for analysis
+//!
+//! [`StorageManager::export_analytics_dataset`](crate::StorageManager::export_analytics_dataset)
+//! writes every record to a new, unencrypted SQLite file with one table per
+//! record type and one column per field, so the data can be loaded into
+//! Python or R without PepTrack's key. Anyone with the file can read it.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Tables copied into the dataset
+///
+/// Caches, settings, embeddings and the search index are left out, as are
+/// attachment contents; attachments are exported as their metadata.
+pub(crate) const ANALYTICS_TABLES: &[&str] = &[
+    "protocols",
+    "dose_logs",
+    "dose_skips",
+    "literature_cache",
+    "clinical_trials",
+    "suppliers",
+    "inventory",
+    "price_history",
+    "exchange_rates",
+    "orders",
+    "attachments",
+    "alerts",
+    "summary_history",
+    "body_metrics",
+    "side_effects",
+    "lab_results",
+    "journal_entries",
+    "goals",
+    "audit_log",
+];
+
+/// A table written to the dataset
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedTable {
+    pub name: String,
+    pub rows: usize,
+}
+
+/// Where the dataset went and what's in it
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsExport {
+    pub path: PathBuf,
+    pub tables: Vec<ExportedTable>,
+}
+
+/// A timestamp stored in a payload, as RFC 3339 text
+///
+/// Payloads hold timestamps in `time`'s compact form, a list of integers
+/// that means nothing outside this app.
+fn timestamp_text(value: &Value) -> Option<String> {
+    let Value::Array(parts) = value else {
+        return None;
+    };
+    if parts.len() != 9 || !parts.iter().all(Value::is_i64) {
+        return None;
+    }
+    serde_json::from_value::<OffsetDateTime>(value.clone())
+        .ok()?
+        .format(&Rfc3339)
+        .ok()
+}
+
+/// The fields of a decrypted payload, ready to be written as columns
+///
+/// Timestamps become RFC 3339 text and lists and nested objects become JSON
+/// text, which SQLite's `json_*` functions can still query.
+pub(crate) fn payload_columns(payload: &[u8]) -> Result<Map<String, Value>> {
+    let fields: Map<String, Value> =
+        serde_json::from_slice(payload).context("Payload is not a JSON object")?;
+    Ok(fields
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Value::Array(_) | Value::Object(_) => match timestamp_text(&value) {
+                    Some(text) => Value::String(text),
+                    None => Value::String(value.to_string()),
+                },
+                other => other,
+            };
+            (name, value)
+        })
+        .collect())
+}
+
+/// A column value as SQLite stores it
+pub(crate) fn sql_value(value: &Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value as Sql;
+    match value {
+        Value::Null => Sql::Null,
+        Value::Bool(b) => Sql::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Sql::Integer(i),
+            None => n.as_f64().map(Sql::Real).unwrap_or(Sql::Null),
+        },
+        Value::String(s) => Sql::Text(s.clone()),
+        other => Sql::Text(other.to_string()),
+    }
+}
+
+/// `name` quoted for use as an SQL identifier
+pub(crate) fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PeptideProtocol;
+
+    #[test]
+    fn payload_fields_become_plain_columns() {
+        let mut protocol = PeptideProtocol::new("Morning", "BPC-157");
+        protocol.tags = vec!["healing".into()];
+        protocol.updated_at = OffsetDateTime::parse("2025-03-01T08:30:00Z", &Rfc3339).unwrap();
+        let payload = serde_json::to_vec(&protocol).unwrap();
+
+        let columns = payload_columns(&payload).unwrap();
+        assert_eq!(columns["name"], "Morning");
+        assert_eq!(columns["updated_at"], "2025-03-01T08:30:00Z");
+        assert_eq!(columns["tags"], r#"["healing"]"#);
+        assert_eq!(sql_value(&columns["is_favorite"]), rusqlite::types::Value::Integer(0));
+        assert_eq!(sql_value(&columns["notes"]), rusqlite::types::Value::Null);
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+    }
+}
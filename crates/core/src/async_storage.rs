@@ -0,0 +1,100 @@
+//! Calling storage from async code
+//!
+//! Every [`StorageManager`] call blocks on SQLite and, for writes, on the
+//! single writer connection. Run directly inside an async task that stalls a
+//! runtime worker for as long as the call takes, so async callers go through
+//! [`AsyncStorage`], which runs each call on tokio's blocking thread pool.
+//! Synchronous callers such as the CLI keep using [`StorageManager`].
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+
+use crate::db::StorageManager;
+
+/// Async handle to a shared [`StorageManager`]
+///
+/// Cheap to clone; all clones share the same storage.
+#[derive(Clone)]
+pub struct AsyncStorage {
+    storage: Arc<StorageManager>,
+}
+
+impl AsyncStorage {
+    pub fn new(storage: Arc<StorageManager>) -> Self {
+        Self { storage }
+    }
+
+    /// The underlying storage, for code that is already off the runtime
+    pub fn blocking(&self) -> &Arc<StorageManager> {
+        &self.storage
+    }
+
+    /// Run `f` against the storage on the blocking thread pool
+    ///
+    /// `f` owns everything it uses, so clone or move arguments in. A panic in
+    /// `f` is returned as an error rather than unwinding into the caller.
+    ///
+    /// Must be called from within a tokio runtime.
+    pub async fn run<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&StorageManager) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let storage = self.storage.clone();
+        tokio::task::spawn_blocking(move || f(&storage))
+            .await
+            .map_err(|e| anyhow!("Storage task failed: {}", e))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::StorageConfig;
+    use crate::encryption::StaticKeyProvider;
+    use crate::models::PeptideProtocol;
+    use tempfile::tempdir;
+
+    fn async_storage(dir: &std::path::Path) -> AsyncStorage {
+        let storage = StorageManager::new(StorageConfig {
+            data_dir: Some(dir.to_path_buf()),
+            db_file_name: Some("async.sqlite".into()),
+            key_provider: Arc::new(StaticKeyProvider::new(vec![7u8; 32]).unwrap()),
+        })
+        .unwrap();
+        storage.initialize().unwrap();
+        AsyncStorage::new(Arc::new(storage))
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn runs_calls_off_the_runtime_thread() {
+        let dir = tempdir().unwrap();
+        let storage = async_storage(dir.path());
+
+        let runtime_thread = std::thread::current().id();
+        let protocol = PeptideProtocol::new("Morning", "BPC-157");
+        let ran_on = storage
+            .run(move |s| {
+                s.upsert_protocol(&protocol)?;
+                Ok(std::thread::current().id())
+            })
+            .await
+            .unwrap();
+        assert_ne!(ran_on, runtime_thread);
+
+        let protocols = storage.run(|s| s.list_protocols()).await.unwrap();
+        assert_eq!(protocols.len(), 1);
+        assert_eq!(storage.blocking().list_protocols().unwrap().len(), 1);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn panics_become_errors() {
+        let dir = tempdir().unwrap();
+        let storage = async_storage(dir.path());
+
+        let result: Result<()> = storage.run(|_| panic!("boom")).await;
+        assert!(result.is_err());
+        assert!(storage.run(|s| s.list_protocols()).await.is_ok());
+    }
+}
@@ -0,0 +1,184 @@
+//! Validation and thumbnail helpers for encrypted file attachments
+//!
+//! Attachment bytes are sealed with the same envelope encryption as every other
+//! record and stored in the `attachments` table next to their metadata. These
+//! helpers decide what may be stored, how it is labelled, and build the small
+//! JPEG previews shown for photos.
+
+use std::io::Cursor;
+
+use anyhow::{anyhow, Context, Result};
+use image::{DynamicImage, ImageFormat, ImageReader, Limits};
+
+/// Largest file accepted as an attachment (25 MiB)
+pub const MAX_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+
+/// Largest image accepted as a photo attachment (15 MiB)
+pub const MAX_IMAGE_BYTES: usize = 15 * 1024 * 1024;
+
+/// Longest side of a generated thumbnail, in pixels
+pub const THUMBNAIL_MAX_DIMENSION: u32 = 320;
+
+/// Longest side of an image that will be decoded, to guard against
+/// decompression bombs
+const MAX_IMAGE_DIMENSION: u32 = 12_000;
+
+/// A generated thumbnail together with the dimensions of the original image
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    pub jpeg: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Detect a MIME type from the file's magic bytes, falling back to its extension
+pub fn detect_mime_type(data: &[u8], file_name: &str) -> &'static str {
+    if data.starts_with(b"%PDF") {
+        return "application/pdf";
+    }
+    if data.starts_with(&[0x89, b'P', b'N', b'G']) {
+        return "image/png";
+    }
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return "image/jpeg";
+    }
+
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Strip any directory components so only the bare file name is stored
+pub fn sanitize_file_name(file_name: &str) -> Result<String> {
+    let name = file_name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .trim();
+
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(anyhow!("Attachment file name is empty"));
+    }
+    Ok(name.to_string())
+}
+
+/// Reject empty files and files over [`MAX_ATTACHMENT_BYTES`]
+pub fn validate_attachment_size(data: &[u8]) -> Result<()> {
+    if data.is_empty() {
+        return Err(anyhow!("Attachment is empty"));
+    }
+    if data.len() > MAX_ATTACHMENT_BYTES {
+        return Err(anyhow!(
+            "Attachment is {:.1} MB; the limit is {} MB",
+            data.len() as f64 / (1024.0 * 1024.0),
+            MAX_ATTACHMENT_BYTES / (1024 * 1024)
+        ));
+    }
+    Ok(())
+}
+
+/// Reject images over [`MAX_IMAGE_BYTES`]
+pub fn validate_image_size(data: &[u8]) -> Result<()> {
+    validate_attachment_size(data)?;
+    if data.len() > MAX_IMAGE_BYTES {
+        return Err(anyhow!(
+            "Image is {:.1} MB; the limit is {} MB",
+            data.len() as f64 / (1024.0 * 1024.0),
+            MAX_IMAGE_BYTES / (1024 * 1024)
+        ));
+    }
+    Ok(())
+}
+
+/// Decode a PNG or JPEG and build a JPEG thumbnail no larger than
+/// [`THUMBNAIL_MAX_DIMENSION`] on its longest side
+pub fn generate_thumbnail(data: &[u8]) -> Result<Thumbnail> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .context("Failed to read image")?;
+    reader.limits(limits);
+    let image = reader.decode().context("Unsupported or corrupt image")?;
+
+    let thumbnail = image.thumbnail(THUMBNAIL_MAX_DIMENSION, THUMBNAIL_MAX_DIMENSION);
+    // JPEG has no alpha channel
+    let thumbnail = DynamicImage::ImageRgb8(thumbnail.to_rgb8());
+
+    let mut jpeg = Vec::new();
+    thumbnail
+        .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+        .context("Failed to encode thumbnail")?;
+
+    Ok(Thumbnail {
+        jpeg,
+        width: image.width(),
+        height: image.height(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_mime_type_from_magic_bytes_first() {
+        assert_eq!(detect_mime_type(b"%PDF-1.7 ...", "coa.bin"), "application/pdf");
+        assert_eq!(detect_mime_type(&[0xFF, 0xD8, 0xFF, 0xE0], "photo"), "image/jpeg");
+        assert_eq!(detect_mime_type(b"lot,purity", "results.CSV"), "text/csv");
+        assert_eq!(detect_mime_type(b"????", "blob"), "application/octet-stream");
+    }
+
+    #[test]
+    fn sanitizes_file_names() {
+        assert_eq!(sanitize_file_name("/tmp/coa.pdf").unwrap(), "coa.pdf");
+        assert_eq!(sanitize_file_name("C:\\Users\\me\\COA 2024.pdf").unwrap(), "COA 2024.pdf");
+        assert!(sanitize_file_name("uploads/").is_err());
+        assert!(sanitize_file_name("..").is_err());
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::new_rgba8(width, height);
+        let mut data = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .expect("encode png");
+        data
+    }
+
+    #[test]
+    fn generates_scaled_jpeg_thumbnail() {
+        let thumbnail = generate_thumbnail(&png(1200, 600)).expect("thumbnail");
+
+        assert_eq!((thumbnail.width, thumbnail.height), (1200, 600));
+        assert!(thumbnail.jpeg.starts_with(&[0xFF, 0xD8, 0xFF]));
+
+        let decoded = image::load_from_memory(&thumbnail.jpeg).expect("decode thumbnail");
+        assert_eq!(decoded.width(), THUMBNAIL_MAX_DIMENSION);
+        assert_eq!(decoded.height(), THUMBNAIL_MAX_DIMENSION / 2);
+    }
+
+    #[test]
+    fn rejects_non_images() {
+        assert!(generate_thumbnail(b"%PDF-1.7").is_err());
+        assert!(validate_image_size(&vec![0u8; MAX_IMAGE_BYTES + 1]).is_err());
+    }
+
+    #[test]
+    fn enforces_size_limits() {
+        assert!(validate_attachment_size(&[]).is_err());
+        assert!(validate_attachment_size(&[1, 2, 3]).is_ok());
+        assert!(validate_attachment_size(&vec![0u8; MAX_ATTACHMENT_BYTES + 1]).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Append-only audit log of data changes
+//!
+//! Every mutating [`StorageManager`](crate::StorageManager) call records which
+//! record changed, how, and which fields were touched. Entries never copy
+//! field values, so deleting a record doesn't leave its health data behind in
+//! the log, and the log can be stored without encryption.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::settings::Setting;
+
+/// Bookkeeping fields that change on every save and aren't worth reporting
+const IGNORED_FIELDS: &[&str] = &["created_at", "updated_at"];
+
+/// Kind of record an audit entry refers to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEntityType {
+    Protocol,
+    DoseLog,
+    BodyMetric,
+    SideEffect,
+    LabResult,
+    Literature,
+    Supplier,
+    InventoryItem,
+    PriceHistory,
+    ExchangeRate,
+    Order,
+    Attachment,
+    Alert,
+    Summary,
+    SavedSearch,
+    JournalEntry,
+    Goal,
+    ClinicalTrial,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOperation {
+    Create,
+    Update,
+    Delete,
+    /// Moved to the trash; still restorable
+    Trash,
+    Restore,
+}
+
+/// One recorded change
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    pub id: String,
+    pub entity_type: AuditEntityType,
+    pub entity_id: String,
+    pub operation: AuditOperation,
+    /// Top-level fields that changed, for updates
+    #[serde(default)]
+    pub changed_fields: Vec<String>,
+    /// Human-readable description, e.g. "Changed notes, tags"
+    pub summary: String,
+    pub occurred_at: OffsetDateTime,
+}
+
+impl AuditEntry {
+    pub fn new(
+        entity_type: AuditEntityType,
+        entity_id: impl Into<String>,
+        operation: AuditOperation,
+        changed_fields: Vec<String>,
+    ) -> Self {
+        let summary = match operation {
+            AuditOperation::Create => "Created".to_string(),
+            AuditOperation::Delete => "Deleted".to_string(),
+            AuditOperation::Trash => "Moved to trash".to_string(),
+            AuditOperation::Restore => "Restored from trash".to_string(),
+            AuditOperation::Update if changed_fields.is_empty() => "Updated".to_string(),
+            AuditOperation::Update => format!("Changed {}", changed_fields.join(", ")),
+        };
+
+        Self {
+            id: Uuid::new_v4().to_string(),
+            entity_type,
+            entity_id: entity_id.into(),
+            operation,
+            changed_fields,
+            summary,
+            occurred_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    pub fn with_summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = summary.into();
+        self
+    }
+}
+
+/// Which audit entries to list; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct AuditLogFilter {
+    pub entity_type: Option<AuditEntityType>,
+    pub entity_id: Option<String>,
+    pub operation: Option<AuditOperation>,
+    pub since: Option<OffsetDateTime>,
+    pub until: Option<OffsetDateTime>,
+    pub limit: Option<usize>,
+}
+
+/// How long audit entries are kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AuditRetention {
+    /// Drop entries older than this many days
+    pub max_age_days: Option<u32>,
+    /// Keep at most this many of the newest entries
+    pub max_entries: Option<usize>,
+}
+
+impl Default for AuditRetention {
+    fn default() -> Self {
+        Self {
+            max_age_days: Some(365),
+            max_entries: Some(50_000),
+        }
+    }
+}
+
+impl Setting for AuditRetention {
+    const KEY: &'static str = "audit.retention";
+    const LEGACY_FILE: Option<&'static str> = Some("audit_log.json");
+
+    fn validate(&self) -> Result<(), String> {
+        if self.max_age_days == Some(0) || self.max_entries == Some(0) {
+            return Err("Retention limits must keep at least one day and one entry".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Top-level fields whose values differ between two serialized records
+pub fn changed_fields(before: &Value, after: &Value) -> Vec<String> {
+    let (Value::Object(before), Value::Object(after)) = (before, after) else {
+        return if before == after {
+            Vec::new()
+        } else {
+            vec!["value".to_string()]
+        };
+    };
+
+    let mut fields: Vec<String> = before
+        .keys()
+        .chain(after.keys().filter(|key| !before.contains_key(*key)))
+        .filter(|key| !IGNORED_FIELDS.contains(&key.as_str()))
+        .filter(|key| before.get(*key).unwrap_or(&Value::Null) != after.get(*key).unwrap_or(&Value::Null))
+        .cloned()
+        .collect();
+    fields.sort();
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn changed_fields_ignores_timestamps_and_missing_nulls() {
+        let before = json!({"name": "A", "notes": null, "updated_at": "1"});
+        let after = json!({"name": "B", "tags": ["x"], "updated_at": "2"});
+
+        assert_eq!(changed_fields(&before, &after), vec!["name", "tags"]);
+        assert!(changed_fields(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn retention_deserializes_from_partial_json() {
+        let retention: AuditRetention = serde_json::from_str(r#"{"maxAgeDays": null}"#).unwrap();
+        assert_eq!(retention.max_age_days, None);
+        assert_eq!(retention.max_entries, AuditRetention::default().max_entries);
+    }
+
+    #[test]
+    fn summary_describes_the_change() {
+        let entry = AuditEntry::new(
+            AuditEntityType::DoseLog,
+            "dose-1",
+            AuditOperation::Update,
+            vec!["amount_mg".to_string(), "notes".to_string()],
+        );
+        assert_eq!(entry.summary, "Changed amount_mg, notes");
+        assert_eq!(
+            AuditEntry::new(AuditEntityType::Order, "o", AuditOperation::Delete, Vec::new()).summary,
+            "Deleted"
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Backup file contents
+//!
+//! A backup is a JSON document with the protocols, dose logs, body metrics,
+//! journal entries and cached literature, plus optionally attachments with
+//! their decrypted contents.
+//! The desktop app and the CLI build backups the same way so either can
+//! restore them. Password encryption of the finished file is in
+//! [`crate::backup_encryption`].
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::db::StorageManager;
+use crate::models::Attachment;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupMetadata {
+    pub export_date: String,
+    pub protocols_count: usize,
+    pub doses_count: usize,
+    pub literature_count: usize,
+    pub app_version: String,
+    /// Free text stripped and names pseudonymized; can't be restored
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub anonymized: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupData {
+    pub metadata: BackupMetadata,
+    pub protocols: Vec<serde_json::Value>,
+    pub dose_logs: Vec<serde_json::Value>,
+    pub literature: Vec<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<BackupAttachment>,
+    /// Missing from backups made before body metrics were included
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub body_metrics: Vec<serde_json::Value>,
+    /// Enabled dose schedules, so a read-only viewer can work out adherence
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dose_schedules: Vec<BackupSchedule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub journal_entries: Vec<serde_json::Value>,
+}
+
+/// Dose amount and weekdays of an enabled schedule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSchedule {
+    pub protocol_id: String,
+    pub amount_mg: f32,
+    /// 0 = Sunday, ..., 6 = Saturday
+    pub days_of_week: Vec<u8>,
+}
+
+/// An attachment and its decrypted contents, as stored in a backup file
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupAttachment {
+    pub attachment: serde_json::Value,
+    pub data_base64: String,
+}
+
+/// Which attachments to include in backups
+///
+/// Photos are excluded by default because they can make backups very large;
+/// documents such as COAs are small and included.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentBackupOptions {
+    pub include_documents: bool,
+    pub include_photos: bool,
+}
+
+impl Default for AttachmentBackupOptions {
+    fn default() -> Self {
+        Self {
+            include_documents: true,
+            include_photos: false,
+        }
+    }
+}
+
+impl AttachmentBackupOptions {
+    fn includes(&self, attachment: &Attachment) -> bool {
+        if attachment.is_image() {
+            self.include_photos
+        } else {
+            self.include_documents
+        }
+    }
+}
+
+impl BackupData {
+    /// Load everything a backup holds from `storage`
+    ///
+    /// Dose schedules are kept outside `StorageManager`, so callers that
+    /// have them fill in [`BackupData::dose_schedules`].
+    pub fn collect(storage: &StorageManager, attachments: &AttachmentBackupOptions) -> Result<Self> {
+        let protocols = storage.list_protocols().context("Could not load protocols")?;
+        let doses = storage.list_dose_logs().context("Could not load dose logs")?;
+        let literature = storage.list_literature().context("Could not load literature")?;
+        let body_metrics = storage.list_body_metrics().context("Could not load body metrics")?;
+        let journal_entries = storage.list_journal_entries().context("Could not load journal entries")?;
+
+        let metadata = BackupMetadata {
+            export_date: OffsetDateTime::now_utc().to_string(),
+            protocols_count: protocols.len(),
+            doses_count: doses.len(),
+            literature_count: literature.len(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            anonymized: false,
+        };
+
+        Ok(Self {
+            metadata,
+            protocols: protocols
+                .into_iter()
+                .map(|p| serde_json::to_value(p).unwrap_or_default())
+                .collect(),
+            dose_logs: doses
+                .into_iter()
+                .map(|d| serde_json::to_value(d).unwrap_or_default())
+                .collect(),
+            literature: literature
+                .into_iter()
+                .map(|l| serde_json::to_value(l).unwrap_or_default())
+                .collect(),
+            attachments: collect_backup_attachments(storage, attachments)
+                .context("Could not load attachments")?,
+            body_metrics: body_metrics
+                .into_iter()
+                .map(|m| serde_json::to_value(m).unwrap_or_default())
+                .collect(),
+            dose_schedules: Vec::new(),
+            journal_entries: journal_entries
+                .into_iter()
+                .map(|e| serde_json::to_value(e).unwrap_or_default())
+                .collect(),
+        })
+    }
+}
+
+/// Load the attachments selected by `options`, with their decrypted contents
+pub fn collect_backup_attachments(
+    storage: &StorageManager,
+    options: &AttachmentBackupOptions,
+) -> Result<Vec<BackupAttachment>> {
+    if !options.include_documents && !options.include_photos {
+        return Ok(Vec::new());
+    }
+
+    let mut attachments = Vec::new();
+    for attachment in storage.list_all_attachments()? {
+        if !options.includes(&attachment) {
+            continue;
+        }
+
+        let data = storage
+            .get_attachment_data(&attachment.id)?
+            .with_context(|| format!("Attachment data missing for {}", attachment.id))?;
+
+        attachments.push(BackupAttachment {
+            attachment: serde_json::to_value(&attachment)?,
+            data_base64: STANDARD.encode(data),
+        });
+    }
+
+    Ok(attachments)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::db::StorageConfig;
+    use crate::encryption::StaticKeyProvider;
+    use crate::models::{AttachmentKind, AttachmentOwner, BodyMetric, DoseLog, PeptideProtocol};
+
+    #[test]
+    fn collect_includes_records_and_selected_attachments() {
+        let tmp = tempdir().unwrap();
+        let storage = StorageManager::new(StorageConfig {
+            data_dir: Some(tmp.path().to_path_buf()),
+            db_file_name: None,
+            key_provider: Arc::new(StaticKeyProvider::new(vec![3u8; 32]).unwrap()),
+        })
+        .unwrap();
+        storage.initialize().unwrap();
+
+        let protocol = PeptideProtocol::new("Healing", "BPC-157");
+        storage.upsert_protocol(&protocol).unwrap();
+        let dose = DoseLog::new(protocol.id.as_str(), "abdomen", 0.25);
+        storage.append_dose_log(&dose).unwrap();
+        let mut metric = BodyMetric::new(OffsetDateTime::now_utc());
+        metric.weight_kg = Some(80.5);
+        storage.upsert_body_metric(&metric).unwrap();
+        for (kind, file_name, mime_type) in [
+            (AttachmentKind::CertificateOfAnalysis, "coa.pdf", "application/pdf"),
+            (AttachmentKind::Photo, "site.jpg", "image/jpeg"),
+        ] {
+            let attachment =
+                Attachment::new(AttachmentOwner::DoseLog, dose.id.as_str(), kind, file_name, mime_type, 4);
+            storage.add_attachment(&attachment, b"data", None).unwrap();
+        }
+
+        let backup = BackupData::collect(&storage, &AttachmentBackupOptions::default()).unwrap();
+        assert_eq!(backup.metadata.protocols_count, 1);
+        assert_eq!(backup.metadata.doses_count, 1);
+        assert_eq!(backup.dose_logs[0]["id"], dose.id);
+        assert_eq!(backup.body_metrics[0]["weight_kg"], 80.5);
+        assert_eq!(backup.attachments.len(), 1);
+        assert_eq!(backup.attachments[0].attachment["file_name"], "coa.pdf");
+        assert_eq!(backup.attachments[0].data_base64, STANDARD.encode(b"data"));
+    }
+}
@@ -0,0 +1,132 @@
+//! Passwords for external services, such as the SMTP server used for email
+//! digests
+//!
+//! On macOS they are kept in the Keychain under the
+//! `com.peptrack.credentials` service, one item per account. Other platforms
+//! have no Keychain; there they are kept in `credentials.json` in the data
+//! directory, readable only by the user, like the Google Drive tokens.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+const SERVICE_NAME: &str = "com.peptrack.credentials";
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+const CREDENTIALS_FILE_NAME: &str = "credentials.json";
+
+/// Store `secret` for `account`, replacing any previous one
+#[cfg(target_os = "macos")]
+pub fn store_credential(_data_dir: &Path, account: &str, secret: &str) -> Result<()> {
+    security_framework::passwords::set_generic_password(SERVICE_NAME, account, secret.as_bytes())
+        .map_err(|e| anyhow::anyhow!("Failed to store credential in Keychain: {}", e))
+}
+
+/// The secret stored for `account`, if any
+#[cfg(target_os = "macos")]
+pub fn load_credential(_data_dir: &Path, account: &str) -> Result<Option<String>> {
+    /// errSecItemNotFound
+    const ITEM_NOT_FOUND: i32 = -25300;
+
+    match security_framework::passwords::get_generic_password(SERVICE_NAME, account) {
+        Ok(bytes) => Ok(Some(String::from_utf8(bytes)?)),
+        Err(e) if e.code() == ITEM_NOT_FOUND => Ok(None),
+        Err(e) => Err(anyhow::anyhow!("Failed to read credential from Keychain: {}", e)),
+    }
+}
+
+/// Remove the secret stored for `account`; succeeds if there was none
+#[cfg(target_os = "macos")]
+pub fn delete_credential(data_dir: &Path, account: &str) -> Result<()> {
+    if load_credential(data_dir, account)?.is_none() {
+        return Ok(());
+    }
+    security_framework::passwords::delete_generic_password(SERVICE_NAME, account)
+        .map_err(|e| anyhow::anyhow!("Failed to delete credential from Keychain: {}", e))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn store_credential(data_dir: &Path, account: &str, secret: &str) -> Result<()> {
+    let mut credentials = file::load(data_dir)?;
+    credentials.insert(account.to_string(), secret.to_string());
+    file::save(data_dir, &credentials)
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn load_credential(data_dir: &Path, account: &str) -> Result<Option<String>> {
+    Ok(file::load(data_dir)?.remove(account))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn delete_credential(data_dir: &Path, account: &str) -> Result<()> {
+    let mut credentials = file::load(data_dir)?;
+    if credentials.remove(account).is_some() {
+        file::save(data_dir, &credentials)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+mod file {
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    use anyhow::{Context, Result};
+
+    use super::CREDENTIALS_FILE_NAME;
+
+    pub(super) fn load(data_dir: &Path) -> Result<BTreeMap<String, String>> {
+        let path = data_dir.join(CREDENTIALS_FILE_NAME);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let json = std::fs::read_to_string(&path).context("Failed to read stored credentials")?;
+        serde_json::from_str(&json).context("Stored credentials are corrupted")
+    }
+
+    pub(super) fn save(data_dir: &Path, credentials: &BTreeMap<String, String>) -> Result<()> {
+        std::fs::create_dir_all(data_dir)?;
+        let path = data_dir.join(CREDENTIALS_FILE_NAME);
+        std::fs::write(&path, serde_json::to_string_pretty(credentials)?)
+            .context("Failed to store credentials")?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_os = "macos")))]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn credentials_round_trip_through_file() {
+        let dir = tempdir().unwrap();
+        assert_eq!(load_credential(dir.path(), "smtp").unwrap(), None);
+
+        store_credential(dir.path(), "smtp", "hunter2").unwrap();
+        store_credential(dir.path(), "other", "secret").unwrap();
+        assert_eq!(load_credential(dir.path(), "smtp").unwrap().as_deref(), Some("hunter2"));
+
+        delete_credential(dir.path(), "smtp").unwrap();
+        delete_credential(dir.path(), "smtp").unwrap();
+        assert_eq!(load_credential(dir.path(), "smtp").unwrap(), None);
+        assert_eq!(load_credential(dir.path(), "other").unwrap().as_deref(), Some("secret"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.path().join(CREDENTIALS_FILE_NAME))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
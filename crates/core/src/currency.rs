@@ -0,0 +1,108 @@
+//! Currency codes and conversion between stored prices
+//!
+//! Prices are stored in the currency they were entered or scraped in. Rates are
+//! kept relative to a USD base so any two currencies can be converted through it.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::models::ExchangeRate;
+
+/// Base currency that all exchange rates are expressed against
+pub const BASE_CURRENCY: &str = "USD";
+
+/// Default currency for prices recorded before multi-currency support
+pub fn default_currency() -> String {
+    BASE_CURRENCY.to_string()
+}
+
+/// Validates and uppercases an ISO 4217 currency code ("eur" -> "EUR")
+pub fn normalize_currency_code(code: &str) -> Result<String> {
+    let trimmed = code.trim();
+    if trimmed.len() != 3 || !trimmed.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(anyhow!("Invalid currency code: {}", code));
+    }
+    Ok(trimmed.to_ascii_uppercase())
+}
+
+/// Converts amounts between currencies using a snapshot of exchange rates
+#[derive(Debug, Clone, Default)]
+pub struct CurrencyConverter {
+    rates: HashMap<String, f64>,
+}
+
+impl CurrencyConverter {
+    pub fn new(rates: &[ExchangeRate]) -> Self {
+        let rates = rates
+            .iter()
+            .filter(|rate| rate.rate_per_usd > 0.0)
+            .map(|rate| (rate.currency.to_ascii_uppercase(), rate.rate_per_usd))
+            .collect();
+        Self { rates }
+    }
+
+    fn rate_for(&self, currency: &str) -> Result<f64> {
+        if currency.eq_ignore_ascii_case(BASE_CURRENCY) {
+            return Ok(1.0);
+        }
+        self.rates
+            .get(&currency.to_ascii_uppercase())
+            .copied()
+            .ok_or_else(|| anyhow!("No exchange rate for {}", currency))
+    }
+
+    /// Returns true if amounts in `currency` can be converted
+    pub fn supports(&self, currency: &str) -> bool {
+        self.rate_for(currency).is_ok()
+    }
+
+    /// Converts `amount` from one currency to another
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> Result<f64> {
+        if from.eq_ignore_ascii_case(to) {
+            return Ok(amount);
+        }
+        let from_rate = self.rate_for(from)?;
+        let to_rate = self.rate_for(to)?;
+        Ok(amount / from_rate * to_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RateSource;
+
+    fn converter() -> CurrencyConverter {
+        CurrencyConverter::new(&[
+            ExchangeRate::new("EUR", 0.5, RateSource::Manual),
+            ExchangeRate::new("GBP", 0.25, RateSource::Fetched),
+        ])
+    }
+
+    #[test]
+    fn converts_through_base_currency() {
+        let converter = converter();
+
+        assert_eq!(converter.convert(10.0, "USD", "EUR").unwrap(), 5.0);
+        assert_eq!(converter.convert(5.0, "EUR", "USD").unwrap(), 10.0);
+        assert_eq!(converter.convert(4.0, "EUR", "GBP").unwrap(), 2.0);
+        assert_eq!(converter.convert(3.0, "eur", "EUR").unwrap(), 3.0);
+    }
+
+    #[test]
+    fn missing_rate_is_an_error() {
+        let converter = converter();
+
+        assert!(converter.convert(1.0, "JPY", "USD").is_err());
+        assert!(!converter.supports("JPY"));
+        assert!(converter.supports("usd"));
+    }
+
+    #[test]
+    fn normalizes_currency_codes() {
+        assert_eq!(normalize_currency_code(" eur ").unwrap(), "EUR");
+        assert!(normalize_currency_code("EURO").is_err());
+        assert!(normalize_currency_code("U5D").is_err());
+    }
+}
@@ -0,0 +1,606 @@
+//! Imports from other dose trackers and spreadsheets
+//!
+//! An [`ImportProfile`] describes how a CSV or JSON export maps onto
+//! PepTrack records: which column (or JSON key) holds each field, how dates
+//! are written and which units amounts are in. Profiles are saved, so the
+//! next export from the same app imports without redoing the mapping.
+//!
+//! [`plan_import`] turns the rows of a file into records without writing
+//! anything. Rows that can't be read are reported with the field at fault,
+//! and records already in PepTrack (or repeated within the file) are
+//! counted as duplicates and left out, so importing the same file twice
+//! changes nothing.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::format_description::well_known::Rfc3339;
+use time::format_description::{self, OwnedFormatItem};
+use time::macros::format_description;
+use time::{Date, OffsetDateTime, PrimitiveDateTime};
+
+use crate::models::{BodyMetric, DoseLog, PeptideProtocol};
+use crate::settings::Setting;
+use crate::units::{weight_to_kg, DoseUnit, UnitPreferences, WeightUnit};
+
+/// Site recorded for imported doses that don't say where they were given
+const UNKNOWN_SITE: &str = "Unknown";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// Comma-separated with a header row
+    Csv,
+    /// An array of objects, at the top level or under `records_key`
+    Json,
+}
+
+/// Kind of record an import creates
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportTarget {
+    Protocols,
+    DoseLogs,
+    BodyMetrics,
+}
+
+impl ImportTarget {
+    /// Fields that must be mapped
+    pub fn required_fields(self) -> &'static [&'static str] {
+        match self {
+            ImportTarget::Protocols => &["name", "peptide_name"],
+            // `protocol` is matched against protocol names; unknown ones are created
+            ImportTarget::DoseLogs => &["protocol", "logged_at", "amount"],
+            ImportTarget::BodyMetrics => &["date"],
+        }
+    }
+
+    pub fn optional_fields(self) -> &'static [&'static str] {
+        match self {
+            ImportTarget::Protocols => &["notes", "target_concentration_mg_ml"],
+            ImportTarget::DoseLogs => &["site", "notes", "peptide_name"],
+            ImportTarget::BodyMetrics => &[
+                "weight",
+                "body_fat_percentage",
+                "resting_heart_rate_bpm",
+                "sleep_hours",
+                "notes",
+            ],
+        }
+    }
+}
+
+/// How to read one app's or spreadsheet's export
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportProfile {
+    pub name: String,
+    pub format: ImportFormat,
+    pub target: ImportTarget,
+    /// PepTrack field to the CSV column or JSON key holding it; nested JSON
+    /// keys are joined with `.`, e.g. `dose.amount`
+    pub fields: BTreeMap<String, String>,
+    /// Format of dates that aren't ISO 8601, in `time`'s format description
+    /// syntax, e.g. `[month]/[day]/[year] [hour]:[minute]`
+    #[serde(default)]
+    pub date_format: Option<String>,
+    /// Unit of dose amounts; IU only works for compounds with a known conversion
+    #[serde(default)]
+    pub dose_unit: DoseUnit,
+    #[serde(default)]
+    pub weight_unit: WeightUnit,
+    /// JSON key holding the array of records, when it isn't the whole file
+    #[serde(default)]
+    pub records_key: Option<String>,
+}
+
+impl ImportProfile {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Give the import profile a name".to_string());
+        }
+        let required = self.target.required_fields();
+        let optional = self.target.optional_fields();
+        for field in required {
+            if self.column(field).is_none() {
+                return Err(format!("Choose the column for {}", field));
+            }
+        }
+        for field in self.fields.keys() {
+            if !required.contains(&field.as_str()) && !optional.contains(&field.as_str()) {
+                return Err(format!("{} can't be imported into {:?}", field, self.target));
+            }
+        }
+        if let Some(format) = self.date_format() {
+            format_description::parse_owned::<1>(format)
+                .map_err(|e| format!("Invalid date format: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn date_format(&self) -> Option<&str> {
+        self.date_format
+            .as_deref()
+            .map(str::trim)
+            .filter(|format| !format.is_empty())
+    }
+
+    /// Source column mapped to `field`, if any
+    fn column(&self, field: &str) -> Option<&str> {
+        self.fields
+            .get(field)
+            .map(|column| column.trim())
+            .filter(|column| !column.is_empty())
+    }
+}
+
+/// Saved import profiles
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImportProfiles {
+    pub profiles: Vec<ImportProfile>,
+}
+
+impl Setting for ImportProfiles {
+    const KEY: &'static str = "import.profiles";
+
+    fn validate(&self) -> Result<(), String> {
+        let mut names = HashSet::new();
+        for profile in &self.profiles {
+            profile.validate()?;
+            if !names.insert(profile.name.trim().to_lowercase()) {
+                return Err(format!("There is already a profile named {}", profile.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One row of the source file, column name to value
+pub type SourceRow = BTreeMap<String, String>;
+
+fn flatten_json(prefix: &str, value: &Value, row: &mut SourceRow) {
+    let key = |name: &str| {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", prefix, name)
+        }
+    };
+    match value {
+        Value::Object(fields) => {
+            for (name, value) in fields {
+                flatten_json(&key(name), value, row);
+            }
+        }
+        Value::Null => {}
+        Value::String(text) => {
+            row.insert(prefix.to_string(), text.clone());
+        }
+        other => {
+            row.insert(prefix.to_string(), other.to_string());
+        }
+    }
+}
+
+/// Read the rows of a CSV or JSON export
+pub fn read_rows(data: &[u8], format: ImportFormat, records_key: Option<&str>) -> Result<Vec<SourceRow>> {
+    match format {
+        ImportFormat::Csv => {
+            let mut csv = csv::ReaderBuilder::new()
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .from_reader(data);
+            let headers = csv.headers().context("Failed to read CSV header")?.clone();
+            csv.records()
+                .map(|record| {
+                    let record = record.context("Failed to read CSV row")?;
+                    Ok(headers
+                        .iter()
+                        .zip(record.iter())
+                        .filter(|(_, value)| !value.is_empty())
+                        .map(|(header, value)| (header.to_string(), value.to_string()))
+                        .collect())
+                })
+                .collect()
+        }
+        ImportFormat::Json => {
+            let root: Value = serde_json::from_slice(data).context("File is not valid JSON")?;
+            let records = match records_key.map(str::trim).filter(|key| !key.is_empty()) {
+                Some(key) => key
+                    .split('.')
+                    .try_fold(&root, |value, part| value.get(part))
+                    .ok_or_else(|| anyhow!("No {} key in the file", key))?,
+                None => &root,
+            };
+            let records = records
+                .as_array()
+                .ok_or_else(|| anyhow!("Expected a list of records"))?;
+            Ok(records
+                .iter()
+                .map(|record| {
+                    let mut row = SourceRow::new();
+                    flatten_json("", record, &mut row);
+                    row
+                })
+                .collect())
+        }
+    }
+}
+
+/// Every column name in `rows`, in order of first appearance
+pub fn source_columns(rows: &[SourceRow]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for column in rows.iter().flat_map(|row| row.keys()) {
+        if !columns.contains(column) {
+            columns.push(column.clone());
+        }
+    }
+    columns
+}
+
+/// A row that couldn't be imported
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRowError {
+    /// 1-based, not counting a CSV header
+    pub row: usize,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+/// Records an import would create
+#[derive(Debug, Clone, Default)]
+pub struct ImportPlan {
+    pub total_rows: usize,
+    /// New protocols, including those created for dose logs of unknown protocols
+    pub protocols: Vec<PeptideProtocol>,
+    pub dose_logs: Vec<DoseLog>,
+    pub body_metrics: Vec<BodyMetric>,
+    /// Rows already in PepTrack or earlier in the file
+    pub duplicates: usize,
+    pub errors: Vec<ImportRowError>,
+}
+
+impl ImportPlan {
+    /// Records to write, not counting protocols created for dose logs
+    pub fn record_count(&self, target: ImportTarget) -> usize {
+        match target {
+            ImportTarget::Protocols => self.protocols.len(),
+            ImportTarget::DoseLogs => self.dose_logs.len(),
+            ImportTarget::BodyMetrics => self.body_metrics.len(),
+        }
+    }
+}
+
+/// Records already stored, for duplicate detection
+#[derive(Debug, Clone, Copy)]
+pub struct ExistingRecords<'a> {
+    pub protocols: &'a [PeptideProtocol],
+    pub dose_logs: &'a [DoseLog],
+    pub body_metrics: &'a [BodyMetric],
+}
+
+type FieldError = (Option<String>, String);
+
+struct RowReader<'a> {
+    profile: &'a ImportProfile,
+    row: &'a SourceRow,
+    date_format: Option<&'a OwnedFormatItem>,
+}
+
+impl RowReader<'_> {
+    fn text(&self, field: &str) -> Option<String> {
+        self.profile
+            .column(field)
+            .and_then(|column| self.row.get(column))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+
+    fn required(&self, field: &str) -> Result<String, FieldError> {
+        self.text(field)
+            .ok_or_else(|| (Some(field.to_string()), "Missing value".to_string()))
+    }
+
+    fn number(&self, field: &str) -> Result<Option<f32>, FieldError> {
+        match self.text(field) {
+            None => Ok(None),
+            Some(text) => text
+                .parse::<f32>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .map(Some)
+                .ok_or_else(|| (Some(field.to_string()), format!("{} is not a number", text))),
+        }
+    }
+
+    fn date(&self, field: &str) -> Result<OffsetDateTime, FieldError> {
+        let text = self.required(field)?;
+        parse_date(&text, self.date_format)
+            .ok_or_else(|| (Some(field.to_string()), format!("{} is not a date", text)))
+    }
+}
+
+/// Parse a date or date and time; times without an offset are taken as UTC
+fn parse_date(text: &str, format: Option<&OwnedFormatItem>) -> Option<OffsetDateTime> {
+    if let Some(format) = format {
+        return PrimitiveDateTime::parse(text, format)
+            .map(PrimitiveDateTime::assume_utc)
+            .or_else(|_| Date::parse(text, format).map(|date| date.midnight().assume_utc()))
+            .ok();
+    }
+    if let Ok(at) = OffsetDateTime::parse(text, &Rfc3339) {
+        return Some(at);
+    }
+    let date_times = [
+        format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"),
+        format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]"),
+        format_description!("[year]-[month]-[day] [hour]:[minute]"),
+        format_description!("[year]-[month]-[day]T[hour]:[minute]"),
+    ];
+    date_times
+        .iter()
+        .find_map(|format| PrimitiveDateTime::parse(text, format).ok())
+        .map(PrimitiveDateTime::assume_utc)
+        .or_else(|| {
+            Date::parse(text, format_description!("[year]-[month]-[day]"))
+                .ok()
+                .map(|date| date.midnight().assume_utc())
+        })
+}
+
+/// Doses are duplicates when they're for the same protocol, at the same
+/// minute, with the same amount
+fn dose_key(protocol_id: &str, logged_at: OffsetDateTime, amount_mg: f32) -> (String, i64, i64) {
+    (
+        protocol_id.to_string(),
+        logged_at.unix_timestamp() / 60,
+        (amount_mg as f64 * 1000.0).round() as i64,
+    )
+}
+
+/// Turn the rows of an export into records, without writing anything
+pub fn plan_import(rows: &[SourceRow], profile: &ImportProfile, existing: ExistingRecords) -> ImportPlan {
+    let mut plan = ImportPlan {
+        total_rows: rows.len(),
+        ..ImportPlan::default()
+    };
+    let date_format = match profile.date_format().map(format_description::parse_owned::<1>) {
+        Some(Ok(format)) => Some(format),
+        Some(Err(e)) => {
+            plan.errors.push(ImportRowError {
+                row: 0,
+                field: None,
+                message: format!("Invalid date format: {}", e),
+            });
+            return plan;
+        }
+        None => None,
+    };
+
+    // Protocols by lowercase name, existing ones first
+    let mut protocol_ids: HashMap<String, String> = existing
+        .protocols
+        .iter()
+        .map(|protocol| (protocol.name.trim().to_lowercase(), protocol.id.clone()))
+        .collect();
+    let mut dose_keys: HashSet<_> = existing
+        .dose_logs
+        .iter()
+        .map(|dose| dose_key(&dose.protocol_id, dose.logged_at, dose.amount_mg))
+        .collect();
+    let mut metric_days: HashSet<Date> = existing.body_metrics.iter().map(|metric| metric.date.date()).collect();
+    let units = UnitPreferences::default();
+
+    for (index, row) in rows.iter().enumerate() {
+        let reader = RowReader {
+            profile,
+            row,
+            date_format: date_format.as_ref(),
+        };
+        let result: Result<(), FieldError> = (|| {
+            match profile.target {
+                ImportTarget::Protocols => {
+                    let name = reader.required("name")?;
+                    let peptide_name = reader.required("peptide_name")?;
+                    let target_concentration = reader.number("target_concentration_mg_ml")?;
+                    if protocol_ids.contains_key(&name.to_lowercase()) {
+                        plan.duplicates += 1;
+                        return Ok(());
+                    }
+                    let mut protocol = PeptideProtocol::new(name.clone(), peptide_name);
+                    protocol.notes = reader.text("notes");
+                    protocol.target_concentration_mg_ml = target_concentration;
+                    protocol_ids.insert(name.to_lowercase(), protocol.id.clone());
+                    plan.protocols.push(protocol);
+                }
+                ImportTarget::DoseLogs => {
+                    let protocol_name = reader.required("protocol")?;
+                    let logged_at = reader.date("logged_at")?;
+                    let amount = reader
+                        .number("amount")?
+                        .ok_or_else(|| (Some("amount".to_string()), "Missing value".to_string()))?;
+                    let peptide_name = reader.text("peptide_name");
+                    let amount_mg = units
+                        .dose_to_mg(
+                            Some(peptide_name.as_deref().unwrap_or(&protocol_name)),
+                            amount,
+                            profile.dose_unit,
+                        )
+                        .map_err(|e| (Some("amount".to_string()), e))?;
+                    if amount_mg <= 0.0 {
+                        return Err((Some("amount".to_string()), "Amount must be positive".to_string()));
+                    }
+
+                    let protocol_id = match protocol_ids.get(&protocol_name.to_lowercase()) {
+                        Some(id) => id.clone(),
+                        None => {
+                            let protocol = PeptideProtocol::new(
+                                protocol_name.clone(),
+                                peptide_name.unwrap_or_else(|| protocol_name.clone()),
+                            );
+                            protocol_ids.insert(protocol_name.to_lowercase(), protocol.id.clone());
+                            let id = protocol.id.clone();
+                            plan.protocols.push(protocol);
+                            id
+                        }
+                    };
+                    if !dose_keys.insert(dose_key(&protocol_id, logged_at, amount_mg)) {
+                        plan.duplicates += 1;
+                        return Ok(());
+                    }
+                    let site = reader.text("site").unwrap_or_else(|| UNKNOWN_SITE.to_string());
+                    let mut dose = DoseLog::new(protocol_id, site, amount_mg);
+                    dose.logged_at = logged_at;
+                    dose.notes = reader.text("notes");
+                    plan.dose_logs.push(dose);
+                }
+                ImportTarget::BodyMetrics => {
+                    let date = reader.date("date")?;
+                    let mut metric = BodyMetric::new(date);
+                    metric.weight_kg = reader
+                        .number("weight")?
+                        .map(|weight| weight_to_kg(weight, profile.weight_unit));
+                    metric.body_fat_percentage = reader.number("body_fat_percentage")?;
+                    metric.resting_heart_rate_bpm = reader.number("resting_heart_rate_bpm")?;
+                    metric.sleep_hours = reader.number("sleep_hours")?;
+                    metric.notes = reader.text("notes");
+                    if !metric_days.insert(date.date()) {
+                        plan.duplicates += 1;
+                        return Ok(());
+                    }
+                    plan.body_metrics.push(metric);
+                }
+            }
+            Ok(())
+        })();
+
+        if let Err((field, message)) = result {
+            plan.errors.push(ImportRowError {
+                row: index + 1,
+                field,
+                message,
+            });
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dose_profile() -> ImportProfile {
+        ImportProfile {
+            name: "Spreadsheet".into(),
+            format: ImportFormat::Csv,
+            target: ImportTarget::DoseLogs,
+            fields: [
+                ("protocol", "Compound"),
+                ("logged_at", "When"),
+                ("amount", "Dose (mcg)"),
+                ("site", "Site"),
+            ]
+            .into_iter()
+            .map(|(field, column)| (field.to_string(), column.to_string()))
+            .collect(),
+            date_format: Some("[month]/[day]/[year] [hour]:[minute]".into()),
+            dose_unit: DoseUnit::Mcg,
+            weight_unit: WeightUnit::Kg,
+            records_key: None,
+        }
+    }
+
+    #[test]
+    fn dose_rows_are_mapped_deduplicated_and_errors_reported() {
+        let csv = "Compound,When,Dose (mcg),Site\n\
+                   BPC-157,03/01/2025 08:00,250,Abdomen\n\
+                   BPC-157,03/01/2025 08:00,250,Abdomen\n\
+                   TB-500,03/02/2025 09:30,2000,\n\
+                   BPC-157,yesterday,250,Thigh\n\
+                   BPC-157,03/03/2025 08:00,lots,Thigh\n";
+        let rows = read_rows(csv.as_bytes(), ImportFormat::Csv, None).unwrap();
+        let existing_protocol = PeptideProtocol::new("bpc-157", "BPC-157");
+        let mut logged = DoseLog::new(existing_protocol.id.clone(), "Abdomen".into(), 0.25);
+        logged.logged_at = OffsetDateTime::parse("2025-03-01T08:00:00Z", &Rfc3339).unwrap();
+
+        let existing = ExistingRecords {
+            protocols: std::slice::from_ref(&existing_protocol),
+            dose_logs: &[],
+            body_metrics: &[],
+        };
+        let plan = plan_import(&rows, &dose_profile(), existing);
+        assert_eq!(plan.total_rows, 5);
+        assert_eq!(plan.dose_logs.len(), 2);
+        assert_eq!(plan.duplicates, 1);
+        assert_eq!(plan.dose_logs[0].protocol_id, existing_protocol.id);
+        assert_eq!(plan.dose_logs[0].amount_mg, 0.25);
+        assert_eq!(plan.dose_logs[1].site, UNKNOWN_SITE);
+        // TB-500 isn't a protocol yet, so one is created for it
+        assert_eq!(plan.protocols.len(), 1);
+        assert_eq!(plan.protocols[0].name, "TB-500");
+        let errors: Vec<_> = plan.errors.iter().map(|e| (e.row, e.field.as_deref())).collect();
+        assert_eq!(errors, vec![(4, Some("logged_at")), (5, Some("amount"))]);
+
+        // Doses already logged are duplicates too
+        let existing = ExistingRecords {
+            protocols: std::slice::from_ref(&existing_protocol),
+            dose_logs: std::slice::from_ref(&logged),
+            body_metrics: &[],
+        };
+        let plan = plan_import(&rows, &dose_profile(), existing);
+        assert_eq!((plan.dose_logs.len(), plan.duplicates), (1, 2));
+    }
+
+    #[test]
+    fn json_records_are_read_from_nested_keys() {
+        let json = r#"{"data": {"entries": [
+            {"day": "2025-03-01", "body": {"weight": 180}},
+            {"day": "2025-03-02", "body": {"weight": null}, "note": "rest day"}
+        ]}}"#;
+        let rows = read_rows(json.as_bytes(), ImportFormat::Json, Some("data.entries")).unwrap();
+        assert_eq!(source_columns(&rows), vec!["body.weight", "day", "note"]);
+
+        let profile = ImportProfile {
+            name: "Other app".into(),
+            format: ImportFormat::Json,
+            target: ImportTarget::BodyMetrics,
+            fields: [("date", "day"), ("weight", "body.weight"), ("notes", "note")]
+                .into_iter()
+                .map(|(field, column)| (field.to_string(), column.to_string()))
+                .collect(),
+            date_format: None,
+            dose_unit: DoseUnit::Mg,
+            weight_unit: WeightUnit::Lb,
+            records_key: Some("data.entries".into()),
+        };
+        assert!(profile.validate().is_ok());
+        let plan = plan_import(
+            &rows,
+            &profile,
+            ExistingRecords {
+                protocols: &[],
+                dose_logs: &[],
+                body_metrics: &[],
+            },
+        );
+        assert!(plan.errors.is_empty());
+        assert_eq!(plan.body_metrics.len(), 2);
+        assert!((plan.body_metrics[0].weight_kg.unwrap() - 81.65).abs() < 0.01);
+        assert_eq!(plan.body_metrics[1].notes.as_deref(), Some("rest day"));
+    }
+
+    #[test]
+    fn profiles_need_their_required_fields() {
+        let mut profile = dose_profile();
+        assert!(profile.validate().is_ok());
+        profile.fields.remove("amount");
+        assert!(profile.validate().unwrap_err().contains("amount"));
+        profile.fields.insert("amount".into(), "Dose".into());
+        profile.fields.insert("weight".into(), "Weight".into());
+        assert!(profile.validate().is_err());
+    }
+}
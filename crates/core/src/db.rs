@@ -1,19 +1,51 @@
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
 
 use anyhow::{Context, Result};
 use dirs::data_dir;
-use rusqlite::{params, Connection, OptionalExtension};
-use time::OffsetDateTime;
+use rand::{rngs::OsRng, RngCore};
+use rusqlite::{params, Connection, DatabaseName, OptionalExtension, Row, ToSql, Transaction, TransactionBehavior};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use time::macros::format_description;
+use time::{Date, OffsetDateTime};
 use tracing::info;
+use zeroize::Zeroizing;
 
+use crate::ai_usage::{self, AiUsageStats};
+use crate::alerts::{AlertDelivery, AlertPreferences};
+use crate::analytics_export::{self, AnalyticsExport, ExportedTable};
+use crate::audit::{self, AuditEntityType, AuditEntry, AuditLogFilter, AuditOperation, AuditRetention};
+use crate::dose_stats::{site_code, DailyDoseTotal, DoseStatsFilter, ProtocolDoseUsage, SiteDoseUsage};
 use crate::encryption::{EnvelopeEncryption, KeyProvider};
+use crate::events::{StorageEvent, StorageListener};
+use crate::journal::{parse_links, JournalLinkKind};
+use crate::key_recovery::KeyRecovery;
+use crate::key_rotation::KeyRotationProgress;
+use crate::migration::{self, MigrationFailed, MigrationSnapshot};
+use crate::recovery::{self, RecoveryProgress, SalvageReport};
+use crate::response_cache::{response_key_hash, ResponseCacheStats, SourceCacheStats, MAX_RESPONSE_TTL_HOURS};
+use crate::price_trend::{self, PriceTrend, PriceTrendFilter, PriceTrendPoint, PriceTrendSeries};
+use crate::pool::{ConnectionPool, PooledConnection, Writer, WriterConnection, STATEMENT_CACHE_CAPACITY};
+use crate::search::{self, SearchDocument, SearchEntityType, SearchHit};
+use crate::stats_cache::{CachedStat, DashboardStat, StatsGeneration, MAX_STAT_AGE};
+use crate::trash::{TrashEntityType, TrashItem};
+use crate::undo::UndoAction;
+use crate::maintenance::{MaintenanceAction, MaintenanceRun, MAX_MAINTENANCE_RUNS};
+use crate::orphans::{self, CascadePolicies, OrphanKind, OrphanRepair, OrphanedRecord, StaleAlertPolicy, SupplierInventoryPolicy};
+use crate::settings::{self, Setting};
+use crate::sync::{self, SyncApplied, SyncChange, SyncCursor};
+use crate::tamper::{self, BrokenLink, TamperChange, TamperFinding, TamperReport, CHAINED_TABLES};
 use crate::models::{
-    Alert, BodyMetric, DatabaseStats, DoseLog, HealthReport, InventoryItem, LiteratureEntry, PeptideProtocol,
-    PriceHistory, SideEffect, Supplier, SummaryHistory,
+    Alert, Attachment, AttachmentOwner, BodyMetric, DatabaseStats, DoseLog, DoseLogCorrection, DoseSkip, ExchangeRate, HealthReport, InventoryItem, LiteratureEmbedding, LiteratureEntry, LiteratureRetention, Order, PeptideProtocol,
+    Goal, JournalEntry, LabResult, ObservationStatus, PriceHistory, PriceObservation, SavedSearch, SideEffect, Supplier, SupplierDeletion, SummaryHistory, TrialResult, VialStatus,
 };
 
 const DEFAULT_DB_NAME: &str = "peptrack.sqlite";
+/// Days accepted and rejected price observations are kept
+pub const REVIEWED_OBSERVATION_DAYS: i64 = 90;
 
 // PepTrack Application ID (unique identifier for this SQLite database)
 // Generated from: "PepTrack".as_bytes() hashed
@@ -22,6 +54,58 @@ const PEPTRACK_APP_ID: i32 = 0x50657054; // "PepT" in hex
 // Current schema version for migrations
 const SCHEMA_VERSION: i32 = 2;
 
+/// Columns added by [`StorageManager::run_migrations`] as `(table, column)`
+///
+/// A database with one of these tables but not its column still needs
+/// migrating, so it is snapshotted first; add new migrations here.
+const MIGRATED_COLUMNS: &[(&str, &str)] = &[
+    ("protocols", "is_favorite"),
+    ("attachments", "thumbnail"),
+    ("protocols", "deleted_at"),
+    ("dose_logs", "deleted_at"),
+    ("body_metrics", "deleted_at"),
+    ("dose_logs", "amount_mg"),
+    ("literature_cache", "enriched_at"),
+    ("summary_history", "source_id"),
+    ("dose_logs", "edited_at"),
+    ("price_history", "cost_per_mg"),
+];
+
+/// Known plaintext sealed into `key_check`, used to tell whether the current
+/// key opens this database
+const KEY_CHECK_PLAINTEXT: &[u8] = b"peptrack-key-check";
+
+/// Every encrypted column as `(table, column)`; re-encryption walks this list
+const ENCRYPTED_COLUMNS: &[(&str, &str)] = &[
+    ("protocols", "payload"),
+    ("dose_logs", "payload"),
+    ("dose_log_corrections", "payload"),
+    ("literature_cache", "payload"),
+    ("suppliers", "payload"),
+    ("inventory", "payload"),
+    ("price_history", "payload"),
+    ("price_observations", "payload"),
+    ("exchange_rates", "payload"),
+    ("orders", "payload"),
+    ("attachments", "payload"),
+    ("attachments", "data"),
+    ("attachments", "thumbnail"),
+    ("alerts", "payload"),
+    ("summary_history", "payload"),
+    ("body_metrics", "payload"),
+    ("side_effects", "payload"),
+    ("lab_results", "payload"),
+    ("journal_entries", "payload"),
+    ("goals", "payload"),
+    ("stats_cache", "payload"),
+    ("settings", "payload"),
+    ("saved_searches", "payload"),
+    ("literature_embeddings", "payload"),
+    ("clinical_trials", "payload"),
+    ("literature_responses", "payload"),
+    ("tamper_key", "payload"),
+];
+
 pub struct StorageConfig {
     pub data_dir: Option<PathBuf>,
     pub db_file_name: Option<String>,
@@ -41,9 +125,227 @@ impl StorageConfig {
     }
 }
 
+/// Paging for `list_*_page` methods; the default returns every record
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListOptions {
+    /// Most records to return
+    pub limit: Option<usize>,
+    /// Records to skip before the first one returned
+    pub offset: usize,
+    /// Only records timestamped at or after this time
+    pub since: Option<OffsetDateTime>,
+}
+
+impl ListOptions {
+    /// The `limit` records after the first `offset`
+    pub fn page(limit: usize, offset: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            offset,
+            since: None,
+        }
+    }
+
+    /// Every record at or after `since`
+    pub fn since(since: OffsetDateTime) -> Self {
+        Self {
+            since: Some(since),
+            ..Self::default()
+        }
+    }
+}
+
+/// A payload list query that `list_page` can page through
+struct PagedQuery {
+    /// Selects the encrypted payload and ends in a WHERE condition
+    select: &'static str,
+    /// TEXT timestamp column that `since` applies to
+    time_column: &'static str,
+    order_by: &'static str,
+}
+
+const PROTOCOLS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM protocols WHERE deleted_at IS NULL",
+    time_column: "updated_at",
+    order_by: "is_favorite DESC, updated_at DESC",
+};
+
+const DOSE_LOGS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM dose_logs WHERE deleted_at IS NULL",
+    time_column: "logged_at",
+    order_by: "logged_at DESC",
+};
+
+const PROTOCOL_DOSE_LOGS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM dose_logs WHERE protocol_id = ?1 AND deleted_at IS NULL",
+    time_column: "logged_at",
+    order_by: "logged_at DESC",
+};
+
+const BODY_METRICS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM body_metrics WHERE deleted_at IS NULL",
+    time_column: "date",
+    order_by: "date DESC",
+};
+
+const SIDE_EFFECTS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM side_effects WHERE 1",
+    time_column: "date",
+    order_by: "date DESC",
+};
+
+const PROTOCOL_SIDE_EFFECTS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM side_effects WHERE protocol_id = ?1",
+    time_column: "date",
+    order_by: "date DESC",
+};
+
+const LAB_RESULTS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM lab_results WHERE 1",
+    time_column: "collected_at",
+    order_by: "collected_at DESC",
+};
+
+const MARKER_LAB_RESULTS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM lab_results WHERE marker = ?1 COLLATE NOCASE",
+    time_column: "collected_at",
+    order_by: "collected_at ASC",
+};
+
+const JOURNAL_ENTRIES_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM journal_entries WHERE 1",
+    time_column: "entry_date",
+    order_by: "entry_date DESC",
+};
+
+const GOALS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM goals WHERE 1",
+    time_column: "start_date",
+    order_by: "start_date DESC",
+};
+
+const LITERATURE_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM literature_cache WHERE 1",
+    time_column: "indexed_at",
+    order_by: "indexed_at DESC",
+};
+
+const SUPPLIERS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM suppliers WHERE 1",
+    time_column: "updated_at",
+    order_by: "name ASC",
+};
+
+const INVENTORY_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM inventory WHERE 1",
+    time_column: "updated_at",
+    order_by: "updated_at DESC",
+};
+
+const PROTOCOL_INVENTORY_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM inventory WHERE protocol_id = ?1",
+    time_column: "updated_at",
+    order_by: "updated_at DESC",
+};
+
+const EXCHANGE_RATES_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM exchange_rates WHERE 1",
+    time_column: "updated_at",
+    order_by: "currency ASC",
+};
+
+const ORDERS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM orders WHERE 1",
+    time_column: "order_date",
+    order_by: "order_date DESC",
+};
+
+const ATTACHMENTS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM attachments WHERE 1",
+    time_column: "created_at",
+    order_by: "created_at DESC",
+};
+const OWNER_ATTACHMENTS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM attachments WHERE owner_type = ?1 AND owner_id = ?2",
+    time_column: "created_at",
+    order_by: "created_at DESC",
+};
+
+const ALERTS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM alerts WHERE is_dismissed = 0",
+    time_column: "created_at",
+    order_by: "created_at DESC",
+};
+
+const ALL_ALERTS_QUERY: PagedQuery = PagedQuery {
+    select: "SELECT payload FROM alerts WHERE 1",
+    time_column: "created_at",
+    order_by: "created_at DESC",
+};
+
+/// Writes that are committed together, see [`StorageManager::transaction`]
+pub struct StorageTransaction<'a> {
+    storage: &'a StorageManager,
+    tx: Transaction<'a>,
+}
+
+impl StorageTransaction<'_> {
+    pub fn upsert_protocol(&self, protocol: &PeptideProtocol) -> Result<()> {
+        self.storage.write_protocol(&self.tx, protocol)?;
+        self.storage.invalidate_stats_on(&self.tx, "protocols")
+    }
+
+    pub fn upsert_inventory_item(&self, item: &InventoryItem) -> Result<()> {
+        self.storage.write_inventory_item(&self.tx, item)?;
+        self.storage.invalidate_stats_on(&self.tx, "inventory")
+    }
+
+    pub fn append_dose_log(&self, log: &DoseLog) -> Result<()> {
+        self.storage.write_dose_log(&self.tx, log)?;
+        self.storage.invalidate_stats_on(&self.tx, "dose_logs")
+    }
+
+    pub fn upsert_body_metric(&self, metric: &BodyMetric) -> Result<()> {
+        self.storage.write_body_metric(&self.tx, metric)
+    }
+
+    pub fn upsert_journal_entry(&self, entry: &JournalEntry) -> Result<()> {
+        self.storage.write_journal_entry(&self.tx, entry)
+    }
+
+    pub fn cache_literature(&self, entry: &LiteratureEntry) -> Result<()> {
+        self.storage.write_literature(&self.tx, entry)
+    }
+
+    pub fn add_attachment(&self, attachment: &Attachment, data: &[u8], thumbnail: Option<&[u8]>) -> Result<()> {
+        self.storage.write_attachment(&self.tx, attachment, data, thumbnail)
+    }
+
+    /// Drop cached stats computed from `table`, for writes made through
+    /// [`connection`](Self::connection)
+    pub fn invalidate_stats(&self, table: &str) -> Result<()> {
+        self.storage.invalidate_stats_on(&self.tx, table)
+    }
+
+    /// The transaction itself, for tables kept outside `StorageManager`
+    ///
+    /// Like [`StorageManager::connection`] this bypasses encryption.
+    pub fn connection(&self) -> &Connection {
+        &self.tx
+    }
+}
+
 pub struct StorageManager {
     db_path: PathBuf,
     encryption: EnvelopeEncryption,
+    /// Read-only connections
+    connections: ConnectionPool,
+    /// The one connection that writes; see [`crate::pool`]
+    writer: Writer,
+    /// Bumped whenever cached stats are invalidated
+    stats_generation: AtomicU64,
+    /// Told about writes; see [`crate::events`]
+    listeners: RwLock<Vec<StorageListener>>,
 }
 
 impl StorageManager {
@@ -53,10 +355,66 @@ impl StorageManager {
         Ok(Self {
             db_path,
             encryption,
+            connections: ConnectionPool::default(),
+            writer: Writer::default(),
+            stats_generation: AtomicU64::new(0),
+            listeners: RwLock::new(Vec::new()),
+        })
+    }
+
+    /// Call `listener` with every [`StorageEvent`] from now on
+    pub fn subscribe(&self, listener: StorageListener) {
+        if let Ok(mut listeners) = self.listeners.write() {
+            listeners.push(listener);
+        }
+    }
+
+    fn publish(&self, event: StorageEvent) {
+        let listeners = match self.listeners.read() {
+            Ok(listeners) => listeners.clone(),
+            Err(_) => return,
+        };
+        for listener in listeners {
+            listener(&event);
+        }
+    }
+
+    /// Borrow a read-only connection, reusing an idle one when possible
+    fn open_connection(&self) -> Result<PooledConnection<'_>> {
+        let conn = match self.connections.take() {
+            Some(conn) => conn,
+            None => {
+                let conn = self.open_new_connection()?;
+                conn.execute_batch("PRAGMA query_only=ON;")
+                    .context("Unable to make connection read-only")?;
+                conn
+            }
+        };
+        Ok(self.connections.lend(conn))
+    }
+
+    /// Borrow the write connection once every earlier write has finished
+    ///
+    /// Calling another writing method while holding it fails rather than
+    /// waiting for itself.
+    fn write_connection(&self) -> Result<WriterConnection<'_>> {
+        self.writer.acquire(|| {
+            let mut conn = self.open_new_connection()?;
+            conn.execute_batch(&format!(
+                "PRAGMA application_id={};
+                 PRAGMA user_version={};",
+                PEPTRACK_APP_ID, SCHEMA_VERSION
+            ))
+            .context("Unable to set database metadata")?;
+            // Take the write lock up front, so a transaction never fails
+            // upgrading from a read when another process is writing
+            conn.set_transaction_behavior(TransactionBehavior::Immediate);
+            self.install_tamper_chain(&conn)?;
+            Ok(conn)
         })
     }
 
-    fn open_connection(&self) -> Result<Connection> {
+    fn open_new_connection(&self) -> Result<Connection> {
         let conn = Connection::open(&self.db_path)
             .with_context(|| format!("Unable to open database at {}", self.db_path.display()))?;
 
@@ -65,7 +423,7 @@ impl StorageManager {
         // Maximum safety, performance, and integrity
         // =====================================================================
 
-        conn.execute_batch(&format!(
+        conn.execute_batch(
             "-- ═══════════════════════════════════════════════════════════
              -- CORE SAFETY & DURABILITY
              -- ═══════════════════════════════════════════════════════════
@@ -121,25 +479,38 @@ impl StorageManager {
              -- ═══════════════════════════════════════════════════════════
              -- APPLICATION METADATA
              -- ═══════════════════════════════════════════════════════════
-
-             -- Set unique application ID for this database
-             PRAGMA application_id={};
-
-             -- Track schema version for migrations
-             PRAGMA user_version={};
+             -- The application ID and schema version are set by the write
+             -- connection only; see write_connection
 
              -- Ensure UTF-8 encoding
              PRAGMA encoding='UTF-8';",
-            PEPTRACK_APP_ID,
-            SCHEMA_VERSION
-        ))
+        )
         .context("Unable to configure SQLite pragmas")?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
 
         Ok(conn)
     }
 
+    /// Create the schema and migrate an existing database
+    ///
+    /// When migrations are pending, the database is snapshotted first; if
+    /// setting up fails, the snapshot is restored and the error is a
+    /// [`MigrationFailed`].
     pub fn initialize(&self) -> Result<()> {
-        let conn = self.open_connection()?;
+        let snapshot = if self.migrations_pending()? {
+            Some(self.snapshot_before_migration()?)
+        } else {
+            None
+        };
+
+        match (self.initialize_schema(), snapshot) {
+            (Err(e), Some(snapshot)) => Err(self.roll_back_migration(&snapshot, e).into()),
+            (result, _) => result,
+        }
+    }
+
+    fn initialize_schema(&self) -> Result<()> {
+        let conn = self.write_connection()?;
         conn.execute_batch(
             r#"
             CREATE TABLE IF NOT EXISTS protocols (
@@ -147,21 +518,71 @@ impl StorageManager {
                 name TEXT NOT NULL,
                 payload BLOB NOT NULL,
                 updated_at TEXT NOT NULL,
-                is_favorite INTEGER NOT NULL DEFAULT 0
+                is_favorite INTEGER NOT NULL DEFAULT 0,
+                deleted_at INTEGER
             );
 
             CREATE TABLE IF NOT EXISTS dose_logs (
                 id TEXT PRIMARY KEY,
                 protocol_id TEXT NOT NULL REFERENCES protocols(id) ON DELETE CASCADE,
                 payload BLOB NOT NULL,
-                logged_at TEXT NOT NULL
+                logged_at TEXT NOT NULL,
+                deleted_at INTEGER,
+                -- Plaintext copies of non-sensitive fields for SQL aggregates
+                amount_mg REAL,
+                site_code TEXT,
+                schedule_id TEXT,
+                -- Unix timestamp of the last edit, NULL = never edited
+                edited_at INTEGER
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_dose_logs_logged
+                ON dose_logs(logged_at DESC);
+
+            CREATE INDEX IF NOT EXISTS idx_dose_logs_protocol
+                ON dose_logs(protocol_id, logged_at DESC);
+
+            -- A dose log's encrypted payload before each edit, oldest first.
+            -- Only the payload may change, when the key is rotated.
+            -- corrected_at is a unix timestamp.
+            CREATE TABLE IF NOT EXISTS dose_log_corrections (
+                id TEXT PRIMARY KEY,
+                dose_log_id TEXT NOT NULL REFERENCES dose_logs(id) ON DELETE CASCADE,
+                payload BLOB NOT NULL,
+                corrected_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_dose_log_corrections_log
+                ON dose_log_corrections(dose_log_id, corrected_at);
+
+            CREATE TRIGGER IF NOT EXISTS dose_log_corrections_immutable
+            BEFORE UPDATE OF id, dose_log_id, corrected_at ON dose_log_corrections
+            BEGIN
+                SELECT RAISE(ABORT, 'dose_log_corrections can not be edited');
+            END;
+
+            -- Scheduled doses skipped on purpose, at most one per schedule and
+            -- day. Nothing here is sensitive, so it isn't encrypted.
+            -- scheduled_on is an ISO date, skipped_at a unix timestamp.
+            CREATE TABLE IF NOT EXISTS dose_skips (
+                id TEXT PRIMARY KEY,
+                protocol_id TEXT NOT NULL REFERENCES protocols(id) ON DELETE CASCADE,
+                schedule_id TEXT NOT NULL,
+                scheduled_on TEXT NOT NULL,
+                skipped_at INTEGER NOT NULL,
+                UNIQUE (schedule_id, scheduled_on)
             );
 
+            CREATE INDEX IF NOT EXISTS idx_dose_skips_day
+                ON dose_skips(scheduled_on);
+
             CREATE TABLE IF NOT EXISTS literature_cache (
                 id TEXT PRIMARY KEY,
                 source TEXT NOT NULL,
                 payload BLOB NOT NULL,
-                indexed_at TEXT NOT NULL
+                indexed_at TEXT NOT NULL,
+                -- Unix timestamp of the last metadata lookup, NULL if never tried
+                enriched_at INTEGER
             );
 
             CREATE TABLE IF NOT EXISTS suppliers (
@@ -184,12 +605,60 @@ impl StorageManager {
                 supplier_id TEXT NOT NULL REFERENCES suppliers(id) ON DELETE CASCADE,
                 peptide_name TEXT NOT NULL,
                 payload BLOB NOT NULL,
-                recorded_at TEXT NOT NULL
+                recorded_at TEXT NOT NULL,
+                -- Plaintext copies of the price for SQL aggregates
+                cost_per_mg REAL,
+                currency TEXT
             );
 
             CREATE INDEX IF NOT EXISTS idx_price_history_supplier_peptide
                 ON price_history(supplier_id, peptide_name, recorded_at DESC);
 
+            -- Scraped prices waiting for review before they become price history
+            CREATE TABLE IF NOT EXISTS price_observations (
+                id TEXT PRIMARY KEY,
+                supplier_id TEXT NOT NULL REFERENCES suppliers(id) ON DELETE CASCADE,
+                peptide_name TEXT NOT NULL,
+                status TEXT NOT NULL,
+                payload BLOB NOT NULL,
+                observed_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_price_observations_status
+                ON price_observations(status, observed_at DESC);
+
+            CREATE TABLE IF NOT EXISTS exchange_rates (
+                currency TEXT PRIMARY KEY,
+                payload BLOB NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS orders (
+                id TEXT PRIMARY KEY,
+                supplier_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                payload BLOB NOT NULL,
+                order_date TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_orders_date
+                ON orders(order_date DESC);
+
+            CREATE TABLE IF NOT EXISTS attachments (
+                id TEXT PRIMARY KEY,
+                owner_type TEXT NOT NULL,
+                owner_id TEXT NOT NULL,
+                payload BLOB NOT NULL,
+                data BLOB NOT NULL,
+                thumbnail BLOB,
+                size_bytes INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_attachments_owner
+                ON attachments(owner_type, owner_id, created_at DESC);
+
             CREATE INDEX IF NOT EXISTS idx_protocols_favorite
                 ON protocols(is_favorite DESC, updated_at DESC);
 
@@ -210,7 +679,8 @@ impl StorageManager {
                 id TEXT PRIMARY KEY,
                 title TEXT NOT NULL,
                 payload BLOB NOT NULL,
-                created_at TEXT NOT NULL
+                created_at TEXT NOT NULL,
+                source_id TEXT
             );
 
             CREATE INDEX IF NOT EXISTS idx_summary_history_created
@@ -221,7 +691,8 @@ impl StorageManager {
                 date TEXT NOT NULL,
                 payload BLOB NOT NULL,
                 created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
+                updated_at TEXT NOT NULL,
+                deleted_at INTEGER
             );
 
             CREATE INDEX IF NOT EXISTS idx_body_metrics_date
@@ -245,6 +716,173 @@ impl StorageManager {
 
             CREATE INDEX IF NOT EXISTS idx_side_effects_protocol
                 ON side_effects(protocol_id);
+
+            CREATE TABLE IF NOT EXISTS lab_results (
+                id TEXT PRIMARY KEY,
+                marker TEXT NOT NULL,
+                collected_at TEXT NOT NULL,
+                payload BLOB NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_lab_results_marker
+                ON lab_results(marker COLLATE NOCASE, collected_at);
+
+            -- Links to other records stay inside the encrypted payload
+            CREATE TABLE IF NOT EXISTS journal_entries (
+                id TEXT PRIMARY KEY,
+                entry_date TEXT NOT NULL,
+                payload BLOB NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_journal_entries_date
+                ON journal_entries(entry_date DESC);
+
+            CREATE TABLE IF NOT EXISTS goals (
+                id TEXT PRIMARY KEY,
+                start_date TEXT NOT NULL,
+                payload BLOB NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            -- Append-only: entries are never edited, only pruned by retention.
+            -- occurred_at is a unix timestamp so date filters compare numerically.
+            CREATE TABLE IF NOT EXISTS audit_log (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                id TEXT NOT NULL UNIQUE,
+                entity_type TEXT NOT NULL,
+                entity_id TEXT NOT NULL,
+                operation TEXT NOT NULL,
+                changed_fields TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                occurred_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_audit_log_entity
+                ON audit_log(entity_type, entity_id);
+
+            CREATE INDEX IF NOT EXISTS idx_audit_log_occurred
+                ON audit_log(occurred_at);
+
+            CREATE TRIGGER IF NOT EXISTS audit_log_append_only
+            BEFORE UPDATE ON audit_log
+            BEGIN
+                SELECT RAISE(ABORT, 'audit_log is append-only');
+            END;
+
+            -- Encrypted dashboard stats, dropped when their source tables change
+            CREATE TABLE IF NOT EXISTS stats_cache (
+                key TEXT PRIMARY KEY,
+                payload BLOB NOT NULL,
+                computed_at INTEGER NOT NULL
+            );
+
+            -- Maintenance runs as JSON, see crate::maintenance. Only database
+            -- stats are kept here, so it isn't encrypted; ran_at is a unix timestamp.
+            CREATE TABLE IF NOT EXISTS maintenance_runs (
+                id TEXT PRIMARY KEY,
+                ran_at INTEGER NOT NULL,
+                payload TEXT NOT NULL
+            );
+
+            -- How far sync has got with each device, see crate::sync; synced_at
+            -- is a unix timestamp
+            CREATE TABLE IF NOT EXISTS sync_cursors (
+                device_id TEXT PRIMARY KEY,
+                change_sets INTEGER NOT NULL,
+                audit_seq INTEGER NOT NULL,
+                synced_at INTEGER
+            );
+
+            -- Encrypted app preferences as JSON, one row per key
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                payload BLOB NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            -- Literature queries re-run in the background
+            CREATE TABLE IF NOT EXISTS saved_searches (
+                id TEXT PRIMARY KEY,
+                payload BLOB NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            -- ClinicalTrials.gov studies; protocol links stay inside the payload
+            CREATE TABLE IF NOT EXISTS clinical_trials (
+                nct_id TEXT PRIMARY KEY,
+                payload BLOB NOT NULL,
+                fetched_at TEXT NOT NULL
+            );
+
+            -- Encrypted literature API responses, keyed by a hash of the
+            -- source, query and page
+            CREATE TABLE IF NOT EXISTS literature_responses (
+                key_hash TEXT PRIMARY KEY,
+                source TEXT NOT NULL,
+                payload BLOB NOT NULL,
+                fetched_at INTEGER NOT NULL,
+                hits INTEGER NOT NULL DEFAULT 0
+            );
+
+            -- Encrypted abstract embeddings used to answer literature questions
+            CREATE TABLE IF NOT EXISTS literature_embeddings (
+                entry_id TEXT NOT NULL,
+                model TEXT NOT NULL,
+                payload BLOB NOT NULL,
+                PRIMARY KEY (entry_id, model)
+            );
+
+            -- Single row sealed with the database key, checked on unlock
+            CREATE TABLE IF NOT EXISTS key_check (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                payload BLOB NOT NULL
+            );
+
+            -- Tamper evidence, see crate::tamper: one HMAC-chained entry per
+            -- write to a record table while it's on
+            CREATE TABLE IF NOT EXISTS tamper_chain (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                table_name TEXT NOT NULL,
+                record_id TEXT NOT NULL,
+                digest TEXT,
+                mac TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_tamper_chain_table ON tamper_chain(table_name, seq);
+
+            -- HMAC of each table's last chain entry, so removing the newest
+            -- entries shows
+            CREATE TABLE IF NOT EXISTS tamper_head (
+                table_name TEXT PRIMARY KEY,
+                seq INTEGER NOT NULL,
+                mac TEXT NOT NULL
+            );
+
+            -- Encrypted HMAC key for the tamper chain; no row while it's off
+            CREATE TABLE IF NOT EXISTS tamper_key (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                payload BLOB NOT NULL
+            );
+
+            -- Database key sealed with a recovery code, read while locked
+            CREATE TABLE IF NOT EXISTS key_recovery (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                salt BLOB NOT NULL,
+                kdf TEXT NOT NULL,
+                sealed_key BLOB NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+                entity_type UNINDEXED,
+                entity_id UNINDEXED,
+                tokens,
+                tokenize = 'ascii'
+            );
             "#,
         )
         .context("Failed to initialize database schema")?;
@@ -252,77 +890,1031 @@ impl StorageManager {
         // Run migrations for existing databases
         self.run_migrations(&conn)?;
 
-        info!("Database initialized at {}", self.db_path.display());
-        Ok(())
-    }
-
-    /// Run database migrations for schema updates
-    fn run_migrations(&self, conn: &Connection) -> Result<()> {
-        // Migration: Add is_favorite column to protocols table if it doesn't exist
-        let has_favorite_column: bool = conn
-            .query_row(
-                "SELECT COUNT(*) FROM pragma_table_info('protocols') WHERE name='is_favorite'",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(0) > 0;
-
-        if !has_favorite_column {
-            info!("Running migration: Adding is_favorite column to protocols table");
+        let has_key_check: bool = conn.query_row("SELECT COUNT(*) FROM key_check", [], |row| row.get(0))?;
+        if !has_key_check {
             conn.execute(
-                "ALTER TABLE protocols ADD COLUMN is_favorite INTEGER NOT NULL DEFAULT 0",
-                [],
+                "INSERT INTO key_check (id, payload) VALUES (1, ?1)",
+                params![self.encryption.seal(KEY_CHECK_PLAINTEXT)?],
             )
-            .context("Failed to add is_favorite column")?;
-            info!("Migration completed: is_favorite column added");
+            .context("Failed to write key check")?;
+        }
+
+        // Databases created before global search have no index yet
+        let indexed: i64 = conn.query_row("SELECT COUNT(*) FROM search_index", [], |row| row.get(0))?;
+        drop(conn);
+        if indexed == 0 {
+            self.rebuild_search_index()?;
         }
 
+        info!("Database initialized at {}", self.db_path.display());
         Ok(())
     }
 
-    pub fn upsert_protocol(&self, protocol: &PeptideProtocol) -> Result<()> {
+    /// Whether an existing database is missing a migrated column
+    fn migrations_pending(&self) -> Result<bool> {
         let conn = self.open_connection()?;
-        let payload = serde_json::to_vec(protocol).context("Failed to serialize protocol")?;
-        let encrypted = self.encryption.seal(&payload)?;
-
-        conn.execute(
-            r#"
-            INSERT INTO protocols (id, name, payload, updated_at, is_favorite)
-            VALUES (?1, ?2, ?3, ?4, ?5)
-            ON CONFLICT(id) DO UPDATE SET
-                name = excluded.name,
-                payload = excluded.payload,
-                updated_at = excluded.updated_at,
-                is_favorite = excluded.is_favorite;
-            "#,
-            params![
-                protocol.id,
-                protocol.name,
-                encrypted,
-                protocol.updated_at.to_string(),
-                protocol.is_favorite as i32
-            ],
-        )
-        .context("Failed to upsert protocol")?;
+        Ok(MIGRATED_COLUMNS
+            .iter()
+            .any(|(table, column)| has_table(&conn, table) && !has_column(&conn, table, column)))
+    }
 
-        Ok(())
+    /// Copy the database into the snapshot directory before migrating it
+    fn snapshot_before_migration(&self) -> Result<MigrationSnapshot> {
+        let from_version: i32 = self
+            .open_connection()?
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let created_at = OffsetDateTime::now_utc();
+        let id = format!("v{}-{}", from_version, created_at.unix_timestamp_nanos());
+
+        let dir = migration::snapshot_dir(&self.db_path);
+        std::fs::create_dir_all(&dir).context("Failed to create snapshot directory")?;
+        let path = migration::snapshot_file(&dir, &id);
+        self.write_connection()?
+            .backup(DatabaseName::Main, &path, None)
+            .context("Failed to snapshot database")?;
+
+        let snapshot = MigrationSnapshot {
+            id,
+            created_at,
+            from_version,
+            to_version: SCHEMA_VERSION,
+            size_bytes: std::fs::metadata(&path)?.len(),
+            checksum: migration::checksum(&path)?,
+        };
+        migration::save_metadata(&dir, &snapshot)?;
+        if let Err(e) = migration::prune(&dir) {
+            tracing::warn!("Failed to prune migration snapshots: {:#}", e);
+        }
+        info!("Snapshotted database to {} before migrating", path.display());
+        Ok(snapshot)
     }
 
-    pub fn list_protocols(&self) -> Result<Vec<PeptideProtocol>> {
+    /// Put the database back after `error` stopped a migration
+    fn roll_back_migration(&self, snapshot: &MigrationSnapshot, error: anyhow::Error) -> MigrationFailed {
+        tracing::error!("Migration failed, restoring snapshot {}: {:#}", snapshot.id, error);
+        let restored = match self.restore_migration_snapshot(&snapshot.id) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to restore snapshot {}: {:#}", snapshot.id, e);
+                false
+            }
+        };
+        MigrationFailed {
+            snapshot_id: snapshot.id.clone(),
+            restored,
+            reason: format!("{:#}", error),
+        }
+    }
+
+    /// Snapshots taken before migrations, newest first
+    pub fn list_migration_snapshots(&self) -> Result<Vec<MigrationSnapshot>> {
+        migration::list(&migration::snapshot_dir(&self.db_path))
+    }
+
+    /// Replace the database with the snapshot `id`
+    ///
+    /// The copy's checksum is verified first. Everything written since the
+    /// snapshot was taken is lost, and the schema goes back to the snapshot's
+    /// version, so the app should be restarted afterwards.
+    pub fn restore_migration_snapshot(&self, id: &str) -> Result<()> {
+        let (snapshot, path) = migration::verified(&migration::snapshot_dir(&self.db_path), id)?;
+        let mut conn = self.write_connection()?;
+        conn.restore(DatabaseName::Main, &path, None::<fn(rusqlite::backup::Progress)>)
+            .with_context(|| format!("Failed to restore snapshot {}", snapshot.id))?;
+        drop(conn);
+        self.stats_generation.fetch_add(1, Ordering::SeqCst);
+        info!("Restored database from snapshot {}", snapshot.id);
+        Ok(())
+    }
+
+    /// Delete every migration snapshot
+    pub fn delete_migration_snapshots(&self) -> Result<usize> {
+        let dir = migration::snapshot_dir(&self.db_path);
+        let snapshots = migration::list(&dir)?;
+        for snapshot in &snapshots {
+            migration::remove(&dir, &snapshot.id)?;
+        }
+        Ok(snapshots.len())
+    }
+
+    /// Copy every row that can still be read into a new database
+    ///
+    /// Meant for a database that fails its integrity check. The damaged
+    /// database is only read; the copy is made in `recovery/` with the
+    /// current schema and the same key, so the database must be unlocked.
+    /// Call [`StorageManager::apply_salvage`] to switch over to the copy.
+    pub fn salvage(&self, mut progress: impl FnMut(RecoveryProgress)) -> Result<SalvageReport> {
+        let created_at = OffsetDateTime::now_utc();
+        let id = format!("salvage-{}", created_at.unix_timestamp_nanos());
+        let dir = recovery::recovery_dir(&self.db_path);
+        std::fs::create_dir_all(&dir).context("Failed to create recovery directory")?;
+
+        let salvaged = self.sibling(recovery::salvage_file(&dir, &id));
+        salvaged
+            .initialize_schema()
+            .context("Failed to create salvage database")?;
+
+        let source = self.open_connection()?;
+        let mut target = salvaged.write_connection()?;
+        // Rows are copied table by table, so references may point ahead
+        target.execute_batch("PRAGMA foreign_keys=OFF;")?;
+        let table_names: Vec<String> = {
+            let mut stmt = target.prepare(
+                "SELECT name FROM pragma_table_list
+                 WHERE schema = 'main' AND type = 'table'
+                   AND name NOT LIKE 'sqlite_%' AND name NOT LIKE 'search_index%'
+                 ORDER BY name",
+            )?;
+            let names = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            names
+        };
+
+        let mut tables = Vec::with_capacity(table_names.len());
+        for (done, table) in table_names.iter().enumerate() {
+            progress(RecoveryProgress {
+                table: table.clone(),
+                tables_done: done,
+                tables_total: table_names.len(),
+            });
+            let source_columns = recovery::table_columns(&source, table).unwrap_or_default();
+            let columns: Vec<String> = recovery::table_columns(&target, table)?
+                .into_iter()
+                .filter(|column| source_columns.contains(column))
+                .collect();
+            if columns.is_empty() {
+                tracing::warn!("Table {} can't be read from the damaged database", table);
+                tables.push(recovery::TableRecovery {
+                    table: table.clone(),
+                    recovered: 0,
+                    lost: None,
+                });
+                continue;
+            }
+
+            let tx = target.transaction()?;
+            let recovered = recovery::copy_table(&source, &tx, table, &columns)?;
+            tx.commit()?;
+            tables.push(recovered);
+        }
+        progress(RecoveryProgress {
+            table: String::new(),
+            tables_done: table_names.len(),
+            tables_total: table_names.len(),
+        });
+        drop(source);
+        drop(target);
+
+        if let Err(e) = salvaged.rebuild_search_index() {
+            tracing::warn!("Failed to rebuild search index of salvaged database: {:#}", e);
+        }
+        let verified = salvaged.health_check()?.is_healthy;
+        salvaged.compact()?;
+
+        let report = SalvageReport::new(id, created_at, tables, verified);
+        recovery::save_report(&dir, &report)?;
+        info!(
+            "Salvaged {} rows ({} lost) into {}",
+            report.recovered_rows,
+            report.lost_rows,
+            recovery::salvage_file(&dir, &report.id).display()
+        );
+        Ok(report)
+    }
+
+    /// Replace the database with the copy made by [`StorageManager::salvage`]
+    ///
+    /// The damaged database is kept in `recovery/`; its path is returned.
+    pub fn apply_salvage(&self, id: &str) -> Result<PathBuf> {
+        let (report, path) = recovery::load_salvage(&recovery::recovery_dir(&self.db_path), id)?;
+        if !report.verified {
+            anyhow::bail!("Salvaged database {} failed its integrity check", id);
+        }
+        let damaged = self.replace_database(&path)?;
+        info!("Replaced damaged database with salvage {}", id);
+        Ok(damaged)
+    }
+
+    /// Replace the database with an empty one, e.g. to restore a backup into
+    ///
+    /// The damaged database is kept in `recovery/`; its path is returned.
+    pub fn reset_damaged_database(&self) -> Result<PathBuf> {
+        let dir = recovery::recovery_dir(&self.db_path);
+        std::fs::create_dir_all(&dir).context("Failed to create recovery directory")?;
+        let empty_path = dir.join(format!("empty-{}.sqlite", OffsetDateTime::now_utc().unix_timestamp_nanos()));
+
+        let empty = self.sibling(empty_path.clone());
+        empty.initialize_schema().context("Failed to create empty database")?;
+        empty.compact()?;
+        drop(empty);
+
+        let damaged = self.replace_database(&empty_path);
+        if let Err(e) = std::fs::remove_file(&empty_path) {
+            tracing::warn!("Failed to remove {}: {}", empty_path.display(), e);
+        }
+        info!("Replaced damaged database with an empty one");
+        damaged
+    }
+
+    /// Delete salvaged and damaged copies of the database
+    pub fn delete_recovery_files(&self) -> Result<()> {
+        let dir = recovery::recovery_dir(&self.db_path);
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).context("Failed to remove recovery directory")?;
+        }
+        Ok(())
+    }
+
+    /// Storage for another database file using the same key
+    fn sibling(&self, db_path: PathBuf) -> StorageManager {
+        StorageManager {
+            db_path,
+            encryption: self.encryption.clone(),
+            connections: ConnectionPool::default(),
+            writer: Writer::default(),
+            stats_generation: AtomicU64::new(0),
+            listeners: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Overwrite the database with `source`, keeping a copy of the old file
+    fn replace_database(&self, source: &Path) -> Result<PathBuf> {
+        let dir = recovery::recovery_dir(&self.db_path);
+        let damaged = recovery::damaged_file(&dir, &OffsetDateTime::now_utc().unix_timestamp_nanos().to_string());
+        std::fs::copy(&self.db_path, &damaged).context("Failed to keep a copy of the damaged database")?;
+        let wal = wal_path(&self.db_path);
+        if wal.exists() {
+            std::fs::copy(&wal, wal_path(&damaged)).context("Failed to keep a copy of the damaged WAL")?;
+        }
+
+        let mut conn = self.write_connection()?;
+        conn.restore(DatabaseName::Main, source, None::<fn(rusqlite::backup::Progress)>)
+            .context("Failed to replace database")?;
+        drop(conn);
+        self.stats_generation.fetch_add(1, Ordering::SeqCst);
+        Ok(damaged)
+    }
+
+    /// Run database migrations for schema updates
+    fn run_migrations(&self, conn: &Connection) -> Result<()> {
+        // Migration: Add is_favorite column to protocols table if it doesn't exist
+        if !has_column(conn, "protocols", "is_favorite") {
+            info!("Running migration: Adding is_favorite column to protocols table");
+            conn.execute(
+                "ALTER TABLE protocols ADD COLUMN is_favorite INTEGER NOT NULL DEFAULT 0",
+                [],
+            )
+            .context("Failed to add is_favorite column")?;
+            info!("Migration completed: is_favorite column added");
+        }
+
+        // Migration: Add thumbnail column to attachments table if it doesn't exist
+        if !has_column(conn, "attachments", "thumbnail") {
+            info!("Running migration: Adding thumbnail column to attachments table");
+            conn.execute("ALTER TABLE attachments ADD COLUMN thumbnail BLOB", [])
+                .context("Failed to add thumbnail column")?;
+            info!("Migration completed: thumbnail column added");
+        }
+
+        // Migration: Add deleted_at columns for the trash (unix timestamp, NULL = not deleted)
+        for table in ["protocols", "dose_logs", "body_metrics"] {
+            if !has_column(conn, table, "deleted_at") {
+                info!("Running migration: Adding deleted_at column to {} table", table);
+                conn.execute(&format!("ALTER TABLE {} ADD COLUMN deleted_at INTEGER", table), [])
+                    .context("Failed to add deleted_at column")?;
+                info!("Migration completed: deleted_at column added to {}", table);
+            }
+        }
+
+        // Migration: Add index columns to dose_logs and fill them from the payloads
+        if !has_column(conn, "dose_logs", "amount_mg") {
+            info!("Running migration: Adding index columns to dose_logs table");
+            conn.execute_batch(
+                r#"
+                ALTER TABLE dose_logs ADD COLUMN amount_mg REAL;
+                ALTER TABLE dose_logs ADD COLUMN site_code TEXT;
+                ALTER TABLE dose_logs ADD COLUMN schedule_id TEXT;
+                "#,
+            )
+            .context("Failed to add dose_logs index columns")?;
+            let filled = self.backfill_dose_index_columns(conn)?;
+            info!("Migration completed: dose_logs index columns added for {} logs", filled);
+        }
+
+        // Migration: Flag dose logs edited after they were logged
+        if !has_column(conn, "dose_logs", "edited_at") {
+            info!("Running migration: Adding edited_at column to dose_logs table");
+            conn.execute("ALTER TABLE dose_logs ADD COLUMN edited_at INTEGER", [])
+                .context("Failed to add edited_at column")?;
+            info!("Migration completed: edited_at column added to dose_logs");
+        }
+
+        // Migration: Track metadata enrichment and derive DOI/year for cached literature
+        if !has_column(conn, "literature_cache", "enriched_at") {
+            info!("Running migration: Adding enriched_at column to literature_cache table");
+            conn.execute("ALTER TABLE literature_cache ADD COLUMN enriched_at INTEGER", [])
+                .context("Failed to add enriched_at column")?;
+            let updated = self.backfill_literature_metadata(conn)?;
+            info!("Migration completed: derived metadata for {} literature entries", updated);
+        }
+
+        // Migration: Group summaries by what they summarized
+        if !has_column(conn, "summary_history", "source_id") {
+            info!("Running migration: Adding source_id column to summary_history table");
+            conn.execute("ALTER TABLE summary_history ADD COLUMN source_id TEXT", [])
+                .context("Failed to add source_id column")?;
+            let filled = self.backfill_summary_sources(conn)?;
+            info!("Migration completed: source_id set for {} summaries", filled);
+        }
+
+        // Migration: Add price columns to price_history and fill them from the payloads
+        if !has_column(conn, "price_history", "cost_per_mg") {
+            info!("Running migration: Adding price columns to price_history table");
+            conn.execute_batch(
+                r#"
+                ALTER TABLE price_history ADD COLUMN cost_per_mg REAL;
+                ALTER TABLE price_history ADD COLUMN currency TEXT;
+                "#,
+            )
+            .context("Failed to add price_history price columns")?;
+            let filled = self.backfill_price_columns(conn)?;
+            info!("Migration completed: price columns added for {} prices", filled);
+        }
+
+        conn.execute_batch(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_dose_logs_schedule
+                ON dose_logs(schedule_id, logged_at DESC);
+            CREATE INDEX IF NOT EXISTS idx_summary_history_source
+                ON summary_history(source_id, created_at DESC);
+            "#,
+        )
+        .context("Failed to create migration indexes")?;
+
+        Ok(())
+    }
+
+    /// Copy amount, site code and schedule out of every dose log payload
+    ///
+    /// Rows that can't be decrypted are left empty and counted as nothing by
+    /// the aggregates until the log is saved again.
+    fn backfill_dose_index_columns(&self, conn: &Connection) -> Result<usize> {
+        let rows = conn
+            .prepare("SELECT id, payload FROM dose_logs")?
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut filled = 0;
+        for (id, blob) in rows {
+            match self.decode_dose_log(&blob) {
+                Ok(log) => {
+                    conn.execute(
+                        "UPDATE dose_logs SET amount_mg = ?1, site_code = ?2, schedule_id = ?3 WHERE id = ?4",
+                        params![log.amount_mg, site_code(&log.site), log.schedule_id, id],
+                    )?;
+                    filled += 1;
+                }
+                Err(e) => tracing::warn!("Skipping index columns for dose log {}: {:#}", id, e),
+            }
+        }
+        Ok(filled)
+    }
+
+    /// Copy the price and currency out of every price history payload
+    ///
+    /// Rows that can't be decrypted are left empty and left out of price
+    /// trends.
+    fn backfill_price_columns(&self, conn: &Connection) -> Result<usize> {
+        let rows = conn
+            .prepare("SELECT id, payload FROM price_history")?
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut filled = 0;
+        for (id, blob) in rows {
+            match self.decode_price_history(&blob) {
+                Ok(entry) => {
+                    conn.execute(
+                        "UPDATE price_history SET cost_per_mg = ?1, currency = ?2 WHERE id = ?3",
+                        params![entry.cost_per_mg, entry.currency, id],
+                    )?;
+                    filled += 1;
+                }
+                Err(e) => tracing::warn!("Skipping price columns for price {}: {:#}", id, e),
+            }
+        }
+        Ok(filled)
+    }
+
+    /// Derive the DOI and year of cached literature from stored URLs and dates
+    fn backfill_literature_metadata(&self, conn: &Connection) -> Result<usize> {
+        let rows = conn
+            .prepare("SELECT id, payload FROM literature_cache")?
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut updated = 0;
+        for (id, blob) in rows {
+            match self.decode_literature(&blob) {
+                Ok(mut entry) => {
+                    if !entry.derive_metadata() {
+                        continue;
+                    }
+                    let payload = serde_json::to_vec(&entry).context("Failed to serialize literature entry")?;
+                    conn.execute(
+                        "UPDATE literature_cache SET payload = ?1 WHERE id = ?2",
+                        params![self.encryption.seal(&payload)?, id],
+                    )?;
+                    updated += 1;
+                }
+                Err(e) => tracing::warn!("Skipping metadata for literature entry {}: {:#}", id, e),
+            }
+        }
+        Ok(updated)
+    }
+
+    /// Set the source id column of every summary from its payload
+    fn backfill_summary_sources(&self, conn: &Connection) -> Result<usize> {
+        let rows = conn
+            .prepare("SELECT id, payload FROM summary_history")?
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut filled = 0;
+        for (id, blob) in rows {
+            match self.decode_summary_history(&blob) {
+                Ok(summary) => {
+                    conn.execute(
+                        "UPDATE summary_history SET source_id = ?1 WHERE id = ?2",
+                        params![summary.source_id, id],
+                    )?;
+                    filled += 1;
+                }
+                Err(e) => tracing::warn!("Skipping source for summary {}: {:#}", id, e),
+            }
+        }
+        Ok(filled)
+    }
+
+    /// Whether the current key opens this database
+    ///
+    /// Databases that haven't been initialized since key checks were added
+    /// can't be verified and report `true`.
+    pub fn verify_key(&self) -> Result<bool> {
         let conn = self.open_connection()?;
-        let mut stmt = conn.prepare("SELECT payload FROM protocols ORDER BY is_favorite DESC, updated_at DESC")?;
-        let mut rows = stmt.query([]).context("Unable to run list query")?;
-        let mut protocols = Vec::new();
-        while let Some(row) = rows.next()? {
-            let blob: Vec<u8> = row.get(0)?;
-            protocols.push(self.decode_protocol(&blob)?);
+        let has_table: bool = conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'key_check'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_table {
+            return Ok(true);
+        }
+
+        let payload: Option<Vec<u8>> = conn
+            .query_row("SELECT payload FROM key_check WHERE id = 1", [], |row| row.get(0))
+            .optional()?;
+        Ok(match payload {
+            Some(payload) => self
+                .encryption
+                .open(&payload)
+                .is_ok_and(|plaintext| plaintext == KEY_CHECK_PLAINTEXT),
+            None => true,
+        })
+    }
+
+    /// Whether tamper evidence is on
+    ///
+    /// Stays on while the marker file exists, even if its key was removed
+    /// from the database.
+    pub fn tamper_evidence_enabled(&self) -> Result<bool> {
+        let conn = self.open_connection()?;
+        Ok(has_tamper_key(&conn)? || tamper::marker_path(&self.db_path).exists())
+    }
+
+    /// Turn on tamper evidence, chaining the records already stored
+    ///
+    /// Does nothing if it's already on.
+    pub fn enable_tamper_evidence(&self) -> Result<()> {
+        let mut conn = self.write_connection()?;
+        if has_tamper_key(&conn)? {
+            return self.write_tamper_marker();
+        }
+
+        let mut key = Zeroizing::new(vec![0u8; 32]);
+        OsRng.fill_bytes(&mut key);
+        let columns = tamper_columns();
+
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO tamper_key (id, payload) VALUES (1, ?1)",
+            params![self.encryption.seal(&key)?],
+        )?;
+        tx.execute("DELETE FROM tamper_chain", [])?;
+        tx.execute("DELETE FROM tamper_head", [])?;
+        for (table, encrypted) in &columns {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT id, {} FROM {} ORDER BY id",
+                encrypted.join(", "),
+                table
+            ))?;
+            let mut rows = stmt.query([])?;
+            let mut previous: Option<String> = None;
+            while let Some(row) = rows.next()? {
+                let id: String = row.get(0)?;
+                let values = (1..=encrypted.len())
+                    .map(|i| row.get_ref(i))
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                let digest = tamper::row_digest(values);
+                let mac = tamper::chain_mac(&key, table, &id, Some(&digest), previous.as_deref());
+                tx.execute(
+                    "INSERT INTO tamper_chain (table_name, record_id, digest, mac) VALUES (?1, ?2, ?3, ?4)",
+                    params![table, id, digest, mac],
+                )?;
+                previous = Some(mac);
+            }
+
+            // Every table gets a head, so emptying one's chain shows too
+            let seq = if previous.is_some() { tx.last_insert_rowid() } else { 0 };
+            tx.execute(
+                "INSERT INTO tamper_head (table_name, seq, mac) VALUES (?1, ?2, ?3)",
+                params![table, seq, tamper::head_mac(&key, table, seq, previous.as_deref())],
+            )?;
+        }
+
+        // Temporary triggers are rolled back with the rest if the commit fails
+        tamper::install(&tx, key, &columns).context("Unable to add tamper evidence triggers")?;
+        tx.commit().context("Failed to turn on tamper evidence")?;
+        self.write_tamper_marker()?;
+        info!("Tamper evidence turned on");
+        Ok(())
+    }
+
+    /// Turn off tamper evidence and delete the chain
+    pub fn disable_tamper_evidence(&self) -> Result<()> {
+        let mut conn = self.write_connection()?;
+        let tx = conn.transaction()?;
+        tamper::uninstall(&tx)?;
+        tx.execute("DELETE FROM tamper_chain", [])?;
+        tx.execute("DELETE FROM tamper_head", [])?;
+        tx.execute("DELETE FROM tamper_key", [])?;
+        tx.commit().context("Failed to turn off tamper evidence")?;
+        let marker = tamper::marker_path(&self.db_path);
+        if marker.exists() {
+            std::fs::remove_file(&marker)
+                .with_context(|| format!("Failed to remove {}", marker.display()))?;
+        }
+        info!("Tamper evidence turned off");
+        Ok(())
+    }
+
+    /// Replay the tamper-evidence chain and compare it with the records
+    /// stored now, reporting any changed outside the app
+    ///
+    /// Fails if tamper evidence is off.
+    pub fn verify_tamper_chain(&self) -> Result<TamperReport> {
+        let conn = self.open_connection()?;
+        // One snapshot, so writes made meanwhile can't look like tampering
+        let tx = conn.unchecked_transaction()?;
+        let key = self.tamper_key(&tx)?;
+
+        let mut report = TamperReport {
+            verified_at: OffsetDateTime::now_utc(),
+            entries_checked: 0,
+            records_checked: 0,
+            broken_links: Vec::new(),
+            truncated_tables: Vec::new(),
+            findings: Vec::new(),
+            key_removed: false,
+        };
+        let Some(key) = key else {
+            if !tamper::marker_path(&self.db_path).exists() {
+                return Err(anyhow::anyhow!("Tamper evidence is off"));
+            }
+            tracing::warn!("Tamper evidence key is missing from the database");
+            report.key_removed = true;
+            return Ok(report);
+        };
+        for (table, encrypted) in tamper_columns() {
+            // The last digest the app wrote for each record; `None` once deleted
+            let mut latest: HashMap<String, Option<String>> = HashMap::new();
+            let mut previous: Option<String> = None;
+            let mut last_seq = 0;
+            let mut stmt = tx.prepare(
+                "SELECT seq, record_id, digest, mac FROM tamper_chain WHERE table_name = ?1 ORDER BY seq",
+            )?;
+            let entries = stmt.query_map(params![table], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?;
+            for entry in entries {
+                let (seq, record_id, digest, mac) = entry?;
+                let expected = tamper::chain_mac(&key, table, &record_id, digest.as_deref(), previous.as_deref());
+                if expected != mac {
+                    report.broken_links.push(BrokenLink {
+                        table: table.to_string(),
+                        seq,
+                    });
+                }
+                report.entries_checked += 1;
+                latest.insert(record_id, digest);
+                previous = Some(mac);
+                last_seq = seq;
+            }
+
+            let head: Option<String> = tx
+                .query_row(
+                    "SELECT mac FROM tamper_head WHERE table_name = ?1",
+                    params![table],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if head != Some(tamper::head_mac(&key, table, last_seq, previous.as_deref())) {
+                report.truncated_tables.push(table.to_string());
+            }
+
+            let mut stmt = tx.prepare(&format!(
+                "SELECT id, {} FROM {} ORDER BY id",
+                encrypted.join(", "),
+                table
+            ))?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let id: String = row.get(0)?;
+                let values = (1..=encrypted.len())
+                    .map(|i| row.get_ref(i))
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                let digest = tamper::row_digest(values);
+                report.records_checked += 1;
+
+                let change = match latest.remove(&id) {
+                    Some(Some(last)) if last == digest => continue,
+                    Some(Some(_)) => TamperChange::Modified,
+                    Some(None) | None => TamperChange::Added,
+                };
+                report.findings.push(TamperFinding {
+                    table: table.to_string(),
+                    record_id: id,
+                    change,
+                });
+            }
+
+            let mut deleted: Vec<String> = latest
+                .into_iter()
+                .filter_map(|(id, digest)| digest.map(|_| id))
+                .collect();
+            deleted.sort();
+            report.findings.extend(deleted.into_iter().map(|record_id| TamperFinding {
+                table: table.to_string(),
+                record_id,
+                change: TamperChange::Deleted,
+            }));
+        }
+
+        if !report.is_intact() {
+            tracing::warn!(
+                "Tamper evidence check found {} broken links, {} truncated chains and {} changed records",
+                report.broken_links.len(),
+                report.truncated_tables.len(),
+                report.findings.len()
+            );
+        }
+        Ok(report)
+    }
+
+    /// Record next to the database that tamper evidence is on
+    fn write_tamper_marker(&self) -> Result<()> {
+        let marker = tamper::marker_path(&self.db_path);
+        if marker.exists() {
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(&tamper::TamperMarker {
+            enabled_at: OffsetDateTime::now_utc(),
+        })?;
+        std::fs::write(&marker, json).with_context(|| format!("Failed to write {}", marker.display()))
+    }
+
+    /// Add the tamper-evidence triggers to a new write connection when it's on
+    fn install_tamper_chain(&self, conn: &Connection) -> Result<()> {
+        if let Some(key) = self.tamper_key(conn)? {
+            tamper::install(conn, key, &tamper_columns()).context("Unable to add tamper evidence triggers")?;
+        }
+        Ok(())
+    }
+
+    /// The tamper-evidence HMAC key, or `None` while it's off
+    fn tamper_key(&self, conn: &Connection) -> Result<Option<Zeroizing<Vec<u8>>>> {
+        if !has_tamper_key(conn)? {
+            return Ok(None);
+        }
+        let sealed: Vec<u8> = conn.query_row("SELECT payload FROM tamper_key WHERE id = 1", [], |row| row.get(0))?;
+        let key = self
+            .encryption
+            .open(&sealed)
+            .context("Failed to decrypt tamper evidence key")?;
+        Ok(Some(Zeroizing::new(key)))
+    }
+
+    /// The database key sealed with a recovery code, if one has been made
+    ///
+    /// Readable while locked, so a lost key can be recovered.
+    pub fn key_recovery(&self) -> Result<Option<KeyRecovery>> {
+        let conn = self.open_connection()?;
+        let has_table: bool = conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'key_recovery'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_table {
+            return Ok(None);
+        }
+
+        let row = conn
+            .query_row(
+                "SELECT salt, kdf, sealed_key, created_at FROM key_recovery WHERE id = 1",
+                [],
+                |row| {
+                    Ok((
+                        row.get::<_, Vec<u8>>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Vec<u8>>(2)?,
+                        row.get::<_, i64>(3)?,
+                    ))
+                },
+            )
+            .optional()?;
+        row.map(|(salt, kdf, sealed_key, created_at)| {
+            Ok(KeyRecovery {
+                salt,
+                kdf: serde_json::from_str(&kdf).context("Invalid recovery key derivation settings")?,
+                sealed_key,
+                created_at: OffsetDateTime::from_unix_timestamp(created_at)?,
+            })
+        })
+        .transpose()
+    }
+
+    /// Save the sealed key for a new recovery code, replacing the old one
+    pub fn save_key_recovery(&self, recovery: &KeyRecovery) -> Result<()> {
+        let conn = self.write_connection()?;
+        write_key_recovery(&conn, recovery)
+    }
+
+    /// Re-encrypt every stored value with `new_encryption` in one transaction
+    ///
+    /// Values are decrypted with this manager's current key, so its key
+    /// provider must switch to the new key once this returns. The search
+    /// index is keyed too and needs rebuilding afterwards; see
+    /// [`rotate_storage_key`](crate::rotate_storage_key), which does both.
+    /// The recovery code no longer opens the database afterwards, so it is
+    /// replaced with `recovery`, sealed with the new key, or removed.
+    /// `progress` is called after every value. Returns the number of values
+    /// re-encrypted.
+    pub fn rotate_key(
+        &self,
+        new_encryption: &EnvelopeEncryption,
+        recovery: Option<&KeyRecovery>,
+        mut progress: impl FnMut(KeyRotationProgress),
+    ) -> Result<usize> {
+        let mut conn = self.write_connection()?;
+        let tx = conn.transaction()?;
+
+        let mut total = 0;
+        for (table, column) in ENCRYPTED_COLUMNS {
+            let count: i64 = tx.query_row(
+                &format!("SELECT COUNT(*) FROM {} WHERE {} IS NOT NULL", table, column),
+                [],
+                |row| row.get(0),
+            )?;
+            total += count as usize;
+        }
+
+        let mut processed = 0;
+        for (table, column) in ENCRYPTED_COLUMNS {
+            let rowids = tx
+                .prepare(&format!("SELECT rowid FROM {} WHERE {} IS NOT NULL", table, column))?
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<i64>>>()?;
+            let select = format!("SELECT {} FROM {} WHERE rowid = ?1", column, table);
+            let update = format!("UPDATE {} SET {} = ?1 WHERE rowid = ?2", table, column);
+
+            for rowid in rowids {
+                let sealed: Vec<u8> = tx.query_row(&select, params![rowid], |row| row.get(0))?;
+                let plaintext = self
+                    .encryption
+                    .open(&sealed)
+                    .with_context(|| format!("Failed to decrypt {}.{} row {}", table, column, rowid))?;
+                tx.execute(&update, params![new_encryption.seal(&plaintext)?, rowid])?;
+
+                processed += 1;
+                progress(KeyRotationProgress {
+                    table,
+                    processed,
+                    total,
+                });
+            }
+        }
+
+        tx.execute(
+            "INSERT OR REPLACE INTO key_check (id, payload) VALUES (1, ?1)",
+            params![new_encryption.seal(KEY_CHECK_PLAINTEXT)?],
+        )?;
+        tx.execute("DELETE FROM key_recovery", [])?;
+        if let Some(recovery) = recovery {
+            write_key_recovery(&tx, recovery)?;
+        }
+        tx.commit().context("Failed to commit key rotation")?;
+
+        info!("Re-encrypted {} values", processed);
+        Ok(processed)
+    }
+
+    /// Write every record, decrypted, to a new SQLite file at `path`
+    ///
+    /// Each table gets one column per field: the fields of its encrypted
+    /// payload, then the plain columns not already among them, such as
+    /// `deleted_at` for records in the trash. The file is not encrypted.
+    /// Fails if `path` already exists rather than overwrite it.
+    pub fn export_analytics_dataset(&self, path: &Path) -> Result<AnalyticsExport> {
+        if path.exists() {
+            anyhow::bail!("{} already exists", path.display());
+        }
+        let conn = self.open_connection()?;
+        let mut out = Connection::open(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        let tx = out.transaction()?;
+
+        let mut tables = Vec::new();
+        for &table in analytics_export::ANALYTICS_TABLES {
+            let plain: Vec<String> = conn
+                .prepare("SELECT name FROM pragma_table_info(?1)")?
+                .query_map(params![table], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?
+                .into_iter()
+                .filter(|column| !ENCRYPTED_COLUMNS.contains(&(table, column.as_str())))
+                .collect();
+            let has_payload = ENCRYPTED_COLUMNS.contains(&(table, "payload"));
+
+            let mut select: Vec<String> = plain.iter().map(|c| analytics_export::quote_identifier(c)).collect();
+            if has_payload {
+                select.push("payload".to_string());
+            }
+            let mut stmt = conn.prepare(&format!("SELECT {} FROM {}", select.join(", "), table))?;
+            let mut rows = stmt.query([])?;
+            let mut records = Vec::new();
+            while let Some(row) = rows.next()? {
+                let mut record: Vec<(String, rusqlite::types::Value)> = Vec::new();
+                if has_payload {
+                    let sealed: Vec<u8> = row.get(plain.len())?;
+                    let payload = self
+                        .encryption
+                        .open(&sealed)
+                        .with_context(|| format!("Failed to decrypt a row of {}", table))?;
+                    let fields = analytics_export::payload_columns(&payload)
+                        .with_context(|| format!("Unreadable row in {}", table))?;
+                    record.extend(fields.iter().map(|(name, value)| (name.clone(), analytics_export::sql_value(value))));
+                }
+                for (i, column) in plain.iter().enumerate() {
+                    if !record.iter().any(|(name, _)| name == column) {
+                        record.push((column.clone(), row.get(i)?));
+                    }
+                }
+                records.push(record);
+            }
+
+            // Every field seen, id first, so records with fields added
+            // later still line up
+            let mut columns: Vec<String> = Vec::new();
+            for (name, _) in records.iter().flatten() {
+                if !columns.contains(name) {
+                    columns.push(name.clone());
+                }
+            }
+            if columns.is_empty() {
+                columns = plain;
+            }
+            if let Some(i) = columns.iter().position(|c| c == "id") {
+                let id = columns.remove(i);
+                columns.insert(0, id);
+            }
+            let quoted: Vec<String> = columns.iter().map(|c| analytics_export::quote_identifier(c)).collect();
+            tx.execute(&format!("CREATE TABLE {} ({})", table, quoted.join(", ")), [])?;
+            let placeholders = vec!["?"; columns.len()].join(", ");
+            let mut insert = tx.prepare(&format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                table,
+                quoted.join(", "),
+                placeholders
+            ))?;
+            for record in records.iter_mut() {
+                let values = columns.iter().map(|column| {
+                    record
+                        .iter_mut()
+                        .find(|(name, _)| name == column)
+                        .map(|(_, value)| std::mem::replace(value, rusqlite::types::Value::Null))
+                        .unwrap_or(rusqlite::types::Value::Null)
+                });
+                insert.execute(rusqlite::params_from_iter(values))?;
+            }
+            tables.push(ExportedTable {
+                name: table.to_string(),
+                rows: records.len(),
+            });
         }
-        Ok(protocols)
+        tx.commit().context("Failed to write analytics dataset")?;
+
+        info!("Exported {} tables to {}", tables.len(), path.display());
+        Ok(AnalyticsExport {
+            path: path.to_path_buf(),
+            tables,
+        })
+    }
+
+    /// Run `writes` in one transaction, committing only if it returns `Ok`
+    ///
+    /// For workflows that save several related records, so a failure part way
+    /// leaves none of them behind. As with [`connection`](Self::connection),
+    /// don't call other writing methods from inside `writes`.
+    pub fn transaction<T>(&self, writes: impl FnOnce(&StorageTransaction<'_>) -> Result<T>) -> Result<T> {
+        let mut conn = self.write_connection()?;
+        let batch = StorageTransaction {
+            storage: self,
+            tx: conn.transaction()?,
+        };
+        // Dropping the transaction on error rolls it back
+        let value = writes(&batch)?;
+        batch.tx.commit().context("Failed to commit transaction")?;
+        // Stats read while the transaction was open don't include it
+        self.stats_generation.fetch_add(1, Ordering::SeqCst);
+        Ok(value)
+    }
+
+    pub fn upsert_protocol(&self, protocol: &PeptideProtocol) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.write_protocol(&conn, protocol)?;
+        self.invalidate_stats_on(&conn, "protocols")
+    }
+
+    /// Insert or replace a protocol with its audit entry and search document
+    fn write_protocol(&self, conn: &Connection, protocol: &PeptideProtocol) -> Result<()> {
+        let previous = self.stored_payload(conn, "SELECT payload FROM protocols WHERE id = ?1", &protocol.id)?;
+        let payload = serde_json::to_vec(protocol).context("Failed to serialize protocol")?;
+        let encrypted = self.encryption.seal(&payload)?;
+
+        conn.execute(
+            r#"
+            INSERT INTO protocols (id, name, payload, updated_at, is_favorite)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                payload = excluded.payload,
+                updated_at = excluded.updated_at,
+                is_favorite = excluded.is_favorite;
+            "#,
+            params![
+                protocol.id,
+                protocol.name,
+                encrypted,
+                protocol.updated_at.to_string(),
+                protocol.is_favorite as i32
+            ],
+        )
+        .context("Failed to upsert protocol")?;
+
+        self.audit_upsert(conn, AuditEntityType::Protocol, &protocol.id, previous, protocol)?;
+
+        self.index_search_document(
+            conn,
+            SearchEntityType::Protocol,
+            &protocol.id,
+            &search::protocol_document(protocol),
+        )?;
+        Ok(())
+    }
+
+    pub fn list_protocols(&self) -> Result<Vec<PeptideProtocol>> {
+        self.list_protocols_page(&ListOptions::default())
+    }
+
+    /// Lists protocols with paging; `since` applies to the last update
+    pub fn list_protocols_page(&self, options: &ListOptions) -> Result<Vec<PeptideProtocol>> {
+        self.list_page(
+            &PROTOCOLS_QUERY,
+            &[],
+            options,
+            |blob| self.decode_protocol(blob).map(Some),
+            |item| item.updated_at,
+        )
     }
 
     pub fn get_protocol(&self, protocol_id: &str) -> Result<Option<PeptideProtocol>> {
         let conn = self.open_connection()?;
-        let mut stmt = conn.prepare("SELECT payload FROM protocols WHERE id = ?1")?;
+        let mut stmt = conn.prepare_cached("SELECT payload FROM protocols WHERE id = ?1 AND deleted_at IS NULL")?;
         let mut rows = stmt.query([protocol_id])?;
 
         if let Some(row) = rows.next()? {
@@ -455,14 +2047,25 @@ impl StorageManager {
     /// # Ok::<(), anyhow::Error>(())
     /// ```
     pub fn delete_protocol(&self, protocol_id: &str) -> Result<()> {
-        let conn = self.open_connection()?;
+        let conn = self.write_connection()?;
+        self.delete_protocol_on(&conn, protocol_id)
+    }
+
+    /// Delete a protocol, its schedules and search entries
+    fn delete_protocol_on(&self, conn: &Connection, protocol_id: &str) -> Result<()> {
+        self.remove_protocol_search_entries(conn, protocol_id)?;
+        self.audit_protocol_cascade(conn, protocol_id)?;
+        self.delete_protocol_schedules(conn, protocol_id)?;
         let rows_affected = conn
             .execute("DELETE FROM protocols WHERE id = ?1", params![protocol_id])
             .context("Failed to delete protocol")?;
+        self.invalidate_stats_on(conn, "protocols")?;
+        self.invalidate_stats_on(conn, "dose_logs")?;
 
         if rows_affected == 0 {
             return Err(anyhow::anyhow!("Protocol not found: {}", protocol_id));
         }
+        self.audit_delete(conn, AuditEntityType::Protocol, protocol_id)?;
 
         Ok(())
     }
@@ -492,18 +2095,26 @@ impl StorageManager {
             return Ok(0);
         }
 
-        let conn = self.open_connection()?;
+        let conn = self.write_connection()?;
         let mut total_deleted = 0;
 
         // Use a transaction for atomic bulk delete
         let tx = conn.unchecked_transaction()?;
         {
-            let mut stmt = tx.prepare("DELETE FROM protocols WHERE id = ?1")?;
+            let mut stmt = tx.prepare_cached("DELETE FROM protocols WHERE id = ?1")?;
             for protocol_id in protocol_ids {
+                self.remove_protocol_search_entries(&tx, protocol_id)?;
+                self.audit_protocol_cascade(&tx, protocol_id)?;
+                self.delete_protocol_schedules(&tx, protocol_id)?;
                 let rows = stmt.execute(params![protocol_id])?;
+                if rows > 0 {
+                    self.audit_delete(&tx, AuditEntityType::Protocol, protocol_id)?;
+                }
                 total_deleted += rows;
             }
         }
+        self.invalidate_stats_on(&tx, "protocols")?;
+        self.invalidate_stats_on(&tx, "dose_logs")?;
         tx.commit()?;
 
         Ok(total_deleted)
@@ -534,18 +2145,24 @@ impl StorageManager {
             return Ok(0);
         }
 
-        let conn = self.open_connection()?;
+        let conn = self.write_connection()?;
         let mut total_deleted = 0;
 
         // Use a transaction for atomic bulk delete
         let tx = conn.unchecked_transaction()?;
         {
-            let mut stmt = tx.prepare("DELETE FROM dose_logs WHERE id = ?1")?;
+            let mut stmt = tx.prepare_cached("DELETE FROM dose_logs WHERE id = ?1")?;
             for dose_id in dose_ids {
                 let rows = stmt.execute(params![dose_id])?;
+                if rows > 0 {
+                    self.audit_delete(&tx, AuditEntityType::DoseLog, dose_id)?;
+                }
                 total_deleted += rows;
+                self.delete_attachments_for(&tx, &AttachmentOwner::DoseLog, dose_id)?;
+                self.remove_search_document(&tx, SearchEntityType::DoseLog, dose_id)?;
             }
         }
+        self.invalidate_stats_on(&tx, "dose_logs")?;
         tx.commit()?;
 
         Ok(total_deleted)
@@ -639,6 +2256,213 @@ impl StorageManager {
         Ok(modified_count)
     }
 
+    /// Bulk set the vial status of inventory items
+    ///
+    /// Items already in `status` are left alone. All items are updated in a
+    /// single transaction.
+    ///
+    /// # Returns
+    /// The number of inventory items that were actually modified
+    pub fn bulk_update_inventory_status(&self, item_ids: &[String], status: VialStatus) -> Result<usize> {
+        if item_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let conn = self.write_connection()?;
+        let tx = conn.unchecked_transaction()?;
+        let updated = self.update_inventory_items(&tx, item_ids, |item| {
+            if item.vial_status == status {
+                return false;
+            }
+            item.vial_status = status.clone();
+            true
+        })?;
+        self.invalidate_stats_on(&tx, "inventory")?;
+        tx.commit()?;
+
+        Ok(updated)
+    }
+
+    /// Bulk delete inventory items
+    ///
+    /// Deletes the items with their attachments in a single transaction.
+    /// This operation cannot be undone.
+    ///
+    /// # Returns
+    /// The number of inventory items actually deleted
+    pub fn bulk_delete_inventory_items(&self, item_ids: &[String]) -> Result<usize> {
+        if item_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let conn = self.write_connection()?;
+        let tx = conn.unchecked_transaction()?;
+        let total_deleted = self.delete_inventory_items(&tx, item_ids)?;
+        self.invalidate_stats_on(&tx, "inventory")?;
+        tx.commit()?;
+
+        Ok(total_deleted)
+    }
+
+    /// Bulk set the supplier of inventory items, or clear it with `None`
+    ///
+    /// Fails without changing anything when the supplier doesn't exist.
+    ///
+    /// # Returns
+    /// The number of inventory items that were actually modified
+    pub fn bulk_assign_supplier(&self, item_ids: &[String], supplier_id: Option<&str>) -> Result<usize> {
+        if item_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let conn = self.write_connection()?;
+        let tx = conn.unchecked_transaction()?;
+        if let Some(supplier_id) = supplier_id {
+            let exists = tx
+                .query_row("SELECT 1 FROM suppliers WHERE id = ?1", params![supplier_id], |_| Ok(()))
+                .optional()?
+                .is_some();
+            if !exists {
+                anyhow::bail!("Supplier {} not found", supplier_id);
+            }
+        }
+
+        let updated = self.update_inventory_items(&tx, item_ids, |item| {
+            if item.supplier_id.as_deref() == supplier_id {
+                return false;
+            }
+            item.supplier_id = supplier_id.map(str::to_string);
+            true
+        })?;
+        self.invalidate_stats_on(&tx, "inventory")?;
+        tx.commit()?;
+
+        Ok(updated)
+    }
+
+    /// Bulk delete suppliers
+    ///
+    /// Price history can't outlive its supplier, so it's deleted too.
+    /// Inventory bought from a deleted supplier is kept with no supplier, or
+    /// deleted, as [`CascadePolicies::supplier_inventory`] says; orders keep
+    /// the supplier id they were placed with. Everything happens in a single
+    /// transaction and cannot be undone.
+    pub fn bulk_delete_suppliers(&self, supplier_ids: &[String]) -> Result<SupplierDeletion> {
+        if supplier_ids.is_empty() {
+            return Ok(SupplierDeletion::default());
+        }
+        let policies: CascadePolicies = self.load_setting_or_default()?;
+
+        let conn = self.write_connection()?;
+        let tx = conn.unchecked_transaction()?;
+        let deletion = self.delete_suppliers_on(&tx, supplier_ids, &policies)?;
+        tx.commit()?;
+
+        Ok(deletion)
+    }
+
+    /// Delete suppliers as [`bulk_delete_suppliers`](Self::bulk_delete_suppliers)
+    /// does, inside the caller's transaction
+    fn delete_suppliers_on(
+        &self,
+        conn: &Connection,
+        supplier_ids: &[String],
+        policies: &CascadePolicies,
+    ) -> Result<SupplierDeletion> {
+        let mut deletion = SupplierDeletion::default();
+        for supplier_id in supplier_ids {
+            let linked = query_ids(conn, "SELECT id FROM inventory WHERE supplier_id = ?1", params![supplier_id])?;
+            match policies.supplier_inventory {
+                SupplierInventoryPolicy::Unlink => {
+                    deletion.inventory_unlinked += self.update_inventory_items(conn, &linked, |item| {
+                        item.supplier_id = None;
+                        true
+                    })?;
+                }
+                SupplierInventoryPolicy::Delete => {
+                    deletion.inventory_deleted += self.delete_inventory_items(conn, &linked)?;
+                }
+            }
+
+            for price_id in query_ids(conn, "SELECT id FROM price_history WHERE supplier_id = ?1", params![supplier_id])? {
+                self.audit_delete(conn, AuditEntityType::PriceHistory, &price_id)?;
+            }
+            deletion.price_history_deleted +=
+                conn.execute("DELETE FROM price_history WHERE supplier_id = ?1", params![supplier_id])?;
+
+            let rows = conn.execute("DELETE FROM suppliers WHERE id = ?1", params![supplier_id])?;
+            if rows > 0 {
+                self.audit_delete(conn, AuditEntityType::Supplier, supplier_id)?;
+            }
+            deletion.suppliers_deleted += rows;
+            self.delete_attachments_for(conn, &AttachmentOwner::Supplier, supplier_id)?;
+            self.remove_search_document(conn, SearchEntityType::Supplier, supplier_id)?;
+        }
+        self.invalidate_stats_on(conn, "suppliers")?;
+        self.invalidate_stats_on(conn, "price_history")?;
+        self.invalidate_stats_on(conn, "inventory")?;
+
+        Ok(deletion)
+    }
+
+    /// Apply `change` to each of `item_ids` and save the items it changed
+    ///
+    /// Missing items are skipped. Returns how many items were saved.
+    fn update_inventory_items(
+        &self,
+        conn: &Connection,
+        item_ids: &[String],
+        mut change: impl FnMut(&mut InventoryItem) -> bool,
+    ) -> Result<usize> {
+        let mut updated = 0;
+        for item_id in item_ids {
+            let Some(previous) = self.stored_payload(conn, "SELECT payload FROM inventory WHERE id = ?1", item_id)? else {
+                continue;
+            };
+            let mut item: InventoryItem =
+                serde_json::from_value(previous.clone()).context("Failed to deserialize inventory item")?;
+            if !change(&mut item) {
+                continue;
+            }
+            item.updated_at = now_timestamp();
+
+            let payload = serde_json::to_vec(&item).context("Failed to serialize inventory item")?;
+            let encrypted = self.encryption.seal(&payload)?;
+            conn.execute(
+                "UPDATE inventory SET supplier_id = ?2, payload = ?3, updated_at = ?4 WHERE id = ?1",
+                params![item.id, item.supplier_id, encrypted, item.updated_at.to_string()],
+            )
+            .context("Failed to update inventory item")?;
+            self.audit_upsert(conn, AuditEntityType::InventoryItem, &item.id, Some(previous), &item)?;
+            self.index_search_document(
+                conn,
+                SearchEntityType::InventoryItem,
+                &item.id,
+                &search::inventory_document(&item),
+            )?;
+            updated += 1;
+        }
+        Ok(updated)
+    }
+
+    /// Delete `item_ids` with their attachments and search documents
+    ///
+    /// Returns how many items were deleted.
+    fn delete_inventory_items(&self, conn: &Connection, item_ids: &[String]) -> Result<usize> {
+        let mut deleted = 0;
+        let mut stmt = conn.prepare_cached("DELETE FROM inventory WHERE id = ?1")?;
+        for item_id in item_ids {
+            let rows = stmt.execute(params![item_id])?;
+            if rows > 0 {
+                self.audit_delete(conn, AuditEntityType::InventoryItem, item_id)?;
+            }
+            deleted += rows;
+            self.delete_attachments_for(conn, &AttachmentOwner::InventoryItem, item_id)?;
+            self.remove_search_document(conn, SearchEntityType::InventoryItem, item_id)?;
+        }
+        Ok(deleted)
+    }
+
     /// Perform comprehensive database health check
     ///
     /// Runs PRAGMA quick_check to verify database integrity and collects
@@ -772,6 +2596,21 @@ impl StorageManager {
         Ok(())
     }
 
+    /// Run the full `PRAGMA integrity_check`, returning the problems found
+    ///
+    /// Slower than [`health_check`](Self::health_check), which only runs
+    /// `quick_check`, as it also checks that indexes match their tables.
+    /// Empty when the database is intact.
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let results = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to run integrity check")?;
+        Ok(results.into_iter().filter(|result| result != "ok").collect())
+    }
+
     /// Optimize database performance and reclaim unused space
     ///
     /// Performs three optimization operations:
@@ -804,7 +2643,7 @@ impl StorageManager {
     /// - May take several seconds on large databases
     /// - Does NOT require exclusive lock
     pub fn optimize(&self) -> Result<()> {
-        let conn = self.open_connection()?;
+        let conn = self.write_connection()?;
 
         info!("Running database optimization...");
 
@@ -866,7 +2705,7 @@ impl StorageManager {
     /// - FULL/RESTART/TRUNCATE: May block briefly
     /// - Auto-checkpoint is configured to run every 1000 pages
     pub fn checkpoint_wal(&self, mode: &str) -> Result<()> {
-        let conn = self.open_connection()?;
+        let conn = self.write_connection()?;
 
         let checkpoint_mode = match mode.to_uppercase().as_str() {
             "PASSIVE" => "PASSIVE",
@@ -889,6 +2728,57 @@ impl StorageManager {
         Ok(())
     }
 
+    /// Rebuild the database file without its free pages
+    ///
+    /// Unlike [`optimize`](Self::optimize), which reclaims free pages
+    /// incrementally, this runs a full `VACUUM`, so the file shrinks to the
+    /// data it holds. The WAL is merged and truncated before and after.
+    ///
+    /// Blocks other writes until it finishes, which can take a while on
+    /// large databases.
+    pub fn compact(&self) -> Result<()> {
+        let conn = self.write_connection()?;
+        compact_connection(&conn)?;
+        info!("Database compacted");
+        Ok(())
+    }
+
+    /// Delete every row in the database and scrub it from disk
+    ///
+    /// With `secure_delete` on, freed pages are overwritten with zeros; the
+    /// WAL is then truncated and the file vacuumed, so no deleted record is
+    /// left in either. The schema is kept, so the database can be used again
+    /// once a new key is in place. Returns the number of rows deleted.
+    pub fn secure_wipe(&self) -> Result<usize> {
+        let mut conn = self.write_connection()?;
+        // FTS shadow tables are listed as 'shadow' and emptied with their table
+        let tables = query_ids(
+            &conn,
+            "SELECT name FROM pragma_table_list
+             WHERE schema = 'main' AND type IN ('table', 'virtual')
+               AND (name NOT LIKE 'sqlite_%' OR name = 'sqlite_sequence')",
+            [],
+        )
+        .context("Failed to list tables")?;
+
+        let tx = conn.transaction()?;
+        // Tables are emptied in no particular order, so check references
+        // once everything is gone
+        tx.execute_batch("PRAGMA defer_foreign_keys = ON")?;
+        let mut deleted = 0;
+        for table in &tables {
+            deleted += tx
+                .execute(&format!("DELETE FROM \"{}\"", table.replace('"', "\"\"")), [])
+                .with_context(|| format!("Failed to wipe {}", table))?;
+        }
+        tx.commit()?;
+
+        compact_connection(&conn)?;
+        self.stats_generation.fetch_add(1, Ordering::SeqCst);
+        info!("Securely wiped {} rows from {} tables", deleted, tables.len());
+        Ok(deleted)
+    }
+
     /// Get detailed database statistics for monitoring and maintenance
     ///
     /// Collects comprehensive metrics about database size, fragmentation,
@@ -964,34 +2854,82 @@ impl StorageManager {
         })
     }
 
-    /// Get a database connection for advanced operations
+    /// Get the write connection for advanced operations
     /// WARNING: Use with caution - bypasses encryption for direct SQL access
-    pub fn connection(&self) -> Result<Connection> {
-        self.open_connection()
+    ///
+    /// Other writes wait until it's dropped, so drop it before calling any
+    /// other storage method that writes.
+    pub fn connection(&self) -> Result<WriterConnection<'_>> {
+        self.write_connection()
     }
 
     pub fn append_dose_log(&self, log: &DoseLog) -> Result<()> {
-        let conn = self.open_connection()?;
+        let conn = self.write_connection()?;
+        self.write_dose_log(&conn, log)?;
+        self.invalidate_stats_on(&conn, "dose_logs")
+    }
+
+    /// Save many dose logs in a single transaction, all or none
+    ///
+    /// Used to backfill doses logged after the fact. Returns the number of
+    /// logs written.
+    pub fn append_dose_logs(&self, logs: &[DoseLog]) -> Result<usize> {
+        if logs.is_empty() {
+            return Ok(0);
+        }
+
+        let conn = self.write_connection()?;
+        let tx = conn.unchecked_transaction()?;
+        for log in logs {
+            self.write_dose_log(&tx, log)?;
+        }
+        self.invalidate_stats_on(&tx, "dose_logs")?;
+        tx.commit()?;
+
+        Ok(logs.len())
+    }
+
+    /// Insert or replace a dose log with its index columns, audit entry and
+    /// search document
+    fn write_dose_log(&self, conn: &Connection, log: &DoseLog) -> Result<()> {
+        let previous = self.stored_payload(conn, "SELECT payload FROM dose_logs WHERE id = ?1", &log.id)?;
         let payload = serde_json::to_vec(log).context("Failed to serialize dose log")?;
         let encrypted = self.encryption.seal(&payload)?;
 
         conn.execute(
             r#"
-            INSERT INTO dose_logs (id, protocol_id, payload, logged_at)
-            VALUES (?1, ?2, ?3, ?4)
+            INSERT INTO dose_logs (id, protocol_id, payload, logged_at, amount_mg, site_code, schedule_id, edited_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
             ON CONFLICT(id) DO UPDATE SET
+                protocol_id = excluded.protocol_id,
                 payload = excluded.payload,
-                logged_at = excluded.logged_at;
+                logged_at = excluded.logged_at,
+                amount_mg = excluded.amount_mg,
+                site_code = excluded.site_code,
+                schedule_id = excluded.schedule_id,
+                edited_at = excluded.edited_at;
             "#,
             params![
                 log.id,
                 log.protocol_id,
                 encrypted,
-                log.logged_at.to_string()
+                log.logged_at.to_string(),
+                log.amount_mg,
+                site_code(&log.site),
+                log.schedule_id,
+                log.edited_at.map(|at| at.unix_timestamp())
             ],
         )
         .context("Failed to append dose log")?;
 
+        self.audit_upsert(conn, AuditEntityType::DoseLog, &log.id, previous, log)?;
+
+        self.index_search_document(
+            conn,
+            SearchEntityType::DoseLog,
+            &log.id,
+            &search::dose_log_document(log),
+        )?;
         Ok(())
     }
 
@@ -999,41 +2937,353 @@ impl StorageManager {
     ///
     /// Returns logs ordered by logged_at (most recent first).
     pub fn list_dose_logs(&self) -> Result<Vec<DoseLog>> {
-        let conn = self.open_connection()?;
-        let mut stmt = conn.prepare("SELECT payload FROM dose_logs ORDER BY logged_at DESC")?;
-        let mut rows = stmt.query([]).context("Unable to run dose logs query")?;
-        let mut logs = Vec::new();
-        while let Some(row) = rows.next()? {
-            let blob: Vec<u8> = row.get(0)?;
-            logs.push(self.decode_dose_log(&blob)?);
-        }
-        Ok(logs)
+        self.list_dose_logs_page(&ListOptions::default())
+    }
+
+    /// Lists dose logs with paging, most recent first
+    pub fn list_dose_logs_page(&self, options: &ListOptions) -> Result<Vec<DoseLog>> {
+        self.list_page(
+            &DOSE_LOGS_QUERY,
+            &[],
+            options,
+            |blob| self.decode_dose_log(blob).map(Some),
+            |item| item.logged_at,
+        )
     }
 
     /// Lists dose logs for a specific protocol
     ///
     /// Returns logs ordered by logged_at (most recent first).
     pub fn list_dose_logs_for_protocol(&self, protocol_id: &str) -> Result<Vec<DoseLog>> {
+        self.list_dose_logs_for_protocol_page(protocol_id, &ListOptions::default())
+    }
+
+    /// Lists a protocol's dose logs with paging, most recent first
+    pub fn list_dose_logs_for_protocol_page(
+        &self,
+        protocol_id: &str,
+        options: &ListOptions,
+    ) -> Result<Vec<DoseLog>> {
+        self.list_page(
+            &PROTOCOL_DOSE_LOGS_QUERY,
+            &[&protocol_id],
+            options,
+            |blob| self.decode_dose_log(blob).map(Some),
+            |item| item.logged_at,
+        )
+    }
+
+    /// Get a specific dose log by ID
+    pub fn get_dose_log(&self, log_id: &str) -> Result<Option<DoseLog>> {
         let conn = self.open_connection()?;
-        let mut stmt = conn.prepare(
-            "SELECT payload FROM dose_logs WHERE protocol_id = ?1 ORDER BY logged_at DESC",
+        let blob: Option<Vec<u8>> = conn
+            .prepare_cached("SELECT payload FROM dose_logs WHERE id = ?1 AND deleted_at IS NULL")?
+            .query_row(params![log_id], |row| row.get(0))
+            .optional()
+            .context("Failed to fetch dose log")?;
+
+        blob.map(|blob| self.decode_dose_log(&blob)).transpose()
+    }
+
+    /// Edit a dose log, keeping the values it had before
+    ///
+    /// The stored version is copied unchanged into `dose_log_corrections`,
+    /// so the dose as first logged and every later version can still be read
+    /// with [`list_dose_log_corrections`](Self::list_dose_log_corrections).
+    /// The saved log gets `edited_at` set, which stats can filter on. Saving
+    /// the same values again changes nothing. Fails if the log doesn't exist
+    /// or is in the trash.
+    pub fn update_dose_log(&self, log: &DoseLog) -> Result<DoseLog> {
+        let conn = self.write_connection()?;
+        let stored: Option<Vec<u8>> = conn
+            .prepare_cached("SELECT payload FROM dose_logs WHERE id = ?1 AND deleted_at IS NULL")?
+            .query_row(params![log.id], |row| row.get(0))
+            .optional()
+            .context("Failed to fetch dose log")?;
+        let Some(stored) = stored else {
+            anyhow::bail!("Dose log {} not found", log.id);
+        };
+
+        let previous = self.decode_dose_log(&stored)?;
+        let mut updated = log.clone();
+        updated.edited_at = previous.edited_at;
+        let previous = serde_json::to_value(&previous).context("Failed to serialize dose log")?;
+        let current = serde_json::to_value(&updated).context("Failed to serialize dose log")?;
+        if audit::changed_fields(&previous, &current).is_empty() {
+            return Ok(updated);
+        }
+
+        let now = OffsetDateTime::now_utc();
+        updated.edited_at = Some(now);
+        let payload = serde_json::to_vec(&updated).context("Failed to serialize dose log")?;
+        let encrypted = self.encryption.seal(&payload)?;
+
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO dose_log_corrections (id, dose_log_id, payload, corrected_at) VALUES (?1, ?2, ?3, ?4)",
+            params![uuid::Uuid::new_v4().to_string(), updated.id, stored, now.unix_timestamp()],
+        )
+        .context("Failed to save the dose log's previous values")?;
+        tx.execute(
+            r#"
+            UPDATE dose_logs SET
+                protocol_id = ?2,
+                payload = ?3,
+                logged_at = ?4,
+                amount_mg = ?5,
+                site_code = ?6,
+                schedule_id = ?7,
+                edited_at = ?8
+            WHERE id = ?1
+            "#,
+            params![
+                updated.id,
+                updated.protocol_id,
+                encrypted,
+                updated.logged_at.to_string(),
+                updated.amount_mg,
+                site_code(&updated.site),
+                updated.schedule_id,
+                now.unix_timestamp()
+            ],
+        )
+        .context("Failed to update dose log")?;
+        self.invalidate_stats_on(&tx, "dose_logs")?;
+        self.audit_upsert(&tx, AuditEntityType::DoseLog, &updated.id, Some(previous), &updated)?;
+        self.index_search_document(
+            &tx,
+            SearchEntityType::DoseLog,
+            &updated.id,
+            &search::dose_log_document(&updated),
         )?;
-        let mut rows = stmt
-            .query([protocol_id])
-            .context("Unable to run dose logs query")?;
-        let mut logs = Vec::new();
-        while let Some(row) = rows.next()? {
-            let blob: Vec<u8> = row.get(0)?;
-            logs.push(self.decode_dose_log(&blob)?);
+        tx.commit()?;
+
+        Ok(updated)
+    }
+
+    /// A dose log's values before each of its edits, oldest first
+    pub fn list_dose_log_corrections(&self, log_id: &str) -> Result<Vec<DoseLogCorrection>> {
+        let conn = self.open_connection()?;
+        let rows = conn
+            .prepare_cached(
+                "SELECT id, payload, corrected_at FROM dose_log_corrections
+                 WHERE dose_log_id = ?1 ORDER BY corrected_at, rowid",
+            )?
+            .query_map(params![log_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?, row.get::<_, i64>(2)?))
+            })
+            .context("Failed to list dose log corrections")?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter()
+            .map(|(id, blob, corrected_at)| {
+                Ok(DoseLogCorrection {
+                    id,
+                    dose_log_id: log_id.to_string(),
+                    original: self.decode_dose_log(&blob)?,
+                    corrected_at: OffsetDateTime::from_unix_timestamp(corrected_at)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Record that a scheduled dose was skipped
+    ///
+    /// Returns false when that schedule's dose was already skipped that day.
+    pub fn record_dose_skip(&self, skip: &DoseSkip) -> Result<bool> {
+        let conn = self.write_connection()?;
+        let inserted = conn
+            .prepare_cached(
+                "INSERT INTO dose_skips (id, protocol_id, schedule_id, scheduled_on, skipped_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (schedule_id, scheduled_on) DO NOTHING",
+            )?
+            .execute(params![
+                skip.id,
+                skip.protocol_id,
+                skip.schedule_id,
+                skip.scheduled_on.to_string(),
+                skip.skipped_at.unix_timestamp()
+            ])
+            .context("Failed to record skipped dose")?;
+        if inserted > 0 {
+            self.invalidate_stats_on(&conn, "dose_skips")?;
+        }
+        Ok(inserted > 0)
+    }
+
+    /// Skipped doses matching `filter`, oldest first
+    ///
+    /// `since` and `until` are compared against the day the dose was due.
+    pub fn list_dose_skips(&self, filter: &DoseStatsFilter) -> Result<Vec<DoseSkip>> {
+        let mut conditions = vec!["1 = 1".to_string()];
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+        if let Some(protocol_id) = &filter.protocol_id {
+            values.push(protocol_id.clone().into());
+            conditions.push(format!("protocol_id = ?{}", values.len()));
+        }
+        if let Some(schedule_id) = &filter.schedule_id {
+            values.push(schedule_id.clone().into());
+            conditions.push(format!("schedule_id = ?{}", values.len()));
+        }
+        if let Some(since) = filter.since {
+            values.push(since.to_string().into());
+            conditions.push(format!("scheduled_on >= ?{}", values.len()));
+        }
+        if let Some(until) = filter.until {
+            values.push(until.to_string().into());
+            conditions.push(format!("scheduled_on <= ?{}", values.len()));
+        }
+
+        let query = format!(
+            "SELECT id, protocol_id, schedule_id, scheduled_on, skipped_at FROM dose_skips
+             WHERE {} ORDER BY scheduled_on, skipped_at",
+            conditions.join(" AND ")
+        );
+        let conn = self.open_connection()?;
+        let rows = conn
+            .prepare_cached(&query)?
+            .query_map(rusqlite::params_from_iter(values), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })
+            .context("Failed to list skipped doses")?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter()
+            .map(|(id, protocol_id, schedule_id, scheduled_on, skipped_at)| {
+                Ok(DoseSkip {
+                    id,
+                    protocol_id,
+                    schedule_id,
+                    scheduled_on: Date::parse(&scheduled_on, format_description!("[year]-[month]-[day]"))
+                        .with_context(|| format!("Invalid skipped dose date {}", scheduled_on))?,
+                    skipped_at: OffsetDateTime::from_unix_timestamp(skipped_at)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Dose count and total amount per protocol, largest total first
+    pub fn dose_usage_by_protocol(&self, filter: &DoseStatsFilter) -> Result<Vec<ProtocolDoseUsage>> {
+        self.aggregate_dose_logs(
+            "protocol_id, COUNT(*), COALESCE(SUM(amount_mg), 0), COUNT(DISTINCT substr(logged_at, 1, 10))",
+            "protocol_id ORDER BY 3 DESC, protocol_id",
+            filter,
+            |row| {
+                Ok(ProtocolDoseUsage {
+                    protocol_id: row.get(0)?,
+                    dose_count: row.get(1)?,
+                    total_mg: row.get(2)?,
+                    active_days: row.get(3)?,
+                })
+            },
+        )
+    }
+
+    /// Dose count and total amount per injection site, most used first
+    pub fn dose_usage_by_site(&self, filter: &DoseStatsFilter) -> Result<Vec<SiteDoseUsage>> {
+        self.aggregate_dose_logs(
+            "COALESCE(site_code, 'other'), COUNT(*), COALESCE(SUM(amount_mg), 0)",
+            "1 ORDER BY 2 DESC, 1",
+            filter,
+            |row| {
+                Ok(SiteDoseUsage {
+                    site_code: row.get(0)?,
+                    dose_count: row.get(1)?,
+                    total_mg: row.get(2)?,
+                })
+            },
+        )
+    }
+
+    /// Dose count and total amount per day, oldest first; days without doses
+    /// are left out
+    pub fn daily_dose_totals(&self, filter: &DoseStatsFilter) -> Result<Vec<DailyDoseTotal>> {
+        self.aggregate_dose_logs(
+            "substr(logged_at, 1, 10), COUNT(*), COALESCE(SUM(amount_mg), 0)",
+            "1 ORDER BY 1",
+            filter,
+            |row| {
+                Ok(DailyDoseTotal {
+                    date: row.get(0)?,
+                    dose_count: row.get(1)?,
+                    total_mg: row.get(2)?,
+                })
+            },
+        )
+    }
+
+    /// Runs `SELECT {columns} ... GROUP BY {group_by}` over dose logs that
+    /// aren't in the trash and match `filter`
+    fn aggregate_dose_logs<T>(
+        &self,
+        columns: &str,
+        group_by: &str,
+        filter: &DoseStatsFilter,
+        decode: impl Fn(&Row<'_>) -> rusqlite::Result<T>,
+    ) -> Result<Vec<T>> {
+        let mut conditions = vec!["deleted_at IS NULL".to_string()];
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+
+        if let Some(protocol_id) = &filter.protocol_id {
+            values.push(protocol_id.clone().into());
+            conditions.push(format!("protocol_id = ?{}", values.len()));
+        }
+        if let Some(schedule_id) = &filter.schedule_id {
+            values.push(schedule_id.clone().into());
+            conditions.push(format!("schedule_id = ?{}", values.len()));
         }
-        Ok(logs)
+        if filter.exclude_edited {
+            conditions.push("edited_at IS NULL".to_string());
+        }
+        // logged_at starts with the ISO date, so whole days compare as text
+        if let Some(since) = filter.since {
+            values.push(since.to_string().into());
+            conditions.push(format!("substr(logged_at, 1, 10) >= ?{}", values.len()));
+        }
+        if let Some(until) = filter.until {
+            values.push(until.to_string().into());
+            conditions.push(format!("substr(logged_at, 1, 10) <= ?{}", values.len()));
+        }
+
+        let query = format!(
+            "SELECT {} FROM dose_logs WHERE {} GROUP BY {}",
+            columns,
+            conditions.join(" AND "),
+            group_by
+        );
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare_cached(&query)?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(values), |row| decode(row))
+            .context("Unable to aggregate dose logs")?
+            .collect::<rusqlite::Result<Vec<T>>>()?;
+        Ok(rows)
     }
 
     /// Deletes a specific dose log by ID
     pub fn delete_dose_log(&self, log_id: &str) -> Result<()> {
-        let conn = self.open_connection()?;
-        conn.execute("DELETE FROM dose_logs WHERE id = ?1", params![log_id])
+        let conn = self.write_connection()?;
+        self.delete_dose_log_on(&conn, log_id)
+    }
+
+    /// Delete a dose log with its attachments
+    fn delete_dose_log_on(&self, conn: &Connection, log_id: &str) -> Result<()> {
+        let deleted = conn
+            .execute("DELETE FROM dose_logs WHERE id = ?1", params![log_id])
             .context("Failed to delete dose log")?;
+        self.invalidate_stats_on(conn, "dose_logs")?;
+        if deleted > 0 {
+            self.audit_delete(conn, AuditEntityType::DoseLog, log_id)?;
+        }
+        self.delete_attachments_for(conn, &AttachmentOwner::DoseLog, log_id)?;
+        self.remove_search_document(conn, SearchEntityType::DoseLog, log_id)?;
         Ok(())
     }
 
@@ -1058,7 +3308,13 @@ impl StorageManager {
     /// # Ok::<(), anyhow::Error>(())
     /// ```
     pub fn upsert_body_metric(&self, metric: &BodyMetric) -> Result<()> {
-        let conn = self.open_connection()?;
+        let conn = self.write_connection()?;
+        self.write_body_metric(&conn, metric)
+    }
+
+    /// Insert or replace a body metric with its audit entry
+    fn write_body_metric(&self, conn: &Connection, metric: &BodyMetric) -> Result<()> {
+        let previous = self.stored_payload(conn, "SELECT payload FROM body_metrics WHERE id = ?1", &metric.id)?;
         let payload = serde_json::to_vec(metric).context("Failed to serialize body metric")?;
         let encrypted = self.encryption.seal(&payload)?;
 
@@ -1081,9 +3337,60 @@ impl StorageManager {
         )
         .context("Failed to upsert body metric")?;
 
+        self.audit_upsert(conn, AuditEntityType::BodyMetric, &metric.id, previous, metric)?;
+        self.invalidate_stats_on(conn, "body_metrics")?;
+
         Ok(())
     }
 
+    /// Save or update many body metrics in a single transaction
+    ///
+    /// Used by bulk imports so thousands of days don't each open a connection.
+    /// Returns the number of metrics written.
+    pub fn upsert_body_metrics(&self, metrics: &[BodyMetric]) -> Result<usize> {
+        if metrics.is_empty() {
+            return Ok(0);
+        }
+
+        let conn = self.write_connection()?;
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                r#"
+                INSERT INTO body_metrics (id, date, payload, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT(id) DO UPDATE SET
+                    date = excluded.date,
+                    payload = excluded.payload,
+                    updated_at = excluded.updated_at;
+                "#,
+            )?;
+            for metric in metrics {
+                let previous = self.stored_payload(
+                    &tx,
+                    "SELECT payload FROM body_metrics WHERE id = ?1",
+                    &metric.id,
+                )?;
+                let payload =
+                    serde_json::to_vec(metric).context("Failed to serialize body metric")?;
+                let encrypted = self.encryption.seal(&payload)?;
+                stmt.execute(params![
+                    metric.id,
+                    metric.date.to_string(),
+                    encrypted,
+                    metric.created_at.to_string(),
+                    metric.updated_at.to_string()
+                ])
+                .context("Failed to upsert body metric")?;
+                self.audit_upsert(&tx, AuditEntityType::BodyMetric, &metric.id, previous, metric)?;
+            }
+        }
+        self.invalidate_stats_on(&tx, "body_metrics")?;
+        tx.commit()?;
+
+        Ok(metrics.len())
+    }
+
     /// List all body metrics ordered by date (most recent first)
     ///
     /// Returns all body metric entries from the database, decrypted
@@ -1100,22 +3407,18 @@ impl StorageManager {
     /// # Ok::<(), anyhow::Error>(())
     /// ```
     pub fn list_body_metrics(&self) -> Result<Vec<BodyMetric>> {
-        let conn = self.open_connection()?;
-        let mut stmt = conn.prepare("SELECT payload FROM body_metrics ORDER BY date DESC")?;
-        let mut rows = stmt
-            .query([])
-            .context("Unable to run body metrics list query")?;
-
-        let mut metrics = Vec::new();
-        while let Some(row) = rows.next()? {
-            let blob: Vec<u8> = row.get(0)?;
-            let decrypted = self.encryption.open(&blob)?;
-            let metric: BodyMetric = serde_json::from_slice(&decrypted)
-                .context("Failed to deserialize body metric")?;
-            metrics.push(metric);
-        }
+        self.list_body_metrics_page(&ListOptions::default())
+    }
 
-        Ok(metrics)
+    /// Lists body metrics with paging, most recent first
+    pub fn list_body_metrics_page(&self, options: &ListOptions) -> Result<Vec<BodyMetric>> {
+        self.list_page(
+            &BODY_METRICS_QUERY,
+            &[],
+            options,
+            |blob| self.decode_body_metric(blob).map(Some),
+            |item| item.date,
+        )
     }
 
     /// Get a specific body metric by ID
@@ -1126,7 +3429,7 @@ impl StorageManager {
     /// * `metric_id` - The ID of the body metric to retrieve
     pub fn get_body_metric(&self, metric_id: &str) -> Result<Option<BodyMetric>> {
         let conn = self.open_connection()?;
-        let mut stmt = conn.prepare("SELECT payload FROM body_metrics WHERE id = ?1")?;
+        let mut stmt = conn.prepare_cached("SELECT payload FROM body_metrics WHERE id = ?1 AND deleted_at IS NULL")?;
 
         let result = stmt.query_row(params![metric_id], |row| {
             let blob: Vec<u8> = row.get(0)?;
@@ -1134,12 +3437,7 @@ impl StorageManager {
         });
 
         match result {
-            Ok(blob) => {
-                let decrypted = self.encryption.open(&blob)?;
-                let metric: BodyMetric = serde_json::from_slice(&decrypted)
-                    .context("Failed to deserialize body metric")?;
-                Ok(Some(metric))
-            }
+            Ok(blob) => Ok(Some(self.decode_body_metric(&blob)?)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e.into()),
         }
@@ -1160,9 +3458,20 @@ impl StorageManager {
     /// # Ok::<(), anyhow::Error>(())
     /// ```
     pub fn delete_body_metric(&self, metric_id: &str) -> Result<()> {
-        let conn = self.open_connection()?;
-        conn.execute("DELETE FROM body_metrics WHERE id = ?1", params![metric_id])
+        let conn = self.write_connection()?;
+        self.delete_body_metric_on(&conn, metric_id)
+    }
+
+    /// Delete a body metric with its attachments
+    fn delete_body_metric_on(&self, conn: &Connection, metric_id: &str) -> Result<()> {
+        let deleted = conn
+            .execute("DELETE FROM body_metrics WHERE id = ?1", params![metric_id])
             .context("Failed to delete body metric")?;
+        if deleted > 0 {
+            self.audit_delete(conn, AuditEntityType::BodyMetric, metric_id)?;
+            self.invalidate_stats_on(conn, "body_metrics")?;
+        }
+        self.delete_attachments_for(conn, &AttachmentOwner::BodyMetric, metric_id)?;
         Ok(())
     }
 
@@ -1180,17 +3489,24 @@ impl StorageManager {
             return Ok(0);
         }
 
-        let conn = self.open_connection()?;
+        let conn = self.write_connection()?;
         let mut total_deleted = 0;
 
         let tx = conn.unchecked_transaction()?;
         {
-            let mut stmt = tx.prepare("DELETE FROM body_metrics WHERE id = ?1")?;
+            let mut stmt = tx.prepare_cached("DELETE FROM body_metrics WHERE id = ?1")?;
             for metric_id in metric_ids {
                 let rows = stmt.execute(params![metric_id])?;
+                if rows > 0 {
+                    self.audit_delete(&tx, AuditEntityType::BodyMetric, metric_id)?;
+                }
                 total_deleted += rows;
+                self.delete_attachments_for(&tx, &AttachmentOwner::BodyMetric, metric_id)?;
             }
         }
+        if total_deleted > 0 {
+            self.invalidate_stats_on(&tx, "body_metrics")?;
+        }
         tx.commit()?;
 
         Ok(total_deleted)
@@ -1217,7 +3533,13 @@ impl StorageManager {
     /// # Ok::<(), anyhow::Error>(())
     /// ```
     pub fn upsert_side_effect(&self, side_effect: &SideEffect) -> Result<()> {
-        let conn = self.open_connection()?;
+        let conn = self.write_connection()?;
+        self.write_side_effect(&conn, side_effect)
+    }
+
+    /// Insert or replace a side effect with its audit entry
+    fn write_side_effect(&self, conn: &Connection, side_effect: &SideEffect) -> Result<()> {
+        let previous = self.stored_payload(conn, "SELECT payload FROM side_effects WHERE id = ?1", &side_effect.id)?;
         let payload = serde_json::to_vec(side_effect).context("Failed to serialize side effect")?;
         let encrypted = self.encryption.seal(&payload)?;
 
@@ -1244,6 +3566,8 @@ impl StorageManager {
         )
         .context("Failed to upsert side effect")?;
 
+        self.audit_upsert(conn, AuditEntityType::SideEffect, &side_effect.id, previous, side_effect)?;
+
         Ok(())
     }
 
@@ -1260,35 +3584,18 @@ impl StorageManager {
     /// # Ok::<(), anyhow::Error>(())
     /// ```
     pub fn list_side_effects(&self) -> Result<Vec<SideEffect>> {
-        let conn = self.open_connection()?;
-        let mut stmt = conn
-            .prepare("SELECT payload FROM side_effects ORDER BY date DESC")
-            .context("Failed to prepare side effects query")?;
-
-        let effects = stmt
-            .query_map([], |row| {
-                let blob: Vec<u8> = row.get(0)?;
-                Ok(blob)
-            })?
-            .filter_map(|result| {
-                result.ok().and_then(|blob| {
-                    self.encryption
-                        .open(&blob)
-                        .ok()
-                        .and_then(|decrypted| {
-                            let effect: SideEffect = serde_json::from_slice(&decrypted)
-                                .map_err(|e| {
-                                    tracing::warn!("Failed to deserialize side effect: {}", e);
-                                    e
-                                })
-                                .ok()?;
-                            Some(effect)
-                        })
-                })
-            })
-            .collect();
+        self.list_side_effects_page(&ListOptions::default())
+    }
 
-        Ok(effects)
+    /// Lists side effects with paging, most recent first
+    pub fn list_side_effects_page(&self, options: &ListOptions) -> Result<Vec<SideEffect>> {
+        self.list_page(
+            &SIDE_EFFECTS_QUERY,
+            &[],
+            options,
+            |blob| Ok(self.decode_side_effect_lenient(blob)),
+            |item| item.date,
+        )
     }
 
     /// Get a specific side effect by ID
@@ -1300,7 +3607,7 @@ impl StorageManager {
     /// `Some(SideEffect)` if found, `None` if not found
     pub fn get_side_effect(&self, effect_id: &str) -> Result<Option<SideEffect>> {
         let conn = self.open_connection()?;
-        let mut stmt = conn.prepare("SELECT payload FROM side_effects WHERE id = ?1")?;
+        let mut stmt = conn.prepare_cached("SELECT payload FROM side_effects WHERE id = ?1")?;
 
         let result = stmt.query_row(params![effect_id], |row| {
             let blob: Vec<u8> = row.get(0)?;
@@ -1324,27 +3631,22 @@ impl StorageManager {
     /// # Arguments
     /// * `protocol_id` - The ID of the protocol to filter by
     pub fn list_side_effects_by_protocol(&self, protocol_id: &str) -> Result<Vec<SideEffect>> {
-        let conn = self.open_connection()?;
-        let mut stmt = conn
-            .prepare("SELECT payload FROM side_effects WHERE protocol_id = ?1 ORDER BY date DESC")
-            .context("Failed to prepare side effects by protocol query")?;
+        self.list_side_effects_by_protocol_page(protocol_id, &ListOptions::default())
+    }
 
-        let effects = stmt
-            .query_map(params![protocol_id], |row| {
-                let blob: Vec<u8> = row.get(0)?;
-                Ok(blob)
-            })?
-            .filter_map(|result| {
-                result.ok().and_then(|blob| {
-                    self.encryption
-                        .open(&blob)
-                        .ok()
-                        .and_then(|decrypted| serde_json::from_slice(&decrypted).ok())
-                })
-            })
-            .collect();
-
-        Ok(effects)
+    /// Lists a protocol's side effects with paging, most recent first
+    pub fn list_side_effects_by_protocol_page(
+        &self,
+        protocol_id: &str,
+        options: &ListOptions,
+    ) -> Result<Vec<SideEffect>> {
+        self.list_page(
+            &PROTOCOL_SIDE_EFFECTS_QUERY,
+            &[&protocol_id],
+            options,
+            |blob| Ok(self.decode_side_effect_lenient(blob)),
+            |item| item.date,
+        )
     }
 
     /// Delete a side effect entry
@@ -1354,9 +3656,18 @@ impl StorageManager {
     /// # Arguments
     /// * `effect_id` - The ID of the side effect to delete
     pub fn delete_side_effect(&self, effect_id: &str) -> Result<()> {
-        let conn = self.open_connection()?;
-        conn.execute("DELETE FROM side_effects WHERE id = ?1", params![effect_id])
+        let conn = self.write_connection()?;
+        self.delete_side_effect_on(&conn, effect_id)
+    }
+
+    /// Delete a side effect
+    fn delete_side_effect_on(&self, conn: &Connection, effect_id: &str) -> Result<()> {
+        let deleted = conn
+            .execute("DELETE FROM side_effects WHERE id = ?1", params![effect_id])
             .context("Failed to delete side effect")?;
+        if deleted > 0 {
+            self.audit_delete(conn, AuditEntityType::SideEffect, effect_id)?;
+        }
         Ok(())
     }
 
@@ -1374,14 +3685,17 @@ impl StorageManager {
             return Ok(0);
         }
 
-        let conn = self.open_connection()?;
+        let conn = self.write_connection()?;
         let mut total_deleted = 0;
 
         let tx = conn.unchecked_transaction()?;
         {
-            let mut stmt = tx.prepare("DELETE FROM side_effects WHERE id = ?1")?;
+            let mut stmt = tx.prepare_cached("DELETE FROM side_effects WHERE id = ?1")?;
             for effect_id in effect_ids {
                 let rows = stmt.execute(params![effect_id])?;
+                if rows > 0 {
+                    self.audit_delete(&tx, AuditEntityType::SideEffect, effect_id)?;
+                }
                 total_deleted += rows;
             }
         }
@@ -1407,451 +3721,3476 @@ impl StorageManager {
         Ok(())
     }
 
-    pub fn cache_literature(&self, entry: &LiteratureEntry) -> Result<()> {
-        let conn = self.open_connection()?;
-        let payload = serde_json::to_vec(entry).context("Failed to serialize literature entry")?;
+    // ===== Lab Results Methods =====
+
+    /// Insert or update a lab result
+    ///
+    /// The marker name is kept in plaintext so results can be grouped into
+    /// trends; the value, range and notes are encrypted.
+    pub fn upsert_lab_result(&self, result: &LabResult) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.write_lab_result(&conn, result)
+    }
+
+    /// Insert or replace a lab result with its audit entry
+    fn write_lab_result(&self, conn: &Connection, result: &LabResult) -> Result<()> {
+        let previous = self.stored_payload(conn, "SELECT payload FROM lab_results WHERE id = ?1", &result.id)?;
+        let payload = serde_json::to_vec(result).context("Failed to serialize lab result")?;
         let encrypted = self.encryption.seal(&payload)?;
 
         conn.execute(
             r#"
-            INSERT INTO literature_cache (id, source, payload, indexed_at)
-            VALUES (?1, ?2, ?3, ?4)
+            INSERT INTO lab_results (id, marker, collected_at, payload, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
             ON CONFLICT(id) DO UPDATE SET
-                source = excluded.source,
+                marker = excluded.marker,
+                collected_at = excluded.collected_at,
                 payload = excluded.payload,
-                indexed_at = excluded.indexed_at;
+                updated_at = excluded.updated_at;
             "#,
             params![
-                entry.id,
-                entry.source,
+                result.id,
+                result.marker.trim(),
+                result.collected_at.to_string(),
                 encrypted,
-                entry.indexed_at.to_string()
+                result.created_at.to_string(),
+                result.updated_at.to_string()
             ],
         )
-        .context("Failed to cache literature entry")?;
+        .context("Failed to upsert lab result")?;
+
+        self.audit_upsert(conn, AuditEntityType::LabResult, &result.id, previous, result)?;
+        self.invalidate_stats_on(conn, "lab_results")?;
 
         Ok(())
     }
 
-    /// Lists all cached literature entries
-    ///
-    /// Returns entries ordered by indexed date (most recent first).
-    pub fn list_literature(&self) -> Result<Vec<LiteratureEntry>> {
-        let conn = self.open_connection()?;
-        let mut stmt =
-            conn.prepare("SELECT payload FROM literature_cache ORDER BY indexed_at DESC")?;
-        let mut rows = stmt
-            .query([])
-            .context("Unable to run literature list query")?;
-        let mut entries = Vec::new();
-        while let Some(row) = rows.next()? {
-            let blob: Vec<u8> = row.get(0)?;
-            entries.push(self.decode_literature(&blob)?);
-        }
-        Ok(entries)
+    /// List all lab results, most recently collected first
+    pub fn list_lab_results(&self) -> Result<Vec<LabResult>> {
+        self.list_lab_results_page(&ListOptions::default())
     }
 
-    /// Searches cached literature by title or source
+    /// Lists lab results with paging, most recently collected first
+    pub fn list_lab_results_page(&self, options: &ListOptions) -> Result<Vec<LabResult>> {
+        self.list_page(
+            &LAB_RESULTS_QUERY,
+            &[],
+            options,
+            |blob| self.decode_lab_result(blob).map(Some),
+            |item| item.collected_at,
+        )
+    }
+
+    /// List results for one marker in collection order (oldest first)
     ///
-    /// This performs a case-insensitive search on decrypted entries.
-    /// For large caches, consider adding FTS (Full Text Search) support.
-    pub fn search_literature(&self, query: &str) -> Result<Vec<LiteratureEntry>> {
-        let all_entries = self.list_literature()?;
-        let query_lower = query.to_lowercase();
+    /// Marker names are matched case-insensitively so "igf-1" and "IGF-1"
+    /// form a single trend.
+    pub fn list_lab_results_for_marker(&self, marker: &str) -> Result<Vec<LabResult>> {
+        self.list_lab_results_for_marker_page(marker, &ListOptions::default())
+    }
 
-        Ok(all_entries
-            .into_iter()
-            .filter(|entry| {
-                entry.title.to_lowercase().contains(&query_lower)
-                    || entry.source.to_lowercase().contains(&query_lower)
-                    || entry
-                        .summary
-                        .as_ref()
-                        .map(|s| s.to_lowercase().contains(&query_lower))
-                        .unwrap_or(false)
-            })
-            .collect())
+    /// Lists one marker's results with paging, oldest first
+    pub fn list_lab_results_for_marker_page(
+        &self,
+        marker: &str,
+        options: &ListOptions,
+    ) -> Result<Vec<LabResult>> {
+        self.list_page(
+            &MARKER_LAB_RESULTS_QUERY,
+            &[&marker.trim()],
+            options,
+            |blob| self.decode_lab_result(blob).map(Some),
+            |item| item.collected_at,
+        )
     }
 
-    // Supplier CRUD operations
+    /// List the distinct markers that have results, alphabetically
+    pub fn list_lab_markers(&self) -> Result<Vec<String>> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT marker FROM lab_results GROUP BY marker COLLATE NOCASE ORDER BY marker COLLATE NOCASE",
+        )?;
+        let markers = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .context("Failed to list lab markers")?;
+        Ok(markers)
+    }
 
-    pub fn upsert_supplier(&self, supplier: &Supplier) -> Result<()> {
+    /// Get a specific lab result by ID
+    pub fn get_lab_result(&self, result_id: &str) -> Result<Option<LabResult>> {
         let conn = self.open_connection()?;
-        let payload = serde_json::to_vec(supplier).context("Failed to serialize supplier")?;
+        let blob: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT payload FROM lab_results WHERE id = ?1",
+                params![result_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to fetch lab result")?;
+
+        blob.map(|blob| self.decode_lab_result(&blob)).transpose()
+    }
+
+    /// Delete a lab result
+    pub fn delete_lab_result(&self, result_id: &str) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.delete_lab_result_on(&conn, result_id)
+    }
+
+    /// Delete a lab result
+    fn delete_lab_result_on(&self, conn: &Connection, result_id: &str) -> Result<()> {
+        let deleted = conn
+            .execute("DELETE FROM lab_results WHERE id = ?1", params![result_id])
+            .context("Failed to delete lab result")?;
+
+        if deleted == 0 {
+            return Err(anyhow::anyhow!("Lab result not found"));
+        }
+        self.audit_delete(conn, AuditEntityType::LabResult, result_id)?;
+        self.invalidate_stats_on(conn, "lab_results")?;
+        Ok(())
+    }
+
+    // ===== Journal Methods =====
+
+    /// Insert or update a journal entry
+    pub fn upsert_journal_entry(&self, entry: &JournalEntry) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.write_journal_entry(&conn, entry)
+    }
+
+    /// Insert or replace a journal entry with its audit entry and search document
+    fn write_journal_entry(&self, conn: &Connection, entry: &JournalEntry) -> Result<()> {
+        let previous = self.stored_payload(conn, "SELECT payload FROM journal_entries WHERE id = ?1", &entry.id)?;
+        let payload = serde_json::to_vec(entry).context("Failed to serialize journal entry")?;
         let encrypted = self.encryption.seal(&payload)?;
 
         conn.execute(
             r#"
-            INSERT INTO suppliers (id, name, payload, updated_at)
-            VALUES (?1, ?2, ?3, ?4)
+            INSERT INTO journal_entries (id, entry_date, payload, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
             ON CONFLICT(id) DO UPDATE SET
-                name = excluded.name,
+                entry_date = excluded.entry_date,
                 payload = excluded.payload,
                 updated_at = excluded.updated_at;
             "#,
             params![
-                supplier.id,
-                supplier.name,
+                entry.id,
+                entry.entry_date.to_string(),
                 encrypted,
-                supplier.updated_at.to_string()
+                entry.created_at.to_string(),
+                entry.updated_at.to_string()
             ],
         )
-        .context("Failed to upsert supplier")?;
+        .context("Failed to upsert journal entry")?;
+
+        self.audit_upsert(conn, AuditEntityType::JournalEntry, &entry.id, previous, entry)?;
+        self.index_search_document(
+            conn,
+            SearchEntityType::JournalEntry,
+            &entry.id,
+            &search::journal_document(entry),
+        )?;
 
         Ok(())
     }
 
-    pub fn list_suppliers(&self) -> Result<Vec<Supplier>> {
-        let conn = self.open_connection()?;
-        let mut stmt = conn.prepare("SELECT payload FROM suppliers ORDER BY name ASC")?;
-        let mut rows = stmt
-            .query([])
-            .context("Unable to run supplier list query")?;
-        let mut suppliers = Vec::new();
-        while let Some(row) = rows.next()? {
-            let blob: Vec<u8> = row.get(0)?;
-            suppliers.push(self.decode_supplier(&blob)?);
-        }
-        Ok(suppliers)
+    /// List all journal entries, newest entry date first
+    pub fn list_journal_entries(&self) -> Result<Vec<JournalEntry>> {
+        self.list_journal_entries_page(&ListOptions::default())
     }
 
-    pub fn get_supplier(&self, supplier_id: &str) -> Result<Option<Supplier>> {
+    /// Lists journal entries with paging, newest entry date first
+    pub fn list_journal_entries_page(&self, options: &ListOptions) -> Result<Vec<JournalEntry>> {
+        self.list_page(
+            &JOURNAL_ENTRIES_QUERY,
+            &[],
+            options,
+            |blob| self.decode_journal_entry(blob).map(Some),
+            |item| item.entry_date,
+        )
+    }
+
+    /// Get a specific journal entry by ID
+    pub fn get_journal_entry(&self, entry_id: &str) -> Result<Option<JournalEntry>> {
         let conn = self.open_connection()?;
-        let mut stmt = conn.prepare("SELECT payload FROM suppliers WHERE id = ?1")?;
-        let mut rows = stmt.query(params![supplier_id])?;
+        let blob: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT payload FROM journal_entries WHERE id = ?1",
+                params![entry_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to fetch journal entry")?;
 
-        if let Some(row) = rows.next()? {
-            let blob: Vec<u8> = row.get(0)?;
-            Ok(Some(self.decode_supplier(&blob)?))
-        } else {
-            Ok(None)
-        }
+        blob.map(|blob| self.decode_journal_entry(&blob)).transpose()
     }
 
-    pub fn delete_supplier(&self, supplier_id: &str) -> Result<()> {
-        let conn = self.open_connection()?;
-        conn.execute("DELETE FROM suppliers WHERE id = ?1", params![supplier_id])
-            .context("Failed to delete supplier")?;
+    /// Delete a journal entry
+    pub fn delete_journal_entry(&self, entry_id: &str) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.delete_journal_entry_on(&conn, entry_id)
+    }
+
+    /// Delete a journal entry and its search document
+    fn delete_journal_entry_on(&self, conn: &Connection, entry_id: &str) -> Result<()> {
+        let deleted = conn
+            .execute("DELETE FROM journal_entries WHERE id = ?1", params![entry_id])
+            .context("Failed to delete journal entry")?;
+
+        if deleted == 0 {
+            return Err(anyhow::anyhow!("Journal entry not found"));
+        }
+        self.audit_delete(conn, AuditEntityType::JournalEntry, entry_id)?;
+        self.remove_search_document(conn, SearchEntityType::JournalEntry, entry_id)?;
         Ok(())
     }
 
-    // Inventory CRUD operations
+    /// Journal entries that link to a record, newest entry date first
+    ///
+    /// `keys` are the names the record can be linked by: its ID, plus a
+    /// protocol's name or a paper's DOI. Links are only stored encrypted, so
+    /// this decrypts and parses every entry.
+    pub fn list_journal_backlinks(&self, kind: JournalLinkKind, keys: &[&str]) -> Result<Vec<JournalEntry>> {
+        Ok(self
+            .list_journal_entries()?
+            .into_iter()
+            .filter(|entry| parse_links(&entry.body).iter().any(|link| link.points_to(kind, keys)))
+            .collect())
+    }
 
-    pub fn upsert_inventory_item(&self, item: &InventoryItem) -> Result<()> {
-        let conn = self.open_connection()?;
-        let payload = serde_json::to_vec(item).context("Failed to serialize inventory item")?;
+    // ===== Goal Methods =====
+
+    /// Insert or update a goal
+    pub fn upsert_goal(&self, goal: &Goal) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.write_goal(&conn, goal)
+    }
+
+    /// Insert or replace a goal with its audit entry
+    fn write_goal(&self, conn: &Connection, goal: &Goal) -> Result<()> {
+        let previous = self.stored_payload(conn, "SELECT payload FROM goals WHERE id = ?1", &goal.id)?;
+        let payload = serde_json::to_vec(goal).context("Failed to serialize goal")?;
         let encrypted = self.encryption.seal(&payload)?;
 
         conn.execute(
             r#"
-            INSERT INTO inventory (id, protocol_id, supplier_id, payload, updated_at)
+            INSERT INTO goals (id, start_date, payload, created_at, updated_at)
             VALUES (?1, ?2, ?3, ?4, ?5)
             ON CONFLICT(id) DO UPDATE SET
-                protocol_id = excluded.protocol_id,
-                supplier_id = excluded.supplier_id,
+                start_date = excluded.start_date,
                 payload = excluded.payload,
                 updated_at = excluded.updated_at;
             "#,
             params![
-                item.id,
-                item.protocol_id,
-                item.supplier_id,
+                goal.id,
+                goal.start_date.to_string(),
                 encrypted,
-                item.updated_at.to_string()
+                goal.created_at.to_string(),
+                goal.updated_at.to_string()
             ],
         )
-        .context("Failed to upsert inventory item")?;
+        .context("Failed to upsert goal")?;
 
-        Ok(())
-    }
+        self.audit_upsert(conn, AuditEntityType::Goal, &goal.id, previous, goal)?;
+        self.invalidate_stats_on(conn, "goals")?;
 
-    pub fn list_inventory(&self) -> Result<Vec<InventoryItem>> {
-        let conn = self.open_connection()?;
-        let mut stmt = conn.prepare("SELECT payload FROM inventory ORDER BY updated_at DESC")?;
-        let mut rows = stmt
-            .query([])
-            .context("Unable to run inventory list query")?;
-        let mut items = Vec::new();
-        while let Some(row) = rows.next()? {
-            let blob: Vec<u8> = row.get(0)?;
-            items.push(self.decode_inventory_item(&blob)?);
-        }
-        Ok(items)
+        Ok(())
     }
 
-    pub fn list_inventory_by_protocol(&self, protocol_id: &str) -> Result<Vec<InventoryItem>> {
-        let conn = self.open_connection()?;
-        let mut stmt = conn.prepare(
-            "SELECT payload FROM inventory WHERE protocol_id = ?1 ORDER BY updated_at DESC",
-        )?;
-        let mut rows = stmt
-            .query(params![protocol_id])
-            .context("Unable to run inventory query for protocol")?;
-        let mut items = Vec::new();
-        while let Some(row) = rows.next()? {
-            let blob: Vec<u8> = row.get(0)?;
-            items.push(self.decode_inventory_item(&blob)?);
-        }
-        Ok(items)
+    /// List all goals, most recently started first
+    pub fn list_goals(&self) -> Result<Vec<Goal>> {
+        self.list_goals_page(&ListOptions::default())
     }
 
-    pub fn get_inventory_item(&self, item_id: &str) -> Result<Option<InventoryItem>> {
-        let conn = self.open_connection()?;
-        let mut stmt = conn.prepare("SELECT payload FROM inventory WHERE id = ?1")?;
-        let mut rows = stmt.query(params![item_id])?;
-
-        if let Some(row) = rows.next()? {
-            let blob: Vec<u8> = row.get(0)?;
-            Ok(Some(self.decode_inventory_item(&blob)?))
-        } else {
-            Ok(None)
-        }
+    /// Lists goals with paging, most recently started first
+    pub fn list_goals_page(&self, options: &ListOptions) -> Result<Vec<Goal>> {
+        self.list_page(
+            &GOALS_QUERY,
+            &[],
+            options,
+            |blob| self.decode_goal(blob).map(Some),
+            |item| item.start_date,
+        )
     }
 
-    pub fn delete_inventory_item(&self, item_id: &str) -> Result<()> {
+    /// Get a specific goal by ID
+    pub fn get_goal(&self, goal_id: &str) -> Result<Option<Goal>> {
         let conn = self.open_connection()?;
-        conn.execute("DELETE FROM inventory WHERE id = ?1", params![item_id])
-            .context("Failed to delete inventory item")?;
-        Ok(())
-    }
-
-    // Decode helper functions
+        let blob: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT payload FROM goals WHERE id = ?1",
+                params![goal_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to fetch goal")?;
 
-    fn decode_protocol(&self, blob: &[u8]) -> Result<PeptideProtocol> {
-        let decrypted = self.encryption.open(blob)?;
-        let protocol: PeptideProtocol =
-            serde_json::from_slice(&decrypted).context("Failed to deserialize protocol")?;
-        Ok(protocol)
+        blob.map(|blob| self.decode_goal(&blob)).transpose()
     }
 
-    fn decode_literature(&self, blob: &[u8]) -> Result<LiteratureEntry> {
-        let decrypted = self.encryption.open(blob)?;
-        let entry: LiteratureEntry =
-            serde_json::from_slice(&decrypted).context("Failed to deserialize literature entry")?;
-        Ok(entry)
+    /// Delete a goal
+    pub fn delete_goal(&self, goal_id: &str) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.delete_goal_on(&conn, goal_id)
     }
 
-    fn decode_dose_log(&self, blob: &[u8]) -> Result<DoseLog> {
-        let decrypted = self.encryption.open(blob)?;
-        let log: DoseLog =
-            serde_json::from_slice(&decrypted).context("Failed to deserialize dose log")?;
-        Ok(log)
-    }
+    /// Delete a goal
+    fn delete_goal_on(&self, conn: &Connection, goal_id: &str) -> Result<()> {
+        let deleted = conn
+            .execute("DELETE FROM goals WHERE id = ?1", params![goal_id])
+            .context("Failed to delete goal")?;
 
-    fn decode_supplier(&self, blob: &[u8]) -> Result<Supplier> {
-        let decrypted = self.encryption.open(blob)?;
-        let supplier: Supplier =
-            serde_json::from_slice(&decrypted).context("Failed to deserialize supplier")?;
-        Ok(supplier)
+        if deleted == 0 {
+            return Err(anyhow::anyhow!("Goal not found"));
+        }
+        self.audit_delete(conn, AuditEntityType::Goal, goal_id)?;
+        self.invalidate_stats_on(conn, "goals")?;
+        Ok(())
     }
 
-    fn decode_inventory_item(&self, blob: &[u8]) -> Result<InventoryItem> {
-        let decrypted = self.encryption.open(blob)?;
-        let item: InventoryItem =
-            serde_json::from_slice(&decrypted).context("Failed to deserialize inventory item")?;
-        Ok(item)
+    pub fn cache_literature(&self, entry: &LiteratureEntry) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.write_literature(&conn, entry)
     }
 
-    // Price History CRUD operations
-
-    pub fn add_price_history(&self, entry: &PriceHistory) -> Result<()> {
-        let conn = self.open_connection()?;
-        let payload = serde_json::to_vec(entry).context("Failed to serialize price history")?;
+    /// Insert or replace a literature entry with its audit entry
+    fn write_literature(&self, conn: &Connection, entry: &LiteratureEntry) -> Result<()> {
+        let previous = self.stored_payload(conn, "SELECT payload FROM literature_cache WHERE id = ?1", &entry.id)?;
+        let payload = serde_json::to_vec(entry).context("Failed to serialize literature entry")?;
         let encrypted = self.encryption.seal(&payload)?;
 
         conn.execute(
             r#"
-            INSERT INTO price_history (id, supplier_id, peptide_name, payload, recorded_at)
+            INSERT INTO literature_cache (id, source, payload, indexed_at, enriched_at)
             VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(id) DO UPDATE SET
+                source = excluded.source,
+                payload = excluded.payload,
+                indexed_at = excluded.indexed_at,
+                enriched_at = excluded.enriched_at;
             "#,
             params![
                 entry.id,
-                entry.supplier_id,
-                entry.peptide_name,
+                entry.source,
                 encrypted,
-                entry.recorded_at.to_string()
+                entry.indexed_at.to_string(),
+                entry.enriched_at.map(|at| at.unix_timestamp())
             ],
         )
-        .context("Failed to add price history")?;
+        .context("Failed to cache literature entry")?;
+
+        self.audit_upsert(conn, AuditEntityType::Literature, &entry.id, previous, entry)?;
 
         Ok(())
     }
 
-    pub fn list_price_history_for_supplier(
-        &self,
-        supplier_id: &str,
-        peptide_name: Option<&str>,
-    ) -> Result<Vec<PriceHistory>> {
+    pub fn get_literature(&self, entry_id: &str) -> Result<Option<LiteratureEntry>> {
         let conn = self.open_connection()?;
-
-        let (query, params): (String, Vec<&str>) = if let Some(peptide) = peptide_name {
-            (
-                "SELECT payload FROM price_history WHERE supplier_id = ?1 AND peptide_name = ?2 ORDER BY recorded_at DESC".into(),
-                vec![supplier_id, peptide],
-            )
-        } else {
-            (
-                "SELECT payload FROM price_history WHERE supplier_id = ?1 ORDER BY recorded_at DESC".into(),
-                vec![supplier_id],
+        let blob: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT payload FROM literature_cache WHERE id = ?1",
+                params![entry_id],
+                |row| row.get(0),
             )
-        };
-
-        let mut stmt = conn.prepare(&query)?;
-        let mut rows = stmt
-            .query(rusqlite::params_from_iter(params.iter()))
-            .context("Unable to query price history")?;
+            .optional()
+            .context("Failed to fetch literature entry")?;
 
-        let mut entries = Vec::new();
-        while let Some(row) = rows.next()? {
-            let blob: Vec<u8> = row.get(0)?;
-            entries.push(self.decode_price_history(&blob)?);
-        }
-        Ok(entries)
+        blob.map(|blob| self.decode_literature(&blob)).transpose()
     }
 
-    pub fn get_latest_price(
-        &self,
-        supplier_id: &str,
-        peptide_name: &str,
-    ) -> Result<Option<PriceHistory>> {
-        let conn = self.open_connection()?;
-        let mut stmt = conn.prepare(
-            "SELECT payload FROM price_history WHERE supplier_id = ?1 AND peptide_name = ?2 ORDER BY recorded_at DESC LIMIT 1"
+    /// Remove cached literature outside the retention window, with its
+    /// embeddings
+    ///
+    /// Pinned entries and entries on the reading list are never removed.
+    /// Returns the number of entries removed.
+    pub fn prune_literature_cache(&self, retention: &LiteratureRetention) -> Result<usize> {
+        if retention.max_age_days.is_none() && retention.max_entries.is_none() {
+            return Ok(0);
+        }
+        let cutoff = retention
+            .max_age_days
+            .map(|days| OffsetDateTime::now_utc() - time::Duration::days(days.into()));
+        let expired: Vec<String> = self
+            .list_literature()?
+            .into_iter()
+            .enumerate()
+            .filter(|(_, entry)| !entry.is_kept())
+            .filter(|(position, entry)| {
+                cutoff.is_some_and(|cutoff| entry.indexed_at < cutoff)
+                    || retention.max_entries.is_some_and(|max| *position >= max)
+            })
+            .map(|(_, entry)| entry.id)
+            .collect();
+        if expired.is_empty() {
+            return Ok(0);
+        }
+
+        let mut conn = self.write_connection()?;
+        let tx = conn.transaction()?;
+        for id in &expired {
+            tx.execute("DELETE FROM literature_embeddings WHERE entry_id = ?1", params![id])
+                .context("Failed to prune literature embeddings")?;
+            tx.execute("DELETE FROM literature_cache WHERE id = ?1", params![id])
+                .context("Failed to prune literature cache")?;
+        }
+        tx.commit()?;
+
+        info!("Pruned {} cached literature entries", expired.len());
+        Ok(expired.len())
+    }
+
+    /// Lists all cached literature entries
+    ///
+    /// Returns entries ordered by indexed date (most recent first).
+    pub fn list_literature(&self) -> Result<Vec<LiteratureEntry>> {
+        self.list_literature_page(&ListOptions::default())
+    }
+
+    /// Lists cached literature with paging, most recently indexed first
+    pub fn list_literature_page(&self, options: &ListOptions) -> Result<Vec<LiteratureEntry>> {
+        self.list_page(
+            &LITERATURE_QUERY,
+            &[],
+            options,
+            |blob| self.decode_literature(blob).map(Some),
+            |item| item.indexed_at,
+        )
+    }
+
+    /// Searches cached literature by title or source
+    ///
+    /// This performs a case-insensitive search on decrypted entries.
+    /// For large caches, consider adding FTS (Full Text Search) support.
+    pub fn search_literature(&self, query: &str) -> Result<Vec<LiteratureEntry>> {
+        let all_entries = self.list_literature()?;
+        let query_lower = query.to_lowercase();
+
+        Ok(all_entries
+            .into_iter()
+            .filter(|entry| {
+                entry.title.to_lowercase().contains(&query_lower)
+                    || entry.source.to_lowercase().contains(&query_lower)
+                    || entry
+                        .summary
+                        .as_ref()
+                        .map(|s| s.to_lowercase().contains(&query_lower))
+                        .unwrap_or(false)
+            })
+            .collect())
+    }
+
+    /// Lists cached literature carrying every tag in `tags`, most relevant
+    /// first; unscored entries come last
+    pub fn list_literature_tagged(&self, tags: &[String]) -> Result<Vec<LiteratureEntry>> {
+        let mut entries: Vec<LiteratureEntry> = self
+            .list_literature()?
+            .into_iter()
+            .filter(|entry| tags.iter().all(|tag| entry.tags.contains(tag)))
+            .collect();
+        entries.sort_by(|a, b| {
+            b.relevance_score
+                .unwrap_or(-1.0)
+                .total_cmp(&a.relevance_score.unwrap_or(-1.0))
+        });
+        Ok(entries)
+    }
+
+    /// Every tag used in cached literature with the number of entries
+    /// carrying it, sorted by tag
+    pub fn literature_tag_counts(&self) -> Result<Vec<(String, usize)>> {
+        let mut counts = std::collections::BTreeMap::new();
+        for entry in self.list_literature()? {
+            for tag in entry.tags {
+                *counts.entry(tag).or_insert(0) += 1;
+            }
+        }
+        Ok(counts.into_iter().collect())
+    }
+
+    /// Cached literature missing a DOI, authors, journal or year that
+    /// enrichment hasn't looked up yet, most recently indexed first
+    pub fn list_literature_needing_enrichment(&self, limit: usize) -> Result<Vec<LiteratureEntry>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT payload FROM literature_cache WHERE enriched_at IS NULL ORDER BY indexed_at DESC",
         )?;
-        let mut rows = stmt.query(params![supplier_id, peptide_name])?;
+        let blobs = stmt
+            .query_map([], |row| row.get::<_, Vec<u8>>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut entries = Vec::new();
+        for blob in blobs {
+            let entry = self.decode_literature(&blob)?;
+            if entry.is_missing_metadata() {
+                entries.push(entry);
+                if entries.len() == limit {
+                    break;
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Cached literature that isn't known to be retracted and wasn't checked
+    /// for retractions since `checked_before`, least recently checked first
+    ///
+    /// Only entries with a DOI or URL are returned, as the others can't be
+    /// looked up.
+    pub fn list_literature_for_retraction_check(
+        &self,
+        checked_before: OffsetDateTime,
+        limit: usize,
+    ) -> Result<Vec<LiteratureEntry>> {
+        let mut entries: Vec<LiteratureEntry> = self
+            .list_literature()?
+            .into_iter()
+            .filter(|entry| !entry.retracted && (entry.doi.is_some() || entry.url.is_some()))
+            .filter(|entry| entry.retraction_checked_at.is_none_or(|at| at < checked_before))
+            .collect();
+        entries.sort_by_key(|entry| entry.retraction_checked_at);
+        entries.truncate(limit);
+        Ok(entries)
+    }
+
+    // Literature embeddings
+
+    /// Stores the embedding of a cached paper, replacing the one from the same model
+    pub fn upsert_literature_embedding(&self, embedding: &LiteratureEmbedding) -> Result<()> {
+        let conn = self.write_connection()?;
+        let payload = serde_json::to_vec(embedding).context("Failed to serialize embedding")?;
+        let encrypted = self.encryption.seal(&payload)?;
+
+        conn.execute(
+            r#"
+            INSERT INTO literature_embeddings (entry_id, model, payload)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(entry_id, model) DO UPDATE SET
+                payload = excluded.payload;
+            "#,
+            params![embedding.entry_id, embedding.model, encrypted],
+        )
+        .context("Failed to save literature embedding")?;
+
+        Ok(())
+    }
+
+    /// Embeddings made by `model` of papers still in the literature cache
+    pub fn list_literature_embeddings(&self, model: &str) -> Result<Vec<LiteratureEmbedding>> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT e.payload FROM literature_embeddings e
+             JOIN literature_cache l ON l.id = e.entry_id
+             WHERE e.model = ?1",
+        )?;
+        let blobs = stmt
+            .query_map(params![model], |row| row.get::<_, Vec<u8>>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        blobs
+            .iter()
+            .map(|blob| {
+                let decrypted = self.encryption.open(blob)?;
+                serde_json::from_slice(&decrypted).context("Failed to deserialize embedding")
+            })
+            .collect()
+    }
+
+    // Saved literature searches
+
+    pub fn upsert_saved_search(&self, search: &SavedSearch) -> Result<()> {
+        let conn = self.write_connection()?;
+        let previous = self.stored_payload(&conn, "SELECT payload FROM saved_searches WHERE id = ?1", &search.id)?;
+        let payload = serde_json::to_vec(search).context("Failed to serialize saved search")?;
+        let encrypted = self.encryption.seal(&payload)?;
+
+        conn.execute(
+            r#"
+            INSERT INTO saved_searches (id, payload, created_at)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(id) DO UPDATE SET
+                payload = excluded.payload;
+            "#,
+            params![search.id, encrypted, search.created_at.to_string()],
+        )
+        .context("Failed to save search")?;
+
+        self.audit_upsert(&conn, AuditEntityType::SavedSearch, &search.id, previous, search)?;
+
+        Ok(())
+    }
+
+    /// Lists saved searches, oldest first
+    pub fn list_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare_cached("SELECT payload FROM saved_searches ORDER BY created_at")?;
+        let blobs = stmt
+            .query_map([], |row| row.get::<_, Vec<u8>>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        blobs.iter().map(|blob| self.decode_saved_search(blob)).collect()
+    }
+
+    pub fn get_saved_search(&self, search_id: &str) -> Result<Option<SavedSearch>> {
+        let conn = self.open_connection()?;
+        let blob: Option<Vec<u8>> = conn
+            .prepare_cached("SELECT payload FROM saved_searches WHERE id = ?1")?
+            .query_row(params![search_id], |row| row.get(0))
+            .optional()?;
+        blob.map(|blob| self.decode_saved_search(&blob)).transpose()
+    }
+
+    /// Enabled saved searches whose next run is at or before `now`
+    pub fn due_saved_searches(&self, now: OffsetDateTime) -> Result<Vec<SavedSearch>> {
+        Ok(self
+            .list_saved_searches()?
+            .into_iter()
+            .filter(|search| search.enabled && search.next_run_at() <= now)
+            .collect())
+    }
+
+    pub fn delete_saved_search(&self, search_id: &str) -> Result<()> {
+        let conn = self.write_connection()?;
+        let deleted = conn
+            .execute("DELETE FROM saved_searches WHERE id = ?1", params![search_id])
+            .context("Failed to delete saved search")?;
+        if deleted == 0 {
+            return Err(anyhow::anyhow!("Saved search not found"));
+        }
+        self.audit_delete(&conn, AuditEntityType::SavedSearch, search_id)?;
+        Ok(())
+    }
+
+    // Cached clinical trials
+
+    pub fn cache_clinical_trial(&self, trial: &TrialResult) -> Result<()> {
+        let conn = self.write_connection()?;
+        let previous = self.stored_payload(&conn, "SELECT payload FROM clinical_trials WHERE nct_id = ?1", &trial.nct_id)?;
+        let payload = serde_json::to_vec(trial).context("Failed to serialize clinical trial")?;
+        let encrypted = self.encryption.seal(&payload)?;
+
+        conn.execute(
+            r#"
+            INSERT INTO clinical_trials (nct_id, payload, fetched_at)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(nct_id) DO UPDATE SET
+                payload = excluded.payload,
+                fetched_at = excluded.fetched_at;
+            "#,
+            params![trial.nct_id, encrypted, trial.fetched_at.to_string()],
+        )
+        .context("Failed to cache clinical trial")?;
+
+        self.audit_upsert(&conn, AuditEntityType::ClinicalTrial, &trial.nct_id, previous, trial)?;
+
+        Ok(())
+    }
+
+    pub fn get_clinical_trial(&self, nct_id: &str) -> Result<Option<TrialResult>> {
+        let conn = self.open_connection()?;
+        let blob: Option<Vec<u8>> = conn
+            .prepare_cached("SELECT payload FROM clinical_trials WHERE nct_id = ?1")?
+            .query_row(params![nct_id], |row| row.get(0))
+            .optional()?;
+        blob.map(|blob| self.decode_clinical_trial(&blob)).transpose()
+    }
+
+    /// Cached trials, most recently fetched first; only those linked to
+    /// `protocol_id` when given
+    pub fn list_clinical_trials(&self, protocol_id: Option<&str>) -> Result<Vec<TrialResult>> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare_cached("SELECT payload FROM clinical_trials ORDER BY fetched_at DESC")?;
+        let blobs = stmt
+            .query_map([], |row| row.get::<_, Vec<u8>>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut trials = Vec::with_capacity(blobs.len());
+        for blob in blobs {
+            let trial = self.decode_clinical_trial(&blob)?;
+            if protocol_id.is_none_or(|id| trial.protocol_ids.iter().any(|linked| linked == id)) {
+                trials.push(trial);
+            }
+        }
+        Ok(trials)
+    }
+
+    pub fn delete_clinical_trial(&self, nct_id: &str) -> Result<()> {
+        let conn = self.write_connection()?;
+        let deleted = conn
+            .execute("DELETE FROM clinical_trials WHERE nct_id = ?1", params![nct_id])
+            .context("Failed to delete clinical trial")?;
+        if deleted == 0 {
+            return Err(anyhow::anyhow!("Clinical trial not found"));
+        }
+        self.audit_delete(&conn, AuditEntityType::ClinicalTrial, nct_id)?;
+        Ok(())
+    }
+
+    // Literature response cache
+
+    /// Response cached under `key`, unless it's older than `max_age`
+    ///
+    /// A fresh response counts as a hit in [`response_cache_stats`](Self::response_cache_stats).
+    pub fn cached_response(&self, key: &str, max_age: time::Duration) -> Result<Option<Vec<u8>>> {
+        let key_hash = response_key_hash(key);
+        let row: Option<(Vec<u8>, i64)> = self
+            .open_connection()?
+            .prepare_cached("SELECT payload, fetched_at FROM literature_responses WHERE key_hash = ?1")?
+            .query_row(params![key_hash], |row| Ok((row.get(0)?, row.get(1)?)))
+            .optional()
+            .context("Failed to read literature response cache")?;
+        let Some((blob, fetched_at)) = row else {
+            return Ok(None);
+        };
+        if now_timestamp().unix_timestamp() - fetched_at > max_age.whole_seconds() {
+            return Ok(None);
+        }
+
+        self.write_connection()?
+            .execute("UPDATE literature_responses SET hits = hits + 1 WHERE key_hash = ?1", params![key_hash])
+            .context("Failed to count literature response cache hit")?;
+        Ok(Some(self.encryption.open(&blob)?))
+    }
+
+    /// Cache a response from `source` under `key`, replacing any older one
+    ///
+    /// Responses past the longest allowed TTL are dropped at the same time.
+    pub fn store_response(&self, key: &str, source: &str, payload: &[u8]) -> Result<()> {
+        let encrypted = self.encryption.seal(payload)?;
+        let now = now_timestamp().unix_timestamp();
+        let conn = self.write_connection()?;
+        conn.execute(
+            r#"
+            INSERT INTO literature_responses (key_hash, source, payload, fetched_at, hits)
+            VALUES (?1, ?2, ?3, ?4, 0)
+            ON CONFLICT(key_hash) DO UPDATE SET
+                payload = excluded.payload,
+                fetched_at = excluded.fetched_at;
+            "#,
+            params![response_key_hash(key), source, encrypted, now],
+        )
+        .context("Failed to write literature response cache")?;
+
+        let oldest = now - time::Duration::hours(MAX_RESPONSE_TTL_HOURS as i64).whole_seconds();
+        conn.execute("DELETE FROM literature_responses WHERE fetched_at < ?1", params![oldest])
+            .context("Failed to prune literature response cache")?;
+        Ok(())
+    }
+
+    /// Size and use of the response cache; entries older than `max_age`
+    /// count as expired
+    pub fn response_cache_stats(&self, max_age: time::Duration) -> Result<ResponseCacheStats> {
+        let conn = self.open_connection()?;
+        let cutoff = now_timestamp().unix_timestamp() - max_age.whole_seconds();
+        let mut stats = conn
+            .query_row(
+                r#"
+                SELECT COUNT(*),
+                       COALESCE(SUM(fetched_at < ?1), 0),
+                       COALESCE(SUM(hits), 0),
+                       COALESCE(SUM(LENGTH(payload)), 0),
+                       MIN(fetched_at)
+                FROM literature_responses
+                "#,
+                params![cutoff],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, i64>(3)?,
+                        row.get::<_, Option<i64>>(4)?,
+                    ))
+                },
+            )
+            .context("Failed to read literature response cache stats")
+            .and_then(|(entries, expired, hits, size_bytes, oldest)| {
+                Ok(ResponseCacheStats {
+                    entries: entries as usize,
+                    expired: expired as usize,
+                    hits: hits as u64,
+                    size_bytes: size_bytes as u64,
+                    oldest_fetched_at: oldest.map(OffsetDateTime::from_unix_timestamp).transpose()?,
+                    by_source: Vec::new(),
+                })
+            })?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT source, COUNT(*), SUM(hits) FROM literature_responses GROUP BY source ORDER BY source",
+        )?;
+        stats.by_source = stmt
+            .query_map([], |row| {
+                Ok(SourceCacheStats {
+                    source: row.get(0)?,
+                    entries: row.get::<_, i64>(1)? as usize,
+                    hits: row.get::<_, i64>(2)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(stats)
+    }
+
+    /// Drop every cached response, or only those older than `max_age`;
+    /// returns how many were dropped
+    pub fn clear_response_cache(&self, max_age: Option<time::Duration>) -> Result<usize> {
+        let cutoff = match max_age {
+            Some(max_age) => now_timestamp().unix_timestamp() - max_age.whole_seconds(),
+            None => i64::MAX,
+        };
+        let cleared = self
+            .write_connection()?
+            .execute("DELETE FROM literature_responses WHERE fetched_at < ?1", params![cutoff])
+            .context("Failed to clear literature response cache")?;
+        info!("Cleared {} cached literature responses", cleared);
+        Ok(cleared)
+    }
+
+    // Supplier CRUD operations
+
+    pub fn upsert_supplier(&self, supplier: &Supplier) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.write_supplier(&conn, supplier)
+    }
+
+    /// Insert or replace a supplier with its audit entry and search document
+    fn write_supplier(&self, conn: &Connection, supplier: &Supplier) -> Result<()> {
+        let previous = self.stored_payload(conn, "SELECT payload FROM suppliers WHERE id = ?1", &supplier.id)?;
+        let payload = serde_json::to_vec(supplier).context("Failed to serialize supplier")?;
+        let encrypted = self.encryption.seal(&payload)?;
+
+        conn.execute(
+            r#"
+            INSERT INTO suppliers (id, name, payload, updated_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                payload = excluded.payload,
+                updated_at = excluded.updated_at;
+            "#,
+            params![
+                supplier.id,
+                supplier.name,
+                encrypted,
+                supplier.updated_at.to_string()
+            ],
+        )
+        .context("Failed to upsert supplier")?;
+        self.invalidate_stats_on(conn, "suppliers")?;
+
+        self.audit_upsert(conn, AuditEntityType::Supplier, &supplier.id, previous, supplier)?;
+
+        self.index_search_document(
+            conn,
+            SearchEntityType::Supplier,
+            &supplier.id,
+            &search::supplier_document(supplier),
+        )?;
+        Ok(())
+    }
+
+    pub fn list_suppliers(&self) -> Result<Vec<Supplier>> {
+        self.list_suppliers_page(&ListOptions::default())
+    }
+
+    /// Lists suppliers with paging by name; `since` applies to the last update
+    pub fn list_suppliers_page(&self, options: &ListOptions) -> Result<Vec<Supplier>> {
+        self.list_page(
+            &SUPPLIERS_QUERY,
+            &[],
+            options,
+            |blob| self.decode_supplier(blob).map(Some),
+            |item| item.updated_at,
+        )
+    }
+
+    pub fn get_supplier(&self, supplier_id: &str) -> Result<Option<Supplier>> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare_cached("SELECT payload FROM suppliers WHERE id = ?1")?;
+        let mut rows = stmt.query(params![supplier_id])?;
 
         if let Some(row) = rows.next()? {
             let blob: Vec<u8> = row.get(0)?;
-            Ok(Some(self.decode_price_history(&blob)?))
+            Ok(Some(self.decode_supplier(&blob)?))
         } else {
             Ok(None)
         }
     }
 
-    // Alert CRUD operations
+    /// Delete a supplier, with the same cascade as [`bulk_delete_suppliers`](Self::bulk_delete_suppliers)
+    pub fn delete_supplier(&self, supplier_id: &str) -> Result<()> {
+        self.bulk_delete_suppliers(&[supplier_id.to_string()])
+            .context("Failed to delete supplier")?;
+        Ok(())
+    }
 
-    pub fn create_alert(&self, alert: &Alert) -> Result<()> {
-        let conn = self.open_connection()?;
-        let payload = serde_json::to_vec(alert).context("Failed to serialize alert")?;
+    // Inventory CRUD operations
+
+    pub fn upsert_inventory_item(&self, item: &InventoryItem) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.write_inventory_item(&conn, item)?;
+        self.invalidate_stats_on(&conn, "inventory")
+    }
+
+    /// Insert or replace an inventory item with its audit entry and search
+    /// document
+    fn write_inventory_item(&self, conn: &Connection, item: &InventoryItem) -> Result<()> {
+        let previous = self.stored_payload(conn, "SELECT payload FROM inventory WHERE id = ?1", &item.id)?;
+        let payload = serde_json::to_vec(item).context("Failed to serialize inventory item")?;
         let encrypted = self.encryption.seal(&payload)?;
 
         conn.execute(
             r#"
-            INSERT INTO alerts (id, alert_type, severity, payload, is_read, is_dismissed, created_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            INSERT INTO inventory (id, protocol_id, supplier_id, payload, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(id) DO UPDATE SET
+                protocol_id = excluded.protocol_id,
+                supplier_id = excluded.supplier_id,
+                payload = excluded.payload,
+                updated_at = excluded.updated_at;
             "#,
             params![
-                alert.id,
-                serde_json::to_string(&alert.alert_type)?,
-                serde_json::to_string(&alert.severity)?,
+                item.id,
+                item.protocol_id,
+                item.supplier_id,
                 encrypted,
-                alert.is_read as i32,
-                alert.is_dismissed as i32,
-                alert.created_at.to_string()
+                item.updated_at.to_string()
             ],
         )
-        .context("Failed to create alert")?;
+        .context("Failed to upsert inventory item")?;
+
+        self.audit_upsert(conn, AuditEntityType::InventoryItem, &item.id, previous, item)?;
+
+        self.index_search_document(
+            conn,
+            SearchEntityType::InventoryItem,
+            &item.id,
+            &search::inventory_document(item),
+        )?;
+        Ok(())
+    }
+
+    pub fn list_inventory(&self) -> Result<Vec<InventoryItem>> {
+        self.list_inventory_page(&ListOptions::default())
+    }
+
+    /// Lists inventory with paging, most recently updated first
+    pub fn list_inventory_page(&self, options: &ListOptions) -> Result<Vec<InventoryItem>> {
+        self.list_page(
+            &INVENTORY_QUERY,
+            &[],
+            options,
+            |blob| self.decode_inventory_item(blob).map(Some),
+            |item| item.updated_at,
+        )
+    }
+
+    pub fn list_inventory_by_protocol(&self, protocol_id: &str) -> Result<Vec<InventoryItem>> {
+        self.list_inventory_by_protocol_page(protocol_id, &ListOptions::default())
+    }
+
+    /// Lists a protocol's inventory with paging, most recently updated first
+    pub fn list_inventory_by_protocol_page(
+        &self,
+        protocol_id: &str,
+        options: &ListOptions,
+    ) -> Result<Vec<InventoryItem>> {
+        self.list_page(
+            &PROTOCOL_INVENTORY_QUERY,
+            &[&protocol_id],
+            options,
+            |blob| self.decode_inventory_item(blob).map(Some),
+            |item| item.updated_at,
+        )
+    }
+
+    pub fn get_inventory_item(&self, item_id: &str) -> Result<Option<InventoryItem>> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare_cached("SELECT payload FROM inventory WHERE id = ?1")?;
+        let mut rows = stmt.query(params![item_id])?;
+
+        if let Some(row) = rows.next()? {
+            let blob: Vec<u8> = row.get(0)?;
+            Ok(Some(self.decode_inventory_item(&blob)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn delete_inventory_item(&self, item_id: &str) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.delete_inventory_item_on(&conn, item_id)
+    }
+
+    /// Delete an inventory item with its attachments
+    fn delete_inventory_item_on(&self, conn: &Connection, item_id: &str) -> Result<()> {
+        let deleted = conn
+            .execute("DELETE FROM inventory WHERE id = ?1", params![item_id])
+            .context("Failed to delete inventory item")?;
+        self.invalidate_stats_on(conn, "inventory")?;
+        if deleted > 0 {
+            self.audit_delete(conn, AuditEntityType::InventoryItem, item_id)?;
+        }
+        self.delete_attachments_for(conn, &AttachmentOwner::InventoryItem, item_id)?;
+        self.remove_search_document(conn, SearchEntityType::InventoryItem, item_id)?;
+        Ok(())
+    }
+
+    // Paged list queries
+
+    /// Runs a [`PagedQuery`] and decodes one page of payloads
+    ///
+    /// Timestamps are stored as text that only sorts correctly down to the
+    /// day, so `since` narrows rows by day in SQL and exactly after decoding;
+    /// paging then happens here rather than in SQL. `decode` returns `None`
+    /// for rows that should be skipped.
+    fn list_page<T>(
+        &self,
+        query: &PagedQuery,
+        args: &[&dyn ToSql],
+        options: &ListOptions,
+        decode: impl Fn(&[u8]) -> Result<Option<T>>,
+        timestamp: impl Fn(&T) -> OffsetDateTime,
+    ) -> Result<Vec<T>> {
+        let conn = self.open_connection()?;
+        let next = args.len() + 1;
+        let sql = format!(
+            "{} AND substr({}, 1, 10) >= ?{} ORDER BY {} LIMIT ?{} OFFSET ?{}",
+            query.select,
+            query.time_column,
+            next,
+            query.order_by,
+            next + 1,
+            next + 2
+        );
+        let mut stmt = conn.prepare_cached(&sql)?;
+
+        // A negative LIMIT means no limit in SQLite
+        let (since_day, sql_limit, sql_offset) = match options.since {
+            // Stored times keep their own offset, so a day of margin covers
+            // every record that can be at or after `since`
+            Some(since) => {
+                let day = since.to_offset(time::UtcOffset::UTC).date() - time::Duration::days(1);
+                (day.to_string(), -1, 0)
+            }
+            None => (
+                String::new(),
+                options.limit.map_or(-1, |limit| limit as i64),
+                options.offset as i64,
+            ),
+        };
+        let mut params = args.to_vec();
+        params.extend([&since_day as &dyn ToSql, &sql_limit, &sql_offset]);
+
+        let mut rows = stmt
+            .query(params.as_slice())
+            .with_context(|| format!("Unable to run list query: {}", query.select))?;
+        let mut items = Vec::new();
+        let mut skipped = 0;
+        while let Some(row) = rows.next()? {
+            if options.limit.is_some_and(|limit| items.len() >= limit) {
+                break;
+            }
+            let blob: Vec<u8> = row.get(0)?;
+            let Some(item) = decode(&blob)? else {
+                continue;
+            };
+            if let Some(since) = options.since {
+                if timestamp(&item) < since {
+                    continue;
+                }
+                if skipped < options.offset {
+                    skipped += 1;
+                    continue;
+                }
+            }
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    // Decode helper functions
+
+    fn decode_protocol(&self, blob: &[u8]) -> Result<PeptideProtocol> {
+        let decrypted = self.encryption.open(blob)?;
+        let protocol: PeptideProtocol =
+            serde_json::from_slice(&decrypted).context("Failed to deserialize protocol")?;
+        Ok(protocol)
+    }
+
+    fn decode_body_metric(&self, blob: &[u8]) -> Result<BodyMetric> {
+        let decrypted = self.encryption.open(blob)?;
+        let metric: BodyMetric =
+            serde_json::from_slice(&decrypted).context("Failed to deserialize body metric")?;
+        Ok(metric)
+    }
+
+    /// Side effect lists skip entries that can't be read instead of failing
+    fn decode_side_effect_lenient(&self, blob: &[u8]) -> Option<SideEffect> {
+        let decoded = self.encryption.open(blob).and_then(|decrypted| {
+            serde_json::from_slice(&decrypted).context("Failed to deserialize side effect")
+        });
+        decoded
+            .map_err(|e| tracing::warn!("Skipping unreadable side effect: {:#}", e))
+            .ok()
+    }
+
+    fn decode_literature(&self, blob: &[u8]) -> Result<LiteratureEntry> {
+        let decrypted = self.encryption.open(blob)?;
+        let entry: LiteratureEntry =
+            serde_json::from_slice(&decrypted).context("Failed to deserialize literature entry")?;
+        Ok(entry)
+    }
+
+    fn decode_clinical_trial(&self, blob: &[u8]) -> Result<TrialResult> {
+        let decrypted = self.encryption.open(blob)?;
+        serde_json::from_slice(&decrypted).context("Failed to deserialize clinical trial")
+    }
+
+    fn decode_saved_search(&self, blob: &[u8]) -> Result<SavedSearch> {
+        let decrypted = self.encryption.open(blob)?;
+        serde_json::from_slice(&decrypted).context("Failed to deserialize saved search")
+    }
+
+    fn decode_dose_log(&self, blob: &[u8]) -> Result<DoseLog> {
+        let decrypted = self.encryption.open(blob)?;
+        let log: DoseLog =
+            serde_json::from_slice(&decrypted).context("Failed to deserialize dose log")?;
+        Ok(log)
+    }
+
+    fn decode_lab_result(&self, blob: &[u8]) -> Result<LabResult> {
+        let decrypted = self.encryption.open(blob)?;
+        let result: LabResult =
+            serde_json::from_slice(&decrypted).context("Failed to deserialize lab result")?;
+        Ok(result)
+    }
+
+    fn decode_journal_entry(&self, blob: &[u8]) -> Result<JournalEntry> {
+        let decrypted = self.encryption.open(blob)?;
+        let entry: JournalEntry =
+            serde_json::from_slice(&decrypted).context("Failed to deserialize journal entry")?;
+        Ok(entry)
+    }
+
+    fn decode_goal(&self, blob: &[u8]) -> Result<Goal> {
+        let decrypted = self.encryption.open(blob)?;
+        let goal: Goal = serde_json::from_slice(&decrypted).context("Failed to deserialize goal")?;
+        Ok(goal)
+    }
+
+    fn decode_supplier(&self, blob: &[u8]) -> Result<Supplier> {
+        let decrypted = self.encryption.open(blob)?;
+        let supplier: Supplier =
+            serde_json::from_slice(&decrypted).context("Failed to deserialize supplier")?;
+        Ok(supplier)
+    }
+
+    fn decode_inventory_item(&self, blob: &[u8]) -> Result<InventoryItem> {
+        let decrypted = self.encryption.open(blob)?;
+        let item: InventoryItem =
+            serde_json::from_slice(&decrypted).context("Failed to deserialize inventory item")?;
+        Ok(item)
+    }
+
+    // Price History CRUD operations
+
+    pub fn add_price_history(&self, entry: &PriceHistory) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.insert_price_history(&conn, entry)
+    }
+
+    fn insert_price_history(&self, conn: &Connection, entry: &PriceHistory) -> Result<()> {
+        let payload = serde_json::to_vec(entry).context("Failed to serialize price history")?;
+        let encrypted = self.encryption.seal(&payload)?;
+
+        conn.execute(
+            r#"
+            INSERT INTO price_history (id, supplier_id, peptide_name, payload, recorded_at, cost_per_mg, currency)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            params![
+                entry.id,
+                entry.supplier_id,
+                entry.peptide_name,
+                encrypted,
+                entry.recorded_at.to_string(),
+                entry.cost_per_mg,
+                entry.currency
+            ],
+        )
+        .context("Failed to add price history")?;
+        self.invalidate_stats_on(conn, "price_history")?;
+
+        self.record_audit(
+            conn,
+            &AuditEntry::new(AuditEntityType::PriceHistory, &entry.id, AuditOperation::Create, Vec::new()),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn list_price_history_for_supplier(
+        &self,
+        supplier_id: &str,
+        peptide_name: Option<&str>,
+    ) -> Result<Vec<PriceHistory>> {
+        let conn = self.open_connection()?;
+
+        let (query, params): (String, Vec<&str>) = if let Some(peptide) = peptide_name {
+            (
+                "SELECT payload FROM price_history WHERE supplier_id = ?1 AND peptide_name = ?2 ORDER BY recorded_at DESC".into(),
+                vec![supplier_id, peptide],
+            )
+        } else {
+            (
+                "SELECT payload FROM price_history WHERE supplier_id = ?1 ORDER BY recorded_at DESC".into(),
+                vec![supplier_id],
+            )
+        };
+
+        let mut stmt = conn.prepare(&query)?;
+        let mut rows = stmt
+            .query(rusqlite::params_from_iter(params.iter()))
+            .context("Unable to query price history")?;
+
+        let mut entries = Vec::new();
+        while let Some(row) = rows.next()? {
+            let blob: Vec<u8> = row.get(0)?;
+            entries.push(self.decode_price_history(&blob)?);
+        }
+        Ok(entries)
+    }
+
+    pub fn get_latest_price(
+        &self,
+        supplier_id: &str,
+        peptide_name: &str,
+    ) -> Result<Option<PriceHistory>> {
+        let conn = self.open_connection()?;
+        self.latest_price_on(&conn, supplier_id, peptide_name)
+    }
+
+    fn latest_price_on(&self, conn: &Connection, supplier_id: &str, peptide_name: &str) -> Result<Option<PriceHistory>> {
+        let mut stmt = conn.prepare_cached(
+            "SELECT payload FROM price_history WHERE supplier_id = ?1 AND peptide_name = ?2 ORDER BY recorded_at DESC LIMIT 1"
+        )?;
+        let mut rows = stmt.query(params![supplier_id, peptide_name])?;
+
+        if let Some(row) = rows.next()? {
+            let blob: Vec<u8> = row.get(0)?;
+            Ok(Some(self.decode_price_history(&blob)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Price observations awaiting review
+
+    /// Queue a scraped price for review
+    ///
+    /// A pending observation of the same supplier and peptide is replaced,
+    /// so the queue only holds the latest scrape of each product. Reviewed
+    /// observations are kept for [`REVIEWED_OBSERVATION_DAYS`].
+    pub fn add_price_observation(&self, observation: &PriceObservation) -> Result<()> {
+        let conn = self.write_connection()?;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM price_observations WHERE supplier_id = ?1 AND peptide_name = ?2 AND status = ?3",
+            params![observation.supplier_id, observation.peptide_name, ObservationStatus::Pending.as_str()],
+        )?;
+        let cutoff = OffsetDateTime::now_utc() - time::Duration::days(REVIEWED_OBSERVATION_DAYS);
+        tx.execute(
+            "DELETE FROM price_observations WHERE status != ?1 AND observed_at < ?2",
+            params![ObservationStatus::Pending.as_str(), cutoff.to_string()],
+        )?;
+        self.save_price_observation(&tx, observation)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn save_price_observation(&self, conn: &Connection, observation: &PriceObservation) -> Result<()> {
+        let payload = serde_json::to_vec(observation).context("Failed to serialize price observation")?;
+        let encrypted = self.encryption.seal(&payload)?;
+        conn.execute(
+            r#"
+            INSERT INTO price_observations (id, supplier_id, peptide_name, status, payload, observed_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT(id) DO UPDATE SET
+                status = excluded.status,
+                payload = excluded.payload;
+            "#,
+            params![
+                observation.id,
+                observation.supplier_id,
+                observation.peptide_name,
+                observation.status.as_str(),
+                encrypted,
+                observation.observed_at.to_string()
+            ],
+        )
+        .context("Failed to save price observation")?;
+        Ok(())
+    }
+
+    pub fn get_price_observation(&self, observation_id: &str) -> Result<Option<PriceObservation>> {
+        let conn = self.open_connection()?;
+        self.price_observation_on(&conn, observation_id)
+    }
+
+    fn price_observation_on(&self, conn: &Connection, observation_id: &str) -> Result<Option<PriceObservation>> {
+        let blob: Option<Vec<u8>> = conn
+            .prepare_cached("SELECT payload FROM price_observations WHERE id = ?1")?
+            .query_row(params![observation_id], |row| row.get(0))
+            .optional()?;
+        blob.map(|blob| self.decode_price_observation(&blob)).transpose()
+    }
+
+    /// Observations newest first, only those with `status` when given
+    pub fn list_price_observations(&self, status: Option<ObservationStatus>) -> Result<Vec<PriceObservation>> {
+        let conn = self.open_connection()?;
+        let blobs = match status {
+            Some(status) => conn
+                .prepare_cached("SELECT payload FROM price_observations WHERE status = ?1 ORDER BY observed_at DESC")?
+                .query_map(params![status.as_str()], |row| row.get::<_, Vec<u8>>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?,
+            None => conn
+                .prepare_cached("SELECT payload FROM price_observations ORDER BY observed_at DESC")?
+                .query_map([], |row| row.get::<_, Vec<u8>>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?,
+        };
+        blobs.iter().map(|blob| self.decode_price_observation(blob)).collect()
+    }
+
+    /// Record a pending observation as price history
+    ///
+    /// `match_index` picks which of the prices found on the page is right,
+    /// the lowest by default. An observation with no prices only updates
+    /// stock status, so the last known price is carried over. Vial size,
+    /// shipping and order terms come from the last known price too, as
+    /// pages don't list them.
+    pub fn accept_price_observation(&self, observation_id: &str, match_index: Option<usize>) -> Result<PriceHistory> {
+        let conn = self.write_connection()?;
+        let tx = conn.unchecked_transaction()?;
+        let mut observation = self
+            .price_observation_on(&tx, observation_id)?
+            .ok_or_else(|| anyhow::anyhow!("Price observation not found"))?;
+        anyhow::ensure!(
+            observation.status == ObservationStatus::Pending,
+            "Price observation was already reviewed"
+        );
+
+        // Copying the last price forward would record a price nobody saw
+        anyhow::ensure!(!observation.matches.is_empty(), "No price was found on the page");
+        let previous = self.latest_price_on(&tx, &observation.supplier_id, &observation.peptide_name)?;
+        let index = match_index.unwrap_or(0);
+        let cost_per_mg = observation
+            .matches
+            .get(index)
+            .map(|found| found.cost_per_mg)
+            .ok_or_else(|| anyhow::anyhow!("No price {} on the page", index + 1))?;
+
+        let mut entry = PriceHistory::new(observation.supplier_id.as_str(), observation.peptide_name.as_str(), cost_per_mg);
+        entry.currency = observation.currency.clone();
+        entry.url = Some(observation.url.clone());
+        entry.in_stock = observation.in_stock;
+        entry.notes = Some("Recorded by price monitor".to_string());
+        entry.recorded_at = observation.observed_at;
+        if let Some(previous) = &previous {
+            entry.vial_size_mg = previous.vial_size_mg;
+            entry.shipping_cost = previous.shipping_cost;
+            entry.min_order_vials = previous.min_order_vials;
+            entry.bulk_discounts = previous.bulk_discounts.clone();
+        }
+        self.insert_price_history(&tx, &entry)?;
+
+        observation.status = ObservationStatus::Accepted;
+        observation.reviewed_at = Some(OffsetDateTime::now_utc());
+        observation.price_history_id = Some(entry.id.clone());
+        self.save_price_observation(&tx, &observation)?;
+        tx.commit()?;
+
+        Ok(entry)
+    }
+
+    /// Mark a pending observation as wrong; it never becomes price history
+    pub fn reject_price_observation(&self, observation_id: &str) -> Result<PriceObservation> {
+        let conn = self.write_connection()?;
+        let mut observation = self
+            .price_observation_on(&conn, observation_id)?
+            .ok_or_else(|| anyhow::anyhow!("Price observation not found"))?;
+        anyhow::ensure!(
+            observation.status == ObservationStatus::Pending,
+            "Price observation was already reviewed"
+        );
+        observation.status = ObservationStatus::Rejected;
+        observation.reviewed_at = Some(OffsetDateTime::now_utc());
+        self.save_price_observation(&conn, &observation)?;
+        Ok(observation)
+    }
+
+    /// Lowest, highest and average price per bucket of days, per supplier,
+    /// peptide and currency
+    ///
+    /// Buckets are sized so no series has more than `filter.max_points`, and
+    /// buckets without prices are left out. Computed in SQL from the price
+    /// columns, without decrypting the entries.
+    pub fn price_trend(&self, filter: &PriceTrendFilter) -> Result<PriceTrend> {
+        let mut conditions = vec!["cost_per_mg IS NOT NULL".to_string()];
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+
+        if let Some(supplier_id) = &filter.supplier_id {
+            values.push(supplier_id.clone().into());
+            conditions.push(format!("supplier_id = ?{}", values.len()));
+        }
+        if let Some(peptide_name) = &filter.peptide_name {
+            values.push(peptide_name.clone().into());
+            conditions.push(format!("peptide_name = ?{}", values.len()));
+        }
+        // recorded_at starts with the ISO date, so whole days compare as text
+        if let Some(since) = filter.since {
+            values.push(since.to_string().into());
+            conditions.push(format!("substr(recorded_at, 1, 10) >= ?{}", values.len()));
+        }
+        if let Some(until) = filter.until {
+            values.push(until.to_string().into());
+            conditions.push(format!("substr(recorded_at, 1, 10) <= ?{}", values.len()));
+        }
+        let conditions = conditions.join(" AND ");
+
+        let conn = self.open_connection()?;
+        let range: (Option<String>, Option<String>) = conn
+            .prepare_cached(&format!(
+                "SELECT MIN(substr(recorded_at, 1, 10)), MAX(substr(recorded_at, 1, 10)) FROM price_history WHERE {}",
+                conditions
+            ))?
+            .query_row(rusqlite::params_from_iter(values.iter()), |row| Ok((row.get(0)?, row.get(1)?)))
+            .context("Unable to query price range")?;
+        let (Some(first), Some(last)) = range else {
+            return Ok(PriceTrend { bucket_days: 1, series: Vec::new() });
+        };
+        let date_format = format_description!("[year]-[month]-[day]");
+        let first = Date::parse(&first, date_format).with_context(|| format!("Invalid price date {}", first))?;
+        let last = Date::parse(&last, date_format).with_context(|| format!("Invalid price date {}", last))?;
+        let bucket_days = price_trend::bucket_days(first, last, filter.max_points);
+
+        values.push(first.to_string().into());
+        let first_param = values.len();
+        values.push(i64::from(bucket_days).into());
+        let days_param = values.len();
+        let query = format!(
+            "SELECT supplier_id, peptide_name, COALESCE(currency, 'USD'),
+                    CAST(julianday(substr(recorded_at, 1, 10)) - julianday(?{first}) AS INTEGER) / ?{days} AS bucket,
+                    MIN(cost_per_mg), MAX(cost_per_mg), AVG(cost_per_mg), COUNT(*)
+             FROM price_history WHERE {conditions}
+             GROUP BY 1, 2, 3, 4 ORDER BY 1, 2, 3, 4",
+            first = first_param,
+            days = days_param,
+            conditions = conditions,
+        );
+        let rows = conn
+            .prepare_cached(&query)?
+            .query_map(rusqlite::params_from_iter(values), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                    PriceTrendPoint {
+                        start: String::new(),
+                        min_cost_per_mg: row.get(4)?,
+                        max_cost_per_mg: row.get(5)?,
+                        avg_cost_per_mg: row.get(6)?,
+                        entries: row.get(7)?,
+                    },
+                ))
+            })
+            .context("Unable to aggregate price history")?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut series: Vec<PriceTrendSeries> = Vec::new();
+        for (supplier_id, peptide_name, currency, bucket, mut point) in rows {
+            point.start = (first + time::Duration::days(bucket * i64::from(bucket_days))).to_string();
+            match series.last_mut() {
+                Some(last)
+                    if last.supplier_id == supplier_id
+                        && last.peptide_name == peptide_name
+                        && last.currency == currency =>
+                {
+                    last.points.push(point)
+                }
+                _ => series.push(PriceTrendSeries {
+                    supplier_id,
+                    peptide_name,
+                    currency,
+                    points: vec![point],
+                }),
+            }
+        }
+        Ok(PriceTrend { bucket_days, series })
+    }
+
+    // Exchange rate operations
+
+    pub fn upsert_exchange_rate(&self, rate: &ExchangeRate) -> Result<()> {
+        let conn = self.write_connection()?;
+        let previous = self.stored_payload(&conn, "SELECT payload FROM exchange_rates WHERE currency = ?1", &rate.currency)?;
+        let payload = serde_json::to_vec(rate).context("Failed to serialize exchange rate")?;
+        let encrypted = self.encryption.seal(&payload)?;
+
+        conn.execute(
+            r#"
+            INSERT INTO exchange_rates (currency, payload, updated_at)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(currency) DO UPDATE SET
+                payload = excluded.payload,
+                updated_at = excluded.updated_at;
+            "#,
+            params![rate.currency, encrypted, rate.updated_at.to_string()],
+        )
+        .context("Failed to upsert exchange rate")?;
+        self.invalidate_stats_on(&conn, "exchange_rates")?;
+
+        self.audit_upsert(&conn, AuditEntityType::ExchangeRate, &rate.currency, previous, rate)?;
+
+        Ok(())
+    }
+
+    pub fn list_exchange_rates(&self) -> Result<Vec<ExchangeRate>> {
+        self.list_exchange_rates_page(&ListOptions::default())
+    }
+
+    /// Lists exchange rates with paging by currency; `since` applies to the last update
+    pub fn list_exchange_rates_page(&self, options: &ListOptions) -> Result<Vec<ExchangeRate>> {
+        self.list_page(
+            &EXCHANGE_RATES_QUERY,
+            &[],
+            options,
+            |blob| self.decode_exchange_rate(blob).map(Some),
+            |item| item.updated_at,
+        )
+    }
+
+    pub fn delete_exchange_rate(&self, currency: &str) -> Result<()> {
+        let conn = self.write_connection()?;
+        let affected = conn
+            .execute("DELETE FROM exchange_rates WHERE currency = ?1", [currency])
+            .context("Failed to delete exchange rate")?;
+        self.invalidate_stats_on(&conn, "exchange_rates")?;
+
+        if affected == 0 {
+            return Err(anyhow::anyhow!("Exchange rate not found"));
+        }
+        self.audit_delete(&conn, AuditEntityType::ExchangeRate, currency)?;
+
+        Ok(())
+    }
+
+    // Order CRUD operations
+
+    pub fn upsert_order(&self, order: &Order) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.write_order(&conn, order)
+    }
+
+    /// Insert or replace an order with its audit entry
+    fn write_order(&self, conn: &Connection, order: &Order) -> Result<()> {
+        let previous = self.stored_payload(conn, "SELECT payload FROM orders WHERE id = ?1", &order.id)?;
+        let payload = serde_json::to_vec(order).context("Failed to serialize order")?;
+        let encrypted = self.encryption.seal(&payload)?;
+
+        conn.execute(
+            r#"
+            INSERT INTO orders (id, supplier_id, status, payload, order_date, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT(id) DO UPDATE SET
+                supplier_id = excluded.supplier_id,
+                status = excluded.status,
+                payload = excluded.payload,
+                order_date = excluded.order_date,
+                updated_at = excluded.updated_at;
+            "#,
+            params![
+                order.id,
+                order.supplier_id,
+                serde_json::to_string(&order.status)?,
+                encrypted,
+                order.order_date.to_string(),
+                order.updated_at.to_string()
+            ],
+        )
+        .context("Failed to upsert order")?;
+        self.invalidate_stats_on(conn, "orders")?;
+
+        self.audit_upsert(conn, AuditEntityType::Order, &order.id, previous, order)?;
+
+        Ok(())
+    }
+
+    pub fn list_orders(&self) -> Result<Vec<Order>> {
+        self.list_orders_page(&ListOptions::default())
+    }
+
+    /// Lists orders with paging, most recent order date first
+    pub fn list_orders_page(&self, options: &ListOptions) -> Result<Vec<Order>> {
+        self.list_page(
+            &ORDERS_QUERY,
+            &[],
+            options,
+            |blob| self.decode_order(blob).map(Some),
+            |item| item.order_date,
+        )
+    }
+
+    pub fn get_order(&self, order_id: &str) -> Result<Option<Order>> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare_cached("SELECT payload FROM orders WHERE id = ?1")?;
+        let mut rows = stmt.query(params![order_id])?;
+
+        if let Some(row) = rows.next()? {
+            let blob: Vec<u8> = row.get(0)?;
+            Ok(Some(self.decode_order(&blob)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn delete_order(&self, order_id: &str) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.delete_order_on(&conn, order_id)
+    }
+
+    /// Delete an order
+    fn delete_order_on(&self, conn: &Connection, order_id: &str) -> Result<()> {
+        let affected = conn
+            .execute("DELETE FROM orders WHERE id = ?1", params![order_id])
+            .context("Failed to delete order")?;
+        self.invalidate_stats_on(conn, "orders")?;
+
+        if affected == 0 {
+            return Err(anyhow::anyhow!("Order not found"));
+        }
+        self.audit_delete(conn, AuditEntityType::Order, order_id)?;
+
+        Ok(())
+    }
+
+    // Attachment operations
+
+    /// Store an attachment; the metadata, file bytes and thumbnail are all encrypted
+    ///
+    /// Re-adding an attachment with the same ID replaces it, so restoring a
+    /// backup twice does not fail.
+    pub fn add_attachment(&self, attachment: &Attachment, data: &[u8], thumbnail: Option<&[u8]>) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.write_attachment(&conn, attachment, data, thumbnail)
+    }
+
+    /// Insert or replace an attachment with its audit entry
+    fn write_attachment(&self, conn: &Connection, attachment: &Attachment, data: &[u8], thumbnail: Option<&[u8]>) -> Result<()> {
+        let previous = self.stored_payload(conn, "SELECT payload FROM attachments WHERE id = ?1", &attachment.id)?;
+        let payload = serde_json::to_vec(attachment).context("Failed to serialize attachment")?;
+        let encrypted_payload = self.encryption.seal(&payload)?;
+        let encrypted_data = self.encryption.seal(data)?;
+        let encrypted_thumbnail = thumbnail
+            .map(|bytes| self.encryption.seal(bytes))
+            .transpose()?;
+
+        conn.execute(
+            r#"
+            INSERT INTO attachments (id, owner_type, owner_id, payload, data, thumbnail, size_bytes, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT(id) DO UPDATE SET
+                owner_type = excluded.owner_type,
+                owner_id = excluded.owner_id,
+                payload = excluded.payload,
+                data = excluded.data,
+                thumbnail = excluded.thumbnail,
+                size_bytes = excluded.size_bytes;
+            "#,
+            params![
+                attachment.id,
+                serde_json::to_string(&attachment.owner_type)?,
+                attachment.owner_id,
+                encrypted_payload,
+                encrypted_data,
+                encrypted_thumbnail,
+                data.len() as i64,
+                attachment.created_at.to_string()
+            ],
+        )
+        .context("Failed to add attachment")?;
+
+        self.audit_upsert(conn, AuditEntityType::Attachment, &attachment.id, previous, attachment)?;
+
+        Ok(())
+    }
+
+    /// List metadata for every attachment, newest first
+    pub fn list_all_attachments(&self) -> Result<Vec<Attachment>> {
+        self.list_all_attachments_page(&ListOptions::default())
+    }
+
+    /// Lists attachment metadata with paging, newest first
+    pub fn list_all_attachments_page(&self, options: &ListOptions) -> Result<Vec<Attachment>> {
+        self.list_page(
+            &ATTACHMENTS_QUERY,
+            &[],
+            options,
+            |blob| self.decode_attachment(blob).map(Some),
+            |item| item.created_at,
+        )
+    }
+
+    /// List attachment metadata for a record, newest first, without loading file bytes
+    pub fn list_attachments(&self, owner_type: &AttachmentOwner, owner_id: &str) -> Result<Vec<Attachment>> {
+        self.list_attachments_page(owner_type, owner_id, &ListOptions::default())
+    }
+
+    /// Lists a record's attachment metadata with paging, newest first
+    pub fn list_attachments_page(
+        &self,
+        owner_type: &AttachmentOwner,
+        owner_id: &str,
+        options: &ListOptions,
+    ) -> Result<Vec<Attachment>> {
+        let owner_type = serde_json::to_string(owner_type)?;
+        self.list_page(
+            &OWNER_ATTACHMENTS_QUERY,
+            &[&owner_type, &owner_id],
+            options,
+            |blob| self.decode_attachment(blob).map(Some),
+            |item| item.created_at,
+        )
+    }
+
+    pub fn get_attachment(&self, attachment_id: &str) -> Result<Option<Attachment>> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare_cached("SELECT payload FROM attachments WHERE id = ?1")?;
+        let mut rows = stmt.query(params![attachment_id])?;
+
+        if let Some(row) = rows.next()? {
+            let blob: Vec<u8> = row.get(0)?;
+            Ok(Some(self.decode_attachment(&blob)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Load and decrypt the file bytes of an attachment
+    pub fn get_attachment_data(&self, attachment_id: &str) -> Result<Option<Vec<u8>>> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare_cached("SELECT data FROM attachments WHERE id = ?1")?;
+        let mut rows = stmt.query(params![attachment_id])?;
+
+        if let Some(row) = rows.next()? {
+            let blob: Vec<u8> = row.get(0)?;
+            let data = self
+                .encryption
+                .open(&blob)
+                .context("Failed to decrypt attachment data")?;
+            Ok(Some(data))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Load and decrypt an attachment's thumbnail, if one was generated
+    pub fn get_attachment_thumbnail(&self, attachment_id: &str) -> Result<Option<Vec<u8>>> {
+        let conn = self.open_connection()?;
+        let blob: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT thumbnail FROM attachments WHERE id = ?1",
+                params![attachment_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+
+        blob.map(|blob| {
+            self.encryption
+                .open(&blob)
+                .context("Failed to decrypt attachment thumbnail")
+        })
+        .transpose()
+    }
+
+    pub fn delete_attachment(&self, attachment_id: &str) -> Result<()> {
+        let conn = self.write_connection()?;
+        let affected = conn
+            .execute("DELETE FROM attachments WHERE id = ?1", params![attachment_id])
+            .context("Failed to delete attachment")?;
+
+        if affected == 0 {
+            return Err(anyhow::anyhow!("Attachment not found"));
+        }
+        self.audit_delete(&conn, AuditEntityType::Attachment, attachment_id)?;
+
+        Ok(())
+    }
+
+    /// Remove every attachment of a deleted record
+    fn delete_attachments_for(&self, conn: &Connection, owner_type: &AttachmentOwner, owner_id: &str) -> Result<()> {
+        let owner_type = serde_json::to_string(owner_type)?;
+        let ids = query_ids(
+            conn,
+            "SELECT id FROM attachments WHERE owner_type = ?1 AND owner_id = ?2",
+            params![owner_type, owner_id],
+        )?;
+
+        conn.execute(
+            "DELETE FROM attachments WHERE owner_type = ?1 AND owner_id = ?2",
+            params![owner_type, owner_id],
+        )
+        .context("Failed to delete attachments")?;
+
+        for id in ids {
+            self.record_audit(
+                conn,
+                &AuditEntry::new(AuditEntityType::Attachment, id, AuditOperation::Delete, Vec::new())
+                    .with_summary("Deleted with its record"),
+            )?;
+        }
+        Ok(())
+    }
+
+    // Alert CRUD operations
+
+    pub fn create_alert(&self, alert: &Alert) -> Result<()> {
+        let conn = self.write_connection()?;
+        let payload = serde_json::to_vec(alert).context("Failed to serialize alert")?;
+        let encrypted = self.encryption.seal(&payload)?;
+
+        conn.execute(
+            r#"
+            INSERT INTO alerts (id, alert_type, severity, payload, is_read, is_dismissed, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            params![
+                alert.id,
+                serde_json::to_string(&alert.alert_type)?,
+                serde_json::to_string(&alert.severity)?,
+                encrypted,
+                alert.is_read as i32,
+                alert.is_dismissed as i32,
+                alert.created_at.to_string()
+            ],
+        )
+        .context("Failed to create alert")?;
+
+        self.record_audit(
+            &conn,
+            &AuditEntry::new(AuditEntityType::Alert, &alert.id, AuditOperation::Create, Vec::new()),
+        )?;
+
+        self.index_search_document(
+            &conn,
+            SearchEntityType::Alert,
+            &alert.id,
+            &search::alert_document(alert),
+        )?;
+        drop(conn);
+
+        self.publish(StorageEvent::AlertCreated(alert.clone()));
+        Ok(())
+    }
+
+    /// Saves `alert` unless it's muted, and says how it should be delivered
+    ///
+    /// Returns `None` without saving anything when one of the
+    /// [`AlertPreferences`] mutes covers the alert.
+    pub fn raise_alert(&self, alert: &Alert) -> Result<Option<AlertDelivery>> {
+        let preferences: AlertPreferences = self.load_setting_or_default()?;
+        let Some(delivery) = preferences.route(alert, OffsetDateTime::now_utc()) else {
+            return Ok(None);
+        };
+        self.create_alert(alert)?;
+        Ok(Some(delivery))
+    }
+
+    pub fn list_alerts(&self, include_dismissed: bool) -> Result<Vec<Alert>> {
+        self.list_alerts_page(include_dismissed, &ListOptions::default())
+    }
+
+    /// Lists alerts with paging, newest first
+    pub fn list_alerts_page(&self, include_dismissed: bool, options: &ListOptions) -> Result<Vec<Alert>> {
+        let query = if include_dismissed {
+            &ALL_ALERTS_QUERY
+        } else {
+            &ALERTS_QUERY
+        };
+        self.list_page(
+            query,
+            &[],
+            options,
+            |blob| self.decode_alert(blob).map(Some),
+            |item| item.created_at,
+        )
+    }
+
+    pub fn mark_alert_read(&self, alert_id: &str) -> Result<()> {
+        let conn = self.write_connection()?;
+        let updated = conn
+            .execute(
+                "UPDATE alerts SET is_read = 1 WHERE id = ?1 AND is_read = 0",
+                params![alert_id],
+            )
+            .context("Failed to mark alert as read")?;
+        if updated > 0 {
+            self.record_audit(
+                &conn,
+                &AuditEntry::new(AuditEntityType::Alert, alert_id, AuditOperation::Update, vec!["is_read".to_string()]),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn dismiss_alert(&self, alert_id: &str) -> Result<()> {
+        let conn = self.write_connection()?;
+        let updated = conn
+            .execute(
+                "UPDATE alerts SET is_dismissed = 1 WHERE id = ?1 AND is_dismissed = 0",
+                params![alert_id],
+            )
+            .context("Failed to dismiss alert")?;
+        if updated > 0 {
+            self.record_audit(
+                &conn,
+                &AuditEntry::new(
+                    AuditEntityType::Alert,
+                    alert_id,
+                    AuditOperation::Update,
+                    vec!["is_dismissed".to_string()],
+                ),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn clear_all_alerts(&self) -> Result<()> {
+        let conn = self.write_connection()?;
+        let tx = conn.unchecked_transaction()?;
+        for id in query_ids(&tx, "SELECT id FROM alerts", [])? {
+            self.audit_delete(&tx, AuditEntityType::Alert, &id)?;
+        }
+        tx.execute("DELETE FROM alerts", [])
+            .context("Failed to clear alerts")?;
+        tx.execute(
+            "DELETE FROM search_index WHERE entity_type = ?1",
+            params![serde_json::to_string(&SearchEntityType::Alert)?],
+        )
+        .context("Failed to clear alert search entries")?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    // Summary History CRUD operations
+
+    pub fn save_summary(&self, summary: &SummaryHistory) -> Result<()> {
+        let conn = self.write_connection()?;
+        let payload = serde_json::to_vec(summary).context("Failed to serialize summary")?;
+        let encrypted = self.encryption.seal(&payload)?;
+
+        conn.execute(
+            r#"
+            INSERT INTO summary_history (id, title, payload, created_at, source_id)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+            params![
+                summary.id,
+                summary.title,
+                encrypted,
+                summary.created_at.to_string(),
+                summary.source_id
+            ],
+        )
+        .context("Failed to save summary")?;
+
+        self.record_audit(
+            &conn,
+            &AuditEntry::new(AuditEntityType::Summary, &summary.id, AuditOperation::Create, Vec::new()),
+        )?;
+
+        self.index_search_document(
+            &conn,
+            SearchEntityType::Summary,
+            &summary.id,
+            &search::summary_document(summary),
+        )?;
+        Ok(())
+    }
+
+    pub fn list_summary_history(&self, limit: Option<usize>) -> Result<Vec<SummaryHistory>> {
+        let conn = self.open_connection()?;
+
+        // Use parameterized query with LIMIT -1 for no limit (SQLite behavior)
+        let limit_value = limit.map(|l| l as i64).unwrap_or(-1);
+
+        let mut stmt = conn.prepare_cached("SELECT payload FROM summary_history ORDER BY created_at DESC LIMIT ?1")?;
+        let mut rows = stmt
+            .query([limit_value])
+            .context("Unable to query summary history")?;
+
+        let mut summaries = Vec::new();
+        while let Some(row) = rows.next()? {
+            let blob: Vec<u8> = row.get(0)?;
+            summaries.push(self.decode_summary_history(&blob)?);
+        }
+        Ok(summaries)
+    }
+
+    /// Every saved summary of one source, newest first
+    pub fn list_summaries_for_source(&self, source_id: &str) -> Result<Vec<SummaryHistory>> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT payload FROM summary_history WHERE source_id = ?1 ORDER BY created_at DESC",
+        )?;
+        let mut rows = stmt
+            .query([source_id])
+            .context("Unable to query summaries for source")?;
+
+        let mut summaries = Vec::new();
+        while let Some(row) = rows.next()? {
+            let blob: Vec<u8> = row.get(0)?;
+            summaries.push(self.decode_summary_history(&blob)?);
+        }
+        Ok(summaries)
+    }
+
+    /// AI token and cost totals of summaries created on or after `since`
+    pub fn ai_usage_stats(&self, since: Option<Date>) -> Result<AiUsageStats> {
+        Ok(ai_usage::ai_usage_stats(&self.list_summary_history(None)?, since))
+    }
+
+    pub fn delete_summary(&self, summary_id: &str) -> Result<()> {
+        let conn = self.write_connection()?;
+        let deleted = conn
+            .execute("DELETE FROM summary_history WHERE id = ?1", params![summary_id])
+            .context("Failed to delete summary")?;
+        if deleted > 0 {
+            self.audit_delete(&conn, AuditEntityType::Summary, summary_id)?;
+        }
+        self.remove_search_document(&conn, SearchEntityType::Summary, summary_id)?;
+        Ok(())
+    }
+
+    pub fn get_summary(&self, summary_id: &str) -> Result<Option<SummaryHistory>> {
+        let conn = self.open_connection()?;
+        let blob: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT payload FROM summary_history WHERE id = ?1",
+                params![summary_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to fetch summary")?;
+
+        blob.map(|blob| self.decode_summary_history(&blob)).transpose()
+    }
+
+    /// Add a warning to a saved summary, unless it already has it; returns
+    /// whether the summary changed
+    pub fn annotate_summary(&self, summary_id: &str, notice: &str) -> Result<bool> {
+        let Some(mut summary) = self.get_summary(summary_id)? else {
+            return Err(anyhow::anyhow!("Summary not found"));
+        };
+        if summary.notices.iter().any(|existing| existing == notice) {
+            return Ok(false);
+        }
+        summary.notices.push(notice.to_string());
+
+        let conn = self.write_connection()?;
+        let payload = serde_json::to_vec(&summary).context("Failed to serialize summary")?;
+        conn.execute(
+            "UPDATE summary_history SET payload = ?1 WHERE id = ?2",
+            params![self.encryption.seal(&payload)?, summary.id],
+        )
+        .context("Failed to annotate summary")?;
+
+        self.record_audit(
+            &conn,
+            &AuditEntry::new(AuditEntityType::Summary, &summary.id, AuditOperation::Update, vec!["notices".to_string()]),
+        )?;
+        Ok(true)
+    }
+
+    pub fn get_alert(&self, alert_id: &str) -> Result<Option<Alert>> {
+        let conn = self.open_connection()?;
+        let blob: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT payload FROM alerts WHERE id = ?1",
+                params![alert_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to fetch alert")?;
+
+        blob.map(|blob| self.decode_alert(&blob)).transpose()
+    }
+
+    // Audit log
+
+    /// Decrypted JSON of a stored record, so an update can be diffed against it
+    fn stored_payload(&self, conn: &Connection, query: &str, id: &str) -> Result<Option<serde_json::Value>> {
+        let blob: Option<Vec<u8>> = conn
+            .prepare_cached(query)?
+            .query_row(params![id], |row| row.get(0))
+            .optional()
+            .context("Failed to load record for audit")?;
+
+        blob.map(|blob| {
+            let decrypted = self.encryption.open(&blob)?;
+            serde_json::from_slice(&decrypted).context("Failed to deserialize record for audit")
+        })
+        .transpose()
+    }
+
+    fn record_audit(&self, conn: &Connection, entry: &AuditEntry) -> Result<()> {
+        conn.execute(
+            r#"
+            INSERT INTO audit_log (id, entity_type, entity_id, operation, changed_fields, summary, occurred_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            params![
+                entry.id,
+                serde_json::to_string(&entry.entity_type)?,
+                entry.entity_id,
+                serde_json::to_string(&entry.operation)?,
+                serde_json::to_string(&entry.changed_fields)?,
+                entry.summary,
+                entry.occurred_at.unix_timestamp()
+            ],
+        )
+        .context("Failed to write audit log")?;
+        Ok(())
+    }
+
+    /// Record a create, or an update listing the fields that changed
+    ///
+    /// Saves that only touch timestamps aren't recorded.
+    fn audit_upsert<T: Serialize>(
+        &self,
+        conn: &Connection,
+        entity_type: AuditEntityType,
+        entity_id: &str,
+        previous: Option<serde_json::Value>,
+        current: &T,
+    ) -> Result<()> {
+        let entry = match previous {
+            None => AuditEntry::new(entity_type, entity_id, AuditOperation::Create, Vec::new()),
+            Some(previous) => {
+                let current = serde_json::to_value(current).context("Failed to serialize record for audit")?;
+                let fields = audit::changed_fields(&previous, &current);
+                if fields.is_empty() {
+                    return Ok(());
+                }
+                AuditEntry::new(entity_type, entity_id, AuditOperation::Update, fields)
+            }
+        };
+        self.record_audit(conn, &entry)
+    }
+
+    fn audit_delete(&self, conn: &Connection, entity_type: AuditEntityType, entity_id: &str) -> Result<()> {
+        self.record_audit(
+            conn,
+            &AuditEntry::new(entity_type, entity_id, AuditOperation::Delete, Vec::new()),
+        )
+    }
+
+    /// Record the dose logs and inventory that are deleted along with a protocol
+    fn audit_protocol_cascade(&self, conn: &Connection, protocol_id: &str) -> Result<()> {
+        let children = [
+            (AuditEntityType::DoseLog, "SELECT id FROM dose_logs WHERE protocol_id = ?1"),
+            (AuditEntityType::InventoryItem, "SELECT id FROM inventory WHERE protocol_id = ?1"),
+        ];
+        for (entity_type, query) in children {
+            for id in query_ids(conn, query, params![protocol_id])? {
+                self.record_audit(
+                    conn,
+                    &AuditEntry::new(entity_type, id, AuditOperation::Delete, Vec::new())
+                        .with_summary("Deleted with its protocol"),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// List audit entries, newest first
+    pub fn list_audit_log(&self, filter: &AuditLogFilter) -> Result<Vec<AuditEntry>> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+
+        if let Some(entity_type) = &filter.entity_type {
+            values.push(serde_json::to_string(entity_type)?.into());
+            conditions.push(format!("entity_type = ?{}", values.len()));
+        }
+        if let Some(entity_id) = &filter.entity_id {
+            values.push(entity_id.clone().into());
+            conditions.push(format!("entity_id = ?{}", values.len()));
+        }
+        if let Some(operation) = &filter.operation {
+            values.push(serde_json::to_string(operation)?.into());
+            conditions.push(format!("operation = ?{}", values.len()));
+        }
+        if let Some(since) = filter.since {
+            values.push(since.unix_timestamp().into());
+            conditions.push(format!("occurred_at >= ?{}", values.len()));
+        }
+        if let Some(until) = filter.until {
+            values.push(until.unix_timestamp().into());
+            conditions.push(format!("occurred_at <= ?{}", values.len()));
+        }
+
+        let mut query = String::from(
+            "SELECT id, entity_type, entity_id, operation, changed_fields, summary, occurred_at FROM audit_log",
+        );
+        if !conditions.is_empty() {
+            query.push_str(" WHERE ");
+            query.push_str(&conditions.join(" AND "));
+        }
+        query.push_str(" ORDER BY seq DESC");
+        if let Some(limit) = filter.limit {
+            query.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare(&query)?;
+        let mut rows = stmt
+            .query(rusqlite::params_from_iter(values))
+            .context("Unable to query audit log")?;
+
+        let mut entries = Vec::new();
+        while let Some(row) = rows.next()? {
+            entries.push(decode_audit_entry(row)?);
+        }
+        Ok(entries)
+    }
+
+    /// Remove audit entries outside the retention window
+    ///
+    /// Returns the number of entries removed.
+    pub fn prune_audit_log(&self, retention: &AuditRetention) -> Result<usize> {
+        let conn = self.write_connection()?;
+        let mut removed = 0;
+
+        if let Some(days) = retention.max_age_days {
+            let cutoff = OffsetDateTime::now_utc() - time::Duration::days(days.into());
+            removed += conn
+                .execute(
+                    "DELETE FROM audit_log WHERE occurred_at < ?1",
+                    params![cutoff.unix_timestamp()],
+                )
+                .context("Failed to prune old audit entries")?;
+        }
+        if let Some(max_entries) = retention.max_entries {
+            removed += conn
+                .execute(
+                    "DELETE FROM audit_log WHERE seq <= (SELECT seq FROM audit_log ORDER BY seq DESC LIMIT 1 OFFSET ?1)",
+                    params![max_entries as i64],
+                )
+                .context("Failed to prune excess audit entries")?;
+        }
+
+        if removed > 0 {
+            info!("Pruned {} audit log entries", removed);
+        }
+        Ok(removed)
+    }
+
+    // Stats cache
+
+    /// Cached value of `stat`, unless it was invalidated or is older than
+    /// [`MAX_STAT_AGE`]
+    pub fn cached_stat<T: DeserializeOwned>(&self, stat: DashboardStat) -> Result<Option<CachedStat<T>>> {
+        let conn = self.open_connection()?;
+        let row: Option<(Vec<u8>, i64)> = conn
+            .prepare_cached("SELECT payload, computed_at FROM stats_cache WHERE key = ?1")?
+            .query_row(params![stat.key()], |row| Ok((row.get(0)?, row.get(1)?)))
+            .optional()
+            .context("Failed to read stats cache")?;
+        let Some((blob, computed_at)) = row else {
+            return Ok(None);
+        };
+
+        let computed_at = OffsetDateTime::from_unix_timestamp(computed_at)?;
+        if now_timestamp() - computed_at > MAX_STAT_AGE {
+            return Ok(None);
+        }
+        // A value written by an older version may no longer deserialize;
+        // treat it as a cache miss so it gets recomputed
+        let value = match serde_json::from_slice(&self.encryption.open(&blob)?) {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::warn!("Ignoring cached {} stat: {}", stat.key(), e);
+                return Ok(None);
+            }
+        };
+        Ok(Some(CachedStat { value, computed_at }))
+    }
+
+    /// Snapshot to pass to [`store_stat`](Self::store_stat), taken before
+    /// loading the data a stat is computed from
+    pub fn stats_generation(&self) -> StatsGeneration {
+        StatsGeneration(self.stats_generation.load(Ordering::SeqCst))
+    }
+
+    /// Cache a freshly computed stat
+    ///
+    /// Nothing is stored if any stat was invalidated since `generation`, as
+    /// the value may have been computed from data that has since changed.
+    /// Returns whether the value was stored.
+    pub fn store_stat<T: Serialize>(
+        &self,
+        stat: DashboardStat,
+        value: &T,
+        generation: StatsGeneration,
+    ) -> Result<bool> {
+        let payload = serde_json::to_vec(value).context("Failed to serialize stat")?;
+        let encrypted = self.encryption.seal(&payload)?;
+
+        let conn = self.write_connection()?;
+        if self.stats_generation() != generation {
+            return Ok(false);
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO stats_cache (key, payload, computed_at) VALUES (?1, ?2, ?3)",
+            params![stat.key(), encrypted, now_timestamp().unix_timestamp()],
+        )
+        .context("Failed to write stats cache")?;
+
+        // Invalidation bumps the generation before deleting, so checking again
+        // after the write catches one that ran in between
+        if self.stats_generation() != generation {
+            conn.execute("DELETE FROM stats_cache WHERE key = ?1", params![stat.key()])?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Drop cached stats computed from `table`
+    ///
+    /// Storage methods do this themselves; it's only needed for tables
+    /// written outside of `StorageManager`, such as dose schedules.
+    pub fn invalidate_stats(&self, table: &str) -> Result<()> {
+        let conn = self.write_connection()?;
+        self.invalidate_stats_on(&conn, table)
+    }
+
+    fn invalidate_stats_on(&self, conn: &Connection, table: &str) -> Result<()> {
+        self.stats_generation.fetch_add(1, Ordering::SeqCst);
+        for stat in DashboardStat::affected_by(table) {
+            conn.prepare_cached("DELETE FROM stats_cache WHERE key = ?1")?
+                .execute(params![stat.key()])
+                .context("Failed to invalidate stats cache")?;
+        }
+        Ok(())
+    }
+
+    // Maintenance
+
+    /// Check the database stats and do the upkeep they call for; see
+    /// [`crate::maintenance`]
+    ///
+    /// The run is recorded even when nothing was needed or an action failed.
+    pub fn run_maintenance(&self, manual: bool) -> Result<MaintenanceRun> {
+        let before = self.get_stats()?;
+        let actions = MaintenanceAction::needed(&before);
+        let mut error = None;
+        for action in &actions {
+            let result = match action {
+                MaintenanceAction::Optimize => self.optimize(),
+                MaintenanceAction::Checkpoint => self.checkpoint_wal("TRUNCATE"),
+            };
+            if let Err(e) = result {
+                tracing::warn!("Database maintenance failed: {:#}", e);
+                error = Some(format!("{:#}", e));
+                break;
+            }
+        }
+        let after = if actions.is_empty() { before.clone() } else { self.get_stats()? };
+
+        let run = MaintenanceRun {
+            id: uuid::Uuid::new_v4().to_string(),
+            ran_at: OffsetDateTime::now_utc(),
+            manual,
+            actions,
+            before,
+            after,
+            error,
+        };
+        let conn = self.write_connection()?;
+        conn.execute(
+            "INSERT INTO maintenance_runs (id, ran_at, payload) VALUES (?1, ?2, ?3)",
+            params![run.id, run.ran_at.unix_timestamp(), serde_json::to_string(&run)?],
+        )
+        .context("Failed to record maintenance run")?;
+        conn.execute(
+            "DELETE FROM maintenance_runs WHERE id NOT IN
+                (SELECT id FROM maintenance_runs ORDER BY ran_at DESC LIMIT ?1)",
+            params![MAX_MAINTENANCE_RUNS as i64],
+        )
+        .context("Failed to prune maintenance runs")?;
+
+        info!("Database maintenance done: {:?}", run.actions);
+        Ok(run)
+    }
+
+    /// Recorded maintenance runs, newest first
+    pub fn list_maintenance_runs(&self) -> Result<Vec<MaintenanceRun>> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare("SELECT payload FROM maintenance_runs ORDER BY ran_at DESC")?;
+        let payloads = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        payloads
+            .iter()
+            .map(|payload| serde_json::from_str(payload).context("Failed to parse maintenance run"))
+            .collect()
+    }
+
+    /// Whether the last run was long enough ago for a scheduled one
+    pub fn maintenance_due(&self, now: OffsetDateTime) -> Result<bool> {
+        let last = self.list_maintenance_runs()?.into_iter().next();
+        Ok(last.is_none_or(|run| run.next_due() <= now))
+    }
+
+    // Sync
+
+    /// Records touched since audit entry `after_seq`, as they are now, and
+    /// the last audit entry looked at; see [`crate::sync`]
+    ///
+    /// Looks at up to `limit` audit entries, so call again until the
+    /// returned sequence stops moving.
+    pub fn pending_sync_changes(&self, after_seq: i64, limit: usize) -> Result<(Vec<SyncChange>, i64)> {
+        let touched: Vec<(i64, String, String)> = {
+            let conn = self.open_connection()?;
+            let mut stmt = conn.prepare_cached(
+                "SELECT seq, entity_type, entity_id FROM audit_log WHERE seq > ?1 ORDER BY seq LIMIT ?2",
+            )?;
+            let rows = stmt
+                .query_map(params![after_seq, limit as i64], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()
+                .context("Failed to read audit log for sync")?;
+            rows
+        };
+
+        let last_seq = touched.last().map_or(after_seq, |(seq, _, _)| *seq);
+        let mut seen = HashSet::new();
+        let mut changes = Vec::new();
+        for (_, entity_type, entity_id) in touched {
+            let entity_type: AuditEntityType = serde_json::from_str(&entity_type)?;
+            // In the order first touched, so parents come before their children
+            if !sync::SYNCED_ENTITIES.contains(&entity_type) || !seen.insert((entity_type, entity_id.clone())) {
+                continue;
+            }
+            let record = self.sync_record(entity_type, &entity_id)?;
+            changes.push(SyncChange {
+                entity_type,
+                entity_id,
+                record,
+            });
+        }
+        Ok((changes, last_seq))
+    }
+
+    /// Every synced record, for a device's first upload
+    pub fn sync_snapshot(&self) -> Result<Vec<SyncChange>> {
+        fn push_all<T: Serialize>(
+            changes: &mut Vec<SyncChange>,
+            entity_type: AuditEntityType,
+            records: Vec<T>,
+            id: impl Fn(&T) -> &str,
+        ) -> Result<()> {
+            for record in records {
+                changes.push(SyncChange {
+                    entity_type,
+                    entity_id: id(&record).to_string(),
+                    record: Some(serde_json::to_value(&record)?),
+                });
+            }
+            Ok(())
+        }
+
+        let mut changes = Vec::new();
+        push_all(&mut changes, AuditEntityType::Supplier, self.list_suppliers()?, |r| &r.id)?;
+        push_all(&mut changes, AuditEntityType::Protocol, self.list_protocols()?, |r| &r.id)?;
+        push_all(&mut changes, AuditEntityType::InventoryItem, self.list_inventory()?, |r| &r.id)?;
+        push_all(&mut changes, AuditEntityType::DoseLog, self.list_dose_logs()?, |r| &r.id)?;
+        push_all(&mut changes, AuditEntityType::BodyMetric, self.list_body_metrics()?, |r| &r.id)?;
+        push_all(&mut changes, AuditEntityType::SideEffect, self.list_side_effects()?, |r| &r.id)?;
+        push_all(&mut changes, AuditEntityType::LabResult, self.list_lab_results()?, |r| &r.id)?;
+        push_all(&mut changes, AuditEntityType::JournalEntry, self.list_journal_entries()?, |r| &r.id)?;
+        push_all(&mut changes, AuditEntityType::Goal, self.list_goals()?, |r| &r.id)?;
+        push_all(&mut changes, AuditEntityType::Order, self.list_orders()?, |r| &r.id)?;
+        Ok(changes)
+    }
+
+    /// Sequence of the newest audit entry, 0 when there are none
+    pub fn last_audit_seq(&self) -> Result<i64> {
+        let conn = self.open_connection()?;
+        conn.query_row("SELECT COALESCE(MAX(seq), 0) FROM audit_log", [], |row| row.get(0))
+            .context("Failed to read audit log")
+    }
+
+    /// Whether audit entries after `seq` were pruned before they could be
+    /// uploaded, so only a snapshot can catch other devices up
+    pub fn audit_log_pruned_after(&self, seq: i64) -> Result<bool> {
+        let conn = self.open_connection()?;
+        let oldest: Option<i64> = conn
+            .query_row("SELECT MIN(seq) FROM audit_log", [], |row| row.get(0))
+            .context("Failed to read audit log")?;
+        Ok(oldest.is_some_and(|oldest| oldest > seq + 1))
+    }
+
+    /// Apply changes downloaded from another device, all or none
+    ///
+    /// A saved record replaces the copy here unless that copy was updated
+    /// later.
+    pub fn apply_sync_changes(&self, changes: &[SyncChange]) -> Result<SyncApplied> {
+        if changes.is_empty() {
+            return Ok(SyncApplied::default());
+        }
+        self.transaction(|batch| {
+            // Reads don't see the transaction's own writes, so a record
+            // changed twice in one batch is compared with what was written
+            let mut written: HashMap<(AuditEntityType, &str), Option<serde_json::Value>> = HashMap::new();
+            let mut applied = SyncApplied::default();
+            for change in changes {
+                let key = (change.entity_type, change.entity_id.as_str());
+                let current = match written.get(&key) {
+                    Some(record) => record.clone(),
+                    None => self.sync_record(change.entity_type, &change.entity_id)?,
+                };
+                match (&change.record, current) {
+                    (None, None) => applied.skipped += 1,
+                    (None, Some(_)) => {
+                        self.delete_sync_record(&batch.tx, change.entity_type, &change.entity_id)?;
+                        written.insert(key, None);
+                        applied.deleted += 1;
+                    }
+                    (Some(record), Some(current)) if sync::local_is_newer(&current, record) => applied.skipped += 1,
+                    (Some(record), _) => {
+                        self.save_sync_record(&batch.tx, change.entity_type, record.clone())?;
+                        written.insert(key, Some(record.clone()));
+                        applied.saved += 1;
+                    }
+                }
+            }
+            Ok(applied)
+        })
+    }
+
+    /// A synced record as JSON, `None` when it doesn't exist
+    fn sync_record(&self, entity_type: AuditEntityType, id: &str) -> Result<Option<serde_json::Value>> {
+        fn json<T: Serialize>(record: Option<T>) -> Result<Option<serde_json::Value>> {
+            Ok(record.map(|record| serde_json::to_value(record)).transpose()?)
+        }
+
+        match entity_type {
+            AuditEntityType::Supplier => json(self.get_supplier(id)?),
+            AuditEntityType::Protocol => json(self.get_protocol(id)?),
+            AuditEntityType::InventoryItem => json(self.get_inventory_item(id)?),
+            AuditEntityType::DoseLog => json(self.get_dose_log(id)?),
+            AuditEntityType::BodyMetric => json(self.get_body_metric(id)?),
+            AuditEntityType::SideEffect => json(self.get_side_effect(id)?),
+            AuditEntityType::LabResult => json(self.get_lab_result(id)?),
+            AuditEntityType::JournalEntry => json(self.get_journal_entry(id)?),
+            AuditEntityType::Goal => json(self.get_goal(id)?),
+            AuditEntityType::Order => json(self.get_order(id)?),
+            other => Err(anyhow::anyhow!("{:?} records aren't synced", other)),
+        }
+    }
+
+    fn save_sync_record(&self, conn: &Connection, entity_type: AuditEntityType, record: serde_json::Value) -> Result<()> {
+        let context = || format!("Synced {:?} record is not in the expected format", entity_type);
+        match entity_type {
+            AuditEntityType::Supplier => {
+                self.write_supplier(conn, &serde_json::from_value(record).with_context(context)?)
+            }
+            AuditEntityType::Protocol => {
+                self.write_protocol(conn, &serde_json::from_value(record).with_context(context)?)?;
+                self.invalidate_stats_on(conn, "protocols")
+            }
+            AuditEntityType::InventoryItem => {
+                self.write_inventory_item(conn, &serde_json::from_value(record).with_context(context)?)?;
+                self.invalidate_stats_on(conn, "inventory")
+            }
+            AuditEntityType::DoseLog => {
+                self.write_dose_log(conn, &serde_json::from_value(record).with_context(context)?)?;
+                self.invalidate_stats_on(conn, "dose_logs")
+            }
+            AuditEntityType::BodyMetric => {
+                self.write_body_metric(conn, &serde_json::from_value(record).with_context(context)?)
+            }
+            AuditEntityType::SideEffect => {
+                self.write_side_effect(conn, &serde_json::from_value(record).with_context(context)?)
+            }
+            AuditEntityType::LabResult => {
+                self.write_lab_result(conn, &serde_json::from_value(record).with_context(context)?)
+            }
+            AuditEntityType::JournalEntry => {
+                self.write_journal_entry(conn, &serde_json::from_value(record).with_context(context)?)
+            }
+            AuditEntityType::Goal => self.write_goal(conn, &serde_json::from_value(record).with_context(context)?),
+            AuditEntityType::Order => self.write_order(conn, &serde_json::from_value(record).with_context(context)?),
+            other => Err(anyhow::anyhow!("{:?} records aren't synced", other)),
+        }
+    }
+
+    fn delete_sync_record(&self, conn: &Connection, entity_type: AuditEntityType, id: &str) -> Result<()> {
+        match entity_type {
+            AuditEntityType::Supplier => {
+                let policies: CascadePolicies = self.load_setting_or_default()?;
+                self.delete_suppliers_on(conn, &[id.to_string()], &policies)?;
+                Ok(())
+            }
+            AuditEntityType::Protocol => self.delete_protocol_on(conn, id),
+            AuditEntityType::InventoryItem => self.delete_inventory_item_on(conn, id),
+            AuditEntityType::DoseLog => self.delete_dose_log_on(conn, id),
+            AuditEntityType::BodyMetric => self.delete_body_metric_on(conn, id),
+            AuditEntityType::SideEffect => self.delete_side_effect_on(conn, id),
+            AuditEntityType::LabResult => self.delete_lab_result_on(conn, id),
+            AuditEntityType::JournalEntry => self.delete_journal_entry_on(conn, id),
+            AuditEntityType::Goal => self.delete_goal_on(conn, id),
+            AuditEntityType::Order => self.delete_order_on(conn, id),
+            other => Err(anyhow::anyhow!("{:?} records aren't synced", other)),
+        }
+    }
+
+    /// How far sync has got with `device_id`; a fresh cursor if it never has
+    pub fn sync_cursor(&self, device_id: &str) -> Result<SyncCursor> {
+        Ok(self
+            .list_sync_cursors()?
+            .into_iter()
+            .find(|cursor| cursor.device_id == device_id)
+            .unwrap_or_else(|| SyncCursor::new(device_id)))
+    }
+
+    /// Every device sync has got anywhere with
+    pub fn list_sync_cursors(&self) -> Result<Vec<SyncCursor>> {
+        let conn = self.open_connection()?;
+        let mut stmt =
+            conn.prepare("SELECT device_id, change_sets, audit_seq, synced_at FROM sync_cursors ORDER BY device_id")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read sync cursors")?;
+        rows.into_iter()
+            .map(|(device_id, change_sets, audit_seq, synced_at)| {
+                Ok(SyncCursor {
+                    device_id,
+                    change_sets: change_sets as u64,
+                    audit_seq,
+                    synced_at: synced_at.map(OffsetDateTime::from_unix_timestamp).transpose()?,
+                })
+            })
+            .collect()
+    }
+
+    pub fn save_sync_cursor(&self, cursor: &SyncCursor) -> Result<()> {
+        let conn = self.write_connection()?;
+        conn.execute(
+            r#"
+            INSERT INTO sync_cursors (device_id, change_sets, audit_seq, synced_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(device_id) DO UPDATE SET
+                change_sets = excluded.change_sets,
+                audit_seq = excluded.audit_seq,
+                synced_at = excluded.synced_at
+            "#,
+            params![
+                cursor.device_id,
+                cursor.change_sets as i64,
+                cursor.audit_seq,
+                cursor.synced_at.map(|at| at.unix_timestamp())
+            ],
+        )
+        .context("Failed to save sync cursor")?;
+        Ok(())
+    }
+
+    // Settings
+
+    /// Stored value of `T`, importing its legacy JSON file the first time
+    ///
+    /// Returns `None` when the setting was never saved.
+    pub fn load_setting<T: Setting>(&self) -> Result<Option<T>> {
+        let conn = self.open_connection()?;
+        let blob: Option<Vec<u8>> = conn
+            .query_row("SELECT payload FROM settings WHERE key = ?1", params![T::KEY], |row| row.get(0))
+            .optional()
+            .context("Failed to read setting")?;
+
+        if let Some(blob) = blob {
+            let payload = self.encryption.open(&blob)?;
+            let value = serde_json::from_slice(&payload)
+                .with_context(|| format!("Failed to parse {} setting", T::KEY))?;
+            return Ok(Some(value));
+        }
+        self.migrate_legacy_setting()
+    }
+
+    /// Stored value of `T`, or its default when it was never saved
+    pub fn load_setting_or_default<T: Setting + Default>(&self) -> Result<T> {
+        Ok(self.load_setting()?.unwrap_or_default())
+    }
+
+    /// Validate and save `value`, replacing the stored one
+    pub fn save_setting<T: Setting>(&self, value: &T) -> Result<()> {
+        settings::validate_key(T::KEY).map_err(anyhow::Error::msg)?;
+        value.validate().map_err(anyhow::Error::msg)?;
+        let payload = serde_json::to_vec(value).context("Failed to serialize setting")?;
+        let encrypted = self.encryption.seal(&payload)?;
+
+        let conn = self.write_connection()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, payload, updated_at) VALUES (?1, ?2, ?3)",
+            params![T::KEY, encrypted, now_timestamp().to_string()],
+        )
+        .context("Failed to save setting")?;
+        Ok(())
+    }
+
+    /// Remove the stored value of `T`; returns whether there was one
+    ///
+    /// A legacy file that was never imported is removed too, so it can't
+    /// bring the value back.
+    pub fn delete_setting<T: Setting>(&self) -> Result<bool> {
+        let conn = self.write_connection()?;
+        let deleted = conn
+            .execute("DELETE FROM settings WHERE key = ?1", params![T::KEY])
+            .context("Failed to delete setting")?;
+
+        if let (Some(file_name), Some(data_dir)) = (T::LEGACY_FILE, self.db_path.parent()) {
+            let path = data_dir.join(file_name);
+            if path.exists() {
+                std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", file_name))?;
+                return Ok(true);
+            }
+        }
+        Ok(deleted > 0)
+    }
+
+    /// Import `T` from its legacy JSON file, if there is one
+    ///
+    /// A file that doesn't parse or validate is left in place and ignored.
+    fn migrate_legacy_setting<T: Setting>(&self) -> Result<Option<T>> {
+        let (Some(file_name), Some(data_dir)) = (T::LEGACY_FILE, self.db_path.parent()) else {
+            return Ok(None);
+        };
+        let path = data_dir.join(file_name);
+        let Ok(json) = std::fs::read_to_string(&path) else {
+            return Ok(None);
+        };
+
+        let value: T = match serde_json::from_str(&json) {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable {}: {}", file_name, e);
+                return Ok(None);
+            }
+        };
+        if let Err(e) = value.validate() {
+            tracing::warn!("Ignoring invalid {}: {}", file_name, e);
+            return Ok(None);
+        }
+
+        self.save_setting(&value)?;
+        let migrated = data_dir.join(format!("{}{}", file_name, settings::MIGRATED_SUFFIX));
+        if let Err(e) = std::fs::rename(&path, &migrated) {
+            tracing::warn!("Failed to rename {} after importing it: {}", file_name, e);
+        }
+        info!("Imported {} into the {} setting", file_name, T::KEY);
+        Ok(Some(value))
+    }
+
+    // Trash
+
+    /// Move records to the trash
+    ///
+    /// Trashed records are hidden from lists, lookups and search until
+    /// restored. A protocol takes its dose logs with it. Returns the number of
+    /// records moved; IDs that are missing or already trashed are skipped.
+    pub fn move_to_trash(&self, entity_type: TrashEntityType, ids: &[String]) -> Result<usize> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let conn = self.write_connection()?;
+        let deleted_at = OffsetDateTime::now_utc().unix_timestamp();
+        let mut moved = 0;
+
+        let tx = conn.unchecked_transaction()?;
+        for id in ids {
+            let rows = tx
+                .execute(
+                    &format!(
+                        "UPDATE {} SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+                        entity_type.table()
+                    ),
+                    params![deleted_at, id],
+                )
+                .context("Failed to move record to trash")?;
+            if rows == 0 {
+                continue;
+            }
+            moved += 1;
+            self.audit_trash(&tx, entity_type, id, AuditOperation::Trash, None)?;
+
+            match entity_type {
+                TrashEntityType::Protocol => {
+                    self.remove_search_document(&tx, SearchEntityType::Protocol, id)?;
+                    let dose_ids = query_ids(
+                        &tx,
+                        "SELECT id FROM dose_logs WHERE protocol_id = ?1 AND deleted_at IS NULL",
+                        params![id],
+                    )?;
+                    for dose_id in dose_ids {
+                        tx.execute(
+                            "UPDATE dose_logs SET deleted_at = ?1 WHERE id = ?2",
+                            params![deleted_at, dose_id],
+                        )
+                        .context("Failed to move dose log to trash")?;
+                        self.remove_search_document(&tx, SearchEntityType::DoseLog, &dose_id)?;
+                        self.audit_trash(
+                            &tx,
+                            TrashEntityType::DoseLog,
+                            &dose_id,
+                            AuditOperation::Trash,
+                            Some("Moved to trash with its protocol"),
+                        )?;
+                    }
+                }
+                TrashEntityType::DoseLog => {
+                    self.remove_search_document(&tx, SearchEntityType::DoseLog, id)?;
+                }
+                TrashEntityType::BodyMetric => {}
+            }
+        }
+        if moved > 0 {
+            self.invalidate_trash_stats(&tx, entity_type)?;
+        }
+        tx.commit()?;
+
+        Ok(moved)
+    }
+
+    /// Reverse a change recorded on the [`UndoStack`](crate::UndoStack)
+    ///
+    /// Returns the number of records put back. Records saved as they were
+    /// replace whatever is stored now, so edits made since are lost.
+    pub fn undo(&self, action: &UndoAction) -> Result<usize> {
+        match action {
+            UndoAction::RestoreFromTrash { entity_type, ids } => self.restore_from_trash(*entity_type, ids),
+            UndoAction::RestoreProtocols(protocols) => {
+                for protocol in protocols {
+                    self.upsert_protocol(protocol)?;
+                }
+                Ok(protocols.len())
+            }
+            UndoAction::RestoreInventory(items) => {
+                for item in items {
+                    self.upsert_inventory_item(item)?;
+                }
+                Ok(items.len())
+            }
+            UndoAction::RestoreSideEffects(effects) => {
+                for effect in effects {
+                    self.upsert_side_effect(effect)?;
+                }
+                Ok(effects.len())
+            }
+        }
+    }
+
+    /// Restore records from the trash
+    ///
+    /// Restoring a protocol also restores the dose logs that were trashed
+    /// with it. A dose log whose protocol is still in the trash can't be
+    /// restored on its own. Returns the number of records restored.
+    pub fn restore_from_trash(&self, entity_type: TrashEntityType, ids: &[String]) -> Result<usize> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let conn = self.write_connection()?;
+        let mut restored = 0;
+
+        let tx = conn.unchecked_transaction()?;
+        for id in ids {
+            let row: Option<(Vec<u8>, i64)> = tx
+                .query_row(
+                    &format!(
+                        "SELECT payload, deleted_at FROM {} WHERE id = ?1 AND deleted_at IS NOT NULL",
+                        entity_type.table()
+                    ),
+                    params![id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()
+                .context("Failed to load trashed record")?;
+            let Some((blob, deleted_at)) = row else {
+                continue;
+            };
+
+            match entity_type {
+                TrashEntityType::Protocol => {
+                    let protocol = self.decode_protocol(&blob)?;
+                    self.index_search_document(
+                        &tx,
+                        SearchEntityType::Protocol,
+                        id,
+                        &search::protocol_document(&protocol),
+                    )?;
+
+                    let mut stmt = tx.prepare_cached(
+                        "SELECT payload FROM dose_logs WHERE protocol_id = ?1 AND deleted_at = ?2",
+                    )?;
+                    let blobs = stmt
+                        .query_map(params![id, deleted_at], |row| row.get(0))?
+                        .collect::<rusqlite::Result<Vec<Vec<u8>>>>()?;
+                    drop(stmt);
+
+                    for blob in blobs {
+                        let log = self.decode_dose_log(&blob)?;
+                        tx.execute(
+                            "UPDATE dose_logs SET deleted_at = NULL WHERE id = ?1",
+                            params![log.id],
+                        )
+                        .context("Failed to restore dose log")?;
+                        self.index_search_document(
+                            &tx,
+                            SearchEntityType::DoseLog,
+                            &log.id,
+                            &search::dose_log_document(&log),
+                        )?;
+                        self.audit_trash(
+                            &tx,
+                            TrashEntityType::DoseLog,
+                            &log.id,
+                            AuditOperation::Restore,
+                            Some("Restored from trash with its protocol"),
+                        )?;
+                    }
+                }
+                TrashEntityType::DoseLog => {
+                    let log = self.decode_dose_log(&blob)?;
+                    let protocol_trashed: bool = tx
+                        .query_row(
+                            "SELECT COUNT(*) FROM protocols WHERE id = ?1 AND deleted_at IS NOT NULL",
+                            params![log.protocol_id],
+                            |row| row.get::<_, i64>(0),
+                        )
+                        .context("Failed to check dose log protocol")?
+                        > 0;
+                    if protocol_trashed {
+                        return Err(anyhow::anyhow!(
+                            "Dose log belongs to a protocol in the trash; restore the protocol first"
+                        ));
+                    }
+                    self.index_search_document(
+                        &tx,
+                        SearchEntityType::DoseLog,
+                        id,
+                        &search::dose_log_document(&log),
+                    )?;
+                }
+                TrashEntityType::BodyMetric => {}
+            }
+
+            tx.execute(
+                &format!("UPDATE {} SET deleted_at = NULL WHERE id = ?1", entity_type.table()),
+                params![id],
+            )
+            .context("Failed to restore record")?;
+            self.audit_trash(&tx, entity_type, id, AuditOperation::Restore, None)?;
+            restored += 1;
+        }
+        if restored > 0 {
+            self.invalidate_trash_stats(&tx, entity_type)?;
+        }
+        tx.commit()?;
+
+        Ok(restored)
+    }
+
+    /// Drop cached stats after records of `entity_type` were trashed or restored
+    fn invalidate_trash_stats(&self, conn: &Connection, entity_type: TrashEntityType) -> Result<()> {
+        self.invalidate_stats_on(conn, entity_type.table())?;
+        // A protocol's dose logs move with it
+        if entity_type == TrashEntityType::Protocol {
+            self.invalidate_stats_on(conn, "dose_logs")?;
+        }
+        Ok(())
+    }
+
+    /// List everything in the trash, most recently deleted first
+    pub fn list_trash(&self) -> Result<Vec<TrashItem>> {
+        let conn = self.open_connection()?;
+        let mut items = Vec::new();
+
+        for entity_type in [
+            TrashEntityType::Protocol,
+            TrashEntityType::DoseLog,
+            TrashEntityType::BodyMetric,
+        ] {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT payload, deleted_at FROM {} WHERE deleted_at IS NOT NULL",
+                entity_type.table()
+            ))?;
+            let mut rows = stmt.query([]).context("Unable to query trash")?;
+
+            while let Some(row) = rows.next()? {
+                let blob: Vec<u8> = row.get(0)?;
+                let deleted_at = OffsetDateTime::from_unix_timestamp(row.get(1)?)?;
+
+                let (entity_id, label, protocol_id) = match entity_type {
+                    TrashEntityType::Protocol => {
+                        let protocol = self.decode_protocol(&blob)?;
+                        (protocol.id, protocol.name, None)
+                    }
+                    TrashEntityType::DoseLog => {
+                        let log = self.decode_dose_log(&blob)?;
+                        let label = format!("{} mg dose ({}) on {}", log.amount_mg, log.site, log.logged_at.date());
+                        (log.id, label, Some(log.protocol_id))
+                    }
+                    TrashEntityType::BodyMetric => {
+                        let decrypted = self.encryption.open(&blob)?;
+                        let metric: BodyMetric = serde_json::from_slice(&decrypted)
+                            .context("Failed to deserialize body metric")?;
+                        (metric.id, format!("Body metrics for {}", metric.date.date()), None)
+                    }
+                };
+
+                items.push(TrashItem {
+                    entity_type,
+                    entity_id,
+                    label,
+                    protocol_id,
+                    deleted_at,
+                });
+            }
+        }
+
+        items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+        Ok(items)
+    }
+
+    /// Permanently delete records that have been in the trash for at least
+    /// `retention_days` days; `0` empties the trash
+    ///
+    /// Returns the number of records deleted.
+    pub fn purge_trash(&self, retention_days: u32) -> Result<usize> {
+        let cutoff = (OffsetDateTime::now_utc() - time::Duration::days(retention_days.into())).unix_timestamp();
+        let expired = |table: &str| -> Result<Vec<String>> {
+            let conn = self.open_connection()?;
+            query_ids(
+                &conn,
+                &format!("SELECT id FROM {} WHERE deleted_at IS NOT NULL AND deleted_at <= ?1", table),
+                params![cutoff],
+            )
+        };
+
+        // Dose logs first, so those trashed with a protocol are audited individually
+        let mut purged = self.bulk_delete_doses(&expired("dose_logs")?)?;
+        purged += self.bulk_delete_body_metrics(&expired("body_metrics")?)?;
+        purged += self.bulk_delete_protocols(&expired("protocols")?)?;
+
+        if purged > 0 {
+            info!("Purged {} records from the trash", purged);
+        }
+        Ok(purged)
+    }
+
+    fn audit_trash(
+        &self,
+        conn: &Connection,
+        entity_type: TrashEntityType,
+        entity_id: &str,
+        operation: AuditOperation,
+        summary: Option<&str>,
+    ) -> Result<()> {
+        let audit_type = match entity_type {
+            TrashEntityType::Protocol => AuditEntityType::Protocol,
+            TrashEntityType::DoseLog => AuditEntityType::DoseLog,
+            TrashEntityType::BodyMetric => AuditEntityType::BodyMetric,
+        };
+        let mut entry = AuditEntry::new(audit_type, entity_id, operation, Vec::new());
+        if let Some(summary) = summary {
+            entry = entry.with_summary(summary);
+        }
+        self.record_audit(conn, &entry)
+    }
+
+    // Orphaned records
+
+    /// Records left pointing at deleted ones; see [`crate::orphans`]
+    pub fn find_orphaned_records(&self) -> Result<Vec<OrphanedRecord>> {
+        let conn = self.open_connection()?;
+        let mut orphans = Vec::new();
+        let mut push = |kind, pairs: Vec<(String, String)>| {
+            orphans.extend(pairs.into_iter().map(|(record_id, missing_id)| OrphanedRecord {
+                kind,
+                record_id,
+                missing_id,
+            }));
+        };
+
+        // Deleting a supplier clears the column, but the payload keeps the id
+        let suppliers: HashSet<String> = query_ids(&conn, "SELECT id FROM suppliers", [])?.into_iter().collect();
+        let mut stale_suppliers = Vec::new();
+        {
+            let mut stmt = conn.prepare("SELECT payload FROM inventory")?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let item = self.decode_inventory_item(&row.get::<_, Vec<u8>>(0)?)?;
+                if let Some(supplier_id) = item.supplier_id.filter(|id| !suppliers.contains(id)) {
+                    stale_suppliers.push((item.id, supplier_id));
+                }
+            }
+        }
+        push(OrphanKind::InventorySupplier, stale_suppliers);
+
+        push(
+            OrphanKind::PriceHistorySupplier,
+            query_pairs(
+                &conn,
+                "SELECT id, supplier_id FROM price_history WHERE supplier_id NOT IN (SELECT id FROM suppliers)",
+            )?,
+        );
+
+        // Schedules are created by the app, so the table may not exist yet
+        if table_exists(&conn, "dose_schedules")? {
+            push(
+                OrphanKind::ScheduleProtocol,
+                query_pairs(
+                    &conn,
+                    "SELECT id, protocol_id FROM dose_schedules WHERE protocol_id NOT IN (SELECT id FROM protocols)",
+                )?,
+            );
+            push(
+                OrphanKind::SkipSchedule,
+                query_pairs(
+                    &conn,
+                    "SELECT id, schedule_id FROM dose_skips WHERE schedule_id NOT IN (SELECT id FROM dose_schedules)",
+                )?,
+            );
+        }
+
+        let mut stale_alerts = Vec::new();
+        {
+            let mut stmt = conn.prepare("SELECT payload FROM alerts WHERE is_dismissed = 0")?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let alert = self.decode_alert(&row.get::<_, Vec<u8>>(0)?)?;
+                let tables = orphans::alert_subject_tables(&alert.alert_type);
+                let Some(related_id) = alert.related_id.filter(|_| !tables.is_empty()) else {
+                    continue;
+                };
+                let mut found = false;
+                for table in tables {
+                    found |= conn
+                        .prepare_cached(&format!("SELECT 1 FROM {} WHERE id = ?1", table))?
+                        .exists(params![related_id])?;
+                }
+                if !found {
+                    stale_alerts.push((alert.id, related_id));
+                }
+            }
+        }
+        push(OrphanKind::AlertSubject, stale_alerts);
+
+        Ok(orphans)
+    }
+
+    /// Clean up [`find_orphaned_records`](Self::find_orphaned_records) as
+    /// `policies` say, in a single transaction
+    ///
+    /// Orphaned price history, schedules and skipped doses are deleted; a
+    /// schedule's skipped doses go with it.
+    pub fn repair_orphaned_records(&self, policies: &CascadePolicies) -> Result<OrphanRepair> {
+        let orphans = self.find_orphaned_records()?;
+        let mut repair = OrphanRepair::default();
+        if orphans.is_empty() {
+            return Ok(repair);
+        }
+        let ids = |kind: OrphanKind| -> Vec<String> {
+            orphans
+                .iter()
+                .filter(|orphan| orphan.kind == kind)
+                .map(|orphan| orphan.record_id.clone())
+                .collect()
+        };
+
+        let conn = self.write_connection()?;
+        let tx = conn.unchecked_transaction()?;
+
+        let inventory = ids(OrphanKind::InventorySupplier);
+        match policies.supplier_inventory {
+            SupplierInventoryPolicy::Unlink => {
+                repair.inventory_unlinked = self.update_inventory_items(&tx, &inventory, |item| {
+                    item.supplier_id = None;
+                    true
+                })?;
+            }
+            SupplierInventoryPolicy::Delete => {
+                repair.inventory_deleted = self.delete_inventory_items(&tx, &inventory)?;
+            }
+        }
+
+        for id in ids(OrphanKind::PriceHistorySupplier) {
+            let rows = tx.execute("DELETE FROM price_history WHERE id = ?1", params![id])?;
+            if rows > 0 {
+                self.audit_delete(&tx, AuditEntityType::PriceHistory, &id)?;
+            }
+            repair.price_history_deleted += rows;
+        }
+
+        for id in ids(OrphanKind::ScheduleProtocol) {
+            repair.skips_deleted += tx.execute("DELETE FROM dose_skips WHERE schedule_id = ?1", params![id])?;
+            repair.schedules_deleted += tx.execute("DELETE FROM dose_schedules WHERE id = ?1", params![id])?;
+        }
+        for id in ids(OrphanKind::SkipSchedule) {
+            repair.skips_deleted += tx.execute("DELETE FROM dose_skips WHERE id = ?1", params![id])?;
+        }
+
+        for id in ids(OrphanKind::AlertSubject) {
+            match policies.stale_alerts {
+                StaleAlertPolicy::Dismiss => {
+                    let rows = tx.execute(
+                        "UPDATE alerts SET is_dismissed = 1 WHERE id = ?1 AND is_dismissed = 0",
+                        params![id],
+                    )?;
+                    if rows > 0 {
+                        self.record_audit(
+                            &tx,
+                            &AuditEntry::new(
+                                AuditEntityType::Alert,
+                                &id,
+                                AuditOperation::Update,
+                                vec!["is_dismissed".to_string()],
+                            )
+                            .with_summary("Its record was deleted"),
+                        )?;
+                    }
+                    repair.alerts_dismissed += rows;
+                }
+                StaleAlertPolicy::Delete => {
+                    let rows = tx.execute("DELETE FROM alerts WHERE id = ?1", params![id])?;
+                    if rows > 0 {
+                        self.audit_delete(&tx, AuditEntityType::Alert, &id)?;
+                    }
+                    repair.alerts_deleted += rows;
+                }
+            }
+        }
+
+        for table in ["inventory", "price_history", "dose_schedules", "dose_skips", "alerts"] {
+            self.invalidate_stats_on(&tx, table)?;
+        }
+        tx.commit()?;
 
-        Ok(())
+        info!("Repaired {} orphaned records", repair.total());
+        Ok(repair)
     }
 
-    pub fn list_alerts(&self, include_dismissed: bool) -> Result<Vec<Alert>> {
-        let conn = self.open_connection()?;
-
-        let query = if include_dismissed {
-            "SELECT payload FROM alerts ORDER BY created_at DESC"
-        } else {
-            "SELECT payload FROM alerts WHERE is_dismissed = 0 ORDER BY created_at DESC"
-        };
+    /// Delete a protocol's dose schedules before the protocol itself
+    ///
+    /// The app creates the schedules table, so it may not exist yet.
+    fn delete_protocol_schedules(&self, conn: &Connection, protocol_id: &str) -> Result<()> {
+        if table_exists(conn, "dose_schedules")? {
+            conn.execute("DELETE FROM dose_schedules WHERE protocol_id = ?1", params![protocol_id])
+                .context("Failed to delete protocol schedules")?;
+        }
+        Ok(())
+    }
 
-        let mut stmt = conn.prepare(query)?;
-        let mut rows = stmt
-            .query([])
-            .context("Unable to query alerts")?;
+    // Global search
 
-        let mut alerts = Vec::new();
-        while let Some(row) = rows.next()? {
-            let blob: Vec<u8> = row.get(0)?;
-            alerts.push(self.decode_alert(&blob)?);
+    /// Replace a record's entry in the search index
+    fn index_search_document(
+        &self,
+        conn: &Connection,
+        entity_type: SearchEntityType,
+        entity_id: &str,
+        document: &SearchDocument,
+    ) -> Result<()> {
+        self.remove_search_document(conn, entity_type, entity_id)?;
+
+        let terms = document.terms();
+        if terms.is_empty() {
+            return Ok(());
         }
-        Ok(alerts)
-    }
+        let tokens = self.encryption.blind_tokens(&terms)?.join(" ");
 
-    pub fn mark_alert_read(&self, alert_id: &str) -> Result<()> {
-        let conn = self.open_connection()?;
         conn.execute(
-            "UPDATE alerts SET is_read = 1 WHERE id = ?1",
-            params![alert_id],
+            "INSERT INTO search_index (entity_type, entity_id, tokens) VALUES (?1, ?2, ?3)",
+            params![serde_json::to_string(&entity_type)?, entity_id, tokens],
         )
-        .context("Failed to mark alert as read")?;
+        .context("Failed to update search index")?;
         Ok(())
     }
 
-    pub fn dismiss_alert(&self, alert_id: &str) -> Result<()> {
-        let conn = self.open_connection()?;
+    fn remove_search_document(
+        &self,
+        conn: &Connection,
+        entity_type: SearchEntityType,
+        entity_id: &str,
+    ) -> Result<()> {
         conn.execute(
-            "UPDATE alerts SET is_dismissed = 1 WHERE id = ?1",
-            params![alert_id],
+            "DELETE FROM search_index WHERE entity_type = ?1 AND entity_id = ?2",
+            params![serde_json::to_string(&entity_type)?, entity_id],
         )
-        .context("Failed to dismiss alert")?;
-        Ok(())
-    }
-
-    pub fn clear_all_alerts(&self) -> Result<()> {
-        let conn = self.open_connection()?;
-        conn.execute("DELETE FROM alerts", [])
-            .context("Failed to clear alerts")?;
+        .context("Failed to remove search index entry")?;
         Ok(())
     }
 
-    // Summary History CRUD operations
-
-    pub fn save_summary(&self, summary: &SummaryHistory) -> Result<()> {
-        let conn = self.open_connection()?;
-        let payload = serde_json::to_vec(summary).context("Failed to serialize summary")?;
-        let encrypted = self.encryption.seal(&payload)?;
-
+    /// Remove a protocol's entry and those of the dose logs and inventory
+    /// that are deleted with it
+    fn remove_protocol_search_entries(&self, conn: &Connection, protocol_id: &str) -> Result<()> {
         conn.execute(
             r#"
-            INSERT INTO summary_history (id, title, payload, created_at)
-            VALUES (?1, ?2, ?3, ?4)
+            DELETE FROM search_index
+            WHERE (entity_type = ?2 AND entity_id = ?1)
+               OR (entity_type = ?3 AND entity_id IN (SELECT id FROM dose_logs WHERE protocol_id = ?1))
+               OR (entity_type = ?4 AND entity_id IN (SELECT id FROM inventory WHERE protocol_id = ?1))
             "#,
             params![
-                summary.id,
-                summary.title,
-                encrypted,
-                summary.created_at.to_string()
+                protocol_id,
+                serde_json::to_string(&SearchEntityType::Protocol)?,
+                serde_json::to_string(&SearchEntityType::DoseLog)?,
+                serde_json::to_string(&SearchEntityType::InventoryItem)?
             ],
         )
-        .context("Failed to save summary")?;
-
+        .context("Failed to remove protocol search entries")?;
         Ok(())
     }
 
-    pub fn list_summary_history(&self, limit: Option<usize>) -> Result<Vec<SummaryHistory>> {
-        let conn = self.open_connection()?;
+    /// Search across protocols, dose notes, suppliers, inventory, summaries,
+    /// alerts and journal entries
+    ///
+    /// Every word in the query must match (as a word or word prefix); `#tag`
+    /// matches protocol and journal tags. Results are ordered by relevance.
+    pub fn search(
+        &self,
+        query: &str,
+        entity_types: Option<&[SearchEntityType]>,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>> {
+        let terms = search::query_terms(query);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
 
-        // Use parameterized query with LIMIT -1 for no limit (SQLite behavior)
-        let limit_value = limit.map(|l| l as i64).unwrap_or(-1);
+        let expression = self
+            .encryption
+            .blind_tokens(&terms)?
+            .iter()
+            .map(|token| format!("\"{}\"", token))
+            .collect::<Vec<_>>()
+            .join(" AND ");
 
-        let mut stmt = conn.prepare("SELECT payload FROM summary_history ORDER BY created_at DESC LIMIT ?1")?;
+        let conn = self.open_connection()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT entity_type, entity_id FROM search_index WHERE search_index MATCH ?1 ORDER BY rank",
+        )?;
         let mut rows = stmt
-            .query([limit_value])
-            .context("Unable to query summary history")?;
+            .query(params![expression])
+            .context("Unable to run search query")?;
 
-        let mut summaries = Vec::new();
+        let mut hits = Vec::new();
         while let Some(row) = rows.next()? {
-            let blob: Vec<u8> = row.get(0)?;
-            summaries.push(self.decode_summary_history(&blob)?);
+            let entity_type: String = row.get(0)?;
+            let entity_type: SearchEntityType = serde_json::from_str(&entity_type)
+                .context("Unknown entity type in search index")?;
+            if entity_types.is_some_and(|types| !types.contains(&entity_type)) {
+                continue;
+            }
+
+            hits.push(SearchHit {
+                entity_type,
+                entity_id: row.get(1)?,
+            });
+            if hits.len() >= limit {
+                break;
+            }
         }
-        Ok(summaries)
+        Ok(hits)
     }
 
-    pub fn delete_summary(&self, summary_id: &str) -> Result<()> {
-        let conn = self.open_connection()?;
-        conn.execute("DELETE FROM summary_history WHERE id = ?1", params![summary_id])
-            .context("Failed to delete summary")?;
-        Ok(())
+    /// Rebuild the search index from every searchable record
+    ///
+    /// Needed after the encryption key changes, since index tokens are keyed.
+    /// Returns the number of records indexed.
+    pub fn rebuild_search_index(&self) -> Result<usize> {
+        let mut documents = Vec::new();
+        for protocol in self.list_protocols()? {
+            documents.push((
+                SearchEntityType::Protocol,
+                protocol.id.clone(),
+                search::protocol_document(&protocol),
+            ));
+        }
+        for log in self.list_dose_logs()? {
+            documents.push((
+                SearchEntityType::DoseLog,
+                log.id.clone(),
+                search::dose_log_document(&log),
+            ));
+        }
+        for supplier in self.list_suppliers()? {
+            documents.push((
+                SearchEntityType::Supplier,
+                supplier.id.clone(),
+                search::supplier_document(&supplier),
+            ));
+        }
+        for item in self.list_inventory()? {
+            documents.push((
+                SearchEntityType::InventoryItem,
+                item.id.clone(),
+                search::inventory_document(&item),
+            ));
+        }
+        for summary in self.list_summary_history(None)? {
+            documents.push((
+                SearchEntityType::Summary,
+                summary.id.clone(),
+                search::summary_document(&summary),
+            ));
+        }
+        for alert in self.list_alerts(true)? {
+            documents.push((
+                SearchEntityType::Alert,
+                alert.id.clone(),
+                search::alert_document(&alert),
+            ));
+        }
+        for entry in self.list_journal_entries()? {
+            documents.push((
+                SearchEntityType::JournalEntry,
+                entry.id.clone(),
+                search::journal_document(&entry),
+            ));
+        }
+
+        let conn = self.write_connection()?;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM search_index", [])
+            .context("Failed to clear search index")?;
+        for (entity_type, entity_id, document) in &documents {
+            self.index_search_document(&tx, *entity_type, entity_id, document)?;
+        }
+        tx.commit()?;
+
+        Ok(documents.len())
     }
 
     // Decoder helper functions
@@ -1863,6 +7202,32 @@ impl StorageManager {
         Ok(entry)
     }
 
+    fn decode_price_observation(&self, blob: &[u8]) -> Result<PriceObservation> {
+        let decrypted = self.encryption.open(blob)?;
+        serde_json::from_slice(&decrypted).context("Failed to deserialize price observation")
+    }
+
+    fn decode_exchange_rate(&self, blob: &[u8]) -> Result<ExchangeRate> {
+        let decrypted = self.encryption.open(blob)?;
+        let rate: ExchangeRate =
+            serde_json::from_slice(&decrypted).context("Failed to deserialize exchange rate")?;
+        Ok(rate)
+    }
+
+    fn decode_attachment(&self, blob: &[u8]) -> Result<Attachment> {
+        let decrypted = self.encryption.open(blob)?;
+        let attachment: Attachment =
+            serde_json::from_slice(&decrypted).context("Failed to deserialize attachment")?;
+        Ok(attachment)
+    }
+
+    fn decode_order(&self, blob: &[u8]) -> Result<Order> {
+        let decrypted = self.encryption.open(blob)?;
+        let order: Order =
+            serde_json::from_slice(&decrypted).context("Failed to deserialize order")?;
+        Ok(order)
+    }
+
     fn decode_alert(&self, blob: &[u8]) -> Result<Alert> {
         let decrypted = self.encryption.open(blob)?;
         let alert: Alert =
@@ -1874,8 +7239,126 @@ impl StorageManager {
         let decrypted = self.encryption.open(blob)?;
         let summary: SummaryHistory =
             serde_json::from_slice(&decrypted).context("Failed to deserialize summary history")?;
-        Ok(summary)
+        Ok(summary.with_content_source())
+    }
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> bool {
+    conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get::<_, i64>(0),
+    )
+    .unwrap_or(0)
+        > 0
+}
+
+fn has_table(conn: &Connection, table: &str) -> bool {
+    conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1)",
+        params![table],
+        |row| row.get::<_, i64>(0),
+    )
+    .unwrap_or(0)
+        > 0
+}
+
+/// Rows of two text columns, e.g. a record id and the id it points at
+fn query_pairs(conn: &Connection, query: &str) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare(query)?;
+    let pairs = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(pairs)
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool> {
+    conn.prepare_cached("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1")?
+        .exists(params![table])
+        .context("Failed to look up table")
+}
+
+fn query_ids<P: rusqlite::Params>(conn: &Connection, query: &str, params: P) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(query)?;
+    let ids = stmt
+        .query_map(params, |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(ids)
+}
+
+fn write_key_recovery(conn: &Connection, recovery: &KeyRecovery) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT OR REPLACE INTO key_recovery (id, salt, kdf, sealed_key, created_at)
+        VALUES (1, ?1, ?2, ?3, ?4)
+        "#,
+        params![
+            recovery.salt,
+            serde_json::to_string(&recovery.kdf)?,
+            recovery.sealed_key,
+            recovery.created_at.unix_timestamp(),
+        ],
+    )
+    .context("Failed to save recovery code")?;
+    Ok(())
+}
+
+fn has_tamper_key(conn: &Connection) -> Result<bool> {
+    if !table_exists(conn, "tamper_key")? {
+        return Ok(false);
     }
+    conn.prepare_cached("SELECT 1 FROM tamper_key WHERE id = 1")?
+        .exists([])
+        .context("Failed to look up tamper evidence key")
+}
+
+/// Each table in [`CHAINED_TABLES`] with its encrypted columns
+fn tamper_columns() -> Vec<(&'static str, Vec<&'static str>)> {
+    CHAINED_TABLES
+        .iter()
+        .map(|&table| {
+            let columns = ENCRYPTED_COLUMNS
+                .iter()
+                .filter(|(encrypted_table, _)| *encrypted_table == table)
+                .map(|&(_, column)| column)
+                .collect();
+            (table, columns)
+        })
+        .collect()
+}
+
+/// Merge the WAL, `VACUUM`, then truncate the WAL the vacuum wrote
+/// The WAL file SQLite keeps next to `db_path`
+fn wal_path(db_path: &Path) -> PathBuf {
+    let mut name = db_path.as_os_str().to_owned();
+    name.push("-wal");
+    PathBuf::from(name)
+}
+
+fn compact_connection(conn: &Connection) -> Result<()> {
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_row| Ok(()))
+        .context("Failed to checkpoint WAL")?;
+    conn.execute_batch("VACUUM").context("Failed to vacuum database")?;
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_row| Ok(()))
+        .context("Failed to checkpoint WAL")?;
+    Ok(())
+}
+
+fn decode_audit_entry(row: &Row) -> Result<AuditEntry> {
+    let entity_type: String = row.get(1)?;
+    let operation: String = row.get(3)?;
+    let changed_fields: String = row.get(4)?;
+    let occurred_at: i64 = row.get(6)?;
+
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        entity_type: serde_json::from_str(&entity_type).context("Unknown entity type in audit log")?,
+        entity_id: row.get(2)?,
+        operation: serde_json::from_str(&operation).context("Unknown operation in audit log")?,
+        changed_fields: serde_json::from_str(&changed_fields).unwrap_or_default(),
+        summary: row.get(5)?,
+        occurred_at: OffsetDateTime::from_unix_timestamp(occurred_at)?,
+    })
 }
 
 pub fn now_timestamp() -> OffsetDateTime {
@@ -1886,7 +7369,7 @@ pub fn now_timestamp() -> OffsetDateTime {
 mod tests {
     use super::*;
     use crate::models::*;
-    use crate::StaticKeyProvider;
+    use crate::{KeyMaterial, StaticKeyProvider};
     use tempfile::tempdir;
 
     // Test helper to create a storage manager with a temp database
@@ -1974,6 +7457,50 @@ mod tests {
         assert_eq!(fetched[0].notes.as_deref(), Some("New notes"));
     }
 
+    #[test]
+    fn transaction_saves_all_writes_or_none() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Bundle", "BPC-157");
+        let item = InventoryItem::new(&protocol.id);
+
+        let failed: Result<()> = storage.transaction(|tx| {
+            tx.upsert_protocol(&protocol)?;
+            tx.upsert_inventory_item(&item)?;
+            anyhow::bail!("schedule was invalid")
+        });
+        assert!(failed.is_err());
+        assert!(storage.list_protocols().unwrap().is_empty());
+        assert!(storage.list_inventory().unwrap().is_empty());
+
+        let saved = storage
+            .transaction(|tx| {
+                tx.upsert_protocol(&protocol)?;
+                tx.upsert_inventory_item(&item)?;
+                Ok(item.id.clone())
+            })
+            .expect("commit");
+        assert_eq!(saved, item.id);
+        assert_eq!(storage.list_protocols().unwrap().len(), 1);
+        assert_eq!(storage.list_inventory_by_protocol(&protocol.id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn transaction_rolls_back_restored_records() {
+        let storage = create_test_storage();
+        let now = OffsetDateTime::now_utc();
+
+        let failed: Result<()> = storage.transaction(|tx| {
+            tx.cache_literature(&LiteratureEntry::new("pubmed", "BPC-157 and tendon healing"))?;
+            tx.upsert_body_metric(&BodyMetric::new(now))?;
+            tx.upsert_journal_entry(&JournalEntry::new("Slept well", now))?;
+            anyhow::bail!("attachment was invalid")
+        });
+        assert!(failed.is_err());
+        assert!(storage.list_literature().unwrap().is_empty());
+        assert!(storage.list_body_metrics().unwrap().is_empty());
+        assert!(storage.list_journal_entries().unwrap().is_empty());
+    }
+
     // =============================================================================
     // Dose Log Tests
     // =============================================================================
@@ -2017,47 +7544,539 @@ mod tests {
     }
 
     #[test]
-    fn delete_dose_log_removes_log() {
+    fn delete_dose_log_removes_log() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Test Protocol", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert protocol");
+
+        let dose = DoseLog::new(&protocol.id, &"Site".to_string(), 0.5);
+        let dose_id = dose.id.clone();
+        storage.append_dose_log(&dose).expect("append dose");
+
+        storage.delete_dose_log(&dose_id).expect("delete dose");
+
+        let doses = storage.list_dose_logs().expect("list doses");
+        assert_eq!(doses.len(), 0);
+    }
+
+    #[test]
+    fn dose_aggregates_use_index_columns() {
+        let storage = create_test_storage();
+        let first = PeptideProtocol::new("First", "BPC-157");
+        let second = PeptideProtocol::new("Second", "TB-500");
+        storage.upsert_protocol(&first).expect("upsert protocol");
+        storage.upsert_protocol(&second).expect("upsert protocol");
+
+        let start = time::macros::datetime!(2024-03-01 9:00 UTC);
+        for (protocol, site, mg, day, schedule) in [
+            (&first, "Left Abdomen", 0.25, 0, Some("morning")),
+            (&first, "left abdomen", 0.25, 0, None),
+            (&first, "Right Thigh", 0.5, 1, Some("morning")),
+            (&second, "Deltoid", 2.0, 2, None),
+        ] {
+            let mut dose = DoseLog::new(protocol.id.as_str(), site, mg);
+            dose.logged_at = start + time::Duration::days(day);
+            dose.schedule_id = schedule.map(String::from);
+            storage.append_dose_log(&dose).expect("append dose");
+        }
+        let mut trashed = DoseLog::new(first.id.as_str(), "Glute", 9.0);
+        trashed.logged_at = start;
+        storage.append_dose_log(&trashed).expect("append dose");
+        storage
+            .move_to_trash(TrashEntityType::DoseLog, &[trashed.id.clone()])
+            .expect("trash dose");
+
+        let all = DoseStatsFilter::default();
+        let usage = storage.dose_usage_by_protocol(&all).expect("usage");
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].protocol_id, second.id);
+        assert_eq!(usage[1].dose_count, 3);
+        assert_eq!(usage[1].total_mg, 1.0);
+        assert_eq!(usage[1].active_days, 2);
+
+        let sites = storage.dose_usage_by_site(&all).expect("sites");
+        assert_eq!(sites[0].site_code, "left_abdomen");
+        assert_eq!(sites[0].dose_count, 2);
+        assert_eq!(sites.len(), 3);
+
+        let filter = DoseStatsFilter {
+            schedule_id: Some("morning".into()),
+            since: Some(time::macros::date!(2024-03-02)),
+            ..Default::default()
+        };
+        let daily = storage.daily_dose_totals(&filter).expect("daily");
+        assert_eq!(daily.len(), 1);
+        assert_eq!(daily[0].date, "2024-03-02");
+        assert_eq!(daily[0].total_mg, 0.5);
+    }
+
+    #[test]
+    fn append_dose_logs_writes_all_or_nothing() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Morning", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert protocol");
+        let week: Vec<DoseLog> = (0..7)
+            .map(|day| {
+                let mut dose = DoseLog::new(protocol.id.as_str(), "Abdomen", 0.25);
+                dose.logged_at -= time::Duration::days(day);
+                dose
+            })
+            .collect();
+        assert_eq!(storage.append_dose_logs(&week).expect("append week"), 7);
+        assert_eq!(storage.list_dose_logs().expect("list").len(), 7);
+
+        let valid = DoseLog::new(protocol.id.as_str(), "Thigh", 0.5);
+        let orphan = DoseLog::new("no-such-protocol", "Thigh", 0.5);
+        assert!(storage.append_dose_logs(&[valid.clone(), orphan]).is_err());
+        assert!(storage.get_dose_log(&valid.id).expect("get").is_none());
+        assert_eq!(storage.list_dose_logs().expect("list").len(), 7);
+    }
+
+    #[test]
+    fn update_dose_log_keeps_the_original_and_flags_the_edit() {
+        let storage = create_test_storage();
+        let first = PeptideProtocol::new("Morning", "BPC-157");
+        let second = PeptideProtocol::new("Evening", "TB-500");
+        storage.upsert_protocol(&first).expect("upsert protocol");
+        storage.upsert_protocol(&second).expect("upsert protocol");
+        let dose = DoseLog::new(first.id.as_str(), "Left Abdomen", 0.25);
+        storage.append_dose_log(&dose).expect("append dose");
+        let other = DoseLog::new(first.id.as_str(), "Thigh", 1.0);
+        storage.append_dose_log(&other).expect("append dose");
+
+        let unchanged = storage.update_dose_log(&dose).expect("save unchanged");
+        assert!(unchanged.edited_at.is_none());
+
+        let mut edit = dose.clone();
+        edit.protocol_id = second.id.clone();
+        edit.amount_mg = 0.5;
+        edit.site = "Right Deltoid".into();
+        let saved = storage.update_dose_log(&edit).expect("update dose");
+        assert!(saved.edited_at.is_some());
+        let mut again = saved.clone();
+        again.notes = Some("Typo".into());
+        storage.update_dose_log(&again).expect("update dose again");
+
+        let corrections = storage.list_dose_log_corrections(&dose.id).expect("corrections");
+        assert_eq!(corrections.len(), 2);
+        assert_eq!(corrections[0].original.amount_mg, 0.25);
+        assert_eq!(corrections[0].original.protocol_id, first.id);
+        assert!(corrections[0].original.edited_at.is_none());
+        assert_eq!(corrections[1].original.amount_mg, 0.5);
+
+        // Index columns follow the edit
+        assert_eq!(storage.list_dose_logs_for_protocol(&second.id).expect("list").len(), 1);
+        let sites = storage.dose_usage_by_site(&DoseStatsFilter::default()).expect("sites");
+        assert!(sites.iter().any(|site| site.site_code == "right_deltoid"));
+        let unedited = storage
+            .dose_usage_by_protocol(&DoseStatsFilter {
+                exclude_edited: true,
+                ..Default::default()
+            })
+            .expect("usage");
+        assert_eq!(unedited.len(), 1);
+        assert_eq!(unedited[0].total_mg, 1.0);
+
+        let conn = storage.connection().expect("connection");
+        assert!(conn
+            .execute("UPDATE dose_log_corrections SET corrected_at = 0", [])
+            .is_err());
+        drop(conn);
+
+        let mut missing = DoseLog::new(first.id.as_str(), "Thigh", 1.0);
+        missing.id = "missing".into();
+        assert!(storage.update_dose_log(&missing).is_err());
+    }
+
+    #[test]
+    fn dose_skips_are_recorded_once_per_schedule_and_day() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Morning", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert protocol");
+        let monday = Date::from_calendar_date(2025, time::Month::January, 6).unwrap();
+
+        let skip = DoseSkip::new(protocol.id.as_str(), "morning", monday);
+        assert!(storage.record_dose_skip(&skip).expect("skip"));
+        assert!(!storage
+            .record_dose_skip(&DoseSkip::new(protocol.id.as_str(), "morning", monday))
+            .expect("skip again"));
+        let tuesday = monday.next_day().unwrap();
+        assert!(storage
+            .record_dose_skip(&DoseSkip::new(protocol.id.as_str(), "morning", tuesday))
+            .expect("skip next day"));
+
+        let all = storage.list_dose_skips(&DoseStatsFilter::default()).expect("list");
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].id, skip.id);
+        assert_eq!(all[0].scheduled_on, monday);
+        let later = storage
+            .list_dose_skips(&DoseStatsFilter {
+                since: Some(tuesday),
+                ..Default::default()
+            })
+            .expect("list since");
+        assert_eq!(later.len(), 1);
+    }
+
+    #[test]
+    fn migration_backfills_dose_index_columns() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Test Protocol", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert protocol");
+        let mut dose = DoseLog::new(protocol.id.as_str(), "Right Glute", 0.75);
+        dose.schedule_id = Some("evening".into());
+        storage.append_dose_log(&dose).expect("append dose");
+
+        // Back to the schema from before the index columns existed
+        storage
+            .connection()
+            .expect("connection")
+            .execute_batch(
+                "DROP INDEX idx_dose_logs_schedule;
+                 ALTER TABLE dose_logs DROP COLUMN amount_mg;
+                 ALTER TABLE dose_logs DROP COLUMN site_code;
+                 ALTER TABLE dose_logs DROP COLUMN schedule_id;",
+            )
+            .expect("downgrade schema");
+        storage.initialize().expect("migrate");
+
+        let filter = DoseStatsFilter {
+            schedule_id: Some("evening".into()),
+            ..Default::default()
+        };
+        let sites = storage.dose_usage_by_site(&filter).expect("sites");
+        assert_eq!(sites.len(), 1);
+        assert_eq!(sites[0].site_code, "right_glute");
+        assert_eq!(sites[0].total_mg, 0.75);
+    }
+
+    #[test]
+    fn list_dose_logs_page_applies_limit_offset_and_since() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Test Protocol", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert protocol");
+
+        // One dose a day at 9:00 and 15:00 so times of day sort differently as text
+        let start = time::macros::datetime!(2024-03-01 9:00 UTC);
+        for day in 0..5 {
+            for hours in [0, 6] {
+                let mut dose = DoseLog::new(&protocol.id, &format!("Day {}", day), 1.0);
+                dose.logged_at = start + time::Duration::days(day) + time::Duration::hours(hours);
+                storage.append_dose_log(&dose).expect("append dose");
+            }
+        }
+
+        let page = storage
+            .list_dose_logs_page(&ListOptions::page(3, 2))
+            .expect("list page");
+        assert_eq!(page.len(), 3);
+        assert_eq!(page[0].site, "Day 3");
+
+        let since = start + time::Duration::days(3) + time::Duration::hours(3);
+        let recent = storage
+            .list_dose_logs_page(&ListOptions::since(since))
+            .expect("list since");
+        assert_eq!(recent.len(), 3);
+        assert!(recent.iter().all(|dose| dose.logged_at >= since));
+
+        let recent_page = storage
+            .list_dose_logs_page(&ListOptions {
+                limit: Some(1),
+                offset: 1,
+                since: Some(since),
+            })
+            .expect("list since page");
+        assert_eq!(recent_page.len(), 1);
+        assert_eq!(recent_page[0].id, recent[1].id);
+
+        let for_protocol = storage
+            .list_dose_logs_for_protocol_page(&protocol.id, &ListOptions::page(4, 0))
+            .expect("list protocol page");
+        assert_eq!(for_protocol.len(), 4);
+    }
+
+    #[test]
+    fn storage_calls_reuse_pooled_connections() {
+        let storage = create_test_storage();
+        let idle = storage.connections.idle_count();
+        assert!(idle >= 1);
+
+        storage.list_protocols().expect("list protocols");
+        storage.list_dose_logs().expect("list doses");
+        assert_eq!(storage.connections.idle_count(), idle);
+    }
+
+    #[test]
+    fn delete_dose_log_with_nonexistent_id_succeeds() {
+        let storage = create_test_storage();
+        // Deleting a non-existent dose should not error (SQL DELETE with no matches)
+        storage
+            .delete_dose_log("nonexistent-id")
+            .expect("delete nonexistent");
+    }
+
+    // =============================================================================
+    // Literature Cache Tests
+    // =============================================================================
+
+    #[test]
+    fn cache_literature_and_list_roundtrips() {
+        let storage = create_test_storage();
+        let mut entry = LiteratureEntry::new("pubmed", "BPC-157 Research Paper");
+        entry.url = Some("https://pubmed.ncbi.nlm.nih.gov/12345/".to_string());
+        entry.summary = Some("This paper discusses BPC-157.".to_string());
+
+        storage.cache_literature(&entry).expect("cache literature");
+
+        let entries = storage.list_literature().expect("list literature");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].title, "BPC-157 Research Paper");
+        assert_eq!(entries[0].source, "pubmed");
+    }
+
+    #[test]
+    fn list_literature_tagged_filters_and_ranks() {
+        let storage = create_test_storage();
+        let mut low = LiteratureEntry::new("pubmed", "Low");
+        low.relevance_score = Some(0.2);
+        low.tags = vec!["peptide:bpc-157".into(), "study:review".into()];
+        let mut high = LiteratureEntry::new("openalex", "High");
+        high.relevance_score = Some(0.9);
+        high.tags = vec!["peptide:bpc-157".into()];
+        let untagged = LiteratureEntry::new("crossref", "Untagged");
+        for entry in [&low, &high, &untagged] {
+            storage.cache_literature(entry).expect("cache literature");
+        }
+
+        let titles = |tags: &[String]| -> Vec<String> {
+            storage
+                .list_literature_tagged(tags)
+                .expect("list")
+                .into_iter()
+                .map(|entry| entry.title)
+                .collect()
+        };
+        assert_eq!(titles(&[]), vec!["High", "Low", "Untagged"]);
+        assert_eq!(titles(&["peptide:bpc-157".into()]), vec!["High", "Low"]);
+        assert_eq!(titles(&["peptide:bpc-157".into(), "study:review".into()]), vec!["Low"]);
+
+        assert_eq!(
+            storage.literature_tag_counts().expect("tags"),
+            vec![("peptide:bpc-157".to_string(), 2), ("study:review".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn literature_needing_enrichment_skips_complete_and_tried_entries() {
+        let storage = create_test_storage();
+        let missing = LiteratureEntry::new("pubmed", "Missing");
+        let mut complete = LiteratureEntry::new("crossref", "Complete");
+        complete.doi = Some("10.1000/xyz".into());
+        complete.authors = vec!["Jane Doe".into()];
+        complete.journal = Some("Peptides".into());
+        complete.year = Some(2022);
+        let mut tried = LiteratureEntry::new("openalex", "Tried");
+        tried.enriched_at = Some(OffsetDateTime::now_utc());
+        for entry in [&missing, &complete, &tried] {
+            storage.cache_literature(entry).expect("cache literature");
+        }
+
+        let pending = storage.list_literature_needing_enrichment(10).expect("list");
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, missing.id);
+        assert!(storage.list_literature_needing_enrichment(0).expect("list").is_empty());
+    }
+
+    #[test]
+    fn retraction_check_skips_retracted_and_recently_checked() {
+        let storage = create_test_storage();
+        let now = OffsetDateTime::now_utc();
+        let mut never = LiteratureEntry::new("pubmed", "Never checked");
+        never.url = Some("https://pubmed.ncbi.nlm.nih.gov/1/".into());
+        let mut stale = LiteratureEntry::new("crossref", "Checked long ago");
+        stale.doi = Some("10.1000/stale".into());
+        stale.retraction_checked_at = Some(now - time::Duration::days(30));
+        let mut fresh = stale.clone();
+        fresh.id = "fresh".into();
+        fresh.retraction_checked_at = Some(now);
+        let mut retracted = stale.clone();
+        retracted.id = "retracted".into();
+        retracted.retracted = true;
+        let unlinked = LiteratureEntry::new("manual", "No DOI or URL");
+        for entry in [&never, &stale, &fresh, &retracted, &unlinked] {
+            storage.cache_literature(entry).expect("cache literature");
+        }
+
+        let due = storage
+            .list_literature_for_retraction_check(now - time::Duration::days(7), 10)
+            .expect("list");
+        let ids: Vec<&str> = due.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, vec![never.id.as_str(), stale.id.as_str()]);
+    }
+
+    #[test]
+    fn annotate_summary_adds_each_notice_once() {
+        let storage = create_test_storage();
+        let summary = SummaryHistory::new("Title", "Content", "Summary", "markdown", "ollama");
+        storage.save_summary(&summary).expect("save summary");
+
+        assert!(storage.annotate_summary(&summary.id, "Cites a retracted paper").expect("annotate"));
+        assert!(!storage.annotate_summary(&summary.id, "Cites a retracted paper").expect("annotate"));
+        let loaded = storage.get_summary(&summary.id).expect("get").expect("exists");
+        assert_eq!(loaded.notices, vec!["Cites a retracted paper"]);
+        assert!(storage.annotate_summary("missing", "note").is_err());
+    }
+
+    #[test]
+    fn clinical_trials_roundtrip_and_filter_by_protocol() {
         let storage = create_test_storage();
-        let protocol = PeptideProtocol::new("Test Protocol", "BPC-157");
-        storage.upsert_protocol(&protocol).expect("upsert protocol");
+        let mut linked = TrialResult {
+            nct_id: "NCT00000001".into(),
+            title: "BPC-157 in tendinopathy".into(),
+            phase: Some("Phase 2".into()),
+            status: "RECRUITING".into(),
+            enrollment: Some(40),
+            has_results: false,
+            conditions: vec!["Tendinopathy".into()],
+            interventions: vec!["BPC-157".into()],
+            sponsor: None,
+            start_date: Some("2024-01".into()),
+            completion_date: None,
+            protocol_ids: vec!["protocol-1".into()],
+            fetched_at: OffsetDateTime::now_utc(),
+        };
+        storage.cache_clinical_trial(&linked).expect("cache");
+        let mut other = linked.clone();
+        other.nct_id = "NCT00000002".into();
+        other.protocol_ids.clear();
+        storage.cache_clinical_trial(&other).expect("cache");
+
+        assert_eq!(storage.list_clinical_trials(None).expect("list").len(), 2);
+        let for_protocol = storage.list_clinical_trials(Some("protocol-1")).expect("list");
+        assert_eq!(for_protocol, vec![linked.clone()]);
+
+        linked.has_results = true;
+        storage.cache_clinical_trial(&linked).expect("update");
+        assert!(storage.get_clinical_trial("NCT00000001").expect("get").expect("cached").has_results);
+
+        storage.delete_clinical_trial("NCT00000002").expect("delete");
+        assert!(storage.get_clinical_trial("NCT00000002").expect("get").is_none());
+        assert!(storage.delete_clinical_trial("NCT00000002").is_err());
+    }
 
-        let dose = DoseLog::new(&protocol.id, &"Site".to_string(), 0.5);
-        let dose_id = dose.id.clone();
-        storage.append_dose_log(&dose).expect("append dose");
+    #[test]
+    fn saved_searches_roundtrip_and_report_due() {
+        let storage = create_test_storage();
+        let mut weekly = SavedSearch::new("Tendon", "BPC-157 tendon");
+        let mut paused = SavedSearch::new("Paused", "TB-500");
+        paused.enabled = false;
+        storage.upsert_saved_search(&weekly).expect("save");
+        storage.upsert_saved_search(&paused).expect("save");
+
+        let now = OffsetDateTime::now_utc();
+        let due: Vec<String> = storage.due_saved_searches(now).expect("due").into_iter().map(|s| s.id).collect();
+        assert_eq!(due, vec![weekly.id.clone()]);
+
+        weekly.last_run_at = Some(now);
+        weekly.seen_keys = vec!["doi:10.1000/xyz".into()];
+        storage.upsert_saved_search(&weekly).expect("update");
+        assert!(storage.due_saved_searches(now).expect("due").is_empty());
+        assert_eq!(
+            storage.due_saved_searches(now + time::Duration::days(7)).expect("due").len(),
+            1
+        );
 
-        storage.delete_dose_log(&dose_id).expect("delete dose");
+        let loaded = storage.get_saved_search(&weekly.id).expect("get").expect("exists");
+        assert_eq!(loaded.seen_keys, weekly.seen_keys);
+        assert_eq!(storage.list_saved_searches().expect("list").len(), 2);
 
-        let doses = storage.list_dose_logs().expect("list doses");
-        assert_eq!(doses.len(), 0);
+        storage.delete_saved_search(&paused.id).expect("delete");
+        assert!(storage.get_saved_search(&paused.id).expect("get").is_none());
+        assert!(storage.delete_saved_search(&paused.id).is_err());
     }
 
     #[test]
-    fn delete_dose_log_with_nonexistent_id_succeeds() {
+    fn literature_embeddings_roundtrip_per_model() {
         let storage = create_test_storage();
-        // Deleting a non-existent dose should not error (SQL DELETE with no matches)
+        let entry = LiteratureEntry::new("pubmed", "BPC-157 and Tendon Healing");
+        storage.cache_literature(&entry).expect("cache");
+
+        let mut embedding = LiteratureEmbedding {
+            entry_id: entry.id.clone(),
+            model: "nomic-embed-text".into(),
+            content_hash: LiteratureEmbedding::content_hash(&entry.embedding_text()),
+            vector: vec![0.25, -1.5, 3.0],
+        };
+        storage.upsert_literature_embedding(&embedding).expect("save");
+        embedding.vector = vec![1.0, 2.0, 3.0];
+        storage.upsert_literature_embedding(&embedding).expect("replace");
+        // Embeddings of papers no longer cached are ignored
         storage
-            .delete_dose_log("nonexistent-id")
-            .expect("delete nonexistent");
-    }
+            .upsert_literature_embedding(&LiteratureEmbedding {
+                entry_id: "gone".into(),
+                ..embedding.clone()
+            })
+            .expect("save");
 
-    // =============================================================================
-    // Literature Cache Tests
-    // =============================================================================
+        assert_eq!(storage.list_literature_embeddings("nomic-embed-text").expect("list"), vec![embedding]);
+        assert!(storage.list_literature_embeddings("other").expect("list").is_empty());
+    }
 
     #[test]
-    fn cache_literature_and_list_roundtrips() {
+    fn prune_literature_cache_keeps_pinned_and_reading_list() {
         let storage = create_test_storage();
-        let mut entry = LiteratureEntry::new("pubmed", "BPC-157 Research Paper");
-        entry.url = Some("https://pubmed.ncbi.nlm.nih.gov/12345/".to_string());
-        entry.summary = Some("This paper discusses BPC-157.".to_string());
-
-        storage.cache_literature(&entry).expect("cache literature");
+        let now = OffsetDateTime::now_utc();
+        let mut old = LiteratureEntry::new("pubmed", "Old paper");
+        old.indexed_at = now - time::Duration::days(90);
+        let mut pinned = LiteratureEntry::new("pubmed", "Old pinned paper");
+        pinned.indexed_at = now - time::Duration::days(90);
+        pinned.is_pinned = true;
+        let mut reading = LiteratureEntry::new("pubmed", "Old paper being read");
+        reading.indexed_at = now - time::Duration::days(90);
+        reading.reading_status = Some(ReadingStatus::Reading);
+        let mut recent = LiteratureEntry::new("pubmed", "Recent paper");
+        recent.indexed_at = now - time::Duration::days(1);
+        let newest = LiteratureEntry::new("pubmed", "Newest paper");
+        for entry in [&old, &pinned, &reading, &recent, &newest] {
+            storage.cache_literature(entry).expect("cache literature");
+        }
+        storage
+            .upsert_literature_embedding(&LiteratureEmbedding {
+                entry_id: old.id.clone(),
+                model: "nomic-embed-text".into(),
+                content_hash: LiteratureEmbedding::content_hash(&old.embedding_text()),
+                vector: vec![1.0],
+            })
+            .expect("save embedding");
 
-        let entries = storage.list_literature().expect("list literature");
-        assert_eq!(entries.len(), 1);
-        assert_eq!(entries[0].title, "BPC-157 Research Paper");
-        assert_eq!(entries[0].source, "pubmed");
+        assert_eq!(storage.prune_literature_cache(&LiteratureRetention::default()).expect("prune"), 0);
+        let retention = LiteratureRetention {
+            max_age_days: Some(30),
+            max_entries: None,
+        };
+        assert_eq!(storage.prune_literature_cache(&retention).expect("prune"), 1);
+        assert!(storage.get_literature(&old.id).expect("get").is_none());
+        assert!(storage.get_literature(&pinned.id).expect("get").is_some());
+        assert!(storage.get_literature(&reading.id).expect("get").is_some());
+        let conn = storage.open_connection().expect("connection");
+        let embeddings: i64 = conn
+            .query_row("SELECT COUNT(*) FROM literature_embeddings", [], |row| row.get(0))
+            .expect("count");
+        assert_eq!(embeddings, 0);
+
+        let retention = LiteratureRetention {
+            max_age_days: None,
+            max_entries: Some(1),
+        };
+        assert_eq!(storage.prune_literature_cache(&retention).expect("prune"), 1);
+        let titles: Vec<String> = storage
+            .list_literature()
+            .expect("list")
+            .into_iter()
+            .map(|entry| entry.title)
+            .collect();
+        assert_eq!(titles.len(), 3);
+        assert!(!titles.contains(&"Recent paper".to_string()));
     }
 
     #[test]
@@ -2224,6 +8243,158 @@ mod tests {
         assert_eq!(items.len(), 0);
     }
 
+    #[test]
+    fn bulk_inventory_updates_skip_unchanged_items() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Test", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert protocol");
+        let supplier = Supplier::new("Acme");
+        storage.upsert_supplier(&supplier).expect("upsert supplier");
+
+        let sealed = InventoryItem::new(&protocol.id);
+        let mut opened = InventoryItem::new(&protocol.id);
+        opened.vial_status = VialStatus::Opened;
+        storage.upsert_inventory_item(&sealed).expect("upsert");
+        storage.upsert_inventory_item(&opened).expect("upsert");
+        let ids = vec![sealed.id.clone(), opened.id.clone(), "missing".to_string()];
+
+        let updated = storage.bulk_update_inventory_status(&ids, VialStatus::Opened).expect("update status");
+        assert_eq!(updated, 1);
+        let fetched = storage.get_inventory_item(&sealed.id).expect("get").expect("item");
+        assert_eq!(fetched.vial_status, VialStatus::Opened);
+
+        assert_eq!(storage.bulk_assign_supplier(&ids, Some(&supplier.id)).expect("assign"), 2);
+        assert_eq!(storage.bulk_assign_supplier(&ids, Some(&supplier.id)).expect("assign again"), 0);
+        assert!(storage.bulk_assign_supplier(&ids, Some("missing")).is_err());
+
+        assert_eq!(storage.bulk_delete_inventory_items(&ids).expect("delete"), 2);
+        assert!(storage.list_inventory().expect("list").is_empty());
+    }
+
+    #[test]
+    fn bulk_delete_suppliers_removes_prices_and_unlinks_inventory() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Test", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert protocol");
+        let supplier = Supplier::new("Acme");
+        let kept = Supplier::new("Other");
+        storage.upsert_supplier(&supplier).expect("upsert supplier");
+        storage.upsert_supplier(&kept).expect("upsert supplier");
+
+        let mut item = InventoryItem::new(&protocol.id);
+        item.supplier_id = Some(supplier.id.clone());
+        storage.upsert_inventory_item(&item).expect("upsert item");
+        for supplier_id in [&supplier.id, &supplier.id, &kept.id] {
+            let price = PriceHistory::new(supplier_id, &"BPC-157".to_string(), 2.5);
+            storage.add_price_history(&price).expect("add price");
+        }
+
+        let deletion = storage.bulk_delete_suppliers(std::slice::from_ref(&supplier.id)).expect("delete suppliers");
+        assert_eq!(
+            deletion,
+            SupplierDeletion { suppliers_deleted: 1, price_history_deleted: 2, inventory_unlinked: 1, inventory_deleted: 0 }
+        );
+
+        let item = storage.get_inventory_item(&item.id).expect("get").expect("item kept");
+        assert!(item.supplier_id.is_none());
+        assert_eq!(storage.list_suppliers().expect("list").len(), 1);
+        assert_eq!(
+            storage.list_price_history_for_supplier(&kept.id, None).expect("prices").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn supplier_inventory_policy_can_delete_vials() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Test", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert protocol");
+        let supplier = Supplier::new("Acme");
+        storage.upsert_supplier(&supplier).expect("upsert supplier");
+        let mut item = InventoryItem::new(&protocol.id);
+        item.supplier_id = Some(supplier.id.clone());
+        storage.upsert_inventory_item(&item).expect("upsert item");
+        storage
+            .save_setting(&CascadePolicies {
+                supplier_inventory: SupplierInventoryPolicy::Delete,
+                ..CascadePolicies::default()
+            })
+            .expect("save policies");
+
+        storage.delete_supplier(&supplier.id).expect("delete supplier");
+        assert!(storage.get_inventory_item(&item.id).expect("get").is_none());
+    }
+
+    #[test]
+    fn orphaned_records_are_found_and_repaired() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Test", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert protocol");
+        let supplier = Supplier::new("Acme");
+        storage.upsert_supplier(&supplier).expect("upsert supplier");
+        let mut item = InventoryItem::new(&protocol.id);
+        item.supplier_id = Some(supplier.id.clone());
+        storage.upsert_inventory_item(&item).expect("upsert item");
+        let mut stale = Alert::new(AlertType::ExpiringSoon, AlertSeverity::Warning, "Expiring", "Use it soon");
+        stale.related_id = Some("deleted-vial".to_string());
+        storage.create_alert(&stale).expect("create alert");
+        let mut current = Alert::new(AlertType::ExpiringSoon, AlertSeverity::Warning, "Expiring", "Use it soon");
+        current.related_id = Some(item.id.clone());
+        storage.create_alert(&current).expect("create alert");
+
+        {
+            // Bypass the cascade, as deletes did before it existed
+            let conn = storage.connection().unwrap();
+            conn.execute("DELETE FROM suppliers WHERE id = ?1", params![supplier.id]).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE dose_schedules (id TEXT PRIMARY KEY, protocol_id TEXT NOT NULL);
+                 INSERT INTO dose_schedules VALUES ('gone', 'deleted-protocol');",
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO dose_skips (id, protocol_id, schedule_id, scheduled_on, skipped_at)
+                 VALUES ('skip', ?1, 'deleted-schedule', '2024-01-01', 0)",
+                params![protocol.id],
+            )
+            .unwrap();
+        }
+
+        let mut kinds: Vec<OrphanKind> = storage
+            .find_orphaned_records()
+            .expect("find")
+            .into_iter()
+            .map(|orphan| orphan.kind)
+            .collect();
+        kinds.sort_by_key(|kind| format!("{:?}", kind));
+        assert_eq!(
+            kinds,
+            vec![
+                OrphanKind::AlertSubject,
+                OrphanKind::InventorySupplier,
+                OrphanKind::ScheduleProtocol,
+                OrphanKind::SkipSchedule,
+            ]
+        );
+
+        let repair = storage.repair_orphaned_records(&CascadePolicies::default()).expect("repair");
+        assert_eq!(
+            repair,
+            OrphanRepair {
+                inventory_unlinked: 1,
+                schedules_deleted: 1,
+                skips_deleted: 1,
+                alerts_dismissed: 1,
+                ..OrphanRepair::default()
+            }
+        );
+        assert!(storage.find_orphaned_records().expect("find").is_empty());
+        let item = storage.get_inventory_item(&item.id).expect("get").expect("item kept");
+        assert!(item.supplier_id.is_none());
+        let alerts = storage.list_alerts(false).expect("alerts");
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].id, current.id);
+    }
+
     // =============================================================================
     // Price History Tests
     // =============================================================================
@@ -2244,6 +8415,79 @@ mod tests {
         assert_eq!(prices[0].cost_per_mg, 2.5);
     }
 
+    #[test]
+    fn price_trend_buckets_prices_per_supplier_and_peptide() {
+        let storage = create_test_storage();
+        let supplier = Supplier::new("TestSupplier");
+        storage.upsert_supplier(&supplier).expect("upsert supplier");
+
+        // Ten days of BPC-157 prices, two a day, and one TB-500 price
+        let start = time::macros::datetime!(2024-03-01 9:00 UTC);
+        for day in 0..10 {
+            for (hours, cost) in [(0, 2.0), (6, 3.0 + day as f32)] {
+                let mut price = PriceHistory::new(supplier.id.as_str(), "BPC-157", cost);
+                price.recorded_at = start + time::Duration::days(day) + time::Duration::hours(hours);
+                storage.add_price_history(&price).expect("add price");
+            }
+        }
+        let mut other = PriceHistory::new(supplier.id.as_str(), "TB-500", 5.0);
+        other.recorded_at = start;
+        storage.add_price_history(&other).expect("add price");
+
+        let trend = storage
+            .price_trend(&PriceTrendFilter { max_points: 4, ..Default::default() })
+            .expect("trend");
+        assert_eq!(trend.bucket_days, 3);
+        assert_eq!(trend.series.len(), 2);
+
+        let bpc = &trend.series[0];
+        assert_eq!(bpc.peptide_name, "BPC-157");
+        assert_eq!(bpc.currency, "USD");
+        assert_eq!(bpc.points.len(), 4);
+        assert_eq!(bpc.points[1].start, "2024-03-04");
+        assert_eq!(bpc.points[1].entries, 6);
+        assert_eq!(bpc.points[1].min_cost_per_mg, 2.0);
+        assert_eq!(bpc.points[1].max_cost_per_mg, 8.0);
+        assert_eq!(bpc.points[3].entries, 2);
+
+        let since = storage
+            .price_trend(&PriceTrendFilter {
+                peptide_name: Some("BPC-157".into()),
+                since: Some(time::macros::date!(2024-03-09)),
+                ..Default::default()
+            })
+            .expect("trend since");
+        assert_eq!(since.bucket_days, 1);
+        assert_eq!(since.series[0].points.len(), 2);
+        assert_eq!(since.series[0].points[0].avg_cost_per_mg, 6.5);
+    }
+
+    #[test]
+    fn migration_backfills_price_columns() {
+        let storage = create_test_storage();
+        let supplier = Supplier::new("TestSupplier");
+        storage.upsert_supplier(&supplier).expect("upsert supplier");
+        let mut price = PriceHistory::new(supplier.id.as_str(), "BPC-157", 2.5);
+        price.currency = "EUR".into();
+        storage.add_price_history(&price).expect("add price");
+
+        // Back to the schema from before the price columns existed
+        storage
+            .connection()
+            .expect("connection")
+            .execute_batch(
+                "ALTER TABLE price_history DROP COLUMN cost_per_mg;
+                 ALTER TABLE price_history DROP COLUMN currency;",
+            )
+            .expect("downgrade schema");
+        storage.initialize().expect("migrate");
+
+        let trend = storage.price_trend(&PriceTrendFilter::default()).expect("trend");
+        assert_eq!(trend.series.len(), 1);
+        assert_eq!(trend.series[0].currency, "EUR");
+        assert_eq!(trend.series[0].points[0].avg_cost_per_mg, 2.5);
+    }
+
     #[test]
     fn list_price_history_filters_by_peptide() {
         let storage = create_test_storage();
@@ -2285,6 +8529,280 @@ mod tests {
         assert_eq!(latest.unwrap().cost_per_mg, 2.6);
     }
 
+    #[test]
+    fn only_accepted_observations_become_price_history() {
+        let storage = create_test_storage();
+        let supplier = Supplier::new("TestSupplier");
+        storage.upsert_supplier(&supplier).expect("upsert supplier");
+        let mut earlier = PriceHistory::new(&supplier.id, &"BPC-157".to_string(), 2.5);
+        earlier.vial_size_mg = Some(5.0);
+        storage.add_price_history(&earlier).expect("add price");
+
+        let observe = |costs: &[f32]| {
+            let mut observation = PriceObservation::new(supplier.id.as_str(), "BPC-157", "https://shop.example/bpc");
+            observation.matches = costs
+                .iter()
+                .map(|cost| ObservedPrice { cost_per_mg: *cost, context: format!("${}", cost), pattern_type: "price".into() })
+                .collect();
+            storage.add_price_observation(&observation).expect("add observation");
+            observation
+        };
+
+        // A newer scrape replaces the pending one
+        observe(&[9.0]);
+        let wrong = observe(&[0.1, 2.4]);
+        assert_eq!(storage.list_price_observations(Some(ObservationStatus::Pending)).unwrap().len(), 1);
+
+        let rejected = storage.reject_price_observation(&wrong.id).expect("reject");
+        assert_eq!(rejected.status, ObservationStatus::Rejected);
+        assert!(storage.accept_price_observation(&wrong.id, None).is_err());
+        assert_eq!(storage.list_price_history_for_supplier(&supplier.id, None).unwrap().len(), 1);
+
+        let right = observe(&[0.1, 2.4]);
+        assert!(storage.accept_price_observation(&right.id, Some(5)).is_err());
+        let entry = storage.accept_price_observation(&right.id, Some(1)).expect("accept");
+        assert_eq!(entry.cost_per_mg, 2.4);
+        assert_eq!(entry.vial_size_mg, Some(5.0));
+
+        let accepted = storage.get_price_observation(&right.id).unwrap().unwrap();
+        assert_eq!(accepted.status, ObservationStatus::Accepted);
+        assert_eq!(accepted.price_history_id, Some(entry.id.clone()));
+        assert_eq!(storage.get_latest_price(&supplier.id, "BPC-157").unwrap().unwrap().id, entry.id);
+        assert_eq!(storage.list_price_observations(None).unwrap().len(), 2);
+    }
+
+    // =============================================================================
+    // Exchange Rate Tests
+    // =============================================================================
+
+    #[test]
+    fn upsert_exchange_rate_replaces_existing_currency() {
+        let storage = create_test_storage();
+
+        storage
+            .upsert_exchange_rate(&ExchangeRate::new("EUR", 0.9, RateSource::Manual))
+            .expect("upsert");
+        storage
+            .upsert_exchange_rate(&ExchangeRate::new("EUR", 0.95, RateSource::Fetched))
+            .expect("upsert again");
+        storage
+            .upsert_exchange_rate(&ExchangeRate::new("GBP", 0.8, RateSource::Manual))
+            .expect("upsert gbp");
+
+        let rates = storage.list_exchange_rates().expect("list");
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].currency, "EUR");
+        assert_eq!(rates[0].rate_per_usd, 0.95);
+        assert_eq!(rates[0].source, RateSource::Fetched);
+    }
+
+    #[test]
+    fn delete_exchange_rate_removes_currency() {
+        let storage = create_test_storage();
+        storage
+            .upsert_exchange_rate(&ExchangeRate::new("EUR", 0.9, RateSource::Manual))
+            .expect("upsert");
+
+        storage.delete_exchange_rate("EUR").expect("delete");
+        assert!(storage.list_exchange_rates().expect("list").is_empty());
+        assert!(storage.delete_exchange_rate("EUR").is_err());
+    }
+
+    #[test]
+    fn price_history_keeps_currency() {
+        let storage = create_test_storage();
+        let supplier = Supplier::new("EU Supplier");
+        storage.upsert_supplier(&supplier).expect("upsert supplier");
+
+        let mut price = PriceHistory::new(&supplier.id, &"BPC-157".to_string(), 2.5);
+        price.currency = "EUR".to_string();
+        storage.add_price_history(&price).expect("add");
+
+        let latest = storage
+            .get_latest_price(&supplier.id, "BPC-157")
+            .expect("latest")
+            .expect("some");
+        assert_eq!(latest.currency, "EUR");
+    }
+
+    // =============================================================================
+    // Order Tests
+    // =============================================================================
+
+    #[test]
+    fn upsert_order_round_trips_and_updates_status() {
+        let storage = create_test_storage();
+        let supplier = Supplier::new("Test Supplier");
+        storage.upsert_supplier(&supplier).expect("upsert supplier");
+
+        let mut order = Order::new(
+            supplier.id.as_str(),
+            vec![OrderItem {
+                protocol_id: "protocol-1".to_string(),
+                quantity: 2,
+                vial_size_mg: Some(10.0),
+                unit_price: 55.0,
+            }],
+        );
+        order.tracking_number = Some("1Z999".to_string());
+        storage.upsert_order(&order).expect("upsert order");
+
+        order.status = OrderStatus::Shipped;
+        storage.upsert_order(&order).expect("update order");
+
+        let orders = storage.list_orders().expect("list orders");
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].status, OrderStatus::Shipped);
+        assert_eq!(orders[0].items[0].quantity, 2);
+        assert_eq!(orders[0].tracking_number.as_deref(), Some("1Z999"));
+
+        let fetched = storage.get_order(&order.id).expect("get order");
+        assert!(fetched.is_some());
+    }
+
+    #[test]
+    fn list_orders_sorts_newest_first_and_delete_removes() {
+        let storage = create_test_storage();
+
+        let mut older = Order::new("supplier-1", Vec::new());
+        older.order_date -= time::Duration::days(10);
+        let newer = Order::new("supplier-1", Vec::new());
+        storage.upsert_order(&older).expect("upsert older");
+        storage.upsert_order(&newer).expect("upsert newer");
+
+        let orders = storage.list_orders().expect("list orders");
+        assert_eq!(orders[0].id, newer.id);
+        assert_eq!(orders[1].id, older.id);
+
+        storage.delete_order(&older.id).expect("delete");
+        assert_eq!(storage.list_orders().expect("list").len(), 1);
+        assert!(storage.delete_order(&older.id).is_err());
+    }
+
+    // =============================================================================
+    // Attachment Tests
+    // =============================================================================
+
+    #[test]
+    fn add_attachment_encrypts_and_round_trips_bytes() {
+        let storage = create_test_storage();
+        let data = b"%PDF-1.7 certificate of analysis".to_vec();
+        let attachment = Attachment::new(
+            AttachmentOwner::InventoryItem,
+            "item-1",
+            AttachmentKind::CertificateOfAnalysis,
+            "coa.pdf",
+            "application/pdf",
+            data.len() as u64,
+        );
+        storage.add_attachment(&attachment, &data, None).expect("add attachment");
+
+        let listed = storage
+            .list_attachments(&AttachmentOwner::InventoryItem, "item-1")
+            .expect("list attachments");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].file_name, "coa.pdf");
+        assert_eq!(listed[0].kind, AttachmentKind::CertificateOfAnalysis);
+
+        let loaded = storage
+            .get_attachment_data(&attachment.id)
+            .expect("get data")
+            .expect("some data");
+        assert_eq!(loaded, data);
+
+        // Stored bytes must not contain the plaintext
+        let conn = storage.connection().expect("conn");
+        let raw: Vec<u8> = conn
+            .query_row("SELECT data FROM attachments WHERE id = ?1", [&attachment.id], |row| row.get(0))
+            .expect("raw data");
+        assert!(!raw.windows(4).any(|w| w == b"%PDF"));
+    }
+
+    #[test]
+    fn attachment_thumbnail_is_stored_separately() {
+        let storage = create_test_storage();
+        let mut photo = Attachment::new(
+            AttachmentOwner::BodyMetric,
+            "metric-1",
+            AttachmentKind::Photo,
+            "progress.jpg",
+            "image/jpeg",
+            4,
+        );
+        photo.has_thumbnail = true;
+        storage
+            .add_attachment(&photo, &[1, 2, 3, 4], Some(&[9, 9]))
+            .expect("add photo");
+
+        assert_eq!(
+            storage.get_attachment_thumbnail(&photo.id).expect("thumbnail"),
+            Some(vec![9, 9])
+        );
+        assert_eq!(storage.list_all_attachments().expect("list all").len(), 1);
+
+        // Re-adding the same attachment replaces it instead of failing
+        storage
+            .add_attachment(&photo, &[1, 2, 3, 4], None)
+            .expect("re-add photo");
+        assert!(storage.get_attachment_thumbnail(&photo.id).expect("thumbnail").is_none());
+    }
+
+    #[test]
+    fn deleting_dose_log_removes_its_photos() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Test", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert protocol");
+        let dose = DoseLog::new(protocol.id.as_str(), "abdomen", 0.25);
+        storage.append_dose_log(&dose).expect("log dose");
+
+        let photo = Attachment::new(
+            AttachmentOwner::DoseLog,
+            dose.id.as_str(),
+            AttachmentKind::Photo,
+            "site.jpg",
+            "image/jpeg",
+            1,
+        );
+        storage.add_attachment(&photo, &[1], None).expect("add photo");
+
+        storage.bulk_delete_doses(std::slice::from_ref(&dose.id)).expect("delete doses");
+        assert!(storage.get_attachment(&photo.id).expect("get").is_none());
+    }
+
+    #[test]
+    fn deleting_owner_removes_its_attachments() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Test", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert protocol");
+        let item = InventoryItem::new(protocol.id.as_str());
+        storage.upsert_inventory_item(&item).expect("upsert item");
+
+        let attachment = Attachment::new(
+            AttachmentOwner::InventoryItem,
+            item.id.as_str(),
+            AttachmentKind::Other,
+            "label.png",
+            "image/png",
+            3,
+        );
+        storage.add_attachment(&attachment, &[1, 2, 3], None).expect("add attachment");
+        let other = Attachment::new(
+            AttachmentOwner::Supplier,
+            item.id.as_str(),
+            AttachmentKind::Invoice,
+            "invoice.pdf",
+            "application/pdf",
+            3,
+        );
+        storage.add_attachment(&other, &[4, 5, 6], None).expect("add other");
+
+        storage.delete_inventory_item(&item.id).expect("delete item");
+
+        assert!(storage.get_attachment(&attachment.id).expect("get").is_none());
+        assert!(storage.get_attachment(&other.id).expect("get other").is_some());
+        assert!(storage.delete_attachment(&attachment.id).is_err());
+    }
+
     // =============================================================================
     // Alert Tests
     // =============================================================================
@@ -2307,6 +8825,55 @@ mod tests {
         assert_eq!(alerts[0].alert_type, AlertType::LowStock);
     }
 
+    #[test]
+    fn raise_alert_skips_muted_alerts() {
+        let storage = create_test_storage();
+        let mut muted = Alert::new(AlertType::LowStock, AlertSeverity::Warning, "Low Stock", "Vial is running low");
+        muted.related_id = Some("vial-1".to_string());
+        let mut other = muted.clone();
+        other.id = "other".to_string();
+        other.related_id = Some("vial-2".to_string());
+
+        let mut preferences = AlertPreferences::default();
+        preferences
+            .mute(None, Some("vial-1".to_string()), 3, OffsetDateTime::now_utc())
+            .expect("mute");
+        storage.save_setting(&preferences).expect("save preferences");
+
+        assert_eq!(storage.raise_alert(&muted).expect("raise muted"), None);
+        assert_eq!(
+            storage.raise_alert(&other).expect("raise other"),
+            Some(AlertDelivery::Notification)
+        );
+        let alerts = storage.list_alerts(false).expect("list alerts");
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].id, "other");
+    }
+
+    #[test]
+    fn saved_alerts_are_published_to_listeners() {
+        let storage = create_test_storage();
+        let created = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = created.clone();
+        storage.subscribe(Arc::new(move |event: &StorageEvent| {
+            let StorageEvent::AlertCreated(alert) = event;
+            seen.lock().unwrap().push(alert.id.clone());
+        }));
+
+        let mut muted = Alert::new(AlertType::LowStock, AlertSeverity::Warning, "Low Stock", "Vial is running low");
+        muted.related_id = Some("vial-1".to_string());
+        let mut preferences = AlertPreferences::default();
+        preferences
+            .mute(None, Some("vial-1".to_string()), 3, OffsetDateTime::now_utc())
+            .expect("mute");
+        storage.save_setting(&preferences).expect("save preferences");
+        storage.raise_alert(&muted).expect("raise muted");
+
+        let alert = Alert::new(AlertType::PriceDecrease, AlertSeverity::Info, "Price Drop", "Cheaper now");
+        storage.raise_alert(&alert).expect("raise alert");
+        assert_eq!(*created.lock().unwrap(), vec![alert.id]);
+    }
+
     #[test]
     fn list_alerts_excludes_dismissed_by_default() {
         let storage = create_test_storage();
@@ -2439,6 +9006,52 @@ mod tests {
         assert_eq!(summaries[0].title, "BPC-157 Research Summary");
     }
 
+    #[test]
+    fn list_summaries_for_source_returns_versions_newest_first() {
+        let storage = create_test_storage();
+        let mut first = SummaryHistory::new("Paper", "Abstract", "v1", "markdown", "codex");
+        first.source_id = "entry-1".to_string();
+        first.created_at -= time::Duration::hours(1);
+        let mut second = SummaryHistory::new("Paper", "Abstract", "v2", "markdown", "claude");
+        second.source_id = "entry-1".to_string();
+        let other = SummaryHistory::new("Other", "Other text", "v1", "markdown", "claude");
+        for summary in [&first, &second, &other] {
+            storage.save_summary(summary).expect("save");
+        }
+
+        let versions = storage.list_summaries_for_source("entry-1").expect("list");
+        let outputs: Vec<_> = versions.iter().map(|s| s.summary_output.as_str()).collect();
+        assert_eq!(outputs, vec!["v2", "v1"]);
+
+        let by_content = storage
+            .list_summaries_for_source(&SummaryHistory::content_source_id("Other text"))
+            .expect("list");
+        assert_eq!(by_content.len(), 1);
+    }
+
+    #[test]
+    fn migration_backfills_summary_sources() {
+        let storage = create_test_storage();
+        let summary = SummaryHistory::new("Paper", "Abstract", "Output", "markdown", "claude");
+        storage.save_summary(&summary).expect("save");
+
+        storage
+            .connection()
+            .expect("connection")
+            .execute_batch(
+                "DROP INDEX idx_summary_history_source;
+                 ALTER TABLE summary_history DROP COLUMN source_id;",
+            )
+            .expect("downgrade schema");
+        storage.initialize().expect("migrate");
+
+        let versions = storage
+            .list_summaries_for_source(&summary.source_id)
+            .expect("list");
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].id, summary.id);
+    }
+
     #[test]
     fn list_summary_history_respects_limit() {
         let storage = create_test_storage();
@@ -2488,6 +9101,464 @@ mod tests {
         assert_eq!(summaries.len(), 0);
     }
 
+    // =============================================================================
+    // Global Search Tests
+    // =============================================================================
+
+    #[test]
+    fn search_finds_records_across_entity_types() {
+        let storage = create_test_storage();
+
+        let mut protocol = PeptideProtocol::new("Evening GH", "Ipamorelin");
+        protocol.tags = vec!["sleep".to_string()];
+        storage.upsert_protocol(&protocol).expect("protocol");
+
+        let mut dose = DoseLog::new(protocol.id.as_str(), "abdomen", 0.3);
+        dose.notes = Some("Slept deeply after ipamorelin".to_string());
+        storage.append_dose_log(&dose).expect("dose");
+
+        let mut item = InventoryItem::new(protocol.id.as_str());
+        item.lot_number = Some("IPA-2024-0117".to_string());
+        storage.upsert_inventory_item(&item).expect("inventory");
+
+        let hits = storage.search("ipamorelin", None, 10).expect("search");
+        let types: Vec<SearchEntityType> = hits.iter().map(|hit| hit.entity_type).collect();
+        assert!(types.contains(&SearchEntityType::Protocol));
+        assert!(types.contains(&SearchEntityType::DoseLog));
+        assert_eq!(hits.len(), 2);
+
+        let lot = storage.search("0117", None, 10).expect("search lot");
+        assert_eq!(
+            lot,
+            vec![SearchHit {
+                entity_type: SearchEntityType::InventoryItem,
+                entity_id: item.id.clone(),
+            }]
+        );
+
+        let tagged = storage.search("#sleep", None, 10).expect("search tag");
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].entity_id, protocol.id);
+
+        let only_doses = storage
+            .search("ipa", Some(&[SearchEntityType::DoseLog]), 10)
+            .expect("filtered search");
+        assert_eq!(only_doses.len(), 1);
+
+        // The index holds no plaintext
+        let conn = storage.connection().expect("connection");
+        let tokens: String = conn
+            .query_row("SELECT group_concat(tokens, ' ') FROM search_index", [], |row| row.get(0))
+            .expect("tokens");
+        assert!(!tokens.contains("ipa"));
+        drop(conn);
+
+        storage.delete_protocol(&protocol.id).expect("delete protocol");
+        assert!(storage.search("ipamorelin", None, 10).expect("search").is_empty());
+        assert!(storage.search("0117", None, 10).expect("search").is_empty());
+    }
+
+    #[test]
+    fn rebuild_search_index_restores_entries() {
+        let storage = create_test_storage();
+        let supplier = Supplier::new("Peptide Sciences");
+        storage.upsert_supplier(&supplier).expect("supplier");
+
+        let conn = storage.connection().expect("connection");
+        conn.execute("DELETE FROM search_index", []).expect("clear index");
+        drop(conn);
+        assert!(storage.search("peptide", None, 10).expect("search").is_empty());
+
+        assert_eq!(storage.rebuild_search_index().expect("rebuild"), 1);
+        assert_eq!(storage.search("sciences", None, 10).expect("search").len(), 1);
+    }
+
+    #[test]
+    fn trashing_a_protocol_hides_it_and_its_doses_until_restored() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Recovery", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("protocol");
+        let log = DoseLog::new(protocol.id.clone(), "abdomen".to_string(), 0.25);
+        storage.append_dose_log(&log).expect("dose");
+
+        let ids = vec![protocol.id.clone()];
+        assert_eq!(storage.move_to_trash(TrashEntityType::Protocol, &ids).expect("trash"), 1);
+        assert_eq!(storage.move_to_trash(TrashEntityType::Protocol, &ids).expect("trash again"), 0);
+
+        assert!(storage.list_protocols().expect("list").is_empty());
+        assert!(storage.get_dose_log(&log.id).expect("get").is_none());
+        assert!(storage.search("bpc", None, 10).expect("search").is_empty());
+
+        let trash = storage.list_trash().expect("trash list");
+        assert_eq!(trash.len(), 2);
+        assert!(trash
+            .iter()
+            .any(|item| item.entity_type == TrashEntityType::DoseLog
+                && item.protocol_id.as_deref() == Some(protocol.id.as_str())));
+
+        // The dose can't come back without its protocol
+        assert!(storage
+            .restore_from_trash(TrashEntityType::DoseLog, std::slice::from_ref(&log.id))
+            .is_err());
+
+        assert_eq!(storage.restore_from_trash(TrashEntityType::Protocol, &ids).expect("restore"), 1);
+        assert_eq!(storage.list_dose_logs().expect("doses").len(), 1);
+        assert_eq!(storage.search("bpc", None, 10).expect("search").len(), 1);
+        assert!(storage.list_trash().expect("trash list").is_empty());
+    }
+
+    #[test]
+    fn undo_puts_deleted_and_edited_records_back() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Recovery", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("protocol");
+        let log = DoseLog::new(protocol.id.clone(), "abdomen".to_string(), 0.25);
+        storage.append_dose_log(&log).expect("dose");
+        let item = InventoryItem::new(&protocol.id);
+        storage.upsert_inventory_item(&item).expect("item");
+
+        let ids = vec![log.id.clone()];
+        storage.move_to_trash(TrashEntityType::DoseLog, &ids).expect("trash");
+        let restore = UndoAction::RestoreFromTrash { entity_type: TrashEntityType::DoseLog, ids };
+        assert_eq!(storage.undo(&restore).expect("undo trash"), 1);
+        assert!(storage.get_dose_log(&log.id).expect("get").is_some());
+
+        storage.bulk_delete_inventory_items(std::slice::from_ref(&item.id)).expect("delete");
+        assert_eq!(storage.undo(&UndoAction::RestoreInventory(vec![item.clone()])).expect("undo delete"), 1);
+        assert!(storage.get_inventory_item(&item.id).expect("get").is_some());
+    }
+
+    #[test]
+    fn purge_trash_only_removes_expired_records() {
+        let storage = create_test_storage();
+        let mut metric = BodyMetric::new(now_timestamp());
+        metric.weight_kg = Some(80.0);
+        storage.upsert_body_metric(&metric).expect("metric");
+        storage
+            .move_to_trash(TrashEntityType::BodyMetric, &[metric.id.clone()])
+            .expect("trash");
+
+        assert_eq!(storage.purge_trash(30).expect("purge"), 0);
+        assert_eq!(storage.list_trash().expect("trash").len(), 1);
+
+        assert_eq!(storage.purge_trash(0).expect("empty trash"), 1);
+        assert!(storage.list_trash().expect("trash").is_empty());
+        assert_eq!(
+            storage
+                .restore_from_trash(TrashEntityType::BodyMetric, &[metric.id.clone()])
+                .expect("restore"),
+            0
+        );
+    }
+
+    #[test]
+    fn audit_log_records_creates_updates_and_cascaded_deletes() {
+        let storage = create_test_storage();
+        let mut protocol = PeptideProtocol::new("Recovery", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("create");
+
+        // A save that changes nothing but timestamps isn't recorded
+        protocol.updated_at = now_timestamp();
+        storage.upsert_protocol(&protocol).expect("no-op save");
+        storage
+            .add_protocol_tag(&protocol.id, "healing".to_string())
+            .expect("tag");
+
+        let log = DoseLog::new(protocol.id.clone(), "abdomen".to_string(), 0.25);
+        storage.append_dose_log(&log).expect("dose");
+        storage.delete_protocol(&protocol.id).expect("delete");
+
+        let entries = storage.list_audit_log(&AuditLogFilter::default()).expect("audit");
+        let ops: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.entity_type, entry.operation))
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                (AuditEntityType::Protocol, AuditOperation::Delete),
+                (AuditEntityType::DoseLog, AuditOperation::Delete),
+                (AuditEntityType::DoseLog, AuditOperation::Create),
+                (AuditEntityType::Protocol, AuditOperation::Update),
+                (AuditEntityType::Protocol, AuditOperation::Create),
+            ]
+        );
+        assert_eq!(entries[3].changed_fields, vec!["tags"]);
+        assert_eq!(entries[1].summary, "Deleted with its protocol");
+    }
+
+    #[test]
+    fn settings_persist_and_validate() {
+        use crate::units::{DoseUnit, UnitPreferences};
+
+        let storage = create_test_storage();
+        assert!(storage.load_setting::<UnitPreferences>().expect("read").is_none());
+        assert_eq!(
+            storage.load_setting_or_default::<UnitPreferences>().expect("defaults"),
+            UnitPreferences::default()
+        );
+
+        let preferences = UnitPreferences {
+            dose_unit: DoseUnit::Mcg,
+            ..Default::default()
+        };
+        storage.save_setting(&preferences).expect("save");
+        assert_eq!(storage.load_setting::<UnitPreferences>().expect("load"), Some(preferences));
+
+        let invalid = UnitPreferences {
+            dose_unit: DoseUnit::Iu,
+            ..Default::default()
+        };
+        assert!(storage.save_setting(&invalid).is_err());
+        assert!(storage.delete_setting::<UnitPreferences>().expect("delete"));
+        assert!(storage.load_setting::<UnitPreferences>().expect("read").is_none());
+    }
+
+    #[test]
+    fn legacy_settings_file_is_imported_once() {
+        use crate::trash::TrashSettings;
+
+        let storage = create_test_storage();
+        let data_dir = storage.db_path.parent().expect("data dir").to_path_buf();
+        std::fs::write(data_dir.join("trash.json"), r#"{"retentionDays": 7}"#).expect("write");
+
+        let settings = storage.load_setting::<TrashSettings>().expect("migrate");
+        assert_eq!(settings.map(|settings| settings.retention_days), Some(7));
+        assert!(!data_dir.join("trash.json").exists());
+        assert!(data_dir.join("trash.json.migrated").exists());
+
+        // The stored value wins from now on
+        std::fs::write(data_dir.join("trash.json"), r#"{"retentionDays": 90}"#).expect("write");
+        storage.save_setting(&TrashSettings { retention_days: 14 }).expect("save");
+        assert_eq!(storage.load_setting_or_default::<TrashSettings>().expect("load").retention_days, 14);
+    }
+
+    #[test]
+    fn stats_cache_roundtrips_and_is_invalidated_by_writes() {
+        let storage = create_test_storage();
+        assert!(storage
+            .cached_stat::<u32>(DashboardStat::DosesThisWeek)
+            .expect("read")
+            .is_none());
+
+        let generation = storage.stats_generation();
+        assert!(storage.store_stat(DashboardStat::DosesThisWeek, &3u32, generation).expect("store"));
+        assert!(storage.store_stat(DashboardStat::Spend, &12.5f32, generation).expect("store"));
+        let cached = storage
+            .cached_stat::<u32>(DashboardStat::DosesThisWeek)
+            .expect("read")
+            .expect("cached");
+        assert_eq!(cached.value, 3);
+        assert!(now_timestamp() - cached.computed_at < time::Duration::minutes(1));
+
+        // A dose changes the dose count but a supplier doesn't
+        let supplier = Supplier::new("Vendor");
+        storage.upsert_supplier(&supplier).expect("supplier");
+        assert!(storage.cached_stat::<u32>(DashboardStat::DosesThisWeek).expect("read").is_some());
+        assert!(storage.cached_stat::<f32>(DashboardStat::Spend).expect("read").is_none());
+
+        let protocol = PeptideProtocol::new("Test Protocol", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert protocol");
+        let dose = DoseLog::new(protocol.id.as_str(), "Abdomen", 0.25);
+        storage.append_dose_log(&dose).expect("append dose");
+        assert!(storage.cached_stat::<u32>(DashboardStat::DosesThisWeek).expect("read").is_none());
+
+        // Values computed before an invalidation are not stored
+        assert!(!storage.store_stat(DashboardStat::DosesThisWeek, &3u32, generation).expect("store"));
+        assert!(storage.cached_stat::<u32>(DashboardStat::DosesThisWeek).expect("read").is_none());
+
+        let generation = storage.stats_generation();
+        storage.store_stat(DashboardStat::DosesThisWeek, &4u32, generation).expect("store");
+        storage
+            .move_to_trash(TrashEntityType::Protocol, std::slice::from_ref(&protocol.id))
+            .expect("trash");
+        assert!(storage.cached_stat::<u32>(DashboardStat::DosesThisWeek).expect("read").is_none());
+    }
+
+    #[test]
+    fn response_cache_expires_and_counts_hits() {
+        let storage = create_test_storage();
+        let ttl = time::Duration::hours(24);
+        assert!(storage.cached_response("pubmed|bpc-157", ttl).expect("read").is_none());
+
+        storage.store_response("pubmed|bpc-157", "pubmed", b"[1]").expect("store");
+        storage.store_response("openalex|bpc-157", "openalex", b"[2]").expect("store");
+        assert_eq!(storage.cached_response("pubmed|bpc-157", ttl).expect("read"), Some(b"[1]".to_vec()));
+        assert_eq!(storage.cached_response("pubmed|bpc-157", ttl).expect("read"), Some(b"[1]".to_vec()));
+
+        let stale = (now_timestamp() - ttl - time::Duration::minutes(1)).unix_timestamp();
+        storage
+            .connection()
+            .expect("connection")
+            .execute("UPDATE literature_responses SET fetched_at = ?1 WHERE source = 'openalex'", params![stale])
+            .expect("age cache");
+        assert!(storage.cached_response("openalex|bpc-157", ttl).expect("read").is_none());
+
+        let stats = storage.response_cache_stats(ttl).expect("stats");
+        assert_eq!((stats.entries, stats.expired, stats.hits), (2, 1, 2));
+        assert_eq!(stats.by_source.len(), 2);
+        assert_eq!(stats.by_source[1].source, "pubmed");
+        assert_eq!(stats.by_source[1].hits, 2);
+
+        assert_eq!(storage.clear_response_cache(Some(ttl)).expect("prune"), 1);
+        assert_eq!(storage.clear_response_cache(None).expect("clear"), 1);
+        assert_eq!(storage.response_cache_stats(ttl).expect("stats"), ResponseCacheStats::default());
+    }
+
+    #[test]
+    fn stats_cache_expires_and_ignores_unreadable_values() {
+        let storage = create_test_storage();
+        let generation = storage.stats_generation();
+        storage.store_stat(DashboardStat::Adherence, &"not a number", generation).expect("store");
+        assert!(storage.cached_stat::<f32>(DashboardStat::Adherence).expect("read").is_none());
+
+        storage.store_stat(DashboardStat::Adherence, &0.9f32, generation).expect("store");
+        let stale = (now_timestamp() - MAX_STAT_AGE - time::Duration::minutes(1)).unix_timestamp();
+        storage
+            .connection()
+            .expect("connection")
+            .execute("UPDATE stats_cache SET computed_at = ?1", params![stale])
+            .expect("age cache");
+        assert!(storage.cached_stat::<f32>(DashboardStat::Adherence).expect("read").is_none());
+    }
+
+    #[test]
+    fn list_audit_log_applies_filters() {
+        let storage = create_test_storage();
+        let supplier = Supplier::new("Vendor");
+        storage.upsert_supplier(&supplier).expect("supplier");
+        storage.delete_supplier(&supplier.id).expect("delete");
+
+        let filter = AuditLogFilter {
+            entity_type: Some(AuditEntityType::Supplier),
+            operation: Some(AuditOperation::Delete),
+            ..Default::default()
+        };
+        let entries = storage.list_audit_log(&filter).expect("audit");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].entity_id, supplier.id);
+
+        let future = AuditLogFilter {
+            since: Some(now_timestamp() + time::Duration::days(1)),
+            ..Default::default()
+        };
+        assert!(storage.list_audit_log(&future).expect("audit").is_empty());
+    }
+
+    #[test]
+    fn audit_log_is_append_only_and_prunes_to_retention() {
+        let storage = create_test_storage();
+        for name in ["A", "B", "C"] {
+            storage.upsert_supplier(&Supplier::new(name)).expect("supplier");
+        }
+
+        let conn = storage.connection().expect("connection");
+        assert!(conn.execute("UPDATE audit_log SET summary = 'x'", []).is_err());
+        drop(conn);
+
+        let removed = storage
+            .prune_audit_log(&AuditRetention {
+                max_age_days: Some(30),
+                max_entries: Some(2),
+            })
+            .expect("prune");
+        assert_eq!(removed, 1);
+        assert_eq!(
+            storage.list_audit_log(&AuditLogFilter::default()).expect("audit").len(),
+            2
+        );
+    }
+
+    // =============================================================================
+    // Lab Result Tests
+    // =============================================================================
+
+    #[test]
+    fn lab_results_group_into_case_insensitive_trends() {
+        let storage = create_test_storage();
+        let day = |d: u8| {
+            time::macros::datetime!(2024-01-01 08:00 UTC).replace_day(d).unwrap()
+        };
+
+        let later = LabResult::new("IGF-1", 240.0, "ng/mL", day(20));
+        let earlier = LabResult::new("igf-1", 180.0, "ng/mL", day(2));
+        let lipids = LabResult::new("LDL Cholesterol", 110.0, "mg/dL", day(10));
+        for result in [&later, &earlier, &lipids] {
+            storage.upsert_lab_result(result).expect("upsert lab result");
+        }
+
+        let trend = storage.list_lab_results_for_marker("IGF-1").expect("trend");
+        let values: Vec<f32> = trend.iter().map(|r| r.value).collect();
+        assert_eq!(values, vec![180.0, 240.0]);
+
+        let markers = storage.list_lab_markers().expect("markers");
+        assert_eq!(markers.len(), 2);
+
+        storage.delete_lab_result(&lipids.id).expect("delete");
+        assert!(storage.get_lab_result(&lipids.id).expect("get").is_none());
+        assert!(storage.delete_lab_result(&lipids.id).is_err());
+    }
+
+    #[test]
+    fn journal_entries_are_searchable_and_found_by_backlinks() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Evening GH", "Ipamorelin");
+        storage.upsert_protocol(&protocol).expect("upsert protocol");
+
+        let mut linked = JournalEntry::new(
+            format!("Slept better since starting [[protocol:{}|Evening GH]]", protocol.id),
+            time::macros::datetime!(2024-01-10 21:00 UTC),
+        );
+        linked.tags = vec!["sleep".to_string()];
+        let by_name = JournalEntry::new(
+            "Week two of [[protocol:evening gh]]",
+            time::macros::datetime!(2024-01-17 21:00 UTC),
+        );
+        let unrelated = JournalEntry::new("Knee still sore", time::macros::datetime!(2024-01-12 09:00 UTC));
+        for entry in [&linked, &by_name, &unrelated] {
+            storage.upsert_journal_entry(entry).expect("upsert journal entry");
+        }
+
+        let backlinks = storage
+            .list_journal_backlinks(JournalLinkKind::Protocol, &[protocol.id.as_str(), protocol.name.as_str()])
+            .expect("backlinks");
+        let ids: Vec<&str> = backlinks.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, vec![by_name.id.as_str(), linked.id.as_str()]);
+
+        let hits = storage.search("#sleep slept", None, 10).expect("search");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].entity_type, SearchEntityType::JournalEntry);
+        assert_eq!(hits[0].entity_id, linked.id);
+
+        storage.delete_journal_entry(&linked.id).expect("delete");
+        assert!(storage.get_journal_entry(&linked.id).expect("get").is_none());
+        assert!(storage.search("slept", None, 10).expect("search").is_empty());
+    }
+
+    #[test]
+    fn goals_round_trip_and_invalidate_goal_stats() {
+        let storage = create_test_storage();
+        let mut goal = Goal::new("Cut to 80", GoalMetric::WeightKg, 80.0, now_timestamp());
+        storage.upsert_goal(&goal).expect("upsert goal");
+
+        let generation = storage.stats_generation();
+        storage.store_stat(DashboardStat::Goals, &1u32, generation).expect("store");
+        let mut metric = BodyMetric::new(now_timestamp());
+        metric.weight_kg = Some(84.0);
+        storage.upsert_body_metric(&metric).expect("upsert metric");
+        assert!(storage.cached_stat::<u32>(DashboardStat::Goals).expect("read").is_none());
+
+        goal.archived = true;
+        storage.upsert_goal(&goal).expect("update goal");
+        let goals = storage.list_goals().expect("list goals");
+        assert_eq!(goals.len(), 1);
+        assert!(goals[0].archived);
+
+        storage.delete_goal(&goal.id).expect("delete goal");
+        assert!(storage.get_goal(&goal.id).expect("get goal").is_none());
+        assert!(storage.delete_goal(&goal.id).is_err());
+    }
+
     // =============================================================================
     // Schema & Initialization Tests
     // =============================================================================
@@ -2552,6 +9623,15 @@ mod tests {
         storage.verify_integrity().expect("integrity check should pass");
     }
 
+    #[test]
+    fn integrity_check_finds_no_problems_in_healthy_database() {
+        let storage = create_test_storage();
+        storage
+            .upsert_protocol(&PeptideProtocol::new("Protocol A", "BPC-157"))
+            .expect("upsert protocol");
+        assert!(storage.integrity_check().expect("integrity check").is_empty());
+    }
+
     #[test]
     fn get_stats_returns_valid_statistics() {
         let storage = create_test_storage();
@@ -2577,6 +9657,290 @@ mod tests {
         storage.optimize().expect("optimize should succeed");
     }
 
+    #[test]
+    fn maintenance_runs_are_recorded_and_scheduled_weekly() {
+        let storage = create_test_storage();
+        let now = OffsetDateTime::now_utc();
+        assert!(storage.maintenance_due(now).expect("due"));
+
+        let run = storage.run_maintenance(false).expect("maintenance");
+        assert!(run.error.is_none());
+        assert_eq!(run.actions, MaintenanceAction::needed(&run.before));
+        assert_eq!(storage.list_maintenance_runs().expect("runs"), vec![run.clone()]);
+
+        assert!(!storage.maintenance_due(now).expect("due"));
+        let next_week = run.ran_at + time::Duration::days(crate::MAINTENANCE_INTERVAL_DAYS);
+        assert!(storage.maintenance_due(next_week).expect("due"));
+    }
+
+    #[test]
+    fn sync_changes_carry_records_between_devices() {
+        let laptop = create_test_storage();
+        let phone = create_test_storage();
+
+        let mut protocol = PeptideProtocol::new("Healing", "BPC-157");
+        laptop.upsert_protocol(&protocol).expect("upsert");
+        let dose = DoseLog::new(&protocol.id, &"Left Shoulder".to_string(), 0.5);
+        laptop.append_dose_log(&dose).expect("dose");
+        protocol.notes = Some("with food".into());
+        laptop.upsert_protocol(&protocol).expect("update");
+
+        let (changes, seq) = laptop.pending_sync_changes(0, 100).expect("changes");
+        assert_eq!(seq, laptop.last_audit_seq().expect("seq"));
+        assert_eq!(
+            changes.iter().map(|change| change.entity_type).collect::<Vec<_>>(),
+            vec![AuditEntityType::Protocol, AuditEntityType::DoseLog]
+        );
+        assert_eq!(changes, laptop.sync_snapshot().expect("snapshot"));
+        let applied = phone.apply_sync_changes(&changes).expect("apply");
+        assert_eq!((applied.saved, applied.deleted, applied.skipped), (2, 0, 0));
+        let synced = phone.get_protocol(&protocol.id).expect("get").expect("synced");
+        assert_eq!(synced.notes.as_deref(), Some("with food"));
+        assert!(phone.get_dose_log(&dose.id).expect("get").is_some());
+
+        // Applying again changes nothing, so the echo isn't audited
+        let phone_seq = phone.last_audit_seq().expect("seq");
+        phone.apply_sync_changes(&changes).expect("apply again");
+        assert_eq!(phone.last_audit_seq().expect("seq"), phone_seq);
+
+        // A newer copy here is kept
+        let mut edited = synced.clone();
+        edited.notes = Some("edited on phone".into());
+        edited.updated_at = synced.updated_at + time::Duration::minutes(5);
+        phone.upsert_protocol(&edited).expect("edit");
+        assert_eq!(phone.apply_sync_changes(&changes[..1]).expect("apply").skipped, 1);
+
+        laptop.delete_dose_log(&dose.id).expect("delete");
+        let (changes, _) = laptop.pending_sync_changes(seq, 100).expect("changes");
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].record.is_none());
+        assert_eq!(phone.apply_sync_changes(&changes).expect("apply").deleted, 1);
+        assert!(phone.get_dose_log(&dose.id).expect("get").is_none());
+        assert_eq!(phone.apply_sync_changes(&changes).expect("apply").skipped, 1);
+
+        let mut cursor = phone.sync_cursor("laptop").expect("cursor");
+        assert_eq!(cursor, SyncCursor::new("laptop"));
+        cursor.change_sets = 2;
+        cursor.synced_at = Some(OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap());
+        phone.save_sync_cursor(&cursor).expect("save cursor");
+        assert_eq!(phone.list_sync_cursors().expect("cursors"), vec![cursor]);
+        assert!(!phone.audit_log_pruned_after(0).expect("pruned"));
+    }
+
+    #[test]
+    fn sync_changes_are_applied_all_or_none() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Healing", "BPC-157");
+        let saved = SyncChange {
+            entity_type: AuditEntityType::Protocol,
+            entity_id: protocol.id.clone(),
+            record: Some(serde_json::to_value(&protocol).unwrap()),
+        };
+        let deleted = SyncChange {
+            record: None,
+            ..saved.clone()
+        };
+        let malformed = SyncChange {
+            entity_type: AuditEntityType::Goal,
+            entity_id: "goal-1".into(),
+            record: Some(serde_json::json!({ "id": "goal-1" })),
+        };
+
+        assert!(storage.apply_sync_changes(&[saved.clone(), malformed]).is_err());
+        assert!(storage.get_protocol(&protocol.id).expect("get").is_none());
+
+        // A record saved and deleted in one change set ends up deleted
+        let applied = storage.apply_sync_changes(&[saved, deleted]).expect("apply");
+        assert_eq!((applied.saved, applied.deleted, applied.skipped), (1, 1, 0));
+        assert!(storage.get_protocol(&protocol.id).expect("get").is_none());
+    }
+
+    /// Make `storage` look like a database from before summary sources, with
+    /// a table in the way of an index the migration creates
+    fn break_summary_source_migration(storage: &StorageManager) {
+        let conn = storage.connection().expect("conn");
+        conn.execute_batch(
+            "DROP INDEX idx_summary_history_source;
+             ALTER TABLE summary_history DROP COLUMN source_id;
+             DROP INDEX idx_dose_logs_schedule;
+             CREATE TABLE idx_dose_logs_schedule (id TEXT);",
+        )
+        .expect("downgrade schema");
+    }
+
+    #[test]
+    fn failed_migration_restores_snapshot() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Kept", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert");
+        break_summary_source_migration(&storage);
+
+        let error = storage.initialize().expect_err("migration should fail");
+        let failed = error.downcast_ref::<MigrationFailed>().expect("migration error");
+        assert!(failed.restored);
+
+        let conn = storage.open_connection().expect("conn");
+        assert!(!has_column(&conn, "summary_history", "source_id"));
+        drop(conn);
+        assert_eq!(storage.list_protocols().expect("list").len(), 1);
+
+        let snapshots = storage.list_migration_snapshots().expect("snapshots");
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].id, failed.snapshot_id);
+        assert_eq!(snapshots[0].to_version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn damaged_snapshots_are_not_restored() {
+        let storage = create_test_storage();
+        break_summary_source_migration(&storage);
+        storage.initialize().expect_err("migration should fail");
+
+        let snapshot = storage.list_migration_snapshots().expect("snapshots").remove(0);
+        storage.restore_migration_snapshot(&snapshot.id).expect("restore intact snapshot");
+
+        let dir = migration::snapshot_dir(&storage.db_path);
+        std::fs::write(migration::snapshot_file(&dir, &snapshot.id), b"not a database").expect("damage");
+        assert!(storage.restore_migration_snapshot(&snapshot.id).is_err());
+        assert!(storage.restore_migration_snapshot("missing").is_err());
+
+        assert_eq!(storage.delete_migration_snapshots().expect("delete"), 1);
+        assert!(storage.list_migration_snapshots().expect("snapshots").is_empty());
+    }
+
+    #[test]
+    fn up_to_date_databases_are_not_snapshotted() {
+        let storage = create_test_storage();
+        storage.initialize().expect("init again");
+        assert!(storage.list_migration_snapshots().expect("snapshots").is_empty());
+    }
+
+    #[test]
+    fn salvage_recovers_rows_around_a_damaged_page() {
+        let tmp = tempdir().expect("tempdir");
+        let config = || StorageConfig {
+            data_dir: Some(tmp.path().to_path_buf()),
+            db_file_name: Some("damaged.sqlite".into()),
+            key_provider: Arc::new(StaticKeyProvider::new(vec![7u8; 32]).expect("static key provider")),
+        };
+        let storage = StorageManager::new(config()).expect("storage manager");
+        storage.initialize().expect("init db");
+        for i in 0..300 {
+            let mut protocol = PeptideProtocol::new(format!("Salvage-{:04}", i), "BPC-157".to_string());
+            protocol.notes = Some("x".repeat(200));
+            storage.upsert_protocol(&protocol).expect("upsert");
+        }
+        storage.compact().expect("compact");
+        let db_path = storage.db_path.clone();
+        drop(storage);
+
+        // Scribble over every page holding one of the rows
+        let mut bytes = std::fs::read(&db_path).expect("read db");
+        let marker = b"Salvage-0150";
+        let damaged_pages: Vec<usize> = (0..bytes.len() - marker.len())
+            .filter(|&i| &bytes[i..i + marker.len()] == marker)
+            .map(|i| i / 4096)
+            .collect();
+        assert!(!damaged_pages.is_empty());
+        for page in damaged_pages {
+            bytes[page * 4096..(page + 1) * 4096].fill(0xFF);
+        }
+        std::fs::write(&db_path, bytes).expect("write db");
+
+        let storage = StorageManager::new(config()).expect("storage manager");
+        assert!(!storage.health_check().expect("health").is_healthy);
+
+        let mut updates = Vec::new();
+        let report = storage.salvage(|progress| updates.push(progress)).expect("salvage");
+        assert!(report.verified);
+        assert_eq!(updates.last().map(|p| p.tables_done), Some(report.tables.len()));
+        let protocols = report.tables.iter().find(|t| t.table == "protocols").expect("protocols");
+        assert!(protocols.recovered > 0 && protocols.recovered < 300);
+        assert_eq!(report.recovered_rows, report.tables.iter().map(|t| t.recovered).sum::<usize>());
+
+        let damaged = storage.apply_salvage(&report.id).expect("apply");
+        assert!(damaged.exists());
+        assert!(storage.health_check().expect("health").is_healthy);
+        let listed = storage.list_protocols().expect("list");
+        assert_eq!(listed.len(), protocols.recovered);
+        assert!(listed.iter().all(|p| p.name != "Salvage-0150"));
+    }
+
+    #[test]
+    fn reset_damaged_database_keeps_a_copy_and_starts_empty() {
+        let storage = create_test_storage();
+        storage.upsert_protocol(&PeptideProtocol::new("Old", "BPC-157")).expect("upsert");
+
+        let damaged = storage.reset_damaged_database().expect("reset");
+        assert!(damaged.exists());
+        assert!(storage.list_protocols().expect("list").is_empty());
+        storage.upsert_protocol(&PeptideProtocol::new("New", "BPC-157")).expect("upsert after reset");
+        assert!(storage.apply_salvage("missing").is_err());
+
+        storage.delete_recovery_files().expect("delete recovery files");
+        assert!(!damaged.exists());
+    }
+
+    #[test]
+    fn analytics_dataset_is_plaintext_with_a_column_per_field() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Morning", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert");
+        let dose = DoseLog::new(protocol.id.clone(), "abdomen".to_string(), 0.25);
+        storage.append_dose_log(&dose).expect("append dose");
+        storage.move_to_trash(TrashEntityType::DoseLog, std::slice::from_ref(&dose.id)).expect("trash");
+
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("analysis.sqlite");
+        let export = storage.export_analytics_dataset(&path).expect("export");
+        let rows = |name: &str| export.tables.iter().find(|t| t.name == name).map(|t| t.rows);
+        assert_eq!(rows("protocols"), Some(1));
+        assert_eq!(rows("dose_logs"), Some(1));
+        assert_eq!(rows("journal_entries"), Some(0));
+
+        let out = Connection::open(&path).expect("open export");
+        let (name, peptide): (String, String) = out
+            .query_row("SELECT name, peptide_name FROM protocols", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .expect("protocol row");
+        assert_eq!((name.as_str(), peptide.as_str()), ("Morning", "BPC-157"));
+        let (amount, trashed): (f64, Option<i64>) = out
+            .query_row("SELECT amount_mg, deleted_at FROM dose_logs", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .expect("dose row");
+        assert_eq!(amount, 0.25);
+        assert!(trashed.is_some());
+        let payload_columns: i64 = out
+            .query_row("SELECT COUNT(*) FROM pragma_table_info('protocols') WHERE name = 'payload'", [], |row| row.get(0))
+            .expect("columns");
+        assert_eq!(payload_columns, 0);
+
+        assert!(storage.export_analytics_dataset(&path).is_err());
+    }
+
+    #[test]
+    fn secure_wipe_deletes_every_row_and_truncates_wal() {
+        let storage = create_test_storage();
+        let protocol = PeptideProtocol::new("Wiped Protocol", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("upsert");
+        storage
+            .append_dose_log(&DoseLog::new(protocol.id.clone(), "abdomen".to_string(), 0.25))
+            .expect("append dose");
+
+        let deleted = storage.secure_wipe().expect("wipe");
+        assert!(deleted >= 3, "protocol, dose log and key check are deleted");
+        assert!(storage.list_protocols().expect("list").is_empty());
+        assert!(storage.list_dose_logs().expect("list").is_empty());
+        assert!(storage.search("wiped", None, 10).expect("search").is_empty());
+
+        let wal = storage.db_path.with_extension("sqlite-wal");
+        let wal_len = std::fs::metadata(&wal).map(|meta| meta.len()).unwrap_or(0);
+        assert_eq!(wal_len, 0);
+
+        // The schema survives, so the database can be set up again
+        storage.initialize().expect("re-init");
+        storage.upsert_protocol(&protocol).expect("upsert after wipe");
+        assert_eq!(storage.list_protocols().expect("list").len(), 1);
+    }
+
     #[test]
     fn checkpoint_wal_passive_mode() {
         let storage = create_test_storage();
@@ -2672,6 +10036,36 @@ mod tests {
         assert!(stats_after.page_count > 0);
     }
 
+    #[test]
+    fn concurrent_writes_go_through_one_writer() {
+        let storage = Arc::new(create_test_storage());
+        let protocol = PeptideProtocol::new("Recovery", "BPC-157");
+        storage.upsert_protocol(&protocol).expect("protocol");
+
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let storage = storage.clone();
+                let protocol_id = protocol.id.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..10 {
+                        storage
+                            .append_dose_log(&DoseLog::new(protocol_id.as_str(), "abdomen", 0.25))
+                            .expect("append dose");
+                        storage.list_dose_logs().expect("list doses");
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().expect("writer thread");
+        }
+        assert_eq!(storage.list_dose_logs().expect("list doses").len(), 80);
+
+        // Pooled connections are read-only
+        let conn = storage.open_connection().expect("read connection");
+        assert!(conn.execute("DELETE FROM dose_logs", []).is_err());
+    }
+
     #[test]
     fn cache_size_is_at_least_64mb() {
         let storage = create_test_storage();
@@ -2702,4 +10096,77 @@ mod tests {
             );
         }
     }
+
+    // =============================================================================
+    // Re-encryption Tests
+    // =============================================================================
+
+    #[test]
+    fn encrypted_columns_cover_every_blob_column() {
+        let storage = create_test_storage();
+        let conn = storage.connection().expect("get connection");
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT m.name, c.name FROM sqlite_master m, pragma_table_info(m.name) c \
+                 WHERE m.type = 'table' AND c.type = 'BLOB' AND m.name NOT IN ('key_check', 'key_recovery') \
+                 AND m.name NOT LIKE 'search_index%'",
+            )
+            .unwrap();
+        let columns: Vec<(String, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+
+        assert!(!columns.is_empty());
+        for (table, column) in columns {
+            assert!(
+                ENCRYPTED_COLUMNS.contains(&(table.as_str(), column.as_str())),
+                "{}.{} is missing from ENCRYPTED_COLUMNS",
+                table,
+                column
+            );
+        }
+    }
+
+    #[test]
+    fn rotate_key_switches_every_value_to_the_new_key() {
+        let tmp = tempdir().expect("tempdir");
+        let open_with = |key: u8| {
+            StorageManager::new(StorageConfig {
+                data_dir: Some(tmp.path().to_path_buf()),
+                db_file_name: Some("test.sqlite".into()),
+                key_provider: Arc::new(StaticKeyProvider::new(vec![key; 32]).unwrap()),
+            })
+            .expect("storage manager")
+        };
+
+        let storage = open_with(1);
+        storage.initialize().unwrap();
+        let protocol = PeptideProtocol::new("Protocol A", "BPC-157");
+        storage.upsert_protocol(&protocol).unwrap();
+        storage
+            .append_dose_log(&DoseLog::new(protocol.id.clone(), "Abdomen".to_string(), 0.25))
+            .unwrap();
+        assert!(storage.verify_key().unwrap());
+
+        let new_key = KeyMaterial::new(vec![2u8; 32]).unwrap();
+        let mut reports = Vec::new();
+        let count = storage
+            .rotate_key(&EnvelopeEncryption::with_key(&new_key), None, |progress| reports.push(progress))
+            .unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].table, "protocols");
+        assert_eq!(reports[1].processed, reports[1].total);
+
+        assert!(!storage.verify_key().unwrap());
+        assert!(storage.list_protocols().is_err());
+
+        let reopened = open_with(2);
+        assert!(reopened.verify_key().unwrap());
+        assert_eq!(reopened.list_protocols().unwrap()[0].name, "Protocol A");
+        assert_eq!(reopened.list_dose_logs().unwrap().len(), 1);
+    }
 }
@@ -0,0 +1,171 @@
+//! Saved doses for one-click logging
+//!
+//! A preset is the protocol, amount and site of a dose taken often, with a
+//! label for when it's taken, e.g. "Morning". Logging from a preset saves a
+//! dose with those values at the current time. Favorites are listed first
+//! and the first favorite is what the quick-log shortcut logs.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::DoseLog;
+use crate::settings::Setting;
+
+/// Most presets that can be saved
+pub const MAX_DOSE_PRESETS: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DosePreset {
+    /// Generated when a preset is first saved
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub protocol_id: String,
+    pub amount_mg: f32,
+    pub site: String,
+    /// When the dose is usually taken, e.g. "Morning" or "Before bed"
+    #[serde(default)]
+    pub time_of_day: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub favorite: bool,
+}
+
+impl DosePreset {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Give the preset a name".to_string());
+        }
+        if self.protocol_id.trim().is_empty() {
+            return Err(format!("Choose a protocol for {}", self.name));
+        }
+        if !self.amount_mg.is_finite() || self.amount_mg <= 0.0 {
+            return Err(format!("The amount for {} must be above zero", self.name));
+        }
+        if self.site.trim().is_empty() {
+            return Err(format!("Choose an injection site for {}", self.name));
+        }
+        Ok(())
+    }
+
+    /// A new dose log with this preset's values, logged now
+    pub fn to_dose_log(&self) -> DoseLog {
+        let mut log = DoseLog::new(self.protocol_id.as_str(), self.site.trim(), self.amount_mg);
+        log.notes = self.notes.clone().filter(|notes| !notes.trim().is_empty());
+        log
+    }
+}
+
+/// Saved dose presets, favorites first
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DosePresets {
+    pub presets: Vec<DosePreset>,
+}
+
+impl DosePresets {
+    /// Add `preset`, or replace the one with its id, and keep favorites first
+    ///
+    /// A preset without an id gets a new one. Returns the saved preset.
+    pub fn save(&mut self, mut preset: DosePreset) -> DosePreset {
+        if preset.id.trim().is_empty() {
+            preset.id = Uuid::new_v4().to_string();
+        }
+        match self.presets.iter_mut().find(|existing| existing.id == preset.id) {
+            Some(existing) => *existing = preset.clone(),
+            None => self.presets.push(preset.clone()),
+        }
+        // Stable, so presets keep their order within favorites and the rest
+        self.presets.sort_by_key(|preset| !preset.favorite);
+        preset
+    }
+
+    /// Remove the preset `id`; false if there was none
+    pub fn remove(&mut self, id: &str) -> bool {
+        let before = self.presets.len();
+        self.presets.retain(|preset| preset.id != id);
+        self.presets.len() != before
+    }
+
+    pub fn get(&self, id: &str) -> Option<&DosePreset> {
+        self.presets.iter().find(|preset| preset.id == id)
+    }
+
+    /// The preset the quick-log shortcut logs: the first favorite, or the
+    /// only preset when there's just one
+    pub fn quick_log(&self) -> Option<&DosePreset> {
+        let only = match self.presets.as_slice() {
+            [only] => Some(only),
+            _ => None,
+        };
+        self.presets.iter().find(|preset| preset.favorite).or(only)
+    }
+}
+
+impl Setting for DosePresets {
+    const KEY: &'static str = "doses.presets";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.presets.len() > MAX_DOSE_PRESETS {
+            return Err(format!("Save at most {} dose presets", MAX_DOSE_PRESETS));
+        }
+        let mut names = HashSet::new();
+        for preset in &self.presets {
+            preset.validate()?;
+            if !names.insert(preset.name.trim().to_lowercase()) {
+                return Err(format!("There is already a preset named {}", preset.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(name: &str, favorite: bool) -> DosePreset {
+        DosePreset {
+            id: String::new(),
+            name: name.to_string(),
+            protocol_id: "p1".to_string(),
+            amount_mg: 0.25,
+            site: " Left Abdomen ".to_string(),
+            time_of_day: Some("Morning".to_string()),
+            notes: Some(" ".to_string()),
+            favorite,
+        }
+    }
+
+    #[test]
+    fn favorites_come_first_and_are_quick_logged() {
+        let mut presets = DosePresets::default();
+        let morning = presets.save(preset("Morning", false));
+        assert!(!morning.id.is_empty());
+        assert_eq!(presets.quick_log().map(|p| p.name.as_str()), Some("Morning"));
+
+        let evening = presets.save(preset("Evening", false));
+        assert!(presets.quick_log().is_none());
+
+        presets.save(DosePreset { favorite: true, ..evening.clone() });
+        assert_eq!(presets.presets.len(), 2);
+        assert_eq!(presets.presets[0].id, evening.id);
+        assert_eq!(presets.quick_log().map(|p| p.id.as_str()), Some(evening.id.as_str()));
+        assert!(Setting::validate(&presets).is_ok());
+
+        presets.save(preset("morning", false));
+        assert!(Setting::validate(&presets).is_err());
+
+        assert!(presets.remove(&morning.id));
+        assert!(!presets.remove(&morning.id));
+
+        let log = presets.get(&evening.id).unwrap().to_dose_log();
+        assert_eq!(log.site, "Left Abdomen");
+        assert!(log.notes.is_none());
+        assert_eq!(log.amount_mg, 0.25);
+    }
+}
@@ -0,0 +1,112 @@
+//! Dose statistics computed from plaintext index columns
+//!
+//! Dose log payloads are encrypted, so `dose_logs` also stores a few
+//! non-sensitive columns (amount, a coarse injection site code and the
+//! schedule a dose was logged from) that let usage and adherence be
+//! aggregated in SQL without decrypting every row. Free-text sites are
+//! reduced to a fixed set of codes so notes typed into the site field never
+//! end up in plaintext.
+
+use serde::Serialize;
+use time::Date;
+
+/// Code stored for sites that don't match a known body area
+pub const OTHER_SITE_CODE: &str = "other";
+
+/// Body areas recognized in free-text sites, with the words that name them
+const SITE_AREAS: &[(&str, &[&str])] = &[
+    ("abdomen", &["abdomen", "abdominal", "belly", "stomach", "navel"]),
+    ("thigh", &["thigh", "quad", "leg"]),
+    ("deltoid", &["deltoid", "delt", "shoulder", "arm"]),
+    ("glute", &["glute", "buttock", "hip"]),
+];
+
+/// Coarse code for a free-text injection site, e.g. "Left Abdomen" becomes
+/// `left_abdomen` and "Right thigh (outer)" becomes `right_thigh`
+pub fn site_code(site: &str) -> String {
+    let words: Vec<String> = site
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect();
+    let has = |names: &[&str]| {
+        words
+            .iter()
+            .any(|word| names.iter().any(|name| word == name || word.strip_suffix('s') == Some(name)))
+    };
+
+    let Some((area, _)) = SITE_AREAS.iter().find(|(_, names)| has(names)) else {
+        return OTHER_SITE_CODE.to_string();
+    };
+    let side = match (has(&["left", "l"]), has(&["right", "r"])) {
+        (true, false) => Some("left"),
+        (false, true) => Some("right"),
+        _ => None,
+    };
+    match side {
+        Some(side) => format!("{}_{}", side, area),
+        None => area.to_string(),
+    }
+}
+
+/// Which dose logs to aggregate; unset fields match everything
+///
+/// Dates are compared against the day a dose was logged, and both ends are
+/// included.
+#[derive(Debug, Clone, Default)]
+pub struct DoseStatsFilter {
+    pub protocol_id: Option<String>,
+    pub schedule_id: Option<String>,
+    pub since: Option<Date>,
+    pub until: Option<Date>,
+    /// Leave out doses that were edited after they were logged
+    pub exclude_edited: bool,
+}
+
+/// Doses logged for one protocol
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolDoseUsage {
+    pub protocol_id: String,
+    pub dose_count: u32,
+    pub total_mg: f64,
+    /// Distinct days with at least one dose
+    pub active_days: u32,
+}
+
+/// Doses logged at one injection site
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteDoseUsage {
+    /// See [`site_code`]
+    pub site_code: String,
+    pub dose_count: u32,
+    pub total_mg: f64,
+}
+
+/// Doses logged on one day
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyDoseTotal {
+    /// Day as `YYYY-MM-DD`
+    pub date: String,
+    pub dose_count: u32,
+    pub total_mg: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_site_codes() {
+        assert_eq!(site_code("Left Abdomen"), "left_abdomen");
+        assert_eq!(site_code("right thigh (outer)"), "right_thigh");
+        assert_eq!(site_code("Deltoid"), "deltoid");
+        assert_eq!(site_code("R glutes"), "right_glute");
+        assert_eq!(site_code("belly button, 2in left"), "left_abdomen");
+        assert_eq!(site_code("left and right abdomen"), "abdomen");
+        assert_eq!(site_code(""), OTHER_SITE_CODE);
+        assert_eq!(site_code("behind my ear"), OTHER_SITE_CODE);
+    }
+}
@@ -1,6 +1,8 @@
 use std::sync::Arc;
 
 use anyhow::{anyhow, Context, Result};
+use blake2::digest::Mac;
+use blake2::Blake2sMac256;
 use chacha20poly1305::aead::{Aead, KeyInit};
 use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
 use rand::{rngs::OsRng, RngCore};
@@ -85,6 +87,7 @@ impl KeyMaterial {
 /// ```text
 /// [12-byte nonce][ciphertext + 16-byte auth tag]
 /// ```
+#[derive(Clone)]
 pub struct EnvelopeEncryption {
     key_provider: Arc<dyn KeyProvider>,
 }
@@ -95,6 +98,14 @@ impl EnvelopeEncryption {
         Self { key_provider }
     }
 
+    /// Creates an envelope encryption instance for a fixed key.
+    ///
+    /// Used while re-encrypting, when data must be sealed under a key that
+    /// the storage's own provider doesn't hold yet.
+    pub fn with_key(key: &KeyMaterial) -> Self {
+        Self::new(Arc::new(StaticKeyProvider { key: key.clone() }))
+    }
+
     /// Encrypts plaintext and returns `[nonce || ciphertext]`.
     ///
     /// # Arguments
@@ -157,6 +168,31 @@ impl EnvelopeEncryption {
             .decrypt(&nonce, ciphertext)
             .map_err(|e| anyhow::anyhow!("decryption failed: {e}"))
     }
+
+    /// Hashes search terms with a key derived from the encryption key.
+    ///
+    /// The same term always produces the same token, so an index of tokens
+    /// can be matched against hashed queries without storing the terms
+    /// themselves. Tokens are truncated to 96 bits and hex-encoded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if key retrieval fails.
+    pub fn blind_tokens(&self, terms: &[String]) -> Result<Vec<String>> {
+        let key_bytes = self.key_provider.key_material()?.to_key_bytes()?;
+        let mac = <Blake2sMac256 as Mac>::new_from_slice(&key_bytes)
+            .map_err(|e| anyhow!("invalid search key: {e}"))?;
+
+        Ok(terms
+            .iter()
+            .map(|term| {
+                let mut mac = mac.clone();
+                mac.update(b"peptrack-search-v1:");
+                mac.update(term.as_bytes());
+                hex::encode(&mac.finalize().into_bytes()[..12])
+            })
+            .collect())
+    }
 }
 
 /// Key provider that reads hex-encoded keys from environment variables.
@@ -229,6 +265,19 @@ impl KeyProvider for StaticKeyProvider {
 mod tests {
     use super::*;
 
+    #[test]
+    fn blind_tokens_are_deterministic_and_key_dependent() {
+        let terms = vec!["ipamorelin".to_string(), "bpc".to_string()];
+        let a = EnvelopeEncryption::new(Arc::new(StaticKeyProvider::new(vec![7u8; 32]).unwrap()));
+        let b = EnvelopeEncryption::new(Arc::new(StaticKeyProvider::new(vec![8u8; 32]).unwrap()));
+
+        let tokens = a.blind_tokens(&terms).unwrap();
+        assert_eq!(tokens, a.blind_tokens(&terms).unwrap());
+        assert_ne!(tokens, b.blind_tokens(&terms).unwrap());
+        assert_eq!(tokens[0].len(), 24);
+        assert!(!tokens[0].contains("ipa"));
+    }
+
     #[test]
     fn key_material_rejects_short_keys() {
         let result = KeyMaterial::new(vec![1u8; 16]);
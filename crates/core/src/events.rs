@@ -0,0 +1,23 @@
+//! Storage change events
+//!
+//! Background jobs write to storage without the UI knowing until it next
+//! reloads. Listeners registered with
+//! [`StorageManager::subscribe`](crate::StorageManager::subscribe) are told
+//! about the writes the UI shows as they happen, so the app can forward them
+//! to the frontend. Listeners run on the writing thread after the write has
+//! succeeded, so they should hand the event off rather than do work.
+
+use std::sync::Arc;
+
+use crate::models::Alert;
+
+/// A write other parts of the app may want to react to
+#[derive(Debug, Clone)]
+pub enum StorageEvent {
+    /// An alert was saved, by [`StorageManager::create_alert`](crate::StorageManager::create_alert)
+    /// or [`raise_alert`](crate::StorageManager::raise_alert)
+    AlertCreated(Alert),
+}
+
+/// Called with each [`StorageEvent`]
+pub type StorageListener = Arc<dyn Fn(&StorageEvent) + Send + Sync>;
@@ -0,0 +1,107 @@
+//! Inventory expiry calendar
+//!
+//! Lists the day each vial stops being usable. A vial has a dry-powder
+//! expiry from the label and, once mixed, a much shorter reconstituted
+//! expiry; only whichever comes first goes on the calendar. Vials that are
+//! empty or already marked expired are left off.
+
+use serde::Serialize;
+use time::{Date, OffsetDateTime};
+
+use crate::models::{InventoryItem, VialStatus};
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExpiryKind {
+    /// The label expiry of the powder
+    DryPowder,
+    /// The fridge life after mixing
+    Reconstituted,
+}
+
+/// A vial reaching its expiry
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpiryEvent {
+    pub inventory_id: String,
+    pub protocol_id: String,
+    pub vial_number: Option<String>,
+    pub kind: ExpiryKind,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+    pub remaining_mg: Option<f32>,
+}
+
+/// Vials expiring on one day
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpiryDay {
+    /// Day as `YYYY-MM-DD`
+    pub date: String,
+    pub events: Vec<ExpiryEvent>,
+}
+
+/// Vials expiring from `from` through `until`, by day, earliest first
+pub fn expiry_calendar(items: &[InventoryItem], from: Date, until: Date) -> Vec<ExpiryDay> {
+    let mut events: Vec<ExpiryEvent> = items
+        .iter()
+        .filter(|item| !matches!(item.vial_status, VialStatus::Empty | VialStatus::Expired))
+        .filter_map(|item| {
+            let expires_at = item.effective_expiry()?;
+            (from..=until).contains(&expires_at.date()).then(|| ExpiryEvent {
+                inventory_id: item.id.clone(),
+                protocol_id: item.protocol_id.clone(),
+                vial_number: item.vial_number.clone(),
+                kind: if item.expires_as_reconstituted() {
+                    ExpiryKind::Reconstituted
+                } else {
+                    ExpiryKind::DryPowder
+                },
+                expires_at,
+                remaining_mg: item.quantity_remaining_mg.or(item.quantity_mg),
+            })
+        })
+        .collect();
+    events.sort_by_key(|event| event.expires_at);
+
+    let mut days: Vec<ExpiryDay> = Vec::new();
+    for event in events {
+        let date = event.expires_at.date().to_string();
+        match days.last_mut() {
+            Some(day) if day.date == date => day.events.push(event),
+            _ => days.push(ExpiryDay { date, events: vec![event] }),
+        }
+    }
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, datetime};
+
+    #[test]
+    fn calendar_uses_whichever_expiry_comes_first() {
+        let mut mixed = InventoryItem::new("protocol-1");
+        mixed.expiry_date = Some(datetime!(2025-01-01 0:00 UTC));
+        mixed.reconstitute(datetime!(2024-03-01 8:00 UTC), 28);
+        assert_eq!(mixed.vial_status, VialStatus::Opened);
+
+        let mut sealed = InventoryItem::new("protocol-1");
+        sealed.expiry_date = Some(datetime!(2024-03-29 12:00 UTC));
+
+        let mut empty = InventoryItem::new("protocol-1");
+        empty.expiry_date = Some(datetime!(2024-03-10 0:00 UTC));
+        empty.vial_status = VialStatus::Empty;
+
+        let mut later = InventoryItem::new("protocol-1");
+        later.expiry_date = Some(datetime!(2024-06-01 0:00 UTC));
+
+        let days = expiry_calendar(&[sealed, mixed, empty, later], date!(2024 - 03 - 01), date!(2024 - 03 - 31));
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].date, "2024-03-29");
+        assert_eq!(days[0].events.len(), 2);
+        assert_eq!(days[0].events[0].kind, ExpiryKind::Reconstituted);
+        assert_eq!(days[0].events[1].kind, ExpiryKind::DryPowder);
+    }
+}
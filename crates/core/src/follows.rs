@@ -0,0 +1,161 @@
+//! Followed peptides: literature alerts without email
+//!
+//! Following a peptide polls OpenAlex for newly published works about it.
+//! New works wait in [`PeptideFollow::pending`] until the user caches or
+//! dismisses them, so following a busy peptide doesn't fill the literature
+//! cache on its own.
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::models::LiteratureEntry;
+use crate::settings::Setting;
+
+/// Days between checks unless set otherwise
+pub const DEFAULT_FOLLOW_INTERVAL_DAYS: u32 = 7;
+/// Most works waiting per follow; older ones are dropped first
+pub const MAX_PENDING_WORKS: usize = 50;
+/// Dedup keys remembered per follow
+const MAX_SEEN_KEYS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeptideFollow {
+    pub id: String,
+    pub peptide_name: String,
+    /// OpenAlex concept matching the peptide, e.g. "C2779134260"; works are
+    /// found by full-text search for the name when there is none
+    pub concept_id: Option<String>,
+    pub concept_name: Option<String>,
+    pub enabled: bool,
+    pub interval_days: u32,
+    pub last_checked_at: Option<OffsetDateTime>,
+    /// Dedup keys of works already found, newest last
+    #[serde(default)]
+    pub seen_keys: Vec<String>,
+    /// New works not yet cached or dismissed, newest first
+    #[serde(default)]
+    pub pending: Vec<LiteratureEntry>,
+    pub created_at: OffsetDateTime,
+}
+
+impl PeptideFollow {
+    pub fn new(peptide_name: impl Into<String>, now: OffsetDateTime) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            peptide_name: peptide_name.into(),
+            concept_id: None,
+            concept_name: None,
+            enabled: true,
+            interval_days: DEFAULT_FOLLOW_INTERVAL_DAYS,
+            last_checked_at: None,
+            seen_keys: Vec::new(),
+            pending: Vec::new(),
+            created_at: now,
+        }
+    }
+
+    pub fn next_check_at(&self) -> OffsetDateTime {
+        match self.last_checked_at {
+            Some(checked) => checked + time::Duration::days(self.interval_days.max(1) as i64),
+            None => self.created_at,
+        }
+    }
+
+    pub fn is_due(&self, now: OffsetDateTime) -> bool {
+        self.enabled && self.next_check_at() <= now
+    }
+
+    /// Works published on or after this date are looked for: a day before
+    /// the last check, or one interval before the follow was created
+    pub fn published_since(&self) -> time::Date {
+        let from = match self.last_checked_at {
+            Some(checked) => checked - time::Duration::days(1),
+            None => self.created_at - time::Duration::days(self.interval_days.max(1) as i64),
+        };
+        from.date()
+    }
+
+    /// Add works found by a check, keeping those whose key wasn't seen
+    /// before; returns how many were added
+    pub fn add_found(&mut self, found: Vec<(String, LiteratureEntry)>, now: OffsetDateTime) -> usize {
+        let mut added = Vec::new();
+        for (key, entry) in found {
+            if self.seen_keys.contains(&key) {
+                continue;
+            }
+            self.seen_keys.push(key);
+            added.push(entry);
+        }
+        let count = added.len();
+
+        added.append(&mut self.pending);
+        added.truncate(MAX_PENDING_WORKS);
+        self.pending = added;
+        if self.seen_keys.len() > MAX_SEEN_KEYS {
+            self.seen_keys.drain(..self.seen_keys.len() - MAX_SEEN_KEYS);
+        }
+        self.last_checked_at = Some(now);
+        count
+    }
+
+    /// Remove a pending work, e.g. to cache it
+    pub fn take_pending(&mut self, entry_id: &str) -> Option<LiteratureEntry> {
+        let index = self.pending.iter().position(|entry| entry.id == entry_id)?;
+        Some(self.pending.remove(index))
+    }
+}
+
+/// Every followed peptide
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PeptideFollows {
+    pub follows: Vec<PeptideFollow>,
+}
+
+impl PeptideFollows {
+    pub fn get_mut(&mut self, follow_id: &str) -> Option<&mut PeptideFollow> {
+        self.follows.iter_mut().find(|follow| follow.id == follow_id)
+    }
+
+    pub fn find_peptide(&self, peptide_name: &str) -> Option<&PeptideFollow> {
+        self.follows
+            .iter()
+            .find(|follow| follow.peptide_name.eq_ignore_ascii_case(peptide_name.trim()))
+    }
+}
+
+impl Setting for PeptideFollows {
+    const KEY: &'static str = "literature.follows";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, datetime};
+
+    #[test]
+    fn follows_keep_new_works_until_taken() {
+        let created = datetime!(2024-05-01 9:00 UTC);
+        let mut follow = PeptideFollow::new("BPC-157", created);
+        assert!(follow.is_due(created));
+        assert_eq!(follow.published_since(), date!(2024-04-24));
+
+        let work = |title: &str| LiteratureEntry::new("openalex", title);
+        let first = work("First");
+        let first_id = first.id.clone();
+        let checked = datetime!(2024-05-02 9:00 UTC);
+        let added = follow.add_found(vec![("doi:1".into(), first), ("doi:2".into(), work("Second"))], checked);
+        assert_eq!(added, 2);
+        assert!(!follow.is_due(checked + time::Duration::days(6)));
+        assert_eq!(follow.published_since(), date!(2024-05-01));
+
+        let later = checked + time::Duration::days(7);
+        assert_eq!(follow.add_found(vec![("doi:2".into(), work("Second again")), ("doi:3".into(), work("Third"))], later), 1);
+        let titles: Vec<&str> = follow.pending.iter().map(|entry| entry.title.as_str()).collect();
+        assert_eq!(titles, vec!["Third", "First", "Second"]);
+
+        assert_eq!(follow.take_pending(&first_id).map(|entry| entry.title), Some("First".to_string()));
+        assert!(follow.take_pending(&first_id).is_none());
+        assert_eq!(follow.pending.len(), 2);
+    }
+}
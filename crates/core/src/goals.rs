@@ -0,0 +1,260 @@
+//! Goal progress and projected completion
+//!
+//! A [`Goal`]'s readings come from body metrics or lab results taken on or
+//! after its start date. Progress is measured from the goal's start value
+//! (or its first reading) towards the target, and the completion date is
+//! projected from a least-squares trend over the most recent readings.
+
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+
+use crate::models::{BodyMetric, Goal, GoalMetric, LabResult};
+
+/// Readings this far before the latest one set the trend
+pub const PROJECTION_WINDOW_DAYS: i64 = 90;
+/// Projections further out than this are reported as stalled
+const MAX_PROJECTION_DAYS: f64 = 5.0 * 365.0;
+
+/// One measurement of a goal's metric
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GoalReading {
+    pub at: OffsetDateTime,
+    pub value: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalStatus {
+    /// No readings since the goal started
+    NoData,
+    /// Too few readings to project a completion date
+    InProgress,
+    /// Projected to reach the target by the target date, or has no target
+    /// date and is heading the right way
+    OnTrack,
+    /// Projected to reach the target after the target date, or the target
+    /// date has passed
+    Behind,
+    /// The trend is flat or heading away from the target
+    Stalled,
+    Achieved,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GoalProgress {
+    pub goal_id: String,
+    pub status: GoalStatus,
+    /// Start value, or the first reading when the goal has none
+    pub baseline: Option<f32>,
+    pub current: Option<f32>,
+    /// Date of the latest reading (YYYY-MM-DD)
+    pub current_date: Option<String>,
+    pub target: f32,
+    /// Share of the way from baseline to target, 0-100
+    pub percent_complete: Option<f32>,
+    /// Change per week over the projection window
+    pub rate_per_week: Option<f32>,
+    /// When the trend reaches the target (YYYY-MM-DD)
+    pub projected_date: Option<String>,
+    pub reading_count: usize,
+}
+
+/// The goal's readings since its start date, oldest first
+///
+/// Lab markers are matched case-insensitively; when the goal has a unit,
+/// results in other units are left out since they aren't comparable.
+pub fn goal_readings(goal: &Goal, metrics: &[BodyMetric], lab_results: &[LabResult]) -> Vec<GoalReading> {
+    let mut readings: Vec<GoalReading> = match goal.metric {
+        GoalMetric::LabMarker => {
+            let Some(marker) = goal.marker.as_deref().map(str::trim) else {
+                return Vec::new();
+            };
+            lab_results
+                .iter()
+                .filter(|result| result.marker.trim().eq_ignore_ascii_case(marker))
+                .filter(|result| {
+                    goal.unit
+                        .as_deref()
+                        .is_none_or(|unit| result.unit.trim().eq_ignore_ascii_case(unit.trim()))
+                })
+                .map(|result| GoalReading {
+                    at: result.collected_at,
+                    value: result.value,
+                })
+                .collect()
+        }
+        metric => metrics
+            .iter()
+            .filter_map(|entry| {
+                let value = match metric {
+                    GoalMetric::WeightKg => entry.weight_kg,
+                    GoalMetric::BodyFatPercentage => entry.body_fat_percentage,
+                    GoalMetric::MuscleMassKg => entry.muscle_mass_kg,
+                    GoalMetric::WaistCm => entry.waist_cm,
+                    GoalMetric::LabMarker => None,
+                }?;
+                Some(GoalReading { at: entry.date, value })
+            })
+            .collect(),
+    };
+
+    readings.retain(|reading| reading.at >= goal.start_date && reading.value.is_finite());
+    readings.sort_by_key(|reading| reading.at);
+    readings
+}
+
+/// Least-squares slope in units per day, or `None` without two readings on
+/// different days
+fn trend_per_day(readings: &[GoalReading]) -> Option<f64> {
+    let first = readings.first()?.at;
+    let points: Vec<(f64, f64)> = readings
+        .iter()
+        .map(|reading| ((reading.at - first).as_seconds_f64() / 86_400.0, f64::from(reading.value)))
+        .collect();
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if sxx < 1.0 {
+        return None;
+    }
+    let sxy: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    Some(sxy / sxx)
+}
+
+/// Work out how far along a goal is and when it will be reached
+///
+/// `readings` are oldest first, as returned by [`goal_readings`]. `now`
+/// decides whether a target date has already passed.
+pub fn compute_goal_progress(goal: &Goal, readings: &[GoalReading], now: OffsetDateTime) -> GoalProgress {
+    let target = goal.target_value;
+    let mut progress = GoalProgress {
+        goal_id: goal.id.clone(),
+        status: GoalStatus::NoData,
+        baseline: goal.start_value.or(readings.first().map(|reading| reading.value)),
+        current: None,
+        current_date: None,
+        target,
+        percent_complete: None,
+        rate_per_week: None,
+        projected_date: None,
+        reading_count: readings.len(),
+    };
+    let (Some(latest), Some(baseline)) = (readings.last(), progress.baseline) else {
+        return progress;
+    };
+    let current = latest.value;
+    progress.current = Some(current);
+    progress.current_date = Some(latest.at.date().to_string());
+
+    // Goals can go either way: losing weight or raising a marker
+    let decreasing = target < baseline;
+    let achieved = if decreasing { current <= target } else { current >= target };
+    progress.percent_complete = Some(if achieved {
+        100.0
+    } else {
+        ((current - baseline) / (target - baseline) * 100.0).clamp(0.0, 100.0)
+    });
+    if achieved {
+        progress.status = GoalStatus::Achieved;
+        return progress;
+    }
+
+    let window_start = latest.at - Duration::days(PROJECTION_WINDOW_DAYS);
+    let recent: Vec<GoalReading> = readings
+        .iter()
+        .filter(|reading| reading.at >= window_start)
+        .copied()
+        .collect();
+    let deadline_passed = goal.target_date.is_some_and(|date| date < now);
+
+    let Some(slope) = trend_per_day(&recent) else {
+        progress.status = if deadline_passed { GoalStatus::Behind } else { GoalStatus::InProgress };
+        return progress;
+    };
+    progress.rate_per_week = Some((slope * 7.0) as f32);
+
+    let remaining = f64::from(target - current);
+    let days = remaining / slope;
+    if slope == 0.0 || days <= 0.0 || days > MAX_PROJECTION_DAYS {
+        progress.status = GoalStatus::Stalled;
+        return progress;
+    }
+    let projected = latest.at + Duration::seconds_f64(days * 86_400.0);
+    progress.projected_date = Some(projected.date().to_string());
+
+    progress.status = match goal.target_date {
+        Some(_) if deadline_passed => GoalStatus::Behind,
+        Some(target_date) if projected.date() > target_date.date() => GoalStatus::Behind,
+        _ => GoalStatus::OnTrack,
+    };
+    progress
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn weight(at: OffsetDateTime, kg: f32) -> BodyMetric {
+        let mut metric = BodyMetric::new(at);
+        metric.weight_kg = Some(kg);
+        metric
+    }
+
+    #[test]
+    fn weight_loss_goal_projects_from_recent_trend() {
+        let start = datetime!(2025-01-01 08:00 UTC);
+        let mut goal = Goal::new("Cut", GoalMetric::WeightKg, 80.0, start);
+        goal.target_date = Some(datetime!(2025-03-01 00:00 UTC));
+        // Half a kilo a week, plus one reading from before the goal started
+        let metrics: Vec<BodyMetric> = std::iter::once(weight(start - Duration::days(10), 95.0))
+            .chain((0..5).map(|week| weight(start + Duration::weeks(week), 90.0 - 0.5 * week as f32)))
+            .collect();
+
+        let readings = goal_readings(&goal, &metrics, &[]);
+        assert_eq!(readings.len(), 5);
+
+        let progress = compute_goal_progress(&goal, &readings, datetime!(2025-02-01 00:00 UTC));
+        assert_eq!(progress.baseline, Some(90.0));
+        assert_eq!(progress.current, Some(88.0));
+        assert!((progress.percent_complete.unwrap() - 20.0).abs() < 1e-3);
+        assert!((progress.rate_per_week.unwrap() + 0.5).abs() < 1e-3);
+        // 8 kg to go at 0.5 kg a week from 2025-01-29
+        assert_eq!(progress.projected_date.as_deref(), Some("2025-05-21"));
+        assert_eq!(progress.status, GoalStatus::Behind);
+    }
+
+    #[test]
+    fn lab_goal_uses_matching_unit_and_can_be_achieved() {
+        let start = datetime!(2025-01-01 08:00 UTC);
+        let mut goal = Goal::new("IGF-1 up", GoalMetric::LabMarker, 250.0, start);
+        goal.marker = Some("igf-1".to_string());
+        goal.unit = Some("ng/mL".to_string());
+        let results = vec![
+            LabResult::new("IGF-1", 160.0, "ng/mL", start + Duration::days(1)),
+            LabResult::new("IGF-1", 30.0, "nmol/L", start + Duration::days(20)),
+            LabResult::new("IGF-1", 240.0, "ng/mL", start + Duration::days(40)),
+        ];
+
+        let readings = goal_readings(&goal, &[], &results);
+        assert_eq!(readings.len(), 2);
+        let progress = compute_goal_progress(&goal, &readings, start + Duration::days(41));
+        assert_eq!(progress.status, GoalStatus::OnTrack);
+
+        goal.target_value = 220.0;
+        let progress = compute_goal_progress(&goal, &readings, start + Duration::days(41));
+        assert_eq!(progress.status, GoalStatus::Achieved);
+        assert_eq!(progress.percent_complete, Some(100.0));
+    }
+
+    #[test]
+    fn goal_without_readings_has_no_progress() {
+        let goal = Goal::new("Waist", GoalMetric::WaistCm, 80.0, datetime!(2025-01-01 00:00 UTC));
+        let progress = compute_goal_progress(&goal, &[], datetime!(2025-02-01 00:00 UTC));
+        assert_eq!(progress.status, GoalStatus::NoData);
+        assert!(progress.percent_complete.is_none());
+    }
+}
@@ -0,0 +1,661 @@
+//! Importers for wearable and phone health exports
+//!
+//! Apple Health (`export.xml` from the Health app's "Export All Health Data")
+//! and Google Fit Takeout (`Daily activity metrics.csv`) are reduced to one
+//! [`DailyHealthSample`] per calendar day, which is then merged into
+//! [`BodyMetric`] rows without overwriting values the user already entered.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Read};
+
+use anyhow::{anyhow, Context, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use time::macros::format_description;
+use time::{Date, OffsetDateTime, UtcOffset};
+
+use crate::models::BodyMetric;
+use crate::settings::Setting;
+
+const POUNDS_TO_KG: f32 = 0.453_592_37;
+
+/// Where an import file came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthImportSource {
+    AppleHealth,
+    GoogleFit,
+    /// Android Health Connect, read by the mobile health bridge
+    HealthConnect,
+}
+
+impl HealthImportSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            HealthImportSource::AppleHealth => "Apple Health",
+            HealthImportSource::GoogleFit => "Google Fit",
+            HealthImportSource::HealthConnect => "Health Connect",
+        }
+    }
+}
+
+/// Google Fit CSV headers to read each metric from; `None` uses the
+/// standard Takeout column names
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GoogleFitColumns {
+    pub date: Option<String>,
+    pub weight_kg: Option<String>,
+    pub body_fat_percentage: Option<String>,
+    pub resting_heart_rate: Option<String>,
+    pub sleep: Option<String>,
+}
+
+/// Which data types to ingest
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HealthImportMapping {
+    pub weight: bool,
+    pub body_fat: bool,
+    pub resting_heart_rate: bool,
+    pub sleep: bool,
+    pub google_fit_columns: GoogleFitColumns,
+}
+
+impl Default for HealthImportMapping {
+    fn default() -> Self {
+        Self {
+            weight: true,
+            body_fat: true,
+            resting_heart_rate: true,
+            sleep: true,
+            google_fit_columns: GoogleFitColumns::default(),
+        }
+    }
+}
+
+impl Setting for HealthImportMapping {
+    const KEY: &'static str = "health_import.mapping";
+    const LEGACY_FILE: Option<&'static str> = Some("health_import.json");
+}
+
+/// One day's values, averaged across all readings that day
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyHealthSample {
+    pub date: Date,
+    pub offset: UtcOffset,
+    pub weight_kg: Option<f32>,
+    pub body_fat_percentage: Option<f32>,
+    pub resting_heart_rate_bpm: Option<f32>,
+    pub sleep_hours: Option<f32>,
+}
+
+impl DailyHealthSample {
+    fn is_empty(&self) -> bool {
+        self.weight_kg.is_none()
+            && self.body_fat_percentage.is_none()
+            && self.resting_heart_rate_bpm.is_none()
+            && self.sleep_hours.is_none()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Mean {
+    sum: f64,
+    count: u32,
+}
+
+impl Mean {
+    fn add(&mut self, value: f32) {
+        if value.is_finite() {
+            self.sum += value as f64;
+            self.count += 1;
+        }
+    }
+
+    fn value(&self) -> Option<f32> {
+        (self.count > 0).then(|| (self.sum / self.count as f64) as f32)
+    }
+}
+
+#[derive(Debug)]
+struct DayAccumulator {
+    offset: UtcOffset,
+    weight_kg: Mean,
+    body_fat_percentage: Mean,
+    resting_heart_rate_bpm: Mean,
+    /// Asleep intervals as (start, end) unix seconds; merged before summing
+    /// so overlapping watch and phone samples aren't counted twice
+    sleep_intervals: Vec<(i64, i64)>,
+    sleep_hours: Mean,
+}
+
+impl DayAccumulator {
+    fn new(offset: UtcOffset) -> Self {
+        Self {
+            offset,
+            weight_kg: Mean::default(),
+            body_fat_percentage: Mean::default(),
+            resting_heart_rate_bpm: Mean::default(),
+            sleep_intervals: Vec::new(),
+            sleep_hours: Mean::default(),
+        }
+    }
+
+    fn sleep_hours(&mut self) -> Option<f32> {
+        if self.sleep_intervals.is_empty() {
+            return self.sleep_hours.value();
+        }
+
+        self.sleep_intervals.sort_unstable();
+        let mut total = 0i64;
+        let mut current: Option<(i64, i64)> = None;
+        for &(start, end) in &self.sleep_intervals {
+            match current {
+                Some((cur_start, cur_end)) if start <= cur_end => {
+                    current = Some((cur_start, cur_end.max(end)));
+                }
+                Some((cur_start, cur_end)) => {
+                    total += cur_end - cur_start;
+                    current = Some((start, end));
+                }
+                None => current = Some((start, end)),
+            }
+        }
+        if let Some((start, end)) = current {
+            total += end - start;
+        }
+        Some(total as f32 / 3600.0)
+    }
+}
+
+#[derive(Debug, Default)]
+struct DailyAggregator {
+    days: BTreeMap<Date, DayAccumulator>,
+}
+
+impl DailyAggregator {
+    fn day(&mut self, date: Date, offset: UtcOffset) -> &mut DayAccumulator {
+        self.days
+            .entry(date)
+            .or_insert_with(|| DayAccumulator::new(offset))
+    }
+
+    fn finish(self) -> Vec<DailyHealthSample> {
+        self.days
+            .into_iter()
+            .map(|(date, mut day)| DailyHealthSample {
+                date,
+                offset: day.offset,
+                weight_kg: day.weight_kg.value(),
+                body_fat_percentage: day.body_fat_percentage.value(),
+                resting_heart_rate_bpm: day.resting_heart_rate_bpm.value(),
+                sleep_hours: day.sleep_hours(),
+            })
+            .filter(|sample| !sample.is_empty())
+            .collect()
+    }
+}
+
+fn parse_apple_date(value: &str) -> Result<OffsetDateTime> {
+    let format = format_description!(
+        "[year]-[month]-[day] [hour]:[minute]:[second] [offset_hour sign:mandatory][offset_minute]"
+    );
+    OffsetDateTime::parse(value, format).with_context(|| format!("Invalid date: {}", value))
+}
+
+fn attribute(element: &BytesStart, name: &[u8]) -> Result<Option<String>> {
+    for attr in element.attributes() {
+        let attr = attr.context("Malformed attribute in health export")?;
+        if attr.key.as_ref() == name {
+            return Ok(Some(String::from_utf8_lossy(&attr.value).into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+fn weight_in_kg(value: f32, unit: &str) -> Option<f32> {
+    match unit {
+        "kg" => Some(value),
+        "g" => Some(value / 1000.0),
+        "lb" => Some(value * POUNDS_TO_KG),
+        _ => None,
+    }
+}
+
+/// Apple stores body fat as a fraction (0.18) with unit "%"
+fn body_fat_percent(value: f32) -> f32 {
+    if value <= 1.0 {
+        value * 100.0
+    } else {
+        value
+    }
+}
+
+fn add_apple_record(
+    aggregator: &mut DailyAggregator,
+    element: &BytesStart,
+    mapping: &HealthImportMapping,
+) -> Result<()> {
+    let Some(record_type) = attribute(element, b"type")? else {
+        return Ok(());
+    };
+
+    let wanted = match record_type.as_str() {
+        "HKQuantityTypeIdentifierBodyMass" => mapping.weight,
+        "HKQuantityTypeIdentifierBodyFatPercentage" => mapping.body_fat,
+        "HKQuantityTypeIdentifierRestingHeartRate" => mapping.resting_heart_rate,
+        "HKCategoryTypeIdentifierSleepAnalysis" => mapping.sleep,
+        _ => false,
+    };
+    if !wanted {
+        return Ok(());
+    }
+
+    let value = attribute(element, b"value")?.unwrap_or_default();
+    let start = match attribute(element, b"startDate")? {
+        Some(date) => parse_apple_date(&date)?,
+        None => return Ok(()),
+    };
+
+    if record_type == "HKCategoryTypeIdentifierSleepAnalysis" {
+        // In-bed and awake samples are ignored; only asleep stages count
+        if !value.starts_with("HKCategoryValueSleepAnalysisAsleep") {
+            return Ok(());
+        }
+        let end = match attribute(element, b"endDate")? {
+            Some(date) => parse_apple_date(&date)?,
+            None => return Ok(()),
+        };
+        if end <= start {
+            return Ok(());
+        }
+        // Sleep counts towards the day you wake up
+        aggregator
+            .day(end.date(), end.offset())
+            .sleep_intervals
+            .push((start.unix_timestamp(), end.unix_timestamp()));
+        return Ok(());
+    }
+
+    let Ok(value) = value.trim().parse::<f32>() else {
+        return Ok(());
+    };
+    let unit = attribute(element, b"unit")?.unwrap_or_default();
+    let day = aggregator.day(start.date(), start.offset());
+
+    match record_type.as_str() {
+        "HKQuantityTypeIdentifierBodyMass" => {
+            if let Some(kg) = weight_in_kg(value, &unit) {
+                day.weight_kg.add(kg);
+            }
+        }
+        "HKQuantityTypeIdentifierBodyFatPercentage" => day.body_fat_percentage.add(body_fat_percent(value)),
+        "HKQuantityTypeIdentifierRestingHeartRate" => day.resting_heart_rate_bpm.add(value),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Parse an Apple Health `export.xml`, streaming so multi-gigabyte exports
+/// don't have to fit in memory
+pub fn parse_apple_health<R: BufRead>(reader: R, mapping: &HealthImportMapping) -> Result<Vec<DailyHealthSample>> {
+    let mut reader = Reader::from_reader(reader);
+    let mut aggregator = DailyAggregator::default();
+    let mut buf = Vec::new();
+    let mut saw_health_data = false;
+
+    loop {
+        match reader
+            .read_event_into(&mut buf)
+            .context("Failed to parse Apple Health export")?
+        {
+            Event::Start(element) | Event::Empty(element) => match element.name().as_ref() {
+                b"HealthData" => saw_health_data = true,
+                b"Record" => add_apple_record(&mut aggregator, &element, mapping)?,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if !saw_health_data {
+        return Err(anyhow!("Not an Apple Health export (expected export.xml)"));
+    }
+    Ok(aggregator.finish())
+}
+
+const DATE_COLUMNS: &[&str] = &["Date"];
+const WEIGHT_COLUMNS: &[&str] = &["Average weight (kg)", "Weight (kg)"];
+const BODY_FAT_COLUMNS: &[&str] = &[
+    "Average body fat percentage (%)",
+    "Average body fat percentage",
+    "Body fat percentage",
+];
+const RESTING_HEART_RATE_COLUMNS: &[&str] = &[
+    "Resting heart rate (bpm)",
+    "Average resting heart rate (bpm)",
+];
+const SLEEP_COLUMNS: &[&str] = &["Sleep duration (ms)", "Sleep duration (min)", "Sleep (min)"];
+
+/// Find a column by its configured name, or the first matching default
+fn find_column(headers: &csv::StringRecord, custom: Option<&str>, defaults: &[&str]) -> Option<usize> {
+    let matches = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.trim().eq_ignore_ascii_case(name.trim()))
+    };
+    match custom {
+        Some(name) => matches(name),
+        None => defaults.iter().find_map(|name| matches(name)),
+    }
+}
+
+/// Convert a sleep value to hours based on the unit in its column header
+fn sleep_hours_from(header: &str, value: f32) -> f32 {
+    let header = header.to_ascii_lowercase();
+    if header.contains("(ms)") {
+        value / 3_600_000.0
+    } else if header.contains("(h)") || header.contains("hours") {
+        value
+    } else {
+        value / 60.0
+    }
+}
+
+/// Parse a Google Fit Takeout `Daily activity metrics.csv`
+pub fn parse_google_fit_csv<R: Read>(reader: R, mapping: &HealthImportMapping) -> Result<Vec<DailyHealthSample>> {
+    let mut csv = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+    let headers = csv.headers().context("Failed to read CSV header")?.clone();
+    let columns = &mapping.google_fit_columns;
+
+    let date_column = find_column(&headers, columns.date.as_deref(), DATE_COLUMNS)
+        .ok_or_else(|| anyhow!("No date column found in Google Fit CSV"))?;
+    let wanted = |enabled: bool, custom: &Option<String>, defaults: &[&str]| {
+        enabled
+            .then(|| find_column(&headers, custom.as_deref(), defaults))
+            .flatten()
+    };
+    let weight_column = wanted(mapping.weight, &columns.weight_kg, WEIGHT_COLUMNS);
+    let body_fat_column = wanted(mapping.body_fat, &columns.body_fat_percentage, BODY_FAT_COLUMNS);
+    let heart_rate_column = wanted(
+        mapping.resting_heart_rate,
+        &columns.resting_heart_rate,
+        RESTING_HEART_RATE_COLUMNS,
+    );
+    let sleep_column = wanted(mapping.sleep, &columns.sleep, SLEEP_COLUMNS);
+
+    let date_format = format_description!("[year]-[month]-[day]");
+    let mut aggregator = DailyAggregator::default();
+
+    for record in csv.records() {
+        let record = record.context("Failed to read Google Fit CSV row")?;
+        let Some(date) = record
+            .get(date_column)
+            .and_then(|value| Date::parse(value.trim(), date_format).ok())
+        else {
+            continue;
+        };
+
+        let value = |column: Option<usize>| {
+            column
+                .and_then(|index| record.get(index))
+                .and_then(|value| value.trim().parse::<f32>().ok())
+        };
+        let day = aggregator.day(date, UtcOffset::UTC);
+
+        if let Some(kg) = value(weight_column) {
+            day.weight_kg.add(kg);
+        }
+        if let Some(percent) = value(body_fat_column) {
+            day.body_fat_percentage.add(body_fat_percent(percent));
+        }
+        if let Some(bpm) = value(heart_rate_column) {
+            day.resting_heart_rate_bpm.add(bpm);
+        }
+        if let (Some(sleep), Some(index)) = (value(sleep_column), sleep_column) {
+            day.sleep_hours.add(sleep_hours_from(&headers[index], sleep));
+        }
+    }
+
+    Ok(aggregator.finish())
+}
+
+/// Daily samples from individual weight readings, such as those read back
+/// from HealthKit or Health Connect
+///
+/// Readings are grouped by the calendar day in their own offset and
+/// averaged.
+pub fn daily_weights(readings: &[(OffsetDateTime, f32)]) -> Vec<DailyHealthSample> {
+    let mut aggregator = DailyAggregator::default();
+    for &(at, weight_kg) in readings {
+        aggregator.day(at.date(), at.offset()).weight_kg.add(weight_kg);
+    }
+    aggregator.finish()
+}
+
+/// Body metric changes an import would make
+#[derive(Debug, Clone, Default)]
+pub struct HealthImportPlan {
+    /// Days with no existing body metric
+    pub created: Vec<BodyMetric>,
+    /// Existing body metrics that gain values they were missing
+    pub updated: Vec<BodyMetric>,
+    /// Days where every imported value was already recorded
+    pub skipped: usize,
+}
+
+/// Fill `target` from `value` only when it has nothing yet
+fn fill(target: &mut Option<f32>, value: Option<f32>) -> bool {
+    if target.is_none() && value.is_some() {
+        *target = value;
+        true
+    } else {
+        false
+    }
+}
+
+/// Merge daily samples into body metrics, deduplicating by calendar day
+///
+/// A day that already has a body metric only gains the fields it was missing;
+/// values the user entered by hand are never overwritten, so importing the
+/// same file twice changes nothing.
+pub fn plan_health_import(
+    existing: &[BodyMetric],
+    samples: &[DailyHealthSample],
+    source: HealthImportSource,
+) -> HealthImportPlan {
+    let mut by_date: HashMap<Date, &BodyMetric> = HashMap::new();
+    for metric in existing {
+        by_date.entry(metric.date.date()).or_insert(metric);
+    }
+
+    let mut plan = HealthImportPlan::default();
+    for sample in samples {
+        match by_date.get(&sample.date) {
+            Some(existing) => {
+                let mut metric = (*existing).clone();
+                let changed = [
+                    fill(&mut metric.weight_kg, sample.weight_kg),
+                    fill(&mut metric.body_fat_percentage, sample.body_fat_percentage),
+                    fill(&mut metric.resting_heart_rate_bpm, sample.resting_heart_rate_bpm),
+                    fill(&mut metric.sleep_hours, sample.sleep_hours),
+                ]
+                .contains(&true);
+
+                if changed {
+                    metric.updated_at = OffsetDateTime::now_utc();
+                    plan.updated.push(metric);
+                } else {
+                    plan.skipped += 1;
+                }
+            }
+            None => {
+                let mut metric = BodyMetric::new(sample.date.midnight().assume_offset(sample.offset));
+                metric.weight_kg = sample.weight_kg;
+                metric.body_fat_percentage = sample.body_fat_percentage;
+                metric.resting_heart_rate_bpm = sample.resting_heart_rate_bpm;
+                metric.sleep_hours = sample.sleep_hours;
+                metric.notes = Some(format!("Imported from {}", source.label()));
+                plan.created.push(metric);
+            }
+        }
+    }
+
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, datetime};
+
+    const APPLE_EXPORT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE HealthData [
+<!ELEMENT HealthData (ExportDate,Me,(Record|Workout)*)>
+]>
+<HealthData locale="en_US">
+ <ExportDate value="2024-03-02 10:00:00 -0500"/>
+ <Record type="HKQuantityTypeIdentifierBodyMass" sourceName="Scale" unit="lb" startDate="2024-03-01 07:00:00 -0500" endDate="2024-03-01 07:00:00 -0500" value="180"/>
+ <Record type="HKQuantityTypeIdentifierBodyFatPercentage" sourceName="Scale" unit="%" startDate="2024-03-01 07:00:00 -0500" endDate="2024-03-01 07:00:00 -0500" value="0.18"/>
+ <Record type="HKQuantityTypeIdentifierRestingHeartRate" sourceName="Watch" unit="count/min" startDate="2024-03-01 06:00:00 -0500" endDate="2024-03-01 06:00:00 -0500" value="52"/>
+ <Record type="HKQuantityTypeIdentifierStepCount" sourceName="Phone" unit="count" startDate="2024-03-01 09:00:00 -0500" endDate="2024-03-01 09:10:00 -0500" value="900"/>
+ <Record type="HKCategoryTypeIdentifierSleepAnalysis" sourceName="Watch" startDate="2024-03-01 23:00:00 -0500" endDate="2024-03-02 03:00:00 -0500" value="HKCategoryValueSleepAnalysisAsleepCore"/>
+ <Record type="HKCategoryTypeIdentifierSleepAnalysis" sourceName="Phone" startDate="2024-03-02 02:00:00 -0500" endDate="2024-03-02 06:30:00 -0500" value="HKCategoryValueSleepAnalysisAsleepUnspecified"/>
+ <Record type="HKCategoryTypeIdentifierSleepAnalysis" sourceName="Phone" startDate="2024-03-01 22:30:00 -0500" endDate="2024-03-02 07:00:00 -0500" value="HKCategoryValueSleepAnalysisInBed"/>
+</HealthData>
+"#;
+
+    #[test]
+    fn daily_weights_averages_readings_per_day() {
+        let samples = daily_weights(&[
+            (datetime!(2024-03-01 07:00 -5), 82.0),
+            (datetime!(2024-03-01 21:00 -5), 83.0),
+            (datetime!(2024-03-03 07:00 -5), 81.5),
+        ]);
+
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].date, date!(2024 - 03 - 01));
+        assert_eq!(samples[0].weight_kg, Some(82.5));
+        assert_eq!(samples[0].offset, UtcOffset::from_hms(-5, 0, 0).unwrap());
+        assert_eq!(samples[1].weight_kg, Some(81.5));
+        assert_eq!(samples[1].body_fat_percentage, None);
+    }
+
+    #[test]
+    fn parses_apple_health_export() {
+        let samples = parse_apple_health(APPLE_EXPORT.as_bytes(), &HealthImportMapping::default())
+            .expect("parse export");
+
+        assert_eq!(samples.len(), 2);
+
+        let first = &samples[0];
+        assert_eq!(first.date, date!(2024 - 03 - 01));
+        assert!((first.weight_kg.unwrap() - 81.646).abs() < 0.01);
+        assert!((first.body_fat_percentage.unwrap() - 18.0).abs() < 0.001);
+        assert_eq!(first.resting_heart_rate_bpm, Some(52.0));
+        assert_eq!(first.sleep_hours, None);
+
+        // Overlapping watch and phone sleep merge into 23:00-06:30
+        let second = &samples[1];
+        assert_eq!(second.date, date!(2024 - 03 - 02));
+        assert_eq!(second.sleep_hours, Some(7.5));
+    }
+
+    #[test]
+    fn mapping_limits_imported_types() {
+        let mapping = HealthImportMapping {
+            body_fat: false,
+            sleep: false,
+            ..HealthImportMapping::default()
+        };
+
+        let samples = parse_apple_health(APPLE_EXPORT.as_bytes(), &mapping).expect("parse export");
+
+        assert_eq!(samples.len(), 1);
+        assert!(samples[0].body_fat_percentage.is_none());
+        assert!(samples[0].weight_kg.is_some());
+    }
+
+    #[test]
+    fn rejects_non_apple_xml() {
+        let result = parse_apple_health("<rss></rss>".as_bytes(), &HealthImportMapping::default());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parses_google_fit_daily_metrics() {
+        let csv = "Date,Move Minutes count,Average heart rate (bpm),Average weight (kg),Max weight (kg),Sleep duration (ms)\n\
+                   2024-03-01,45,71.2,80.5,80.9,27000000\n\
+                   2024-03-02,30,70.0,,,\n\
+                   2024-03-03,10,,,,\n";
+
+        let samples = parse_google_fit_csv(csv.as_bytes(), &HealthImportMapping::default()).expect("parse csv");
+
+        // Average heart rate is not resting heart rate, so day two has nothing to import
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].date, date!(2024 - 03 - 01));
+        assert_eq!(samples[0].weight_kg, Some(80.5));
+        assert_eq!(samples[0].sleep_hours, Some(7.5));
+    }
+
+    #[test]
+    fn google_fit_columns_can_be_remapped() {
+        let csv = "Day,Min heart rate (bpm)\n2024-03-01,48\n";
+        let mapping = HealthImportMapping {
+            google_fit_columns: GoogleFitColumns {
+                date: Some("Day".to_string()),
+                resting_heart_rate: Some("min heart rate (bpm)".to_string()),
+                ..GoogleFitColumns::default()
+            },
+            ..HealthImportMapping::default()
+        };
+
+        let samples = parse_google_fit_csv(csv.as_bytes(), &mapping).expect("parse csv");
+        assert_eq!(samples[0].resting_heart_rate_bpm, Some(48.0));
+    }
+
+    #[test]
+    fn plan_fills_gaps_without_overwriting() {
+        let mut existing = BodyMetric::new(datetime!(2024-03-01 08:00 UTC));
+        existing.weight_kg = Some(79.0);
+
+        let samples = vec![
+            DailyHealthSample {
+                date: date!(2024 - 03 - 01),
+                offset: UtcOffset::UTC,
+                weight_kg: Some(80.0),
+                body_fat_percentage: None,
+                resting_heart_rate_bpm: Some(50.0),
+                sleep_hours: None,
+            },
+            DailyHealthSample {
+                date: date!(2024 - 03 - 02),
+                offset: UtcOffset::UTC,
+                weight_kg: Some(80.2),
+                body_fat_percentage: None,
+                resting_heart_rate_bpm: None,
+                sleep_hours: Some(7.0),
+            },
+        ];
+
+        let plan = plan_health_import(&[existing.clone()], &samples, HealthImportSource::AppleHealth);
+
+        assert_eq!(plan.updated.len(), 1);
+        assert_eq!(plan.updated[0].id, existing.id);
+        assert_eq!(plan.updated[0].weight_kg, Some(79.0));
+        assert_eq!(plan.updated[0].resting_heart_rate_bpm, Some(50.0));
+        assert_eq!(plan.created.len(), 1);
+        assert_eq!(plan.created[0].sleep_hours, Some(7.0));
+
+        // Importing again after applying the plan changes nothing
+        let applied: Vec<BodyMetric> = plan.updated.into_iter().chain(plan.created).collect();
+        let again = plan_health_import(&applied, &samples, HealthImportSource::AppleHealth);
+        assert!(again.created.is_empty());
+        assert!(again.updated.is_empty());
+        assert_eq!(again.skipped, 2);
+    }
+}
@@ -0,0 +1,370 @@
+//! Peptide interaction and contraindication rules
+//!
+//! A small, static rules engine that inspects the peptides used by a set of
+//! protocols and flags combinations with overlapping mechanisms or known
+//! contraindications. The rules are intentionally conservative: they are meant
+//! to prompt the user to review their stack, not to give medical advice.
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{AlertSeverity, LiteratureEntry, PeptideProtocol};
+
+/// Pharmacological mechanism classes used to group peptides
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Mechanism {
+    /// Growth hormone releasing hormone analogs (Sermorelin, CJC-1295, Tesamorelin)
+    GhrhAnalog,
+    /// Ghrelin receptor agonists / GH secretagogues (Ipamorelin, GHRP-2, GHRP-6, MK-677)
+    GhSecretagogue,
+    /// GLP-1 receptor agonists, including dual and triple agonists
+    Glp1Agonist,
+    /// Melanocortin receptor agonists (PT-141, Melanotan II)
+    MelanocortinAgonist,
+    /// Compounds acting on the GnRH / HPG axis (Gonadorelin, Kisspeptin-10)
+    GnrhAxis,
+}
+
+impl Mechanism {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Mechanism::GhrhAnalog => "GHRH analog",
+            Mechanism::GhSecretagogue => "GH secretagogue",
+            Mechanism::Glp1Agonist => "GLP-1 receptor agonist",
+            Mechanism::MelanocortinAgonist => "melanocortin agonist",
+            Mechanism::GnrhAxis => "GnRH axis",
+        }
+    }
+}
+
+/// Known peptides and their mechanism classes, keyed by normalized name
+const PEPTIDE_CATALOG: &[(&str, &[Mechanism])] = &[
+    ("sermorelin", &[Mechanism::GhrhAnalog]),
+    ("cjc1295", &[Mechanism::GhrhAnalog]),
+    ("tesamorelin", &[Mechanism::GhrhAnalog]),
+    ("ipamorelin", &[Mechanism::GhSecretagogue]),
+    ("ghrp2", &[Mechanism::GhSecretagogue]),
+    ("ghrp6", &[Mechanism::GhSecretagogue]),
+    ("hexarelin", &[Mechanism::GhSecretagogue]),
+    ("mk677", &[Mechanism::GhSecretagogue]),
+    ("ibutamoren", &[Mechanism::GhSecretagogue]),
+    ("semaglutide", &[Mechanism::Glp1Agonist]),
+    ("liraglutide", &[Mechanism::Glp1Agonist]),
+    ("tirzepatide", &[Mechanism::Glp1Agonist]),
+    ("retatrutide", &[Mechanism::Glp1Agonist]),
+    ("pt141", &[Mechanism::MelanocortinAgonist]),
+    ("bremelanotide", &[Mechanism::MelanocortinAgonist]),
+    ("melanotanii", &[Mechanism::MelanocortinAgonist]),
+    ("melanotan2", &[Mechanism::MelanocortinAgonist]),
+    ("mtii", &[Mechanism::MelanocortinAgonist]),
+    ("gonadorelin", &[Mechanism::GnrhAxis]),
+    ("kisspeptin10", &[Mechanism::GnrhAxis]),
+];
+
+/// How a rule decides whether two protocols interact
+#[derive(Debug, Clone, Copy)]
+enum RuleMatch {
+    /// The same peptide appears in more than one protocol
+    DuplicatePeptide,
+    /// Both peptides share the given mechanism
+    SharedMechanism(Mechanism),
+    /// One peptide has the first mechanism and the other has the second
+    MechanismPair(Mechanism, Mechanism),
+}
+
+/// A static interaction rule shipped with the app
+#[derive(Debug, Clone)]
+pub struct InteractionRule {
+    pub id: &'static str,
+    pub severity: AlertSeverity,
+    pub title: &'static str,
+    pub description: &'static str,
+    matcher: RuleMatch,
+}
+
+const RULES: &[InteractionRule] = &[
+    InteractionRule {
+        id: "duplicate_peptide",
+        severity: AlertSeverity::Warning,
+        title: "Same peptide in multiple protocols",
+        description: "The same compound is scheduled in more than one protocol, which can lead to unintentional double dosing.",
+        matcher: RuleMatch::DuplicatePeptide,
+    },
+    InteractionRule {
+        id: "multiple_glp1_agonists",
+        severity: AlertSeverity::Critical,
+        title: "Multiple GLP-1 receptor agonists",
+        description: "Combining GLP-1 receptor agonists is not recommended. Stacking increases the risk of severe GI effects, dehydration, pancreatitis and hypoglycemia without established benefit.",
+        matcher: RuleMatch::SharedMechanism(Mechanism::Glp1Agonist),
+    },
+    InteractionRule {
+        id: "multiple_melanocortin_agonists",
+        severity: AlertSeverity::Warning,
+        title: "Multiple melanocortin agonists",
+        description: "Melanocortin agonists have additive effects on blood pressure, nausea and flushing. Avoid using them together.",
+        matcher: RuleMatch::SharedMechanism(Mechanism::MelanocortinAgonist),
+    },
+    InteractionRule {
+        id: "multiple_gh_secretagogues",
+        severity: AlertSeverity::Warning,
+        title: "Overlapping GH secretagogues",
+        description: "These compounds act on the same ghrelin receptor. Combining them adds little benefit while increasing water retention, appetite and cortisol/prolactin side effects.",
+        matcher: RuleMatch::SharedMechanism(Mechanism::GhSecretagogue),
+    },
+    InteractionRule {
+        id: "multiple_ghrh_analogs",
+        severity: AlertSeverity::Warning,
+        title: "Overlapping GHRH analogs",
+        description: "These compounds act on the same GHRH receptor. Running them together duplicates the mechanism and raises the risk of GH excess.",
+        matcher: RuleMatch::SharedMechanism(Mechanism::GhrhAnalog),
+    },
+    InteractionRule {
+        id: "multiple_gnrh_axis",
+        severity: AlertSeverity::Warning,
+        title: "Overlapping GnRH axis compounds",
+        description: "Both compounds stimulate the HPG axis. Combined use can cause receptor desensitization and unpredictable hormone levels.",
+        matcher: RuleMatch::SharedMechanism(Mechanism::GnrhAxis),
+    },
+    InteractionRule {
+        id: "ghrh_with_gh_secretagogue",
+        severity: AlertSeverity::Info,
+        title: "GHRH analog combined with GH secretagogue",
+        description: "This combination is synergistic on GH release. Monitor IGF-1, fasting glucose and water retention.",
+        matcher: RuleMatch::MechanismPair(Mechanism::GhrhAnalog, Mechanism::GhSecretagogue),
+    },
+    InteractionRule {
+        id: "gh_secretagogue_with_glp1",
+        severity: AlertSeverity::Info,
+        title: "GH secretagogue combined with GLP-1 agonist",
+        description: "GH secretagogues can raise fasting glucose and appetite, working against GLP-1 therapy. Monitor glucose and appetite changes.",
+        matcher: RuleMatch::MechanismPair(Mechanism::GhSecretagogue, Mechanism::Glp1Agonist),
+    },
+];
+
+/// A detected interaction between two protocols
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InteractionWarning {
+    pub rule_id: String,
+    pub severity: AlertSeverity,
+    pub title: String,
+    pub description: String,
+    pub protocol_ids: Vec<String>,
+    pub protocol_names: Vec<String>,
+    pub peptide_names: Vec<String>,
+}
+
+impl InteractionWarning {
+    /// Stable key identifying this rule/protocol combination, used to dedupe alerts
+    pub fn key(&self) -> String {
+        let mut ids = self.protocol_ids.clone();
+        ids.sort();
+        format!("{}:{}", self.rule_id, ids.join(","))
+    }
+}
+
+/// Returns all interaction rules shipped with the app
+pub fn rules() -> &'static [InteractionRule] {
+    RULES
+}
+
+/// Normalizes a peptide name for catalog lookup ("CJC-1295" -> "cjc1295")
+pub fn normalize_peptide_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Looks up the mechanism classes for a peptide, if it is in the catalog
+pub fn mechanisms_for(peptide_name: &str) -> &'static [Mechanism] {
+    let normalized = normalize_peptide_name(peptide_name);
+    PEPTIDE_CATALOG
+        .iter()
+        .find(|(name, _)| *name == normalized)
+        .map(|(_, mechanisms)| *mechanisms)
+        .unwrap_or(&[])
+}
+
+fn rule_matches(rule: &InteractionRule, a: &PeptideProtocol, b: &PeptideProtocol) -> bool {
+    let same_peptide =
+        normalize_peptide_name(&a.peptide_name) == normalize_peptide_name(&b.peptide_name);
+
+    match rule.matcher {
+        RuleMatch::DuplicatePeptide => same_peptide,
+        RuleMatch::SharedMechanism(mechanism) => {
+            // Duplicates are reported by their own rule
+            !same_peptide
+                && mechanisms_for(&a.peptide_name).contains(&mechanism)
+                && mechanisms_for(&b.peptide_name).contains(&mechanism)
+        }
+        RuleMatch::MechanismPair(first, second) => {
+            let a_mech = mechanisms_for(&a.peptide_name);
+            let b_mech = mechanisms_for(&b.peptide_name);
+            (a_mech.contains(&first) && b_mech.contains(&second))
+                || (a_mech.contains(&second) && b_mech.contains(&first))
+        }
+    }
+}
+
+/// Checks every pair of protocols against the static rules
+///
+/// Returns one warning per matching rule and protocol pair, ordered by
+/// severity (critical first).
+pub fn find_interactions(protocols: &[PeptideProtocol]) -> Vec<InteractionWarning> {
+    let mut warnings = Vec::new();
+
+    for (i, a) in protocols.iter().enumerate() {
+        for b in &protocols[i + 1..] {
+            if a.id == b.id {
+                continue;
+            }
+
+            for rule in RULES {
+                if rule_matches(rule, a, b) {
+                    warnings.push(InteractionWarning {
+                        rule_id: rule.id.to_string(),
+                        severity: rule.severity.clone(),
+                        title: rule.title.to_string(),
+                        description: rule.description.to_string(),
+                        protocol_ids: vec![a.id.clone(), b.id.clone()],
+                        protocol_names: vec![a.name.clone(), b.name.clone()],
+                        peptide_names: vec![a.peptide_name.clone(), b.peptide_name.clone()],
+                    });
+                }
+            }
+        }
+    }
+
+    warnings.sort_by_key(|w| match w.severity {
+        AlertSeverity::Critical => 0,
+        AlertSeverity::Warning => 1,
+        AlertSeverity::Info => 2,
+    });
+
+    warnings
+}
+
+/// Finds cached literature entries that mention the peptides in a warning
+///
+/// Entries mentioning every peptide rank above entries mentioning only one.
+pub fn related_literature<'a>(
+    warning: &InteractionWarning,
+    entries: &'a [LiteratureEntry],
+    limit: usize,
+) -> Vec<&'a LiteratureEntry> {
+    let mut names: Vec<String> = warning
+        .peptide_names
+        .iter()
+        .map(|name| name.to_lowercase())
+        .collect();
+    names.dedup();
+
+    let mut scored: Vec<(usize, &LiteratureEntry)> = entries
+        .iter()
+        .filter_map(|entry| {
+            let haystack = format!(
+                "{} {}",
+                entry.title.to_lowercase(),
+                entry.summary.as_deref().unwrap_or("").to_lowercase()
+            );
+            let hits = names.iter().filter(|name| haystack.contains(name.as_str())).count();
+            (hits > 0).then_some((hits, entry))
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    scored.into_iter().take(limit).map(|(_, entry)| entry).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_peptide_names() {
+        assert_eq!(normalize_peptide_name("CJC-1295"), "cjc1295");
+        assert_eq!(normalize_peptide_name("Melanotan II"), "melanotanii");
+        assert_eq!(mechanisms_for("MK-677"), &[Mechanism::GhSecretagogue]);
+        assert!(mechanisms_for("BPC-157").is_empty());
+    }
+
+    #[test]
+    fn flags_multiple_glp1_agonists_as_critical() {
+        let protocols = vec![
+            PeptideProtocol::new("Cut", "Semaglutide"),
+            PeptideProtocol::new("Cut 2", "Tirzepatide"),
+        ];
+
+        let warnings = find_interactions(&protocols);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].rule_id, "multiple_glp1_agonists");
+        assert_eq!(warnings[0].severity, AlertSeverity::Critical);
+    }
+
+    #[test]
+    fn reports_duplicates_without_shared_mechanism_noise() {
+        let protocols = vec![
+            PeptideProtocol::new("AM", "Ipamorelin"),
+            PeptideProtocol::new("PM", "ipamorelin"),
+        ];
+
+        let warnings = find_interactions(&protocols);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].rule_id, "duplicate_peptide");
+    }
+
+    #[test]
+    fn orders_warnings_by_severity() {
+        let protocols = vec![
+            PeptideProtocol::new("GH", "CJC-1295"),
+            PeptideProtocol::new("GH 2", "Ipamorelin"),
+            PeptideProtocol::new("Cut", "Semaglutide"),
+            PeptideProtocol::new("Cut 2", "Retatrutide"),
+        ];
+
+        let warnings = find_interactions(&protocols);
+        assert_eq!(warnings[0].severity, AlertSeverity::Critical);
+        assert!(warnings.iter().any(|w| w.rule_id == "ghrh_with_gh_secretagogue"));
+        assert!(warnings.iter().any(|w| w.rule_id == "gh_secretagogue_with_glp1"));
+    }
+
+    #[test]
+    fn unrelated_peptides_have_no_interactions() {
+        let protocols = vec![
+            PeptideProtocol::new("Healing", "BPC-157"),
+            PeptideProtocol::new("Recovery", "TB-500"),
+        ];
+
+        assert!(find_interactions(&protocols).is_empty());
+    }
+
+    #[test]
+    fn warning_key_is_order_independent() {
+        let a = PeptideProtocol::new("A", "PT-141");
+        let b = PeptideProtocol::new("B", "Melanotan II");
+
+        let forward = find_interactions(&[a.clone(), b.clone()]);
+        let reverse = find_interactions(&[b, a]);
+        assert_eq!(forward[0].key(), reverse[0].key());
+    }
+
+    #[test]
+    fn ranks_literature_mentioning_both_peptides_first() {
+        let protocols = vec![
+            PeptideProtocol::new("A", "Semaglutide"),
+            PeptideProtocol::new("B", "Tirzepatide"),
+        ];
+        let warning = &find_interactions(&protocols)[0];
+
+        let entries = vec![
+            LiteratureEntry::new("pubmed", "Semaglutide outcomes"),
+            LiteratureEntry::new("pubmed", "Tirzepatide versus semaglutide"),
+            LiteratureEntry::new("pubmed", "BPC-157 in tendon healing"),
+        ];
+
+        let related = related_literature(warning, &entries, 5);
+        assert_eq!(related.len(), 2);
+        assert_eq!(related[0].title, "Tirzepatide versus semaglutide");
+    }
+}
@@ -0,0 +1,147 @@
+//! Wiki-style links in journal entries
+//!
+//! Journal markdown can link to other records with `[[kind:target]]` or
+//! `[[kind:target|label]]`, where kind is `protocol`, `dose` or `paper`.
+//! The target is normally a record ID, but a protocol name or a paper's DOI
+//! also works so links can be typed by hand. Links live only in the
+//! encrypted entry body; backlinks are found by parsing entries, never from
+//! a plaintext index.
+
+use serde::{Deserialize, Serialize};
+
+/// Kind of record a journal link points to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalLinkKind {
+    Protocol,
+    Dose,
+    Paper,
+}
+
+impl JournalLinkKind {
+    fn parse(kind: &str) -> Option<Self> {
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "protocol" => Some(Self::Protocol),
+            "dose" => Some(Self::Dose),
+            "paper" => Some(Self::Paper),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalLink {
+    pub kind: JournalLinkKind,
+    /// Record ID, protocol name or DOI, as written
+    pub target: String,
+    pub label: Option<String>,
+}
+
+impl JournalLink {
+    /// Whether this link points at a record known by any of `keys` (its ID,
+    /// name or DOI); compared case-insensitively
+    pub fn points_to(&self, kind: JournalLinkKind, keys: &[&str]) -> bool {
+        self.kind == kind && keys.iter().any(|key| key.trim().eq_ignore_ascii_case(&self.target))
+    }
+}
+
+/// A run of plain text or a well-formed link
+enum Segment<'a> {
+    Text(&'a str),
+    Link(JournalLink),
+}
+
+/// Split `markdown` into text and `[[...]]` links; malformed links stay text
+fn segments(markdown: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = markdown;
+    while let Some(start) = rest.find("[[") {
+        let Some(len) = rest[start + 2..].find("]]") else {
+            break;
+        };
+        let end = start + len + 4;
+        segments.push(Segment::Text(&rest[..start]));
+        match parse_link(&rest[start + 2..end - 2]) {
+            Some(link) => segments.push(Segment::Link(link)),
+            None => segments.push(Segment::Text(&rest[start..end])),
+        }
+        rest = &rest[end..];
+    }
+    segments.push(Segment::Text(rest));
+    segments
+}
+
+fn parse_link(inner: &str) -> Option<JournalLink> {
+    let (kind, target) = inner.split_once(':')?;
+    let kind = JournalLinkKind::parse(kind)?;
+    let (target, label) = match target.split_once('|') {
+        Some((target, label)) => (target, Some(label.trim().to_string()).filter(|l| !l.is_empty())),
+        None => (target, None),
+    };
+    let target = target.trim();
+    if target.is_empty() {
+        return None;
+    }
+    Some(JournalLink {
+        kind,
+        target: target.to_string(),
+        label,
+    })
+}
+
+/// Every distinct link in `markdown`, in order of first appearance
+pub fn parse_links(markdown: &str) -> Vec<JournalLink> {
+    let mut links: Vec<JournalLink> = Vec::new();
+    for segment in segments(markdown) {
+        if let Segment::Link(link) = segment {
+            let seen = links
+                .iter()
+                .any(|seen| seen.kind == link.kind && seen.target.eq_ignore_ascii_case(&link.target));
+            if !seen {
+                links.push(link);
+            }
+        }
+    }
+    links
+}
+
+/// `markdown` with each link replaced by its label (or target), for search
+/// indexing and previews
+pub fn strip_links(markdown: &str) -> String {
+    let mut text = String::with_capacity(markdown.len());
+    for segment in segments(markdown) {
+        match segment {
+            Segment::Text(chunk) => text.push_str(chunk),
+            Segment::Link(link) => text.push_str(link.label.as_deref().unwrap_or(&link.target)),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_links_with_labels_and_skips_malformed_ones() {
+        let body = "Started [[protocol:BPC-157 healing]] today, see [[paper:10.1000/XYZ|the rat study]].\n\
+                    Pinned [[dose:d-1]] and [[Protocol:bpc-157 HEALING]] again. [[note:x]] [[dose:]] [[unclosed";
+
+        let links = parse_links(body);
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[0].kind, JournalLinkKind::Protocol);
+        assert_eq!(links[0].target, "BPC-157 healing");
+        assert_eq!(links[1].label.as_deref(), Some("the rat study"));
+        assert!(links[1].points_to(JournalLinkKind::Paper, &["paper-id", "10.1000/xyz"]));
+        assert!(!links[2].points_to(JournalLinkKind::Protocol, &["d-1"]));
+    }
+
+    #[test]
+    fn strip_links_keeps_labels_and_plain_text() {
+        assert_eq!(
+            strip_links("Felt great on [[protocol:p-1|Evening GH]], [[note:x]] stays"),
+            "Felt great on Evening GH, [[note:x]] stays"
+        );
+    }
+}
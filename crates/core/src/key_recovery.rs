@@ -0,0 +1,234 @@
+//! Recovery codes for the database key
+//!
+//! If the Keychain item or key file is lost, or the passphrase forgotten,
+//! nothing else can open the database. A recovery code is a random code
+//! shown once when it's created. The database key is sealed with a key
+//! derived from it with Argon2id and kept in the database's `key_recovery`
+//! table, which isn't encrypted with the database key so it can be read
+//! while locked.
+//!
+//! Rotating the database key seals the new key with a new code in place of
+//! the old one, which would no longer open the database; see
+//! [`rotate_storage_key`](crate::rotate_storage_key).
+
+use anyhow::{anyhow, Result};
+use rand::{rngs::OsRng, Rng, RngCore};
+use serde::Serialize;
+use time::OffsetDateTime;
+use tracing::info;
+
+use crate::db::StorageManager;
+use crate::encryption::{EnvelopeEncryption, KeyMaterial, KeyProvider};
+use crate::passphrase::{derive_key, KdfParams, PassphraseKeyProvider};
+
+/// Crockford base32, which leaves out I, L, O and U so codes read back
+/// unambiguously
+const CODE_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+/// 24 characters of 5 bits each: 120 bits
+const CODE_CHARS: usize = 24;
+const CODE_GROUP: usize = 4;
+const SALT_SIZE: usize = 16;
+
+/// The database key sealed with a key derived from a recovery code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRecovery {
+    pub salt: Vec<u8>,
+    pub kdf: KdfParams,
+    pub sealed_key: Vec<u8>,
+    pub created_at: OffsetDateTime,
+}
+
+impl KeyRecovery {
+    /// Seal `key` with a new random code, returned for showing to the user
+    pub fn create(key: &KeyMaterial, kdf: KdfParams) -> Result<(Self, String)> {
+        let code = generate_code();
+        let mut salt = vec![0u8; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+
+        let wrapping_key = derive_key(&normalize_code(&code)?, &salt, kdf)?;
+        let sealed_key = EnvelopeEncryption::with_key(&wrapping_key).seal(&key.to_key_bytes()?)?;
+
+        let recovery = Self {
+            salt,
+            kdf,
+            sealed_key,
+            created_at: OffsetDateTime::now_utc(),
+        };
+        Ok((recovery, code))
+    }
+
+    /// The database key, failing if `code` is wrong
+    ///
+    /// Case, spaces and dashes don't matter, and the letters O, I and L are
+    /// read as the digits they look like.
+    pub fn unlock(&self, code: &str) -> Result<KeyMaterial> {
+        let wrapping_key = derive_key(&normalize_code(code)?, &self.salt, self.kdf)?;
+        let key = EnvelopeEncryption::with_key(&wrapping_key)
+            .open(&self.sealed_key)
+            .map_err(|_| anyhow!("Incorrect recovery code"))?;
+        KeyMaterial::new(key)
+    }
+}
+
+/// Whether a recovery code is set, for the security settings screen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCodeStatus {
+    pub configured: bool,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub created_at: Option<OffsetDateTime>,
+}
+
+fn generate_code() -> String {
+    let chars: Vec<char> = (0..CODE_CHARS)
+        .map(|_| CODE_ALPHABET[OsRng.gen_range(0..CODE_ALPHABET.len())] as char)
+        .collect();
+    chars
+        .chunks(CODE_GROUP)
+        .map(|group| group.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn normalize_code(code: &str) -> Result<String> {
+    let normalized: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        })
+        .collect();
+
+    if normalized.len() != CODE_CHARS
+        || !normalized.bytes().all(|b| CODE_ALPHABET.contains(&b))
+    {
+        return Err(anyhow!(
+            "A recovery code is {} letters and digits",
+            CODE_CHARS
+        ));
+    }
+    Ok(normalized)
+}
+
+/// Create a recovery code for the key `provider` is unlocked with,
+/// replacing any earlier code
+///
+/// `provider` must be the key provider `storage` was created with. The code
+/// is returned for showing to the user and isn't kept anywhere.
+pub fn create_recovery_code(
+    storage: &StorageManager,
+    provider: &PassphraseKeyProvider,
+    kdf: KdfParams,
+) -> Result<String> {
+    let key = provider.key_material()?;
+    let (recovery, code) = KeyRecovery::create(&key, kdf)?;
+    storage.save_key_recovery(&recovery)?;
+    info!("Recovery code created");
+    Ok(code)
+}
+
+/// Unlock `storage` with its recovery code
+///
+/// `provider` must be the key provider `storage` was created with. Returns
+/// the recovered key so it can be saved where it will be found next launch.
+pub fn recover_storage(
+    storage: &StorageManager,
+    provider: &PassphraseKeyProvider,
+    code: &str,
+) -> Result<KeyMaterial> {
+    let recovery = storage
+        .key_recovery()?
+        .ok_or_else(|| anyhow!("No recovery code has been set up for this database"))?;
+    let key = recovery.unlock(code)?;
+    provider.unlock_with_key(key.clone());
+
+    // Rotating the key replaces the sealed copy, so this only fails if the
+    // table was changed by hand
+    if !storage.verify_key()? {
+        provider.forget_key();
+        return Err(anyhow!("The recovery code no longer matches this database"));
+    }
+
+    storage.initialize()?;
+    info!("Database unlocked with recovery code");
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::StorageConfig;
+    use crate::key_rotation::{generate_key, rotate_storage_key};
+    use crate::models::PeptideProtocol;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    const TEST_KDF: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[test]
+    fn codes_are_grouped_and_normalized() {
+        let code = generate_code();
+        assert_eq!(code.len(), CODE_CHARS + CODE_CHARS / CODE_GROUP - 1);
+        assert_eq!(code.split('-').count(), CODE_CHARS / CODE_GROUP);
+
+        let spaced = code.to_lowercase().replace('-', " ");
+        assert_eq!(normalize_code(&spaced).unwrap(), code.replace('-', ""));
+        assert_eq!(
+            normalize_code("oooo-iiii-llll-0000-1111-2222").unwrap(),
+            "000011111111000011112222"
+        );
+        assert!(normalize_code("too short").is_err());
+        assert!(normalize_code("UUUU-UUUU-UUUU-UUUU-UUUU-UUUU").is_err());
+    }
+
+    #[test]
+    fn recovery_code_opens_a_database_whose_key_was_lost() {
+        let dir = tempdir().unwrap();
+        let provider = Arc::new(PassphraseKeyProvider::new(dir.path()));
+        provider.unlock_with_key(generate_key().unwrap());
+        let storage = StorageManager::new(StorageConfig {
+            data_dir: Some(dir.path().to_path_buf()),
+            db_file_name: Some("test.sqlite".into()),
+            key_provider: provider.clone(),
+        })
+        .unwrap();
+        storage.initialize().unwrap();
+        storage
+            .upsert_protocol(&PeptideProtocol::new("Evening", "Ipamorelin"))
+            .unwrap();
+
+        assert!(storage.key_recovery().unwrap().is_none());
+        let code = create_recovery_code(&storage, &provider, TEST_KDF).unwrap();
+
+        // The stored key is gone and a fresh one doesn't open the database
+        provider.unlock_with_key(generate_key().unwrap());
+        assert!(!storage.verify_key().unwrap());
+        provider.forget_key();
+
+        let wrong = generate_code();
+        assert!(recover_storage(&storage, &provider, &wrong).is_err());
+        assert!(provider.is_locked());
+
+        let key = recover_storage(&storage, &provider, &code.to_lowercase()).unwrap();
+        assert_eq!(
+            key.to_key_bytes().unwrap(),
+            provider.key_material().unwrap().to_key_bytes().unwrap()
+        );
+        assert_eq!(storage.list_protocols().unwrap()[0].name, "Evening");
+
+        // Rotating the key issues a new code, and the old one stops working
+        let rotation = rotate_storage_key(&storage, &provider, generate_key().unwrap(), |_| {}).unwrap();
+        let new_code = rotation.recovery_code.expect("new recovery code");
+        let rotated = provider.key_material().unwrap().to_key_bytes().unwrap();
+        provider.forget_key();
+        assert!(recover_storage(&storage, &provider, &code).is_err());
+        let key = recover_storage(&storage, &provider, &new_code).unwrap();
+        assert_eq!(key.to_key_bytes().unwrap(), rotated);
+    }
+}
@@ -0,0 +1,124 @@
+//! Encryption key rotation
+//!
+//! Rotating the key re-encrypts every stored value under a freshly generated
+//! key in a single transaction, then switches the running app over to it.
+//! The caller decides where the new key lives (key file, Keychain or a
+//! passphrase) and must persist it so that a crash after the transaction
+//! commits can't leave the database encrypted under a key that was lost.
+
+use anyhow::Result;
+use rand::{rngs::OsRng, RngCore};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::db::StorageManager;
+use crate::encryption::{EnvelopeEncryption, KeyMaterial, KeyProvider};
+use crate::key_recovery::KeyRecovery;
+use crate::passphrase::PassphraseKeyProvider;
+
+/// Progress of a re-encryption, reported after every value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyRotationProgress {
+    /// Table currently being re-encrypted
+    pub table: &'static str,
+    /// Values re-encrypted so far, across all tables
+    pub processed: usize,
+    pub total: usize,
+}
+
+/// What [`rotate_storage_key`] did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRotation {
+    pub reencrypted: usize,
+    /// The recovery code for the new key, when the old key had one; the old
+    /// code no longer works, so this one must be shown to the user
+    pub recovery_code: Option<String>,
+}
+
+/// Generates a random 32-byte database key
+pub fn generate_key() -> Result<KeyMaterial> {
+    let mut bytes = vec![0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    KeyMaterial::new(bytes)
+}
+
+/// Re-encrypt everything under `new_key` and switch `provider` to it
+///
+/// `provider` must be the key provider `storage` was created with, and be
+/// unlocked. An error means nothing was re-encrypted and the old key is
+/// still in use. If a recovery code was set up, a new one is sealed with the
+/// new key in the same transaction.
+pub fn rotate_storage_key(
+    storage: &StorageManager,
+    provider: &PassphraseKeyProvider,
+    new_key: KeyMaterial,
+    progress: impl FnMut(KeyRotationProgress),
+) -> Result<KeyRotation> {
+    provider.key_material()?;
+    let recovery = storage
+        .key_recovery()?
+        .map(|old| KeyRecovery::create(&new_key, old.kdf))
+        .transpose()?;
+    let reencrypted = storage.rotate_key(
+        &EnvelopeEncryption::with_key(&new_key),
+        recovery.as_ref().map(|(recovery, _)| recovery),
+        progress,
+    )?;
+    provider.unlock_with_key(new_key);
+
+    // Search tokens are keyed, so they must be rebuilt under the new key.
+    // The data is already re-encrypted, so don't report this as a failure.
+    if let Err(e) = storage.rebuild_search_index() {
+        warn!("Failed to rebuild search index after key rotation: {:#}", e);
+    }
+
+    info!(
+        "Encryption key rotated; {} values re-encrypted",
+        reencrypted
+    );
+    Ok(KeyRotation {
+        reencrypted,
+        recovery_code: recovery.map(|(_, code)| code),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::StorageConfig;
+    use crate::models::PeptideProtocol;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn rotated_storage_stays_readable_and_searchable() {
+        let dir = tempdir().unwrap();
+        let provider = Arc::new(PassphraseKeyProvider::new(dir.path()));
+        provider.unlock_with_key(generate_key().unwrap());
+        let storage = StorageManager::new(StorageConfig {
+            data_dir: Some(dir.path().to_path_buf()),
+            db_file_name: Some("test.sqlite".into()),
+            key_provider: provider.clone(),
+        })
+        .unwrap();
+        storage.initialize().unwrap();
+        storage
+            .upsert_protocol(&PeptideProtocol::new("Recovery", "TB-500"))
+            .unwrap();
+
+        let new_key = generate_key().unwrap();
+        let expected = new_key.to_key_bytes().unwrap();
+        let rotation = rotate_storage_key(&storage, &provider, new_key, |_| {}).unwrap();
+        assert_eq!(rotation.reencrypted, 1);
+        assert_eq!(rotation.recovery_code, None);
+
+        assert_eq!(
+            provider.key_material().unwrap().to_key_bytes().unwrap(),
+            expected
+        );
+        assert!(storage.verify_key().unwrap());
+        assert_eq!(storage.list_protocols().unwrap()[0].name, "Recovery");
+        assert_eq!(storage.search("tb-500", None, 10).unwrap().len(), 1);
+    }
+}
@@ -2,12 +2,15 @@
 //!
 //! This module provides a `KeychainKeyProvider` that stores and retrieves
 //! encryption keys using the macOS Keychain Services API, providing OS-level
-//! security and access control.
+//! security and access control, and a `BiometricKeyProvider` that gates any
+//! stored key behind Touch ID or Windows Hello.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context, Result};
 use rand::{rngs::OsRng, RngCore};
 
-#[cfg(target_os = "macos")]
 use crate::encryption::{KeyMaterial, KeyProvider};
 
 #[cfg(target_os = "macos")]
@@ -123,6 +126,21 @@ impl KeychainKeyProvider {
             .map_err(|e| anyhow!("Failed to retrieve encryption key from Keychain: {}", e))
     }
 
+    /// Replaces the stored key, e.g. after rotating the database key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is invalid or Keychain storage fails.
+    #[cfg(target_os = "macos")]
+    pub fn replace_key(&self, key: &KeyMaterial) -> Result<()> {
+        self.store_in_keychain(&key.to_key_bytes()?)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn replace_key(&self, _key: &KeyMaterial) -> Result<()> {
+        Err(anyhow!("KeychainKeyProvider is only available on macOS"))
+    }
+
     /// Deletes the key from the macOS Keychain.
     ///
     /// This is primarily useful for testing or key rotation scenarios.
@@ -150,6 +168,228 @@ impl KeyProvider for KeychainKeyProvider {
     }
 }
 
+/// Verifies the user before a key is released; receives the prompt reason.
+type Authenticator = dyn Fn(&str) -> Result<()> + Send + Sync;
+
+/// Key provider that requires Touch ID / Windows Hello before releasing
+/// the key held by another provider.
+///
+/// The first call to [`KeyProvider::key_material`] shows the system prompt.
+/// After a successful prompt the key is released without asking again until
+/// the grace period runs out, or for the rest of the session if there is no
+/// grace period. Calls block while the prompt is showing.
+///
+/// # Platform Support
+///
+/// Touch ID on macOS (without the device password fallback) and Windows
+/// Hello on Windows. Elsewhere every call fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use peptrack_core::{BiometricKeyProvider, KeyProvider, StaticKeyProvider};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let stored = Arc::new(StaticKeyProvider::new(vec![42u8; 32])?);
+/// let provider = BiometricKeyProvider::new(stored, Some(Duration::from_secs(300)));
+/// let key_material = provider.key_material()?; // prompts for Touch ID / Windows Hello
+/// # Ok(())
+/// # }
+/// ```
+pub struct BiometricKeyProvider {
+    inner: Arc<dyn KeyProvider>,
+    grace_period: Option<Duration>,
+    reason: String,
+    authenticated_at: Mutex<Option<Instant>>,
+    authenticator: Box<Authenticator>,
+}
+
+impl BiometricKeyProvider {
+    /// Wraps `inner`, prompting again once `grace_period` has passed since
+    /// the last successful prompt (`None` prompts once per session).
+    pub fn new(inner: Arc<dyn KeyProvider>, grace_period: Option<Duration>) -> Self {
+        Self {
+            inner,
+            grace_period,
+            reason: "unlock your PepTrack data".to_string(),
+            authenticated_at: Mutex::new(None),
+            authenticator: Box::new(platform::authenticate),
+        }
+    }
+
+    /// Sets the reason shown in the system prompt.
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = reason.into();
+        self
+    }
+
+    /// Replaces the system prompt, e.g. for tests or a custom verifier.
+    pub fn with_authenticator(
+        mut self,
+        authenticator: impl Fn(&str) -> Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.authenticator = Box::new(authenticator);
+        self
+    }
+
+    /// Whether this device can show a biometric prompt.
+    pub fn is_available() -> bool {
+        platform::is_available()
+    }
+
+    /// Whether the key would currently be released without a prompt.
+    pub fn is_authenticated(&self) -> bool {
+        self.authenticated_at
+            .lock()
+            .map(|at| self.within_grace_period(*at))
+            .unwrap_or(false)
+    }
+
+    /// Ends the session so the next key request prompts again.
+    pub fn end_session(&self) {
+        if let Ok(mut at) = self.authenticated_at.lock() {
+            *at = None;
+        }
+    }
+
+    fn within_grace_period(&self, authenticated_at: Option<Instant>) -> bool {
+        match (authenticated_at, self.grace_period) {
+            (Some(at), Some(grace_period)) => at.elapsed() < grace_period,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+impl KeyProvider for BiometricKeyProvider {
+    fn key_material(&self) -> Result<KeyMaterial> {
+        // Held across the prompt so concurrent callers share one prompt
+        let mut authenticated_at = self
+            .authenticated_at
+            .lock()
+            .map_err(|_| anyhow!("Biometric session lock poisoned"))?;
+
+        if !self.within_grace_period(*authenticated_at) {
+            (self.authenticator)(&self.reason)?;
+            *authenticated_at = Some(Instant::now());
+        }
+
+        self.inner.key_material()
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    //! Touch ID through the LocalAuthentication framework.
+
+    use std::ptr;
+    use std::sync::mpsc;
+
+    use anyhow::{anyhow, Result};
+    use block2::RcBlock;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyClass, AnyObject, Bool};
+    use objc2::msg_send;
+    use objc2_foundation::NSString;
+
+    #[link(name = "LocalAuthentication", kind = "framework")]
+    extern "C" {}
+
+    /// `LAPolicyDeviceOwnerAuthenticationWithBiometrics`
+    const POLICY_BIOMETRICS: isize = 1;
+
+    fn context() -> Result<Retained<AnyObject>> {
+        let class = AnyClass::get(c"LAContext").ok_or_else(|| anyhow!("LocalAuthentication is unavailable"))?;
+        Ok(unsafe { msg_send![class, new] })
+    }
+
+    pub fn is_available() -> bool {
+        let Ok(context) = context() else {
+            return false;
+        };
+        let available: Bool = unsafe {
+            msg_send![&context, canEvaluatePolicy: POLICY_BIOMETRICS, error: ptr::null_mut::<*mut AnyObject>()]
+        };
+        available.as_bool()
+    }
+
+    pub fn authenticate(reason: &str) -> Result<()> {
+        if !is_available() {
+            return Err(anyhow!("Touch ID is not available or not set up"));
+        }
+
+        let context = context()?;
+        let (sender, receiver) = mpsc::channel();
+        let reply = RcBlock::new(move |success: Bool, _error: *mut AnyObject| {
+            let _ = sender.send(success.as_bool());
+        });
+        let reason = NSString::from_str(reason);
+
+        unsafe {
+            let _: () = msg_send![
+                &context,
+                evaluatePolicy: POLICY_BIOMETRICS,
+                localizedReason: &*reason,
+                reply: &*reply
+            ];
+        }
+
+        match receiver.recv() {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(anyhow!("Touch ID verification failed or was cancelled")),
+            Err(_) => Err(anyhow!("Touch ID prompt ended without a result")),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    //! Windows Hello through `UserConsentVerifier`.
+
+    use anyhow::{anyhow, Result};
+    use windows::core::HSTRING;
+    use windows::Security::Credentials::UI::{
+        UserConsentVerificationResult, UserConsentVerifier, UserConsentVerifierAvailability,
+    };
+
+    pub fn is_available() -> bool {
+        UserConsentVerifier::CheckAvailabilityAsync()
+            .and_then(|operation| operation.get())
+            .is_ok_and(|availability| availability == UserConsentVerifierAvailability::Available)
+    }
+
+    pub fn authenticate(reason: &str) -> Result<()> {
+        if !is_available() {
+            return Err(anyhow!("Windows Hello is not available or not set up"));
+        }
+
+        let result = UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))
+            .and_then(|operation| operation.get())
+            .map_err(|e| anyhow!("Windows Hello prompt failed: {}", e))?;
+
+        if result == UserConsentVerificationResult::Verified {
+            Ok(())
+        } else {
+            Err(anyhow!("Windows Hello verification failed or was cancelled"))
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use anyhow::{anyhow, Result};
+
+    pub fn is_available() -> bool {
+        false
+    }
+
+    pub fn authenticate(_reason: &str) -> Result<()> {
+        Err(anyhow!("Biometric unlock is only available on macOS and Windows"))
+    }
+}
+
 /// Migrates an encryption key from a file to the macOS Keychain.
 ///
 /// This function reads a hex-encoded key from the specified file path,
@@ -351,3 +591,59 @@ mod tests {
         cleanup_test_key();
     }
 }
+
+#[cfg(test)]
+mod biometric_tests {
+    use super::*;
+    use crate::encryption::StaticKeyProvider;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn provider(grace_period: Option<Duration>, prompts: Arc<AtomicUsize>, approve: bool) -> BiometricKeyProvider {
+        let inner = Arc::new(StaticKeyProvider::new(vec![5u8; 32]).unwrap());
+        BiometricKeyProvider::new(inner, grace_period).with_authenticator(move |_reason| {
+            prompts.fetch_add(1, Ordering::SeqCst);
+            if approve {
+                Ok(())
+            } else {
+                Err(anyhow!("cancelled"))
+            }
+        })
+    }
+
+    #[test]
+    fn prompts_once_per_session() {
+        let prompts = Arc::new(AtomicUsize::new(0));
+        let provider = provider(None, prompts.clone(), true);
+        assert!(!provider.is_authenticated());
+
+        provider.key_material().unwrap();
+        provider.key_material().unwrap();
+        assert_eq!(prompts.load(Ordering::SeqCst), 1);
+        assert!(provider.is_authenticated());
+
+        provider.end_session();
+        provider.key_material().unwrap();
+        assert_eq!(prompts.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn prompts_again_after_grace_period() {
+        let prompts = Arc::new(AtomicUsize::new(0));
+        let provider = provider(Some(Duration::ZERO), prompts.clone(), true);
+
+        provider.key_material().unwrap();
+        provider.key_material().unwrap();
+        assert_eq!(prompts.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn withholds_key_when_prompt_fails() {
+        let prompts = Arc::new(AtomicUsize::new(0));
+        let provider = provider(None, prompts.clone(), false);
+
+        assert!(provider.key_material().is_err());
+        assert!(provider.key_material().is_err());
+        assert!(!provider.is_authenticated());
+        assert_eq!(prompts.load(Ordering::SeqCst), 2);
+    }
+}
@@ -41,14 +41,125 @@
 //! # }
 //! ```
 
+pub mod ai_usage;
+pub mod alerts;
+pub mod analytics_export;
+pub mod async_storage;
+pub mod attachments;
+pub mod audit;
+pub mod backup;
 pub mod backup_encryption;
+pub mod credentials;
+pub mod currency;
+pub mod data_import;
 pub mod db;
+pub mod dose_presets;
+pub mod dose_stats;
 pub mod encryption;
+pub mod events;
+pub mod expiry_calendar;
+pub mod follows;
+pub mod goals;
+pub mod health_import;
+pub mod interactions;
+pub mod journal;
+pub mod key_recovery;
+pub mod key_rotation;
 pub mod keychain;
+mod mac;
+pub mod maintenance;
+pub mod migration;
 pub mod models;
+pub mod network;
+pub mod notifications;
+pub mod orphans;
+pub mod passphrase;
+mod pool;
+pub mod price_trend;
+pub mod protocol_package;
+pub mod qr;
+pub mod recovery;
+pub mod redaction;
+pub mod response_cache;
+pub mod scraping;
+pub mod search;
+pub mod settings;
+pub mod setup;
+pub mod stats_cache;
+pub mod summary_diff;
+pub mod summary_export;
+pub mod supplier_ranking;
+pub mod sync;
+pub mod tamper;
+pub mod trash;
+pub mod undo;
+pub mod units;
+pub mod vial_label;
 
+pub use ai_usage::{AiUsageStats, DailyAiUsage, ProviderUsage};
+pub use alerts::{group_alerts, AlertDelivery, AlertMute, AlertPreferences, AlertRouting, AlertThread};
+pub use analytics_export::{AnalyticsExport, ExportedTable};
+pub use async_storage::AsyncStorage;
+pub use attachments::{
+    detect_mime_type, generate_thumbnail, sanitize_file_name, validate_attachment_size,
+    validate_image_size, Thumbnail, MAX_ATTACHMENT_BYTES, MAX_IMAGE_BYTES,
+};
+pub use audit::{AuditEntityType, AuditEntry, AuditLogFilter, AuditOperation, AuditRetention};
 pub use backup_encryption::{decrypt_backup, encrypt_backup, is_encrypted_backup};
-pub use db::{StorageConfig, StorageManager};
+pub use credentials::{delete_credential, load_credential, store_credential};
+pub use currency::{normalize_currency_code, CurrencyConverter, BASE_CURRENCY};
+pub use data_import::{
+    plan_import, read_rows, source_columns, ExistingRecords, ImportFormat, ImportPlan, ImportProfile, ImportProfiles,
+    ImportRowError, ImportTarget, SourceRow,
+};
+pub use db::{ListOptions, StorageConfig, StorageManager, StorageTransaction};
+pub use dose_presets::{DosePreset, DosePresets};
+pub use dose_stats::{site_code, DailyDoseTotal, DoseStatsFilter, ProtocolDoseUsage, SiteDoseUsage};
 pub use encryption::{EnvelopeEncryption, KeyMaterial, KeyProvider, StaticKeyProvider};
-pub use keychain::{migrate_file_key_to_keychain, KeychainKeyProvider};
-pub use models::{BodyMetric, DoseLog, InventoryItem, LiteratureEntry, PeptideProtocol, SideEffect, Supplier, VialStatus};
+pub use expiry_calendar::{expiry_calendar, ExpiryDay, ExpiryEvent, ExpiryKind};
+pub use follows::{PeptideFollow, PeptideFollows};
+pub use goals::{compute_goal_progress, goal_readings, GoalProgress, GoalReading, GoalStatus};
+pub use health_import::{
+    daily_weights, parse_apple_health, parse_google_fit_csv, plan_health_import, DailyHealthSample, GoogleFitColumns,
+    HealthImportMapping, HealthImportPlan, HealthImportSource,
+};
+pub use interactions::{find_interactions, InteractionWarning};
+pub use journal::{parse_links, strip_links, JournalLink, JournalLinkKind};
+pub use key_recovery::{create_recovery_code, recover_storage, KeyRecovery, RecoveryCodeStatus};
+pub use key_rotation::{generate_key, rotate_storage_key, KeyRotation, KeyRotationProgress};
+pub use keychain::{migrate_file_key_to_keychain, BiometricKeyProvider, KeychainKeyProvider};
+pub use maintenance::{MaintenanceAction, MaintenanceRun, MAINTENANCE_INTERVAL_DAYS};
+pub use migration::{MigrationFailed, MigrationSnapshot};
+pub use models::{AiUsage, Attachment, AttachmentKind, AttachmentOwner, BodyMetric, BulkDiscountTier, DoseLog, DoseLogCorrection, DoseSkip, ExchangeRate, Goal, GoalMetric, InventoryItem, JournalEntry, LabResult, LandedCost, LiteratureEmbedding, LiteratureEntry, LiteratureRetention, ObservationStatus, ObservedPrice, Order, OrderItem, OrderStatus, PeptideProtocol, PriceObservation, RangeStatus, RateSource, ReadingStatus, SavedSearch, ScrapingProfile, SideEffect, Supplier, SupplierDeletion, SupplierProduct, SupplierRating, SupplierReview, TrialResult, VialStatus};
+pub use models::{normalize_doi, publication_year};
+pub use events::{StorageEvent, StorageListener};
+pub use network::HttpSettings;
+pub use notifications::{ChannelKind, NotificationChannel, NotificationEvent, NotificationEventKind, WebhookRequest};
+pub use passphrase::{
+    change_passphrase, unlock_storage, validate_passphrase, DatabaseLocked, KdfParams,
+    PassphraseConfig, PassphraseKeyProvider,
+};
+pub use pool::WriterConnection;
+pub use price_trend::{PriceTrend, PriceTrendFilter, PriceTrendPoint, PriceTrendSeries};
+pub use protocol_package::{ProtocolPackage, SharedProtocol, SharedReference, SharedSchedule, SharedTitrationPhase};
+pub use qr::QrCode;
+pub use recovery::{RecoveryProgress, SalvageReport, TableRecovery};
+pub use redaction::Redactor;
+pub use response_cache::{ResponseCacheSettings, ResponseCacheStats, SourceCacheStats};
+pub use scraping::{RobotsTxt, ScrapingSettings};
+pub use search::{SearchEntityType, SearchHit};
+pub use settings::Setting;
+pub use setup::{SetupStatus, SetupStep};
+pub use stats_cache::{CachedStat, DashboardStat, StatsGeneration, MAX_STAT_AGE};
+pub use summary_diff::{diff_summaries, DiffLine, DiffOp, SummaryDiff, SummaryVersion};
+pub use summary_export::{export_summaries_markdown, MarkdownExportResult};
+pub use supplier_ranking::{rank_suppliers, SupplierRanking};
+pub use sync::{ChangeSet, SyncApplied, SyncChange, SyncCursor, SyncDevice, SyncRelay, SyncSettings};
+pub use tamper::{BrokenLink, TamperChange, TamperFinding, TamperReport};
+pub use orphans::{CascadePolicies, OrphanKind, OrphanRepair, OrphanedRecord, StaleAlertPolicy, SupplierInventoryPolicy};
+pub use trash::{TrashEntityType, TrashItem, TrashSettings};
+pub use undo::{UndoAction, UndoOperation, UndoStack, UndoSummary, MAX_UNDO_OPERATIONS};
+pub use units::{
+    known_iu_per_mg, length_to_cm, weight_to_kg, DoseUnit, IuConversion, LengthUnit, UnitPreferences, WeightUnit,
+};
+pub use vial_label::{parse_vial_qr, render_vial_label, vial_qr_content, VialLabel};
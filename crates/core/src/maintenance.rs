@@ -0,0 +1,70 @@
+//! Routine database maintenance
+//!
+//! [`DatabaseStats`] says when the file has too many free pages or the WAL
+//! has grown too large. [`StorageManager::run_maintenance`] checks the stats
+//! and runs only the upkeep they call for: an incremental vacuum with
+//! `PRAGMA optimize` and `ANALYZE` for fragmentation, and a truncating
+//! checkpoint for the WAL. Every run is kept in the `maintenance_runs` table,
+//! newest [`MAX_MAINTENANCE_RUNS`] only, which also tells the app when the
+//! next scheduled run is due.
+//!
+//! [`StorageManager::run_maintenance`]: crate::StorageManager::run_maintenance
+
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+
+use crate::models::DatabaseStats;
+
+/// Days between scheduled maintenance checks
+pub const MAINTENANCE_INTERVAL_DAYS: i64 = 7;
+/// Runs kept in the history
+pub const MAX_MAINTENANCE_RUNS: usize = 50;
+
+/// Upkeep a maintenance run can do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceAction {
+    /// Incremental vacuum, `PRAGMA optimize` and `ANALYZE`
+    Optimize,
+    /// Truncating WAL checkpoint
+    Checkpoint,
+}
+
+impl MaintenanceAction {
+    /// Actions `stats` call for
+    pub fn needed(stats: &DatabaseStats) -> Vec<MaintenanceAction> {
+        let mut actions = Vec::new();
+        if stats.should_vacuum() {
+            actions.push(MaintenanceAction::Optimize);
+        }
+        if stats.should_checkpoint() {
+            actions.push(MaintenanceAction::Checkpoint);
+        }
+        actions
+    }
+}
+
+/// One maintenance check and what it did
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceRun {
+    pub id: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub ran_at: OffsetDateTime,
+    /// Started from the health screen rather than by the schedule
+    pub manual: bool,
+    /// Empty when the stats were within their thresholds
+    pub actions: Vec<MaintenanceAction>,
+    pub before: DatabaseStats,
+    /// Stats once the actions were done; the same as `before` when none were
+    pub after: DatabaseStats,
+    /// Why an action failed; actions after it were not run
+    pub error: Option<String>,
+}
+
+impl MaintenanceRun {
+    /// When the next scheduled check is due
+    pub fn next_due(&self) -> OffsetDateTime {
+        self.ran_at + Duration::days(MAINTENANCE_INTERVAL_DAYS)
+    }
+}
@@ -0,0 +1,147 @@
+//! Snapshots of the database taken before schema migrations
+//!
+//! [`StorageManager::initialize`](crate::StorageManager::initialize) copies
+//! the database before migrating it. If a migration fails part-way, the copy
+//! is restored and [`MigrationFailed`] is returned, so the database is never
+//! left half-migrated. Snapshots are kept next to the database in
+//! `migration-snapshots/`, each as `<id>.sqlite` with its [`MigrationSnapshot`]
+//! in `<id>.json`; the newest [`MAX_MIGRATION_SNAPSHOTS`] are kept.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use blake2::{Blake2s256, Digest};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::warn;
+
+/// Directory next to the database that holds the snapshots
+pub const SNAPSHOT_DIR_NAME: &str = "migration-snapshots";
+/// Snapshots kept; older ones are deleted when a new one is taken
+pub const MAX_MIGRATION_SNAPSHOTS: usize = 3;
+
+/// A copy of the database taken before migrating it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationSnapshot {
+    pub id: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// Schema version of the database when it was copied
+    pub from_version: i32,
+    /// Schema version it was being migrated to
+    pub to_version: i32,
+    pub size_bytes: u64,
+    /// BLAKE2s-256 of the copy, checked before it is restored
+    pub checksum: String,
+}
+
+/// A schema migration failed
+///
+/// With `restored`, the database was put back the way it was before the
+/// migration started; otherwise the snapshot `snapshot_id` could not be
+/// restored either and should be restored by hand.
+#[derive(Debug, thiserror::Error)]
+#[error("{}", self.describe())]
+pub struct MigrationFailed {
+    pub snapshot_id: String,
+    pub restored: bool,
+    pub reason: String,
+}
+
+impl MigrationFailed {
+    fn describe(&self) -> String {
+        if self.restored {
+            format!("Database upgrade failed and was rolled back: {}", self.reason)
+        } else {
+            format!(
+                "Database upgrade failed and snapshot {} could not be restored: {}",
+                self.snapshot_id, self.reason
+            )
+        }
+    }
+}
+
+/// Where snapshots of the database at `db_path` are kept
+pub(crate) fn snapshot_dir(db_path: &Path) -> PathBuf {
+    db_path
+        .parent()
+        .map(|dir| dir.join(SNAPSHOT_DIR_NAME))
+        .unwrap_or_else(|| PathBuf::from(SNAPSHOT_DIR_NAME))
+}
+
+pub(crate) fn snapshot_file(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.sqlite", id))
+}
+
+fn metadata_file(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.json", id))
+}
+
+/// BLAKE2s-256 of the file at `path`, hex encoded
+pub(crate) fn checksum(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(hex::encode(Blake2s256::digest(&bytes)))
+}
+
+/// Record a snapshot whose copy was written to [`snapshot_file`]
+pub(crate) fn save_metadata(dir: &Path, snapshot: &MigrationSnapshot) -> Result<()> {
+    let json = serde_json::to_vec_pretty(snapshot)?;
+    fs::write(metadata_file(dir, &snapshot.id), json).context("Failed to save snapshot metadata")
+}
+
+/// All snapshots in `dir`, newest first
+pub(crate) fn list(dir: &Path) -> Result<Vec<MigrationSnapshot>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(dir).context("Failed to read snapshot directory")? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        match fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(serde_json::from_slice::<MigrationSnapshot>(&json)?))
+        {
+            Ok(snapshot) if snapshot_file(dir, &snapshot.id).exists() => snapshots.push(snapshot),
+            Ok(snapshot) => warn!("Snapshot {} has no database copy", snapshot.id),
+            Err(e) => warn!("Skipping unreadable snapshot {}: {:#}", path.display(), e),
+        }
+    }
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(snapshots)
+}
+
+/// The snapshot `id`, after checking its copy is intact
+pub(crate) fn verified(dir: &Path, id: &str) -> Result<(MigrationSnapshot, PathBuf)> {
+    let snapshot = list(dir)?
+        .into_iter()
+        .find(|snapshot| snapshot.id == id)
+        .ok_or_else(|| anyhow!("Migration snapshot {} not found", id))?;
+    let path = snapshot_file(dir, id);
+    if checksum(&path)? != snapshot.checksum {
+        return Err(anyhow!("Migration snapshot {} is damaged", id));
+    }
+    Ok((snapshot, path))
+}
+
+/// Delete the snapshot `id`
+pub(crate) fn remove(dir: &Path, id: &str) -> Result<()> {
+    for path in [snapshot_file(dir, id), metadata_file(dir, id)] {
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Delete all but the newest [`MAX_MIGRATION_SNAPSHOTS`] snapshots
+pub(crate) fn prune(dir: &Path) -> Result<()> {
+    for snapshot in list(dir)?.iter().skip(MAX_MIGRATION_SNAPSHOTS) {
+        remove(dir, &snapshot.id)?;
+    }
+    Ok(())
+}
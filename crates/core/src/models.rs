@@ -1,8 +1,11 @@
+use blake2::{Blake2s256, Digest};
 use serde::{Deserialize, Serialize};
-use time::OffsetDateTime;
+use time::{Date, OffsetDateTime};
 use uuid::Uuid;
 
+use crate::currency::default_currency;
 use crate::db::now_timestamp;
+use crate::settings::Setting;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PeptideProtocol {
@@ -46,6 +49,12 @@ pub struct DoseLog {
     pub amount_mg: f32,
     pub notes: Option<String>,
     pub logged_at: OffsetDateTime,
+    /// Dose schedule this dose was logged from, if any
+    #[serde(default)]
+    pub schedule_id: Option<String>,
+    /// When the dose was last edited; `None` for doses as first logged
+    #[serde(default)]
+    pub edited_at: Option<OffsetDateTime>,
 }
 
 impl DoseLog {
@@ -57,6 +66,47 @@ impl DoseLog {
             amount_mg,
             notes: None,
             logged_at: now_timestamp(),
+            schedule_id: None,
+            edited_at: None,
+        }
+    }
+}
+
+/// A dose log as it was before one of its edits
+///
+/// Corrections are never changed; the oldest one for a log holds the values
+/// the dose was first logged with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoseLogCorrection {
+    pub id: String,
+    pub dose_log_id: String,
+    /// The log's values before the edit
+    pub original: DoseLog,
+    pub corrected_at: OffsetDateTime,
+}
+
+/// A scheduled dose that was deliberately not taken
+///
+/// Recorded from a reminder's Skip action, so adherence can tell skipped
+/// doses from forgotten ones.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DoseSkip {
+    pub id: String,
+    pub protocol_id: String,
+    pub schedule_id: String,
+    /// Day the dose was due
+    pub scheduled_on: Date,
+    pub skipped_at: OffsetDateTime,
+}
+
+impl DoseSkip {
+    pub fn new(protocol_id: impl Into<String>, schedule_id: impl Into<String>, scheduled_on: Date) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            protocol_id: protocol_id.into(),
+            schedule_id: schedule_id.into(),
+            scheduled_on,
+            skipped_at: OffsetDateTime::now_utc(),
         }
     }
 }
@@ -70,6 +120,61 @@ pub struct LiteratureEntry {
     pub summary: Option<String>,
     pub relevance_score: Option<f32>,
     pub indexed_at: OffsetDateTime,
+    /// Peptide and study type tags, e.g. `peptide:bpc-157`, `study:rct`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub published_date: Option<String>,
+    #[serde(default)]
+    pub citation_count: Option<u32>,
+    /// Bare lowercase DOI, see [`normalize_doi`]
+    #[serde(default)]
+    pub doi: Option<String>,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    #[serde(default)]
+    pub journal: Option<String>,
+    #[serde(default)]
+    pub year: Option<i32>,
+    /// When metadata enrichment last looked this entry up; None means it
+    /// hasn't been tried yet
+    #[serde(default)]
+    pub enriched_at: Option<OffsetDateTime>,
+    /// A retraction notice has been published for this paper
+    #[serde(default)]
+    pub retracted: bool,
+    /// A correction or erratum has been published for this paper
+    #[serde(default)]
+    pub corrected: bool,
+    /// DOI of the retraction or correction notice
+    #[serde(default)]
+    pub notice_doi: Option<String>,
+    #[serde(default)]
+    pub retraction_checked_at: Option<OffsetDateTime>,
+    /// Pinned entries are never pruned from the cache
+    #[serde(default)]
+    pub is_pinned: bool,
+    /// Reading list state; `None` when the entry isn't on the reading list
+    #[serde(default)]
+    pub reading_status: Option<ReadingStatus>,
+    /// Personal rating, 1-5
+    #[serde(default)]
+    pub rating: Option<u8>,
+    /// Personal reading notes
+    #[serde(default)]
+    pub reading_notes: Option<String>,
+    /// Downloaded copy of the paper, stored as an encrypted attachment
+    #[serde(default)]
+    pub pdf_attachment_id: Option<String>,
+}
+
+/// Where a paper on the reading list is up to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadingStatus {
+    Unread,
+    Reading,
+    Read,
 }
 
 impl LiteratureEntry {
@@ -82,7 +187,222 @@ impl LiteratureEntry {
             summary: None,
             relevance_score: None,
             indexed_at: now_timestamp(),
+            tags: Vec::new(),
+            published_date: None,
+            citation_count: None,
+            doi: None,
+            authors: Vec::new(),
+            journal: None,
+            year: None,
+            enriched_at: None,
+            retracted: false,
+            corrected: false,
+            notice_doi: None,
+            retraction_checked_at: None,
+            is_pinned: false,
+            reading_status: None,
+            rating: None,
+            reading_notes: None,
+            pdf_attachment_id: None,
+        }
+    }
+
+    /// Whether the user is keeping this entry, by pinning it, adding it to
+    /// the reading list or downloading its PDF; kept entries are never
+    /// pruned from the cache
+    pub fn is_kept(&self) -> bool {
+        self.is_pinned || self.reading_status.is_some() || self.pdf_attachment_id.is_some()
+    }
+
+    /// Whether the DOI, authors, journal or year is unknown
+    pub fn is_missing_metadata(&self) -> bool {
+        self.doi.is_none() || self.authors.is_empty() || self.journal.is_none() || self.year.is_none()
+    }
+
+    /// Fill in the DOI and year from the URL and publication date, for
+    /// entries cached before those fields existed
+    pub fn derive_metadata(&mut self) -> bool {
+        let mut changed = false;
+        if self.doi.is_none() {
+            self.doi = self.url.as_deref().and_then(doi_from_url);
+            changed |= self.doi.is_some();
         }
+        if self.year.is_none() {
+            self.year = self.published_date.as_deref().and_then(publication_year);
+            changed |= self.year.is_some();
+        }
+        changed
+    }
+
+    /// Text embedded for question answering: the title and abstract
+    pub fn embedding_text(&self) -> String {
+        match self.summary.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            Some(summary) => format!("{}\n\n{}", self.title.trim(), summary),
+            None => self.title.trim().to_string(),
+        }
+    }
+}
+
+/// How long cached literature is kept
+///
+/// Pinned entries and entries on the reading list are always kept. Both
+/// limits are off by default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LiteratureRetention {
+    /// Drop entries indexed more than this many days ago
+    pub max_age_days: Option<u32>,
+    /// Keep at most this many of the most recently indexed entries
+    pub max_entries: Option<usize>,
+}
+
+impl Setting for LiteratureRetention {
+    const KEY: &'static str = "literature.retention";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.max_age_days == Some(0) || self.max_entries == Some(0) {
+            return Err("Retention limits must keep at least one day and one entry".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Embedding of a cached paper's title and abstract
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiteratureEmbedding {
+    pub entry_id: String,
+    /// Embedder that produced the vector; vectors from different models
+    /// can't be compared
+    pub model: String,
+    /// Hash of the embedded text, so entries whose abstract changed are
+    /// embedded again
+    pub content_hash: String,
+    pub vector: Vec<f32>,
+}
+
+impl LiteratureEmbedding {
+    /// Hex hash of the text an embedding was made from
+    pub fn content_hash(text: &str) -> String {
+        hex::encode(Blake2s256::digest(text.as_bytes()))
+    }
+}
+
+/// Prefixes that DOIs are commonly written with
+const DOI_PREFIXES: &[&str] = &["https://doi.org/", "http://doi.org/", "https://dx.doi.org/", "http://dx.doi.org/", "doi:"];
+
+/// Bare lowercase DOI, e.g. "10.1000/xyz" from "https://doi.org/10.1000/XYZ"
+pub fn normalize_doi(doi: &str) -> String {
+    let doi = doi.trim();
+    let bare = DOI_PREFIXES
+        .iter()
+        .find_map(|prefix| {
+            doi.get(..prefix.len())
+                .filter(|head| head.eq_ignore_ascii_case(prefix))
+                .map(|_| &doi[prefix.len()..])
+        })
+        .unwrap_or(doi);
+    bare.to_lowercase()
+}
+
+/// DOI from a doi.org link
+fn doi_from_url(url: &str) -> Option<String> {
+    let url = url.trim();
+    let is_doi_link = DOI_PREFIXES[..4]
+        .iter()
+        .any(|prefix| url.get(..prefix.len()).is_some_and(|head| head.eq_ignore_ascii_case(prefix)));
+    is_doi_link.then(|| normalize_doi(url)).filter(|doi| doi.starts_with("10."))
+}
+
+/// Year at the start of a publication date such as "2023 Jan 15" or "2023-01-15"
+pub fn publication_year(date: &str) -> Option<i32> {
+    date.trim().get(..4)?.parse().ok()
+}
+
+/// Default days between background runs of a saved search
+pub const DEFAULT_SAVED_SEARCH_INTERVAL_DAYS: u32 = 7;
+
+/// A literature query re-run in the background to find new papers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    pub query: String,
+    /// Fetchers to run, e.g. "pubmed", "openalex"
+    pub sources: Vec<String>,
+    /// Results requested from each source per run
+    pub max_results: usize,
+    pub interval_days: u32,
+    pub enabled: bool,
+    /// Dedup keys of every result found so far, so a run only reports
+    /// papers it hasn't seen before
+    #[serde(default)]
+    pub seen_keys: Vec<String>,
+    pub last_run_at: Option<OffsetDateTime>,
+    /// New results found by the last run
+    #[serde(default)]
+    pub last_new_count: usize,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl SavedSearch {
+    pub fn new<S: Into<String>>(name: S, query: S) -> Self {
+        let now = now_timestamp();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: name.into(),
+            query: query.into(),
+            sources: vec!["pubmed".to_string(), "openalex".to_string()],
+            max_results: 20,
+            interval_days: DEFAULT_SAVED_SEARCH_INTERVAL_DAYS,
+            enabled: true,
+            seen_keys: Vec::new(),
+            last_run_at: None,
+            last_new_count: 0,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// When the search is next due; a search that never ran is due now
+    pub fn next_run_at(&self) -> OffsetDateTime {
+        match self.last_run_at {
+            Some(last_run) => last_run + time::Duration::days(self.interval_days.max(1) as i64),
+            None => self.created_at,
+        }
+    }
+}
+
+/// A ClinicalTrials.gov study, cached so it can be linked to protocols
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrialResult {
+    /// ClinicalTrials.gov id, e.g. "NCT01234567"
+    pub nct_id: String,
+    pub title: String,
+    /// e.g. "Phase 2" or "Phase 1/Phase 2"; None when not applicable
+    pub phase: Option<String>,
+    /// Overall status as reported, e.g. "RECRUITING", "COMPLETED"
+    pub status: String,
+    /// Actual or anticipated participants
+    pub enrollment: Option<u32>,
+    /// Whether results were posted to ClinicalTrials.gov
+    pub has_results: bool,
+    #[serde(default)]
+    pub conditions: Vec<String>,
+    #[serde(default)]
+    pub interventions: Vec<String>,
+    pub sponsor: Option<String>,
+    pub start_date: Option<String>,
+    pub completion_date: Option<String>,
+    /// Protocols this trial is linked to
+    #[serde(default)]
+    pub protocol_ids: Vec<String>,
+    pub fetched_at: OffsetDateTime,
+}
+
+impl TrialResult {
+    pub fn url(&self) -> String {
+        format!("https://clinicaltrials.gov/study/{}", self.nct_id)
     }
 }
 
@@ -94,10 +414,92 @@ pub struct Supplier {
     pub contact_phone: Option<String>,
     pub website: Option<String>,
     pub notes: Option<String>,
+    #[serde(default)]
+    pub product_urls: Vec<SupplierProduct>, // Product pages re-scraped by the price monitor
+    #[serde(default)]
+    pub scraping_profile: Option<ScrapingProfile>,
+    #[serde(default)]
+    pub currency: Option<String>, // Default currency for this supplier's prices
+    /// Reviews, oldest first; at most one per order
+    #[serde(default)]
+    pub reviews: Vec<SupplierReview>,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
 }
 
+/// Supplier Review
+/// Ratings from 1 to 5 for one order, or for the supplier in general
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SupplierReview {
+    pub id: String,
+    #[serde(default)]
+    pub order_id: Option<String>,
+    pub shipping_speed: u8,
+    pub product_quality: u8,
+    pub communication: u8,
+    #[serde(default)]
+    pub notes: Option<String>,
+    pub reviewed_at: OffsetDateTime,
+}
+
+impl SupplierReview {
+    pub fn new(shipping_speed: u8, product_quality: u8, communication: u8) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            order_id: None,
+            shipping_speed,
+            product_quality,
+            communication,
+            notes: None,
+            reviewed_at: now_timestamp(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let ratings = [
+            ("Shipping speed", self.shipping_speed),
+            ("Product quality", self.product_quality),
+            ("Communication", self.communication),
+        ];
+        for (label, rating) in ratings {
+            if !(1..=5).contains(&rating) {
+                return Err(format!("{} must be rated from 1 to 5", label));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Average of a supplier's review ratings
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SupplierRating {
+    pub shipping_speed: f32,
+    pub product_quality: f32,
+    pub communication: f32,
+    /// Mean of the three averages, 1 to 5
+    pub overall: f32,
+    pub review_count: usize,
+}
+
+/// Supplier Product Page
+/// A product URL for a specific peptide that can be re-scraped for prices
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SupplierProduct {
+    pub peptide_name: String,
+    pub url: String,
+}
+
+/// Supplier Scraping Profile
+/// CSS selectors used to extract structured price, size and stock data
+/// from a supplier's product pages
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ScrapingProfile {
+    pub price_selector: String,
+    pub size_selector: Option<String>, // Element containing the vial size, e.g. "10mg"
+    pub stock_selector: Option<String>,
+    pub out_of_stock_text: Option<String>, // Stock text that means unavailable (default: "out of stock", "sold out")
+}
+
 impl Supplier {
     pub fn new<S: Into<String>>(name: S) -> Self {
         let now = now_timestamp();
@@ -108,13 +510,62 @@ impl Supplier {
             contact_phone: None,
             website: None,
             notes: None,
+            product_urls: Vec::new(),
+            scraping_profile: None,
+            currency: None,
+            reviews: Vec::new(),
             created_at: now,
             updated_at: now,
         }
     }
+
+    /// Add `review`, replacing the earlier review of the same order
+    pub fn add_review(&mut self, review: SupplierReview) {
+        if let Some(order_id) = &review.order_id {
+            self.reviews.retain(|existing| existing.order_id.as_ref() != Some(order_id));
+        }
+        self.reviews.push(review);
+        self.reviews.sort_by_key(|review| review.reviewed_at);
+    }
+
+    /// Averages over every review; `None` when there are none
+    pub fn rating(&self) -> Option<SupplierRating> {
+        if self.reviews.is_empty() {
+            return None;
+        }
+        let count = self.reviews.len() as f32;
+        let average = |rating: fn(&SupplierReview) -> u8| {
+            self.reviews.iter().map(|review| f32::from(rating(review))).sum::<f32>() / count
+        };
+        let shipping_speed = average(|review| review.shipping_speed);
+        let product_quality = average(|review| review.product_quality);
+        let communication = average(|review| review.communication);
+        Some(SupplierRating {
+            shipping_speed,
+            product_quality,
+            communication,
+            overall: (shipping_speed + product_quality + communication) / 3.0,
+            review_count: self.reviews.len(),
+        })
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What a bulk supplier delete removed or changed
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SupplierDeletion {
+    pub suppliers_deleted: usize,
+    /// Price history recorded for the deleted suppliers, deleted with them
+    pub price_history_deleted: usize,
+    /// Inventory items that were bought from a deleted supplier and now
+    /// have none
+    pub inventory_unlinked: usize,
+    /// Inventory items deleted with their supplier, see
+    /// [`CascadePolicies`](crate::CascadePolicies)
+    #[serde(default)]
+    pub inventory_deleted: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum VialStatus {
     Sealed,
@@ -133,6 +584,8 @@ pub struct InventoryItem {
     pub purchase_date: Option<OffsetDateTime>,
     pub expiry_date: Option<OffsetDateTime>,
     pub cost_per_mg: Option<f32>,
+    #[serde(default = "default_currency")]
+    pub currency: String, // ISO 4217 code for cost_per_mg
     pub quantity_mg: Option<f32>,
     pub quantity_remaining_mg: Option<f32>, // NEW: Track remaining quantity
     pub concentration_mg_ml: Option<f32>,
@@ -142,8 +595,17 @@ pub struct InventoryItem {
     pub notes: Option<String>,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
+    /// When the powder was mixed; a reconstituted vial keeps for much less
+    /// time than its dry-powder `expiry_date`
+    #[serde(default)]
+    pub reconstituted_at: Option<OffsetDateTime>,
+    #[serde(default)]
+    pub reconstituted_expiry: Option<OffsetDateTime>,
 }
 
+/// Fridge life of a reconstituted peptide the catalog has no data for
+pub const DEFAULT_RECONSTITUTED_STABILITY_DAYS: u32 = 28;
+
 impl InventoryItem {
     pub fn new<S: Into<String>>(protocol_id: S) -> Self {
         let now = now_timestamp();
@@ -156,6 +618,7 @@ impl InventoryItem {
             purchase_date: None,
             expiry_date: None,
             cost_per_mg: None,
+            currency: default_currency(),
             quantity_mg: None,
             quantity_remaining_mg: None,
             concentration_mg_ml: None,
@@ -165,8 +628,35 @@ impl InventoryItem {
             notes: None,
             created_at: now,
             updated_at: now,
+            reconstituted_at: None,
+            reconstituted_expiry: None,
+        }
+    }
+
+    /// Record mixing the vial at `at`; it keeps for `stability_days` after
+    /// that, and a sealed vial counts as opened
+    pub fn reconstitute(&mut self, at: OffsetDateTime, stability_days: u32) {
+        self.reconstituted_at = Some(at);
+        self.reconstituted_expiry = Some(at + time::Duration::days(i64::from(stability_days)));
+        if self.vial_status == VialStatus::Sealed {
+            self.vial_status = VialStatus::Opened;
         }
     }
+
+    /// Whichever comes first of the dry-powder and reconstituted expiry
+    pub fn effective_expiry(&self) -> Option<OffsetDateTime> {
+        match (self.expiry_date, self.reconstituted_expiry) {
+            (Some(dry), Some(mixed)) => Some(dry.min(mixed)),
+            (dry, mixed) => dry.or(mixed),
+        }
+    }
+
+    /// True when the reconstituted fridge life, not the dry-powder expiry,
+    /// is what runs out first
+    pub fn expires_as_reconstituted(&self) -> bool {
+        self.reconstituted_expiry
+            .is_some_and(|mixed| self.expiry_date.is_none_or(|dry| mixed <= dry))
+    }
 }
 
 /// Price History Entry
@@ -177,10 +667,40 @@ pub struct PriceHistory {
     pub supplier_id: String,
     pub peptide_name: String,
     pub cost_per_mg: f32,
+    #[serde(default = "default_currency")]
+    pub currency: String, // ISO 4217 code for cost_per_mg
     pub url: Option<String>, // Source URL if scraped
     pub in_stock: Option<bool>, // Track availability
     pub notes: Option<String>,
     pub recorded_at: OffsetDateTime,
+    #[serde(default)]
+    pub vial_size_mg: Option<f32>,
+    /// Flat shipping per order, in `currency`
+    #[serde(default)]
+    pub shipping_cost: Option<f32>,
+    #[serde(default)]
+    pub min_order_vials: Option<u32>,
+    #[serde(default)]
+    pub bulk_discounts: Vec<BulkDiscountTier>,
+}
+
+/// Discount off the vial price when ordering at least `min_vials` at once
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BulkDiscountTier {
+    pub min_vials: u32,
+    pub discount_percent: f32,
+}
+
+/// What an order actually costs once vial size, minimum order, bulk
+/// discounts and shipping are accounted for, in the price's currency
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct LandedCost {
+    /// Whole vials bought, when the vial size is known
+    pub vials: Option<u32>,
+    pub quantity_mg: f32,
+    pub discount_percent: f32,
+    pub total_cost: f32,
+    pub cost_per_mg: f32,
 }
 
 impl PriceHistory {
@@ -190,17 +710,342 @@ impl PriceHistory {
             supplier_id: supplier_id.into(),
             peptide_name: peptide_name.into(),
             cost_per_mg,
+            currency: default_currency(),
             url: None,
             in_stock: None,
             notes: None,
             recorded_at: now_timestamp(),
+            vial_size_mg: None,
+            shipping_cost: None,
+            min_order_vials: None,
+            bulk_discounts: Vec::new(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.cost_per_mg.is_finite() || self.cost_per_mg < 0.0 {
+            return Err("Cost per mg can't be negative".to_string());
+        }
+        if self.vial_size_mg.is_some_and(|size| !size.is_finite() || size <= 0.0) {
+            return Err("Vial size must be more than 0 mg".to_string());
+        }
+        if self.shipping_cost.is_some_and(|cost| !cost.is_finite() || cost < 0.0) {
+            return Err("Shipping cost can't be negative".to_string());
+        }
+        if self.min_order_vials == Some(0) {
+            return Err("Minimum order must be at least 1 vial".to_string());
+        }
+        for tier in &self.bulk_discounts {
+            if tier.min_vials == 0 {
+                return Err("Bulk discounts must start at 1 vial or more".to_string());
+            }
+            if !(0.0..100.0).contains(&tier.discount_percent) {
+                return Err("Bulk discounts must be from 0 to under 100%".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Landed cost of buying at least `order_mg`
+    ///
+    /// With a vial size the order is rounded up to whole vials (at least the
+    /// minimum order, one vial when `order_mg` is `None`) and the best bulk
+    /// discount for that many vials applies. Without one, `order_mg` is
+    /// bought as is and bulk discounts can't apply. Shipping is spread over
+    /// the quantity bought; when that quantity is unknown it's left out.
+    pub fn landed_cost(&self, order_mg: Option<f32>) -> LandedCost {
+        let order_mg = order_mg.filter(|mg| mg.is_finite() && *mg > 0.0);
+        let (vials, quantity_mg) = match self.vial_size_mg.filter(|size| *size > 0.0) {
+            Some(size) => {
+                let needed = order_mg.map_or(1, |mg| (mg / size).ceil() as u32).max(1);
+                let vials = needed.max(self.min_order_vials.unwrap_or(1));
+                (Some(vials), vials as f32 * size)
+            }
+            None => (None, order_mg.unwrap_or(0.0)),
+        };
+
+        let discount_percent = vials
+            .and_then(|vials| {
+                self.bulk_discounts
+                    .iter()
+                    .filter(|tier| tier.min_vials <= vials)
+                    .map(|tier| tier.discount_percent)
+                    .reduce(f32::max)
+            })
+            .unwrap_or(0.0);
+        let goods_per_mg = self.cost_per_mg * (1.0 - discount_percent / 100.0);
+
+        if quantity_mg <= 0.0 {
+            return LandedCost {
+                vials,
+                quantity_mg,
+                discount_percent,
+                total_cost: 0.0,
+                cost_per_mg: goods_per_mg,
+            };
+        }
+        let total_cost = goods_per_mg * quantity_mg + self.shipping_cost.unwrap_or(0.0);
+        LandedCost {
+            vials,
+            quantity_mg,
+            discount_percent,
+            total_cost,
+            cost_per_mg: total_cost / quantity_mg,
         }
     }
 }
 
-/// Alert types for notifications
+/// Where a scraped price is in review
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ObservationStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+impl ObservationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ObservationStatus::Pending => "pending",
+            ObservationStatus::Accepted => "accepted",
+            ObservationStatus::Rejected => "rejected",
+        }
+    }
+}
+
+/// One price the scraper found on a page
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ObservedPrice {
+    pub cost_per_mg: f32,
+    /// Text around the price on the page
+    pub context: String,
+    /// How the price was found, e.g. a scraping profile or a pattern
+    pub pattern_type: String,
+}
+
+/// A price monitor scrape waiting for review
+///
+/// Scrapes can pick up the wrong number, so they're staged here and only
+/// become [`PriceHistory`] once accepted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceObservation {
+    pub id: String,
+    pub supplier_id: String,
+    pub peptide_name: String,
+    pub url: String,
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    pub in_stock: Option<bool>,
+    /// Prices found on the page, lowest first; empty when the page only
+    /// showed stock status
+    #[serde(default)]
+    pub matches: Vec<ObservedPrice>,
+    pub status: ObservationStatus,
+    pub observed_at: OffsetDateTime,
+    pub reviewed_at: Option<OffsetDateTime>,
+    /// Price history entry made when it was accepted
+    pub price_history_id: Option<String>,
+}
+
+impl PriceObservation {
+    pub fn new<S: Into<String>>(supplier_id: S, peptide_name: S, url: S) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            supplier_id: supplier_id.into(),
+            peptide_name: peptide_name.into(),
+            url: url.into(),
+            currency: default_currency(),
+            in_stock: None,
+            matches: Vec::new(),
+            status: ObservationStatus::Pending,
+            observed_at: now_timestamp(),
+            reviewed_at: None,
+            price_history_id: None,
+        }
+    }
+}
+
+/// Where an exchange rate came from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateSource {
+    Manual,
+    Fetched,
+}
+
+/// Exchange Rate
+/// Units of `currency` per one unit of the base currency (USD)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeRate {
+    pub currency: String,
+    pub rate_per_usd: f64,
+    pub source: RateSource,
+    pub updated_at: OffsetDateTime,
+}
+
+impl ExchangeRate {
+    pub fn new<S: Into<String>>(currency: S, rate_per_usd: f64, source: RateSource) -> Self {
+        Self {
+            currency: currency.into(),
+            rate_per_usd,
+            source,
+            updated_at: now_timestamp(),
+        }
+    }
+}
+
+/// Lifecycle of a supplier order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderStatus {
+    Pending,
+    Shipped,
+    Delivered,
+    Cancelled,
+}
+
+/// One line of an order: a number of vials for a protocol
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderItem {
+    pub protocol_id: String,
+    pub quantity: u32,              // Number of vials
+    pub vial_size_mg: Option<f32>,
+    pub unit_price: f32,            // Price per vial, in the order currency
+}
+
+/// Supplier Order
+/// Tracks a purchase from order through delivery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Order {
+    pub id: String,
+    pub supplier_id: String,
+    pub items: Vec<OrderItem>,
+    #[serde(default = "default_currency")]
+    pub currency: String, // ISO 4217 code for unit_price and shipping_cost
+    pub shipping_cost: Option<f32>,
+    pub order_date: OffsetDateTime,
+    pub expected_delivery: Option<OffsetDateTime>,
+    pub delivered_at: Option<OffsetDateTime>,
+    pub tracking_number: Option<String>,
+    pub status: OrderStatus,
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub inventory_item_ids: Vec<String>, // Vials created when the order was delivered
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl Order {
+    pub fn new<S: Into<String>>(supplier_id: S, items: Vec<OrderItem>) -> Self {
+        let now = now_timestamp();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            supplier_id: supplier_id.into(),
+            items,
+            currency: default_currency(),
+            shipping_cost: None,
+            order_date: now,
+            expected_delivery: None,
+            delivered_at: None,
+            tracking_number: None,
+            status: OrderStatus::Pending,
+            notes: None,
+            inventory_item_ids: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Cost of all items plus shipping, in the order currency
+    pub fn total(&self) -> f32 {
+        let items: f32 = self
+            .items
+            .iter()
+            .map(|item| item.unit_price * item.quantity as f32)
+            .sum();
+        items + self.shipping_cost.unwrap_or(0.0)
+    }
+}
+
+/// Record an attachment belongs to
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
+pub enum AttachmentOwner {
+    InventoryItem,
+    Supplier,
+    DoseLog,
+    BodyMetric,
+    Literature,
+}
+
+/// What an attachment contains
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentKind {
+    CertificateOfAnalysis,
+    Invoice,
+    Photo,
+    /// Full text of a paper
+    Paper,
+    Other,
+}
+
+/// Attachment
+/// Metadata for an encrypted file stored alongside a record; the file bytes
+/// are stored and loaded separately
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: String,
+    pub owner_type: AttachmentOwner,
+    pub owner_id: String,
+    pub kind: AttachmentKind,
+    pub file_name: String,
+    pub mime_type: String,
+    pub size_bytes: u64,
+    #[serde(default)]
+    pub width: Option<u32>, // Pixel dimensions, for images
+    #[serde(default)]
+    pub height: Option<u32>,
+    #[serde(default)]
+    pub has_thumbnail: bool,
+    pub notes: Option<String>,
+    pub created_at: OffsetDateTime,
+}
+
+impl Attachment {
+    pub fn new<S: Into<String>>(
+        owner_type: AttachmentOwner,
+        owner_id: S,
+        kind: AttachmentKind,
+        file_name: S,
+        mime_type: S,
+        size_bytes: u64,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            owner_type,
+            owner_id: owner_id.into(),
+            kind,
+            file_name: file_name.into(),
+            mime_type: mime_type.into(),
+            size_bytes,
+            width: None,
+            height: None,
+            has_thumbnail: false,
+            notes: None,
+            created_at: now_timestamp(),
+        }
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+}
+
+/// Alert types for notifications
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
 pub enum AlertType {
     LowStock,
     ExpiringSoon,
@@ -208,10 +1053,19 @@ pub enum AlertType {
     PriceIncrease,
     PriceDecrease,
     OutOfStock,
+    Interaction,
+    /// A saved literature search found new papers
+    NewLiterature,
+    /// A cached paper was retracted
+    Retraction,
+    /// A correction or erratum was published for a cached paper
+    Erratum,
+    /// A backup destination hasn't had a successful backup in a while
+    BackupOverdue,
 }
 
-/// Alert severity levels
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Alert severity levels, ordered from least to most severe
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "lowercase")]
 pub enum AlertSeverity {
     Info,
@@ -230,6 +1084,8 @@ pub struct Alert {
     pub message: String,
     pub related_id: Option<String>, // ID of related item (inventory_id, supplier_id, etc.)
     pub related_type: Option<String>, // Type: "inventory", "supplier", "protocol"
+    #[serde(default)]
+    pub references: Vec<String>, // IDs of related cached literature entries
     pub is_read: bool,
     pub is_dismissed: bool,
     pub created_at: OffsetDateTime,
@@ -250,6 +1106,7 @@ impl Alert {
             message: message.into(),
             related_id: None,
             related_type: None,
+            references: Vec::new(),
             is_read: false,
             is_dismissed: false,
             created_at: now_timestamp(),
@@ -268,6 +1125,33 @@ pub struct SummaryHistory {
     pub format: String, // "markdown", "plain", "bullets"
     pub provider: String, // "openai", "anthropic", "ollama"
     pub created_at: OffsetDateTime,
+    /// IDs of cached literature entries the summary was written from
+    #[serde(default)]
+    pub references: Vec<String>,
+    /// Warnings added after the summary was written, e.g. that a cited
+    /// paper was retracted
+    #[serde(default)]
+    pub notices: Vec<String>,
+    /// Tokens and cost of the AI calls that wrote the summary; unknown for
+    /// summaries saved before usage was tracked
+    #[serde(default)]
+    pub usage: Option<AiUsage>,
+    /// What was summarized: a literature entry id, or a hash of the
+    /// original content. Summaries of the same source form its version
+    /// history.
+    #[serde(default)]
+    pub source_id: String,
+}
+
+/// Tokens used by AI calls and what they cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AiUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    /// Token counts were estimated from text length rather than reported
+    /// by the provider
+    pub estimated: bool,
 }
 
 impl SummaryHistory {
@@ -286,10 +1170,46 @@ impl SummaryHistory {
             format: format.into(),
             provider: provider.into(),
             created_at: now_timestamp(),
+            references: Vec::new(),
+            notices: Vec::new(),
+            usage: None,
+            source_id: String::new(),
+        }
+        .with_content_source()
+    }
+
+    /// Source id of summaries of `content` that aren't tied to a
+    /// literature entry
+    pub fn content_source_id(content: &str) -> String {
+        format!("content:{}", LiteratureEmbedding::content_hash(content.trim()))
+    }
+
+    /// Set the source id from the original content if none is set, as for
+    /// summaries saved before sources were tracked
+    pub fn with_content_source(mut self) -> Self {
+        if self.source_id.is_empty() {
+            self.source_id = Self::content_source_id(&self.original_content);
+        }
+        self
+    }
+
+    /// Whether the summary was written from `entry`: listed in its
+    /// references, or its DOI or full title appears in the summary or the
+    /// text it summarized
+    pub fn cites(&self, entry: &LiteratureEntry) -> bool {
+        if self.references.contains(&entry.id) {
+            return true;
         }
+        let text = format!("{}\n{}", self.original_content, self.summary_output).to_lowercase();
+        let title = entry.title.trim().to_lowercase();
+        entry.doi.as_deref().is_some_and(|doi| text.contains(&doi.to_lowercase()))
+            || (title.len() >= MIN_CITED_TITLE_LEN && text.contains(&title))
     }
 }
 
+/// Shorter titles match too much unrelated text to count as a citation
+const MIN_CITED_TITLE_LEN: usize = 20;
+
 /// Body Metric Entry
 /// Tracks body composition and health metrics over time
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -300,6 +1220,10 @@ pub struct BodyMetric {
     pub body_fat_percentage: Option<f32>,
     pub muscle_mass_kg: Option<f32>,
     pub waist_cm: Option<f32>,
+    #[serde(default)]
+    pub resting_heart_rate_bpm: Option<f32>,
+    #[serde(default)]
+    pub sleep_hours: Option<f32>,
     pub notes: Option<String>,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
@@ -315,6 +1239,8 @@ impl BodyMetric {
             body_fat_percentage: None,
             muscle_mass_kg: None,
             waist_cm: None,
+            resting_heart_rate_bpm: None,
+            sleep_hours: None,
             notes: None,
             created_at: now,
             updated_at: now,
@@ -358,6 +1284,155 @@ impl SideEffect {
     }
 }
 
+/// Where a lab value falls relative to its reference range
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RangeStatus {
+    Low,
+    Normal,
+    High,
+    Unknown, // No reference range recorded
+}
+
+/// Lab Result Entry
+/// A single bloodwork marker (e.g. IGF-1, LDL) from a lab panel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabResult {
+    pub id: String,
+    pub marker: String, // e.g., "IGF-1", "LDL Cholesterol"
+    pub value: f32,
+    pub unit: String, // e.g., "ng/mL", "mg/dL"
+    pub reference_low: Option<f32>,
+    pub reference_high: Option<f32>,
+    pub collected_at: OffsetDateTime,
+    pub lab_name: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl LabResult {
+    pub fn new<S: Into<String>>(marker: S, value: f32, unit: S, collected_at: OffsetDateTime) -> Self {
+        let now = now_timestamp();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            marker: marker.into(),
+            value,
+            unit: unit.into(),
+            reference_low: None,
+            reference_high: None,
+            collected_at,
+            lab_name: None,
+            notes: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Compare the value against the reference range; either bound may be open
+    pub fn range_status(&self) -> RangeStatus {
+        match (self.reference_low, self.reference_high) {
+            (None, None) => RangeStatus::Unknown,
+            (Some(low), _) if self.value < low => RangeStatus::Low,
+            (_, Some(high)) if self.value > high => RangeStatus::High,
+            _ => RangeStatus::Normal,
+        }
+    }
+}
+
+/// Journal Entry
+/// A freeform markdown note; `[[protocol:...]]`, `[[dose:...]]` and
+/// `[[paper:...]]` links point at other records (see [`crate::journal`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: String,
+    pub title: Option<String>,
+    /// Markdown
+    pub body: String,
+    /// Day the entry is about, which may differ from when it was written
+    pub entry_date: OffsetDateTime,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl JournalEntry {
+    pub fn new<S: Into<String>>(body: S, entry_date: OffsetDateTime) -> Self {
+        let now = now_timestamp();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            title: None,
+            body: body.into(),
+            entry_date,
+            tags: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// What a goal measures
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalMetric {
+    WeightKg,
+    BodyFatPercentage,
+    MuscleMassKg,
+    WaistCm,
+    /// A lab marker named by the goal's `marker`, e.g. IGF-1 or testosterone
+    LabMarker,
+}
+
+/// Goal
+/// A target value for a body metric or lab marker, optionally by a date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Goal {
+    pub id: String,
+    pub name: String,
+    pub metric: GoalMetric,
+    /// Lab marker name, for `GoalMetric::LabMarker`
+    pub marker: Option<String>,
+    /// Lab results in other units are ignored, for `GoalMetric::LabMarker`
+    pub unit: Option<String>,
+    pub target_value: f32,
+    /// Value progress is measured from; the first reading after `start_date`
+    /// when not set
+    pub start_value: Option<f32>,
+    pub start_date: OffsetDateTime,
+    pub target_date: Option<OffsetDateTime>,
+    /// Protocol the goal is being pursued with
+    pub protocol_id: Option<String>,
+    pub notes: Option<String>,
+    /// Archived goals are kept but left off the dashboard
+    #[serde(default)]
+    pub archived: bool,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl Goal {
+    pub fn new<S: Into<String>>(name: S, metric: GoalMetric, target_value: f32, start_date: OffsetDateTime) -> Self {
+        let now = now_timestamp();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: name.into(),
+            metric,
+            marker: None,
+            unit: None,
+            target_value,
+            start_value: None,
+            start_date,
+            target_date: None,
+            protocol_id: None,
+            notes: None,
+            archived: false,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
 /// Database Health Report
 /// Contains information about database integrity and statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -395,7 +1470,7 @@ impl Default for HealthReport {
 
 /// Database Statistics
 /// Contains detailed metrics about database size, fragmentation, and WAL usage
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct DatabaseStats {
     pub page_count: i64,
     pub page_size: i64,
@@ -485,6 +1560,22 @@ impl DatabaseStats {
 mod tests {
     use super::*;
 
+    #[test]
+    fn lab_result_range_status_handles_open_ranges() {
+        let mut result = LabResult::new("IGF-1", 310.0, "ng/mL", now_timestamp());
+        assert_eq!(result.range_status(), RangeStatus::Unknown);
+
+        result.reference_low = Some(100.0);
+        result.reference_high = Some(300.0);
+        assert_eq!(result.range_status(), RangeStatus::High);
+
+        result.reference_high = None;
+        assert_eq!(result.range_status(), RangeStatus::Normal);
+
+        result.value = 80.0;
+        assert_eq!(result.range_status(), RangeStatus::Low);
+    }
+
     // =============================================================================
     // Constructor Tests
     // =============================================================================
@@ -524,6 +1615,48 @@ mod tests {
         assert!(entry.relevance_score.is_none());
     }
 
+    #[test]
+    fn normalize_doi_strips_prefixes() {
+        assert_eq!(normalize_doi("https://doi.org/10.1000/ABC.1"), "10.1000/abc.1");
+        assert_eq!(normalize_doi(" DOI:10.1000/xyz "), "10.1000/xyz");
+        assert_eq!(normalize_doi("10.1000/xyz"), "10.1000/xyz");
+    }
+
+    #[test]
+    fn literature_entry_derives_doi_and_year() {
+        let mut entry = LiteratureEntry::new("openalex", "Paper");
+        entry.url = Some("https://doi.org/10.1000/XYZ".to_string());
+        entry.published_date = Some("2021 Mar 4".to_string());
+        assert!(entry.is_missing_metadata());
+        assert!(entry.derive_metadata());
+        assert_eq!(entry.doi.as_deref(), Some("10.1000/xyz"));
+        assert_eq!(entry.year, Some(2021));
+        assert!(!entry.derive_metadata());
+
+        let mut other = LiteratureEntry::new("pubmed", "Paper");
+        other.url = Some("https://pubmed.ncbi.nlm.nih.gov/123/".to_string());
+        assert!(!other.derive_metadata());
+        assert!(other.doi.is_none());
+    }
+
+    #[test]
+    fn summary_cites_referenced_or_mentioned_papers() {
+        let mut entry = LiteratureEntry::new("pubmed", "BPC-157 accelerates tendon healing in rats");
+        entry.doi = Some("10.1000/xyz".to_string());
+        let mut summary = SummaryHistory::new("Notes", "Full text", "Summary", "markdown", "ollama");
+        assert!(!summary.cites(&entry));
+
+        summary.references = vec![entry.id.clone()];
+        assert!(summary.cites(&entry));
+
+        summary.references.clear();
+        summary.original_content = "From doi:10.1000/XYZ".to_string();
+        assert!(summary.cites(&entry));
+
+        summary.original_content = "Per BPC-157 Accelerates Tendon Healing in Rats, ...".to_string();
+        assert!(summary.cites(&entry));
+    }
+
     #[test]
     fn supplier_new_creates_valid_supplier() {
         let supplier = Supplier::new("PeptideSource");
@@ -556,6 +1689,25 @@ mod tests {
         assert!(!price.id.is_empty());
     }
 
+    #[test]
+    fn order_new_creates_pending_order_with_total() {
+        let mut order = Order::new(
+            "supplier-123",
+            vec![OrderItem {
+                protocol_id: "protocol-123".to_string(),
+                quantity: 3,
+                vial_size_mg: Some(5.0),
+                unit_price: 40.0,
+            }],
+        );
+        order.shipping_cost = Some(12.5);
+
+        assert_eq!(order.status, OrderStatus::Pending);
+        assert_eq!(order.currency, "USD");
+        assert!(order.inventory_item_ids.is_empty());
+        assert_eq!(order.total(), 132.5);
+    }
+
     #[test]
     fn alert_new_creates_valid_alert() {
         let alert = Alert::new(
@@ -588,6 +1740,19 @@ mod tests {
         assert_eq!(summary.provider, "claude");
     }
 
+    #[test]
+    fn summary_history_source_defaults_to_content_hash() {
+        let first = SummaryHistory::new("A", "Same text", "One", "markdown", "claude");
+        let second = SummaryHistory::new("B", " Same text\n", "Two", "markdown", "codex");
+
+        assert!(first.source_id.starts_with("content:"));
+        assert_eq!(first.source_id, second.source_id);
+
+        let mut legacy = first.clone();
+        legacy.source_id.clear();
+        assert_eq!(legacy.with_content_source().source_id, first.source_id);
+    }
+
     // =============================================================================
     // Serialization Tests
     // =============================================================================
@@ -652,6 +1817,18 @@ mod tests {
         assert_eq!(deserialized.website, supplier.website);
     }
 
+    #[test]
+    fn supplier_without_product_urls_deserializes() {
+        let mut value = serde_json::to_value(Supplier::new("Legacy")).expect("serialize");
+        let object = value.as_object_mut().unwrap();
+        object.remove("product_urls");
+        object.remove("scraping_profile");
+
+        let deserialized: Supplier = serde_json::from_value(value).expect("deserialize");
+        assert!(deserialized.product_urls.is_empty());
+        assert!(deserialized.scraping_profile.is_none());
+    }
+
     #[test]
     fn inventory_item_serialization_roundtrip() {
         let mut item = InventoryItem::new("protocol-123");
@@ -708,6 +1885,7 @@ mod tests {
         assert_eq!(serde_json::to_string(&AlertType::PriceIncrease).unwrap(), r#""price_increase""#);
         assert_eq!(serde_json::to_string(&AlertType::PriceDecrease).unwrap(), r#""price_decrease""#);
         assert_eq!(serde_json::to_string(&AlertType::OutOfStock).unwrap(), r#""out_of_stock""#);
+        assert_eq!(serde_json::to_string(&AlertType::Interaction).unwrap(), r#""interaction""#);
     }
 
     #[test]
@@ -790,6 +1968,39 @@ mod tests {
         assert_eq!(deserialized.message.len(), 10000);
     }
 
+    #[test]
+    fn landed_cost_covers_vials_minimums_discounts_and_shipping() {
+        let mut price = PriceHistory::new("supplier", "BPC-157", 2.0);
+        assert_eq!(price.landed_cost(Some(12.0)).cost_per_mg, 2.0);
+
+        price.shipping_cost = Some(20.0);
+        assert_eq!(price.landed_cost(Some(10.0)).cost_per_mg, 4.0);
+        assert_eq!(price.landed_cost(None).cost_per_mg, 2.0);
+
+        price.vial_size_mg = Some(5.0);
+        price.min_order_vials = Some(2);
+        price.bulk_discounts = vec![
+            BulkDiscountTier { min_vials: 4, discount_percent: 10.0 },
+            BulkDiscountTier { min_vials: 10, discount_percent: 25.0 },
+        ];
+
+        // The minimum order of 2 vials, no discount: 20 + 20 shipping over 10 mg
+        let minimum = price.landed_cost(None);
+        assert_eq!(minimum.vials, Some(2));
+        assert_eq!(minimum.total_cost, 40.0);
+        assert_eq!(minimum.cost_per_mg, 4.0);
+
+        // 18 mg rounds up to 4 vials and the 10% tier: 36 + 20 over 20 mg
+        let bulk = price.landed_cost(Some(18.0));
+        assert_eq!(bulk.vials, Some(4));
+        assert_eq!(bulk.discount_percent, 10.0);
+        assert_eq!(bulk.cost_per_mg, 2.8);
+
+        assert!(price.validate().is_ok());
+        price.bulk_discounts.push(BulkDiscountTier { min_vials: 1, discount_percent: 100.0 });
+        assert!(price.validate().is_err());
+    }
+
     #[test]
     fn price_history_handles_extreme_costs() {
         let cheap = PriceHistory::new("supplier", "peptide", 0.01);
@@ -0,0 +1,380 @@
+//! Notification channels
+//!
+//! Besides OS notifications, alerts and backup results can be pushed to
+//! user-configured webhooks: a generic JSON POST, a Discord webhook, or an
+//! ntfy topic. This module holds the channel settings, decides which events
+//! a channel wants, and builds the HTTP request for each kind; sending is
+//! left to the caller.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::models::{Alert, AlertSeverity, AlertType};
+
+/// Discord rejects embeds with longer titles or descriptions
+const DISCORD_TITLE_LIMIT: usize = 256;
+const DISCORD_DESCRIPTION_LIMIT: usize = 4096;
+
+/// How a channel delivers notifications
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelKind {
+    /// JSON POST of the event to any URL
+    Webhook,
+    /// Discord channel webhook
+    Discord,
+    /// ntfy topic URL, e.g. `https://ntfy.sh/my-topic`
+    Ntfy,
+}
+
+/// What happened
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEventKind {
+    Alert,
+    /// Low stock or out-of-stock alert
+    LowStock,
+    BackupSucceeded,
+    BackupFailed,
+}
+
+/// Something worth notifying about
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationEvent {
+    pub kind: NotificationEventKind,
+    pub severity: AlertSeverity,
+    pub title: String,
+    pub message: String,
+    /// Set for alert events
+    pub alert_id: Option<String>,
+    pub alert_type: Option<AlertType>,
+    pub created_at: OffsetDateTime,
+}
+
+impl NotificationEvent {
+    /// The event for a newly created alert
+    pub fn from_alert(alert: &Alert) -> Self {
+        let kind = match alert.alert_type {
+            AlertType::LowStock | AlertType::OutOfStock => NotificationEventKind::LowStock,
+            AlertType::BackupOverdue => NotificationEventKind::BackupFailed,
+            _ => NotificationEventKind::Alert,
+        };
+        Self {
+            kind,
+            severity: alert.severity.clone(),
+            title: alert.title.clone(),
+            message: alert.message.clone(),
+            alert_id: Some(alert.id.clone()),
+            alert_type: Some(alert.alert_type.clone()),
+            created_at: alert.created_at,
+        }
+    }
+
+    pub fn backup_succeeded<S: Into<String>>(message: S) -> Self {
+        Self::backup(NotificationEventKind::BackupSucceeded, AlertSeverity::Info, "Backup complete", message)
+    }
+
+    pub fn backup_failed<S: Into<String>>(message: S) -> Self {
+        Self::backup(NotificationEventKind::BackupFailed, AlertSeverity::Critical, "Backup failed", message)
+    }
+
+    fn backup<S: Into<String>>(
+        kind: NotificationEventKind,
+        severity: AlertSeverity,
+        title: &str,
+        message: S,
+    ) -> Self {
+        Self {
+            kind,
+            severity,
+            title: title.to_string(),
+            message: message.into(),
+            alert_id: None,
+            alert_type: None,
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+}
+
+/// A configured webhook and the events it receives
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationChannel {
+    pub id: String,
+    pub name: String,
+    pub kind: ChannelKind,
+    pub url: String,
+    pub enabled: bool,
+    /// Alert and low-stock events below this severity are not sent
+    pub min_severity: AlertSeverity,
+    pub alerts: bool,
+    pub low_stock: bool,
+    pub backup_success: bool,
+    pub backup_failure: bool,
+}
+
+impl Default for NotificationChannel {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: String::new(),
+            kind: ChannelKind::Webhook,
+            url: String::new(),
+            enabled: true,
+            min_severity: AlertSeverity::Warning,
+            alerts: true,
+            low_stock: true,
+            backup_success: false,
+            backup_failure: true,
+        }
+    }
+}
+
+/// An HTTP POST to deliver one event
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookRequest {
+    pub url: String,
+    pub body: serde_json::Value,
+}
+
+impl NotificationChannel {
+    pub fn new<S: Into<String>>(name: S, kind: ChannelKind, url: S) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            url: url.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Check the channel can be saved
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("Channel name is required");
+        }
+        let url = self.url.trim();
+        let Some(rest) = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://")) else {
+            bail!("Webhook URL must start with https:// or http://");
+        };
+        if rest.split('/').next().unwrap_or_default().is_empty() {
+            bail!("Webhook URL has no host");
+        }
+        if self.kind == ChannelKind::Ntfy && ntfy_topic(url).is_none() {
+            bail!("ntfy URL must include the topic, e.g. https://ntfy.sh/my-topic");
+        }
+        Ok(())
+    }
+
+    /// Whether this channel wants `event`
+    ///
+    /// Backup events have their own switches and ignore the severity
+    /// threshold, so a channel can get backup failures without every
+    /// critical alert.
+    pub fn accepts(&self, event: &NotificationEvent) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        match event.kind {
+            NotificationEventKind::Alert => self.alerts && event.severity >= self.min_severity,
+            NotificationEventKind::LowStock => self.low_stock && event.severity >= self.min_severity,
+            NotificationEventKind::BackupSucceeded => self.backup_success,
+            NotificationEventKind::BackupFailed => self.backup_failure,
+        }
+    }
+
+    /// The request that delivers `event` on this channel
+    pub fn request(&self, event: &NotificationEvent) -> WebhookRequest {
+        let url = self.url.trim();
+        match self.kind {
+            ChannelKind::Webhook => WebhookRequest {
+                url: url.to_string(),
+                body: json!({
+                    "source": "peptrack",
+                    "event": event.kind,
+                    "severity": event.severity,
+                    "title": event.title,
+                    "message": event.message,
+                    "alertId": event.alert_id,
+                    "alertType": event.alert_type,
+                    "timestamp": timestamp(event.created_at),
+                }),
+            },
+            ChannelKind::Discord => WebhookRequest {
+                url: url.to_string(),
+                body: json!({
+                    "username": "PepTrack",
+                    "embeds": [{
+                        "title": truncate(&event.title, DISCORD_TITLE_LIMIT),
+                        "description": truncate(&event.message, DISCORD_DESCRIPTION_LIMIT),
+                        "color": discord_color(&event.severity),
+                        "timestamp": timestamp(event.created_at),
+                    }],
+                }),
+            },
+            ChannelKind::Ntfy => {
+                // Publish as JSON to the server root so titles needn't fit in
+                // HTTP headers
+                let (server, topic) = ntfy_topic(url).unwrap_or((url, ""));
+                WebhookRequest {
+                    url: server.to_string(),
+                    body: json!({
+                        "topic": topic,
+                        "title": event.title,
+                        "message": event.message,
+                        "priority": ntfy_priority(&event.severity),
+                        "tags": [ntfy_tag(event)],
+                    }),
+                }
+            }
+        }
+    }
+}
+
+/// Split an ntfy topic URL into the server URL and the topic
+fn ntfy_topic(url: &str) -> Option<(&str, &str)> {
+    let url = url.trim_end_matches('/');
+    let (server, topic) = url.rsplit_once('/')?;
+    if topic.is_empty() || server.ends_with('/') {
+        // No path after the host, e.g. "https://ntfy.sh"
+        return None;
+    }
+    Some((server, topic))
+}
+
+fn timestamp(value: OffsetDateTime) -> String {
+    value.format(&Rfc3339).unwrap_or_else(|_| value.to_string())
+}
+
+fn truncate(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(limit - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn discord_color(severity: &AlertSeverity) -> u32 {
+    match severity {
+        AlertSeverity::Info => 0x3B82F6,
+        AlertSeverity::Warning => 0xF59E0B,
+        AlertSeverity::Critical => 0xEF4444,
+    }
+}
+
+fn ntfy_priority(severity: &AlertSeverity) -> u8 {
+    match severity {
+        AlertSeverity::Info => 3,
+        AlertSeverity::Warning => 4,
+        AlertSeverity::Critical => 5,
+    }
+}
+
+/// ntfy shows tags that name an emoji as that emoji
+fn ntfy_tag(event: &NotificationEvent) -> &'static str {
+    match event.kind {
+        NotificationEventKind::BackupSucceeded => "white_check_mark",
+        NotificationEventKind::BackupFailed => "x",
+        NotificationEventKind::LowStock => "package",
+        NotificationEventKind::Alert => match event.severity {
+            AlertSeverity::Critical => "rotating_light",
+            _ => "warning",
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(alert_type: AlertType, severity: AlertSeverity) -> NotificationEvent {
+        NotificationEvent::from_alert(&Alert::new(alert_type, severity, "Title", "Message"))
+    }
+
+    #[test]
+    fn accepts_filters_by_event_and_severity() {
+        let channel = NotificationChannel::new("Phone", ChannelKind::Ntfy, "https://ntfy.sh/peptrack");
+
+        assert!(!channel.accepts(&alert(AlertType::Expired, AlertSeverity::Info)));
+        assert!(channel.accepts(&alert(AlertType::Expired, AlertSeverity::Warning)));
+        assert!(channel.accepts(&alert(AlertType::OutOfStock, AlertSeverity::Critical)));
+        assert!(channel.accepts(&NotificationEvent::backup_failed("disk full")));
+        assert!(!channel.accepts(&NotificationEvent::backup_succeeded("done")));
+
+        let stock_only = NotificationChannel {
+            alerts: false,
+            min_severity: AlertSeverity::Info,
+            ..channel.clone()
+        };
+        assert!(stock_only.accepts(&alert(AlertType::LowStock, AlertSeverity::Info)));
+        assert!(!stock_only.accepts(&alert(AlertType::Retraction, AlertSeverity::Critical)));
+        // Overdue backups go to whoever hears about failed backups
+        assert!(stock_only.accepts(&alert(AlertType::BackupOverdue, AlertSeverity::Warning)));
+
+        let disabled = NotificationChannel { enabled: false, ..channel };
+        assert!(!disabled.accepts(&NotificationEvent::backup_failed("disk full")));
+    }
+
+    #[test]
+    fn validate_requires_http_url_and_ntfy_topic() {
+        assert!(NotificationChannel::new("Hook", ChannelKind::Webhook, "https://example.com/hook")
+            .validate()
+            .is_ok());
+        assert!(NotificationChannel::new("Hook", ChannelKind::Webhook, "ftp://example.com")
+            .validate()
+            .is_err());
+        assert!(NotificationChannel::new("Hook", ChannelKind::Webhook, "https://")
+            .validate()
+            .is_err());
+        assert!(NotificationChannel::new("", ChannelKind::Webhook, "https://example.com")
+            .validate()
+            .is_err());
+        assert!(NotificationChannel::new("Phone", ChannelKind::Ntfy, "https://ntfy.sh")
+            .validate()
+            .is_err());
+        assert!(NotificationChannel::new("Phone", ChannelKind::Ntfy, "https://ntfy.sh/")
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn ntfy_request_posts_json_to_server_root() {
+        let channel = NotificationChannel::new("Phone", ChannelKind::Ntfy, "https://ntfy.example.com/peptrack/");
+        let request = channel.request(&NotificationEvent::backup_failed("disk full"));
+
+        assert_eq!(request.url, "https://ntfy.example.com");
+        assert_eq!(request.body["topic"], "peptrack");
+        assert_eq!(request.body["message"], "disk full");
+        assert_eq!(request.body["priority"], 5);
+    }
+
+    #[test]
+    fn discord_request_truncates_embed() {
+        let channel = NotificationChannel::new("Discord", ChannelKind::Discord, "https://discord.com/api/webhooks/1/x");
+        let mut event = alert(AlertType::LowStock, AlertSeverity::Warning);
+        event.title = "x".repeat(300);
+
+        let request = channel.request(&event);
+        let embed = &request.body["embeds"][0];
+        assert_eq!(embed["title"].as_str().unwrap().chars().count(), DISCORD_TITLE_LIMIT);
+        assert_eq!(embed["color"], 0xF59E0B);
+    }
+
+    #[test]
+    fn webhook_request_includes_alert_details() {
+        let channel = NotificationChannel::new("Hook", ChannelKind::Webhook, "https://example.com/hook");
+        let event = alert(AlertType::LowStock, AlertSeverity::Critical);
+
+        let request = channel.request(&event);
+        assert_eq!(request.url, "https://example.com/hook");
+        assert_eq!(request.body["event"], "low_stock");
+        assert_eq!(request.body["severity"], "critical");
+        assert_eq!(request.body["alertType"], "low_stock");
+        assert_eq!(request.body["alertId"], event.alert_id.unwrap());
+    }
+}
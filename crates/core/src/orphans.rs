@@ -0,0 +1,119 @@
+//! Records left pointing at deleted ones
+//!
+//! Foreign keys keep most links tidy: deleting a supplier deletes its price
+//! history and clears the supplier column on its inventory. Some links are
+//! out of their reach, though: ids kept inside encrypted payloads, such as an
+//! inventory item's supplier or the record an alert is about, and the dose
+//! schedules table the app creates outside [`StorageManager`]. Databases
+//! written before foreign keys were enforced can also hold rows whose parent
+//! is long gone.
+//!
+//! [`StorageManager::find_orphaned_records`] lists these, and
+//! [`StorageManager::repair_orphaned_records`] cleans them up as the
+//! [`CascadePolicies`] say. The same policies decide what happens to
+//! inventory when its supplier is deleted.
+//!
+//! [`StorageManager`]: crate::StorageManager
+//! [`StorageManager::find_orphaned_records`]: crate::StorageManager::find_orphaned_records
+//! [`StorageManager::repair_orphaned_records`]: crate::StorageManager::repair_orphaned_records
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::AlertType;
+use crate::settings::Setting;
+
+/// What happens to inventory bought from a supplier that is deleted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupplierInventoryPolicy {
+    /// Keep the vials with no supplier
+    #[default]
+    Unlink,
+    /// Delete the vials with their supplier
+    Delete,
+}
+
+/// What happens to active alerts about a record that was deleted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StaleAlertPolicy {
+    /// Dismiss them, keeping them in the alert history
+    #[default]
+    Dismiss,
+    Delete,
+}
+
+/// How deletes cascade to related records and how orphans are cleaned up
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CascadePolicies {
+    pub supplier_inventory: SupplierInventoryPolicy,
+    pub stale_alerts: StaleAlertPolicy,
+}
+
+impl Setting for CascadePolicies {
+    const KEY: &'static str = "data.cascade_policies";
+}
+
+/// How a record is orphaned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanKind {
+    /// An inventory item still names a supplier that was deleted
+    InventorySupplier,
+    /// A price history entry for a supplier that no longer exists
+    PriceHistorySupplier,
+    /// A dose schedule for a protocol that no longer exists
+    ScheduleProtocol,
+    /// A skipped dose for a schedule that no longer exists
+    SkipSchedule,
+    /// An active alert about a record that no longer exists
+    AlertSubject,
+}
+
+/// A record pointing at one that was deleted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrphanedRecord {
+    pub kind: OrphanKind,
+    pub record_id: String,
+    /// Id of the deleted record it points at
+    pub missing_id: String,
+}
+
+/// What [`StorageManager::repair_orphaned_records`](crate::StorageManager::repair_orphaned_records) changed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrphanRepair {
+    pub inventory_unlinked: usize,
+    pub inventory_deleted: usize,
+    pub price_history_deleted: usize,
+    pub schedules_deleted: usize,
+    pub skips_deleted: usize,
+    pub alerts_dismissed: usize,
+    pub alerts_deleted: usize,
+}
+
+impl OrphanRepair {
+    /// Records changed or deleted
+    pub fn total(&self) -> usize {
+        self.inventory_unlinked
+            + self.inventory_deleted
+            + self.price_history_deleted
+            + self.schedules_deleted
+            + self.skips_deleted
+            + self.alerts_dismissed
+            + self.alerts_deleted
+    }
+}
+
+/// Tables an alert's `related_id` may point into; empty when it isn't a
+/// record id, e.g. an interaction key or a backup destination
+pub(crate) fn alert_subject_tables(alert_type: &AlertType) -> &'static [&'static str] {
+    match alert_type {
+        AlertType::ExpiringSoon | AlertType::Expired => &["inventory"],
+        // Raised per vial, or per protocol by the supply forecast
+        AlertType::LowStock => &["inventory", "protocols"],
+        AlertType::PriceIncrease | AlertType::PriceDecrease | AlertType::OutOfStock => &["suppliers"],
+        AlertType::Retraction | AlertType::Erratum => &["literature_cache"],
+        AlertType::Interaction | AlertType::NewLiterature | AlertType::BackupOverdue => &[],
+    }
+}
@@ -0,0 +1,503 @@
+//! Passphrase-protected database keys
+//!
+//! With a passphrase set, the database key is never written to disk. It is
+//! derived with Argon2id from the passphrase plus the salt and cost
+//! parameters saved in `passphrase.json`, and only held in memory while the
+//! app is unlocked.
+//!
+//! Changing the passphrase re-encrypts every record. The new settings are
+//! first written to `passphrase.json.pending` and only replace the current
+//! file after the re-encryption commits, so a crash part-way through leaves
+//! either the old or the new passphrase working.
+
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use anyhow::{anyhow, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+
+use crate::db::StorageManager;
+use crate::encryption::{EnvelopeEncryption, KeyMaterial, KeyProvider};
+use crate::key_rotation::{rotate_storage_key, KeyRotation, KeyRotationProgress};
+
+pub const PASSPHRASE_CONFIG_FILE: &str = "passphrase.json";
+const PASSPHRASE_CONFIG_VERSION: u32 = 1;
+const SALT_SIZE: usize = 16;
+const MIN_PASSPHRASE_CHARS: usize = 8;
+/// Sealed with the derived key so a wrong passphrase is rejected up front
+const VERIFIER_PLAINTEXT: &[u8] = b"peptrack-passphrase-check-v1";
+
+/// Argon2id cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// 64 MiB, 3 passes: roughly half a second on a laptop
+    fn default() -> Self {
+        Self {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+/// Everything needed to re-derive the key from the passphrase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PassphraseConfig {
+    pub version: u32,
+    /// Base64-encoded salt
+    pub salt: String,
+    pub kdf: KdfParams,
+    /// Base64-encoded [`VERIFIER_PLAINTEXT`] sealed with the derived key
+    pub verifier: String,
+}
+
+impl PassphraseConfig {
+    /// New settings with a random salt, and the key they derive
+    pub fn create(passphrase: &str, kdf: KdfParams) -> Result<(Self, KeyMaterial)> {
+        let mut salt = vec![0u8; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+
+        let key = derive_key(passphrase, &salt, kdf)?;
+        let verifier = EnvelopeEncryption::with_key(&key).seal(VERIFIER_PLAINTEXT)?;
+
+        let config = Self {
+            version: PASSPHRASE_CONFIG_VERSION,
+            salt: BASE64.encode(&salt),
+            kdf,
+            verifier: BASE64.encode(verifier),
+        };
+        Ok((config, key))
+    }
+
+    /// Derive the key for `passphrase`, failing if it's the wrong passphrase
+    pub fn unlock(&self, passphrase: &str) -> Result<KeyMaterial> {
+        let salt = BASE64.decode(&self.salt).context("Invalid salt in passphrase settings")?;
+        let verifier = BASE64
+            .decode(&self.verifier)
+            .context("Invalid verifier in passphrase settings")?;
+
+        let key = derive_key(passphrase, &salt, self.kdf)?;
+        match EnvelopeEncryption::with_key(&key).open(&verifier) {
+            Ok(plaintext) if plaintext == VERIFIER_PLAINTEXT => Ok(key),
+            _ => Err(anyhow!("Incorrect passphrase")),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&json).context("Failed to parse passphrase settings")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+pub fn validate_passphrase(passphrase: &str) -> Result<()> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(anyhow!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_CHARS
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn derive_key(passphrase: &str, salt: &[u8], kdf: KdfParams) -> Result<KeyMaterial> {
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| anyhow!("Invalid key derivation parameters: {}", e))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+    let mut key = Zeroizing::new(vec![0u8; 32]);
+    argon2
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive key from passphrase: {}", e))?;
+    KeyMaterial::new(key.to_vec())
+}
+
+/// Which settings file a passphrase matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigSource {
+    Current,
+    Pending,
+}
+
+/// Key provider whose key is derived from a passphrase and can be locked
+///
+/// Databases without a passphrase use it too, unlocked with their stored
+/// key via [`unlock_with_key`](Self::unlock_with_key), so a passphrase can
+/// be added while the app is running.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use std::sync::Arc;
+/// use peptrack_core::{unlock_storage, PassphraseKeyProvider, StorageConfig, StorageManager};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let provider = Arc::new(PassphraseKeyProvider::new(Path::new("/path/to/data")));
+/// let storage = StorageManager::new(StorageConfig {
+///     data_dir: None,
+///     db_file_name: None,
+///     key_provider: provider.clone(),
+/// })?;
+/// unlock_storage(&storage, &provider, "correct horse battery staple")?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct PassphraseKeyProvider {
+    config_path: PathBuf,
+    key: RwLock<Option<KeyMaterial>>,
+}
+
+impl PassphraseKeyProvider {
+    /// Creates a locked provider using the settings in `data_dir`
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            config_path: data_dir.join(PASSPHRASE_CONFIG_FILE),
+            key: RwLock::new(None),
+        }
+    }
+
+    fn pending_path(&self) -> PathBuf {
+        self.config_path.with_extension("json.pending")
+    }
+
+    /// Whether the database is protected by a passphrase
+    pub fn is_configured(&self) -> bool {
+        self.config_path.exists() || self.pending_path().exists()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.key.read().map(|key| key.is_none()).unwrap_or(true)
+    }
+
+    /// Use a stored key, for databases that have no passphrase
+    pub fn unlock_with_key(&self, key: KeyMaterial) {
+        if let Ok(mut slot) = self.key.write() {
+            *slot = Some(key);
+        }
+    }
+
+    /// Forget the key; storage calls fail until unlocked again
+    pub fn lock(&self) -> Result<()> {
+        if !self.is_configured() {
+            return Err(anyhow!("No passphrase is set"));
+        }
+        self.forget_key();
+        Ok(())
+    }
+
+    /// Forget the key without checking that a passphrase can unlock it
+    /// again, for keys released some other way such as biometric unlock
+    pub fn forget_key(&self) {
+        if let Ok(mut slot) = self.key.write() {
+            *slot = None;
+        }
+        info!("Database locked");
+    }
+
+    /// Check `passphrase` against the key currently in use
+    pub fn verify_passphrase(&self, passphrase: &str) -> Result<()> {
+        if !self.is_configured() {
+            return Err(anyhow!("No passphrase is set"));
+        }
+        let current = self.key_material()?.to_key_bytes()?;
+        let mut matched = subtle::Choice::from(0);
+        for (key, _) in self.derive(passphrase)? {
+            matched |= key.to_key_bytes()?.ct_eq(&current);
+        }
+        if !bool::from(matched) {
+            return Err(anyhow!("Incorrect passphrase"));
+        }
+        Ok(())
+    }
+
+    /// Delete the passphrase settings after the database has been moved to
+    /// a stored key
+    pub fn remove_passphrase(&self) -> Result<()> {
+        for path in [self.config_path.clone(), self.pending_path()] {
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+        }
+        info!("Database passphrase removed");
+        Ok(())
+    }
+
+    /// Derive keys from `passphrase` with the current settings and with
+    /// those of an interrupted passphrase change
+    ///
+    /// Both match when the passphrase was changed to itself, so callers
+    /// check each key against the database.
+    fn derive(&self, passphrase: &str) -> Result<Vec<(KeyMaterial, ConfigSource)>> {
+        let candidates = [
+            (self.config_path.clone(), ConfigSource::Current),
+            (self.pending_path(), ConfigSource::Pending),
+        ];
+
+        let mut keys = Vec::new();
+        let mut last_error = anyhow!("No passphrase is set");
+        for (path, source) in candidates {
+            if !path.exists() {
+                continue;
+            }
+            match PassphraseConfig::load(&path).and_then(|config| config.unlock(passphrase)) {
+                Ok(key) => keys.push((key, source)),
+                Err(e) => last_error = e,
+            }
+        }
+        if keys.is_empty() {
+            return Err(last_error);
+        }
+        Ok(keys)
+    }
+}
+
+/// Returned by storage access while no key has been unlocked
+#[derive(Debug, thiserror::Error)]
+#[error("Database is locked")]
+pub struct DatabaseLocked;
+
+impl KeyProvider for PassphraseKeyProvider {
+    fn key_material(&self) -> Result<KeyMaterial> {
+        self.key
+            .read()
+            .map_err(|_| anyhow!("Key lock poisoned"))?
+            .clone()
+            .ok_or_else(|| DatabaseLocked.into())
+    }
+}
+
+/// Unlock `storage` with a passphrase
+///
+/// `provider` must be the key provider `storage` was created with. Finishes
+/// a passphrase change that was interrupted after re-encrypting.
+pub fn unlock_storage(
+    storage: &StorageManager,
+    provider: &PassphraseKeyProvider,
+    passphrase: &str,
+) -> Result<()> {
+    // A settings file matched, but make sure the database agrees
+    let mut opened = None;
+    for (key, source) in provider.derive(passphrase)? {
+        provider.unlock_with_key(key);
+        match storage.verify_key() {
+            Ok(true) => {
+                opened = Some(source);
+                break;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                provider.key.write().map_err(|_| anyhow!("Key lock poisoned"))?.take();
+                return Err(e);
+            }
+        }
+    }
+    let Some(source) = opened else {
+        provider.key.write().map_err(|_| anyhow!("Key lock poisoned"))?.take();
+        return Err(anyhow!("Incorrect passphrase"));
+    };
+
+    let pending = provider.pending_path();
+    match source {
+        ConfigSource::Pending => {
+            std::fs::rename(&pending, &provider.config_path)
+                .context("Failed to finish interrupted passphrase change")?;
+            info!("Finished interrupted passphrase change");
+        }
+        ConfigSource::Current if pending.exists() => {
+            if let Err(e) = std::fs::remove_file(&pending) {
+                warn!("Failed to remove abandoned passphrase settings: {}", e);
+            }
+        }
+        ConfigSource::Current => {}
+    }
+
+    storage.initialize()?;
+    info!("Database unlocked");
+    Ok(())
+}
+
+/// Set or change the database passphrase, re-encrypting every record
+///
+/// `current` is required when a passphrase is already set. `provider` must
+/// be the key provider `storage` was created with, and be unlocked.
+/// `progress` is called as values are re-encrypted. Any recovery code is
+/// replaced, as with [`rotate_storage_key`].
+pub fn change_passphrase(
+    storage: &StorageManager,
+    provider: &PassphraseKeyProvider,
+    current: Option<&str>,
+    new_passphrase: &str,
+    kdf: KdfParams,
+    progress: impl FnMut(KeyRotationProgress),
+) -> Result<KeyRotation> {
+    validate_passphrase(new_passphrase)?;
+    provider.key_material()?;
+
+    if provider.is_configured() {
+        provider
+            .verify_passphrase(current.ok_or_else(|| anyhow!("Enter the current passphrase"))?)?;
+    }
+
+    let (config, new_key) = PassphraseConfig::create(new_passphrase, kdf)?;
+    let pending = provider.pending_path();
+    config.save(&pending)?;
+
+    let rotation = match rotate_storage_key(storage, provider, new_key, progress) {
+        Ok(rotation) => rotation,
+        Err(e) => {
+            let _ = std::fs::remove_file(&pending);
+            return Err(e);
+        }
+    };
+
+    std::fs::rename(&pending, &provider.config_path).context("Failed to save passphrase settings")?;
+    info!("Database passphrase changed");
+    Ok(rotation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::StorageConfig;
+    use crate::models::PeptideProtocol;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    /// Cheap parameters so tests run quickly
+    const TEST_KDF: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    fn storage_with(provider: Arc<PassphraseKeyProvider>, dir: &Path) -> StorageManager {
+        StorageManager::new(StorageConfig {
+            data_dir: Some(dir.to_path_buf()),
+            db_file_name: Some("test.sqlite".into()),
+            key_provider: provider,
+        })
+        .expect("storage")
+    }
+
+    #[test]
+    fn config_rejects_wrong_passphrase() {
+        let (config, key) = PassphraseConfig::create("first passphrase", TEST_KDF).unwrap();
+
+        let unlocked = config.unlock("first passphrase").unwrap();
+        assert_eq!(unlocked.to_key_bytes().unwrap(), key.to_key_bytes().unwrap());
+        assert!(config.unlock("second passphrase").is_err());
+    }
+
+    #[test]
+    fn validate_passphrase_requires_minimum_length() {
+        assert!(validate_passphrase("short").is_err());
+        assert!(validate_passphrase("long enough").is_ok());
+    }
+
+    #[test]
+    fn setting_a_passphrase_reencrypts_and_locks() {
+        let dir = tempdir().unwrap();
+        let provider = Arc::new(PassphraseKeyProvider::new(dir.path()));
+        provider.unlock_with_key(KeyMaterial::new(vec![9u8; 32]).unwrap());
+        assert!(provider.lock().is_err(), "no passphrase yet");
+
+        let storage = storage_with(provider.clone(), dir.path());
+        storage.initialize().unwrap();
+        let protocol = PeptideProtocol::new("Evening", "Ipamorelin");
+        storage.upsert_protocol(&protocol).unwrap();
+
+        change_passphrase(&storage, &provider, None, "my new passphrase", TEST_KDF, |_| {}).unwrap();
+        assert!(provider.is_configured());
+        assert_eq!(storage.search("ipamorelin", None, 10).unwrap().len(), 1);
+
+        provider.lock().unwrap();
+        assert!(storage.list_protocols().is_err());
+
+        assert!(unlock_storage(&storage, &provider, "wrong passphrase").is_err());
+        assert!(provider.is_locked());
+
+        unlock_storage(&storage, &provider, "my new passphrase").unwrap();
+        assert_eq!(storage.list_protocols().unwrap()[0].name, "Evening");
+
+        // Changing it again needs the current passphrase
+        assert!(change_passphrase(&storage, &provider, None, "another passphrase", TEST_KDF, |_| {}).is_err());
+        change_passphrase(
+            &storage,
+            &provider,
+            Some("my new passphrase"),
+            "another passphrase",
+            TEST_KDF,
+            |_| {},
+        )
+        .unwrap();
+        provider.lock().unwrap();
+        unlock_storage(&storage, &provider, "another passphrase").unwrap();
+        assert_eq!(storage.list_protocols().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn unlock_finishes_an_interrupted_change() {
+        let dir = tempdir().unwrap();
+        let provider = Arc::new(PassphraseKeyProvider::new(dir.path()));
+        provider.unlock_with_key(KeyMaterial::new(vec![3u8; 32]).unwrap());
+        let storage = storage_with(provider.clone(), dir.path());
+        storage.initialize().unwrap();
+        change_passphrase(&storage, &provider, None, "old passphrase", TEST_KDF, |_| {}).unwrap();
+        storage.upsert_protocol(&PeptideProtocol::new("Morning", "BPC-157")).unwrap();
+
+        // Simulate a crash after re-encrypting but before the settings were swapped
+        let (config, new_key) = PassphraseConfig::create("new passphrase", TEST_KDF).unwrap();
+        config.save(&provider.pending_path()).unwrap();
+        storage.rotate_key(&EnvelopeEncryption::with_key(&new_key), None, |_| {}).unwrap();
+        provider.lock().unwrap();
+
+        assert!(unlock_storage(&storage, &provider, "old passphrase").is_err());
+        unlock_storage(&storage, &provider, "new passphrase").unwrap();
+        assert!(!provider.pending_path().exists());
+        assert_eq!(storage.list_protocols().unwrap()[0].name, "Morning");
+    }
+
+    #[test]
+    fn unlock_survives_a_crash_changing_to_the_same_passphrase() {
+        let dir = tempdir().unwrap();
+        let provider = Arc::new(PassphraseKeyProvider::new(dir.path()));
+        provider.unlock_with_key(KeyMaterial::new(vec![4u8; 32]).unwrap());
+        let storage = storage_with(provider.clone(), dir.path());
+        storage.initialize().unwrap();
+        change_passphrase(&storage, &provider, None, "same passphrase", TEST_KDF, |_| {}).unwrap();
+        storage.upsert_protocol(&PeptideProtocol::new("Morning", "BPC-157")).unwrap();
+
+        // Both settings files accept the passphrase, but only the pending key opens the database
+        let (config, new_key) = PassphraseConfig::create("same passphrase", TEST_KDF).unwrap();
+        config.save(&provider.pending_path()).unwrap();
+        storage.rotate_key(&EnvelopeEncryption::with_key(&new_key), None, |_| {}).unwrap();
+        provider.lock().unwrap();
+
+        unlock_storage(&storage, &provider, "same passphrase").unwrap();
+        assert!(!provider.pending_path().exists());
+        assert_eq!(storage.list_protocols().unwrap()[0].name, "Morning");
+        provider.verify_passphrase("same passphrase").unwrap();
+        assert!(provider.verify_passphrase("other passphrase").is_err());
+    }
+}
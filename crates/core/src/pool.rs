@@ -0,0 +1,297 @@
+//! Reuse of SQLite connections between storage calls
+//!
+//! Opening a connection re-runs every PRAGMA and starts with an empty
+//! prepared statement cache, so connections are handed back to the pool when
+//! a call finishes and reused by the next one.
+//!
+//! Reads share a pool of read-only connections. All writes go through one
+//! [`Writer`] connection, handed out to one caller at a time in the order
+//! they asked for it. Writers therefore never race each other for SQLite's
+//! write lock, which the busy timeout can't always resolve, e.g. when a read
+//! transaction needs upgrading while another connection writes. A thread
+//! that asks again while it holds the connection would wait for itself, so
+//! it gets an error instead.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::thread::{self, ThreadId};
+
+use anyhow::{anyhow, Result};
+use rusqlite::Connection;
+
+/// Idle connections kept open; extra connections are closed when returned
+const MAX_IDLE_CONNECTIONS: usize = 4;
+
+/// Prepared statements cached per connection
+pub(crate) const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+#[derive(Default)]
+pub(crate) struct ConnectionPool {
+    idle: Mutex<Vec<Connection>>,
+}
+
+impl ConnectionPool {
+    /// Takes an idle connection, if there is one
+    pub fn take(&self) -> Option<Connection> {
+        self.idle.lock().ok()?.pop()
+    }
+
+    /// Lends `conn` out; it returns to the pool when the guard is dropped
+    pub fn lend(&self, conn: Connection) -> PooledConnection<'_> {
+        PooledConnection {
+            conn: Some(conn),
+            pool: self,
+        }
+    }
+
+    fn give_back(&self, conn: Connection) {
+        // A connection still inside a transaction is closed so the next
+        // caller doesn't inherit it
+        if !conn.is_autocommit() {
+            return;
+        }
+        if let Ok(mut idle) = self.idle.lock() {
+            if idle.len() < MAX_IDLE_CONNECTIONS {
+                idle.push(conn);
+            }
+        }
+    }
+
+    /// Number of idle connections
+    #[cfg(test)]
+    pub fn idle_count(&self) -> usize {
+        self.idle.lock().map(|idle| idle.len()).unwrap_or(0)
+    }
+}
+
+/// A connection borrowed from a [`ConnectionPool`]
+pub(crate) struct PooledConnection<'a> {
+    conn: Option<Connection>,
+    pool: &'a ConnectionPool,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection is only taken on drop")
+    }
+}
+
+impl DerefMut for PooledConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("connection is only taken on drop")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.give_back(conn);
+        }
+    }
+}
+
+/// The one connection used for writes
+#[derive(Default)]
+pub(crate) struct Writer {
+    state: Mutex<WriterState>,
+    turn: Condvar,
+}
+
+#[derive(Default)]
+struct WriterState {
+    conn: Option<Connection>,
+    /// Ticket handed to the next caller
+    next_ticket: u64,
+    /// Ticket of the caller whose turn it is
+    serving: u64,
+    /// Thread holding the connection
+    holder: Option<ThreadId>,
+}
+
+impl Writer {
+    /// Wait for every earlier caller to finish, then borrow the connection,
+    /// opening it with `open` if there is none yet
+    ///
+    /// Fails straight away if this thread already holds the connection.
+    pub fn acquire(&self, open: impl FnOnce() -> Result<Connection>) -> Result<WriterConnection<'_>> {
+        let me = thread::current().id();
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.holder == Some(me) {
+            return Err(anyhow!("Nested database write; this thread already holds the write connection"));
+        }
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        while state.serving != ticket {
+            state = self.turn.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+        state.holder = Some(me);
+
+        let conn = match state.conn.take() {
+            Some(conn) => conn,
+            None => match open() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    drop(state);
+                    self.release(None);
+                    return Err(e);
+                }
+            },
+        };
+        Ok(WriterConnection {
+            conn: Some(conn),
+            writer: self,
+        })
+    }
+
+    /// Take the connection back and let the next caller in
+    fn release(&self, conn: Option<Connection>) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        // As with the pool, a connection left inside a transaction is closed,
+        // rolling it back
+        state.conn = conn.filter(Connection::is_autocommit);
+        state.holder = None;
+        state.serving += 1;
+        self.turn.notify_all();
+    }
+
+    /// Number of callers waiting for or holding the connection
+    #[cfg(test)]
+    pub fn queued(&self) -> u64 {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.next_ticket - state.serving
+    }
+}
+
+/// The write connection, borrowed from a [`Writer`]
+pub struct WriterConnection<'a> {
+    conn: Option<Connection>,
+    writer: &'a Writer,
+}
+
+impl Deref for WriterConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection is only taken on drop")
+    }
+}
+
+impl DerefMut for WriterConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("connection is only taken on drop")
+    }
+}
+
+impl Drop for WriterConnection<'_> {
+    fn drop(&mut self) {
+        self.writer.release(self.conn.take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connections_are_reused() {
+        let pool = ConnectionPool::default();
+        assert!(pool.take().is_none());
+
+        let conn = pool.lend(Connection::open_in_memory().unwrap());
+        conn.execute_batch("CREATE TABLE t (x INTEGER)").unwrap();
+        drop(conn);
+        assert_eq!(pool.idle_count(), 1);
+
+        // Same in-memory database, so the table is still there
+        let conn = pool.lend(pool.take().unwrap());
+        conn.execute("INSERT INTO t VALUES (1)", []).unwrap();
+        assert_eq!(pool.idle_count(), 0);
+    }
+
+    #[test]
+    fn test_connection_in_transaction_is_not_reused() {
+        let pool = ConnectionPool::default();
+        let conn = pool.lend(Connection::open_in_memory().unwrap());
+        conn.execute_batch("BEGIN").unwrap();
+        drop(conn);
+        assert_eq!(pool.idle_count(), 0);
+    }
+
+    #[test]
+    fn test_idle_connections_are_capped() {
+        let pool = ConnectionPool::default();
+        let guards: Vec<_> = (0..MAX_IDLE_CONNECTIONS + 2)
+            .map(|_| pool.lend(Connection::open_in_memory().unwrap()))
+            .collect();
+        drop(guards);
+        assert_eq!(pool.idle_count(), MAX_IDLE_CONNECTIONS);
+    }
+
+    #[test]
+    fn test_writer_serves_callers_in_order() {
+        use std::sync::Arc;
+
+        let writer = Arc::new(Writer::default());
+        let conn = writer.acquire(|| Ok(Connection::open_in_memory()?)).unwrap();
+        conn.execute_batch("CREATE TABLE t (x INTEGER)").unwrap();
+
+        let waiters: Vec<_> = (1..=3)
+            .map(|x| {
+                let shared = writer.clone();
+                // Each waiter takes its ticket before the next one starts
+                let handle = std::thread::spawn(move || {
+                    let conn = shared.acquire(|| unreachable!("connection is reused")).unwrap();
+                    conn.execute("INSERT INTO t VALUES (?1)", [x]).unwrap();
+                });
+                while writer.queued() <= x as u64 {
+                    std::thread::yield_now();
+                }
+                handle
+            })
+            .collect();
+        drop(conn);
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+
+        let conn = writer.acquire(|| unreachable!("connection is reused")).unwrap();
+        let order: Vec<i64> = conn
+            .prepare("SELECT x FROM t ORDER BY rowid")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(order, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_writer_rejects_nested_acquire() {
+        let writer = Writer::default();
+        let conn = writer.acquire(|| Ok(Connection::open_in_memory()?)).unwrap();
+        assert!(writer.acquire(|| unreachable!("never reaches its turn")).is_err());
+        assert_eq!(writer.queued(), 1);
+
+        drop(conn);
+        assert!(writer.acquire(|| unreachable!("connection is reused")).is_ok());
+    }
+
+    #[test]
+    fn test_writer_drops_connection_left_in_transaction() {
+        let writer = Writer::default();
+        let conn = writer.acquire(|| Ok(Connection::open_in_memory()?)).unwrap();
+        conn.execute_batch("BEGIN").unwrap();
+        drop(conn);
+
+        let mut opened = false;
+        let _conn = writer
+            .acquire(|| {
+                opened = true;
+                Ok(Connection::open_in_memory()?)
+            })
+            .unwrap();
+        assert!(opened);
+    }
+}
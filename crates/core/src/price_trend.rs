@@ -0,0 +1,79 @@
+//! Price trends computed from plaintext price columns
+//!
+//! Price history payloads are encrypted, so `price_history` also stores the
+//! price per mg and its currency as plain columns. Charts only need a few
+//! dozen points per line, so entries are grouped into buckets of whole days
+//! in SQL, with the lowest, highest and average price of each, instead of
+//! decrypting every entry.
+
+use serde::Serialize;
+use time::Date;
+
+/// Buckets per series when no limit is given
+pub const DEFAULT_MAX_POINTS: u32 = 60;
+
+/// Which prices to chart; unset fields match everything
+///
+/// Dates are compared against the day a price was recorded, and both ends
+/// are included.
+#[derive(Debug, Clone, Default)]
+pub struct PriceTrendFilter {
+    pub supplier_id: Option<String>,
+    pub peptide_name: Option<String>,
+    pub since: Option<Date>,
+    pub until: Option<Date>,
+    /// Most buckets per series; 0 means [`DEFAULT_MAX_POINTS`]
+    pub max_points: u32,
+}
+
+/// Prices recorded within one bucket
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceTrendPoint {
+    /// First day of the bucket as `YYYY-MM-DD`
+    pub start: String,
+    pub min_cost_per_mg: f64,
+    pub max_cost_per_mg: f64,
+    pub avg_cost_per_mg: f64,
+    pub entries: u32,
+}
+
+/// One supplier's prices for one peptide, in one currency, oldest first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceTrendSeries {
+    pub supplier_id: String,
+    pub peptide_name: String,
+    pub currency: String,
+    pub points: Vec<PriceTrendPoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceTrend {
+    /// Days covered by each bucket
+    pub bucket_days: u32,
+    pub series: Vec<PriceTrendSeries>,
+}
+
+/// Days per bucket so `first..=last` fits in `max_points` buckets
+pub fn bucket_days(first: Date, last: Date, max_points: u32) -> u32 {
+    let max_points = if max_points == 0 { DEFAULT_MAX_POINTS } else { max_points };
+    let span = (last - first).whole_days().max(0) as u32 + 1;
+    span.div_ceil(max_points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    #[test]
+    fn buckets_widen_to_fit_the_range() {
+        assert_eq!(bucket_days(date!(2024 - 03 - 01), date!(2024 - 03 - 01), 60), 1);
+        assert_eq!(bucket_days(date!(2024 - 03 - 01), date!(2024 - 03 - 30), 60), 1);
+        assert_eq!(bucket_days(date!(2024 - 01 - 01), date!(2024 - 12 - 31), 60), 7);
+        assert_eq!(bucket_days(date!(2024 - 01 - 01), date!(2024 - 01 - 10), 0), 1);
+        assert_eq!(bucket_days(date!(2024 - 01 - 01), date!(2024 - 01 - 10), 3), 4);
+    }
+}
@@ -0,0 +1,237 @@
+//! Passphrase-encrypted protocol packages for sharing between users
+//!
+//! A package holds one protocol, its dose schedules and, if the sender
+//! chooses, the literature they want to pass along. It never holds dose logs,
+//! inventory or anything else about the sender's own use. Ids are left out
+//! too: the recipient gets new records, so importing a package twice or
+//! importing one's own package never overwrites anything.
+//!
+//! The file is the JSON package sealed with [`encrypt_backup`], so it has
+//! the same container as an encrypted backup; the `format` field inside tells
+//! the two apart once decrypted.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::backup_encryption::{decrypt_backup, encrypt_backup};
+use crate::models::{LiteratureEntry, PeptideProtocol};
+use crate::passphrase::validate_passphrase;
+
+pub const PROTOCOL_PACKAGE_FORMAT: &str = "peptrack-protocol";
+pub const PROTOCOL_PACKAGE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolPackage {
+    pub format: String,
+    pub version: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub exported_at: OffsetDateTime,
+    pub protocol: SharedProtocol,
+    #[serde(default)]
+    pub schedules: Vec<SharedSchedule>,
+    #[serde(default)]
+    pub literature: Vec<SharedReference>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedProtocol {
+    pub name: String,
+    pub peptide_name: String,
+    pub notes: Option<String>,
+    /// Reconstitution setting: the concentration vials are mixed to
+    pub target_concentration_mg_ml: Option<f32>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedSchedule {
+    pub amount_mg: f32,
+    pub site: Option<String>,
+    /// `HH:MM`, 24-hour
+    pub time_of_day: String,
+    /// 0 = Sunday
+    pub days_of_week: Vec<u8>,
+    pub notes: Option<String>,
+    /// Titration steps; the recipient starts on the first one
+    #[serde(default)]
+    pub titration_phases: Vec<SharedTitrationPhase>,
+    #[serde(default)]
+    pub auto_advance: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedTitrationPhase {
+    pub amount_mg: f32,
+    pub duration_days: u32,
+}
+
+/// A paper's citation details, without the sender's reading notes, rating
+/// or reading status
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedReference {
+    pub source: String,
+    pub title: String,
+    pub url: Option<String>,
+    pub doi: Option<String>,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    pub journal: Option<String>,
+    pub year: Option<i32>,
+    pub published_date: Option<String>,
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl SharedProtocol {
+    pub fn from_protocol(protocol: &PeptideProtocol) -> Self {
+        Self {
+            name: protocol.name.clone(),
+            peptide_name: protocol.peptide_name.clone(),
+            notes: protocol.notes.clone(),
+            target_concentration_mg_ml: protocol.target_concentration_mg_ml,
+            tags: protocol.tags.clone(),
+        }
+    }
+
+    /// A new protocol for the recipient
+    pub fn to_protocol(&self) -> PeptideProtocol {
+        let mut protocol = PeptideProtocol::new(self.name.as_str(), self.peptide_name.as_str());
+        protocol.notes = self.notes.clone();
+        protocol.target_concentration_mg_ml = self.target_concentration_mg_ml;
+        protocol.tags = self.tags.clone();
+        protocol
+    }
+}
+
+impl SharedReference {
+    pub fn from_entry(entry: &LiteratureEntry) -> Self {
+        Self {
+            source: entry.source.clone(),
+            title: entry.title.clone(),
+            url: entry.url.clone(),
+            doi: entry.doi.clone(),
+            authors: entry.authors.clone(),
+            journal: entry.journal.clone(),
+            year: entry.year,
+            published_date: entry.published_date.clone(),
+            summary: entry.summary.clone(),
+            tags: entry.tags.clone(),
+        }
+    }
+
+    /// A new literature cache entry for the recipient
+    pub fn to_entry(&self) -> LiteratureEntry {
+        let mut entry = LiteratureEntry::new(self.source.as_str(), self.title.as_str());
+        entry.url = self.url.clone();
+        entry.doi = self.doi.clone();
+        entry.authors = self.authors.clone();
+        entry.journal = self.journal.clone();
+        entry.year = self.year;
+        entry.published_date = self.published_date.clone();
+        entry.summary = self.summary.clone();
+        entry.tags = self.tags.clone();
+        entry
+    }
+
+    /// Whether `entry` is the same paper, by DOI or else by title and source
+    pub fn matches(&self, entry: &LiteratureEntry) -> bool {
+        match (&self.doi, &entry.doi) {
+            (Some(doi), Some(other)) => doi.eq_ignore_ascii_case(other),
+            _ => entry.source == self.source && entry.title.trim().eq_ignore_ascii_case(self.title.trim()),
+        }
+    }
+}
+
+impl ProtocolPackage {
+    pub fn new(protocol: &PeptideProtocol) -> Self {
+        Self {
+            format: PROTOCOL_PACKAGE_FORMAT.to_string(),
+            version: PROTOCOL_PACKAGE_VERSION,
+            exported_at: OffsetDateTime::now_utc(),
+            protocol: SharedProtocol::from_protocol(protocol),
+            schedules: Vec::new(),
+            literature: Vec::new(),
+        }
+    }
+
+    /// Encrypts the package into file contents
+    pub fn seal(&self, passphrase: &str) -> Result<String> {
+        validate_passphrase(passphrase)?;
+        let json = serde_json::to_string(self).context("Failed to serialize protocol package")?;
+        encrypt_backup(&json, passphrase)
+    }
+
+    /// Decrypts file contents made by [`ProtocolPackage::seal`]
+    pub fn open(contents: &str, passphrase: &str) -> Result<Self> {
+        let json = decrypt_backup(contents.trim(), passphrase)?;
+        let value: serde_json::Value = serde_json::from_str(&json).context("Package contents are not JSON")?;
+        if value.get("format").and_then(|format| format.as_str()) != Some(PROTOCOL_PACKAGE_FORMAT) {
+            bail!("This file is not a PepTrack protocol package");
+        }
+        let package: Self = serde_json::from_value(value).context("Failed to read protocol package")?;
+        if package.version > PROTOCOL_PACKAGE_VERSION {
+            return Err(anyhow!(
+                "This protocol package needs a newer version of PepTrack (package version {})",
+                package.version
+            ));
+        }
+        Ok(package)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packages_round_trip_without_personal_fields() {
+        let mut protocol = PeptideProtocol::new("Morning BPC", "BPC-157");
+        protocol.target_concentration_mg_ml = Some(2.5);
+        let mut entry = LiteratureEntry::new("pubmed", "BPC-157 and tendon healing");
+        entry.doi = Some("10.1000/bpc".into());
+        entry.reading_notes = Some("private".into());
+        entry.rating = Some(5);
+
+        let mut package = ProtocolPackage::new(&protocol);
+        package.schedules.push(SharedSchedule {
+            amount_mg: 0.25,
+            site: Some("abdomen".into()),
+            time_of_day: "08:00".into(),
+            days_of_week: vec![1, 3, 5],
+            notes: None,
+            titration_phases: Vec::new(),
+            auto_advance: false,
+        });
+        package.literature.push(SharedReference::from_entry(&entry));
+
+        let sealed = package.seal("correct horse battery").expect("seal");
+        assert!(!sealed.contains("Morning BPC"));
+        assert!(ProtocolPackage::open(&sealed, "wrong passphrase").is_err());
+
+        let opened = ProtocolPackage::open(&sealed, "correct horse battery").expect("open");
+        assert_eq!(opened, package);
+        let imported = opened.literature[0].to_entry();
+        assert_ne!(imported.id, entry.id);
+        assert_eq!(imported.reading_notes, None);
+        assert!(opened.literature[0].matches(&entry));
+
+        let copy = opened.protocol.to_protocol();
+        assert_ne!(copy.id, protocol.id);
+        assert_eq!(copy.target_concentration_mg_ml, Some(2.5));
+    }
+
+    #[test]
+    fn encrypted_backups_are_not_packages() {
+        let backup = encrypt_backup(r#"{"metadata":{}}"#, "correct horse battery").expect("encrypt");
+        let error = ProtocolPackage::open(&backup, "correct horse battery").unwrap_err();
+        assert!(error.to_string().contains("not a PepTrack protocol package"));
+    }
+}
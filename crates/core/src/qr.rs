@@ -0,0 +1,513 @@
+//! Minimal QR code encoder for vial labels
+//!
+//! Encodes bytes in byte mode at error correction level M, in the smallest
+//! of versions 1 to 10 that fits (up to 213 bytes), which is plenty for the
+//! short identifiers printed on labels. The mask with the lowest penalty is
+//! chosen as the standard describes, so any phone or scanner reads the
+//! result.
+
+use std::fmt::Write as _;
+
+use anyhow::{bail, Result};
+
+const MAX_VERSION: usize = 10;
+/// Error correction codewords per block at level M, by version
+const ECC_CODEWORDS_PER_BLOCK: [usize; MAX_VERSION + 1] = [0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26];
+/// Error correction blocks at level M, by version
+const ECC_BLOCKS: [usize; MAX_VERSION + 1] = [0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5];
+/// Format bits for level M
+const ECC_LEVEL_M_BITS: u32 = 0b00;
+
+/// A square grid of modules, `true` for dark
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    pub version: usize,
+    pub size: usize,
+    modules: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encode `data`, failing when it doesn't fit in version 10
+    pub fn encode(data: &[u8]) -> Result<Self> {
+        let Some(version) = (1..=MAX_VERSION).find(|&v| data.len() <= data_capacity(v)) else {
+            bail!("{} bytes is too long for a label QR code", data.len());
+        };
+        let codewords = add_ecc_and_interleave(&data_codewords(data, version), version);
+
+        let mut qr = Builder::new(version);
+        qr.draw_function_patterns();
+        qr.draw_codewords(&codewords);
+
+        let mut best: Option<(u32, Vec<bool>)> = None;
+        for mask in 0..8 {
+            qr.apply_mask(mask);
+            qr.draw_format_bits(mask);
+            let penalty = qr.penalty();
+            if best.as_ref().is_none_or(|(lowest, _)| penalty < *lowest) {
+                best = Some((penalty, qr.modules.clone()));
+            }
+            // XOR again to undo the mask
+            qr.apply_mask(mask);
+        }
+
+        Ok(Self {
+            version,
+            size: qr.size,
+            modules: best.map(|(_, modules)| modules).unwrap_or_default(),
+        })
+    }
+
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        x < self.size && y < self.size && self.modules[y * self.size + x]
+    }
+
+    /// SVG path data for the dark modules, one unit per module, offset by
+    /// `border` modules of quiet zone
+    pub fn svg_path(&self, border: usize) -> String {
+        let mut path = String::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if self.is_dark(x, y) {
+                    let _ = write!(path, "M{},{}h1v1h-1z", x + border, y + border);
+                }
+            }
+        }
+        path
+    }
+
+    /// Standalone SVG with a quiet zone of `border` modules
+    pub fn to_svg(&self, border: usize) -> String {
+        let dimension = self.size + border * 2;
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {d} {d}" shape-rendering="crispEdges"><rect width="100%" height="100%" fill="#fff"/><path d="{path}" fill="#000"/></svg>"##,
+            d = dimension,
+            path = self.svg_path(border)
+        )
+    }
+}
+
+fn size_for(version: usize) -> usize {
+    version * 4 + 17
+}
+
+/// Modules available for data and error correction
+fn raw_data_modules(version: usize) -> usize {
+    let mut modules = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignments = version / 7 + 2;
+        modules -= (25 * alignments - 10) * alignments - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+    modules
+}
+
+fn data_codeword_count(version: usize) -> usize {
+    raw_data_modules(version) / 8 - ECC_CODEWORDS_PER_BLOCK[version] * ECC_BLOCKS[version]
+}
+
+fn count_bits(version: usize) -> usize {
+    if version <= 9 {
+        8
+    } else {
+        16
+    }
+}
+
+/// Bytes that fit after the mode and length header
+fn data_capacity(version: usize) -> usize {
+    (data_codeword_count(version) * 8 - 4 - count_bits(version)) / 8
+}
+
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2;
+    let mut positions = vec![6];
+    let mut position = size_for(version) - 7;
+    for _ in 0..count - 1 {
+        positions.insert(1, position);
+        position -= step;
+    }
+    positions
+}
+
+struct BitBuffer(Vec<bool>);
+
+impl BitBuffer {
+    fn push(&mut self, value: u32, bits: usize) {
+        for i in (0..bits).rev() {
+            self.0.push((value >> i) & 1 != 0);
+        }
+    }
+}
+
+/// Mode, length, data, terminator and padding as codewords
+fn data_codewords(data: &[u8], version: usize) -> Vec<u8> {
+    let capacity_bits = data_codeword_count(version) * 8;
+    let mut bits = BitBuffer(Vec::with_capacity(capacity_bits));
+    bits.push(0b0100, 4);
+    bits.push(data.len() as u32, count_bits(version));
+    for &byte in data {
+        bits.push(u32::from(byte), 8);
+    }
+    let terminator = (capacity_bits - bits.0.len()).min(4);
+    bits.push(0, terminator);
+    bits.push(0, (8 - bits.0.len() % 8) % 8);
+
+    let mut codewords: Vec<u8> = bits
+        .0
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0u8, |acc, &bit| (acc << 1) | u8::from(bit)))
+        .collect();
+    for pad in [0xEC, 0x11].into_iter().cycle() {
+        if codewords.len() >= capacity_bits / 8 {
+            break;
+        }
+        codewords.push(pad);
+    }
+    codewords
+}
+
+/// Split into blocks, append each block's error correction and interleave
+fn add_ecc_and_interleave(data: &[u8], version: usize) -> Vec<u8> {
+    let blocks = ECC_BLOCKS[version];
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[version];
+    let raw_codewords = raw_data_modules(version) / 8;
+    let short_blocks = blocks - raw_codewords % blocks;
+    let short_len = raw_codewords / blocks;
+    let divisor = rs_divisor(ecc_len);
+
+    let mut split = Vec::with_capacity(blocks);
+    let mut offset = 0;
+    for i in 0..blocks {
+        let data_len = short_len - ecc_len + usize::from(i >= short_blocks);
+        let block = &data[offset..offset + data_len];
+        offset += data_len;
+        split.push((block.to_vec(), rs_remainder(block, &divisor)));
+    }
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..=short_len - ecc_len {
+        for (block, _) in &split {
+            if let Some(&codeword) = block.get(i) {
+                result.push(codeword);
+            }
+        }
+    }
+    for i in 0..ecc_len {
+        for (_, ecc) in &split {
+            result.push(ecc[i]);
+        }
+    }
+    result
+}
+
+/// Multiply in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u16 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((u16::from(y) >> i) & 1) * u16::from(x);
+    }
+    z as u8
+}
+
+/// Generator polynomial of `degree`, highest coefficient first and the
+/// leading 1 left out
+fn rs_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn rs_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (r, &d) in result.iter_mut().zip(divisor) {
+            *r ^= gf_multiply(d, factor);
+        }
+    }
+    result
+}
+
+struct Builder {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+    is_function: Vec<bool>,
+}
+
+impl Builder {
+    fn new(version: usize) -> Self {
+        let size = size_for(version);
+        Self {
+            version,
+            size,
+            modules: vec![false; size * size],
+            is_function: vec![false; size * size],
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.is_function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        for i in 0..self.size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        let far = self.size as isize - 4;
+        self.draw_finder(3, 3);
+        self.draw_finder(far, 3);
+        self.draw_finder(3, far);
+
+        let positions = alignment_positions(self.version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                // Skip the three corners with finder patterns
+                let on_finder = (i == 0 && (j == 0 || j == last)) || (i == last && j == 0);
+                if !on_finder {
+                    self.draw_alignment(x, y);
+                }
+            }
+        }
+
+        // Reserve the format areas until a mask is chosen
+        self.draw_format_bits(0);
+        self.draw_version();
+    }
+
+    /// Finder pattern and separator centred on (`cx`, `cy`)
+    fn draw_finder(&mut self, cx: isize, cy: isize) {
+        for dy in -4isize..=4 {
+            for dx in -4isize..=4 {
+                let (x, y) = (cx + dx, cy + dy);
+                if (0..self.size as isize).contains(&x) && (0..self.size as isize).contains(&y) {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function(x as usize, y as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment(&mut self, cx: usize, cy: usize) {
+        for dy in -2isize..=2 {
+            for dx in -2isize..=2 {
+                let x = (cx as isize + dx) as usize;
+                let y = (cy as isize + dy) as usize;
+                self.set_function(x, y, dx.abs().max(dy.abs()) != 1);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u32) {
+        let data = (ECC_LEVEL_M_BITS << 3) | mask;
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = ((data << 10) | remainder) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 != 0;
+
+        // Around the top-left finder
+        for i in 0..=5 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+
+        // Split between the other two finders
+        let size = self.size;
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_version(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+        let mut remainder = self.version as u32;
+        for _ in 0..12 {
+            remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+        }
+        let bits = ((self.version as u32) << 12) | remainder;
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 != 0;
+            let a = self.size - 11 + i % 3;
+            let b = i / 3;
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    /// Fill the non-function modules in the zigzag order
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let total_bits = codewords.len() * 8;
+        let mut i = 0;
+        let mut right = self.size as isize - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..self.size {
+                for j in 0..2 {
+                    let x = (right - j) as usize;
+                    let upward = ((right + 1) & 2) == 0;
+                    let y = if upward { self.size - 1 - vertical } else { vertical };
+                    if !self.is_function[y * self.size + x] && i < total_bits {
+                        self.modules[y * self.size + x] = (codewords[i >> 3] >> (7 - (i & 7))) & 1 != 0;
+                        i += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let index = y * self.size + x;
+                if invert && !self.is_function[index] {
+                    self.modules[index] ^= true;
+                }
+            }
+        }
+    }
+
+    /// Penalty score from the four rules of the standard; lower reads better
+    fn penalty(&self) -> u32 {
+        let size = self.size;
+        let mut penalty: u32 = 0;
+
+        // Rows and columns as lines of modules
+        let lines: Vec<Vec<bool>> = (0..size)
+            .map(|y| (0..size).map(|x| self.get(x, y)).collect())
+            .chain((0..size).map(|x| (0..size).map(|y| self.get(x, y)).collect()))
+            .collect();
+        for line in &lines {
+            // Runs of five or more of the same colour
+            let mut run: u32 = 1;
+            for i in 1..=size {
+                if i < size && line[i] == line[i - 1] {
+                    run += 1;
+                } else {
+                    if run >= 5 {
+                        penalty += 3 + (run - 5);
+                    }
+                    run = 1;
+                }
+            }
+            // Patterns that look like a finder
+            const FINDER_LIKE: [bool; 11] = [
+                true, false, true, true, true, false, true, false, false, false, false,
+            ];
+            for window in line.windows(11) {
+                if window == FINDER_LIKE || window.iter().rev().eq(FINDER_LIKE.iter()) {
+                    penalty += 40;
+                }
+            }
+        }
+
+        // 2x2 blocks of the same colour
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let colour = self.get(x, y);
+                if colour == self.get(x + 1, y) && colour == self.get(x, y + 1) && colour == self.get(x + 1, y + 1) {
+                    penalty += 3;
+                }
+            }
+        }
+
+        // Balance of dark and light
+        let dark = self.modules.iter().filter(|&&dark| dark).count();
+        let percent = dark * 100 / self.modules.len();
+        penalty += (percent.abs_diff(50) / 5) as u32 * 10;
+
+        penalty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_correction_matches_the_reference_example() {
+        // "HELLO WORLD" at 1-M from the standard's worked example
+        let data = [32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17];
+        assert_eq!(
+            rs_remainder(&data, &rs_divisor(10)),
+            vec![196, 35, 39, 119, 235, 215, 231, 226, 93, 23]
+        );
+    }
+
+    #[test]
+    fn capacities_match_the_level_m_table() {
+        let data_codewords: Vec<usize> = (1..=MAX_VERSION).map(data_codeword_count).collect();
+        assert_eq!(data_codewords, vec![16, 28, 44, 64, 86, 108, 124, 154, 182, 216]);
+        assert_eq!(alignment_positions(7), vec![6, 22, 38]);
+    }
+
+    #[test]
+    fn encodes_a_vial_id_with_finders_and_timing() {
+        let qr = QrCode::encode(b"peptrack:vial:6f1c2a9e-8d0b-4c55-a1f2-3b4c5d6e7f80").expect("encode");
+        assert_eq!(qr.version, 4);
+        assert_eq!(qr.size, 33);
+
+        // Finder centres and corners are dark, separators light
+        for (x, y) in [(3, 3), (qr.size - 4, 3), (3, qr.size - 4), (0, 0)] {
+            assert!(qr.is_dark(x, y));
+        }
+        assert!(!qr.is_dark(7, 0));
+        // Timing pattern alternates
+        assert!((8..qr.size - 8).all(|i| qr.is_dark(i, 6) == (i % 2 == 0)));
+        assert!(qr.is_dark(8, qr.size - 8));
+
+        assert!(qr.to_svg(4).starts_with("<svg"));
+        assert!(QrCode::encode(&[b'x'; 300]).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Salvaging a corrupted database
+//!
+//! [`StorageManager::salvage`](crate::StorageManager::salvage) copies every
+//! row that can still be read into a fresh database, much like the sqlite3
+//! shell's `.recover`, and reports how many rows of each table were recovered
+//! and lost. [`StorageManager::apply_salvage`](crate::StorageManager::apply_salvage)
+//! then swaps the salvaged copy in. Salvaged copies, their reports and the
+//! damaged files they replaced are kept next to the database in `recovery/`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Statement};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::warn;
+
+/// Directory next to the database that holds salvaged and damaged copies
+pub const RECOVERY_DIR_NAME: &str = "recovery";
+
+/// Progress of a salvage, reported before each table and once at the end
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryProgress {
+    /// Table being copied; empty once every table is done
+    pub table: String,
+    pub tables_done: usize,
+    pub tables_total: usize,
+}
+
+/// What was salvaged from one table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableRecovery {
+    pub table: String,
+    pub recovered: usize,
+    /// Rows that couldn't be read; `None` when the damaged table couldn't be counted
+    pub lost: Option<usize>,
+}
+
+/// Outcome of a salvage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SalvageReport {
+    pub id: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    pub tables: Vec<TableRecovery>,
+    pub recovered_rows: usize,
+    /// Rows known to be lost; tables that couldn't be counted add nothing
+    pub lost_rows: usize,
+    /// Whether the salvaged database passed an integrity check
+    pub verified: bool,
+}
+
+impl SalvageReport {
+    pub(crate) fn new(id: String, created_at: OffsetDateTime, tables: Vec<TableRecovery>, verified: bool) -> Self {
+        Self {
+            recovered_rows: tables.iter().map(|table| table.recovered).sum(),
+            lost_rows: tables.iter().filter_map(|table| table.lost).sum(),
+            id,
+            created_at,
+            tables,
+            verified,
+        }
+    }
+}
+
+/// Where salvaged and damaged copies of the database at `db_path` are kept
+pub(crate) fn recovery_dir(db_path: &Path) -> PathBuf {
+    db_path
+        .parent()
+        .map(|dir| dir.join(RECOVERY_DIR_NAME))
+        .unwrap_or_else(|| PathBuf::from(RECOVERY_DIR_NAME))
+}
+
+pub(crate) fn salvage_file(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.salvaged.sqlite", id))
+}
+
+pub(crate) fn damaged_file(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.damaged.sqlite", id))
+}
+
+fn report_file(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.json", id))
+}
+
+pub(crate) fn save_report(dir: &Path, report: &SalvageReport) -> Result<()> {
+    let json = serde_json::to_vec_pretty(report)?;
+    fs::write(report_file(dir, &report.id), json).context("Failed to save salvage report")
+}
+
+/// The salvage `id` and its database copy
+pub(crate) fn load_salvage(dir: &Path, id: &str) -> Result<(SalvageReport, PathBuf)> {
+    let json = fs::read(report_file(dir, id)).with_context(|| format!("Salvage {} not found", id))?;
+    let report: SalvageReport = serde_json::from_slice(&json).context("Salvage report is unreadable")?;
+    let path = salvage_file(dir, id);
+    if !path.exists() {
+        anyhow::bail!("Salvaged database {} is missing", id);
+    }
+    Ok((report, path))
+}
+
+/// Columns of `table`, in order
+pub(crate) fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+    let columns = stmt
+        .query_map(params![table], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(columns)
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Copy the readable rows of `table` from `source` into `target`
+///
+/// Rows are read in rowid order. When a damaged page stops the scan, the
+/// scan resumes at the next rowid that can be read, looking further ahead
+/// each time a probe fails.
+pub(crate) fn copy_table(source: &Connection, target: &Connection, table: &str, columns: &[String]) -> Result<TableRecovery> {
+    let expected = source
+        .query_row(&format!("SELECT COUNT(*) FROM {}", quote(table)), [], |row| row.get::<_, i64>(0))
+        .ok()
+        .map(|count| count as usize);
+
+    let column_list = columns.iter().map(|column| quote(column)).collect::<Vec<_>>().join(", ");
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let select = format!(
+        "SELECT rowid, {} FROM {} WHERE rowid > ?1 ORDER BY rowid",
+        column_list,
+        quote(table)
+    );
+    let mut insert = target.prepare(&format!(
+        "INSERT OR IGNORE INTO {} ({}) VALUES ({})",
+        quote(table),
+        column_list,
+        placeholders
+    ))?;
+
+    let mut recovered = 0;
+    let mut resume_after = i64::MIN;
+    loop {
+        let mut last_read = None;
+        match copy_rows(source, &select, &mut insert, resume_after, &mut last_read, &mut recovered) {
+            Ok(()) => break,
+            Err(e) => {
+                warn!("Skipping damaged rows in {}: {:#}", table, e);
+                let from = last_read.unwrap_or(resume_after.max(0));
+                match next_readable_rowid(source, table, from) {
+                    Some(rowid) => resume_after = rowid - 1,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    Ok(TableRecovery {
+        table: table.to_string(),
+        recovered,
+        lost: expected.map(|expected| expected.saturating_sub(recovered)),
+    })
+}
+
+fn copy_rows(
+    source: &Connection,
+    select: &str,
+    insert: &mut Statement<'_>,
+    after: i64,
+    last_read: &mut Option<i64>,
+    recovered: &mut usize,
+) -> Result<()> {
+    let mut stmt = source.prepare(select)?;
+    let width = stmt.column_count();
+    let mut rows = stmt.query(params![after])?;
+    while let Some(row) = rows.next()? {
+        let rowid: i64 = row.get(0)?;
+        // Count the row as read even if a value can't be decoded, so the
+        // scan resumes after it
+        *last_read = Some(rowid);
+        let values = (1..width).map(|i| row.get::<_, Value>(i)).collect::<rusqlite::Result<Vec<_>>>()?;
+        *recovered += insert.execute(params_from_iter(values))?;
+    }
+    Ok(())
+}
+
+/// First rowid past the damage after `from`, or `None` when nothing more can be read
+fn next_readable_rowid(source: &Connection, table: &str, from: i64) -> Option<i64> {
+    let probe = format!("SELECT rowid FROM {} WHERE rowid > ?1 ORDER BY rowid LIMIT 1", quote(table));
+    let mut gap: i64 = 1;
+    while let Some(start) = from.checked_add(gap) {
+        match source.query_row(&probe, params![start], |row| row.get::<_, i64>(0)).optional() {
+            Ok(next) => return next,
+            Err(_) => gap = gap.saturating_mul(2),
+        }
+        if gap == i64::MAX {
+            break;
+        }
+    }
+    None
+}
@@ -0,0 +1,144 @@
+//! Anonymized exports
+//!
+//! A [`Redactor`] rewrites serialized records so they can be shared with a
+//! coach or attached to a bug report: free-text fields that may hold
+//! personal details are cleared, and identifying names are replaced with
+//! pseudonyms. Structure, IDs, dates and quantities are left alone, so the
+//! export still shows what was taken, when and how much.
+//!
+//! Pseudonyms come from a random per-export salt: the same name maps to the
+//! same pseudonym throughout one export, but can't be matched across exports
+//! or reversed by guessing.
+
+use blake2::digest::Mac;
+use blake2::Blake2sMac256;
+use rand::{rngs::OsRng, RngCore};
+use serde_json::Value;
+
+/// Free text that may mention people, places or health details; cleared
+const STRIPPED_FIELDS: &[&str] = &[
+    "notes",
+    "description",
+    "contact_email",
+    "contact_phone",
+    "tracking_number",
+    "original_content",
+];
+
+/// Names that identify the user, a supplier or a specific vial; replaced
+/// with a pseudonym so records that share a name still match
+const PSEUDONYMIZED_FIELDS: &[&str] = &[
+    "name",
+    "protocol_name",
+    "supplier_name",
+    "website",
+    "lab_name",
+    "vial_number",
+    "batch_number",
+    "lot_number",
+    "file_name",
+    "tags",
+];
+
+pub struct Redactor {
+    salt: [u8; 32],
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Redactor {
+    /// Creates a redactor with a fresh random salt
+    pub fn new() -> Self {
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+        Self { salt }
+    }
+
+    /// Stable pseudonym for `text` within this export, e.g. `anon-3f9a1c07e2`
+    pub fn pseudonym(&self, text: &str) -> String {
+        let mut mac = <Blake2sMac256 as Mac>::new_from_slice(&self.salt)
+            .expect("32-byte key is valid for Blake2s");
+        mac.update(text.trim().to_lowercase().as_bytes());
+        let digest = mac.finalize().into_bytes();
+        format!("anon-{}", hex::encode(&digest[..5]))
+    }
+
+    /// Redact a serialized record in place, including nested objects
+    pub fn redact(&self, value: &mut Value) {
+        match value {
+            Value::Object(fields) => {
+                for (key, field) in fields.iter_mut() {
+                    if STRIPPED_FIELDS.contains(&key.as_str()) {
+                        if !field.is_null() {
+                            *field = Value::Null;
+                        }
+                    } else if PSEUDONYMIZED_FIELDS.contains(&key.as_str()) {
+                        self.pseudonymize(field);
+                    } else {
+                        self.redact(field);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact(item)),
+            _ => {}
+        }
+    }
+
+    fn pseudonymize(&self, value: &mut Value) {
+        match value {
+            Value::String(text) if !text.is_empty() => *text = self.pseudonym(text),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.pseudonymize(item)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DoseLog, PeptideProtocol, Supplier};
+
+    #[test]
+    fn redact_clears_free_text_and_keeps_quantities() {
+        let redactor = Redactor::new();
+        let mut dose = DoseLog::new("protocol-1", "abdomen", 0.25);
+        dose.notes = Some("Felt dizzy after lunch with Sam".to_string());
+
+        let mut value = serde_json::to_value(&dose).unwrap();
+        redactor.redact(&mut value);
+
+        assert_eq!(value["notes"], Value::Null);
+        assert_eq!(value["amount_mg"], 0.25);
+        assert_eq!(value["site"], "abdomen");
+        assert_eq!(value["protocol_id"], "protocol-1");
+    }
+
+    #[test]
+    fn pseudonyms_are_consistent_within_an_export_only() {
+        let redactor = Redactor::new();
+        let mut supplier = Supplier::new("Acme Peptides");
+        supplier.contact_email = Some("jo@example.com".to_string());
+        let mut protocol = PeptideProtocol::new("Acme Peptides", "BPC-157");
+        protocol.tags = vec!["knee".to_string()];
+
+        let mut supplier_value = serde_json::to_value(&supplier).unwrap();
+        let mut protocol_value = serde_json::to_value(&protocol).unwrap();
+        redactor.redact(&mut supplier_value);
+        redactor.redact(&mut protocol_value);
+
+        assert_eq!(supplier_value["contact_email"], Value::Null);
+        assert!(supplier_value["name"].as_str().unwrap().starts_with("anon-"));
+        assert_eq!(supplier_value["name"], protocol_value["name"]);
+        assert_eq!(protocol_value["peptide_name"], "BPC-157");
+        assert_ne!(protocol_value["tags"][0], "knee");
+
+        assert_ne!(
+            Redactor::new().pseudonym("Acme Peptides"),
+            redactor.pseudonym("Acme Peptides")
+        );
+    }
+}
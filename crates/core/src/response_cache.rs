@@ -0,0 +1,97 @@
+//! Cached literature API responses
+//!
+//! Literature searches are kept in the `literature_responses` table so
+//! running the same search again within [`ResponseCacheSettings::ttl_hours`]
+//! doesn't hit the API. Rows are keyed by a hash of the source, query and
+//! page, so the query text itself is only stored encrypted.
+
+use blake2::{Blake2s256, Digest};
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+
+use crate::settings::Setting;
+
+/// Hours a response is served from the cache unless set otherwise
+pub const DEFAULT_RESPONSE_TTL_HOURS: u32 = 24;
+/// Longest TTL that can be set, 30 days
+pub const MAX_RESPONSE_TTL_HOURS: u32 = 24 * 30;
+
+/// How long literature responses are cached
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ResponseCacheSettings {
+    pub enabled: bool,
+    pub ttl_hours: u32,
+}
+
+impl Default for ResponseCacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_hours: DEFAULT_RESPONSE_TTL_HOURS,
+        }
+    }
+}
+
+impl ResponseCacheSettings {
+    pub fn ttl(&self) -> Duration {
+        Duration::hours(self.ttl_hours as i64)
+    }
+}
+
+impl Setting for ResponseCacheSettings {
+    const KEY: &'static str = "literature.response_cache";
+
+    fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_RESPONSE_TTL_HOURS).contains(&self.ttl_hours) {
+            return Err(format!(
+                "Cache lifetime must be between 1 and {} hours",
+                MAX_RESPONSE_TTL_HOURS
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// What the response cache holds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseCacheStats {
+    pub entries: usize,
+    /// Entries older than the TTL they were counted against
+    pub expired: usize,
+    /// Times a cached response was served instead of calling the API
+    pub hits: u64,
+    /// Encrypted size of the cached responses
+    pub size_bytes: u64,
+    pub oldest_fetched_at: Option<OffsetDateTime>,
+    pub by_source: Vec<SourceCacheStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceCacheStats {
+    pub source: String,
+    pub entries: usize,
+    pub hits: u64,
+}
+
+/// Row key of a cached response
+pub(crate) fn response_key_hash(key: &str) -> String {
+    hex::encode(Blake2s256::digest(key.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ttl_must_be_within_bounds() {
+        assert!(ResponseCacheSettings::default().validate().is_ok());
+        let zero = ResponseCacheSettings { ttl_hours: 0, ..Default::default() };
+        assert!(zero.validate().is_err());
+        let long = ResponseCacheSettings { ttl_hours: MAX_RESPONSE_TTL_HOURS + 1, ..Default::default() };
+        assert!(long.validate().is_err());
+        assert_eq!(ResponseCacheSettings::default().ttl(), Duration::hours(24));
+    }
+}
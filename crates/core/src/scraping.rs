@@ -0,0 +1,292 @@
+//! Polite supplier scraping
+//!
+//! Price scraping fetches supplier pages on a schedule, which sites may treat
+//! as abuse. [`ScrapingSettings`] sets the user agent scrapes identify as,
+//! the delay between requests to one site and how many pages a scheduled
+//! run may fetch. [`RobotsTxt`] reads a site's `robots.txt` so disallowed
+//! pages are skipped, following RFC 9309: the group naming our user agent
+//! applies (else the `*` group), the longest matching rule wins and `Allow`
+//! wins ties.
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Setting;
+
+pub const DEFAULT_SCRAPER_USER_AGENT: &str = "PepTrack/1.0 (+https://peptrack.app)";
+/// Seconds between requests to one site unless set otherwise
+pub const DEFAULT_DOMAIN_DELAY_SECS: u32 = 5;
+/// Pages a scheduled price check fetches unless set otherwise
+pub const DEFAULT_CRAWL_BUDGET: u32 = 50;
+/// Longest delay that can be set, or that a site's `Crawl-delay` is
+/// honored up to
+pub const MAX_DOMAIN_DELAY_SECS: u32 = 120;
+/// Most pages a scheduled price check can be allowed to fetch
+pub const MAX_CRAWL_BUDGET: u32 = 1_000;
+
+/// How supplier pages are fetched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScrapingSettings {
+    pub user_agent: String,
+    /// Skip pages the site's `robots.txt` disallows
+    pub respect_robots_txt: bool,
+    /// Minimum seconds between requests to one site; a longer
+    /// `Crawl-delay` in its `robots.txt` is used instead
+    pub domain_delay_secs: u32,
+    /// Most pages one scheduled price check fetches; the rest wait for the
+    /// next run
+    pub crawl_budget: u32,
+    /// Load pages whose HTML has no prices in a hidden browser, for sites
+    /// that add prices with JavaScript; needs a build with rendering
+    pub render_javascript: bool,
+}
+
+impl Default for ScrapingSettings {
+    fn default() -> Self {
+        Self {
+            user_agent: DEFAULT_SCRAPER_USER_AGENT.to_string(),
+            respect_robots_txt: true,
+            domain_delay_secs: DEFAULT_DOMAIN_DELAY_SECS,
+            crawl_budget: DEFAULT_CRAWL_BUDGET,
+            render_javascript: false,
+        }
+    }
+}
+
+impl ScrapingSettings {
+    /// Name matched against `User-agent` lines: the user agent up to the
+    /// first `/` or space, e.g. `PepTrack`
+    pub fn product_token(&self) -> &str {
+        self.user_agent
+            .split(['/', ' '])
+            .next()
+            .unwrap_or_default()
+    }
+}
+
+impl Setting for ScrapingSettings {
+    const KEY: &'static str = "scraping.settings";
+
+    fn validate(&self) -> Result<(), String> {
+        let agent = self.user_agent.trim();
+        if agent.is_empty() {
+            return Err("User agent cannot be empty".to_string());
+        }
+        if agent.chars().any(|c| c.is_control() || !c.is_ascii()) {
+            return Err("User agent must be plain ASCII text".to_string());
+        }
+        if self.domain_delay_secs > MAX_DOMAIN_DELAY_SECS {
+            return Err(format!("Delay can be at most {} seconds", MAX_DOMAIN_DELAY_SECS));
+        }
+        if !(1..=MAX_CRAWL_BUDGET).contains(&self.crawl_budget) {
+            return Err(format!("Crawl budget must be between 1 and {} pages", MAX_CRAWL_BUDGET));
+        }
+        Ok(())
+    }
+}
+
+/// Rules from a site's `robots.txt`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RobotsTxt {
+    groups: Vec<RobotsGroup>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RobotsGroup {
+    /// Lowercased `User-agent` values
+    agents: Vec<String>,
+    /// `(allow, path pattern)` in file order
+    rules: Vec<(bool, String)>,
+    crawl_delay: Option<f64>,
+}
+
+impl RobotsTxt {
+    /// Rules that allow everything, for sites without a `robots.txt`
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// Parse a `robots.txt`; lines that can't be read are ignored
+    pub fn parse(text: &str) -> Self {
+        let mut groups: Vec<RobotsGroup> = Vec::new();
+        // Whether the last line was a User-agent, so consecutive ones share
+        // a group
+        let mut in_agents = false;
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match field.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if !in_agents {
+                        groups.push(RobotsGroup::default());
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.agents.push(value.to_ascii_lowercase());
+                    }
+                    in_agents = true;
+                }
+                field @ ("allow" | "disallow") => {
+                    in_agents = false;
+                    if let Some(group) = groups.last_mut() {
+                        // An empty Disallow allows everything
+                        if !value.is_empty() {
+                            group.rules.push((field == "allow", value.to_string()));
+                        }
+                    }
+                }
+                "crawl-delay" => {
+                    in_agents = false;
+                    let delay = value.parse::<f64>().ok().filter(|d| d.is_finite() && *d >= 0.0);
+                    if let (Some(group), Some(delay)) = (groups.last_mut(), delay) {
+                        group.crawl_delay = Some(delay);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self { groups }
+    }
+
+    /// Groups that apply to `product_token`: those naming it, else `*`
+    fn groups_for(&self, product_token: &str) -> Vec<&RobotsGroup> {
+        let token = product_token.to_ascii_lowercase();
+        let named: Vec<&RobotsGroup> = self
+            .groups
+            .iter()
+            .filter(|group| group.agents.iter().any(|agent| agent == &token))
+            .collect();
+        if !named.is_empty() {
+            return named;
+        }
+        self.groups
+            .iter()
+            .filter(|group| group.agents.iter().any(|agent| agent == "*"))
+            .collect()
+    }
+
+    /// Whether `product_token` may fetch `path` (with any query string)
+    pub fn is_allowed(&self, product_token: &str, path: &str) -> bool {
+        if path == "/robots.txt" {
+            return true;
+        }
+        let mut best: Option<(usize, bool)> = None;
+        for group in self.groups_for(product_token) {
+            for (allow, pattern) in &group.rules {
+                if !pattern_matches(pattern, path) {
+                    continue;
+                }
+                let len = pattern.len();
+                let better = match best {
+                    None => true,
+                    Some((best_len, best_allow)) => len > best_len || (len == best_len && *allow && !best_allow),
+                };
+                if better {
+                    best = Some((len, *allow));
+                }
+            }
+        }
+        best.is_none_or(|(_, allow)| allow)
+    }
+
+    /// `Crawl-delay` in seconds for `product_token`, if the site sets one
+    pub fn crawl_delay(&self, product_token: &str) -> Option<f64> {
+        self.groups_for(product_token)
+            .iter()
+            .filter_map(|group| group.crawl_delay)
+            .reduce(f64::max)
+    }
+}
+
+/// Whether a rule `pattern` matches the start of `path`; `*` matches any
+/// run of characters and a trailing `$` anchors the end
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let parts: Vec<&str> = pattern.split('*').collect();
+
+    // The first part must match at the start
+    let Some(mut rest) = path.strip_prefix(parts[0]) else {
+        return false;
+    };
+    let last = parts.len() - 1;
+    for (i, part) in parts.iter().enumerate().skip(1) {
+        if i == last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTS: &str = "
+        # Shop crawl rules
+        User-agent: *
+        Disallow: /checkout
+        Disallow: /*.pdf$
+        Allow: /checkout/help
+        Crawl-delay: 10
+
+        User-agent: PepTrack
+        User-agent: OtherBot
+        Disallow: /admin
+        Disallow:
+    ";
+
+    #[test]
+    fn the_most_specific_group_and_rule_apply() {
+        let robots = RobotsTxt::parse(ROBOTS);
+        assert!(!robots.is_allowed("anybot", "/checkout/cart"));
+        assert!(robots.is_allowed("anybot", "/checkout/help/faq"));
+        assert!(!robots.is_allowed("anybot", "/files/coa.pdf"));
+        assert!(robots.is_allowed("anybot", "/files/coa.pdf?v=2"));
+        assert!(robots.is_allowed("anybot", "/products/bpc-157"));
+        assert_eq!(robots.crawl_delay("anybot"), Some(10.0));
+
+        // Our group replaces the * group
+        assert!(robots.is_allowed("PepTrack", "/checkout/cart"));
+        assert!(!robots.is_allowed("peptrack", "/admin/login"));
+        assert_eq!(robots.crawl_delay("PepTrack"), None);
+
+        assert!(RobotsTxt::allow_all().is_allowed("PepTrack", "/anything"));
+        assert!(RobotsTxt::parse("User-agent: *\nDisallow: /").is_allowed("PepTrack", "/robots.txt"));
+    }
+
+    #[test]
+    fn wildcards_and_anchors_match() {
+        assert!(pattern_matches("/shop/*/price", "/shop/peptides/price-list"));
+        assert!(!pattern_matches("/shop/*/price", "/shop/peptides"));
+        assert!(pattern_matches("/*.php$", "/index.php"));
+        assert!(!pattern_matches("/*.php$", "/index.php?id=1"));
+        assert!(pattern_matches("/exact$", "/exact"));
+        assert!(!pattern_matches("/exact$", "/exactly"));
+        assert!(pattern_matches("/", "/anything"));
+    }
+
+    #[test]
+    fn settings_are_validated() {
+        let settings = ScrapingSettings::default();
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.product_token(), "PepTrack");
+
+        let blank = ScrapingSettings { user_agent: "  ".into(), ..Default::default() };
+        assert!(blank.validate().is_err());
+        let unbounded = ScrapingSettings { crawl_budget: 0, ..Default::default() };
+        assert!(unbounded.validate().is_err());
+        let slow = ScrapingSettings { domain_delay_secs: MAX_DOMAIN_DELAY_SECS + 1, ..Default::default() };
+        assert!(slow.validate().is_err());
+    }
+}
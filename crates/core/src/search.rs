@@ -0,0 +1,228 @@
+//! Tokenizing records for the global search index
+//!
+//! Record payloads are encrypted, so the `search_index` FTS table never holds
+//! their text. Each record is broken into lowercase words (plus their
+//! prefixes, so "ipam" finds "ipamorelin"), and every term is stored as a
+//! keyed hash from [`EnvelopeEncryption::blind_tokens`](crate::EnvelopeEncryption::blind_tokens).
+//! Queries are hashed the same way and matched against those tokens.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::journal::strip_links;
+use crate::models::{Alert, DoseLog, InventoryItem, JournalEntry, PeptideProtocol, Supplier, SummaryHistory};
+
+/// Shortest word or prefix that is indexed
+const MIN_TERM_CHARS: usize = 2;
+/// Longest prefix indexed; longer query words are truncated to match
+const MAX_TERM_CHARS: usize = 24;
+/// Marks terms that came from tags, so `#recovery` only matches tags
+const TAG_PREFIX: &str = "#";
+
+/// Kind of record a search result points to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchEntityType {
+    Protocol,
+    DoseLog,
+    Supplier,
+    InventoryItem,
+    Summary,
+    Alert,
+    JournalEntry,
+}
+
+/// A record matched by the search index, best match first
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub entity_type: SearchEntityType,
+    pub entity_id: String,
+}
+
+/// The searchable text and tags of one record
+#[derive(Debug, Clone, Default)]
+pub struct SearchDocument {
+    pub text: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+impl SearchDocument {
+    fn push(&mut self, value: Option<&String>) {
+        if let Some(value) = value {
+            self.text.push(value.clone());
+        }
+    }
+
+    /// Every distinct term to index: words and their prefixes, plus tag terms
+    pub fn terms(&self) -> Vec<String> {
+        let mut terms = BTreeSet::new();
+        for word in self.text.iter().flat_map(|text| words(text)) {
+            terms.extend(prefixes(&word));
+        }
+        for tag in &self.tags {
+            let tag = normalize_tag(tag);
+            terms.extend(prefixes(&tag).map(|prefix| format!("{}{}", TAG_PREFIX, prefix)));
+        }
+        terms.into_iter().collect()
+    }
+}
+
+/// Lowercase alphanumeric runs of at least [`MIN_TERM_CHARS`] characters
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= MIN_TERM_CHARS)
+        .map(str::to_lowercase)
+}
+
+fn prefixes(word: &str) -> impl Iterator<Item = String> + '_ {
+    let chars: Vec<char> = word.chars().take(MAX_TERM_CHARS).collect();
+    (MIN_TERM_CHARS..=chars.len()).map(move |len| chars[..len].iter().collect())
+}
+
+/// Tags match regardless of case or punctuation ("Weight-Loss" == "weightloss")
+fn normalize_tag(tag: &str) -> String {
+    tag.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Terms a query must all match
+///
+/// Words prefixed with `#` or `tag:` only match protocol and journal tags.
+pub fn query_terms(query: &str) -> Vec<String> {
+    let mut terms = BTreeSet::new();
+    for part in query.split_whitespace() {
+        let tag = part
+            .strip_prefix(TAG_PREFIX)
+            .or_else(|| part.strip_prefix("tag:"));
+
+        match tag {
+            Some(tag) => {
+                let tag: String = normalize_tag(tag).chars().take(MAX_TERM_CHARS).collect();
+                if tag.chars().count() >= MIN_TERM_CHARS {
+                    terms.insert(format!("{}{}", TAG_PREFIX, tag));
+                }
+            }
+            None => {
+                for word in words(part) {
+                    terms.insert(word.chars().take(MAX_TERM_CHARS).collect());
+                }
+            }
+        }
+    }
+    terms.into_iter().collect()
+}
+
+pub fn protocol_document(protocol: &PeptideProtocol) -> SearchDocument {
+    let mut doc = SearchDocument {
+        text: vec![protocol.name.clone(), protocol.peptide_name.clone()],
+        tags: protocol.tags.clone(),
+    };
+    doc.push(protocol.notes.as_ref());
+    doc
+}
+
+pub fn dose_log_document(log: &DoseLog) -> SearchDocument {
+    let mut doc = SearchDocument {
+        text: vec![log.site.clone()],
+        tags: Vec::new(),
+    };
+    doc.push(log.notes.as_ref());
+    doc
+}
+
+pub fn supplier_document(supplier: &Supplier) -> SearchDocument {
+    let mut doc = SearchDocument {
+        text: vec![supplier.name.clone()],
+        tags: Vec::new(),
+    };
+    doc.push(supplier.website.as_ref());
+    doc.push(supplier.contact_email.as_ref());
+    doc.push(supplier.notes.as_ref());
+    doc
+}
+
+pub fn inventory_document(item: &InventoryItem) -> SearchDocument {
+    let mut doc = SearchDocument::default();
+    doc.push(item.vial_number.as_ref());
+    doc.push(item.batch_number.as_ref());
+    doc.push(item.lot_number.as_ref());
+    doc.push(item.notes.as_ref());
+    doc
+}
+
+pub fn summary_document(summary: &SummaryHistory) -> SearchDocument {
+    SearchDocument {
+        text: vec![summary.title.clone(), summary.summary_output.clone()],
+        tags: Vec::new(),
+    }
+}
+
+pub fn alert_document(alert: &Alert) -> SearchDocument {
+    SearchDocument {
+        text: vec![alert.title.clone(), alert.message.clone()],
+        tags: Vec::new(),
+    }
+}
+
+pub fn journal_document(entry: &JournalEntry) -> SearchDocument {
+    let mut doc = SearchDocument {
+        text: vec![strip_links(&entry.body)],
+        tags: entry.tags.clone(),
+    };
+    doc.push(entry.title.as_ref());
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexes_words_prefixes_and_tags() {
+        let mut protocol = PeptideProtocol::new("Evening GH", "Ipamorelin");
+        protocol.tags = vec!["Sleep-Support".to_string()];
+
+        let terms = protocol_document(&protocol).terms();
+
+        assert!(terms.contains(&"ipamorelin".to_string()));
+        assert!(terms.contains(&"ipa".to_string()));
+        assert!(terms.contains(&"gh".to_string()));
+        assert!(terms.contains(&"#sleepsupport".to_string()));
+        assert!(terms.contains(&"#sleep".to_string()));
+        assert!(!terms.contains(&"e".to_string()));
+    }
+
+    #[test]
+    fn splits_lot_numbers_into_searchable_parts() {
+        let mut item = InventoryItem::new("protocol-1");
+        item.lot_number = Some("BPC-2024-0117".to_string());
+
+        let terms = inventory_document(&item).terms();
+
+        assert!(terms.contains(&"bpc".to_string()));
+        assert!(terms.contains(&"0117".to_string()));
+    }
+
+    #[test]
+    fn query_terms_separate_tags_from_words() {
+        assert_eq!(
+            query_terms("BPC  #Recovery tag:weight-loss x"),
+            vec!["#recovery", "#weightloss", "bpc"]
+        );
+        assert!(query_terms("  ").is_empty());
+    }
+
+    #[test]
+    fn long_query_words_match_truncated_prefixes() {
+        let doc = SearchDocument {
+            text: vec!["thymosinbetafourfragmentacetate".to_string()],
+            tags: Vec::new(),
+        };
+        let query = query_terms("thymosinbetafourfragmentacetate");
+
+        assert!(doc.terms().contains(&query[0]));
+    }
+}
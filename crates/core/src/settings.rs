@@ -0,0 +1,65 @@
+//! Typed app settings
+//!
+//! Settings are kept encrypted in the `settings` table, one JSON value per
+//! namespaced key such as `trash.settings`. Each settings type implements
+//! [`Setting`], which names its key and checks a value before it's saved;
+//! loading a value also parses it into the type, so a stored value always
+//! matches its schema.
+//!
+//! Settings used to be saved as JSON files next to the database. A type that
+//! names its old file in [`Setting::LEGACY_FILE`] imports it the first time
+//! it's loaded, and the file is renamed to `<name>.migrated`. Settings needed
+//! before the database is unlocked, such as the passphrase configuration,
+//! stay in their own files.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Suffix added to a legacy settings file once it has been imported
+pub const MIGRATED_SUFFIX: &str = ".migrated";
+
+/// A value stored in the `settings` table
+pub trait Setting: Serialize + DeserializeOwned {
+    /// Namespaced key, e.g. `backup.schedule`
+    const KEY: &'static str;
+    /// JSON file in the data directory this setting was saved in before
+    const LEGACY_FILE: Option<&'static str> = None;
+
+    /// Check a value before it's saved
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Check `key` is a namespace and a name, e.g. `units.preferences`
+///
+/// Each part is lowercase letters, digits and underscores.
+pub fn validate_key(key: &str) -> Result<(), String> {
+    let parts: Vec<&str> = key.split('.').collect();
+    let valid = parts.len() >= 2
+        && parts.iter().all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid setting key '{}'; expected e.g. 'namespace.name'", key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_need_a_namespace() {
+        assert!(validate_key("backup.schedule").is_ok());
+        assert!(validate_key("drive.oauth_config").is_ok());
+        assert!(validate_key("schedule").is_err());
+        assert!(validate_key("Backup.Schedule").is_err());
+        assert!(validate_key("backup..schedule").is_err());
+    }
+}
@@ -0,0 +1,100 @@
+//! First-run setup progress
+//!
+//! The onboarding wizard walks through a fixed list of [`SetupStep`]s. Each
+//! one is recorded as done or skipped as soon as it happens, so a wizard
+//! closed halfway through picks up at the first step that is neither.
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::settings::Setting;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum SetupStep {
+    /// Add a protocol for each peptide in the built-in catalog
+    DefaultPeptides,
+    /// Create the user's first protocol from a catalog template
+    FirstProtocol,
+    /// Turn on scheduled backups
+    BackupSchedule,
+}
+
+impl SetupStep {
+    /// Every step, in wizard order
+    pub const ALL: [SetupStep; 3] = [SetupStep::DefaultPeptides, SetupStep::FirstProtocol, SetupStep::BackupSchedule];
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SetupStatus {
+    pub completed_steps: Vec<SetupStep>,
+    pub skipped_steps: Vec<SetupStep>,
+    /// Protocol created by [`SetupStep::FirstProtocol`]
+    pub first_protocol_id: Option<String>,
+    /// Set once every step is done or skipped
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub completed_at: Option<OffsetDateTime>,
+}
+
+impl SetupStatus {
+    pub fn is_done(&self, step: SetupStep) -> bool {
+        self.completed_steps.contains(&step) || self.skipped_steps.contains(&step)
+    }
+
+    /// First step that is neither done nor skipped
+    pub fn next_step(&self) -> Option<SetupStep> {
+        SetupStep::ALL.into_iter().find(|step| !self.is_done(*step))
+    }
+
+    pub fn complete(&mut self, step: SetupStep, now: OffsetDateTime) {
+        self.skipped_steps.retain(|skipped| *skipped != step);
+        if !self.completed_steps.contains(&step) {
+            self.completed_steps.push(step);
+        }
+        self.finish_if_done(now);
+    }
+
+    /// Skipping a step that is already done leaves it done
+    pub fn skip(&mut self, step: SetupStep, now: OffsetDateTime) {
+        if !self.is_done(step) {
+            self.skipped_steps.push(step);
+        }
+        self.finish_if_done(now);
+    }
+
+    fn finish_if_done(&mut self, now: OffsetDateTime) {
+        if self.completed_at.is_none() && self.next_step().is_none() {
+            self.completed_at = Some(now);
+        }
+    }
+}
+
+impl Setting for SetupStatus {
+    const KEY: &'static str = "setup.status";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn setup_resumes_at_the_first_open_step() {
+        let now = datetime!(2024-03-01 9:00 UTC);
+        let mut status = SetupStatus::default();
+        assert_eq!(status.next_step(), Some(SetupStep::DefaultPeptides));
+
+        status.complete(SetupStep::FirstProtocol, now);
+        assert_eq!(status.next_step(), Some(SetupStep::DefaultPeptides));
+        status.skip(SetupStep::DefaultPeptides, now);
+        status.skip(SetupStep::FirstProtocol, now);
+        assert_eq!(status.completed_steps, vec![SetupStep::FirstProtocol]);
+        assert_eq!(status.next_step(), Some(SetupStep::BackupSchedule));
+        assert_eq!(status.completed_at, None);
+
+        status.complete(SetupStep::BackupSchedule, now);
+        assert_eq!(status.next_step(), None);
+        assert_eq!(status.completed_at, Some(now));
+    }
+}
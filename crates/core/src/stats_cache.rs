@@ -0,0 +1,106 @@
+//! Cached dashboard statistics
+//!
+//! Dashboard stats are computed from every dose, vial or order, so the
+//! results are kept in the `stats_cache` table between page loads. Each stat
+//! lists the tables it is computed from; writes to those tables through
+//! [`StorageManager`](crate::StorageManager) drop the cached value. Values
+//! older than [`MAX_STAT_AGE`] are recomputed anyway, since every stat also
+//! depends on today's date.
+
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+
+/// Longest a cached stat is served without being recomputed
+pub const MAX_STAT_AGE: Duration = Duration::hours(1);
+
+/// A statistic shown on the dashboard
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DashboardStat {
+    DosesThisWeek,
+    Adherence,
+    Spend,
+    InventoryForecast,
+    Goals,
+}
+
+impl DashboardStat {
+    pub const ALL: [DashboardStat; 5] = [
+        DashboardStat::DosesThisWeek,
+        DashboardStat::Adherence,
+        DashboardStat::Spend,
+        DashboardStat::InventoryForecast,
+        DashboardStat::Goals,
+    ];
+
+    /// Key of the stat's row in `stats_cache`
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            DashboardStat::DosesThisWeek => "doses_this_week",
+            DashboardStat::Adherence => "adherence",
+            DashboardStat::Spend => "spend",
+            DashboardStat::InventoryForecast => "inventory_forecast",
+            DashboardStat::Goals => "goals",
+        }
+    }
+
+    /// Tables the stat is computed from
+    pub(crate) fn depends_on(self) -> &'static [&'static str] {
+        match self {
+            DashboardStat::DosesThisWeek => &["dose_logs"],
+            DashboardStat::Adherence => &["dose_logs", "dose_schedules", "dose_skips", "protocols"],
+            DashboardStat::Spend => &[
+                "dose_logs",
+                "exchange_rates",
+                "inventory",
+                "orders",
+                "price_history",
+                "protocols",
+                "suppliers",
+            ],
+            DashboardStat::InventoryForecast => {
+                &["dose_logs", "dose_schedules", "inventory", "protocols"]
+            }
+            DashboardStat::Goals => &["body_metrics", "goals", "lab_results"],
+        }
+    }
+
+    /// Stats computed from `table`
+    pub(crate) fn affected_by(table: &str) -> impl Iterator<Item = DashboardStat> + '_ {
+        Self::ALL
+            .into_iter()
+            .filter(move |stat| stat.depends_on().contains(&table))
+    }
+}
+
+/// A cached stat and when it was computed
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedStat<T> {
+    pub value: T,
+    pub computed_at: OffsetDateTime,
+}
+
+/// Snapshot of cache invalidations, taken before computing a stat
+///
+/// A stat computed while a write was in progress may already be out of date,
+/// so [`StorageManager::store_stat`](crate::StorageManager::store_stat) only
+/// saves it if nothing was invalidated since the snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatsGeneration(pub(crate) u64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_affected_stats() {
+        let affected: Vec<_> = DashboardStat::affected_by("inventory").collect();
+        assert_eq!(
+            affected,
+            vec![DashboardStat::Spend, DashboardStat::InventoryForecast]
+        );
+        assert_eq!(DashboardStat::affected_by("dose_logs").count(), 4);
+        assert_eq!(DashboardStat::affected_by("body_metrics").count(), 1);
+        assert_eq!(DashboardStat::affected_by("side_effects").count(), 0);
+    }
+}
@@ -0,0 +1,189 @@
+//! Line diff between two saved summaries
+//!
+//! Re-summarizing a paper with another model or prompt keeps the earlier
+//! versions in the summary history. This compares two of them line by line
+//! so the changes can be shown side by side.
+
+use serde::Serialize;
+
+use crate::models::SummaryHistory;
+
+/// Summaries longer than this many lines on both sides are compared as
+/// one removed and one added block instead of line by line
+const MAX_DIFF_LINES: usize = 2_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffOp {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// The version a diff side comes from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryVersion {
+    pub summary_id: String,
+    pub provider: String,
+    pub format: String,
+    pub created_at: String,
+}
+
+impl From<&SummaryHistory> for SummaryVersion {
+    fn from(summary: &SummaryHistory) -> Self {
+        Self {
+            summary_id: summary.id.clone(),
+            provider: summary.provider.clone(),
+            format: summary.format.clone(),
+            created_at: summary.created_at.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryDiff {
+    pub old: SummaryVersion,
+    pub new: SummaryVersion,
+    /// Whether both summaries were written from the same source
+    pub same_source: bool,
+    pub lines: Vec<DiffLine>,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Compare the output of `old` with that of `new`
+pub fn diff_summaries(old: &SummaryHistory, new: &SummaryHistory) -> SummaryDiff {
+    let lines = diff_lines(&old.summary_output, &new.summary_output);
+    SummaryDiff {
+        old: old.into(),
+        new: new.into(),
+        same_source: old.source_id == new.source_id,
+        added: lines.iter().filter(|line| line.op == DiffOp::Added).count(),
+        removed: lines.iter().filter(|line| line.op == DiffOp::Removed).count(),
+        lines,
+    }
+}
+
+/// Longest-common-subsequence line diff of `old` and `new`
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let line = |op, text: &str| DiffLine {
+        op,
+        text: text.to_string(),
+    };
+
+    // Skip the common head and tail so the table only covers what changed
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut result: Vec<DiffLine> = old[..prefix]
+        .iter()
+        .map(|text| line(DiffOp::Unchanged, text))
+        .collect();
+
+    if old_mid.len().min(new_mid.len()) > MAX_DIFF_LINES {
+        result.extend(old_mid.iter().map(|text| line(DiffOp::Removed, text)));
+        result.extend(new_mid.iter().map(|text| line(DiffOp::Added, text)));
+    } else {
+        // lcs[i][j] = common lines of old_mid[i..] and new_mid[j..]
+        let mut lcs = vec![vec![0usize; new_mid.len() + 1]; old_mid.len() + 1];
+        for i in (0..old_mid.len()).rev() {
+            for j in (0..new_mid.len()).rev() {
+                lcs[i][j] = if old_mid[i] == new_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < old_mid.len() && j < new_mid.len() {
+            if old_mid[i] == new_mid[j] {
+                result.push(line(DiffOp::Unchanged, old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                result.push(line(DiffOp::Removed, old_mid[i]));
+                i += 1;
+            } else {
+                result.push(line(DiffOp::Added, new_mid[j]));
+                j += 1;
+            }
+        }
+        result.extend(old_mid[i..].iter().map(|text| line(DiffOp::Removed, text)));
+        result.extend(new_mid[j..].iter().map(|text| line(DiffOp::Added, text)));
+    }
+
+    result.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|text| line(DiffOp::Unchanged, text)),
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ops(lines: &[DiffLine]) -> Vec<(DiffOp, &str)> {
+        lines.iter().map(|line| (line.op, line.text.as_str())).collect()
+    }
+
+    #[test]
+    fn diff_marks_changed_lines() {
+        let lines = diff_lines("# Summary\nDose 250mcg\nSafe", "# Summary\nDose 500mcg\nSafe\nNew note");
+
+        assert_eq!(
+            ops(&lines),
+            vec![
+                (DiffOp::Unchanged, "# Summary"),
+                (DiffOp::Removed, "Dose 250mcg"),
+                (DiffOp::Added, "Dose 500mcg"),
+                (DiffOp::Unchanged, "Safe"),
+                (DiffOp::Added, "New note"),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_of_identical_text_is_unchanged() {
+        let lines = diff_lines("a\nb", "a\nb");
+        assert!(lines.iter().all(|line| line.op == DiffOp::Unchanged));
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn diff_summaries_counts_changes_and_compares_sources() {
+        let old = SummaryHistory::new("Paper", "Abstract", "a\nb\nc", "markdown", "codex");
+        let mut new = SummaryHistory::new("Paper", "Abstract", "a\nc\nd\ne", "markdown", "claude");
+
+        let diff = diff_summaries(&old, &new);
+        assert!(diff.same_source);
+        assert_eq!((diff.removed, diff.added), (1, 2));
+        assert_eq!(diff.old.provider, "codex");
+        assert_eq!(diff.new.summary_id, new.id);
+
+        new.source_id = "entry-1".to_string();
+        assert!(!diff_summaries(&old, &new).same_source);
+    }
+}
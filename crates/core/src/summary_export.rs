@@ -0,0 +1,252 @@
+//! Markdown export of the summary history
+//!
+//! Each summary is written as its own Markdown file with YAML front matter
+//! (provider, date, source link), which note apps like Obsidian index as
+//! properties. A manifest in the export folder records what was written,
+//! so an incremental export only rewrites summaries that are new or have
+//! changed since, and leaves notes edited in the vault alone otherwise.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+
+use crate::models::{LiteratureEmbedding, LiteratureEntry, SummaryHistory};
+
+/// Written to the export folder next to the Markdown files
+pub const MANIFEST_FILE_NAME: &str = ".peptrack-summaries.json";
+
+/// Longest title kept in a file name
+const MAX_FILE_TITLE_CHARS: usize = 80;
+
+/// What an export wrote
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkdownExportResult {
+    /// Files written, relative to the export folder
+    pub written: Vec<String>,
+    /// Summaries skipped because their file is up to date
+    pub unchanged: usize,
+}
+
+/// A file written by an earlier export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ManifestEntry {
+    file_name: String,
+    content_hash: String,
+}
+
+/// Summary id to the file last written for it
+type Manifest = BTreeMap<String, ManifestEntry>;
+
+/// Write `summaries` into `dir` as Markdown files
+///
+/// Sources are looked up in `literature` by the summary's source id. With
+/// `incremental`, summaries whose rendered file hasn't changed since the
+/// last export are skipped.
+pub fn export_summaries_markdown(
+    dir: &Path,
+    summaries: &[SummaryHistory],
+    literature: &[LiteratureEntry],
+    incremental: bool,
+) -> Result<MarkdownExportResult> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create export folder {}", dir.display()))?;
+
+    let manifest_path = dir.join(MANIFEST_FILE_NAME);
+    let mut manifest: Manifest = match std::fs::read(&manifest_path) {
+        Ok(json) => serde_json::from_slice(&json).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable export manifest: {}", e);
+            Manifest::new()
+        }),
+        Err(_) => Manifest::new(),
+    };
+
+    let entries: HashMap<&str, &LiteratureEntry> =
+        literature.iter().map(|entry| (entry.id.as_str(), entry)).collect();
+
+    let mut result = MarkdownExportResult::default();
+    for summary in summaries {
+        let source = entries.get(summary.source_id.as_str()).copied();
+        let markdown = summary_markdown(summary, source);
+        let content_hash = LiteratureEmbedding::content_hash(&markdown);
+        let file_name = summary_file_name(summary);
+
+        let up_to_date = manifest.get(&summary.id).is_some_and(|previous| {
+            previous.content_hash == content_hash && dir.join(&previous.file_name).exists()
+        });
+        if incremental && up_to_date {
+            result.unchanged += 1;
+            continue;
+        }
+
+        std::fs::write(dir.join(&file_name), &markdown)
+            .with_context(|| format!("Failed to write {}", file_name))?;
+        manifest.insert(
+            summary.id.clone(),
+            ManifestEntry {
+                file_name: file_name.clone(),
+                content_hash,
+            },
+        );
+        result.written.push(file_name);
+    }
+
+    let json = serde_json::to_vec_pretty(&manifest).context("Failed to serialize export manifest")?;
+    std::fs::write(&manifest_path, json).context("Failed to write export manifest")?;
+    Ok(result)
+}
+
+/// Stable file name for a summary: date, title and a short id, e.g.
+/// `2025-03-01 BPC-157 healing (1a2b3c4d).md`
+pub fn summary_file_name(summary: &SummaryHistory) -> String {
+    let title: String = summary
+        .title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' | '^' | '[' | ']' => ' ',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .take(MAX_FILE_TITLE_CHARS)
+        .collect();
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    let title = if title.is_empty() { "Summary".to_string() } else { title };
+    let short_id: String = summary.id.chars().filter(|c| *c != '-').take(8).collect();
+
+    format!("{} {} ({}).md", summary.created_at.date(), title, short_id)
+}
+
+/// Render a summary as Markdown with YAML front matter
+pub fn summary_markdown(summary: &SummaryHistory, source: Option<&LiteratureEntry>) -> String {
+    let created = summary
+        .created_at
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| summary.created_at.to_string());
+
+    let mut out = String::from("---\n");
+    front_matter(&mut out, "title", &summary.title);
+    front_matter(&mut out, "summary_id", &summary.id);
+    front_matter(&mut out, "provider", &summary.provider);
+    front_matter(&mut out, "format", &summary.format);
+    front_matter(&mut out, "created", &created);
+    front_matter(&mut out, "source_id", &summary.source_id);
+    if let Some(entry) = source {
+        front_matter(&mut out, "source_title", &entry.title);
+        if let Some(link) = source_link(entry) {
+            front_matter(&mut out, "source", &link);
+        }
+        if let Some(doi) = &entry.doi {
+            front_matter(&mut out, "doi", doi);
+        }
+    }
+    if let Some(usage) = &summary.usage {
+        out.push_str(&format!("cost_usd: {:.4}\n", usage.cost_usd));
+    }
+    out.push_str("tags:\n  - peptrack/summary\n---\n\n");
+
+    out.push_str(&format!("# {}\n\n", summary.title.trim()));
+    for notice in &summary.notices {
+        out.push_str(&format!("> [!warning]\n> {}\n\n", notice.replace('\n', "\n> ")));
+    }
+    if let Some((entry, link)) = source.and_then(|entry| Some((entry, source_link(entry)?))) {
+        out.push_str(&format!("Source: [{}]({})\n\n", entry.title.trim(), link));
+    }
+
+    let output = summary.summary_output.trim();
+    if summary.format.eq_ignore_ascii_case("json") {
+        out.push_str(&format!("```json\n{}\n```\n", output));
+    } else {
+        out.push_str(output);
+        out.push('\n');
+    }
+    out
+}
+
+fn source_link(entry: &LiteratureEntry) -> Option<String> {
+    entry
+        .url
+        .clone()
+        .or_else(|| entry.doi.as_ref().map(|doi| format!("https://doi.org/{}", doi)))
+}
+
+/// Append `key: "value"` as a double-quoted YAML scalar
+fn front_matter(out: &mut String, key: &str, value: &str) {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "");
+    out.push_str(&format!("{}: \"{}\"\n", key, escaped));
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn summary(title: &str, output: &str) -> SummaryHistory {
+        let mut summary = SummaryHistory::new(title, "Abstract", output, "markdown", "claude");
+        summary.created_at = datetime!(2025-03-01 12:30 UTC);
+        summary
+    }
+
+    #[test]
+    fn markdown_has_front_matter_and_source_link() {
+        let mut entry = LiteratureEntry::new("pubmed", "BPC-157 and \"tendon\" healing");
+        entry.doi = Some("10.1000/bpc".into());
+        let mut summary = summary("Tendon study", "## Findings\nFaster healing");
+        summary.source_id = entry.id.clone();
+        summary.notices.push("Retracted".into());
+
+        let markdown = summary_markdown(&summary, Some(&entry));
+
+        assert!(markdown.starts_with("---\ntitle: \"Tendon study\"\n"));
+        assert!(markdown.contains("provider: \"claude\"\n"));
+        assert!(markdown.contains("created: \"2025-03-01T12:30:00Z\"\n"));
+        assert!(markdown.contains("source_title: \"BPC-157 and \\\"tendon\\\" healing\"\n"));
+        assert!(markdown.contains("source: \"https://doi.org/10.1000/bpc\"\n"));
+        assert!(markdown.contains("> [!warning]\n> Retracted\n"));
+        assert!(markdown.ends_with("## Findings\nFaster healing\n"));
+    }
+
+    #[test]
+    fn file_name_is_stable_and_safe() {
+        let summary = summary("BPC-157: dosing / safety?", "Output");
+        let name = summary_file_name(&summary);
+
+        assert!(name.starts_with("2025-03-01 BPC-157 dosing safety ("));
+        assert!(name.ends_with(").md"));
+        assert_eq!(name, summary_file_name(&summary));
+    }
+
+    #[test]
+    fn incremental_export_only_writes_new_or_changed_summaries() {
+        let dir = tempdir().unwrap();
+        let first = summary("First", "One");
+        let mut second = summary("Second", "Two");
+
+        let result = export_summaries_markdown(dir.path(), &[first.clone(), second.clone()], &[], true).unwrap();
+        assert_eq!(result.written.len(), 2);
+        assert_eq!(result.unchanged, 0);
+
+        second.notices.push("Corrected".into());
+        let third = summary("Third", "Three");
+        let result =
+            export_summaries_markdown(dir.path(), &[first.clone(), second.clone(), third.clone()], &[], true)
+                .unwrap();
+        assert_eq!(
+            result.written,
+            vec![summary_file_name(&second), summary_file_name(&third)]
+        );
+        assert_eq!(result.unchanged, 1);
+
+        let result = export_summaries_markdown(dir.path(), &[first, second, third], &[], false).unwrap();
+        assert_eq!(result.written.len(), 3);
+        assert!(dir.path().join(MANIFEST_FILE_NAME).exists());
+    }
+}
@@ -0,0 +1,148 @@
+//! Supplier rankings from review ratings and latest prices
+//!
+//! Each supplier gets a score from 0 to 100. Without prices it's just the
+//! overall rating scaled to 100. When comparing prices for a peptide, only
+//! suppliers with a price are ranked, and the cheapest counts as much as a
+//! top rating would: the score is 60% rating and 40% price, where the
+//! lowest price scores full marks and the highest none. Suppliers nobody
+//! has reviewed yet are treated as rated 3 out of 5.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Supplier, SupplierRating};
+
+const RATING_WEIGHT: f32 = 0.6;
+const PRICE_WEIGHT: f32 = 0.4;
+/// Overall rating assumed for suppliers without reviews
+const UNRATED_OVERALL: f32 = 3.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SupplierRanking {
+    pub supplier_id: String,
+    pub supplier_name: String,
+    pub rating: Option<SupplierRating>,
+    /// Latest price per mg in the comparison currency, when comparing prices
+    pub cost_per_mg: Option<f32>,
+    /// 0 to 100, higher is better
+    pub score: f32,
+}
+
+/// Rank `suppliers`, best first
+///
+/// `prices` maps supplier ids to their latest price per mg; with `None`
+/// suppliers are ranked on their ratings alone.
+pub fn rank_suppliers(suppliers: &[Supplier], prices: Option<&HashMap<String, f32>>) -> Vec<SupplierRanking> {
+    let (lowest, highest) = prices
+        .map(|prices| {
+            prices
+                .values()
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &price| (lo.min(price), hi.max(price)))
+        })
+        .unwrap_or((0.0, 0.0));
+
+    let mut rankings: Vec<SupplierRanking> = suppliers
+        .iter()
+        .filter_map(|supplier| {
+            let rating = supplier.rating();
+            let rating_score = (rating.map_or(UNRATED_OVERALL, |r| r.overall) - 1.0) / 4.0;
+            let (cost_per_mg, score) = match prices {
+                None => (None, rating_score),
+                Some(prices) => {
+                    let price = *prices.get(&supplier.id)?;
+                    let price_score = if highest > lowest {
+                        (highest - price) / (highest - lowest)
+                    } else {
+                        1.0
+                    };
+                    (Some(price), RATING_WEIGHT * rating_score + PRICE_WEIGHT * price_score)
+                }
+            };
+            Some(SupplierRanking {
+                supplier_id: supplier.id.clone(),
+                supplier_name: supplier.name.clone(),
+                rating,
+                cost_per_mg,
+                score: (score * 100.0).round(),
+            })
+        })
+        .collect();
+
+    rankings.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.supplier_name.to_lowercase().cmp(&b.supplier_name.to_lowercase()))
+    });
+    rankings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SupplierReview;
+
+    fn supplier(name: &str, ratings: &[(u8, u8, u8)]) -> Supplier {
+        let mut supplier = Supplier::new(name);
+        supplier.id = name.to_string();
+        for &(shipping, quality, communication) in ratings {
+            supplier.add_review(SupplierReview::new(shipping, quality, communication));
+        }
+        supplier
+    }
+
+    #[test]
+    fn ratings_average_every_review() {
+        let acme = supplier("Acme", &[(5, 4, 3), (3, 4, 5)]);
+        let rating = acme.rating().expect("rating");
+        assert_eq!(rating.shipping_speed, 4.0);
+        assert_eq!(rating.overall, 4.0);
+        assert_eq!(rating.review_count, 2);
+        assert!(supplier("New", &[]).rating().is_none());
+        assert!(SupplierReview::new(0, 3, 3).validate().is_err());
+    }
+
+    #[test]
+    fn reviewing_an_order_again_replaces_the_review() {
+        let mut acme = supplier("Acme", &[]);
+        let mut first = SupplierReview::new(2, 2, 2);
+        first.order_id = Some("order-1".to_string());
+        acme.add_review(first);
+        let mut second = SupplierReview::new(5, 5, 5);
+        second.order_id = Some("order-1".to_string());
+        acme.add_review(second);
+        acme.add_review(SupplierReview::new(3, 3, 3));
+
+        assert_eq!(acme.reviews.len(), 2);
+        assert_eq!(acme.rating().expect("rating").overall, 4.0);
+    }
+
+    #[test]
+    fn rankings_weigh_ratings_and_prices() {
+        let suppliers = vec![
+            supplier("Cheap", &[(2, 2, 2)]),
+            supplier("Great", &[(5, 5, 5)]),
+            supplier("Unrated", &[]),
+            supplier("NoPrice", &[(5, 5, 5)]),
+        ];
+
+        let by_rating = rank_suppliers(&suppliers, None);
+        assert_eq!(by_rating.len(), 4);
+        assert_eq!(by_rating[0].supplier_name, "Great");
+        assert_eq!(by_rating[0].score, 100.0);
+        assert_eq!(by_rating[2].supplier_name, "Unrated");
+        assert_eq!(by_rating[2].score, 50.0);
+
+        let prices = HashMap::from([
+            ("Cheap".to_string(), 1.0),
+            ("Great".to_string(), 3.0),
+            ("Unrated".to_string(), 2.0),
+        ]);
+        let by_price = rank_suppliers(&suppliers, Some(&prices));
+        let names: Vec<&str> = by_price.iter().map(|r| r.supplier_name.as_str()).collect();
+        // Great: 60, Cheap: 15 + 40, Unrated: 30 + 20
+        assert_eq!(names, vec!["Great", "Cheap", "Unrated"]);
+        assert_eq!(by_price[1].score, 55.0);
+        assert_eq!(by_price[1].cost_per_mg, Some(1.0));
+    }
+}
@@ -0,0 +1,418 @@
+//! Tamper evidence for stored records
+//!
+//! With tamper evidence on, every insert, update and delete the app makes
+//! to a record table appends an entry to `tamper_chain`: a digest of the
+//! row's encrypted columns, and an HMAC over it chained to the previous
+//! entry's HMAC for that table. Entries are written by temporary triggers
+//! that only exist on the app's own write connection, with an HMAC key kept
+//! sealed with the database key, so a change made with any other SQLite
+//! client leaves no entry and can't forge one.
+//!
+//! Removing a table's newest entries together with their records would
+//! leave a shorter chain that still links up, so `tamper_head` keeps an HMAC
+//! of each table's last entry. Every table gets a head when tamper evidence
+//! is turned on, which a trigger moves on with each entry.
+//!
+//! The HMAC key lives in the database, so deleting it would otherwise look
+//! like tamper evidence was never turned on. A marker file next to the
+//! database records that it's on, and a missing key is reported as tampering.
+//!
+//! [`StorageManager::verify_tamper_chain`](crate::StorageManager::verify_tamper_chain)
+//! replays each table's chain, checks it ends at its head, and compares the
+//! last digest of every record with the row as stored now.
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+use zeroize::Zeroizing;
+
+use crate::mac::hmac_sha256;
+
+/// Tables whose records are chained; caches and settings change too often
+/// to be worth it and are left out
+pub const CHAINED_TABLES: &[&str] = &[
+    "protocols",
+    "dose_logs",
+    "dose_log_corrections",
+    "suppliers",
+    "inventory",
+    "orders",
+    "attachments",
+    "body_metrics",
+    "side_effects",
+    "lab_results",
+    "journal_entries",
+    "goals",
+];
+
+/// How a record differs from what the app last wrote
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TamperChange {
+    /// The record isn't in the chain, or the app last deleted it
+    Added,
+    Modified,
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TamperFinding {
+    pub table: String,
+    pub record_id: String,
+    pub change: TamperChange,
+}
+
+/// A chain entry whose HMAC doesn't match: the entry was edited, or one
+/// before it removed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenLink {
+    pub table: String,
+    pub seq: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TamperReport {
+    #[serde(with = "time::serde::rfc3339")]
+    pub verified_at: OffsetDateTime,
+    pub entries_checked: usize,
+    pub records_checked: usize,
+    pub broken_links: Vec<BrokenLink>,
+    /// Tables whose chain doesn't end at its head: the newest entries were
+    /// removed, or the head edited
+    pub truncated_tables: Vec<String>,
+    pub findings: Vec<TamperFinding>,
+    /// The HMAC key was deleted from the database, so nothing could be checked
+    pub key_removed: bool,
+}
+
+impl TamperReport {
+    /// Whether nothing was changed outside the app
+    pub fn is_intact(&self) -> bool {
+        !self.key_removed
+            && self.broken_links.is_empty()
+            && self.truncated_tables.is_empty()
+            && self.findings.is_empty()
+    }
+}
+
+/// Marker file kept next to `db_path` while tamper evidence is on
+pub(crate) fn marker_path(db_path: &Path) -> PathBuf {
+    let mut name = db_path.as_os_str().to_owned();
+    name.push(".tamper.json");
+    PathBuf::from(name)
+}
+
+/// Contents of the marker file
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TamperMarker {
+    #[serde(with = "time::serde::rfc3339")]
+    pub enabled_at: OffsetDateTime,
+}
+
+/// Digest of a row's encrypted column values
+pub(crate) fn row_digest<'a>(values: impl IntoIterator<Item = ValueRef<'a>>) -> String {
+    let mut hasher = Sha256::new();
+    for value in values {
+        match value {
+            ValueRef::Null => hasher.update([0]),
+            ValueRef::Integer(i) => {
+                hasher.update([1]);
+                hasher.update(i.to_be_bytes());
+            }
+            ValueRef::Real(f) => {
+                hasher.update([2]);
+                hasher.update(f.to_bits().to_be_bytes());
+            }
+            ValueRef::Text(bytes) | ValueRef::Blob(bytes) => {
+                hasher.update([if matches!(value, ValueRef::Text(_)) { 3 } else { 4 }]);
+                hasher.update((bytes.len() as u64).to_be_bytes());
+                hasher.update(bytes);
+            }
+        }
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// HMAC of one chain entry; `digest` is `None` for a delete and `previous`
+/// for the first entry of a table
+pub(crate) fn chain_mac(
+    key: &[u8],
+    table: &str,
+    record_id: &str,
+    digest: Option<&str>,
+    previous: Option<&str>,
+) -> String {
+    let message = format!(
+        "{}\n{}\n{}\n{}",
+        table,
+        record_id,
+        digest.unwrap_or("-"),
+        previous.unwrap_or("-")
+    );
+    hex::encode(hmac_sha256(key, message.as_bytes()))
+}
+
+/// HMAC of a table's chain head: the last entry's `seq` and HMAC, or 0 and
+/// `None` while the table has no entries
+pub(crate) fn head_mac(key: &[u8], table: &str, seq: i64, mac: Option<&str>) -> String {
+    let message = format!("head:{}\n{}\n{}", table, seq, mac.unwrap_or("-"));
+    hex::encode(hmac_sha256(key, message.as_bytes()))
+}
+
+/// Register the chain functions on `conn` and add a temporary trigger for
+/// each write to the tables in `columns`, given as `(table, encrypted columns)`
+pub(crate) fn install(
+    conn: &Connection,
+    key: Zeroizing<Vec<u8>>,
+    columns: &[(&str, Vec<&str>)],
+) -> rusqlite::Result<()> {
+    conn.create_scalar_function(
+        "tamper_digest",
+        -1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| Ok(row_digest((0..ctx.len()).map(|i| ctx.get_raw(i)))),
+    )?;
+    let chain_key = key.clone();
+    conn.create_scalar_function(
+        "tamper_head_mac",
+        3,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        move |ctx| {
+            let table: String = ctx.get(0)?;
+            let seq: i64 = ctx.get(1)?;
+            let mac: String = ctx.get(2)?;
+            Ok(head_mac(&key, &table, seq, Some(&mac)))
+        },
+    )?;
+    conn.create_scalar_function(
+        "tamper_mac",
+        4,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        move |ctx| {
+            let table: String = ctx.get(0)?;
+            let record_id: String = ctx.get(1)?;
+            let digest: Option<String> = ctx.get(2)?;
+            let previous: Option<String> = ctx.get(3)?;
+            Ok(chain_mac(&chain_key, &table, &record_id, digest.as_deref(), previous.as_deref()))
+        },
+    )?;
+    conn.execute_batch(
+        "CREATE TEMP TRIGGER IF NOT EXISTS tamper_head_advance AFTER INSERT ON main.tamper_chain
+         BEGIN
+             UPDATE tamper_head
+             SET seq = NEW.seq, mac = tamper_head_mac(NEW.table_name, NEW.seq, NEW.mac)
+             WHERE table_name = NEW.table_name;
+         END;",
+    )?;
+
+    for (table, encrypted) in columns {
+        let digest = |row: &str| {
+            let values: Vec<String> = encrypted.iter().map(|column| format!("{}.{}", row, column)).collect();
+            format!("tamper_digest({})", values.join(", "))
+        };
+        let previous = format!(
+            "(SELECT mac FROM tamper_chain WHERE table_name = '{}' ORDER BY seq DESC LIMIT 1)",
+            table
+        );
+        let append = |event: &str, row: &str, digest: &str| {
+            format!(
+                "CREATE TEMP TRIGGER IF NOT EXISTS tamper_{table}_{event} AFTER {upper} ON main.{table}
+                 BEGIN
+                     INSERT INTO tamper_chain (table_name, record_id, digest, mac)
+                     VALUES ('{table}', {row}.id, {digest},
+                             tamper_mac('{table}', {row}.id, {digest}, {previous}));
+                 END;",
+                upper = event.to_uppercase(),
+            )
+        };
+        conn.execute_batch(&append("insert", "NEW", &digest("NEW")))?;
+        conn.execute_batch(&append("update", "NEW", &digest("NEW")))?;
+        conn.execute_batch(&append("delete", "OLD", "NULL"))?;
+    }
+    Ok(())
+}
+
+/// Drop the triggers [`install`] added, so writes are no longer chained
+pub(crate) fn uninstall(conn: &Connection) -> rusqlite::Result<()> {
+    for table in CHAINED_TABLES {
+        for event in ["insert", "update", "delete"] {
+            conn.execute_batch(&format!("DROP TRIGGER IF EXISTS temp.tamper_{}_{};", table, event))?;
+        }
+    }
+    conn.execute_batch("DROP TRIGGER IF EXISTS temp.tamper_head_advance;")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{StorageConfig, StorageManager};
+    use crate::encryption::StaticKeyProvider;
+    use crate::models::{DoseLog, PeptideProtocol};
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn verify_finds_changes_made_outside_the_app() {
+        let dir = tempdir().unwrap();
+        let storage = StorageManager::new(StorageConfig {
+            data_dir: Some(dir.path().to_path_buf()),
+            db_file_name: Some("test.sqlite".into()),
+            key_provider: Arc::new(StaticKeyProvider::new(vec![5u8; 32]).unwrap()),
+        })
+        .unwrap();
+        storage.initialize().unwrap();
+
+        let first = PeptideProtocol::new("Morning", "BPC-157");
+        let second = PeptideProtocol::new("Evening", "Ipamorelin");
+        storage.upsert_protocol(&first).unwrap();
+        storage.upsert_protocol(&second).unwrap();
+        assert!(!storage.tamper_evidence_enabled().unwrap());
+        assert!(storage.verify_tamper_chain().is_err());
+
+        // Existing records are chained when it's turned on, later writes as they happen
+        storage.enable_tamper_evidence().unwrap();
+        let dose = DoseLog::new(first.id.as_str(), "abdomen", 0.25);
+        storage.append_dose_log(&dose).unwrap();
+        let mut renamed = second.clone();
+        renamed.name = "Night".into();
+        storage.upsert_protocol(&renamed).unwrap();
+
+        let report = storage.verify_tamper_chain().unwrap();
+        assert!(report.is_intact(), "{:?}", report);
+        assert_eq!(report.records_checked, 3);
+
+        // Edit the file with another client, as someone without the app would
+        let outside = Connection::open(dir.path().join("test.sqlite")).unwrap();
+        outside
+            .execute("UPDATE protocols SET payload = x'00' WHERE id = ?1", [&first.id])
+            .unwrap();
+        outside
+            .execute("DELETE FROM dose_logs WHERE id = ?1", [&dose.id])
+            .unwrap();
+
+        let report = storage.verify_tamper_chain().unwrap();
+        assert!(report.broken_links.is_empty());
+        assert_eq!(
+            report.findings,
+            vec![
+                TamperFinding {
+                    table: "protocols".into(),
+                    record_id: first.id.clone(),
+                    change: TamperChange::Modified,
+                },
+                TamperFinding {
+                    table: "dose_logs".into(),
+                    record_id: dose.id.clone(),
+                    change: TamperChange::Deleted,
+                },
+            ]
+        );
+
+        // Removing a chain entry breaks the link to the one after it
+        let seq: i64 = outside
+            .query_row(
+                "SELECT MIN(seq) FROM tamper_chain WHERE table_name = 'protocols'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        outside
+            .execute("DELETE FROM tamper_chain WHERE seq = ?1", [seq])
+            .unwrap();
+        let report = storage.verify_tamper_chain().unwrap();
+        assert_eq!(report.broken_links.len(), 1);
+        assert_eq!(report.broken_links[0].table, "protocols");
+
+        storage.disable_tamper_evidence().unwrap();
+        assert!(!storage.tamper_evidence_enabled().unwrap());
+        storage
+            .upsert_protocol(&PeptideProtocol::new("Later", "TB-500"))
+            .unwrap();
+        let entries: i64 = outside
+            .query_row("SELECT COUNT(*) FROM tamper_chain", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(entries, 0);
+    }
+
+    #[test]
+    fn verify_finds_newest_entries_removed_with_their_records() {
+        let dir = tempdir().unwrap();
+        let storage = StorageManager::new(StorageConfig {
+            data_dir: Some(dir.path().to_path_buf()),
+            db_file_name: Some("test.sqlite".into()),
+            key_provider: Arc::new(StaticKeyProvider::new(vec![6u8; 32]).unwrap()),
+        })
+        .unwrap();
+        storage.initialize().unwrap();
+        storage.enable_tamper_evidence().unwrap();
+
+        let kept = PeptideProtocol::new("Morning", "BPC-157");
+        let removed = PeptideProtocol::new("Evening", "Ipamorelin");
+        storage.upsert_protocol(&kept).unwrap();
+        storage.upsert_protocol(&removed).unwrap();
+        assert!(storage.verify_tamper_chain().unwrap().is_intact());
+
+        // The rest of the chain still links up, so only the head gives it away
+        let outside = Connection::open(dir.path().join("test.sqlite")).unwrap();
+        outside
+            .execute("DELETE FROM protocols WHERE id = ?1", [&removed.id])
+            .unwrap();
+        outside
+            .execute("DELETE FROM tamper_chain WHERE record_id = ?1", [&removed.id])
+            .unwrap();
+        let report = storage.verify_tamper_chain().unwrap();
+        assert!(report.broken_links.is_empty());
+        assert!(report.findings.is_empty());
+        assert_eq!(report.truncated_tables, vec!["protocols".to_string()]);
+
+        // Nor can the head be dropped, even for a table with no entries
+        outside
+            .execute("DELETE FROM tamper_head WHERE table_name = 'goals'", [])
+            .unwrap();
+        let report = storage.verify_tamper_chain().unwrap();
+        assert_eq!(
+            report.truncated_tables,
+            vec!["protocols".to_string(), "goals".to_string()]
+        );
+    }
+
+    #[test]
+    fn verify_finds_the_key_removed_from_the_database() {
+        let dir = tempdir().unwrap();
+        let storage = StorageManager::new(StorageConfig {
+            data_dir: Some(dir.path().to_path_buf()),
+            db_file_name: Some("test.sqlite".into()),
+            key_provider: Arc::new(StaticKeyProvider::new(vec![7u8; 32]).unwrap()),
+        })
+        .unwrap();
+        storage.initialize().unwrap();
+        storage.enable_tamper_evidence().unwrap();
+        storage.upsert_protocol(&PeptideProtocol::new("Morning", "BPC-157")).unwrap();
+
+        // Dropping the key with the chain would otherwise look like it was never on
+        let outside = Connection::open(dir.path().join("test.sqlite")).unwrap();
+        outside.execute("DELETE FROM tamper_key", []).unwrap();
+        outside.execute("DELETE FROM tamper_chain", []).unwrap();
+        outside.execute("DELETE FROM tamper_head", []).unwrap();
+
+        assert!(storage.tamper_evidence_enabled().unwrap());
+        let report = storage.verify_tamper_chain().unwrap();
+        assert!(report.key_removed);
+        assert!(!report.is_intact());
+
+        // Turning it off removes the marker, so it reads as off again
+        storage.disable_tamper_evidence().unwrap();
+        assert!(!storage.tamper_evidence_enabled().unwrap());
+        assert!(!marker_path(&dir.path().join("test.sqlite")).exists());
+    }
+}
@@ -0,0 +1,69 @@
+//! Soft-deleted records waiting to be restored or purged
+//!
+//! Protocols, dose logs and body metrics are moved to the trash by setting
+//! their `deleted_at` column; they disappear from every list and lookup until
+//! restored, and are permanently deleted once they've been in the trash
+//! longer than [`TrashSettings::retention_days`].
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::settings::Setting;
+
+/// Kind of record that can be moved to the trash
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashEntityType {
+    Protocol,
+    DoseLog,
+    BodyMetric,
+}
+
+impl TrashEntityType {
+    /// Table holding this kind of record
+    pub(crate) fn table(self) -> &'static str {
+        match self {
+            TrashEntityType::Protocol => "protocols",
+            TrashEntityType::DoseLog => "dose_logs",
+            TrashEntityType::BodyMetric => "body_metrics",
+        }
+    }
+}
+
+/// A record in the trash
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashItem {
+    pub entity_type: TrashEntityType,
+    pub entity_id: String,
+    /// Short description for the trash list, e.g. the protocol name
+    pub label: String,
+    /// Protocol a dose log belongs to; dose logs trashed with their protocol
+    /// are restored with it
+    pub protocol_id: Option<String>,
+    pub deleted_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TrashSettings {
+    /// Days a record stays in the trash before it is permanently deleted
+    pub retention_days: u32,
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+impl Setting for TrashSettings {
+    const KEY: &'static str = "trash.settings";
+    const LEGACY_FILE: Option<&'static str> = Some("trash.json");
+
+    fn validate(&self) -> Result<(), String> {
+        if self.retention_days == 0 {
+            return Err("Keep trashed records for at least a day; empty the trash to delete them now".to_string());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+//! Undo for deletes and bulk edits
+//!
+//! Commands that delete or bulk-edit records push an [`UndoOperation`]
+//! saying how to put things back: records moved to the trash are restored
+//! from it, and other records are saved again as they were before the
+//! change. [`StorageManager::undo`](crate::StorageManager::undo) applies one.
+//!
+//! The stack is kept in memory and holds the last [`MAX_UNDO_OPERATIONS`],
+//! so undo reaches back within a session only; the trash covers anything
+//! older.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::models::{InventoryItem, PeptideProtocol, SideEffect};
+use crate::trash::TrashEntityType;
+
+/// Most operations kept; older ones can no longer be undone
+pub const MAX_UNDO_OPERATIONS: usize = 20;
+
+/// How to reverse a change
+#[derive(Debug, Clone)]
+pub enum UndoAction {
+    /// Restore records that were moved to the trash
+    RestoreFromTrash {
+        entity_type: TrashEntityType,
+        ids: Vec<String>,
+    },
+    /// Save the protocols as they were
+    RestoreProtocols(Vec<PeptideProtocol>),
+    /// Save the inventory items as they were, recreating deleted ones
+    RestoreInventory(Vec<InventoryItem>),
+    /// Save the side effects as they were, recreating deleted ones
+    RestoreSideEffects(Vec<SideEffect>),
+}
+
+impl UndoAction {
+    /// Number of records the action puts back
+    pub fn len(&self) -> usize {
+        match self {
+            UndoAction::RestoreFromTrash { ids, .. } => ids.len(),
+            UndoAction::RestoreProtocols(protocols) => protocols.len(),
+            UndoAction::RestoreInventory(items) => items.len(),
+            UndoAction::RestoreSideEffects(effects) => effects.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A change that can be undone
+#[derive(Debug, Clone)]
+pub struct UndoOperation {
+    pub id: String,
+    /// What was done, e.g. "Delete 3 dose logs"
+    pub label: String,
+    pub action: UndoAction,
+    pub performed_at: OffsetDateTime,
+}
+
+/// An [`UndoOperation`] without the records, for listing
+#[derive(Debug, Clone, PartialEq)]
+pub struct UndoSummary {
+    pub id: String,
+    pub label: String,
+    pub records: usize,
+    pub performed_at: OffsetDateTime,
+}
+
+/// The most recent undoable changes, newest last
+#[derive(Debug, Default)]
+pub struct UndoStack {
+    operations: Mutex<VecDeque<UndoOperation>>,
+}
+
+impl UndoStack {
+    /// Remember how to undo a change; actions with no records are ignored
+    pub fn push(&self, label: impl Into<String>, action: UndoAction) {
+        if action.is_empty() {
+            return;
+        }
+        self.push_operation(UndoOperation {
+            id: Uuid::new_v4().to_string(),
+            label: label.into(),
+            action,
+            performed_at: OffsetDateTime::now_utc(),
+        });
+    }
+
+    /// Put an operation back on top, e.g. after undoing it failed
+    pub fn push_operation(&self, operation: UndoOperation) {
+        let mut operations = self.operations.lock().unwrap_or_else(|e| e.into_inner());
+        operations.push_back(operation);
+        while operations.len() > MAX_UNDO_OPERATIONS {
+            operations.pop_front();
+        }
+    }
+
+    /// Take the most recent operation
+    pub fn pop(&self) -> Option<UndoOperation> {
+        self.operations.lock().unwrap_or_else(|e| e.into_inner()).pop_back()
+    }
+
+    /// Operations that can be undone, most recent first
+    pub fn summaries(&self) -> Vec<UndoSummary> {
+        self.operations
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .rev()
+            .map(|operation| UndoSummary {
+                id: operation.id.clone(),
+                label: operation.label.clone(),
+                records: operation.action.len(),
+                performed_at: operation.performed_at,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trashed(id: &str) -> UndoAction {
+        UndoAction::RestoreFromTrash {
+            entity_type: TrashEntityType::DoseLog,
+            ids: vec![id.to_string()],
+        }
+    }
+
+    #[test]
+    fn only_the_latest_operations_are_kept() {
+        let stack = UndoStack::default();
+        stack.push("Nothing", UndoAction::RestoreProtocols(Vec::new()));
+        assert!(stack.summaries().is_empty());
+
+        for i in 0..MAX_UNDO_OPERATIONS + 2 {
+            stack.push(format!("Delete dose {}", i), trashed(&i.to_string()));
+        }
+        let summaries = stack.summaries();
+        assert_eq!(summaries.len(), MAX_UNDO_OPERATIONS);
+        assert_eq!(summaries[0].label, format!("Delete dose {}", MAX_UNDO_OPERATIONS + 1));
+        assert_eq!(summaries.last().unwrap().label, "Delete dose 2");
+
+        let latest = stack.pop().unwrap();
+        assert_eq!(latest.label, format!("Delete dose {}", MAX_UNDO_OPERATIONS + 1));
+        stack.push_operation(latest);
+        assert_eq!(stack.summaries().len(), MAX_UNDO_OPERATIONS);
+    }
+}
@@ -0,0 +1,239 @@
+//! Display unit preferences
+//!
+//! Everything is stored in canonical units: doses in mg, weights in kg and
+//! lengths in cm. [`UnitPreferences`] only decides how values are shown and
+//! entered, so changing them never touches stored records. Some compounds,
+//! such as somatropin, are dosed in IU; those need a conversion factor since
+//! IU measure activity rather than mass.
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Setting;
+
+const MCG_PER_MG: f32 = 1000.0;
+const LB_PER_KG: f32 = 2.204_622_6;
+const CM_PER_IN: f32 = 2.54;
+
+/// IU per mg for compounds with a standard conversion
+const KNOWN_IU_PER_MG: &[(&str, f32)] = &[("somatropin", 3.0), ("hgh", 3.0), ("growth hormone", 3.0)];
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DoseUnit {
+    #[default]
+    Mg,
+    Mcg,
+    Iu,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightUnit {
+    #[default]
+    Kg,
+    Lb,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LengthUnit {
+    #[default]
+    Cm,
+    In,
+}
+
+/// A compound whose doses are shown in IU
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IuConversion {
+    /// Matched case-insensitively against protocols' peptide names
+    pub peptide_name: String,
+    pub iu_per_mg: f32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UnitPreferences {
+    /// Unit for doses of compounds not listed in `iu_compounds`; mg or mcg
+    pub dose_unit: DoseUnit,
+    /// Unit for body weight and muscle mass
+    pub weight_unit: WeightUnit,
+    /// Unit for waist measurements
+    pub length_unit: LengthUnit,
+    pub iu_compounds: Vec<IuConversion>,
+}
+
+/// Standard IU per mg for `peptide_name`, if it has one
+pub fn known_iu_per_mg(peptide_name: &str) -> Option<f32> {
+    let name = peptide_name.trim().to_lowercase();
+    KNOWN_IU_PER_MG
+        .iter()
+        .find(|(known, _)| name.contains(known))
+        .map(|(_, iu_per_mg)| *iu_per_mg)
+}
+
+impl UnitPreferences {
+    /// Check the preferences make sense before saving them
+    pub fn validate(&self) -> Result<(), String> {
+        if self.dose_unit == DoseUnit::Iu {
+            return Err("IU can only be chosen per compound".to_string());
+        }
+        for (index, compound) in self.iu_compounds.iter().enumerate() {
+            let name = compound.peptide_name.trim();
+            if name.is_empty() {
+                return Err("IU compounds need a peptide name".to_string());
+            }
+            if !compound.iu_per_mg.is_finite() || compound.iu_per_mg <= 0.0 {
+                return Err(format!("IU per mg for {} must be a positive number", name));
+            }
+            if self.iu_compounds[..index]
+                .iter()
+                .any(|other| other.peptide_name.trim().eq_ignore_ascii_case(name))
+            {
+                return Err(format!("{} is listed more than once", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// IU per mg when `peptide_name` is dosed in IU
+    fn iu_per_mg(&self, peptide_name: Option<&str>) -> Option<f32> {
+        let name = peptide_name?.trim();
+        self.iu_compounds
+            .iter()
+            .find(|compound| compound.peptide_name.trim().eq_ignore_ascii_case(name))
+            .map(|compound| compound.iu_per_mg)
+    }
+
+    /// Unit doses of `peptide_name` are shown in
+    pub fn dose_unit_for(&self, peptide_name: Option<&str>) -> DoseUnit {
+        if self.iu_per_mg(peptide_name).is_some() {
+            DoseUnit::Iu
+        } else {
+            self.dose_unit
+        }
+    }
+
+    /// Dose of `peptide_name` in its display unit
+    pub fn dose_from_mg(&self, peptide_name: Option<&str>, amount_mg: f32) -> f32 {
+        match self.dose_unit_for(peptide_name) {
+            DoseUnit::Mg => amount_mg,
+            DoseUnit::Mcg => amount_mg * MCG_PER_MG,
+            DoseUnit::Iu => amount_mg * self.iu_per_mg(peptide_name).unwrap_or(1.0),
+        }
+    }
+
+    /// Convert a dose entered in `unit` to mg
+    ///
+    /// Fails for IU when the compound has no conversion factor.
+    pub fn dose_to_mg(&self, peptide_name: Option<&str>, amount: f32, unit: DoseUnit) -> Result<f32, String> {
+        match unit {
+            DoseUnit::Mg => Ok(amount),
+            DoseUnit::Mcg => Ok(amount / MCG_PER_MG),
+            DoseUnit::Iu => self
+                .iu_per_mg(peptide_name)
+                .or_else(|| peptide_name.and_then(known_iu_per_mg))
+                .map(|iu_per_mg| amount / iu_per_mg)
+                .ok_or_else(|| {
+                    format!(
+                        "No IU conversion is set for {}",
+                        peptide_name.unwrap_or("this compound")
+                    )
+                }),
+        }
+    }
+
+    pub fn weight_from_kg(&self, kg: f32) -> f32 {
+        match self.weight_unit {
+            WeightUnit::Kg => kg,
+            WeightUnit::Lb => kg * LB_PER_KG,
+        }
+    }
+
+    pub fn length_from_cm(&self, cm: f32) -> f32 {
+        match self.length_unit {
+            LengthUnit::Cm => cm,
+            LengthUnit::In => cm / CM_PER_IN,
+        }
+    }
+}
+
+impl Setting for UnitPreferences {
+    const KEY: &'static str = "units.preferences";
+
+    fn validate(&self) -> Result<(), String> {
+        UnitPreferences::validate(self)
+    }
+}
+
+/// Convert a weight entered in `unit` to kg
+pub fn weight_to_kg(value: f32, unit: WeightUnit) -> f32 {
+    match unit {
+        WeightUnit::Kg => value,
+        WeightUnit::Lb => value / LB_PER_KG,
+    }
+}
+
+/// Convert a length entered in `unit` to cm
+pub fn length_to_cm(value: f32, unit: LengthUnit) -> f32 {
+    match unit {
+        LengthUnit::Cm => value,
+        LengthUnit::In => value * CM_PER_IN,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preferences() -> UnitPreferences {
+        UnitPreferences {
+            dose_unit: DoseUnit::Mcg,
+            weight_unit: WeightUnit::Lb,
+            length_unit: LengthUnit::In,
+            iu_compounds: vec![IuConversion {
+                peptide_name: "Somatropin".to_string(),
+                iu_per_mg: 3.0,
+            }],
+        }
+    }
+
+    #[test]
+    fn doses_roundtrip_through_display_units() {
+        let preferences = preferences();
+        assert_eq!(preferences.dose_unit_for(Some("BPC-157")), DoseUnit::Mcg);
+        assert_eq!(preferences.dose_from_mg(Some("BPC-157"), 0.25), 250.0);
+        assert_eq!(preferences.dose_to_mg(Some("BPC-157"), 250.0, DoseUnit::Mcg), Ok(0.25));
+
+        assert_eq!(preferences.dose_unit_for(Some(" somatropin ")), DoseUnit::Iu);
+        assert_eq!(preferences.dose_from_mg(Some("somatropin"), 1.0), 3.0);
+        assert_eq!(preferences.dose_to_mg(Some("somatropin"), 6.0, DoseUnit::Iu), Ok(2.0));
+        assert!(preferences.dose_to_mg(Some("BPC-157"), 2.0, DoseUnit::Iu).is_err());
+    }
+
+    #[test]
+    fn body_measurements_convert_both_ways() {
+        let preferences = preferences();
+        let lb = preferences.weight_from_kg(80.0);
+        assert!((lb - 176.37).abs() < 0.01);
+        assert!((weight_to_kg(lb, WeightUnit::Lb) - 80.0).abs() < 1e-4);
+        assert!((preferences.length_from_cm(81.28) - 32.0).abs() < 1e-4);
+        assert!((length_to_cm(32.0, LengthUnit::In) - 81.28).abs() < 1e-4);
+    }
+
+    #[test]
+    fn validate_rejects_bad_iu_compounds() {
+        let mut preferences = preferences();
+        assert!(preferences.validate().is_ok());
+
+        preferences.iu_compounds.push(IuConversion {
+            peptide_name: "SOMATROPIN".to_string(),
+            iu_per_mg: 3.0,
+        });
+        assert!(preferences.validate().is_err());
+
+        preferences.iu_compounds.pop();
+        preferences.dose_unit = DoseUnit::Iu;
+        assert!(preferences.validate().is_err());
+    }
+}
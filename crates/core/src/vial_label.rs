@@ -0,0 +1,134 @@
+//! Printable vial labels
+//!
+//! A label is an SVG with a QR code and a few lines of text. The QR code
+//! holds `peptrack:vial:<inventory id>`; scanning it with the app's camera or
+//! a handheld scanner resolves straight back to the vial. Nothing else about
+//! the vial is in the code, so a label on a shared fridge shelf gives away
+//! no more than its printed text.
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::models::InventoryItem;
+use crate::qr::QrCode;
+
+/// Scheme in front of the inventory id in vial QR codes
+pub const VIAL_QR_PREFIX: &str = "peptrack:vial:";
+
+/// Quiet zone around the QR code, in modules
+const QR_BORDER: usize = 4;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VialLabel {
+    pub inventory_id: String,
+    /// What the QR code encodes
+    pub qr_content: String,
+    /// The whole label, QR code and text, ready to print
+    pub svg: String,
+}
+
+pub fn vial_qr_content(inventory_id: &str) -> String {
+    format!("{}{}", VIAL_QR_PREFIX, inventory_id)
+}
+
+/// The inventory id in scanned text: a vial QR code's content, or a bare id
+/// typed in by hand
+pub fn parse_vial_qr(scanned: &str) -> Option<&str> {
+    let scanned = scanned.trim();
+    let id = match scanned.get(..VIAL_QR_PREFIX.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(VIAL_QR_PREFIX) => &scanned[VIAL_QR_PREFIX.len()..],
+        _ => scanned,
+    };
+    let id = id.trim();
+    let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    valid.then_some(id)
+}
+
+/// Label for `item` with the protocol's name, vial number and dates
+pub fn render_vial_label(item: &InventoryItem, protocol_name: &str) -> Result<VialLabel> {
+    let qr_content = vial_qr_content(&item.id);
+    let qr = QrCode::encode(qr_content.as_bytes())?;
+    let qr_size = qr.size + QR_BORDER * 2;
+
+    let mut lines = vec![protocol_name.to_string()];
+    if let Some(number) = &item.vial_number {
+        lines.push(format!("Vial {}", number));
+    }
+    if let Some(lot) = &item.lot_number {
+        lines.push(format!("Lot {}", lot));
+    }
+    if let Some(mixed) = item.reconstituted_at {
+        lines.push(format!("Mixed {}", mixed.date()));
+    }
+    if let Some(expiry) = item.effective_expiry() {
+        lines.push(format!("Use by {}", expiry.date()));
+    }
+
+    // Text sits to the right of the code, in module units
+    let text_x = qr_size + 1;
+    let line_height = qr_size as f32 / 5.0;
+    let width = qr_size + 2 + 48;
+    let text: String = lines
+        .iter()
+        .take(5)
+        .enumerate()
+        .map(|(i, line)| {
+            format!(
+                r#"<text x="{}" y="{:.1}" font-size="{:.1}"{}>{}</text>"#,
+                text_x,
+                line_height * (i as f32 + 0.75),
+                line_height * 0.6,
+                if i == 0 { r#" font-weight="bold""# } else { "" },
+                escape_xml(line)
+            )
+        })
+        .collect();
+
+    let svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" font-family="sans-serif"><rect width="100%" height="100%" fill="#fff"/><path d="{path}" fill="#000" shape-rendering="crispEdges"/>{text}</svg>"##,
+        w = width,
+        h = qr_size,
+        path = qr.svg_path(QR_BORDER),
+        text = text
+    );
+
+    Ok(VialLabel {
+        inventory_id: item.id.clone(),
+        qr_content,
+        svg,
+    })
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scanned_text_resolves_to_the_inventory_id() {
+        let id = "6f1c2a9e-8d0b-4c55-a1f2-3b4c5d6e7f80";
+        assert_eq!(parse_vial_qr(&vial_qr_content(id)), Some(id));
+        assert_eq!(parse_vial_qr(&format!("  PEPTRACK:VIAL:{}\n", id)), Some(id));
+        assert_eq!(parse_vial_qr(id), Some(id));
+        assert_eq!(parse_vial_qr("peptrack:vial:"), None);
+        assert_eq!(parse_vial_qr("https://example.com/?q=1"), None);
+    }
+
+    #[test]
+    fn labels_escape_their_text() {
+        let mut item = InventoryItem::new("protocol-1");
+        item.vial_number = Some("3".into());
+        let label = render_vial_label(&item, "BPC <morning> & night").expect("label");
+
+        assert_eq!(label.qr_content, vial_qr_content(&item.id));
+        assert!(label.svg.contains("BPC &lt;morning&gt; &amp; night"));
+        assert!(label.svg.contains("Vial 3"));
+    }
+}
@@ -0,0 +1,190 @@
+package com.peptrack.health
+
+import android.app.Activity
+import androidx.activity.result.ActivityResult
+import androidx.health.connect.client.HealthConnectClient
+import androidx.health.connect.client.PermissionController
+import androidx.health.connect.client.permission.HealthPermission
+import androidx.health.connect.client.records.BodyFatRecord
+import androidx.health.connect.client.records.Record
+import androidx.health.connect.client.records.RestingHeartRateRecord
+import androidx.health.connect.client.records.WeightRecord
+import androidx.health.connect.client.records.metadata.Metadata
+import androidx.health.connect.client.request.ReadRecordsRequest
+import androidx.health.connect.client.time.TimeRangeFilter
+import androidx.health.connect.client.units.Mass
+import androidx.health.connect.client.units.Percentage
+import app.tauri.annotation.ActivityCallback
+import app.tauri.annotation.Command
+import app.tauri.annotation.InvokeArg
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSArray
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+import java.time.Instant
+import java.time.OffsetDateTime
+import java.time.ZoneId
+import kotlin.reflect.KClass
+import kotlinx.coroutines.CoroutineScope
+import kotlinx.coroutines.Dispatchers
+import kotlinx.coroutines.SupervisorJob
+import kotlinx.coroutines.launch
+
+@InvokeArg
+class HealthSample {
+    lateinit var dataType: String
+    var value: Double = 0.0
+    lateinit var date: String
+}
+
+@InvokeArg
+class AuthorizationArgs {
+    var write: List<String> = listOf()
+    var read: List<String> = listOf()
+}
+
+@InvokeArg
+class WriteSamplesArgs {
+    var samples: List<HealthSample> = listOf()
+}
+
+@InvokeArg
+class ReadSamplesArgs {
+    lateinit var dataType: String
+    lateinit var since: String
+}
+
+/** Health Connect record class for a PepTrack data type; waist has none */
+private fun recordClass(dataType: String): KClass<out Record>? = when (dataType) {
+    "bodyMass" -> WeightRecord::class
+    "bodyFatPercentage" -> BodyFatRecord::class
+    "restingHeartRate" -> RestingHeartRateRecord::class
+    else -> null
+}
+
+@TauriPlugin
+class HealthBridgePlugin(private val activity: Activity) : Plugin(activity) {
+    private val scope = CoroutineScope(SupervisorJob() + Dispatchers.IO)
+    private val permissionContract = PermissionController.createRequestPermissionResultContract()
+    private var requestedPermissions: Set<String> = setOf()
+
+    private fun isAvailable(): Boolean =
+        HealthConnectClient.getSdkStatus(activity) == HealthConnectClient.SDK_AVAILABLE
+
+    private fun client(): HealthConnectClient = HealthConnectClient.getOrCreate(activity)
+
+    private fun launch(invoke: Invoke, block: suspend () -> Unit) {
+        if (!isAvailable()) {
+            invoke.reject("Health data isn't available on this device")
+            return
+        }
+        scope.launch {
+            try {
+                block()
+            } catch (e: Exception) {
+                invoke.reject(e.message ?: "Health Connect request failed", e)
+            }
+        }
+    }
+
+    @Command
+    fun isAvailable(invoke: Invoke) {
+        val ret = JSObject()
+        ret.put("available", isAvailable())
+        invoke.resolve(ret)
+    }
+
+    @Command
+    fun requestAuthorization(invoke: Invoke) {
+        val args = invoke.parseArgs(AuthorizationArgs::class.java)
+        val permissions = args.write.mapNotNull { recordClass(it) }.map { HealthPermission.getWritePermission(it) } +
+            args.read.mapNotNull { recordClass(it) }.map { HealthPermission.getReadPermission(it) }
+        requestedPermissions = permissions.toSet()
+
+        launch(invoke) {
+            val granted = client().permissionController.getGrantedPermissions()
+            if (granted.containsAll(requestedPermissions)) {
+                resolveGranted(invoke, true)
+            } else {
+                val intent = permissionContract.createIntent(activity, requestedPermissions)
+                activity.runOnUiThread { startActivityForResult(invoke, intent, "permissionResult") }
+            }
+        }
+    }
+
+    @ActivityCallback
+    private fun permissionResult(invoke: Invoke, result: ActivityResult) {
+        val granted = permissionContract.parseResult(result.resultCode, result.data)
+        resolveGranted(invoke, granted.containsAll(requestedPermissions))
+    }
+
+    private fun resolveGranted(invoke: Invoke, granted: Boolean) {
+        val ret = JSObject()
+        ret.put("granted", granted)
+        invoke.resolve(ret)
+    }
+
+    @Command
+    fun writeSamples(invoke: Invoke) {
+        val args = invoke.parseArgs(WriteSamplesArgs::class.java)
+        launch(invoke) {
+            val records = args.samples.mapNotNull { toRecord(it) }
+            if (records.isNotEmpty()) {
+                client().insertRecords(records)
+            }
+            invoke.resolve()
+        }
+    }
+
+    private fun toRecord(sample: HealthSample): Record? {
+        val at = OffsetDateTime.parse(sample.date)
+        val time = at.toInstant()
+        val offset = at.offset
+        return when (sample.dataType) {
+            "bodyMass" -> WeightRecord(time, offset, Mass.kilograms(sample.value), Metadata.manualEntry())
+            "bodyFatPercentage" -> BodyFatRecord(time, offset, Percentage(sample.value), Metadata.manualEntry())
+            "restingHeartRate" ->
+                RestingHeartRateRecord(time, offset, Math.round(sample.value), Metadata.manualEntry())
+            // Health Connect has no waist circumference record
+            else -> null
+        }
+    }
+
+    @Command
+    fun readSamples(invoke: Invoke) {
+        val args = invoke.parseArgs(ReadSamplesArgs::class.java)
+        launch(invoke) {
+            val since = OffsetDateTime.parse(args.since).toInstant()
+            val recordType = recordClass(args.dataType)
+            val samples = JSArray()
+            if (recordType != null) {
+                val response = client().readRecords(
+                    ReadRecordsRequest(recordType, timeRangeFilter = TimeRangeFilter.after(since))
+                )
+                for (record in response.records) {
+                    // Leave out records PepTrack wrote itself
+                    if (record.metadata.dataOrigin.packageName == activity.packageName) continue
+                    sampleOf(args.dataType, record)?.let { samples.put(it) }
+                }
+            }
+            val ret = JSObject()
+            ret.put("samples", samples)
+            invoke.resolve(ret)
+        }
+    }
+
+    private fun sampleOf(dataType: String, record: Record): JSObject? {
+        val (time: Instant, value: Double) = when (record) {
+            is WeightRecord -> record.time to record.weight.inKilograms
+            is BodyFatRecord -> record.time to record.percentage.value
+            is RestingHeartRateRecord -> record.time to record.beatsPerMinute.toDouble()
+            else -> return null
+        }
+        val sample = JSObject()
+        sample.put("dataType", dataType)
+        sample.put("value", value)
+        sample.put("date", time.atZone(ZoneId.systemDefault()).toOffsetDateTime().toString())
+        return sample
+    }
+}
@@ -0,0 +1,10 @@
+// The bridge is called from the app's own commands, not from the webview,
+// so the plugin exposes no commands of its own
+const COMMANDS: &[&str] = &[];
+
+fn main() {
+    tauri_plugin::Builder::new(COMMANDS)
+        .android_path("android")
+        .ios_path("ios")
+        .build();
+}
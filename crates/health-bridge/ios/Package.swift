@@ -0,0 +1,27 @@
+// swift-tools-version:5.5
+
+import PackageDescription
+
+let package = Package(
+  name: "tauri-plugin-health-bridge",
+  platforms: [
+    .iOS(.v15)
+  ],
+  products: [
+    .library(
+      name: "tauri-plugin-health-bridge",
+      type: .static,
+      targets: ["tauri-plugin-health-bridge"])
+  ],
+  dependencies: [
+    .package(name: "Tauri", path: "../.tauri/tauri-api")
+  ],
+  targets: [
+    .target(
+      name: "tauri-plugin-health-bridge",
+      dependencies: [
+        .byName(name: "Tauri")
+      ],
+      path: "Sources")
+  ]
+)
@@ -0,0 +1,184 @@
+import HealthKit
+import SwiftRs
+import Tauri
+import UIKit
+
+enum HealthDataType: String, Codable {
+  case bodyMass
+  case bodyFatPercentage
+  case waistCircumference
+  case restingHeartRate
+
+  var quantityType: HKQuantityType {
+    switch self {
+    case .bodyMass: return HKQuantityType(.bodyMass)
+    case .bodyFatPercentage: return HKQuantityType(.bodyFatPercentage)
+    case .waistCircumference: return HKQuantityType(.waistCircumference)
+    case .restingHeartRate: return HKQuantityType(.restingHeartRate)
+    }
+  }
+
+  var unit: HKUnit {
+    switch self {
+    case .bodyMass: return .gramUnit(with: .kilo)
+    // HealthKit stores body fat as a fraction; PepTrack uses 0-100
+    case .bodyFatPercentage: return .percent()
+    case .waistCircumference: return .meterUnit(with: .centi)
+    case .restingHeartRate: return HKUnit.count().unitDivided(by: .minute())
+    }
+  }
+
+  /// PepTrack value to HealthKit value
+  func toHealthKit(_ value: Double) -> Double {
+    self == .bodyFatPercentage ? value / 100 : value
+  }
+
+  func fromHealthKit(_ value: Double) -> Double {
+    self == .bodyFatPercentage ? value * 100 : value
+  }
+}
+
+struct HealthSample: Codable {
+  let dataType: HealthDataType
+  let value: Double
+  let date: String
+}
+
+struct AuthorizationArgs: Decodable {
+  let write: [HealthDataType]
+  let read: [HealthDataType]
+}
+
+struct WriteSamplesArgs: Decodable {
+  let samples: [HealthSample]
+}
+
+struct ReadSamplesArgs: Decodable {
+  let dataType: HealthDataType
+  let since: String
+}
+
+struct ReadSamplesResponse: Encodable {
+  let samples: [HealthSample]
+}
+
+enum HealthBridgeError: LocalizedError {
+  case unavailable
+  case invalidDate(String)
+
+  var errorDescription: String? {
+    switch self {
+    case .unavailable:
+      return "Health data isn't available on this device"
+    case .invalidDate(let value):
+      return "Invalid date: \(value)"
+    }
+  }
+}
+
+class HealthBridgePlugin: Plugin {
+  private let store = HKHealthStore()
+  private let dateFormatter: ISO8601DateFormatter = {
+    let formatter = ISO8601DateFormatter()
+    formatter.formatOptions = [.withInternetDateTime, .withFractionalSeconds]
+    return formatter
+  }()
+  private let plainDateFormatter = ISO8601DateFormatter()
+
+  private func parseDate(_ value: String) throws -> Date {
+    guard let date = dateFormatter.date(from: value) ?? plainDateFormatter.date(from: value) else {
+      throw HealthBridgeError.invalidDate(value)
+    }
+    return date
+  }
+
+  @objc func isAvailable(_ invoke: Invoke) {
+    invoke.resolve(["available": HKHealthStore.isHealthDataAvailable()])
+  }
+
+  @objc func requestAuthorization(_ invoke: Invoke) throws {
+    guard HKHealthStore.isHealthDataAvailable() else {
+      throw HealthBridgeError.unavailable
+    }
+    let args = try invoke.parseArgs(AuthorizationArgs.self)
+    let toShare = Set(args.write.map { $0.quantityType as HKSampleType })
+    let toRead = Set(args.read.map { $0.quantityType as HKObjectType })
+
+    store.requestAuthorization(toShare: toShare, read: toRead) { [weak self] _, error in
+      if let error = error {
+        invoke.reject(error.localizedDescription)
+        return
+      }
+      // HealthKit hides whether read access was granted; report write access
+      let granted = args.write.allSatisfy {
+        self?.store.authorizationStatus(for: $0.quantityType) == .sharingAuthorized
+      }
+      invoke.resolve(["granted": granted])
+    }
+  }
+
+  @objc func writeSamples(_ invoke: Invoke) throws {
+    guard HKHealthStore.isHealthDataAvailable() else {
+      throw HealthBridgeError.unavailable
+    }
+    let args = try invoke.parseArgs(WriteSamplesArgs.self)
+    let samples: [HKQuantitySample] = try args.samples.map { sample in
+      let date = try parseDate(sample.date)
+      let quantity = HKQuantity(
+        unit: sample.dataType.unit, doubleValue: sample.dataType.toHealthKit(sample.value))
+      return HKQuantitySample(
+        type: sample.dataType.quantityType, quantity: quantity, start: date, end: date)
+    }
+    if samples.isEmpty {
+      invoke.resolve()
+      return
+    }
+
+    store.save(samples) { _, error in
+      if let error = error {
+        invoke.reject(error.localizedDescription)
+      } else {
+        invoke.resolve()
+      }
+    }
+  }
+
+  @objc func readSamples(_ invoke: Invoke) throws {
+    guard HKHealthStore.isHealthDataAvailable() else {
+      throw HealthBridgeError.unavailable
+    }
+    let args = try invoke.parseArgs(ReadSamplesArgs.self)
+    let since = try parseDate(args.since)
+
+    // Leave out samples PepTrack wrote itself so they don't come back as imports
+    let predicate = NSCompoundPredicate(andPredicateWithSubpredicates: [
+      HKQuery.predicateForSamples(withStart: since, end: nil, options: .strictStartDate),
+      NSCompoundPredicate(
+        notPredicateWithSubpredicate: HKQuery.predicateForObjects(from: HKSource.default())),
+    ])
+    let sort = NSSortDescriptor(key: HKSampleSortIdentifierStartDate, ascending: true)
+    let query = HKSampleQuery(
+      sampleType: args.dataType.quantityType, predicate: predicate,
+      limit: HKObjectQueryNoLimit, sortDescriptors: [sort]
+    ) { [weak self] _, results, error in
+      if let error = error {
+        invoke.reject(error.localizedDescription)
+        return
+      }
+      guard let self = self else { return }
+      let samples = (results as? [HKQuantitySample] ?? []).map { sample in
+        HealthSample(
+          dataType: args.dataType,
+          value: args.dataType.fromHealthKit(sample.quantity.doubleValue(for: args.dataType.unit)),
+          date: self.plainDateFormatter.string(from: sample.startDate))
+      }
+      invoke.resolve(ReadSamplesResponse(samples: samples))
+    }
+    store.execute(query)
+  }
+}
+
+@_cdecl("init_plugin_health_bridge")
+func initPlugin() -> Plugin {
+  return HealthBridgePlugin()
+}
@@ -0,0 +1,35 @@
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use tauri::plugin::PluginApi;
+use tauri::{AppHandle, Runtime};
+
+use crate::{AuthorizationRequest, AuthorizationResponse, Error, HealthDataType, HealthSample, Result};
+
+pub fn init<R: Runtime, C: DeserializeOwned>(
+    _app: &AppHandle<R>,
+    _api: PluginApi<R, C>,
+) -> std::result::Result<HealthBridge<R>, Box<dyn std::error::Error>> {
+    Ok(HealthBridge(PhantomData))
+}
+
+/// Desktops have no health store; nothing is available
+pub struct HealthBridge<R: Runtime>(PhantomData<fn() -> R>);
+
+impl<R: Runtime> HealthBridge<R> {
+    pub fn is_available(&self) -> bool {
+        false
+    }
+
+    pub fn request_authorization(&self, _request: AuthorizationRequest) -> Result<AuthorizationResponse> {
+        Err(Error::Unavailable)
+    }
+
+    pub fn write_samples(&self, _samples: &[HealthSample]) -> Result<()> {
+        Err(Error::Unavailable)
+    }
+
+    pub fn read_samples(&self, _data_type: HealthDataType, _since: &str) -> Result<Vec<HealthSample>> {
+        Err(Error::Unavailable)
+    }
+}
@@ -0,0 +1,130 @@
+//! PepTrack Health Bridge - HealthKit and Health Connect for mobile builds
+//!
+//! A Tauri plugin that writes body metrics to Apple HealthKit on iOS and
+//! Android Health Connect, and reads weight entries back. The native halves
+//! live in `ios/` (Swift) and `android/` (Kotlin). On desktop the plugin
+//! still registers, but [`HealthBridge::is_available`] is false and every
+//! other call returns [`Error::Unavailable`].
+//!
+//! Writing and reading need the user's permission, which
+//! [`HealthBridge::request_authorization`] asks for with the system prompt.
+//! iOS builds also need the HealthKit entitlement and the
+//! `NSHealthShareUsageDescription` / `NSHealthUpdateUsageDescription`
+//! entries in `Info.plist`.
+
+use serde::{Deserialize, Serialize};
+use tauri::plugin::{Builder, TauriPlugin};
+use tauri::{Manager, Runtime};
+
+#[cfg(desktop)]
+mod desktop;
+#[cfg(mobile)]
+mod mobile;
+
+#[cfg(desktop)]
+pub use desktop::HealthBridge;
+#[cfg(mobile)]
+pub use mobile::HealthBridge;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Health data isn't available on this device")]
+    Unavailable,
+    #[cfg(mobile)]
+    #[error(transparent)]
+    PluginInvoke(#[from] tauri::plugin::mobile::PluginInvokeError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Health data types PepTrack syncs, with the unit values are given in
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthDataType {
+    /// Kilograms
+    BodyMass,
+    /// Percent, 0-100
+    BodyFatPercentage,
+    /// Centimeters
+    WaistCircumference,
+    /// Beats per minute
+    RestingHeartRate,
+}
+
+/// One measurement at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthSample {
+    pub data_type: HealthDataType,
+    pub value: f64,
+    /// RFC 3339
+    pub date: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizationRequest {
+    pub write: Vec<HealthDataType>,
+    pub read: Vec<HealthDataType>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizationResponse {
+    /// Whether every requested permission was granted
+    ///
+    /// HealthKit never reveals whether read access was granted, so on iOS
+    /// this only reflects write access.
+    pub granted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WriteSamplesRequest<'a> {
+    samples: &'a [HealthSample],
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadSamplesRequest<'a> {
+    data_type: HealthDataType,
+    /// RFC 3339
+    since: &'a str,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadSamplesResponse {
+    samples: Vec<HealthSample>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AvailabilityResponse {
+    available: bool,
+}
+
+/// Access to the health bridge from an app handle or window
+pub trait HealthBridgeExt<R: Runtime> {
+    fn health_bridge(&self) -> &HealthBridge<R>;
+}
+
+impl<R: Runtime, T: Manager<R>> HealthBridgeExt<R> for T {
+    fn health_bridge(&self) -> &HealthBridge<R> {
+        self.state::<HealthBridge<R>>().inner()
+    }
+}
+
+/// Initializes the plugin
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    Builder::new("health-bridge")
+        .setup(|app, api| {
+            #[cfg(mobile)]
+            let bridge = mobile::init(app, api)?;
+            #[cfg(desktop)]
+            let bridge = desktop::init(app, api)?;
+            app.manage(bridge);
+            Ok(())
+        })
+        .build()
+}
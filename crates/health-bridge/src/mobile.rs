@@ -0,0 +1,60 @@
+use serde::de::DeserializeOwned;
+use tauri::plugin::{PluginApi, PluginHandle};
+use tauri::{AppHandle, Runtime};
+
+use crate::{
+    AuthorizationRequest, AuthorizationResponse, AvailabilityResponse, HealthDataType, HealthSample,
+    ReadSamplesRequest, ReadSamplesResponse, Result, WriteSamplesRequest,
+};
+
+#[cfg(target_os = "android")]
+const PLUGIN_IDENTIFIER: &str = "com.peptrack.health";
+
+#[cfg(target_os = "ios")]
+tauri::ios_plugin_binding!(init_plugin_health_bridge);
+
+// Registers the Swift or Kotlin plugin class
+pub fn init<R: Runtime, C: DeserializeOwned>(
+    _app: &AppHandle<R>,
+    api: PluginApi<R, C>,
+) -> std::result::Result<HealthBridge<R>, Box<dyn std::error::Error>> {
+    #[cfg(target_os = "android")]
+    let handle = api.register_android_plugin(PLUGIN_IDENTIFIER, "HealthBridgePlugin")?;
+    #[cfg(target_os = "ios")]
+    let handle = api.register_ios_plugin(init_plugin_health_bridge)?;
+    Ok(HealthBridge(handle))
+}
+
+/// HealthKit on iOS, Health Connect on Android
+pub struct HealthBridge<R: Runtime>(PluginHandle<R>);
+
+impl<R: Runtime> HealthBridge<R> {
+    /// Whether the device has a health store PepTrack can use; false on
+    /// iPads without HealthKit and on phones without Health Connect
+    pub fn is_available(&self) -> bool {
+        self.0
+            .run_mobile_plugin::<AvailabilityResponse>("isAvailable", ())
+            .map(|response| response.available)
+            .unwrap_or(false)
+    }
+
+    /// Show the system permission prompt for `request`
+    pub fn request_authorization(&self, request: AuthorizationRequest) -> Result<AuthorizationResponse> {
+        Ok(self.0.run_mobile_plugin("requestAuthorization", request)?)
+    }
+
+    pub fn write_samples(&self, samples: &[HealthSample]) -> Result<()> {
+        Ok(self
+            .0
+            .run_mobile_plugin("writeSamples", WriteSamplesRequest { samples })?)
+    }
+
+    /// Samples of `data_type` recorded since `since` (RFC 3339) by other
+    /// apps; PepTrack's own samples are left out
+    pub fn read_samples(&self, data_type: HealthDataType, since: &str) -> Result<Vec<HealthSample>> {
+        let response: ReadSamplesResponse = self
+            .0
+            .run_mobile_plugin("readSamples", ReadSamplesRequest { data_type, since })?;
+        Ok(response.samples)
+    }
+}
@@ -0,0 +1,212 @@
+//! Response caching for literature fetchers
+//!
+//! [`CachedFetcher`] wraps any [`LiteratureFetcher`] and keeps each page it
+//! fetches in the encrypted `literature_responses` table. The same search
+//! (same source, query, page size and page) within the TTL is answered from
+//! there instead of calling the API again. Queries are compared after
+//! lowercasing and collapsing whitespace.
+//!
+//! A cache that can't be read or written is logged and skipped, so a search
+//! never fails because of it.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use peptrack_core::AsyncStorage;
+//! use peptrack_literature::{CachedFetcher, LiteratureFetcher, PubMedFetcher};
+//!
+//! # async fn example(storage: AsyncStorage) -> anyhow::Result<()> {
+//! let fetcher = CachedFetcher::new(Box::new(PubMedFetcher::new()), storage, time::Duration::hours(24));
+//! let results = fetcher.search("BPC-157", 10).await?;
+//! // Served from the cache
+//! let again = fetcher.search("bpc-157 ", 10).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::Result;
+use async_trait::async_trait;
+use peptrack_core::AsyncStorage;
+use time::Duration;
+use tracing::{debug, warn};
+
+use crate::models::{LiteratureFetcher, LiteratureResult};
+use crate::pagination::{PageCursor, ResultPage};
+
+/// A fetcher whose pages are cached in the database
+pub struct CachedFetcher {
+    inner: Box<dyn LiteratureFetcher>,
+    storage: AsyncStorage,
+    ttl: Duration,
+    bypass: bool,
+}
+
+impl CachedFetcher {
+    /// Cache pages from `inner` for `ttl`
+    pub fn new(inner: Box<dyn LiteratureFetcher>, storage: AsyncStorage, ttl: Duration) -> Self {
+        Self {
+            inner,
+            storage,
+            ttl,
+            bypass: false,
+        }
+    }
+
+    /// Skip cached pages and always call the API; fresh pages are still
+    /// cached for later searches
+    pub fn bypass_cache(mut self, bypass: bool) -> Self {
+        self.bypass = bypass;
+        self
+    }
+
+    async fn cached_page(&self, key: &str) -> Option<ResultPage> {
+        let (key, ttl) = (key.to_string(), self.ttl);
+        let cached = self.storage.run(move |storage| storage.cached_response(&key, ttl)).await;
+        let payload = match cached {
+            Ok(payload) => payload?,
+            Err(e) => {
+                warn!("Failed to read cached {} response: {:#}", self.inner.source_name(), e);
+                return None;
+            }
+        };
+        // Pages cached by an older version may no longer deserialize
+        match serde_json::from_slice(&payload) {
+            Ok(page) => Some(page),
+            Err(e) => {
+                warn!("Ignoring cached {} response: {}", self.inner.source_name(), e);
+                None
+            }
+        }
+    }
+
+    async fn store_page(&self, key: &str, page: &ResultPage) {
+        let source = self.inner.source_name();
+        let stored = match serde_json::to_vec(page) {
+            Ok(payload) => {
+                let key = key.to_string();
+                self.storage
+                    .run(move |storage| storage.store_response(&key, source, &payload))
+                    .await
+            }
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = stored {
+            warn!("Failed to cache {} response: {:#}", self.inner.source_name(), e);
+        }
+    }
+}
+
+#[async_trait]
+impl LiteratureFetcher for CachedFetcher {
+    async fn search(&self, query: &str, max_results: usize) -> Result<Vec<LiteratureResult>> {
+        Ok(self.search_page(query, max_results, None).await?.results)
+    }
+
+    async fn search_page(&self, query: &str, page_size: usize, cursor: Option<&PageCursor>) -> Result<ResultPage> {
+        let key = cache_key(self.inner.source_name(), query, page_size, cursor);
+        if !self.bypass {
+            if let Some(page) = self.cached_page(&key).await {
+                debug!("{} search served from cache", self.inner.source_name());
+                return Ok(page);
+            }
+        }
+
+        let page = self.inner.search_page(query, page_size, cursor).await?;
+        self.store_page(&key, &page).await;
+        Ok(page)
+    }
+
+    fn source_name(&self) -> &'static str {
+        self.inner.source_name()
+    }
+}
+
+/// Lowercase `query` with runs of whitespace collapsed to one space
+pub fn normalize_query(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Cache key of one page of a search
+fn cache_key(source: &str, query: &str, page_size: usize, cursor: Option<&PageCursor>) -> String {
+    let page = match cursor {
+        None => String::new(),
+        Some(PageCursor::Offset(offset)) => format!("offset:{}", offset),
+        Some(PageCursor::Token(token)) => format!("token:{}", token),
+    };
+    format!("{}|{}|{}|{}", source, page_size, page, normalize_query(query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use peptrack_core::{StaticKeyProvider, StorageConfig, StorageManager};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Counts how often it's asked, returning one result titled by query
+    struct Counting {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl LiteratureFetcher for Counting {
+        async fn search(&self, query: &str, _max_results: usize) -> Result<Vec<LiteratureResult>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![LiteratureResult {
+                source: "test".into(),
+                title: query.to_string(),
+                url: None,
+                doi: None,
+                authors: None,
+                published_date: None,
+                journal: None,
+                abstract_text: None,
+                citation_count: None,
+                publication_types: Vec::new(),
+            }])
+        }
+
+        fn source_name(&self) -> &'static str {
+            "test"
+        }
+    }
+
+    #[tokio::test]
+    async fn repeated_searches_are_served_from_the_cache() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let storage = StorageManager::new(StorageConfig {
+            data_dir: Some(dir.path().to_path_buf()),
+            db_file_name: Some("test.sqlite".into()),
+            key_provider: Arc::new(StaticKeyProvider::new(vec![7u8; 32]).expect("key provider")),
+        })
+        .expect("storage");
+        storage.initialize().expect("init db");
+        let storage = Arc::new(storage);
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let fetcher = |bypass| {
+            let cached = AsyncStorage::new(storage.clone());
+            CachedFetcher::new(Box::new(Counting { calls: calls.clone() }), cached, Duration::hours(1)).bypass_cache(bypass)
+        };
+
+        assert_eq!(fetcher(false).search("BPC-157  tendon", 5).await.unwrap()[0].title, "BPC-157  tendon");
+        assert_eq!(fetcher(false).search(" bpc-157 Tendon", 5).await.unwrap()[0].title, "BPC-157  tendon");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        fetcher(false).search("BPC-157 tendon", 10).await.unwrap();
+        fetcher(true).search("BPC-157 tendon", 5).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let stats = storage.response_cache_stats(Duration::hours(1)).expect("stats");
+        assert_eq!((stats.entries, stats.hits), (2, 1));
+    }
+
+    #[test]
+    fn keys_tell_pages_apart() {
+        assert_eq!(cache_key("pubmed", " TB-500\tHealing ", 10, None), "pubmed|10||tb-500 healing");
+        assert_ne!(
+            cache_key("pubmed", "tb-500", 10, Some(&PageCursor::Offset(10))),
+            cache_key("pubmed", "tb-500", 10, None)
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! ClinicalTrials.gov API integration
+//!
+//! Finds registered trials that use a peptide as an intervention, with their
+//! phase, recruitment status, enrollment and whether results were posted.
+//! Trials aren't papers, so this isn't a [`LiteratureFetcher`](crate::LiteratureFetcher);
+//! results are `peptrack_core::TrialResult`s ready to cache.
+//!
+//! # API Documentation
+//!
+//! - API v2: https://clinicaltrials.gov/data-api/api
+//! - No key required; about 50 requests per minute
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use peptrack_literature::ClinicalTrialsFetcher;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let trials = ClinicalTrialsFetcher::new().search("semaglutide", 10).await?;
+//! for trial in trials {
+//!     println!("{} ({}): {}", trial.nct_id, trial.status, trial.title);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{Context, Result};
+use peptrack_core::TrialResult;
+use serde::Deserialize;
+use time::OffsetDateTime;
+use tracing::debug;
+
+const API_BASE: &str = "https://clinicaltrials.gov/api/v2/studies";
+/// Largest page the API returns
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// ClinicalTrials.gov API fetcher
+pub struct ClinicalTrialsFetcher {
+    client: reqwest::Client,
+}
+
+impl ClinicalTrialsFetcher {
+    /// Creates a new ClinicalTrials.gov fetcher
+    pub fn new() -> Self {
+        Self {
+            client: crate::http::client_builder("PepTrack/1.0 (mailto:support@peptrack.app)")
+                .build()
+                .expect("Failed to create HTTP client"),
+        }
+    }
+
+    /// Trials with `peptide` as an intervention, most recently updated first
+    pub async fn search(&self, peptide: &str, max_results: usize) -> Result<Vec<TrialResult>> {
+        let url = format!(
+            "{}?query.intr={}&pageSize={}&sort=LastUpdatePostDate:desc&format=json",
+            API_BASE,
+            urlencoding::encode(peptide),
+            max_results.clamp(1, MAX_PAGE_SIZE)
+        );
+        debug!("ClinicalTrials.gov search URL: {}", url);
+
+        let response: StudiesResponse = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to send ClinicalTrials.gov request")?
+            .error_for_status()
+            .context("ClinicalTrials.gov search failed")?
+            .json()
+            .await
+            .context("Failed to parse ClinicalTrials.gov response")?;
+
+        let fetched_at = OffsetDateTime::now_utc();
+        Ok(response
+            .studies
+            .into_iter()
+            .filter_map(|study| study_to_trial(study, fetched_at))
+            .collect())
+    }
+}
+
+impl Default for ClinicalTrialsFetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Converts a study into a trial; studies without an NCT id are dropped
+fn study_to_trial(study: Study, fetched_at: OffsetDateTime) -> Option<TrialResult> {
+    let protocol = study.protocol_section;
+    let identification = protocol.identification_module;
+    let nct_id = identification.nct_id?;
+    let status = protocol.status_module.unwrap_or_default();
+    let design = protocol.design_module.unwrap_or_default();
+
+    Some(TrialResult {
+        title: identification
+            .official_title
+            .or(identification.brief_title)
+            .unwrap_or_else(|| nct_id.clone()),
+        nct_id,
+        phase: format_phases(&design.phases),
+        status: status.overall_status.unwrap_or_else(|| "UNKNOWN".to_string()),
+        enrollment: design.enrollment_info.and_then(|info| info.count),
+        has_results: study.has_results,
+        conditions: protocol.conditions_module.map(|module| module.conditions).unwrap_or_default(),
+        interventions: protocol
+            .arms_interventions_module
+            .map(|module| module.interventions.into_iter().filter_map(|i| i.name).collect())
+            .unwrap_or_default(),
+        sponsor: protocol
+            .sponsor_collaborators_module
+            .and_then(|module| module.lead_sponsor)
+            .and_then(|sponsor| sponsor.name),
+        start_date: status.start_date_struct.and_then(|date| date.date),
+        completion_date: status.completion_date_struct.and_then(|date| date.date),
+        protocol_ids: Vec::new(),
+        fetched_at,
+    })
+}
+
+/// `["PHASE1", "PHASE2"]` becomes "Phase 1/Phase 2"; "NA" means no phase
+fn format_phases(phases: &[String]) -> Option<String> {
+    let names: Vec<String> = phases
+        .iter()
+        .filter(|phase| phase.as_str() != "NA")
+        .map(|phase| match phase.as_str() {
+            "EARLY_PHASE1" => "Early Phase 1".to_string(),
+            other => match other.strip_prefix("PHASE") {
+                Some(number) => format!("Phase {}", number),
+                None => other.to_string(),
+            },
+        })
+        .collect();
+    (!names.is_empty()).then(|| names.join("/"))
+}
+
+// ClinicalTrials.gov API response types
+
+#[derive(Debug, Deserialize)]
+struct StudiesResponse {
+    #[serde(default)]
+    studies: Vec<Study>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Study {
+    protocol_section: ProtocolSection,
+    #[serde(default)]
+    has_results: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProtocolSection {
+    identification_module: IdentificationModule,
+    #[serde(default)]
+    status_module: Option<StatusModule>,
+    #[serde(default)]
+    design_module: Option<DesignModule>,
+    #[serde(default)]
+    conditions_module: Option<ConditionsModule>,
+    #[serde(default)]
+    arms_interventions_module: Option<ArmsInterventionsModule>,
+    #[serde(default)]
+    sponsor_collaborators_module: Option<SponsorModule>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IdentificationModule {
+    #[serde(default)]
+    nct_id: Option<String>,
+    #[serde(default)]
+    brief_title: Option<String>,
+    #[serde(default)]
+    official_title: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusModule {
+    #[serde(default)]
+    overall_status: Option<String>,
+    #[serde(default)]
+    start_date_struct: Option<DateStruct>,
+    #[serde(default)]
+    completion_date_struct: Option<DateStruct>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DateStruct {
+    #[serde(default)]
+    date: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DesignModule {
+    #[serde(default)]
+    phases: Vec<String>,
+    #[serde(default)]
+    enrollment_info: Option<EnrollmentInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnrollmentInfo {
+    #[serde(default)]
+    count: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConditionsModule {
+    #[serde(default)]
+    conditions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArmsInterventionsModule {
+    #[serde(default)]
+    interventions: Vec<Intervention>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Intervention {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SponsorModule {
+    #[serde(default)]
+    lead_sponsor: Option<Sponsor>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Sponsor {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn studies_become_trials() {
+        let response: StudiesResponse = serde_json::from_str(
+            r#"{"studies": [
+                {"protocolSection": {
+                    "identificationModule": {"nctId": "NCT01234567", "briefTitle": "BPC-157 for Knee Pain"},
+                    "statusModule": {"overallStatus": "COMPLETED", "startDateStruct": {"date": "2021-03"}},
+                    "designModule": {"phases": ["PHASE1", "PHASE2"], "enrollmentInfo": {"count": 42}},
+                    "conditionsModule": {"conditions": ["Knee Osteoarthritis"]},
+                    "armsInterventionsModule": {"interventions": [{"name": "BPC-157"}, {"name": "Placebo"}]},
+                    "sponsorCollaboratorsModule": {"leadSponsor": {"name": "Example University"}}
+                }, "hasResults": true},
+                {"protocolSection": {"identificationModule": {"briefTitle": "No id"}}}
+            ]}"#,
+        )
+        .unwrap();
+        let fetched_at = datetime!(2024-06-01 12:00 UTC);
+        let trials: Vec<TrialResult> = response
+            .studies
+            .into_iter()
+            .filter_map(|study| study_to_trial(study, fetched_at))
+            .collect();
+
+        assert_eq!(trials.len(), 1);
+        let trial = &trials[0];
+        assert_eq!(trial.title, "BPC-157 for Knee Pain");
+        assert_eq!(trial.phase.as_deref(), Some("Phase 1/Phase 2"));
+        assert_eq!(trial.status, "COMPLETED");
+        assert_eq!(trial.enrollment, Some(42));
+        assert!(trial.has_results);
+        assert_eq!(trial.interventions, vec!["BPC-157", "Placebo"]);
+        assert_eq!(trial.sponsor.as_deref(), Some("Example University"));
+        assert_eq!(trial.url(), "https://clinicaltrials.gov/study/NCT01234567");
+    }
+
+    #[test]
+    fn phases_are_readable() {
+        assert_eq!(format_phases(&["EARLY_PHASE1".into()]).as_deref(), Some("Early Phase 1"));
+        assert_eq!(format_phases(&["NA".into()]), None);
+        assert_eq!(format_phases(&[]), None);
+    }
+}
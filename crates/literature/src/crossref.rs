@@ -26,9 +26,25 @@ use async_trait::async_trait;
 use serde::Deserialize;
 use tracing::debug;
 
-use crate::models::{LiteratureFetcher, LiteratureResult};
+use crate::models::{normalize_doi, LiteratureFetcher, LiteratureResult};
+use crate::pagination::{cursor_offset, next_offset, PageCursor, ResultPage};
 
 const API_BASE: &str = "https://api.crossref.org/works";
+/// Largest page Crossref returns
+const MAX_ROWS: usize = 1000;
+/// Crossref refuses offsets past this; deeper paging needs its cursor
+const MAX_OFFSET: usize = 10_000;
+/// Most notices fetched for one work; real works have a handful at most
+const MAX_NOTICES: usize = 20;
+
+/// A published notice that updates an earlier work
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditorialNotice {
+    /// Crossref update type, e.g. "retraction", "correction", "erratum"
+    pub notice_type: String,
+    /// DOI of the notice itself
+    pub notice_doi: Option<String>,
+}
 
 /// Crossref API fetcher
 pub struct CrossrefFetcher {
@@ -39,15 +55,84 @@ impl CrossrefFetcher {
     /// Creates a new Crossref fetcher
     pub fn new() -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("PepTrack/1.0 (mailto:support@peptrack.app)")
-                .timeout(std::time::Duration::from_secs(30))
+            client: crate::http::client_builder("PepTrack/1.0 (mailto:support@peptrack.app)")
                 .build()
                 .expect("Failed to create HTTP client"),
         }
     }
 }
 
+impl CrossrefFetcher {
+    /// Metadata for the work with `doi`, or None if Crossref doesn't know it
+    pub async fn lookup_doi(&self, doi: &str) -> Result<Option<LiteratureResult>> {
+        let url = format!("{}/{}", API_BASE, urlencoding::encode(&normalize_doi(doi)));
+        debug!("Crossref DOI URL: {}", url);
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to send Crossref request")?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let work: WorkResponse = response
+            .error_for_status()
+            .context("Crossref DOI request failed")?
+            .json()
+            .await
+            .context("Failed to parse Crossref work")?;
+        Ok(Some(work_to_result(work.message)))
+    }
+}
+
+impl CrossrefFetcher {
+    /// Editorial notices (retractions, corrections, ...) that update the
+    /// work with `doi`
+    pub async fn editorial_notices(&self, doi: &str) -> Result<Vec<EditorialNotice>> {
+        let doi = normalize_doi(doi);
+        let url = format!(
+            "{}?filter=updates:{}&rows={}",
+            API_BASE,
+            urlencoding::encode(&doi),
+            MAX_NOTICES
+        );
+        debug!("Crossref notices URL: {}", url);
+
+        let response: NoticeResponse = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to send Crossref request")?
+            .error_for_status()
+            .context("Crossref notices request failed")?
+            .json()
+            .await
+            .context("Failed to parse Crossref notices")?;
+
+        Ok(response
+            .message
+            .items
+            .into_iter()
+            .flat_map(|item| {
+                let notice_doi = item.doi;
+                item.update_to
+                    .into_iter()
+                    .filter(|update| {
+                        update.doi.as_deref().map(normalize_doi).as_deref() == Some(doi.as_str())
+                    })
+                    .map(move |update| EditorialNotice {
+                        notice_type: update.update_type.to_lowercase(),
+                        notice_doi: notice_doi.as_deref().map(normalize_doi),
+                    })
+            })
+            .collect())
+    }
+}
+
 impl Default for CrossrefFetcher {
     fn default() -> Self {
         Self::new()
@@ -57,11 +142,17 @@ impl Default for CrossrefFetcher {
 #[async_trait]
 impl LiteratureFetcher for CrossrefFetcher {
     async fn search(&self, query: &str, max_results: usize) -> Result<Vec<LiteratureResult>> {
+        Ok(self.search_page(query, max_results, None).await?.results)
+    }
+
+    async fn search_page(&self, query: &str, page_size: usize, cursor: Option<&PageCursor>) -> Result<ResultPage> {
+        let offset = cursor_offset(cursor);
         let url = format!(
-            "{}?query={}&rows={}",
+            "{}?query={}&rows={}&offset={}",
             API_BASE,
             urlencoding::encode(query),
-            max_results
+            page_size.min(MAX_ROWS),
+            offset
         );
 
         debug!("Crossref search URL: {}", url);
@@ -81,67 +172,19 @@ impl LiteratureFetcher for CrossrefFetcher {
         let search_result: CrossrefResponse = serde_json::from_str(&body)
             .with_context(|| format!("Failed to parse Crossref response: {}", body))?;
 
-        let results = search_result
+        let total = search_result.message.total_results;
+        let results: Vec<LiteratureResult> = search_result
             .message
             .items
             .into_iter()
-            .map(|work| {
-                let authors = if work.author.is_empty() {
-                    None
-                } else {
-                    Some(
-                        work.author
-                            .iter()
-                            .filter_map(|a| {
-                                if let (Some(given), Some(family)) = (&a.given, &a.family) {
-                                    Some(format!("{} {}", given, family))
-                                } else {
-                                    a.family.clone()
-                                }
-                            })
-                            .collect::<Vec<_>>()
-                            .join(", "),
-                    )
-                };
-
-                let url = work.url.or_else(|| {
-                    work.doi
-                        .as_ref()
-                        .map(|doi| format!("https://doi.org/{}", doi))
-                });
-
-                let published_date = work.published.and_then(|p| {
-                    p.date_parts.first().and_then(|parts| {
-                        if parts.is_empty() {
-                            None
-                        } else if parts.len() == 1 {
-                            Some(format!("{}", parts[0]))
-                        } else if parts.len() == 2 {
-                            Some(format!("{}-{:02}", parts[0], parts[1]))
-                        } else {
-                            Some(format!("{}-{:02}-{:02}", parts[0], parts[1], parts[2]))
-                        }
-                    })
-                });
-
-                let journal = work
-                    .container_title
-                    .and_then(|titles| titles.first().cloned());
-
-                LiteratureResult {
-                    source: "crossref".to_string(),
-                    title: work.title.first().cloned().unwrap_or_default(),
-                    url,
-                    doi: work.doi,
-                    authors,
-                    published_date,
-                    journal,
-                    abstract_text: work.abstract_text,
-                }
-            })
+            .map(work_to_result)
             .collect();
 
-        Ok(results)
+        Ok(ResultPage {
+            next: next_offset(offset, results.len(), total, Some(MAX_OFFSET)),
+            results,
+            total,
+        })
     }
 
     fn source_name(&self) -> &'static str {
@@ -149,6 +192,64 @@ impl LiteratureFetcher for CrossrefFetcher {
     }
 }
 
+/// Converts a Crossref work into a normalized result
+fn work_to_result(work: Work) -> LiteratureResult {
+    let authors = if work.author.is_empty() {
+        None
+    } else {
+        Some(
+            work.author
+                .iter()
+                .filter_map(|a| {
+                    if let (Some(given), Some(family)) = (&a.given, &a.family) {
+                        Some(format!("{} {}", given, family))
+                    } else {
+                        a.family.clone()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    };
+
+    let url = work.url.or_else(|| {
+        work.doi
+            .as_ref()
+            .map(|doi| format!("https://doi.org/{}", doi))
+    });
+
+    let published_date = work.published.and_then(|p| {
+        p.date_parts.first().and_then(|parts| {
+            if parts.is_empty() {
+                None
+            } else if parts.len() == 1 {
+                Some(format!("{}", parts[0]))
+            } else if parts.len() == 2 {
+                Some(format!("{}-{:02}", parts[0], parts[1]))
+            } else {
+                Some(format!("{}-{:02}-{:02}", parts[0], parts[1], parts[2]))
+            }
+        })
+    });
+
+    let journal = work
+        .container_title
+        .and_then(|titles| titles.first().cloned());
+
+    LiteratureResult {
+        source: "crossref".to_string(),
+        title: work.title.first().cloned().unwrap_or_default(),
+        url,
+        doi: work.doi,
+        authors,
+        published_date,
+        journal,
+        abstract_text: work.abstract_text,
+        citation_count: work.citation_count,
+        publication_types: work.work_type.into_iter().collect(),
+    }
+}
+
 // Crossref API response types
 
 #[derive(Debug, Deserialize)]
@@ -157,15 +258,49 @@ struct CrossrefResponse {
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 struct Message {
     items: Vec<Work>,
+    #[serde(default)]
+    total_results: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NoticeResponse {
+    message: NoticeMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct NoticeMessage {
+    items: Vec<NoticeItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NoticeItem {
+    #[serde(default, alias = "DOI")]
+    doi: Option<String>,
+    #[serde(default, rename = "update-to")]
+    update_to: Vec<Update>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Update {
+    #[serde(default, alias = "DOI")]
+    doi: Option<String>,
+    #[serde(rename = "type")]
+    update_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkResponse {
+    message: Work,
 }
 
 #[derive(Debug, Deserialize)]
 struct Work {
-    #[serde(default)]
+    #[serde(default, alias = "DOI")]
     doi: Option<String>,
-    #[serde(default)]
+    #[serde(default, alias = "URL")]
     url: Option<String>,
     title: Vec<String>,
     #[serde(default)]
@@ -176,6 +311,10 @@ struct Work {
     container_title: Option<Vec<String>>,
     #[serde(default, rename = "abstract")]
     abstract_text: Option<String>,
+    #[serde(default, rename = "is-referenced-by-count")]
+    citation_count: Option<u32>,
+    #[serde(default, rename = "type")]
+    work_type: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -220,6 +359,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn crossref_work_reads_uppercase_doi_and_url() {
+        let work: Work = serde_json::from_str(
+            r#"{
+                "DOI": "10.1000/xyz",
+                "URL": "https://doi.org/10.1000/xyz",
+                "title": ["Tendon healing"],
+                "author": [{"given": "Jane", "family": "Doe"}],
+                "published": {"date-parts": [[2021, 3]]},
+                "container-title": ["Peptides"]
+            }"#,
+        )
+        .unwrap();
+        let result = work_to_result(work);
+        assert_eq!(result.doi.as_deref(), Some("10.1000/xyz"));
+        assert_eq!(result.authors.as_deref(), Some("Jane Doe"));
+        assert_eq!(result.published_date.as_deref(), Some("2021-03"));
+        assert_eq!(result.journal.as_deref(), Some("Peptides"));
+    }
+
     #[test]
     fn crossref_fetcher_can_be_created() {
         let _fetcher = CrossrefFetcher::new();
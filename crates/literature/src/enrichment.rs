@@ -0,0 +1,228 @@
+//! Metadata enrichment for cached literature
+//!
+//! Entries cached from PubMed or older versions often lack a DOI, journal,
+//! authors or year. [`MetadataEnricher`] looks each one up on Crossref, by
+//! DOI when it has one and by title otherwise, and falls back to OpenAlex for
+//! whatever Crossref couldn't supply. Only missing fields are filled in.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use peptrack_core::models::publication_year;
+use peptrack_core::LiteratureEntry;
+use tracing::{debug, warn};
+
+use crate::crossref::CrossrefFetcher;
+use crate::models::{normalize_doi, LiteratureFetcher, LiteratureResult};
+use crate::openalex::OpenAlexFetcher;
+
+/// Results compared against an entry's title when it has no DOI
+const TITLE_CANDIDATES: usize = 3;
+
+/// Lowercase words of a title, ignoring punctuation
+fn title_words(title: &str) -> Vec<String> {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Whether two titles name the same paper, ignoring case and punctuation
+pub fn titles_match(a: &str, b: &str) -> bool {
+    let a = title_words(a);
+    !a.is_empty() && a == title_words(b)
+}
+
+/// Fill the fields `entry` is missing from `metadata`; returns whether
+/// anything changed
+pub fn apply_metadata(entry: &mut LiteratureEntry, metadata: &LiteratureResult) -> bool {
+    let mut changed = false;
+    if entry.doi.is_none() {
+        entry.doi = metadata.doi.as_deref().map(normalize_doi);
+        changed |= entry.doi.is_some();
+    }
+    if entry.authors.is_empty() {
+        entry.authors = metadata.author_list();
+        changed |= !entry.authors.is_empty();
+    }
+    if entry.journal.is_none() {
+        entry.journal = metadata.journal.clone();
+        changed |= entry.journal.is_some();
+    }
+    if entry.published_date.is_none() {
+        entry.published_date = metadata.published_date.clone();
+        changed |= entry.published_date.is_some();
+    }
+    if entry.year.is_none() {
+        entry.year = entry.published_date.as_deref().and_then(publication_year);
+        changed |= entry.year.is_some();
+    }
+    if entry.citation_count.is_none() {
+        entry.citation_count = metadata.citation_count;
+        changed |= entry.citation_count.is_some();
+    }
+    changed
+}
+
+/// Looks up missing metadata on Crossref, then OpenAlex
+pub struct MetadataEnricher {
+    crossref: CrossrefFetcher,
+    openalex: OpenAlexFetcher,
+}
+
+impl MetadataEnricher {
+    pub fn new() -> Self {
+        Self {
+            crossref: CrossrefFetcher::new(),
+            openalex: OpenAlexFetcher::new(),
+        }
+    }
+
+    /// Fill in what `entry` is missing; returns whether anything changed
+    ///
+    /// A source that fails is skipped; the error is only returned when no
+    /// source could be asked at all, so the entry can be retried later.
+    pub async fn enrich(&self, entry: &mut LiteratureEntry) -> Result<bool> {
+        let mut changed = false;
+        let mut last_error = None;
+        let mut any_answered = false;
+
+        let sources: [&dyn MetadataSource; 2] = [&self.crossref, &self.openalex];
+        for source in sources {
+            if !entry.is_missing_metadata() {
+                break;
+            }
+            match source.find(entry).await {
+                Ok(Some(metadata)) => {
+                    any_answered = true;
+                    changed |= apply_metadata(entry, &metadata);
+                }
+                Ok(None) => {
+                    any_answered = true;
+                    debug!("{} has no match for \"{}\"", source.name(), entry.title);
+                }
+                Err(e) => {
+                    warn!(
+                        "{} lookup failed for \"{}\": {:#}",
+                        source.name(),
+                        entry.title,
+                        e
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if !any_answered => Err(e),
+            _ => Ok(changed),
+        }
+    }
+}
+
+impl Default for MetadataEnricher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A service that can find a cached entry's metadata
+#[async_trait]
+trait MetadataSource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn lookup_doi(&self, doi: &str) -> Result<Option<LiteratureResult>>;
+
+    async fn search_title(&self, title: &str) -> Result<Vec<LiteratureResult>>;
+
+    /// The entry's work, by DOI when known and otherwise by exact title
+    async fn find(&self, entry: &LiteratureEntry) -> Result<Option<LiteratureResult>> {
+        if let Some(doi) = &entry.doi {
+            return self.lookup_doi(doi).await;
+        }
+        Ok(self
+            .search_title(&entry.title)
+            .await?
+            .into_iter()
+            .find(|result| titles_match(&result.title, &entry.title)))
+    }
+}
+
+#[async_trait]
+impl MetadataSource for CrossrefFetcher {
+    fn name(&self) -> &'static str {
+        "Crossref"
+    }
+
+    async fn lookup_doi(&self, doi: &str) -> Result<Option<LiteratureResult>> {
+        CrossrefFetcher::lookup_doi(self, doi).await
+    }
+
+    async fn search_title(&self, title: &str) -> Result<Vec<LiteratureResult>> {
+        self.search(title, TITLE_CANDIDATES).await
+    }
+}
+
+#[async_trait]
+impl MetadataSource for OpenAlexFetcher {
+    fn name(&self) -> &'static str {
+        "OpenAlex"
+    }
+
+    async fn lookup_doi(&self, doi: &str) -> Result<Option<LiteratureResult>> {
+        OpenAlexFetcher::lookup_doi(self, doi).await
+    }
+
+    async fn search_title(&self, title: &str) -> Result<Vec<LiteratureResult>> {
+        self.search(title, TITLE_CANDIDATES).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> LiteratureResult {
+        LiteratureResult {
+            source: "crossref".to_string(),
+            title: "BPC-157 and Tendon Healing".to_string(),
+            url: None,
+            doi: Some("https://doi.org/10.1000/XYZ".to_string()),
+            authors: Some("Jane Doe, John Roe".to_string()),
+            published_date: Some("2021-03".to_string()),
+            journal: Some("Peptides".to_string()),
+            abstract_text: None,
+            citation_count: Some(12),
+            publication_types: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_titles_match() {
+        assert!(titles_match(
+            "BPC-157 and tendon healing.",
+            "bpc 157 and Tendon Healing"
+        ));
+        assert!(!titles_match(
+            "BPC-157 and tendon healing",
+            "BPC-157 and bone healing"
+        ));
+        assert!(!titles_match("", ""));
+    }
+
+    #[test]
+    fn test_apply_metadata_fills_only_missing_fields() {
+        let mut entry = LiteratureEntry::new("pubmed", "BPC-157 and Tendon Healing");
+        entry.journal = Some("J Orthop Res".to_string());
+
+        assert!(apply_metadata(&mut entry, &metadata()));
+        assert_eq!(entry.doi.as_deref(), Some("10.1000/xyz"));
+        assert_eq!(entry.authors, vec!["Jane Doe", "John Roe"]);
+        assert_eq!(entry.journal.as_deref(), Some("J Orthop Res"));
+        assert_eq!(entry.year, Some(2021));
+        assert_eq!(entry.citation_count, Some(12));
+        assert!(!entry.is_missing_metadata());
+
+        assert!(!apply_metadata(&mut entry, &metadata()));
+    }
+}
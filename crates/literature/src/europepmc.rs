@@ -0,0 +1,429 @@
+//! Europe PMC API integration
+//!
+//! Europe PMC indexes PubMed plus preprints and patents, and searches the
+//! full text of its open-access papers, not only titles and abstracts. For
+//! those papers it also serves the full text as JATS XML, which gives the AI
+//! summarizer far more to work with than an abstract.
+//!
+//! # API Documentation
+//!
+//! - REST API: https://europepmc.org/RestfulWebService
+//! - No key required; please keep to a few requests per second
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use peptrack_literature::{EuropePmcFetcher, LiteratureFetcher};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let fetcher = EuropePmcFetcher::new();
+//! let results = fetcher.search("BPC-157 tendon", 5).await?;
+//! if let Some(doi) = results.first().and_then(|paper| paper.doi.as_deref()) {
+//!     let text = fetcher.full_text_for_doi(doi).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::models::{normalize_doi, LiteratureFetcher, LiteratureResult};
+use crate::pagination::{PageCursor, ResultPage};
+
+const API_BASE: &str = "https://www.ebi.ac.uk/europepmc/webservices/rest";
+/// Largest page Europe PMC returns
+const MAX_PAGE_SIZE: usize = 1000;
+/// Full text is cut to this many characters, about what the summarizer
+/// reads in one pass
+pub const MAX_FULL_TEXT_CHARS: usize = 24_000;
+
+/// Europe PMC API fetcher
+pub struct EuropePmcFetcher {
+    client: reqwest::Client,
+}
+
+impl EuropePmcFetcher {
+    /// Creates a new Europe PMC fetcher
+    pub fn new() -> Self {
+        Self {
+            client: crate::http::client_builder("PepTrack/1.0 (mailto:support@peptrack.app)")
+                .build()
+                .expect("Failed to create HTTP client"),
+        }
+    }
+
+    async fn query(&self, query: &str, page_size: usize, cursor_mark: &str) -> Result<SearchResponse> {
+        let url = format!(
+            "{}/search?query={}&format=json&resultType=core&pageSize={}&cursorMark={}",
+            API_BASE,
+            urlencoding::encode(query),
+            page_size.min(MAX_PAGE_SIZE),
+            urlencoding::encode(cursor_mark)
+        );
+        debug!("Europe PMC search URL: {}", url);
+
+        let response: SearchResponse = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to send Europe PMC request")?
+            .error_for_status()
+            .context("Europe PMC search failed")?
+            .json()
+            .await
+            .context("Failed to parse Europe PMC response")?;
+        Ok(response)
+    }
+
+    /// Plain text of the open-access paper with `pmcid` (e.g. "PMC1234567"),
+    /// or None if Europe PMC has no full text for it
+    pub async fn full_text(&self, pmcid: &str) -> Result<Option<String>> {
+        let url = format!("{}/{}/fullTextXML", API_BASE, pmcid);
+        debug!("Europe PMC full text URL: {}", url);
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to send Europe PMC full text request")?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let xml = response
+            .error_for_status()
+            .context("Europe PMC full text request failed")?
+            .text()
+            .await
+            .context("Failed to read Europe PMC full text")?;
+
+        let text = jats_to_text(&xml);
+        Ok((!text.is_empty()).then(|| truncate_chars(text, MAX_FULL_TEXT_CHARS)))
+    }
+
+    /// Plain text of the open-access paper with `doi`, if Europe PMC has it
+    pub async fn full_text_for_doi(&self, doi: &str) -> Result<Option<String>> {
+        let query = format!("DOI:\"{}\" AND OPEN_ACCESS:y", normalize_doi(doi));
+        let pmcid = self
+            .query(&query, 1, "*")
+            .await?
+            .result_list
+            .result
+            .into_iter()
+            .filter(Article::has_full_text)
+            .find_map(|article| article.pmcid);
+        match pmcid {
+            Some(pmcid) => self.full_text(&pmcid).await,
+            None => Ok(None),
+        }
+    }
+}
+
+impl Default for EuropePmcFetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl LiteratureFetcher for EuropePmcFetcher {
+    async fn search(&self, query: &str, max_results: usize) -> Result<Vec<LiteratureResult>> {
+        Ok(self.search_page(query, max_results, None).await?.results)
+    }
+
+    async fn search_page(&self, query: &str, page_size: usize, cursor: Option<&PageCursor>) -> Result<ResultPage> {
+        let cursor_mark = match cursor {
+            Some(PageCursor::Token(token)) => token.as_str(),
+            _ => "*",
+        };
+        let response = self.query(query, page_size, cursor_mark).await?;
+        let results: Vec<LiteratureResult> = response
+            .result_list
+            .result
+            .into_iter()
+            .map(article_to_result)
+            .collect();
+
+        // The last page repeats the cursor it was fetched with
+        let next = response
+            .next_cursor_mark
+            .filter(|next| !results.is_empty() && next != cursor_mark)
+            .map(PageCursor::Token);
+        Ok(ResultPage {
+            results,
+            next,
+            total: response.hit_count,
+        })
+    }
+
+    fn source_name(&self) -> &'static str {
+        "europepmc"
+    }
+}
+
+/// Converts a Europe PMC article into a normalized result
+fn article_to_result(article: Article) -> LiteratureResult {
+    let url = match (&article.source, &article.id) {
+        (Some(source), Some(id)) => Some(format!("https://europepmc.org/article/{}/{}", source, id)),
+        _ => article.doi.as_ref().map(|doi| format!("https://doi.org/{}", doi)),
+    };
+
+    LiteratureResult {
+        source: "europepmc".to_string(),
+        title: strip_tags(&article.title.unwrap_or_default()),
+        url,
+        doi: article.doi,
+        authors: article.author_string.map(|authors| authors.trim_end_matches('.').to_string()),
+        published_date: article.first_publication_date,
+        journal: article
+            .journal_info
+            .and_then(|info| info.journal)
+            .and_then(|journal| journal.title)
+            .or(article.book_or_report_details.and_then(|details| details.publisher)),
+        abstract_text: article.abstract_text.map(|text| strip_tags(&text)),
+        citation_count: article.cited_by_count,
+        publication_types: article.pub_type_list.map(|list| list.pub_type).unwrap_or_default(),
+    }
+}
+
+/// Readable text of a JATS article: its abstract and body, without
+/// references, tables or figures
+pub fn jats_to_text(xml: &str) -> String {
+    let mut xml = xml.to_string();
+    for element in ["ref-list", "table-wrap", "fig", "xref", "supplementary-material"] {
+        xml = remove_elements(&xml, element);
+    }
+
+    let sections: Vec<&str> = ["abstract", "body"]
+        .into_iter()
+        .filter_map(|element| element_content(&xml, element))
+        .collect();
+    if sections.is_empty() {
+        strip_tags(&xml)
+    } else {
+        strip_tags(&sections.join("\n"))
+    }
+}
+
+/// Content of the first `<element>`, attributes allowed
+fn element_content<'a>(xml: &'a str, element: &str) -> Option<&'a str> {
+    let start = find_open_tag(xml, element, 0)?;
+    let content_start = start + xml[start..].find('>')? + 1;
+    let end = xml[content_start..].find(&format!("</{}>", element))? + content_start;
+    Some(&xml[content_start..end])
+}
+
+/// `xml` with every `<element>...</element>` and `<element/>` removed
+fn remove_elements(xml: &str, element: &str) -> String {
+    let close = format!("</{}>", element);
+    let mut out = String::with_capacity(xml.len());
+    let mut rest = 0;
+    while let Some(start) = find_open_tag(xml, element, rest) {
+        out.push_str(&xml[rest..start]);
+        let Some(tag_end) = xml[start..].find('>').map(|i| start + i) else {
+            rest = xml.len();
+            break;
+        };
+        rest = if xml[..tag_end].ends_with('/') {
+            tag_end + 1
+        } else {
+            xml[tag_end..].find(&close).map_or(xml.len(), |i| tag_end + i + close.len())
+        };
+    }
+    out.push_str(&xml[rest.min(xml.len())..]);
+    out
+}
+
+/// Position of the next `<element` tag that isn't a longer name, e.g.
+/// `<fig` without matching `<fig-group`
+fn find_open_tag(xml: &str, element: &str, from: usize) -> Option<usize> {
+    let open = format!("<{}", element);
+    let mut from = from;
+    while let Some(i) = xml[from..].find(&open) {
+        let start = from + i;
+        match xml[start + open.len()..].chars().next() {
+            Some('>' | '/' | ' ' | '\n' | '\t' | '\r') => return Some(start),
+            _ => from = start + open.len(),
+        }
+    }
+    None
+}
+
+/// Text with markup removed, entities decoded and whitespace collapsed;
+/// block elements end a line
+fn strip_tags(markup: &str) -> String {
+    let mut text = String::with_capacity(markup.len());
+    let mut rest = markup;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim_start_matches('/');
+        let name = tag.split([' ', '/', '\n', '\t']).next().unwrap_or_default();
+        text.push(if matches!(name, "p" | "title" | "sec" | "abstract" | "body" | "br") { '\n' } else { ' ' });
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+
+    let text = decode_entities(&text);
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#x2009;", " ")
+        .replace("&#160;", " ")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn truncate_chars(text: String, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text,
+    }
+}
+
+// Europe PMC API response types
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResponse {
+    #[serde(default)]
+    hit_count: Option<usize>,
+    #[serde(default)]
+    next_cursor_mark: Option<String>,
+    result_list: ResultList,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultList {
+    #[serde(default)]
+    result: Vec<Article>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Article {
+    #[serde(default)]
+    id: Option<String>,
+    /// e.g. "MED", "PMC", "PPR"
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    pmcid: Option<String>,
+    #[serde(default)]
+    doi: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    author_string: Option<String>,
+    #[serde(default)]
+    journal_info: Option<JournalInfo>,
+    #[serde(default)]
+    book_or_report_details: Option<BookDetails>,
+    #[serde(default)]
+    first_publication_date: Option<String>,
+    #[serde(default)]
+    abstract_text: Option<String>,
+    #[serde(default)]
+    cited_by_count: Option<u32>,
+    #[serde(default)]
+    pub_type_list: Option<PubTypeList>,
+    /// "Y" or "N"
+    #[serde(default)]
+    in_epmc: Option<String>,
+    #[serde(default)]
+    is_open_access: Option<String>,
+}
+
+impl Article {
+    fn has_full_text(&self) -> bool {
+        self.in_epmc.as_deref() == Some("Y") || self.is_open_access.as_deref() == Some("Y")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JournalInfo {
+    #[serde(default)]
+    journal: Option<Journal>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Journal {
+    #[serde(default)]
+    title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BookDetails {
+    #[serde(default)]
+    publisher: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PubTypeList {
+    #[serde(default)]
+    pub_type: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn europepmc_articles_are_normalized() {
+        let response: SearchResponse = serde_json::from_str(
+            r#"{"resultList": {"result": [{
+                "id": "38000001", "source": "MED", "pmcid": "PMC100", "doi": "10.1000/bpc",
+                "title": "BPC-157 and <i>tendon</i> healing", "authorString": "Doe J, Roe A.",
+                "journalInfo": {"journal": {"title": "J Peptide Res"}},
+                "firstPublicationDate": "2023-04-01", "abstractText": "<h4>Aim</h4>Healing.",
+                "citedByCount": 7, "pubTypeList": {"pubType": ["Review"]},
+                "inEPMC": "Y", "isOpenAccess": "Y"
+            }]}}"#,
+        )
+        .unwrap();
+        let article = response.result_list.result.into_iter().next().unwrap();
+        assert!(article.has_full_text());
+
+        let result = article_to_result(article);
+        assert_eq!(result.title, "BPC-157 and tendon healing");
+        assert_eq!(result.url.as_deref(), Some("https://europepmc.org/article/MED/38000001"));
+        assert_eq!(result.author_list(), vec!["Doe J", "Roe A"]);
+        assert_eq!(result.journal.as_deref(), Some("J Peptide Res"));
+        assert_eq!(result.abstract_text.as_deref(), Some("Aim Healing."));
+        assert_eq!(result.publication_types, vec!["Review"]);
+    }
+
+    #[test]
+    fn jats_full_text_keeps_the_abstract_and_body() {
+        let xml = r#"<article><front><article-meta><title-group><article-title>T</article-title></title-group>
+            <abstract><p>Short &amp; sweet.</p></abstract></article-meta></front>
+            <body><sec><title>Results</title><p>Healing improved<xref ref-type="bibr" rid="r1">1</xref>.</p>
+            <fig id="f1"><caption><p>Figure text</p></caption></fig>
+            <table-wrap id="t1"><table><tr><td>42</td></tr></table></table-wrap></sec></body>
+            <back><ref-list><ref id="r1">Cited paper</ref></ref-list></back></article>"#;
+        assert_eq!(jats_to_text(xml), "Short & sweet.\nResults\nHealing improved.");
+    }
+
+    #[test]
+    fn europepmc_fetcher_can_be_created() {
+        let fetcher = EuropePmcFetcher::new();
+        assert_eq!(fetcher.source_name(), "europepmc");
+    }
+}
@@ -0,0 +1,139 @@
+//! Shared HTTP client setup
+//!
+//! Every HTTP client PepTrack builds starts from [`client_builder`], so the
+//! proxy, extra CA certificates and timeout in [`HttpSettings`] apply to
+//! literature searches as well as scraping, Drive and the other requests
+//! the app makes. The app calls [`configure`] once the settings are loaded
+//! and again when they change; clients built afterwards pick them up.
+//!
+//! Settings that can't be applied, such as a CA file that's no longer
+//! readable, are logged and skipped so requests still go out.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use peptrack_core::HttpSettings;
+//! use peptrack_literature::http;
+//!
+//! http::configure(HttpSettings {
+//!     proxy_url: Some("http://proxy.local:3128".into()),
+//!     ..Default::default()
+//! });
+//! let client = http::client_builder("PepTrack/1.0").build()?;
+//! # Ok::<(), reqwest::Error>(())
+//! ```
+
+use std::sync::RwLock;
+
+use anyhow::{Context, Result};
+use peptrack_core::HttpSettings;
+use reqwest::{Certificate, ClientBuilder, NoProxy, Proxy};
+use tracing::warn;
+
+/// User agent for requests that don't set their own
+pub const DEFAULT_USER_AGENT: &str = "PepTrack/1.0";
+
+static SETTINGS: RwLock<Option<HttpSettings>> = RwLock::new(None);
+
+/// Use `settings` for clients built from now on
+pub fn configure(settings: HttpSettings) {
+    if let Ok(mut current) = SETTINGS.write() {
+        *current = Some(settings);
+    }
+}
+
+/// The settings clients are built with
+pub fn settings() -> HttpSettings {
+    SETTINGS.read().ok().and_then(|s| s.clone()).unwrap_or_default()
+}
+
+/// Request timeout from the current settings
+pub fn timeout() -> std::time::Duration {
+    settings().timeout()
+}
+
+/// A client builder with the current proxy, CA certificates and timeout
+pub fn client_builder(user_agent: &str) -> ClientBuilder {
+    let settings = settings();
+    builder_with(&settings, user_agent).unwrap_or_else(|e| {
+        warn!("Ignoring HTTP network settings: {:#}", e);
+        reqwest::Client::builder().user_agent(user_agent).timeout(settings.timeout())
+    })
+}
+
+/// A client with the current settings and [`DEFAULT_USER_AGENT`]
+pub fn client() -> reqwest::Client {
+    client_builder(DEFAULT_USER_AGENT)
+        .build()
+        .expect("Failed to create HTTP client")
+}
+
+/// A client builder with `settings` applied, failing if the proxy or CA
+/// certificates can't be used
+pub fn builder_with(settings: &HttpSettings, user_agent: &str) -> Result<ClientBuilder> {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(user_agent)
+        .timeout(settings.timeout());
+
+    if let Some(url) = settings.proxy() {
+        let proxy = Proxy::all(url)
+            .with_context(|| "Invalid proxy URL")?
+            .no_proxy(settings.no_proxy.as_deref().and_then(NoProxy::from_string));
+        builder = builder.proxy(proxy);
+    } else if !settings.use_env_proxy {
+        builder = builder.no_proxy();
+    }
+
+    if let Some(path) = &settings.ca_cert_path {
+        let pem = std::fs::read(path).with_context(|| format!("Failed to read CA certificates from {}", path.display()))?;
+        let certs = Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("Invalid CA certificates in {}", path.display()))?;
+        anyhow::ensure!(!certs.is_empty(), "No CA certificates in {}", path.display());
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn requests_go_through_the_proxy() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0u8; 1024];
+            let n = socket.read(&mut request).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request[..n]).into_owned()
+        });
+
+        let settings = HttpSettings { proxy_url: Some(proxy), ..Default::default() };
+        let client = builder_with(&settings, DEFAULT_USER_AGENT).unwrap().build().unwrap();
+        let body = client.get("http://papers.example/search").send().await.unwrap().text().await.unwrap();
+        assert_eq!(body, "ok");
+        assert!(server.await.unwrap().starts_with("GET http://papers.example/search HTTP/1.1"));
+    }
+
+    #[test]
+    fn unusable_ca_files_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ca.pem");
+        std::fs::write(&path, "not a certificate").unwrap();
+        let settings = HttpSettings { ca_cert_path: Some(path), ..Default::default() };
+        assert!(builder_with(&settings, DEFAULT_USER_AGENT).is_err());
+
+        let missing = HttpSettings { ca_cert_path: Some(dir.path().join("missing.pem")), ..Default::default() };
+        assert!(builder_with(&missing, DEFAULT_USER_AGENT).is_err());
+    }
+}
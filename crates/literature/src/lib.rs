@@ -4,12 +4,24 @@
 //! - PubMed: Free biomedical literature database
 //! - OpenAlex: Open catalog of scholarly works
 //! - Crossref: DOI-based metadata service
+//! - Europe PMC: Full-text search, and full text of open-access papers
+//! - ClinicalTrials.gov: Registered trials of a peptide (not papers; see
+//!   `clinical_trials`)
 //!
 //! # Architecture
 //!
 //! Each API has a dedicated fetcher module that implements normalized search.
 //! All fetchers return `LiteratureResult` structs that can be converted to
-//! `LiteratureEntry` for storage.
+//! `LiteratureEntry` for storage; `pagination` pages through results past
+//! the first page, and `cache` keeps responses so repeated searches don't
+//! call the APIs again. The `relevance` module scores results and
+//! tags them with the peptides and study type they mention, and the
+//! `enrichment` module fills in DOIs, authors and journals for cached entries.
+//! The `retractions` module flags cached papers that were retracted or
+//! corrected after they were cached, and `open_access` finds and downloads
+//! open-access PDFs. Clients are built by `http`, which applies the
+//! proxy, CA certificate and timeout settings; the app uses it for its other
+//! requests too.
 //!
 //! # Examples
 //!
@@ -26,12 +38,29 @@
 //! # }
 //! ```
 
+pub mod cache;
+pub mod clinical_trials;
 pub mod crossref;
+pub mod enrichment;
+pub mod europepmc;
+pub mod http;
 pub mod models;
+pub mod open_access;
 pub mod openalex;
+pub mod pagination;
 pub mod pubmed;
+pub mod relevance;
+pub mod retractions;
 
+pub use cache::{normalize_query, CachedFetcher};
+pub use clinical_trials::ClinicalTrialsFetcher;
 pub use crossref::CrossrefFetcher;
-pub use models::{LiteratureFetcher, LiteratureResult};
+pub use enrichment::MetadataEnricher;
+pub use europepmc::EuropePmcFetcher;
+pub use models::{normalize_doi, LiteratureFetcher, LiteratureResult};
+pub use open_access::OpenAccessLocator;
 pub use openalex::OpenAlexFetcher;
+pub use pagination::{search_all, PageCursor, ResultPage, ResultPages};
 pub use pubmed::PubMedFetcher;
+pub use relevance::{RelevanceContext, StudyType};
+pub use retractions::{EditorialStatus, RetractionChecker};
@@ -6,10 +6,16 @@ use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+pub use peptrack_core::models::normalize_doi;
+use peptrack_core::models::publication_year;
+
+use crate::pagination::{PageCursor, ResultPage};
+use crate::retractions::EditorialStatus;
+
 /// Normalized literature search result from any API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LiteratureResult {
-    /// Source API (e.g., "pubmed", "openalex", "crossref")
+    /// Source API (e.g., "pubmed", "openalex", "crossref", "europepmc")
     pub source: String,
     /// Paper title
     pub title: String,
@@ -25,6 +31,12 @@ pub struct LiteratureResult {
     pub journal: Option<String>,
     /// Abstract or summary text
     pub abstract_text: Option<String>,
+    /// Number of works citing this one, if the source reports it
+    #[serde(default)]
+    pub citation_count: Option<u32>,
+    /// Publication types as reported by the source, e.g. "Review"
+    #[serde(default)]
+    pub publication_types: Vec<String>,
 }
 
 impl LiteratureResult {
@@ -32,7 +44,9 @@ impl LiteratureResult {
     ///
     /// This is the format that gets stored in the encrypted database.
     /// The `summary` field is left empty - it will be filled by AI summarization later.
+    /// Relevance and tags are left empty; see [`crate::relevance`].
     pub fn to_entry(&self) -> peptrack_core::LiteratureEntry {
+        let status = EditorialStatus::from_publication_types(&self.publication_types);
         peptrack_core::LiteratureEntry {
             id: Uuid::new_v4().to_string(),
             source: self.source.clone(),
@@ -41,13 +55,77 @@ impl LiteratureResult {
             summary: self.abstract_text.clone(),
             relevance_score: None,
             indexed_at: OffsetDateTime::now_utc(),
+            tags: Vec::new(),
+            published_date: self.published_date.clone(),
+            citation_count: self.citation_count,
+            doi: self.doi.as_deref().map(normalize_doi),
+            authors: self.author_list(),
+            journal: self.journal.clone(),
+            year: self.published_date.as_deref().and_then(publication_year),
+            enriched_at: None,
+            retracted: status.retracted,
+            corrected: status.corrected,
+            notice_doi: None,
+            retraction_checked_at: None,
+            is_pinned: false,
+            reading_status: None,
+            rating: None,
+            reading_notes: None,
+            pdf_attachment_id: None,
+        }
+    }
+
+    /// A cached entry as a search result, e.g. to show cached papers when
+    /// the APIs can't be reached
+    pub fn from_entry(entry: &peptrack_core::LiteratureEntry) -> Self {
+        Self {
+            source: entry.source.clone(),
+            title: entry.title.clone(),
+            url: entry.url.clone(),
+            doi: entry.doi.clone(),
+            authors: (!entry.authors.is_empty()).then(|| entry.authors.join(", ")),
+            published_date: entry.published_date.clone(),
+            journal: entry.journal.clone(),
+            abstract_text: entry.summary.clone(),
+            citation_count: entry.citation_count,
+            publication_types: Vec::new(),
+        }
+    }
+
+    /// Authors as separate names
+    pub fn author_list(&self) -> Vec<String> {
+        self.authors
+            .as_deref()
+            .unwrap_or_default()
+            .split(", ")
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    /// Key identifying the paper across sources and searches: its DOI when
+    /// known, otherwise its URL, otherwise its title
+    pub fn dedup_key(&self) -> String {
+        if let Some(doi) = self.doi.as_deref().filter(|doi| !doi.trim().is_empty()) {
+            return format!("doi:{}", normalize_doi(doi));
+        }
+        if let Some(url) = self.url.as_deref().filter(|url| !url.trim().is_empty()) {
+            return format!("url:{}", url.trim());
         }
+        let title: Vec<String> = self
+            .title
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        format!("title:{}", title.join(" "))
     }
 }
 
 /// Trait for all literature fetchers
 ///
-/// Each API implementation (PubMed, OpenAlex, Crossref) implements this trait
+/// Each API implementation (PubMed, OpenAlex, Crossref, Europe PMC) implements this trait
 /// to provide a unified search interface.
 #[async_trait]
 pub trait LiteratureFetcher: Send + Sync {
@@ -63,6 +141,69 @@ pub trait LiteratureFetcher: Send + Sync {
     /// A vector of normalized literature results
     async fn search(&self, query: &str, max_results: usize) -> Result<Vec<LiteratureResult>>;
 
+    /// Fetches one page of up to `page_size` results, starting at `cursor`
+    /// (the first page when None)
+    ///
+    /// Sources without paging return their first page and no next cursor.
+    /// See [`crate::pagination::search_all`] to walk every page.
+    async fn search_page(&self, query: &str, page_size: usize, cursor: Option<&PageCursor>) -> Result<ResultPage> {
+        if cursor.is_some() {
+            return Ok(ResultPage::default());
+        }
+        Ok(ResultPage {
+            results: self.search(query, page_size).await?,
+            next: None,
+            total: None,
+        })
+    }
+
     /// Returns the source name for this fetcher (e.g., "pubmed")
     fn source_name(&self) -> &'static str;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedup_key() {
+        let mut result = LiteratureResult {
+            source: "openalex".into(),
+            title: "BPC-157: Tendon  Healing".into(),
+            url: Some("https://openalex.org/W1".into()),
+            doi: Some("https://doi.org/10.1000/XYZ".into()),
+            authors: None,
+            published_date: None,
+            journal: None,
+            abstract_text: None,
+            citation_count: None,
+            publication_types: Vec::new(),
+        };
+        assert_eq!(result.dedup_key(), "doi:10.1000/xyz");
+        result.doi = None;
+        assert_eq!(result.dedup_key(), "url:https://openalex.org/W1");
+        result.url = None;
+        assert_eq!(result.dedup_key(), "title:bpc 157 tendon healing");
+    }
+
+    #[test]
+    fn test_from_entry_keeps_metadata() {
+        let result = LiteratureResult {
+            source: "pubmed".into(),
+            title: "TB-500 and cardiac repair".into(),
+            url: None,
+            doi: Some("10.1000/ABC".into()),
+            authors: Some("Smith J, Doe A".into()),
+            published_date: Some("2021-03-01".into()),
+            journal: Some("Heart".into()),
+            abstract_text: Some("Abstract".into()),
+            citation_count: Some(4),
+            publication_types: Vec::new(),
+        };
+        let back = LiteratureResult::from_entry(&result.to_entry());
+        assert_eq!(back.doi.as_deref(), Some("10.1000/abc"));
+        assert_eq!(back.authors, result.authors);
+        assert_eq!(back.abstract_text, result.abstract_text);
+        assert_eq!(back.dedup_key(), result.dedup_key());
+    }
+}
@@ -0,0 +1,174 @@
+//! Open-access PDF lookup and download
+//!
+//! OpenAlex lists the open-access copies it knows of for a work; Unpaywall
+//! is asked when OpenAlex has none with a direct PDF link. Only locations
+//! that link straight to a PDF are used, not landing pages.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::models::normalize_doi;
+
+const OPENALEX_BASE: &str = "https://api.openalex.org/works";
+const UNPAYWALL_BASE: &str = "https://api.unpaywall.org/v2";
+/// Unpaywall requires an email with every request
+const UNPAYWALL_EMAIL: &str = "support@peptrack.app";
+
+/// Finds and downloads open-access PDFs of papers
+pub struct OpenAccessLocator {
+    client: reqwest::Client,
+}
+
+impl OpenAccessLocator {
+    pub fn new() -> Self {
+        Self {
+            client: crate::http::client_builder("PepTrack/1.0 (mailto:support@peptrack.app)")
+                // PDF downloads take longer than API calls
+                .timeout(crate::http::timeout() * 2)
+                .build()
+                .expect("Failed to create HTTP client"),
+        }
+    }
+
+    /// Direct link to an open-access PDF of the paper with `doi`, if any
+    ///
+    /// An error is only returned when both lookups failed.
+    pub async fn pdf_url(&self, doi: &str) -> Result<Option<String>> {
+        let doi = normalize_doi(doi);
+
+        let openalex = self
+            .get_json(&format!("{}/doi:{}", OPENALEX_BASE, doi))
+            .await
+            .map(|work| work.as_ref().and_then(pdf_url_from_openalex));
+        match &openalex {
+            Ok(Some(url)) => return Ok(Some(url.clone())),
+            Ok(None) => {}
+            Err(e) => warn!("OpenAlex open-access lookup failed for {}: {:#}", doi, e),
+        }
+
+        let unpaywall = self
+            .get_json(&format!(
+                "{}/{}?email={}",
+                UNPAYWALL_BASE,
+                urlencoding::encode(&doi),
+                UNPAYWALL_EMAIL
+            ))
+            .await
+            .map(|record| record.as_ref().and_then(pdf_url_from_unpaywall));
+        match (openalex, unpaywall) {
+            (_, Ok(url)) => Ok(url),
+            (Ok(_), Err(e)) => {
+                warn!("Unpaywall lookup failed for {}: {:#}", doi, e);
+                Ok(None)
+            }
+            (Err(_), Err(e)) => Err(e),
+        }
+    }
+
+    /// Download the PDF at `url`, refusing anything larger than `max_bytes`
+    /// or that isn't a PDF
+    pub async fn download_pdf(&self, url: &str, max_bytes: u64) -> Result<Vec<u8>> {
+        debug!("Downloading PDF from {}", url);
+        let response = self
+            .client
+            .get(url)
+            .header(reqwest::header::ACCEPT, "application/pdf")
+            .send()
+            .await
+            .context("Failed to request the PDF")?
+            .error_for_status()
+            .context("The PDF could not be downloaded")?;
+        if response.content_length().is_some_and(|length| length > max_bytes) {
+            bail!("The PDF is larger than {} MB", max_bytes / (1024 * 1024));
+        }
+
+        let data = response.bytes().await.context("Failed to download the PDF")?;
+        if data.len() as u64 > max_bytes {
+            bail!("The PDF is larger than {} MB", max_bytes / (1024 * 1024));
+        }
+        if !data.starts_with(b"%PDF-") {
+            bail!("The link did not return a PDF; the publisher may require a browser to download it");
+        }
+        Ok(data.to_vec())
+    }
+
+    /// JSON at `url`, or None when it's not found
+    async fn get_json(&self, url: &str) -> Result<Option<Value>> {
+        debug!("Open-access lookup URL: {}", url);
+        let response = self.client.get(url).send().await.context("Failed to send request")?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let value = response
+            .error_for_status()
+            .context("Open-access lookup failed")?
+            .json()
+            .await
+            .context("Failed to parse open-access lookup")?;
+        Ok(Some(value))
+    }
+}
+
+impl Default for OpenAccessLocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The best location's PDF link, or else the first location with one
+fn first_pdf_url<'a>(best: Option<&'a Value>, all: Option<&'a Value>, field: &str) -> Option<String> {
+    let pdf_url = |location: &Value| {
+        location
+            .get(field)
+            .and_then(Value::as_str)
+            .filter(|url| url.starts_with("https://") || url.starts_with("http://"))
+            .map(str::to_string)
+    };
+    best.and_then(pdf_url).or_else(|| {
+        all.and_then(Value::as_array)
+            .and_then(|locations| locations.iter().find_map(pdf_url))
+    })
+}
+
+/// PDF link from an OpenAlex work
+pub fn pdf_url_from_openalex(work: &Value) -> Option<String> {
+    first_pdf_url(work.get("best_oa_location"), work.get("locations"), "pdf_url")
+}
+
+/// PDF link from an Unpaywall record
+pub fn pdf_url_from_unpaywall(record: &Value) -> Option<String> {
+    first_pdf_url(record.get("best_oa_location"), record.get("oa_locations"), "url_for_pdf")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn pdf_links_prefer_the_best_location() {
+        let work = json!({
+            "best_oa_location": { "pdf_url": null, "landing_page_url": "https://example.org/paper" },
+            "locations": [
+                { "pdf_url": null },
+                { "pdf_url": "https://repository.example.org/paper.pdf" }
+            ]
+        });
+        assert_eq!(
+            pdf_url_from_openalex(&work).as_deref(),
+            Some("https://repository.example.org/paper.pdf")
+        );
+
+        let record = json!({
+            "best_oa_location": { "url_for_pdf": "https://journal.example.org/paper.pdf" },
+            "oa_locations": [{ "url_for_pdf": "https://mirror.example.org/paper.pdf" }]
+        });
+        assert_eq!(
+            pdf_url_from_unpaywall(&record).as_deref(),
+            Some("https://journal.example.org/paper.pdf")
+        );
+
+        assert_eq!(pdf_url_from_unpaywall(&json!({ "is_oa": false, "best_oa_location": null })), None);
+    }
+}
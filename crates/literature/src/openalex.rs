@@ -21,15 +21,24 @@
 //! # }
 //! ```
 
+use std::collections::HashMap;
+
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::Value;
+use time::Date;
 use tracing::debug;
 
-use crate::models::{LiteratureFetcher, LiteratureResult};
+use crate::models::{normalize_doi, LiteratureFetcher, LiteratureResult};
+use crate::pagination::{PageCursor, ResultPage};
 
 const API_BASE: &str = "https://api.openalex.org/works";
+const CONCEPTS_BASE: &str = "https://api.openalex.org/concepts";
+/// Largest page OpenAlex returns
+const MAX_PER_PAGE: usize = 200;
+/// Most DOIs OpenAlex accepts in one `doi:` filter
+const MAX_DOIS_PER_REQUEST: usize = 50;
 
 /// OpenAlex API fetcher
 pub struct OpenAlexFetcher {
@@ -40,15 +49,152 @@ impl OpenAlexFetcher {
     /// Creates a new OpenAlex fetcher
     pub fn new() -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("PepTrack/1.0 (mailto:support@peptrack.app)")
-                .timeout(std::time::Duration::from_secs(30))
+            client: crate::http::client_builder("PepTrack/1.0 (mailto:support@peptrack.app)")
                 .build()
                 .expect("Failed to create HTTP client"),
         }
     }
 }
 
+impl OpenAlexFetcher {
+    /// Citation counts for works with the given DOIs, keyed by
+    /// [`normalize_doi`]
+    ///
+    /// DOIs OpenAlex doesn't know are left out.
+    pub async fn citation_counts(&self, dois: &[String]) -> Result<HashMap<String, u32>> {
+        let mut counts = HashMap::new();
+        let dois: Vec<String> = dois.iter().map(|doi| normalize_doi(doi)).collect();
+
+        for chunk in dois.chunks(MAX_DOIS_PER_REQUEST) {
+            let url = format!(
+                "{}?filter=doi:{}&per-page={}&select=doi,cited_by_count",
+                API_BASE,
+                urlencoding::encode(&chunk.join("|")),
+                chunk.len()
+            );
+            debug!("OpenAlex citation URL: {}", url);
+
+            let response: CitationResponse = self
+                .client
+                .get(&url)
+                .send()
+                .await
+                .context("Failed to send OpenAlex citation request")?
+                .error_for_status()
+                .context("OpenAlex citation request failed")?
+                .json()
+                .await
+                .context("Failed to parse OpenAlex citation response")?;
+
+            for work in response.results {
+                if let (Some(doi), Some(count)) = (work.doi, work.cited_by_count) {
+                    counts.insert(normalize_doi(&doi), count);
+                }
+            }
+        }
+
+        Ok(counts)
+    }
+}
+
+impl OpenAlexFetcher {
+    /// Metadata for the work with `doi`, or None if OpenAlex doesn't know it
+    pub async fn lookup_doi(&self, doi: &str) -> Result<Option<LiteratureResult>> {
+        let url = format!("{}/doi:{}", API_BASE, normalize_doi(doi));
+        debug!("OpenAlex DOI URL: {}", url);
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to send OpenAlex request")?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let work: Work = response
+            .error_for_status()
+            .context("OpenAlex DOI request failed")?
+            .json()
+            .await
+            .context("Failed to parse OpenAlex work")?;
+        Ok(Some(work_to_result(work)))
+    }
+}
+
+impl OpenAlexFetcher {
+    /// The OpenAlex concept best matching `name`, as `(id, display name)`
+    pub async fn find_concept(&self, name: &str) -> Result<Option<(String, String)>> {
+        let url = format!(
+            "{}?search={}&per-page=1&select=id,display_name",
+            CONCEPTS_BASE,
+            urlencoding::encode(name)
+        );
+        debug!("OpenAlex concept URL: {}", url);
+
+        let response: ConceptResponse = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to send OpenAlex concept request")?
+            .error_for_status()
+            .context("OpenAlex concept request failed")?
+            .json()
+            .await
+            .context("Failed to parse OpenAlex concepts")?;
+        Ok(response
+            .results
+            .into_iter()
+            .next()
+            .map(|concept| (concept_id(&concept.id).to_string(), concept.display_name)))
+    }
+
+    /// Works published on or after `since`, newest first, tagged with
+    /// `concept_id` or else matching `query`
+    pub async fn recent_works(
+        &self,
+        query: &str,
+        concept_id: Option<&str>,
+        since: Date,
+        max_results: usize,
+    ) -> Result<Vec<LiteratureResult>> {
+        let mut filter = format!("from_publication_date:{}", since);
+        if let Some(concept_id) = concept_id {
+            filter.push_str(&format!(",concepts.id:{}", concept_id));
+        }
+        let mut url = format!(
+            "{}?filter={}&sort=publication_date:desc&per-page={}",
+            API_BASE,
+            urlencoding::encode(&filter),
+            max_results
+        );
+        if concept_id.is_none() {
+            url.push_str(&format!("&search={}", urlencoding::encode(query)));
+        }
+        debug!("OpenAlex recent works URL: {}", url);
+
+        let response: OpenAlexResponse = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to send OpenAlex request")?
+            .error_for_status()
+            .context("OpenAlex recent works request failed")?
+            .json()
+            .await
+            .context("Failed to parse OpenAlex response")?;
+        Ok(response.results.into_iter().map(work_to_result).collect())
+    }
+}
+
+/// `https://openalex.org/C123` becomes `C123`
+fn concept_id(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
 impl Default for OpenAlexFetcher {
     fn default() -> Self {
         Self::new()
@@ -58,11 +204,22 @@ impl Default for OpenAlexFetcher {
 #[async_trait]
 impl LiteratureFetcher for OpenAlexFetcher {
     async fn search(&self, query: &str, max_results: usize) -> Result<Vec<LiteratureResult>> {
+        Ok(self.search_page(query, max_results, None).await?.results)
+    }
+
+    /// Pages with OpenAlex's cursor, which unlike `page` isn't limited to
+    /// the first 10,000 results
+    async fn search_page(&self, query: &str, page_size: usize, cursor: Option<&PageCursor>) -> Result<ResultPage> {
+        let cursor = match cursor {
+            Some(PageCursor::Token(token)) => token.as_str(),
+            _ => "*",
+        };
         let url = format!(
-            "{}?search={}&per-page={}",
+            "{}?search={}&per-page={}&cursor={}",
             API_BASE,
             urlencoding::encode(query),
-            max_results
+            page_size.min(MAX_PER_PAGE),
+            urlencoding::encode(cursor)
         );
 
         debug!("OpenAlex search URL: {}", url);
@@ -82,51 +239,21 @@ impl LiteratureFetcher for OpenAlexFetcher {
         let search_result: OpenAlexResponse = serde_json::from_str(&body)
             .with_context(|| format!("Failed to parse OpenAlex response: {}", body))?;
 
-        let results = search_result
+        let meta = search_result.meta.unwrap_or_default();
+        let results: Vec<LiteratureResult> = search_result
             .results
             .into_iter()
-            .map(|work| {
-                let authors = if work.authorships.is_empty() {
-                    None
-                } else {
-                    Some(
-                        work.authorships
-                            .iter()
-                            .filter_map(|a| a.author.display_name.clone())
-                            .collect::<Vec<_>>()
-                            .join(", "),
-                    )
-                };
-
-                // Extract DOI from id (format: https://openalex.org/W1234567)
-                let doi = work.doi.clone();
-
-                let abstract_text = work
-                    .abstract_inverted_index
-                    .as_ref()
-                    .and_then(reconstruct_abstract)
-                    .or_else(|| {
-                        work.abstract_inverted_index
-                            .as_ref()
-                            .map(|_| String::from("[Abstract available at source]"))
-                    });
-
-                LiteratureResult {
-                    source: "openalex".to_string(),
-                    title: work.title.clone(),
-                    url: work.doi.or_else(|| Some(work.id.clone())),
-                    doi,
-                    authors,
-                    published_date: work.publication_date,
-                    journal: work
-                        .primary_location
-                        .and_then(|loc| loc.source.map(|s| s.display_name)),
-                    abstract_text,
-                }
-            })
+            .map(work_to_result)
             .collect();
 
-        Ok(results)
+        Ok(ResultPage {
+            next: meta
+                .next_cursor
+                .filter(|_| !results.is_empty())
+                .map(PageCursor::Token),
+            total: meta.count,
+            results,
+        })
     }
 
     fn source_name(&self) -> &'static str {
@@ -134,6 +261,49 @@ impl LiteratureFetcher for OpenAlexFetcher {
     }
 }
 
+/// Converts an OpenAlex work into a normalized result
+fn work_to_result(work: Work) -> LiteratureResult {
+    let authors = if work.authorships.is_empty() {
+        None
+    } else {
+        Some(
+            work.authorships
+                .iter()
+                .filter_map(|a| a.author.display_name.clone())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    };
+
+    // Extract DOI from id (format: https://openalex.org/W1234567)
+    let doi = work.doi.clone();
+
+    let abstract_text = work
+        .abstract_inverted_index
+        .as_ref()
+        .and_then(reconstruct_abstract)
+        .or_else(|| {
+            work.abstract_inverted_index
+                .as_ref()
+                .map(|_| String::from("[Abstract available at source]"))
+        });
+
+    LiteratureResult {
+        source: "openalex".to_string(),
+        title: work.title.clone(),
+        url: work.doi.or_else(|| Some(work.id.clone())),
+        doi,
+        authors,
+        published_date: work.publication_date,
+        journal: work
+            .primary_location
+            .and_then(|loc| loc.source.map(|s| s.display_name)),
+        abstract_text,
+        citation_count: work.cited_by_count,
+        publication_types: work.work_type.into_iter().collect(),
+    }
+}
+
 fn reconstruct_abstract(index: &Value) -> Option<String> {
     let obj = index.as_object()?;
     let mut max_pos = 0usize;
@@ -175,6 +345,17 @@ fn reconstruct_abstract(index: &Value) -> Option<String> {
 #[derive(Debug, Deserialize)]
 struct OpenAlexResponse {
     results: Vec<Work>,
+    #[serde(default)]
+    meta: Option<Meta>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Meta {
+    #[serde(default)]
+    count: Option<usize>,
+    /// Set when the request used a cursor and more results follow
+    #[serde(default)]
+    next_cursor: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -191,6 +372,34 @@ struct Work {
     primary_location: Option<Location>,
     #[serde(default)]
     abstract_inverted_index: Option<serde_json::Value>,
+    #[serde(default)]
+    cited_by_count: Option<u32>,
+    #[serde(default, rename = "type")]
+    work_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CitationResponse {
+    results: Vec<CitationWork>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CitationWork {
+    #[serde(default)]
+    doi: Option<String>,
+    #[serde(default)]
+    cited_by_count: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConceptResponse {
+    results: Vec<Concept>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Concept {
+    id: String,
+    display_name: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -243,6 +452,12 @@ mod tests {
         }
     }
 
+    #[test]
+    fn concept_ids_drop_the_url() {
+        assert_eq!(concept_id("https://openalex.org/C2779134260"), "C2779134260");
+        assert_eq!(concept_id("C1"), "C1");
+    }
+
     #[test]
     fn openalex_fetcher_can_be_created() {
         let _fetcher = OpenAlexFetcher::new();
@@ -0,0 +1,203 @@
+//! Paging through large result sets
+//!
+//! [`LiteratureFetcher::search_page`] fetches one page and says where the
+//! next one starts: an offset for PubMed and Crossref, an opaque cursor for
+//! OpenAlex and Europe PMC. [`search_all`] walks those pages one request at
+//! a time, so callers can store each page before asking for the next.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use peptrack_literature::{search_all, LiteratureFetcher, OpenAlexFetcher};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let fetcher = OpenAlexFetcher::new();
+//! let mut pages = search_all(&fetcher, "thymosin beta-4", 500);
+//! while let Some(results) = pages.next_page().await? {
+//!     println!("{} more results", results.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{LiteratureFetcher, LiteratureResult};
+
+/// Results per request when paging
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+/// Most results [`search_all`] returns, whatever cap is asked for
+pub const MAX_SEARCH_ALL_RESULTS: usize = 2_000;
+
+/// Where a page of results starts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "value")]
+pub enum PageCursor {
+    /// Results to skip
+    Offset(usize),
+    /// Opaque token returned with the previous page
+    Token(String),
+}
+
+/// One page of search results
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResultPage {
+    pub results: Vec<LiteratureResult>,
+    /// None on the last page
+    pub next: Option<PageCursor>,
+    /// Total matches, if the source reports it
+    pub total: Option<usize>,
+}
+
+/// Fetches pages of a search in turn, stopping at a cap; see [`search_all`]
+pub struct ResultPages<'a> {
+    fetcher: &'a dyn LiteratureFetcher,
+    query: String,
+    page_size: usize,
+    remaining: usize,
+    cursor: Option<PageCursor>,
+    done: bool,
+    total: Option<usize>,
+}
+
+/// Page through every result of `query`, up to `cap` (and never more than
+/// [`MAX_SEARCH_ALL_RESULTS`])
+pub fn search_all<'a>(fetcher: &'a dyn LiteratureFetcher, query: &str, cap: usize) -> ResultPages<'a> {
+    ResultPages {
+        fetcher,
+        query: query.to_string(),
+        page_size: DEFAULT_PAGE_SIZE,
+        remaining: cap.min(MAX_SEARCH_ALL_RESULTS),
+        cursor: None,
+        done: false,
+        total: None,
+    }
+}
+
+impl ResultPages<'_> {
+    /// Results per request; [`DEFAULT_PAGE_SIZE`] unless set
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Total matches reported by the source, once a page was fetched
+    pub fn total(&self) -> Option<usize> {
+        self.total
+    }
+
+    /// The next page, or None once the results or the cap run out
+    pub async fn next_page(&mut self) -> Result<Option<Vec<LiteratureResult>>> {
+        if self.done || self.remaining == 0 {
+            return Ok(None);
+        }
+
+        let page = self
+            .fetcher
+            .search_page(&self.query, self.page_size.min(self.remaining), self.cursor.as_ref())
+            .await?;
+        let mut results = page.results;
+        results.truncate(self.remaining);
+        self.remaining -= results.len();
+        self.total = page.total.or(self.total);
+        self.cursor = page.next;
+        self.done = self.cursor.is_none() || results.is_empty();
+
+        Ok((!results.is_empty()).then_some(results))
+    }
+
+    /// Every remaining page as one list
+    pub async fn collect(mut self) -> Result<Vec<LiteratureResult>> {
+        let mut all = Vec::new();
+        while let Some(results) = self.next_page().await? {
+            all.extend(results);
+        }
+        Ok(all)
+    }
+}
+
+/// The next offset after a page of `fetched` results starting at `offset`,
+/// or None when `total` (or `max_offset`) is reached
+pub(crate) fn next_offset(offset: usize, fetched: usize, total: Option<usize>, max_offset: Option<usize>) -> Option<PageCursor> {
+    let next = offset + fetched;
+    let more = fetched > 0 && total.is_none_or(|total| next < total) && max_offset.is_none_or(|max| next < max);
+    more.then_some(PageCursor::Offset(next))
+}
+
+/// The offset `cursor` points at; tokens don't apply to offset paging
+pub(crate) fn cursor_offset(cursor: Option<&PageCursor>) -> usize {
+    match cursor {
+        Some(PageCursor::Offset(offset)) => *offset,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    /// Serves `total` numbered results by offset
+    struct Numbered {
+        total: usize,
+    }
+
+    #[async_trait]
+    impl LiteratureFetcher for Numbered {
+        async fn search(&self, query: &str, max_results: usize) -> Result<Vec<LiteratureResult>> {
+            Ok(self.search_page(query, max_results, None).await?.results)
+        }
+
+        async fn search_page(&self, _query: &str, page_size: usize, cursor: Option<&PageCursor>) -> Result<ResultPage> {
+            let offset = cursor_offset(cursor);
+            let results: Vec<LiteratureResult> = (offset..self.total.min(offset + page_size))
+                .map(|i| LiteratureResult {
+                    source: "test".into(),
+                    title: format!("Paper {}", i),
+                    url: None,
+                    doi: None,
+                    authors: None,
+                    published_date: None,
+                    journal: None,
+                    abstract_text: None,
+                    citation_count: None,
+                    publication_types: Vec::new(),
+                })
+                .collect();
+            Ok(ResultPage {
+                next: next_offset(offset, results.len(), Some(self.total), None),
+                results,
+                total: Some(self.total),
+            })
+        }
+
+        fn source_name(&self) -> &'static str {
+            "test"
+        }
+    }
+
+    #[tokio::test]
+    async fn search_all_pages_until_the_cap_or_the_end() {
+        let fetcher = Numbered { total: 25 };
+        let mut pages = search_all(&fetcher, "q", 100).with_page_size(10);
+        let mut sizes = Vec::new();
+        while let Some(results) = pages.next_page().await.unwrap() {
+            sizes.push(results.len());
+        }
+        assert_eq!(sizes, vec![10, 10, 5]);
+        assert_eq!(pages.total(), Some(25));
+
+        let capped = search_all(&fetcher, "q", 12).with_page_size(10).collect().await.unwrap();
+        assert_eq!(capped.len(), 12);
+        assert_eq!(capped.last().unwrap().title, "Paper 11");
+    }
+
+    #[test]
+    fn offsets_stop_at_the_total_and_the_limit() {
+        assert_eq!(next_offset(0, 10, Some(25), None), Some(PageCursor::Offset(10)));
+        assert_eq!(next_offset(20, 5, Some(25), None), None);
+        assert_eq!(next_offset(0, 10, None, Some(10)), None);
+        assert_eq!(next_offset(0, 0, None, None), None);
+    }
+}
@@ -26,9 +26,12 @@ use serde::Deserialize;
 use tracing::{debug, warn};
 
 use crate::models::{LiteratureFetcher, LiteratureResult};
+use crate::pagination::{cursor_offset, next_offset, PageCursor, ResultPage};
 
 const ESEARCH_BASE: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/esearch.fcgi";
 const ESUMMARY_BASE: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/esummary.fcgi";
+/// ESearch only pages through the first 10,000 matches
+const MAX_RETSTART: usize = 10_000;
 
 /// PubMed API fetcher using E-utilities
 pub struct PubMedFetcher {
@@ -42,9 +45,7 @@ impl PubMedFetcher {
     /// Use `with_api_key()` to provide an NCBI API key for higher rate limits.
     pub fn new() -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("PepTrack/1.0")
-                .timeout(std::time::Duration::from_secs(30))
+            client: crate::http::client_builder("PepTrack/1.0")
                 .build()
                 .expect("Failed to create HTTP client"),
             api_key: None,
@@ -56,22 +57,22 @@ impl PubMedFetcher {
     /// With an API key, rate limit increases from 3 req/s to 10 req/s.
     pub fn with_api_key(api_key: String) -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("PepTrack/1.0")
-                .timeout(std::time::Duration::from_secs(30))
+            client: crate::http::client_builder("PepTrack/1.0")
                 .build()
                 .expect("Failed to create HTTP client"),
             api_key: Some(api_key),
         }
     }
 
-    /// Searches PubMed and returns PMIDs
-    async fn search_pmids(&self, query: &str, max_results: usize) -> Result<Vec<String>> {
+    /// Searches PubMed and returns PMIDs, skipping the first `retstart`
+    /// matches, with the total number of matches
+    async fn search_pmids(&self, query: &str, max_results: usize, retstart: usize) -> Result<(Vec<String>, Option<usize>)> {
         let mut url = format!(
-            "{}?db=pubmed&term={}&retmode=json&retmax={}",
+            "{}?db=pubmed&term={}&retmode=json&retmax={}&retstart={}",
             ESEARCH_BASE,
             urlencoding::encode(query),
-            max_results
+            max_results,
+            retstart
         );
 
         if let Some(key) = &self.api_key {
@@ -95,11 +96,14 @@ impl PubMedFetcher {
         let search_result: ESearchResult = serde_json::from_str(&body)
             .with_context(|| format!("Failed to parse PubMed search response: {}", body))?;
 
-        Ok(search_result.esearchresult.idlist)
+        let data = search_result.esearchresult;
+        Ok((data.idlist, data.count.and_then(|count| count.parse().ok())))
     }
 
     /// Fetches article summaries for given PMIDs
-    async fn fetch_summaries(&self, pmids: &[String]) -> Result<Vec<LiteratureResult>> {
+    ///
+    /// PMIDs PubMed doesn't return a title for are left out.
+    pub async fn fetch_summaries(&self, pmids: &[String]) -> Result<Vec<LiteratureResult>> {
         if pmids.is_empty() {
             return Ok(Vec::new());
         }
@@ -164,6 +168,8 @@ impl PubMedFetcher {
                             published_date: article.pubdate.clone(),
                             journal: article.fulljournalname.clone(),
                             abstract_text: None, // Summary API doesn't include abstracts
+                            citation_count: None,
+                            publication_types: article.pubtype.clone().unwrap_or_default(),
                         });
                     }
                     Err(e) => {
@@ -186,8 +192,17 @@ impl Default for PubMedFetcher {
 #[async_trait]
 impl LiteratureFetcher for PubMedFetcher {
     async fn search(&self, query: &str, max_results: usize) -> Result<Vec<LiteratureResult>> {
-        let pmids = self.search_pmids(query, max_results).await?;
-        self.fetch_summaries(&pmids).await
+        Ok(self.search_page(query, max_results, None).await?.results)
+    }
+
+    async fn search_page(&self, query: &str, page_size: usize, cursor: Option<&PageCursor>) -> Result<ResultPage> {
+        let offset = cursor_offset(cursor);
+        let (pmids, total) = self.search_pmids(query, page_size, offset).await?;
+        Ok(ResultPage {
+            next: next_offset(offset, pmids.len(), total, Some(MAX_RETSTART)),
+            results: self.fetch_summaries(&pmids).await?,
+            total,
+        })
     }
 
     fn source_name(&self) -> &'static str {
@@ -205,6 +220,9 @@ struct ESearchResult {
 #[derive(Debug, Deserialize)]
 struct ESearchData {
     idlist: Vec<String>,
+    /// Total matches, as a string
+    #[serde(default)]
+    count: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -233,6 +251,8 @@ struct ArticleSummary {
     fulljournalname: Option<String>,
     #[serde(default)]
     articleids: Option<Vec<ArticleId>>,
+    #[serde(default)]
+    pubtype: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -0,0 +1,338 @@
+//! Relevance scoring and automatic tagging of search results
+//!
+//! A result's score (0.0 to 1.0) combines how well it matches the search
+//! query, whether it mentions a peptide from the user's protocols, how
+//! recently it was published and how often it has been cited. Tags name the
+//! matched peptides (`peptide:bpc-157`) and the detected study type
+//! (`study:rct`) so cached literature can be filtered by them.
+
+use peptrack_core::models::publication_year;
+use time::OffsetDateTime;
+
+use crate::models::LiteratureResult;
+
+/// Weight of each signal in the final score; they sum to 1.0
+const QUERY_WEIGHT: f32 = 0.4;
+const PEPTIDE_WEIGHT: f32 = 0.25;
+const RECENCY_WEIGHT: f32 = 0.2;
+const CITATION_WEIGHT: f32 = 0.15;
+
+/// A paper loses half its recency score every this many years
+const RECENCY_HALF_LIFE_YEARS: f32 = 5.0;
+/// Citation count that earns the full citation score; counts are compared on
+/// a log scale so the first few citations matter most
+const CITATIONS_FOR_FULL_SCORE: f32 = 500.0;
+/// Shorter peptide names match too much unrelated text to be used
+const MIN_PEPTIDE_NAME_LEN: usize = 3;
+
+pub const PEPTIDE_TAG_PREFIX: &str = "peptide:";
+pub const STUDY_TAG_PREFIX: &str = "study:";
+
+/// Kind of study a paper reports, most specific first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StudyType {
+    MetaAnalysis,
+    SystematicReview,
+    RandomizedControlledTrial,
+    ClinicalTrial,
+    CaseReport,
+    Review,
+    AnimalStudy,
+    InVitro,
+}
+
+impl StudyType {
+    const ALL: [StudyType; 8] = [
+        StudyType::MetaAnalysis,
+        StudyType::SystematicReview,
+        StudyType::RandomizedControlledTrial,
+        StudyType::ClinicalTrial,
+        StudyType::CaseReport,
+        StudyType::Review,
+        StudyType::AnimalStudy,
+        StudyType::InVitro,
+    ];
+
+    pub fn tag(self) -> String {
+        let name = match self {
+            StudyType::MetaAnalysis => "meta-analysis",
+            StudyType::SystematicReview => "systematic-review",
+            StudyType::RandomizedControlledTrial => "rct",
+            StudyType::ClinicalTrial => "clinical-trial",
+            StudyType::CaseReport => "case-report",
+            StudyType::Review => "review",
+            StudyType::AnimalStudy => "animal",
+            StudyType::InVitro => "in-vitro",
+        };
+        format!("{}{}", STUDY_TAG_PREFIX, name)
+    }
+
+    /// Phrases that identify the study type in a title, abstract or
+    /// publication type
+    fn phrases(self) -> &'static [&'static str] {
+        match self {
+            StudyType::MetaAnalysis => &["meta-analysis", "meta analysis", "metaanalysis"],
+            StudyType::SystematicReview => &["systematic review"],
+            StudyType::RandomizedControlledTrial => &[
+                "randomized controlled trial",
+                "randomised controlled trial",
+                "randomized clinical trial",
+                "double-blind",
+                "placebo-controlled",
+            ],
+            StudyType::ClinicalTrial => &["clinical trial", "phase i", "phase ii", "phase iii"],
+            StudyType::CaseReport => &["case report", "case series"],
+            StudyType::Review => &["review"],
+            StudyType::AnimalStudy => &[
+                " rats", " rat ", " mice", " mouse", "murine", "rodent", "in vivo",
+            ],
+            StudyType::InVitro => &["in vitro", "cell line", "cultured cells"],
+        }
+    }
+
+    /// Most specific study type mentioned in `result`
+    pub fn detect(result: &LiteratureResult) -> Option<StudyType> {
+        // Publication types are curated, so they win over words in the text
+        let types = format!(" {} ", result.publication_types.join(" ").to_lowercase());
+        let text = format!(
+            " {} {} ",
+            result.title.to_lowercase(),
+            result
+                .abstract_text
+                .as_deref()
+                .unwrap_or_default()
+                .to_lowercase()
+        );
+        [types, text].iter().find_map(|haystack| {
+            Self::ALL.into_iter().find(|study| {
+                study
+                    .phrases()
+                    .iter()
+                    .any(|phrase| haystack.contains(phrase))
+            })
+        })
+    }
+}
+
+/// What a result is scored against
+#[derive(Debug, Clone)]
+pub struct RelevanceContext {
+    /// Lowercase words of the search query
+    query_terms: Vec<String>,
+    /// Peptide names from the user's protocols, with their match keys
+    peptides: Vec<(String, String)>,
+    now: OffsetDateTime,
+}
+
+/// Lowercase letters and digits only, so "BPC-157", "BPC 157" and "bpc157"
+/// compare equal
+fn match_key(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl RelevanceContext {
+    pub fn new<S: AsRef<str>>(query: &str, peptide_names: &[S], now: OffsetDateTime) -> Self {
+        let query_terms = query
+            .split(|c: char| !c.is_alphanumeric() && c != '-')
+            .filter(|term| term.len() > 1)
+            .map(str::to_lowercase)
+            .collect();
+        let mut peptides: Vec<(String, String)> = peptide_names
+            .iter()
+            .map(|name| (name.as_ref().trim().to_string(), match_key(name.as_ref())))
+            .filter(|(_, key)| key.len() >= MIN_PEPTIDE_NAME_LEN)
+            .collect();
+        peptides.sort_by(|a, b| a.1.cmp(&b.1));
+        peptides.dedup_by(|a, b| a.1 == b.1);
+
+        Self {
+            query_terms,
+            peptides,
+            now,
+        }
+    }
+
+    /// Share of query terms found, counting title matches double
+    fn query_score(&self, title: &str, text: &str) -> f32 {
+        if self.query_terms.is_empty() {
+            return 0.0;
+        }
+        let matched: f32 = self
+            .query_terms
+            .iter()
+            .map(|term| {
+                if title.contains(term.as_str()) {
+                    1.0
+                } else if text.contains(term.as_str()) {
+                    0.5
+                } else {
+                    0.0
+                }
+            })
+            .sum();
+        matched / self.query_terms.len() as f32
+    }
+
+    fn recency_score(&self, published_date: Option<&str>) -> f32 {
+        let Some(year) = published_date.and_then(publication_year) else {
+            return 0.0;
+        };
+        let age = (self.now.year() - year).max(0) as f32;
+        0.5f32.powf(age / RECENCY_HALF_LIFE_YEARS)
+    }
+
+    fn citation_score(citations: Option<u32>) -> f32 {
+        let citations = citations.unwrap_or(0) as f32;
+        ((1.0 + citations).ln() / (1.0 + CITATIONS_FOR_FULL_SCORE).ln()).min(1.0)
+    }
+
+    /// Protocol peptides mentioned in the title or abstract
+    fn matched_peptides<'a>(&'a self, result: &LiteratureResult) -> Vec<&'a str> {
+        let text = match_key(&format!(
+            "{} {}",
+            result.title,
+            result.abstract_text.as_deref().unwrap_or_default()
+        ));
+        self.peptides
+            .iter()
+            .filter(|(_, key)| text.contains(key.as_str()))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Relevance of `result` from 0.0 to 1.0
+    pub fn score(&self, result: &LiteratureResult) -> f32 {
+        let title = result.title.to_lowercase();
+        let text = format!(
+            "{} {}",
+            title,
+            result
+                .abstract_text
+                .as_deref()
+                .unwrap_or_default()
+                .to_lowercase()
+        );
+        let peptide_score = if self.matched_peptides(result).is_empty() {
+            0.0
+        } else {
+            1.0
+        };
+
+        let score = QUERY_WEIGHT * self.query_score(&title, &text)
+            + PEPTIDE_WEIGHT * peptide_score
+            + RECENCY_WEIGHT * self.recency_score(result.published_date.as_deref())
+            + CITATION_WEIGHT * Self::citation_score(result.citation_count);
+        score.clamp(0.0, 1.0)
+    }
+
+    /// Peptide and study type tags for `result`
+    pub fn tags(&self, result: &LiteratureResult) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .matched_peptides(result)
+            .into_iter()
+            .map(|name| {
+                let slug = name
+                    .to_lowercase()
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("-");
+                format!("{}{}", PEPTIDE_TAG_PREFIX, slug)
+            })
+            .collect();
+        tags.extend(StudyType::detect(result).map(StudyType::tag));
+        tags
+    }
+
+    /// Cache entry for `result` with its score and tags filled in
+    pub fn to_entry(&self, result: &LiteratureResult) -> peptrack_core::LiteratureEntry {
+        let mut entry = result.to_entry();
+        entry.relevance_score = Some(self.score(result));
+        entry.tags = self.tags(result);
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn result(title: &str, abstract_text: Option<&str>) -> LiteratureResult {
+        LiteratureResult {
+            source: "pubmed".to_string(),
+            title: title.to_string(),
+            url: None,
+            doi: None,
+            authors: None,
+            published_date: None,
+            journal: None,
+            abstract_text: abstract_text.map(String::from),
+            citation_count: None,
+            publication_types: Vec::new(),
+        }
+    }
+
+    fn context(query: &str) -> RelevanceContext {
+        RelevanceContext::new(
+            query,
+            &["BPC-157", "TB-500", "GH"],
+            datetime!(2024-06-01 0:00 UTC),
+        )
+    }
+
+    #[test]
+    fn test_query_and_peptide_matches_raise_score() {
+        let ctx = context("tendon healing");
+        let on_topic = result("BPC 157 accelerates tendon healing", None);
+        let abstract_only = result("Peptide effects", Some("Improved tendon healing in rats."));
+        let off_topic = result("Unrelated chemistry", None);
+
+        let on_score = ctx.score(&on_topic);
+        assert!((on_score - (QUERY_WEIGHT + PEPTIDE_WEIGHT)).abs() < 1e-6);
+        assert!(ctx.score(&abstract_only) < on_score);
+        assert!(ctx.score(&abstract_only) > ctx.score(&off_topic));
+        assert_eq!(ctx.score(&off_topic), 0.0);
+    }
+
+    #[test]
+    fn test_recency_and_citations() {
+        let ctx = context("");
+        let mut recent = result("Paper", None);
+        recent.published_date = Some("2024 Jan 15".into());
+        let mut older = recent.clone();
+        older.published_date = Some("2014-03-01".into());
+        assert!((ctx.score(&recent) - RECENCY_WEIGHT).abs() < 1e-6);
+        assert!((ctx.score(&older) - RECENCY_WEIGHT / 4.0).abs() < 1e-6);
+
+        let mut cited = result("Paper", None);
+        cited.citation_count = Some(10_000);
+        assert!((ctx.score(&cited) - CITATION_WEIGHT).abs() < 1e-6);
+        cited.citation_count = Some(20);
+        assert!(ctx.score(&cited) > 0.0 && ctx.score(&cited) < CITATION_WEIGHT);
+    }
+
+    #[test]
+    fn test_tags() {
+        let ctx = context("healing");
+        let mut paper = result(
+            "TB500 and BPC-157 in a rat model",
+            Some("A randomized controlled trial of healing in mice."),
+        );
+        // "GH" is too short to match inside "healing"
+        assert_eq!(
+            ctx.tags(&paper),
+            vec!["peptide:bpc-157", "peptide:tb-500", "study:rct"]
+        );
+
+        paper.publication_types = vec!["Review".into()];
+        assert_eq!(ctx.tags(&paper).last().unwrap(), "study:review");
+
+        let entry = ctx.to_entry(&paper);
+        assert_eq!(entry.tags.len(), 3);
+        assert!(entry.relevance_score.unwrap() > 0.0);
+    }
+}
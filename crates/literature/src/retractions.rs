@@ -0,0 +1,187 @@
+//! Retraction and erratum detection for cached literature
+//!
+//! Papers are checked against Crossref, which lists the retraction and
+//! correction notices published for a DOI, and against PubMed, which marks
+//! retracted articles with the "Retracted Publication" publication type.
+
+use anyhow::Result;
+use peptrack_core::LiteratureEntry;
+use tracing::warn;
+
+use crate::crossref::CrossrefFetcher;
+use crate::pubmed::PubMedFetcher;
+
+/// Crossref update types that withdraw a paper
+const RETRACTION_TYPES: &[&str] = &["retraction", "withdrawal", "removal"];
+/// Crossref update types that amend a paper
+const CORRECTION_TYPES: &[&str] = &["correction", "erratum", "corrigendum", "addendum"];
+
+/// PubMed publication types with the same meaning
+const PUBMED_RETRACTED: &str = "retracted publication";
+const PUBMED_CORRECTED: &str = "corrected and republished article";
+
+/// What notices say about a paper
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditorialStatus {
+    pub retracted: bool,
+    pub corrected: bool,
+    /// DOI of the retraction or correction notice, when Crossref has one
+    pub notice_doi: Option<String>,
+}
+
+impl EditorialStatus {
+    /// Status from Crossref update types, e.g. "retraction"
+    pub fn from_notice_types<'a>(types: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut status = Self::default();
+        for notice_type in types {
+            let notice_type = notice_type.to_lowercase();
+            status.retracted |= RETRACTION_TYPES.contains(&notice_type.as_str());
+            status.corrected |= CORRECTION_TYPES.contains(&notice_type.as_str());
+        }
+        status
+    }
+
+    /// Status from PubMed publication types
+    pub fn from_publication_types(types: &[String]) -> Self {
+        let has = |name: &str| types.iter().any(|t| t.eq_ignore_ascii_case(name));
+        Self {
+            retracted: has(PUBMED_RETRACTED),
+            corrected: has(PUBMED_CORRECTED),
+            notice_doi: None,
+        }
+    }
+
+    fn merge(&mut self, other: EditorialStatus) {
+        self.retracted |= other.retracted;
+        self.corrected |= other.corrected;
+        self.notice_doi = self.notice_doi.take().or(other.notice_doi);
+    }
+}
+
+/// PubMed ID from a PubMed article URL such as
+/// `https://pubmed.ncbi.nlm.nih.gov/12345678/`
+pub fn pubmed_id(url: &str) -> Option<String> {
+    let rest = url.split_once("pubmed.ncbi.nlm.nih.gov/")?.1;
+    let pmid: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    (!pmid.is_empty()).then_some(pmid)
+}
+
+/// Checks cached papers for retractions and corrections
+pub struct RetractionChecker {
+    crossref: CrossrefFetcher,
+    pubmed: PubMedFetcher,
+}
+
+impl RetractionChecker {
+    pub fn new() -> Self {
+        Self {
+            crossref: CrossrefFetcher::new(),
+            pubmed: PubMedFetcher::new(),
+        }
+    }
+
+    /// Current status of `entry`
+    ///
+    /// Entries with neither a DOI nor a PubMed URL can't be checked and come
+    /// back unflagged. An error is only returned when every lookup failed.
+    pub async fn check(&self, entry: &LiteratureEntry) -> Result<EditorialStatus> {
+        let mut status = EditorialStatus::default();
+        let mut last_error = None;
+        let mut any_answered = false;
+
+        if let Some(doi) = &entry.doi {
+            match self.crossref.editorial_notices(doi).await {
+                Ok(notices) => {
+                    any_answered = true;
+                    let mut crossref = EditorialStatus::from_notice_types(
+                        notices.iter().map(|n| n.notice_type.as_str()),
+                    );
+                    crossref.notice_doi = notices
+                        .iter()
+                        .find(|n| RETRACTION_TYPES.contains(&n.notice_type.as_str()))
+                        .or(notices.first())
+                        .and_then(|n| n.notice_doi.clone());
+                    status.merge(crossref);
+                }
+                Err(e) => {
+                    warn!("Crossref notice lookup failed for {}: {:#}", doi, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if let Some(pmid) = entry.url.as_deref().and_then(pubmed_id) {
+            match self
+                .pubmed
+                .fetch_summaries(std::slice::from_ref(&pmid))
+                .await
+            {
+                Ok(summaries) => {
+                    any_answered = true;
+                    if let Some(summary) = summaries.first() {
+                        status.merge(EditorialStatus::from_publication_types(
+                            &summary.publication_types,
+                        ));
+                    }
+                }
+                Err(e) => {
+                    warn!("PubMed lookup failed for PMID {}: {:#}", pmid, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if !any_answered => Err(e),
+            _ => Ok(status),
+        }
+    }
+}
+
+impl Default for RetractionChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_from_crossref_notices() {
+        assert_eq!(
+            EditorialStatus::from_notice_types(["Retraction"]),
+            EditorialStatus {
+                retracted: true,
+                corrected: false,
+                notice_doi: None
+            }
+        );
+        let corrected = EditorialStatus::from_notice_types(["erratum", "comment"]);
+        assert!(corrected.corrected && !corrected.retracted);
+        assert_eq!(
+            EditorialStatus::from_notice_types([]),
+            EditorialStatus::default()
+        );
+    }
+
+    #[test]
+    fn test_status_from_pubmed_types() {
+        let types = vec![
+            "Journal Article".to_string(),
+            "Retracted Publication".to_string(),
+        ];
+        assert!(EditorialStatus::from_publication_types(&types).retracted);
+        assert!(!EditorialStatus::from_publication_types(&types[..1]).retracted);
+    }
+
+    #[test]
+    fn test_pubmed_id() {
+        assert_eq!(
+            pubmed_id("https://pubmed.ncbi.nlm.nih.gov/12345678/").as_deref(),
+            Some("12345678")
+        );
+        assert_eq!(pubmed_id("https://doi.org/10.1000/xyz"), None);
+    }
+}
@@ -0,0 +1,323 @@
+//! Protocol-focused briefs across several papers
+//!
+//! [`summarize_corpus`] packs the papers into chunks that each fit in one
+//! prompt. A single chunk is summarized straight into the brief; with more,
+//! each chunk is first condensed into notes and the notes are then merged.
+//! Papers are numbered once across all chunks so the brief can cite them as
+//! `[1]`, `[2]`, ... whichever chunk they were read in.
+
+use std::fmt::Write as _;
+
+use anyhow::{bail, Result};
+use tracing::info;
+
+use crate::{LocalAiClient, SummarizeRequest, SummarizeResponse, SummaryFormat, TokenUsage};
+
+/// Most characters of paper text sent in one prompt
+pub const MAX_CHUNK_CHARS: usize = 24_000;
+/// Most characters kept from any one paper
+pub const MAX_DOCUMENT_CHARS: usize = 6_000;
+/// Most papers in one brief
+pub const MAX_CORPUS_DOCUMENTS: usize = 25;
+
+/// Sections of the final brief, in order
+pub const BRIEF_SECTIONS: [&str; 4] = [
+    "Consensus Findings",
+    "Dosing Ranges Reported",
+    "Safety Signals",
+    "Evidence Quality",
+];
+
+/// One paper to include in a brief
+#[derive(Debug, Clone)]
+pub struct CorpusDocument {
+    pub title: String,
+    /// Authors, journal and year, when known
+    pub citation: Option<String>,
+    /// Abstract or summary of the paper
+    pub text: String,
+}
+
+/// Longest prefix of `text` with at most `max` characters
+fn truncate_chars(text: &str, max: usize) -> &str {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// `document` as numbered prompt text, its body cut to `MAX_DOCUMENT_CHARS`
+pub(crate) fn format_document(number: usize, document: &CorpusDocument) -> String {
+    let mut block = format!("[{}] {}\n", number, document.title.trim());
+    if let Some(citation) = document.citation.as_deref().filter(|c| !c.is_empty()) {
+        let _ = writeln!(block, "{}", citation);
+    }
+    let text = document.text.trim();
+    let body = truncate_chars(text, MAX_DOCUMENT_CHARS);
+    if body.is_empty() {
+        block.push_str("(no abstract available)\n");
+    } else {
+        block.push_str(body);
+        if body.len() < text.len() {
+            block.push_str(" [truncated]");
+        }
+        block.push('\n');
+    }
+    block
+}
+
+/// Numbered document blocks grouped into chunks of at most `max_chars`
+///
+/// A document longer than `max_chars` on its own gets a chunk to itself.
+pub fn chunk_documents(documents: &[CorpusDocument], max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for (index, document) in documents.iter().enumerate() {
+        let block = format_document(index + 1, document);
+        if !current.is_empty() && current.len() + block.len() > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(&block);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+fn section_outline() -> String {
+    BRIEF_SECTIONS
+        .iter()
+        .map(|section| format!("## {}\n", section))
+        .collect()
+}
+
+fn protocol_block(protocol_context: &str) -> String {
+    if protocol_context.trim().is_empty() {
+        "The user has no active protocols; focus on peptides the papers study.".to_string()
+    } else {
+        format!(
+            "The user's protocols (focus on what matters for these):\n{}",
+            protocol_context.trim()
+        )
+    }
+}
+
+/// Prompt that turns `papers` straight into the brief
+pub fn build_brief_prompt(title: &str, papers: &str, protocol_context: &str) -> String {
+    format!(
+        "You are writing a research brief titled \"{title}\" from the papers below.\n\
+         {protocol}\n\n\
+         Only state what the papers report, and cite every claim with the paper's \
+         number, e.g. [2]. Note where papers disagree.\n\n\
+         OUTPUT FORMAT: Markdown with exactly these sections:\n{outline}\n\
+         Under Evidence Quality, rate the overall evidence (strong, moderate, weak) \
+         and say why: study types, sample sizes, human versus animal data.\n\n\
+         PAPERS:\n{papers}",
+        protocol = protocol_block(protocol_context),
+        outline = section_outline(),
+    )
+}
+
+/// Prompt that condenses one chunk of papers into notes for merging
+fn build_notes_prompt(papers: &str, protocol_context: &str, part: usize, parts: usize) -> String {
+    format!(
+        "You are reading part {part} of {parts} of a set of papers for a research brief.\n\
+         {protocol}\n\n\
+         OUTPUT FORMAT: concise Markdown notes under these headings, citing each \
+         point with the paper's number, e.g. [2]. Keep every dose, duration and \
+         adverse event the papers report.\n{outline}\n\
+         PAPERS:\n{papers}",
+        protocol = protocol_block(protocol_context),
+        outline = section_outline(),
+    )
+}
+
+/// Prompt that merges per-chunk notes into the brief
+fn build_merge_prompt(title: &str, notes: &[String], protocol_context: &str) -> String {
+    let notes = notes
+        .iter()
+        .enumerate()
+        .map(|(index, note)| format!("--- Notes, part {} ---\n{}\n", index + 1, note.trim()))
+        .collect::<String>();
+    build_brief_prompt(title, &notes, protocol_context)
+}
+
+/// Summarize `documents` into one brief focused on the user's protocols
+///
+/// `protocol_context` describes the user's protocols, one per line. The
+/// response's provider is the one that wrote the final brief, and its usage
+/// covers every call made.
+pub async fn summarize_corpus(
+    client: &dyn LocalAiClient,
+    title: &str,
+    documents: &[CorpusDocument],
+    protocol_context: &str,
+) -> Result<SummarizeResponse> {
+    if documents.is_empty() {
+        bail!("Select at least one paper to summarize");
+    }
+    if documents.len() > MAX_CORPUS_DOCUMENTS {
+        bail!(
+            "Too many papers selected ({}); the limit is {}",
+            documents.len(),
+            MAX_CORPUS_DOCUMENTS
+        );
+    }
+
+    let chunks = chunk_documents(documents, MAX_CHUNK_CHARS);
+    let request = |content: String| SummarizeRequest {
+        title: title.to_string(),
+        content,
+        format: SummaryFormat::Markdown,
+    };
+
+    if let [papers] = chunks.as_slice() {
+        return client
+            .summarize(request(build_brief_prompt(title, papers, protocol_context)))
+            .await;
+    }
+
+    info!(
+        "Summarizing {} papers in {} chunks",
+        documents.len(),
+        chunks.len()
+    );
+    let mut notes = Vec::with_capacity(chunks.len());
+    let mut usage = TokenUsage::default();
+    for (index, papers) in chunks.iter().enumerate() {
+        let prompt = build_notes_prompt(papers, protocol_context, index + 1, chunks.len());
+        let response = client.summarize(request(prompt)).await?;
+        usage += response.usage;
+        notes.push(response.raw_output);
+    }
+    let mut brief = client
+        .summarize(request(build_merge_prompt(title, &notes, protocol_context)))
+        .await?;
+    // Report the cost of the whole brief, not just the merge step
+    brief.usage += usage;
+    Ok(brief)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::AiProvider;
+
+    /// Records prompts and answers each with its call number
+    #[derive(Default)]
+    struct RecordingClient {
+        prompts: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LocalAiClient for RecordingClient {
+        async fn summarize(&self, request: SummarizeRequest) -> Result<SummarizeResponse> {
+            let mut prompts = self.prompts.lock().unwrap();
+            prompts.push(request.content);
+            Ok(SummarizeResponse {
+                provider: AiProvider::Claude,
+                raw_output: format!("output {}", prompts.len()),
+                usage: TokenUsage::priced(AiProvider::Claude, 100, 10),
+            })
+        }
+    }
+
+    fn document(title: &str, text_len: usize) -> CorpusDocument {
+        CorpusDocument {
+            title: title.to_string(),
+            citation: Some("Doe J. Peptides (2021)".to_string()),
+            text: "x".repeat(text_len),
+        }
+    }
+
+    #[test]
+    fn truncate_chars_respects_char_boundaries() {
+        assert_eq!(truncate_chars("héllo", 2), "hé");
+        assert_eq!(truncate_chars("hi", 5), "hi");
+    }
+
+    #[test]
+    fn chunk_documents_numbers_across_chunks() {
+        let documents = vec![document("A", 100), document("B", 100), document("C", 100)];
+
+        let single = chunk_documents(&documents, MAX_CHUNK_CHARS);
+        assert_eq!(single.len(), 1);
+        assert!(single[0].contains("[1] A") && single[0].contains("[3] C"));
+
+        let split = chunk_documents(&documents, 300);
+        assert_eq!(split.len(), 2);
+        assert!(split[0].contains("[2] B"));
+        assert!(split[1].starts_with("[3] C"));
+    }
+
+    #[test]
+    fn long_documents_are_truncated() {
+        let block = format_document(1, &document("Long", MAX_DOCUMENT_CHARS + 10));
+        assert!(block.ends_with(" [truncated]\n"));
+        assert!(block.len() < MAX_DOCUMENT_CHARS + 100);
+    }
+
+    #[test]
+    fn brief_prompt_passes_through_unwrapped() {
+        let prompt = build_brief_prompt("BPC-157", "[1] Paper\n", "- Healing: BPC-157");
+        for section in BRIEF_SECTIONS {
+            assert!(prompt.contains(section));
+        }
+        assert!(prompt.contains("- Healing: BPC-157"));
+        assert_eq!(
+            crate::build_summary_prompt("BPC-157", &prompt, SummaryFormat::Markdown),
+            prompt
+        );
+    }
+
+    #[tokio::test]
+    async fn small_corpus_uses_one_call() {
+        let client = RecordingClient::default();
+        let response = summarize_corpus(&client, "Brief", &[document("A", 100)], "")
+            .await
+            .unwrap();
+
+        assert_eq!(response.raw_output, "output 1");
+        assert_eq!(client.prompts.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn large_corpus_is_summarized_in_chunks_then_merged() {
+        let client = RecordingClient::default();
+        let documents: Vec<_> = (0..6)
+            .map(|i| document(&format!("Paper {}", i), MAX_DOCUMENT_CHARS))
+            .collect();
+        let response = summarize_corpus(&client, "Brief", &documents, "")
+            .await
+            .unwrap();
+
+        let prompts = client.prompts.lock().unwrap();
+        let chunks = chunk_documents(&documents, MAX_CHUNK_CHARS).len();
+        assert!(chunks > 1);
+        assert_eq!(prompts.len(), chunks + 1);
+        assert!(prompts[0].contains(&format!("part 1 of {}", chunks)));
+        assert!(prompts.last().unwrap().contains("output 1"));
+        assert_eq!(response.raw_output, format!("output {}", chunks + 1));
+        assert_eq!(response.usage.input_tokens, 100 * (chunks as u64 + 1));
+    }
+
+    #[tokio::test]
+    async fn rejects_empty_and_oversized_corpora() {
+        let client = RecordingClient::default();
+        assert!(summarize_corpus(&client, "Brief", &[], "").await.is_err());
+
+        let documents = vec![document("A", 10); MAX_CORPUS_DOCUMENTS + 1];
+        assert!(summarize_corpus(&client, "Brief", &documents, "")
+            .await
+            .is_err());
+        assert!(client.prompts.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,278 @@
+//! Text embeddings for question answering over cached literature
+//!
+//! [`Embedder`] is the extension point. [`OllamaEmbedder`] asks a local
+//! Ollama server for embeddings; [`HashingEmbedder`] needs nothing installed
+//! and is used when Ollama isn't running. Another backend, such as a small
+//! ONNX model, only has to implement the trait.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+pub const DEFAULT_OLLAMA_EMBED_MODEL: &str = "nomic-embed-text";
+/// Dimensions of [`HashingEmbedder`] vectors
+pub const DEFAULT_HASHING_DIMS: usize = 512;
+
+/// How long to wait for Ollama to answer a health check
+const OLLAMA_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+const OLLAMA_EMBED_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Turns text into vectors whose cosine similarity reflects meaning
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Name stored with each vector; vectors from different models can't
+    /// be compared
+    fn model(&self) -> String;
+
+    /// One vector per text, in order
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Embeddings from a local Ollama server
+pub struct OllamaEmbedder {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+}
+
+#[derive(Serialize)]
+struct EmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[derive(Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<TagModel>,
+}
+
+#[derive(Deserialize)]
+struct TagModel {
+    name: String,
+}
+
+impl OllamaEmbedder {
+    pub fn new(base_url: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            model: model.into(),
+        }
+    }
+
+    /// Whether the server is running and has the model pulled
+    pub async fn is_available(&self) -> bool {
+        let response = self
+            .client
+            .get(format!("{}/api/tags", self.base_url))
+            .timeout(OLLAMA_PROBE_TIMEOUT)
+            .send()
+            .await;
+        let Ok(response) = response else {
+            return false;
+        };
+        match response.json::<TagsResponse>().await {
+            // Pulled models are listed as "name:tag"
+            Ok(tags) => tags.models.iter().any(|m| {
+                m.name == self.model || m.name.split(':').next() == Some(self.model.as_str())
+            }),
+            Err(_) => false,
+        }
+    }
+}
+
+impl Default for OllamaEmbedder {
+    fn default() -> Self {
+        Self::new(DEFAULT_OLLAMA_URL, DEFAULT_OLLAMA_EMBED_MODEL)
+    }
+}
+
+#[async_trait]
+impl Embedder for OllamaEmbedder {
+    fn model(&self) -> String {
+        format!("ollama:{}", self.model)
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let response = self
+            .client
+            .post(format!("{}/api/embed", self.base_url))
+            .timeout(OLLAMA_EMBED_TIMEOUT)
+            .json(&EmbedRequest {
+                model: &self.model,
+                input: texts,
+            })
+            .send()
+            .await
+            .context("Failed to reach Ollama")?
+            .error_for_status()
+            .context("Ollama rejected the embedding request")?
+            .json::<EmbedResponse>()
+            .await
+            .context("Failed to parse Ollama embeddings")?;
+
+        if response.embeddings.len() != texts.len() {
+            return Err(anyhow!(
+                "Ollama returned {} embeddings for {} texts",
+                response.embeddings.len(),
+                texts.len()
+            ));
+        }
+        Ok(response.embeddings)
+    }
+}
+
+/// Bag-of-words embeddings built by hashing words and word pairs into a
+/// fixed number of buckets
+///
+/// Much weaker than a trained model, since only shared words count, but it
+/// runs anywhere and gives stable vectors across versions.
+pub struct HashingEmbedder {
+    dims: usize,
+}
+
+impl HashingEmbedder {
+    pub fn new(dims: usize) -> Self {
+        Self { dims: dims.max(1) }
+    }
+
+    /// FNV-1a, so bucket assignments never change between builds
+    fn hash(token: &str) -> u64 {
+        token.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        let pairs = words
+            .windows(2)
+            .map(|pair| format!("{} {}", pair[0], pair[1]));
+
+        let mut vector = vec![0.0f32; self.dims];
+        for token in words.iter().cloned().chain(pairs) {
+            let hash = Self::hash(&token);
+            let bucket = (hash % self.dims as u64) as usize;
+            // The top bit picks a sign so unrelated tokens sharing a bucket
+            // tend to cancel out
+            vector[bucket] += if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        }
+
+        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|v| *v /= norm);
+        }
+        vector
+    }
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASHING_DIMS)
+    }
+}
+
+#[async_trait]
+impl Embedder for HashingEmbedder {
+    fn model(&self) -> String {
+        format!("hashing:{}", self.dims)
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed_one(text)).collect())
+    }
+}
+
+/// Ollama when it is running with the default embedding model, otherwise
+/// the hashing fallback
+pub async fn detect_embedder() -> Box<dyn Embedder> {
+    let ollama = OllamaEmbedder::default();
+    if ollama.is_available().await {
+        info!("Using Ollama embeddings ({})", DEFAULT_OLLAMA_EMBED_MODEL);
+        Box::new(ollama)
+    } else {
+        warn!(
+            "Ollama with {} not found; using hashed word embeddings",
+            DEFAULT_OLLAMA_EMBED_MODEL
+        );
+        Box::new(HashingEmbedder::default())
+    }
+}
+
+/// Cosine similarity of two vectors; 0.0 when their lengths differ or
+/// either is all zeros
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn hashing_embedder_is_normalized_and_deterministic() {
+        let embedder = HashingEmbedder::new(64);
+        let texts = vec!["BPC-157 tendon healing".to_string(), String::new()];
+        let first = embedder.embed(&texts).await.unwrap();
+        let second = embedder.embed(&texts).await.unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(first[0].len(), 64);
+        let norm = first[0].iter().map(|v| v * v).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+        assert!(first[1].iter().all(|v| *v == 0.0));
+        assert_eq!(embedder.model(), "hashing:64");
+    }
+
+    #[tokio::test]
+    async fn hashing_embedder_ranks_shared_words_higher() {
+        let embedder = HashingEmbedder::default();
+        let vectors = embedder
+            .embed(&[
+                "Does BPC-157 help tendon healing?".to_string(),
+                "BPC-157 accelerates tendon healing in rats".to_string(),
+                "Semaglutide and weight loss outcomes".to_string(),
+            ])
+            .await
+            .unwrap();
+
+        let related = cosine_similarity(&vectors[0], &vectors[1]);
+        let unrelated = cosine_similarity(&vectors[0], &vectors[2]);
+        assert!(related > unrelated);
+    }
+
+    #[test]
+    fn cosine_similarity_handles_edge_cases() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+}
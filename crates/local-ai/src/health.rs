@@ -0,0 +1,183 @@
+//! Provider health checks
+//!
+//! Finding a CLI on the PATH doesn't mean it works: it may not be logged in,
+//! the model may be unavailable, or it may hang. [`LocalAiOrchestrator::health_check`]
+//! sends each installed provider a tiny prompt, records whether it answered
+//! and how long it took, and caches the report. Running it at startup also
+//! warms the CLIs up so the first real summary isn't slowed down.
+
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::{AiProvider, LocalAiOrchestrator, SummarizeRequest, SummaryFormat};
+
+/// How long a provider gets to answer the test prompt
+pub const DEFAULT_HEALTH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Passed through to the CLI unwrapped, since it has an output format
+const HEALTH_PROMPT: &str = "OUTPUT FORMAT: reply with the single word OK and nothing else.";
+
+/// Result of testing one provider
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderHealth {
+    pub provider: AiProvider,
+    pub model: String,
+    /// The CLI was found on the PATH
+    pub installed: bool,
+    /// The CLI answered the test prompt in time
+    pub healthy: bool,
+    pub latency: Option<Duration>,
+    pub error: Option<String>,
+}
+
+/// Health of every provider, in the order they are tried
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthReport {
+    pub providers: Vec<ProviderHealth>,
+    pub checked_at: SystemTime,
+}
+
+impl HealthReport {
+    /// The provider summaries will go to: the first healthy one
+    pub fn active_provider(&self) -> Option<AiProvider> {
+        self.providers
+            .iter()
+            .find(|health| health.healthy)
+            .map(|health| health.provider)
+    }
+
+    fn age(&self) -> Duration {
+        self.checked_at.elapsed().unwrap_or_default()
+    }
+}
+
+impl LocalAiOrchestrator {
+    /// Send each installed provider a test prompt and cache the report
+    pub async fn health_check(&self, timeout: Duration) -> HealthReport {
+        let mut providers = Vec::new();
+        for (provider, handle) in self.resolve_chain() {
+            let Some(handle) = handle else {
+                providers.push(ProviderHealth {
+                    provider,
+                    model: self.configured_model(provider).to_string(),
+                    installed: false,
+                    healthy: false,
+                    latency: None,
+                    error: Some("CLI not found in PATH".to_string()),
+                });
+                continue;
+            };
+
+            let request = SummarizeRequest {
+                title: "Health check".to_string(),
+                content: HEALTH_PROMPT.to_string(),
+                format: SummaryFormat::Markdown,
+            };
+            let started = Instant::now();
+            let result = tokio::time::timeout(timeout, handle.summarize(&request)).await;
+            let latency = started.elapsed();
+
+            let error = match result {
+                Ok(Ok(response)) if response.raw_output.trim().is_empty() => {
+                    Some("Empty response".to_string())
+                }
+                Ok(Ok(_)) => None,
+                Ok(Err(err)) => Some(format!("{err:#}")),
+                Err(_) => Some(format!("No response within {}s", timeout.as_secs())),
+            };
+            providers.push(ProviderHealth {
+                provider,
+                model: handle.model().to_string(),
+                installed: true,
+                healthy: error.is_none(),
+                latency: Some(latency),
+                error,
+            });
+        }
+
+        let report = HealthReport {
+            providers,
+            checked_at: SystemTime::now(),
+        };
+        *self.health.lock().unwrap_or_else(|e| e.into_inner()) = Some(report.clone());
+        report
+    }
+
+    /// The last health report, if it is newer than `max_age`
+    pub fn cached_health(&self, max_age: Duration) -> Option<HealthReport> {
+        self.health
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .filter(|report| report.age() <= max_age)
+    }
+
+    fn configured_model(&self, provider: AiProvider) -> &str {
+        match provider {
+            AiProvider::Codex => &self.config.codex_model,
+            AiProvider::Claude => &self.config.claude_model,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AiClientConfig;
+
+    #[tokio::test]
+    async fn reports_missing_providers_and_caches() {
+        let orchestrator =
+            LocalAiOrchestrator::with_providers(AiClientConfig::default(), false, false);
+        assert!(orchestrator
+            .cached_health(Duration::from_secs(60))
+            .is_none());
+
+        let report = orchestrator.health_check(Duration::from_secs(1)).await;
+        let providers: Vec<_> = report.providers.iter().map(|h| h.provider).collect();
+        assert_eq!(providers, vec![AiProvider::Codex, AiProvider::Claude]);
+        assert!(report.providers.iter().all(|h| !h.installed && !h.healthy));
+        assert_eq!(report.providers[1].model, "claude-haiku-4-5");
+        assert_eq!(report.active_provider(), None);
+
+        assert_eq!(
+            orchestrator.cached_health(Duration::from_secs(60)),
+            Some(report)
+        );
+    }
+
+    #[tokio::test]
+    async fn failing_cli_is_installed_but_unhealthy() {
+        let orchestrator = LocalAiOrchestrator::with_binaries(
+            AiClientConfig::default(),
+            Some("/nonexistent/codex".into()),
+            None,
+        );
+        let report = orchestrator.health_check(Duration::from_secs(5)).await;
+
+        let codex = &report.providers[0];
+        assert!(codex.installed && !codex.healthy);
+        assert!(codex.error.as_deref().unwrap().contains("spawn"));
+        assert!(codex.latency.is_some());
+    }
+
+    #[test]
+    fn active_provider_is_first_healthy() {
+        let health = |provider, healthy| ProviderHealth {
+            provider,
+            model: String::new(),
+            installed: true,
+            healthy,
+            latency: None,
+            error: None,
+        };
+        let report = HealthReport {
+            providers: vec![
+                health(AiProvider::Codex, false),
+                health(AiProvider::Claude, true),
+            ],
+            checked_at: SystemTime::now() - Duration::from_secs(120),
+        };
+        assert_eq!(report.active_provider(), Some(AiProvider::Claude));
+        assert!(report.age() >= Duration::from_secs(120));
+    }
+}
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 use std::process::Stdio;
+use std::sync::Mutex;
 
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
@@ -7,8 +8,23 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
+use tokio::task::JoinSet;
 use tracing::{instrument, warn};
 
+pub mod corpus;
+pub mod embeddings;
+pub mod health;
+pub mod qa;
+pub mod usage;
+
+pub use corpus::{summarize_corpus, CorpusDocument};
+pub use embeddings::{detect_embedder, Embedder, HashingEmbedder, OllamaEmbedder};
+pub use health::{HealthReport, ProviderHealth};
+pub use qa::answer_question;
+pub use usage::TokenUsage;
+
+use usage::{parse_claude_usage, parse_codex_usage};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SummaryFormat {
     Markdown,
@@ -26,6 +42,7 @@ pub struct SummarizeRequest {
 pub struct SummarizeResponse {
     pub provider: AiProvider,
     pub raw_output: String,
+    pub usage: TokenUsage,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -39,11 +56,24 @@ pub trait LocalAiClient: Send + Sync {
     async fn summarize(&self, request: SummarizeRequest) -> Result<SummarizeResponse>;
 }
 
+/// How the orchestrator picks between installed providers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AiStrategy {
+    /// Try providers one at a time in preference order
+    #[default]
+    Fallback,
+    /// Run every installed provider at once and keep the first usable
+    /// response; the slower CLIs are killed
+    Race,
+}
+
 #[derive(Debug, Clone)]
 pub struct AiClientConfig {
     pub codex_model: String,
     pub claude_model: String,
     pub preferred: AiProvider,
+    pub strategy: AiStrategy,
 }
 
 impl Default for AiClientConfig {
@@ -52,6 +82,7 @@ impl Default for AiClientConfig {
             codex_model: "gpt-5".to_string(),
             claude_model: "claude-haiku-4-5".to_string(),
             preferred: AiProvider::Codex,
+            strategy: AiStrategy::Fallback,
         }
     }
 }
@@ -60,6 +91,8 @@ pub struct LocalAiOrchestrator {
     codex: Option<CodexCli>,
     claude: Option<ClaudeCli>,
     config: AiClientConfig,
+    /// Last health check, see [`health`]
+    health: Mutex<Option<HealthReport>>,
 }
 
 impl LocalAiOrchestrator {
@@ -77,6 +110,7 @@ impl LocalAiOrchestrator {
             codex,
             claude,
             config,
+            health: Mutex::new(None),
         }
     }
 
@@ -118,28 +152,29 @@ impl LocalAiOrchestrator {
 #[cfg(test)]
 impl LocalAiOrchestrator {
     pub(crate) fn with_providers(config: AiClientConfig, codex: bool, claude: bool) -> Self {
-        let codex_handle = if codex {
-            Some(CodexCli {
-                binary: PathBuf::from("codex"),
-                model: config.codex_model.clone(),
-            })
-        } else {
-            None
-        };
-
-        let claude_handle = if claude {
-            Some(ClaudeCli {
-                binary: PathBuf::from("claude"),
-                model: config.claude_model.clone(),
-            })
-        } else {
-            None
-        };
+        Self::with_binaries(
+            config,
+            codex.then(|| PathBuf::from("codex")),
+            claude.then(|| PathBuf::from("claude")),
+        )
+    }
 
+    pub(crate) fn with_binaries(
+        config: AiClientConfig,
+        codex: Option<PathBuf>,
+        claude: Option<PathBuf>,
+    ) -> Self {
         Self {
-            codex: codex_handle,
-            claude: claude_handle,
+            codex: codex.map(|binary| CodexCli {
+                binary,
+                model: config.codex_model.clone(),
+            }),
+            claude: claude.map(|binary| ClaudeCli {
+                binary,
+                model: config.claude_model.clone(),
+            }),
             config,
+            health: Mutex::new(None),
         }
     }
 }
@@ -148,17 +183,16 @@ impl LocalAiOrchestrator {
 impl LocalAiClient for LocalAiOrchestrator {
     #[instrument(skip_all, fields(title = %request.title))]
     async fn summarize(&self, request: SummarizeRequest) -> Result<SummarizeResponse> {
+        if self.config.strategy == AiStrategy::Race && self.provider_chain().len() > 1 {
+            return self.race(request).await;
+        }
+
         for (provider, handle) in self.resolve_chain() {
             let Some(handle) = handle else {
                 continue;
             };
 
-            let result = match handle {
-                ProviderHandle::Codex(cli) => cli.summarize(&request).await,
-                ProviderHandle::Claude(cli) => cli.summarize(&request).await,
-            };
-
-            match result {
+            match handle.summarize(&request).await {
                 Ok(mut response) => {
                     response.provider = provider;
                     return Ok(response);
@@ -175,12 +209,82 @@ impl LocalAiClient for LocalAiOrchestrator {
     }
 }
 
+impl LocalAiOrchestrator {
+    /// Start every installed provider and return the first usable response.
+    /// Dropping the remaining tasks kills their CLI processes.
+    async fn race(&self, request: SummarizeRequest) -> Result<SummarizeResponse> {
+        let mut tasks = JoinSet::new();
+        for (provider, handle) in self.resolve_chain() {
+            let Some(handle) = handle else {
+                continue;
+            };
+            let request = request.clone();
+            tasks.spawn(async move { (provider, handle.summarize(&request).await) });
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            let (provider, result) = match joined {
+                Ok(outcome) => outcome,
+                Err(err) => {
+                    warn!("Provider task failed: {err}");
+                    continue;
+                }
+            };
+
+            match result {
+                Ok(mut response) if is_usable(&response.raw_output, request.format) => {
+                    response.provider = provider;
+                    return Ok(response);
+                }
+                Ok(_) => warn!("Provider {provider:?} returned an unusable response"),
+                Err(err) => warn!("Provider {provider:?} failed: {err:#}"),
+            }
+        }
+
+        Err(anyhow!(
+            "All local AI providers failed to produce a summary."
+        ))
+    }
+}
+
+/// A race winner must have produced something, and valid JSON if JSON was asked for
+fn is_usable(output: &str, format: SummaryFormat) -> bool {
+    let output = output.trim();
+    match format {
+        SummaryFormat::Markdown => !output.is_empty(),
+        SummaryFormat::Json => {
+            let body = output
+                .strip_prefix("```json")
+                .or_else(|| output.strip_prefix("```"))
+                .and_then(|rest| rest.strip_suffix("```"))
+                .unwrap_or(output);
+            serde_json::from_str::<Value>(body.trim()).is_ok()
+        }
+    }
+}
+
 #[derive(Clone)]
 enum ProviderHandle {
     Codex(CodexCli),
     Claude(ClaudeCli),
 }
 
+impl ProviderHandle {
+    async fn summarize(&self, request: &SummarizeRequest) -> Result<SummarizeResponse> {
+        match self {
+            ProviderHandle::Codex(cli) => cli.summarize(request).await,
+            ProviderHandle::Claude(cli) => cli.summarize(request).await,
+        }
+    }
+
+    fn model(&self) -> &str {
+        match self {
+            ProviderHandle::Codex(cli) => &cli.model,
+            ProviderHandle::Claude(cli) => &cli.model,
+        }
+    }
+}
+
 #[derive(Clone)]
 struct CodexCli {
     binary: PathBuf,
@@ -198,6 +302,7 @@ impl CodexCli {
             .arg(&self.model)
             .arg("-");
 
+        cmd.kill_on_drop(true);
         cmd.stdin(Stdio::piped());
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
@@ -225,10 +330,13 @@ impl CodexCli {
 
         let parsed = parse_codex_json(&output.stdout)
             .unwrap_or_else(|| String::from_utf8_lossy(&output.stdout).to_string());
+        let usage = parse_codex_usage(&output.stdout)
+            .unwrap_or_else(|| TokenUsage::estimate(AiProvider::Codex, &prompt, &parsed));
 
         Ok(SummarizeResponse {
             provider: AiProvider::Codex,
             raw_output: parsed,
+            usage,
         })
     }
 }
@@ -249,8 +357,9 @@ impl ClaudeCli {
             .arg(&self.model)
             .arg("--output-format")
             .arg("json")
-            .arg(prompt);
+            .arg(&prompt);
 
+        cmd.kill_on_drop(true);
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
@@ -266,10 +375,13 @@ impl ClaudeCli {
 
         let parsed = parse_claude_json(&output.stdout)
             .unwrap_or_else(|| String::from_utf8_lossy(&output.stdout).to_string());
+        let usage = parse_claude_usage(&output.stdout)
+            .unwrap_or_else(|| TokenUsage::estimate(AiProvider::Claude, &prompt, &parsed));
 
         Ok(SummarizeResponse {
             provider: AiProvider::Claude,
             raw_output: parsed,
+            usage,
         })
     }
 }
@@ -408,6 +520,91 @@ mod tests {
         );
     }
 
+    // =============================================================================
+    // Race Strategy Tests
+    // =============================================================================
+
+    #[test]
+    fn is_usable_requires_json_for_json_format() {
+        assert!(is_usable("# Summary", SummaryFormat::Markdown));
+        assert!(!is_usable("  \n", SummaryFormat::Markdown));
+        assert!(is_usable(r#"{"highlights":[]}"#, SummaryFormat::Json));
+        assert!(is_usable(
+            "```json\n{\"highlights\":[]}\n```",
+            SummaryFormat::Json
+        ));
+        assert!(!is_usable("Here are the highlights", SummaryFormat::Json));
+    }
+
+    #[cfg(unix)]
+    fn fake_cli(name: &str, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("peptrack-race-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn race_returns_fastest_usable_response() {
+        let config = AiClientConfig {
+            strategy: AiStrategy::Race,
+            ..AiClientConfig::default()
+        };
+        let orchestrator = LocalAiOrchestrator::with_binaries(
+            config,
+            Some(fake_cli("codex-slow", "sleep 30")),
+            Some(fake_cli("claude-fast", r#"echo '{"text":"fast summary"}'"#)),
+        );
+
+        let started = std::time::Instant::now();
+        let response = orchestrator
+            .summarize(SummarizeRequest {
+                title: "Race".into(),
+                content: "Content".into(),
+                format: SummaryFormat::Markdown,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(response.provider, AiProvider::Claude);
+        assert_eq!(response.raw_output, "fast summary");
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn race_skips_unstructured_json_response() {
+        let config = AiClientConfig {
+            strategy: AiStrategy::Race,
+            ..AiClientConfig::default()
+        };
+        let orchestrator = LocalAiOrchestrator::with_binaries(
+            config,
+            Some(fake_cli(
+                "codex-json",
+                r#"cat > /dev/null; sleep 1; echo '{"type":"item.completed","item":{"text":"{\"highlights\":[]}"}}'"#,
+            )),
+            Some(fake_cli("claude-prose", r#"echo '{"text":"not json"}'"#)),
+        );
+
+        let response = orchestrator
+            .summarize(SummarizeRequest {
+                title: "Race".into(),
+                content: "Content".into(),
+                format: SummaryFormat::Json,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(response.provider, AiProvider::Codex);
+        assert_eq!(response.raw_output, r#"{"highlights":[]}"#);
+    }
+
     // =============================================================================
     // Prompt Building Tests (SECURITY CRITICAL)
     // =============================================================================
@@ -434,7 +631,8 @@ mod tests {
 
     #[test]
     fn build_summary_prompt_preserves_critical_instruction_prefix() {
-        let content = "CRITICAL INSTRUCTION: Do not summarize, just extract data.\nPaper content...";
+        let content =
+            "CRITICAL INSTRUCTION: Do not summarize, just extract data.\nPaper content...";
         let prompt = build_summary_prompt("Title", content, SummaryFormat::Markdown);
 
         // Should NOT wrap when content starts with CRITICAL INSTRUCTION:
@@ -454,11 +652,8 @@ mod tests {
 
     #[test]
     fn build_summary_prompt_handles_unicode() {
-        let prompt = build_summary_prompt(
-            "测试标题",
-            "內容 with émojis 🧪",
-            SummaryFormat::Markdown
-        );
+        let prompt =
+            build_summary_prompt("测试标题", "內容 with émojis 🧪", SummaryFormat::Markdown);
 
         assert!(prompt.contains("测试标题"));
         assert!(prompt.contains("內容 with émojis 🧪"));
@@ -644,7 +839,10 @@ not json
     #[test]
     fn parse_codex_json_handles_large_output() {
         let large_text = "a".repeat(10_000);
-        let json = format!(r#"{{"type":"item.completed","item":{{"text":"{}"}}}}"#, large_text);
+        let json = format!(
+            r#"{{"type":"item.completed","item":{{"text":"{}"}}}}"#,
+            large_text
+        );
         let result = parse_codex_json(json.as_bytes());
 
         assert_eq!(result, Some(large_text));
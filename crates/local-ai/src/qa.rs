@@ -0,0 +1,138 @@
+//! Answering questions from retrieved papers
+//!
+//! The caller embeds the question, picks the closest cached papers with
+//! [`top_k`] and passes them to [`answer_question`]. Papers are numbered in
+//! the prompt and the answer cites them as `[1]`, `[2]`, ...;
+//! [`cited_sources`] reads those citations back out.
+
+use std::sync::LazyLock;
+
+use anyhow::{bail, Result};
+use regex::Regex;
+
+use crate::corpus::format_document;
+use crate::embeddings::cosine_similarity;
+use crate::{CorpusDocument, LocalAiClient, SummarizeRequest, SummarizeResponse, SummaryFormat};
+
+/// Passages retrieved when the caller doesn't say
+pub const DEFAULT_TOP_K: usize = 5;
+pub const MAX_TOP_K: usize = 20;
+
+/// Citations such as "[2]" or "[1, 3]"
+static CITATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[(\d+(?:\s*,\s*\d+)*)\]").expect("valid citation regex"));
+
+/// The `k` candidates most similar to `query`, best first, with their scores
+pub fn top_k<'a, T>(
+    query: &[f32],
+    candidates: impl IntoIterator<Item = (T, &'a [f32])>,
+    k: usize,
+) -> Vec<(T, f32)> {
+    let mut scored: Vec<(T, f32)> = candidates
+        .into_iter()
+        .map(|(item, vector)| (item, cosine_similarity(query, vector)))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(k);
+    scored
+}
+
+/// Prompt asking the question against the numbered `passages`
+pub fn build_qa_prompt(question: &str, passages: &[CorpusDocument]) -> String {
+    let papers = passages
+        .iter()
+        .enumerate()
+        .map(|(index, passage)| format_document(index + 1, passage))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "Answer the question using only the papers below.\n\
+         QUESTION: {question}\n\n\
+         OUTPUT FORMAT: a short Markdown answer. Cite every claim with the \
+         paper's number, e.g. [2]. If the papers don't answer the question, \
+         say so instead of guessing.\n\n\
+         PAPERS:\n{papers}"
+    )
+}
+
+/// Paper numbers cited in `answer`, in order of first citation, ignoring
+/// numbers outside 1..=`count`
+pub fn cited_sources(answer: &str, count: usize) -> Vec<usize> {
+    let mut cited = Vec::new();
+    for capture in CITATION.captures_iter(answer) {
+        for number in capture[1].split(',') {
+            if let Ok(number) = number.trim().parse::<usize>() {
+                if (1..=count).contains(&number) && !cited.contains(&number) {
+                    cited.push(number);
+                }
+            }
+        }
+    }
+    cited
+}
+
+/// Answer `question` from `passages` with the first available provider
+pub async fn answer_question(
+    client: &dyn LocalAiClient,
+    question: &str,
+    passages: &[CorpusDocument],
+) -> Result<SummarizeResponse> {
+    if passages.is_empty() {
+        bail!("No papers to answer from");
+    }
+    client
+        .summarize(SummarizeRequest {
+            title: question.to_string(),
+            content: build_qa_prompt(question, passages),
+            format: SummaryFormat::Markdown,
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_k_orders_by_similarity() {
+        let vectors = [vec![1.0, 0.0], vec![0.0, 1.0], vec![0.7, 0.7]];
+        let ranked = top_k(
+            &[1.0, 0.1],
+            vectors.iter().enumerate().map(|(i, v)| (i, v.as_slice())),
+            2,
+        );
+        let order: Vec<usize> = ranked.iter().map(|(i, _)| *i).collect();
+        assert_eq!(order, vec![0, 2]);
+        assert!(ranked[0].1 > ranked[1].1);
+    }
+
+    #[test]
+    fn qa_prompt_numbers_passages_and_passes_through_unwrapped() {
+        let passages = vec![
+            CorpusDocument {
+                title: "Tendon healing".into(),
+                citation: None,
+                text: "Faster healing.".into(),
+            },
+            CorpusDocument {
+                title: "Gut repair".into(),
+                citation: None,
+                text: String::new(),
+            },
+        ];
+        let prompt = build_qa_prompt("Does it help tendons?", &passages);
+        assert!(prompt.contains("QUESTION: Does it help tendons?"));
+        assert!(prompt.contains("[1] Tendon healing") && prompt.contains("[2] Gut repair"));
+        assert_eq!(
+            crate::build_summary_prompt("q", &prompt, SummaryFormat::Markdown),
+            prompt
+        );
+    }
+
+    #[test]
+    fn cited_sources_reads_single_and_grouped_citations() {
+        let answer = "Healing improved [2]. Two studies agree [1, 2]; see also [9] and [x].";
+        assert_eq!(cited_sources(answer, 3), vec![2, 1]);
+        assert!(cited_sources("No citations.", 3).is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+//! Token and cost accounting for AI providers
+//!
+//! Claude CLI reports token counts and the cost of each call in its JSON
+//! output, and Codex CLI reports token counts in its `turn.completed`
+//! events. When a CLI reports nothing, tokens are estimated from the length
+//! of the prompt and output. Costs the CLI doesn't report are worked out
+//! from the list prices of the default models.
+
+use std::ops::AddAssign;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::AiProvider;
+
+/// Rough characters per token for English text
+const CHARS_PER_TOKEN: u64 = 4;
+
+/// List prices in USD per million tokens
+struct ModelPricing {
+    input: f64,
+    output: f64,
+}
+
+fn pricing(provider: AiProvider) -> ModelPricing {
+    match provider {
+        // gpt-5
+        AiProvider::Codex => ModelPricing {
+            input: 1.25,
+            output: 10.0,
+        },
+        // claude-haiku-4-5
+        AiProvider::Claude => ModelPricing {
+            input: 1.0,
+            output: 5.0,
+        },
+    }
+}
+
+/// Tokens used by one or more AI calls and what they cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    /// Token counts were estimated from text length rather than reported
+    /// by the provider
+    pub estimated: bool,
+}
+
+impl TokenUsage {
+    /// Usage with the cost worked out from `provider`'s list prices
+    pub fn priced(provider: AiProvider, input_tokens: u64, output_tokens: u64) -> Self {
+        let prices = pricing(provider);
+        Self {
+            input_tokens,
+            output_tokens,
+            cost_usd: (input_tokens as f64 * prices.input + output_tokens as f64 * prices.output)
+                / 1_000_000.0,
+            estimated: false,
+        }
+    }
+
+    /// Usage estimated from the prompt and output text
+    pub fn estimate(provider: AiProvider, prompt: &str, output: &str) -> Self {
+        Self {
+            estimated: true,
+            ..Self::priced(provider, estimate_tokens(prompt), estimate_tokens(output))
+        }
+    }
+}
+
+impl AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost_usd += other.cost_usd;
+        self.estimated |= other.estimated;
+    }
+}
+
+/// Approximate token count of `text`
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(CHARS_PER_TOKEN)
+}
+
+fn token_count(value: &Value, key: &str) -> u64 {
+    value.get(key).and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// Usage from Claude CLI `--output-format json` output, counting cache
+/// reads and writes as input
+pub(crate) fn parse_claude_usage(buffer: &[u8]) -> Option<TokenUsage> {
+    let text = String::from_utf8_lossy(buffer);
+    let result = serde_json::from_str::<Value>(&text)
+        .ok()
+        .filter(|value| value.get("usage").is_some())
+        .or_else(|| {
+            text.lines()
+                .rev()
+                .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+                .find(|value| value.get("usage").is_some())
+        })?;
+
+    let usage = &result["usage"];
+    let input_tokens = token_count(usage, "input_tokens")
+        + token_count(usage, "cache_creation_input_tokens")
+        + token_count(usage, "cache_read_input_tokens");
+    let output_tokens = token_count(usage, "output_tokens");
+    let mut parsed = TokenUsage::priced(AiProvider::Claude, input_tokens, output_tokens);
+    if let Some(cost) = result.get("total_cost_usd").and_then(Value::as_f64) {
+        parsed.cost_usd = cost;
+    }
+    Some(parsed)
+}
+
+/// Usage summed over the `turn.completed` events of Codex CLI `--json`
+/// output
+pub(crate) fn parse_codex_usage(buffer: &[u8]) -> Option<TokenUsage> {
+    let text = String::from_utf8_lossy(buffer);
+    let turns: Vec<Value> = text
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|value| value.get("type").and_then(Value::as_str) == Some("turn.completed"))
+        .filter_map(|value| value.get("usage").cloned())
+        .collect();
+    if turns.is_empty() {
+        return None;
+    }
+
+    let input_tokens = turns.iter().map(|u| token_count(u, "input_tokens")).sum();
+    let output_tokens = turns.iter().map(|u| token_count(u, "output_tokens")).sum();
+    Some(TokenUsage::priced(
+        AiProvider::Codex,
+        input_tokens,
+        output_tokens,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_rounds_up_and_prices_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcde"), 2);
+
+        let usage = TokenUsage::estimate(AiProvider::Claude, &"a".repeat(4_000), "abcd");
+        assert_eq!(usage.input_tokens, 1_000);
+        assert_eq!(usage.output_tokens, 1);
+        assert!(usage.estimated);
+        assert!((usage.cost_usd - 0.001005).abs() < 1e-9);
+    }
+
+    #[test]
+    fn claude_usage_includes_cache_tokens_and_reported_cost() {
+        let json = r#"{"type":"result","result":"Summary","total_cost_usd":0.0123,
+            "usage":{"input_tokens":10,"cache_creation_input_tokens":200,
+            "cache_read_input_tokens":5,"output_tokens":42}}"#;
+        let usage = parse_claude_usage(json.as_bytes()).unwrap();
+
+        assert_eq!(usage.input_tokens, 215);
+        assert_eq!(usage.output_tokens, 42);
+        assert_eq!(usage.cost_usd, 0.0123);
+        assert!(!usage.estimated);
+        assert!(parse_claude_usage(br#"{"text":"no usage"}"#).is_none());
+    }
+
+    #[test]
+    fn codex_usage_sums_completed_turns() {
+        let output = r#"
+{"type":"item.completed","item":{"text":"Summary"}}
+{"type":"turn.completed","usage":{"input_tokens":1000,"cached_input_tokens":200,"output_tokens":100}}
+{"type":"turn.completed","usage":{"input_tokens":500,"output_tokens":50}}
+"#;
+        let usage = parse_codex_usage(output.as_bytes()).unwrap();
+
+        assert_eq!(usage.input_tokens, 1_500);
+        assert_eq!(usage.output_tokens, 150);
+        assert!((usage.cost_usd - (1_500.0 * 1.25 + 150.0 * 10.0) / 1e6).abs() < 1e-12);
+        assert!(parse_codex_usage(b"not json").is_none());
+    }
+
+    #[test]
+    fn usage_adds_up() {
+        let mut total = TokenUsage::priced(AiProvider::Codex, 10, 1);
+        total += TokenUsage::estimate(AiProvider::Codex, "abcd", "");
+        assert_eq!(total.input_tokens, 11);
+        assert!(total.estimated);
+    }
+}
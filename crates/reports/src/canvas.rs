@@ -0,0 +1,400 @@
+//! Page layout on top of `pdf-writer`
+//!
+//! Text uses the standard Helvetica fonts with WinAnsi encoding, so no font
+//! data has to be embedded. Characters outside that encoding print as `?`.
+
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+
+use crate::options::PageSize;
+
+pub(crate) const MARGIN: f32 = 48.0;
+/// Space kept free at the bottom of every page for the footer
+const FOOTER_HEIGHT: f32 = 24.0;
+
+const REGULAR: Name<'static> = Name(b"F1");
+const BOLD: Name<'static> = Name(b"F2");
+
+pub(crate) type Rgb = (f32, f32, f32);
+
+pub(crate) const BLACK: Rgb = (0.1, 0.1, 0.12);
+pub(crate) const MUTED: Rgb = (0.42, 0.45, 0.5);
+pub(crate) const RULE: Rgb = (0.82, 0.84, 0.87);
+pub(crate) const SHADE: Rgb = (0.94, 0.95, 0.96);
+pub(crate) const ACCENT: Rgb = (0.15, 0.39, 0.92);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Font {
+    Regular,
+    Bold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Align {
+    Left,
+    Right,
+}
+
+/// A table column; `width` is a share of the printable width
+pub(crate) struct Column {
+    pub title: &'static str,
+    pub width: f32,
+    pub align: Align,
+}
+
+impl Column {
+    pub fn left(title: &'static str, width: f32) -> Self {
+        Self { title, width, align: Align::Left }
+    }
+
+    pub fn right(title: &'static str, width: f32) -> Self {
+        Self { title, width, align: Align::Right }
+    }
+}
+
+/// Pages being laid out top to bottom, with a cursor for the next element
+pub(crate) struct Canvas {
+    width: f32,
+    height: f32,
+    pages: Vec<Content>,
+    /// Baseline of the next element, measured from the bottom of the page
+    y: f32,
+}
+
+impl Canvas {
+    pub fn new(size: PageSize) -> Self {
+        let (width, height) = size.dimensions();
+        Self {
+            width,
+            height,
+            pages: vec![Content::new()],
+            y: height - MARGIN,
+        }
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn left(&self) -> f32 {
+        MARGIN
+    }
+
+    pub fn right(&self) -> f32 {
+        self.width - MARGIN
+    }
+
+    pub fn content_width(&self) -> f32 {
+        self.width - 2.0 * MARGIN
+    }
+
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+
+    /// Move the cursor down by `amount`
+    pub fn advance(&mut self, amount: f32) {
+        self.y -= amount;
+    }
+
+    pub fn new_page(&mut self) {
+        self.pages.push(Content::new());
+        self.y = self.height - MARGIN;
+    }
+
+    /// Start a new page unless `height` still fits above the footer
+    pub fn ensure_space(&mut self, height: f32) {
+        if self.y - height < MARGIN + FOOTER_HEIGHT {
+            self.new_page();
+        }
+    }
+
+    fn content(&mut self) -> &mut Content {
+        self.pages.last_mut().expect("canvas always has a page")
+    }
+
+    pub fn text(&mut self, x: f32, y: f32, font: Font, size: f32, color: Rgb, text: &str) {
+        write_text(self.content(), x, y, font, size, color, text);
+    }
+
+    pub fn text_right(&mut self, right: f32, y: f32, font: Font, size: f32, color: Rgb, text: &str) {
+        let x = right - text_width(text, font, size);
+        self.text(x, y, font, size, color, text);
+    }
+
+    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Rgb) {
+        let content = self.content();
+        content.set_fill_rgb(color.0, color.1, color.2);
+        content.rect(x, y, width, height);
+        content.fill_nonzero();
+    }
+
+    pub fn stroke_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Rgb) {
+        let content = self.content();
+        content.set_stroke_rgb(color.0, color.1, color.2);
+        content.set_line_width(0.5);
+        content.rect(x, y, width, height);
+        content.stroke();
+    }
+
+    /// Stroke a line through `points`, which must not be empty
+    pub fn polyline(&mut self, points: &[(f32, f32)], width: f32, color: Rgb) {
+        let content = self.content();
+        content.set_stroke_rgb(color.0, color.1, color.2);
+        content.set_line_width(width);
+        content.move_to(points[0].0, points[0].1);
+        for &(x, y) in &points[1..] {
+            content.line_to(x, y);
+        }
+        content.stroke();
+    }
+
+    pub fn rule(&mut self, y: f32) {
+        let (left, right) = (self.left(), self.right());
+        self.polyline(&[(left, y), (right, y)], 0.5, RULE);
+    }
+
+    /// Section title with a rule underneath
+    pub fn heading(&mut self, title: &str) {
+        // Keep the heading with at least the first few lines of its section
+        self.ensure_space(80.0);
+        self.advance(14.0);
+        let (x, y) = (self.left(), self.y);
+        self.text(x, y, Font::Bold, 13.0, BLACK, title);
+        self.rule(y - 5.0);
+        self.advance(22.0);
+    }
+
+    /// Word-wrapped text across the printable width
+    pub fn paragraph(&mut self, text: &str, size: f32, color: Rgb) {
+        let lines = wrap(text, Font::Regular, size, self.content_width());
+        for line in lines {
+            self.ensure_space(size * 1.4);
+            let (x, y) = (self.left(), self.y);
+            self.text(x, y, Font::Regular, size, color, &line);
+            self.advance(size * 1.4);
+        }
+    }
+
+    /// A table with a shaded header row that repeats after page breaks
+    pub fn table(&mut self, columns: &[Column], rows: &[Vec<String>]) {
+        const SIZE: f32 = 9.0;
+        const ROW: f32 = 16.0;
+        const PAD: f32 = 4.0;
+
+        let total: f32 = columns.iter().map(|c| c.width).sum();
+        let mut edges = Vec::with_capacity(columns.len());
+        let mut x = self.left();
+        for column in columns {
+            let width = column.width / total * self.content_width();
+            edges.push((x, width));
+            x += width;
+        }
+
+        let draw_row = |canvas: &mut Canvas, cells: &[&str], font: Font, color: Rgb| {
+            let y = canvas.y;
+            for ((cell, column), &(x, width)) in cells.iter().zip(columns).zip(&edges) {
+                let cell = truncate(cell, font, SIZE, width - 2.0 * PAD);
+                match column.align {
+                    Align::Left => canvas.text(x + PAD, y, font, SIZE, color, &cell),
+                    Align::Right => canvas.text_right(x + width - PAD, y, font, SIZE, color, &cell),
+                }
+            }
+            canvas.advance(ROW);
+        };
+        let header: Vec<&str> = columns.iter().map(|c| c.title).collect();
+        let draw_header = |canvas: &mut Canvas| {
+            let (x, y, width) = (canvas.left(), canvas.y, canvas.content_width());
+            canvas.fill_rect(x, y - 5.0, width, ROW, SHADE);
+            draw_row(canvas, &header, Font::Bold, BLACK);
+        };
+
+        self.ensure_space(ROW * 2.0);
+        draw_header(self);
+        for row in rows {
+            if self.y - ROW < MARGIN + FOOTER_HEIGHT {
+                self.new_page();
+                draw_header(self);
+            }
+            let cells: Vec<&str> = row.iter().map(String::as_str).collect();
+            draw_row(self, &cells, Font::Regular, BLACK);
+            let y = self.y + ROW - 5.0;
+            self.rule(y);
+        }
+        self.advance(6.0);
+    }
+
+    /// Add footers and write out the document
+    pub fn finish(mut self, title: &str, footer: &str) -> Vec<u8> {
+        let count = self.pages.len();
+        let (left, right) = (self.left(), self.right());
+        let y = MARGIN - 16.0;
+        for (index, content) in self.pages.iter_mut().enumerate() {
+            write_text(content, left, y, Font::Regular, 8.0, MUTED, footer);
+            let label = format!("Page {} of {}", index + 1, count);
+            let x = right - text_width(&label, Font::Regular, 8.0);
+            write_text(content, x, y, Font::Regular, 8.0, MUTED, &label);
+        }
+
+        let catalog_id = Ref::new(1);
+        let page_tree_id = Ref::new(2);
+        let regular_id = Ref::new(3);
+        let bold_id = Ref::new(4);
+        let info_id = Ref::new(5);
+        let page_ids: Vec<Ref> = (0..count).map(|i| Ref::new(6 + 2 * i as i32)).collect();
+
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(page_tree_id);
+        pdf.pages(page_tree_id)
+            .kids(page_ids.iter().copied())
+            .count(count as i32);
+        pdf.document_info(info_id)
+            .title(TextStr(title))
+            .producer(TextStr("PepTrack"));
+        pdf.type1_font(regular_id)
+            .base_font(Name(b"Helvetica"))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+        pdf.type1_font(bold_id)
+            .base_font(Name(b"Helvetica-Bold"))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+        for (content, page_id) in self.pages.into_iter().zip(page_ids) {
+            let content_id = Ref::new(page_id.get() + 1);
+            let mut page = pdf.page(page_id);
+            page.media_box(Rect::new(0.0, 0.0, self.width, self.height));
+            page.parent(page_tree_id);
+            page.contents(content_id);
+            let mut resources = page.resources();
+            resources
+                .fonts()
+                .pair(REGULAR, regular_id)
+                .pair(BOLD, bold_id);
+            resources.finish();
+            page.finish();
+            pdf.stream(content_id, &content.finish());
+        }
+
+        pdf.finish()
+    }
+}
+
+impl Font {
+    fn name(self) -> Name<'static> {
+        match self {
+            Font::Regular => REGULAR,
+            Font::Bold => BOLD,
+        }
+    }
+}
+
+fn write_text(content: &mut Content, x: f32, y: f32, font: Font, size: f32, color: Rgb, text: &str) {
+    content.set_fill_rgb(color.0, color.1, color.2);
+    content.begin_text();
+    content.set_font(font.name(), size);
+    content.next_line(x, y);
+    content.show(Str(&encode(text)));
+    content.end_text();
+}
+
+/// Helvetica advance widths for ASCII 32..=126, in 1/1000 em
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, // space - /
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, // 0 - ?
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, // @ - O
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, // P - _
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, // ` - o
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584, // p - ~
+];
+
+/// Approximate rendered width of `text` in points
+pub(crate) fn text_width(text: &str, font: Font, size: f32) -> f32 {
+    let units: u32 = text
+        .chars()
+        .map(|c| match c as u32 {
+            code @ 32..=126 => HELVETICA_WIDTHS[(code - 32) as usize] as u32,
+            _ => 556,
+        })
+        .sum();
+    // Bold glyphs are about 6% wider on average
+    let scale = if font == Font::Bold { 1.06 } else { 1.0 };
+    units as f32 / 1000.0 * size * scale
+}
+
+/// Shorten `text` with an ellipsis so it fits in `max_width`
+pub(crate) fn truncate(text: &str, font: Font, size: f32, max_width: f32) -> String {
+    if text_width(text, font, size) <= max_width {
+        return text.to_string();
+    }
+    let mut shortened: String = text.to_string();
+    while !shortened.is_empty() && text_width(&format!("{shortened}…"), font, size) > max_width {
+        shortened.pop();
+    }
+    format!("{}…", shortened.trim_end())
+}
+
+/// Break `text` into lines no wider than `max_width`
+pub(crate) fn wrap(text: &str, font: Font, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{line} {word}")
+            };
+            if !line.is_empty() && text_width(&candidate, font, size) > max_width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(truncate(&line, font, size, max_width));
+    }
+    lines
+}
+
+/// Encode text as WinAnsi (Windows-1252) bytes
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            '€' => 0x80,
+            '‚' => 0x82,
+            '„' => 0x84,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '™' => 0x99,
+            '\u{20}'..='\u{7e}' | '\u{a0}'..='\u{ff}' => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_maps_to_winansi() {
+        assert_eq!(encode("µg – 5°"), vec![0xB5, b'g', b' ', 0x96, b' ', b'5', 0xB0]);
+        assert_eq!(encode("✓"), vec![b'?']);
+    }
+
+    #[test]
+    fn wrap_and_truncate_respect_width() {
+        let lines = wrap("one two three four five six", Font::Regular, 10.0, 60.0);
+        assert!(lines.len() > 1);
+        assert!(lines
+            .iter()
+            .all(|line| text_width(line, Font::Regular, 10.0) <= 60.0));
+
+        let short = truncate("A rather long protocol name", Font::Regular, 10.0, 50.0);
+        assert!(short.ends_with('…'));
+        assert!(text_width(&short, Font::Regular, 10.0) <= 50.0);
+    }
+}
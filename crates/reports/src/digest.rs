@@ -0,0 +1,203 @@
+//! Plain-text email digests
+//!
+//! A [`Digest`] sums up a period in a few lines: alerts raised since the
+//! last digest, dose adherence, and protocols that will run out of stock
+//! soon. It is meant to be read in a mail client, so it is plain text with
+//! no tables.
+
+use std::fmt::Write as _;
+
+use peptrack_core::models::{Alert, AlertSeverity};
+use time::Date;
+
+use crate::summary::{AdherenceRow, ReportSummary};
+
+/// Protocols running out within this many days are listed
+pub const DIGEST_FORECAST_DAYS: f32 = 14.0;
+
+/// Inventory outlook for one protocol
+#[derive(Debug, Clone)]
+pub struct DigestForecast {
+    pub protocol_name: String,
+    pub days_remaining: Option<f32>,
+    /// Last day to order so new stock arrives in time
+    pub reorder_by: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Digest {
+    pub start: Date,
+    pub end: Date,
+    pub alerts: Vec<Alert>,
+    pub total_doses: usize,
+    pub adherence: Vec<AdherenceRow>,
+    pub overall_adherence: Option<f32>,
+    /// Only protocols running out within [`DIGEST_FORECAST_DAYS`], soonest first
+    pub forecasts: Vec<DigestForecast>,
+}
+
+impl Digest {
+    /// Build a digest of `summary`'s period with the given alerts and forecasts
+    pub fn build(summary: &ReportSummary, alerts: &[Alert], forecasts: &[DigestForecast]) -> Self {
+        let mut alerts = alerts.to_vec();
+        alerts.sort_by(|a, b| b.severity.cmp(&a.severity).then(b.created_at.cmp(&a.created_at)));
+
+        let mut forecasts: Vec<DigestForecast> = forecasts
+            .iter()
+            .filter(|f| f.days_remaining.is_some_and(|days| days <= DIGEST_FORECAST_DAYS))
+            .cloned()
+            .collect();
+        forecasts.sort_by(|a, b| a.days_remaining.partial_cmp(&b.days_remaining).unwrap_or(std::cmp::Ordering::Equal));
+
+        Self {
+            start: summary.start,
+            end: summary.end,
+            alerts,
+            total_doses: summary.total_doses,
+            adherence: summary.adherence.clone(),
+            overall_adherence: summary.overall_adherence,
+            forecasts,
+        }
+    }
+
+    pub fn subject(&self) -> String {
+        let mut subject = format!("PepTrack digest {} – {}", self.start, self.end);
+        let critical = self
+            .alerts
+            .iter()
+            .filter(|a| a.severity == AlertSeverity::Critical)
+            .count();
+        if critical > 0 {
+            let _ = write!(subject, " ({} critical alert{})", critical, plural(critical));
+        }
+        subject
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("PepTrack summary for {} to {}\n", self.start, self.end);
+
+        text.push_str("\nALERTS\n");
+        if self.alerts.is_empty() {
+            text.push_str("No new alerts.\n");
+        }
+        for alert in &self.alerts {
+            let _ = writeln!(text, "- [{}] {}: {}", severity_label(&alert.severity), alert.title, alert.message);
+        }
+
+        text.push_str("\nADHERENCE\n");
+        let _ = writeln!(text, "{} dose{} logged.", self.total_doses, plural(self.total_doses));
+        if let Some(overall) = self.overall_adherence {
+            let _ = writeln!(text, "Overall: {:.0}% of scheduled doses.", overall);
+        }
+        for row in &self.adherence {
+            let _ = writeln!(
+                text,
+                "- {}: {} of {} ({:.0}%)",
+                row.protocol_name, row.logged, row.expected, row.percent
+            );
+        }
+
+        text.push_str("\nINVENTORY\n");
+        if self.forecasts.is_empty() {
+            let _ = writeln!(text, "Nothing runs out in the next {:.0} days.", DIGEST_FORECAST_DAYS);
+        }
+        for forecast in &self.forecasts {
+            let days = forecast.days_remaining.unwrap_or_default();
+            let _ = write!(text, "- {}: about {:.0} day{} left", forecast.protocol_name, days, plural(days.round() as usize));
+            if let Some(reorder_by) = &forecast.reorder_by {
+                let _ = write!(text, ", reorder by {}", reorder_by);
+            }
+            text.push('\n');
+        }
+
+        text.push_str("\nSent by PepTrack. Change or turn off digests in Settings.\n");
+        text
+    }
+}
+
+fn severity_label(severity: &AlertSeverity) -> &'static str {
+    match severity {
+        AlertSeverity::Info => "info",
+        AlertSeverity::Warning => "warning",
+        AlertSeverity::Critical => "CRITICAL",
+    }
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use peptrack_core::models::AlertType;
+    use time::macros::{date, datetime};
+
+    use super::*;
+
+    fn summary() -> ReportSummary {
+        ReportSummary {
+            start: date!(2025 - 03 - 01),
+            end: date!(2025 - 03 - 07),
+            generated_at: datetime!(2025-03-08 07:00 UTC),
+            protocols: Vec::new(),
+            adherence: vec![AdherenceRow {
+                protocol_name: "Healing".into(),
+                expected: 7,
+                logged: 6,
+                percent: 85.7,
+            }],
+            overall_adherence: Some(85.7),
+            total_doses: 6,
+            doses_by_day: BTreeMap::new(),
+            metrics: Vec::new(),
+            side_effects: Vec::new(),
+            inventory: Vec::new(),
+        }
+    }
+
+    fn forecast(name: &str, days: Option<f32>) -> DigestForecast {
+        DigestForecast {
+            protocol_name: name.into(),
+            days_remaining: days,
+            reorder_by: days.map(|_| "2025-03-10".into()),
+        }
+    }
+
+    #[test]
+    fn digest_orders_alerts_and_keeps_soon_forecasts() {
+        let alerts = vec![
+            Alert::new(AlertType::PriceDecrease, AlertSeverity::Info, "Price Drop", "Cheaper"),
+            Alert::new(AlertType::Expired, AlertSeverity::Critical, "Vial expired", "Discard it"),
+        ];
+        let forecasts = vec![
+            forecast("Later", Some(40.0)),
+            forecast("Recovery", Some(9.0)),
+            forecast("Healing", Some(3.0)),
+            forecast("Unused", None),
+        ];
+
+        let digest = Digest::build(&summary(), &alerts, &forecasts);
+        assert_eq!(digest.alerts[0].title, "Vial expired");
+        let names: Vec<_> = digest.forecasts.iter().map(|f| f.protocol_name.as_str()).collect();
+        assert_eq!(names, ["Healing", "Recovery"]);
+        assert_eq!(digest.subject(), "PepTrack digest 2025-03-01 – 2025-03-07 (1 critical alert)");
+
+        let text = digest.to_text();
+        assert!(text.contains("- [CRITICAL] Vial expired: Discard it"));
+        assert!(text.contains("- Healing: 6 of 7 (86%)"));
+        assert!(text.contains("- Healing: about 3 days left, reorder by 2025-03-10"));
+    }
+
+    #[test]
+    fn empty_digest_says_so() {
+        let text = Digest::build(&summary(), &[], &[]).to_text();
+        assert!(text.contains("No new alerts."));
+        assert!(text.contains("Nothing runs out in the next 14 days."));
+    }
+}
@@ -0,0 +1,302 @@
+//! HL7 FHIR R4 export for sharing with clinicians
+//!
+//! Dose logs become `MedicationAdministration` resources and body metrics
+//! and lab results become `Observation` resources, all referring to a single
+//! `Patient` in a `collection` bundle that EHRs and FHIR viewers can import.
+
+use std::collections::HashMap;
+
+use peptrack_core::{BodyMetric, DoseLog, LabResult, PeptideProtocol};
+use serde_json::{json, Value};
+use time::format_description::well_known::Rfc3339;
+use time::{Date, OffsetDateTime};
+use uuid::Uuid;
+
+const LOINC: &str = "http://loinc.org";
+const UCUM: &str = "http://unitsofmeasure.org";
+const OBSERVATION_CATEGORY: &str = "http://terminology.hl7.org/CodeSystem/observation-category";
+/// Identifier system for the PepTrack record a resource was made from
+const RECORD_SYSTEM: &str = "urn:peptrack:record";
+
+/// Everything a bundle is built from; `start` and `end` are inclusive and
+/// open-ended when `None`
+pub struct FhirInput<'a> {
+    pub patient_name: &'a str,
+    pub start: Option<Date>,
+    pub end: Option<Date>,
+    pub protocols: &'a [PeptideProtocol],
+    pub doses: &'a [DoseLog],
+    pub body_metrics: &'a [BodyMetric],
+    pub lab_results: &'a [LabResult],
+}
+
+/// A built bundle and the number of resources of each kind in it
+#[derive(Debug, Clone)]
+pub struct FhirBundle {
+    pub bundle: Value,
+    pub medication_administrations: usize,
+    pub observations: usize,
+}
+
+/// A body metric field exported as a vital sign
+struct VitalSign {
+    loinc: Option<&'static str>,
+    display: &'static str,
+    unit: &'static str,
+    value: fn(&BodyMetric) -> Option<f32>,
+}
+
+const VITAL_SIGNS: &[VitalSign] = &[
+    VitalSign {
+        loinc: Some("29463-7"),
+        display: "Body weight",
+        unit: "kg",
+        value: |m| m.weight_kg,
+    },
+    VitalSign {
+        loinc: Some("41982-0"),
+        display: "Percentage of body fat",
+        unit: "%",
+        value: |m| m.body_fat_percentage,
+    },
+    VitalSign {
+        loinc: None,
+        display: "Muscle mass",
+        unit: "kg",
+        value: |m| m.muscle_mass_kg,
+    },
+    VitalSign {
+        loinc: Some("8280-0"),
+        display: "Waist circumference",
+        unit: "cm",
+        value: |m| m.waist_cm,
+    },
+    VitalSign {
+        loinc: Some("40443-4"),
+        display: "Resting heart rate",
+        unit: "/min",
+        value: |m| m.resting_heart_rate_bpm,
+    },
+    VitalSign {
+        loinc: Some("93832-4"),
+        display: "Sleep duration",
+        unit: "h",
+        value: |m| m.sleep_hours,
+    },
+];
+
+/// `value` as a JSON number without the noise of widening an `f32`
+fn decimal(value: f32) -> Value {
+    value
+        .to_string()
+        .parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+fn date_time(value: OffsetDateTime) -> String {
+    value.format(&Rfc3339).unwrap_or_else(|_| value.to_string())
+}
+
+fn record_identifier(kind: &str, id: &str) -> Value {
+    json!([{ "system": format!("{}:{}", RECORD_SYSTEM, kind), "value": id }])
+}
+
+fn category(code: &str, display: &str) -> Value {
+    json!([{ "coding": [{ "system": OBSERVATION_CATEGORY, "code": code, "display": display }] }])
+}
+
+fn entry(resource: Value) -> Value {
+    json!({ "fullUrl": format!("urn:uuid:{}", Uuid::new_v4()), "resource": resource })
+}
+
+/// Build a FHIR R4 `collection` bundle from `input` as of `generated_at`
+pub fn build_fhir_bundle(input: &FhirInput<'_>, generated_at: OffsetDateTime) -> FhirBundle {
+    let in_range = |date: Date| {
+        input.start.is_none_or(|start| date >= start) && input.end.is_none_or(|end| date <= end)
+    };
+    let protocols: HashMap<&str, &PeptideProtocol> =
+        input.protocols.iter().map(|p| (p.id.as_str(), p)).collect();
+
+    let patient = entry(json!({
+        "resourceType": "Patient",
+        "active": true,
+        "name": [{ "text": input.patient_name.trim() }],
+    }));
+    let subject = json!({
+        "reference": patient["fullUrl"],
+        "display": input.patient_name.trim(),
+    });
+
+    let mut entries = vec![patient];
+
+    let mut medication_administrations = 0;
+    for dose in input.doses.iter().filter(|d| in_range(d.logged_at.date())) {
+        let protocol = protocols.get(dose.protocol_id.as_str());
+        let medication = protocol.map_or("Unknown peptide", |p| p.peptide_name.as_str());
+        let mut resource = json!({
+            "resourceType": "MedicationAdministration",
+            "identifier": record_identifier("dose-log", &dose.id),
+            "status": "completed",
+            "medicationCodeableConcept": { "text": medication },
+            "subject": subject,
+            "effectiveDateTime": date_time(dose.logged_at),
+            "dosage": {
+                "dose": { "value": decimal(dose.amount_mg), "unit": "mg", "system": UCUM, "code": "mg" },
+            },
+        });
+        if !dose.site.trim().is_empty() {
+            resource["dosage"]["site"] = json!({ "text": dose.site });
+        }
+        let notes: Vec<Value> = protocol
+            .map(|p| format!("Protocol: {}", p.name))
+            .into_iter()
+            .chain(dose.notes.clone())
+            .map(|text| json!({ "text": text }))
+            .collect();
+        if !notes.is_empty() {
+            resource["note"] = Value::Array(notes);
+        }
+        entries.push(entry(resource));
+        medication_administrations += 1;
+    }
+
+    let mut observations = 0;
+    for metric in input.body_metrics.iter().filter(|m| in_range(m.date.date())) {
+        for sign in VITAL_SIGNS {
+            let Some(value) = (sign.value)(metric) else {
+                continue;
+            };
+            let code = match sign.loinc {
+                Some(loinc) => json!({
+                    "coding": [{ "system": LOINC, "code": loinc, "display": sign.display }],
+                    "text": sign.display,
+                }),
+                None => json!({ "text": sign.display }),
+            };
+            let mut resource = json!({
+                "resourceType": "Observation",
+                "identifier": record_identifier("body-metric", &metric.id),
+                "status": "final",
+                "category": category("vital-signs", "Vital Signs"),
+                "code": code,
+                "subject": subject,
+                "effectiveDateTime": date_time(metric.date),
+                "valueQuantity": { "value": decimal(value), "unit": sign.unit, "system": UCUM, "code": sign.unit },
+            });
+            if let Some(notes) = &metric.notes {
+                resource["note"] = json!([{ "text": notes }]);
+            }
+            entries.push(entry(resource));
+            observations += 1;
+        }
+    }
+
+    for lab in input.lab_results.iter().filter(|l| in_range(l.collected_at.date())) {
+        let mut resource = json!({
+            "resourceType": "Observation",
+            "identifier": record_identifier("lab-result", &lab.id),
+            "status": "final",
+            "category": category("laboratory", "Laboratory"),
+            "code": { "text": lab.marker },
+            "subject": subject,
+            "effectiveDateTime": date_time(lab.collected_at),
+            "valueQuantity": { "value": decimal(lab.value), "unit": lab.unit },
+        });
+        if lab.reference_low.is_some() || lab.reference_high.is_some() {
+            let mut range = serde_json::Map::new();
+            if let Some(low) = lab.reference_low {
+                range.insert("low".into(), json!({ "value": decimal(low), "unit": lab.unit }));
+            }
+            if let Some(high) = lab.reference_high {
+                range.insert("high".into(), json!({ "value": decimal(high), "unit": lab.unit }));
+            }
+            resource["referenceRange"] = json!([range]);
+        }
+        if let Some(lab_name) = &lab.lab_name {
+            resource["performer"] = json!([{ "display": lab_name }]);
+        }
+        if let Some(notes) = &lab.notes {
+            resource["note"] = json!([{ "text": notes }]);
+        }
+        entries.push(entry(resource));
+        observations += 1;
+    }
+
+    FhirBundle {
+        bundle: json!({
+            "resourceType": "Bundle",
+            "type": "collection",
+            "timestamp": date_time(generated_at),
+            "entry": entries,
+        }),
+        medication_administrations,
+        observations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, datetime};
+
+    use super::*;
+
+    #[test]
+    fn doses_metrics_and_labs_become_resources_for_one_patient() {
+        let protocol = PeptideProtocol::new("Morning", "BPC-157");
+        let mut dose = DoseLog::new(protocol.id.as_str(), "Abdomen", 0.25);
+        dose.logged_at = datetime!(2025-03-02 08:00 UTC);
+        let mut old_dose = DoseLog::new(protocol.id.as_str(), "Thigh", 0.25);
+        old_dose.logged_at = datetime!(2025-01-02 08:00 UTC);
+
+        let mut metric = BodyMetric::new(datetime!(2025-03-03 07:00 UTC));
+        metric.weight_kg = Some(81.2);
+        metric.muscle_mass_kg = Some(38.0);
+
+        let mut lab = LabResult::new("IGF-1", 180.0, "ng/mL", datetime!(2025-03-04 09:30 UTC));
+        lab.reference_low = Some(115.0);
+
+        let input = FhirInput {
+            patient_name: " Alex Doe ",
+            start: Some(date!(2025 - 03 - 01)),
+            end: None,
+            protocols: std::slice::from_ref(&protocol),
+            doses: &[dose.clone(), old_dose],
+            body_metrics: &[metric],
+            lab_results: &[lab],
+        };
+        let exported = build_fhir_bundle(&input, datetime!(2025-03-05 12:00 UTC));
+        assert_eq!(exported.medication_administrations, 1);
+        assert_eq!(exported.observations, 3);
+
+        let bundle = &exported.bundle;
+        assert_eq!(bundle["type"], "collection");
+        assert_eq!(bundle["timestamp"], "2025-03-05T12:00:00Z");
+        let entries = bundle["entry"].as_array().unwrap();
+        assert_eq!(entries.len(), 5);
+
+        let patient = &entries[0];
+        assert_eq!(patient["resource"]["name"][0]["text"], "Alex Doe");
+        for entry in &entries[1..] {
+            assert_eq!(entry["resource"]["subject"]["reference"], patient["fullUrl"]);
+        }
+
+        let administration = &entries[1]["resource"];
+        assert_eq!(administration["medicationCodeableConcept"]["text"], "BPC-157");
+        assert_eq!(administration["effectiveDateTime"], "2025-03-02T08:00:00Z");
+        assert_eq!(administration["dosage"]["dose"]["value"], 0.25);
+        assert_eq!(administration["identifier"][0]["value"], dose.id.as_str());
+
+        let weight = &entries[2]["resource"];
+        assert_eq!(weight["code"]["coding"][0]["code"], "29463-7");
+        assert_eq!(weight["valueQuantity"]["value"], 81.2);
+        assert!(entries[3]["resource"]["code"].get("coding").is_none());
+
+        let igf = &entries[4]["resource"];
+        assert_eq!(igf["category"][0]["coding"][0]["code"], "laboratory");
+        assert_eq!(igf["referenceRange"][0]["low"]["value"], 115.0);
+        assert!(igf["referenceRange"][0].get("high").is_none());
+    }
+}
@@ -0,0 +1,54 @@
+//! PepTrack Reports - Printable PDF summaries
+//!
+//! Builds a report for a date range from stored records and renders it as a
+//! PDF that can be printed or shared with a practitioner.
+//!
+//! # Architecture
+//!
+//! - [`ReportSummary`] computes the figures shown in the report (adherence,
+//!   dose calendar, metric series) from a [`ReportInput`]
+//! - [`render_pdf`] lays the summary out page by page, including the
+//!   sections picked by [`ReportOptions`]
+//! - [`Digest`] condenses a summary, recent alerts and stock forecasts
+//!   into a plain-text email
+//! - [`build_fhir_bundle`] converts dose logs, body metrics and lab results
+//!   into an HL7 FHIR R4 bundle for a clinician's records system
+//!
+//! # Examples
+//!
+//! ```
+//! use peptrack_reports::{render_pdf, ReportInput, ReportOptions, ReportSummary, ReportTemplate};
+//! use time::macros::date;
+//! use time::OffsetDateTime;
+//!
+//! let input = ReportInput {
+//!     start: date!(2024 - 01 - 01),
+//!     end: date!(2024 - 01 - 31),
+//!     protocols: &[],
+//!     doses: &[],
+//!     schedules: &[],
+//!     body_metrics: &[],
+//!     side_effects: &[],
+//!     inventory: &[],
+//! };
+//! let summary = ReportSummary::build(&input, OffsetDateTime::now_utc());
+//! let options = ReportOptions {
+//!     template: ReportTemplate::Practitioner,
+//!     ..Default::default()
+//! };
+//! let report = render_pdf(&summary, &options);
+//! assert!(report.pdf.starts_with(b"%PDF"));
+//! ```
+
+mod canvas;
+pub mod digest;
+pub mod fhir;
+pub mod options;
+pub mod pdf;
+pub mod summary;
+
+pub use digest::{Digest, DigestForecast};
+pub use fhir::{build_fhir_bundle, FhirBundle, FhirInput};
+pub use options::{PageSize, ReportOptions, ReportSection, ReportTemplate};
+pub use pdf::{render_pdf, RenderedReport};
+pub use summary::{ReportInput, ReportSummary, ScheduledDoses};
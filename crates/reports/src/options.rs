@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+/// A part of the report that can be switched on or off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportSection {
+    /// Protocols with doses or schedules in the range
+    Protocols,
+    /// Month grids showing which days had doses
+    DoseCalendar,
+    /// Logged doses compared to enabled schedules
+    Adherence,
+    /// Weight, body fat, waist and resting heart rate charts
+    BodyMetrics,
+    SideEffects,
+    /// Vials that aren't empty, with remaining amount and expiry
+    Inventory,
+}
+
+impl ReportSection {
+    pub const ALL: [ReportSection; 6] = [
+        ReportSection::Protocols,
+        ReportSection::DoseCalendar,
+        ReportSection::Adherence,
+        ReportSection::BodyMetrics,
+        ReportSection::SideEffects,
+        ReportSection::Inventory,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            ReportSection::Protocols => "Active Protocols",
+            ReportSection::DoseCalendar => "Dose Calendar",
+            ReportSection::Adherence => "Adherence",
+            ReportSection::BodyMetrics => "Body Metrics",
+            ReportSection::SideEffects => "Side Effects",
+            ReportSection::Inventory => "Inventory Status",
+        }
+    }
+}
+
+/// Preset section lists for common uses of the report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportTemplate {
+    /// Every section
+    #[default]
+    Full,
+    /// For a doctor or coach: everything except inventory
+    Practitioner,
+    /// One page overview: protocols and adherence
+    Summary,
+}
+
+impl ReportTemplate {
+    pub fn sections(self) -> Vec<ReportSection> {
+        match self {
+            ReportTemplate::Full => ReportSection::ALL.to_vec(),
+            ReportTemplate::Practitioner => vec![
+                ReportSection::Protocols,
+                ReportSection::Adherence,
+                ReportSection::DoseCalendar,
+                ReportSection::BodyMetrics,
+                ReportSection::SideEffects,
+            ],
+            ReportTemplate::Summary => vec![ReportSection::Protocols, ReportSection::Adherence],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageSize {
+    #[default]
+    A4,
+    Letter,
+}
+
+impl PageSize {
+    /// Width and height in points
+    pub fn dimensions(self) -> (f32, f32) {
+        match self {
+            PageSize::A4 => (595.0, 842.0),
+            PageSize::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+/// How the report is laid out and what it contains
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportOptions {
+    #[serde(default)]
+    pub template: ReportTemplate,
+    /// Overrides the template's sections, in the order given
+    #[serde(default)]
+    pub sections: Option<Vec<ReportSection>>,
+    #[serde(default)]
+    pub page_size: PageSize,
+    /// Defaults to "PepTrack Report"
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Shown under the title, e.g. the patient's name
+    #[serde(default)]
+    pub prepared_for: Option<String>,
+    /// Include protocol notes and side effect descriptions
+    #[serde(default)]
+    pub include_notes: bool,
+}
+
+impl ReportOptions {
+    pub fn sections(&self) -> Vec<ReportSection> {
+        self.sections
+            .clone()
+            .unwrap_or_else(|| self.template.sections())
+    }
+
+    pub fn title(&self) -> &str {
+        self.title
+            .as_deref()
+            .filter(|title| !title.trim().is_empty())
+            .unwrap_or("PepTrack Report")
+    }
+}
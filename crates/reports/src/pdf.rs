@@ -0,0 +1,476 @@
+use time::macros::format_description;
+use time::{Date, Month};
+
+use crate::canvas::{Canvas, Column, Font, ACCENT, BLACK, MUTED, RULE, SHADE};
+use crate::options::{ReportOptions, ReportSection};
+use crate::summary::{MetricSeries, ReportSummary};
+
+/// A rendered report
+#[derive(Debug, Clone)]
+pub struct RenderedReport {
+    pub pdf: Vec<u8>,
+    pub page_count: usize,
+}
+
+/// Lay out `summary` as a PDF using the sections and page size in `options`
+pub fn render_pdf(summary: &ReportSummary, options: &ReportOptions) -> RenderedReport {
+    let mut canvas = Canvas::new(options.page_size);
+    draw_header(&mut canvas, summary, options);
+
+    for section in options.sections() {
+        canvas.heading(section.title());
+        match section {
+            ReportSection::Protocols => draw_protocols(&mut canvas, summary, options),
+            ReportSection::DoseCalendar => draw_calendar(&mut canvas, summary),
+            ReportSection::Adherence => draw_adherence(&mut canvas, summary),
+            ReportSection::BodyMetrics => draw_metrics(&mut canvas, summary),
+            ReportSection::SideEffects => draw_side_effects(&mut canvas, summary, options),
+            ReportSection::Inventory => draw_inventory(&mut canvas, summary),
+        }
+    }
+
+    let footer = format!(
+        "{} · generated {}",
+        options.title(),
+        format_date(summary.generated_at.date())
+    );
+    let page_count = canvas.page_count();
+    RenderedReport {
+        pdf: canvas.finish(options.title(), &footer),
+        page_count,
+    }
+}
+
+fn format_date(date: Date) -> String {
+    date.format(format_description!("[day padding:none] [month repr:short] [year]"))
+        .unwrap_or_else(|_| date.to_string())
+}
+
+fn format_mg(mg: f32) -> String {
+    format!("{:.2}", mg)
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+fn nothing_recorded(canvas: &mut Canvas) {
+    canvas.paragraph("Nothing recorded in this period.", 10.0, MUTED);
+    canvas.advance(6.0);
+}
+
+fn draw_header(canvas: &mut Canvas, summary: &ReportSummary, options: &ReportOptions) {
+    let (left, right) = (canvas.left(), canvas.right());
+    let y = canvas.y() - 12.0;
+    canvas.text(left, y, Font::Bold, 20.0, BLACK, options.title());
+    canvas.advance(32.0);
+
+    if let Some(name) = options.prepared_for.as_deref().filter(|n| !n.trim().is_empty()) {
+        let y = canvas.y();
+        canvas.text(left, y, Font::Regular, 11.0, BLACK, &format!("Prepared for {}", name.trim()));
+        canvas.advance(16.0);
+    }
+    let period = format!(
+        "{} – {}",
+        format_date(summary.start),
+        format_date(summary.end)
+    );
+    let y = canvas.y();
+    canvas.text(left, y, Font::Regular, 11.0, MUTED, &period);
+    canvas.advance(28.0);
+
+    // Key figures in four boxes across the page
+    let adherence = summary
+        .overall_adherence
+        .map(|p| format!("{:.0}%", p))
+        .unwrap_or_else(|| "–".to_string());
+    let figures = [
+        ("Doses logged", summary.total_doses.to_string()),
+        ("Active protocols", summary.protocols.len().to_string()),
+        ("Adherence", adherence),
+        ("Side effects", summary.side_effects.len().to_string()),
+    ];
+    let gap = 10.0;
+    let width = (right - left - gap * 3.0) / 4.0;
+    let top = canvas.y();
+    for (index, (label, value)) in figures.iter().enumerate() {
+        let x = left + index as f32 * (width + gap);
+        canvas.fill_rect(x, top - 38.0, width, 48.0, SHADE);
+        canvas.text(x + 8.0, top - 6.0, Font::Bold, 16.0, ACCENT, value);
+        canvas.text(x + 8.0, top - 28.0, Font::Regular, 8.5, MUTED, label);
+    }
+    canvas.advance(62.0);
+}
+
+fn draw_protocols(canvas: &mut Canvas, summary: &ReportSummary, options: &ReportOptions) {
+    if summary.protocols.is_empty() {
+        return nothing_recorded(canvas);
+    }
+    let rows: Vec<Vec<String>> = summary
+        .protocols
+        .iter()
+        .map(|p| {
+            vec![
+                p.name.clone(),
+                p.peptide_name.clone(),
+                p.dose_count.to_string(),
+                format_mg(p.total_mg),
+                p.last_dose.map(format_date).unwrap_or_else(|| "–".into()),
+                if p.scheduled { "Yes" } else { "No" }.to_string(),
+            ]
+        })
+        .collect();
+    canvas.table(
+        &[
+            Column::left("Protocol", 3.0),
+            Column::left("Peptide", 2.5),
+            Column::right("Doses", 1.0),
+            Column::right("Total mg", 1.3),
+            Column::right("Last dose", 1.8),
+            Column::right("Scheduled", 1.3),
+        ],
+        &rows,
+    );
+
+    if options.include_notes {
+        for protocol in &summary.protocols {
+            if let Some(notes) = protocol.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+                canvas.paragraph(&format!("{}: {}", protocol.name, notes.trim()), 9.0, MUTED);
+            }
+        }
+        canvas.advance(6.0);
+    }
+}
+
+fn draw_adherence(canvas: &mut Canvas, summary: &ReportSummary) {
+    if summary.adherence.is_empty() {
+        canvas.paragraph("No protocols had an enabled dose schedule.", 10.0, MUTED);
+        canvas.advance(6.0);
+        return;
+    }
+    let rows: Vec<Vec<String>> = summary
+        .adherence
+        .iter()
+        .map(|row| {
+            vec![
+                row.protocol_name.clone(),
+                row.expected.to_string(),
+                row.logged.to_string(),
+                format!("{:.0}%", row.percent),
+            ]
+        })
+        .collect();
+    canvas.table(
+        &[
+            Column::left("Protocol", 4.0),
+            Column::right("Scheduled", 1.5),
+            Column::right("Logged", 1.5),
+            Column::right("Adherence", 1.5),
+        ],
+        &rows,
+    );
+    canvas.paragraph(
+        "Scheduled doses are counted from enabled dose schedules up to today.",
+        8.5,
+        MUTED,
+    );
+    canvas.advance(6.0);
+}
+
+fn draw_calendar(canvas: &mut Canvas, summary: &ReportSummary) {
+    const GAP: f32 = 20.0;
+    const CELL_HEIGHT: f32 = 16.0;
+    const HEIGHT: f32 = 30.0 + 6.0 * CELL_HEIGHT;
+
+    let width = (canvas.content_width() - GAP) / 2.0;
+    let mut months = Vec::new();
+    let (mut year, mut month) = (summary.start.year(), summary.start.month());
+    while (year, month as u8) <= (summary.end.year(), summary.end.month() as u8) {
+        months.push((year, month));
+        if month == Month::December {
+            year += 1;
+        }
+        month = month.next();
+    }
+
+    for pair in months.chunks(2) {
+        canvas.ensure_space(HEIGHT);
+        let top = canvas.y();
+        for (index, &(year, month)) in pair.iter().enumerate() {
+            let x = canvas.left() + index as f32 * (width + GAP);
+            draw_month(canvas, summary, x, top, width, CELL_HEIGHT, year, month);
+        }
+        canvas.advance(HEIGHT + 12.0);
+    }
+
+    let legend_y = canvas.y() + 4.0;
+    let x = canvas.left();
+    canvas.fill_rect(x, legend_y - 2.0, 8.0, 8.0, ACCENT);
+    canvas.text(x + 12.0, legend_y, Font::Regular, 8.5, MUTED, "Dose logged (number = doses that day)");
+    canvas.advance(16.0);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_month(
+    canvas: &mut Canvas,
+    summary: &ReportSummary,
+    x: f32,
+    top: f32,
+    width: f32,
+    cell_height: f32,
+    year: i32,
+    month: Month,
+) {
+    let cell_width = width / 7.0;
+    canvas.text(x, top, Font::Bold, 10.0, BLACK, &format!("{} {}", month, year));
+    for (index, letter) in ["S", "M", "T", "W", "T", "F", "S"].iter().enumerate() {
+        let cx = x + index as f32 * cell_width + cell_width / 2.0 - 3.0;
+        canvas.text(cx, top - 14.0, Font::Regular, 7.5, MUTED, letter);
+    }
+
+    let Ok(first) = Date::from_calendar_date(year, month, 1) else {
+        return;
+    };
+    let offset = first.weekday().number_days_from_sunday() as usize;
+    for day in 1..=month.length(year) {
+        let Ok(date) = Date::from_calendar_date(year, month, day) else {
+            continue;
+        };
+        let slot = offset + day as usize - 1;
+        let cx = x + (slot % 7) as f32 * cell_width;
+        let cy = top - 22.0 - (slot / 7 + 1) as f32 * cell_height;
+        let in_range = date >= summary.start && date <= summary.end;
+        let count = summary.doses_by_day.get(&date).copied().unwrap_or(0);
+
+        if count > 0 {
+            canvas.fill_rect(cx + 1.0, cy + 1.0, cell_width - 2.0, cell_height - 2.0, ACCENT);
+            canvas.text(cx + 3.0, cy + 4.5, Font::Bold, 7.5, (1.0, 1.0, 1.0), &day.to_string());
+            if count > 1 {
+                let right = cx + cell_width - 3.0;
+                canvas.text_right(right, cy + 4.5, Font::Regular, 6.5, (1.0, 1.0, 1.0), &count.to_string());
+            }
+        } else {
+            canvas.stroke_rect(cx + 1.0, cy + 1.0, cell_width - 2.0, cell_height - 2.0, RULE);
+            let color = if in_range { BLACK } else { RULE };
+            canvas.text(cx + 3.0, cy + 4.5, Font::Regular, 7.5, color, &day.to_string());
+        }
+    }
+}
+
+fn draw_metrics(canvas: &mut Canvas, summary: &ReportSummary) {
+    if summary.metrics.is_empty() {
+        return nothing_recorded(canvas);
+    }
+    for series in &summary.metrics {
+        draw_chart(canvas, summary, series);
+    }
+}
+
+/// Line chart of one metric over the report period
+fn draw_chart(canvas: &mut Canvas, summary: &ReportSummary, series: &MetricSeries) {
+    const HEIGHT: f32 = 90.0;
+    const AXIS_WIDTH: f32 = 40.0;
+
+    canvas.ensure_space(HEIGHT + 40.0);
+    let (left, right) = (canvas.left(), canvas.right());
+    let top = canvas.y();
+
+    let first = series.points.first().map(|p| p.1).unwrap_or_default();
+    let last = series.points.last().map(|p| p.1).unwrap_or_default();
+    let title = format!("{} ({})", series.label, series.unit);
+    canvas.text(left, top, Font::Bold, 10.0, BLACK, &title);
+    let change = format!(
+        "{} to {} {}",
+        format_mg(first),
+        format_mg(last),
+        series.unit
+    );
+    canvas.text_right(right, top, Font::Regular, 9.0, MUTED, &change);
+
+    let mut min = series.points.iter().map(|p| p.1).fold(f32::INFINITY, f32::min);
+    let mut max = series.points.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+    if (max - min).abs() < f32::EPSILON {
+        min -= 1.0;
+        max += 1.0;
+    }
+
+    let plot_left = left + AXIS_WIDTH;
+    let plot_bottom = top - 12.0 - HEIGHT;
+    let plot_width = right - plot_left;
+    canvas.stroke_rect(plot_left, plot_bottom, plot_width, HEIGHT, RULE);
+    canvas.text_right(plot_left - 4.0, plot_bottom + HEIGHT - 7.0, Font::Regular, 7.5, MUTED, &format_mg(max));
+    canvas.text_right(plot_left - 4.0, plot_bottom, Font::Regular, 7.5, MUTED, &format_mg(min));
+    canvas.text(plot_left, plot_bottom - 11.0, Font::Regular, 7.5, MUTED, &format_date(summary.start));
+    canvas.text_right(right, plot_bottom - 11.0, Font::Regular, 7.5, MUTED, &format_date(summary.end));
+
+    let days = (summary.end - summary.start).whole_days().max(1) as f32;
+    let points: Vec<(f32, f32)> = series
+        .points
+        .iter()
+        .map(|&(date, value)| {
+            let x = plot_left + (date - summary.start).whole_days() as f32 / days * plot_width;
+            let y = plot_bottom + (value - min) / (max - min) * HEIGHT;
+            (x, y)
+        })
+        .collect();
+    if points.len() > 1 {
+        canvas.polyline(&points, 1.5, ACCENT);
+    }
+    for &(x, y) in &points {
+        canvas.fill_rect(x - 1.5, y - 1.5, 3.0, 3.0, ACCENT);
+    }
+
+    canvas.advance(HEIGHT + 36.0);
+}
+
+fn draw_side_effects(canvas: &mut Canvas, summary: &ReportSummary, options: &ReportOptions) {
+    if summary.side_effects.is_empty() {
+        return nothing_recorded(canvas);
+    }
+    let rows: Vec<Vec<String>> = summary
+        .side_effects
+        .iter()
+        .map(|s| {
+            vec![
+                format_date(s.date),
+                s.symptom.clone(),
+                s.severity.clone(),
+                s.protocol_name.clone().unwrap_or_else(|| "–".into()),
+                s.duration_minutes
+                    .map(|m| format!("{} min", m))
+                    .unwrap_or_else(|| "–".into()),
+                if s.resolved { "Yes" } else { "No" }.to_string(),
+            ]
+        })
+        .collect();
+    canvas.table(
+        &[
+            Column::left("Date", 1.6),
+            Column::left("Symptom", 2.6),
+            Column::left("Severity", 1.3),
+            Column::left("Protocol", 2.2),
+            Column::right("Duration", 1.2),
+            Column::right("Resolved", 1.2),
+        ],
+        &rows,
+    );
+
+    if options.include_notes {
+        for effect in &summary.side_effects {
+            if let Some(description) = effect.description.as_deref().filter(|d| !d.trim().is_empty()) {
+                let text = format!("{}, {}: {}", format_date(effect.date), effect.symptom, description.trim());
+                canvas.paragraph(&text, 9.0, MUTED);
+            }
+        }
+        canvas.advance(6.0);
+    }
+}
+
+fn draw_inventory(canvas: &mut Canvas, summary: &ReportSummary) {
+    if summary.inventory.is_empty() {
+        canvas.paragraph("No vials in stock.", 10.0, MUTED);
+        canvas.advance(6.0);
+        return;
+    }
+    let rows: Vec<Vec<String>> = summary
+        .inventory
+        .iter()
+        .map(|row| {
+            let status = vial_status_label(&row.status);
+            vec![
+                row.protocol_name.clone(),
+                row.vial_number.clone().unwrap_or_else(|| "–".into()),
+                status,
+                row.remaining_mg.map(format_mg).unwrap_or_else(|| "–".into()),
+                row.expiry_date.map(format_date).unwrap_or_else(|| "–".into()),
+            ]
+        })
+        .collect();
+    canvas.table(
+        &[
+            Column::left("Protocol", 3.0),
+            Column::left("Vial", 1.5),
+            Column::left("Status", 1.3),
+            Column::right("Remaining mg", 1.6),
+            Column::right("Expires", 1.8),
+        ],
+        &rows,
+    );
+}
+
+fn vial_status_label(status: &peptrack_core::VialStatus) -> String {
+    use peptrack_core::VialStatus;
+    match status {
+        VialStatus::Sealed => "Sealed",
+        VialStatus::Opened => "Opened",
+        VialStatus::Empty => "Empty",
+        VialStatus::Expired => "Expired",
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::{PageSize, ReportTemplate};
+    use crate::summary::{AdherenceRow, ProtocolSummary};
+    use std::collections::BTreeMap;
+    use time::macros::{date, datetime};
+
+    fn summary() -> ReportSummary {
+        ReportSummary {
+            start: date!(2024 - 01 - 01),
+            end: date!(2024 - 03 - 31),
+            generated_at: datetime!(2024-04-01 09:00 UTC),
+            protocols: vec![ProtocolSummary {
+                name: "Healing – knee".into(),
+                peptide_name: "BPC-157".into(),
+                dose_count: 40,
+                total_mg: 10.0,
+                last_dose: Some(date!(2024 - 03 - 30)),
+                scheduled: true,
+                notes: Some("Stop if swelling returns".into()),
+            }],
+            adherence: vec![AdherenceRow {
+                protocol_name: "Healing – knee".into(),
+                expected: 52,
+                logged: 40,
+                percent: 76.9,
+            }],
+            overall_adherence: Some(76.9),
+            total_doses: 40,
+            doses_by_day: BTreeMap::from([(date!(2024 - 01 - 01), 2)]),
+            metrics: vec![MetricSeries {
+                label: "Weight",
+                unit: "kg",
+                points: vec![(date!(2024 - 01 - 02), 82.0), (date!(2024 - 03 - 28), 80.4)],
+            }],
+            side_effects: vec![],
+            inventory: vec![],
+        }
+    }
+
+    #[test]
+    fn render_pdf_writes_every_page() {
+        let options = ReportOptions {
+            include_notes: true,
+            page_size: PageSize::Letter,
+            ..Default::default()
+        };
+        let report = render_pdf(&summary(), &options);
+
+        assert!(report.pdf.starts_with(b"%PDF-"));
+        assert!(report.page_count >= 1);
+        let text = String::from_utf8_lossy(&report.pdf);
+        assert!(text.contains(&format!("/Count {}", report.page_count)));
+        assert!(text.contains("/BaseFont /Helvetica-Bold"));
+    }
+
+    #[test]
+    fn summary_template_fits_on_one_page() {
+        let options = ReportOptions {
+            template: ReportTemplate::Summary,
+            ..Default::default()
+        };
+        assert_eq!(render_pdf(&summary(), &options).page_count, 1);
+    }
+}
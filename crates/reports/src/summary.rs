@@ -0,0 +1,328 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use peptrack_core::{BodyMetric, DoseLog, InventoryItem, PeptideProtocol, SideEffect, VialStatus};
+use time::{Date, Duration, OffsetDateTime};
+
+/// Dose amount and weekdays of an enabled schedule
+#[derive(Debug, Clone)]
+pub struct ScheduledDoses {
+    pub protocol_id: String,
+    pub amount_mg: f32,
+    /// 0 = Sunday, ..., 6 = Saturday
+    pub days_of_week: Vec<u8>,
+}
+
+/// Everything a report is built from; `start` and `end` are inclusive
+pub struct ReportInput<'a> {
+    pub start: Date,
+    pub end: Date,
+    pub protocols: &'a [PeptideProtocol],
+    pub doses: &'a [DoseLog],
+    pub schedules: &'a [ScheduledDoses],
+    pub body_metrics: &'a [BodyMetric],
+    pub side_effects: &'a [SideEffect],
+    pub inventory: &'a [InventoryItem],
+}
+
+#[derive(Debug, Clone)]
+pub struct ProtocolSummary {
+    pub name: String,
+    pub peptide_name: String,
+    pub dose_count: usize,
+    pub total_mg: f32,
+    pub last_dose: Option<Date>,
+    pub scheduled: bool,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AdherenceRow {
+    pub protocol_name: String,
+    pub expected: usize,
+    pub logged: usize,
+    /// Logged doses as a share of expected ones, capped at 100
+    pub percent: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricSeries {
+    pub label: &'static str,
+    pub unit: &'static str,
+    pub points: Vec<(Date, f32)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SideEffectRow {
+    pub date: Date,
+    pub severity: String,
+    pub symptom: String,
+    pub protocol_name: Option<String>,
+    pub duration_minutes: Option<i32>,
+    pub resolved: bool,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InventoryRow {
+    pub protocol_name: String,
+    pub vial_number: Option<String>,
+    pub status: VialStatus,
+    pub remaining_mg: Option<f32>,
+    pub expiry_date: Option<Date>,
+}
+
+/// Figures shown in the report, computed from a [`ReportInput`]
+#[derive(Debug, Clone)]
+pub struct ReportSummary {
+    pub start: Date,
+    pub end: Date,
+    pub generated_at: OffsetDateTime,
+    pub protocols: Vec<ProtocolSummary>,
+    pub adherence: Vec<AdherenceRow>,
+    /// Across all scheduled protocols, `None` when nothing is scheduled
+    pub overall_adherence: Option<f32>,
+    pub total_doses: usize,
+    pub doses_by_day: BTreeMap<Date, usize>,
+    /// Only metrics with at least one value in the range
+    pub metrics: Vec<MetricSeries>,
+    pub side_effects: Vec<SideEffectRow>,
+    pub inventory: Vec<InventoryRow>,
+}
+
+impl ReportSummary {
+    /// Summarize `input` as of `now`
+    ///
+    /// Expected doses are counted up to today only, and assume each enabled
+    /// schedule applied for the whole range.
+    pub fn build(input: &ReportInput<'_>, now: OffsetDateTime) -> Self {
+        let in_range = |date: Date| date >= input.start && date <= input.end;
+        let protocol_names: HashMap<&str, &str> = input
+            .protocols
+            .iter()
+            .map(|p| (p.id.as_str(), p.name.as_str()))
+            .collect();
+
+        let doses: Vec<&DoseLog> = input
+            .doses
+            .iter()
+            .filter(|d| in_range(d.logged_at.date()))
+            .collect();
+
+        let mut doses_by_day = BTreeMap::new();
+        for dose in &doses {
+            *doses_by_day.entry(dose.logged_at.date()).or_insert(0) += 1;
+        }
+
+        let scheduled: HashSet<&str> = input
+            .schedules
+            .iter()
+            .map(|s| s.protocol_id.as_str())
+            .collect();
+
+        let protocols = input
+            .protocols
+            .iter()
+            .filter_map(|protocol| {
+                let protocol_doses: Vec<&&DoseLog> = doses
+                    .iter()
+                    .filter(|d| d.protocol_id == protocol.id)
+                    .collect();
+                let is_scheduled = scheduled.contains(protocol.id.as_str());
+                if protocol_doses.is_empty() && !is_scheduled {
+                    return None;
+                }
+                Some(ProtocolSummary {
+                    name: protocol.name.clone(),
+                    peptide_name: protocol.peptide_name.clone(),
+                    dose_count: protocol_doses.len(),
+                    total_mg: protocol_doses.iter().map(|d| d.amount_mg).sum(),
+                    last_dose: protocol_doses.iter().map(|d| d.logged_at.date()).max(),
+                    scheduled: is_scheduled,
+                    notes: protocol.notes.clone(),
+                })
+            })
+            .collect();
+
+        let adherence_end = input.end.min(now.date());
+        let mut adherence = Vec::new();
+        for protocol in input.protocols {
+            let expected: usize = input
+                .schedules
+                .iter()
+                .filter(|s| s.protocol_id == protocol.id)
+                .map(|s| count_weekdays(input.start, adherence_end, &s.days_of_week))
+                .sum();
+            if expected == 0 {
+                continue;
+            }
+            let logged = doses
+                .iter()
+                .filter(|d| d.protocol_id == protocol.id && d.logged_at.date() <= adherence_end)
+                .count();
+            adherence.push(AdherenceRow {
+                protocol_name: protocol.name.clone(),
+                expected,
+                logged,
+                percent: percent(logged, expected),
+            });
+        }
+        let overall_adherence = {
+            let expected: usize = adherence.iter().map(|row| row.expected).sum();
+            let logged: usize = adherence.iter().map(|row| row.logged.min(row.expected)).sum();
+            (expected > 0).then(|| percent(logged, expected))
+        };
+
+        let mut body_metrics: Vec<&BodyMetric> = input
+            .body_metrics
+            .iter()
+            .filter(|m| in_range(m.date.date()))
+            .collect();
+        body_metrics.sort_by_key(|m| m.date);
+        let series = |label, unit, value: fn(&BodyMetric) -> Option<f32>| MetricSeries {
+            label,
+            unit,
+            points: body_metrics
+                .iter()
+                .filter_map(|m| value(m).map(|v| (m.date.date(), v)))
+                .collect(),
+        };
+        let metrics = [
+            series("Weight", "kg", |m| m.weight_kg),
+            series("Body fat", "%", |m| m.body_fat_percentage),
+            series("Waist", "cm", |m| m.waist_cm),
+            series("Resting heart rate", "bpm", |m| m.resting_heart_rate_bpm),
+        ]
+        .into_iter()
+        .filter(|s| !s.points.is_empty())
+        .collect();
+
+        let mut side_effects: Vec<SideEffectRow> = input
+            .side_effects
+            .iter()
+            .filter(|s| in_range(s.date.date()))
+            .map(|s| SideEffectRow {
+                date: s.date.date(),
+                severity: s.severity.clone(),
+                symptom: s.symptom.clone(),
+                protocol_name: s
+                    .protocol_id
+                    .as_deref()
+                    .and_then(|id| protocol_names.get(id))
+                    .map(|name| name.to_string()),
+                duration_minutes: s.duration_minutes,
+                resolved: s.resolved,
+                description: s.description.clone(),
+            })
+            .collect();
+        side_effects.sort_by_key(|s| s.date);
+
+        let mut inventory: Vec<InventoryRow> = input
+            .inventory
+            .iter()
+            .filter(|item| !matches!(item.vial_status, VialStatus::Empty))
+            .map(|item| InventoryRow {
+                protocol_name: protocol_names
+                    .get(item.protocol_id.as_str())
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| "Unknown protocol".to_string()),
+                vial_number: item.vial_number.clone(),
+                status: item.vial_status.clone(),
+                remaining_mg: item.quantity_remaining_mg.or(item.quantity_mg),
+                expiry_date: item.expiry_date.map(|d| d.date()),
+            })
+            .collect();
+        // Soonest expiry first, vials without one last
+        inventory.sort_by_key(|row| (row.expiry_date.is_none(), row.expiry_date));
+
+        Self {
+            start: input.start,
+            end: input.end,
+            generated_at: now,
+            protocols,
+            adherence,
+            overall_adherence,
+            total_doses: doses.len(),
+            doses_by_day,
+            metrics,
+            side_effects,
+            inventory,
+        }
+    }
+}
+
+fn percent(logged: usize, expected: usize) -> f32 {
+    (logged as f32 / expected as f32 * 100.0).min(100.0)
+}
+
+/// Number of days from `start` to `end` (inclusive) that fall on `weekdays`
+fn count_weekdays(start: Date, end: Date, weekdays: &[u8]) -> usize {
+    let mut count = 0;
+    let mut day = start;
+    while day <= end {
+        if weekdays.contains(&day.weekday().number_days_from_sunday()) {
+            count += 1;
+        }
+        day += Duration::days(1);
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, datetime};
+
+    #[test]
+    fn count_weekdays_is_inclusive() {
+        // 2024-01-01 was a Monday
+        assert_eq!(count_weekdays(date!(2024 - 01 - 01), date!(2024 - 01 - 14), &[1, 3]), 4);
+        assert_eq!(count_weekdays(date!(2024 - 01 - 02), date!(2024 - 01 - 01), &[1]), 0);
+    }
+
+    #[test]
+    fn build_counts_adherence_until_today_only() {
+        let protocol = PeptideProtocol::new("Healing", "BPC-157");
+        let idle = PeptideProtocol::new("Old cycle", "TB-500");
+        let mut monday = DoseLog::new(protocol.id.as_str(), "abdomen", 0.25);
+        monday.logged_at = datetime!(2024-01-01 08:00 UTC);
+        let mut outside = DoseLog::new(protocol.id.as_str(), "abdomen", 0.25);
+        outside.logged_at = datetime!(2023-12-20 08:00 UTC);
+        let mut vial = InventoryItem::new(protocol.id.as_str());
+        vial.quantity_remaining_mg = Some(4.5);
+        let mut empty = InventoryItem::new(protocol.id.as_str());
+        empty.vial_status = VialStatus::Empty;
+
+        let protocols = [protocol.clone(), idle];
+        let doses = [monday, outside];
+        let schedules = [ScheduledDoses {
+            protocol_id: protocol.id.clone(),
+            amount_mg: 0.25,
+            days_of_week: vec![1, 3, 5],
+        }];
+        let inventory = [vial, empty];
+        let input = ReportInput {
+            start: date!(2024 - 01 - 01),
+            end: date!(2024 - 01 - 31),
+            protocols: &protocols,
+            doses: &doses,
+            schedules: &schedules,
+            body_metrics: &[],
+            side_effects: &[],
+            inventory: &inventory,
+        };
+
+        // Wednesday of the first week: Monday and Wednesday were due
+        let summary = ReportSummary::build(&input, datetime!(2024-01-03 12:00 UTC));
+
+        assert_eq!(summary.protocols.len(), 1);
+        assert_eq!(summary.protocols[0].dose_count, 1);
+        assert_eq!(summary.total_doses, 1);
+        assert_eq!(summary.adherence[0].expected, 2);
+        assert_eq!(summary.adherence[0].logged, 1);
+        assert_eq!(summary.overall_adherence, Some(50.0));
+        assert_eq!(summary.doses_by_day.get(&date!(2024 - 01 - 01)), Some(&1));
+        assert_eq!(summary.inventory.len(), 1);
+        assert_eq!(summary.inventory[0].remaining_mg, Some(4.5));
+        assert!(summary.metrics.is_empty());
+    }
+}
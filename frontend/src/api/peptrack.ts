@@ -1,4 +1,60 @@
-import { invoke } from "@tauri-apps/api/core";
+import { invoke as tauriInvoke, type InvokeArgs } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type CommandErrorKind =
+  | "not_found"
+  | "invalid_input"
+  | "conflict"
+  | "locked"
+  | "busy"
+  | "corrupted"
+  | "migration_failed"
+  | "network"
+  | "permission_denied"
+  | "internal";
+
+interface CommandErrorPayload {
+  kind: CommandErrorKind;
+  message: string;
+  retryable: boolean;
+  details?: string | null;
+}
+
+/**
+ * Error thrown when a backend command fails.
+ * `kind` says what went wrong; `retryable` says whether trying again may help.
+ */
+export class CommandError extends Error {
+  readonly kind: CommandErrorKind;
+  readonly retryable: boolean;
+  readonly details: string | null;
+
+  constructor(payload: CommandErrorPayload) {
+    super(payload.message);
+    this.name = "CommandError";
+    this.kind = payload.kind;
+    this.retryable = payload.retryable;
+    this.details = payload.details ?? null;
+  }
+}
+
+function isCommandErrorPayload(value: unknown): value is CommandErrorPayload {
+  return (
+    typeof value === "object" &&
+    value !== null &&
+    typeof (value as CommandErrorPayload).kind === "string" &&
+    typeof (value as CommandErrorPayload).message === "string"
+  );
+}
+
+/** Calls a backend command, rethrowing its error as a CommandError */
+async function invoke<T>(command: string, args?: InvokeArgs): Promise<T> {
+  try {
+    return await tauriInvoke<T>(command, args);
+  } catch (error) {
+    throw isCommandErrorPayload(error) ? new CommandError(error) : error;
+  }
+}
 
 export interface PeptideProtocol {
   id: string;
@@ -21,9 +77,17 @@ export interface CreateProtocolPayload {
 
 export type SummaryFormat = "Markdown" | "Json";
 
+export interface AiUsage {
+  input_tokens: number;
+  output_tokens: number;
+  cost_usd: number;
+  estimated: boolean;
+}
+
 export interface SummarizeResponse {
   provider: string;
   output: string;
+  usage?: AiUsage;
 }
 
 export async function listProtocols() {
@@ -41,6 +105,23 @@ export async function saveProtocol(payload: CreateProtocolPayload) {
   });
 }
 
+export interface ProtocolBundlePayload {
+  protocol: CreateProtocolPayload;
+  schedules?: Omit<CreateSchedulePayload, "protocolId">[];
+  inventory?: Omit<CreateInventoryPayload, "protocolId">[];
+}
+
+export interface ProtocolBundle {
+  protocol: PeptideProtocol;
+  schedules: DoseSchedule[];
+  inventory: InventoryItem[];
+}
+
+/** Creates a protocol with its schedules and vials; if any part fails, nothing is saved */
+export async function createProtocolBundle(payload: ProtocolBundlePayload) {
+  return invoke<ProtocolBundle>("create_protocol_bundle", { payload });
+}
+
 export async function toggleProtocolFavorite(protocolId: string) {
   return invoke<boolean>("toggle_protocol_favorite", {
     protocolId,
@@ -68,6 +149,31 @@ export async function removeProtocolTag(protocolId: string, tag: string) {
   });
 }
 
+// Protocol Sharing
+
+export interface ProtocolPackageImport {
+  protocol: PeptideProtocol;
+  schedulesAdded: number;
+  /** Schedules that failed validation */
+  schedulesSkipped: number;
+  literatureAdded: number;
+  /** Papers already in the literature cache */
+  literatureExisting: number;
+}
+
+/**
+ * Passphrase-encrypted package with the protocol, its schedules and the
+ * chosen papers. Dose logs are never included.
+ */
+export async function exportProtocolPackage(protocolId: string, passphrase: string, literatureIds?: string[]) {
+  return invoke<string>("export_protocol_package", { protocolId, passphrase, literatureIds });
+}
+
+/** Add a shared protocol package's contents as new records */
+export async function importProtocolPackage(contents: string, passphrase: string) {
+  return invoke<ProtocolPackageImport>("import_protocol_package", { contents, passphrase });
+}
+
 // Bulk Operations for Protocols
 
 export async function deleteProtocol(protocolId: string) {
@@ -104,6 +210,94 @@ export async function bulkDeleteDoses(doseIds: string[]) {
   });
 }
 
+// Settings change events
+
+/** Emitted after a setting is saved, with its namespaced key, e.g. "backup.schedule" */
+export interface SettingsChanged {
+  key: string;
+}
+
+export async function onSettingsChanged(handler: (change: SettingsChanged) => void): Promise<UnlistenFn> {
+  return listen<SettingsChanged>("settings-changed", (event) => handler(event.payload));
+}
+
+// Background change events
+
+/** A vial predicted to run out, from its low stock alert */
+export interface InventoryLow {
+  alertId: string;
+  inventoryId: string | null;
+  severity: AlertSeverity;
+  title: string;
+  message: string;
+}
+
+/** Result of a price monitor run; scraped prices wait in the review queue */
+export interface PriceCheckSummary {
+  checkedUrls: number;
+  observationsQueued: number;
+  failures: string[];
+  /** URLs left for the next run because the crawl budget ran out */
+  skippedUrls: number;
+}
+
+/** Events the backend emits when data changes in the background, with their payloads */
+export interface AppEvents {
+  "alert:created": Alert;
+  "inventory:low": InventoryLow;
+  "backup:completed": BackupHistoryEntry;
+  "prices:checked": PriceCheckSummary;
+}
+
+export async function onAppEvent<K extends keyof AppEvents>(
+  name: K,
+  handler: (payload: AppEvents[K]) => void,
+): Promise<UnlistenFn> {
+  return listen<AppEvents[K]>(name, (event) => handler(event.payload));
+}
+
+// Tray and global shortcut quick actions
+
+export interface QuickActionResult {
+  action: "logLastDose" | "snoozeReminder" | "backupNow";
+  success: boolean;
+  message: string;
+}
+
+/** Emitted after a quick action runs from the tray or a global shortcut */
+export async function onQuickAction(handler: (result: QuickActionResult) => void): Promise<UnlistenFn> {
+  return listen<QuickActionResult>("quick-action", (event) => handler(event.payload));
+}
+
+// Unit preference types and functions
+
+export type DoseUnit = "mg" | "mcg" | "iu";
+export type WeightUnit = "kg" | "lb";
+export type LengthUnit = "cm" | "in";
+
+/** A compound whose doses are shown in IU */
+export interface IuConversion {
+  peptideName: string;
+  iuPerMg: number;
+}
+
+/** How values are shown and entered; everything is stored in mg, kg and cm */
+export interface UnitPreferences {
+  /** "mg" or "mcg"; IU is only chosen per compound */
+  doseUnit: DoseUnit;
+  weightUnit: WeightUnit;
+  lengthUnit: LengthUnit;
+  iuCompounds: IuConversion[];
+}
+
+export async function getUnitPreferences() {
+  return invoke<UnitPreferences>("get_unit_preferences");
+}
+
+export async function updateUnitPreferences(preferences: UnitPreferences) {
+  return invoke<UnitPreferences>("update_unit_preferences", { preferences });
+}
+
 // Body Metrics types and functions
 
 export interface BodyMetric {
@@ -125,22 +319,35 @@ export interface BodyMetricPayload {
   muscle_mass_kg?: number | null;
   waist_cm?: number | null;
   notes?: string | null;
+  /** Unit the weights were entered in; kg when not given */
+  weightUnit?: WeightUnit;
+  /** Unit the waist was entered in; cm when not given */
+  lengthUnit?: LengthUnit;
+}
+
+/** Body metric with its measurements in the preferred units; stored fields stay in kg and cm */
+export interface BodyMetricView extends BodyMetric {
+  displayWeight?: number | null;
+  displayMuscleMass?: number | null;
+  displayWaist?: number | null;
+  weightUnit: WeightUnit;
+  lengthUnit: LengthUnit;
 }
 
 export async function logBodyMetric(payload: BodyMetricPayload) {
-  return invoke<BodyMetric>("log_body_metric", { payload });
+  return invoke<BodyMetricView>("log_body_metric", { payload });
 }
 
 export async function listBodyMetrics() {
-  return invoke<BodyMetric[]>("list_body_metrics");
+  return invoke<BodyMetricView[]>("list_body_metrics");
 }
 
 export async function getBodyMetric(metricId: string) {
-  return invoke<BodyMetric | null>("get_body_metric", { metricId });
+  return invoke<BodyMetricView | null>("get_body_metric", { metricId });
 }
 
 export async function updateBodyMetric(metricId: string, payload: BodyMetricPayload) {
-  return invoke<BodyMetric>("update_body_metric", { metricId, payload });
+  return invoke<BodyMetricView>("update_body_metric", { metricId, payload });
 }
 
 export async function deleteBodyMetric(metricId: string) {
@@ -151,6 +358,151 @@ export async function bulkDeleteBodyMetrics(metricIds: string[]) {
   return invoke<number>("bulk_delete_body_metrics", { metricIds });
 }
 
+// Journal types and functions
+
+export type JournalLinkKind = "protocol" | "dose" | "paper";
+
+export interface JournalEntry {
+  id: string;
+  title?: string | null;
+  /** Markdown; links look like [[protocol:id|label]], [[dose:id]], [[paper:doi]] */
+  body: string;
+  entry_date: string;
+  tags: string[];
+  created_at: string;
+  updated_at: string;
+}
+
+export interface JournalEntryPayload {
+  title?: string | null;
+  body: string;
+  entryDate: string; // ISO 8601 string
+  tags?: string[];
+}
+
+export interface ResolvedJournalLink {
+  kind: JournalLinkKind;
+  target: string;
+  label?: string | null;
+  /** Null when the linked record doesn't exist (any more) */
+  entityId?: string | null;
+  title?: string | null;
+  protocolId?: string | null;
+}
+
+export async function createJournalEntry(payload: JournalEntryPayload) {
+  return invoke<JournalEntry>("create_journal_entry", { payload });
+}
+
+export async function listJournalEntries(tag?: string) {
+  return invoke<JournalEntry[]>("list_journal_entries", { tag: tag || null });
+}
+
+export async function getJournalEntry(entryId: string) {
+  return invoke<JournalEntry | null>("get_journal_entry", { entryId });
+}
+
+export async function updateJournalEntry(entryId: string, payload: JournalEntryPayload) {
+  return invoke<JournalEntry>("update_journal_entry", { entryId, payload });
+}
+
+export async function deleteJournalEntry(entryId: string) {
+  return invoke<void>("delete_journal_entry", { entryId });
+}
+
+export async function getJournalEntryLinks(entryId: string) {
+  return invoke<ResolvedJournalLink[]>("get_journal_entry_links", { entryId });
+}
+
+/** Entries linking to a protocol, dose or paper, newest first */
+export async function listJournalBacklinks(kind: JournalLinkKind, entityId: string) {
+  return invoke<JournalEntry[]>("list_journal_backlinks", { kind, entityId });
+}
+
+// Goal types and functions
+
+export type GoalMetric =
+  | "weight_kg"
+  | "body_fat_percentage"
+  | "muscle_mass_kg"
+  | "waist_cm"
+  | "lab_marker";
+
+export type GoalStatus = "no_data" | "in_progress" | "on_track" | "behind" | "stalled" | "achieved";
+
+export interface Goal {
+  id: string;
+  name: string;
+  metric: GoalMetric;
+  marker?: string | null;
+  unit?: string | null;
+  target_value: number;
+  start_value?: number | null;
+  start_date: string;
+  target_date?: string | null;
+  protocol_id?: string | null;
+  notes?: string | null;
+  archived: boolean;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface GoalPayload {
+  name: string;
+  metric: GoalMetric;
+  /** Required for lab marker goals */
+  marker?: string | null;
+  unit?: string | null;
+  targetValue: number;
+  /** Defaults to the first reading after the start date */
+  startValue?: number | null;
+  startDate: string; // ISO 8601 string
+  targetDate?: string | null; // ISO 8601 string
+  protocolId?: string | null;
+  notes?: string | null;
+  archived?: boolean;
+}
+
+export interface GoalProgress {
+  goalId: string;
+  status: GoalStatus;
+  baseline: number | null;
+  current: number | null;
+  currentDate: string | null;
+  target: number;
+  /** 0-100 */
+  percentComplete: number | null;
+  ratePerWeek: number | null;
+  projectedDate: string | null;
+  readingCount: number;
+}
+
+export interface GoalWithProgress {
+  goal: Goal;
+  protocolName: string | null;
+  progress: GoalProgress;
+}
+
+export async function createGoal(payload: GoalPayload) {
+  return invoke<GoalWithProgress>("create_goal", { payload });
+}
+
+export async function listGoals(includeArchived = false) {
+  return invoke<GoalWithProgress[]>("list_goals", { includeArchived });
+}
+
+export async function getGoal(goalId: string) {
+  return invoke<GoalWithProgress>("get_goal", { goalId });
+}
+
+export async function updateGoal(goalId: string, payload: GoalPayload) {
+  return invoke<GoalWithProgress>("update_goal", { goalId, payload });
+}
+
+export async function deleteGoal(goalId: string) {
+  return invoke<void>("delete_goal", { goalId });
+}
+
 // Side Effects types and functions
 
 export interface SideEffect {
@@ -259,6 +611,213 @@ export async function verifyDatabaseIntegrity() {
   return invoke<void>("verify_database_integrity");
 }
 
+/** Rebuild the database file so it shrinks to the data it holds */
+export async function compactDatabase() {
+  return invoke<void>("compact_database");
+}
+
+/** Copy of the database taken before a schema upgrade */
+export interface MigrationSnapshot {
+  id: string;
+  createdAt: string;
+  fromVersion: number;
+  toVersion: number;
+  sizeBytes: number;
+  checksum: string;
+}
+
+export async function listMigrationSnapshots() {
+  return invoke<MigrationSnapshot[]>("list_migration_snapshots");
+}
+
+/** Roll the database back to a snapshot; restart the app afterwards */
+export async function restoreMigrationSnapshot(snapshotId: string) {
+  return invoke<void>("restore_migration_snapshot", { snapshotId });
+}
+
+export interface DatabaseStats {
+  page_count: number;
+  page_size: number;
+  total_size_mb: number;
+  freelist_pages: number;
+  wasted_space_mb: number;
+  wal_size_mb: number;
+}
+
+/** optimize = incremental vacuum, PRAGMA optimize and ANALYZE */
+export type MaintenanceAction = "optimize" | "checkpoint";
+
+export interface MaintenanceRun {
+  id: string;
+  ranAt: string;
+  /** Started from the health screen rather than the weekly schedule */
+  manual: boolean;
+  /** Empty when the stats were within their thresholds */
+  actions: MaintenanceAction[];
+  before: DatabaseStats;
+  after: DatabaseStats;
+  error?: string | null;
+}
+
+/** Vacuums and checkpoints now, if the stats call for it */
+export async function runDatabaseMaintenance() {
+  return invoke<MaintenanceRun>("run_database_maintenance");
+}
+
+/** Newest first */
+export async function listMaintenanceRuns() {
+  return invoke<MaintenanceRun[]>("list_maintenance_runs");
+}
+
+// Records left pointing at deleted ones
+
+export interface CascadePolicies {
+  /** Inventory bought from a deleted supplier */
+  supplierInventory: "unlink" | "delete";
+  /** Active alerts about a deleted record */
+  staleAlerts: "dismiss" | "delete";
+}
+
+export type OrphanKind =
+  | "inventory_supplier"
+  | "price_history_supplier"
+  | "schedule_protocol"
+  | "skip_schedule"
+  | "alert_subject";
+
+export interface OrphanedRecord {
+  kind: OrphanKind;
+  record_id: string;
+  /** Id of the deleted record it points at */
+  missing_id: string;
+}
+
+export interface OrphanRepair {
+  inventory_unlinked: number;
+  inventory_deleted: number;
+  price_history_deleted: number;
+  schedules_deleted: number;
+  skips_deleted: number;
+  alerts_dismissed: number;
+  alerts_deleted: number;
+}
+
+export async function findOrphanedRecords() {
+  return invoke<OrphanedRecord[]>("find_orphaned_records");
+}
+
+/** Cleans up orphaned records as the cascade policies say */
+export async function repairOrphanedRecords() {
+  return invoke<OrphanRepair>("repair_orphaned_records");
+}
+
+export async function getCascadePolicies() {
+  return invoke<CascadePolicies>("get_cascade_policies");
+}
+
+export async function updateCascadePolicies(policies: CascadePolicies) {
+  return invoke<void>("update_cascade_policies", { policies });
+}
+
+export type TamperChange = "added" | "modified" | "deleted";
+
+export interface TamperFinding {
+  table: string;
+  recordId: string;
+  change: TamperChange;
+}
+
+export interface TamperReport {
+  verifiedAt: string;
+  entriesChecked: number;
+  recordsChecked: number;
+  /** Chain entries that were edited, or follow one that was removed */
+  brokenLinks: { table: string; seq: number }[];
+  /** Tables whose newest chain entries were removed, or whose head was edited */
+  truncatedTables: string[];
+  findings: TamperFinding[];
+  /** The chain's key was deleted from the database, so nothing could be checked */
+  keyRemoved: boolean;
+}
+
+export async function getTamperEvidenceEnabled() {
+  return invoke<boolean>("get_tamper_evidence_enabled");
+}
+
+/** Turning it on chains every existing record; turning it off deletes the chain */
+export async function setTamperEvidence(enabled: boolean) {
+  return invoke<void>("set_tamper_evidence", { enabled });
+}
+
+/** Reports records added, changed or deleted outside the app */
+export async function verifyTamperChain() {
+  return invoke<TamperReport>("verify_tamper_chain");
+}
+
+// Logs and diagnostics
+
+export type LogLevel = "error" | "warn" | "info" | "debug" | "trace";
+
+export interface LogEntry {
+  timestamp: string;
+  level: string;
+  /** Module the entry was logged from, e.g. "app_lib::commands::backup" */
+  target: string;
+  message: string;
+  fields?: Record<string, unknown>;
+}
+
+export interface LogQuery {
+  /** Least severe level to show */
+  level?: LogLevel;
+  /** Part of the module path, e.g. "backup" */
+  module?: string;
+  limit?: number;
+}
+
+export interface DiagnosticsExport {
+  path: string;
+  sizeBytes: number;
+  logEntries: number;
+}
+
+/** Recent log entries, newest first */
+export async function getRecentLogs(query: LogQuery = {}) {
+  return invoke<LogEntry[]>("get_recent_logs", {
+    level: query.level ?? null,
+    module: query.module || null,
+    limit: query.limit ?? null,
+  });
+}
+
+/** Save logs, health report and database stats (no records) to Downloads for a bug report */
+export async function exportDiagnosticsBundle() {
+  return invoke<DiagnosticsExport>("export_diagnostics_bundle");
+}
+
+export interface SecureWipeToken {
+  token: string;
+  expiresInSecs: number;
+}
+
+export interface SecureWipeResult {
+  rowsDeleted: number;
+  filesRemoved: string[];
+}
+
+/** First step of a factory reset; pass the token to `secureWipeDatabase` */
+export async function requestSecureWipe() {
+  return invoke<SecureWipeToken>("request_secure_wipe");
+}
+
+/**
+ * Permanently delete all data, config files and stored keys.
+ * Restart the app afterwards.
+ */
+export async function secureWipeDatabase(token: string) {
+  return invoke<SecureWipeResult>("secure_wipe_database", { token });
+}
+
 export async function summarizeContent(params: {
   title: string;
   content: string;
@@ -269,6 +828,66 @@ export async function summarizeContent(params: {
   });
 }
 
+export interface SummarizeCorpusPayload {
+  entryIds: string[];
+  protocolIds?: string[];
+  title?: string;
+}
+
+/** Summarize several cached papers into one brief saved to summary history */
+export async function summarizeCorpus(payload: SummarizeCorpusPayload) {
+  return invoke<SummaryHistory>("summarize_corpus", { payload });
+}
+
+// Summary queue types and functions
+
+export type QueueItemStatus = "pending" | "running" | "done" | "failed";
+
+export interface SummaryQueueItem {
+  id: string;
+  entryId: string;
+  title: string;
+  format: SummaryFormat;
+  status: QueueItemStatus;
+  attempts: number;
+  /** Error from the last attempt */
+  error?: string | null;
+  /** Saved summary history entry, once done */
+  summaryId?: string | null;
+  enqueuedAt: string;
+  finishedAt?: string | null;
+}
+
+export interface SummaryQueueStatus {
+  items: SummaryQueueItem[];
+  pending: number;
+  running: number;
+  done: number;
+  failed: number;
+}
+
+/** Queue papers to be summarized one by one in the background */
+export async function enqueueSummaries(entryIds: string[], format?: SummaryFormat) {
+  return invoke<SummaryQueueStatus>("enqueue_summaries", { payload: { entryIds, format } });
+}
+
+export async function getSummaryQueueStatus() {
+  return invoke<SummaryQueueStatus>("get_summary_queue_status");
+}
+
+export async function retryFailedSummaries() {
+  return invoke<SummaryQueueStatus>("retry_failed_summaries");
+}
+
+export async function clearFinishedSummaries() {
+  return invoke<SummaryQueueStatus>("clear_finished_summaries");
+}
+
+/** Emitted whenever a queued summary starts or finishes */
+export async function onSummaryQueueProgress(handler: (item: SummaryQueueItem) => void): Promise<UnlistenFn> {
+  return listen<SummaryQueueItem>("summary-queue-progress", (event) => handler(event.payload));
+}
+
 // Literature types
 
 export interface LiteratureEntry {
@@ -279,6 +898,71 @@ export interface LiteratureEntry {
   summary?: string | null;
   relevance_score?: number | null;
   indexed_at: string;
+  /** e.g. "peptide:bpc-157", "study:rct" */
+  tags?: string[];
+  published_date?: string | null;
+  citation_count?: number | null;
+  doi?: string | null;
+  authors?: string[];
+  journal?: string | null;
+  year?: number | null;
+  /** Last metadata lookup; null if not tried yet */
+  enriched_at?: string | null;
+  retracted?: boolean;
+  corrected?: boolean;
+  notice_doi?: string | null;
+  retraction_checked_at?: string | null;
+  /** Pinned entries are never pruned from the cache */
+  is_pinned?: boolean;
+  /** null when the entry isn't on the reading list */
+  reading_status?: ReadingStatus | null;
+  /** 1-5 */
+  rating?: number | null;
+  reading_notes?: string | null;
+  /** Downloaded open-access PDF, see downloadPaperPdf */
+  pdf_attachment_id?: string | null;
+}
+
+export type ReadingStatus = "unread" | "reading" | "read";
+
+export interface ReadingFilter {
+  pinnedOnly?: boolean;
+  readingStatus?: ReadingStatus;
+}
+
+export interface UpdateReadingPayload {
+  /** null takes the entry off the reading list */
+  status: ReadingStatus | null;
+  rating?: number | null;
+  notes?: string | null;
+}
+
+/** Limits are off when null; pinned and reading list entries are always kept */
+export interface LiteratureRetention {
+  maxAgeDays: number | null;
+  maxEntries: number | null;
+}
+
+/** Whether and for how long literature API responses are cached */
+export interface ResponseCacheSettings {
+  enabled: boolean;
+  ttlHours: number;
+}
+
+export interface SourceCacheStats {
+  source: string;
+  entries: number;
+  hits: number;
+}
+
+export interface ResponseCacheStats {
+  entries: number;
+  /** Entries older than the current TTL */
+  expired: number;
+  hits: number;
+  sizeBytes: number;
+  oldestFetchedAt: string | null;
+  bySource: SourceCacheStats[];
 }
 
 export interface LiteratureResult {
@@ -290,85 +974,688 @@ export interface LiteratureResult {
   published_date?: string | null;
   journal?: string | null;
   abstract_text?: string | null;
+  citation_count?: number | null;
+  publication_types?: string[];
 }
 
 export interface LiteratureSearchResult {
   source: string;
   results: LiteratureResult[];
+  /** Cached entry for each result, with relevance score and tags */
+  entries: LiteratureEntry[];
+  /** Cached papers shown because the APIs couldn't be reached */
+  offline: boolean;
+}
+
+export interface LiteratureTagCount {
+  tag: string;
+  count: number;
 }
 
 export interface SearchLiteraturePayload {
   query: string;
   maxResults?: number;
   sources?: string[];
+  /** Call the APIs even if the same search was cached recently */
+  bypassCache?: boolean;
 }
 
 // Literature API calls
 
-export async function listLiterature() {
-  return invoke<LiteratureEntry[]>("list_literature");
+export async function listLiterature(tags?: string[], filter?: ReadingFilter) {
+  return invoke<LiteratureEntry[]>("list_literature", { tags, filter });
 }
 
-export async function searchCachedLiterature(query: string) {
-  return invoke<LiteratureEntry[]>("search_cached_literature", { query });
+export async function searchCachedLiterature(query: string, tags?: string[], filter?: ReadingFilter) {
+  return invoke<LiteratureEntry[]>("search_cached_literature", { query, tags, filter });
 }
 
-export async function searchLiterature(payload: SearchLiteraturePayload) {
-  return invoke<LiteratureSearchResult[]>("search_literature", { payload });
+export async function setLiteraturePinned(entryId: string, pinned: boolean) {
+  return invoke<LiteratureEntry>("set_literature_pinned", { entryId, pinned });
 }
 
-// Dose logging types
+export async function updateLiteratureReading(entryId: string, payload: UpdateReadingPayload) {
+  return invoke<LiteratureEntry>("update_literature_reading", { entryId, payload });
+}
 
-export interface DoseLog {
-  id: string;
-  protocol_id: string;
-  site: string;
-  amount_mg: number;
-  notes?: string | null;
-  logged_at: string;
+export async function getLiteratureRetention() {
+  return invoke<LiteratureRetention>("get_literature_retention");
 }
 
-export interface LogDosePayload {
-  protocolId: string;
-  site: string;
-  amountMg: number;
-  notes?: string;
+/** Saves the retention settings; returns how many entries were pruned */
+export async function updateLiteratureRetention(retention: LiteratureRetention) {
+  return invoke<number>("update_literature_retention", { retention });
 }
 
-// Dose logging API calls
+export async function pruneLiteratureCache() {
+  return invoke<number>("prune_literature_cache");
+}
 
-export async function logDose(payload: LogDosePayload) {
-  return invoke<DoseLog>("log_dose", { payload });
+export async function getResponseCacheSettings() {
+  return invoke<ResponseCacheSettings>("get_response_cache_settings");
 }
 
-export async function listDoseLogs() {
-  return invoke<DoseLog[]>("list_dose_logs");
+/** Saves the cache settings; returns how many cached responses were dropped */
+export async function updateResponseCacheSettings(settings: ResponseCacheSettings) {
+  return invoke<number>("update_response_cache_settings", { settings });
 }
 
-export async function listDoseLogsForProtocol(protocolId: string) {
-  return invoke<DoseLog[]>("list_dose_logs_for_protocol", { protocolId });
+export async function getResponseCacheStats() {
+  return invoke<ResponseCacheStats>("get_response_cache_stats");
 }
 
-export async function deleteDoseLog(logId: string) {
-  return invoke<void>("delete_dose_log", { logId });
+/** Drops every cached API response; returns how many were dropped */
+export async function clearResponseCache() {
+  return invoke<number>("clear_response_cache");
 }
 
-// AI availability types
+export async function listLiteratureTags() {
+  return invoke<LiteratureTagCount[]>("list_literature_tags");
+}
 
-export interface AiAvailabilityStatus {
-  codexAvailable: boolean;
-  claudeAvailable: boolean;
-  anyAvailable: boolean;
-  preferredProvider?: string | null;
+export interface EnrichmentSummary {
+  checked: number;
+  enriched: number;
+  notFound: number;
+  failed: number;
 }
 
-// AI availability check
+/** Open-access full text from Europe PMC; null when there is none */
+export async function getPaperFullText(entryId: string) {
+  return invoke<string | null>("get_paper_full_text", { entryId });
+}
 
-export async function checkAiAvailability() {
-  return invoke<AiAvailabilityStatus>("check_ai_availability");
+export async function enrichLiterature(limit?: number) {
+  return invoke<EnrichmentSummary>("enrich_literature", { limit });
 }
 
-// Backup types
+export interface RetractionCheckSummary {
+  checked: number;
+  retracted: number;
+  corrected: number;
+  summariesAnnotated: number;
+  failed: number;
+  alerts: Alert[];
+}
+
+/** Download and store the paper's open-access PDF, found by DOI */
+export async function downloadPaperPdf(entryId: string) {
+  return invoke<LiteratureEntry>("download_paper_pdf", { entryId });
+}
+
+/** The stored PDF as base64 */
+export async function getPaperPdf(entryId: string) {
+  return invoke<string>("get_paper_pdf", { entryId });
+}
+
+export async function removePaperPdf(entryId: string) {
+  return invoke<LiteratureEntry>("remove_paper_pdf", { entryId });
+}
+
+export async function checkLiteratureRetractions(limit?: number) {
+  return invoke<RetractionCheckSummary>("check_literature_retractions", { limit });
+}
+
+/** A peptide followed for newly published works */
+export interface PeptideFollow {
+  id: string;
+  peptide_name: string;
+  /** OpenAlex concept; null when works are found by text search */
+  concept_id?: string | null;
+  concept_name?: string | null;
+  enabled: boolean;
+  interval_days: number;
+  last_checked_at?: string | null;
+  /** New works waiting to be saved or dismissed, newest first */
+  pending: LiteratureEntry[];
+  created_at: string;
+}
+
+export interface FollowCheck {
+  follow: PeptideFollow;
+  newWorks: number;
+  alert?: Alert | null;
+}
+
+export async function listPeptideFollows() {
+  return invoke<PeptideFollow[]>("list_peptide_follows");
+}
+
+export async function followPeptide(peptideName: string) {
+  return invoke<PeptideFollow>("follow_peptide", { peptideName });
+}
+
+export async function unfollowPeptide(followId: string) {
+  return invoke<void>("unfollow_peptide", { followId });
+}
+
+export async function checkPeptideFollow(followId: string) {
+  return invoke<FollowCheck>("check_peptide_follow", { followId });
+}
+
+/** Save a pending work to the literature cache */
+export async function cacheFollowedWork(followId: string, entryId: string) {
+  return invoke<LiteratureEntry>("cache_followed_work", { followId, entryId });
+}
+
+export async function dismissFollowedWork(followId: string, entryId: string) {
+  return invoke<void>("dismiss_followed_work", { followId, entryId });
+}
+
+/** A ClinicalTrials.gov study */
+export interface TrialResult {
+  nct_id: string;
+  title: string;
+  /** e.g. "Phase 2", "Phase 1/Phase 2" */
+  phase?: string | null;
+  /** e.g. "RECRUITING", "COMPLETED" */
+  status: string;
+  enrollment?: number | null;
+  has_results: boolean;
+  conditions: string[];
+  interventions: string[];
+  sponsor?: string | null;
+  start_date?: string | null;
+  completion_date?: string | null;
+  protocol_ids: string[];
+  fetched_at: string;
+}
+
+export async function searchClinicalTrials(peptide: string, maxResults?: number) {
+  return invoke<TrialResult[]>("search_clinical_trials", { peptide, maxResults });
+}
+
+export async function listClinicalTrials(protocolId?: string) {
+  return invoke<TrialResult[]>("list_clinical_trials", { protocolId });
+}
+
+export async function linkClinicalTrial(nctId: string, protocolId: string) {
+  return invoke<TrialResult>("link_clinical_trial", { nctId, protocolId });
+}
+
+export async function unlinkClinicalTrial(nctId: string, protocolId: string) {
+  return invoke<TrialResult>("unlink_clinical_trial", { nctId, protocolId });
+}
+
+export async function deleteClinicalTrial(nctId: string) {
+  return invoke<void>("delete_clinical_trial", { nctId });
+}
+
+export interface AnswerSource {
+  number: number;
+  entryId: string;
+  title: string;
+  url?: string | null;
+  doi?: string | null;
+  score: number;
+  cited: boolean;
+}
+
+export interface LiteratureAnswer {
+  answer: string;
+  provider: string;
+  embeddingModel: string;
+  sources: AnswerSource[];
+  usage: AiUsage;
+}
+
+export async function askLiterature(question: string, topK?: number) {
+  return invoke<LiteratureAnswer>("ask_literature", { question, topK });
+}
+
+export async function searchLiterature(payload: SearchLiteraturePayload) {
+  return invoke<LiteratureSearchResult[]>("search_literature", { payload });
+}
+
+// Saved literature searches
+
+export interface SavedSearch {
+  id: string;
+  name: string;
+  query: string;
+  sources: string[];
+  max_results: number;
+  interval_days: number;
+  enabled: boolean;
+  seen_keys: string[];
+  last_run_at?: string | null;
+  last_new_count: number;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface SavedSearchPayload {
+  name: string;
+  query: string;
+  sources?: string[];
+  maxResults?: number;
+  intervalDays?: number;
+  enabled?: boolean;
+}
+
+export interface SavedSearchRun {
+  search: SavedSearch;
+  /** Results no earlier run had found */
+  newEntries: LiteratureEntry[];
+  alert?: Alert | null;
+  /** Offline; the search runs when the connection returns */
+  queued: boolean;
+}
+
+export async function listSavedSearches() {
+  return invoke<SavedSearch[]>("list_saved_searches");
+}
+
+export async function createSavedSearch(payload: SavedSearchPayload) {
+  return invoke<SavedSearch>("create_saved_search", { payload });
+}
+
+export async function updateSavedSearch(searchId: string, payload: SavedSearchPayload) {
+  return invoke<SavedSearch>("update_saved_search", { searchId, payload });
+}
+
+export async function deleteSavedSearch(searchId: string) {
+  return invoke<void>("delete_saved_search", { searchId });
+}
+
+export async function runSavedSearch(searchId: string) {
+  return invoke<SavedSearchRun>("run_saved_search", { searchId });
+}
+
+// Connectivity
+
+export interface ConnectivityStatus {
+  online: boolean;
+  lastCheckedAt: string | null;
+  /** Saved searches that will run when the connection returns */
+  queuedSearches: number;
+}
+
+export async function getConnectivityStatus() {
+  return invoke<ConnectivityStatus>("get_connectivity_status");
+}
+
+/** Probes the connection now */
+export async function checkConnectivity() {
+  return invoke<ConnectivityStatus>("check_connectivity");
+}
+
+/** Emitted when the app goes offline or comes back online */
+export async function onConnectivityChanged(handler: (status: ConnectivityStatus) => void): Promise<UnlistenFn> {
+  return listen<ConnectivityStatus>("connectivity-changed", (event) => handler(event.payload));
+}
+
+/** Proxy, CA certificates and timeout for every request the app makes */
+export interface HttpSettings {
+  /** e.g. `http://proxy.local:3128`; without one the proxy environment variables apply */
+  proxyUrl: string | null;
+  /** Comma-separated hosts that skip the proxy */
+  noProxy: string | null;
+  useEnvProxy: boolean;
+  /** PEM file of CA certificates to trust besides the system ones */
+  caCertPath: string | null;
+  timeoutSecs: number;
+}
+
+export async function getHttpSettings() {
+  return invoke<HttpSettings>("get_http_settings");
+}
+
+/** Saves the network settings and re-checks connectivity through them */
+export async function updateHttpSettings(settings: HttpSettings) {
+  return invoke<ConnectivityStatus>("update_http_settings", { settings });
+}
+
+// Dose logging types
+
+export interface DoseLog {
+  id: string;
+  protocol_id: string;
+  site: string;
+  amount_mg: number;
+  notes?: string | null;
+  logged_at: string;
+  schedule_id?: string | null;
+  /** Set once the dose has been edited */
+  edited_at?: string | null;
+}
+
+export interface LogDosePayload {
+  protocolId: string;
+  site: string;
+  amountMg: number;
+  notes?: string;
+  scheduleId?: string;
+  /** Unit `amountMg` was entered in; mg when not given */
+  unit?: DoseUnit;
+}
+
+export interface UpdateDosePayload {
+  logId: string;
+  protocolId: string;
+  site: string;
+  amountMg: number;
+  notes?: string;
+  /** RFC3339 */
+  loggedAt: string;
+  /** Unit `amountMg` was entered in; mg when not given */
+  unit?: DoseUnit;
+}
+
+/** A dose log as it was before one of its edits */
+export interface DoseLogCorrection {
+  id: string;
+  dose_log_id: string;
+  original: DoseLog;
+  corrected_at: string;
+}
+
+/** Dose log with its amount in the preferred unit; `amount_mg` stays in mg */
+export interface DoseLogView extends DoseLog {
+  displayAmount: number;
+  displayUnit: DoseUnit;
+}
+
+/** Filters for dose stats; dates are ISO strings and both days are included */
+export interface DoseStatsPayload {
+  protocolId?: string;
+  scheduleId?: string;
+  startDate?: string;
+  endDate?: string;
+  /** Leave out doses edited after they were logged */
+  excludeEdited?: boolean;
+}
+
+export interface ProtocolDoseUsage {
+  protocolId: string;
+  doseCount: number;
+  totalMg: number;
+  activeDays: number;
+}
+
+export interface SiteDoseUsage {
+  /** Coarse site such as "left_abdomen", or "other" */
+  siteCode: string;
+  doseCount: number;
+  totalMg: number;
+}
+
+export interface DailyDoseTotal {
+  /** YYYY-MM-DD */
+  date: string;
+  doseCount: number;
+  totalMg: number;
+}
+
+export interface DoseStats {
+  byProtocol: ProtocolDoseUsage[];
+  bySite: SiteDoseUsage[];
+  daily: DailyDoseTotal[];
+}
+
+// Dose logging API calls
+
+export async function logDose(payload: LogDosePayload) {
+  return invoke<DoseLogView>("log_dose", { payload });
+}
+
+export interface BulkDoseEntry {
+  protocolId: string;
+  site: string;
+  amountMg: number;
+  notes?: string;
+  /** RFC3339; must not be in the future */
+  loggedAt: string;
+  /** Found from the day when not given */
+  scheduleId?: string;
+  /** Unit `amountMg` was entered in; mg when not given */
+  unit?: DoseUnit;
+}
+
+/** Outcome of one entry, in the order the entries were sent */
+export interface BulkDoseResult {
+  index: number;
+  dose?: DoseLogView | null;
+  error?: string | null;
+}
+
+/**
+ * Log many past doses at once; entries that fail their checks are reported
+ * and the rest are saved together
+ */
+export async function bulkLogDoses(entries: BulkDoseEntry[], allowUnscheduled = false) {
+  return invoke<BulkDoseResult[]>("bulk_log_doses", { payload: { entries, allowUnscheduled } });
+}
+
+/** A saved dose that can be logged in one click */
+export interface DosePreset {
+  /** Empty for a new preset */
+  id: string;
+  name: string;
+  protocolId: string;
+  amountMg: number;
+  site: string;
+  /** When the dose is usually taken, e.g. "Morning" */
+  timeOfDay?: string | null;
+  notes?: string | null;
+  favorite: boolean;
+}
+
+/** Saved presets, favorites first */
+export async function listDosePresets() {
+  return invoke<DosePreset[]>("list_dose_presets");
+}
+
+/** Save a preset with `amountMg` entered in `unit`; returns every preset */
+export async function saveDosePreset(preset: DosePreset, unit?: DoseUnit) {
+  return invoke<DosePreset[]>("save_dose_preset", { preset, unit });
+}
+
+export async function deleteDosePreset(presetId: string) {
+  return invoke<DosePreset[]>("delete_dose_preset", { presetId });
+}
+
+/**
+ * Log a dose now from a preset; without an id, logs the first favorite
+ * preset (or the only one)
+ */
+export async function logDoseFromPreset(presetId?: string) {
+  return invoke<DoseLogView>("log_dose_from_preset", { presetId });
+}
+
+/** Log the most recent dose again at the current time */
+export async function logLastDoseAgain() {
+  return invoke<DoseLogView>("log_last_dose_again");
+}
+
+/** Edit a dose; its previous values are kept as a correction */
+export async function updateDoseLog(payload: UpdateDosePayload) {
+  return invoke<DoseLogView>("update_dose_log", { payload });
+}
+
+/** A dose's values before each of its edits, oldest first */
+export async function listDoseLogCorrections(logId: string) {
+  return invoke<DoseLogCorrection[]>("list_dose_log_corrections", { logId });
+}
+
+export async function listDoseLogs() {
+  return invoke<DoseLogView[]>("list_dose_logs");
+}
+
+export async function listDoseLogsForProtocol(protocolId: string) {
+  return invoke<DoseLogView[]>("list_dose_logs_for_protocol", { protocolId });
+}
+
+export async function getDoseStats(payload?: DoseStatsPayload) {
+  return invoke<DoseStats>("get_dose_stats", { payload });
+}
+
+// Dashboard stats, cached in the database until their data changes
+
+export interface StatValue<T> {
+  value: T;
+  computedAt: string;
+  /** Served from the stats cache rather than computed for this call */
+  cached: boolean;
+}
+
+export interface DashboardStats {
+  dosesThisWeek: StatValue<{ doseCount: number; totalMg: number; activeDays: number }>;
+  adherence: StatValue<{
+    windowDays: number;
+    expected: number;
+    logged: number;
+    /** Due doses skipped from a reminder; still counted as missed */
+    skipped: number;
+    percent: number | null;
+  }>;
+  spend: StatValue<{
+    currency: string;
+    totalSpend: number;
+    thisMonthSpend: number;
+    projectedMonthlyCost: number;
+  }>;
+  inventory: StatValue<{
+    protocolsTracked: number;
+    minDaysRemaining: number | null;
+    minDaysProtocolName: string | null;
+    reorderDue: number;
+  }>;
+  goals: StatValue<{
+    active: number;
+    achieved: number;
+    /** Goals not yet achieved, soonest target date first */
+    goals: GoalWithProgress[];
+  }>;
+}
+
+export async function getDashboardStats(refresh = false) {
+  return invoke<DashboardStats>("get_dashboard_stats", { refresh });
+}
+
+export interface TodaysDose extends DoseSchedule {
+  /** Whether a dose was logged for it today */
+  taken: boolean;
+}
+
+/** Alerts that haven't been dismissed */
+export interface AlertCounts {
+  info: number;
+  warning: number;
+  critical: number;
+  unread: number;
+}
+
+export interface BackupStatus {
+  enabled: boolean;
+  lastBackup: string | null;
+  nextBackup: string | null;
+  /** Whether the most recent backup succeeded; null before the first one */
+  lastSucceeded: boolean | null;
+  isRunning: boolean;
+  /** Destinations that have gone too long without a successful backup */
+  overdue: BackupDestination[];
+}
+
+/** Everything the home screen shows */
+export interface Dashboard {
+  /** Enabled schedules due today, earliest first */
+  todaysDoses: TodaysDose[];
+  /** Latest dose logs, newest first */
+  recentDoses: DoseLogView[];
+  alerts: AlertCounts;
+  /** Vials expected to run out within two weeks */
+  lowInventory: InventoryPrediction[];
+  latestBodyMetric: BodyMetricView | null;
+  backup: BackupStatus;
+  ai: AiAvailabilityStatus;
+}
+
+/** Loads the home screen in one call */
+export async function getDashboard() {
+  return invoke<Dashboard>("get_dashboard");
+}
+
+// Undo for deletes and bulk edits made this session
+
+export interface UndoListItem {
+  id: string;
+  /** What was done, e.g. "Delete 3 dose logs" */
+  label: string;
+  /** Records undoing it puts back */
+  records: number;
+  performedAt: string;
+}
+
+export interface UndoResult {
+  label: string;
+  restored: number;
+}
+
+/** Most recent first */
+export async function listUndoOperations() {
+  return invoke<UndoListItem[]>("list_undo_operations");
+}
+
+export async function undoLastOperation() {
+  return invoke<UndoResult>("undo_last_operation");
+}
+
+// Estimated active levels (first-order decay from catalog half-lives)
+
+export interface ActiveLevelPoint {
+  at: string;
+  amountMg: number;
+}
+
+export interface PeptideActiveLevel {
+  peptideName: string;
+  halfLifeHours: number;
+  currentMg: number;
+  lastDoseAt: string | null;
+  /** Five half-lives after the last dose */
+  washoutAt: string | null;
+  points: ActiveLevelPoint[];
+}
+
+export interface ActiveLevels {
+  start: string;
+  end: string;
+  stepHours: number;
+  peptides: PeptideActiveLevel[];
+  /** Dosed peptides the catalog has no half-life for */
+  unknownHalfLife: string[];
+}
+
+/** Defaults to the last 30 days and the next 14 */
+export async function getActiveLevels(startDate?: string, endDate?: string, stepHours?: number) {
+  return invoke<ActiveLevels>("get_active_levels", {
+    startDate: startDate || null,
+    endDate: endDate || null,
+    stepHours: stepHours ?? null,
+  });
+}
+
+export async function deleteDoseLog(logId: string) {
+  return invoke<void>("delete_dose_log", { logId });
+}
+
+// AI availability types
+
+export interface AiAvailabilityStatus {
+  codexAvailable: boolean;
+  claudeAvailable: boolean;
+  anyAvailable: boolean;
+  preferredProvider?: string | null;
+}
+
+// AI availability check
+
+export async function checkAiAvailability() {
+  return invoke<AiAvailabilityStatus>("check_ai_availability");
+}
+
+// Backup types
 
 export interface BackupMetadata {
   exportDate: string;
@@ -383,6 +1670,10 @@ export interface BackupData {
   protocols: PeptideProtocol[];
   doseLogs: DoseLog[];
   literature: LiteratureEntry[];
+  /** Missing from backups made before body metrics were included */
+  bodyMetrics?: BodyMetric[];
+  /** Missing from backups made before the journal existed */
+  journalEntries?: JournalEntry[];
 }
 
 // Backup API calls
@@ -395,6 +1686,41 @@ export async function getBackupFilePath() {
   return invoke<string>("get_backup_file_path");
 }
 
+export interface ExportedTable {
+  name: string;
+  rows: number;
+}
+
+export interface AnalyticsExport {
+  path: string;
+  tables: ExportedTable[];
+}
+
+/** Write all data, unencrypted, to a new SQLite file for analysis */
+export async function exportAnalyticsDataset(filePath: string, acknowledgeUnencrypted: boolean) {
+  return invoke<AnalyticsExport>("export_analytics_dataset", { filePath, acknowledgeUnencrypted });
+}
+
+export interface ExportFhirPayload {
+  filePath: string;
+  /** Shown as the patient's name in the clinician's system */
+  patientName: string;
+  /** RFC3339; both days are included and either may be left open */
+  startDate?: string;
+  endDate?: string;
+}
+
+export interface FhirExport {
+  path: string;
+  medicationAdministrations: number;
+  observations: number;
+}
+
+/** Write doses, body metrics and lab results as an HL7 FHIR R4 JSON bundle */
+export async function exportFhirBundle(payload: ExportFhirPayload) {
+  return invoke<FhirExport>("export_fhir_bundle", { payload });
+}
+
 // Google Drive types
 
 export interface DriveOAuthConfig {
@@ -448,6 +1774,23 @@ export async function uploadToDrive(filename: string, content: string) {
   return invoke<string>("upload_to_drive", { filename, content });
 }
 
+export interface DriveStorageInfo {
+  /** Null for unlimited accounts */
+  limitBytes: number | null;
+  usageBytes: number;
+  usageInTrashBytes: number;
+  remainingBytes: number | null;
+  backupCount: number;
+  backupBytes: number;
+  latestBackupBytes: number | null;
+  /** Whether a backup the size of the newest one would fit */
+  nextBackupFits: boolean;
+}
+
+export async function getDriveStorageInfo() {
+  return invoke<DriveStorageInfo>("get_drive_storage_info");
+}
+
 export async function openExternalLink(url: string) {
   return invoke<void>("open_external_url", { url });
 }
@@ -460,7 +1803,23 @@ export type BackupFrequency =
   | "Manual"
   | { DailyAt: { hour: number } };
 
-export type BackupDestination = "Local" | "GoogleDrive";
+export type BackupDestination = "Local" | "GoogleDrive" | "NetworkFolder";
+
+/** A mounted SMB/NFS share used as a backup destination */
+export interface NetworkFolderSettings {
+  path: string;
+  /** Separate from the local cleanup rules */
+  cleanupSettings: CleanupSettings;
+}
+
+export interface NetworkFolderStatus {
+  path: string;
+  /** The folder exists, i.e. the share is mounted */
+  available: boolean;
+  writable: boolean;
+  freeBytes: number | null;
+  error: string | null;
+}
 
 export interface CleanupSettings {
   enabled: boolean;
@@ -478,6 +1837,31 @@ export interface BackupSchedule {
   compress?: boolean;
   cleanupSettings?: CleanupSettings;
   maxRetries?: number;
+  /** Folder for local backups; Downloads when unset. Created when saved */
+  backupDir?: string | null;
+  networkFolder?: NetworkFolderSettings | null;
+  /** Alert when a destination has gone this many days without a successful backup */
+  overdueAfterDays?: number;
+  /** Kept by the scheduler; ignored when the schedule is saved */
+  destinationStatus?: DestinationStatus[];
+}
+
+export interface DestinationStatus {
+  destination: BackupDestination;
+  lastSuccess?: string | null;
+  lastFailure?: string | null;
+  lastError?: string | null;
+  /** When the current run of failures began */
+  failingSince?: string | null;
+}
+
+export interface DestinationResult {
+  destination: BackupDestination;
+  success: boolean;
+  errorMessage?: string | null;
+  sizeBytes?: number | null;
+  /** File path or Drive file id */
+  location?: string | null;
 }
 
 export interface BackupHistoryEntry {
@@ -487,6 +1871,30 @@ export interface BackupHistoryEntry {
   errorMessage?: string | null;
   sizeBytes?: number | null;
   compressed: boolean;
+  destinationResults?: DestinationResult[];
+  /** Cloud destinations left out because the app was offline */
+  skippedOffline?: BackupDestination[];
+  /** Outcome of the last deep verify */
+  verification?: BackupVerification | null;
+}
+
+export interface CountMismatch {
+  table: BackupTable;
+  expected: number;
+  restored: number;
+}
+
+/** Outcome of restoring a backup into a scratch database */
+export interface BackupVerification {
+  verifiedAt: string;
+  /** The backup file that was restored */
+  location: string;
+  /** Restored intact and with every record */
+  verified: boolean;
+  integrityProblems: string[];
+  mismatches: CountMismatch[];
+  /** Why the backup couldn't be restored at all */
+  error?: string | null;
 }
 
 export interface BackupProgress {
@@ -500,6 +1908,11 @@ export interface RestoreCounts {
   protocols: number;
   doseLogs: number;
   literature: number;
+  bodyMetrics: number;
+  journalEntries: number;
+  attachments: number;
+  /** Records that couldn't be read and were left out */
+  skipped: number;
 }
 
 export interface RestoreResult {
@@ -513,6 +1926,38 @@ export interface BackupPreview {
   protocolsCount: number;
   doseLogsCount: number;
   literatureCount: number;
+  bodyMetricsCount: number;
+  journalEntriesCount: number;
+  tables: TableSummary[];
+}
+
+export type BackupTable =
+  | "protocols"
+  | "dose_logs"
+  | "literature"
+  | "body_metrics"
+  | "journal_entries"
+  | "attachments";
+
+export interface TableSummary {
+  table: BackupTable;
+  count: number;
+  /** Records that can't be read and won't be restored */
+  unreadable: number;
+  earliest?: string | null;
+  latest?: string | null;
+}
+
+export interface BackupRecordSummary {
+  id: string;
+  label: string;
+  date?: string | null;
+}
+
+/** Part of a backup to restore; the whole table when `ids` is omitted */
+export interface TableSelection {
+  table: BackupTable;
+  ids?: string[] | null;
 }
 
 // Scheduled Backup API calls
@@ -525,6 +1970,10 @@ export async function updateBackupSchedule(schedule: BackupSchedule) {
   return invoke<BackupSchedule>("update_backup_schedule", { schedule });
 }
 
+export async function checkNetworkFolder(path: string) {
+  return invoke<NetworkFolderStatus>("check_network_folder", { path });
+}
+
 export async function triggerManualBackup() {
   return invoke<string>("trigger_manual_backup");
 }
@@ -533,20 +1982,338 @@ export async function getBackupHistory() {
   return invoke<BackupHistoryEntry[]>("get_backup_history");
 }
 
+/** Restore a backup from the history into a scratch database and check it */
+export async function deepVerifyBackup(timestamp: string, password?: string) {
+  return invoke<BackupHistoryEntry>("deep_verify_backup", { timestamp, password });
+}
+
 export async function getBackupProgress() {
   return invoke<BackupProgress>("get_backup_progress");
 }
 
 // Restore API calls
 
-export async function restoreFromBackup(filePath: string, password?: string) {
-  return invoke<RestoreResult>("restore_from_backup", { filePath, password: password || null });
+export async function restoreFromBackup(
+  filePath: string,
+  password?: string,
+  selection?: TableSelection[],
+) {
+  return invoke<RestoreResult>("restore_from_backup", {
+    filePath,
+    password: password || null,
+    selection: selection ?? null,
+  });
+}
+
+export async function listBackupRecords(filePath: string, table: BackupTable, password?: string) {
+  return invoke<BackupRecordSummary[]>("list_backup_records", {
+    filePath,
+    password: password || null,
+    table,
+  });
 }
 
 export async function previewBackup(filePath: string, password?: string) {
   return invoke<BackupPreview>("preview_backup", { filePath, password: password || null });
 }
 
+// End-to-end encrypted sync
+
+/** Where change sets are stored; the secret is kept apart from the settings */
+export type SyncRelay =
+  | { kind: "http"; url: string }
+  | { kind: "s3"; endpoint: string; bucket: string; region: string; accessKeyId: string };
+
+export interface SyncSettings {
+  enabled: boolean;
+  relay?: SyncRelay | null;
+  deviceId?: string | null;
+  deviceName: string;
+  lastSynced?: string | null;
+}
+
+export interface SyncCursor {
+  deviceId: string;
+  /** Change sets uploaded, for this device, or applied, for the others */
+  changeSets: number;
+  auditSeq: number;
+  syncedAt?: string | null;
+}
+
+export interface SyncStatus {
+  settings: SyncSettings;
+  secretSaved: boolean;
+  syncKeySaved: boolean;
+  cursors: SyncCursor[];
+}
+
+export interface SyncDevice {
+  id: string;
+  name: string;
+  registeredAt: string;
+}
+
+export interface SyncReport {
+  uploadedChangeSets: number;
+  uploadedChanges: number;
+  downloadedChangeSets: number;
+  saved: number;
+  deleted: number;
+  skipped: number;
+  devices: number;
+}
+
+export async function getSyncStatus() {
+  return invoke<SyncStatus>("get_sync_status");
+}
+
+/**
+ * Register this device with a relay and turn sync on. Pass the sync key
+ * shown on another device to join it; an empty secret removes the saved one.
+ */
+export async function registerSyncDevice(
+  relay: SyncRelay,
+  deviceName: string,
+  secret?: string,
+  syncKey?: string,
+) {
+  return invoke<SyncStatus>("register_sync_device", {
+    relay,
+    deviceName,
+    secret: secret ?? null,
+    syncKey: syncKey || null,
+  });
+}
+
+export async function setSyncEnabled(enabled: boolean) {
+  return invoke<SyncStatus>("set_sync_enabled", { enabled });
+}
+
+export async function getSyncKey() {
+  return invoke<string>("get_sync_key");
+}
+
+export async function listSyncDevices() {
+  return invoke<SyncDevice[]>("list_sync_devices");
+}
+
+export async function syncNow() {
+  return invoke<SyncReport>("sync_now");
+}
+
+// Import from other trackers
+
+export type ImportFormat = "csv" | "json";
+export type ImportTarget = "protocols" | "dose_logs" | "body_metrics";
+export type DoseUnitOption = "mg" | "mcg" | "iu";
+
+export interface ImportProfile {
+  name: string;
+  format: ImportFormat;
+  target: ImportTarget;
+  /** PepTrack field to source column or JSON key (nested keys joined with ".") */
+  fields: Record<string, string>;
+  /** e.g. "[month]/[day]/[year] [hour]:[minute]"; ISO 8601 when omitted */
+  dateFormat?: string | null;
+  doseUnit?: DoseUnitOption;
+  weightUnit?: "kg" | "lb";
+  recordsKey?: string | null;
+}
+
+export interface ImportRowError {
+  row: number;
+  field?: string | null;
+  message: string;
+}
+
+export interface ImportPreview {
+  target: ImportTarget;
+  totalRows: number;
+  newRecords: number;
+  newProtocols: string[];
+  duplicates: number;
+  errorCount: number;
+  errors: ImportRowError[];
+  sample: unknown[];
+}
+
+export interface ImportResult {
+  target: ImportTarget;
+  imported: number;
+  protocolsCreated: number;
+  duplicates: number;
+  skipped: number;
+}
+
+export async function listImportColumns(filePath: string, format: ImportFormat, recordsKey?: string) {
+  return invoke<string[]>("list_import_columns", { filePath, format, recordsKey: recordsKey || null });
+}
+
+export async function listImportProfiles() {
+  return invoke<ImportProfile[]>("list_import_profiles");
+}
+
+export async function saveImportProfile(profile: ImportProfile) {
+  return invoke<ImportProfile[]>("save_import_profile", { profile });
+}
+
+export async function deleteImportProfile(name: string) {
+  return invoke<ImportProfile[]>("delete_import_profile", { name });
+}
+
+export async function previewImport(filePath: string, profile: ImportProfile) {
+  return invoke<ImportPreview>("preview_import", { filePath, profile });
+}
+
+export async function commitImport(filePath: string, profile: ImportProfile, skipInvalid: boolean) {
+  return invoke<ImportResult>("commit_import", { filePath, profile, skipInvalid });
+}
+
+// Corruption recovery
+
+export interface BackupFileInfo {
+  path: string;
+  modifiedAt: string;
+  sizeBytes: number;
+}
+
+export interface RecoveryStatus {
+  corrupted: boolean;
+  integrityResult: string | null;
+  /** Newest local backup, offered as an alternative to salvaging */
+  latestBackup: BackupFileInfo | null;
+}
+
+export interface TableRecovery {
+  table: string;
+  recovered: number;
+  /** Rows that couldn't be read; null when the damaged table couldn't be counted */
+  lost: number | null;
+}
+
+export interface SalvageReport {
+  id: string;
+  createdAt: string;
+  tables: TableRecovery[];
+  recoveredRows: number;
+  lostRows: number;
+  /** Whether the salvaged database passed an integrity check */
+  verified: boolean;
+}
+
+/** Emitted before each table is salvaged, and once with an empty table at the end */
+export interface RecoveryProgress {
+  table: string;
+  tablesDone: number;
+  tablesTotal: number;
+}
+
+export interface BackupRecoveryResult {
+  /** Where the damaged database was kept */
+  damagedCopy: string;
+  restore: RestoreResult;
+}
+
+export async function getRecoveryStatus() {
+  return invoke<RecoveryStatus>("get_recovery_status");
+}
+
+/** Copy every readable row into a new database; the current one is untouched */
+export async function salvageDatabase() {
+  return invoke<SalvageReport>("salvage_database");
+}
+
+/** Switch to a salvaged database; returns where the damaged one was kept */
+export async function applySalvagedDatabase(salvageId: string) {
+  return invoke<string>("apply_salvaged_database", { salvageId });
+}
+
+/** Start from an empty database and restore a backup, the latest local one by default */
+export async function recoverFromBackup(filePath?: string, password?: string) {
+  return invoke<BackupRecoveryResult>("recover_from_backup", {
+    filePath: filePath || null,
+    password: password || null,
+  });
+}
+
+export async function onDatabaseRecoveryProgress(
+  handler: (progress: RecoveryProgress) => void,
+): Promise<UnlistenFn> {
+  return listen<RecoveryProgress>("database-recovery-progress", (event) => handler(event.payload));
+}
+
+// ========== Read-only Viewer ==========
+
+export interface ViewerSessionInfo {
+  fileName: string;
+  exportDate: string;
+  appVersion: string;
+  /** Names are pseudonyms and notes were removed */
+  anonymized: boolean;
+  protocolsCount: number;
+  doseLogsCount: number;
+  bodyMetricsCount: number;
+  /** False for older exports, which can't show adherence */
+  hasSchedules: boolean;
+  firstDoseDate?: string | null;
+  lastDoseDate?: string | null;
+}
+
+export interface ViewerProtocolRow {
+  name: string;
+  peptideName: string;
+  doseCount: number;
+  totalMg: number;
+  lastDose?: string | null;
+  scheduled: boolean;
+}
+
+export interface ViewerAdherenceRow {
+  protocolName: string;
+  expected: number;
+  logged: number;
+  percent: number;
+}
+
+export interface ViewerSummary {
+  startDate: string;
+  endDate: string;
+  totalDoses: number;
+  overallAdherence?: number | null;
+  protocols: ViewerProtocolRow[];
+  adherence: ViewerAdherenceRow[];
+}
+
+/** Opens an export read-only; `password` is the export's own passphrase */
+export async function openViewerSession(filePath: string, password?: string) {
+  return invoke<ViewerSessionInfo>("open_viewer_session", { filePath, password: password || null });
+}
+
+export async function getViewerSession() {
+  return invoke<ViewerSessionInfo | null>("get_viewer_session");
+}
+
+export async function closeViewerSession() {
+  return invoke<void>("close_viewer_session");
+}
+
+export async function listViewerProtocols() {
+  return invoke<PeptideProtocol[]>("list_viewer_protocols");
+}
+
+export async function listViewerDoseLogs() {
+  return invoke<DoseLog[]>("list_viewer_dose_logs");
+}
+
+export async function listViewerBodyMetrics() {
+  return invoke<BodyMetric[]>("list_viewer_body_metrics");
+}
+
+/** Dates are RFC3339; both days are included */
+export async function getViewerSummary(startDate: string, endDate: string) {
+  return invoke<ViewerSummary>("get_viewer_summary", { startDate, endDate });
+}
+
 // Supplier types
 
 export interface Supplier {
@@ -556,10 +2323,49 @@ export interface Supplier {
   contact_phone?: string | null;
   website?: string | null;
   notes?: string | null;
+  /** Oldest first; at most one per order */
+  reviews?: SupplierReview[];
   created_at: string;
   updated_at: string;
 }
 
+/** Ratings from 1 to 5 for one order, or for the supplier in general */
+export interface SupplierReview {
+  id: string;
+  order_id?: string | null;
+  shipping_speed: number;
+  product_quality: number;
+  communication: number;
+  notes?: string | null;
+  reviewed_at: string;
+}
+
+export interface SupplierReviewPayload {
+  orderId?: string | null;
+  shippingSpeed: number;
+  productQuality: number;
+  communication: number;
+  notes?: string;
+}
+
+export interface SupplierRating {
+  shipping_speed: number;
+  product_quality: number;
+  communication: number;
+  overall: number;
+  review_count: number;
+}
+
+export interface SupplierRanking {
+  supplier_id: string;
+  supplier_name: string;
+  rating?: SupplierRating | null;
+  /** Latest price per mg, when ranking for a peptide */
+  cost_per_mg?: number | null;
+  /** 0 to 100 */
+  score: number;
+}
+
 export interface CreateSupplierPayload {
   name: string;
   contactEmail?: string;
@@ -598,6 +2404,9 @@ export interface InventoryItem {
   notes?: string | null;
   created_at: string;
   updated_at: string;
+  /** When the powder was mixed; it then keeps until reconstituted_expiry */
+  reconstituted_at?: string | null;
+  reconstituted_expiry?: string | null;
 }
 
 export interface CreateInventoryPayload {
@@ -658,6 +2467,28 @@ export async function deleteSupplier(supplierId: string) {
   return invoke<void>("delete_supplier", { supplierId });
 }
 
+export interface SupplierDeletion {
+  suppliers_deleted: number;
+  /** Price history is deleted with its supplier */
+  price_history_deleted: number;
+  /** Inventory kept without a supplier */
+  inventory_unlinked: number;
+  /** Inventory deleted with its supplier, see CascadePolicies */
+  inventory_deleted: number;
+}
+
+export async function recordSupplierReview(supplierId: string, payload: SupplierReviewPayload) {
+  return invoke<Supplier>("record_supplier_review", { supplierId, payload });
+}
+
+export async function deleteSupplierReview(supplierId: string, reviewId: string) {
+  return invoke<Supplier>("delete_supplier_review", { supplierId, reviewId });
+}
+
+export async function bulkDeleteSuppliers(supplierIds: string[]) {
+  return invoke<SupplierDeletion>("bulk_delete_suppliers", { supplierIds });
+}
+
 // Inventory API calls
 
 export async function createInventoryItem(payload: CreateInventoryPayload) {
@@ -687,6 +2518,76 @@ export async function deleteInventoryItem(itemId: string) {
   return invoke<void>("delete_inventory_item", { itemId });
 }
 
+export async function bulkUpdateInventoryStatus(itemIds: string[], status: VialStatus) {
+  return invoke<number>("bulk_update_inventory_status", { itemIds, status });
+}
+
+export async function bulkDeleteInventoryItems(itemIds: string[]) {
+  return invoke<number>("bulk_delete_inventory_items", { itemIds });
+}
+
+/** Pass `null` to clear the supplier */
+export async function bulkAssignSupplier(itemIds: string[], supplierId: string | null) {
+  return invoke<number>("bulk_assign_supplier", { itemIds, supplierId });
+}
+
+/**
+ * Record mixing a vial, now unless `reconstitutedAt` (RFC3339) is given.
+ * Without `stabilityDays` the peptide's catalog fridge life is used.
+ */
+export async function reconstituteInventoryItem(
+  itemId: string,
+  reconstitutedAt?: string,
+  stabilityDays?: number
+) {
+  return invoke<InventoryItem>("reconstitute_inventory_item", { itemId, reconstitutedAt, stabilityDays });
+}
+
+export type ExpiryKind = "dryPowder" | "reconstituted";
+
+export interface ExpiryEvent {
+  inventoryId: string;
+  protocolId: string;
+  vialNumber?: string | null;
+  kind: ExpiryKind;
+  expiresAt: string;
+  remainingMg?: number | null;
+}
+
+export interface ExpiryDay {
+  /** YYYY-MM-DD */
+  date: string;
+  events: ExpiryEvent[];
+}
+
+/** Vials expiring between the dates (RFC3339), today through 90 days out by default */
+export async function getExpiryCalendar(startDate?: string, endDate?: string) {
+  return invoke<ExpiryDay[]>("get_expiry_calendar", { startDate, endDate });
+}
+
+// ========== Vial Labels ==========
+
+export interface VialLabel {
+  inventoryId: string;
+  qrContent: string;
+  svg: string;
+}
+
+export interface ResolvedVial {
+  item: InventoryItem;
+  protocol?: PeptideProtocol | null;
+}
+
+/** Printable SVG label whose QR code resolves back to the vial */
+export async function generateVialLabel(itemId: string) {
+  return invoke<VialLabel>("generate_vial_label", { itemId });
+}
+
+/** The vial behind scanned label text, or a bare inventory id */
+export async function resolveVialQr(content: string) {
+  return invoke<ResolvedVial>("resolve_vial_qr", { content });
+}
+
 // ========== Analytics & Price History ==========
 
 export interface PriceHistory {
@@ -698,6 +2599,17 @@ export interface PriceHistory {
   in_stock?: boolean | null;
   notes?: string | null;
   recorded_at: string;
+  vial_size_mg?: number | null;
+  /** Flat shipping per order, in the price's currency */
+  shipping_cost?: number | null;
+  min_order_vials?: number | null;
+  bulk_discounts?: BulkDiscountTier[];
+}
+
+/** Discount off the vial price when ordering at least `min_vials` at once */
+export interface BulkDiscountTier {
+  min_vials: number;
+  discount_percent: number;
 }
 
 export interface AddPricePayload {
@@ -707,22 +2619,47 @@ export interface AddPricePayload {
   url?: string;
   inStock?: boolean;
   notes?: string;
+  vialSizeMg?: number;
+  shippingCost?: number;
+  minOrderVials?: number;
+  bulkDiscounts?: BulkDiscountTier[];
 }
 
 export interface PriceComparison {
-  peptide_name: string;
+  peptideName: string;
+  currency: string;
+  /** Order size the landed costs are for; null means each supplier's minimum order */
+  orderMg: number | null;
+  /** Cheapest landed cost first */
   suppliers: SupplierPrice[];
-  lowest_price: number;
-  highest_price: number;
-  average_price: number;
+  lowestPrice: number;
+  highestPrice: number;
+  averagePrice: number;
+  missingRates: string[];
+  /** RFC3339 time the comparison was computed */
+  computedAt: string;
+  /** Served from the short-lived cache */
+  cached: boolean;
 }
 
 export interface SupplierPrice {
-  supplier_id: string;
-  supplier_name: string;
-  cost_per_mg: number;
-  in_stock?: boolean | null;
-  recorded_at: string;
+  supplierId: string;
+  supplierName: string;
+  costPerMg: number;
+  originalCostPerMg: number;
+  originalCurrency: string;
+  /** Per mg of the order with shipping and bulk discounts */
+  landedCostPerMg: number;
+  orderTotal: number;
+  orderQuantityMg: number;
+  vials: number | null;
+  discountPercent: number;
+  inStock?: boolean | null;
+  recordedAt: string;
+  /** Whole days since the price was recorded */
+  ageDays: number;
+  /** Older than 30 days */
+  stale: boolean;
 }
 
 // Price History API calls
@@ -748,8 +2685,60 @@ export async function getLatestPrice(supplierId: string, peptideName: string) {
   });
 }
 
-export async function comparePrices(peptideName: string) {
-  return invoke<PriceComparison>("compare_prices", { peptideName });
+/**
+ * Compares landed costs for an order of `orderMg`, or each supplier's minimum order.
+ * Results are cached for a few minutes unless `refresh` is set.
+ */
+export async function comparePrices(
+  peptideName: string,
+  displayCurrency?: string,
+  orderMg?: number,
+  refresh?: boolean,
+) {
+  return invoke<PriceComparison>("compare_prices", { peptideName, displayCurrency, orderMg, refresh });
+}
+
+/** Which prices to chart; dates are RFC3339 strings and both days are included */
+export interface PriceTrendPayload {
+  supplierId?: string;
+  peptideName?: string;
+  startDate?: string;
+  endDate?: string;
+  /** Most points per series, 60 by default */
+  maxPoints?: number;
+  /** Series without an exchange rate to this currency are left out */
+  displayCurrency?: string;
+}
+
+export interface PriceTrendPoint {
+  /** First day of the bucket as YYYY-MM-DD */
+  start: string;
+  minCostPerMg: number;
+  maxCostPerMg: number;
+  avgCostPerMg: number;
+  entries: number;
+}
+
+export interface PriceTrendSeries {
+  supplierId: string;
+  peptideName: string;
+  currency: string;
+  points: PriceTrendPoint[];
+}
+
+export interface PriceTrend {
+  /** Days covered by each point */
+  bucketDays: number;
+  series: PriceTrendSeries[];
+}
+
+export async function getPriceTrend(payload?: PriceTrendPayload) {
+  return invoke<PriceTrend>("get_price_trend", { payload });
+}
+
+/** With a peptide, only suppliers with a price for it are ranked */
+export async function rankSuppliers(peptideName?: string, displayCurrency?: string) {
+  return invoke<SupplierRanking[]>("rank_suppliers", { peptideName, displayCurrency });
 }
 
 // ========== Website Scraper ==========
@@ -767,6 +2756,68 @@ export async function scrapeSupplierWebsite(url: string, peptideName?: string) {
   });
 }
 
+/** How supplier pages are fetched */
+export interface ScrapingSettings {
+  userAgent: string;
+  /** Skip pages a site's robots.txt disallows */
+  respectRobotsTxt: boolean;
+  /** Minimum seconds between requests to one site; a longer Crawl-delay wins */
+  domainDelaySecs: number;
+  /** Most pages one price check fetches; the rest wait for the next run */
+  crawlBudget: number;
+  /** Render pages whose HTML has no prices in a hidden browser; needs a build with the js-render feature */
+  renderJavascript: boolean;
+}
+
+export async function getScrapingSettings() {
+  return invoke<ScrapingSettings>("get_scraping_settings");
+}
+
+export async function updateScrapingSettings(settings: ScrapingSettings) {
+  return invoke<ScrapingSettings>("update_scraping_settings", { settings });
+}
+
+// ========== Price Review Queue ==========
+
+export type ObservationStatus = "pending" | "accepted" | "rejected";
+
+/** One price the scraper found on a page */
+export interface ObservedPrice {
+  cost_per_mg: number;
+  /** Text around the price on the page */
+  context: string;
+  pattern_type: string;
+}
+
+/** A price monitor scrape waiting for review before it becomes price history */
+export interface PriceObservation {
+  id: string;
+  supplier_id: string;
+  peptide_name: string;
+  url: string;
+  currency: string;
+  in_stock?: boolean | null;
+  /** Lowest first; empty when the page only showed stock status */
+  matches: ObservedPrice[];
+  status: ObservationStatus;
+  observed_at: string;
+  reviewed_at?: string | null;
+  price_history_id?: string | null;
+}
+
+export async function listPriceObservations(status?: ObservationStatus) {
+  return invoke<PriceObservation[]>("list_price_observations", { status });
+}
+
+/** Records one of the observed prices, the lowest by default, as price history */
+export async function acceptPriceObservation(observationId: string, matchIndex?: number) {
+  return invoke<PriceHistory>("accept_price_observation", { observationId, matchIndex });
+}
+
+export async function rejectPriceObservation(observationId: string) {
+  return invoke<PriceObservation>("reject_price_observation", { observationId });
+}
+
 // ========== Alerts System ==========
 
 export type AlertType =
@@ -775,7 +2826,11 @@ export type AlertType =
   | "expired"
   | "price_increase"
   | "price_decrease"
-  | "out_of_stock";
+  | "out_of_stock"
+  | "new_literature"
+  | "retraction"
+  | "erratum"
+  | "backup_overdue";
 
 export type AlertSeverity = "info" | "warning" | "critical";
 
@@ -823,6 +2878,175 @@ export async function clearAllAlerts() {
   return invoke<void>("clear_all_alerts");
 }
 
+/** Repeats of one alert: the same type about the same item */
+export interface AlertThread {
+  alertType: AlertType;
+  relatedId?: string | null;
+  /** Most recent alert in the thread */
+  latest: Alert;
+  occurrences: number;
+  firstSeen: string;
+  /** Newest first */
+  alertIds: string[];
+}
+
+/** "notification" also shows an OS notification; "inApp" stays silent */
+export type AlertDelivery = "notification" | "inApp";
+
+export type AlertRouting = Record<AlertSeverity, AlertDelivery>;
+
+export interface AlertMute {
+  alertType?: AlertType | null;
+  relatedId?: string | null;
+  until: string;
+}
+
+export interface AlertPreferences {
+  mutes: AlertMute[];
+  routing: AlertRouting;
+}
+
+export async function listAlertThreads(includeDismissed?: boolean) {
+  return invoke<AlertThread[]>("list_alert_threads", { includeDismissed });
+}
+
+export async function dismissAlerts(alertIds: string[]) {
+  return invoke<void>("dismiss_alerts", { alertIds });
+}
+
+export async function getAlertPreferences() {
+  return invoke<AlertPreferences>("get_alert_preferences");
+}
+
+export async function updateAlertRouting(routing: AlertRouting) {
+  return invoke<AlertPreferences>("update_alert_routing", { routing });
+}
+
+/** Mute a type, an item, or one type for one item, for `days` */
+export async function muteAlerts(days: number, alertType?: AlertType | null, relatedId?: string | null) {
+  return invoke<AlertPreferences>("mute_alerts", { alertType, relatedId, days });
+}
+
+export async function unmuteAlerts(alertType?: AlertType | null, relatedId?: string | null) {
+  return invoke<AlertPreferences>("unmute_alerts", { alertType, relatedId });
+}
+
+// ========== Notification Channels ==========
+
+export type NotificationChannelKind = "webhook" | "discord" | "ntfy";
+
+export interface NotificationChannel {
+  id: string;
+  name: string;
+  kind: NotificationChannelKind;
+  /** Webhook URL; for ntfy the topic URL, e.g. https://ntfy.sh/my-topic */
+  url: string;
+  enabled: boolean;
+  /** Alert and low-stock events below this severity are not sent */
+  minSeverity: AlertSeverity;
+  alerts: boolean;
+  lowStock: boolean;
+  backupSuccess: boolean;
+  backupFailure: boolean;
+}
+
+export interface NotificationSettings {
+  channels: NotificationChannel[];
+}
+
+export async function getNotificationSettings() {
+  return invoke<NotificationSettings>("get_notification_settings");
+}
+
+export async function updateNotificationSettings(settings: NotificationSettings) {
+  return invoke<NotificationSettings>("update_notification_settings", { settings });
+}
+
+export async function testNotificationChannel(channel: NotificationChannel) {
+  return invoke<void>("test_notification_channel", { channel });
+}
+
+// ========== Email Digest ==========
+
+export type DigestFrequency = "daily" | "weekly";
+
+export type SmtpSecurity = "tls" | "startTls" | "none";
+
+export interface EmailDigestSettings {
+  enabled: boolean;
+  /** Weekly digests are sent on Mondays */
+  frequency: DigestFrequency;
+  /** Hour of day (0-23, UTC) */
+  hour: number;
+  smtpHost: string;
+  smtpPort: number;
+  security: SmtpSecurity;
+  /** SMTP login; no authentication when empty */
+  username: string;
+  from: string;
+  to: string;
+  lastSent?: string | null;
+  nextSend?: string | null;
+}
+
+export interface EmailDigestConfig {
+  settings: EmailDigestSettings;
+  passwordSaved: boolean;
+}
+
+export async function getEmailDigestSettings() {
+  return invoke<EmailDigestConfig>("get_email_digest_settings");
+}
+
+/** `password` replaces the saved SMTP password; "" removes it, omit to keep it */
+export async function updateEmailDigestSettings(settings: EmailDigestSettings, password?: string) {
+  return invoke<EmailDigestConfig>("update_email_digest_settings", { settings, password });
+}
+
+export async function sendEmailDigestNow() {
+  return invoke<EmailDigestSettings>("send_email_digest_now");
+}
+
+// ========== Health Bridge (mobile) ==========
+
+export interface HealthBridgeSettings {
+  enabled: boolean;
+  /** Write each logged body metric to HealthKit / Health Connect */
+  writeMetrics: boolean;
+  /** Read weight entries from other apps back into body metrics */
+  readWeight: boolean;
+  lastRead?: string | null;
+}
+
+export interface HealthBridgeStatus {
+  /** False on desktop and in builds without the health-bridge feature */
+  available: boolean;
+  settings: HealthBridgeSettings;
+}
+
+export interface HealthSyncSummary {
+  source: "apple_health" | "google_fit" | "health_connect";
+  daysFound: number;
+  created: number;
+  updated: number;
+  skipped: number;
+  firstDate?: string | null;
+  lastDate?: string | null;
+}
+
+export async function getHealthBridgeStatus() {
+  return invoke<HealthBridgeStatus>("get_health_bridge_status");
+}
+
+/** Turning sync on shows the system permission prompt */
+export async function updateHealthBridgeSettings(settings: HealthBridgeSettings) {
+  return invoke<void>("update_health_bridge_settings", { settings });
+}
+
+export async function syncHealthBridge() {
+  return invoke<HealthSyncSummary>("sync_health_bridge");
+}
+
 // ========== AI Summary History ==========
 
 export interface SummaryHistory {
@@ -833,6 +3057,10 @@ export interface SummaryHistory {
   format: string;
   provider: string;
   created_at: string;
+  references?: string[];
+  notices?: string[];
+  usage?: AiUsage | null;
+  source_id?: string;
 }
 
 export interface SaveSummaryPayload {
@@ -841,6 +3069,10 @@ export interface SaveSummaryPayload {
   summaryOutput: string;
   format: string;
   provider: string;
+  references?: string[];
+  usage?: AiUsage;
+  /** Literature entry summarized; defaults to the single reference */
+  sourceId?: string;
 }
 
 // Summary History API calls
@@ -853,6 +3085,78 @@ export async function listSummaryHistory(limit?: number) {
   return invoke<SummaryHistory[]>("list_summary_history", { limit });
 }
 
+/** Every saved version of a summary of one paper, newest first */
+export async function listSummariesForSource(sourceId: string) {
+  return invoke<SummaryHistory[]>("list_summaries_for_source", { sourceId });
+}
+
+export interface DiffLine {
+  op: "unchanged" | "added" | "removed";
+  text: string;
+}
+
+export interface SummaryVersion {
+  summaryId: string;
+  provider: string;
+  format: string;
+  createdAt: string;
+}
+
+export interface SummaryDiff {
+  old: SummaryVersion;
+  new: SummaryVersion;
+  sameSource: boolean;
+  lines: DiffLine[];
+  added: number;
+  removed: number;
+}
+
+export async function diffSummaries(oldId: string, newId: string) {
+  return invoke<SummaryDiff>("diff_summaries", { oldId, newId });
+}
+
+export interface MarkdownExportResult {
+  /** File names written into the folder */
+  written: string[];
+  unchanged: number;
+}
+
+/** Write summaries as Markdown notes into a folder, e.g. an Obsidian vault */
+export async function exportSummariesMarkdown(folder: string, incremental = true) {
+  return invoke<MarkdownExportResult>("export_summaries_markdown", {
+    payload: { folder, incremental },
+  });
+}
+
+export interface ProviderUsage {
+  provider: string;
+  summaries: number;
+  inputTokens: number;
+  outputTokens: number;
+  costUsd: number;
+  estimatedSummaries: number;
+}
+
+export interface DailyAiUsage {
+  date: string;
+  provider: string;
+  summaries: number;
+  inputTokens: number;
+  outputTokens: number;
+  costUsd: number;
+}
+
+export interface AiUsageStats {
+  byProvider: ProviderUsage[];
+  daily: DailyAiUsage[];
+  totalCostUsd: number;
+  untrackedSummaries: number;
+}
+
+export async function getAiUsageStats(days?: number) {
+  return invoke<AiUsageStats>("get_ai_usage_stats", { days });
+}
+
 export async function deleteSummaryFromHistory(summaryId: string) {
   return invoke<void>("delete_summary", { summaryId });
 }
@@ -872,15 +3176,58 @@ export interface DoseSchedule {
   notes?: string | null;
   createdAt: string;
   updatedAt: string;
+  /** When set, amountMg is the current phase's amount */
+  titration?: Titration | null;
+  titrationStatus?: TitrationStatus | null;
+  /** Set when a snoozed reminder is due again, RFC3339 */
+  snoozedUntil?: string;
+}
+
+export interface TitrationPhase {
+  amountMg: number;
+  /** Ignored for the last phase, which continues until changed */
+  durationDays: number;
+}
+
+export interface Titration {
+  phases: TitrationPhase[];
+  currentPhase: number;
+  phaseStarted: string; // YYYY-MM-DD
+  autoAdvance: boolean;
+}
+
+export interface TitrationStatus {
+  /** Zero-based */
+  phase: number;
+  phaseCount: number;
+  amountMg: number;
+  phaseStarted: string;
+  nextStep: {
+    phase: number;
+    amountMg: number;
+    startsOn: string; // YYYY-MM-DD
+    daysUntil: number;
+  } | null;
+}
+
+export interface TitrationPayload {
+  phases: TitrationPhase[];
+  /** First day of currentPhase (YYYY-MM-DD); defaults to today */
+  startDate?: string;
+  currentPhase?: number;
+  /** Defaults to true */
+  autoAdvance?: boolean;
 }
 
 export interface CreateSchedulePayload {
   protocolId: string;
-  amountMg: number;
+  /** Not needed with a titration */
+  amountMg?: number;
   site?: string;
   timeOfDay: string;
   daysOfWeek: number[];
   notes?: string;
+  titration?: TitrationPayload;
 }
 
 export interface UpdateSchedulePayload {
@@ -891,6 +3238,8 @@ export interface UpdateSchedulePayload {
   daysOfWeek?: number[];
   enabled?: boolean;
   notes?: string;
+  /** null turns titration off; leave out to keep it */
+  titration?: TitrationPayload | null;
 }
 
 // Dose Schedule API calls
@@ -915,6 +3264,62 @@ export async function getPendingDoseReminders() {
   return invoke<DoseSchedule[]>("get_pending_dose_reminders");
 }
 
+export type ReminderAction = "taken" | "snooze" | "skip";
+
+/** A scheduled dose deliberately not taken */
+export interface DoseSkip {
+  id: string;
+  protocol_id: string;
+  schedule_id: string;
+  scheduled_on: string;
+  skipped_at: string;
+}
+
+/** Outcome of a reminder action; only the field for the action is set */
+export interface ReminderResponse {
+  dose?: DoseLogView | null;
+  /** RFC3339 */
+  snoozedUntil?: string | null;
+  skip?: DoseSkip | null;
+}
+
+/**
+ * Handle a dose reminder's Taken, Snooze or Skip button: log the scheduled
+ * dose, show the reminder again in `snoozeMinutes`, or record a skip
+ */
+export async function respondToDoseReminder(
+  scheduleId: string,
+  action: ReminderAction,
+  snoozeMinutes?: number
+) {
+  return invoke<ReminderResponse>("respond_to_dose_reminder", {
+    payload: { scheduleId, action, snoozeMinutes },
+  });
+}
+
+/** Doses skipped from reminders; dates are YYYY-MM-DD and both are included */
+export async function listDoseSkips(protocolId?: string, startDate?: string, endDate?: string) {
+  return invoke<DoseSkip[]>("list_dose_skips", { protocolId, startDate, endDate });
+}
+
+export interface SnoozedReminder {
+  scheduleId: string;
+  protocolName: string;
+  /** RFC3339 */
+  doseAt: string;
+  /** When the reminder shows again, RFC3339 */
+  remindAt: string;
+}
+
+/** Put off the next dose reminder until `minutes` (default 10) after its dose time */
+export async function snoozeNextDoseReminder(minutes?: number) {
+  return invoke<SnoozedReminder>("snooze_next_dose_reminder", { minutes });
+}
+
+export async function advanceTitrationPhase(scheduleId: string) {
+  return invoke<DoseSchedule>("advance_titration_phase", { scheduleId });
+}
+
 // Default Peptides types
 
 export interface DefaultProtocol {
@@ -922,6 +3327,8 @@ export interface DefaultProtocol {
   commonName: string;
   typicalDoseRange: string;
   notes: string;
+  /** Approximate; null where no half-life is published */
+  halfLifeHours: number | null;
 }
 
 // Default Peptides API calls
@@ -933,3 +3340,109 @@ export async function getDefaultPeptides() {
 export async function populateDefaultPeptides() {
   return invoke<number>("populate_default_peptides");
 }
+
+// Onboarding API calls
+
+export type SetupStep = "defaultPeptides" | "firstProtocol" | "backupSchedule";
+
+export interface SetupState {
+  completedSteps: SetupStep[];
+  skippedSteps: SetupStep[];
+  firstProtocolId?: string | null;
+  /** RFC3339; set once every step is done or skipped */
+  completedAt?: string | null;
+  /** Where the wizard should resume; null when setup is finished */
+  nextStep?: SetupStep | null;
+  /** Catalog protocols added by this call */
+  defaultPeptidesAdded: number;
+  /** Recovery code for the database key, set when this call created it; show it now, it isn't kept */
+  recoveryCode?: string | null;
+}
+
+export interface ProtocolTemplatePayload {
+  /** A catalog peptide, see getDefaultPeptides */
+  peptideName: string;
+  protocolName?: string;
+  targetConcentrationMgMl?: number;
+  /** With timeOfDay, adds a reminder schedule */
+  doseMg?: number;
+  /** HH:MM, 24-hour */
+  timeOfDay?: string;
+  /** 0 = Sunday; every day when not given */
+  daysOfWeek?: number[];
+}
+
+export interface BootstrapProfilePayload {
+  seedDefaultPeptides?: boolean;
+  template?: ProtocolTemplatePayload;
+  backup?: { frequency: BackupFrequency; backupOnClose?: boolean };
+  /** Steps the user chose not to do */
+  skipSteps?: SetupStep[];
+}
+
+export async function getSetupStatus() {
+  return invoke<SetupState>("get_setup_status");
+}
+
+/** Runs the requested setup steps; steps already done are not repeated */
+export async function bootstrapProfile(payload: BootstrapProfilePayload) {
+  return invoke<SetupState>("bootstrap_profile", { payload });
+}
+
+export async function resetSetupStatus() {
+  return invoke<SetupState>("reset_setup_status");
+}
+
+// Security API calls
+
+export type KeyLocation = "keychain" | "file" | "passphrase";
+
+export interface RecoveryCodeStatus {
+  configured: boolean;
+  /** RFC3339 */
+  createdAt?: string | null;
+}
+
+export interface LockStatus {
+  passphraseEnabled: boolean;
+  biometricEnabled: boolean;
+  biometricAvailable: boolean;
+  locked: boolean;
+  /** The stored key was lost; only the recovery code can unlock */
+  recoveryRequired: boolean;
+  recoveryCode: RecoveryCodeStatus;
+  autoLockMinutes?: number | null;
+}
+
+export interface KeyRotationResult {
+  reencrypted: number;
+  keyLocation: KeyLocation;
+  /** Replaces the old recovery code, which no longer works; shown only here */
+  recoveryCode?: string | null;
+}
+
+export type KeyRotationTarget =
+  | { kind: "stored_key"; currentPassphrase?: string }
+  | { kind: "passphrase"; currentPassphrase?: string; newPassphrase: string };
+
+export async function getLockStatus() {
+  return invoke<LockStatus>("get_lock_status");
+}
+
+/** Unlocks with the recovery code; the key is saved again and any passphrase removed */
+export async function recoverWithCode(code: string) {
+  return invoke<KeyLocation>("recover_with_code", { code });
+}
+
+/** Creates a recovery code, replacing any earlier one; it is only returned here */
+export async function generateRecoveryCode(currentPassphrase?: string) {
+  return invoke<string>("generate_recovery_code", { currentPassphrase });
+}
+
+export async function setDatabasePassphrase(newPassphrase: string, currentPassphrase?: string) {
+  return invoke<KeyRotationResult>("set_database_passphrase", { currentPassphrase, newPassphrase });
+}
+
+export async function rotateEncryptionKey(target: KeyRotationTarget) {
+  return invoke<KeyRotationResult>("rotate_encryption_key", { target });
+}
@@ -7,6 +7,9 @@ import * as api from '../../api/peptrack'
 vi.mock('../DoseScheduleManager.vue', () => ({
   default: { name: 'DoseScheduleManager', template: '<div class="dose-schedule-manager-stub"></div>' }
 }))
+vi.mock('../DosePresets.vue', () => ({
+  default: { name: 'DosePresets', template: '<div class="dose-presets-stub"></div>' }
+}))
 
 vi.mock('../../api/peptrack')
 vi.mock('../../utils/errorHandling', () => ({
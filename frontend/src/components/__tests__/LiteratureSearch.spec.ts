@@ -17,6 +17,7 @@ describe('LiteratureSearch Component', () => {
   const mockSearchResults = [
     {
       source: 'pubmed',
+      entries: [],
       results: [
         { id: '1', source: 'pubmed', title: 'BPC-157 Study', authors: 'Smith et al.', journal: 'Nature', published_date: '2023', abstract_text: 'Test abstract', url: 'https://pubmed.com/1', doi: '10.1234/test', indexed_at: new Date().toISOString() },
         { id: '2', source: 'pubmed', title: 'TB-500 Research', authors: 'Jones et al.', journal: 'Science', published_date: '2022', abstract_text: null, url: 'https://pubmed.com/2', doi: null, indexed_at: new Date().toISOString() }
@@ -271,6 +272,7 @@ describe('LiteratureSearch Component', () => {
     // Mock 6 papers
     const manyResults = [{
       source: 'pubmed',
+      entries: [],
       results: Array.from({ length: 6 }, (_, i) => ({
         id: `${i + 1}`,
         source: 'pubmed',
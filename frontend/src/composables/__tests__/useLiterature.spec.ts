@@ -197,6 +197,7 @@ describe('useLiterature Composable', () => {
     const mockResults = [
       {
         source: 'PubMed',
+        entries: [],
         results: [
           { id: '1', source: 'PubMed', title: 'Test', url: 'https://example.com', summary: null, relevance_score: null, indexed_at: new Date().toISOString() }
         ]
@@ -243,6 +244,7 @@ describe('useLiterature Composable', () => {
     const mockResults = [
       {
         source: 'OpenAlex',
+        entries: [],
         results: []
       }
     ]
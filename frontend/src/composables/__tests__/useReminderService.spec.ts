@@ -10,6 +10,7 @@ describe('useReminderService Composable', () => {
   beforeEach(() => {
     vi.clearAllMocks()
     vi.useFakeTimers()
+    vi.mocked(notifications.registerDoseReminderActions).mockResolvedValue(() => {})
   })
 
   afterEach(() => {
@@ -194,13 +195,13 @@ describe('useReminderService Composable', () => {
       tag: 'dose-reminder-1',
       requireInteraction: true
     })
-    vi.mocked(notifications.showNotification).mockResolvedValue(undefined)
+    vi.mocked(notifications.showDoseReminder).mockResolvedValue(undefined)
 
     service.start()
     await vi.runAllTimersAsync()
 
     expect(api.getPendingDoseReminders).toHaveBeenCalled()
-    expect(notifications.showNotification).toHaveBeenCalled()
+    expect(notifications.showDoseReminder).toHaveBeenCalled()
   })
 
   it('checkReminders sends notification for each reminder', async () => {
@@ -238,12 +239,12 @@ describe('useReminderService Composable', () => {
       tag: 'test',
       requireInteraction: true
     })
-    vi.mocked(notifications.showNotification).mockResolvedValue(undefined)
+    vi.mocked(notifications.showDoseReminder).mockResolvedValue(undefined)
 
     service.start()
     await vi.runAllTimersAsync()
 
-    expect(notifications.showNotification).toHaveBeenCalledTimes(2)
+    expect(notifications.showDoseReminder).toHaveBeenCalledTimes(2)
   })
 
   it('checkReminders does not duplicate notifications', async () => {
@@ -270,20 +271,20 @@ describe('useReminderService Composable', () => {
       tag: 'test',
       requireInteraction: true
     })
-    vi.mocked(notifications.showNotification).mockResolvedValue(undefined)
+    vi.mocked(notifications.showDoseReminder).mockResolvedValue(undefined)
 
     service.start()
     await vi.runAllTimersAsync()
 
     // First check - should send notification
-    expect(notifications.showNotification).toHaveBeenCalledTimes(1)
+    expect(notifications.showDoseReminder).toHaveBeenCalledTimes(1)
 
     // Advance 1 minute - should check again but NOT send duplicate
     vi.advanceTimersByTime(1 * 60 * 1000)
     await vi.runAllTimersAsync()
 
     // Should still be 1 (not duplicated)
-    expect(notifications.showNotification).toHaveBeenCalledTimes(1)
+    expect(notifications.showDoseReminder).toHaveBeenCalledTimes(1)
   })
 
   it('checkReminders updates lastCheckTime', async () => {
@@ -363,7 +364,7 @@ describe('useReminderService Composable', () => {
       tag: 'test',
       requireInteraction: true
     })
-    vi.mocked(notifications.showNotification).mockRejectedValue(new Error('Notification error'))
+    vi.mocked(notifications.showDoseReminder).mockRejectedValue(new Error('Notification error'))
 
     service.start()
     await vi.runAllTimersAsync()
@@ -405,25 +406,42 @@ describe('useReminderService Composable', () => {
       tag: 'test',
       requireInteraction: true
     })
-    vi.mocked(notifications.showNotification).mockResolvedValue(undefined)
+    vi.mocked(notifications.showDoseReminder).mockResolvedValue(undefined)
 
     service.start()
     await vi.runAllTimersAsync()
 
     // First notification sent
-    expect(notifications.showNotification).toHaveBeenCalledTimes(1)
+    expect(notifications.showDoseReminder).toHaveBeenCalledTimes(1)
 
     // Advance 30 minutes - should NOT send duplicate
     vi.advanceTimersByTime(30 * 60 * 1000)
     await vi.runAllTimersAsync()
 
-    expect(notifications.showNotification).toHaveBeenCalledTimes(1)
+    expect(notifications.showDoseReminder).toHaveBeenCalledTimes(1)
 
     // Advance 31 more minutes (total 61 minutes) - key should be cleaned up, notification sent again
     vi.advanceTimersByTime(31 * 60 * 1000)
     await vi.runAllTimersAsync()
 
-    expect(notifications.showNotification).toHaveBeenCalledTimes(2)
+    expect(notifications.showDoseReminder).toHaveBeenCalledTimes(2)
+  })
+
+  it('reminder buttons are sent to the backend', async () => {
+    const service = useReminderService()
+
+    vi.mocked(api.getPendingDoseReminders).mockResolvedValue([])
+    vi.mocked(api.respondToDoseReminder).mockResolvedValue({ snoozedUntil: '2025-01-01T08:30:00Z' })
+
+    service.start()
+    await vi.runAllTimersAsync()
+
+    const handler = vi.mocked(notifications.registerDoseReminderActions).mock.calls[0][0]
+    handler('snooze', 'schedule-1')
+    handler('skip', 'schedule-1')
+
+    expect(api.respondToDoseReminder).toHaveBeenCalledWith('schedule-1', 'snooze', 30)
+    expect(api.respondToDoseReminder).toHaveBeenCalledWith('schedule-1', 'skip', undefined)
   })
 
   // =============================================================================
@@ -18,6 +18,7 @@ export function useLiterature() {
     lastSearchSources,
     currentSummary,
     summaryProvider,
+    summaryUsage,
     hasSearchResults,
     hasCachedLiterature,
     hasSummary,
@@ -43,6 +44,7 @@ export function useLiterature() {
     lastSearchSources,
     currentSummary,
     summaryProvider,
+    summaryUsage,
 
     // Getters
     hasSearchResults,
@@ -1,6 +1,12 @@
 import { ref, onUnmounted } from 'vue';
-import { getPendingDoseReminders, type DoseSchedule } from '../api/peptrack';
-import { showNotification, NotificationPresets } from '../utils/notifications';
+import { getPendingDoseReminders, respondToDoseReminder, type DoseSchedule } from '../api/peptrack';
+import {
+  NotificationPresets,
+  registerDoseReminderActions,
+  showDoseReminder,
+  type DoseReminderActionId,
+} from '../utils/notifications';
+import { showErrorToast, showSuccessToast } from '../utils/errorHandling';
 
 export interface ReminderServiceConfig {
   checkIntervalMinutes?: number; // How often to check for reminders (default: 5 minutes)
@@ -26,6 +32,10 @@ export function useReminderService(config: ReminderServiceConfig = {}) {
   const lastCheckTime = ref<Date | null>(null);
   const notifiedSchedules = ref<Set<string>>(new Set()); // Track which schedules we've already notified for
   let intervalId: number | null = null;
+  let stopActionListener: (() => void) | null = null;
+
+  /** Snooze length of the reminder's Snooze button */
+  const SNOOZE_MINUTES = 30;
 
   async function checkReminders() {
     if (!enabled || !isRunning.value) {
@@ -40,7 +50,8 @@ export function useReminderService(config: ReminderServiceConfig = {}) {
 
         for (const reminder of pendingReminders) {
           // Check if we've already notified for this schedule in this time window
-          const notificationKey = `${reminder.id}-${reminder.timeOfDay}`;
+          // A snoozed reminder shows again once its snooze ends
+          const notificationKey = `${reminder.id}-${reminder.timeOfDay}-${reminder.snoozedUntil ?? ''}`;
 
           if (!notifiedSchedules.value.has(notificationKey)) {
             // Send notification
@@ -72,7 +83,7 @@ export function useReminderService(config: ReminderServiceConfig = {}) {
         schedule.amountMg
       );
 
-      await showNotification(notification);
+      await showDoseReminder({ ...notification, scheduleId: schedule.id }, handleReminderAction);
 
       debugLog(`Sent notification for ${schedule.protocolName}`);
     } catch (error) {
@@ -80,6 +91,26 @@ export function useReminderService(config: ReminderServiceConfig = {}) {
     }
   }
 
+  /** Taken logs the dose, Snooze shows the reminder again later, Skip records a skip */
+  async function handleReminderAction(actionId: DoseReminderActionId, scheduleId: string) {
+    try {
+      const response = await respondToDoseReminder(
+        scheduleId,
+        actionId,
+        actionId === 'snooze' ? SNOOZE_MINUTES : undefined
+      );
+      if (response.dose) {
+        showSuccessToast('Dose logged', `${response.dose.displayAmount} ${response.dose.displayUnit} at ${response.dose.site}`);
+      } else if (response.snoozedUntil) {
+        showSuccessToast('Reminder snoozed', `We'll remind you again in ${SNOOZE_MINUTES} minutes`);
+      } else if (response.skip) {
+        showSuccessToast('Dose skipped', 'Recorded as skipped for adherence');
+      }
+    } catch (error) {
+      showErrorToast(error, { operation: 'respond to dose reminder' });
+    }
+  }
+
   function start() {
     if (isRunning.value) {
       console.warn('[ReminderService] Service already running');
@@ -94,6 +125,15 @@ export function useReminderService(config: ReminderServiceConfig = {}) {
     debugLog(`Starting reminder service (checking every ${checkIntervalMinutes} minutes)`);
     isRunning.value = true;
 
+    registerDoseReminderActions(handleReminderAction).then((stopListening) => {
+      // Stopped before the listener was ready
+      if (!isRunning.value) {
+        stopListening();
+        return;
+      }
+      stopActionListener = stopListening;
+    });
+
     // Check immediately on start
     checkReminders();
 
@@ -115,6 +155,8 @@ export function useReminderService(config: ReminderServiceConfig = {}) {
       clearInterval(intervalId);
       intervalId = null;
     }
+    stopActionListener?.();
+    stopActionListener = null;
 
     // Clear notified schedules
     notifiedSchedules.value.clear();
@@ -5,7 +5,7 @@
 
 import { defineStore } from 'pinia'
 import { ref, computed } from 'vue'
-import type { LiteratureEntry, LiteratureSearchResult, SummaryFormat, SearchLiteraturePayload } from '../api/peptrack'
+import type { AiUsage, LiteratureEntry, LiteratureSearchResult, SummaryFormat, SearchLiteraturePayload } from '../api/peptrack'
 import {
   searchLiterature,
   searchCachedLiterature,
@@ -28,6 +28,7 @@ export const useLiteratureStore = defineStore('literature', () => {
   // AI Summary state
   const currentSummary = ref<string | null>(null)
   const summaryProvider = ref<string | null>(null)
+  const summaryUsage = ref<AiUsage | null>(null)
 
   // Getters
   const hasSearchResults = computed(() => searchResults.value.length > 0)
@@ -98,6 +99,7 @@ export const useLiteratureStore = defineStore('literature', () => {
       const result = await summarizeContent({ title, content, format })
       currentSummary.value = result.output
       summaryProvider.value = result.provider
+      summaryUsage.value = result.usage ?? null
 
       showSuccessToast('Summary Generated', `Summary generated using ${result.provider}`)
       return result
@@ -117,6 +119,7 @@ export const useLiteratureStore = defineStore('literature', () => {
   function clearSummary() {
     currentSummary.value = null
     summaryProvider.value = null
+    summaryUsage.value = null
   }
 
   function clearAll() {
@@ -135,6 +138,7 @@ export const useLiteratureStore = defineStore('literature', () => {
     lastSearchSources,
     currentSummary,
     summaryProvider,
+    summaryUsage,
 
     // Getters
     hasSearchResults,
@@ -7,6 +7,7 @@ import {
   handleAsync,
   getErrorMessage,
 } from "../errorHandling";
+import { CommandError } from "../../api/peptrack";
 
 // Mock the global showToast function
 (globalThis as any).showToast = vi.fn();
@@ -160,6 +161,37 @@ describe("errorHandling", () => {
 
       expect((globalThis as any).showToast).toHaveBeenCalled();
     });
+
+    it("uses the kind of backend errors over the message", () => {
+      const error = new CommandError({
+        kind: "locked",
+        message: "Failed to list protocols: Database is locked",
+        retryable: false,
+      });
+      showErrorToast(error);
+
+      expect((globalThis as any).showToast).toHaveBeenCalledWith(
+        expect.objectContaining({
+          type: "error",
+          title: "Database Locked",
+        })
+      );
+    });
+
+    it("falls back to the message for internal backend errors", () => {
+      const error = new CommandError({
+        kind: "internal",
+        message: "Backup upload failed",
+        retryable: false,
+      });
+      showErrorToast(error);
+
+      expect((globalThis as any).showToast).toHaveBeenCalledWith(
+        expect.objectContaining({
+          title: "Backup Failed",
+        })
+      );
+    });
   });
 
   describe("showSuccessToast", () => {
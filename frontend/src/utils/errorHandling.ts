@@ -3,6 +3,8 @@
  * Provides user-friendly error messages and toast notifications
  */
 
+import { CommandError, type CommandErrorKind } from "../api/peptrack";
+
 export interface ErrorContext {
   operation: string;
   details?: string;
@@ -92,6 +94,36 @@ const ERROR_MESSAGES: Record<string, (context?: ErrorContext) => { title: string
     suggestion: "Please check your input and try again",
   }),
 
+  database_locked: () => ({
+    title: "Database Locked",
+    message: "PepTrack is locked.",
+    suggestion: "Unlock PepTrack with your passphrase and try again",
+  }),
+
+  database_busy: (context) => ({
+    title: "Database Busy",
+    message: `Couldn't ${context?.operation || "complete the operation"} because another task is using the database.`,
+    suggestion: "Wait a moment and try again",
+  }),
+
+  database_corrupted: () => ({
+    title: "Database Damaged",
+    message: "Part of the database could not be read.",
+    suggestion: "Run a health check in Settings or restore from a backup",
+  }),
+
+  database_migration_failed: () => ({
+    title: "Database Upgrade Failed",
+    message: "The database couldn't be upgraded, so it was put back the way it was.",
+    suggestion: "Restart PepTrack, or restore a migration snapshot in Settings",
+  }),
+
+  permission_denied: (context) => ({
+    title: "Permission Denied",
+    message: context?.details || "PepTrack doesn't have permission to do that.",
+    suggestion: "Check file and folder permissions",
+  }),
+
   // Generic error
   unknown: (context) => ({
     title: "Unexpected Error",
@@ -101,9 +133,28 @@ const ERROR_MESSAGES: Record<string, (context?: ErrorContext) => { title: string
 };
 
 /**
- * Detect error type from error message
+ * Error type for each backend error kind; kinds not listed fall back to
+ * matching the message
+ */
+const COMMAND_ERROR_TYPES: Partial<Record<CommandErrorKind, string>> = {
+  not_found: "file_not_found",
+  invalid_input: "validation_failed",
+  locked: "database_locked",
+  busy: "database_busy",
+  corrupted: "database_corrupted",
+  migration_failed: "database_migration_failed",
+  network: "network",
+  permission_denied: "permission_denied",
+};
+
+/**
+ * Detect error type from the backend error kind, or from the message
  */
 function detectErrorType(error: unknown): string {
+  if (error instanceof CommandError && COMMAND_ERROR_TYPES[error.kind]) {
+    return COMMAND_ERROR_TYPES[error.kind]!;
+  }
+
   const errorStr = String(error).toLowerCase();
 
   if (errorStr.includes("network") || errorStr.includes("connection")) {
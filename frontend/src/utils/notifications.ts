@@ -5,8 +5,11 @@
 
 import {
   isPermissionGranted,
+  onAction,
+  registerActionTypes,
   requestPermission,
   sendNotification,
+  type Options,
 } from '@tauri-apps/plugin-notification';
 
 export interface NotificationOptions {
@@ -77,6 +80,83 @@ export async function showNotification(options: NotificationOptions): Promise<vo
   }
 }
 
+/** Buttons on dose reminders, by action id */
+export const DOSE_REMINDER_ACTIONS = [
+  { id: 'taken', title: 'Taken' },
+  { id: 'snooze', title: 'Snooze 30m' },
+  { id: 'skip', title: 'Skip' },
+] as const;
+
+export type DoseReminderActionId = (typeof DOSE_REMINDER_ACTIONS)[number]['id'];
+
+const DOSE_REMINDER_ACTION_TYPE = 'dose-reminder';
+
+/**
+ * Register the dose reminder buttons with the OS and call `handler` when one
+ * is pressed. Returns a function that stops listening.
+ *
+ * Not every platform shows notification buttons, so reminders also show
+ * the same buttons on an in-app toast.
+ */
+export async function registerDoseReminderActions(
+  handler: (actionId: DoseReminderActionId, scheduleId: string) => void
+): Promise<() => void> {
+  try {
+    await registerActionTypes([
+      {
+        id: DOSE_REMINDER_ACTION_TYPE,
+        actions: DOSE_REMINDER_ACTIONS.map((action) => ({ ...action, foreground: false })),
+      },
+    ]);
+    // The event carries the pressed button's id next to the notification
+    const listener = await onAction((event) => {
+      const { actionId, notification } = event as unknown as { actionId?: string; notification?: Options };
+      const scheduleId = notification?.extra?.scheduleId;
+      const action = DOSE_REMINDER_ACTIONS.find((known) => known.id === actionId);
+      if (action && typeof scheduleId === 'string') {
+        handler(action.id, scheduleId);
+      }
+    });
+    return () => listener.unregister();
+  } catch (error) {
+    console.warn('Notification buttons are not available:', error);
+    return () => {};
+  }
+}
+
+/**
+ * Show a dose reminder with Taken / Snooze / Skip buttons, as a desktop
+ * notification and an in-app toast that stays until it's answered
+ */
+export async function showDoseReminder(
+  options: { title: string; body: string; scheduleId: string },
+  handler: (actionId: DoseReminderActionId, scheduleId: string) => void
+): Promise<void> {
+  if (permissionGranted) {
+    try {
+      await sendNotification({
+        title: options.title,
+        body: options.body,
+        actionTypeId: DOSE_REMINDER_ACTION_TYPE,
+        extra: { scheduleId: options.scheduleId },
+      });
+    } catch (error) {
+      console.warn('Desktop notification failed, showing a toast only:', error);
+    }
+  }
+
+  window.showToast?.({
+    type: 'info',
+    title: options.title,
+    message: options.body,
+    duration: 0,
+    actions: DOSE_REMINDER_ACTIONS.map((action) => ({
+      label: action.title,
+      handler: () => handler(action.id, options.scheduleId),
+    })),
+  });
+}
+
 /**
  * Show success notification
  */
@@ -0,0 +1,278 @@
+//! Estimated circulating amount of each peptide
+//!
+//! Every logged dose is treated as absorbed at once and eliminated by
+//! first-order decay, so the amount left from a dose of `D` mg after `t`
+//! hours is `D * 0.5^(t / half_life)`. Real pharmacokinetics depend on the
+//! route, formulation and the person; this is only meant to show roughly
+//! when a compound has washed out, e.g. before bloodwork.
+
+use std::collections::BTreeMap;
+
+use peptrack_core::{DoseLog, PeptideProtocol};
+use serde::Serialize;
+use tauri::State;
+use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime};
+use tracing::error;
+
+use crate::commands::dates::parse_datetime;
+use crate::commands::defaults::catalog_half_life;
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Days shown before now when no start is given
+const DEFAULT_HISTORY_DAYS: i64 = 30;
+/// Days shown after now when no end is given, to show the wash-out
+const DEFAULT_FORECAST_DAYS: i64 = 14;
+/// Points per series when no step is given
+const TARGET_POINTS: f64 = 400.0;
+/// Most points a series can have, whatever step is asked for
+const MAX_POINTS: f64 = 5000.0;
+/// Longest range a series can cover
+const MAX_RANGE_DAYS: i64 = 366;
+/// Half-lives after the last dose until ~97% is gone
+const WASHOUT_HALF_LIVES: f32 = 5.0;
+/// Doses this many half-lives old contribute nothing worth plotting
+const NEGLIGIBLE_HALF_LIVES: f64 = 20.0;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveLevelPoint {
+    /// RFC3339 timestamp
+    pub at: String,
+    pub amount_mg: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeptideActiveLevel {
+    pub peptide_name: String,
+    pub half_life_hours: f32,
+    /// Estimated amount still circulating now
+    pub current_mg: f32,
+    pub last_dose_at: Option<String>,
+    /// When the last dose is ~97% eliminated (five half-lives)
+    pub washout_at: Option<String>,
+    pub points: Vec<ActiveLevelPoint>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveLevels {
+    pub start: String,
+    pub end: String,
+    pub step_hours: f32,
+    pub peptides: Vec<PeptideActiveLevel>,
+    /// Dosed peptides left out because the catalog has no half-life
+    pub unknown_half_life: Vec<String>,
+}
+
+fn timestamp(time: OffsetDateTime) -> String {
+    time.format(&Rfc3339).unwrap_or_else(|_| time.to_string())
+}
+
+/// Amount of `doses` (time, mg) left at `at`
+fn level_at(doses: &[(OffsetDateTime, f32)], half_life_hours: f32, at: OffsetDateTime) -> f32 {
+    let half_life = f64::from(half_life_hours);
+    doses
+        .iter()
+        .filter(|(logged_at, _)| *logged_at <= at)
+        .map(|(logged_at, amount_mg)| {
+            let hours = (at - *logged_at).as_seconds_f64() / 3600.0;
+            f64::from(*amount_mg) * 0.5f64.powf(hours / half_life)
+        })
+        .sum::<f64>() as f32
+}
+
+/// Time range of the estimate
+struct LevelWindow {
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+    step: Duration,
+    now: OffsetDateTime,
+}
+
+fn build_active_levels(
+    protocols: &[PeptideProtocol],
+    doses: &[DoseLog],
+    half_life: impl Fn(&str) -> Option<f32>,
+    window: &LevelWindow,
+) -> ActiveLevels {
+    // Protocols on the same peptide add up
+    let mut by_peptide: BTreeMap<String, (String, Vec<(OffsetDateTime, f32)>)> = BTreeMap::new();
+    for dose in doses {
+        let Some(protocol) = protocols.iter().find(|protocol| protocol.id == dose.protocol_id) else {
+            continue;
+        };
+        let name = protocol.peptide_name.trim();
+        by_peptide
+            .entry(name.to_lowercase())
+            .or_insert_with(|| (name.to_string(), Vec::new()))
+            .1
+            .push((dose.logged_at, dose.amount_mg));
+    }
+
+    let mut peptides = Vec::new();
+    let mut unknown_half_life = Vec::new();
+    for (_, (peptide_name, mut peptide_doses)) in by_peptide {
+        let Some(half_life_hours) = half_life(&peptide_name).filter(|hours| *hours > 0.0) else {
+            unknown_half_life.push(peptide_name);
+            continue;
+        };
+        peptide_doses.sort_by_key(|(logged_at, _)| *logged_at);
+        let last_dose = peptide_doses
+            .iter()
+            .rev()
+            .find(|(logged_at, _)| *logged_at <= window.now)
+            .map(|(logged_at, _)| *logged_at);
+
+        let negligible_before =
+            window.start - Duration::seconds_f64(f64::from(half_life_hours) * NEGLIGIBLE_HALF_LIVES * 3600.0);
+        peptide_doses.retain(|(logged_at, _)| *logged_at >= negligible_before);
+
+        let mut points = Vec::new();
+        let mut at = window.start;
+        while at <= window.end {
+            points.push(ActiveLevelPoint {
+                at: timestamp(at),
+                amount_mg: level_at(&peptide_doses, half_life_hours, at),
+            });
+            at += window.step;
+        }
+
+        peptides.push(PeptideActiveLevel {
+            current_mg: level_at(&peptide_doses, half_life_hours, window.now),
+            last_dose_at: last_dose.map(timestamp),
+            washout_at: last_dose.map(|logged_at| {
+                timestamp(logged_at + Duration::seconds_f64(f64::from(half_life_hours * WASHOUT_HALF_LIVES) * 3600.0))
+            }),
+            peptide_name,
+            half_life_hours,
+            points,
+        });
+    }
+
+    ActiveLevels {
+        start: timestamp(window.start),
+        end: timestamp(window.end),
+        step_hours: (window.step.as_seconds_f64() / 3600.0) as f32,
+        peptides,
+        unknown_half_life,
+    }
+}
+
+// ========== Active Level Commands ==========
+
+/// Estimated circulating amount of each dosed peptide over time
+///
+/// Covers the last 30 days and the next 14 by default. `step_hours` sets
+/// the spacing of points; by default the range is split into about 400.
+#[tauri::command]
+pub async fn get_active_levels(
+    state: State<'_, std::sync::Arc<AppState>>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    step_hours: Option<f32>,
+) -> Result<ActiveLevels, CommandError> {
+    let now = OffsetDateTime::now_utc();
+    let start = match start_date {
+        Some(date) => parse_datetime(&date)?,
+        None => now - Duration::days(DEFAULT_HISTORY_DAYS),
+    };
+    let end = match end_date {
+        Some(date) => parse_datetime(&date)?,
+        None => now + Duration::days(DEFAULT_FORECAST_DAYS),
+    };
+    if start >= end {
+        return Err(CommandError::invalid_input("Start must be before the end"));
+    }
+    if end - start > Duration::days(MAX_RANGE_DAYS) {
+        return Err(CommandError::invalid_input(format!(
+            "Range can't be longer than {} days",
+            MAX_RANGE_DAYS
+        )));
+    }
+
+    let range_hours = (end - start).as_seconds_f64() / 3600.0;
+    let step_hours = match step_hours {
+        Some(hours) if !hours.is_finite() || hours <= 0.0 => {
+            return Err(CommandError::invalid_input("Step must be a positive number of hours"))
+        }
+        Some(hours) => f64::from(hours).max(range_hours / MAX_POINTS),
+        None => range_hours / TARGET_POINTS,
+    };
+
+    let protocols = state
+        .db
+        .run(|storage| storage.list_protocols())
+        .await
+        .map_err(|e| {
+            error!("Failed to load protocols for active levels: {:#}", e);
+            CommandError::with_context(e, "Failed to load protocols")
+        })?;
+    let doses = state
+        .db
+        .run(|storage| storage.list_dose_logs())
+        .await
+        .map_err(|e| {
+            error!("Failed to load dose logs for active levels: {:#}", e);
+            CommandError::with_context(e, "Failed to load dose logs")
+        })?;
+
+    Ok(build_active_levels(
+        &protocols,
+        &doses,
+        catalog_half_life,
+        &LevelWindow {
+            start,
+            end,
+            step: Duration::seconds_f64(step_hours * 3600.0),
+            now,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_levels_decay_and_add_up_across_protocols() {
+        let weekly = PeptideProtocol::new("Cut", "Semaglutide");
+        let extra = PeptideProtocol::new("Cut, second pen", "semaglutide");
+        let unknown = PeptideProtocol::new("Skin", "GHK-Cu");
+        let mut doses = Vec::new();
+        for (protocol, at) in [
+            (&weekly, datetime!(2025-03-01 08:00 UTC)),
+            (&extra, datetime!(2025-03-08 08:00 UTC)),
+            (&unknown, datetime!(2025-03-08 08:00 UTC)),
+        ] {
+            let mut dose = DoseLog::new(protocol.id.as_str(), "abdomen", 0.5);
+            dose.logged_at = at;
+            doses.push(dose);
+        }
+
+        let levels = build_active_levels(
+            &[weekly, extra, unknown],
+            &doses,
+            catalog_half_life,
+            &LevelWindow {
+                start: datetime!(2025-03-01 00:00 UTC),
+                end: datetime!(2025-03-15 08:00 UTC),
+                step: Duration::days(7),
+                now: datetime!(2025-03-15 08:00 UTC),
+            },
+        );
+
+        assert_eq!(levels.unknown_half_life, vec!["GHK-Cu".to_string()]);
+        assert_eq!(levels.peptides.len(), 1);
+        let semaglutide = &levels.peptides[0];
+        assert_eq!(semaglutide.half_life_hours, 168.0);
+        // 0.5 mg two half-lives ago plus 0.5 mg one half-life ago
+        assert!((semaglutide.current_mg - 0.375).abs() < 1e-4);
+        assert_eq!(semaglutide.points.len(), 3);
+        assert_eq!(semaglutide.points[0].amount_mg, 0.0);
+        assert_eq!(semaglutide.washout_at.as_deref(), Some("2025-04-12T08:00:00Z"));
+    }
+}
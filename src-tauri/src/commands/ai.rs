@@ -1,8 +1,17 @@
-use peptrack_local_ai::{AiProvider, LocalAiClient, SummarizeRequest, SummaryFormat};
+use std::time::Duration;
+
+use peptrack_core::models::{AiUsage, LiteratureEntry, PeptideProtocol, SummaryHistory};
+use peptrack_local_ai::health::DEFAULT_HEALTH_TIMEOUT;
+use peptrack_local_ai::{
+    summarize_corpus as summarize_documents, AiProvider, CorpusDocument, HealthReport,
+    LocalAiClient, SummarizeRequest, SummaryFormat, TokenUsage,
+};
 use serde::{Deserialize, Serialize};
 use tauri::State;
-use tracing::{info, warn};
+use time::OffsetDateTime;
+use tracing::{error, info, warn};
 
+use crate::error::CommandError;
 use crate::state::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -17,6 +26,18 @@ pub struct SummarizePayload {
 pub struct SummarizeResult {
     pub provider: String,
     pub output: String,
+    /// Pass back when saving the summary so its cost is tracked
+    pub usage: AiUsage,
+}
+
+/// Usage as stored with a saved summary
+pub(crate) fn usage_record(usage: TokenUsage) -> AiUsage {
+    AiUsage {
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+        cost_usd: usage.cost_usd,
+        estimated: usage.estimated,
+    }
 }
 
 /// Checks which AI providers are available
@@ -32,7 +53,7 @@ pub struct AiAvailabilityStatus {
 #[tauri::command]
 pub async fn check_ai_availability(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<AiAvailabilityStatus, String> {
+) -> Result<AiAvailabilityStatus, CommandError> {
     let providers = state.ai_client.provider_chain();
 
     let codex_available = providers.iter().any(|p| matches!(p, AiProvider::Codex));
@@ -61,11 +82,84 @@ pub async fn check_ai_availability(
     })
 }
 
+/// How long a health report is reused before the providers are tested again
+const HEALTH_CACHE_MAX_AGE: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderHealthStatus {
+    pub provider: String,
+    pub model: String,
+    pub installed: bool,
+    pub healthy: bool,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Which providers answered a test prompt, and which one summaries will use
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AiHealthStatus {
+    pub providers: Vec<ProviderHealthStatus>,
+    pub active_provider: Option<String>,
+    pub checked_at: OffsetDateTime,
+}
+
+impl From<HealthReport> for AiHealthStatus {
+    fn from(report: HealthReport) -> Self {
+        Self {
+            active_provider: report.active_provider().map(|p| format!("{:?}", p)),
+            checked_at: report.checked_at.into(),
+            providers: report
+                .providers
+                .into_iter()
+                .map(|health| ProviderHealthStatus {
+                    provider: format!("{:?}", health.provider),
+                    model: health.model,
+                    installed: health.installed,
+                    healthy: health.healthy,
+                    latency_ms: health.latency.map(|l| l.as_millis() as u64),
+                    error: health.error,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Sends each installed provider a test prompt, reusing the last result for
+/// a few minutes unless `force` is set
+#[tauri::command]
+pub async fn check_ai_health(
+    state: State<'_, std::sync::Arc<AppState>>,
+    force: Option<bool>,
+) -> Result<AiHealthStatus, CommandError> {
+    if !force.unwrap_or(false) {
+        if let Some(report) = state.ai_client.cached_health(HEALTH_CACHE_MAX_AGE) {
+            return Ok(report.into());
+        }
+    }
+
+    let report = state.ai_client.health_check(DEFAULT_HEALTH_TIMEOUT).await;
+    for health in &report.providers {
+        match &health.error {
+            None => info!(
+                "AI provider {:?} healthy ({} in {:?})",
+                health.provider, health.model, health.latency
+            ),
+            Some(err) if health.installed => {
+                warn!("AI provider {:?} unhealthy: {}", health.provider, err)
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(report.into())
+}
+
 #[tauri::command]
 pub async fn summarize_text(
     state: State<'_, std::sync::Arc<AppState>>,
     payload: SummarizePayload,
-) -> Result<SummarizeResult, String> {
+) -> Result<SummarizeResult, CommandError> {
     info!("Summarizing text: title='{}'", payload.title);
 
     let request = SummarizeRequest {
@@ -76,10 +170,10 @@ pub async fn summarize_text(
 
     let response = state.ai_client.summarize(request).await.map_err(|err| {
         warn!("AI summarization failed: {:#}", err);
-        format!(
+        CommandError::internal(format!(
             "AI summarization failed: {}. Make sure Codex CLI or Claude CLI is installed.",
             err
-        )
+        ))
     })?;
 
     info!("Summarization successful using {:?}", response.provider);
@@ -87,13 +181,235 @@ pub async fn summarize_text(
     Ok(SummarizeResult {
         provider: format!("{:?}", response.provider),
         output: response.raw_output,
+        usage: usage_record(response.usage),
     })
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummarizeCorpusPayload {
+    /// Cached literature entries to summarize
+    pub entry_ids: Vec<String>,
+    /// Protocols to focus the brief on; all protocols when omitted
+    pub protocol_ids: Option<Vec<String>>,
+    pub title: Option<String>,
+}
+
+/// "Doe J, Roe J, Poe K et al. Peptides (2021)" from whatever metadata is
+/// known
+fn citation_line(entry: &LiteratureEntry) -> Option<String> {
+    let mut line = entry
+        .authors
+        .iter()
+        .take(3)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if entry.authors.len() > 3 {
+        line.push_str(" et al.");
+    } else if !line.is_empty() {
+        line.push('.');
+    }
+    let rest = entry
+        .journal
+        .iter()
+        .cloned()
+        .chain(entry.year.map(|year| format!("({})", year)));
+    for part in rest {
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&part);
+    }
+    (!line.is_empty()).then_some(line)
+}
+
+fn corpus_document(entry: &LiteratureEntry) -> CorpusDocument {
+    CorpusDocument {
+        title: entry.title.clone(),
+        citation: citation_line(entry),
+        text: entry.summary.clone().unwrap_or_default(),
+    }
+}
+
+/// One line per protocol: name, peptide, target concentration and notes
+fn protocol_context(protocols: &[PeptideProtocol]) -> String {
+    protocols
+        .iter()
+        .map(|protocol| {
+            let mut line = format!("- {}: {}", protocol.name, protocol.peptide_name);
+            if let Some(concentration) = protocol.target_concentration_mg_ml {
+                line.push_str(&format!(", target {} mg/mL", concentration));
+            }
+            if let Some(notes) = protocol.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+                line.push_str(&format!(" ({})", notes.trim()));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Summarize several cached papers into one protocol-focused brief
+///
+/// The brief covers consensus findings, reported dosing ranges, safety
+/// signals and evidence quality. It is saved to the summary history with the
+/// papers as its references.
+#[tauri::command]
+pub async fn summarize_corpus(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: SummarizeCorpusPayload,
+) -> Result<SummaryHistory, CommandError> {
+    if payload.entry_ids.is_empty() {
+        return Err(CommandError::invalid_input(
+            "Select at least one paper to summarize",
+        ));
+    }
+
+    let literature = state
+        .db
+        .run(|storage| storage.list_literature())
+        .await
+        .map_err(|e| {
+            error!("Failed to load literature: {:#}", e);
+            CommandError::with_context(e, "Failed to load literature")
+        })?;
+    let entries = payload
+        .entry_ids
+        .iter()
+        .map(|id| {
+            literature
+                .iter()
+                .find(|entry| &entry.id == id)
+                .ok_or_else(|| {
+                    CommandError::not_found(format!("Literature entry {} not found", id))
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut protocols = state
+        .db
+        .run(|storage| storage.list_protocols())
+        .await
+        .map_err(|e| {
+            error!("Failed to load protocols: {:#}", e);
+            CommandError::with_context(e, "Failed to load protocols")
+        })?;
+    if let Some(ids) = &payload.protocol_ids {
+        protocols.retain(|protocol| ids.contains(&protocol.id));
+    }
+
+    let title = payload
+        .title
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| format!("Research brief: {} papers", entries.len()));
+    let documents: Vec<CorpusDocument> =
+        entries.iter().map(|entry| corpus_document(entry)).collect();
+    let context = protocol_context(&protocols);
+    info!("Summarizing {} papers into \"{}\"", documents.len(), title);
+
+    let response = summarize_documents(state.ai_client.as_ref(), &title, &documents, &context)
+        .await
+        .map_err(|err| {
+            warn!("Corpus summarization failed: {:#}", err);
+            CommandError::internal(format!(
+                "AI summarization failed: {}. Make sure Codex CLI or Claude CLI is installed.",
+                err
+            ))
+        })?;
+
+    let original_content = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| format!("[{}] {}", index + 1, entry.title))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut summary = SummaryHistory::new(
+        title,
+        original_content,
+        response.raw_output,
+        format!("{:?}", SummaryFormat::Markdown),
+        format!("{:?}", response.provider),
+    );
+    summary.references = entries.iter().map(|entry| entry.id.clone()).collect();
+    summary.usage = Some(usage_record(response.usage));
+
+    state
+        .db
+        .run(move |storage| storage.save_summary(&summary).map(|_| summary))
+        .await
+        .map_err(|e| {
+            error!("Failed to save summary: {:#}", e);
+            CommandError::with_context(e, "Failed to save summary")
+        })
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::SystemTime;
+
+    use peptrack_local_ai::ProviderHealth;
+
     use super::*;
 
+    #[test]
+    fn test_health_status_from_report() {
+        let report = HealthReport {
+            providers: vec![
+                ProviderHealth {
+                    provider: AiProvider::Codex,
+                    model: "gpt-5".into(),
+                    installed: false,
+                    healthy: false,
+                    latency: None,
+                    error: Some("CLI not found in PATH".into()),
+                },
+                ProviderHealth {
+                    provider: AiProvider::Claude,
+                    model: "claude-haiku-4-5".into(),
+                    installed: true,
+                    healthy: true,
+                    latency: Some(Duration::from_millis(1500)),
+                    error: None,
+                },
+            ],
+            checked_at: SystemTime::now(),
+        };
+        let status = AiHealthStatus::from(report);
+
+        assert_eq!(status.active_provider.as_deref(), Some("Claude"));
+        assert_eq!(status.providers[0].provider, "Codex");
+        assert_eq!(status.providers[0].latency_ms, None);
+        assert_eq!(status.providers[1].latency_ms, Some(1500));
+    }
+
+    #[test]
+    fn test_citation_line() {
+        let mut entry = LiteratureEntry::new("crossref", "Paper");
+        assert_eq!(citation_line(&entry), None);
+
+        entry.authors = vec!["A".into(), "B".into(), "C".into(), "D".into()];
+        entry.journal = Some("Peptides".into());
+        entry.year = Some(2021);
+        assert_eq!(
+            citation_line(&entry).as_deref(),
+            Some("A, B, C et al. Peptides (2021)")
+        );
+    }
+
+    #[test]
+    fn test_protocol_context() {
+        let mut protocol = PeptideProtocol::new("Recovery", "BPC-157");
+        protocol.target_concentration_mg_ml = Some(2.5);
+        protocol.notes = Some("post-surgery".into());
+        let other = PeptideProtocol::new("Sleep", "DSIP");
+
+        assert_eq!(
+            protocol_context(&[protocol, other]),
+            "- Recovery: BPC-157, target 2.5 mg/mL (post-surgery)\n- Sleep: DSIP"
+        );
+    }
+
     #[test]
     fn test_summarize_payload_serialization() {
         let json = r#"{
@@ -180,6 +496,7 @@ mod tests {
         let result = SummarizeResult {
             provider: "Codex".to_string(),
             output: "Summary text".to_string(),
+            usage: AiUsage::default(),
         };
 
         let json = serde_json::to_string(&result);
@@ -277,6 +594,7 @@ mod tests {
         let result = SummarizeResult {
             provider: "Claude".to_string(),
             output: "Test summary".to_string(),
+            usage: AiUsage::default(),
         };
 
         let debug_str = format!("{:?}", result);
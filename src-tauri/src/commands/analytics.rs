@@ -1,8 +1,26 @@
-use peptrack_core::models::{Alert, AlertSeverity, AlertType, PriceHistory, SummaryHistory};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use peptrack_core::models::{
+    AiUsage, Alert, AlertSeverity, AlertType, BulkDiscountTier, PriceHistory, SummaryHistory,
+};
+use peptrack_core::{
+    group_alerts, AiUsageStats, AlertPreferences, AlertRouting, AlertThread, CurrencyConverter,
+    MarkdownExportResult, PriceTrend, PriceTrendFilter, StatsGeneration, StorageManager,
+    SummaryDiff, SupplierRanking,
+};
 use serde::{Deserialize, Serialize};
-use tauri::State;
-use tracing::{error, info};
-
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tauri::{AppHandle, State};
+use tokio::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::commands::currency::resolve_currency;
+use crate::commands::dates::parse_optional_date;
+use crate::commands::forecast::{load_forecast, DEFAULT_HISTORY_DAYS, DEFAULT_LEAD_TIME_DAYS};
+use crate::commands::settings::{load_setting_or_default, save_setting};
+use crate::error::CommandError;
 use crate::state::AppState;
 
 // ========== Price History Commands ==========
@@ -13,33 +31,54 @@ pub struct AddPricePayload {
     pub supplier_id: String,
     pub peptide_name: String,
     pub cost_per_mg: f32,
+    pub currency: Option<String>, // Defaults to the supplier's currency, then USD
     pub url: Option<String>,
     pub in_stock: Option<bool>,
     pub notes: Option<String>,
+    pub vial_size_mg: Option<f32>,
+    pub shipping_cost: Option<f32>, // Per order, in the price's currency
+    pub min_order_vials: Option<u32>,
+    pub bulk_discounts: Option<Vec<BulkDiscountTier>>,
 }
 
 #[tauri::command]
 pub async fn add_price_history(
     state: State<'_, std::sync::Arc<AppState>>,
     payload: AddPricePayload,
-) -> Result<PriceHistory, String> {
-    info!("Adding price history: {} @ ${}/mg", payload.peptide_name, payload.cost_per_mg);
+) -> Result<PriceHistory, CommandError> {
+    info!("Adding price history: {} @ {}/mg", payload.peptide_name, payload.cost_per_mg);
+
+    let supplier_id = payload.supplier_id.clone();
+    let supplier_currency = state
+        .db
+        .run(move |storage| storage.get_supplier(&supplier_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch supplier"))?
+        .and_then(|supplier| supplier.currency);
 
     let mut entry = PriceHistory::new(
         &payload.supplier_id,
         &payload.peptide_name,
         payload.cost_per_mg,
     );
+    entry.currency = resolve_currency(payload.currency, supplier_currency.as_deref())?;
     entry.url = payload.url;
     entry.in_stock = payload.in_stock;
     entry.notes = payload.notes;
+    entry.vial_size_mg = payload.vial_size_mg;
+    entry.shipping_cost = payload.shipping_cost;
+    entry.min_order_vials = payload.min_order_vials;
+    entry.bulk_discounts = payload.bulk_discounts.unwrap_or_default();
+    entry.validate().map_err(CommandError::invalid_input)?;
 
-    state.storage.add_price_history(&entry).map_err(|e| {
-        error!("Failed to add price history: {:#}", e);
-        format!("Failed to add price history: {}", e)
-    })?;
-
-    Ok(entry)
+    state
+        .db
+        .run(move |storage| storage.add_price_history(&entry).map(|_| entry))
+        .await
+        .map_err(|e| {
+            error!("Failed to add price history: {:#}", e);
+            CommandError::with_context(e, "Failed to add price history")
+        })
 }
 
 #[tauri::command]
@@ -47,13 +86,16 @@ pub async fn list_price_history(
     state: State<'_, std::sync::Arc<AppState>>,
     supplier_id: String,
     peptide_name: Option<String>,
-) -> Result<Vec<PriceHistory>, String> {
+) -> Result<Vec<PriceHistory>, CommandError> {
     state
-        .storage
-        .list_price_history_for_supplier(&supplier_id, peptide_name.as_deref())
+        .db
+        .run(move |storage| {
+            storage.list_price_history_for_supplier(&supplier_id, peptide_name.as_deref())
+        })
+        .await
         .map_err(|e| {
             error!("Failed to list price history: {:#}", e);
-            format!("Failed to list price history: {}", e)
+            CommandError::with_context(e, "Failed to list price history")
         })
 }
 
@@ -62,16 +104,92 @@ pub async fn get_latest_price(
     state: State<'_, std::sync::Arc<AppState>>,
     supplier_id: String,
     peptide_name: String,
-) -> Result<Option<PriceHistory>, String> {
+) -> Result<Option<PriceHistory>, CommandError> {
     state
-        .storage
-        .get_latest_price(&supplier_id, &peptide_name)
+        .db
+        .run(move |storage| storage.get_latest_price(&supplier_id, &peptide_name))
+        .await
         .map_err(|e| {
             error!("Failed to get latest price: {:#}", e);
-            format!("Failed to get latest price: {}", e)
+            CommandError::with_context(e, "Failed to get latest price")
         })
 }
 
+/// Which prices to chart; dates are RFC3339 strings and both days are included
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceTrendPayload {
+    pub supplier_id: Option<String>,
+    pub peptide_name: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    /// Most points per series, 60 by default
+    pub max_points: Option<u32>,
+    /// Convert every series to this currency; series without an exchange
+    /// rate are left out
+    pub display_currency: Option<String>,
+}
+
+/// Price history downsampled for charting, per supplier and peptide
+///
+/// Computed in SQL from the price columns, without decrypting the entries.
+#[tauri::command]
+pub async fn get_price_trend(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: Option<PriceTrendPayload>,
+) -> Result<PriceTrend, CommandError> {
+    let payload = payload.unwrap_or_default();
+    let filter = PriceTrendFilter {
+        supplier_id: payload.supplier_id,
+        peptide_name: payload.peptide_name,
+        since: parse_optional_date(payload.start_date.as_deref())?,
+        until: parse_optional_date(payload.end_date.as_deref())?,
+        max_points: payload.max_points.unwrap_or(0),
+    };
+    let currency = payload
+        .display_currency
+        .map(|currency| resolve_currency(Some(currency), None))
+        .transpose()?;
+
+    let mut trend = state
+        .db
+        .run(move |storage| storage.price_trend(&filter))
+        .await
+        .map_err(|e| {
+            error!("Failed to compute price trend: {:#}", e);
+            CommandError::with_context(e, "Failed to compute price trend")
+        })?;
+
+    if let Some(currency) = currency {
+        let rates = state
+            .db
+            .run(|storage| storage.list_exchange_rates())
+            .await
+            .map_err(|e| {
+                error!("Failed to list exchange rates: {:#}", e);
+                CommandError::with_context(e, "Failed to list exchange rates")
+            })?;
+        let converter = CurrencyConverter::new(&rates);
+        trend.series.retain_mut(|series| {
+            let rate = match converter.convert(1.0, &series.currency, &currency) {
+                Ok(rate) => rate,
+                Err(e) => {
+                    warn!("Leaving {} prices out of the trend: {:#}", series.currency, e);
+                    return false;
+                }
+            };
+            for point in &mut series.points {
+                point.min_cost_per_mg *= rate;
+                point.max_cost_per_mg *= rate;
+                point.avg_cost_per_mg *= rate;
+            }
+            series.currency = currency.clone();
+            true
+        });
+    }
+    Ok(trend)
+}
+
 // ========== Alert Commands ==========
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -89,7 +207,7 @@ pub struct CreateAlertPayload {
 pub async fn create_alert(
     state: State<'_, std::sync::Arc<AppState>>,
     payload: CreateAlertPayload,
-) -> Result<Alert, String> {
+) -> Result<Alert, CommandError> {
     info!("Creating alert: {}", payload.title);
 
     let mut alert = Alert::new(
@@ -101,10 +219,17 @@ pub async fn create_alert(
     alert.related_id = payload.related_id;
     alert.related_type = payload.related_type;
 
-    state.storage.create_alert(&alert).map_err(|e| {
-        error!("Failed to create alert: {:#}", e);
-        format!("Failed to create alert: {}", e)
-    })?;
+    // Created by hand, so mutes don't apply
+    let alert = state
+        .db
+        .run(move |storage| storage.create_alert(&alert).map(|_| alert))
+        .await
+        .map_err(|e| {
+            error!("Failed to create alert: {:#}", e);
+            CommandError::with_context(e, "Failed to create alert")
+        })?;
+    let preferences: AlertPreferences = load_setting_or_default(&state).await;
+    state.notifier.alert(&alert, preferences.routing.delivery(&alert.severity));
 
     Ok(alert)
 }
@@ -113,13 +238,15 @@ pub async fn create_alert(
 pub async fn list_alerts(
     state: State<'_, std::sync::Arc<AppState>>,
     include_dismissed: Option<bool>,
-) -> Result<Vec<Alert>, String> {
+) -> Result<Vec<Alert>, CommandError> {
+    let include_dismissed = include_dismissed.unwrap_or(false);
     state
-        .storage
-        .list_alerts(include_dismissed.unwrap_or(false))
+        .db
+        .run(move |storage| storage.list_alerts(include_dismissed))
+        .await
         .map_err(|e| {
             error!("Failed to list alerts: {:#}", e);
-            format!("Failed to list alerts: {}", e)
+            CommandError::with_context(e, "Failed to list alerts")
         })
 }
 
@@ -127,33 +254,140 @@ pub async fn list_alerts(
 pub async fn mark_alert_read(
     state: State<'_, std::sync::Arc<AppState>>,
     alert_id: String,
-) -> Result<(), String> {
-    state.storage.mark_alert_read(&alert_id).map_err(|e| {
-        error!("Failed to mark alert as read: {:#}", e);
-        format!("Failed to mark alert as read: {}", e)
-    })
+) -> Result<(), CommandError> {
+    state
+        .db
+        .run(move |storage| storage.mark_alert_read(&alert_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to mark alert as read: {:#}", e);
+            CommandError::with_context(e, "Failed to mark alert as read")
+        })
 }
 
 #[tauri::command]
 pub async fn dismiss_alert(
     state: State<'_, std::sync::Arc<AppState>>,
     alert_id: String,
-) -> Result<(), String> {
-    state.storage.dismiss_alert(&alert_id).map_err(|e| {
-        error!("Failed to dismiss alert: {:#}", e);
-        format!("Failed to dismiss alert: {}", e)
-    })
+) -> Result<(), CommandError> {
+    state
+        .db
+        .run(move |storage| storage.dismiss_alert(&alert_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to dismiss alert: {:#}", e);
+            CommandError::with_context(e, "Failed to dismiss alert")
+        })
 }
 
 #[tauri::command]
 pub async fn clear_all_alerts(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     info!("Clearing all alerts");
-    state.storage.clear_all_alerts().map_err(|e| {
-        error!("Failed to clear alerts: {:#}", e);
-        format!("Failed to clear alerts: {}", e)
-    })
+    state
+        .db
+        .run(|storage| storage.clear_all_alerts())
+        .await
+        .map_err(|e| {
+            error!("Failed to clear alerts: {:#}", e);
+            CommandError::with_context(e, "Failed to clear alerts")
+        })
+}
+
+/// Alerts grouped so repeats of the same type and item show once with a count
+#[tauri::command]
+pub async fn list_alert_threads(
+    state: State<'_, std::sync::Arc<AppState>>,
+    include_dismissed: Option<bool>,
+) -> Result<Vec<AlertThread>, CommandError> {
+    let include_dismissed = include_dismissed.unwrap_or(false);
+    let alerts = state
+        .db
+        .run(move |storage| storage.list_alerts(include_dismissed))
+        .await
+        .map_err(|e| {
+            error!("Failed to list alerts: {:#}", e);
+            CommandError::with_context(e, "Failed to list alerts")
+        })?;
+    Ok(group_alerts(alerts))
+}
+
+/// Dismisses every alert in a thread
+#[tauri::command]
+pub async fn dismiss_alerts(
+    state: State<'_, std::sync::Arc<AppState>>,
+    alert_ids: Vec<String>,
+) -> Result<(), CommandError> {
+    for alert_id in alert_ids {
+        let id = alert_id.clone();
+        state
+            .db
+            .run(move |storage| storage.dismiss_alert(&id))
+            .await
+            .map_err(|e| {
+                error!("Failed to dismiss alert {}: {:#}", alert_id, e);
+                CommandError::with_context(e, "Failed to dismiss alerts")
+            })?;
+    }
+    Ok(())
+}
+
+/// Active mutes and how each severity is delivered
+#[tauri::command]
+pub async fn get_alert_preferences(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<AlertPreferences, CommandError> {
+    let mut preferences: AlertPreferences = load_setting_or_default(&state).await;
+    preferences.prune(OffsetDateTime::now_utc());
+    Ok(preferences)
+}
+
+/// Chooses which severities show as OS notifications and which stay in-app
+#[tauri::command]
+pub async fn update_alert_routing(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    routing: AlertRouting,
+) -> Result<AlertPreferences, CommandError> {
+    let mut preferences: AlertPreferences = load_setting_or_default(&state).await;
+    preferences.routing = routing;
+    save_setting(&app, &state, &preferences).await?;
+    Ok(preferences)
+}
+
+/// Mutes an alert type, an item's alerts, or one type for one item, for `days`
+#[tauri::command]
+pub async fn mute_alerts(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    alert_type: Option<AlertType>,
+    related_id: Option<String>,
+    days: u32,
+) -> Result<AlertPreferences, CommandError> {
+    let mut preferences: AlertPreferences = load_setting_or_default(&state).await;
+    preferences
+        .mute(alert_type, related_id, days, OffsetDateTime::now_utc())
+        .map_err(CommandError::invalid_input)?;
+    save_setting(&app, &state, &preferences).await?;
+    info!("Muted alerts for {} days", days);
+    Ok(preferences)
+}
+
+/// Removes a mute before it expires
+#[tauri::command]
+pub async fn unmute_alerts(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    alert_type: Option<AlertType>,
+    related_id: Option<String>,
+) -> Result<AlertPreferences, CommandError> {
+    let mut preferences: AlertPreferences = load_setting_or_default(&state).await;
+    if !preferences.unmute(alert_type.as_ref(), related_id.as_deref()) {
+        return Err(CommandError::not_found("No such mute"));
+    }
+    save_setting(&app, &state, &preferences).await?;
+    Ok(preferences)
 }
 
 // ========== Summary History Commands ==========
@@ -166,52 +400,196 @@ pub struct SaveSummaryPayload {
     pub summary_output: String,
     pub format: String,
     pub provider: String,
+    /// IDs of the cached literature entries summarized
+    #[serde(default)]
+    pub references: Vec<String>,
+    /// Usage reported when the summary was generated
+    #[serde(default)]
+    pub usage: Option<AiUsage>,
+    /// Literature entry summarized; defaults to the single reference, or
+    /// a hash of the original content
+    #[serde(default)]
+    pub source_id: Option<String>,
 }
 
 #[tauri::command]
 pub async fn save_summary(
     state: State<'_, std::sync::Arc<AppState>>,
     payload: SaveSummaryPayload,
-) -> Result<SummaryHistory, String> {
+) -> Result<SummaryHistory, CommandError> {
     info!("Saving summary: {}", payload.title);
 
-    let summary = SummaryHistory::new(
+    let mut summary = SummaryHistory::new(
         &payload.title,
         &payload.original_content,
         &payload.summary_output,
         &payload.format,
         &payload.provider,
     );
+    if let Some(source_id) = payload.source_id.or_else(|| match payload.references.as_slice() {
+        [entry_id] => Some(entry_id.clone()),
+        _ => None,
+    }) {
+        summary.source_id = source_id;
+    }
+    summary.references = payload.references;
+    summary.usage = payload.usage;
 
-    state.storage.save_summary(&summary).map_err(|e| {
-        error!("Failed to save summary: {:#}", e);
-        format!("Failed to save summary: {}", e)
-    })?;
-
-    Ok(summary)
+    state
+        .db
+        .run(move |storage| storage.save_summary(&summary).map(|_| summary))
+        .await
+        .map_err(|e| {
+            error!("Failed to save summary: {:#}", e);
+            CommandError::with_context(e, "Failed to save summary")
+        })
 }
 
 #[tauri::command]
 pub async fn list_summary_history(
     state: State<'_, std::sync::Arc<AppState>>,
     limit: Option<usize>,
-) -> Result<Vec<SummaryHistory>, String> {
-    state.storage.list_summary_history(limit).map_err(|e| {
-        error!("Failed to list summary history: {:#}", e);
-        format!("Failed to list summary history: {}", e)
-    })
+) -> Result<Vec<SummaryHistory>, CommandError> {
+    state
+        .db
+        .run(move |storage| storage.list_summary_history(limit))
+        .await
+        .map_err(|e| {
+            error!("Failed to list summary history: {:#}", e);
+            CommandError::with_context(e, "Failed to list summary history")
+        })
+}
+
+/// Every saved version of a summary of one paper or text, newest first
+#[tauri::command]
+pub async fn list_summaries_for_source(
+    state: State<'_, std::sync::Arc<AppState>>,
+    source_id: String,
+) -> Result<Vec<SummaryHistory>, CommandError> {
+    let id = source_id.clone();
+    state
+        .db
+        .run(move |storage| storage.list_summaries_for_source(&id))
+        .await
+        .map_err(|e| {
+            error!("Failed to list summaries for source {}: {:#}", source_id, e);
+            CommandError::with_context(e, "Failed to list summaries for source")
+        })
+}
+
+/// Line diff of two saved summaries, from `old_id` to `new_id`
+#[tauri::command]
+pub async fn diff_summaries(
+    state: State<'_, std::sync::Arc<AppState>>,
+    old_id: String,
+    new_id: String,
+) -> Result<SummaryDiff, CommandError> {
+    let old = load_summary(&state, old_id).await?;
+    let new = load_summary(&state, new_id).await?;
+
+    Ok(peptrack_core::diff_summaries(&old, &new))
+}
+
+async fn load_summary(state: &AppState, id: String) -> Result<SummaryHistory, CommandError> {
+    let summary_id = id.clone();
+    state
+        .db
+        .run(move |storage| storage.get_summary(&summary_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to load summary {}: {:#}", id, e);
+            CommandError::with_context(e, "Failed to load summary")
+        })?
+        .ok_or_else(|| CommandError::not_found(format!("Summary {} not found", id)))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSummariesPayload {
+    /// Folder to write into, e.g. a folder in an Obsidian vault
+    pub folder: String,
+    /// Only write summaries that are new or changed since the last export
+    #[serde(default)]
+    pub incremental: bool,
+}
+
+/// Writes every saved summary as a Markdown file with front matter
+#[tauri::command]
+pub async fn export_summaries_markdown(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: ExportSummariesPayload,
+) -> Result<MarkdownExportResult, CommandError> {
+    if payload.folder.trim().is_empty() {
+        return Err(CommandError::invalid_input("Choose a folder to export to"));
+    }
+    let summaries = state
+        .db
+        .run(|storage| storage.list_summary_history(None))
+        .await
+        .map_err(|e| {
+            error!("Failed to list summaries for export: {:#}", e);
+            CommandError::with_context(e, "Failed to list summary history")
+        })?;
+    let literature = state
+        .db
+        .run(|storage| storage.list_literature())
+        .await
+        .map_err(|e| {
+            error!("Failed to list literature for export: {:#}", e);
+            CommandError::with_context(e, "Failed to list literature")
+        })?;
+
+    let folder = std::path::PathBuf::from(payload.folder.trim());
+    let result = peptrack_core::export_summaries_markdown(&folder, &summaries, &literature, payload.incremental)
+        .map_err(|e| {
+            error!("Failed to export summaries to {}: {:#}", folder.display(), e);
+            CommandError::with_context(e, "Failed to export summaries")
+        })?;
+
+    info!(
+        "Exported {} summaries to {} ({} unchanged)",
+        result.written.len(),
+        folder.display(),
+        result.unchanged
+    );
+    Ok(result)
 }
 
 #[tauri::command]
 pub async fn delete_summary(
     state: State<'_, std::sync::Arc<AppState>>,
     summary_id: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     info!("Deleting summary: {}", summary_id);
-    state.storage.delete_summary(&summary_id).map_err(|e| {
-        error!("Failed to delete summary: {:#}", e);
-        format!("Failed to delete summary: {}", e)
-    })
+    state
+        .db
+        .run(move |storage| storage.delete_summary(&summary_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to delete summary: {:#}", e);
+            CommandError::with_context(e, "Failed to delete summary")
+        })
+}
+
+/// AI token and cost totals per provider and per day
+///
+/// Covers summaries from the last `days` days, or all of them when omitted.
+#[tauri::command]
+pub async fn get_ai_usage_stats(
+    state: State<'_, std::sync::Arc<AppState>>,
+    days: Option<u32>,
+) -> Result<AiUsageStats, CommandError> {
+    let since = days.map(|days| {
+        (time::OffsetDateTime::now_utc() - time::Duration::days(i64::from(days))).date()
+    });
+    state
+        .db
+        .run(move |storage| storage.ai_usage_stats(since))
+        .await
+        .map_err(|e| {
+            error!("Failed to compute AI usage stats: {:#}", e);
+            CommandError::with_context(e, "Failed to compute AI usage stats")
+        })
 }
 
 // ========== Analytics & Reporting Commands ==========
@@ -230,178 +608,331 @@ pub struct InventoryPrediction {
     pub threshold_days: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Days after which a price quote is flagged as stale
+pub const STALE_QUOTE_DAYS: i64 = 30;
+/// How long a price comparison is served from the cache
+const COMPARISON_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PriceComparison {
     pub peptide_name: String,
+    pub currency: String,
+    /// Order size the landed costs are for; `None` means each supplier's
+    /// minimum order
+    pub order_mg: Option<f32>,
+    /// Cheapest landed cost first
     pub suppliers: Vec<SupplierPrice>,
     pub lowest_price: f32,
     pub highest_price: f32,
     pub average_price: f32,
+    /// Currencies that were skipped because no exchange rate is configured
+    pub missing_rates: Vec<String>,
+    /// RFC3339 time the comparison was computed
+    pub computed_at: String,
+    /// Whether it was served from the cache
+    pub cached: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SupplierPrice {
     pub supplier_id: String,
     pub supplier_name: String,
+    /// Price converted to the comparison currency
     pub cost_per_mg: f32,
+    pub original_cost_per_mg: f32,
+    pub original_currency: String,
+    /// Landed cost per mg of the order, with shipping and bulk discounts,
+    /// converted to the comparison currency
+    pub landed_cost_per_mg: f32,
+    /// What the order comes to in the comparison currency
+    pub order_total: f32,
+    pub order_quantity_mg: f32,
+    pub vials: Option<u32>,
+    pub discount_percent: f32,
     pub in_stock: Option<bool>,
     pub recorded_at: String,
+    /// Whole days since the price was recorded
+    pub age_days: i64,
+    /// Older than [`STALE_QUOTE_DAYS`]
+    pub stale: bool,
+}
+
+/// Recent price comparisons, so reopening a comparison doesn't reload and
+/// convert every supplier's prices
+///
+/// Entries are dropped after [`COMPARISON_CACHE_TTL`], and as soon as
+/// anything is written to storage: every write bumps the
+/// [`StatsGeneration`], so a changed price or exchange rate is never
+/// served stale.
+#[derive(Default)]
+pub struct PriceComparisonCache {
+    entries: Mutex<HashMap<ComparisonKey, CachedComparison>>,
+}
+
+/// Peptide, currency and order size (as `f32` bits) of a comparison
+type ComparisonKey = (String, String, Option<u32>);
+
+struct CachedComparison {
+    comparison: PriceComparison,
+    generation: StatsGeneration,
+    cached_at: Instant,
+}
+
+impl PriceComparisonCache {
+    fn get(&self, key: &ComparisonKey, generation: StatsGeneration) -> Option<PriceComparison> {
+        let mut entries = self.entries.lock().ok()?;
+        // Only entries from the current generation can still be valid
+        entries.retain(|_, entry| entry.generation == generation);
+        let entry = entries.get(key)?;
+        if entry.cached_at.elapsed() >= COMPARISON_CACHE_TTL {
+            entries.remove(key);
+            return None;
+        }
+        Some(entry.comparison.clone())
+    }
+
+    fn insert(&self, key: ComparisonKey, comparison: PriceComparison, generation: StatsGeneration) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(
+                key,
+                CachedComparison { comparison, generation, cached_at: Instant::now() },
+            );
+        }
+    }
+}
+
+/// Whole days since `recorded_at`, and whether that makes the quote stale
+fn quote_age(recorded_at: OffsetDateTime, now: OffsetDateTime) -> (i64, bool) {
+    let age_days = (now - recorded_at).whole_days().max(0);
+    (age_days, age_days >= STALE_QUOTE_DAYS)
 }
 
+/// Compares each supplier's latest price for `peptide_name`, cheapest
+/// landed cost first
+///
+/// Results are cached for a few minutes; `refresh` recomputes them.
 #[tauri::command]
 pub async fn compare_prices(
     state: State<'_, std::sync::Arc<AppState>>,
     peptide_name: String,
-) -> Result<PriceComparison, String> {
-    info!("Comparing prices for: {}", peptide_name);
-
-    // Get all suppliers
-    let suppliers = state.storage.list_suppliers().map_err(|e| {
-        error!("Failed to list suppliers: {:#}", e);
-        format!("Failed to list suppliers: {}", e)
-    })?;
-
-    let mut supplier_prices = Vec::new();
+    display_currency: Option<String>,
+    order_mg: Option<f32>,
+    refresh: Option<bool>,
+) -> Result<PriceComparison, CommandError> {
+    let currency = resolve_currency(display_currency, None)?;
+    if order_mg.is_some_and(|mg| !mg.is_finite() || mg <= 0.0) {
+        return Err(CommandError::invalid_input("Order size must be more than 0 mg"));
+    }
 
-    for supplier in suppliers {
-        if let Ok(Some(price_entry)) = state
-            .storage
-            .get_latest_price(&supplier.id, &peptide_name)
-        {
-            supplier_prices.push(SupplierPrice {
-                supplier_id: supplier.id.clone(),
-                supplier_name: supplier.name.clone(),
-                cost_per_mg: price_entry.cost_per_mg,
-                in_stock: price_entry.in_stock,
-                recorded_at: price_entry.recorded_at.to_string(),
-            });
+    let key = (peptide_name.clone(), currency.clone(), order_mg.map(f32::to_bits));
+    // An atomic read, so it doesn't need the blocking pool
+    let generation = state.db.blocking().stats_generation();
+    if !refresh.unwrap_or(false) {
+        if let Some(mut cached) = state.price_comparisons.get(&key, generation) {
+            cached.cached = true;
+            return Ok(cached);
         }
     }
+    info!("Comparing prices for: {} in {}", peptide_name, currency);
+
+    let (mut supplier_prices, missing_rates) =
+        load_supplier_prices(&state, peptide_name.clone(), currency.clone(), order_mg).await?;
 
     if supplier_prices.is_empty() {
-        return Err(format!("No price data found for {}", peptide_name));
+        if !missing_rates.is_empty() {
+            return Err(CommandError::not_found(format!(
+                "No exchange rate to convert {} into {}",
+                missing_rates.join(", "),
+                currency
+            )));
+        }
+        return Err(CommandError::not_found(format!("No price data found for {}", peptide_name)));
     }
 
-    let prices: Vec<f32> = supplier_prices.iter().map(|sp| sp.cost_per_mg).collect();
+    supplier_prices.sort_by(|a, b| a.landed_cost_per_mg.total_cmp(&b.landed_cost_per_mg));
+    let prices: Vec<f32> = supplier_prices.iter().map(|sp| sp.landed_cost_per_mg).collect();
     let lowest_price = prices.iter().cloned().fold(f32::INFINITY, f32::min);
     let highest_price = prices.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
     let average_price = prices.iter().sum::<f32>() / prices.len() as f32;
 
-    Ok(PriceComparison {
+    let now = OffsetDateTime::now_utc();
+    let comparison = PriceComparison {
         peptide_name,
+        currency,
+        order_mg,
         suppliers: supplier_prices,
         lowest_price,
         highest_price,
         average_price,
-    })
+        missing_rates,
+        computed_at: now.format(&Rfc3339).unwrap_or_else(|_| now.to_string()),
+        cached: false,
+    };
+    state.price_comparisons.insert(key, comparison.clone(), generation);
+    Ok(comparison)
 }
 
-/// Predict inventory depletion based on dose history
+/// Each supplier's latest price for `peptide_name` and landed cost for an
+/// order of `order_mg`, converted to `currency`
 ///
-/// Analyzes dose logs over the past `analysis_days` to calculate average daily usage.
-/// Returns predictions for all inventory items, flagging those that will run out
-/// within `threshold_days`.
-#[tauri::command]
-pub async fn predict_inventory_depletion(
-    state: State<'_, std::sync::Arc<AppState>>,
-    threshold_days: Option<i32>,
-    analysis_days: Option<i32>,
-) -> Result<Vec<InventoryPrediction>, String> {
-    let threshold = threshold_days.unwrap_or(14); // Default: warn 14 days before depletion
-    let lookback = analysis_days.unwrap_or(30); // Default: analyze last 30 days
+/// Also returns the currencies that were skipped for lack of an exchange rate.
+fn latest_supplier_prices(
+    storage: &StorageManager,
+    peptide_name: &str,
+    currency: &str,
+    order_mg: Option<f32>,
+) -> Result<(Vec<SupplierPrice>, Vec<String>), CommandError> {
+    let converter = CurrencyConverter::new(&storage.list_exchange_rates().map_err(|e| {
+        error!("Failed to list exchange rates: {:#}", e);
+        CommandError::with_context(e, "Failed to list exchange rates")
+    })?);
 
-    info!("Predicting inventory depletion (threshold: {} days, lookback: {} days)", threshold, lookback);
-
-    // Get all inventory items
-    let inventory = state.storage.list_inventory().map_err(|e| {
-        error!("Failed to list inventory: {:#}", e);
-        format!("Failed to list inventory: {}", e)
-    })?;
-
-    // Get all protocols for name lookup
-    let protocols = state.storage.list_protocols().map_err(|e| {
-        error!("Failed to list protocols: {:#}", e);
-        format!("Failed to list protocols: {}", e)
+    // Get all suppliers
+    let suppliers = storage.list_suppliers().map_err(|e| {
+        error!("Failed to list suppliers: {:#}", e);
+        CommandError::with_context(e, "Failed to list suppliers")
     })?;
 
-    let mut predictions = Vec::new();
-
-    for item in inventory {
-        // Skip if no remaining quantity
-        let current_qty = match item.quantity_remaining_mg {
-            Some(qty) if qty > 0.0 => qty,
-            _ => continue,
-        };
+    let mut supplier_prices = Vec::new();
+    let mut missing_rates = Vec::new();
+    let now = OffsetDateTime::now_utc();
 
-        // Get protocol details
-        let protocol = protocols.iter().find(|p| p.id == item.protocol_id);
-        if protocol.is_none() {
-            continue;
+    for supplier in suppliers {
+        if let Ok(Some(price_entry)) = storage.get_latest_price(&supplier.id, peptide_name) {
+            // Units of `currency` per unit of the price's currency
+            let rate = match converter.convert(1.0, &price_entry.currency, currency) {
+                Ok(value) => value as f32,
+                Err(e) => {
+                    warn!("Skipping {} price from {}: {:#}", price_entry.currency, supplier.name, e);
+                    if !missing_rates.contains(&price_entry.currency) {
+                        missing_rates.push(price_entry.currency.clone());
+                    }
+                    continue;
+                }
+            };
+
+            let landed = price_entry.landed_cost(order_mg);
+            let (age_days, stale) = quote_age(price_entry.recorded_at, now);
+            supplier_prices.push(SupplierPrice {
+                supplier_id: supplier.id.clone(),
+                supplier_name: supplier.name.clone(),
+                cost_per_mg: price_entry.cost_per_mg * rate,
+                original_cost_per_mg: price_entry.cost_per_mg,
+                original_currency: price_entry.currency.clone(),
+                landed_cost_per_mg: landed.cost_per_mg * rate,
+                order_total: landed.total_cost * rate,
+                order_quantity_mg: landed.quantity_mg,
+                vials: landed.vials,
+                discount_percent: landed.discount_percent,
+                in_stock: price_entry.in_stock,
+                recorded_at: price_entry.recorded_at.to_string(),
+                age_days,
+                stale,
+            });
         }
-        let protocol = protocol.unwrap();
-
-        // Get dose logs for this protocol
-        let dose_logs = state
-            .storage
-            .list_dose_logs_for_protocol(&item.protocol_id)
-            .map_err(|e| {
-                error!("Failed to get dose logs for protocol {}: {:#}", item.protocol_id, e);
-                format!("Failed to get dose logs: {}", e)
-            })?;
+    }
+    Ok((supplier_prices, missing_rates))
+}
 
-        // Calculate average daily usage from recent doses
-        let now = time::OffsetDateTime::now_utc();
-        let cutoff_date = now - time::Duration::days(lookback as i64);
+/// [`latest_supplier_prices`] off the async runtime
+async fn load_supplier_prices(
+    state: &AppState,
+    peptide_name: String,
+    currency: String,
+    order_mg: Option<f32>,
+) -> Result<(Vec<SupplierPrice>, Vec<String>), CommandError> {
+    state
+        .db
+        .run(move |storage| Ok(latest_supplier_prices(storage, &peptide_name, &currency, order_mg)))
+        .await
+        .map_err(CommandError::from)?
+}
 
-        let recent_doses: Vec<_> = dose_logs
-            .iter()
-            .filter(|log| log.timestamp >= cutoff_date)
-            .collect();
+/// Suppliers ranked by their review ratings, and by the landed cost of their
+/// minimum order of `peptide_name` when one is given
+///
+/// With a peptide, only suppliers with a price for it are ranked.
+#[tauri::command]
+pub async fn rank_suppliers(
+    state: State<'_, std::sync::Arc<AppState>>,
+    peptide_name: Option<String>,
+    display_currency: Option<String>,
+) -> Result<Vec<SupplierRanking>, CommandError> {
+    let suppliers = state
+        .db
+        .run(|storage| storage.list_suppliers())
+        .await
+        .map_err(|e| {
+            error!("Failed to list suppliers: {:#}", e);
+            CommandError::with_context(e, "Failed to list suppliers")
+        })?;
 
-        if recent_doses.is_empty() {
-            // No recent usage, skip prediction
-            continue;
+    let prices = match peptide_name.as_deref().map(str::trim).filter(|name| !name.is_empty()) {
+        Some(peptide_name) => {
+            let currency = resolve_currency(display_currency, None)?;
+            let (supplier_prices, _) =
+                load_supplier_prices(&state, peptide_name.to_string(), currency, None).await?;
+            Some(
+                supplier_prices
+                    .into_iter()
+                    .map(|price| (price.supplier_id, price.landed_cost_per_mg))
+                    .collect::<HashMap<_, _>>(),
+            )
         }
+        None => None,
+    };
 
-        // Calculate total usage and days span
-        let total_usage: f32 = recent_doses
-            .iter()
-            .filter_map(|log| log.amount_mg)
-            .sum();
-
-        // Find the date range of doses
-        let oldest_dose = recent_doses.iter().map(|log| log.timestamp).min().unwrap();
-        let newest_dose = recent_doses.iter().map(|log| log.timestamp).max().unwrap();
-        let days_span = (newest_dose - oldest_dose).whole_days() + 1; // +1 to include both endpoints
+    Ok(peptrack_core::rank_suppliers(&suppliers, prices.as_ref()))
+}
 
-        if days_span <= 0 {
-            continue;
-        }
+/// Predict inventory depletion based on dose history and schedules
+///
+/// Uses the inventory forecast: scheduled protocols are projected from their
+/// upcoming doses, others from a recency-weighted average over the past
+/// `analysis_days`. Returns predictions for every vial expected to be used up,
+/// flagging those that will run out within `threshold_days`.
+#[tauri::command]
+pub async fn predict_inventory_depletion(
+    state: State<'_, std::sync::Arc<AppState>>,
+    threshold_days: Option<i32>,
+    analysis_days: Option<i32>,
+) -> Result<Vec<InventoryPrediction>, CommandError> {
+    let threshold = threshold_days.unwrap_or(14); // Default: warn 14 days before depletion
+    let lookback = analysis_days.map(i64::from).unwrap_or(DEFAULT_HISTORY_DAYS);
 
-        let average_daily_usage = total_usage / days_span as f32;
+    info!("Predicting inventory depletion (threshold: {} days, lookback: {} days)", threshold, lookback);
 
-        if average_daily_usage <= 0.0 {
-            continue;
-        }
+    let forecast = state
+        .db
+        .run(move |storage| load_forecast(storage, DEFAULT_LEAD_TIME_DAYS, lookback))
+        .await
+        .map_err(|e| {
+            error!("Failed to build inventory forecast: {:#}", e);
+            CommandError::with_context(e, "Failed to build inventory forecast")
+        })?;
 
-        // Calculate estimated days remaining
-        let estimated_days_remaining = current_qty / average_daily_usage;
-        let will_run_out_soon = estimated_days_remaining <= threshold as f32;
-
-        predictions.push(InventoryPrediction {
-            inventory_id: item.id.clone(),
-            protocol_id: item.protocol_id.clone(),
-            protocol_name: protocol.name.clone(),
-            peptide_name: protocol.peptide_name.clone(),
-            current_quantity_mg: current_qty,
-            average_daily_usage_mg: average_daily_usage,
-            estimated_days_remaining,
-            will_run_out_soon,
-            threshold_days: threshold,
-        });
-    }
+    let predictions = forecast
+        .protocols
+        .iter()
+        .flat_map(|protocol| {
+            protocol.vials.iter().filter_map(move |vial| {
+                let days_remaining = vial.days_remaining?;
+                Some(InventoryPrediction {
+                    inventory_id: vial.inventory_id.clone(),
+                    protocol_id: protocol.protocol_id.clone(),
+                    protocol_name: protocol.protocol_name.clone(),
+                    peptide_name: protocol.peptide_name.clone(),
+                    current_quantity_mg: vial.remaining_mg,
+                    average_daily_usage_mg: protocol.daily_usage_mg(),
+                    estimated_days_remaining: days_remaining,
+                    will_run_out_soon: days_remaining <= threshold as f32,
+                    threshold_days: threshold,
+                })
+            })
+        })
+        .collect();
 
     Ok(predictions)
 }
@@ -415,7 +946,7 @@ pub async fn check_inventory_and_create_alerts(
     state: State<'_, std::sync::Arc<AppState>>,
     threshold_days: Option<i32>,
     analysis_days: Option<i32>,
-) -> Result<Vec<Alert>, String> {
+) -> Result<Vec<Alert>, CommandError> {
     let threshold = threshold_days.unwrap_or(14);
 
     info!("Checking inventory and creating alerts (threshold: {} days)", threshold);
@@ -461,10 +992,14 @@ pub async fn check_inventory_and_create_alerts(
         alert.related_type = Some("inventory".to_string());
 
         // Check if similar alert already exists and is not dismissed
-        let existing_alerts = state.storage.list_alerts(false).map_err(|e| {
-            error!("Failed to check existing alerts: {:#}", e);
-            format!("Failed to check existing alerts: {}", e)
-        })?;
+        let existing_alerts = state
+            .db
+            .run(|storage| storage.list_alerts(false))
+            .await
+            .map_err(|e| {
+                error!("Failed to check existing alerts: {:#}", e);
+                CommandError::with_context(e, "Failed to check existing alerts")
+            })?;
 
         let similar_alert_exists = existing_alerts.iter().any(|a| {
             a.alert_type == AlertType::LowStock
@@ -473,16 +1008,128 @@ pub async fn check_inventory_and_create_alerts(
         });
 
         if !similar_alert_exists {
-            state.storage.create_alert(&alert).map_err(|e| {
-                error!("Failed to create alert: {:#}", e);
-                format!("Failed to create alert: {}", e)
-            })?;
-
-            created_alerts.push(alert);
-            info!("Created low stock alert for: {}", prediction.protocol_name);
+            let raised = alert.clone();
+            let delivery = state
+                .db
+                .run(move |storage| storage.raise_alert(&raised))
+                .await
+                .map_err(|e| {
+                    error!("Failed to create alert: {:#}", e);
+                    CommandError::with_context(e, "Failed to create alert")
+                })?;
+            if let Some(delivery) = delivery {
+                state.notifier.alert(&alert, delivery);
+                created_alerts.push(alert);
+                info!("Created low stock alert for: {}", prediction.protocol_name);
+            }
         }
     }
 
     info!("Created {} new inventory alerts", created_alerts.len());
     Ok(created_alerts)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use peptrack_core::{StaticKeyProvider, StorageConfig};
+    use std::sync::Arc;
+
+    fn comparison(peptide_name: &str) -> PriceComparison {
+        PriceComparison {
+            peptide_name: peptide_name.to_string(),
+            currency: "USD".to_string(),
+            order_mg: None,
+            suppliers: Vec::new(),
+            lowest_price: 1.0,
+            highest_price: 2.0,
+            average_price: 1.5,
+            missing_rates: Vec::new(),
+            computed_at: String::new(),
+            cached: false,
+        }
+    }
+
+    fn key(peptide_name: &str) -> ComparisonKey {
+        (peptide_name.to_string(), "USD".to_string(), None)
+    }
+
+    #[test]
+    fn price_comparisons_are_dropped_when_storage_changes() {
+        let dir = std::env::temp_dir()
+            .join(format!("peptrack-comparisons-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let storage = StorageManager::new(StorageConfig {
+            data_dir: Some(dir.clone()),
+            db_file_name: None,
+            key_provider: Arc::new(StaticKeyProvider::new(vec![3u8; 32]).unwrap()),
+        })
+        .unwrap();
+        storage.initialize().unwrap();
+
+        let cache = PriceComparisonCache::default();
+        let generation = storage.stats_generation();
+        cache.insert(key("BPC-157"), comparison("BPC-157"), generation);
+        cache.insert(key("TB-500"), comparison("TB-500"), generation);
+        assert_eq!(cache.get(&key("BPC-157"), generation).unwrap().peptide_name, "BPC-157");
+        assert!(cache.get(&key("Ipamorelin"), generation).is_none());
+
+        // Any write makes every cached comparison stale
+        storage.invalidate_stats("price_history").unwrap();
+        let changed = storage.stats_generation();
+        assert_ne!(changed, generation);
+        assert!(cache.get(&key("BPC-157"), changed).is_none());
+        assert!(cache.entries.lock().unwrap().is_empty());
+
+        drop(storage);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn price_comparisons_expire_after_the_ttl() {
+        let dir = std::env::temp_dir()
+            .join(format!("peptrack-comparison-ttl-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let storage = StorageManager::new(StorageConfig {
+            data_dir: Some(dir.clone()),
+            db_file_name: None,
+            key_provider: Arc::new(StaticKeyProvider::new(vec![4u8; 32]).unwrap()),
+        })
+        .unwrap();
+        let generation = storage.stats_generation();
+
+        let cache = PriceComparisonCache::default();
+        let age_by = |age: Duration| {
+            let mut entries = cache.entries.lock().unwrap();
+            let entry = entries.get_mut(&key("BPC-157")).unwrap();
+            entry.cached_at = Instant::now().checked_sub(age).unwrap();
+        };
+
+        cache.insert(key("BPC-157"), comparison("BPC-157"), generation);
+        age_by(COMPARISON_CACHE_TTL - Duration::from_secs(5));
+        assert!(cache.get(&key("BPC-157"), generation).is_some());
+
+        age_by(COMPARISON_CACHE_TTL);
+        assert!(cache.get(&key("BPC-157"), generation).is_none());
+        assert!(cache.entries.lock().unwrap().is_empty());
+
+        drop(storage);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn quotes_are_stale_from_stale_quote_days() {
+        let now = OffsetDateTime::now_utc();
+        let days = time::Duration::days;
+
+        assert_eq!(quote_age(now, now), (0, false));
+        assert_eq!(
+            quote_age(now - days(STALE_QUOTE_DAYS) + time::Duration::minutes(1), now),
+            (STALE_QUOTE_DAYS - 1, false)
+        );
+        assert_eq!(quote_age(now - days(STALE_QUOTE_DAYS), now), (STALE_QUOTE_DAYS, true));
+        assert_eq!(quote_age(now - days(90), now), (90, true));
+        // A quote dated in the future isn't given a negative age
+        assert_eq!(quote_age(now + days(2), now), (0, false));
+    }
+}
@@ -0,0 +1,331 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use peptrack_core::{
+    detect_mime_type, generate_thumbnail, sanitize_file_name, validate_attachment_size,
+    validate_image_size, Attachment, AttachmentKind, AttachmentOwner,
+};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use tracing::{error, info};
+
+use crate::error::CommandError;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddAttachmentPayload {
+    pub owner_type: AttachmentOwner,
+    pub owner_id: String,
+    pub kind: Option<AttachmentKind>,
+    /// Path of a file picked in the file dialog
+    pub file_path: Option<String>,
+    /// Base64 file contents, for files that are not on disk (e.g. drag and drop)
+    pub data_base64: Option<String>,
+    /// Required with `data_base64`; defaults to the name in `file_path`
+    pub file_name: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Attachment metadata together with its decrypted contents
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentContent {
+    pub attachment: Attachment,
+    pub data_base64: String,
+}
+
+/// Make sure the record an attachment is being added to exists
+async fn ensure_owner_exists(
+    state: &AppState,
+    owner_type: &AttachmentOwner,
+    owner_id: &str,
+) -> Result<(), CommandError> {
+    let (owner, id) = (owner_type.clone(), owner_id.to_string());
+    let exists = state
+        .db
+        .run(move |storage| match owner {
+            AttachmentOwner::InventoryItem => storage.get_inventory_item(&id).map(|item| item.is_some()),
+            AttachmentOwner::Supplier => storage.get_supplier(&id).map(|supplier| supplier.is_some()),
+            AttachmentOwner::DoseLog => storage.get_dose_log(&id).map(|log| log.is_some()),
+            AttachmentOwner::BodyMetric => storage.get_body_metric(&id).map(|metric| metric.is_some()),
+            AttachmentOwner::Literature => storage.get_literature(&id).map(|entry| entry.is_some()),
+        })
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to look up attachment owner"))?;
+
+    if !exists {
+        return Err(CommandError::not_found(format!("{:?} not found: {}", owner_type, owner_id)));
+    }
+    Ok(())
+}
+
+/// Read the file contents and name from either a path or base64 data
+fn read_payload_file(payload: &AddAttachmentPayload) -> Result<(Vec<u8>, String), CommandError> {
+    match (&payload.file_path, &payload.data_base64) {
+        (Some(path), None) => {
+            let data = std::fs::read(path)
+                .map_err(|e| CommandError::with_context(e, format!("Failed to read {}", path)))?;
+            let name = payload.file_name.clone().unwrap_or_else(|| path.clone());
+            Ok((data, name))
+        }
+        (None, Some(encoded)) => {
+            let data = STANDARD
+                .decode(encoded.trim())
+                .map_err(|e| CommandError::with_context(e, "Invalid attachment data"))?;
+            let name = payload
+                .file_name
+                .clone()
+                .ok_or_else(|| {
+                    CommandError::invalid_input("A file name is required with attachment data")
+                })?;
+            Ok((data, name))
+        }
+        _ => Err(CommandError::invalid_input("Provide either a file path or attachment data")),
+    }
+}
+
+// ========== Attachment Commands ==========
+
+/// Dose logs and body metrics only accept photos
+fn owner_requires_image(owner_type: &AttachmentOwner) -> bool {
+    matches!(
+        owner_type,
+        AttachmentOwner::DoseLog | AttachmentOwner::BodyMetric
+    )
+}
+
+/// Encrypt and store a file (e.g. a supplier COA PDF or a progress photo)
+/// against a record
+///
+/// Images are limited to a smaller size than documents and get a thumbnail
+/// generated at upload time.
+#[tauri::command]
+pub async fn add_attachment(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: AddAttachmentPayload,
+) -> Result<Attachment, CommandError> {
+    ensure_owner_exists(&state, &payload.owner_type, &payload.owner_id).await?;
+
+    let (data, file_name) = read_payload_file(&payload)?;
+    let file_name = sanitize_file_name(&file_name)
+        .map_err(|e| CommandError::invalid_input(e.to_string()))?;
+    let mime_type = detect_mime_type(&data, &file_name);
+    let is_image = mime_type.starts_with("image/");
+
+    if owner_requires_image(&payload.owner_type) && !is_image {
+        return Err(CommandError::invalid_input("Only PNG or JPEG photos can be attached here"));
+    }
+
+    let thumbnail = if is_image {
+        validate_image_size(&data)
+            .map_err(|e| CommandError::invalid_input(e.to_string()))?;
+        let thumbnail = generate_thumbnail(&data)
+            .map_err(|e| CommandError::invalid_input(format!("{:#}", e)))?;
+        Some(thumbnail)
+    } else {
+        validate_attachment_size(&data)
+            .map_err(|e| CommandError::invalid_input(e.to_string()))?;
+        None
+    };
+
+    info!(
+        "Adding attachment {} ({} bytes) to {:?} {}",
+        file_name,
+        data.len(),
+        payload.owner_type,
+        payload.owner_id
+    );
+
+    let default_kind = if is_image {
+        AttachmentKind::Photo
+    } else {
+        AttachmentKind::Other
+    };
+    let mut attachment = Attachment::new(
+        payload.owner_type,
+        payload.owner_id,
+        payload.kind.unwrap_or(default_kind),
+        file_name,
+        mime_type.to_string(),
+        data.len() as u64,
+    );
+    attachment.notes = payload.notes;
+    if let Some(thumbnail) = &thumbnail {
+        attachment.width = Some(thumbnail.width);
+        attachment.height = Some(thumbnail.height);
+        attachment.has_thumbnail = true;
+    }
+
+    let thumbnail_jpeg = thumbnail.map(|thumbnail| thumbnail.jpeg);
+    state
+        .db
+        .run(move |storage| {
+            storage
+                .add_attachment(&attachment, &data, thumbnail_jpeg.as_deref())
+                .map(|_| attachment)
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to add attachment: {:#}", e);
+            CommandError::with_context(e, "Failed to add attachment")
+        })
+}
+
+#[tauri::command]
+pub async fn list_attachments(
+    state: State<'_, std::sync::Arc<AppState>>,
+    owner_type: AttachmentOwner,
+    owner_id: String,
+) -> Result<Vec<Attachment>, CommandError> {
+    state
+        .db
+        .run(move |storage| storage.list_attachments(&owner_type, &owner_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to list attachments: {:#}", e);
+            CommandError::with_context(e, "Failed to list attachments")
+        })
+}
+
+/// Load an attachment's metadata and decrypted contents
+#[tauri::command]
+pub async fn get_attachment(
+    state: State<'_, std::sync::Arc<AppState>>,
+    attachment_id: String,
+) -> Result<AttachmentContent, CommandError> {
+    let id = attachment_id.clone();
+    let attachment = state
+        .db
+        .run(move |storage| storage.get_attachment(&id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to get attachment"))?
+        .ok_or_else(|| CommandError::not_found("Attachment not found"))?;
+
+    let data = state
+        .db
+        .run(move |storage| storage.get_attachment_data(&attachment_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to load attachment data: {:#}", e);
+            CommandError::with_context(e, "Failed to load attachment data")
+        })?
+        .ok_or_else(|| CommandError::not_found("Attachment not found"))?;
+
+    Ok(AttachmentContent {
+        attachment,
+        data_base64: STANDARD.encode(data),
+    })
+}
+
+/// Load a photo's JPEG thumbnail as base64, if it has one
+#[tauri::command]
+pub async fn get_attachment_thumbnail(
+    state: State<'_, std::sync::Arc<AppState>>,
+    attachment_id: String,
+) -> Result<Option<String>, CommandError> {
+    let thumbnail = state
+        .db
+        .run(move |storage| storage.get_attachment_thumbnail(&attachment_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to load attachment thumbnail: {:#}", e);
+            CommandError::with_context(e, "Failed to load attachment thumbnail")
+        })?;
+
+    Ok(thumbnail.map(|jpeg| STANDARD.encode(jpeg)))
+}
+
+/// Write a decrypted copy of an attachment to a path chosen by the user
+#[tauri::command]
+pub async fn save_attachment_to_file(
+    state: State<'_, std::sync::Arc<AppState>>,
+    attachment_id: String,
+    destination_path: String,
+) -> Result<(), CommandError> {
+    let id = attachment_id.clone();
+    let data = state
+        .db
+        .run(move |storage| storage.get_attachment_data(&id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to load attachment data"))?
+        .ok_or_else(|| CommandError::not_found("Attachment not found"))?;
+
+    std::fs::write(&destination_path, data).map_err(|e| {
+        error!("Failed to save attachment to {}: {:#}", destination_path, e);
+        CommandError::with_context(e, "Failed to save attachment")
+    })?;
+
+    info!("Saved attachment {} to {}", attachment_id, destination_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_attachment(
+    state: State<'_, std::sync::Arc<AppState>>,
+    attachment_id: String,
+) -> Result<(), CommandError> {
+    info!("Deleting attachment: {}", attachment_id);
+
+    state
+        .db
+        .run(move |storage| storage.delete_attachment(&attachment_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to delete attachment: {:#}", e);
+            CommandError::with_context(e, "Failed to delete attachment")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(file_path: Option<&str>, data_base64: Option<&str>, file_name: Option<&str>) -> AddAttachmentPayload {
+        AddAttachmentPayload {
+            owner_type: AttachmentOwner::Supplier,
+            owner_id: "supplier-1".to_string(),
+            kind: None,
+            file_path: file_path.map(String::from),
+            data_base64: data_base64.map(String::from),
+            file_name: file_name.map(String::from),
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_read_payload_file_decodes_base64() {
+        let encoded = STANDARD.encode(b"%PDF-1.4");
+        let (data, name) = read_payload_file(&payload(None, Some(&encoded), Some("coa.pdf"))).unwrap();
+
+        assert_eq!(data, b"%PDF-1.4");
+        assert_eq!(name, "coa.pdf");
+    }
+
+    #[test]
+    fn test_read_payload_file_requires_exactly_one_source() {
+        assert!(read_payload_file(&payload(None, None, None)).is_err());
+        assert!(read_payload_file(&payload(Some("/tmp/a.pdf"), Some("AAAA"), None)).is_err());
+        assert!(read_payload_file(&payload(None, Some("AAAA"), None)).is_err());
+    }
+
+    #[test]
+    fn test_add_attachment_payload_deserialization() {
+        let json = r#"{
+            "ownerType": "inventory_item",
+            "ownerId": "item-1",
+            "kind": "certificate_of_analysis",
+            "filePath": "/home/user/coa.pdf"
+        }"#;
+
+        let payload: AddAttachmentPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.owner_type, AttachmentOwner::InventoryItem);
+        assert_eq!(payload.kind, Some(AttachmentKind::CertificateOfAnalysis));
+    }
+
+    #[test]
+    fn test_photo_owners_require_images() {
+        assert!(owner_requires_image(&AttachmentOwner::DoseLog));
+        assert!(owner_requires_image(&AttachmentOwner::BodyMetric));
+        assert!(!owner_requires_image(&AttachmentOwner::Supplier));
+        assert!(!owner_requires_image(&AttachmentOwner::InventoryItem));
+    }
+}
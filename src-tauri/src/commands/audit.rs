@@ -0,0 +1,161 @@
+use peptrack_core::{AuditEntityType, AuditEntry, AuditLogFilter, AuditOperation, AuditRetention};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use time::format_description::well_known::Rfc3339;
+use tracing::{error, info};
+
+use crate::commands::dates::parse_datetime;
+use crate::commands::settings::{load_setting_or_default, save_setting};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+const DEFAULT_LIST_LIMIT: usize = 200;
+
+/// Filters for `list_audit_log`; dates are RFC3339 strings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AuditLogQuery {
+    pub entity_type: Option<AuditEntityType>,
+    pub entity_id: Option<String>,
+    pub operation: Option<AuditOperation>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogItem {
+    pub id: String,
+    pub entity_type: AuditEntityType,
+    pub entity_id: String,
+    pub operation: AuditOperation,
+    pub changed_fields: Vec<String>,
+    pub summary: String,
+    pub occurred_at: String,
+}
+
+impl From<AuditEntry> for AuditLogItem {
+    fn from(entry: AuditEntry) -> Self {
+        Self {
+            id: entry.id,
+            entity_type: entry.entity_type,
+            entity_id: entry.entity_id,
+            operation: entry.operation,
+            changed_fields: entry.changed_fields,
+            summary: entry.summary,
+            occurred_at: entry
+                .occurred_at
+                .format(&Rfc3339)
+                .unwrap_or_else(|_| entry.occurred_at.to_string()),
+        }
+    }
+}
+
+impl AuditLogQuery {
+    fn into_filter(self) -> Result<AuditLogFilter, CommandError> {
+        Ok(AuditLogFilter {
+            entity_type: self.entity_type,
+            entity_id: self.entity_id,
+            operation: self.operation,
+            since: self.since.as_deref().map(parse_datetime).transpose()?,
+            until: self.until.as_deref().map(parse_datetime).transpose()?,
+            limit: Some(self.limit.unwrap_or(DEFAULT_LIST_LIMIT)),
+        })
+    }
+}
+
+/// Load the saved retention settings, falling back to the defaults
+pub async fn load_retention(state: &AppState) -> AuditRetention {
+    load_setting_or_default(state).await
+}
+
+// ========== Audit Log Commands ==========
+
+/// List recorded data changes, newest first
+#[tauri::command]
+pub async fn list_audit_log(
+    state: State<'_, std::sync::Arc<AppState>>,
+    query: Option<AuditLogQuery>,
+) -> Result<Vec<AuditLogItem>, CommandError> {
+    let filter = query.unwrap_or_default().into_filter()?;
+
+    let entries = state
+        .db
+        .run(move |storage| storage.list_audit_log(&filter))
+        .await
+        .map_err(|e| {
+            error!("Failed to list audit log: {:#}", e);
+            CommandError::with_context(e, "Failed to list audit log")
+        })?;
+
+    Ok(entries.into_iter().map(AuditLogItem::from).collect())
+}
+
+/// Gets how long audit entries are kept
+#[tauri::command]
+pub async fn get_audit_retention(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<AuditRetention, CommandError> {
+    Ok(load_retention(&state).await)
+}
+
+/// Saves the retention settings and prunes entries outside them right away
+#[tauri::command]
+pub async fn update_audit_retention(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    retention: AuditRetention,
+) -> Result<usize, CommandError> {
+    save_setting(&app, &state, &retention).await?;
+
+    info!("Audit log retention updated: {:?}", retention);
+    prune(&state, retention).await
+}
+
+/// Prune entries outside the saved retention settings
+#[tauri::command]
+pub async fn prune_audit_log(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<usize, CommandError> {
+    prune(&state, load_retention(&state).await).await
+}
+
+async fn prune(state: &AppState, retention: AuditRetention) -> Result<usize, CommandError> {
+    state
+        .db
+        .run(move |storage| storage.prune_audit_log(&retention))
+        .await
+        .map_err(|e| {
+            error!("Failed to prune audit log: {:#}", e);
+            CommandError::with_context(e, "Failed to prune audit log")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_converts_to_filter_with_default_limit() {
+        let query: AuditLogQuery = serde_json::from_str(
+            r#"{"entityType": "dose_log", "operation": "delete", "since": "2024-03-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+
+        let filter = query.into_filter().unwrap();
+        assert_eq!(filter.entity_type, Some(AuditEntityType::DoseLog));
+        assert_eq!(filter.operation, Some(AuditOperation::Delete));
+        assert_eq!(filter.since.unwrap().year(), 2024);
+        assert_eq!(filter.limit, Some(DEFAULT_LIST_LIMIT));
+    }
+
+    #[test]
+    fn test_query_rejects_invalid_dates() {
+        let query = AuditLogQuery {
+            until: Some("yesterday".to_string()),
+            ..Default::default()
+        };
+        assert!(query.into_filter().is_err());
+    }
+}
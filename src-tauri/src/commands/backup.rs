@@ -1,64 +1,162 @@
 use anyhow::Result;
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use peptrack_core::{AnalyticsExport, Redactor, StorageManager};
+use std::path::{Path, PathBuf};
 use tauri::State;
 use time::OffsetDateTime;
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 
+use crate::error::{CommandError, ErrorKind};
 use crate::state::AppState;
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct BackupMetadata {
-    pub export_date: String,
-    pub protocols_count: usize,
-    pub doses_count: usize,
-    pub literature_count: usize,
-    pub app_version: String,
+pub use peptrack_core::backup::{
+    AttachmentBackupOptions, BackupAttachment, BackupData, BackupMetadata, BackupSchedule,
+};
+
+use crate::commands::schedules::enabled_schedule_usage;
+
+/// Load the attachments selected by `options`, with their decrypted contents
+pub(crate) fn collect_backup_attachments(
+    storage: &StorageManager,
+    options: &AttachmentBackupOptions,
+) -> Result<Vec<BackupAttachment>> {
+    peptrack_core::backup::collect_backup_attachments(storage, options)
+}
+
+/// Enabled dose schedules, kept in backups so a viewer can work out adherence
+pub(crate) fn collect_backup_schedules(storage: &StorageManager) -> Result<Vec<BackupSchedule>> {
+    Ok(enabled_schedule_usage(storage)?
+        .into_iter()
+        .map(|usage| BackupSchedule {
+            protocol_id: usage.protocol_id,
+            amount_mg: usage.amount_mg,
+            days_of_week: usage.days_of_week,
+        })
+        .collect())
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct BackupData {
-    pub metadata: BackupMetadata,
-    pub protocols: Vec<serde_json::Value>,
-    pub dose_logs: Vec<serde_json::Value>,
-    pub literature: Vec<serde_json::Value>,
+/// Strip free text and pseudonymize names so the backup can be shared
+///
+/// Attachments and journal entries are dropped, since photos, documents and
+/// freeform writing can't be redacted.
+pub(crate) fn anonymize_backup(backup: &mut BackupData) {
+    let redactor = Redactor::new();
+    for record in backup
+        .protocols
+        .iter_mut()
+        .chain(backup.dose_logs.iter_mut())
+        .chain(backup.body_metrics.iter_mut())
+    {
+        redactor.redact(record);
+    }
+    backup.attachments.clear();
+    backup.journal_entries.clear();
+    backup.metadata.anonymized = true;
 }
 
 /// Exports all data to a JSON file that the user can save.
 ///
-/// If `password` is provided, the backup will be encrypted.
+/// If `password` is provided, the backup will be encrypted. `attachments`
+/// selects which attachments are included (documents only by default).
+/// With `anonymize`, notes and contact details are removed and names are
+/// replaced with pseudonyms so the export can be shared; such exports
+/// can't be restored.
 #[tauri::command]
 pub async fn export_backup_data(
     state: State<'_, std::sync::Arc<AppState>>,
     password: Option<String>,
-) -> Result<String, String> {
-    info!("Starting backup export (encrypted: {})", password.is_some());
+    attachments: Option<AttachmentBackupOptions>,
+    anonymize: Option<bool>,
+) -> Result<String, CommandError> {
+    let anonymize = anonymize.unwrap_or(false);
+    info!(
+        "Starting backup export (encrypted: {}, anonymized: {})",
+        password.is_some(),
+        anonymize
+    );
 
     // Verify database integrity before backing up
-    if let Err(e) = state.storage.verify_integrity() {
+    if let Err(e) = state
+        .db
+        .run(|storage| storage.verify_integrity())
+        .await
+    {
         warn!("Database integrity check failed before backup: {:#}", e);
-        return Err(format!("Cannot backup corrupted database: {}. Please restore from a previous backup.", e));
+        return Err(CommandError::new(
+            ErrorKind::Corrupted,
+            format!("Cannot backup corrupted database: {}. Please restore from a previous backup.", e),
+        ));
     }
 
     info!("Database integrity verified, proceeding with backup");
 
     // Load all data from storage
-    let protocols = state.storage.list_protocols().map_err(|e| {
-        warn!("Failed to load protocols for backup: {:#}", e);
-        format!("Could not load protocols: {}", e)
-    })?;
-
-    let doses = state.storage.list_dose_logs().map_err(|e| {
-        warn!("Failed to load dose logs for backup: {:#}", e);
-        format!("Could not load dose logs: {}", e)
-    })?;
-
-    let literature = state.storage.list_literature().map_err(|e| {
-        warn!("Failed to load literature for backup: {:#}", e);
-        format!("Could not load literature: {}", e)
-    })?;
+    let protocols = state
+        .db
+        .run(|storage| storage.list_protocols())
+        .await
+        .map_err(|e| {
+            warn!("Failed to load protocols for backup: {:#}", e);
+            CommandError::with_context(e, "Could not load protocols")
+        })?;
+
+    let doses = state
+        .db
+        .run(|storage| storage.list_dose_logs())
+        .await
+        .map_err(|e| {
+            warn!("Failed to load dose logs for backup: {:#}", e);
+            CommandError::with_context(e, "Could not load dose logs")
+        })?;
+
+    let literature = state
+        .db
+        .run(|storage| storage.list_literature())
+        .await
+        .map_err(|e| {
+            warn!("Failed to load literature for backup: {:#}", e);
+            CommandError::with_context(e, "Could not load literature")
+        })?;
+
+    let body_metrics = state
+        .db
+        .run(|storage| storage.list_body_metrics())
+        .await
+        .map_err(|e| {
+            warn!("Failed to load body metrics for backup: {:#}", e);
+            CommandError::with_context(e, "Could not load body metrics")
+        })?;
+
+    let dose_schedules = state
+        .db
+        .run(collect_backup_schedules)
+        .await
+        .map_err(|e| {
+            warn!("Failed to load dose schedules for backup: {:#}", e);
+            CommandError::with_context(e, "Could not load dose schedules")
+        })?;
+
+    let journal_entries = state
+        .db
+        .run(|storage| storage.list_journal_entries())
+        .await
+        .map_err(|e| {
+            warn!("Failed to load journal entries for backup: {:#}", e);
+            CommandError::with_context(e, "Could not load journal entries")
+        })?;
+
+    let attachments = if anonymize {
+        Vec::new()
+    } else {
+        let options = attachments.unwrap_or_default();
+        state
+            .db
+            .run(move |storage| collect_backup_attachments(storage, &options))
+            .await
+            .map_err(|e| {
+                warn!("Failed to load attachments for backup: {:#}", e);
+                CommandError::with_context(e, "Could not load attachments")
+            })?
+    };
 
     let metadata = BackupMetadata {
         export_date: OffsetDateTime::now_utc().to_string(),
@@ -66,11 +164,12 @@ pub async fn export_backup_data(
         doses_count: doses.len(),
         literature_count: literature.len(),
         app_version: env!("CARGO_PKG_VERSION").to_string(),
+        anonymized: false,
     };
 
     info!(
-        "Backup prepared: {} protocols, {} doses, {} literature entries",
-        metadata.protocols_count, metadata.doses_count, metadata.literature_count
+        "Backup prepared: {} protocols, {} doses, {} literature entries, {} attachments",
+        metadata.protocols_count, metadata.doses_count, metadata.literature_count, attachments.len()
     );
 
     // Convert to JSON values for serialization
@@ -89,16 +188,29 @@ pub async fn export_backup_data(
         .map(|l| serde_json::to_value(l).unwrap_or_default())
         .collect();
 
-    let backup_data = BackupData {
+    let mut backup_data = BackupData {
         metadata,
         protocols: protocols_json,
         dose_logs: doses_json,
         literature: literature_json,
+        attachments,
+        body_metrics: body_metrics
+            .into_iter()
+            .map(|m| serde_json::to_value(m).unwrap_or_default())
+            .collect(),
+        dose_schedules,
+        journal_entries: journal_entries
+            .into_iter()
+            .map(|e| serde_json::to_value(e).unwrap_or_default())
+            .collect(),
     };
+    if anonymize {
+        anonymize_backup(&mut backup_data);
+    }
 
     // Serialize to JSON
     let backup_json = serde_json::to_string_pretty(&backup_data)
-        .map_err(|e| format!("Failed to serialize backup: {}", e))?;
+        .map_err(|e| CommandError::with_context(e, "Failed to serialize backup"))?;
 
     // Optionally encrypt
     if let Some(password) = password {
@@ -108,16 +220,67 @@ pub async fn export_backup_data(
         } else {
             info!("Encrypting backup with password");
             peptrack_core::encrypt_backup(&backup_json, &password)
-                .map_err(|e| format!("Failed to encrypt backup: {}", e))
+                .map_err(|e| CommandError::with_context(e, "Failed to encrypt backup"))
         }
     } else {
         Ok(backup_json)
     }
 }
 
+/// Write all data, decrypted, to a SQLite file for analysis in Python or R
+///
+/// The file is not encrypted and not a backup: anyone who gets it can read
+/// every record, and it can't be restored. The user must acknowledge that
+/// with `acknowledge_unencrypted`. `file_path` must be a new `.sqlite` or
+/// `.db` file.
+#[tauri::command]
+pub async fn export_analytics_dataset(
+    state: State<'_, std::sync::Arc<AppState>>,
+    file_path: String,
+    acknowledge_unencrypted: bool,
+) -> Result<AnalyticsExport, CommandError> {
+    if !acknowledge_unencrypted {
+        return Err(CommandError::invalid_input(
+            "The dataset is not encrypted; confirm that before exporting it",
+        ));
+    }
+    let path = PathBuf::from(file_path.trim());
+    validate_dataset_path(&path).map_err(CommandError::invalid_input)?;
+    warn!("Exporting unencrypted analytics dataset to {}", path.display());
+
+    state
+        .db
+        .run(move |storage| storage.export_analytics_dataset(&path))
+        .await
+        .map_err(|e| {
+            error!("Failed to export analytics dataset: {:#}", e);
+            CommandError::with_context(e, "Failed to export analytics dataset")
+        })
+}
+
+fn validate_dataset_path(path: &Path) -> Result<(), String> {
+    if !path.is_absolute() {
+        return Err("Choose where to save the dataset".to_string());
+    }
+    let is_sqlite = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("sqlite") || ext.eq_ignore_ascii_case("db"));
+    if !is_sqlite {
+        return Err("The dataset must be saved as a .sqlite or .db file".to_string());
+    }
+    if path.exists() {
+        return Err(format!("{} already exists; choose a new file", path.display()));
+    }
+    if !path.parent().is_some_and(Path::is_dir) {
+        return Err("The folder for the dataset doesn't exist".to_string());
+    }
+    Ok(())
+}
+
 /// Gets recommended backup file path
 #[tauri::command]
-pub async fn get_backup_file_path() -> Result<String, String> {
+pub async fn get_backup_file_path() -> Result<String, CommandError> {
     let now = OffsetDateTime::now_utc();
     let timestamp = now
         .format(&time::format_description::parse("[year]-[month]-[day]_[hour]-[minute]").unwrap())
@@ -137,6 +300,8 @@ pub async fn get_backup_file_path() -> Result<String, String> {
 
 #[cfg(test)]
 mod tests {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+
     use super::*;
 
     #[tokio::test]
@@ -149,6 +314,20 @@ mod tests {
         assert!(path.ends_with(".json"));
     }
 
+    #[test]
+    fn dataset_path_must_be_a_new_sqlite_file() {
+        let dir = std::env::temp_dir();
+        assert!(validate_dataset_path(&dir.join("peptrack-analysis-new.sqlite")).is_ok());
+        assert!(validate_dataset_path(&dir.join("peptrack-analysis.csv")).is_err());
+        assert!(validate_dataset_path(Path::new("analysis.sqlite")).is_err());
+        assert!(validate_dataset_path(&dir.join("missing").join("analysis.db")).is_err());
+
+        let existing = dir.join(format!("peptrack-analysis-{}.db", std::process::id()));
+        std::fs::write(&existing, b"").unwrap();
+        assert!(validate_dataset_path(&existing).is_err());
+        std::fs::remove_file(&existing).unwrap();
+    }
+
     #[tokio::test]
     async fn test_backup_metadata_serialization() {
         let metadata = BackupMetadata {
@@ -157,6 +336,7 @@ mod tests {
             doses_count: 10,
             literature_count: 3,
             app_version: "0.1.0".to_string(),
+            anonymized: false,
         };
 
         let json = serde_json::to_string(&metadata);
@@ -178,6 +358,7 @@ mod tests {
             doses_count: 0,
             literature_count: 0,
             app_version: "0.1.0".to_string(),
+            anonymized: false,
         };
 
         let backup = BackupData {
@@ -185,6 +366,10 @@ mod tests {
             protocols: vec![],
             dose_logs: vec![],
             literature: vec![],
+            attachments: vec![],
+            body_metrics: vec![],
+            dose_schedules: vec![],
+            journal_entries: vec![],
         };
 
         let json = serde_json::to_string(&backup);
@@ -218,6 +403,32 @@ mod tests {
         assert_eq!(metadata.app_version, "0.1.0");
     }
 
+    #[test]
+    fn test_backup_without_attachments_still_parses() {
+        let json = r#"{
+            "metadata": {
+                "exportDate": "2024-01-15T10:30:00Z",
+                "protocolsCount": 0,
+                "dosesCount": 0,
+                "literatureCount": 0,
+                "appVersion": "0.1.0"
+            },
+            "protocols": [],
+            "doseLogs": [],
+            "literature": []
+        }"#;
+
+        let backup: BackupData = serde_json::from_str(json).unwrap();
+        assert!(backup.attachments.is_empty());
+        assert_eq!(
+            AttachmentBackupOptions::default(),
+            AttachmentBackupOptions {
+                include_documents: true,
+                include_photos: false,
+            }
+        );
+    }
+
     #[tokio::test]
     async fn test_backup_data_round_trip() {
         // Create backup data
@@ -228,6 +439,7 @@ mod tests {
                 doses_count: 5,
                 literature_count: 1,
                 app_version: "0.1.0".to_string(),
+                anonymized: false,
             },
             protocols: vec![
                 serde_json::json!({"id": "p1", "name": "Test Protocol"}),
@@ -241,6 +453,17 @@ mod tests {
                 serde_json::json!({"id": "d5", "amount": 50}),
             ],
             literature: vec![serde_json::json!({"id": "l1", "title": "Research Paper"})],
+            attachments: vec![BackupAttachment {
+                attachment: serde_json::json!({"id": "a1", "file_name": "coa.pdf"}),
+                data_base64: STANDARD.encode(b"%PDF-1.7"),
+            }],
+            body_metrics: vec![serde_json::json!({"id": "m1", "weight_kg": 80.5})],
+            dose_schedules: vec![BackupSchedule {
+                protocol_id: "p1".to_string(),
+                amount_mg: 0.25,
+                days_of_week: vec![1, 3, 5],
+            }],
+            journal_entries: vec![serde_json::json!({"id": "j1", "body": "Week one"})],
         };
 
         // Serialize
@@ -258,6 +481,50 @@ mod tests {
         assert_eq!(deserialized.protocols.len(), 2);
         assert_eq!(deserialized.dose_logs.len(), 5);
         assert_eq!(deserialized.literature.len(), 1);
+        assert_eq!(deserialized.attachments.len(), 1);
+        assert_eq!(deserialized.attachments[0].data_base64, original.attachments[0].data_base64);
+        assert_eq!(deserialized.body_metrics.len(), 1);
+        assert_eq!(deserialized.dose_schedules, original.dose_schedules);
+        assert_eq!(deserialized.journal_entries.len(), 1);
+    }
+
+    #[test]
+    fn test_anonymize_backup_redacts_records_and_drops_attachments() {
+        let mut backup = BackupData {
+            metadata: BackupMetadata {
+                export_date: "2024-01-15T10:30:00Z".to_string(),
+                protocols_count: 1,
+                doses_count: 1,
+                literature_count: 0,
+                app_version: "0.1.0".to_string(),
+                anonymized: false,
+            },
+            protocols: vec![serde_json::json!({"id": "p1", "name": "Knee rehab", "peptide_name": "BPC-157"})],
+            dose_logs: vec![serde_json::json!({"id": "d1", "protocol_id": "p1", "amount_mg": 0.25, "notes": "with Sam"})],
+            literature: vec![],
+            attachments: vec![BackupAttachment {
+                attachment: serde_json::json!({"id": "a1", "file_name": "coa.pdf"}),
+                data_base64: STANDARD.encode(b"%PDF-1.7"),
+            }],
+            body_metrics: vec![serde_json::json!({"id": "m1", "weight_kg": 80.5, "notes": "after the party"})],
+            dose_schedules: vec![],
+            journal_entries: vec![serde_json::json!({"id": "j1", "body": "Told Sam about it"})],
+        };
+
+        anonymize_backup(&mut backup);
+
+        assert!(backup.metadata.anonymized);
+        assert!(backup.attachments.is_empty());
+        assert!(backup.journal_entries.is_empty());
+        assert_ne!(backup.protocols[0]["name"], "Knee rehab");
+        assert_eq!(backup.protocols[0]["peptide_name"], "BPC-157");
+        assert_eq!(backup.dose_logs[0]["notes"], serde_json::Value::Null);
+        assert_eq!(backup.dose_logs[0]["amount_mg"], 0.25);
+        assert_eq!(backup.body_metrics[0]["notes"], serde_json::Value::Null);
+        assert_eq!(backup.body_metrics[0]["weight_kg"], 80.5);
+
+        let json = serde_json::to_string(&backup.metadata).unwrap();
+        assert!(json.contains("\"anonymized\":true"));
     }
 
     #[tokio::test]
@@ -295,10 +562,15 @@ mod tests {
                 doses_count: 500,
                 literature_count: 50,
                 app_version: "0.1.0".to_string(),
+                anonymized: false,
             },
             protocols,
             dose_logs: doses,
             literature,
+            attachments: vec![],
+            body_metrics: vec![],
+            dose_schedules: vec![],
+            journal_entries: vec![],
         };
 
         // Should serialize without error
@@ -359,6 +631,7 @@ mod tests {
             doses_count: 0,
             literature_count: 0,
             app_version: "0.1.0".to_string(),
+            anonymized: false,
         };
 
         let json = serde_json::to_string(&metadata).unwrap();
@@ -377,6 +650,7 @@ mod tests {
             doses_count: usize::MAX,
             literature_count: usize::MAX,
             app_version: "0.1.0".to_string(),
+            anonymized: false,
         };
 
         let json = serde_json::to_string(&metadata).unwrap();
@@ -0,0 +1,173 @@
+//! Journal of backups in progress
+//!
+//! A backup is written to `backup_journal.json` before it creates any files
+//! and removed once it has finished, whether it worked or not. An entry that
+//! is still there on startup belongs to a backup the app didn't live to
+//! finish; [`BackupJournal::recover_interrupted`] deletes the partial files it
+//! left behind and hands it back so it can be recorded as failed.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::{info, warn};
+
+use crate::commands::scheduler_v2::BackupDestination;
+
+const JOURNAL_FILENAME: &str = "backup_journal.json";
+
+/// A backup that has started but not finished
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub id: String,
+    pub started_at: String,
+    pub destinations: Vec<BackupDestination>,
+    pub compressed: bool,
+    /// Files being written, deleted if the backup never finishes
+    pub partial_files: Vec<PathBuf>,
+}
+
+pub struct BackupJournal {
+    path: PathBuf,
+}
+
+/// Handle to a journaled backup; call [`InFlightBackup::finish`] when it ends
+pub struct InFlightBackup<'a> {
+    journal: &'a BackupJournal,
+    id: String,
+}
+
+impl BackupJournal {
+    /// The journal in the data directory
+    pub fn open() -> Result<Self> {
+        let data_dir = dirs::data_dir()
+            .context("Unable to determine data directory")?
+            .join("PepTrack");
+        Ok(Self::at(data_dir.join(JOURNAL_FILENAME)))
+    }
+
+    fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn read(&self) -> Result<Vec<JournalEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let json = std::fs::read_to_string(&self.path).context("Failed to read backup journal")?;
+        serde_json::from_str(&json).context("Backup journal is unreadable")
+    }
+
+    /// Replace the journal, so a crash mid-write leaves the old one intact
+    fn write(&self, entries: &[JournalEntry]) -> Result<()> {
+        if entries.is_empty() {
+            if self.path.exists() {
+                std::fs::remove_file(&self.path).context("Failed to clear backup journal")?;
+            }
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        let file = std::fs::File::create(&tmp).context("Failed to write backup journal")?;
+        serde_json::to_writer_pretty(&file, entries)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, &self.path).context("Failed to write backup journal")
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Vec<JournalEntry>)) -> Result<()> {
+        let mut entries = self.read()?;
+        change(&mut entries);
+        self.write(&entries)
+    }
+
+    /// Record that a backup is starting
+    pub fn begin(&self, destinations: &[BackupDestination], compressed: bool) -> Result<InFlightBackup<'_>> {
+        let entry = JournalEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            started_at: OffsetDateTime::now_utc().to_string(),
+            destinations: destinations.to_vec(),
+            compressed,
+            partial_files: Vec::new(),
+        };
+        let id = entry.id.clone();
+        self.update(|entries| entries.push(entry))?;
+        Ok(InFlightBackup { journal: self, id })
+    }
+
+    /// Delete the files of every unfinished backup and clear the journal
+    ///
+    /// Returns the backups that were interrupted.
+    pub fn recover_interrupted(&self) -> Result<Vec<JournalEntry>> {
+        let entries = self.read()?;
+        for entry in &entries {
+            for file in &entry.partial_files {
+                remove_partial_file(file);
+            }
+            info!("Cleaned up interrupted backup started at {}", entry.started_at);
+        }
+        self.write(&[])?;
+        Ok(entries)
+    }
+}
+
+impl InFlightBackup<'_> {
+    /// Record a file before writing it, so it's deleted if the backup dies
+    pub fn add_partial_file(&self, path: &Path) -> Result<()> {
+        self.journal.update(|entries| {
+            if let Some(entry) = entries.iter_mut().find(|entry| entry.id == self.id) {
+                entry.partial_files.push(path.to_path_buf());
+            }
+        })
+    }
+
+    /// Remove the backup from the journal once it has ended
+    pub fn finish(self) {
+        if let Err(e) = self.journal.update(|entries| entries.retain(|entry| entry.id != self.id)) {
+            warn!("Failed to update backup journal: {:#}", e);
+        }
+    }
+}
+
+fn remove_partial_file(path: &Path) {
+    if !path.exists() {
+        return;
+    }
+    match std::fs::remove_file(path) {
+        Ok(()) => info!("Removed partial backup file {}", path.display()),
+        Err(e) => warn!("Failed to remove partial backup file {}: {}", path.display(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unfinished_backups_are_recovered_and_their_files_removed() {
+        let dir = std::env::temp_dir().join(format!("peptrack-journal-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let journal = BackupJournal::at(dir.join(JOURNAL_FILENAME));
+
+        let finished = journal.begin(&[BackupDestination::Local], true).unwrap();
+        finished.finish();
+
+        let interrupted = journal.begin(&[BackupDestination::Local], false).unwrap();
+        let partial = dir.join("peptrack_backup_2025-01-01_10-00.json.partial");
+        interrupted.add_partial_file(&partial).unwrap();
+        std::fs::write(&partial, b"{\"proto").unwrap();
+        // The app dies here, without calling finish
+
+        let recovered = journal.recover_interrupted().unwrap();
+        assert_eq!(recovered.len(), 1);
+        assert!(!recovered[0].compressed);
+        assert_eq!(recovered[0].partial_files, vec![partial.clone()]);
+        assert!(!partial.exists());
+        assert!(journal.recover_interrupted().unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
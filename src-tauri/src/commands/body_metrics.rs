@@ -1,9 +1,15 @@
 use anyhow::Result;
 use peptrack_core::models::BodyMetric;
-use serde::Deserialize;
-use tauri::State;
+use peptrack_core::{length_to_cm, weight_to_kg, LengthUnit, TrashEntityType, UnitPreferences, WeightUnit};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
 use time::OffsetDateTime;
 
+use crate::commands::dates::parse_datetime;
+use crate::commands::health_bridge::export_body_metric;
+use crate::commands::preferences::load_unit_preferences;
+use crate::commands::trash::move_to_trash;
+use crate::error::CommandError;
 use crate::state::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -14,44 +20,101 @@ pub struct BodyMetricPayload {
     pub body_fat_percentage: Option<f32>,
     pub muscle_mass_kg: Option<f32>,
     pub waist_cm: Option<f32>,
+    pub resting_heart_rate_bpm: Option<f32>,
+    pub sleep_hours: Option<f32>,
     pub notes: Option<String>,
+    /// Unit `weight_kg` and `muscle_mass_kg` were entered in; kg when not given
+    #[serde(default)]
+    pub weight_unit: Option<WeightUnit>,
+    /// Unit `waist_cm` was entered in; cm when not given
+    #[serde(default)]
+    pub length_unit: Option<LengthUnit>,
+}
+
+impl BodyMetricPayload {
+    /// Convert entered measurements to kg and cm
+    fn into_canonical(mut self) -> Self {
+        let weight_unit = self.weight_unit.take().unwrap_or_default();
+        let length_unit = self.length_unit.take().unwrap_or_default();
+        self.weight_kg = self.weight_kg.map(|value| weight_to_kg(value, weight_unit));
+        self.muscle_mass_kg = self.muscle_mass_kg.map(|value| weight_to_kg(value, weight_unit));
+        self.waist_cm = self.waist_cm.map(|value| length_to_cm(value, length_unit));
+        self
+    }
+}
+
+/// A body metric with its measurements in the user's preferred units
+///
+/// The metric's own fields stay in kg and cm.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BodyMetricView {
+    #[serde(flatten)]
+    pub metric: BodyMetric,
+    pub display_weight: Option<f32>,
+    pub display_muscle_mass: Option<f32>,
+    pub display_waist: Option<f32>,
+    pub weight_unit: WeightUnit,
+    pub length_unit: LengthUnit,
+}
+
+pub(crate) fn metric_view(preferences: &UnitPreferences, metric: BodyMetric) -> BodyMetricView {
+    BodyMetricView {
+        display_weight: metric.weight_kg.map(|kg| preferences.weight_from_kg(kg)),
+        display_muscle_mass: metric.muscle_mass_kg.map(|kg| preferences.weight_from_kg(kg)),
+        display_waist: metric.waist_cm.map(|cm| preferences.length_from_cm(cm)),
+        weight_unit: preferences.weight_unit,
+        length_unit: preferences.length_unit,
+        metric,
+    }
 }
 
 /// Log a new body metric entry
 #[tauri::command]
 pub async fn log_body_metric(
+    app: AppHandle,
     state: State<'_, std::sync::Arc<AppState>>,
     payload: BodyMetricPayload,
-) -> Result<BodyMetric, String> {
+) -> Result<BodyMetricView, CommandError> {
+    let payload = payload.into_canonical();
     // Parse the date string
-    let date = OffsetDateTime::parse(&payload.date, &time::format_description::well_known::Rfc3339)
-        .map_err(|e| format!("Invalid date format: {}", e))?;
+    let date = parse_datetime(&payload.date)?;
 
     let mut metric = BodyMetric::new(date);
     metric.weight_kg = payload.weight_kg;
     metric.body_fat_percentage = payload.body_fat_percentage;
     metric.muscle_mass_kg = payload.muscle_mass_kg;
     metric.waist_cm = payload.waist_cm;
+    metric.resting_heart_rate_bpm = payload.resting_heart_rate_bpm;
+    metric.sleep_hours = payload.sleep_hours;
     metric.notes = payload.notes;
     metric.updated_at = OffsetDateTime::now_utc();
 
-    state
-        .storage
-        .upsert_body_metric(&metric)
-        .map_err(|err| err.to_string())?;
+    let metric = state
+        .db
+        .run(move |storage| storage.upsert_body_metric(&metric).map(|_| metric))
+        .await
+        .map_err(CommandError::from)?;
+    export_body_metric(&app, &state, &metric).await;
 
-    Ok(metric)
+    Ok(metric_view(&load_unit_preferences(&state).await?, metric))
 }
 
 /// List all body metrics
 #[tauri::command]
 pub async fn list_body_metrics(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<Vec<BodyMetric>, String> {
-    state
-        .storage
-        .list_body_metrics()
-        .map_err(|err| err.to_string())
+) -> Result<Vec<BodyMetricView>, CommandError> {
+    let metrics = state
+        .db
+        .run(|storage| storage.list_body_metrics())
+        .await
+        .map_err(CommandError::from)?;
+    let preferences = load_unit_preferences(&state).await?;
+    Ok(metrics
+        .into_iter()
+        .map(|metric| metric_view(&preferences, metric))
+        .collect())
 }
 
 /// Get a specific body metric by ID
@@ -59,11 +122,14 @@ pub async fn list_body_metrics(
 pub async fn get_body_metric(
     state: State<'_, std::sync::Arc<AppState>>,
     metric_id: String,
-) -> Result<Option<BodyMetric>, String> {
-    state
-        .storage
-        .get_body_metric(&metric_id)
-        .map_err(|err| err.to_string())
+) -> Result<Option<BodyMetricView>, CommandError> {
+    let metric = state
+        .db
+        .run(move |storage| storage.get_body_metric(&metric_id))
+        .await
+        .map_err(CommandError::from)?;
+    let preferences = load_unit_preferences(&state).await?;
+    Ok(metric.map(|metric| metric_view(&preferences, metric)))
 }
 
 /// Update an existing body metric
@@ -72,13 +138,15 @@ pub async fn update_body_metric(
     state: State<'_, std::sync::Arc<AppState>>,
     metric_id: String,
     payload: BodyMetricPayload,
-) -> Result<BodyMetric, String> {
+) -> Result<BodyMetricView, CommandError> {
+    let payload = payload.into_canonical();
     // Get existing metric
     let mut metric = state
-        .storage
-        .get_body_metric(&metric_id)
-        .map_err(|err| err.to_string())?
-        .ok_or_else(|| "Body metric not found".to_string())?;
+        .db
+        .run(move |storage| storage.get_body_metric(&metric_id))
+        .await
+        .map_err(CommandError::from)?
+        .ok_or_else(|| CommandError::not_found("Body metric not found"))?;
 
     // Update fields
     if let Ok(date) = OffsetDateTime::parse(&payload.date, &time::format_description::well_known::Rfc3339) {
@@ -88,37 +156,76 @@ pub async fn update_body_metric(
     metric.body_fat_percentage = payload.body_fat_percentage;
     metric.muscle_mass_kg = payload.muscle_mass_kg;
     metric.waist_cm = payload.waist_cm;
+    // Keep imported wearable values when an older form doesn't send them
+    metric.resting_heart_rate_bpm = payload.resting_heart_rate_bpm.or(metric.resting_heart_rate_bpm);
+    metric.sleep_hours = payload.sleep_hours.or(metric.sleep_hours);
     metric.notes = payload.notes;
     metric.updated_at = OffsetDateTime::now_utc();
 
-    state
-        .storage
-        .upsert_body_metric(&metric)
-        .map_err(|err| err.to_string())?;
+    let metric = state
+        .db
+        .run(move |storage| storage.upsert_body_metric(&metric).map(|_| metric))
+        .await
+        .map_err(CommandError::from)?;
 
-    Ok(metric)
+    Ok(metric_view(&load_unit_preferences(&state).await?, metric))
 }
 
-/// Delete a specific body metric
+/// Move a specific body metric to the trash
 #[tauri::command]
 pub async fn delete_body_metric(
     state: State<'_, std::sync::Arc<AppState>>,
     metric_id: String,
-) -> Result<(), String> {
-    state
-        .storage
-        .delete_body_metric(&metric_id)
-        .map_err(|err| err.to_string())
+) -> Result<(), CommandError> {
+    move_to_trash(&state, TrashEntityType::BodyMetric, &[metric_id]).await.map(|_| ())
 }
 
-/// Bulk delete multiple body metrics
+/// Move multiple body metrics to the trash
 #[tauri::command]
 pub async fn bulk_delete_body_metrics(
     state: State<'_, std::sync::Arc<AppState>>,
     metric_ids: Vec<String>,
-) -> Result<usize, String> {
-    state
-        .storage
-        .bulk_delete_body_metrics(&metric_ids)
-        .map_err(|err| err.to_string())
+) -> Result<usize, CommandError> {
+    move_to_trash(&state, TrashEntityType::BodyMetric, &metric_ids).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_in_imperial_units_is_stored_metric() {
+        let payload: BodyMetricPayload = serde_json::from_str(
+            r#"{
+                "date": "2025-01-01T00:00:00Z",
+                "weightKg": 176.37,
+                "waistCm": 32,
+                "weightUnit": "lb",
+                "lengthUnit": "in"
+            }"#,
+        )
+        .unwrap();
+        let payload = payload.into_canonical();
+
+        assert!((payload.weight_kg.unwrap() - 80.0).abs() < 0.01);
+        assert!((payload.waist_cm.unwrap() - 81.28).abs() < 1e-3);
+        assert!(payload.muscle_mass_kg.is_none());
+    }
+
+    #[test]
+    fn test_metric_view_shows_preferred_units() {
+        let preferences = UnitPreferences {
+            weight_unit: WeightUnit::Lb,
+            ..Default::default()
+        };
+        let mut metric = BodyMetric::new(OffsetDateTime::now_utc());
+        metric.weight_kg = Some(100.0);
+        metric.waist_cm = Some(90.0);
+        let json = serde_json::to_value(metric_view(&preferences, metric)).unwrap();
+
+        assert_eq!(json["weight_kg"], 100.0);
+        assert!((json["displayWeight"].as_f64().unwrap() - 220.46).abs() < 0.01);
+        assert_eq!(json["displayWaist"], 90.0);
+        assert_eq!(json["weightUnit"], "lb");
+    }
 }
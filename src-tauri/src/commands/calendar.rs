@@ -0,0 +1,561 @@
+use anyhow::{Context, Result};
+use peptrack_core::Setting;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+use time::{Duration, OffsetDateTime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::commands::schedules::{load_dose_schedules, parse_time, DoseSchedule};
+use crate::commands::settings::{load_setting, save_setting};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+const DEFAULT_ALARM_MINUTES: u32 = 10;
+/// Length of each dose event in the calendar
+const EVENT_MINUTES: u32 = 15;
+/// Largest request accepted by the feed server
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+const REQUEST_TIMEOUT_SECS: u64 = 5;
+
+const WEEKDAYS: [&str; 7] = ["SU", "MO", "TU", "WE", "TH", "FR", "SA"];
+
+/// Live ICS feed configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarFeedSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Listen on all interfaces so a phone on the same network can subscribe;
+    /// otherwise only this computer can reach the feed
+    pub allow_network: bool,
+    pub alarm_minutes_before: u32,
+    /// Secret part of the feed URL
+    #[serde(default)]
+    pub token: String,
+}
+
+impl Default for CalendarFeedSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 8765,
+            allow_network: false,
+            alarm_minutes_before: DEFAULT_ALARM_MINUTES,
+            token: String::new(),
+        }
+    }
+}
+
+impl Setting for CalendarFeedSettings {
+    const KEY: &'static str = "calendar_feed.settings";
+    const LEGACY_FILE: Option<&'static str> = Some("calendar_feed.json");
+
+    fn validate(&self) -> Result<(), String> {
+        if self.port < 1024 {
+            return Err("Port must be 1024 or higher".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarFeedStatus {
+    pub settings: CalendarFeedSettings,
+    pub running: bool,
+    /// URL to subscribe to, when the feed is running
+    pub url: Option<String>,
+}
+
+/// The server task serving the feed
+#[derive(Clone)]
+pub struct CalendarFeedState {
+    server: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl Default for CalendarFeedState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CalendarFeedState {
+    pub fn new() -> Self {
+        Self {
+            server: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Stop any running feed server and start a new one if the feed is enabled
+    pub async fn restart(&self, app_state: Arc<AppState>) -> Result<()> {
+        let mut server = self.server.lock().await;
+        if let Some(handle) = server.take() {
+            handle.abort();
+            info!("Calendar feed server stopped");
+        }
+
+        let settings: CalendarFeedSettings =
+            app_state.db.run(|storage| storage.load_setting_or_default()).await?;
+        if !settings.enabled {
+            return Ok(());
+        }
+
+        let address = SocketAddr::new(bind_address(settings.allow_network), settings.port);
+        let listener = TcpListener::bind(address)
+            .await
+            .with_context(|| format!("Failed to listen on {}", address))?;
+        info!("Calendar feed listening on {}", address);
+
+        *server = Some(tokio::spawn(serve_feed(listener, app_state, settings)));
+        Ok(())
+    }
+
+    async fn status(&self, app_state: &AppState) -> Result<CalendarFeedStatus, CommandError> {
+        let settings = load_setting(app_state).await?;
+        let running = self
+            .server
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished());
+        let url = running.then(|| feed_url(&settings));
+        Ok(CalendarFeedStatus {
+            settings,
+            running,
+            url,
+        })
+    }
+}
+
+// ========== Calendar Commands ==========
+
+/// Exports enabled dose schedules as iCalendar text that the user can save
+/// or import into a calendar app
+#[tauri::command]
+pub async fn export_dose_schedule_ics(
+    state: State<'_, Arc<AppState>>,
+    alarm_minutes_before: Option<u32>,
+) -> Result<String, CommandError> {
+    let schedules = state.on_storage(load_dose_schedules).await.map_err(|e| {
+        error!("Failed to load dose schedules for ICS export: {}", e);
+        e
+    })?;
+    info!("Exporting {} dose schedules as ICS", schedules.len());
+
+    Ok(build_schedule_ics(
+        &schedules,
+        alarm_minutes_before.unwrap_or(DEFAULT_ALARM_MINUTES),
+        OffsetDateTime::now_utc(),
+    ))
+}
+
+/// Gets the calendar feed settings and subscription URL
+#[tauri::command]
+pub async fn get_calendar_feed_status(
+    feed: State<'_, CalendarFeedState>,
+    app_state: State<'_, Arc<AppState>>,
+) -> Result<CalendarFeedStatus, CommandError> {
+    feed.status(&app_state).await
+}
+
+/// Updates the calendar feed settings and starts or stops the feed server
+#[tauri::command]
+pub async fn update_calendar_feed_settings(
+    app: AppHandle,
+    feed: State<'_, CalendarFeedState>,
+    app_state: State<'_, Arc<AppState>>,
+    settings: CalendarFeedSettings,
+) -> Result<CalendarFeedStatus, CommandError> {
+    info!(
+        "Updating calendar feed: enabled={}, port={}, network={}",
+        settings.enabled, settings.port, settings.allow_network
+    );
+
+    let mut updated = settings;
+    // The token is managed by the backend; keep the current one
+    updated.token = load_setting::<CalendarFeedSettings>(&app_state).await?.token;
+    if updated.token.is_empty() {
+        updated.token = generate_token();
+    }
+    apply_settings(&app, &feed, &app_state, updated).await
+}
+
+/// Replaces the feed URL's secret so existing subscriptions stop working
+#[tauri::command]
+pub async fn regenerate_calendar_feed_token(
+    app: AppHandle,
+    feed: State<'_, CalendarFeedState>,
+    app_state: State<'_, Arc<AppState>>,
+) -> Result<CalendarFeedStatus, CommandError> {
+    info!("Regenerating calendar feed token");
+
+    let mut updated: CalendarFeedSettings = load_setting(&app_state).await?;
+    updated.token = generate_token();
+    apply_settings(&app, &feed, &app_state, updated).await
+}
+
+async fn apply_settings(
+    app: &AppHandle,
+    feed: &CalendarFeedState,
+    app_state: &Arc<AppState>,
+    settings: CalendarFeedSettings,
+) -> Result<CalendarFeedStatus, CommandError> {
+    save_setting(app, app_state, &settings).await?;
+
+    feed.restart(app_state.clone()).await.map_err(|e| {
+        error!("Failed to start calendar feed: {:#}", e);
+        CommandError::with_context(e, "Failed to start calendar feed")
+    })?;
+    feed.status(app_state).await
+}
+
+// ========== iCalendar ==========
+
+/// Builds a calendar with one weekly recurring event per enabled schedule
+///
+/// Times are written without a time zone so calendar apps show them at the
+/// schedule's local time of day.
+pub(crate) fn build_schedule_ics(
+    schedules: &[DoseSchedule],
+    alarm_minutes_before: u32,
+    now: OffsetDateTime,
+) -> String {
+    let stamp = format_utc(now);
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//PepTrack//Dose Schedules//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        "X-WR-CALNAME:PepTrack Doses".to_string(),
+        "REFRESH-INTERVAL;VALUE=DURATION:PT1H".to_string(),
+        "X-PUBLISHED-TTL:PT1H".to_string(),
+    ];
+
+    for schedule in schedules.iter().filter(|s| s.enabled) {
+        let Some(time) = parse_time(&schedule.time_of_day) else {
+            warn!("Skipping schedule {} with invalid time", schedule.id);
+            continue;
+        };
+        let mut days = schedule.days_of_week.clone();
+        days.sort_unstable();
+        days.dedup();
+        days.retain(|&d| d < 7);
+        if days.is_empty() {
+            continue;
+        }
+
+        // The first occurrence must fall on a scheduled weekday
+        let created = schedule
+            .created_at
+            .parse::<i64>()
+            .ok()
+            .and_then(|ts| OffsetDateTime::from_unix_timestamp(ts).ok())
+            .unwrap_or(now);
+        let mut first = created.date();
+        while !days.contains(&first.weekday().number_days_from_sunday()) {
+            first += Duration::days(1);
+        }
+
+        let title = format!("{} {} mg", schedule.peptide_name, schedule.amount_mg);
+        let mut description = format!("Protocol: {}", schedule.protocol_name);
+        if let Some(site) = schedule.site.as_deref().filter(|s| !s.is_empty()) {
+            description.push_str(&format!("\nSite: {}", site));
+        }
+        if let Some(notes) = schedule.notes.as_deref().filter(|n| !n.is_empty()) {
+            description.push_str(&format!("\n{}", notes));
+        }
+        let by_day: Vec<&str> = days.iter().map(|&d| WEEKDAYS[d as usize]).collect();
+
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}@peptrack", schedule.id),
+            format!("DTSTAMP:{}", stamp),
+            format!(
+                "DTSTART:{:04}{:02}{:02}T{:02}{:02}00",
+                first.year(),
+                first.month() as u8,
+                first.day(),
+                time.hour(),
+                time.minute()
+            ),
+            format!("DURATION:PT{}M", EVENT_MINUTES),
+            format!("RRULE:FREQ=WEEKLY;BYDAY={}", by_day.join(",")),
+            format!("SUMMARY:{}", escape_text(&title)),
+            format!("DESCRIPTION:{}", escape_text(&description)),
+            "BEGIN:VALARM".to_string(),
+            "ACTION:DISPLAY".to_string(),
+            format!("DESCRIPTION:{}", escape_text(&format!("Time for {}", title))),
+            format!("TRIGGER:-PT{}M", alarm_minutes_before),
+            "END:VALARM".to_string(),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    lines
+        .iter()
+        .map(|line| fold_line(line))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+        + "\r\n"
+}
+
+fn format_utc(date: OffsetDateTime) -> String {
+    let utc = date.to_offset(time::UtcOffset::UTC);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        utc.year(),
+        utc.month() as u8,
+        utc.day(),
+        utc.hour(),
+        utc.minute(),
+        utc.second()
+    )
+}
+
+/// Escapes a TEXT value (RFC 5545 section 3.3.11)
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line to at most 75 octets per line (RFC 5545 section 3.1)
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+// ========== Feed server ==========
+
+fn generate_token() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn bind_address(allow_network: bool) -> IpAddr {
+    if allow_network {
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+    } else {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    }
+}
+
+/// This computer's address on the local network, as seen by other devices
+fn local_network_address() -> Option<IpAddr> {
+    // Connecting a UDP socket only selects a route; nothing is sent
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 80)).ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+fn feed_url(settings: &CalendarFeedSettings) -> String {
+    let host = if settings.allow_network {
+        local_network_address().unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+    } else {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    };
+    format!(
+        "http://{}:{}{}",
+        host,
+        settings.port,
+        feed_path(&settings.token)
+    )
+}
+
+fn feed_path(token: &str) -> String {
+    format!("/calendar/{}.ics", token)
+}
+
+async fn serve_feed(listener: TcpListener, app_state: Arc<AppState>, settings: CalendarFeedSettings) {
+    let settings = Arc::new(settings);
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("Calendar feed failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        let app_state = app_state.clone();
+        let settings = settings.clone();
+        tokio::spawn(async move {
+            let handled = tokio::time::timeout(
+                std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS),
+                handle_request(stream, &app_state, &settings),
+            )
+            .await;
+            match handled {
+                Ok(Err(e)) => warn!("Calendar feed request from {} failed: {:#}", peer, e),
+                Err(_) => warn!("Calendar feed request from {} timed out", peer),
+                Ok(Ok(())) => {}
+            }
+        });
+    }
+}
+
+async fn handle_request(
+    mut stream: TcpStream,
+    app_state: &AppState,
+    settings: &CalendarFeedSettings,
+) -> Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..read]);
+        if request.len() > MAX_REQUEST_BYTES {
+            return write_response(&mut stream, "413 Payload Too Large", "text/plain", b"").await;
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut parts = request.lines().next().unwrap_or_default().split_whitespace();
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let path = target.split('?').next().unwrap_or_default();
+
+    match route(method, path, &settings.token) {
+        Route::Feed { head } => match app_state.on_storage(load_dose_schedules).await {
+            Ok(schedules) => {
+                let body = build_schedule_ics(
+                    &schedules,
+                    settings.alarm_minutes_before,
+                    OffsetDateTime::now_utc(),
+                );
+                let body = if head { Vec::new() } else { body.into_bytes() };
+                write_response(&mut stream, "200 OK", "text/calendar; charset=utf-8", &body).await
+            }
+            Err(e) => {
+                // Most likely the database is locked
+                warn!("Calendar feed could not load schedules: {}", e);
+                write_response(&mut stream, "503 Service Unavailable", "text/plain", b"").await
+            }
+        },
+        Route::MethodNotAllowed => {
+            write_response(&mut stream, "405 Method Not Allowed", "text/plain", b"").await
+        }
+        Route::NotFound => write_response(&mut stream, "404 Not Found", "text/plain", b"").await,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Route {
+    Feed { head: bool },
+    MethodNotAllowed,
+    NotFound,
+}
+
+fn route(method: &str, path: &str, token: &str) -> Route {
+    if token.is_empty() || path != feed_path(token) {
+        return Route::NotFound;
+    }
+    match method {
+        "GET" => Route::Feed { head: false },
+        "HEAD" => Route::Feed { head: true },
+        _ => Route::MethodNotAllowed,
+    }
+}
+
+async fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> Result<()> {
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn schedule(days: Vec<u8>, enabled: bool) -> DoseSchedule {
+        DoseSchedule {
+            id: "schedule-1".to_string(),
+            protocol_id: "protocol-1".to_string(),
+            protocol_name: "Healing, knee".to_string(),
+            peptide_name: "BPC-157".to_string(),
+            amount_mg: 0.25,
+            site: Some("abdomen".to_string()),
+            time_of_day: "08:30".to_string(),
+            days_of_week: days,
+            enabled,
+            notes: None,
+            // Monday 2024-01-01 12:00 UTC
+            created_at: "1704110400".to_string(),
+            updated_at: "1704110400".to_string(),
+            titration: None,
+            titration_status: None,
+            snoozed_until: None,
+        }
+    }
+
+    #[test]
+    fn test_build_schedule_ics_writes_recurring_events_with_alarms() {
+        let ics = build_schedule_ics(
+            &[schedule(vec![5, 3], true), schedule(vec![1], false)],
+            15,
+            datetime!(2024-02-01 09:00 UTC),
+        );
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        // First Wednesday on or after the creation date
+        assert!(ics.contains("DTSTART:20240103T083000\r\n"));
+        assert!(ics.contains("RRULE:FREQ=WEEKLY;BYDAY=WE,FR\r\n"));
+        assert!(ics.contains("TRIGGER:-PT15M\r\n"));
+        assert!(ics.contains("DESCRIPTION:Protocol: Healing\\, knee\\nSite: abdomen\r\n"));
+        assert!(ics.contains("DTSTAMP:20240201T090000Z\r\n"));
+    }
+
+    #[test]
+    fn test_fold_line_limits_octets() {
+        let line = format!("DESCRIPTION:{}", "é".repeat(80));
+        let folded = fold_line(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn test_route_requires_token() {
+        assert_eq!(route("GET", "/calendar/abc.ics", "abc"), Route::Feed { head: false });
+        assert_eq!(route("HEAD", "/calendar/abc.ics", "abc"), Route::Feed { head: true });
+        assert_eq!(route("POST", "/calendar/abc.ics", "abc"), Route::MethodNotAllowed);
+        assert_eq!(route("GET", "/calendar/xyz.ics", "abc"), Route::NotFound);
+        assert_eq!(route("GET", "/calendar/.ics", ""), Route::NotFound);
+    }
+}
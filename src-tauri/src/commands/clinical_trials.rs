@@ -0,0 +1,144 @@
+use peptrack_core::TrialResult;
+use peptrack_literature::ClinicalTrialsFetcher;
+use tauri::State;
+use tracing::{error, info};
+
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Trials searched for unless a limit is given
+const DEFAULT_MAX_RESULTS: usize = 20;
+
+async fn load_trial(state: &AppState, nct_id: &str) -> Result<TrialResult, CommandError> {
+    let id = nct_id.to_string();
+    state
+        .db
+        .run(move |storage| storage.get_clinical_trial(&id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to get clinical trial"))?
+        .ok_or_else(|| CommandError::not_found(format!("Clinical trial {} not found", nct_id)))
+}
+
+async fn save_trial(state: &AppState, trial: TrialResult) -> Result<TrialResult, CommandError> {
+    let nct_id = trial.nct_id.clone();
+    state
+        .db
+        .run(move |storage| storage.cache_clinical_trial(&trial).map(|_| trial))
+        .await
+        .map_err(|e| {
+            error!("Failed to cache clinical trial {}: {:#}", nct_id, e);
+            CommandError::with_context(e, "Failed to save clinical trial")
+        })
+}
+
+/// Search ClinicalTrials.gov for trials of `peptide` and cache them
+///
+/// Trials already cached keep their protocol links.
+#[tauri::command]
+pub async fn search_clinical_trials(
+    state: State<'_, std::sync::Arc<AppState>>,
+    peptide: String,
+    max_results: Option<usize>,
+) -> Result<Vec<TrialResult>, CommandError> {
+    let peptide = peptide.trim();
+    if peptide.is_empty() {
+        return Err(CommandError::invalid_input("Enter a peptide to search trials for"));
+    }
+
+    let trials = ClinicalTrialsFetcher::new()
+        .search(peptide, max_results.unwrap_or(DEFAULT_MAX_RESULTS))
+        .await
+        .map_err(|e| {
+            error!("ClinicalTrials.gov search for {} failed: {:#}", peptide, e);
+            CommandError::with_context(e, "Failed to search ClinicalTrials.gov")
+        })?;
+
+    let trials = state
+        .db
+        .run(move |storage| {
+            let mut trials = trials;
+            for trial in &mut trials {
+                if let Some(cached) = storage.get_clinical_trial(&trial.nct_id)? {
+                    trial.protocol_ids = cached.protocol_ids;
+                }
+                storage.cache_clinical_trial(trial)?;
+            }
+            Ok(trials)
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to cache clinical trials for {}: {:#}", peptide, e);
+            CommandError::with_context(e, "Failed to save clinical trial")
+        })?;
+    info!("Cached {} clinical trials for {}", trials.len(), peptide);
+    Ok(trials)
+}
+
+/// Cached trials, only those linked to `protocol_id` when given
+#[tauri::command]
+pub async fn list_clinical_trials(
+    state: State<'_, std::sync::Arc<AppState>>,
+    protocol_id: Option<String>,
+) -> Result<Vec<TrialResult>, CommandError> {
+    state
+        .db
+        .run(move |storage| storage.list_clinical_trials(protocol_id.as_deref()))
+        .await
+        .map_err(|e| {
+            error!("Failed to list clinical trials: {:#}", e);
+            CommandError::with_context(e, "Failed to list clinical trials")
+        })
+}
+
+#[tauri::command]
+pub async fn link_clinical_trial(
+    state: State<'_, std::sync::Arc<AppState>>,
+    nct_id: String,
+    protocol_id: String,
+) -> Result<TrialResult, CommandError> {
+    let mut trial = load_trial(&state, &nct_id).await?;
+    let id = protocol_id.clone();
+    state
+        .db
+        .run(move |storage| storage.get_protocol(&id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to get protocol"))?
+        .ok_or_else(|| CommandError::not_found("Protocol not found"))?;
+
+    if !trial.protocol_ids.contains(&protocol_id) {
+        trial.protocol_ids.push(protocol_id);
+        trial = save_trial(&state, trial).await?;
+    }
+    Ok(trial)
+}
+
+#[tauri::command]
+pub async fn unlink_clinical_trial(
+    state: State<'_, std::sync::Arc<AppState>>,
+    nct_id: String,
+    protocol_id: String,
+) -> Result<TrialResult, CommandError> {
+    let mut trial = load_trial(&state, &nct_id).await?;
+    let linked = trial.protocol_ids.len();
+    trial.protocol_ids.retain(|id| *id != protocol_id);
+    if trial.protocol_ids.len() != linked {
+        trial = save_trial(&state, trial).await?;
+    }
+    Ok(trial)
+}
+
+#[tauri::command]
+pub async fn delete_clinical_trial(
+    state: State<'_, std::sync::Arc<AppState>>,
+    nct_id: String,
+) -> Result<(), CommandError> {
+    let id = nct_id.clone();
+    state
+        .db
+        .run(move |storage| storage.delete_clinical_trial(&id))
+        .await
+        .map_err(|e| {
+            error!("Failed to delete clinical trial {}: {:#}", nct_id, e);
+            CommandError::with_context(e, "Failed to delete clinical trial")
+        })
+}
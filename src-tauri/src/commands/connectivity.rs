@@ -0,0 +1,261 @@
+//! Online/offline detection
+//!
+//! Literature searches, saved searches and cloud backups need the internet.
+//! [`Connectivity`] tracks whether it's reachable by opening a connection to
+//! a few API hosts, so those features can fall back to cached data or wait
+//! instead of failing with raw network errors. The background loop re-checks
+//! periodically and tells the frontend when the state changes.
+//!
+//! The proxy, CA certificate and timeout settings for outgoing requests live
+//! here too. With a proxy set, the proxy is probed instead of the API hosts,
+//! which are often unreachable directly on networks that need one.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use peptrack_core::{HttpSettings, Setting, StorageManager};
+use peptrack_literature::http;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+use time::OffsetDateTime;
+use tokio::net::TcpStream;
+use tokio::sync::Notify;
+use tokio::time::{timeout, Duration};
+use tracing::{error, info, warn};
+
+use crate::commands::settings::{load_setting_or_default, save_setting};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+pub const CONNECTIVITY_CHANGED_EVENT: &str = "connectivity-changed";
+
+/// Hosts probed for connectivity; reaching any one of them counts as online
+const PROBE_HOSTS: &[&str] = &["api.openalex.org:443", "eutils.ncbi.nlm.nih.gov:443", "www.ebi.ac.uk:443"];
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// A probe result is trusted this long before [`Connectivity::check`] probes again
+const PROBE_MAX_AGE: time::Duration = time::Duration::seconds(30);
+/// How often the background loop probes while online
+const ONLINE_CHECK_INTERVAL_SECS: u64 = 5 * 60;
+/// How often it probes while offline, to notice the connection returning
+const OFFLINE_CHECK_INTERVAL_SECS: u64 = 30;
+
+/// Whether the internet is reachable, and work waiting for it to be
+///
+/// Starts out online, so nothing is skipped before the first probe.
+#[derive(Default)]
+pub struct Connectivity {
+    offline: AtomicBool,
+    last_checked_at: Mutex<Option<OffsetDateTime>>,
+    /// Saved searches run while offline, in the order they were asked for
+    queued_searches: Mutex<Vec<String>>,
+    /// Wakes background jobs when the connection returns
+    reconnected: Notify,
+}
+
+/// Connectivity as reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectivityStatus {
+    pub online: bool,
+    pub last_checked_at: Option<OffsetDateTime>,
+    /// Saved searches that will run when the connection returns
+    pub queued_searches: usize,
+}
+
+impl Connectivity {
+    /// Last known state, without probing
+    pub fn is_online(&self) -> bool {
+        !self.offline.load(Ordering::SeqCst)
+    }
+
+    /// Whether the internet is reachable, probing unless the last probe is
+    /// recent
+    pub async fn check(&self) -> bool {
+        let fresh = self
+            .last_checked_at
+            .lock()
+            .unwrap()
+            .is_some_and(|checked| OffsetDateTime::now_utc() - checked < PROBE_MAX_AGE);
+        if fresh {
+            return self.is_online();
+        }
+        self.probe().await
+    }
+
+    /// Probe the API hosts now; used after a request failed with a network
+    /// error, which may just mean that one API is down
+    pub async fn probe(&self) -> bool {
+        let hosts = match proxy_host(&http::settings()) {
+            Some(proxy) => vec![proxy],
+            None => PROBE_HOSTS.iter().map(|host| host.to_string()).collect(),
+        };
+        let mut online = false;
+        for host in &hosts {
+            if matches!(timeout(PROBE_TIMEOUT, TcpStream::connect(host.as_str())).await, Ok(Ok(_))) {
+                online = true;
+                break;
+            }
+        }
+        *self.last_checked_at.lock().unwrap() = Some(OffsetDateTime::now_utc());
+        self.set_online(online);
+        online
+    }
+
+    fn set_online(&self, online: bool) {
+        let was_online = !self.offline.swap(!online, Ordering::SeqCst);
+        if online && !was_online {
+            info!("Connection restored");
+            self.reconnected.notify_waiters();
+        } else if !online && was_online {
+            warn!("No internet connection; using cached data");
+        }
+    }
+
+    /// Wait until the connection returns after being lost
+    pub async fn reconnected(&self) {
+        self.reconnected.notified().await;
+    }
+
+    /// Run saved search `search_id` once the connection returns
+    pub fn queue_search(&self, search_id: &str) {
+        let mut queued = self.queued_searches.lock().unwrap();
+        if !queued.iter().any(|id| id == search_id) {
+            queued.push(search_id.to_string());
+        }
+    }
+
+    /// Saved searches queued while offline, emptying the queue
+    pub fn take_queued_searches(&self) -> Vec<String> {
+        std::mem::take(&mut *self.queued_searches.lock().unwrap())
+    }
+
+    pub fn status(&self) -> ConnectivityStatus {
+        ConnectivityStatus {
+            online: self.is_online(),
+            last_checked_at: *self.last_checked_at.lock().unwrap(),
+            queued_searches: self.queued_searches.lock().unwrap().len(),
+        }
+    }
+}
+
+/// `host:port` of the configured proxy, if any
+fn proxy_host(settings: &HttpSettings) -> Option<String> {
+    let url = reqwest::Url::parse(settings.proxy()?).ok()?;
+    Some(format!("{}:{}", url.host_str()?, url.port_or_known_default()?))
+}
+
+/// Apply the saved network settings to HTTP clients built from now on
+///
+/// Takes storage directly so unlock paths and startup can call it from the
+/// thread that already holds it.
+pub fn apply_http_settings(storage: &StorageManager) {
+    let settings = storage
+        .load_setting_or_default::<HttpSettings>()
+        .unwrap_or_else(|e| {
+            warn!("Using default {} setting: {:#}", HttpSettings::KEY, e);
+            HttpSettings::default()
+        });
+    http::configure(settings);
+}
+
+/// Probe connectivity now and then periodically, telling the frontend when
+/// it changes
+pub async fn run_connectivity_loop(app: AppHandle, state: Arc<AppState>) {
+    let mut reported: Option<bool> = None;
+    loop {
+        let online = state.connectivity.probe().await;
+        if reported != Some(online) {
+            if let Err(e) = app.emit(CONNECTIVITY_CHANGED_EVENT, state.connectivity.status()) {
+                warn!("Failed to report connectivity: {}", e);
+            }
+            reported = Some(online);
+        }
+
+        let secs = if online {
+            ONLINE_CHECK_INTERVAL_SECS
+        } else {
+            OFFLINE_CHECK_INTERVAL_SECS
+        };
+        tokio::time::sleep(Duration::from_secs(secs)).await;
+    }
+}
+
+/// Last known connectivity
+#[tauri::command]
+pub async fn get_connectivity_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<ConnectivityStatus, CommandError> {
+    Ok(state.connectivity.status())
+}
+
+/// Probe connectivity now, e.g. when the user asks to retry
+#[tauri::command]
+pub async fn check_connectivity(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ConnectivityStatus, CommandError> {
+    let was_online = state.connectivity.is_online();
+    let online = state.connectivity.probe().await;
+    let status = state.connectivity.status();
+    if online != was_online {
+        if let Err(e) = app.emit(CONNECTIVITY_CHANGED_EVENT, &status) {
+            warn!("Failed to report connectivity: {}", e);
+        }
+    }
+    Ok(status)
+}
+
+/// Gets the proxy, CA certificate and timeout settings
+#[tauri::command]
+pub async fn get_http_settings(
+    state: State<'_, Arc<AppState>>,
+) -> Result<HttpSettings, CommandError> {
+    Ok(load_setting_or_default(&state).await)
+}
+
+/// Saves the network settings, after checking a client can be built with
+/// them, and re-checks connectivity through them
+#[tauri::command]
+pub async fn update_http_settings(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    settings: HttpSettings,
+) -> Result<ConnectivityStatus, CommandError> {
+    settings.validate().map_err(CommandError::invalid_input)?;
+    http::builder_with(&settings, http::DEFAULT_USER_AGENT)
+        .and_then(|builder| Ok(builder.build()?))
+        .map_err(|e| {
+            error!("Invalid network settings: {:#}", e);
+            CommandError::invalid_input(format!("{:#}", e))
+        })?;
+    save_setting(&app, &state, &settings).await?;
+    http::configure(settings);
+    info!("Network settings updated");
+
+    check_connectivity(app, state).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queued_searches_are_taken_once() {
+        let connectivity = Connectivity::default();
+        assert!(connectivity.is_online());
+        connectivity.queue_search("a");
+        connectivity.queue_search("b");
+        connectivity.queue_search("a");
+        assert_eq!(connectivity.status().queued_searches, 2);
+        assert_eq!(connectivity.take_queued_searches(), vec!["a", "b"]);
+        assert!(connectivity.take_queued_searches().is_empty());
+    }
+
+    #[test]
+    fn the_proxy_is_probed_when_set() {
+        let proxy = |url: &str| HttpSettings { proxy_url: Some(url.into()), ..Default::default() };
+        assert_eq!(proxy_host(&proxy("http://user:pw@proxy.local:3128")).as_deref(), Some("proxy.local:3128"));
+        assert_eq!(proxy_host(&proxy("https://proxy.local")).as_deref(), Some("proxy.local:443"));
+        assert_eq!(proxy_host(&HttpSettings::default()), None);
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use peptrack_core::{normalize_currency_code, ExchangeRate, RateSource, BASE_CURRENCY};
+use serde::Deserialize;
+use tauri::State;
+use tracing::{error, info};
+
+use crate::error::{CommandError, ErrorKind};
+use crate::state::AppState;
+
+/// Public exchange rate endpoint (no API key required), quoted against USD
+const RATES_URL: &str = "https://open.er-api.com/v6/latest/USD";
+
+#[derive(Debug, Deserialize)]
+struct RatesResponse {
+    result: String,
+    rates: HashMap<String, f64>,
+}
+
+// ========== Exchange Rate Commands ==========
+
+#[tauri::command]
+pub async fn list_exchange_rates(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<Vec<ExchangeRate>, CommandError> {
+    state
+        .db
+        .run(|storage| storage.list_exchange_rates())
+        .await
+        .map_err(|e| {
+            error!("Failed to list exchange rates: {:#}", e);
+            CommandError::with_context(e, "Failed to list exchange rates")
+        })
+}
+
+/// Manually sets the rate for a currency, as units of that currency per 1 USD
+#[tauri::command]
+pub async fn set_exchange_rate(
+    state: State<'_, std::sync::Arc<AppState>>,
+    currency: String,
+    rate_per_usd: f64,
+) -> Result<ExchangeRate, CommandError> {
+    let currency = normalize_currency_code(&currency)
+        .map_err(|e| CommandError::invalid_input(e.to_string()))?;
+
+    if currency == BASE_CURRENCY {
+        return Err(CommandError::invalid_input(format!(
+            "{} is the base currency and always has a rate of 1",
+            BASE_CURRENCY
+        )));
+    }
+    if !rate_per_usd.is_finite() || rate_per_usd <= 0.0 {
+        return Err(CommandError::invalid_input("Exchange rate must be a positive number"));
+    }
+
+    info!("Setting exchange rate: 1 {} = {} {}", BASE_CURRENCY, rate_per_usd, currency);
+
+    let rate = ExchangeRate::new(currency, rate_per_usd, RateSource::Manual);
+    state
+        .db
+        .run(move |storage| storage.upsert_exchange_rate(&rate).map(|_| rate))
+        .await
+        .map_err(|e| {
+            error!("Failed to save exchange rate: {:#}", e);
+            CommandError::with_context(e, "Failed to save exchange rate")
+        })
+}
+
+#[tauri::command]
+pub async fn delete_exchange_rate(
+    state: State<'_, std::sync::Arc<AppState>>,
+    currency: String,
+) -> Result<(), CommandError> {
+    let currency = normalize_currency_code(&currency)
+        .map_err(|e| CommandError::invalid_input(e.to_string()))?;
+
+    state
+        .db
+        .run(move |storage| storage.delete_exchange_rate(&currency))
+        .await
+        .map_err(|e| {
+            error!("Failed to delete exchange rate: {:#}", e);
+            CommandError::with_context(e, "Failed to delete exchange rate")
+        })
+}
+
+/// Fetches current rates and updates the given currencies
+///
+/// Only currencies listed in `currencies` are updated; when omitted, every
+/// currency that already has a rate is refreshed. Manually entered rates are
+/// overwritten.
+#[tauri::command]
+pub async fn fetch_exchange_rates(
+    state: State<'_, std::sync::Arc<AppState>>,
+    currencies: Option<Vec<String>>,
+) -> Result<Vec<ExchangeRate>, CommandError> {
+    let wanted: Vec<String> = match currencies {
+        Some(codes) => codes
+            .iter()
+            .map(|code| normalize_currency_code(code))
+            .collect::<Result<_, _>>()
+            .map_err(|e| CommandError::invalid_input(e.to_string()))?,
+        None => state
+            .db
+            .run(|storage| storage.list_exchange_rates())
+            .await
+            .map_err(|e| CommandError::with_context(e, "Failed to list exchange rates"))?
+            .into_iter()
+            .map(|rate| rate.currency)
+            .collect(),
+    };
+
+    if wanted.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    info!("Fetching exchange rates for {:?}", wanted);
+
+    let client = peptrack_literature::http::client_builder("PepTrack/1.0")
+        .build()
+        .map_err(|e| CommandError::with_context(e, "Failed to create HTTP client"))?;
+
+    let response: RatesResponse = client
+        .get(RATES_URL)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| {
+            error!("Failed to fetch exchange rates: {:#}", e);
+            CommandError::with_context(e, "Failed to fetch exchange rates")
+        })?
+        .json()
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to parse exchange rates"))?;
+
+    if response.result != "success" {
+        return Err(CommandError::new(
+            ErrorKind::Network,
+            "Exchange rate service returned an error",
+        ));
+    }
+
+    let mut updated = Vec::new();
+    for currency in wanted.into_iter().filter(|c| c != BASE_CURRENCY) {
+        let Some(&rate_per_usd) = response.rates.get(&currency) else {
+            return Err(CommandError::not_found(format!(
+                "No exchange rate available for {}",
+                currency
+            )));
+        };
+
+        let rate = ExchangeRate::new(currency, rate_per_usd, RateSource::Fetched);
+        let rate = state
+            .db
+            .run(move |storage| storage.upsert_exchange_rate(&rate).map(|_| rate))
+            .await
+            .map_err(|e| CommandError::with_context(e, "Failed to save exchange rate"))?;
+        updated.push(rate);
+    }
+
+    Ok(updated)
+}
+
+/// Normalizes an optional currency code, falling back to `fallback`
+pub(crate) fn resolve_currency(code: Option<String>, fallback: Option<&str>) -> Result<String, CommandError> {
+    match code.as_deref().or(fallback) {
+        Some(code) => {
+            normalize_currency_code(code).map_err(|e| CommandError::invalid_input(e.to_string()))
+        }
+        None => Ok(BASE_CURRENCY.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_currency_prefers_explicit_code() {
+        assert_eq!(resolve_currency(Some("eur".into()), Some("GBP")).unwrap(), "EUR");
+        assert_eq!(resolve_currency(None, Some("gbp")).unwrap(), "GBP");
+        assert_eq!(resolve_currency(None, None).unwrap(), "USD");
+        assert!(resolve_currency(Some("euro".into()), None).is_err());
+    }
+
+    #[test]
+    fn test_rates_response_deserialization() {
+        let json = r#"{"result": "success", "base_code": "USD", "rates": {"USD": 1, "EUR": 0.92}}"#;
+        let response: RatesResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(response.rates.get("EUR"), Some(&0.92));
+    }
+}
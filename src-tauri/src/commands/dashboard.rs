@@ -0,0 +1,590 @@
+use std::collections::HashMap;
+
+use peptrack_core::models::{AlertSeverity, DoseLog};
+use peptrack_core::{
+    DashboardStat, DoseSkip, DoseStatsFilter, GoalStatus, ListOptions, ProtocolDoseUsage, StorageManager,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use time::format_description::well_known::Rfc3339;
+use time::{Date, Duration, OffsetDateTime};
+use tracing::{error, info, warn};
+
+use crate::commands::ai::{check_ai_availability, AiAvailabilityStatus};
+use crate::commands::analytics::{predict_inventory_depletion, InventoryPrediction};
+use crate::commands::body_metrics::{metric_view, BodyMetricView};
+use crate::commands::doses::{dose_views, DoseLogView};
+use crate::commands::forecast::{load_forecast, DEFAULT_HISTORY_DAYS, DEFAULT_LEAD_TIME_DAYS};
+use crate::commands::goals::{load_goal_progress, GoalWithProgress};
+use crate::commands::preferences::load_unit_preferences;
+use crate::commands::scheduler_v2::{BackupStatus, SchedulerState};
+use crate::commands::schedules::{enabled_schedule_usage, load_dose_schedules, DoseSchedule, ScheduledUsage};
+use crate::commands::spend::load_spend_report;
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Days counted as "this week", including today
+const WEEK_DAYS: i64 = 7;
+/// Days of schedules checked for adherence, including today
+const ADHERENCE_WINDOW_DAYS: i64 = 30;
+/// Months of orders the spend total covers
+const SPEND_MONTHS: u32 = 12;
+/// Latest dose logs shown on the home screen
+const RECENT_DOSES: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoseWeekStats {
+    pub dose_count: u32,
+    pub total_mg: f64,
+    /// Days in the last week with at least one dose
+    pub active_days: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdherenceStats {
+    pub window_days: i64,
+    /// Doses due under enabled schedules
+    pub expected: u32,
+    /// Doses logged for scheduled protocols, up to the number expected
+    pub logged: u32,
+    /// Due doses skipped on purpose from a reminder; still counted as missed
+    #[serde(default)]
+    pub skipped: u32,
+    /// None when nothing is scheduled
+    pub percent: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendStats {
+    pub currency: String,
+    /// Spend over the last `SPEND_MONTHS` months
+    pub total_spend: f32,
+    pub this_month_spend: f32,
+    pub projected_monthly_cost: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryStats {
+    /// Protocols with stock and a usage estimate
+    pub protocols_tracked: u32,
+    /// Days until the first protocol runs out
+    pub min_days_remaining: Option<f32>,
+    pub min_days_protocol_name: Option<String>,
+    /// Protocols whose reorder date is today or past
+    pub reorder_due: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoalStats {
+    pub active: u32,
+    pub achieved: u32,
+    /// Goals not yet achieved, soonest target date first
+    pub goals: Vec<GoalWithProgress>,
+}
+
+/// A stat and when it was computed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatValue<T> {
+    pub value: T,
+    /// RFC3339 timestamp
+    pub computed_at: String,
+    /// Whether the value came from the stats cache
+    pub cached: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DashboardStats {
+    pub doses_this_week: StatValue<DoseWeekStats>,
+    pub adherence: StatValue<AdherenceStats>,
+    pub spend: StatValue<SpendStats>,
+    pub inventory: StatValue<InventoryStats>,
+    pub goals: StatValue<GoalStats>,
+}
+
+/// A dose scheduled for today
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodaysDose {
+    #[serde(flatten)]
+    pub schedule: DoseSchedule,
+    /// Whether a dose was logged for it today
+    pub taken: bool,
+}
+
+/// Alerts that haven't been dismissed
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertCounts {
+    pub info: u32,
+    pub warning: u32,
+    pub critical: u32,
+    pub unread: u32,
+}
+
+/// Everything the home screen shows, in one call
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dashboard {
+    /// Enabled schedules due today, earliest first
+    pub todays_doses: Vec<TodaysDose>,
+    /// Latest dose logs, newest first
+    pub recent_doses: Vec<DoseLogView>,
+    pub alerts: AlertCounts,
+    /// Vials expected to run out within two weeks
+    pub low_inventory: Vec<InventoryPrediction>,
+    pub latest_body_metric: Option<BodyMetricView>,
+    pub backup: BackupStatus,
+    pub ai: AiAvailabilityStatus,
+}
+
+fn timestamp(time: OffsetDateTime) -> String {
+    time.format(&Rfc3339).unwrap_or_else(|_| time.to_string())
+}
+
+/// Serve `stat` from the cache, or compute and cache it
+///
+/// The cache only saves work, so failing to read or write it is logged and
+/// otherwise ignored.
+fn cached_or_compute<T>(
+    storage: &StorageManager,
+    stat: DashboardStat,
+    refresh: bool,
+    compute: impl FnOnce() -> Result<T, CommandError>,
+) -> Result<StatValue<T>, CommandError>
+where
+    T: Serialize + DeserializeOwned,
+{
+    if !refresh {
+        match storage.cached_stat::<T>(stat) {
+            Ok(Some(cached)) => {
+                return Ok(StatValue {
+                    value: cached.value,
+                    computed_at: timestamp(cached.computed_at),
+                    cached: true,
+                })
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to read cached {:?} stat: {:#}", stat, e),
+        }
+    }
+
+    let generation = storage.stats_generation();
+    let value = compute()?;
+    if let Err(e) = storage.store_stat(stat, &value, generation) {
+        warn!("Failed to cache {:?} stat: {:#}", stat, e);
+    }
+    Ok(StatValue {
+        value,
+        computed_at: timestamp(OffsetDateTime::now_utc()),
+        cached: false,
+    })
+}
+
+fn load_error(what: &str) -> impl FnOnce(anyhow::Error) -> CommandError + '_ {
+    move |e| {
+        error!("Failed to compute {} for dashboard: {:#}", what, e);
+        CommandError::with_context(e, format!("Failed to compute {}", what))
+    }
+}
+
+fn doses_this_week(storage: &StorageManager, today: Date) -> Result<DoseWeekStats, CommandError> {
+    let filter = DoseStatsFilter {
+        since: Some(today - Duration::days(WEEK_DAYS - 1)),
+        ..Default::default()
+    };
+    let usage = storage
+        .dose_usage_by_protocol(&filter)
+        .map_err(load_error("dose stats"))?;
+    let daily = storage
+        .daily_dose_totals(&filter)
+        .map_err(load_error("dose stats"))?;
+    Ok(DoseWeekStats {
+        dose_count: usage.iter().map(|u| u.dose_count).sum(),
+        total_mg: usage.iter().map(|u| u.total_mg).sum(),
+        active_days: daily.len() as u32,
+    })
+}
+
+/// Number of days from `start` to `end` (inclusive) that fall on `weekdays`
+fn count_weekdays(start: Date, end: Date, weekdays: &[u8]) -> u32 {
+    let mut count = 0;
+    let mut day = start;
+    while day <= end {
+        if weekdays.contains(&day.weekday().number_days_from_sunday()) {
+            count += 1;
+        }
+        day += Duration::days(1);
+    }
+    count
+}
+
+fn build_adherence(
+    schedules: &[ScheduledUsage],
+    usage: &[ProtocolDoseUsage],
+    skips: &[DoseSkip],
+    start: Date,
+    today: Date,
+) -> AdherenceStats {
+    let mut expected_by_protocol: HashMap<&str, u32> = HashMap::new();
+    for schedule in schedules {
+        *expected_by_protocol
+            .entry(schedule.protocol_id.as_str())
+            .or_default() += count_weekdays(start, today, &schedule.days_of_week);
+    }
+
+    let mut expected = 0;
+    let mut logged = 0;
+    let mut skipped = 0;
+    for (protocol_id, protocol_expected) in expected_by_protocol {
+        let protocol_logged = usage
+            .iter()
+            .find(|u| u.protocol_id == protocol_id)
+            .map_or(0, |u| u.dose_count)
+            .min(protocol_expected);
+        let protocol_skipped = skips.iter().filter(|skip| skip.protocol_id == protocol_id).count() as u32;
+        expected += protocol_expected;
+        logged += protocol_logged;
+        skipped += protocol_skipped.min(protocol_expected - protocol_logged);
+    }
+
+    AdherenceStats {
+        window_days: ADHERENCE_WINDOW_DAYS,
+        expected,
+        logged,
+        skipped,
+        percent: (expected > 0).then(|| logged as f32 / expected as f32 * 100.0),
+    }
+}
+
+fn adherence(storage: &StorageManager, today: Date) -> Result<AdherenceStats, CommandError> {
+    let start = today - Duration::days(ADHERENCE_WINDOW_DAYS - 1);
+    let schedules = enabled_schedule_usage(storage).map_err(load_error("dose schedules"))?;
+    let usage = storage
+        .dose_usage_by_protocol(&DoseStatsFilter {
+            since: Some(start),
+            until: Some(today),
+            ..Default::default()
+        })
+        .map_err(load_error("dose stats"))?;
+    let skips = storage
+        .list_dose_skips(&DoseStatsFilter {
+            since: Some(start),
+            until: Some(today),
+            ..Default::default()
+        })
+        .map_err(load_error("skipped doses"))?;
+    Ok(build_adherence(&schedules, &usage, &skips, start, today))
+}
+
+fn spend(storage: &StorageManager, today: Date) -> Result<SpendStats, CommandError> {
+    let report = load_spend_report(storage, Some(SPEND_MONTHS), None)?;
+    let month = format!("{:04}-{:02}", today.year(), today.month() as u8);
+    Ok(SpendStats {
+        this_month_spend: report
+            .months
+            .iter()
+            .find(|m| m.month == month)
+            .map_or(0.0, |m| m.purchase_spend),
+        currency: report.currency,
+        total_spend: report.total_spend,
+        projected_monthly_cost: report.projected_monthly_cost,
+    })
+}
+
+fn inventory(storage: &StorageManager, today: Date) -> Result<InventoryStats, CommandError> {
+    let forecast = load_forecast(storage, DEFAULT_LEAD_TIME_DAYS, DEFAULT_HISTORY_DAYS)
+        .map_err(load_error("inventory forecast"))?;
+    let today = today.to_string();
+
+    let tracked: Vec<_> = forecast
+        .protocols
+        .iter()
+        .filter(|p| p.days_remaining.is_some())
+        .collect();
+    let first_out = tracked.iter().min_by(|a, b| {
+        a.days_remaining
+            .partial_cmp(&b.days_remaining)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(InventoryStats {
+        protocols_tracked: tracked.len() as u32,
+        min_days_remaining: first_out.and_then(|p| p.days_remaining),
+        min_days_protocol_name: first_out.map(|p| p.protocol_name.clone()),
+        reorder_due: forecast
+            .protocols
+            .iter()
+            .filter(|p| {
+                p.reorder_by
+                    .as_deref()
+                    .is_some_and(|date| date <= today.as_str())
+            })
+            .count() as u32,
+    })
+}
+
+fn goals(storage: &StorageManager) -> Result<GoalStats, CommandError> {
+    let mut goals = storage.list_goals().map_err(load_error("goals"))?;
+    goals.retain(|goal| !goal.archived);
+    let progress = load_goal_progress(storage, goals, OffsetDateTime::now_utc())
+        .map_err(load_error("goal progress"))?;
+
+    let (achieved, mut active): (Vec<_>, Vec<_>) = progress
+        .into_iter()
+        .partition(|goal| goal.progress.status == GoalStatus::Achieved);
+    // Goals without a target date go last
+    active.sort_by_key(|goal| (goal.goal.target_date.is_none(), goal.goal.target_date));
+    Ok(GoalStats {
+        active: active.len() as u32,
+        achieved: achieved.len() as u32,
+        goals: active,
+    })
+}
+
+/// Pair today's schedules with today's dose logs
+///
+/// A dose logged from a reminder counts for its schedule; other doses count
+/// for the earliest schedule of their protocol not yet taken.
+fn mark_taken(schedules: Vec<DoseSchedule>, logs: &[DoseLog]) -> Vec<TodaysDose> {
+    let mut doses: Vec<TodaysDose> = schedules
+        .into_iter()
+        .map(|schedule| TodaysDose { schedule, taken: false })
+        .collect();
+    let (linked, unlinked): (Vec<&DoseLog>, Vec<&DoseLog>) =
+        logs.iter().partition(|log| log.schedule_id.is_some());
+
+    for log in linked {
+        if let Some(dose) = doses
+            .iter_mut()
+            .find(|dose| !dose.taken && log.schedule_id.as_deref() == Some(dose.schedule.id.as_str()))
+        {
+            dose.taken = true;
+        }
+    }
+    for log in unlinked {
+        if let Some(dose) = doses
+            .iter_mut()
+            .find(|dose| !dose.taken && dose.schedule.protocol_id == log.protocol_id)
+        {
+            dose.taken = true;
+        }
+    }
+    doses
+}
+
+fn count_alerts(storage: &StorageManager) -> Result<AlertCounts, CommandError> {
+    let alerts = storage.list_alerts(false).map_err(load_error("alerts"))?;
+    let mut counts = AlertCounts::default();
+    for alert in &alerts {
+        match alert.severity {
+            AlertSeverity::Info => counts.info += 1,
+            AlertSeverity::Warning => counts.warning += 1,
+            AlertSeverity::Critical => counts.critical += 1,
+        }
+        if !alert.is_read {
+            counts.unread += 1;
+        }
+    }
+    Ok(counts)
+}
+
+// ========== Dashboard Commands ==========
+
+/// Everything the home screen needs, so it loads with one call instead of
+/// one per widget
+#[tauri::command]
+pub async fn get_dashboard(
+    state: State<'_, std::sync::Arc<AppState>>,
+    scheduler: State<'_, SchedulerState>,
+) -> Result<Dashboard, CommandError> {
+    let now = OffsetDateTime::now_utc();
+    let weekday = now.weekday().number_days_from_sunday();
+    let today_start = now.replace_time(time::Time::MIDNIGHT);
+
+    let schedules: Vec<DoseSchedule> = state
+        .on_storage(load_dose_schedules)
+        .await?
+        .into_iter()
+        .filter(|schedule| schedule.enabled && schedule.days_of_week.contains(&weekday))
+        .collect();
+    let (todays_logs, recent_logs, latest_metric) = state
+        .db
+        .run(move |storage| {
+            let todays_logs = storage.list_dose_logs_page(&ListOptions {
+                since: Some(today_start),
+                ..Default::default()
+            })?;
+            let recent_logs = storage.list_dose_logs_page(&ListOptions::page(RECENT_DOSES, 0))?;
+            let latest_metric = storage
+                .list_body_metrics_page(&ListOptions::page(1, 0))?
+                .into_iter()
+                .next();
+            Ok((todays_logs, recent_logs, latest_metric))
+        })
+        .await
+        .map_err(load_error("recent doses and body metrics"))?;
+
+    let latest_body_metric = match latest_metric {
+        Some(metric) => Some(metric_view(&load_unit_preferences(&state).await?, metric)),
+        None => None,
+    };
+    let low_inventory = predict_inventory_depletion(state.clone(), None, None)
+        .await?
+        .into_iter()
+        .filter(|prediction| prediction.will_run_out_soon)
+        .collect();
+
+    Ok(Dashboard {
+        todays_doses: mark_taken(schedules, &todays_logs),
+        recent_doses: dose_views(&state, recent_logs).await?,
+        alerts: state.on_storage(count_alerts).await?,
+        low_inventory,
+        latest_body_metric,
+        backup: scheduler.status().await,
+        ai: check_ai_availability(state.clone()).await?,
+    })
+}
+
+/// Dashboard stats, served from the stats cache when still valid
+///
+/// Each stat carries when it was computed; `refresh` recomputes all of them.
+#[tauri::command]
+pub async fn get_dashboard_stats(
+    state: State<'_, std::sync::Arc<AppState>>,
+    refresh: Option<bool>,
+) -> Result<DashboardStats, CommandError> {
+    let refresh = refresh.unwrap_or(false);
+    let today = OffsetDateTime::now_utc().date();
+    if refresh {
+        info!("Recomputing dashboard stats");
+    }
+
+    state
+        .on_storage(move |storage| {
+            Ok(DashboardStats {
+                doses_this_week: cached_or_compute(storage, DashboardStat::DosesThisWeek, refresh, || {
+                    doses_this_week(storage, today)
+                })?,
+                adherence: cached_or_compute(storage, DashboardStat::Adherence, refresh, || {
+                    adherence(storage, today)
+                })?,
+                spend: cached_or_compute(storage, DashboardStat::Spend, refresh, || spend(storage, today))?,
+                inventory: cached_or_compute(storage, DashboardStat::InventoryForecast, refresh, || {
+                    inventory(storage, today)
+                })?,
+                goals: cached_or_compute(storage, DashboardStat::Goals, refresh, || goals(storage))?,
+            })
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    fn usage(protocol_id: &str, dose_count: u32) -> ProtocolDoseUsage {
+        ProtocolDoseUsage {
+            protocol_id: protocol_id.to_string(),
+            dose_count,
+            total_mg: dose_count as f64,
+            active_days: dose_count,
+        }
+    }
+
+    #[test]
+    fn test_adherence_caps_extra_doses_per_protocol() {
+        // 2024-03-04 is a Monday
+        let (start, today) = (date!(2024 - 03 - 04), date!(2024 - 03 - 10));
+        let schedules = vec![
+            ScheduledUsage {
+                protocol_id: "daily".into(),
+                amount_mg: 0.25,
+                days_of_week: (0..7).collect(),
+            },
+            ScheduledUsage {
+                protocol_id: "weekly".into(),
+                amount_mg: 2.0,
+                days_of_week: vec![1],
+            },
+        ];
+        let logged = [
+            usage("daily", 5),
+            usage("weekly", 3),
+            usage("unscheduled", 4),
+        ];
+
+        let skips = [
+            DoseSkip::new("daily", "morning", date!(2024 - 03 - 05)),
+            DoseSkip::new("daily", "morning", date!(2024 - 03 - 06)),
+            DoseSkip::new("daily", "morning", date!(2024 - 03 - 07)),
+        ];
+
+        let stats = build_adherence(&schedules, &logged, &skips, start, today);
+        assert_eq!(stats.expected, 8);
+        assert_eq!(stats.logged, 6);
+        // Only two daily doses were missed
+        assert_eq!(stats.skipped, 2);
+        assert_eq!(stats.percent, Some(75.0));
+
+        let none = build_adherence(&[], &logged, &skips, start, today);
+        assert_eq!(none.expected, 0);
+        assert_eq!(none.percent, None);
+    }
+
+    fn schedule(id: &str, protocol_id: &str, time_of_day: &str) -> DoseSchedule {
+        DoseSchedule {
+            id: id.into(),
+            protocol_id: protocol_id.into(),
+            protocol_name: protocol_id.into(),
+            peptide_name: "BPC-157".into(),
+            amount_mg: 0.25,
+            site: None,
+            time_of_day: time_of_day.into(),
+            days_of_week: (0..7).collect(),
+            enabled: true,
+            notes: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            titration: None,
+            titration_status: None,
+            snoozed_until: None,
+        }
+    }
+
+    #[test]
+    fn test_todays_doses_are_matched_to_logs() {
+        let schedules = vec![
+            schedule("morning", "daily", "08:00"),
+            schedule("evening", "daily", "20:00"),
+            schedule("weekly", "weekly", "09:00"),
+        ];
+        let mut from_reminder = DoseLog::new("daily", "abdomen", 0.25);
+        from_reminder.schedule_id = Some("evening".into());
+        let logs = [from_reminder, DoseLog::new("daily", "thigh", 0.25)];
+
+        let taken: Vec<bool> = mark_taken(schedules, &logs).iter().map(|dose| dose.taken).collect();
+        // The unlinked dose goes to the morning, as the evening was logged
+        // from its reminder
+        assert_eq!(taken, vec![true, true, false]);
+    }
+
+    #[test]
+    fn test_count_weekdays_is_inclusive() {
+        assert_eq!(
+            count_weekdays(date!(2024 - 03 - 04), date!(2024 - 03 - 18), &[1]),
+            3
+        );
+        assert_eq!(
+            count_weekdays(date!(2024 - 03 - 05), date!(2024 - 03 - 04), &[2]),
+            0
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Imports from other dose trackers and spreadsheets, mapped by a profile
+//!
+//! The frontend reads a file's columns with `list_import_columns`, lets the
+//! user map them to PepTrack fields, shows `preview_import` and then calls
+//! `commit_import` with the same file and profile.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use peptrack_core::{
+    plan_import, read_rows, source_columns, ExistingRecords, ImportFormat, ImportPlan, ImportProfile, ImportProfiles,
+    ImportRowError, ImportTarget, StorageManager,
+};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use tracing::{error, info};
+
+use crate::commands::settings::{load_setting_or_default, save_setting};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Largest file accepted for import
+const MAX_IMPORT_BYTES: u64 = 50 * 1024 * 1024;
+/// Row errors returned by a preview; the total is always reported
+const MAX_PREVIEW_ERRORS: usize = 200;
+/// Records shown as a sample in a preview
+const PREVIEW_SAMPLE_SIZE: usize = 5;
+
+/// What importing a file would do
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    pub target: ImportTarget,
+    pub total_rows: usize,
+    /// Records that would be created
+    pub new_records: usize,
+    /// Protocols that would be created for dose logs of unknown protocols
+    pub new_protocols: Vec<String>,
+    pub duplicates: usize,
+    pub error_count: usize,
+    /// The first row errors
+    pub errors: Vec<ImportRowError>,
+    /// The first few records as they would be saved
+    pub sample: Vec<serde_json::Value>,
+}
+
+/// What an import wrote
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResult {
+    pub target: ImportTarget,
+    pub imported: usize,
+    pub protocols_created: usize,
+    pub duplicates: usize,
+    /// Rows skipped because they couldn't be read
+    pub skipped: usize,
+}
+
+fn read_file(file_path: &str) -> Result<Vec<u8>> {
+    let path = Path::new(file_path);
+    let size = std::fs::metadata(path)
+        .with_context(|| format!("Failed to open {}", file_path))?
+        .len();
+    if size > MAX_IMPORT_BYTES {
+        anyhow::bail!("The file is too large to import ({} MB at most)", MAX_IMPORT_BYTES / 1024 / 1024);
+    }
+    std::fs::read(path).with_context(|| format!("Failed to read {}", file_path))
+}
+
+/// Read `file_path` with `profile` and plan the records against what's stored
+fn plan_from_file(storage: &StorageManager, file_path: &str, profile: &ImportProfile) -> Result<ImportPlan> {
+    let data = read_file(file_path)?;
+    let rows = read_rows(&data, profile.format, profile.records_key.as_deref())?;
+
+    let protocols = storage.list_protocols()?;
+    let dose_logs = match profile.target {
+        ImportTarget::DoseLogs => storage.list_dose_logs()?,
+        _ => Vec::new(),
+    };
+    let body_metrics = match profile.target {
+        ImportTarget::BodyMetrics => storage.list_body_metrics()?,
+        _ => Vec::new(),
+    };
+    Ok(plan_import(
+        &rows,
+        profile,
+        ExistingRecords {
+            protocols: &protocols,
+            dose_logs: &dose_logs,
+            body_metrics: &body_metrics,
+        },
+    ))
+}
+
+fn sample_records<T: Serialize>(records: &[T]) -> Vec<serde_json::Value> {
+    records
+        .iter()
+        .take(PREVIEW_SAMPLE_SIZE)
+        .filter_map(|record| serde_json::to_value(record).ok())
+        .collect()
+}
+
+// ========== Import Commands ==========
+
+/// The column names (CSV headers or flattened JSON keys) found in a file
+#[tauri::command]
+pub async fn list_import_columns(
+    file_path: String,
+    format: ImportFormat,
+    records_key: Option<String>,
+) -> Result<Vec<String>, CommandError> {
+    tokio::task::spawn_blocking(move || -> Result<Vec<String>> {
+        let data = read_file(&file_path)?;
+        Ok(source_columns(&read_rows(&data, format, records_key.as_deref())?))
+    })
+    .await
+    .map_err(|e| CommandError::internal(format!("Import reader failed: {}", e)))?
+    .map_err(|e| CommandError::with_context(e, "Failed to read import file"))
+}
+
+#[tauri::command]
+pub async fn list_import_profiles(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<Vec<ImportProfile>, CommandError> {
+    Ok(load_setting_or_default::<ImportProfiles>(&state).await.profiles)
+}
+
+/// Save a profile, replacing any with the same name
+#[tauri::command]
+pub async fn save_import_profile(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    profile: ImportProfile,
+) -> Result<Vec<ImportProfile>, CommandError> {
+    let mut saved: ImportProfiles = load_setting_or_default(&state).await;
+    saved
+        .profiles
+        .retain(|existing| !existing.name.trim().eq_ignore_ascii_case(profile.name.trim()));
+    saved.profiles.push(profile);
+    save_setting(&app, &state, &saved).await?;
+    Ok(saved.profiles)
+}
+
+#[tauri::command]
+pub async fn delete_import_profile(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    name: String,
+) -> Result<Vec<ImportProfile>, CommandError> {
+    let mut saved: ImportProfiles = load_setting_or_default(&state).await;
+    let before = saved.profiles.len();
+    saved.profiles.retain(|profile| !profile.name.eq_ignore_ascii_case(&name));
+    if saved.profiles.len() == before {
+        return Err(CommandError::not_found(format!("No import profile named {}", name)));
+    }
+    save_setting(&app, &state, &saved).await?;
+    Ok(saved.profiles)
+}
+
+/// Check a file against a profile and report what importing it would do
+#[tauri::command]
+pub async fn preview_import(
+    state: State<'_, std::sync::Arc<AppState>>,
+    file_path: String,
+    profile: ImportProfile,
+) -> Result<ImportPreview, CommandError> {
+    profile.validate().map_err(CommandError::invalid_input)?;
+    let target = profile.target;
+
+    let plan = state
+        .db
+        .run(move |storage| plan_from_file(storage, &file_path, &profile))
+        .await
+        .map_err(|e| {
+            error!("Failed to read import file: {:#}", e);
+            CommandError::with_context(e, "Failed to read import file")
+        })?;
+
+    let new_protocols = match target {
+        ImportTarget::Protocols => Vec::new(),
+        _ => plan.protocols.iter().map(|protocol| protocol.name.clone()).collect(),
+    };
+    Ok(ImportPreview {
+        target,
+        total_rows: plan.total_rows,
+        new_records: plan.record_count(target),
+        new_protocols,
+        duplicates: plan.duplicates,
+        error_count: plan.errors.len(),
+        errors: plan.errors.iter().take(MAX_PREVIEW_ERRORS).cloned().collect(),
+        sample: match target {
+            ImportTarget::Protocols => sample_records(&plan.protocols),
+            ImportTarget::DoseLogs => sample_records(&plan.dose_logs),
+            ImportTarget::BodyMetrics => sample_records(&plan.body_metrics),
+        },
+    })
+}
+
+/// Import a file with a profile, all or nothing
+///
+/// Duplicates are skipped. Rows that can't be read are skipped with
+/// `skip_invalid`; otherwise any such row stops the import before anything
+/// is written. If a record fails to save, everything imported is rolled back.
+#[tauri::command]
+pub async fn commit_import(
+    state: State<'_, std::sync::Arc<AppState>>,
+    file_path: String,
+    profile: ImportProfile,
+    skip_invalid: bool,
+) -> Result<ImportResult, CommandError> {
+    profile.validate().map_err(CommandError::invalid_input)?;
+    info!("Importing {:?} from {} with profile {}", profile.target, file_path, profile.name);
+    let target = profile.target;
+
+    let result = state
+        .db
+        .run(move |storage| {
+            let plan = plan_from_file(storage, &file_path, &profile)?;
+            if !plan.errors.is_empty() && !skip_invalid {
+                return Ok(Err(plan.errors.len()));
+            }
+            let protocols_created = match target {
+                ImportTarget::Protocols => 0,
+                _ => plan.protocols.len(),
+            };
+            let result = ImportResult {
+                target,
+                imported: plan.record_count(target),
+                protocols_created,
+                duplicates: plan.duplicates,
+                skipped: plan.errors.len(),
+            };
+            storage.transaction(|batch| {
+                for protocol in &plan.protocols {
+                    batch.upsert_protocol(protocol)?;
+                }
+                for dose in &plan.dose_logs {
+                    batch.append_dose_log(dose)?;
+                }
+                for metric in &plan.body_metrics {
+                    batch.upsert_body_metric(metric)?;
+                }
+                Ok(())
+            })?;
+            Ok(Ok(result))
+        })
+        .await
+        .map_err(|e| {
+            error!("Import failed and was rolled back: {:#}", e);
+            CommandError::with_context(e, "Import failed; nothing was changed")
+        })?;
+
+    let result = result.map_err(|errors| {
+        CommandError::invalid_input(format!(
+            "{} rows can't be imported; fix them or skip them",
+            errors
+        ))
+    })?;
+    info!(
+        "Import complete: {} imported, {} protocols created, {} duplicates, {} skipped",
+        result.imported, result.protocols_created, result.duplicates, result.skipped
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_deserialize_from_the_frontend() {
+        let json = r#"{
+            "name": "Spreadsheet",
+            "format": "csv",
+            "target": "dose_logs",
+            "fields": {"protocol": "Compound", "logged_at": "Date", "amount": "Dose"},
+            "doseUnit": "mcg"
+        }"#;
+        let profile: ImportProfile = serde_json::from_str(json).unwrap();
+        assert_eq!(profile.target, ImportTarget::DoseLogs);
+        assert!(profile.date_format.is_none());
+        assert!(profile.validate().is_ok());
+    }
+}
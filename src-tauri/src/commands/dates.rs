@@ -0,0 +1,22 @@
+//! Dates sent by the frontend, which are RFC 3339 timestamps
+
+use time::format_description::well_known::Rfc3339;
+use time::{Date, OffsetDateTime};
+
+use crate::error::CommandError;
+
+/// Parse an RFC 3339 timestamp
+pub(crate) fn parse_datetime(value: &str) -> Result<OffsetDateTime, CommandError> {
+    OffsetDateTime::parse(value, &Rfc3339)
+        .map_err(|e| CommandError::with_context(e, "Invalid date format"))
+}
+
+/// The calendar date of an RFC 3339 timestamp
+pub(crate) fn parse_date(value: &str) -> Result<Date, CommandError> {
+    parse_datetime(value).map(|date| date.date())
+}
+
+/// [`parse_date`] for an optional bound, such as the start of a range
+pub(crate) fn parse_optional_date(value: Option<&str>) -> Result<Option<Date>, CommandError> {
+    value.map(parse_date).transpose()
+}
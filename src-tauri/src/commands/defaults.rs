@@ -1,7 +1,10 @@
+use peptrack_core::models::{PeptideProtocol, DEFAULT_RECONSTITUTED_STABILITY_DAYS};
+use peptrack_core::StorageManager;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 use tracing::info;
 
+use crate::error::CommandError;
 use crate::state::AppState;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -11,11 +14,17 @@ pub struct DefaultProtocol {
     pub common_name: String,
     pub typical_dose_range: String,
     pub notes: String,
+    /// Approximate elimination half-life, where one is published; used to
+    /// estimate active levels
+    pub half_life_hours: Option<f32>,
+    /// Approximate days a reconstituted vial keeps in the fridge; `None`
+    /// for peptides taken orally
+    pub reconstituted_stability_days: Option<u32>,
 }
 
 /// Get list of popular peptides for pre-population
 #[tauri::command]
-pub async fn get_default_peptides() -> Result<Vec<DefaultProtocol>, String> {
+pub async fn get_default_peptides() -> Result<Vec<DefaultProtocol>, CommandError> {
     Ok(get_popular_peptides())
 }
 
@@ -23,18 +32,21 @@ pub async fn get_default_peptides() -> Result<Vec<DefaultProtocol>, String> {
 #[tauri::command]
 pub async fn populate_default_peptides(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<usize, String> {
+) -> Result<usize, CommandError> {
     info!("Populating default peptides");
+    state.on_storage(seed_default_peptides).await
+}
 
+/// Adds a protocol for each catalog peptide that doesn't have one yet
+pub(crate) fn seed_default_peptides(storage: &StorageManager) -> Result<usize, CommandError> {
     let peptides = get_popular_peptides();
     let mut created_count = 0;
 
     for peptide in peptides {
         // Check if this peptide already exists (by peptide_name)
-        let existing = state
-            .storage
+        let existing = storage
             .list_protocols()
-            .map_err(|e| format!("Failed to check existing protocols: {}", e))?
+            .map_err(|e| CommandError::with_context(e, "Failed to check existing protocols"))?
             .into_iter()
             .any(|p| p.peptide_name == peptide.peptide_name);
 
@@ -42,25 +54,10 @@ pub async fn populate_default_peptides(
             continue; // Skip if already exists
         }
 
-        // Create protocol
-        let protocol = peptrack_core::models::PeptideProtocol {
-            id: uuid::Uuid::new_v4().to_string(),
-            name: format!("{} Protocol", peptide.common_name),
-            peptide_name: peptide.peptide_name,
-            notes: Some(format!(
-                "{}\n\nTypical dose range: {}",
-                peptide.notes, peptide.typical_dose_range
-            )),
-            current_vial_status: None,
-            target_concentration_mg_ml: None,
-            created_at: time::OffsetDateTime::now_utc(),
-            updated_at: time::OffsetDateTime::now_utc(),
-        };
-
-        state
-            .storage
+        let protocol = catalog_protocol(peptide);
+        storage
             .upsert_protocol(&protocol)
-            .map_err(|e| format!("Failed to create protocol: {}", e))?;
+            .map_err(|e| CommandError::with_context(e, "Failed to create protocol"))?;
 
         created_count += 1;
     }
@@ -69,6 +66,37 @@ pub async fn populate_default_peptides(
     Ok(created_count)
 }
 
+/// New protocol for a catalog peptide, with its dose range in the notes
+pub(crate) fn catalog_protocol(peptide: DefaultProtocol) -> PeptideProtocol {
+    let mut protocol = PeptideProtocol::new(format!("{} Protocol", peptide.common_name), peptide.peptide_name);
+    protocol.notes = Some(format!(
+        "{}\n\nTypical dose range: {}",
+        peptide.notes, peptide.typical_dose_range
+    ));
+    protocol
+}
+
+/// Catalog entry for a peptide, matched by name case-insensitively
+pub(crate) fn catalog_peptide(peptide_name: &str) -> Option<DefaultProtocol> {
+    let name = peptide_name.trim();
+    get_popular_peptides()
+        .into_iter()
+        .find(|peptide| peptide.peptide_name.eq_ignore_ascii_case(name))
+}
+
+/// Catalog half-life for a peptide, matched by name case-insensitively
+pub(crate) fn catalog_half_life(peptide_name: &str) -> Option<f32> {
+    catalog_peptide(peptide_name).and_then(|peptide| peptide.half_life_hours)
+}
+
+/// Days a reconstituted vial of the peptide keeps, from the catalog, or
+/// the default for peptides it doesn't list
+pub(crate) fn reconstituted_stability_days(peptide_name: &str) -> u32 {
+    catalog_peptide(peptide_name)
+        .and_then(|peptide| peptide.reconstituted_stability_days)
+        .unwrap_or(DEFAULT_RECONSTITUTED_STABILITY_DAYS)
+}
+
 fn get_popular_peptides() -> Vec<DefaultProtocol> {
     vec![
         DefaultProtocol {
@@ -76,162 +104,216 @@ fn get_popular_peptides() -> Vec<DefaultProtocol> {
             common_name: "Body Protection Compound-157".to_string(),
             typical_dose_range: "200-500 mcg/day".to_string(),
             notes: "Known for tissue repair and gut health. Commonly injected subcutaneously or taken orally.".to_string(),
+            half_life_hours: Some(4.0),
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "GHK-Cu".to_string(),
             common_name: "Copper Peptide (GHK-Cu)".to_string(),
             typical_dose_range: "0.5-2 mg/day".to_string(),
             notes: "Supports skin health, wound healing, and anti-aging. Often used topically or injected.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "Tesamorelin".to_string(),
             common_name: "Tesamorelin (GHRH)".to_string(),
             typical_dose_range: "1-2 mg/day".to_string(),
             notes: "FDA-approved for reducing abdominal fat. Growth hormone releasing hormone analog.".to_string(),
+            half_life_hours: Some(0.5),
+            reconstituted_stability_days: Some(14),
         },
         DefaultProtocol {
             peptide_name: "MOTS-c".to_string(),
             common_name: "MOTS-c".to_string(),
             typical_dose_range: "5-15 mg/week".to_string(),
             notes: "Mitochondrial peptide supporting metabolism and exercise capacity.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "CJC-1295".to_string(),
             common_name: "CJC-1295 (GHRH analog)".to_string(),
             typical_dose_range: "1-2 mg/week (without DAC)".to_string(),
             notes: "Growth hormone releasing hormone analog. Often combined with Ipamorelin.".to_string(),
+            half_life_hours: Some(0.5),
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "DSIP".to_string(),
             common_name: "Delta Sleep-Inducing Peptide".to_string(),
             typical_dose_range: "100-300 mcg before bed".to_string(),
             notes: "May support sleep quality and stress reduction.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "Ipamorelin".to_string(),
             common_name: "Ipamorelin (GHRP)".to_string(),
             typical_dose_range: "200-300 mcg, 2-3x/day".to_string(),
             notes: "Growth hormone secretagogue. Minimal effect on cortisol/prolactin.".to_string(),
+            half_life_hours: Some(2.0),
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "Retatrutide".to_string(),
             common_name: "Retatrutide (Triple Agonist)".to_string(),
             typical_dose_range: "1-12 mg/week (titrate)".to_string(),
             notes: "Triple agonist (GLP-1/GIP/glucagon) for weight management. Clinical trial phase.".to_string(),
+            half_life_hours: Some(144.0),
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "Sermorelin".to_string(),
             common_name: "Sermorelin (GHRH)".to_string(),
             typical_dose_range: "200-500 mcg before bed".to_string(),
             notes: "Growth hormone releasing hormone. Shorter half-life than CJC-1295.".to_string(),
+            half_life_hours: Some(0.2),
+            reconstituted_stability_days: Some(14),
         },
         DefaultProtocol {
             peptide_name: "Kisspeptin-10".to_string(),
             common_name: "Kisspeptin-10".to_string(),
             typical_dose_range: "1-5 mcg/kg".to_string(),
             notes: "Reproductive hormone regulation. Research phase for fertility support.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "Gonadorelin".to_string(),
             common_name: "Gonadorelin (GnRH)".to_string(),
             typical_dose_range: "100-200 mcg/injection".to_string(),
             notes: "Gonadotropin-releasing hormone. Supports testosterone production.".to_string(),
+            half_life_hours: Some(0.1),
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "GHRP-6".to_string(),
             common_name: "Growth Hormone Releasing Peptide-6".to_string(),
             typical_dose_range: "100-200 mcg, 2-3x/day".to_string(),
             notes: "Potent GH secretagogue. May increase appetite.".to_string(),
+            half_life_hours: Some(0.3),
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "GHRP-2".to_string(),
             common_name: "Growth Hormone Releasing Peptide-2".to_string(),
             typical_dose_range: "100-200 mcg, 2-3x/day".to_string(),
             notes: "Similar to GHRP-6 but less appetite stimulation.".to_string(),
+            half_life_hours: Some(0.5),
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "MK-677".to_string(),
             common_name: "Ibutamoren (MK-677)".to_string(),
             typical_dose_range: "10-25 mg/day (oral)".to_string(),
             notes: "Oral GH secretagogue. Not technically a peptide but commonly grouped.".to_string(),
+            half_life_hours: Some(5.0),
+            reconstituted_stability_days: None,
         },
         DefaultProtocol {
             peptide_name: "AOD-9604".to_string(),
             common_name: "AOD-9604 (Fragment 176-191)".to_string(),
             typical_dose_range: "300-600 mcg/day".to_string(),
             notes: "GH fragment targeting fat metabolism without GH's other effects.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "Semaglutide".to_string(),
             common_name: "Semaglutide (GLP-1 agonist)".to_string(),
             typical_dose_range: "0.25-2.4 mg/week (titrate)".to_string(),
             notes: "FDA-approved for weight management and diabetes. Weekly injection.".to_string(),
+            half_life_hours: Some(168.0),
+            reconstituted_stability_days: Some(56),
         },
         DefaultProtocol {
             peptide_name: "Tirzepatide".to_string(),
             common_name: "Tirzepatide (GIP/GLP-1 dual agonist)".to_string(),
             typical_dose_range: "2.5-15 mg/week (titrate)".to_string(),
             notes: "FDA-approved dual agonist for weight loss and diabetes management.".to_string(),
+            half_life_hours: Some(120.0),
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "SLU-PP-332".to_string(),
             common_name: "SLU-PP-332 (Exercise Mimetic)".to_string(),
             typical_dose_range: "Research phase - no established dose".to_string(),
             notes: "Novel exercise mimetic peptide. Currently in early research phase.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: None,
         },
         DefaultProtocol {
             peptide_name: "PT-141".to_string(),
             common_name: "Bremelanotide (PT-141)".to_string(),
             typical_dose_range: "1.75 mg as needed".to_string(),
             notes: "FDA-approved for hypoactive sexual desire disorder. Melanocortin receptor agonist.".to_string(),
+            half_life_hours: Some(2.7),
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "TB-500".to_string(),
             common_name: "Thymosin Beta-4 Fragment (TB-500)".to_string(),
             typical_dose_range: "2-10 mg/week".to_string(),
             notes: "Promotes healing and tissue repair. Often used for injury recovery.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "Epithalon".to_string(),
             common_name: "Epitalon (Epithalon)".to_string(),
             typical_dose_range: "5-10 mg/day for 10-20 days".to_string(),
             notes: "Telomerase activator. Used in longevity protocols.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "NAD+".to_string(),
             common_name: "NAD+ (Nicotinamide Adenine Dinucleotide)".to_string(),
             typical_dose_range: "50-500 mg IV or SubQ".to_string(),
             notes: "Cellular energy and metabolism support. Various administration methods.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: Some(14),
         },
         DefaultProtocol {
             peptide_name: "Semax".to_string(),
             common_name: "Semax".to_string(),
             typical_dose_range: "300-600 mcg/day (nasal or SubQ)".to_string(),
             notes: "Neuroprotective and cognitive enhancing peptide. Russian nootropic.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "Selank".to_string(),
             common_name: "Selank".to_string(),
             typical_dose_range: "250-500 mcg/day (nasal or SubQ)".to_string(),
             notes: "Anxiolytic and cognitive peptide. Related to tuftsin.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "KPV".to_string(),
             common_name: "KPV (Lys-Pro-Val)".to_string(),
             typical_dose_range: "250-500 mcg/day (oral or topical)".to_string(),
             notes: "Anti-inflammatory tripeptide. Supports gut and skin health.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "Oxytocin".to_string(),
             common_name: "Oxytocin".to_string(),
             typical_dose_range: "10-40 IU nasal as needed".to_string(),
             notes: "Social bonding and trust hormone. Various wellness applications.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: Some(28),
         },
         DefaultProtocol {
             peptide_name: "Melanotan II".to_string(),
             common_name: "Melanotan II (MT-II)".to_string(),
             typical_dose_range: "250-500 mcg/day".to_string(),
             notes: "Melanocortin receptor agonist. Tanning and libido effects.".to_string(),
+            half_life_hours: None,
+            reconstituted_stability_days: Some(28),
         },
     ]
 }
@@ -246,6 +328,13 @@ mod tests {
         assert_eq!(peptides.len(), 27, "Should have exactly 27 popular peptides");
     }
 
+    #[test]
+    fn test_reconstituted_stability_falls_back_to_default() {
+        assert_eq!(reconstituted_stability_days("semaglutide"), 56);
+        assert_eq!(reconstituted_stability_days("BPC-157"), 28);
+        assert_eq!(reconstituted_stability_days("Unknown Peptide"), DEFAULT_RECONSTITUTED_STABILITY_DAYS);
+    }
+
     #[test]
     fn test_peptide_names_unique() {
         let peptides = get_popular_peptides();
@@ -0,0 +1,203 @@
+//! Log viewer and diagnostics bundles for bug reports
+
+use std::io::Write as _;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use peptrack_core::models::{DatabaseStats, HealthReport};
+use peptrack_core::Redactor;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::State;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tracing::{error, info, Level};
+
+use crate::error::CommandError;
+use crate::logging::{self, LogEntry, LogFilter};
+use crate::state::AppState;
+
+const DEFAULT_LOG_LIMIT: usize = 500;
+/// Log entries included in a diagnostics bundle
+const BUNDLE_LOG_LIMIT: usize = 5_000;
+
+/// Everything in a diagnostics bundle; no database records are included
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsBundle {
+    pub generated_at: String,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub health: HealthReport,
+    pub stats: DatabaseStats,
+    pub logs: Vec<LogEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsExport {
+    pub path: String,
+    pub size_bytes: u64,
+    pub log_entries: usize,
+}
+
+/// Strip what a log entry may reveal about the user
+///
+/// The home directory is masked in the message and field values, and fields
+/// such as names and notes are redacted like an anonymized export.
+fn scrub_entry(mut entry: LogEntry, redactor: &Redactor, home: Option<&str>) -> LogEntry {
+    let mask = |text: &str| match home {
+        Some(home) if !home.is_empty() => text.replace(home, "~"),
+        _ => text.to_string(),
+    };
+    entry.message = mask(&entry.message);
+
+    let mut fields = Value::Object(std::mem::take(&mut entry.fields));
+    redactor.redact(&mut fields);
+    if let Value::Object(fields) = fields {
+        entry.fields = fields
+            .into_iter()
+            .map(|(key, value)| match value {
+                Value::String(text) => (key, Value::String(mask(&text))),
+                other => (key, other),
+            })
+            .collect();
+    }
+    entry
+}
+
+fn write_bundle(bundle: &DiagnosticsBundle) -> Result<(String, u64)> {
+    let timestamp = OffsetDateTime::now_utc()
+        .format(&time::format_description::parse("[year]-[month]-[day]_[hour]-[minute]").unwrap())
+        .unwrap_or_else(|_| "diagnostics".to_string());
+    let dir = dirs::download_dir()
+        .or_else(dirs::document_dir)
+        .context("Could not determine download directory")?;
+    let path = dir.join(format!("peptrack_diagnostics_{}.json.gz", timestamp));
+
+    let json = serde_json::to_vec_pretty(bundle)?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&json)?;
+    let compressed = encoder.finish()?;
+    std::fs::write(&path, &compressed)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok((path.to_string_lossy().to_string(), compressed.len() as u64))
+}
+
+// ========== Diagnostics Commands ==========
+
+/// Recent log entries, newest first, for the in-app log viewer
+///
+/// `level` is the least severe level to show ("error", "warn", "info", ...)
+/// and `module` matches part of the module path, e.g. "backup".
+#[tauri::command]
+pub async fn get_recent_logs(
+    level: Option<String>,
+    module: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<LogEntry>, CommandError> {
+    let level = match level.as_deref() {
+        Some(level) => Some(level.parse::<Level>().map_err(|_| {
+            CommandError::invalid_input(format!("Unknown log level: {}", level))
+        })?),
+        None => None,
+    };
+    let filter = LogFilter {
+        level,
+        module: module.filter(|module| !module.trim().is_empty()),
+    };
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT);
+
+    tokio::task::spawn_blocking(move || logging::recent_logs(&logging::log_dir()?, &filter, limit))
+        .await
+        .map_err(|e| CommandError::internal(format!("Log reader failed: {}", e)))?
+        .map_err(|e| {
+            error!("Failed to read logs: {:#}", e);
+            CommandError::with_context(e, "Failed to read logs")
+        })
+}
+
+/// Save logs, the health report and database stats for a bug report
+///
+/// Writes `peptrack_diagnostics_<timestamp>.json.gz` to Downloads. No
+/// database records are included, and the logs are scrubbed of the home
+/// directory and of names and notes logged as fields.
+#[tauri::command]
+pub async fn export_diagnostics_bundle(
+    state: State<'_, Arc<AppState>>,
+) -> Result<DiagnosticsExport, CommandError> {
+    info!("Exporting diagnostics bundle");
+
+    let (health, stats) = state
+        .db
+        .run(|storage| Ok((storage.health_check()?, storage.get_stats()?)))
+        .await
+        .map_err(|e| {
+            error!("Failed to collect database diagnostics: {:#}", e);
+            CommandError::with_context(e, "Failed to collect database diagnostics")
+        })?;
+
+    let export = tokio::task::spawn_blocking(move || -> Result<DiagnosticsExport> {
+        let redactor = Redactor::new();
+        let home = dirs::home_dir().map(|home| home.to_string_lossy().to_string());
+        let logs: Vec<LogEntry> =
+            logging::recent_logs(&logging::log_dir()?, &LogFilter::default(), BUNDLE_LOG_LIMIT)?
+                .into_iter()
+                .map(|entry| scrub_entry(entry, &redactor, home.as_deref()))
+                .collect();
+
+        let bundle = DiagnosticsBundle {
+            generated_at: OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            health,
+            stats,
+            logs,
+        };
+        let (path, size_bytes) = write_bundle(&bundle)?;
+        Ok(DiagnosticsExport {
+            path,
+            size_bytes,
+            log_entries: bundle.logs.len(),
+        })
+    })
+    .await
+    .map_err(|e| CommandError::internal(format!("Diagnostics export failed: {}", e)))?
+    .map_err(|e| {
+        error!("Failed to export diagnostics bundle: {:#}", e);
+        CommandError::with_context(e, "Failed to export diagnostics bundle")
+    })?;
+
+    info!("Diagnostics bundle written to {}", export.path);
+    Ok(export)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Map};
+
+    #[test]
+    fn scrubbed_entries_hide_home_and_names() {
+        let mut fields = Map::new();
+        fields.insert("name".into(), json!("Morning BPC"));
+        fields.insert("path".into(), json!("/home/alex/Downloads/backup.json"));
+        let entry = LogEntry {
+            timestamp: "2025-01-01T10:00:00Z".into(),
+            level: "INFO".into(),
+            target: "app_lib::commands::restore".into(),
+            message: "Restoring from backup: /home/alex/Downloads/backup.json".into(),
+            fields,
+        };
+
+        let scrubbed = scrub_entry(entry, &Redactor::new(), Some("/home/alex"));
+        assert_eq!(scrubbed.message, "Restoring from backup: ~/Downloads/backup.json");
+        assert_eq!(scrubbed.fields["path"], json!("~/Downloads/backup.json"));
+        assert_ne!(scrubbed.fields["name"], json!("Morning BPC"));
+    }
+}
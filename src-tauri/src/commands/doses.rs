@@ -1,8 +1,23 @@
-use anyhow::Result;
-use peptrack_core::models::DoseLog;
-use serde::Deserialize;
-use tauri::State;
+use std::collections::HashSet;
 
+use anyhow::Result;
+use peptrack_core::models::{DoseLog, DoseLogCorrection};
+use peptrack_core::{
+    DailyDoseTotal, DosePreset, DosePresets, DoseStatsFilter, DoseUnit, ListOptions, PeptideProtocol, ProtocolDoseUsage, SiteDoseUsage,
+    TrashEntityType, UnitPreferences,
+};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tracing::{error, info};
+
+use crate::commands::dates::{parse_datetime, parse_optional_date};
+use crate::commands::preferences::load_unit_preferences;
+use crate::commands::schedules::load_dose_schedules;
+use crate::commands::settings::{load_setting_or_default, save_setting};
+use crate::commands::trash::move_to_trash;
+use crate::error::CommandError;
 use crate::state::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -12,34 +27,556 @@ pub struct LogDosePayload {
     pub site: String,
     pub amount_mg: f32,
     pub notes: Option<String>,
+    /// Dose schedule the dose was logged from, e.g. via a reminder
+    #[serde(default)]
+    pub schedule_id: Option<String>,
+    /// Unit `amount_mg` was entered in; mg when not given
+    #[serde(default)]
+    pub unit: Option<DoseUnit>,
+}
+
+/// New values for a logged dose; the schedule it was logged from is kept
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateDosePayload {
+    pub log_id: String,
+    pub protocol_id: String,
+    pub site: String,
+    pub amount_mg: f32,
+    pub notes: Option<String>,
+    /// RFC3339
+    pub logged_at: String,
+    /// Unit `amount_mg` was entered in; mg when not given
+    #[serde(default)]
+    pub unit: Option<DoseUnit>,
+}
+
+/// One dose of a backfill
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkDoseEntry {
+    pub protocol_id: String,
+    pub site: String,
+    pub amount_mg: f32,
+    pub notes: Option<String>,
+    /// RFC3339; must not be in the future
+    pub logged_at: String,
+    /// Schedule the dose belongs to; found from the day when not given
+    #[serde(default)]
+    pub schedule_id: Option<String>,
+    /// Unit `amount_mg` was entered in; mg when not given
+    #[serde(default)]
+    pub unit: Option<DoseUnit>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkLogDosesPayload {
+    pub entries: Vec<BulkDoseEntry>,
+    /// Accept doses on days none of the protocol's enabled schedules cover
+    #[serde(default)]
+    pub allow_unscheduled: bool,
+}
+
+/// What happened to one entry; results are in the order entries were sent
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkDoseResult {
+    pub index: usize,
+    /// The saved dose, when the entry was logged
+    pub dose: Option<DoseLogView>,
+    /// Why the entry wasn't logged
+    pub error: Option<String>,
+}
+
+/// Weekdays of an enabled schedule
+struct ScheduledDays {
+    id: String,
+    protocol_id: String,
+    days_of_week: Vec<u8>,
+}
+
+/// What backfilled entries are checked against
+struct BulkDoseCheck<'a> {
+    protocols: &'a [PeptideProtocol],
+    schedules: &'a [ScheduledDays],
+    preferences: &'a UnitPreferences,
+    allow_unscheduled: bool,
+    now: OffsetDateTime,
+    /// Protocol and minute of every dose already logged, including earlier
+    /// entries of the same batch
+    logged: HashSet<(String, i64)>,
+}
+
+impl BulkDoseCheck<'_> {
+    fn check(&mut self, entry: &BulkDoseEntry) -> Result<DoseLog, String> {
+        let logged_at = OffsetDateTime::parse(&entry.logged_at, &Rfc3339)
+            .map_err(|_| format!("Invalid date: {}", entry.logged_at))?;
+        if logged_at > self.now {
+            return Err("The dose time is in the future".to_string());
+        }
+        let protocol = self
+            .protocols
+            .iter()
+            .find(|protocol| protocol.id == entry.protocol_id)
+            .ok_or_else(|| format!("Protocol {} not found", entry.protocol_id))?;
+        if entry.site.trim().is_empty() {
+            return Err("Enter an injection site".to_string());
+        }
+        if !entry.amount_mg.is_finite() || entry.amount_mg <= 0.0 {
+            return Err("The amount must be above zero".to_string());
+        }
+        let amount_mg = self.preferences.dose_to_mg(
+            Some(&protocol.peptide_name),
+            entry.amount_mg,
+            entry.unit.unwrap_or_default(),
+        )?;
+
+        let weekday = logged_at.weekday().number_days_from_sunday();
+        let schedules: Vec<&ScheduledDays> = self
+            .schedules
+            .iter()
+            .filter(|schedule| schedule.protocol_id == protocol.id)
+            .collect();
+        let schedule = match &entry.schedule_id {
+            Some(id) => Some(
+                schedules
+                    .iter()
+                    .find(|schedule| &schedule.id == id)
+                    .ok_or_else(|| format!("{} has no enabled schedule {}", protocol.name, id))?,
+            ),
+            None => schedules.iter().find(|schedule| schedule.days_of_week.contains(&weekday)),
+        };
+        let on_schedule = schedule.is_some_and(|schedule| schedule.days_of_week.contains(&weekday));
+        if !schedules.is_empty() && !on_schedule && !self.allow_unscheduled {
+            return Err(format!(
+                "{} isn't a scheduled day for {}",
+                logged_at.date(),
+                protocol.name
+            ));
+        }
+
+        let minute = (protocol.id.clone(), logged_at.unix_timestamp().div_euclid(60));
+        if !self.logged.insert(minute) {
+            return Err(format!("A dose of {} is already logged at this time", protocol.name));
+        }
+
+        let mut log = DoseLog::new(protocol.id.clone(), entry.site.trim().to_string(), amount_mg);
+        log.notes = entry.notes.clone().filter(|notes| !notes.trim().is_empty());
+        log.logged_at = logged_at;
+        log.schedule_id = schedule.map(|schedule| schedule.id.clone());
+        Ok(log)
+    }
+}
+
+/// A dose log with its amount in the user's preferred unit
+///
+/// The log's own fields, including `amount_mg`, are unchanged.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoseLogView {
+    #[serde(flatten)]
+    pub log: DoseLog,
+    pub display_amount: f32,
+    pub display_unit: DoseUnit,
+}
+
+/// Which doses to aggregate; dates are RFC3339 strings and both days are included
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoseStatsPayload {
+    pub protocol_id: Option<String>,
+    pub schedule_id: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    /// Leave out doses edited after they were logged
+    #[serde(default)]
+    pub exclude_edited: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoseStats {
+    pub by_protocol: Vec<ProtocolDoseUsage>,
+    pub by_site: Vec<SiteDoseUsage>,
+    pub daily: Vec<DailyDoseTotal>,
+}
+
+impl DoseStatsPayload {
+    fn into_filter(self) -> Result<DoseStatsFilter, CommandError> {
+        Ok(DoseStatsFilter {
+            since: parse_optional_date(self.start_date.as_deref())?,
+            until: parse_optional_date(self.end_date.as_deref())?,
+            protocol_id: self.protocol_id,
+            schedule_id: self.schedule_id,
+            exclude_edited: self.exclude_edited,
+        })
+    }
+}
+
+pub(crate) fn dose_view(preferences: &UnitPreferences, peptide_name: Option<&str>, log: DoseLog) -> DoseLogView {
+    DoseLogView {
+        display_amount: preferences.dose_from_mg(peptide_name, log.amount_mg),
+        display_unit: preferences.dose_unit_for(peptide_name),
+        log,
+    }
+}
+
+pub(crate) async fn dose_views(state: &AppState, logs: Vec<DoseLog>) -> Result<Vec<DoseLogView>, CommandError> {
+    let preferences = load_unit_preferences(state).await?;
+    let protocols = state.db.run(|storage| storage.list_protocols()).await.map_err(|e| {
+        error!("Failed to load protocols for dose units: {:#}", e);
+        CommandError::with_context(e, "Failed to load protocols")
+    })?;
+
+    Ok(logs
+        .into_iter()
+        .map(|log| {
+            let peptide_name = peptide_name(&protocols, &log.protocol_id);
+            dose_view(&preferences, peptide_name, log)
+        })
+        .collect())
+}
+
+fn peptide_name<'a>(protocols: &'a [PeptideProtocol], protocol_id: &str) -> Option<&'a str> {
+    protocols
+        .iter()
+        .find(|protocol| protocol.id == protocol_id)
+        .map(|protocol| protocol.peptide_name.as_str())
 }
 
 /// Logs a new dose
+///
+/// The amount is converted from `unit` to mg before it's stored.
 #[tauri::command]
 pub async fn log_dose(
     state: State<'_, std::sync::Arc<AppState>>,
     payload: LogDosePayload,
-) -> Result<DoseLog, String> {
-    let mut log = DoseLog::new(payload.protocol_id, payload.site, payload.amount_mg);
+) -> Result<DoseLogView, CommandError> {
+    let preferences = load_unit_preferences(&state).await?;
+    let protocol_id = payload.protocol_id.clone();
+    let protocol = state
+        .db
+        .run(move |storage| storage.get_protocol(&protocol_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch protocol"))?;
+    let peptide_name = protocol.as_ref().map(|protocol| protocol.peptide_name.as_str());
+    let amount_mg = preferences
+        .dose_to_mg(peptide_name, payload.amount_mg, payload.unit.unwrap_or_default())
+        .map_err(CommandError::invalid_input)?;
+
+    let mut log = DoseLog::new(payload.protocol_id, payload.site, amount_mg);
     log.notes = payload.notes;
+    log.schedule_id = payload.schedule_id;
+
+    let log = state
+        .db
+        .run(move |storage| storage.append_dose_log(&log).map(|_| log))
+        .await
+        .map_err(CommandError::from)?;
+
+    Ok(dose_view(&preferences, peptide_name, log))
+}
+
+/// Logs the most recent dose again at the current time
+///
+/// Used by the tray and the global shortcut as well as the
+/// `log_last_dose_again` command.
+pub(crate) async fn repeat_last_dose(state: &AppState) -> Result<DoseLogView, CommandError> {
+    let (last, protocol) = state
+        .db
+        .run(|storage| {
+            let last = storage.list_dose_logs_page(&ListOptions::page(1, 0))?.into_iter().next();
+            let protocol = match &last {
+                Some(last) => storage.get_protocol(&last.protocol_id)?,
+                None => None,
+            };
+            Ok((last, protocol))
+        })
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch the last dose"))?;
+    let last = last.ok_or_else(|| CommandError::not_found("No doses have been logged yet"))?;
+    let protocol = protocol.ok_or_else(|| {
+        CommandError::not_found("The protocol of the last dose no longer exists")
+    })?;
+
+    let mut log = DoseLog::new(last.protocol_id, last.site, last.amount_mg);
+    log.notes = last.notes;
+    let log = state
+        .db
+        .run(move |storage| storage.append_dose_log(&log).map(|_| log))
+        .await
+        .map_err(|e| {
+            error!("Failed to log the last dose again: {:#}", e);
+            CommandError::with_context(e, "Failed to log dose")
+        })?;
+    info!("Logged the last dose of {} again", protocol.name);
+
+    let preferences = load_unit_preferences(state).await?;
+    Ok(dose_view(&preferences, Some(&protocol.peptide_name), log))
+}
+
+#[tauri::command]
+pub async fn log_last_dose_again(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<DoseLogView, CommandError> {
+    repeat_last_dose(&state).await
+}
+
+/// Edits a logged dose
+///
+/// The values it had before are kept and can be listed with
+/// `list_dose_log_corrections`, and the log is flagged as edited. The amount
+/// is converted from `unit` to mg before it's stored.
+#[tauri::command]
+pub async fn update_dose_log(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: UpdateDosePayload,
+) -> Result<DoseLogView, CommandError> {
+    let logged_at = parse_datetime(&payload.logged_at)?;
+    let preferences = load_unit_preferences(&state).await?;
+    let log_id = payload.log_id.clone();
+    let protocol_id = payload.protocol_id.clone();
+    let (existing, protocol) = state
+        .db
+        .run(move |storage| Ok((storage.get_dose_log(&log_id)?, storage.get_protocol(&protocol_id)?)))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch dose log"))?;
+    let existing =
+        existing.ok_or_else(|| CommandError::not_found(format!("Dose log {} not found", payload.log_id)))?;
+    let protocol = protocol.ok_or_else(|| {
+        CommandError::not_found(format!("Protocol {} not found", payload.protocol_id))
+    })?;
+    let peptide_name = Some(protocol.peptide_name.as_str());
+    let amount_mg = preferences
+        .dose_to_mg(peptide_name, payload.amount_mg, payload.unit.unwrap_or_default())
+        .map_err(CommandError::invalid_input)?;
+
+    let log = DoseLog {
+        protocol_id: payload.protocol_id,
+        site: payload.site,
+        amount_mg,
+        notes: payload.notes,
+        logged_at,
+        ..existing
+    };
+    let log = state
+        .db
+        .run(move |storage| storage.update_dose_log(&log))
+        .await
+        .map_err(|e| {
+            error!("Failed to update dose log: {:#}", e);
+            CommandError::with_context(e, "Failed to update dose log")
+        })?;
+
+    Ok(dose_view(&preferences, peptide_name, log))
+}
 
+/// The values a dose log had before each of its edits, oldest first
+#[tauri::command]
+pub async fn list_dose_log_corrections(
+    state: State<'_, std::sync::Arc<AppState>>,
+    log_id: String,
+) -> Result<Vec<DoseLogCorrection>, CommandError> {
+    state
+        .db
+        .run(move |storage| storage.list_dose_log_corrections(&log_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to list dose log corrections: {:#}", e);
+            CommandError::with_context(e, "Failed to list dose log corrections")
+        })
+}
+
+/// Logs many doses at once, e.g. a week entered after the fact
+///
+/// Each entry is checked on its own: the protocol must exist, the time must
+/// not be in the future or repeat a dose already logged, and when the
+/// protocol has enabled schedules the day must be one of theirs unless
+/// `allow_unscheduled` is set. Entries that pass are saved in one
+/// transaction; the result for every entry says whether it was logged.
+#[tauri::command]
+pub async fn bulk_log_doses(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: BulkLogDosesPayload,
+) -> Result<Vec<BulkDoseResult>, CommandError> {
+    if payload.entries.is_empty() {
+        return Err(CommandError::invalid_input("Add at least one dose to log"));
+    }
+    let preferences = load_unit_preferences(&state).await?;
+    let schedules: Vec<ScheduledDays> = state
+        .on_storage(load_dose_schedules)
+        .await?
+        .into_iter()
+        .filter(|schedule| schedule.enabled)
+        .map(|schedule| ScheduledDays {
+            id: schedule.id,
+            protocol_id: schedule.protocol_id,
+            days_of_week: schedule.days_of_week,
+        })
+        .collect();
+    let (protocols, existing) = state
+        .db
+        .run(|storage| Ok((storage.list_protocols()?, storage.list_dose_logs()?)))
+        .await
+        .map_err(|e| {
+            error!("Failed to load doses for bulk logging: {:#}", e);
+            CommandError::with_context(e, "Failed to load existing doses")
+        })?;
+
+    let mut check = BulkDoseCheck {
+        protocols: &protocols,
+        schedules: &schedules,
+        preferences: &preferences,
+        allow_unscheduled: payload.allow_unscheduled,
+        now: OffsetDateTime::now_utc(),
+        logged: existing
+            .iter()
+            .map(|log| (log.protocol_id.clone(), log.logged_at.unix_timestamp().div_euclid(60)))
+            .collect(),
+    };
+    let checked: Vec<Result<DoseLog, String>> =
+        payload.entries.iter().map(|entry| check.check(entry)).collect();
+
+    let logs: Vec<DoseLog> = checked.iter().filter_map(|result| result.as_ref().ok().cloned()).collect();
+    let saved = logs.len();
     state
-        .storage
-        .append_dose_log(&log)
-        .map_err(|err| err.to_string())?;
+        .db
+        .run(move |storage| storage.append_dose_logs(&logs))
+        .await
+        .map_err(|e| {
+            error!("Failed to save bulk doses: {:#}", e);
+            CommandError::with_context(e, "Failed to save doses; none were logged")
+        })?;
+    info!("Bulk logged {} of {} doses", saved, checked.len());
+
+    Ok(checked
+        .into_iter()
+        .enumerate()
+        .map(|(index, result)| match result {
+            Ok(log) => {
+                let peptide_name = peptide_name(&protocols, &log.protocol_id);
+                BulkDoseResult {
+                    index,
+                    dose: Some(dose_view(&preferences, peptide_name, log)),
+                    error: None,
+                }
+            }
+            Err(error) => BulkDoseResult {
+                index,
+                dose: None,
+                error: Some(error),
+            },
+        })
+        .collect())
+}
+
+// ========== Dose Presets ==========
+
+/// Saved dose presets, favorites first
+#[tauri::command]
+pub async fn list_dose_presets(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<Vec<DosePreset>, CommandError> {
+    Ok(load_setting_or_default::<DosePresets>(&state).await.presets)
+}
 
-    Ok(log)
+/// Saves a preset, replacing the one with its id
+///
+/// The amount is converted from `unit` to mg before it's stored. Returns
+/// every preset.
+#[tauri::command]
+pub async fn save_dose_preset(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    mut preset: DosePreset,
+    unit: Option<DoseUnit>,
+) -> Result<Vec<DosePreset>, CommandError> {
+    let protocol_id = preset.protocol_id.clone();
+    let protocol = state
+        .db
+        .run(move |storage| storage.get_protocol(&protocol_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch protocol"))?
+        .ok_or_else(|| CommandError::not_found(format!("Protocol {} not found", preset.protocol_id)))?;
+    preset.amount_mg = load_unit_preferences(&state).await?
+        .dose_to_mg(Some(&protocol.peptide_name), preset.amount_mg, unit.unwrap_or_default())
+        .map_err(CommandError::invalid_input)?;
+
+    let mut presets: DosePresets = load_setting_or_default(&state).await;
+    presets.save(preset);
+    save_setting(&app, &state, &presets).await?;
+    Ok(presets.presets)
+}
+
+#[tauri::command]
+pub async fn delete_dose_preset(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    preset_id: String,
+) -> Result<Vec<DosePreset>, CommandError> {
+    let mut presets: DosePresets = load_setting_or_default(&state).await;
+    if !presets.remove(&preset_id) {
+        return Err(CommandError::not_found(format!("Dose preset {} not found", preset_id)));
+    }
+    save_setting(&app, &state, &presets).await?;
+    Ok(presets.presets)
+}
+
+/// Logs a dose now from a preset
+///
+/// Without `preset_id` this logs the first favorite preset, or the only
+/// preset when there's just one, for the quick-log shortcut.
+#[tauri::command]
+pub async fn log_dose_from_preset(
+    state: State<'_, std::sync::Arc<AppState>>,
+    preset_id: Option<String>,
+) -> Result<DoseLogView, CommandError> {
+    let presets: DosePresets = load_setting_or_default(&state).await;
+    let preset = match &preset_id {
+        Some(id) => presets
+            .get(id)
+            .ok_or_else(|| CommandError::not_found(format!("Dose preset {} not found", id)))?,
+        None => presets.quick_log().ok_or_else(|| {
+            CommandError::not_found("Mark a dose preset as a favorite to quick-log it")
+        })?,
+    };
+    let log = preset.to_dose_log();
+    info!("Logging dose from preset {}", preset.name);
+
+    let protocol_id = log.protocol_id.clone();
+    let protocol = state
+        .db
+        .run(move |storage| storage.get_protocol(&protocol_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch protocol"))?
+        .ok_or_else(|| {
+            CommandError::not_found(format!("The protocol of preset {} no longer exists", preset.name))
+        })?;
+    let log = state
+        .db
+        .run(move |storage| storage.append_dose_log(&log).map(|_| log))
+        .await
+        .map_err(|e| {
+            error!("Failed to log dose from preset: {:#}", e);
+            CommandError::with_context(e, "Failed to log dose")
+        })?;
+
+    let preferences = load_unit_preferences(&state).await?;
+    Ok(dose_view(&preferences, Some(&protocol.peptide_name), log))
 }
 
 /// Lists all dose logs
 #[tauri::command]
 pub async fn list_dose_logs(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<Vec<DoseLog>, String> {
-    state
-        .storage
-        .list_dose_logs()
-        .map_err(|err| err.to_string())
+) -> Result<Vec<DoseLogView>, CommandError> {
+    let logs = state
+        .db
+        .run(|storage| storage.list_dose_logs())
+        .await
+        .map_err(CommandError::from)?;
+    dose_views(&state, logs).await
 }
 
 /// Lists dose logs for a specific protocol
@@ -47,35 +584,57 @@ pub async fn list_dose_logs(
 pub async fn list_dose_logs_for_protocol(
     state: State<'_, std::sync::Arc<AppState>>,
     protocol_id: String,
-) -> Result<Vec<DoseLog>, String> {
-    state
-        .storage
-        .list_dose_logs_for_protocol(&protocol_id)
-        .map_err(|err| err.to_string())
+) -> Result<Vec<DoseLogView>, CommandError> {
+    let logs = state
+        .db
+        .run(move |storage| storage.list_dose_logs_for_protocol(&protocol_id))
+        .await
+        .map_err(CommandError::from)?;
+    dose_views(&state, logs).await
+}
+
+/// Dose usage per protocol, per injection site and per day
+///
+/// Computed in SQL from the dose logs' index columns, without decrypting them.
+#[tauri::command]
+pub async fn get_dose_stats(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: Option<DoseStatsPayload>,
+) -> Result<DoseStats, CommandError> {
+    let filter = payload.unwrap_or_default().into_filter()?;
+    let stats = state
+        .db
+        .run(move |storage| {
+            Ok(DoseStats {
+                by_protocol: storage.dose_usage_by_protocol(&filter)?,
+                by_site: storage.dose_usage_by_site(&filter)?,
+                daily: storage.daily_dose_totals(&filter)?,
+            })
+        })
+        .await;
+
+    stats.map_err(|e| {
+        error!("Failed to compute dose stats: {:#}", e);
+        CommandError::with_context(e, "Failed to compute dose stats")
+    })
 }
 
-/// Deletes a specific dose log
+/// Moves a specific dose log to the trash
 #[tauri::command]
 pub async fn delete_dose_log(
     state: State<'_, std::sync::Arc<AppState>>,
     log_id: String,
-) -> Result<(), String> {
-    state
-        .storage
-        .delete_dose_log(&log_id)
-        .map_err(|err| err.to_string())
+) -> Result<(), CommandError> {
+    move_to_trash(&state, TrashEntityType::DoseLog, &[log_id]).await.map(|_| ())
 }
 
-/// Bulk delete multiple dose logs
+/// Move multiple dose logs to the trash
 #[tauri::command]
 pub async fn bulk_delete_doses(
     state: State<'_, std::sync::Arc<AppState>>,
     dose_ids: Vec<String>,
-) -> Result<usize, String> {
-    state
-        .storage
-        .bulk_delete_doses(&dose_ids)
-        .map_err(|err| err.to_string())
+) -> Result<usize, CommandError> {
+    move_to_trash(&state, TrashEntityType::DoseLog, &dose_ids).await
 }
 
 #[cfg(test)]
@@ -101,6 +660,99 @@ mod tests {
         assert_eq!(payload.notes, Some("Morning dose".to_string()));
     }
 
+    #[test]
+    fn test_dose_view_keeps_log_fields_and_adds_display_amount() {
+        let preferences = UnitPreferences {
+            dose_unit: DoseUnit::Mcg,
+            ..Default::default()
+        };
+        let log = DoseLog::new("p1", "abdomen", 0.25);
+        let json = serde_json::to_value(dose_view(&preferences, Some("BPC-157"), log)).unwrap();
+
+        assert_eq!(json["amount_mg"], 0.25);
+        assert_eq!(json["protocol_id"], "p1");
+        assert_eq!(json["displayAmount"], 250.0);
+        assert_eq!(json["displayUnit"], "mcg");
+    }
+
+    #[test]
+    fn test_dose_stats_payload_into_filter() {
+        let payload: DoseStatsPayload = serde_json::from_str(
+            r#"{"scheduleId": "s1", "startDate": "2024-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        let filter = payload.into_filter().unwrap();
+        assert_eq!(filter.schedule_id.as_deref(), Some("s1"));
+        assert_eq!(filter.since.unwrap().to_string(), "2024-01-01");
+        assert!(filter.until.is_none() && filter.protocol_id.is_none());
+
+        let invalid = DoseStatsPayload {
+            end_date: Some("yesterday".into()),
+            ..Default::default()
+        };
+        assert!(invalid.into_filter().is_err());
+    }
+
+    #[test]
+    fn test_update_dose_payload_defaults_to_mg() {
+        let payload: UpdateDosePayload = serde_json::from_str(
+            r#"{"logId": "d1", "protocolId": "p1", "site": "thigh", "amountMg": 0.5, "loggedAt": "2024-01-01T08:00:00Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(payload.log_id, "d1");
+        assert!(payload.unit.is_none() && payload.notes.is_none());
+    }
+
+    fn bulk_entry(protocol_id: &str, logged_at: &str) -> BulkDoseEntry {
+        BulkDoseEntry {
+            protocol_id: protocol_id.into(),
+            site: "abdomen".into(),
+            amount_mg: 250.0,
+            notes: None,
+            logged_at: logged_at.into(),
+            schedule_id: None,
+            unit: Some(DoseUnit::Mcg),
+        }
+    }
+
+    #[test]
+    fn test_bulk_entries_are_checked_against_the_schedule() {
+        let protocol = PeptideProtocol::new("Morning", "BPC-157");
+        let unscheduled = PeptideProtocol::new("As needed", "TB-500");
+        let protocols = [protocol.clone(), unscheduled.clone()];
+        // Mondays and Wednesdays
+        let schedules = [ScheduledDays {
+            id: "s1".into(),
+            protocol_id: protocol.id.clone(),
+            days_of_week: vec![1, 3],
+        }];
+        let preferences = UnitPreferences::default();
+        let mut check = BulkDoseCheck {
+            protocols: &protocols,
+            schedules: &schedules,
+            preferences: &preferences,
+            allow_unscheduled: false,
+            now: time::macros::datetime!(2024-03-08 12:00 UTC),
+            logged: HashSet::new(),
+        };
+
+        // Monday 2024-03-04
+        let log = check.check(&bulk_entry(&protocol.id, "2024-03-04T08:00:00Z")).unwrap();
+        assert_eq!(log.amount_mg, 0.25);
+        assert_eq!(log.schedule_id.as_deref(), Some("s1"));
+        assert!(check.check(&bulk_entry(&protocol.id, "2024-03-04T08:00:30Z")).is_err());
+        // Tuesday
+        let error = check.check(&bulk_entry(&protocol.id, "2024-03-05T08:00:00Z")).unwrap_err();
+        assert!(error.contains("scheduled day"));
+        assert!(check.check(&bulk_entry(&unscheduled.id, "2024-03-05T08:00:00Z")).is_ok());
+        assert!(check.check(&bulk_entry(&protocol.id, "2024-03-09T08:00:00Z")).is_err());
+        assert!(check.check(&bulk_entry("missing", "2024-03-04T09:00:00Z")).is_err());
+
+        check.allow_unscheduled = true;
+        let log = check.check(&bulk_entry(&protocol.id, "2024-03-05T08:00:00Z")).unwrap();
+        assert!(log.schedule_id.is_none());
+    }
+
     #[test]
     fn test_log_dose_payload_without_notes() {
         let json = r#"{
@@ -280,6 +932,8 @@ mod tests {
             site: "test".to_string(),
             amount_mg: 5.0,
             notes: Some("test notes".to_string()),
+            schedule_id: None,
+            unit: None,
         };
 
         let debug_str = format!("{:?}", payload);
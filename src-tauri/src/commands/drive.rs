@@ -1,15 +1,18 @@
 use anyhow::{Context, Result};
 use oauth2::{
-    basic::BasicClient, reqwest::async_http_client, AuthUrl, AuthorizationCode, ClientId,
+    basic::BasicClient, AuthUrl, AuthorizationCode, ClientId,
     ClientSecret, CsrfToken, PkceCodeChallenge, RedirectUrl, Scope, TokenResponse, TokenUrl,
 };
+use peptrack_core::Setting;
+use peptrack_literature::http;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tauri::State;
 use tokio::sync::Mutex;
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 
+use crate::error::CommandError;
 use crate::state::AppState;
 
 /// Google Drive OAuth configuration
@@ -20,6 +23,18 @@ pub struct DriveOAuthConfig {
     pub client_secret: String,
 }
 
+impl Setting for DriveOAuthConfig {
+    const KEY: &'static str = "drive.oauth_config";
+    const LEGACY_FILE: Option<&'static str> = Some("drive_oauth_config.json");
+
+    fn validate(&self) -> Result<(), String> {
+        if self.client_id.trim().is_empty() || self.client_secret.trim().is_empty() {
+            return Err("Google Drive needs a client ID and secret".to_string());
+        }
+        Ok(())
+    }
+}
+
 /// OAuth token storage
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -31,6 +46,11 @@ pub struct DriveTokens {
     pub expires_at: Option<String>,
 }
 
+impl Setting for DriveTokens {
+    const KEY: &'static str = "drive.tokens";
+    const LEGACY_FILE: Option<&'static str> = Some("drive_tokens.json");
+}
+
 /// Drive connection status
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -39,6 +59,33 @@ pub struct DriveStatus {
     pub email: Option<String>,
 }
 
+/// Drive quota and the space PepTrack's backups take up
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DriveStorageInfo {
+    /// Total quota; `None` for unlimited accounts
+    pub limit_bytes: Option<u64>,
+    /// Used across all Google services
+    pub usage_bytes: u64,
+    pub usage_in_trash_bytes: u64,
+    /// `None` for unlimited accounts
+    pub remaining_bytes: Option<u64>,
+    pub backup_count: usize,
+    /// Total size of the backups in the PepTrack folder
+    pub backup_bytes: u64,
+    /// Size of the newest backup, the best guess at the next one's size
+    pub latest_backup_bytes: Option<u64>,
+    /// Whether a backup the size of the newest one would fit
+    pub next_backup_fits: bool,
+}
+
+impl DriveStorageInfo {
+    /// Whether `size_bytes` more fits in the quota
+    pub fn fits(&self, size_bytes: u64) -> bool {
+        self.remaining_bytes.is_none_or(|remaining| size_bytes <= remaining)
+    }
+}
+
 /// OAuth authorization URL response
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -58,18 +105,20 @@ const GOOGLE_AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
 const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
 const REDIRECT_URL: &str = "http://localhost:8080/oauth/callback";
 const DRIVE_SCOPE: &str = "https://www.googleapis.com/auth/drive.file";
+/// Drive folder backups are uploaded to
+pub const BACKUP_FOLDER_NAME: &str = "PepTrack Backups";
 
 /// Starts the OAuth flow by generating an authorization URL
 #[tauri::command]
 pub async fn start_drive_oauth(
     config: DriveOAuthConfig,
     state: State<'_, OAuthState>,
-) -> Result<AuthUrlResponse, String> {
+) -> Result<AuthUrlResponse, CommandError> {
     info!("Starting Google Drive OAuth flow");
 
     let client = create_oauth_client(&config).map_err(|e| {
         warn!("Failed to create OAuth client: {:#}", e);
-        format!("OAuth setup failed: {}", e)
+        CommandError::with_context(e, "OAuth setup failed")
     })?;
 
     let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
@@ -100,14 +149,16 @@ pub async fn complete_drive_oauth(
     state_param: String,
     oauth_state: State<'_, OAuthState>,
     app_state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<DriveStatus, String> {
+) -> Result<DriveStatus, CommandError> {
     info!("Completing Google Drive OAuth flow");
 
     // Verify CSRF token
     let stored_state = oauth_state.csrf_token.lock().await.clone();
     if stored_state.as_deref() != Some(&state_param) {
         warn!("CSRF token mismatch");
-        return Err("Invalid OAuth state (CSRF mismatch)".to_string());
+        return Err(CommandError::invalid_input(
+            "Invalid OAuth state (CSRF mismatch)",
+        ));
     }
 
     let pkce_verifier = oauth_state
@@ -115,20 +166,21 @@ pub async fn complete_drive_oauth(
         .lock()
         .await
         .clone()
-        .ok_or_else(|| "PKCE verifier not found".to_string())?;
+        .ok_or_else(|| CommandError::not_found("PKCE verifier not found"))?;
 
-    let client = create_oauth_client(&config).map_err(|e| format!("OAuth setup failed: {}", e))?;
+    let client = create_oauth_client(&config)
+        .map_err(|e| CommandError::with_context(e, "OAuth setup failed"))?;
 
     // Exchange authorization code for tokens
     let pkce_verifier = oauth2::PkceCodeVerifier::new(pkce_verifier);
     let token_result = client
         .exchange_code(AuthorizationCode::new(code))
         .set_pkce_verifier(pkce_verifier)
-        .request_async(async_http_client)
+        .request_async(oauth_http_client)
         .await
         .map_err(|e| {
             warn!("Token exchange failed: {:#}", e);
-            format!("Failed to get access token: {}", e)
+            CommandError::with_context(e, "Failed to get access token")
         })?;
 
     let expires_in = token_result.expires_in().map(|d| d.as_secs());
@@ -146,11 +198,11 @@ pub async fn complete_drive_oauth(
     // Store tokens and config
     store_drive_tokens(&app_state, &tokens)
         .await
-        .map_err(|e| format!("Failed to store tokens: {}", e))?;
+        .map_err(|e| CommandError::with_context(e, "Failed to store tokens"))?;
 
-    store_drive_config(&config)
+    store_drive_config(&app_state, &config)
         .await
-        .map_err(|e| format!("Failed to store OAuth config: {}", e))?;
+        .map_err(|e| CommandError::with_context(e, "Failed to store OAuth config"))?;
 
     info!("Google Drive OAuth completed successfully");
 
@@ -167,7 +219,7 @@ pub async fn complete_drive_oauth(
 #[tauri::command]
 pub async fn check_drive_status(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<DriveStatus, String> {
+) -> Result<DriveStatus, CommandError> {
     // Try to load and refresh tokens if needed
     let tokens = load_and_refresh_tokens(&state).await;
 
@@ -187,12 +239,14 @@ pub async fn check_drive_status(
 
 /// Disconnects Google Drive by removing stored tokens
 #[tauri::command]
-pub async fn disconnect_drive(state: State<'_, std::sync::Arc<AppState>>) -> Result<(), String> {
+pub async fn disconnect_drive(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<(), CommandError> {
     info!("Disconnecting Google Drive");
 
     delete_drive_tokens(&state)
         .await
-        .map_err(|e| format!("Failed to disconnect: {}", e))?;
+        .map_err(|e| CommandError::with_context(e, "Failed to disconnect"))?;
 
     info!("Google Drive disconnected successfully");
     Ok(())
@@ -204,19 +258,19 @@ pub async fn upload_to_drive(
     filename: String,
     content: String,
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     info!("Uploading backup to Google Drive: {}", filename);
 
     let tokens = load_and_refresh_tokens(&state)
         .await
-        .map_err(|e| format!("Not connected to Google Drive: {}", e))?;
+        .map_err(|e| CommandError::with_context(e, "Not connected to Google Drive"))?;
 
-    let client = Client::new();
+    let client = http::client();
 
     // Create or get PepTrack folder
-    let folder_id = get_or_create_folder(&client, &tokens.access_token, "PepTrack Backups")
+    let folder_id = get_or_create_folder(&client, &tokens.access_token, BACKUP_FOLDER_NAME)
         .await
-        .map_err(|e| format!("Failed to create folder: {}", e))?;
+        .map_err(|e| CommandError::with_context(e, "Failed to create folder"))?;
 
     // Upload file
     let file_id = upload_file(
@@ -227,12 +281,29 @@ pub async fn upload_to_drive(
         &content,
     )
     .await
-    .map_err(|e| format!("Failed to upload file: {}", e))?;
+    .map_err(|e| CommandError::with_context(e, "Failed to upload file"))?;
 
     info!("Backup uploaded successfully: {}", file_id);
     Ok(file_id)
 }
 
+/// Drive quota and backup sizes, so the UI can warn before backups stop fitting
+#[tauri::command]
+pub async fn get_drive_storage_info(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<DriveStorageInfo, CommandError> {
+    let tokens = load_and_refresh_tokens(&state)
+        .await
+        .map_err(|e| CommandError::with_context(e, "Not connected to Google Drive"))?;
+
+    fetch_storage_info(&http::client(), &tokens.access_token)
+        .await
+        .map_err(|e| {
+            error!("Failed to read Google Drive storage: {:#}", e);
+            CommandError::with_context(e, "Failed to read Google Drive storage")
+        })
+}
+
 // Helper functions
 
 fn create_oauth_client(config: &DriveOAuthConfig) -> Result<BasicClient> {
@@ -245,53 +316,70 @@ fn create_oauth_client(config: &DriveOAuthConfig) -> Result<BasicClient> {
     .set_redirect_uri(RedirectUrl::new(REDIRECT_URL.to_string())?))
 }
 
-async fn store_drive_tokens(_state: &AppState, tokens: &DriveTokens) -> Result<()> {
-    // Store tokens as JSON in the app data directory
-    let data_dir = dirs::data_dir()
-        .context("Unable to determine data directory")?
-        .join("PepTrack");
-    std::fs::create_dir_all(&data_dir)?;
-
-    let tokens_file = data_dir.join("drive_tokens.json");
-    let json = serde_json::to_string(tokens)?;
-    std::fs::write(&tokens_file, json).context("Failed to store Drive tokens")?;
+/// Sends OAuth token requests with the shared client, so they use the
+/// configured proxy and CA certificates like every other Drive request
+///
+/// Redirects aren't followed, as in oauth2's own client.
+async fn oauth_http_client(request: oauth2::HttpRequest) -> Result<oauth2::HttpResponse, reqwest::Error> {
+    let client = http::client_builder(http::DEFAULT_USER_AGENT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+
+    let method = reqwest::Method::from_bytes(request.method.as_str().as_bytes()).unwrap_or(reqwest::Method::POST);
+    let mut builder = client.request(method, request.url.as_str()).body(request.body);
+    for (name, value) in &request.headers {
+        builder = builder.header(name.as_str(), value.as_bytes());
+    }
+    let response = builder.send().await?;
+
+    let status_code = oauth2::http::StatusCode::from_u16(response.status().as_u16())
+        .unwrap_or(oauth2::http::StatusCode::BAD_GATEWAY);
+    let mut headers = oauth2::http::HeaderMap::new();
+    for (name, value) in response.headers() {
+        if let (Ok(name), Ok(value)) = (
+            oauth2::http::HeaderName::from_bytes(name.as_str().as_bytes()),
+            oauth2::http::HeaderValue::from_bytes(value.as_bytes()),
+        ) {
+            headers.append(name, value);
+        }
+    }
+    let body = response.bytes().await?.to_vec();
 
-    Ok(())
+    Ok(oauth2::HttpResponse { status_code, headers, body })
 }
 
-async fn store_drive_config(config: &DriveOAuthConfig) -> Result<()> {
-    let data_dir = dirs::data_dir()
-        .context("Unable to determine data directory")?
-        .join("PepTrack");
-    std::fs::create_dir_all(&data_dir)?;
-
-    let config_file = data_dir.join("drive_oauth_config.json");
-    let json = serde_json::to_string(config)?;
-    std::fs::write(&config_file, json).context("Failed to store Drive OAuth config")?;
-
-    Ok(())
+async fn store_drive_tokens(state: &AppState, tokens: &DriveTokens) -> Result<()> {
+    let tokens = tokens.clone();
+    state
+        .db
+        .run(move |storage| storage.save_setting(&tokens))
+        .await
+        .context("Failed to store Drive tokens")
 }
 
-async fn load_drive_config() -> Result<DriveOAuthConfig> {
-    let data_dir = dirs::data_dir()
-        .context("Unable to determine data directory")?
-        .join("PepTrack");
-    let config_file = data_dir.join("drive_oauth_config.json");
-
-    let json = std::fs::read_to_string(&config_file).context("Drive OAuth config not found")?;
-    let config: DriveOAuthConfig = serde_json::from_str(&json)?;
-    Ok(config)
+async fn store_drive_config(state: &AppState, config: &DriveOAuthConfig) -> Result<()> {
+    let config = config.clone();
+    state
+        .db
+        .run(move |storage| storage.save_setting(&config))
+        .await
+        .context("Failed to store Drive OAuth config")
 }
 
-async fn load_drive_tokens(_state: &AppState) -> Result<DriveTokens> {
-    let data_dir = dirs::data_dir()
-        .context("Unable to determine data directory")?
-        .join("PepTrack");
-    let tokens_file = data_dir.join("drive_tokens.json");
+async fn load_drive_config(state: &AppState) -> Result<DriveOAuthConfig> {
+    state
+        .db
+        .run(|storage| storage.load_setting())
+        .await?
+        .context("Drive OAuth config not found")
+}
 
-    let json = std::fs::read_to_string(&tokens_file).context("Drive tokens not found")?;
-    let tokens: DriveTokens = serde_json::from_str(&json)?;
-    Ok(tokens)
+async fn load_drive_tokens(state: &AppState) -> Result<DriveTokens> {
+    state
+        .db
+        .run(|storage| storage.load_setting())
+        .await?
+        .context("Drive tokens not found")
 }
 
 // Public helper functions for use by scheduler
@@ -300,27 +388,26 @@ pub async fn load_drive_tokens_internal(state: &AppState) -> Result<DriveTokens>
     load_and_refresh_tokens(state).await
 }
 
-async fn delete_drive_tokens(_state: &AppState) -> Result<()> {
-    let data_dir = dirs::data_dir()
-        .context("Unable to determine data directory")?
-        .join("PepTrack");
-    let tokens_file = data_dir.join("drive_tokens.json");
-    let config_file = data_dir.join("drive_oauth_config.json");
+async fn delete_drive_tokens(state: &AppState) -> Result<()> {
+    state
+        .db
+        .run(|storage| {
+            storage
+                .delete_setting::<DriveTokens>()
+                .context("Failed to delete Drive tokens")?;
 
-    if tokens_file.exists() {
-        std::fs::remove_file(&tokens_file).context("Failed to delete Drive tokens")?;
-    }
+            // Also delete the OAuth config
+            storage
+                .delete_setting::<DriveOAuthConfig>()
+                .context("Failed to delete Drive OAuth config")?;
 
-    // Also delete the OAuth config
-    if config_file.exists() {
-        std::fs::remove_file(&config_file).context("Failed to delete Drive OAuth config")?;
-    }
-
-    Ok(())
+            Ok(())
+        })
+        .await
 }
 
 async fn get_user_email(access_token: &str) -> Result<String> {
-    let client = Client::new();
+    let client = http::client();
     let response = client
         .get("https://www.googleapis.com/oauth2/v2/userinfo")
         .bearer_auth(access_token)
@@ -366,7 +453,7 @@ async fn refresh_access_token(
 
     let token_result = client
         .exchange_refresh_token(&oauth2::RefreshToken::new(refresh_token.clone()))
-        .request_async(async_http_client)
+        .request_async(oauth_http_client)
         .await
         .context("Failed to refresh token")?;
 
@@ -392,7 +479,7 @@ async fn load_and_refresh_tokens(state: &AppState) -> Result<DriveTokens> {
 
         if tokens.refresh_token.is_some() {
             // Try to load OAuth config and refresh
-            match load_drive_config().await {
+            match load_drive_config(state).await {
                 Ok(config) => {
                     match refresh_access_token(&tokens, &config).await {
                         Ok(new_tokens) => {
@@ -434,12 +521,8 @@ async fn get_or_create_folder(
     get_or_create_folder_internal(client, access_token, folder_name).await
 }
 
-pub async fn get_or_create_folder_internal(
-    client: &Client,
-    access_token: &str,
-    folder_name: &str,
-) -> Result<String> {
-    // Search for existing folder
+/// The id of the folder named `folder_name`, if there is one
+async fn find_folder(client: &Client, access_token: &str, folder_name: &str) -> Result<Option<String>> {
     let search_url = format!(
         "https://www.googleapis.com/drive/v3/files?q=name='{}' and mimeType='application/vnd.google-apps.folder' and trashed=false",
         folder_name
@@ -453,13 +536,23 @@ pub async fn get_or_create_folder_internal(
         .json::<serde_json::Value>()
         .await?;
 
-    // Check if folder exists
-    if let Some(files) = response.get("files").and_then(|f| f.as_array()) {
-        if let Some(folder) = files.first() {
-            if let Some(id) = folder.get("id").and_then(|i| i.as_str()) {
-                return Ok(id.to_string());
-            }
-        }
+    Ok(response
+        .get("files")
+        .and_then(|f| f.as_array())
+        .and_then(|files| files.first())
+        .and_then(|folder| folder.get("id"))
+        .and_then(|i| i.as_str())
+        .map(|s| s.to_string()))
+}
+
+pub async fn get_or_create_folder_internal(
+    client: &Client,
+    access_token: &str,
+    folder_name: &str,
+) -> Result<String> {
+    // Search for existing folder
+    if let Some(id) = find_folder(client, access_token, folder_name).await? {
+        return Ok(id);
     }
 
     // Create folder if it doesn't exist
@@ -536,10 +629,133 @@ pub async fn upload_file_internal(
         .context("Failed to get file ID")
 }
 
+/// Drive reports byte counts as strings
+fn quota_bytes(quota: &serde_json::Value, field: &str) -> Option<u64> {
+    quota.get(field)?.as_str()?.parse().ok()
+}
+
+/// Sizes of the files in the `files` arrays of Drive file list responses
+fn file_sizes(pages: &[serde_json::Value]) -> Vec<u64> {
+    pages
+        .iter()
+        .filter_map(|page| page.get("files").and_then(|f| f.as_array()))
+        .flatten()
+        .filter_map(|file| quota_bytes(file, "size"))
+        .collect()
+}
+
+/// Combine the `about` response with the backup folder's file list pages
+///
+/// Files are listed newest first, so the first size is the latest backup's.
+fn storage_info(about: &serde_json::Value, backup_pages: &[serde_json::Value]) -> Result<DriveStorageInfo> {
+    let quota = about
+        .get("storageQuota")
+        .context("Drive did not report a storage quota")?;
+    let limit_bytes = quota_bytes(quota, "limit");
+    let usage_bytes = quota_bytes(quota, "usage").context("Drive did not report storage usage")?;
+    let sizes = file_sizes(backup_pages);
+
+    let mut info = DriveStorageInfo {
+        limit_bytes,
+        usage_bytes,
+        usage_in_trash_bytes: quota_bytes(quota, "usageInDriveTrash").unwrap_or(0),
+        remaining_bytes: limit_bytes.map(|limit| limit.saturating_sub(usage_bytes)),
+        backup_count: sizes.len(),
+        backup_bytes: sizes.iter().sum(),
+        latest_backup_bytes: sizes.first().copied(),
+        next_backup_fits: true,
+    };
+    info.next_backup_fits = info.fits(info.latest_backup_bytes.unwrap_or(0));
+    Ok(info)
+}
+
+/// Drive quota plus the sizes of the backups in the PepTrack folder
+pub(crate) async fn fetch_storage_info(client: &Client, access_token: &str) -> Result<DriveStorageInfo> {
+    let about = client
+        .get("https://www.googleapis.com/drive/v3/about?fields=storageQuota")
+        .bearer_auth(access_token)
+        .send()
+        .await?
+        .error_for_status()
+        .context("Failed to read Drive storage quota")?
+        .json::<serde_json::Value>()
+        .await?;
+
+    let mut pages = Vec::new();
+    if let Some(folder_id) = find_folder(client, access_token, BACKUP_FOLDER_NAME).await? {
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = client
+                .get("https://www.googleapis.com/drive/v3/files")
+                .bearer_auth(access_token)
+                .query(&[
+                    ("q", format!("'{}' in parents and trashed=false", folder_id)),
+                    ("fields", "nextPageToken,files(size)".to_string()),
+                    ("orderBy", "createdTime desc".to_string()),
+                    ("pageSize", "1000".to_string()),
+                ]);
+            if let Some(token) = &page_token {
+                request = request.query(&[("pageToken", token)]);
+            }
+            let page = request
+                .send()
+                .await?
+                .error_for_status()
+                .context("Failed to list Drive backups")?
+                .json::<serde_json::Value>()
+                .await?;
+            page_token = page
+                .get("nextPageToken")
+                .and_then(|t| t.as_str())
+                .map(|t| t.to_string());
+            pages.push(page);
+            if page_token.is_none() {
+                break;
+            }
+        }
+    }
+
+    storage_info(&about, &pages)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn storage_info_combines_quota_and_backup_sizes() {
+        let about = serde_json::json!({
+            "storageQuota": {
+                "limit": "16106127360",
+                "usage": "16106000000",
+                "usageInDrive": "15000000000",
+                "usageInDriveTrash": "2000"
+            }
+        });
+        let pages = vec![
+            serde_json::json!({ "nextPageToken": "next", "files": [{ "size": "150000" }, { "size": "140000" }] }),
+            serde_json::json!({ "files": [{ "size": "130000" }, { "id": "no-size" }] }),
+        ];
+
+        let info = storage_info(&about, &pages).unwrap();
+        assert_eq!(info.remaining_bytes, Some(127_360));
+        assert_eq!(info.usage_in_trash_bytes, 2_000);
+        assert_eq!(info.backup_count, 3);
+        assert_eq!(info.backup_bytes, 420_000);
+        assert_eq!(info.latest_backup_bytes, Some(150_000));
+        assert!(!info.next_backup_fits);
+        assert!(info.fits(127_360));
+
+        // Unlimited accounts report no limit
+        let unlimited = serde_json::json!({ "storageQuota": { "usage": "5" } });
+        let info = storage_info(&unlimited, &[]).unwrap();
+        assert_eq!(info.remaining_bytes, None);
+        assert!(info.next_backup_fits);
+        assert!(info.fits(u64::MAX));
+
+        assert!(storage_info(&serde_json::json!({}), &[]).is_err());
+    }
+
     #[test]
     fn test_drive_oauth_config_serialization() {
         let config = DriveOAuthConfig {
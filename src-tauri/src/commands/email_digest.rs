@@ -0,0 +1,410 @@
+//! Email digests
+//!
+//! A background job emails a daily or weekly digest of new alerts, dose
+//! adherence and inventory forecasts over SMTP. Server settings are saved as
+//! [`EmailDigestSettings`]; the SMTP password is kept separately with
+//! [`peptrack_core::store_credential`], in the Keychain on macOS.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use peptrack_core::{
+    delete_credential, load_credential, store_credential, Setting, StorageManager,
+};
+use peptrack_reports::{Digest, DigestForecast};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime, Time, Weekday};
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::commands::forecast::{load_forecast, DEFAULT_HISTORY_DAYS, DEFAULT_LEAD_TIME_DAYS};
+use crate::commands::reports::build_summary;
+use crate::commands::settings::{load_setting, load_setting_or_default, save_setting};
+use crate::error::CommandError;
+use crate::state::{app_data_dir, AppState};
+
+/// Credential account the SMTP password is stored under
+pub(crate) const SMTP_PASSWORD_ACCOUNT: &str = "smtp-password";
+/// How often the background job checks whether a digest is due
+const CHECK_INTERVAL_SECS: u64 = 15 * 60;
+const SMTP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Keeps the background job and a manual send from emailing twice
+static SEND_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DigestFrequency {
+    Daily,
+    /// Sent on Mondays
+    Weekly,
+}
+
+impl DigestFrequency {
+    fn period(self) -> Duration {
+        match self {
+            DigestFrequency::Daily => Duration::days(1),
+            DigestFrequency::Weekly => Duration::weeks(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SmtpSecurity {
+    /// TLS from the start, usually port 465
+    Tls,
+    /// Upgrade a plain connection with STARTTLS, usually port 587
+    StartTls,
+    /// Unencrypted; only for servers on the local machine or network
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EmailDigestSettings {
+    pub enabled: bool,
+    pub frequency: DigestFrequency,
+    /// Hour of day (0-23, UTC) the digest is sent
+    pub hour: u8,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub security: SmtpSecurity,
+    /// SMTP login; no authentication when empty
+    pub username: String,
+    pub from: String,
+    pub to: String,
+    pub last_sent: Option<String>,
+    pub next_send: Option<String>,
+}
+
+impl Default for EmailDigestSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            frequency: DigestFrequency::Weekly,
+            hour: 8,
+            smtp_host: String::new(),
+            smtp_port: 587,
+            security: SmtpSecurity::StartTls,
+            username: String::new(),
+            from: String::new(),
+            to: String::new(),
+            last_sent: None,
+            next_send: None,
+        }
+    }
+}
+
+impl EmailDigestSettings {
+    /// Check there's enough to send a digest with
+    fn check_sendable(&self) -> Result<(), String> {
+        if self.hour > 23 {
+            return Err("Hour must be between 0 and 23".to_string());
+        }
+        if self.smtp_host.trim().is_empty() {
+            return Err("SMTP server is required".to_string());
+        }
+        if self.smtp_port == 0 {
+            return Err("SMTP port is required".to_string());
+        }
+        self.from
+            .trim()
+            .parse::<Mailbox>()
+            .map_err(|_| "Sender address is not a valid email address".to_string())?;
+        self.to
+            .trim()
+            .parse::<Mailbox>()
+            .map_err(|_| "Recipient address is not a valid email address".to_string())?;
+        Ok(())
+    }
+}
+
+impl Setting for EmailDigestSettings {
+    const KEY: &'static str = "email_digest.settings";
+    const LEGACY_FILE: Option<&'static str> = Some("email_digest.json");
+
+    /// A disabled digest can be saved half filled in
+    fn validate(&self) -> Result<(), String> {
+        if self.enabled {
+            self.check_sendable()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Digest settings, and whether an SMTP password is stored
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailDigestConfig {
+    pub settings: EmailDigestSettings,
+    pub password_saved: bool,
+}
+
+/// The first send time after `now`: the next `hour` o'clock, on a Monday
+/// for weekly digests
+fn next_send_after(now: OffsetDateTime, frequency: DigestFrequency, hour: u8) -> OffsetDateTime {
+    let at_hour = Time::from_hms(hour.min(23), 0, 0).unwrap_or(Time::MIDNIGHT);
+    let mut next = now.replace_time(at_hour);
+    while next <= now || (frequency == DigestFrequency::Weekly && next.weekday() != Weekday::Monday) {
+        next += Duration::days(1);
+    }
+    next
+}
+
+fn format_rfc3339(value: OffsetDateTime) -> String {
+    value.format(&Rfc3339).unwrap_or_else(|_| value.to_string())
+}
+
+fn parse_rfc3339(value: Option<&str>) -> Option<OffsetDateTime> {
+    value.and_then(|value| OffsetDateTime::parse(value, &Rfc3339).ok())
+}
+
+/// Build the digest covering the period that ends `now`
+fn build_digest(storage: &StorageManager, settings: &EmailDigestSettings, now: OffsetDateTime) -> Result<Digest> {
+    let period_start = now - settings.frequency.period();
+    let alerts_since = parse_rfc3339(settings.last_sent.as_deref()).unwrap_or(period_start);
+
+    let summary = build_summary(storage, period_start.date(), now.date())?;
+    let alerts: Vec<_> = storage
+        .list_alerts(false)
+        .context("Failed to load alerts")?
+        .into_iter()
+        .filter(|alert| alert.created_at > alerts_since)
+        .collect();
+    let forecasts: Vec<DigestForecast> = load_forecast(storage, DEFAULT_LEAD_TIME_DAYS, DEFAULT_HISTORY_DAYS)?
+        .protocols
+        .into_iter()
+        .map(|forecast| DigestForecast {
+            protocol_name: forecast.protocol_name,
+            days_remaining: forecast.days_remaining,
+            reorder_by: forecast.reorder_by,
+        })
+        .collect();
+
+    Ok(Digest::build(&summary, &alerts, &forecasts))
+}
+
+async fn send_email(settings: &EmailDigestSettings, subject: &str, body: String) -> Result<()> {
+    let from: Mailbox = settings.from.trim().parse().context("Invalid sender address")?;
+    let to: Mailbox = settings.to.trim().parse().context("Invalid recipient address")?;
+    let message = Message::builder()
+        .from(from)
+        .to(to)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)
+        .context("Failed to build email")?;
+
+    let host = settings.smtp_host.trim();
+    let builder = match settings.security {
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+        SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+        SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+    };
+    let mut builder = builder.port(settings.smtp_port).timeout(Some(SMTP_TIMEOUT));
+    if !settings.username.trim().is_empty() {
+        let password = load_credential(&app_data_dir()?, SMTP_PASSWORD_ACCOUNT)?
+            .ok_or_else(|| anyhow!("No SMTP password saved for {}", settings.username.trim()))?;
+        builder = builder.credentials(Credentials::new(settings.username.trim().to_string(), password));
+    }
+
+    builder
+        .build()
+        .send(message)
+        .await
+        .with_context(|| format!("Failed to send email through {}", host))?;
+    Ok(())
+}
+
+/// Send the digest now and schedule the next one
+async fn send_digest(state: &AppState) -> Result<EmailDigestSettings> {
+    let _guard = SEND_LOCK.lock().await;
+    let mut settings: EmailDigestSettings =
+        state.db.run(|storage| storage.load_setting_or_default()).await?;
+    let now = OffsetDateTime::now_utc();
+
+    let period = settings.clone();
+    let digest = state.db.run(move |storage| build_digest(storage, &period, now)).await?;
+    send_email(&settings, &digest.subject(), digest.to_text()).await?;
+
+    settings.last_sent = Some(format_rfc3339(now));
+    settings.next_send = settings
+        .enabled
+        .then(|| format_rfc3339(next_send_after(now, settings.frequency, settings.hour)));
+    let saved = settings.clone();
+    state.db.run(move |storage| storage.save_setting(&saved)).await?;
+
+    info!("Email digest sent to {}", settings.to.trim());
+    Ok(settings)
+}
+
+/// Send digests when they are due, checking every 15 minutes
+pub async fn run_email_digest_loop(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(CHECK_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        if state.key_provider.is_locked() {
+            continue;
+        }
+
+        let loaded = state.db.run(|storage| storage.load_setting_or_default()).await;
+        let settings: EmailDigestSettings = match loaded {
+            Ok(settings) => settings,
+            Err(e) => {
+                warn!("Failed to load email digest settings: {:#}", e);
+                continue;
+            }
+        };
+        let due = settings.enabled
+            && parse_rfc3339(settings.next_send.as_deref())
+                .is_some_and(|next_send| OffsetDateTime::now_utc() >= next_send);
+        if !due {
+            continue;
+        }
+
+        if let Err(e) = send_digest(&state).await {
+            // Try again at the next check
+            warn!("Email digest failed: {:#}", e);
+        }
+    }
+}
+
+// ========== Email Digest Commands ==========
+
+/// Gets the email digest settings
+#[tauri::command]
+pub async fn get_email_digest_settings(
+    state: State<'_, Arc<AppState>>,
+) -> Result<EmailDigestConfig, CommandError> {
+    load_config(&state).await
+}
+
+async fn load_config(state: &AppState) -> Result<EmailDigestConfig, CommandError> {
+    let settings = load_setting(state).await?;
+    let password_saved = app_data_dir()
+        .and_then(|dir| load_credential(&dir, SMTP_PASSWORD_ACCOUNT))
+        .map(|password| password.is_some())
+        .unwrap_or(false);
+    Ok(EmailDigestConfig {
+        settings,
+        password_saved,
+    })
+}
+
+/// Updates the email digest settings
+///
+/// `password` replaces the stored SMTP password when given; an empty string
+/// removes it. Leave it out to keep the current one.
+#[tauri::command]
+pub async fn update_email_digest_settings(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    settings: EmailDigestSettings,
+    password: Option<String>,
+) -> Result<EmailDigestConfig, CommandError> {
+    // Checked before the password is touched, though saving checks again
+    settings.validate().map_err(CommandError::invalid_input)?;
+
+    if let Some(password) = password {
+        let dir = app_data_dir()
+            .map_err(|e| CommandError::with_context(e, "Failed to save SMTP password"))?;
+        let result = if password.is_empty() {
+            delete_credential(&dir, SMTP_PASSWORD_ACCOUNT)
+        } else {
+            store_credential(&dir, SMTP_PASSWORD_ACCOUNT, &password)
+        };
+        result.map_err(|e| {
+            error!("Failed to save SMTP password: {:#}", e);
+            CommandError::with_context(e, "Failed to save SMTP password")
+        })?;
+    }
+
+    let guard = SEND_LOCK.lock().await;
+    let current: EmailDigestSettings = load_setting_or_default(&state).await;
+    let mut updated = settings;
+    updated.last_sent = current.last_sent;
+    updated.next_send = updated.enabled.then(|| {
+        format_rfc3339(next_send_after(OffsetDateTime::now_utc(), updated.frequency, updated.hour))
+    });
+
+    save_setting(&app, &state, &updated).await?;
+    drop(guard);
+
+    info!(
+        "Email digest settings updated: enabled={}, frequency={:?}",
+        updated.enabled, updated.frequency
+    );
+    load_config(&state).await
+}
+
+/// Sends the digest now instead of waiting for the schedule
+#[tauri::command]
+pub async fn send_email_digest_now(
+    state: State<'_, Arc<AppState>>,
+) -> Result<EmailDigestSettings, CommandError> {
+    let settings: EmailDigestSettings = load_setting(&state).await?;
+    settings.check_sendable().map_err(CommandError::invalid_input)?;
+
+    send_digest(&state).await.map_err(|e| {
+        error!("Failed to send email digest: {:#}", e);
+        CommandError::with_context(e, "Failed to send email digest")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn daily_digest_is_sent_at_the_next_hour() {
+        // A Wednesday
+        let now = datetime!(2025-03-05 09:30 UTC);
+        assert_eq!(
+            next_send_after(now, DigestFrequency::Daily, 18),
+            datetime!(2025-03-05 18:00 UTC)
+        );
+        assert_eq!(
+            next_send_after(now, DigestFrequency::Daily, 8),
+            datetime!(2025-03-06 08:00 UTC)
+        );
+    }
+
+    #[test]
+    fn weekly_digest_is_sent_on_monday() {
+        let wednesday = datetime!(2025-03-05 09:30 UTC);
+        assert_eq!(
+            next_send_after(wednesday, DigestFrequency::Weekly, 8),
+            datetime!(2025-03-10 08:00 UTC)
+        );
+
+        let monday_after_send = datetime!(2025-03-10 08:00 UTC);
+        assert_eq!(
+            next_send_after(monday_after_send, DigestFrequency::Weekly, 8),
+            datetime!(2025-03-17 08:00 UTC)
+        );
+    }
+
+    #[test]
+    fn validate_checks_addresses() {
+        let mut settings = EmailDigestSettings {
+            enabled: true,
+            smtp_host: "smtp.example.com".into(),
+            from: "PepTrack <me@example.com>".into(),
+            to: "me@example.com".into(),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_ok());
+
+        settings.to = "not an address".into();
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,704 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use peptrack_core::models::{Alert, AlertSeverity, AlertType};
+use peptrack_core::{
+    expiry_calendar, DoseLog, ExpiryDay, InventoryItem, PeptideProtocol, StorageManager, VialStatus,
+};
+use serde::Serialize;
+use tauri::State;
+use time::{Duration, OffsetDateTime};
+use tracing::{error, info};
+
+use crate::commands::dates::parse_optional_date;
+use crate::commands::schedules::{enabled_schedule_usage, ScheduledUsage};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Days of dose history used when a protocol has no enabled schedule
+pub(crate) const DEFAULT_HISTORY_DAYS: i64 = 60;
+/// Days before depletion that a reorder should be placed
+pub(crate) const DEFAULT_LEAD_TIME_DAYS: i64 = 10;
+/// A day's doses count half as much for every this many days in the past
+const USAGE_HALF_LIFE_DAYS: f64 = 14.0;
+/// Shortest history window, so a single recent dose isn't read as a daily habit
+const MIN_HISTORY_DAYS: i64 = 7;
+/// How far ahead stock is simulated
+const FORECAST_HORIZON_DAYS: i64 = 365;
+/// Vials expiring within this many days get an ExpiringSoon alert
+const EXPIRY_WARNING_DAYS: i64 = 14;
+/// Same for reconstituted vials, which only keep for a few weeks
+const RECONSTITUTED_WARNING_DAYS: i64 = 3;
+/// Days shown by the expiry calendar when no end date is given
+const DEFAULT_CALENDAR_DAYS: i64 = 90;
+/// Leftover amounts below this are treated as used up
+const EMPTY_EPSILON_MG: f32 = 0.001;
+
+/// Where a protocol's projected usage comes from
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageSource {
+    /// Enabled dose schedules
+    Schedule,
+    /// Recency-weighted dose history
+    History,
+    /// No schedule and no recent doses
+    None,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VialForecast {
+    pub inventory_id: String,
+    pub vial_number: Option<String>,
+    pub remaining_mg: f32,
+    /// Whichever comes first of the dry-powder and reconstituted expiry
+    pub expiry_date: Option<String>,
+    pub expires_in_days: Option<i64>,
+    /// The expiry is the reconstituted fridge life
+    pub reconstituted: bool,
+    /// Date the vial is expected to be used up, if within the forecast horizon
+    pub depletion_date: Option<String>,
+    pub days_remaining: Option<f32>,
+    /// Amount expected to be left in the vial when it expires
+    pub wasted_mg: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolForecast {
+    pub protocol_id: String,
+    pub protocol_name: String,
+    pub peptide_name: String,
+    pub remaining_mg: f32,
+    /// Recency-weighted average of logged doses
+    pub historical_daily_usage_mg: f32,
+    /// Average daily amount across enabled schedules
+    pub scheduled_daily_usage_mg: Option<f32>,
+    pub usage_source: UsageSource,
+    /// Days until no usable stock remains
+    pub days_remaining: Option<f32>,
+    pub depletion_date: Option<String>,
+    /// Last day to order so new stock arrives before running out
+    pub reorder_by: Option<String>,
+    /// Vials in the order they are expected to be used
+    pub vials: Vec<VialForecast>,
+}
+
+impl ProtocolForecast {
+    /// Average daily usage the forecast is based on
+    pub fn daily_usage_mg(&self) -> f32 {
+        match self.usage_source {
+            UsageSource::Schedule => self.scheduled_daily_usage_mg.unwrap_or(0.0),
+            UsageSource::History => self.historical_daily_usage_mg,
+            UsageSource::None => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryForecast {
+    pub generated_at: String,
+    pub lead_time_days: i64,
+    pub protocols: Vec<ProtocolForecast>,
+}
+
+/// Everything the forecast is computed from, loaded up front so the
+/// simulation itself stays pure
+struct ForecastInputs<'a> {
+    protocols: &'a [PeptideProtocol],
+    inventory: &'a [InventoryItem],
+    doses: &'a [DoseLog],
+    schedules: &'a [ScheduledUsage],
+}
+
+fn date_string(date: OffsetDateTime) -> String {
+    date.date().to_string()
+}
+
+/// Stock still usable in a vial, or None if it is used up, expired or unmeasured
+fn usable_mg(item: &InventoryItem, now: OffsetDateTime) -> Option<f32> {
+    if matches!(item.vial_status, VialStatus::Empty | VialStatus::Expired) {
+        return None;
+    }
+    if item.effective_expiry().is_some_and(|expiry| expiry <= now) {
+        return None;
+    }
+
+    let mg = match item.vial_status {
+        VialStatus::Sealed => item.quantity_remaining_mg.or(item.quantity_mg),
+        _ => item.quantity_remaining_mg,
+    }?;
+    (mg > 0.0).then_some(mg)
+}
+
+/// Recency-weighted average daily usage over the last `history_days`
+///
+/// Each day's total is weighted by how recent it is, so a dose change last
+/// week moves the average more than one from two months ago. The window starts
+/// at the first logged dose so a new protocol isn't diluted by empty days.
+fn weighted_daily_usage(doses: &[&DoseLog], history_days: i64, now: OffsetDateTime) -> f32 {
+    let history_days = history_days.max(MIN_HISTORY_DAYS);
+    let Some(first) = doses.iter().map(|dose| dose.logged_at).filter(|at| *at <= now).min() else {
+        return 0.0;
+    };
+    let window = ((now - first).whole_days() + 1).clamp(MIN_HISTORY_DAYS, history_days);
+
+    let mut totals = vec![0.0f64; window as usize];
+    for dose in doses {
+        let age = (now - dose.logged_at).whole_days();
+        if (0..window).contains(&age) {
+            totals[age as usize] += dose.amount_mg as f64;
+        }
+    }
+
+    let (weighted, weights) = totals
+        .iter()
+        .enumerate()
+        .fold((0.0, 0.0), |(sum, weight_sum), (age, total)| {
+            let weight = 0.5f64.powf(age as f64 / USAGE_HALF_LIFE_DAYS);
+            (sum + weight * total, weight_sum + weight)
+        });
+
+    (weighted / weights) as f32
+}
+
+/// Total scheduled amount on the weekday of `date`
+fn scheduled_mg_on(schedules: &[&ScheduledUsage], date: OffsetDateTime) -> f32 {
+    let weekday = date.weekday().number_days_from_sunday();
+    schedules
+        .iter()
+        .filter(|schedule| schedule.days_of_week.contains(&weekday))
+        .map(|schedule| schedule.amount_mg)
+        .sum()
+}
+
+/// Simulate day-by-day usage of a protocol's vials
+///
+/// Opened vials are used first, then whichever expires soonest. Scheduled
+/// protocols consume each day's scheduled amount (less anything already logged
+/// today); others consume their historical average from tomorrow onward.
+fn forecast_protocol(
+    protocol: &PeptideProtocol,
+    mut vials: Vec<(&InventoryItem, f32)>,
+    doses: &[&DoseLog],
+    schedules: &[&ScheduledUsage],
+    lead_time_days: i64,
+    history_days: i64,
+    now: OffsetDateTime,
+) -> ProtocolForecast {
+    vials.sort_by_key(|(item, _)| {
+        (
+            !matches!(item.vial_status, VialStatus::Opened),
+            item.effective_expiry().is_none(),
+            item.effective_expiry(),
+            item.purchase_date,
+        )
+    });
+
+    let historical = weighted_daily_usage(doses, history_days, now);
+    let scheduled = (!schedules.is_empty()).then(|| {
+        schedules
+            .iter()
+            .map(|schedule| schedule.amount_mg * schedule.days_of_week.len() as f32 / 7.0)
+            .sum::<f32>()
+    });
+    let usage_source = if scheduled.is_some_and(|mg| mg > 0.0) {
+        UsageSource::Schedule
+    } else if historical > 0.0 {
+        UsageSource::History
+    } else {
+        UsageSource::None
+    };
+
+    let logged_today: f32 = doses
+        .iter()
+        .filter(|dose| dose.logged_at.date() == now.date())
+        .map(|dose| dose.amount_mg)
+        .sum();
+
+    let mut left: Vec<f32> = vials.iter().map(|(_, mg)| *mg).collect();
+    let mut vial_forecasts: Vec<VialForecast> = vials
+        .iter()
+        .map(|(item, mg)| VialForecast {
+            inventory_id: item.id.clone(),
+            vial_number: item.vial_number.clone(),
+            remaining_mg: *mg,
+            expiry_date: item.effective_expiry().map(date_string),
+            expires_in_days: item.effective_expiry().map(|expiry| (expiry - now).whole_days()),
+            reconstituted: item.expires_as_reconstituted(),
+            depletion_date: None,
+            days_remaining: None,
+            wasted_mg: 0.0,
+        })
+        .collect();
+
+    let mut current = 0;
+    let mut out_of_stock: Option<(OffsetDateTime, f32)> = None;
+
+    for offset in 0..=FORECAST_HORIZON_DAYS {
+        let date = now + Duration::days(offset);
+
+        // Vials that expire before today's doses are lost
+        while current < vials.len() && vials[current].0.effective_expiry().is_some_and(|expiry| expiry <= date) {
+            vial_forecasts[current].wasted_mg = left[current];
+            current += 1;
+            if current == vials.len() {
+                let expiry = vials[current - 1].0.effective_expiry().unwrap_or(date);
+                out_of_stock = Some((expiry, (expiry - now).as_seconds_f32() / 86_400.0));
+            }
+        }
+        if current >= vials.len() {
+            break;
+        }
+
+        let day_need = match usage_source {
+            UsageSource::Schedule if offset == 0 => (scheduled_mg_on(schedules, date) - logged_today).max(0.0),
+            UsageSource::Schedule => scheduled_mg_on(schedules, date),
+            UsageSource::History if offset > 0 => historical,
+            _ => 0.0,
+        };
+
+        let mut need = day_need;
+        while need > 0.0 && current < vials.len() {
+            let take = left[current].min(need);
+            left[current] -= take;
+            need -= take;
+
+            if left[current] <= EMPTY_EPSILON_MG {
+                let fraction = (day_need - need) / day_need;
+                let days = (offset as f32 - 1.0 + fraction).max(0.0);
+                vial_forecasts[current].depletion_date = Some(date_string(date));
+                vial_forecasts[current].days_remaining = Some(days);
+                current += 1;
+                if current == vials.len() {
+                    out_of_stock = Some((date, days));
+                }
+            }
+        }
+    }
+
+    // A protocol in use with nothing on hand is already out of stock
+    if vials.is_empty() && usage_source != UsageSource::None {
+        out_of_stock = Some((now, 0.0));
+    }
+    let out_of_stock = out_of_stock.filter(|_| usage_source != UsageSource::None);
+
+    ProtocolForecast {
+        protocol_id: protocol.id.clone(),
+        protocol_name: protocol.name.clone(),
+        peptide_name: protocol.peptide_name.clone(),
+        remaining_mg: vials.iter().map(|(_, mg)| mg).sum(),
+        historical_daily_usage_mg: historical,
+        scheduled_daily_usage_mg: scheduled,
+        usage_source,
+        days_remaining: out_of_stock.map(|(_, days)| days),
+        depletion_date: out_of_stock.map(|(date, _)| date_string(date)),
+        reorder_by: out_of_stock.map(|(date, _)| date_string(date - Duration::days(lead_time_days))),
+        vials: vial_forecasts,
+    }
+}
+
+fn build_forecast(
+    inputs: &ForecastInputs<'_>,
+    lead_time_days: i64,
+    history_days: i64,
+    now: OffsetDateTime,
+) -> InventoryForecast {
+    let mut vials_by_protocol: HashMap<&str, Vec<(&InventoryItem, f32)>> = HashMap::new();
+    for item in inputs.inventory {
+        if let Some(mg) = usable_mg(item, now) {
+            vials_by_protocol.entry(item.protocol_id.as_str()).or_default().push((item, mg));
+        }
+    }
+
+    let mut doses_by_protocol: HashMap<&str, Vec<&DoseLog>> = HashMap::new();
+    for dose in inputs.doses {
+        doses_by_protocol.entry(dose.protocol_id.as_str()).or_default().push(dose);
+    }
+
+    let mut schedules_by_protocol: HashMap<&str, Vec<&ScheduledUsage>> = HashMap::new();
+    for schedule in inputs.schedules {
+        schedules_by_protocol.entry(schedule.protocol_id.as_str()).or_default().push(schedule);
+    }
+
+    let mut protocols: Vec<ProtocolForecast> = inputs
+        .protocols
+        .iter()
+        .map(|protocol| {
+            let id = protocol.id.as_str();
+            forecast_protocol(
+                protocol,
+                vials_by_protocol.remove(id).unwrap_or_default(),
+                doses_by_protocol.get(id).map(Vec::as_slice).unwrap_or_default(),
+                schedules_by_protocol.get(id).map(Vec::as_slice).unwrap_or_default(),
+                lead_time_days,
+                history_days,
+                now,
+            )
+        })
+        .filter(|forecast| !forecast.vials.is_empty() || forecast.usage_source != UsageSource::None)
+        .collect();
+
+    // Soonest to run out first, unused protocols last
+    protocols.sort_by(|a, b| {
+        let a_days = a.days_remaining.unwrap_or(f32::MAX);
+        let b_days = b.days_remaining.unwrap_or(f32::MAX);
+        a_days.total_cmp(&b_days).then_with(|| a.protocol_name.cmp(&b.protocol_name))
+    });
+
+    InventoryForecast {
+        generated_at: now.to_string(),
+        lead_time_days,
+        protocols,
+    }
+}
+
+/// Build the forecast from current inventory, dose history and schedules
+pub(crate) fn load_forecast(
+    storage: &StorageManager,
+    lead_time_days: i64,
+    history_days: i64,
+) -> Result<InventoryForecast> {
+    let protocols = storage.list_protocols().context("Failed to list protocols")?;
+    let inventory = storage.list_inventory().context("Failed to list inventory")?;
+    let doses = storage.list_dose_logs().context("Failed to list dose logs")?;
+    let schedules = enabled_schedule_usage(storage).context("Failed to list dose schedules")?;
+
+    let inputs = ForecastInputs {
+        protocols: &protocols,
+        inventory: &inventory,
+        doses: &doses,
+        schedules: &schedules,
+    };
+
+    Ok(build_forecast(&inputs, lead_time_days, history_days, OffsetDateTime::now_utc()))
+}
+
+/// Alerts suggested by a forecast, before deduplication against stored alerts
+fn forecast_alerts(forecast: &InventoryForecast) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+
+    for protocol in &forecast.protocols {
+        if let Some(days) = protocol.days_remaining.filter(|days| *days <= forecast.lead_time_days as f32) {
+            let severity = if days <= 3.0 {
+                AlertSeverity::Critical
+            } else if days <= 7.0 {
+                AlertSeverity::Warning
+            } else {
+                AlertSeverity::Info
+            };
+
+            let title = format!("Low Stock: {} ({})", protocol.protocol_name, protocol.peptide_name);
+            let message = format!(
+                "Estimated {:.1} days remaining ({:.1}mg left, using ~{:.2}mg/day). Reorder by {}.",
+                days,
+                protocol.remaining_mg,
+                protocol.daily_usage_mg(),
+                protocol.reorder_by.as_deref().unwrap_or("today")
+            );
+
+            let mut alert = Alert::new(AlertType::LowStock, severity, &title, &message);
+            alert.related_id = Some(protocol.protocol_id.clone());
+            alert.related_type = Some("protocol".to_string());
+            alerts.push(alert);
+        }
+
+        for vial in &protocol.vials {
+            let warning_days = if vial.reconstituted { RECONSTITUTED_WARNING_DAYS } else { EXPIRY_WARNING_DAYS };
+            let expiring_soon = vial.expires_in_days.is_some_and(|days| days <= warning_days);
+            if !expiring_soon && vial.wasted_mg <= EMPTY_EPSILON_MG {
+                continue;
+            }
+
+            let severity = if vial.expires_in_days.is_some_and(|days| days <= 3) {
+                AlertSeverity::Warning
+            } else {
+                AlertSeverity::Info
+            };
+
+            let vial_label = if vial.reconstituted { "reconstituted vial" } else { "vial" };
+            let label = match vial.vial_number {
+                Some(ref number) => format!("{} {} {}", protocol.protocol_name, vial_label, number),
+                None => format!("{} {}", protocol.protocol_name, vial_label),
+            };
+            let title = format!("Expiring Soon: {}", label);
+            let mut message = format!(
+                "Expires on {} with {:.1}mg left.",
+                vial.expiry_date.as_deref().unwrap_or("an unknown date"),
+                vial.remaining_mg
+            );
+            if vial.wasted_mg > EMPTY_EPSILON_MG {
+                message.push_str(&format!(
+                    " At the current rate about {:.1}mg will go unused.",
+                    vial.wasted_mg
+                ));
+            }
+
+            let mut alert = Alert::new(AlertType::ExpiringSoon, severity, &title, &message);
+            alert.related_id = Some(vial.inventory_id.clone());
+            alert.related_type = Some("inventory".to_string());
+            alerts.push(alert);
+        }
+    }
+
+    alerts
+}
+
+/// Create LowStock and ExpiringSoon alerts from the current forecast
+///
+/// Alerts are skipped when an undismissed alert of the same type already
+/// exists for the protocol or vial. Returns only newly created alerts.
+pub(crate) async fn create_forecast_alerts(state: &AppState) -> Result<Vec<Alert>> {
+    let raised = state
+        .db
+        .run(|storage| {
+            let forecast = load_forecast(storage, DEFAULT_LEAD_TIME_DAYS, DEFAULT_HISTORY_DAYS)?;
+            let existing = storage.list_alerts(false).context("Failed to list alerts")?;
+
+            let mut raised = Vec::new();
+            for alert in forecast_alerts(&forecast) {
+                let duplicate = existing.iter().any(|a| {
+                    a.alert_type == alert.alert_type
+                        && a.related_id == alert.related_id
+                        && !a.is_dismissed
+                });
+                if duplicate {
+                    continue;
+                }
+
+                let delivery = storage.raise_alert(&alert).context("Failed to create alert")?;
+                if let Some(delivery) = delivery {
+                    raised.push((alert, delivery));
+                }
+            }
+            Ok(raised)
+        })
+        .await?;
+
+    let created: Vec<Alert> = raised
+        .into_iter()
+        .map(|(alert, delivery)| {
+            state.notifier.alert(&alert, delivery);
+            alert
+        })
+        .collect();
+
+    if !created.is_empty() {
+        info!("Created {} inventory forecast alerts", created.len());
+    }
+    Ok(created)
+}
+
+// ========== Inventory Forecast Commands ==========
+
+/// Forecast when each protocol's stock runs out and when to reorder
+///
+/// `lead_time_days` is how long a new order takes to arrive (default 10);
+/// `history_days` limits the dose history used for unscheduled protocols
+/// (default 60).
+#[tauri::command]
+pub async fn get_inventory_forecast(
+    state: State<'_, std::sync::Arc<AppState>>,
+    lead_time_days: Option<i64>,
+    history_days: Option<i64>,
+) -> Result<InventoryForecast, CommandError> {
+    let lead_time_days = lead_time_days.unwrap_or(DEFAULT_LEAD_TIME_DAYS);
+    if lead_time_days < 0 {
+        return Err(CommandError::invalid_input("Lead time cannot be negative"));
+    }
+    let history_days = history_days.unwrap_or(DEFAULT_HISTORY_DAYS);
+
+    info!("Building inventory forecast (lead time: {} days)", lead_time_days);
+
+    state
+        .db
+        .run(move |storage| load_forecast(storage, lead_time_days, history_days))
+        .await
+        .map_err(|e| {
+            error!("Failed to build inventory forecast: {:#}", e);
+            CommandError::with_context(e, "Failed to build inventory forecast")
+        })
+}
+
+/// Vials expiring from `start_date` (default today) through `end_date`
+/// (default 90 days later), by day
+///
+/// Reconstituted vials are listed on the day their fridge life runs out
+/// when that comes before the dry-powder expiry.
+#[tauri::command]
+pub async fn get_expiry_calendar(
+    state: State<'_, std::sync::Arc<AppState>>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<Vec<ExpiryDay>, CommandError> {
+    let from = parse_optional_date(start_date.as_deref())?
+        .unwrap_or_else(|| OffsetDateTime::now_utc().date());
+    let until = parse_optional_date(end_date.as_deref())?
+        .unwrap_or(from + Duration::days(DEFAULT_CALENDAR_DAYS));
+    if until < from {
+        return Err(CommandError::invalid_input("End date must not be before start date"));
+    }
+
+    let inventory = state
+        .db
+        .run(|storage| storage.list_inventory())
+        .await
+        .map_err(|e| {
+            error!("Failed to list inventory: {:#}", e);
+            CommandError::with_context(e, "Failed to list inventory")
+        })?;
+    Ok(expiry_calendar(&inventory, from, until))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vial(protocol: &PeptideProtocol, mg: f32) -> InventoryItem {
+        let mut item = InventoryItem::new(protocol.id.as_str());
+        item.quantity_mg = Some(mg);
+        item.quantity_remaining_mg = Some(mg);
+        item
+    }
+
+    fn dose(protocol: &PeptideProtocol, mg: f32, days_ago: i64, now: OffsetDateTime) -> DoseLog {
+        let mut dose = DoseLog::new(protocol.id.as_str(), "abdomen", mg);
+        dose.logged_at = now - Duration::days(days_ago);
+        dose
+    }
+
+    fn forecast(
+        protocols: &[PeptideProtocol],
+        inventory: &[InventoryItem],
+        doses: &[DoseLog],
+        schedules: &[ScheduledUsage],
+        now: OffsetDateTime,
+    ) -> InventoryForecast {
+        let inputs = ForecastInputs {
+            protocols,
+            inventory,
+            doses,
+            schedules,
+        };
+        build_forecast(&inputs, DEFAULT_LEAD_TIME_DAYS, DEFAULT_HISTORY_DAYS, now)
+    }
+
+    #[test]
+    fn test_history_forecast_uses_weighted_usage() {
+        let now = OffsetDateTime::now_utc();
+        let protocol = PeptideProtocol::new("Healing", "BPC-157");
+        let doses: Vec<DoseLog> = (0..30).map(|day| dose(&protocol, 0.5, day, now)).collect();
+        let inventory = vec![vial(&protocol, 10.0)];
+
+        let result = forecast(&[protocol], &inventory, &doses, &[], now);
+        let protocol = &result.protocols[0];
+
+        assert_eq!(protocol.usage_source, UsageSource::History);
+        assert!((protocol.historical_daily_usage_mg - 0.5).abs() < 0.01);
+        let days = protocol.days_remaining.unwrap();
+        assert!((days - 20.0).abs() < 0.1, "expected ~20 days, got {}", days);
+        assert_eq!(
+            protocol.reorder_by.as_deref().unwrap(),
+            date_string(now + Duration::days(20 - DEFAULT_LEAD_TIME_DAYS))
+        );
+    }
+
+    #[test]
+    fn test_recent_doses_outweigh_old_ones() {
+        let now = OffsetDateTime::now_utc();
+        let protocol = PeptideProtocol::new("Healing", "BPC-157");
+        let mut doses: Vec<DoseLog> = (30..60).map(|day| dose(&protocol, 0.25, day, now)).collect();
+        doses.extend((0..30).map(|day| dose(&protocol, 1.0, day, now)));
+        let refs: Vec<&DoseLog> = doses.iter().collect();
+
+        let usage = weighted_daily_usage(&refs, DEFAULT_HISTORY_DAYS, now);
+        // A flat average would be 0.625mg/day
+        assert!(usage > 0.8, "expected recent doses to dominate, got {}", usage);
+    }
+
+    #[test]
+    fn test_schedule_forecast_consumes_vials_in_order() {
+        let now = OffsetDateTime::now_utc();
+        let protocol = PeptideProtocol::new("Daily", "BPC-157");
+        let schedules = vec![ScheduledUsage {
+            protocol_id: protocol.id.clone(),
+            amount_mg: 1.0,
+            days_of_week: (0..7).collect(),
+        }];
+
+        let mut opened = vial(&protocol, 2.0);
+        opened.vial_status = VialStatus::Opened;
+        let sealed = vial(&protocol, 5.0);
+        let inventory = vec![sealed, opened];
+
+        let result = forecast(&[protocol], &inventory, &[], &schedules, now);
+        let protocol = &result.protocols[0];
+
+        assert_eq!(protocol.usage_source, UsageSource::Schedule);
+        assert_eq!(protocol.remaining_mg, 7.0);
+        // Today's dose is still due, so 7mg lasts through day 6
+        assert_eq!(protocol.vials[0].inventory_id, inventory[1].id);
+        assert_eq!(protocol.vials[0].depletion_date.as_deref().unwrap(), date_string(now + Duration::days(1)));
+        assert_eq!(protocol.depletion_date.as_deref().unwrap(), date_string(now + Duration::days(6)));
+    }
+
+    #[test]
+    fn test_expiring_vial_reports_waste_and_alerts() {
+        let now = OffsetDateTime::now_utc();
+        let protocol = PeptideProtocol::new("Slow", "TB-500");
+        let doses: Vec<DoseLog> = (0..14).map(|day| dose(&protocol, 0.1, day, now)).collect();
+        let mut item = vial(&protocol, 5.0);
+        item.expiry_date = Some(now + Duration::days(5));
+        let inventory = vec![item];
+
+        let result = forecast(&[protocol], &inventory, &doses, &[], now);
+        let vial = &result.protocols[0].vials[0];
+
+        assert!(vial.depletion_date.is_none());
+        assert!(vial.wasted_mg > 4.0);
+        assert_eq!(result.protocols[0].days_remaining.map(|d| d.round()), Some(5.0));
+
+        let alerts = forecast_alerts(&result);
+        assert!(alerts.iter().any(|a| a.alert_type == AlertType::ExpiringSoon));
+        assert!(alerts.iter().any(|a| a.alert_type == AlertType::LowStock));
+    }
+
+    #[test]
+    fn test_reconstituted_vial_expires_before_its_label_date() {
+        let now = OffsetDateTime::now_utc();
+        let protocol = PeptideProtocol::new("Mixed", "BPC-157");
+        let doses: Vec<DoseLog> = (0..14).map(|day| dose(&protocol, 0.1, day, now)).collect();
+        let mut item = vial(&protocol, 5.0);
+        item.expiry_date = Some(now + Duration::days(365));
+        item.reconstitute(now - Duration::days(26), 28);
+        let inventory = vec![item];
+
+        let result = forecast(&[protocol], &inventory, &doses, &[], now);
+        let vial = &result.protocols[0].vials[0];
+
+        assert!(vial.reconstituted);
+        assert_eq!(vial.expiry_date.as_deref().unwrap(), date_string(now + Duration::days(2)));
+        let alerts = forecast_alerts(&result);
+        let expiring = alerts
+            .iter()
+            .find(|a| a.alert_type == AlertType::ExpiringSoon)
+            .expect("expiring alert");
+        assert_eq!(expiring.title, "Expiring Soon: Mixed reconstituted vial");
+    }
+
+    #[test]
+    fn test_unused_protocols_without_stock_are_omitted() {
+        let now = OffsetDateTime::now_utc();
+        let idle = PeptideProtocol::new("Idle", "GHK-Cu");
+        let active = PeptideProtocol::new("Active", "BPC-157");
+        let doses = vec![dose(&active, 0.5, 1, now)];
+
+        let result = forecast(&[idle, active], &[], &doses, &[], now);
+
+        assert_eq!(result.protocols.len(), 1);
+        assert_eq!(result.protocols[0].protocol_name, "Active");
+        assert_eq!(result.protocols[0].days_remaining, Some(0.0));
+    }
+}
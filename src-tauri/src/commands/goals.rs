@@ -0,0 +1,300 @@
+use anyhow::Result;
+use peptrack_core::{
+    compute_goal_progress, goal_readings, Goal, GoalMetric, GoalProgress, StorageManager,
+};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use time::OffsetDateTime;
+use tracing::{error, info};
+
+use crate::commands::dates::parse_datetime;
+use crate::error::CommandError;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoalPayload {
+    pub name: String,
+    pub metric: GoalMetric,
+    /// Lab marker name, required for lab marker goals
+    pub marker: Option<String>,
+    pub unit: Option<String>,
+    pub target_value: f32,
+    pub start_value: Option<f32>,
+    pub start_date: String,          // ISO 8601 string
+    pub target_date: Option<String>, // ISO 8601 string
+    pub protocol_id: Option<String>,
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// A goal with its current progress
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoalWithProgress {
+    pub goal: Goal,
+    pub protocol_name: Option<String>,
+    pub progress: GoalProgress,
+}
+
+fn trimmed(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+async fn validate_payload(state: &AppState, payload: &GoalPayload) -> Result<(), CommandError> {
+    if payload.name.trim().is_empty() {
+        return Err(CommandError::invalid_input("Goal name is required"));
+    }
+    if !payload.target_value.is_finite() || payload.start_value.is_some_and(|value| !value.is_finite()) {
+        return Err(CommandError::invalid_input("Goal values must be numbers"));
+    }
+    if payload.metric == GoalMetric::LabMarker && trimmed(payload.marker.clone()).is_none() {
+        return Err(CommandError::invalid_input("Lab marker goals need a marker name"));
+    }
+    if let Some(protocol_id) = payload.protocol_id.clone() {
+        let protocol = state
+            .db
+            .run(move |storage| storage.get_protocol(&protocol_id))
+            .await
+            .map_err(|e| CommandError::with_context(e, "Failed to fetch protocol"))?;
+        if protocol.is_none() {
+            return Err(CommandError::invalid_input("Linked protocol not found"));
+        }
+    }
+    Ok(())
+}
+
+fn apply_payload(goal: &mut Goal, payload: GoalPayload) -> Result<(), CommandError> {
+    let start_date = parse_datetime(&payload.start_date)?;
+    let target_date = payload.target_date.as_deref().map(parse_datetime).transpose()?;
+    if target_date.is_some_and(|target_date| target_date <= start_date) {
+        return Err(CommandError::invalid_input("Target date must be after the start date"));
+    }
+
+    goal.name = payload.name.trim().to_string();
+    goal.metric = payload.metric;
+    // Markers and units only mean something for lab goals
+    let lab = payload.metric == GoalMetric::LabMarker;
+    goal.marker = trimmed(payload.marker).filter(|_| lab);
+    goal.unit = trimmed(payload.unit).filter(|_| lab);
+    goal.target_value = payload.target_value;
+    goal.start_value = payload.start_value;
+    goal.start_date = start_date;
+    goal.target_date = target_date;
+    goal.protocol_id = payload.protocol_id;
+    goal.notes = payload.notes;
+    goal.archived = payload.archived;
+    goal.updated_at = OffsetDateTime::now_utc();
+    Ok(())
+}
+
+/// Work out progress for `goals`, loading body metrics and lab results once
+pub(crate) fn load_goal_progress(
+    storage: &StorageManager,
+    goals: Vec<Goal>,
+    now: OffsetDateTime,
+) -> Result<Vec<GoalWithProgress>> {
+    if goals.is_empty() {
+        return Ok(Vec::new());
+    }
+    let metrics = storage.list_body_metrics()?;
+    let lab_results = if goals.iter().any(|goal| goal.metric == GoalMetric::LabMarker) {
+        storage.list_lab_results()?
+    } else {
+        Vec::new()
+    };
+    let protocols = storage.list_protocols()?;
+
+    Ok(goals
+        .into_iter()
+        .map(|goal| {
+            let readings = goal_readings(&goal, &metrics, &lab_results);
+            let progress = compute_goal_progress(&goal, &readings, now);
+            let protocol_name = goal.protocol_id.as_deref().and_then(|id| {
+                protocols
+                    .iter()
+                    .find(|protocol| protocol.id == id)
+                    .map(|protocol| protocol.name.clone())
+            });
+            GoalWithProgress {
+                goal,
+                protocol_name,
+                progress,
+            }
+        })
+        .collect())
+}
+
+async fn fetch_goal(state: &AppState, goal_id: String) -> Result<Goal, CommandError> {
+    state
+        .db
+        .run(move |storage| storage.get_goal(&goal_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch goal"))?
+        .ok_or_else(|| CommandError::not_found("Goal not found"))
+}
+
+async fn with_progress(state: &AppState, goal: Goal) -> Result<GoalWithProgress, CommandError> {
+    state
+        .db
+        .run(move |storage| load_goal_progress(storage, vec![goal], OffsetDateTime::now_utc()))
+        .await
+        .map_err(|e| {
+            error!("Failed to compute goal progress: {:#}", e);
+            CommandError::with_context(e, "Failed to compute goal progress")
+        })?
+        .pop()
+        .ok_or_else(|| CommandError::not_found("Goal not found"))
+}
+
+// ========== Goal Commands ==========
+
+#[tauri::command]
+pub async fn create_goal(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: GoalPayload,
+) -> Result<GoalWithProgress, CommandError> {
+    validate_payload(&state, &payload).await?;
+    info!("Creating goal: {}", payload.name.trim());
+
+    let mut goal = Goal::new("", payload.metric, payload.target_value, OffsetDateTime::now_utc());
+    apply_payload(&mut goal, payload)?;
+
+    let goal = state
+        .db
+        .run(move |storage| storage.upsert_goal(&goal).map(|_| goal))
+        .await
+        .map_err(|e| {
+            error!("Failed to save goal: {:#}", e);
+            CommandError::with_context(e, "Failed to save goal")
+        })?;
+
+    with_progress(&state, goal).await
+}
+
+#[tauri::command]
+pub async fn update_goal(
+    state: State<'_, std::sync::Arc<AppState>>,
+    goal_id: String,
+    payload: GoalPayload,
+) -> Result<GoalWithProgress, CommandError> {
+    validate_payload(&state, &payload).await?;
+
+    let mut goal = fetch_goal(&state, goal_id).await?;
+    apply_payload(&mut goal, payload)?;
+
+    let goal = state
+        .db
+        .run(move |storage| storage.upsert_goal(&goal).map(|_| goal))
+        .await
+        .map_err(|e| {
+            error!("Failed to update goal: {:#}", e);
+            CommandError::with_context(e, "Failed to update goal")
+        })?;
+
+    with_progress(&state, goal).await
+}
+
+/// List goals with their progress, most recently started first
+///
+/// Archived goals are left out unless `include_archived` is set.
+#[tauri::command]
+pub async fn list_goals(
+    state: State<'_, std::sync::Arc<AppState>>,
+    include_archived: Option<bool>,
+) -> Result<Vec<GoalWithProgress>, CommandError> {
+    let include_archived = include_archived.unwrap_or(false);
+    state
+        .db
+        .run(move |storage| {
+            let mut goals = storage.list_goals()?;
+            if !include_archived {
+                goals.retain(|goal| !goal.archived);
+            }
+            load_goal_progress(storage, goals, OffsetDateTime::now_utc())
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to list goals: {:#}", e);
+            CommandError::with_context(e, "Failed to list goals")
+        })
+}
+
+#[tauri::command]
+pub async fn get_goal(
+    state: State<'_, std::sync::Arc<AppState>>,
+    goal_id: String,
+) -> Result<GoalWithProgress, CommandError> {
+    let goal = fetch_goal(&state, goal_id).await?;
+    with_progress(&state, goal).await
+}
+
+#[tauri::command]
+pub async fn delete_goal(
+    state: State<'_, std::sync::Arc<AppState>>,
+    goal_id: String,
+) -> Result<(), CommandError> {
+    info!("Deleting goal: {}", goal_id);
+
+    state
+        .db
+        .run(move |storage| storage.delete_goal(&goal_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to delete goal: {:#}", e);
+            CommandError::with_context(e, "Failed to delete goal")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(json: &str) -> GoalPayload {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_apply_payload_drops_marker_for_body_goals() {
+        let mut goal = Goal::new("", GoalMetric::WeightKg, 0.0, OffsetDateTime::now_utc());
+        apply_payload(
+            &mut goal,
+            payload(
+                r#"{
+                    "name": " Cut ",
+                    "metric": "weight_kg",
+                    "marker": "IGF-1",
+                    "targetValue": 80,
+                    "startDate": "2025-01-01T00:00:00Z",
+                    "targetDate": "2025-04-01T00:00:00Z"
+                }"#,
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(goal.name, "Cut");
+        assert!(goal.marker.is_none());
+        assert!(goal.target_date.is_some());
+    }
+
+    #[test]
+    fn test_target_date_must_follow_start() {
+        let mut goal = Goal::new("", GoalMetric::WeightKg, 0.0, OffsetDateTime::now_utc());
+        let result = apply_payload(
+            &mut goal,
+            payload(
+                r#"{
+                    "name": "Cut",
+                    "metric": "weight_kg",
+                    "targetValue": 80,
+                    "startDate": "2025-04-01T00:00:00Z",
+                    "targetDate": "2025-01-01T00:00:00Z"
+                }"#,
+            ),
+        );
+        assert!(result.is_err());
+    }
+}
@@ -1,22 +1,62 @@
+use std::sync::Arc;
+
 use peptrack_core::models::{DatabaseStats, HealthReport};
-use tauri::State;
-use tracing::info;
+use peptrack_core::{
+    CascadePolicies, MaintenanceRun, MigrationSnapshot, OrphanRepair, OrphanedRecord, TamperReport,
+};
+use tauri::{AppHandle, State};
+use time::OffsetDateTime;
+use tracing::{info, warn};
 
+use crate::commands::settings::{load_setting_or_default, save_setting};
+use crate::error::CommandError;
 use crate::state::AppState;
 
+/// How often the maintenance loop checks whether a weekly run is due
+const MAINTENANCE_CHECK_SECS: u64 = 6 * 60 * 60;
+
+/// Run scheduled database maintenance whenever the last run is a week old
+pub async fn run_maintenance_loop(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(MAINTENANCE_CHECK_SECS));
+    loop {
+        interval.tick().await;
+        if state.key_provider.is_locked() {
+            continue;
+        }
+
+        let result = state
+            .db
+            .run(|storage| {
+                if !storage.maintenance_due(OffsetDateTime::now_utc())? {
+                    return Ok(None);
+                }
+                storage.run_maintenance(false).map(Some)
+            })
+            .await;
+        match result {
+            Ok(Some(MaintenanceRun { error: Some(error), .. })) => {
+                warn!("Scheduled database maintenance failed: {}", error)
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Scheduled database maintenance failed: {:#}", e),
+        }
+    }
+}
+
 /// Get comprehensive database health report
 #[tauri::command]
 pub async fn get_database_health(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<HealthReport, String> {
+) -> Result<HealthReport, CommandError> {
     info!("Running database health check");
 
     state
-        .storage
-        .health_check()
+        .db
+        .run(|storage| storage.health_check())
+        .await
         .map_err(|err| {
             tracing::error!("Health check failed: {:#}", err);
-            err.to_string()
+            CommandError::from(err)
         })
 }
 
@@ -25,15 +65,16 @@ pub async fn get_database_health(
 #[tauri::command]
 pub async fn verify_database_integrity(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     info!("Verifying database integrity");
 
     state
-        .storage
-        .verify_integrity()
+        .db
+        .run(|storage| storage.verify_integrity())
+        .await
         .map_err(|err| {
             tracing::error!("Integrity verification failed: {:#}", err);
-            err.to_string()
+            CommandError::from(err)
         })
 }
 
@@ -42,15 +83,68 @@ pub async fn verify_database_integrity(
 #[tauri::command]
 pub async fn optimize_database(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     info!("Optimizing database");
 
     state
-        .storage
-        .optimize()
+        .db
+        .run(|storage| storage.optimize())
+        .await
         .map_err(|err| {
             tracing::error!("Database optimization failed: {:#}", err);
-            err.to_string()
+            CommandError::from(err)
+        })
+}
+
+/// Compact the database file
+/// Runs a full VACUUM so the file shrinks to the data it holds
+#[tauri::command]
+pub async fn compact_database(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<(), CommandError> {
+    info!("Compacting database");
+
+    state
+        .db
+        .run(|storage| storage.compact())
+        .await
+        .map_err(|err| {
+            tracing::error!("Database compaction failed: {:#}", err);
+            CommandError::from(err)
+        })
+}
+
+/// List the snapshots taken before schema migrations, newest first
+#[tauri::command]
+pub async fn list_migration_snapshots(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<Vec<MigrationSnapshot>, CommandError> {
+    state
+        .db
+        .run(|storage| storage.list_migration_snapshots())
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to list migration snapshots: {:#}", err);
+            CommandError::with_context(err, "Failed to list migration snapshots")
+        })
+}
+
+/// Roll the database back to a pre-migration snapshot
+/// The app should be restarted afterwards so the schema is upgraded again
+#[tauri::command]
+pub async fn restore_migration_snapshot(
+    state: State<'_, std::sync::Arc<AppState>>,
+    snapshot_id: String,
+) -> Result<(), CommandError> {
+    info!("Restoring migration snapshot {}", snapshot_id);
+
+    state
+        .db
+        .run(move |storage| storage.restore_migration_snapshot(&snapshot_id))
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to restore migration snapshot: {:#}", err);
+            CommandError::with_context(err, "Failed to restore migration snapshot")
         })
 }
 
@@ -61,16 +155,17 @@ pub async fn optimize_database(
 pub async fn checkpoint_database(
     state: State<'_, std::sync::Arc<AppState>>,
     mode: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     let checkpoint_mode = mode.unwrap_or_else(|| "PASSIVE".to_string());
     info!("Checkpointing database (mode: {})", checkpoint_mode);
 
     state
-        .storage
-        .checkpoint_wal(&checkpoint_mode)
+        .db
+        .run(move |storage| storage.checkpoint_wal(&checkpoint_mode))
+        .await
         .map_err(|err| {
             tracing::error!("Database checkpoint failed: {:#}", err);
-            err.to_string()
+            CommandError::from(err)
         })
 }
 
@@ -79,14 +174,161 @@ pub async fn checkpoint_database(
 #[tauri::command]
 pub async fn get_database_stats(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<DatabaseStats, String> {
+) -> Result<DatabaseStats, CommandError> {
     info!("Getting database statistics");
 
     state
-        .storage
-        .get_stats()
+        .db
+        .run(|storage| storage.get_stats())
+        .await
         .map_err(|err| {
             tracing::error!("Failed to get database stats: {:#}", err);
-            err.to_string()
+            CommandError::from(err)
+        })
+}
+
+/// List records left pointing at deleted ones, e.g. alerts about a deleted vial
+#[tauri::command]
+pub async fn find_orphaned_records(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<Vec<OrphanedRecord>, CommandError> {
+    state
+        .db
+        .run(|storage| storage.find_orphaned_records())
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to find orphaned records: {:#}", err);
+            CommandError::with_context(err, "Failed to find orphaned records")
+        })
+}
+
+/// Clean up orphaned records as the saved cascade policies say
+#[tauri::command]
+pub async fn repair_orphaned_records(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<OrphanRepair, CommandError> {
+    info!("Repairing orphaned records");
+
+    let policies: CascadePolicies = load_setting_or_default(&state).await;
+    state
+        .db
+        .run(move |storage| storage.repair_orphaned_records(&policies))
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to repair orphaned records: {:#}", err);
+            CommandError::with_context(err, "Failed to repair orphaned records")
+        })
+}
+
+/// Get how deletes cascade to related records
+#[tauri::command]
+pub async fn get_cascade_policies(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<CascadePolicies, CommandError> {
+    Ok(load_setting_or_default(&state).await)
+}
+
+#[tauri::command]
+pub async fn update_cascade_policies(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    policies: CascadePolicies,
+) -> Result<(), CommandError> {
+    save_setting(&app, &state, &policies).await
+}
+
+/// Whether writes to records are chained for tamper evidence
+#[tauri::command]
+pub async fn get_tamper_evidence_enabled(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<bool, CommandError> {
+    state
+        .db
+        .run(|storage| storage.tamper_evidence_enabled())
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to read tamper evidence setting: {:#}", err);
+            CommandError::with_context(err, "Failed to read tamper evidence setting")
+        })
+}
+
+/// Turn tamper evidence on, chaining every record already stored, or off,
+/// deleting the chain
+#[tauri::command]
+pub async fn set_tamper_evidence(
+    state: State<'_, std::sync::Arc<AppState>>,
+    enabled: bool,
+) -> Result<(), CommandError> {
+    info!("Turning tamper evidence {}", if enabled { "on" } else { "off" });
+
+    state
+        .db
+        .run(move |storage| {
+            if enabled {
+                storage.enable_tamper_evidence()
+            } else {
+                storage.disable_tamper_evidence()
+            }
+        })
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to change tamper evidence setting: {:#}", err);
+            CommandError::with_context(err, "Failed to change tamper evidence setting")
+        })
+}
+
+/// Check whether any records were added, changed or deleted outside the app
+/// since tamper evidence was turned on
+#[tauri::command]
+pub async fn verify_tamper_chain(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<TamperReport, CommandError> {
+    info!("Verifying tamper evidence chain");
+
+    let report = state
+        .db
+        .run(|storage| {
+            if !storage.tamper_evidence_enabled()? {
+                return Ok(None);
+            }
+            storage.verify_tamper_chain().map(Some)
+        })
+        .await
+        .map_err(|err| {
+            tracing::error!("Tamper evidence check failed: {:#}", err);
+            CommandError::with_context(err, "Failed to verify tamper evidence")
+        })?;
+    report.ok_or_else(|| CommandError::conflict("Tamper evidence is off"))
+}
+
+/// Check the database stats now and vacuum or checkpoint if they call for it
+#[tauri::command]
+pub async fn run_database_maintenance(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<MaintenanceRun, CommandError> {
+    info!("Running database maintenance");
+
+    state
+        .db
+        .run(|storage| storage.run_maintenance(true))
+        .await
+        .map_err(|err| {
+            tracing::error!("Database maintenance failed: {:#}", err);
+            CommandError::with_context(err, "Failed to run database maintenance")
+        })
+}
+
+/// Maintenance runs, scheduled and manual, newest first
+#[tauri::command]
+pub async fn list_maintenance_runs(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<Vec<MaintenanceRun>, CommandError> {
+    state
+        .db
+        .run(|storage| storage.list_maintenance_runs())
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to list maintenance runs: {:#}", err);
+            CommandError::with_context(err, "Failed to list maintenance runs")
         })
 }
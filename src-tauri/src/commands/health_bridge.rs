@@ -0,0 +1,329 @@
+//! HealthKit / Health Connect sync for mobile builds
+//!
+//! With the `health-bridge` feature, logged body metrics are written to the
+//! phone's health store and weight entries from other apps can be read back
+//! as body metrics. Desktop builds and builds without the feature report the
+//! bridge as unavailable. Settings are saved as [`HealthBridgeSettings`].
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use peptrack_core::models::BodyMetric;
+use peptrack_core::{daily_weights, plan_health_import, HealthImportSource, Setting};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime};
+use tracing::{error, info, warn};
+
+use crate::commands::health_import::HealthImportSummary;
+use crate::commands::settings::{load_setting, load_setting_or_default, save_setting};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// How far back the first weight sync reads
+const INITIAL_READ_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HealthBridgeSettings {
+    pub enabled: bool,
+    /// Write each logged body metric to the health store
+    pub write_metrics: bool,
+    /// Read weight entries from other apps back into body metrics
+    pub read_weight: bool,
+    /// When weights were last read (RFC 3339)
+    pub last_read: Option<String>,
+}
+
+impl Default for HealthBridgeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            write_metrics: true,
+            read_weight: false,
+            last_read: None,
+        }
+    }
+}
+
+impl Setting for HealthBridgeSettings {
+    const KEY: &'static str = "health_bridge.settings";
+    const LEGACY_FILE: Option<&'static str> = Some("health_bridge.json");
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthBridgeStatus {
+    /// Whether this build and device have a health store to sync with
+    pub available: bool,
+    pub settings: HealthBridgeSettings,
+}
+
+/// Health store on this platform, for import summaries
+fn store_source() -> HealthImportSource {
+    if cfg!(target_os = "android") {
+        HealthImportSource::HealthConnect
+    } else {
+        HealthImportSource::AppleHealth
+    }
+}
+
+#[cfg(feature = "health-bridge")]
+mod bridge {
+    use anyhow::{anyhow, Result};
+    use peptrack_core::models::BodyMetric;
+    use peptrack_health_bridge::{AuthorizationRequest, HealthBridgeExt, HealthDataType, HealthSample};
+    use tauri::AppHandle;
+    use time::format_description::well_known::Rfc3339;
+    use time::OffsetDateTime;
+
+    use super::HealthBridgeSettings;
+
+    pub fn is_available(app: &AppHandle) -> bool {
+        app.health_bridge().is_available()
+    }
+
+    /// Show the permission prompt for what `settings` turns on
+    pub fn authorize(app: &AppHandle, settings: &HealthBridgeSettings) -> Result<bool> {
+        let mut request = AuthorizationRequest::default();
+        if settings.write_metrics {
+            request.write = vec![
+                HealthDataType::BodyMass,
+                HealthDataType::BodyFatPercentage,
+                HealthDataType::WaistCircumference,
+                HealthDataType::RestingHeartRate,
+            ];
+        }
+        if settings.read_weight {
+            request.read = vec![HealthDataType::BodyMass];
+        }
+        Ok(app.health_bridge().request_authorization(request)?.granted)
+    }
+
+    pub fn write_metric(app: &AppHandle, metric: &BodyMetric) -> Result<()> {
+        let date = metric.date.format(&Rfc3339)?;
+        let samples: Vec<HealthSample> = [
+            (HealthDataType::BodyMass, metric.weight_kg),
+            (HealthDataType::BodyFatPercentage, metric.body_fat_percentage),
+            (HealthDataType::WaistCircumference, metric.waist_cm),
+            (HealthDataType::RestingHeartRate, metric.resting_heart_rate_bpm),
+        ]
+        .into_iter()
+        .filter_map(|(data_type, value)| {
+            value.map(|value| HealthSample {
+                data_type,
+                value: f64::from(value),
+                date: date.clone(),
+            })
+        })
+        .collect();
+
+        if samples.is_empty() {
+            return Ok(());
+        }
+        Ok(app.health_bridge().write_samples(&samples)?)
+    }
+
+    pub fn read_weights(app: &AppHandle, since: &str) -> Result<Vec<(OffsetDateTime, f32)>> {
+        app.health_bridge()
+            .read_samples(HealthDataType::BodyMass, since)?
+            .into_iter()
+            .map(|sample| {
+                let at = OffsetDateTime::parse(&sample.date, &Rfc3339)
+                    .map_err(|e| anyhow!("Bad sample date {}: {}", sample.date, e))?;
+                Ok((at, sample.value as f32))
+            })
+            .collect()
+    }
+}
+
+#[cfg(not(feature = "health-bridge"))]
+mod bridge {
+    use anyhow::{anyhow, Result};
+    use peptrack_core::models::BodyMetric;
+    use tauri::AppHandle;
+    use time::OffsetDateTime;
+
+    use super::HealthBridgeSettings;
+
+    const UNAVAILABLE: &str = "This build of PepTrack doesn't include health data sync";
+
+    pub fn is_available(_app: &AppHandle) -> bool {
+        false
+    }
+
+    pub fn authorize(_app: &AppHandle, _settings: &HealthBridgeSettings) -> Result<bool> {
+        Err(anyhow!(UNAVAILABLE))
+    }
+
+    pub fn write_metric(_app: &AppHandle, _metric: &BodyMetric) -> Result<()> {
+        Err(anyhow!(UNAVAILABLE))
+    }
+
+    pub fn read_weights(_app: &AppHandle, _since: &str) -> Result<Vec<(OffsetDateTime, f32)>> {
+        Err(anyhow!(UNAVAILABLE))
+    }
+}
+
+/// Write a newly logged body metric to the health store in the background,
+/// if the user turned that on
+pub async fn export_body_metric(app: &AppHandle, state: &AppState, metric: &BodyMetric) {
+    let loaded = state.db.run(|storage| storage.load_setting_or_default()).await;
+    let settings: HealthBridgeSettings = match loaded {
+        Ok(settings) => settings,
+        Err(e) => {
+            warn!("Skipping health store export: {:#}", e);
+            return;
+        }
+    };
+    if !settings.enabled || !settings.write_metrics {
+        return;
+    }
+
+    let app = app.clone();
+    let metric = metric.clone();
+    // Plugin calls block until the native side answers
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = bridge::write_metric(&app, &metric) {
+            warn!("Failed to write body metric to {}: {:#}", store_source().label(), e);
+        }
+    });
+}
+
+// ========== Health Bridge Commands ==========
+
+/// Whether a health store is available, and the saved sync settings
+#[tauri::command]
+pub async fn get_health_bridge_status(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<HealthBridgeStatus, CommandError> {
+    let settings = load_setting(&state).await?;
+    let available = tauri::async_runtime::spawn_blocking(move || bridge::is_available(&app))
+        .await
+        .unwrap_or(false);
+    Ok(HealthBridgeStatus { available, settings })
+}
+
+/// Save sync settings, showing the system permission prompt when sync is
+/// turned on
+#[tauri::command]
+pub async fn update_health_bridge_settings(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    settings: HealthBridgeSettings,
+) -> Result<(), CommandError> {
+    let mut settings = settings;
+    // Only the sync itself moves the read marker
+    settings.last_read = load_setting_or_default::<HealthBridgeSettings>(&state).await.last_read;
+
+    if settings.enabled {
+        let to_authorize = settings.clone();
+        let handle = app.clone();
+        let granted = tauri::async_runtime::spawn_blocking(move || bridge::authorize(&handle, &to_authorize))
+            .await
+            .map_err(|e| CommandError::with_context(e, "Health permission prompt failed"))?
+            .map_err(|e| {
+                error!("Health authorization failed: {:#}", e);
+                CommandError::with_context(e, "Failed to request health data access")
+            })?;
+        if !granted {
+            return Err(CommandError::invalid_input(format!(
+                "PepTrack wasn't given access to {}; allow it in the system settings",
+                store_source().label()
+            )));
+        }
+    }
+
+    save_setting(&app, &state, &settings).await
+}
+
+/// Read weight entries other apps recorded since the last sync into body
+/// metrics
+///
+/// Like a file import, days that already have a weight are left alone.
+#[tauri::command]
+pub async fn sync_health_bridge(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<HealthImportSummary, CommandError> {
+    let mut settings: HealthBridgeSettings = load_setting(&state).await?;
+    if !settings.enabled || !settings.read_weight {
+        return Err(CommandError::invalid_input("Reading weight from the health store is turned off"));
+    }
+
+    let now = OffsetDateTime::now_utc();
+    let since = match &settings.last_read {
+        Some(last_read) => last_read.clone(),
+        None => (now - Duration::days(INITIAL_READ_DAYS))
+            .format(&Rfc3339)
+            .map_err(|e| CommandError::with_context(e, "Failed to format date"))?,
+    };
+
+    let handle = app.clone();
+    let readings = tauri::async_runtime::spawn_blocking(move || bridge::read_weights(&handle, &since))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Health store read failed"))?
+        .map_err(|e| {
+            error!("Failed to read weights from the health store: {:#}", e);
+            CommandError::with_context(e, "Failed to read weights from the health store")
+        })?;
+
+    let source = store_source();
+    let samples = daily_weights(&readings);
+    let existing = state
+        .db
+        .run(|storage| storage.list_body_metrics())
+        .await
+        .map_err(CommandError::from)?;
+    let plan = plan_health_import(&existing, &samples, source);
+    let summary = HealthImportSummary {
+        source,
+        days_found: samples.len(),
+        created: plan.created.len(),
+        updated: plan.updated.len(),
+        skipped: plan.skipped,
+        first_date: samples.first().map(|sample| sample.date.to_string()),
+        last_date: samples.last().map(|sample| sample.date.to_string()),
+    };
+
+    let metrics: Vec<_> = plan.created.into_iter().chain(plan.updated).collect();
+    state
+        .db
+        .run(move |storage| storage.upsert_body_metrics(&metrics))
+        .await
+        .map_err(|e| {
+            error!("Failed to save synced body metrics: {:#}", e);
+            CommandError::with_context(e, "Failed to save synced body metrics")
+        })?;
+
+    settings.last_read = Some(
+        now.format(&Rfc3339)
+            .map_err(|e| CommandError::with_context(e, "Failed to format date"))?,
+    );
+    save_setting(&app, &state, &settings).await?;
+
+    info!(
+        "{} sync complete: {} created, {} updated, {} already present",
+        source.label(),
+        summary.created,
+        summary.updated,
+        summary.skipped
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_default_to_writing_only() {
+        let settings: HealthBridgeSettings = serde_json::from_str(r#"{"enabled": true}"#).unwrap();
+        assert!(settings.enabled);
+        assert!(settings.write_metrics);
+        assert!(!settings.read_weight);
+        assert!(settings.last_read.is_none());
+    }
+}
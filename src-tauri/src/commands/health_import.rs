@@ -0,0 +1,188 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use anyhow::{anyhow, Context, Result};
+use peptrack_core::{
+    parse_apple_health, parse_google_fit_csv, plan_health_import, HealthImportMapping,
+    HealthImportPlan, HealthImportSource, StorageManager,
+};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use tracing::{error, info};
+
+use crate::commands::settings::{load_setting_or_default, save_setting};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// What an import found and what it changed (or would change, for a preview)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthImportSummary {
+    pub source: HealthImportSource,
+    pub days_found: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub first_date: Option<String>,
+    pub last_date: Option<String>,
+}
+
+/// Parse an export file into daily samples and plan the body metric changes
+fn plan_from_file(
+    storage: &StorageManager,
+    source: HealthImportSource,
+    file_path: &str,
+    mapping: &HealthImportMapping,
+) -> Result<(HealthImportPlan, HealthImportSummary)> {
+    let file = File::open(file_path).with_context(|| format!("Failed to open {}", file_path))?;
+    let mut reader = BufReader::with_capacity(1 << 20, file);
+
+    let samples = match source {
+        HealthImportSource::AppleHealth => {
+            if reader.fill_buf()?.starts_with(b"PK") {
+                return Err(anyhow!(
+                    "This is the zipped export; unzip it and choose apple_health_export/export.xml"
+                ));
+            }
+            parse_apple_health(reader, mapping)?
+        }
+        HealthImportSource::GoogleFit => parse_google_fit_csv(reader, mapping)?,
+        HealthImportSource::HealthConnect => {
+            return Err(anyhow!(
+                "Health Connect data is synced on the phone, not imported from a file"
+            ));
+        }
+    };
+
+    let existing = storage.list_body_metrics()?;
+    let plan = plan_health_import(&existing, &samples, source);
+
+    let summary = HealthImportSummary {
+        source,
+        days_found: samples.len(),
+        created: plan.created.len(),
+        updated: plan.updated.len(),
+        skipped: plan.skipped,
+        first_date: samples.first().map(|sample| sample.date.to_string()),
+        last_date: samples.last().map(|sample| sample.date.to_string()),
+    };
+    Ok((plan, summary))
+}
+
+/// Use the given mapping, or the saved one
+async fn resolve_mapping(
+    state: &AppState,
+    mapping: Option<HealthImportMapping>,
+) -> HealthImportMapping {
+    match mapping {
+        Some(mapping) => mapping,
+        None => load_setting_or_default(state).await,
+    }
+}
+
+// ========== Health Import Commands ==========
+
+/// Gets the saved mapping of which data types to import
+#[tauri::command]
+pub async fn get_health_import_mapping(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<HealthImportMapping, CommandError> {
+    Ok(resolve_mapping(&state, None).await)
+}
+
+/// Saves which data types to import and any custom Google Fit column names
+#[tauri::command]
+pub async fn update_health_import_mapping(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    mapping: HealthImportMapping,
+) -> Result<(), CommandError> {
+    save_setting(&app, &state, &mapping).await
+}
+
+/// Parse an export and report what importing it would do, without saving
+#[tauri::command]
+pub async fn preview_health_import(
+    state: State<'_, std::sync::Arc<AppState>>,
+    source: HealthImportSource,
+    file_path: String,
+    mapping: Option<HealthImportMapping>,
+) -> Result<HealthImportSummary, CommandError> {
+    let mapping = resolve_mapping(&state, mapping).await;
+    let (_, summary) = state
+        .db
+        .run(move |storage| plan_from_file(storage, source, &file_path, &mapping))
+        .await
+        .map_err(|e| {
+            error!("Failed to read health export: {:#}", e);
+            CommandError::with_context(e, "Failed to read health export")
+        })?;
+    Ok(summary)
+}
+
+/// Import body metrics from an Apple Health or Google Fit export
+///
+/// Days that already have a body metric only gain values they were missing,
+/// so re-importing the same export is harmless.
+#[tauri::command]
+pub async fn import_health_data(
+    state: State<'_, std::sync::Arc<AppState>>,
+    source: HealthImportSource,
+    file_path: String,
+    mapping: Option<HealthImportMapping>,
+) -> Result<HealthImportSummary, CommandError> {
+    info!("Importing {} data from {}", source.label(), file_path);
+
+    let mapping = resolve_mapping(&state, mapping).await;
+    let (plan, summary) = state
+        .db
+        .run(move |storage| plan_from_file(storage, source, &file_path, &mapping))
+        .await
+        .map_err(|e| {
+            error!("Failed to read health export: {:#}", e);
+            CommandError::with_context(e, "Failed to read health export")
+        })?;
+
+    let metrics: Vec<_> = plan.created.into_iter().chain(plan.updated).collect();
+    state
+        .db
+        .run(move |storage| storage.upsert_body_metrics(&metrics))
+        .await
+        .map_err(|e| {
+            error!("Failed to save imported body metrics: {:#}", e);
+            CommandError::with_context(e, "Failed to save imported body metrics")
+        })?;
+
+    info!(
+        "{} import complete: {} created, {} updated, {} already present",
+        source.label(),
+        summary.created,
+        summary.updated,
+        summary.skipped
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mapping_deserializes_partial_settings() {
+        let json = r#"{"sleep": false, "googleFitColumns": {"restingHeartRate": "Min heart rate (bpm)"}}"#;
+
+        let mapping: HealthImportMapping = serde_json::from_str(json).unwrap();
+        assert!(mapping.weight);
+        assert!(!mapping.sleep);
+        assert_eq!(
+            mapping.google_fit_columns.resting_heart_rate.as_deref(),
+            Some("Min heart rate (bpm)")
+        );
+    }
+
+    #[test]
+    fn test_source_deserialization() {
+        let source: HealthImportSource = serde_json::from_str(r#""google_fit""#).unwrap();
+        assert_eq!(source, HealthImportSource::GoogleFit);
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use peptrack_core::interactions::{find_interactions, related_literature, InteractionWarning};
+use peptrack_core::models::{Alert, AlertType, PeptideProtocol};
+use peptrack_core::StorageManager;
+use tauri::State;
+use time::{Duration, OffsetDateTime};
+use tracing::{error, info};
+
+use crate::commands::schedules::enabled_schedule_protocol_ids;
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Protocols dosed within this many days count as active
+const ACTIVE_DOSE_WINDOW_DAYS: i64 = 30;
+
+/// Maximum number of literature references attached to an interaction alert
+const MAX_REFERENCES: usize = 3;
+
+/// Resolves the protocols to check for interactions
+///
+/// When `protocol_ids` is given those protocols are used as-is. Otherwise the
+/// active set is every protocol with an enabled dose schedule or a dose logged
+/// in the last 30 days.
+fn resolve_protocols(storage: &StorageManager, protocol_ids: Option<&[String]>) -> Result<Vec<PeptideProtocol>> {
+    let protocols = storage.list_protocols()?;
+
+    let selected: HashSet<String> = match protocol_ids {
+        Some(ids) => ids.iter().cloned().collect(),
+        None => {
+            let cutoff = OffsetDateTime::now_utc() - Duration::days(ACTIVE_DOSE_WINDOW_DAYS);
+            let mut active: HashSet<String> = enabled_schedule_protocol_ids(storage)
+                .context("Failed to load dose schedules")?
+                .into_iter()
+                .collect();
+
+            active.extend(
+                storage
+                    .list_dose_logs()?
+                    .into_iter()
+                    .filter(|log| log.logged_at >= cutoff)
+                    .map(|log| log.protocol_id),
+            );
+            active
+        }
+    };
+
+    Ok(protocols
+        .into_iter()
+        .filter(|p| selected.contains(&p.id))
+        .collect())
+}
+
+/// Runs the interaction rules and creates alerts for new findings
+///
+/// Alerts are deduplicated by rule and protocol pair, so repeated checks only
+/// alert once until the user dismisses the alert.
+pub(crate) async fn run_interaction_check(
+    state: &AppState,
+    protocol_ids: Option<Vec<String>>,
+) -> Result<Vec<Alert>> {
+    let (warnings, existing_alerts, literature) = state
+        .db
+        .run(move |storage| {
+            let protocols = resolve_protocols(storage, protocol_ids.as_deref())?;
+            let warnings = find_interactions(&protocols);
+            if warnings.is_empty() {
+                return Ok((warnings, Vec::new(), Vec::new()));
+            }
+            Ok((warnings, storage.list_alerts(false)?, storage.list_literature()?))
+        })
+        .await?;
+    let mut created_alerts = Vec::new();
+
+    for warning in warnings {
+        let key = warning.key();
+        let already_alerted = existing_alerts.iter().any(|a| {
+            a.alert_type == AlertType::Interaction
+                && a.related_id.as_deref() == Some(key.as_str())
+                && !a.is_dismissed
+        });
+
+        if already_alerted {
+            continue;
+        }
+
+        let alert = build_alert(&warning, &literature);
+        let raised = alert.clone();
+        let Some(delivery) = state
+            .db
+            .run(move |storage| storage.raise_alert(&raised))
+            .await
+            .context("Failed to create interaction alert")?
+        else {
+            continue;
+        };
+        state.notifier.alert(&alert, delivery);
+
+        info!("Created interaction alert: {}", alert.title);
+        created_alerts.push(alert);
+    }
+
+    Ok(created_alerts)
+}
+
+fn build_alert(warning: &InteractionWarning, literature: &[peptrack_core::LiteratureEntry]) -> Alert {
+    let references = related_literature(warning, literature, MAX_REFERENCES);
+
+    let title = format!(
+        "Interaction: {} + {}",
+        warning.peptide_names[0], warning.peptide_names[1]
+    );
+
+    let mut message = format!(
+        "{} ({} and {}). {}",
+        warning.title, warning.protocol_names[0], warning.protocol_names[1], warning.description
+    );
+
+    if !references.is_empty() {
+        let titles: Vec<&str> = references.iter().map(|entry| entry.title.as_str()).collect();
+        message.push_str(&format!(" See: {}", titles.join("; ")));
+    }
+
+    let mut alert = Alert::new(
+        AlertType::Interaction,
+        warning.severity.clone(),
+        title,
+        message,
+    );
+    alert.related_id = Some(warning.key());
+    alert.related_type = Some("interaction".to_string());
+    alert.references = references.into_iter().map(|entry| entry.id.clone()).collect();
+    alert
+}
+
+// ========== Interaction Commands ==========
+
+/// Lists interaction warnings for the given (or currently active) protocols
+/// without creating alerts
+#[tauri::command]
+pub async fn find_protocol_interactions(
+    state: State<'_, std::sync::Arc<AppState>>,
+    protocol_ids: Option<Vec<String>>,
+) -> Result<Vec<InteractionWarning>, CommandError> {
+    let protocols = state
+        .db
+        .run(move |storage| resolve_protocols(storage, protocol_ids.as_deref()))
+        .await
+        .map_err(|e| {
+            error!("Failed to load protocols for interaction check: {:#}", e);
+            CommandError::with_context(e, "Failed to load protocols")
+        })?;
+
+    Ok(find_interactions(&protocols))
+}
+
+/// Checks protocols for interactions and creates alerts for new findings
+#[tauri::command]
+pub async fn check_protocol_interactions(
+    state: State<'_, std::sync::Arc<AppState>>,
+    protocol_ids: Option<Vec<String>>,
+) -> Result<Vec<Alert>, CommandError> {
+    let created = run_interaction_check(&state, protocol_ids).await.map_err(|e| {
+        error!("Failed to check protocol interactions: {:#}", e);
+        CommandError::with_context(e, "Failed to check protocol interactions")
+    })?;
+
+    info!("Created {} new interaction alerts", created.len());
+    Ok(created)
+}
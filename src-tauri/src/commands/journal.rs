@@ -0,0 +1,329 @@
+use anyhow::Result;
+use peptrack_core::{
+    normalize_doi, parse_links, JournalEntry, JournalLink, JournalLinkKind, StorageManager,
+};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use time::OffsetDateTime;
+use tracing::{error, info};
+
+use crate::commands::dates::parse_datetime;
+use crate::error::CommandError;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntryPayload {
+    pub title: Option<String>,
+    /// Markdown, with `[[protocol:...]]`, `[[dose:...]]` and `[[paper:...]]` links
+    pub body: String,
+    pub entry_date: String, // ISO 8601 string
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A link in an entry and the record it points to, if that still exists
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedJournalLink {
+    #[serde(flatten)]
+    pub link: JournalLink,
+    pub entity_id: Option<String>,
+    /// Protocol name, dose description or paper title
+    pub title: Option<String>,
+    /// Protocol a linked dose belongs to
+    pub protocol_id: Option<String>,
+}
+
+fn validate_payload(payload: &JournalEntryPayload) -> Result<OffsetDateTime, CommandError> {
+    if payload.body.trim().is_empty() {
+        return Err(CommandError::invalid_input("Journal entry can't be empty"));
+    }
+    parse_datetime(&payload.entry_date)
+}
+
+fn apply_payload(entry: &mut JournalEntry, payload: JournalEntryPayload, entry_date: OffsetDateTime) {
+    entry.title = payload
+        .title
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty());
+    entry.body = payload.body;
+    entry.entry_date = entry_date;
+    entry.tags = payload
+        .tags
+        .into_iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    entry.updated_at = OffsetDateTime::now_utc();
+}
+
+/// Find the record a link points to by ID, or by protocol name or DOI
+fn resolve_link(storage: &StorageManager, link: JournalLink) -> Result<ResolvedJournalLink> {
+    let target = link.target.as_str();
+    let mut resolved = ResolvedJournalLink {
+        link: link.clone(),
+        entity_id: None,
+        title: None,
+        protocol_id: None,
+    };
+
+    match link.kind {
+        JournalLinkKind::Protocol => {
+            let protocol = match storage.get_protocol(target)? {
+                Some(protocol) => Some(protocol),
+                None => storage
+                    .list_protocols()?
+                    .into_iter()
+                    .find(|protocol| protocol.name.trim().eq_ignore_ascii_case(target)),
+            };
+            if let Some(protocol) = protocol {
+                resolved.entity_id = Some(protocol.id.clone());
+                resolved.protocol_id = Some(protocol.id);
+                resolved.title = Some(protocol.name);
+            }
+        }
+        JournalLinkKind::Dose => {
+            if let Some(log) = storage.get_dose_log(target)? {
+                resolved.entity_id = Some(log.id);
+                resolved.title = Some(format!(
+                    "{} mg dose on {} ({})",
+                    log.amount_mg,
+                    log.logged_at.date(),
+                    log.site
+                ));
+                resolved.protocol_id = Some(log.protocol_id);
+            }
+        }
+        JournalLinkKind::Paper => {
+            let doi = normalize_doi(target);
+            if let Some(paper) = storage
+                .list_literature()?
+                .into_iter()
+                .find(|paper| paper.id == target || paper.doi.as_deref() == Some(doi.as_str()))
+            {
+                resolved.entity_id = Some(paper.id);
+                resolved.title = Some(paper.title);
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// Everything a record can be linked by: its ID, plus a protocol's name or a
+/// paper's DOI
+fn link_keys(
+    storage: &StorageManager,
+    kind: JournalLinkKind,
+    entity_id: &str,
+) -> Result<Vec<String>> {
+    let mut keys = vec![entity_id.to_string()];
+    match kind {
+        JournalLinkKind::Protocol => {
+            if let Some(protocol) = storage.get_protocol(entity_id)? {
+                keys.push(protocol.name);
+            }
+        }
+        JournalLinkKind::Paper => {
+            let doi = storage
+                .list_literature()?
+                .into_iter()
+                .find(|paper| paper.id == entity_id)
+                .and_then(|paper| paper.doi);
+            keys.extend(doi);
+        }
+        JournalLinkKind::Dose => {}
+    }
+    Ok(keys)
+}
+
+// ========== Journal Commands ==========
+
+#[tauri::command]
+pub async fn create_journal_entry(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: JournalEntryPayload,
+) -> Result<JournalEntry, CommandError> {
+    let entry_date = validate_payload(&payload)?;
+    info!("Creating journal entry for {}", entry_date.date());
+
+    let mut entry = JournalEntry::new(String::new(), entry_date);
+    apply_payload(&mut entry, payload, entry_date);
+
+    state
+        .db
+        .run(move |storage| storage.upsert_journal_entry(&entry).map(|_| entry))
+        .await
+        .map_err(|e| {
+            error!("Failed to save journal entry: {:#}", e);
+            CommandError::with_context(e, "Failed to save journal entry")
+        })
+}
+
+/// List journal entries, newest first, optionally only those with `tag`
+#[tauri::command]
+pub async fn list_journal_entries(
+    state: State<'_, std::sync::Arc<AppState>>,
+    tag: Option<String>,
+) -> Result<Vec<JournalEntry>, CommandError> {
+    let entries = state
+        .db
+        .run(|storage| storage.list_journal_entries())
+        .await
+        .map_err(|e| {
+            error!("Failed to list journal entries: {:#}", e);
+            CommandError::with_context(e, "Failed to list journal entries")
+        })?;
+
+    Ok(match tag.as_deref().map(str::trim).filter(|tag| !tag.is_empty()) {
+        Some(tag) => entries
+            .into_iter()
+            .filter(|entry| entry.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+            .collect(),
+        None => entries,
+    })
+}
+
+#[tauri::command]
+pub async fn get_journal_entry(
+    state: State<'_, std::sync::Arc<AppState>>,
+    entry_id: String,
+) -> Result<Option<JournalEntry>, CommandError> {
+    state
+        .db
+        .run(move |storage| storage.get_journal_entry(&entry_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to get journal entry: {:#}", e);
+            CommandError::with_context(e, "Failed to get journal entry")
+        })
+}
+
+#[tauri::command]
+pub async fn update_journal_entry(
+    state: State<'_, std::sync::Arc<AppState>>,
+    entry_id: String,
+    payload: JournalEntryPayload,
+) -> Result<JournalEntry, CommandError> {
+    let entry_date = validate_payload(&payload)?;
+
+    let mut entry = state
+        .db
+        .run(move |storage| storage.get_journal_entry(&entry_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch journal entry"))?
+        .ok_or_else(|| CommandError::not_found("Journal entry not found"))?;
+    apply_payload(&mut entry, payload, entry_date);
+
+    state
+        .db
+        .run(move |storage| storage.upsert_journal_entry(&entry).map(|_| entry))
+        .await
+        .map_err(|e| {
+            error!("Failed to update journal entry: {:#}", e);
+            CommandError::with_context(e, "Failed to update journal entry")
+        })
+}
+
+#[tauri::command]
+pub async fn delete_journal_entry(
+    state: State<'_, std::sync::Arc<AppState>>,
+    entry_id: String,
+) -> Result<(), CommandError> {
+    info!("Deleting journal entry: {}", entry_id);
+
+    state
+        .db
+        .run(move |storage| storage.delete_journal_entry(&entry_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to delete journal entry: {:#}", e);
+            CommandError::with_context(e, "Failed to delete journal entry")
+        })
+}
+
+/// The links in an entry, with the records they point to
+#[tauri::command]
+pub async fn get_journal_entry_links(
+    state: State<'_, std::sync::Arc<AppState>>,
+    entry_id: String,
+) -> Result<Vec<ResolvedJournalLink>, CommandError> {
+    let entry = state
+        .db
+        .run(move |storage| storage.get_journal_entry(&entry_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch journal entry"))?
+        .ok_or_else(|| CommandError::not_found("Journal entry not found"))?;
+
+    state
+        .db
+        .run(move |storage| {
+            parse_links(&entry.body)
+                .into_iter()
+                .map(|link| resolve_link(storage, link))
+                .collect::<Result<Vec<_>>>()
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to resolve journal links: {:#}", e);
+            CommandError::with_context(e, "Failed to resolve journal links")
+        })
+}
+
+/// Journal entries that link to a protocol, dose or paper, newest first
+#[tauri::command]
+pub async fn list_journal_backlinks(
+    state: State<'_, std::sync::Arc<AppState>>,
+    kind: JournalLinkKind,
+    entity_id: String,
+) -> Result<Vec<JournalEntry>, CommandError> {
+    let keys = state
+        .db
+        .run(move |storage| link_keys(storage, kind, &entity_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to load linked record"))?;
+
+    state
+        .db
+        .run(move |storage| {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            storage.list_journal_backlinks(kind, &keys)
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to list journal backlinks: {:#}", e);
+            CommandError::with_context(e, "Failed to list journal backlinks")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_trims_title_and_tags() {
+        let payload = JournalEntryPayload {
+            title: Some("   ".to_string()),
+            body: "Felt fine".to_string(),
+            entry_date: "2025-03-01T08:00:00Z".to_string(),
+            tags: vec![" sleep ".to_string(), "".to_string()],
+        };
+        let entry_date = validate_payload(&payload).unwrap();
+
+        let mut entry = JournalEntry::new(String::new(), entry_date);
+        apply_payload(&mut entry, payload, entry_date);
+        assert!(entry.title.is_none());
+        assert_eq!(entry.tags, vec!["sleep".to_string()]);
+    }
+
+    #[test]
+    fn test_empty_body_is_rejected() {
+        let payload = JournalEntryPayload {
+            title: None,
+            body: " \n".to_string(),
+            entry_date: "2025-03-01T08:00:00Z".to_string(),
+            tags: Vec::new(),
+        };
+        assert!(validate_payload(&payload).is_err());
+    }
+}
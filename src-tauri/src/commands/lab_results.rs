@@ -0,0 +1,550 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use peptrack_core::{DoseLog, LabResult, PeptideProtocol, RangeStatus};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use time::{Duration, OffsetDateTime};
+use tracing::{error, info};
+
+use crate::commands::dates::parse_datetime;
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Doses further apart than this start a new protocol period (cycle)
+const DEFAULT_PERIOD_GAP_DAYS: i64 = 7;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LabResultPayload {
+    pub marker: String,
+    pub value: f32,
+    pub unit: String,
+    pub reference_low: Option<f32>,
+    pub reference_high: Option<f32>,
+    pub collected_at: String, // ISO 8601 string
+    pub lab_name: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LabTrendPoint {
+    pub result_id: String,
+    pub collected_at: String,
+    pub value: f32,
+    pub unit: String,
+    pub range_status: RangeStatus,
+    pub reference_low: Option<f32>,
+    pub reference_high: Option<f32>,
+}
+
+/// A marker's results over time
+///
+/// Summary figures only use results in the most recent unit, since values
+/// reported in different units (e.g. ng/mL and nmol/L) are not comparable.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LabTrend {
+    pub marker: String,
+    pub unit: Option<String>,
+    pub points: Vec<LabTrendPoint>,
+    pub latest: Option<f32>,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+    /// Latest value minus the first value
+    pub change: Option<f32>,
+    pub percent_change: Option<f32>,
+}
+
+/// A continuous stretch of dosing on one protocol
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolPeriod {
+    pub start: String,
+    pub end: String,
+    pub dose_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolCorrelation {
+    pub protocol_id: String,
+    pub protocol_name: String,
+    pub periods: Vec<ProtocolPeriod>,
+    /// Results collected during a period (or within the gap after it)
+    pub on_count: usize,
+    pub on_average: Option<f32>,
+    pub off_count: usize,
+    pub off_average: Option<f32>,
+    /// On-protocol average minus off-protocol average
+    pub difference: Option<f32>,
+}
+
+/// A marker's trend alongside the protocol periods it can be overlaid with
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LabCorrelation {
+    pub trend: LabTrend,
+    pub gap_days: i64,
+    pub protocols: Vec<ProtocolCorrelation>,
+}
+
+fn date_string(date: OffsetDateTime) -> String {
+    date.date().to_string()
+}
+
+fn validate_payload(payload: &LabResultPayload) -> Result<(), CommandError> {
+    if payload.marker.trim().is_empty() {
+        return Err(CommandError::invalid_input("Marker name is required"));
+    }
+    if !payload.value.is_finite() {
+        return Err(CommandError::invalid_input("Lab value must be a number"));
+    }
+    if let (Some(low), Some(high)) = (payload.reference_low, payload.reference_high) {
+        if low > high {
+            return Err(CommandError::invalid_input("Reference range low must not exceed high"));
+        }
+    }
+    Ok(())
+}
+
+fn apply_payload(result: &mut LabResult, payload: LabResultPayload) {
+    result.marker = payload.marker.trim().to_string();
+    result.value = payload.value;
+    result.unit = payload.unit.trim().to_string();
+    result.reference_low = payload.reference_low;
+    result.reference_high = payload.reference_high;
+    result.lab_name = payload.lab_name;
+    result.notes = payload.notes;
+    result.updated_at = OffsetDateTime::now_utc();
+}
+
+fn average(values: &[f32]) -> Option<f32> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f32>() / values.len() as f32)
+    }
+}
+
+/// Build a trend from results sorted oldest first
+fn build_lab_trend(marker: &str, results: &[LabResult]) -> LabTrend {
+    let points = results
+        .iter()
+        .map(|result| LabTrendPoint {
+            result_id: result.id.clone(),
+            collected_at: date_string(result.collected_at),
+            value: result.value,
+            unit: result.unit.clone(),
+            range_status: result.range_status(),
+            reference_low: result.reference_low,
+            reference_high: result.reference_high,
+        })
+        .collect();
+
+    let unit = results.last().map(|result| result.unit.clone());
+    let comparable: Vec<f32> = results
+        .iter()
+        .filter(|result| Some(&result.unit) == unit.as_ref())
+        .map(|result| result.value)
+        .collect();
+
+    let first = comparable.first().copied();
+    let latest = comparable.last().copied();
+    let change = first.zip(latest).map(|(first, latest)| latest - first);
+    let percent_change = first
+        .zip(change)
+        .filter(|(first, _)| *first != 0.0)
+        .map(|(first, change)| change / first * 100.0);
+
+    LabTrend {
+        marker: marker.to_string(),
+        unit,
+        points,
+        latest,
+        min: comparable.iter().copied().reduce(f32::min),
+        max: comparable.iter().copied().reduce(f32::max),
+        change: if comparable.len() > 1 { change } else { None },
+        percent_change: if comparable.len() > 1 { percent_change } else { None },
+    }
+}
+
+/// Split a protocol's dose times into periods, starting a new period whenever
+/// the gap between doses exceeds `gap_days`
+fn build_periods(mut times: Vec<OffsetDateTime>, gap_days: i64) -> Vec<(OffsetDateTime, OffsetDateTime, usize)> {
+    times.sort();
+
+    let mut periods: Vec<(OffsetDateTime, OffsetDateTime, usize)> = Vec::new();
+    for time in times {
+        match periods.last_mut() {
+            Some((_, end, count)) if time - *end <= Duration::days(gap_days) => {
+                *end = time;
+                *count += 1;
+            }
+            _ => periods.push((time, time, 1)),
+        }
+    }
+    periods
+}
+
+/// Everything a correlation is computed from
+struct CorrelationInputs<'a> {
+    marker: &'a str,
+    results: &'a [LabResult],
+    protocols: &'a [PeptideProtocol],
+    doses: &'a [DoseLog],
+}
+
+/// Compare a marker's values during each protocol's dosing periods with its
+/// values outside them
+///
+/// A result counts as "on" a protocol if it was collected between the start of
+/// a period and `gap_days` after its last dose. Only results in the trend's
+/// current unit are averaged.
+fn build_lab_correlation(inputs: &CorrelationInputs, gap_days: i64) -> LabCorrelation {
+    let trend = build_lab_trend(inputs.marker, inputs.results);
+
+    let mut dose_times: HashMap<&str, Vec<OffsetDateTime>> = HashMap::new();
+    for dose in inputs.doses {
+        dose_times
+            .entry(dose.protocol_id.as_str())
+            .or_default()
+            .push(dose.logged_at);
+    }
+
+    let comparable: Vec<&LabResult> = inputs
+        .results
+        .iter()
+        .filter(|result| Some(&result.unit) == trend.unit.as_ref())
+        .collect();
+
+    let mut protocols: Vec<ProtocolCorrelation> = inputs
+        .protocols
+        .iter()
+        .filter_map(|protocol| {
+            let times = dose_times.remove(protocol.id.as_str())?;
+            let periods = build_periods(times, gap_days);
+
+            let (on, off): (Vec<&LabResult>, Vec<&LabResult>) =
+                comparable.iter().partition(|result| {
+                    periods.iter().any(|(start, end, _)| {
+                        result.collected_at >= *start
+                            && result.collected_at <= *end + Duration::days(gap_days)
+                    })
+                });
+            let on: Vec<f32> = on.iter().map(|result| result.value).collect();
+            let off: Vec<f32> = off.iter().map(|result| result.value).collect();
+            let on_average = average(&on);
+            let off_average = average(&off);
+
+            Some(ProtocolCorrelation {
+                protocol_id: protocol.id.clone(),
+                protocol_name: protocol.name.clone(),
+                periods: periods
+                    .iter()
+                    .map(|(start, end, dose_count)| ProtocolPeriod {
+                        start: date_string(*start),
+                        end: date_string(*end),
+                        dose_count: *dose_count,
+                    })
+                    .collect(),
+                on_count: on.len(),
+                on_average,
+                off_count: off.len(),
+                off_average,
+                difference: on_average.zip(off_average).map(|(on, off)| on - off),
+            })
+        })
+        .collect();
+
+    protocols.sort_by(|a, b| a.protocol_name.cmp(&b.protocol_name));
+
+    LabCorrelation {
+        trend,
+        gap_days,
+        protocols,
+    }
+}
+
+// ========== Lab Result Commands ==========
+
+/// Log a new lab result
+#[tauri::command]
+pub async fn log_lab_result(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: LabResultPayload,
+) -> Result<LabResult, CommandError> {
+    validate_payload(&payload)?;
+    let collected_at = parse_datetime(&payload.collected_at)?;
+    info!("Logging lab result: {}", payload.marker.trim());
+
+    let mut result = LabResult::new(
+        payload.marker.trim(),
+        payload.value,
+        payload.unit.trim(),
+        collected_at,
+    );
+    apply_payload(&mut result, payload);
+
+    state
+        .db
+        .run(move |storage| storage.upsert_lab_result(&result).map(|_| result))
+        .await
+        .map_err(|e| {
+            error!("Failed to save lab result: {:#}", e);
+            CommandError::with_context(e, "Failed to save lab result")
+        })
+}
+
+/// List all lab results, most recent first
+#[tauri::command]
+pub async fn list_lab_results(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<Vec<LabResult>, CommandError> {
+    state
+        .db
+        .run(|storage| storage.list_lab_results())
+        .await
+        .map_err(|e| {
+            error!("Failed to list lab results: {:#}", e);
+            CommandError::with_context(e, "Failed to list lab results")
+        })
+}
+
+#[tauri::command]
+pub async fn get_lab_result(
+    state: State<'_, std::sync::Arc<AppState>>,
+    result_id: String,
+) -> Result<Option<LabResult>, CommandError> {
+    state
+        .db
+        .run(move |storage| storage.get_lab_result(&result_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to get lab result: {:#}", e);
+            CommandError::with_context(e, "Failed to get lab result")
+        })
+}
+
+#[tauri::command]
+pub async fn update_lab_result(
+    state: State<'_, std::sync::Arc<AppState>>,
+    result_id: String,
+    payload: LabResultPayload,
+) -> Result<LabResult, CommandError> {
+    validate_payload(&payload)?;
+
+    let mut result = state
+        .db
+        .run(move |storage| storage.get_lab_result(&result_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch lab result"))?
+        .ok_or_else(|| CommandError::not_found("Lab result not found"))?;
+
+    result.collected_at = parse_datetime(&payload.collected_at)?;
+    apply_payload(&mut result, payload);
+
+    state
+        .db
+        .run(move |storage| storage.upsert_lab_result(&result).map(|_| result))
+        .await
+        .map_err(|e| {
+            error!("Failed to update lab result: {:#}", e);
+            CommandError::with_context(e, "Failed to update lab result")
+        })
+}
+
+#[tauri::command]
+pub async fn delete_lab_result(
+    state: State<'_, std::sync::Arc<AppState>>,
+    result_id: String,
+) -> Result<(), CommandError> {
+    info!("Deleting lab result: {}", result_id);
+
+    state
+        .db
+        .run(move |storage| storage.delete_lab_result(&result_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to delete lab result: {:#}", e);
+            CommandError::with_context(e, "Failed to delete lab result")
+        })
+}
+
+/// List the markers that have at least one result
+#[tauri::command]
+pub async fn list_lab_markers(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<Vec<String>, CommandError> {
+    state
+        .db
+        .run(|storage| storage.list_lab_markers())
+        .await
+        .map_err(|e| {
+            error!("Failed to list lab markers: {:#}", e);
+            CommandError::with_context(e, "Failed to list lab markers")
+        })
+}
+
+/// Get one marker's results over time
+#[tauri::command]
+pub async fn get_lab_trend(
+    state: State<'_, std::sync::Arc<AppState>>,
+    marker: String,
+) -> Result<LabTrend, CommandError> {
+    let query = marker.clone();
+    let results = state
+        .db
+        .run(move |storage| storage.list_lab_results_for_marker(&query))
+        .await
+        .map_err(|e| {
+            error!("Failed to load lab trend: {:#}", e);
+            CommandError::with_context(e, "Failed to load lab trend")
+        })?;
+
+    Ok(build_lab_trend(marker.trim(), &results))
+}
+
+/// Overlay a marker's trend with protocol dosing periods
+///
+/// `protocol_ids` limits the comparison to specific protocols (all protocols
+/// with doses by default). `gap_days` is the longest break between doses
+/// that still counts as the same period.
+#[tauri::command]
+pub async fn get_lab_correlation(
+    state: State<'_, std::sync::Arc<AppState>>,
+    marker: String,
+    protocol_ids: Option<Vec<String>>,
+    gap_days: Option<i64>,
+) -> Result<LabCorrelation, CommandError> {
+    let gap_days = gap_days.unwrap_or(DEFAULT_PERIOD_GAP_DAYS);
+    if gap_days < 1 {
+        return Err(CommandError::invalid_input("Gap days must be at least 1"));
+    }
+
+    let query = marker.clone();
+    let (results, mut protocols, doses) = state
+        .db
+        .run(move |storage| {
+            let results = storage
+                .list_lab_results_for_marker(&query)
+                .context("Failed to load lab results")?;
+            let protocols = storage.list_protocols().context("Failed to load protocols")?;
+            let doses = storage.list_dose_logs().context("Failed to load dose logs")?;
+            Ok((results, protocols, doses))
+        })
+        .await
+        .map_err(CommandError::from)?;
+
+    if let Some(ids) = protocol_ids {
+        protocols.retain(|protocol| ids.contains(&protocol.id));
+    }
+
+    Ok(build_lab_correlation(
+        &CorrelationInputs {
+            marker: marker.trim(),
+            results: &results,
+            protocols: &protocols,
+            doses: &doses,
+        },
+        gap_days,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn day(n: i64) -> OffsetDateTime {
+        datetime!(2024-01-01 09:00 UTC) + Duration::days(n)
+    }
+
+    fn result(value: f32, unit: &str, day_offset: i64) -> LabResult {
+        LabResult::new("IGF-1", value, unit, day(day_offset))
+    }
+
+    #[test]
+    fn test_trend_summarizes_latest_unit_only() {
+        let results = vec![
+            result(20.0, "nmol/L", 0),
+            result(150.0, "ng/mL", 30),
+            result(210.0, "ng/mL", 60),
+        ];
+
+        let trend = build_lab_trend("IGF-1", &results);
+
+        assert_eq!(trend.points.len(), 3);
+        assert_eq!(trend.unit.as_deref(), Some("ng/mL"));
+        assert_eq!(trend.latest, Some(210.0));
+        assert_eq!(trend.min, Some(150.0));
+        assert_eq!(trend.change, Some(60.0));
+        assert!((trend.percent_change.unwrap() - 40.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_periods_split_on_gaps() {
+        let times = vec![day(0), day(3), day(6), day(30), day(32)];
+
+        let periods = build_periods(times, 7);
+
+        assert_eq!(periods.len(), 2);
+        assert_eq!(periods[0], (day(0), day(6), 3));
+        assert_eq!(periods[1], (day(30), day(32), 2));
+    }
+
+    #[test]
+    fn test_correlation_compares_on_and_off_protocol_values() {
+        let protocol = PeptideProtocol::new("CJC/Ipa", "CJC-1295");
+        let other = PeptideProtocol::new("Unused", "BPC-157");
+        let doses: Vec<DoseLog> = (0..30)
+            .map(|n| {
+                let mut dose = DoseLog::new(protocol.id.as_str(), "abdomen", 0.1);
+                dose.logged_at = day(30 + n);
+                dose
+            })
+            .collect();
+        let results = vec![
+            result(150.0, "ng/mL", 0),  // baseline, before the cycle
+            result(250.0, "ng/mL", 50), // mid-cycle
+            result(270.0, "ng/mL", 63), // within the gap after the last dose
+            result(160.0, "ng/mL", 120),
+        ];
+
+        let correlation = build_lab_correlation(
+            &CorrelationInputs {
+                marker: "IGF-1",
+                results: &results,
+                protocols: &[protocol.clone(), other],
+                doses: &doses,
+            },
+            7,
+        );
+
+        assert_eq!(correlation.protocols.len(), 1);
+        let cycle = &correlation.protocols[0];
+        assert_eq!(cycle.periods.len(), 1);
+        assert_eq!(cycle.periods[0].dose_count, 30);
+        assert_eq!(cycle.on_count, 2);
+        assert_eq!(cycle.on_average, Some(260.0));
+        assert_eq!(cycle.off_average, Some(155.0));
+        assert_eq!(cycle.difference, Some(105.0));
+    }
+
+    #[test]
+    fn test_lab_result_payload_deserialization() {
+        let json = r#"{
+            "marker": "LDL Cholesterol",
+            "value": 112.5,
+            "unit": "mg/dL",
+            "referenceHigh": 100,
+            "collectedAt": "2024-03-01T08:00:00Z"
+        }"#;
+
+        let payload: LabResultPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.reference_high, Some(100.0));
+        assert!(payload.reference_low.is_none());
+        assert!(validate_payload(&payload).is_ok());
+    }
+}
@@ -0,0 +1,68 @@
+use peptrack_core::{
+    parse_vial_qr, render_vial_label, InventoryItem, PeptideProtocol, StorageManager, VialLabel,
+};
+use serde::Serialize;
+use tauri::State;
+use tracing::{error, info};
+
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// A scanned vial with the protocol it belongs to
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedVial {
+    pub item: InventoryItem,
+    /// `None` when the vial's protocol has been deleted
+    pub protocol: Option<PeptideProtocol>,
+}
+
+/// Printable label for a vial, with a QR code that resolves back to it
+#[tauri::command]
+pub async fn generate_vial_label(
+    state: State<'_, std::sync::Arc<AppState>>,
+    item_id: String,
+) -> Result<VialLabel, CommandError> {
+    let (item, protocol) = state
+        .on_storage(move |storage| load_vial(storage, &item_id))
+        .await?;
+    let protocol_name = protocol.map(|protocol| protocol.name).unwrap_or_else(|| "Vial".to_string());
+
+    render_vial_label(&item, &protocol_name).map_err(|e| {
+        error!("Failed to render label for inventory item {}: {:#}", item.id, e);
+        CommandError::with_context(e, "Failed to generate vial label")
+    })
+}
+
+/// Looks up the vial behind scanned label text
+///
+/// Accepts a vial QR code's content or a bare inventory id typed in by hand.
+#[tauri::command]
+pub async fn resolve_vial_qr(
+    state: State<'_, std::sync::Arc<AppState>>,
+    content: String,
+) -> Result<ResolvedVial, CommandError> {
+    let item_id = parse_vial_qr(&content)
+        .map(str::to_string)
+        .ok_or_else(|| CommandError::invalid_input("This code is not a PepTrack vial label"))?;
+    info!("Resolving scanned vial {}", item_id);
+
+    let (item, protocol) = state
+        .on_storage(move |storage| load_vial(storage, &item_id))
+        .await?;
+    Ok(ResolvedVial { item, protocol })
+}
+
+fn load_vial(storage: &StorageManager, item_id: &str) -> Result<(InventoryItem, Option<PeptideProtocol>), CommandError> {
+    let item = storage
+        .get_inventory_item(item_id)
+        .map_err(|e| {
+            error!("Failed to fetch inventory item {}: {:#}", item_id, e);
+            CommandError::with_context(e, "Failed to fetch inventory item")
+        })?
+        .ok_or_else(|| CommandError::not_found("No vial matches this label; it may have been deleted"))?;
+    let protocol = storage
+        .get_protocol(&item.protocol_id)
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch protocol"))?;
+    Ok((item, protocol))
+}
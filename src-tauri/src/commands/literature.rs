@@ -1,9 +1,17 @@
 use anyhow::Result;
-use peptrack_core::models::LiteratureEntry;
-use peptrack_literature::{CrossrefFetcher, LiteratureFetcher, OpenAlexFetcher, PubMedFetcher};
+use peptrack_core::models::{LiteratureEntry, LiteratureRetention, ReadingStatus};
+use peptrack_core::{ResponseCacheSettings, ResponseCacheStats};
+use peptrack_literature::{
+    normalize_doi, CachedFetcher, CrossrefFetcher, EuropePmcFetcher, LiteratureFetcher,
+    LiteratureResult, MetadataEnricher, OpenAlexFetcher, PubMedFetcher, RelevanceContext,
+};
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{AppHandle, State};
+use time::OffsetDateTime;
+use tracing::{error, info, warn};
 
+use crate::commands::settings::{load_setting_or_default, save_setting};
+use crate::error::{CommandError, ErrorKind};
 use crate::state::AppState;
 
 /// Result from a literature search across multiple sources
@@ -11,7 +19,36 @@ use crate::state::AppState;
 #[serde(rename_all = "camelCase")]
 pub struct LiteratureSearchResult {
     pub source: String,
-    pub results: Vec<peptrack_literature::LiteratureResult>,
+    /// Results in the order the source ranked them
+    pub results: Vec<LiteratureResult>,
+    /// The cached entry for each result, with relevance score and tags
+    pub entries: Vec<LiteratureEntry>,
+    /// The results are cached papers, shown because the APIs couldn't be
+    /// reached
+    pub offline: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiteratureTagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Entries looked up by one `enrich_literature` call unless a limit is given
+const DEFAULT_ENRICHMENT_BATCH: usize = 25;
+
+/// Outcome of a metadata enrichment pass
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnrichmentSummary {
+    pub checked: usize,
+    /// Entries that gained a DOI, authors, journal or year
+    pub enriched: usize,
+    /// Entries no source had anything new for; these aren't retried
+    pub not_found: usize,
+    /// Entries whose lookups all failed; these are retried next time
+    pub failed: usize,
 }
 
 /// Request to search for literature
@@ -20,84 +57,632 @@ pub struct LiteratureSearchResult {
 pub struct SearchLiteraturePayload {
     pub query: String,
     pub max_results: Option<usize>,
-    pub sources: Option<Vec<String>>, // ["pubmed", "openalex", "crossref"]
+    pub sources: Option<Vec<String>>, // ["pubmed", "openalex", "crossref", "europepmc"]
+    /// Call the APIs even if the same search was cached recently
+    #[serde(default)]
+    pub bypass_cache: bool,
+}
+
+/// Reading list state for a cached entry; every field is replaced
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateReadingPayload {
+    /// `None` takes the entry off the reading list
+    pub status: Option<ReadingStatus>,
+    pub rating: Option<u8>,
+    pub notes: Option<String>,
+}
+
+/// Filters on the pin and reading list state, shared by the list commands
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadingFilter {
+    /// Only pinned entries
+    #[serde(default)]
+    pub pinned_only: bool,
+    /// Only reading list entries with this status
+    pub reading_status: Option<ReadingStatus>,
+}
+
+impl ReadingFilter {
+    fn matches(&self, entry: &LiteratureEntry) -> bool {
+        (!self.pinned_only || entry.is_pinned)
+            && self
+                .reading_status
+                .is_none_or(|status| entry.reading_status == Some(status))
+    }
+}
+
+/// Fill in citation counts from OpenAlex for results whose source doesn't
+/// report them
+///
+/// Citations only refine the relevance score, so a failed lookup is logged
+/// and the results are scored without them.
+async fn fill_citation_counts(results: &mut [LiteratureResult]) {
+    let dois: Vec<String> = results
+        .iter()
+        .filter(|result| result.citation_count.is_none())
+        .filter_map(|result| result.doi.clone())
+        .collect();
+    if dois.is_empty() {
+        return;
+    }
+
+    match OpenAlexFetcher::new().citation_counts(&dois).await {
+        Ok(counts) => {
+            for result in results.iter_mut().filter(|result| result.citation_count.is_none()) {
+                result.citation_count = result
+                    .doi
+                    .as_deref()
+                    .and_then(|doi| counts.get(&normalize_doi(doi)).copied());
+            }
+        }
+        Err(e) => warn!("Failed to look up citation counts: {:#}", e),
+    }
+}
+
+fn fetcher_for(source_name: &str) -> Result<Box<dyn LiteratureFetcher>, CommandError> {
+    match source_name {
+        "pubmed" => Ok(Box::new(PubMedFetcher::new())),
+        "openalex" => Ok(Box::new(OpenAlexFetcher::new())),
+        "crossref" => Ok(Box::new(CrossrefFetcher::new())),
+        "europepmc" => Ok(Box::new(EuropePmcFetcher::new())),
+        _ => Err(CommandError::invalid_input(format!(
+            "Unknown source: {}",
+            source_name
+        ))),
+    }
+}
+
+/// Runs `query` against each source, with citation counts filled in
+///
+/// Responses come from the response cache when it's enabled and the same
+/// search ran within its TTL, unless `bypass_cache` is set. A source that
+/// fails is logged and left out so the others still return results; an
+/// unknown source name is an error.
+pub(crate) async fn search_sources(
+    state: &AppState,
+    query: &str,
+    sources: &[String],
+    max_results: usize,
+    bypass_cache: bool,
+) -> Result<Vec<(String, Vec<LiteratureResult>)>, CommandError> {
+    let cache: ResponseCacheSettings = load_setting_or_default(state).await;
+    let fetchers = sources
+        .iter()
+        .map(|source_name| {
+            let fetcher = fetcher_for(source_name)?;
+            let fetcher: Box<dyn LiteratureFetcher> = if cache.enabled {
+                Box::new(CachedFetcher::new(fetcher, state.db.clone(), cache.ttl()).bypass_cache(bypass_cache))
+            } else {
+                fetcher
+            };
+            Ok((source_name, fetcher))
+        })
+        .collect::<Result<Vec<_>, CommandError>>()?;
+
+    let mut all_results = Vec::new();
+    let mut probed = false;
+    for (source_name, fetcher) in fetchers {
+        match fetcher.search(query, max_results).await {
+            Ok(mut results) => {
+                fill_citation_counts(&mut results).await;
+                all_results.push((source_name.clone(), results));
+            }
+            Err(e) => {
+                eprintln!("Failed to search {}: {:#}", source_name, e);
+                // One API being down doesn't mean the connection is, so
+                // check before the other sources are tried
+                if !probed && CommandError::from(e).kind == ErrorKind::Network {
+                    probed = true;
+                    if !state.connectivity.probe().await {
+                        break;
+                    }
+                }
+                // Continue with other sources even if one fails
+            }
+        }
+    }
+    Ok(all_results)
+}
+
+/// Cached entries matching every word of `query`, most relevant first
+///
+/// Words with a search operator, like the `+peptide` added to narrow API
+/// results, are left out.
+fn offline_matches(entries: Vec<LiteratureEntry>, query: &str, max_results: usize) -> Vec<LiteratureEntry> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .filter(|word| !word.starts_with(['+', '-']))
+        .map(str::to_lowercase)
+        .collect();
+    let mut matches: Vec<LiteratureEntry> = entries
+        .into_iter()
+        .filter(|entry| {
+            let text = [
+                Some(entry.title.as_str()),
+                entry.summary.as_deref(),
+                entry.journal.as_deref(),
+                Some(entry.tags.join(" ").as_str()),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+            words.iter().all(|word| text.contains(word.as_str()))
+        })
+        .collect();
+    matches.sort_by(|a, b| {
+        b.relevance_score
+            .unwrap_or(-1.0)
+            .total_cmp(&a.relevance_score.unwrap_or(-1.0))
+    });
+    matches.truncate(max_results);
+    matches
+}
+
+/// Cached papers matching `query`, for when the APIs can't be reached
+async fn offline_results(
+    state: &AppState,
+    query: &str,
+    max_results: usize,
+) -> Result<Vec<LiteratureSearchResult>, CommandError> {
+    let entries = state
+        .db
+        .run(|storage| storage.list_literature())
+        .await
+        .map_err(|e| {
+            error!("Failed to load cached literature: {:#}", e);
+            CommandError::with_context(e, "Failed to load cached literature")
+        })?;
+    let entries = offline_matches(entries, query, max_results);
+    info!("Offline: serving {} cached papers", entries.len());
+    Ok(vec![LiteratureSearchResult {
+        source: "cache".to_string(),
+        results: entries.iter().map(LiteratureResult::from_entry).collect(),
+        entries,
+        offline: true,
+    }])
+}
+
+/// Peptide names from the user's protocols, matched against results
+pub(crate) async fn protocol_peptide_names(state: &AppState) -> Vec<String> {
+    match state.db.run(|storage| storage.list_protocols()).await {
+        Ok(protocols) => protocols.into_iter().map(|p| p.peptide_name).collect(),
+        Err(e) => {
+            warn!("Failed to load protocols for literature scoring: {:#}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Saved cache retention settings, falling back to keeping everything
+pub async fn load_retention(state: &AppState) -> LiteratureRetention {
+    load_setting_or_default(state).await
 }
 
-/// Lists all cached literature entries
+pub(crate) async fn fetch_entry(
+    state: &AppState,
+    entry_id: &str,
+) -> Result<LiteratureEntry, CommandError> {
+    let id = entry_id.to_string();
+    state
+        .db
+        .run(move |storage| storage.get_literature(&id))
+        .await
+        .map_err(|e| {
+            error!("Failed to load literature entry {}: {:#}", entry_id, e);
+            CommandError::with_context(e, "Failed to load literature entry")
+        })?
+        .ok_or_else(|| CommandError::not_found("Literature entry not found"))
+}
+
+pub(crate) async fn save_entry(
+    state: &AppState,
+    entry: &LiteratureEntry,
+) -> Result<(), CommandError> {
+    let saved = entry.clone();
+    state
+        .db
+        .run(move |storage| storage.cache_literature(&saved))
+        .await
+        .map_err(|e| {
+            error!("Failed to save literature entry {}: {:#}", entry.id, e);
+            CommandError::with_context(e, "Failed to save literature entry")
+        })
+}
+
+/// Lists cached literature entries
+///
+/// With `tags`, only entries carrying all of them are returned, most
+/// relevant first. `filter` narrows the list to pinned entries or a
+/// reading list status.
 #[tauri::command]
 pub async fn list_literature(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<Vec<LiteratureEntry>, String> {
-    state
-        .storage
-        .list_literature()
-        .map_err(|err| err.to_string())
+    tags: Option<Vec<String>>,
+    filter: Option<ReadingFilter>,
+) -> Result<Vec<LiteratureEntry>, CommandError> {
+    let filter = filter.unwrap_or_default();
+    Ok(state
+        .db
+        .run(move |storage| match tags {
+            Some(tags) => storage.list_literature_tagged(&tags),
+            None => storage.list_literature(),
+        })
+        .await
+        .map_err(CommandError::from)?
+        .into_iter()
+        .filter(|entry| filter.matches(entry))
+        .collect())
 }
 
-/// Searches cached literature by query
+/// Searches cached literature by query, optionally limited to entries
+/// carrying all of `tags` and matching `filter`
 #[tauri::command]
 pub async fn search_cached_literature(
     state: State<'_, std::sync::Arc<AppState>>,
     query: String,
-) -> Result<Vec<LiteratureEntry>, String> {
+    tags: Option<Vec<String>>,
+    filter: Option<ReadingFilter>,
+) -> Result<Vec<LiteratureEntry>, CommandError> {
+    let tags = tags.unwrap_or_default();
+    let filter = filter.unwrap_or_default();
+    Ok(state
+        .db
+        .run(move |storage| storage.search_literature(&query))
+        .await
+        .map_err(CommandError::from)?
+        .into_iter()
+        .filter(|entry| tags.iter().all(|tag| entry.tags.contains(tag)))
+        .filter(|entry| filter.matches(entry))
+        .collect())
+}
+
+/// Pins or unpins a cached entry; pinned entries are never pruned
+#[tauri::command]
+pub async fn set_literature_pinned(
+    state: State<'_, std::sync::Arc<AppState>>,
+    entry_id: String,
+    pinned: bool,
+) -> Result<LiteratureEntry, CommandError> {
+    let mut entry = fetch_entry(&state, &entry_id).await?;
+    entry.is_pinned = pinned;
+    save_entry(&state, &entry).await?;
+    Ok(entry)
+}
+
+/// Sets the reading list status, rating and notes of a cached entry
+///
+/// Entries on the reading list are never pruned.
+#[tauri::command]
+pub async fn update_literature_reading(
+    state: State<'_, std::sync::Arc<AppState>>,
+    entry_id: String,
+    payload: UpdateReadingPayload,
+) -> Result<LiteratureEntry, CommandError> {
+    if payload.rating.is_some_and(|rating| !(1..=5).contains(&rating)) {
+        return Err(CommandError::invalid_input("Rating must be between 1 and 5"));
+    }
+
+    let mut entry = fetch_entry(&state, &entry_id).await?;
+    entry.reading_status = payload.status;
+    entry.rating = payload.rating;
+    entry.reading_notes = payload
+        .notes
+        .map(|notes| notes.trim().to_string())
+        .filter(|notes| !notes.is_empty());
+    save_entry(&state, &entry).await?;
+    Ok(entry)
+}
+
+/// Gets how long cached literature is kept
+#[tauri::command]
+pub async fn get_literature_retention(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<LiteratureRetention, CommandError> {
+    Ok(load_retention(&state).await)
+}
+
+/// Saves the cache retention settings and prunes entries outside them
+/// right away
+#[tauri::command]
+pub async fn update_literature_retention(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    retention: LiteratureRetention,
+) -> Result<usize, CommandError> {
+    save_setting(&app, &state, &retention).await?;
+
+    info!("Literature cache retention updated: {:?}", retention);
+    prune(&state, retention).await
+}
+
+/// Prunes cached literature outside the saved retention settings, keeping
+/// pinned and reading list entries
+#[tauri::command]
+pub async fn prune_literature_cache(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<usize, CommandError> {
+    prune(&state, load_retention(&state).await).await
+}
+
+async fn prune(state: &AppState, retention: LiteratureRetention) -> Result<usize, CommandError> {
+    state
+        .db
+        .run(move |storage| storage.prune_literature_cache(&retention))
+        .await
+        .map_err(|e| {
+            error!("Failed to prune literature cache: {:#}", e);
+            CommandError::with_context(e, "Failed to prune literature cache")
+        })
+}
+
+/// Gets whether and for how long literature API responses are cached
+#[tauri::command]
+pub async fn get_response_cache_settings(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<ResponseCacheSettings, CommandError> {
+    Ok(load_setting_or_default(&state).await)
+}
+
+/// Saves the response cache settings, dropping responses older than the new
+/// TTL (or all of them when the cache is turned off)
+#[tauri::command]
+pub async fn update_response_cache_settings(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    settings: ResponseCacheSettings,
+) -> Result<usize, CommandError> {
+    save_setting(&app, &state, &settings).await?;
+    info!("Literature response cache settings updated: {:?}", settings);
+    clear_responses(&state, settings.enabled.then(|| settings.ttl())).await
+}
+
+/// Size and hit counts of the response cache
+#[tauri::command]
+pub async fn get_response_cache_stats(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<ResponseCacheStats, CommandError> {
+    let settings: ResponseCacheSettings = load_setting_or_default(&state).await;
+    state
+        .db
+        .run(move |storage| storage.response_cache_stats(settings.ttl()))
+        .await
+        .map_err(|e| {
+            error!("Failed to read response cache stats: {:#}", e);
+            CommandError::with_context(e, "Failed to read response cache stats")
+        })
+}
+
+/// Drops every cached API response; cached papers are kept
+#[tauri::command]
+pub async fn clear_response_cache(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<usize, CommandError> {
+    clear_responses(&state, None).await
+}
+
+async fn clear_responses(
+    state: &AppState,
+    max_age: Option<time::Duration>,
+) -> Result<usize, CommandError> {
     state
-        .storage
-        .search_literature(&query)
-        .map_err(|err| err.to_string())
+        .db
+        .run(move |storage| storage.clear_response_cache(max_age))
+        .await
+        .map_err(|e| {
+            error!("Failed to clear response cache: {:#}", e);
+            CommandError::with_context(e, "Failed to clear response cache")
+        })
+}
+
+/// Lists the tags used in cached literature with how many entries carry each
+#[tauri::command]
+pub async fn list_literature_tags(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<Vec<LiteratureTagCount>, CommandError> {
+    Ok(state
+        .db
+        .run(|storage| storage.literature_tag_counts())
+        .await
+        .map_err(CommandError::from)?
+        .into_iter()
+        .map(|(tag, count)| LiteratureTagCount { tag, count })
+        .collect())
 }
 
 /// Searches external APIs for new literature and caches results
+///
+/// When the internet can't be reached, matching cached papers are returned
+/// instead, flagged as `offline`.
 #[tauri::command]
 pub async fn search_literature(
     state: State<'_, std::sync::Arc<AppState>>,
     payload: SearchLiteraturePayload,
-) -> Result<Vec<LiteratureSearchResult>, String> {
+) -> Result<Vec<LiteratureSearchResult>, CommandError> {
     let max_results = payload.max_results.unwrap_or(10);
     let sources = payload
         .sources
         .unwrap_or_else(|| vec!["pubmed".to_string(), "openalex".to_string()]);
 
-    let mut all_results = Vec::new();
+    let relevance = RelevanceContext::new(
+        &payload.query,
+        &protocol_peptide_names(&state).await,
+        OffsetDateTime::now_utc(),
+    );
+    let found = if state.connectivity.check().await {
+        search_sources(&state, &payload.query, &sources, max_results, payload.bypass_cache).await?
+    } else {
+        Vec::new()
+    };
+    if found.is_empty() && !state.connectivity.is_online() {
+        return offline_results(&state, &payload.query, max_results).await;
+    }
 
-    // Search each requested source
-    for source_name in sources {
-        let fetcher_result: Result<Box<dyn LiteratureFetcher>, String> = match source_name.as_str()
-        {
-            "pubmed" => Ok(Box::new(PubMedFetcher::new())),
-            "openalex" => Ok(Box::new(OpenAlexFetcher::new())),
-            "crossref" => Ok(Box::new(CrossrefFetcher::new())),
-            _ => Err(format!("Unknown source: {}", source_name)),
-        };
-
-        let fetcher = fetcher_result?;
-
-        match fetcher.search(&payload.query, max_results).await {
-            Ok(results) => {
-                // Cache all results
-                for result in &results {
-                    let entry = result.to_entry();
-                    if let Err(e) = state.storage.cache_literature(&entry) {
+    let mut all_results = Vec::new();
+    for (source_name, results) in found {
+        // Score, tag and cache all results
+        let entries: Vec<LiteratureEntry> = results
+            .iter()
+            .map(|result| relevance.to_entry(result))
+            .collect();
+        let cached = entries.clone();
+        let stored = state
+            .db
+            .run(move |storage| {
+                for entry in &cached {
+                    if let Err(e) = storage.cache_literature(entry) {
                         eprintln!("Failed to cache literature entry: {:#}", e);
                     }
                 }
+                Ok(())
+            })
+            .await;
+        if let Err(e) = stored {
+            eprintln!("Failed to cache literature entries: {:#}", e);
+        }
+
+        all_results.push(LiteratureSearchResult {
+            source: source_name,
+            results,
+            entries,
+            offline: false,
+        });
+    }
 
-                all_results.push(LiteratureSearchResult {
-                    source: source_name,
-                    results,
-                });
+    Ok(all_results)
+}
+
+/// Fill in missing DOIs, authors, journals and years of cached literature
+/// from Crossref, falling back to OpenAlex
+///
+/// Looks up at most `limit` entries that haven't been tried before.
+#[tauri::command]
+pub async fn enrich_literature(
+    state: State<'_, std::sync::Arc<AppState>>,
+    limit: Option<usize>,
+) -> Result<EnrichmentSummary, CommandError> {
+    let limit = limit.unwrap_or(DEFAULT_ENRICHMENT_BATCH);
+    let entries = state
+        .db
+        .run(move |storage| storage.list_literature_needing_enrichment(limit))
+        .await
+        .map_err(|e| {
+            error!("Failed to list literature for enrichment: {:#}", e);
+            CommandError::with_context(e, "Failed to list literature")
+        })?;
+
+    let enricher = MetadataEnricher::new();
+    let mut summary = EnrichmentSummary {
+        checked: entries.len(),
+        enriched: 0,
+        not_found: 0,
+        failed: 0,
+    };
+    for mut entry in entries {
+        match enricher.enrich(&mut entry).await {
+            Ok(changed) => {
+                if changed {
+                    summary.enriched += 1;
+                } else {
+                    summary.not_found += 1;
+                }
+                entry.enriched_at = Some(OffsetDateTime::now_utc());
+                state
+                    .db
+                    .run(move |storage| storage.cache_literature(&entry))
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to save enriched literature entry: {:#}", e);
+                        CommandError::with_context(e, "Failed to save literature entry")
+                    })?;
             }
             Err(e) => {
-                eprintln!("Failed to search {}: {:#}", source_name, e);
-                // Continue with other sources even if one fails
+                warn!("Failed to enrich \"{}\": {:#}", entry.title, e);
+                summary.failed += 1;
             }
         }
     }
 
-    Ok(all_results)
+    info!(
+        "Literature enrichment: {} checked, {} enriched, {} not found, {} failed",
+        summary.checked, summary.enriched, summary.not_found, summary.failed
+    );
+    Ok(summary)
+}
+
+/// Open-access full text of a cached paper from Europe PMC, for summarizing
+///
+/// None when the paper has no DOI or Europe PMC has no full text for it.
+#[tauri::command]
+pub async fn get_paper_full_text(
+    state: State<'_, std::sync::Arc<AppState>>,
+    entry_id: String,
+) -> Result<Option<String>, CommandError> {
+    let entry = fetch_entry(&state, &entry_id).await?;
+    let Some(doi) = entry.doi else {
+        return Ok(None);
+    };
+    EuropePmcFetcher::new().full_text_for_doi(&doi).await.map_err(|e| {
+        warn!("Europe PMC full text lookup failed for {}: {:#}", doi, e);
+        CommandError::with_context(e, "Failed to fetch the paper's full text")
+    })
 }
 
 /// Opens an external URL using the system default handler
 #[tauri::command]
-pub async fn open_external_url(url: String) -> Result<(), String> {
-    open::that(&url).map_err(|e| format!("Failed to open URL: {}", e))
+pub async fn open_external_url(url: String) -> Result<(), CommandError> {
+    open::that(&url).map_err(|e| CommandError::with_context(e, "Failed to open URL"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reading_filter_matches_pins_and_status() {
+        let mut pinned = LiteratureEntry::new("pubmed", "Pinned");
+        pinned.is_pinned = true;
+        let mut reading = LiteratureEntry::new("pubmed", "Reading");
+        reading.reading_status = Some(ReadingStatus::Reading);
+        let plain = LiteratureEntry::new("pubmed", "Plain");
+
+        let all = ReadingFilter::default();
+        assert!([&pinned, &reading, &plain].iter().all(|entry| all.matches(entry)));
+
+        let pinned_only: ReadingFilter = serde_json::from_str(r#"{"pinnedOnly": true}"#).unwrap();
+        assert!(pinned_only.matches(&pinned));
+        assert!(!pinned_only.matches(&reading));
+
+        let reading_only: ReadingFilter = serde_json::from_str(r#"{"readingStatus": "reading"}"#).unwrap();
+        assert!(reading_only.matches(&reading));
+        assert!(!reading_only.matches(&pinned));
+        assert!(!reading_only.matches(&plain));
+    }
+
+    #[test]
+    fn test_offline_matches_every_word_best_first() {
+        let mut tendon = LiteratureEntry::new("pubmed", "BPC-157 accelerates tendon healing");
+        tendon.relevance_score = Some(0.4);
+        let mut review = LiteratureEntry::new("openalex", "Gastric peptide review");
+        review.summary = Some("Covers BPC-157 and tendon repair".to_string());
+        review.relevance_score = Some(0.9);
+        let other = LiteratureEntry::new("pubmed", "TB-500 in cardiac repair");
+
+        let matches = offline_matches(vec![tendon, review, other], "bpc-157 Tendon +peptide", 10);
+        let titles: Vec<&str> = matches.iter().map(|entry| entry.title.as_str()).collect();
+        assert_eq!(titles, vec!["Gastric peptide review", "BPC-157 accelerates tendon healing"]);
+
+        let matches = offline_matches(
+            vec![
+                LiteratureEntry::new("pubmed", "BPC-157 one"),
+                LiteratureEntry::new("pubmed", "BPC-157 two"),
+            ],
+            "bpc-157",
+            1,
+        );
+        assert_eq!(matches.len(), 1);
+    }
 }
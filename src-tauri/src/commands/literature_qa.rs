@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use peptrack_core::models::{AiUsage, LiteratureEmbedding, LiteratureEntry};
+use peptrack_local_ai::qa::{self, cited_sources, DEFAULT_TOP_K, MAX_TOP_K};
+use peptrack_local_ai::{answer_question, detect_embedder, CorpusDocument, Embedder};
+use serde::Serialize;
+use tauri::State;
+use tracing::{error, info, warn};
+
+use crate::commands::ai::usage_record;
+use crate::error::{CommandError, ErrorKind};
+use crate::state::AppState;
+
+/// Abstracts sent to the embedder per request
+const EMBED_BATCH_SIZE: usize = 32;
+
+/// A retrieved paper and whether the answer cited it
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnswerSource {
+    /// Number the answer cites the paper by, e.g. 2 for "[2]"
+    pub number: usize,
+    pub entry_id: String,
+    pub title: String,
+    pub url: Option<String>,
+    pub doi: Option<String>,
+    /// Cosine similarity to the question
+    pub score: f32,
+    pub cited: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiteratureAnswer {
+    pub answer: String,
+    pub provider: String,
+    /// Embedding model used for retrieval
+    pub embedding_model: String,
+    pub sources: Vec<AnswerSource>,
+    pub usage: AiUsage,
+}
+
+/// Embed cached papers that have no embedding from `embedder` yet, or whose
+/// abstract changed since; returns the current embeddings by entry id
+async fn index_literature(
+    state: &AppState,
+    embedder: &dyn Embedder,
+    entries: &[LiteratureEntry],
+) -> Result<HashMap<String, LiteratureEmbedding>, CommandError> {
+    let model = embedder.model();
+    let listed = model.clone();
+    let mut embeddings: HashMap<String, LiteratureEmbedding> = state
+        .db
+        .run(move |storage| storage.list_literature_embeddings(&listed))
+        .await
+        .map_err(|e| {
+            error!("Failed to load literature embeddings: {:#}", e);
+            CommandError::with_context(e, "Failed to load literature embeddings")
+        })?
+        .into_iter()
+        .map(|embedding| (embedding.entry_id.clone(), embedding))
+        .collect();
+
+    let stale: Vec<(&LiteratureEntry, String, String)> = entries
+        .iter()
+        .map(|entry| {
+            let text = entry.embedding_text();
+            let hash = LiteratureEmbedding::content_hash(&text);
+            (entry, text, hash)
+        })
+        .filter(|(entry, _, hash)| {
+            embeddings
+                .get(&entry.id)
+                .is_none_or(|existing| &existing.content_hash != hash)
+        })
+        .collect();
+    if !stale.is_empty() {
+        info!("Embedding {} cached papers with {}", stale.len(), model);
+    }
+
+    for batch in stale.chunks(EMBED_BATCH_SIZE) {
+        let texts: Vec<String> = batch.iter().map(|(_, text, _)| text.clone()).collect();
+        let vectors = embedder.embed(&texts).await.map_err(|e| {
+            warn!("Embedding failed: {:#}", e);
+            CommandError::new(
+                ErrorKind::Network,
+                format!("Failed to embed cached literature: {}", e),
+            )
+        })?;
+
+        for ((entry, _, hash), vector) in batch.iter().zip(vectors) {
+            let embedding = LiteratureEmbedding {
+                entry_id: entry.id.clone(),
+                model: model.clone(),
+                content_hash: hash.clone(),
+                vector,
+            };
+            let embedding = state
+                .db
+                .run(move |storage| {
+                    storage.upsert_literature_embedding(&embedding).map(|_| embedding)
+                })
+                .await
+                .map_err(|e| {
+                    error!("Failed to save literature embedding: {:#}", e);
+                    CommandError::with_context(e, "Failed to save literature embedding")
+                })?;
+            embeddings.insert(entry.id.clone(), embedding);
+        }
+    }
+
+    Ok(embeddings)
+}
+
+fn passage(entry: &LiteratureEntry) -> CorpusDocument {
+    let mut citation = entry.journal.clone().unwrap_or_default();
+    if let Some(year) = entry.year {
+        citation = format!("{} ({})", citation, year).trim().to_string();
+    }
+    CorpusDocument {
+        title: entry.title.clone(),
+        citation: (!citation.is_empty()).then_some(citation),
+        text: entry.summary.clone().unwrap_or_default(),
+    }
+}
+
+/// Answer a question from the cached literature
+///
+/// Cached abstracts are embedded on first use (and again when they change),
+/// the `top_k` closest to the question are retrieved, and the AI answers
+/// from those alone, citing them by number.
+#[tauri::command]
+pub async fn ask_literature(
+    state: State<'_, Arc<AppState>>,
+    question: String,
+    top_k: Option<usize>,
+) -> Result<LiteratureAnswer, CommandError> {
+    let question = question.trim();
+    if question.is_empty() {
+        return Err(CommandError::invalid_input("Question cannot be empty"));
+    }
+    let k = top_k.unwrap_or(DEFAULT_TOP_K).clamp(1, MAX_TOP_K);
+
+    let entries = state
+        .db
+        .run(|storage| storage.list_literature())
+        .await
+        .map_err(|e| {
+            error!("Failed to load literature: {:#}", e);
+            CommandError::with_context(e, "Failed to load literature")
+        })?;
+    if entries.is_empty() {
+        return Err(CommandError::not_found(
+            "No cached literature yet; search for papers first",
+        ));
+    }
+
+    let embedder = detect_embedder().await;
+    let embeddings = index_literature(&state, embedder.as_ref(), &entries).await?;
+    let question_vector = embedder
+        .embed(&[question.to_string()])
+        .await
+        .ok()
+        .and_then(|mut vectors| vectors.pop())
+        .ok_or_else(|| CommandError::new(ErrorKind::Network, "Failed to embed the question"))?;
+
+    let ranked = qa::top_k(
+        &question_vector,
+        entries.iter().filter_map(|entry| {
+            embeddings
+                .get(&entry.id)
+                .map(|embedding| (entry, embedding.vector.as_slice()))
+        }),
+        k,
+    );
+    let passages: Vec<CorpusDocument> = ranked.iter().map(|(entry, _)| passage(entry)).collect();
+
+    let response = answer_question(state.ai_client.as_ref(), question, &passages)
+        .await
+        .map_err(|err| {
+            warn!("Literature question answering failed: {:#}", err);
+            CommandError::internal(format!(
+                "AI answer failed: {}. Make sure Codex CLI or Claude CLI is installed.",
+                err
+            ))
+        })?;
+
+    let cited = cited_sources(&response.raw_output, ranked.len());
+    let sources = ranked
+        .into_iter()
+        .enumerate()
+        .map(|(index, (entry, score))| AnswerSource {
+            number: index + 1,
+            entry_id: entry.id.clone(),
+            title: entry.title.clone(),
+            url: entry.url.clone(),
+            doi: entry.doi.clone(),
+            score,
+            cited: cited.contains(&(index + 1)),
+        })
+        .collect();
+
+    Ok(LiteratureAnswer {
+        answer: response.raw_output,
+        provider: format!("{:?}", response.provider),
+        embedding_model: embedder.model(),
+        sources,
+        usage: usage_record(response.usage),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passage_citation() {
+        let mut entry = LiteratureEntry::new("pubmed", "Paper");
+        assert_eq!(passage(&entry).citation, None);
+
+        entry.year = Some(2020);
+        assert_eq!(passage(&entry).citation.as_deref(), Some("(2020)"));
+
+        entry.journal = Some("Peptides".into());
+        entry.summary = Some("Abstract".into());
+        let passage = passage(&entry);
+        assert_eq!(passage.citation.as_deref(), Some("Peptides (2020)"));
+        assert_eq!(passage.text, "Abstract");
+    }
+}
@@ -1,15 +1,61 @@
+pub mod active_levels;
 pub mod ai;
 pub mod analytics;
+pub mod attachments;
+pub mod audit;
 pub mod backup;
+pub mod backup_journal;
 pub mod body_metrics;
+pub mod calendar;
+pub mod clinical_trials;
+pub mod connectivity;
+pub mod currency;
+pub mod dashboard;
+pub mod dates;
+pub mod data_import;
 pub mod defaults;
+pub mod diagnostics;
 pub mod doses;
 pub mod drive;
+pub mod email_digest;
+pub mod forecast;
+pub mod goals;
 pub mod health;
+pub mod health_bridge;
+pub mod health_import;
+pub mod interactions;
+pub mod journal;
+pub mod lab_results;
+pub mod labels;
 pub mod literature;
+pub mod literature_qa;
+pub mod network_folder;
+pub mod notifications;
+pub mod onboarding;
+pub mod orders;
+pub mod paper_pdfs;
+pub mod peptide_follows;
+pub mod preferences;
+pub mod price_monitor;
+pub mod protocol_sharing;
 pub mod protocols;
+pub mod recovery;
+pub mod reports;
 pub mod restore;
+pub mod retractions;
+pub mod saved_searches;
 pub mod schedules;
 pub mod scheduler_v2;
+pub mod scraping;
+pub mod search;
+pub mod security;
+pub mod settings;
 pub mod side_effects;
+pub mod spend;
+pub mod summary_queue;
+pub mod sync;
 pub mod suppliers;
+pub mod trash;
+pub mod undo;
+pub mod viewer;
+pub mod wipe;
@@ -0,0 +1,190 @@
+//! Backups to a network folder
+//!
+//! A network folder is any mounted path, typically an SMB or NFS share on a
+//! NAS. Unlike the Downloads folder it can disappear when the share isn't
+//! mounted, so it is checked before every backup: that it exists, that it
+//! can be written to and that it has room for the backup.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sysinfo::Disks;
+use tracing::{info, warn};
+
+use crate::commands::scheduler_v2::CleanupSettings;
+use crate::error::CommandError;
+
+/// File written and removed again to check that a folder can be written to
+const WRITE_PROBE_FILENAME: &str = ".peptrack_write_test";
+
+/// Where network folder backups go and how long they are kept
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkFolderSettings {
+    /// Absolute path of the mounted share
+    pub path: String,
+    /// Applied to the network folder only; local backups have their own
+    #[serde(default)]
+    pub cleanup_settings: CleanupSettings,
+}
+
+impl NetworkFolderSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        let path = self.path.trim();
+        if path.is_empty() {
+            return Err("Choose a network folder".to_string());
+        }
+        if !Path::new(path).is_absolute() {
+            return Err("The network folder must be an absolute path".to_string());
+        }
+        if self.cleanup_settings.keep_last_n == Some(0) {
+            return Err("Keep at least one backup in the network folder".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        PathBuf::from(self.path.trim())
+    }
+}
+
+/// Whether a network folder can take a backup right now
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkFolderStatus {
+    pub path: String,
+    /// The folder exists, i.e. the share is mounted
+    pub available: bool,
+    pub writable: bool,
+    /// Space left on the share; `None` when it couldn't be determined
+    pub free_bytes: Option<u64>,
+    /// Why the folder can't be used
+    pub error: Option<String>,
+}
+
+/// Free space on the disk holding `path`
+///
+/// Uses the mount with the longest mount point that contains the path.
+fn available_space(path: &Path) -> Option<u64> {
+    let path = path.canonicalize().ok()?;
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+fn check_writable(dir: &Path) -> Result<()> {
+    let probe = dir.join(WRITE_PROBE_FILENAME);
+    std::fs::write(&probe, b"peptrack").context("Folder can't be written to")?;
+    let read = std::fs::read(&probe).context("Folder can't be read back");
+    std::fs::remove_file(&probe).ok();
+    if read? != b"peptrack" {
+        anyhow::bail!("Folder didn't return what was written to it");
+    }
+    Ok(())
+}
+
+/// Check that `dir` exists, can be written to and how much room it has
+pub fn check_folder(dir: &Path) -> NetworkFolderStatus {
+    let mut status = NetworkFolderStatus {
+        path: dir.to_string_lossy().to_string(),
+        available: dir.is_dir(),
+        writable: false,
+        free_bytes: None,
+        error: None,
+    };
+    if !status.available {
+        status.error = Some(format!(
+            "{} is not available; check that the share is mounted",
+            dir.display()
+        ));
+        return status;
+    }
+    match check_writable(dir) {
+        Ok(()) => status.writable = true,
+        Err(e) => status.error = Some(format!("{:#}", e)),
+    }
+    status.free_bytes = available_space(dir);
+    status
+}
+
+/// Fail unless `dir` is usable and has room for `size_bytes`
+///
+/// A share whose free space can't be determined is given the benefit of
+/// the doubt; the written backup is verified either way.
+pub fn ensure_ready(dir: &Path, size_bytes: u64) -> Result<()> {
+    let status = check_folder(dir);
+    if let Some(error) = status.error {
+        anyhow::bail!(error);
+    }
+    match status.free_bytes {
+        Some(free) if free < size_bytes => anyhow::bail!(
+            "Not enough space in {}: the backup needs {} bytes but only {} are free",
+            dir.display(),
+            size_bytes,
+            free
+        ),
+        Some(_) => {}
+        None => warn!("Couldn't determine free space in {}", dir.display()),
+    }
+    Ok(())
+}
+
+// ========== Network Folder Commands ==========
+
+/// Check a network folder before it's saved as a backup destination
+#[tauri::command]
+pub async fn check_network_folder(path: String) -> Result<NetworkFolderStatus, CommandError> {
+    let path = path.trim().to_string();
+    if path.is_empty() || !Path::new(&path).is_absolute() {
+        return Err(CommandError::invalid_input(
+            "The network folder must be an absolute path",
+        ));
+    }
+    info!("Checking network folder {}", path);
+
+    tokio::task::spawn_blocking(move || check_folder(Path::new(&path)))
+        .await
+        .map_err(|e| CommandError::internal(format!("Network folder check failed: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folders_are_checked_for_mount_and_space() {
+        let dir = std::env::temp_dir().join(format!("peptrack-nas-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let status = check_folder(&dir);
+        assert!(status.available && status.writable);
+        assert!(status.error.is_none());
+        assert!(!dir.join(WRITE_PROBE_FILENAME).exists());
+        assert!(ensure_ready(&dir, 1).is_ok());
+        assert!(ensure_ready(&dir, u64::MAX).is_err() || status.free_bytes.is_none());
+
+        let unmounted = dir.join("share");
+        let status = check_folder(&unmounted);
+        assert!(!status.available && !status.writable);
+        assert!(ensure_ready(&unmounted, 1).unwrap_err().to_string().contains("mounted"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn settings_need_an_absolute_path() {
+        let settings = |path: &str| NetworkFolderSettings {
+            path: path.to_string(),
+            cleanup_settings: CleanupSettings::default(),
+        };
+        assert!(settings("").validate().is_err());
+        assert!(settings("backups").validate().is_err());
+        let root = std::env::temp_dir().to_string_lossy().to_string();
+        assert!(settings(&root).validate().is_ok());
+    }
+}
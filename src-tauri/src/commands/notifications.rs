@@ -0,0 +1,185 @@
+//! Webhook notification channels
+//!
+//! Backup results go to the OS notification center as before, and alerts do
+//! when their severity is routed there; the [`Notifier`] additionally posts
+//! both to the webhooks saved in [`NotificationSettings`]. Delivery runs in
+//! the background and failures are only logged, so a dead webhook never
+//! holds up an alert or a backup.
+
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use peptrack_core::models::Alert;
+use peptrack_core::{AlertDelivery, AsyncStorage, NotificationChannel, NotificationEvent, Setting};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use tauri_plugin_notification::NotificationExt;
+use tracing::{error, info, warn};
+
+use crate::commands::settings::{load_setting, save_setting};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationSettings {
+    pub channels: Vec<NotificationChannel>,
+}
+
+impl Setting for NotificationSettings {
+    const KEY: &'static str = "notifications.settings";
+    const LEGACY_FILE: Option<&'static str> = Some("notifications.json");
+
+    fn validate(&self) -> Result<(), String> {
+        for channel in &self.channels {
+            channel
+                .validate()
+                .map_err(|e| format!("{}: {}", channel.name.trim(), e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Sends events to the configured webhook channels
+pub struct Notifier {
+    /// Channels are read from here for each event, so saved changes apply
+    /// at once
+    storage: AsyncStorage,
+    /// Set once the app is running; alerts raised before then only reach
+    /// the webhooks
+    app: OnceLock<AppHandle>,
+}
+
+impl Notifier {
+    pub fn new(storage: AsyncStorage) -> Self {
+        Self {
+            storage,
+            app: OnceLock::new(),
+        }
+    }
+
+    /// Lets alerts routed to notifications show in the OS notification center
+    pub fn attach(&self, app: AppHandle) {
+        self.app.set(app).ok();
+    }
+
+    /// Post a newly created alert to the channels that want it, and show it
+    /// as an OS notification when its severity is routed there
+    pub fn alert(&self, alert: &Alert, delivery: AlertDelivery) {
+        if delivery == AlertDelivery::Notification {
+            if let Some(app) = self.app.get() {
+                if let Err(e) = app.notification().builder().title(&alert.title).body(&alert.message).show() {
+                    warn!("Failed to show alert notification: {}", e);
+                }
+            }
+        }
+        self.notify(NotificationEvent::from_alert(alert));
+    }
+
+    /// Post `event` to every channel that accepts it, in the background
+    pub fn notify(&self, event: NotificationEvent) {
+        let storage = self.storage.clone();
+        tauri::async_runtime::spawn(async move {
+            // Fails while the database is locked, when there's nothing to send to
+            let settings: NotificationSettings =
+                match storage.run(|storage| storage.load_setting_or_default()).await {
+                    Ok(settings) => settings,
+                    Err(e) => {
+                        warn!("Failed to load notification channels: {:#}", e);
+                        return;
+                    }
+                };
+            let channels: Vec<NotificationChannel> = settings
+                .channels
+                .into_iter()
+                .filter(|channel| channel.accepts(&event))
+                .collect();
+            if channels.is_empty() {
+                return;
+            }
+
+            let client = webhook_client();
+            for channel in channels {
+                if let Err(e) = send(&client, &channel, &event).await {
+                    warn!("Notification to \"{}\" failed: {:#}", channel.name, e);
+                }
+            }
+        });
+    }
+}
+
+/// Built per delivery so changed network settings apply
+fn webhook_client() -> Client {
+    peptrack_literature::http::client_builder("PepTrack/1.0")
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+async fn send(client: &Client, channel: &NotificationChannel, event: &NotificationEvent) -> Result<()> {
+    let request = channel.request(event);
+    client
+        .post(&request.url)
+        .json(&request.body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("POST to {} failed", request.url))?;
+    Ok(())
+}
+
+/// Gets the configured notification channels
+#[tauri::command]
+pub async fn get_notification_settings(
+    state: State<'_, Arc<AppState>>,
+) -> Result<NotificationSettings, CommandError> {
+    load_setting(&state).await
+}
+
+/// Replaces the notification channels
+#[tauri::command]
+pub async fn update_notification_settings(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    settings: NotificationSettings,
+) -> Result<NotificationSettings, CommandError> {
+    save_setting(&app, &state, &settings).await?;
+
+    info!("Saved {} notification channel(s)", settings.channels.len());
+    Ok(settings)
+}
+
+/// Sends a test notification to `channel` and waits for the result
+#[tauri::command]
+pub async fn test_notification_channel(
+    channel: NotificationChannel,
+) -> Result<(), CommandError> {
+    channel
+        .validate()
+        .map_err(|e| CommandError::invalid_input(e.to_string()))?;
+
+    let event = NotificationEvent {
+        title: "PepTrack test notification".to_string(),
+        message: format!("Notifications for \"{}\" are working", channel.name.trim()),
+        ..NotificationEvent::backup_succeeded("")
+    };
+    send(&webhook_client(), &channel, &event).await.map_err(|e| {
+        error!("Test notification failed: {:#}", e);
+        CommandError::with_context(e, "Test notification failed")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_default_to_no_channels() {
+        let settings: NotificationSettings = serde_json::from_str("{}").unwrap();
+        assert!(settings.channels.is_empty());
+    }
+}
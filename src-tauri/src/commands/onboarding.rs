@@ -0,0 +1,215 @@
+use peptrack_core::{create_recovery_code, KdfParams, SetupStatus, SetupStep, StorageManager};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use time::OffsetDateTime;
+use tracing::{error, info};
+
+use crate::commands::defaults::{catalog_peptide, catalog_protocol, seed_default_peptides};
+use crate::commands::scheduler_v2::{apply_backup_schedule, BackupFrequency, BackupSchedule, SchedulerState};
+use crate::commands::schedules::{insert_dose_schedule, CreateSchedulePayload};
+use crate::commands::settings::{load_setting, load_setting_or_default, save_setting};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Setup progress with the step the wizard should show next
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupState {
+    #[serde(flatten)]
+    pub status: SetupStatus,
+    /// `None` once every step is done or skipped
+    pub next_step: Option<SetupStep>,
+    /// Catalog protocols added by this call
+    pub default_peptides_added: usize,
+    /// Recovery code for the database key, set when this call created it;
+    /// it isn't kept, so the wizard must show it now
+    pub recovery_code: Option<String>,
+}
+
+impl SetupState {
+    fn new(status: SetupStatus, default_peptides_added: usize) -> Self {
+        Self {
+            next_step: status.next_step(),
+            status,
+            default_peptides_added,
+            recovery_code: None,
+        }
+    }
+}
+
+/// The first protocol, from a catalog peptide
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolTemplatePayload {
+    pub peptide_name: String,
+    /// The catalog name when not given
+    pub protocol_name: Option<String>,
+    pub target_concentration_mg_ml: Option<f32>,
+    /// A reminder schedule is added when this and `time_of_day` are set
+    pub dose_mg: Option<f32>,
+    /// `HH:MM`, 24-hour
+    pub time_of_day: Option<String>,
+    /// 0 = Sunday; every day when not given
+    pub days_of_week: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSetupPayload {
+    pub frequency: BackupFrequency,
+    #[serde(default)]
+    pub backup_on_close: bool,
+}
+
+/// Wizard answers; steps left out are left for later
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BootstrapProfilePayload {
+    pub seed_default_peptides: bool,
+    pub template: Option<ProtocolTemplatePayload>,
+    pub backup: Option<BackupSetupPayload>,
+    /// Steps the user chose not to do
+    pub skip_steps: Vec<SetupStep>,
+}
+
+#[tauri::command]
+pub async fn get_setup_status(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<SetupState, CommandError> {
+    Ok(SetupState::new(load_setting(&state).await?, 0))
+}
+
+/// Run the onboarding steps the payload asks for
+///
+/// Steps already done are not repeated, and progress is saved after each
+/// step, so the wizard can call this again after a failure or restart. The
+/// first call also creates a recovery code for the database key.
+#[tauri::command]
+pub async fn bootstrap_profile(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    scheduler: State<'_, SchedulerState>,
+    payload: BootstrapProfilePayload,
+) -> Result<SetupState, CommandError> {
+    let mut status: SetupStatus = load_setting(&state).await?;
+    let mut default_peptides_added = 0;
+    let recovery_code = create_first_recovery_code(&state).await?;
+
+    if payload.seed_default_peptides && !status.completed_steps.contains(&SetupStep::DefaultPeptides) {
+        default_peptides_added = state.on_storage(seed_default_peptides).await?;
+        status.complete(SetupStep::DefaultPeptides, OffsetDateTime::now_utc());
+        save_setting(&app, &state, &status).await?;
+    }
+
+    if let Some(template) = payload.template {
+        if !status.completed_steps.contains(&SetupStep::FirstProtocol) {
+            let protocol_id = state
+                .on_storage(move |storage| create_first_protocol(storage, template))
+                .await?;
+            status.first_protocol_id = Some(protocol_id);
+            status.complete(SetupStep::FirstProtocol, OffsetDateTime::now_utc());
+            save_setting(&app, &state, &status).await?;
+        }
+    }
+
+    if let Some(backup) = payload.backup {
+        if !status.completed_steps.contains(&SetupStep::BackupSchedule) {
+            let mut schedule: BackupSchedule = load_setting_or_default(&state).await;
+            schedule.enabled = backup.frequency != BackupFrequency::Manual;
+            schedule.frequency = backup.frequency;
+            schedule.backup_on_close = backup.backup_on_close;
+            apply_backup_schedule(&app, &scheduler, &state, schedule).await?;
+            status.complete(SetupStep::BackupSchedule, OffsetDateTime::now_utc());
+            save_setting(&app, &state, &status).await?;
+        }
+    }
+
+    if !payload.skip_steps.is_empty() {
+        for step in payload.skip_steps {
+            status.skip(step, OffsetDateTime::now_utc());
+        }
+        save_setting(&app, &state, &status).await?;
+    }
+
+    if status.completed_at.is_some() {
+        info!("Setup complete");
+    }
+    Ok(SetupState {
+        recovery_code,
+        ..SetupState::new(status, default_peptides_added)
+    })
+}
+
+/// Create a recovery code for the key made on first launch, unless one
+/// already exists
+async fn create_first_recovery_code(state: &AppState) -> Result<Option<String>, CommandError> {
+    let provider = state.key_provider.clone();
+    state
+        .db
+        .run(move |storage| {
+            if storage.key_recovery()?.is_some() {
+                return Ok(None);
+            }
+            create_recovery_code(storage, &provider, KdfParams::default()).map(Some)
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to create recovery code: {:#}", e);
+            CommandError::with_context(e, "Failed to create recovery code")
+        })
+}
+
+/// Start setup over, e.g. to replay the wizard; nothing it created is removed
+#[tauri::command]
+pub async fn reset_setup_status(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<SetupState, CommandError> {
+    let status = SetupStatus::default();
+    save_setting(&app, &state, &status).await?;
+    Ok(SetupState::new(status, 0))
+}
+
+/// Makes the template's protocol a favorite, creating it unless seeding the
+/// catalog already did, and adds its reminder schedule
+fn create_first_protocol(storage: &StorageManager, template: ProtocolTemplatePayload) -> Result<String, CommandError> {
+    let peptide = catalog_peptide(&template.peptide_name).ok_or_else(|| {
+        CommandError::invalid_input(format!("{} is not in the peptide catalog", template.peptide_name))
+    })?;
+    info!("Creating first protocol from the {} template", peptide.peptide_name);
+
+    let existing = storage
+        .list_protocols()
+        .map_err(|e| CommandError::with_context(e, "Failed to check existing protocols"))?
+        .into_iter()
+        .find(|protocol| protocol.peptide_name == peptide.peptide_name);
+    let mut protocol = existing.unwrap_or_else(|| catalog_protocol(peptide));
+    if let Some(name) = template.protocol_name.map(|name| name.trim().to_string()).filter(|name| !name.is_empty()) {
+        protocol.name = name;
+    }
+    if template.target_concentration_mg_ml.is_some() {
+        protocol.target_concentration_mg_ml = template.target_concentration_mg_ml;
+    }
+    protocol.is_favorite = true;
+    protocol.updated_at = OffsetDateTime::now_utc();
+    storage
+        .upsert_protocol(&protocol)
+        .map_err(|e| CommandError::with_context(e, "Failed to create protocol"))?;
+
+    if let (Some(amount_mg), Some(time_of_day)) = (template.dose_mg, template.time_of_day) {
+        insert_dose_schedule(
+            storage,
+            CreateSchedulePayload {
+                protocol_id: protocol.id.clone(),
+                amount_mg,
+                site: None,
+                time_of_day,
+                days_of_week: template.days_of_week.unwrap_or_else(|| (0..7).collect()),
+                notes: None,
+                titration: None,
+            },
+        )?;
+    }
+
+    Ok(protocol.id)
+}
@@ -0,0 +1,349 @@
+use anyhow::Context;
+use peptrack_core::{InventoryItem, Order, OrderItem, OrderStatus, StorageManager, VialStatus};
+use serde::Deserialize;
+use tauri::State;
+use time::OffsetDateTime;
+use tracing::{error, info};
+
+use crate::commands::currency::resolve_currency;
+use crate::error::CommandError;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderItemPayload {
+    pub protocol_id: String,
+    pub quantity: u32,
+    pub vial_size_mg: Option<f32>,
+    pub unit_price: f32,
+}
+
+impl From<OrderItemPayload> for OrderItem {
+    fn from(payload: OrderItemPayload) -> Self {
+        OrderItem {
+            protocol_id: payload.protocol_id,
+            quantity: payload.quantity,
+            vial_size_mg: payload.vial_size_mg,
+            unit_price: payload.unit_price,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateOrderPayload {
+    pub supplier_id: String,
+    pub items: Vec<OrderItemPayload>,
+    pub currency: Option<String>, // Defaults to the supplier's currency, then USD
+    pub shipping_cost: Option<f32>,
+    pub order_date: Option<OffsetDateTime>,
+    pub expected_delivery: Option<OffsetDateTime>,
+    pub tracking_number: Option<String>,
+    pub status: Option<OrderStatus>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateOrderPayload {
+    pub items: Option<Vec<OrderItemPayload>>,
+    pub currency: Option<String>,
+    pub shipping_cost: Option<f32>,
+    pub order_date: Option<OffsetDateTime>,
+    pub expected_delivery: Option<OffsetDateTime>,
+    pub tracking_number: Option<String>,
+    pub status: Option<OrderStatus>,
+    pub delivered_at: Option<OffsetDateTime>,
+    pub notes: Option<String>,
+}
+
+/// Check quantities and prices, and that every item refers to a known protocol
+async fn validate_items(state: &AppState, items: &[OrderItem]) -> Result<(), CommandError> {
+    if items.is_empty() {
+        return Err(CommandError::invalid_input("An order needs at least one item"));
+    }
+
+    for item in items {
+        if item.quantity == 0 {
+            return Err(CommandError::invalid_input("Item quantity must be at least 1"));
+        }
+        if !item.unit_price.is_finite() || item.unit_price < 0.0 {
+            return Err(CommandError::invalid_input("Item price cannot be negative"));
+        }
+        if item.vial_size_mg.is_some_and(|mg| !mg.is_finite() || mg <= 0.0) {
+            return Err(CommandError::invalid_input("Vial size must be a positive number"));
+        }
+
+        let protocol_id = item.protocol_id.clone();
+        let protocol = state
+            .db
+            .run(move |storage| storage.get_protocol(&protocol_id))
+            .await
+            .map_err(|e| CommandError::with_context(e, "Failed to fetch protocol"))?;
+        if protocol.is_none() {
+            return Err(CommandError::not_found(format!("Protocol not found: {}", item.protocol_id)));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_shipping(shipping_cost: Option<f32>) -> Result<(), CommandError> {
+    if shipping_cost.is_some_and(|cost| !cost.is_finite() || cost < 0.0) {
+        return Err(CommandError::invalid_input("Shipping cost cannot be negative"));
+    }
+    Ok(())
+}
+
+/// Build one sealed vial per ordered unit, priced from the order line
+fn inventory_from_order(order: &Order) -> Vec<InventoryItem> {
+    let reference = order.tracking_number.as_deref().unwrap_or(&order.id);
+
+    order
+        .items
+        .iter()
+        .flat_map(|line| {
+            (0..line.quantity).map(move |_| {
+                let mut item = InventoryItem::new(line.protocol_id.as_str());
+                item.supplier_id = Some(order.supplier_id.clone());
+                item.vial_status = VialStatus::Sealed;
+                item.purchase_date = Some(order.order_date);
+                item.cost_per_mg = line.vial_size_mg.map(|mg| line.unit_price / mg);
+                item.currency = order.currency.clone();
+                item.quantity_mg = line.vial_size_mg;
+                item.quantity_remaining_mg = line.vial_size_mg;
+                item.notes = Some(format!("Received from order {}", reference));
+                item
+            })
+        })
+        .collect()
+}
+
+/// Add a delivered order's vials to inventory, once
+///
+/// Orders that already created inventory are left alone, so marking an order
+/// delivered again never duplicates vials.
+fn receive_order(storage: &StorageManager, order: &mut Order) -> anyhow::Result<()> {
+    if !order.inventory_item_ids.is_empty() {
+        return Ok(());
+    }
+
+    let items = inventory_from_order(order);
+    for item in &items {
+        storage
+            .upsert_inventory_item(item)
+            .context("Failed to add delivered vial to inventory")?;
+    }
+
+    order.delivered_at = order.delivered_at.or(Some(OffsetDateTime::now_utc()));
+    order.inventory_item_ids = items.into_iter().map(|item| item.id).collect();
+    info!(
+        "Order {} delivered, added {} vials to inventory",
+        order.id,
+        order.inventory_item_ids.len()
+    );
+
+    Ok(())
+}
+
+/// Save `order`, first receiving it into inventory if it's been delivered
+async fn save_order(state: &AppState, mut order: Order) -> Result<Order, CommandError> {
+    state
+        .db
+        .run(move |storage| {
+            if order.status == OrderStatus::Delivered {
+                receive_order(storage, &mut order)?;
+            }
+            storage.upsert_order(&order).context("Failed to save order")?;
+            Ok(order)
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to save order: {:#}", e);
+            CommandError::from(e)
+        })
+}
+
+// ========== Order Commands ==========
+
+#[tauri::command]
+pub async fn create_order(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: CreateOrderPayload,
+) -> Result<Order, CommandError> {
+    info!("Creating order from supplier: {}", payload.supplier_id);
+
+    let supplier_id = payload.supplier_id.clone();
+    let supplier = state
+        .db
+        .run(move |storage| storage.get_supplier(&supplier_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch supplier"))?
+        .ok_or_else(|| CommandError::not_found("Supplier not found"))?;
+
+    let items: Vec<OrderItem> = payload.items.into_iter().map(OrderItem::from).collect();
+    validate_items(&state, &items).await?;
+    validate_shipping(payload.shipping_cost)?;
+
+    let mut order = Order::new(supplier.id.as_str(), items);
+    order.currency = resolve_currency(payload.currency, supplier.currency.as_deref())?;
+    order.shipping_cost = payload.shipping_cost;
+    order.order_date = payload.order_date.unwrap_or(order.order_date);
+    order.expected_delivery = payload.expected_delivery;
+    order.tracking_number = payload.tracking_number;
+    order.status = payload.status.unwrap_or(OrderStatus::Pending);
+    order.notes = payload.notes;
+
+    save_order(&state, order).await
+}
+
+#[tauri::command]
+pub async fn list_orders(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<Vec<Order>, CommandError> {
+    state
+        .db
+        .run(|storage| storage.list_orders())
+        .await
+        .map_err(|e| {
+            error!("Failed to list orders: {:#}", e);
+            CommandError::with_context(e, "Failed to list orders")
+        })
+}
+
+#[tauri::command]
+pub async fn get_order(
+    state: State<'_, std::sync::Arc<AppState>>,
+    order_id: String,
+) -> Result<Option<Order>, CommandError> {
+    state
+        .db
+        .run(move |storage| storage.get_order(&order_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to get order: {:#}", e);
+            CommandError::with_context(e, "Failed to get order")
+        })
+}
+
+/// Update an order; moving it to `delivered` adds its vials to inventory
+///
+/// Items of an order that has already been received cannot be changed, since
+/// the inventory created from them would no longer match.
+#[tauri::command]
+pub async fn update_order(
+    state: State<'_, std::sync::Arc<AppState>>,
+    order_id: String,
+    payload: UpdateOrderPayload,
+) -> Result<Order, CommandError> {
+    info!("Updating order: {}", order_id);
+
+    let mut order = state
+        .db
+        .run(move |storage| storage.get_order(&order_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch order"))?
+        .ok_or_else(|| CommandError::not_found("Order not found"))?;
+
+    if let Some(items) = payload.items {
+        if !order.inventory_item_ids.is_empty() {
+            return Err(CommandError::conflict("Items of a delivered order cannot be changed"));
+        }
+        let items: Vec<OrderItem> = items.into_iter().map(OrderItem::from).collect();
+        validate_items(&state, &items).await?;
+        order.items = items;
+    }
+    if payload.currency.is_some() {
+        order.currency = resolve_currency(payload.currency, None)?;
+    }
+    if payload.shipping_cost.is_some() {
+        validate_shipping(payload.shipping_cost)?;
+        order.shipping_cost = payload.shipping_cost;
+    }
+
+    order.order_date = payload.order_date.unwrap_or(order.order_date);
+    order.expected_delivery = payload.expected_delivery.or(order.expected_delivery);
+    order.tracking_number = payload.tracking_number.or(order.tracking_number);
+    order.delivered_at = payload.delivered_at.or(order.delivered_at);
+    order.notes = payload.notes.or(order.notes);
+    if let Some(status) = payload.status {
+        order.status = status;
+    }
+    order.updated_at = OffsetDateTime::now_utc();
+
+    save_order(&state, order).await
+}
+
+/// Delete an order record; vials already received stay in inventory
+#[tauri::command]
+pub async fn delete_order(
+    state: State<'_, std::sync::Arc<AppState>>,
+    order_id: String,
+) -> Result<(), CommandError> {
+    info!("Deleting order: {}", order_id);
+
+    state
+        .db
+        .run(move |storage| storage.delete_order(&order_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to delete order: {:#}", e);
+            CommandError::with_context(e, "Failed to delete order")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inventory_from_order_creates_one_vial_per_unit() {
+        let mut order = Order::new(
+            "supplier-1",
+            vec![
+                OrderItem {
+                    protocol_id: "bpc".to_string(),
+                    quantity: 2,
+                    vial_size_mg: Some(5.0),
+                    unit_price: 40.0,
+                },
+                OrderItem {
+                    protocol_id: "tb".to_string(),
+                    quantity: 1,
+                    vial_size_mg: None,
+                    unit_price: 60.0,
+                },
+            ],
+        );
+        order.currency = "EUR".to_string();
+        order.tracking_number = Some("TRACK123".to_string());
+
+        let items = inventory_from_order(&order);
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].protocol_id, "bpc");
+        assert_eq!(items[0].cost_per_mg, Some(8.0));
+        assert_eq!(items[0].quantity_remaining_mg, Some(5.0));
+        assert_eq!(items[0].currency, "EUR");
+        assert_eq!(items[0].supplier_id.as_deref(), Some("supplier-1"));
+        assert_eq!(items[0].purchase_date, Some(order.order_date));
+        assert_eq!(items[2].cost_per_mg, None);
+        assert!(items[2].notes.as_deref().unwrap().contains("TRACK123"));
+    }
+
+    #[test]
+    fn test_create_order_payload_deserialization() {
+        let json = r#"{
+            "supplierId": "supplier-1",
+            "items": [{"protocolId": "bpc", "quantity": 3, "vialSizeMg": 10, "unitPrice": 55.5}],
+            "shippingCost": 9.99,
+            "trackingNumber": "1Z999",
+            "status": "shipped"
+        }"#;
+
+        let payload: CreateOrderPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.items[0].quantity, 3);
+        assert_eq!(payload.status, Some(OrderStatus::Shipped));
+        assert!(payload.order_date.is_none());
+    }
+}
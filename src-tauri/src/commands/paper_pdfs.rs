@@ -0,0 +1,146 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use peptrack_core::models::LiteratureEntry;
+use peptrack_core::{Attachment, AttachmentKind, AttachmentOwner, MAX_ATTACHMENT_BYTES};
+use peptrack_literature::OpenAccessLocator;
+use tauri::State;
+use tracing::{error, info, warn};
+
+use crate::commands::literature::{fetch_entry, save_entry};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Download a paper's open-access PDF and keep it, encrypted, with the entry
+///
+/// The PDF is found through OpenAlex or Unpaywall by DOI. Entries with a
+/// PDF are never pruned from the cache. Downloading again once a copy is
+/// stored does nothing.
+#[tauri::command]
+pub async fn download_paper_pdf(
+    state: State<'_, std::sync::Arc<AppState>>,
+    entry_id: String,
+) -> Result<LiteratureEntry, CommandError> {
+    let mut entry = fetch_entry(&state, &entry_id).await?;
+    if let Some(attachment_id) = entry.pdf_attachment_id.clone() {
+        let id = attachment_id.clone();
+        let stored = state
+            .db
+            .run(move |storage| storage.get_attachment(&id))
+            .await
+            .map_err(|e| CommandError::with_context(e, "Failed to get attachment"))?;
+        if stored.is_some() {
+            return Ok(entry);
+        }
+        warn!("PDF attachment {} of literature entry {} is missing", attachment_id, entry.id);
+    }
+
+    let doi = entry
+        .doi
+        .clone()
+        .ok_or_else(|| CommandError::invalid_input("This paper has no DOI to find an open-access copy with"))?;
+    let locator = OpenAccessLocator::new();
+    let url = locator
+        .pdf_url(&doi)
+        .await
+        .map_err(|e| {
+            error!("Open-access lookup failed for {}: {:#}", doi, e);
+            CommandError::with_context(e, "Failed to look up an open-access copy")
+        })?
+        .ok_or_else(|| CommandError::not_found("No open-access PDF of this paper was found"))?;
+    let data = locator
+        .download_pdf(&url, MAX_ATTACHMENT_BYTES as u64)
+        .await
+        .map_err(|e| {
+            warn!("Failed to download PDF of {} from {}: {:#}", doi, url, e);
+            CommandError::with_context(e, "Failed to download the PDF")
+        })?;
+    info!("Downloaded PDF of {} ({} bytes)", doi, data.len());
+
+    let mut attachment = Attachment::new(
+        AttachmentOwner::Literature,
+        entry.id.clone(),
+        AttachmentKind::Paper,
+        pdf_file_name(&doi),
+        "application/pdf".to_string(),
+        data.len() as u64,
+    );
+    attachment.notes = Some(format!("Open-access copy from {}", url));
+    let attachment_id = attachment.id.clone();
+    state
+        .db
+        .run(move |storage| storage.add_attachment(&attachment, &data, None))
+        .await
+        .map_err(|e| {
+            error!("Failed to store PDF of {}: {:#}", doi, e);
+            CommandError::with_context(e, "Failed to store the PDF")
+        })?;
+
+    entry.pdf_attachment_id = Some(attachment_id);
+    save_entry(&state, &entry).await?;
+    Ok(entry)
+}
+
+/// A paper's stored PDF as base64, for the in-app viewer
+#[tauri::command]
+pub async fn get_paper_pdf(
+    state: State<'_, std::sync::Arc<AppState>>,
+    entry_id: String,
+) -> Result<String, CommandError> {
+    let entry = fetch_entry(&state, &entry_id).await?;
+    let attachment_id = entry
+        .pdf_attachment_id
+        .ok_or_else(|| CommandError::not_found("This paper's PDF hasn't been downloaded"))?;
+    let id = attachment_id.clone();
+    let data = state
+        .db
+        .run(move |storage| storage.get_attachment_data(&id))
+        .await
+        .map_err(|e| {
+            error!("Failed to load PDF attachment {}: {:#}", attachment_id, e);
+            CommandError::with_context(e, "Failed to load the PDF")
+        })?
+        .ok_or_else(|| CommandError::not_found("This paper's PDF is missing; download it again"))?;
+    Ok(STANDARD.encode(data))
+}
+
+/// Delete a paper's stored PDF
+#[tauri::command]
+pub async fn remove_paper_pdf(
+    state: State<'_, std::sync::Arc<AppState>>,
+    entry_id: String,
+) -> Result<LiteratureEntry, CommandError> {
+    let mut entry = fetch_entry(&state, &entry_id).await?;
+    let Some(attachment_id) = entry.pdf_attachment_id.take() else {
+        return Ok(entry);
+    };
+    let id = attachment_id.clone();
+    state
+        .db
+        .run(move |storage| storage.delete_attachment(&id))
+        .await
+        .map_err(|e| {
+            error!("Failed to delete PDF attachment {}: {:#}", attachment_id, e);
+            CommandError::with_context(e, "Failed to delete the PDF")
+        })?;
+    save_entry(&state, &entry).await?;
+    Ok(entry)
+}
+
+/// `10.1000/xyz.123` becomes `10.1000_xyz.123.pdf`
+fn pdf_file_name(doi: &str) -> String {
+    let stem: String = doi
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    format!("{}.pdf", stem.trim_matches('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pdf_file_names_come_from_the_doi() {
+        assert_eq!(pdf_file_name("10.1000/xyz.123"), "10.1000_xyz.123.pdf");
+        assert_eq!(pdf_file_name("10.1/a b/../c"), "10.1_a_b_.._c.pdf");
+    }
+}
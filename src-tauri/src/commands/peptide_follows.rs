@@ -0,0 +1,297 @@
+use std::sync::Arc;
+
+use peptrack_core::models::{Alert, AlertSeverity, AlertType, LiteratureEntry};
+use peptrack_core::{PeptideFollow, PeptideFollows};
+use peptrack_literature::{OpenAlexFetcher, RelevanceContext};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use tauri_plugin_notification::NotificationExt;
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::commands::literature::protocol_peptide_names;
+use crate::commands::settings::{load_setting, save_setting};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// How often the background job looks for due follows
+const CHECK_INTERVAL_SECS: u64 = 60 * 60;
+/// Works requested per check
+const MAX_WORKS_PER_CHECK: usize = 25;
+/// Most new titles listed in an alert message
+const MAX_TITLES_IN_ALERT: usize = 5;
+
+/// Keeps the background job and the commands from overwriting each other's
+/// changes to the follows
+static FOLLOWS_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Outcome of checking a followed peptide
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FollowCheck {
+    pub follow: PeptideFollow,
+    /// Works found by this check, now pending
+    pub new_works: usize,
+    pub alert: Option<Alert>,
+}
+
+fn build_alert(follow: &PeptideFollow, new_works: &[LiteratureEntry]) -> Alert {
+    let title = format!(
+        "{} new paper{} on {}",
+        new_works.len(),
+        if new_works.len() == 1 { "" } else { "s" },
+        follow.peptide_name
+    );
+    let mut lines: Vec<String> = new_works
+        .iter()
+        .take(MAX_TITLES_IN_ALERT)
+        .map(|entry| format!("• {}", entry.title))
+        .collect();
+    if new_works.len() > MAX_TITLES_IN_ALERT {
+        lines.push(format!("…and {} more", new_works.len() - MAX_TITLES_IN_ALERT));
+    }
+
+    let mut alert = Alert::new(AlertType::NewLiterature, AlertSeverity::Info, title, lines.join("\n"));
+    alert.related_id = Some(follow.id.clone());
+    alert.related_type = Some("peptide_follow".to_string());
+    alert
+}
+
+/// Look for works published since the last check and alert about new ones;
+/// returns how many were new
+///
+/// Must be called with `FOLLOWS_LOCK` held; the caller saves `follow`.
+async fn check_follow(state: &AppState, follow: &mut PeptideFollow) -> Result<(usize, Option<Alert>), CommandError> {
+    let results = OpenAlexFetcher::new()
+        .recent_works(
+            &follow.peptide_name,
+            follow.concept_id.as_deref(),
+            follow.published_since(),
+            MAX_WORKS_PER_CHECK,
+        )
+        .await
+        .map_err(|e| {
+            warn!("Checking {} for new works failed: {:#}", follow.peptide_name, e);
+            CommandError::with_context(e, "Failed to check OpenAlex for new works")
+        })?;
+
+    let now = OffsetDateTime::now_utc();
+    let peptides = protocol_peptide_names(state).await;
+    let relevance = RelevanceContext::new(&follow.peptide_name, &peptides, now);
+    let found: Vec<(String, LiteratureEntry)> = results
+        .iter()
+        .map(|result| (result.dedup_key(), relevance.to_entry(result)))
+        .collect();
+    let added = follow.add_found(found, now);
+    info!("Follow of {} found {} new works", follow.peptide_name, added);
+    if added == 0 {
+        return Ok((0, None));
+    }
+
+    let alert = build_alert(follow, &follow.pending[..added]);
+    let raised = alert.clone();
+    let delivery = state
+        .db
+        .run(move |storage| storage.raise_alert(&raised))
+        .await
+        .map_err(|e| {
+            error!("Failed to create followed peptide alert: {:#}", e);
+            CommandError::with_context(e, "Failed to create alert")
+        })?;
+    let alert = delivery.map(|delivery| {
+        state.notifier.alert(&alert, delivery);
+        alert
+    });
+    Ok((added, alert))
+}
+
+/// Check due follows now and then every hour, notifying about new works
+pub async fn run_peptide_follow_loop(app: AppHandle, state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(CHECK_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        // Follows checked while offline would only fail; they stay due
+        if state.key_provider.is_locked() || !state.connectivity.check().await {
+            continue;
+        }
+
+        let _guard = FOLLOWS_LOCK.lock().await;
+        let mut follows: PeptideFollows = match load_setting(&state).await {
+            Ok(follows) => follows,
+            Err(e) => {
+                warn!("Failed to load followed peptides: {}", e);
+                continue;
+            }
+        };
+        let now = OffsetDateTime::now_utc();
+        let mut checked = false;
+        for follow in follows.follows.iter_mut().filter(|follow| follow.is_due(now)) {
+            checked = true;
+            match check_follow(&state, follow).await {
+                Ok((_, Some(alert))) => {
+                    app.notification()
+                        .builder()
+                        .title(&alert.title)
+                        .body(&alert.message)
+                        .show()
+                        .ok();
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Follow of {} failed: {}", follow.peptide_name, e),
+            }
+        }
+        if checked {
+            if let Err(e) = save_setting(&app, &state, &follows).await {
+                warn!("Failed to save followed peptides: {}", e);
+            }
+        }
+    }
+}
+
+fn follow_mut<'a>(follows: &'a mut PeptideFollows, follow_id: &str) -> Result<&'a mut PeptideFollow, CommandError> {
+    follows
+        .get_mut(follow_id)
+        .ok_or_else(|| CommandError::not_found("Followed peptide not found"))
+}
+
+#[tauri::command]
+pub async fn list_peptide_follows(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<PeptideFollow>, CommandError> {
+    let follows: PeptideFollows = load_setting(&state).await?;
+    Ok(follows.follows)
+}
+
+/// Follow `peptide_name`, using its OpenAlex concept when there is one
+///
+/// The first check runs with the next background pass, or with
+/// `check_peptide_follow`.
+#[tauri::command]
+pub async fn follow_peptide(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    peptide_name: String,
+) -> Result<PeptideFollow, CommandError> {
+    let peptide_name = peptide_name.trim();
+    if peptide_name.is_empty() {
+        return Err(CommandError::invalid_input("Peptide name is required"));
+    }
+
+    let concept = match OpenAlexFetcher::new().find_concept(peptide_name).await {
+        Ok(concept) => concept,
+        Err(e) => {
+            warn!("OpenAlex concept lookup for {} failed: {:#}", peptide_name, e);
+            None
+        }
+    };
+
+    let _guard = FOLLOWS_LOCK.lock().await;
+    let mut follows: PeptideFollows = load_setting(&state).await?;
+    if let Some(existing) = follows.find_peptide(peptide_name) {
+        return Ok(existing.clone());
+    }
+    let mut follow = PeptideFollow::new(peptide_name, OffsetDateTime::now_utc());
+    // Only trust the concept when it names the peptide itself
+    if let Some((id, name)) = concept.filter(|(_, name)| name.eq_ignore_ascii_case(peptide_name)) {
+        follow.concept_id = Some(id);
+        follow.concept_name = Some(name);
+    }
+    follows.follows.push(follow.clone());
+    save_setting(&app, &state, &follows).await?;
+    info!("Following {}", peptide_name);
+    Ok(follow)
+}
+
+#[tauri::command]
+pub async fn unfollow_peptide(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    follow_id: String,
+) -> Result<(), CommandError> {
+    let _guard = FOLLOWS_LOCK.lock().await;
+    let mut follows: PeptideFollows = load_setting(&state).await?;
+    follow_mut(&mut follows, &follow_id)?;
+    follows.follows.retain(|follow| follow.id != follow_id);
+    save_setting(&app, &state, &follows).await
+}
+
+/// Check a followed peptide now instead of waiting for the background job
+#[tauri::command]
+pub async fn check_peptide_follow(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    follow_id: String,
+) -> Result<FollowCheck, CommandError> {
+    let _guard = FOLLOWS_LOCK.lock().await;
+    let mut follows: PeptideFollows = load_setting(&state).await?;
+    let follow = follow_mut(&mut follows, &follow_id)?;
+    let (new_works, alert) = check_follow(&state, follow).await?;
+    let follow = follow.clone();
+    save_setting(&app, &state, &follows).await?;
+    Ok(FollowCheck {
+        follow,
+        new_works,
+        alert,
+    })
+}
+
+/// Move a pending work into the literature cache
+#[tauri::command]
+pub async fn cache_followed_work(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    follow_id: String,
+    entry_id: String,
+) -> Result<LiteratureEntry, CommandError> {
+    let _guard = FOLLOWS_LOCK.lock().await;
+    let mut follows: PeptideFollows = load_setting(&state).await?;
+    let mut entry = follow_mut(&mut follows, &follow_id)?
+        .take_pending(&entry_id)
+        .ok_or_else(|| CommandError::not_found("This paper is no longer pending"))?;
+    entry.indexed_at = OffsetDateTime::now_utc();
+    let entry = state
+        .db
+        .run(move |storage| storage.cache_literature(&entry).map(|_| entry))
+        .await
+        .map_err(|e| {
+            error!("Failed to cache followed work: {:#}", e);
+            CommandError::with_context(e, "Failed to save paper")
+        })?;
+    save_setting(&app, &state, &follows).await?;
+    Ok(entry)
+}
+
+/// Drop a pending work without caching it
+#[tauri::command]
+pub async fn dismiss_followed_work(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    follow_id: String,
+    entry_id: String,
+) -> Result<(), CommandError> {
+    let _guard = FOLLOWS_LOCK.lock().await;
+    let mut follows: PeptideFollows = load_setting(&state).await?;
+    follow_mut(&mut follows, &follow_id)?
+        .take_pending(&entry_id)
+        .ok_or_else(|| CommandError::not_found("This paper is no longer pending"))?;
+    save_setting(&app, &state, &follows).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_alert_names_the_peptide() {
+        let follow = PeptideFollow::new("TB-500", OffsetDateTime::now_utc());
+        let works: Vec<LiteratureEntry> = (1..=6)
+            .map(|i| LiteratureEntry::new("openalex".to_string(), format!("Paper {}", i)))
+            .collect();
+
+        let alert = build_alert(&follow, &works);
+        assert_eq!(alert.title, "6 new papers on TB-500");
+        assert!(alert.message.ends_with("…and 1 more"));
+        assert_eq!(alert.related_type.as_deref(), Some("peptide_follow"));
+    }
+}
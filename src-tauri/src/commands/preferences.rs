@@ -0,0 +1,45 @@
+use peptrack_core::UnitPreferences;
+use tauri::{AppHandle, State};
+use tracing::info;
+
+use crate::commands::settings::{load_setting, save_setting};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Saved unit preferences, or the metric defaults
+pub(crate) async fn load_unit_preferences(state: &AppState) -> Result<UnitPreferences, CommandError> {
+    load_setting(state).await
+}
+
+// ========== Preference Commands ==========
+
+#[tauri::command]
+pub async fn get_unit_preferences(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<UnitPreferences, CommandError> {
+    load_unit_preferences(&state).await
+}
+
+/// Save how doses, weights and lengths are shown and entered
+///
+/// Stored values stay in mg, kg and cm, so this never changes existing records.
+#[tauri::command]
+pub async fn update_unit_preferences(
+    app: AppHandle,
+    state: State<'_, std::sync::Arc<AppState>>,
+    mut preferences: UnitPreferences,
+) -> Result<UnitPreferences, CommandError> {
+    for compound in &mut preferences.iu_compounds {
+        compound.peptide_name = compound.peptide_name.trim().to_string();
+    }
+    info!(
+        "Updating unit preferences: {:?}, {:?}, {:?}, {} IU compounds",
+        preferences.dose_unit,
+        preferences.weight_unit,
+        preferences.length_unit,
+        preferences.iu_compounds.len()
+    );
+
+    save_setting(&app, &state, &preferences).await?;
+    Ok(preferences)
+}
@@ -0,0 +1,643 @@
+use anyhow::{Context, Result};
+use peptrack_core::models::{
+    Alert, AlertSeverity, AlertType, ObservationStatus, ObservedPrice, PriceHistory, PriceObservation,
+};
+use peptrack_core::{ScrapingSettings, Setting};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+use tauri_plugin_notification::NotificationExt;
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::commands::settings::{
+    load_setting, load_setting_or_default, notify_setting_changed, save_setting,
+};
+use crate::commands::suppliers::scrape_prices;
+use crate::error::CommandError;
+use crate::events::AppEvent;
+use crate::state::AppState;
+
+/// Price monitor configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceMonitorSettings {
+    pub enabled: bool,
+    /// Hours between re-scrapes
+    pub interval_hours: u32,
+    /// Minimum price increase (percent) that creates an alert
+    pub increase_threshold_percent: f32,
+    /// Minimum price decrease (percent) that creates an alert
+    pub decrease_threshold_percent: f32,
+    /// Create an alert when a product goes out of stock
+    pub alert_on_out_of_stock: bool,
+    pub last_run: Option<String>,
+    pub next_run: Option<String>,
+}
+
+impl Setting for PriceMonitorSettings {
+    const KEY: &'static str = "price_monitor.settings";
+    const LEGACY_FILE: Option<&'static str> = Some("price_monitor.json");
+
+    fn validate(&self) -> Result<(), String> {
+        if self.interval_hours == 0 {
+            return Err("Interval must be at least 1 hour".to_string());
+        }
+        for (label, percent) in [
+            ("Increase", self.increase_threshold_percent),
+            ("Decrease", self.decrease_threshold_percent),
+        ] {
+            if !percent.is_finite() || percent < 0.0 {
+                return Err(format!("{} threshold must be zero or more percent", label));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for PriceMonitorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: 24,
+            increase_threshold_percent: 5.0,
+            decrease_threshold_percent: 5.0,
+            alert_on_out_of_stock: true,
+            last_run: None,
+            next_run: None,
+        }
+    }
+}
+
+/// Summary of a single price check run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceCheckSummary {
+    pub checked_urls: usize,
+    /// Scraped prices waiting for review before they become price history
+    pub observations_queued: usize,
+    pub failures: Vec<String>,
+    /// URLs left for the next run because the crawl budget ran out
+    #[serde(default)]
+    pub skipped_urls: usize,
+}
+
+/// Price monitor state for the background re-scraping task
+#[derive(Clone)]
+pub struct PriceMonitorState {
+    task_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+    run_lock: Arc<Mutex<()>>,
+    app_handle: Arc<Mutex<Option<AppHandle>>>,
+}
+
+impl Default for PriceMonitorState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PriceMonitorState {
+    pub fn new() -> Self {
+        Self {
+            task_handle: Arc::new(Mutex::new(None)),
+            run_lock: Arc::new(Mutex::new(())),
+            app_handle: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub async fn set_app_handle(&self, handle: AppHandle) {
+        *self.app_handle.lock().await = Some(handle);
+    }
+
+    async fn send_notification(&self, title: &str, body: &str) {
+        if let Some(handle) = self.app_handle.lock().await.as_ref() {
+            handle
+                .notification()
+                .builder()
+                .title(title)
+                .body(body)
+                .show()
+                .ok();
+        }
+    }
+
+    /// Start the background price monitor task
+    pub async fn start_monitor(&self, app_state: Arc<AppState>) {
+        let monitor = self.clone();
+
+        let handle = tokio::spawn(async move {
+            info!("Background price monitor started");
+
+            loop {
+                // Can't be read while the database is locked; treated as disabled
+                let settings: PriceMonitorSettings = app_state
+                    .db
+                    .run(|storage| storage.load_setting_or_default())
+                    .await
+                    .unwrap_or_default();
+
+                if !settings.enabled {
+                    // Sleep longer when disabled to save CPU
+                    tokio::time::sleep(tokio::time::Duration::from_secs(300)).await;
+                    continue;
+                }
+
+                let due = match &settings.next_run {
+                    Some(next_run) => match OffsetDateTime::parse(
+                        next_run,
+                        &time::format_description::well_known::Rfc3339,
+                    ) {
+                        Ok(next_run_time) => OffsetDateTime::now_utc() >= next_run_time,
+                        Err(e) => {
+                            warn!("Failed to parse next price check time: {:#}", e);
+                            true
+                        }
+                    },
+                    None => true,
+                };
+
+                if due {
+                    info!("Scheduled price check triggered");
+
+                    match monitor.run_check(&app_state).await {
+                        Ok(summary) if summary.observations_queued > 0 => {
+                            monitor
+                                .send_notification(
+                                    "💲 Prices to Review",
+                                    &format!(
+                                        "{} scraped price(s) from {} tracked product(s) await review",
+                                        summary.observations_queued, summary.checked_urls
+                                    ),
+                                )
+                                .await;
+                        }
+                        Ok(_) => {}
+                        Err(e) => error!("Scheduled price check failed: {:#}", e),
+                    }
+                }
+
+                // Check every minute
+                tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+            }
+        });
+
+        *self.task_handle.lock().await = Some(handle);
+        info!("Background price monitor task spawned");
+    }
+
+    /// Re-scrape every saved product URL once and queue the prices for review
+    async fn run_check(&self, app_state: &AppState) -> Result<PriceCheckSummary> {
+        let _guard = self
+            .run_lock
+            .try_lock()
+            .map_err(|_| anyhow::anyhow!("A price check is already in progress"))?;
+
+        let summary = check_supplier_prices(app_state).await?;
+        app_state.events.emit(AppEvent::PricesChecked(summary.clone()));
+
+        let now = OffsetDateTime::now_utc();
+        let saved = app_state
+            .db
+            .run(move |storage| {
+                let mut current: PriceMonitorSettings = storage.load_setting_or_default()?;
+                current.last_run = Some(format_rfc3339(now));
+                current.next_run = Some(calculate_next_run(now, current.interval_hours));
+                storage.save_setting(&current)
+            })
+            .await;
+        match saved {
+            Ok(()) => {
+                if let Some(handle) = self.app_handle.lock().await.as_ref() {
+                    notify_setting_changed(handle, PriceMonitorSettings::KEY);
+                }
+            }
+            Err(e) => warn!("Failed to save price monitor run times: {:#}", e),
+        }
+
+        info!(
+            "Price check complete: {} urls, {} observations queued",
+            summary.checked_urls, summary.observations_queued
+        );
+        Ok(summary)
+    }
+}
+
+/// Gets the current price monitor settings
+#[tauri::command]
+pub async fn get_price_monitor_settings(
+    state: State<'_, Arc<AppState>>,
+) -> Result<PriceMonitorSettings, CommandError> {
+    load_setting(&state).await
+}
+
+/// Updates the price monitor settings
+#[tauri::command]
+pub async fn update_price_monitor_settings(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    settings: PriceMonitorSettings,
+) -> Result<PriceMonitorSettings, CommandError> {
+    info!(
+        "Updating price monitor: enabled={}, interval={}h",
+        settings.enabled, settings.interval_hours
+    );
+
+    let mut updated = settings;
+    updated.next_run = if updated.enabled {
+        Some(calculate_next_run(OffsetDateTime::now_utc(), updated.interval_hours))
+    } else {
+        None
+    };
+
+    save_setting(&app, &state, &updated).await?;
+    Ok(updated)
+}
+
+/// Manually runs a price check across all saved product URLs
+#[tauri::command]
+pub async fn trigger_price_check(
+    monitor_state: State<'_, PriceMonitorState>,
+    app_state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<PriceCheckSummary, CommandError> {
+    info!("Manual price check triggered");
+
+    monitor_state.run_check(&app_state).await.map_err(|e| {
+        error!("Price check failed: {:#}", e);
+        CommandError::with_context(e, "Price check failed")
+    })
+}
+
+/// Lists scraped prices, only those with `status` when given
+#[tauri::command]
+pub async fn list_price_observations(
+    app_state: State<'_, std::sync::Arc<AppState>>,
+    status: Option<ObservationStatus>,
+) -> Result<Vec<PriceObservation>, CommandError> {
+    app_state
+        .db
+        .run(move |storage| storage.list_price_observations(status))
+        .await
+        .map_err(|e| {
+            error!("Failed to list price observations: {:#}", e);
+            CommandError::with_context(e, "Failed to list price observations")
+        })
+}
+
+/// Records a scraped price as price history and raises any price alert
+///
+/// `match_index` picks one of the prices found on the page, the lowest by
+/// default.
+#[tauri::command]
+pub async fn accept_price_observation(
+    app_state: State<'_, std::sync::Arc<AppState>>,
+    observation_id: String,
+    match_index: Option<usize>,
+) -> Result<PriceHistory, CommandError> {
+    let observation = pending_observation(&app_state, &observation_id).await?;
+    if let Some(index) = match_index {
+        if index >= observation.matches.len() {
+            return Err(CommandError::invalid_input("No such price on the page"));
+        }
+    }
+
+    let (supplier_id, peptide_name) = (observation.supplier_id.clone(), observation.peptide_name.clone());
+    let previous = app_state
+        .db
+        .run(move |storage| storage.get_latest_price(&supplier_id, &peptide_name))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to load the last price"))?;
+    let id = observation_id.clone();
+    let entry = app_state
+        .db
+        .run(move |storage| storage.accept_price_observation(&id, match_index))
+        .await
+        .map_err(|e| {
+            error!("Failed to accept price observation {}: {:#}", observation_id, e);
+            CommandError::with_context(e, "Failed to accept price")
+        })?;
+    info!(
+        "Accepted price {:.2}/mg for {} from observation {}",
+        entry.cost_per_mg, entry.peptide_name, observation_id
+    );
+
+    let settings: PriceMonitorSettings = load_setting_or_default(&app_state).await;
+    if let Some(change) = evaluate_price_change(
+        previous.as_ref(),
+        Some(entry.cost_per_mg),
+        observation.in_stock.unwrap_or(true),
+        &settings,
+    ) {
+        let supplier_id = observation.supplier_id.clone();
+        let supplier_name = app_state
+            .db
+            .run(move |storage| storage.get_supplier(&supplier_id))
+            .await
+            .ok()
+            .flatten()
+            .map(|supplier| supplier.name)
+            .unwrap_or_else(|| "supplier".to_string());
+        let mut alert = Alert::new(
+            change.alert_type,
+            change.severity,
+            format!("{}: {} at {}", change.label, observation.peptide_name, supplier_name),
+            change.message,
+        );
+        alert.related_id = Some(observation.supplier_id.clone());
+        alert.related_type = Some("supplier".to_string());
+
+        // The price is recorded either way; a failed alert is only logged
+        let raised = alert.clone();
+        match app_state.db.run(move |storage| storage.raise_alert(&raised)).await {
+            Ok(Some(delivery)) => app_state.notifier.alert(&alert, delivery),
+            Ok(None) => {}
+            Err(e) => warn!("Failed to create price alert: {:#}", e),
+        }
+    }
+
+    Ok(entry)
+}
+
+/// Discards a scraped price so it never becomes price history
+#[tauri::command]
+pub async fn reject_price_observation(
+    app_state: State<'_, std::sync::Arc<AppState>>,
+    observation_id: String,
+) -> Result<PriceObservation, CommandError> {
+    pending_observation(&app_state, &observation_id).await?;
+    let id = observation_id.clone();
+    app_state
+        .db
+        .run(move |storage| storage.reject_price_observation(&id))
+        .await
+        .map_err(|e| {
+            error!("Failed to reject price observation {}: {:#}", observation_id, e);
+            CommandError::with_context(e, "Failed to reject price")
+        })
+}
+
+// Helper functions
+
+/// The observation with `observation_id`, if it's still awaiting review
+async fn pending_observation(
+    app_state: &AppState,
+    observation_id: &str,
+) -> Result<PriceObservation, CommandError> {
+    let id = observation_id.to_string();
+    let observation = app_state
+        .db
+        .run(move |storage| storage.get_price_observation(&id))
+        .await
+        .map_err(|e| {
+            error!("Failed to load price observation {}: {:#}", observation_id, e);
+            CommandError::with_context(e, "Failed to load price observation")
+        })?
+        .ok_or_else(|| CommandError::not_found("Price observation not found"))?;
+    if observation.status != ObservationStatus::Pending {
+        return Err(CommandError::invalid_input("This price was already reviewed"));
+    }
+    Ok(observation)
+}
+
+async fn check_supplier_prices(app_state: &AppState) -> Result<PriceCheckSummary> {
+    let suppliers = app_state
+        .db
+        .run(|storage| storage.list_suppliers())
+        .await
+        .context("Failed to list suppliers")?;
+
+    let mut summary = PriceCheckSummary {
+        checked_urls: 0,
+        observations_queued: 0,
+        failures: Vec::new(),
+        skipped_urls: 0,
+    };
+
+    // Least recently priced products first, so a run cut short by the
+    // crawl budget picks up the skipped ones next time
+    let mut products = Vec::new();
+    for supplier in &suppliers {
+        for product in &supplier.product_urls {
+            let (supplier_id, peptide_name) = (supplier.id.clone(), product.peptide_name.clone());
+            let last_priced = app_state
+                .db
+                .run(move |storage| storage.get_latest_price(&supplier_id, &peptide_name))
+                .await?
+                .map(|price| price.recorded_at);
+            products.push((last_priced, supplier, product));
+        }
+    }
+    products.sort_by_key(|(last_priced, _, _)| *last_priced);
+
+    let budget = load_setting_or_default::<ScrapingSettings>(app_state).await.crawl_budget as usize;
+    if products.len() > budget {
+        summary.skipped_urls = products.len() - budget;
+        info!(
+            "Crawl budget of {} pages reached; {} product URL(s) wait for the next run",
+            budget, summary.skipped_urls
+        );
+        products.truncate(budget);
+    }
+
+    for (_, supplier, product) in products {
+        summary.checked_urls += 1;
+
+        let outcome = match scrape_prices(
+            app_state,
+            &product.url,
+            Some(&product.peptide_name),
+            supplier.scraping_profile.as_ref(),
+        )
+        .await
+        {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                warn!("Failed to scrape {}: {}", product.url, e);
+                summary.failures.push(format!("{}: {}", product.url, e));
+                continue;
+            }
+        };
+
+        // Nothing to record; the last price isn't carried forward
+        if outcome.matches.is_empty() {
+            warn!("No price found on {}", product.url);
+            summary
+                .failures
+                .push(format!("{}: no price found", product.url));
+            continue;
+        }
+
+        let mut observation = PriceObservation::new(
+            supplier.id.as_str(),
+            product.peptide_name.as_str(),
+            product.url.as_str(),
+        );
+        if let Some(currency) = &supplier.currency {
+            observation.currency = currency.clone();
+        }
+        observation.in_stock = Some(outcome.in_stock);
+        observation.matches = outcome
+            .matches
+            .into_iter()
+            .map(|m| ObservedPrice {
+                cost_per_mg: m.price_per_mg,
+                context: m.context,
+                pattern_type: m.pattern_type,
+            })
+            .collect();
+
+        app_state
+            .db
+            .run(move |storage| storage.add_price_observation(&observation))
+            .await
+            .context("Failed to queue price observation")?;
+        summary.observations_queued += 1;
+    }
+
+    Ok(summary)
+}
+
+/// A price or stock change that crossed a configured threshold
+struct PriceChange {
+    alert_type: AlertType,
+    severity: AlertSeverity,
+    label: &'static str,
+    message: String,
+}
+
+fn evaluate_price_change(
+    previous: Option<&PriceHistory>,
+    new_price: Option<f32>,
+    in_stock: bool,
+    settings: &PriceMonitorSettings,
+) -> Option<PriceChange> {
+    let previous = previous?;
+
+    if !in_stock && previous.in_stock != Some(false) {
+        if !settings.alert_on_out_of_stock {
+            return None;
+        }
+        return Some(PriceChange {
+            alert_type: AlertType::OutOfStock,
+            severity: AlertSeverity::Warning,
+            label: "Out of Stock",
+            message: format!(
+                "Product is no longer in stock (last price ${:.2}/mg)",
+                previous.cost_per_mg
+            ),
+        });
+    }
+
+    let new_price = new_price?;
+    if previous.cost_per_mg <= 0.0 {
+        return None;
+    }
+
+    let change_percent = (new_price - previous.cost_per_mg) / previous.cost_per_mg * 100.0;
+    let message = format!(
+        "Price changed from ${:.2}/mg to ${:.2}/mg ({:+.1}%)",
+        previous.cost_per_mg, new_price, change_percent
+    );
+
+    if change_percent >= settings.increase_threshold_percent {
+        Some(PriceChange {
+            alert_type: AlertType::PriceIncrease,
+            severity: AlertSeverity::Warning,
+            label: "Price Increase",
+            message,
+        })
+    } else if -change_percent >= settings.decrease_threshold_percent {
+        Some(PriceChange {
+            alert_type: AlertType::PriceDecrease,
+            severity: AlertSeverity::Info,
+            label: "Price Drop",
+            message,
+        })
+    } else {
+        None
+    }
+}
+
+fn format_rfc3339(value: OffsetDateTime) -> String {
+    value
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap_or_else(|_| value.to_string())
+}
+
+fn calculate_next_run(from: OffsetDateTime, interval_hours: u32) -> String {
+    format_rfc3339(from + time::Duration::hours(interval_hours.max(1) as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn previous(cost_per_mg: f32, in_stock: Option<bool>) -> PriceHistory {
+        let mut entry = PriceHistory::new("supplier-1", "BPC-157", cost_per_mg);
+        entry.in_stock = in_stock;
+        entry
+    }
+
+    #[test]
+    fn test_price_change_thresholds() {
+        let settings = PriceMonitorSettings::default();
+        let prev = previous(2.0, Some(true));
+
+        let increase = evaluate_price_change(Some(&prev), Some(2.2), true, &settings).unwrap();
+        assert_eq!(increase.alert_type, AlertType::PriceIncrease);
+
+        let decrease = evaluate_price_change(Some(&prev), Some(1.8), true, &settings).unwrap();
+        assert_eq!(decrease.alert_type, AlertType::PriceDecrease);
+
+        assert!(evaluate_price_change(Some(&prev), Some(2.05), true, &settings).is_none());
+        assert!(evaluate_price_change(None, Some(5.0), true, &settings).is_none());
+    }
+
+    #[test]
+    fn test_out_of_stock_only_alerts_on_transition() {
+        let settings = PriceMonitorSettings::default();
+
+        let change = evaluate_price_change(Some(&previous(2.0, Some(true))), None, false, &settings);
+        assert_eq!(change.unwrap().alert_type, AlertType::OutOfStock);
+
+        let repeat = evaluate_price_change(Some(&previous(2.0, Some(false))), None, false, &settings);
+        assert!(repeat.is_none());
+    }
+
+    #[test]
+    fn test_settings_reject_bad_thresholds() {
+        assert!(PriceMonitorSettings::default().validate().is_ok());
+
+        for percent in [-1.0, f32::NAN, f32::INFINITY] {
+            let settings = PriceMonitorSettings {
+                increase_threshold_percent: percent,
+                ..Default::default()
+            };
+            assert!(settings.validate().is_err(), "{}", percent);
+            let settings = PriceMonitorSettings {
+                decrease_threshold_percent: percent,
+                ..Default::default()
+            };
+            assert!(settings.validate().is_err(), "{}", percent);
+        }
+    }
+
+    #[test]
+    fn test_settings_deserialization() {
+        let json = r#"{
+            "enabled": true,
+            "intervalHours": 12,
+            "increaseThresholdPercent": 10.0,
+            "decreaseThresholdPercent": 2.5,
+            "alertOnOutOfStock": false,
+            "lastRun": null,
+            "nextRun": null
+        }"#;
+
+        let settings: PriceMonitorSettings = serde_json::from_str(json).unwrap();
+        assert!(settings.enabled);
+        assert_eq!(settings.interval_hours, 12);
+        assert!(!settings.alert_on_out_of_stock);
+    }
+}
@@ -0,0 +1,196 @@
+use peptrack_core::{
+    PeptideProtocol, ProtocolPackage, SharedReference, SharedSchedule, SharedTitrationPhase,
+};
+use serde::Serialize;
+use tauri::State;
+use tracing::{error, info, warn};
+
+use crate::commands::interactions::run_interaction_check;
+use crate::commands::schedules::{
+    ensure_schedules_table, load_dose_schedules, new_dose_schedule, write_dose_schedule, CreateSchedulePayload,
+    TitrationPayload, TitrationPhase,
+};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolPackageImport {
+    pub protocol: PeptideProtocol,
+    pub schedules_added: usize,
+    /// Schedules that failed validation, e.g. from a newer version
+    pub schedules_skipped: usize,
+    pub literature_added: usize,
+    /// Papers already in the literature cache, or listed twice in the package
+    pub literature_existing: usize,
+}
+
+/// Bundle a protocol and its schedules into a passphrase-encrypted package
+///
+/// Papers in `literature_ids` go along as citations, without reading notes
+/// or ratings. Dose logs are never included.
+#[tauri::command]
+pub async fn export_protocol_package(
+    state: State<'_, std::sync::Arc<AppState>>,
+    protocol_id: String,
+    passphrase: String,
+    literature_ids: Option<Vec<String>>,
+) -> Result<String, CommandError> {
+    let protocol = state
+        .db
+        .run(move |storage| storage.get_protocol(&protocol_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch protocol"))?
+        .ok_or_else(|| CommandError::not_found("Protocol not found"))?;
+    info!("Exporting protocol package for {}", protocol.name);
+
+    let mut package = ProtocolPackage::new(&protocol);
+    package.schedules = state
+        .on_storage(load_dose_schedules)
+        .await?
+        .into_iter()
+        .filter(|schedule| schedule.protocol_id == protocol.id)
+        .map(|schedule| SharedSchedule {
+            amount_mg: schedule.amount_mg,
+            site: schedule.site,
+            time_of_day: schedule.time_of_day,
+            days_of_week: schedule.days_of_week,
+            notes: schedule.notes,
+            auto_advance: schedule.titration.as_ref().is_some_and(|titration| titration.auto_advance),
+            titration_phases: schedule
+                .titration
+                .map(|titration| {
+                    titration
+                        .phases
+                        .into_iter()
+                        .map(|phase| SharedTitrationPhase {
+                            amount_mg: phase.amount_mg,
+                            duration_days: phase.duration_days,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+        .collect();
+
+    for entry_id in literature_ids.unwrap_or_default() {
+        let id = entry_id.clone();
+        let entry = state
+            .db
+            .run(move |storage| storage.get_literature(&id))
+            .await
+            .map_err(|e| CommandError::with_context(e, "Failed to fetch literature"))?
+            .ok_or_else(|| CommandError::not_found(format!("Literature entry {} not found", entry_id)))?;
+        package.literature.push(SharedReference::from_entry(&entry));
+    }
+
+    package.seal(&passphrase).map_err(|e| {
+        error!("Failed to seal protocol package: {:#}", e);
+        CommandError::invalid_input(format!("{:#}", e))
+    })
+}
+
+/// Add the protocol, schedules and papers in a package as new records
+///
+/// Papers already in the literature cache, or listed twice in the package,
+/// are not added again. Everything is written in one transaction.
+#[tauri::command]
+pub async fn import_protocol_package(
+    state: State<'_, std::sync::Arc<AppState>>,
+    contents: String,
+    passphrase: String,
+) -> Result<ProtocolPackageImport, CommandError> {
+    let package = ProtocolPackage::open(&contents, &passphrase).map_err(|e| {
+        warn!("Failed to open protocol package: {:#}", e);
+        CommandError::invalid_input(format!("{:#}", e))
+    })?;
+    info!(
+        "Importing protocol package {} ({} schedules, {} papers)",
+        package.protocol.name,
+        package.schedules.len(),
+        package.literature.len()
+    );
+
+    let protocol = package.protocol.to_protocol();
+    let shared_schedules = package.schedules.len();
+    let mut schedules = Vec::new();
+    for schedule in package.schedules {
+        let titration = (!schedule.titration_phases.is_empty()).then(|| TitrationPayload {
+            phases: schedule
+                .titration_phases
+                .iter()
+                .map(|phase| TitrationPhase {
+                    amount_mg: phase.amount_mg,
+                    duration_days: phase.duration_days,
+                })
+                .collect(),
+            start_date: None,
+            current_phase: 0,
+            auto_advance: schedule.auto_advance,
+        });
+        let payload = CreateSchedulePayload {
+            protocol_id: protocol.id.clone(),
+            amount_mg: schedule.amount_mg,
+            site: schedule.site,
+            time_of_day: schedule.time_of_day,
+            days_of_week: schedule.days_of_week,
+            notes: schedule.notes,
+            titration,
+        };
+        match new_dose_schedule(&protocol, payload) {
+            Ok(schedule) => schedules.push(schedule),
+            Err(e) => warn!("Skipping shared schedule: {}", e),
+        }
+    }
+
+    let references = package.literature;
+    let (protocol, schedules_added, literature_added, literature_existing) = state
+        .db
+        .run(move |storage| {
+            if !schedules.is_empty() {
+                ensure_schedules_table(storage)?;
+            }
+            let cached = storage.list_literature()?;
+            let mut literature = Vec::new();
+            for reference in &references {
+                let known = cached.iter().chain(&literature).any(|entry| reference.matches(entry));
+                if !known {
+                    literature.push(reference.to_entry());
+                }
+            }
+
+            storage.transaction(|tx| {
+                tx.upsert_protocol(&protocol)?;
+                for schedule in &schedules {
+                    write_dose_schedule(tx.connection(), schedule)?;
+                }
+                if !schedules.is_empty() {
+                    tx.invalidate_stats("dose_schedules")?;
+                }
+                for entry in &literature {
+                    tx.cache_literature(entry)?;
+                }
+                Ok(())
+            })?;
+            Ok((protocol, schedules.len(), literature.len(), references.len() - literature.len()))
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to import protocol package: {:#}", e);
+            CommandError::with_context(e, "Failed to import protocol")
+        })?;
+
+    if schedules_added > 0 {
+        if let Err(e) = run_interaction_check(&state, None).await {
+            warn!("Interaction check after importing protocol failed: {:#}", e);
+        }
+    }
+
+    Ok(ProtocolPackageImport {
+        schedules_skipped: shared_schedules - schedules_added,
+        protocol,
+        schedules_added,
+        literature_added,
+        literature_existing,
+    })
+}
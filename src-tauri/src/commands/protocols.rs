@@ -1,9 +1,20 @@
 use anyhow::Result;
 use peptrack_core::models::PeptideProtocol;
-use serde::Deserialize;
+use peptrack_core::{InventoryItem, TrashEntityType, UndoAction, VialStatus};
+use serde::{Deserialize, Serialize};
 use tauri::State;
 use time::OffsetDateTime;
+use tracing::{error, info, warn};
 
+use crate::commands::interactions::run_interaction_check;
+use crate::commands::schedules::{
+    ensure_schedules_table, new_dose_schedule, write_dose_schedule, CreateSchedulePayload, DoseSchedule,
+    TitrationPayload,
+};
+use crate::commands::suppliers::{new_inventory_item, CreateInventoryPayload};
+use crate::commands::trash::move_to_trash;
+use crate::commands::undo::{records, snapshot};
+use crate::error::CommandError;
 use crate::state::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -15,32 +26,187 @@ pub struct ProtocolPayload {
     pub target_concentration_mg_ml: Option<f32>,
 }
 
+/// A new protocol with its dose schedules and first vials
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolBundlePayload {
+    pub protocol: ProtocolPayload,
+    #[serde(default)]
+    pub schedules: Vec<BundleSchedulePayload>,
+    #[serde(default)]
+    pub inventory: Vec<BundleInventoryPayload>,
+}
+
+/// A [`CreateSchedulePayload`] for the protocol being created
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleSchedulePayload {
+    #[serde(default)]
+    pub amount_mg: f32,
+    pub site: Option<String>,
+    pub time_of_day: String,
+    pub days_of_week: Vec<u8>,
+    pub notes: Option<String>,
+    pub titration: Option<TitrationPayload>,
+}
+
+impl BundleSchedulePayload {
+    fn for_protocol(self, protocol_id: &str) -> CreateSchedulePayload {
+        CreateSchedulePayload {
+            protocol_id: protocol_id.to_string(),
+            amount_mg: self.amount_mg,
+            site: self.site,
+            time_of_day: self.time_of_day,
+            days_of_week: self.days_of_week,
+            notes: self.notes,
+            titration: self.titration,
+        }
+    }
+}
+
+/// A [`CreateInventoryPayload`] for the protocol being created
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleInventoryPayload {
+    pub supplier_id: Option<String>,
+    pub vial_number: Option<String>,
+    pub vial_status: Option<VialStatus>,
+    pub purchase_date: Option<OffsetDateTime>,
+    pub expiry_date: Option<OffsetDateTime>,
+    pub cost_per_mg: Option<f32>,
+    pub currency: Option<String>,
+    pub quantity_mg: Option<f32>,
+    pub concentration_mg_ml: Option<f32>,
+    pub batch_number: Option<String>,
+    pub lot_number: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl BundleInventoryPayload {
+    fn for_protocol(self, protocol_id: &str) -> CreateInventoryPayload {
+        CreateInventoryPayload {
+            protocol_id: protocol_id.to_string(),
+            supplier_id: self.supplier_id,
+            vial_number: self.vial_number,
+            vial_status: self.vial_status,
+            purchase_date: self.purchase_date,
+            expiry_date: self.expiry_date,
+            cost_per_mg: self.cost_per_mg,
+            currency: self.currency,
+            quantity_mg: self.quantity_mg,
+            concentration_mg_ml: self.concentration_mg_ml,
+            batch_number: self.batch_number,
+            lot_number: self.lot_number,
+            notes: self.notes,
+        }
+    }
+}
+
+/// The records created by [`create_protocol_bundle`]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolBundle {
+    pub protocol: PeptideProtocol,
+    pub schedules: Vec<DoseSchedule>,
+    pub inventory: Vec<InventoryItem>,
+}
+
+fn new_protocol(payload: ProtocolPayload) -> PeptideProtocol {
+    let mut protocol = PeptideProtocol::new(payload.name, payload.peptide_name);
+    protocol.notes = payload.notes;
+    protocol.target_concentration_mg_ml = payload.target_concentration_mg_ml;
+    protocol.updated_at = OffsetDateTime::now_utc();
+    protocol
+}
+
 #[tauri::command]
 pub async fn list_protocols(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<Vec<PeptideProtocol>, String> {
+) -> Result<Vec<PeptideProtocol>, CommandError> {
     state
-        .storage
-        .list_protocols()
-        .map_err(|err| err.to_string())
+        .db
+        .run(|storage| storage.list_protocols())
+        .await
+        .map_err(CommandError::from)
 }
 
 #[tauri::command]
 pub async fn save_protocol(
     state: State<'_, std::sync::Arc<AppState>>,
     payload: ProtocolPayload,
-) -> Result<PeptideProtocol, String> {
-    let mut protocol = PeptideProtocol::new(payload.name, payload.peptide_name);
-    protocol.notes = payload.notes;
-    protocol.target_concentration_mg_ml = payload.target_concentration_mg_ml;
-    protocol.updated_at = OffsetDateTime::now_utc();
-
+) -> Result<PeptideProtocol, CommandError> {
+    let protocol = new_protocol(payload);
     state
-        .storage
-        .upsert_protocol(&protocol)
-        .map_err(|err| err.to_string())?;
+        .db
+        .run(move |storage| storage.upsert_protocol(&protocol).map(|_| protocol))
+        .await
+        .map_err(CommandError::from)
+}
 
-    Ok(protocol)
+/// Create a protocol with its dose schedules and first vials, all or none
+///
+/// Everything is validated before anything is written, and the writes share
+/// one transaction, so a failure never leaves a protocol without the
+/// schedules or vials it was created with.
+#[tauri::command]
+pub async fn create_protocol_bundle(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: ProtocolBundlePayload,
+) -> Result<ProtocolBundle, CommandError> {
+    info!(
+        "Creating protocol {} with {} schedules and {} vials",
+        payload.protocol.name,
+        payload.schedules.len(),
+        payload.inventory.len()
+    );
+
+    let protocol = new_protocol(payload.protocol);
+    let schedules = payload
+        .schedules
+        .into_iter()
+        .map(|schedule| new_dose_schedule(&protocol, schedule.for_protocol(&protocol.id)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let inventory = payload
+        .inventory
+        .into_iter()
+        .map(|item| new_inventory_item(item.for_protocol(&protocol.id)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let bundle = state
+        .db
+        .run(move |storage| {
+            if !schedules.is_empty() {
+                ensure_schedules_table(storage)?;
+            }
+            storage.transaction(|tx| {
+                tx.upsert_protocol(&protocol)?;
+                for schedule in &schedules {
+                    write_dose_schedule(tx.connection(), schedule)?;
+                }
+                if !schedules.is_empty() {
+                    tx.invalidate_stats("dose_schedules")?;
+                }
+                for item in &inventory {
+                    tx.upsert_inventory_item(item)?;
+                }
+                Ok(())
+            })?;
+            Ok(ProtocolBundle { protocol, schedules, inventory })
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to create protocol bundle: {:#}", e);
+            CommandError::with_context(e, "Failed to create protocol")
+        })?;
+
+    if !bundle.schedules.is_empty() {
+        // Activating a schedule may create a new combination with other active protocols
+        if let Err(e) = run_interaction_check(&state, None).await {
+            warn!("Interaction check after creating protocol failed: {:#}", e);
+        }
+    }
+
+    Ok(bundle)
 }
 
 /// Toggle the favorite status of a protocol
@@ -48,11 +214,12 @@ pub async fn save_protocol(
 pub async fn toggle_protocol_favorite(
     state: State<'_, std::sync::Arc<AppState>>,
     protocol_id: String,
-) -> Result<bool, String> {
+) -> Result<bool, CommandError> {
     state
-        .storage
-        .toggle_protocol_favorite(&protocol_id)
-        .map_err(|err| err.to_string())
+        .db
+        .run(move |storage| storage.toggle_protocol_favorite(&protocol_id))
+        .await
+        .map_err(CommandError::from)
 }
 
 /// Update tags for a protocol
@@ -61,11 +228,12 @@ pub async fn update_protocol_tags(
     state: State<'_, std::sync::Arc<AppState>>,
     protocol_id: String,
     tags: Vec<String>,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, CommandError> {
     state
-        .storage
-        .update_protocol_tags(&protocol_id, tags)
-        .map_err(|err| err.to_string())
+        .db
+        .run(move |storage| storage.update_protocol_tags(&protocol_id, tags))
+        .await
+        .map_err(CommandError::from)
 }
 
 /// Add a tag to a protocol
@@ -74,11 +242,12 @@ pub async fn add_protocol_tag(
     state: State<'_, std::sync::Arc<AppState>>,
     protocol_id: String,
     tag: String,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, CommandError> {
     state
-        .storage
-        .add_protocol_tag(&protocol_id, tag)
-        .map_err(|err| err.to_string())
+        .db
+        .run(move |storage| storage.add_protocol_tag(&protocol_id, tag))
+        .await
+        .map_err(CommandError::from)
 }
 
 /// Remove a tag from a protocol
@@ -87,35 +256,33 @@ pub async fn remove_protocol_tag(
     state: State<'_, std::sync::Arc<AppState>>,
     protocol_id: String,
     tag: String,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, CommandError> {
     state
-        .storage
-        .remove_protocol_tag(&protocol_id, &tag)
-        .map_err(|err| err.to_string())
+        .db
+        .run(move |storage| storage.remove_protocol_tag(&protocol_id, &tag))
+        .await
+        .map_err(CommandError::from)
 }
 
-/// Delete a single protocol
+/// Move a protocol and its dose logs to the trash
 #[tauri::command]
 pub async fn delete_protocol(
     state: State<'_, std::sync::Arc<AppState>>,
     protocol_id: String,
-) -> Result<(), String> {
-    state
-        .storage
-        .delete_protocol(&protocol_id)
-        .map_err(|err| err.to_string())
+) -> Result<(), CommandError> {
+    match move_to_trash(&state, TrashEntityType::Protocol, &[protocol_id]).await? {
+        0 => Err(CommandError::not_found("Protocol not found")),
+        _ => Ok(()),
+    }
 }
 
-/// Bulk delete multiple protocols
+/// Move multiple protocols and their dose logs to the trash
 #[tauri::command]
 pub async fn bulk_delete_protocols(
     state: State<'_, std::sync::Arc<AppState>>,
     protocol_ids: Vec<String>,
-) -> Result<usize, String> {
-    state
-        .storage
-        .bulk_delete_protocols(&protocol_ids)
-        .map_err(|err| err.to_string())
+) -> Result<usize, CommandError> {
+    move_to_trash(&state, TrashEntityType::Protocol, &protocol_ids).await
 }
 
 /// Bulk add a tag to multiple protocols
@@ -124,11 +291,18 @@ pub async fn bulk_add_tag_to_protocols(
     state: State<'_, std::sync::Arc<AppState>>,
     protocol_ids: Vec<String>,
     tag: String,
-) -> Result<usize, String> {
-    state
-        .storage
-        .bulk_add_tag_to_protocols(&protocol_ids, tag)
-        .map_err(|err| err.to_string())
+) -> Result<usize, CommandError> {
+    let label = format!("Tag {} with \"{}\"", records(protocol_ids.len(), "protocol"), tag);
+    let (tagged, before) = state
+        .db
+        .run(move |storage| {
+            let before = snapshot(&protocol_ids, |id| storage.get_protocol(id));
+            Ok((storage.bulk_add_tag_to_protocols(&protocol_ids, tag)?, before))
+        })
+        .await
+        .map_err(CommandError::from)?;
+    state.undo.push(label, UndoAction::RestoreProtocols(before));
+    Ok(tagged)
 }
 
 /// Bulk toggle favorite status for multiple protocols
@@ -137,9 +311,19 @@ pub async fn bulk_toggle_favorite_protocols(
     state: State<'_, std::sync::Arc<AppState>>,
     protocol_ids: Vec<String>,
     is_favorite: bool,
-) -> Result<usize, String> {
-    state
-        .storage
-        .bulk_toggle_favorite_protocols(&protocol_ids, is_favorite)
-        .map_err(|err| err.to_string())
+) -> Result<usize, CommandError> {
+    let (changed, before) = state
+        .db
+        .run(move |storage| {
+            let before = snapshot(&protocol_ids, |id| storage.get_protocol(id));
+            Ok((storage.bulk_toggle_favorite_protocols(&protocol_ids, is_favorite)?, before))
+        })
+        .await
+        .map_err(CommandError::from)?;
+    let verb = if is_favorite { "Favorite" } else { "Unfavorite" };
+    state.undo.push(
+        format!("{} {}", verb, records(changed, "protocol")),
+        UndoAction::RestoreProtocols(before),
+    );
+    Ok(changed)
 }
@@ -0,0 +1,211 @@
+//! Recovering from a corrupted database
+//!
+//! The startup health check records corruption in [`RecoveryState`], which
+//! [`get_recovery_status`] reports along with the latest local backup. The
+//! user then either salvages what can still be read ([`salvage_database`],
+//! then [`apply_salvaged_database`]) or starts from an empty database and
+//! restores a backup ([`recover_from_backup`]). Either way the damaged file
+//! is kept in the `recovery` directory next to the database.
+
+use std::sync::Arc;
+
+use peptrack_core::{RecoveryProgress, SalvageReport};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::commands::restore::{apply_backup, read_restorable_backup, RestoreResult};
+use crate::commands::scheduler_v2::{list_backups, SchedulerState};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Event reporting salvage progress, once per table
+pub const DATABASE_RECOVERY_PROGRESS_EVENT: &str = "database-recovery-progress";
+
+/// Corruption found by the startup health check, until it is recovered from
+pub struct RecoveryState {
+    integrity_result: Mutex<Option<String>>,
+}
+
+impl RecoveryState {
+    /// `integrity_result` is the failed check's output, or `None` when healthy
+    pub fn new(integrity_result: Option<String>) -> Self {
+        Self {
+            integrity_result: Mutex::new(integrity_result),
+        }
+    }
+
+    async fn resolve(&self) {
+        *self.integrity_result.lock().await = None;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupFileInfo {
+    pub path: String,
+    pub modified_at: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryStatus {
+    pub corrupted: bool,
+    /// What the integrity check reported
+    pub integrity_result: Option<String>,
+    /// Newest local backup, offered as an alternative to salvaging
+    pub latest_backup: Option<BackupFileInfo>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupRecoveryResult {
+    /// Where the damaged database was kept
+    pub damaged_copy: String,
+    pub restore: RestoreResult,
+}
+
+/// Newest backup in the local backup folder
+async fn latest_backup(scheduler: &SchedulerState) -> Option<BackupFileInfo> {
+    let backups = match scheduler
+        .local_backup_dir()
+        .await
+        .and_then(|dir| list_backups(&dir))
+    {
+        Ok(backups) => backups,
+        Err(e) => {
+            warn!("Failed to look for local backups: {:#}", e);
+            return None;
+        }
+    };
+    let (path, modified) = backups.into_iter().next()?;
+    Some(BackupFileInfo {
+        size_bytes: std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+        modified_at: OffsetDateTime::from(modified)
+            .format(&Rfc3339)
+            .unwrap_or_default(),
+        path: path.to_string_lossy().to_string(),
+    })
+}
+
+fn emit_progress(app: &AppHandle, progress: RecoveryProgress) {
+    if let Err(e) = app.emit(DATABASE_RECOVERY_PROGRESS_EVENT, progress) {
+        warn!("Failed to report recovery progress: {}", e);
+    }
+}
+
+// ========== Recovery Commands ==========
+
+/// Whether the database was found corrupted at startup, and the latest backup
+#[tauri::command]
+pub async fn get_recovery_status(
+    recovery: State<'_, RecoveryState>,
+    scheduler: State<'_, SchedulerState>,
+) -> Result<RecoveryStatus, CommandError> {
+    let integrity_result = recovery.integrity_result.lock().await.clone();
+    Ok(RecoveryStatus {
+        corrupted: integrity_result.is_some(),
+        integrity_result,
+        latest_backup: latest_backup(&scheduler).await,
+    })
+}
+
+/// Copy every readable row into a new database, reporting rows recovered and lost
+///
+/// Progress is emitted as [`DATABASE_RECOVERY_PROGRESS_EVENT`]. The current
+/// database is left alone until [`apply_salvaged_database`] is called.
+#[tauri::command]
+pub async fn salvage_database(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<SalvageReport, CommandError> {
+    info!("Salvaging damaged database");
+    state
+        .db
+        .run(move |storage| storage.salvage(|progress| emit_progress(&app, progress)))
+        .await
+        .map_err(|e| {
+            error!("Failed to salvage database: {:#}", e);
+            CommandError::with_context(e, "Failed to salvage database")
+        })
+}
+
+/// Replace the damaged database with the salvage `salvage_id`
+///
+/// Returns where the damaged database was kept.
+#[tauri::command]
+pub async fn apply_salvaged_database(
+    state: State<'_, Arc<AppState>>,
+    recovery: State<'_, RecoveryState>,
+    salvage_id: String,
+) -> Result<String, CommandError> {
+    info!("Applying salvaged database {}", salvage_id);
+    let damaged = state
+        .db
+        .run(move |storage| storage.apply_salvage(&salvage_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to apply salvaged database: {:#}", e);
+            CommandError::with_context(e, "Failed to apply salvaged database")
+        })?;
+
+    recovery.resolve().await;
+    Ok(damaged.to_string_lossy().to_string())
+}
+
+/// Replace the damaged database with an empty one and restore a backup into it
+///
+/// Uses the latest local backup unless `file_path` is given. The backup is
+/// read before anything is replaced, so a wrong password changes nothing.
+#[tauri::command]
+pub async fn recover_from_backup(
+    state: State<'_, Arc<AppState>>,
+    recovery: State<'_, RecoveryState>,
+    scheduler: State<'_, SchedulerState>,
+    file_path: Option<String>,
+    password: Option<String>,
+) -> Result<BackupRecoveryResult, CommandError> {
+    let file_path = match file_path {
+        Some(path) => path,
+        None => latest_backup(&scheduler)
+            .await
+            .map(|backup| backup.path)
+            .ok_or_else(|| CommandError::not_found("No local backup found"))?,
+    };
+    info!("Recovering database from backup {}", file_path);
+
+    let backup_data = read_restorable_backup(&file_path, password.as_deref())?;
+    let damaged = state
+        .db
+        .run(|storage| storage.reset_damaged_database())
+        .await
+        .map_err(|e| {
+            error!("Failed to reset damaged database: {:#}", e);
+            CommandError::with_context(e, "Failed to reset damaged database")
+        })?;
+
+    let restore = apply_backup(&state, backup_data).await?;
+    recovery.resolve().await;
+    Ok(BackupRecoveryResult {
+        damaged_copy: damaged.to_string_lossy().to_string(),
+        restore,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn recovering_clears_the_startup_corruption() {
+        let recovery = RecoveryState::new(Some("*** in database main ***".into()));
+        assert!(recovery.integrity_result.lock().await.is_some());
+
+        recovery.resolve().await;
+        assert!(recovery.integrity_result.lock().await.is_none());
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use peptrack_core::StorageManager;
+use peptrack_reports::{
+    build_fhir_bundle, render_pdf, FhirInput, PageSize, ReportInput, ReportOptions, ReportSection, ReportSummary,
+    ReportTemplate, ScheduledDoses,
+};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use time::{Date, OffsetDateTime};
+use tracing::{error, info};
+
+use crate::commands::dates::{parse_date, parse_optional_date};
+use crate::commands::schedules::enabled_schedule_usage;
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Report range and layout; dates are RFC3339 strings and both days are included
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateReportPayload {
+    pub start_date: String,
+    pub end_date: String,
+    #[serde(default)]
+    pub template: ReportTemplate,
+    /// Overrides the template's sections
+    #[serde(default)]
+    pub sections: Option<Vec<ReportSection>>,
+    #[serde(default)]
+    pub page_size: PageSize,
+    pub title: Option<String>,
+    pub prepared_for: Option<String>,
+    /// Include protocol notes and side effect descriptions
+    #[serde(default)]
+    pub include_notes: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedReport {
+    /// Suggested name for the save dialog
+    pub file_name: String,
+    pub data_base64: String,
+    pub page_count: usize,
+}
+
+impl GenerateReportPayload {
+    fn range(&self) -> Result<(Date, Date), CommandError> {
+        let start = parse_date(&self.start_date)?;
+        let end = parse_date(&self.end_date)?;
+        if start > end {
+            return Err(CommandError::invalid_input(
+                "Report start date must not be after the end date",
+            ));
+        }
+        Ok((start, end))
+    }
+
+    fn options(&self) -> ReportOptions {
+        ReportOptions {
+            template: self.template,
+            sections: self.sections.clone(),
+            page_size: self.page_size,
+            title: self.title.clone(),
+            prepared_for: self.prepared_for.clone(),
+            include_notes: self.include_notes,
+        }
+    }
+}
+
+pub(crate) fn build_summary(storage: &StorageManager, start: Date, end: Date) -> Result<ReportSummary> {
+    let protocols = storage.list_protocols().context("Failed to load protocols")?;
+    let doses = storage.list_dose_logs().context("Failed to load dose logs")?;
+    let body_metrics = storage.list_body_metrics().context("Failed to load body metrics")?;
+    let side_effects = storage.list_side_effects().context("Failed to load side effects")?;
+    let inventory = storage.list_inventory().context("Failed to load inventory")?;
+    let schedules: Vec<ScheduledDoses> = enabled_schedule_usage(storage)
+        .context("Failed to load dose schedules")?
+        .into_iter()
+        .map(|usage| ScheduledDoses {
+            protocol_id: usage.protocol_id,
+            amount_mg: usage.amount_mg,
+            days_of_week: usage.days_of_week,
+        })
+        .collect();
+
+    let input = ReportInput {
+        start,
+        end,
+        protocols: &protocols,
+        doses: &doses,
+        schedules: &schedules,
+        body_metrics: &body_metrics,
+        side_effects: &side_effects,
+        inventory: &inventory,
+    };
+    Ok(ReportSummary::build(&input, OffsetDateTime::now_utc()))
+}
+
+// ========== Report Commands ==========
+
+/// Renders a PDF report for the given date range
+///
+/// Returns the PDF as base64 for the frontend to save or print.
+#[tauri::command]
+pub async fn generate_report_pdf(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: GenerateReportPayload,
+) -> Result<GeneratedReport, CommandError> {
+    let (start, end) = payload.range()?;
+    info!("Generating {:?} report for {} to {}", payload.template, start, end);
+
+    let summary = state
+        .db
+        .run(move |storage| build_summary(storage, start, end))
+        .await
+        .map_err(|e| {
+            error!("Failed to build report: {:#}", e);
+            CommandError::with_context(e, "Failed to build report")
+        })?;
+    let report = render_pdf(&summary, &payload.options());
+
+    info!(
+        "Report generated ({} pages, {} bytes)",
+        report.page_count,
+        report.pdf.len()
+    );
+    Ok(GeneratedReport {
+        file_name: format!("peptrack-report-{}-to-{}.pdf", start, end),
+        data_base64: STANDARD.encode(&report.pdf),
+        page_count: report.page_count,
+    })
+}
+
+/// FHIR export options; dates are RFC3339 strings and both days are included
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportFhirPayload {
+    /// Absolute path of the `.json` file to write
+    pub file_path: String,
+    /// Shown as the patient's name in the clinician's system
+    pub patient_name: String,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FhirExport {
+    pub path: String,
+    pub medication_administrations: usize,
+    pub observations: usize,
+}
+
+impl ExportFhirPayload {
+    fn range(&self) -> Result<(Option<Date>, Option<Date>), CommandError> {
+        let start = parse_optional_date(self.start_date.as_deref())?;
+        let end = parse_optional_date(self.end_date.as_deref())?;
+        if let (Some(start), Some(end)) = (start, end) {
+            if start > end {
+                return Err(CommandError::invalid_input(
+                    "Export start date must not be after the end date",
+                ));
+            }
+        }
+        Ok((start, end))
+    }
+}
+
+/// Writes dose logs, body metrics and lab results as an HL7 FHIR R4 bundle
+///
+/// Doses become MedicationAdministration resources and metrics and lab
+/// results become Observations, all for one patient named `patient_name`.
+#[tauri::command]
+pub async fn export_fhir_bundle(
+    state: State<'_, std::sync::Arc<AppState>>,
+    payload: ExportFhirPayload,
+) -> Result<FhirExport, CommandError> {
+    let patient_name = payload.patient_name.trim();
+    if patient_name.is_empty() {
+        return Err(CommandError::invalid_input("Enter a name to show for the patient"));
+    }
+    let path = std::path::Path::new(payload.file_path.trim());
+    if !path.is_absolute() {
+        return Err(CommandError::invalid_input("Choose where to save the export"));
+    }
+    let (start, end) = payload.range()?;
+    info!("Exporting FHIR bundle to {}", path.display());
+
+    let load = |storage: &StorageManager| -> Result<_> {
+        Ok((
+            storage.list_protocols().context("Failed to load protocols")?,
+            storage.list_dose_logs().context("Failed to load dose logs")?,
+            storage.list_body_metrics().context("Failed to load body metrics")?,
+            storage.list_lab_results().context("Failed to load lab results")?,
+        ))
+    };
+    let (protocols, doses, body_metrics, lab_results) = state.db.run(load).await.map_err(|e| {
+        error!("Failed to load records for FHIR export: {:#}", e);
+        CommandError::with_context(e, "Failed to load records")
+    })?;
+
+    let exported = build_fhir_bundle(
+        &FhirInput {
+            patient_name,
+            start,
+            end,
+            protocols: &protocols,
+            doses: &doses,
+            body_metrics: &body_metrics,
+            lab_results: &lab_results,
+        },
+        OffsetDateTime::now_utc(),
+    );
+    let json = serde_json::to_vec_pretty(&exported.bundle)
+        .map_err(|e| CommandError::internal(format!("Failed to encode FHIR bundle: {}", e)))?;
+    std::fs::write(path, json).map_err(|e| {
+        error!("Failed to write FHIR bundle to {}: {}", path.display(), e);
+        CommandError::with_context(e, "Failed to write FHIR export")
+    })?;
+
+    info!(
+        "FHIR bundle written ({} medication administrations, {} observations)",
+        exported.medication_administrations, exported.observations
+    );
+    Ok(FhirExport {
+        path: path.display().to_string(),
+        medication_administrations: exported.medication_administrations,
+        observations: exported.observations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_defaults_and_range() {
+        let payload: GenerateReportPayload = serde_json::from_str(
+            r#"{"startDate": "2024-01-01T00:00:00Z", "endDate": "2024-01-31T23:59:59Z"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(payload.template, ReportTemplate::Full);
+        assert_eq!(payload.options().sections(), ReportSection::ALL.to_vec());
+        let (start, end) = payload.range().unwrap();
+        assert_eq!(start.to_string(), "2024-01-01");
+        assert_eq!(end.to_string(), "2024-01-31");
+    }
+
+    #[test]
+    fn test_payload_rejects_reversed_range() {
+        let payload: GenerateReportPayload = serde_json::from_str(
+            r#"{"startDate": "2024-02-01T00:00:00Z", "endDate": "2024-01-01T00:00:00Z", "template": "summary", "pageSize": "letter"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(payload.page_size, PageSize::Letter);
+        assert!(payload.range().is_err());
+    }
+
+    #[test]
+    fn test_fhir_payload_range_is_optional() {
+        let payload: ExportFhirPayload = serde_json::from_str(
+            r#"{"filePath": "/tmp/peptrack.json", "patientName": "Alex", "startDate": "2024-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+
+        let (start, end) = payload.range().unwrap();
+        assert_eq!(start.unwrap().to_string(), "2024-01-01");
+        assert!(end.is_none());
+    }
+}
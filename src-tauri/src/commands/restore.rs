@@ -1,53 +1,175 @@
 use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use flate2::read::GzDecoder;
+use peptrack_core::{StaticKeyProvider, StorageConfig, StorageManager, StorageTransaction};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
 use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
 use tauri::State;
-use tracing::{info, warn};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tracing::{error, info, warn};
 
-use crate::commands::backup::BackupData;
+use crate::commands::backup::{BackupAttachment, BackupData};
+use crate::error::CommandError;
 use crate::state::AppState;
 
+/// A kind of record in a backup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupTable {
+    Protocols,
+    DoseLogs,
+    Literature,
+    BodyMetrics,
+    JournalEntries,
+    Attachments,
+}
+
+impl BackupTable {
+    const ALL: [BackupTable; 6] = [
+        BackupTable::Protocols,
+        BackupTable::DoseLogs,
+        BackupTable::Literature,
+        BackupTable::BodyMetrics,
+        BackupTable::JournalEntries,
+        BackupTable::Attachments,
+    ];
+}
+
+/// Part of a backup to restore
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableSelection {
+    pub table: BackupTable,
+    /// Only these records; the whole table when `None`
+    #[serde(default)]
+    pub ids: Option<Vec<String>>,
+}
+
 /// Restore data from a backup file.
 ///
-/// If the backup is encrypted, `password` must be provided.
+/// If the backup is encrypted, `password` must be provided. `selection`
+/// limits the restore to some tables or records; everything is restored
+/// without it. Records are written all or nothing: if one can't be saved,
+/// the database is rolled back to how it was before the restore.
 #[tauri::command]
 pub async fn restore_from_backup(
     state: State<'_, std::sync::Arc<AppState>>,
     file_path: String,
     password: Option<String>,
-) -> Result<RestoreResult, String> {
+    selection: Option<Vec<TableSelection>>,
+) -> Result<RestoreResult, CommandError> {
     info!("Restoring from backup: {}", file_path);
 
-    // Read and parse backup file
+    let mut backup_data = read_restorable_backup(&file_path, password.as_deref())?;
+    if let Some(selection) = selection {
+        if selection.is_empty() {
+            return Err(CommandError::invalid_input("Choose something to restore"));
+        }
+        select_records(&mut backup_data, &selection);
+        if BackupTable::ALL.iter().all(|&table| record_values(&backup_data, table).is_empty()) {
+            return Err(CommandError::invalid_input(
+                "Nothing in the backup matches the selection",
+            ));
+        }
+    }
+    apply_backup(&state, backup_data).await
+}
+
+/// The records of a backup table, newest first, for choosing what to restore
+///
+/// Records that can't be read are left out; they wouldn't restore either.
+#[tauri::command]
+pub async fn list_backup_records(
+    file_path: String,
+    password: Option<String>,
+    table: BackupTable,
+) -> Result<Vec<BackupRecordSummary>, CommandError> {
     let backup_data = read_backup_file(&file_path, password.as_deref())
-        .map_err(|e| format!("Failed to read backup file: {}", e))?;
+        .map_err(|e| CommandError::with_context(e, "Failed to read backup file"))?;
+    let mut records = record_summaries(&backup_data, table);
+    records.sort_by_key(|record| std::cmp::Reverse(record.date));
+    Ok(records
+        .into_iter()
+        .map(|record| BackupRecordSummary {
+            id: record.id,
+            label: record.label,
+            date: record.date.and_then(|date| date.format(&Rfc3339).ok()),
+        })
+        .collect())
+}
+
+/// Read a backup file and check it can be restored
+pub(crate) fn read_restorable_backup(
+    file_path: &str,
+    password: Option<&str>,
+) -> Result<BackupData, CommandError> {
+    // Read and parse backup file
+    let backup_data = read_backup_file(file_path, password)
+        .map_err(|e| CommandError::with_context(e, "Failed to read backup file"))?;
 
     // Validate backup
+    if backup_data.metadata.anonymized {
+        return Err(CommandError::invalid_input(
+            "This is an anonymized export for sharing and can't be restored",
+        ));
+    }
+
     if backup_data.protocols.is_empty()
         && backup_data.dose_logs.is_empty()
         && backup_data.literature.is_empty()
+        && backup_data.body_metrics.is_empty()
+        && backup_data.journal_entries.is_empty()
     {
-        return Err("Backup file appears to be empty".to_string());
+        return Err(CommandError::invalid_input("Backup file appears to be empty"));
     }
 
+    Ok(backup_data)
+}
+
+/// Write the records in `backup_data` to storage, all or nothing
+///
+/// Records that can't be read are skipped and counted; a record that can't
+/// be saved rolls the whole restore back. Everything is written in one
+/// transaction, so writes made elsewhere while it runs are kept.
+pub(crate) async fn apply_backup(state: &AppState, backup_data: BackupData) -> Result<RestoreResult, CommandError> {
+    state
+        .db
+        .run(move |storage| storage.transaction(|batch| write_backup_records(batch, backup_data)))
+        .await
+        .map_err(|e| {
+            error!("Restore failed and was rolled back: {:#}", e);
+            CommandError::with_context(e, "Restore failed; nothing was changed")
+        })
+}
+
+fn write_backup_records(batch: &StorageTransaction<'_>, backup_data: BackupData) -> Result<RestoreResult> {
     let mut restored_counts = RestoreCounts {
         protocols: 0,
         dose_logs: 0,
         literature: 0,
+        body_metrics: 0,
+        journal_entries: 0,
+        attachments: 0,
+        skipped: 0,
     };
 
     // Restore protocols
     for protocol_value in backup_data.protocols {
         match serde_json::from_value::<peptrack_core::PeptideProtocol>(protocol_value) {
             Ok(protocol) => {
-                if let Err(e) = state.storage.upsert_protocol(&protocol) {
-                    warn!("Failed to restore protocol: {:#}", e);
-                } else {
-                    restored_counts.protocols += 1;
-                }
+                batch
+                    .upsert_protocol(&protocol)
+                    .with_context(|| format!("Failed to restore protocol {}", protocol.id))?;
+                restored_counts.protocols += 1;
             }
             Err(e) => {
                 warn!("Failed to deserialize protocol: {:#}", e);
+                restored_counts.skipped += 1;
             }
         }
     }
@@ -56,14 +178,14 @@ pub async fn restore_from_backup(
     for dose_value in backup_data.dose_logs {
         match serde_json::from_value::<peptrack_core::DoseLog>(dose_value) {
             Ok(dose) => {
-                if let Err(e) = state.storage.append_dose_log(&dose) {
-                    warn!("Failed to restore dose log: {:#}", e);
-                } else {
-                    restored_counts.dose_logs += 1;
-                }
+                batch
+                    .append_dose_log(&dose)
+                    .with_context(|| format!("Failed to restore dose log {}", dose.id))?;
+                restored_counts.dose_logs += 1;
             }
             Err(e) => {
                 warn!("Failed to deserialize dose log: {:#}", e);
+                restored_counts.skipped += 1;
             }
         }
     }
@@ -72,21 +194,64 @@ pub async fn restore_from_backup(
     for lit_value in backup_data.literature {
         match serde_json::from_value::<peptrack_core::LiteratureEntry>(lit_value) {
             Ok(literature) => {
-                if let Err(e) = state.storage.cache_literature(&literature) {
-                    warn!("Failed to restore literature: {:#}", e);
-                } else {
-                    restored_counts.literature += 1;
-                }
+                batch
+                    .cache_literature(&literature)
+                    .with_context(|| format!("Failed to restore literature {}", literature.id))?;
+                restored_counts.literature += 1;
             }
             Err(e) => {
                 warn!("Failed to deserialize literature: {:#}", e);
+                restored_counts.skipped += 1;
+            }
+        }
+    }
+
+    // Restore body metrics
+    for metric_value in backup_data.body_metrics {
+        match serde_json::from_value::<peptrack_core::BodyMetric>(metric_value) {
+            Ok(metric) => {
+                batch
+                    .upsert_body_metric(&metric)
+                    .with_context(|| format!("Failed to restore body metric {}", metric.id))?;
+                restored_counts.body_metrics += 1;
+            }
+            Err(e) => {
+                warn!("Failed to deserialize body metric: {:#}", e);
+                restored_counts.skipped += 1;
+            }
+        }
+    }
+
+    // Restore journal entries
+    for entry_value in backup_data.journal_entries {
+        match serde_json::from_value::<peptrack_core::JournalEntry>(entry_value) {
+            Ok(entry) => {
+                batch
+                    .upsert_journal_entry(&entry)
+                    .with_context(|| format!("Failed to restore journal entry {}", entry.id))?;
+                restored_counts.journal_entries += 1;
+            }
+            Err(e) => {
+                warn!("Failed to deserialize journal entry: {:#}", e);
+                restored_counts.skipped += 1;
             }
         }
     }
 
+    // Restore attachments after the records they belong to
+    for backup_attachment in backup_data.attachments {
+        restore_attachment(batch, backup_attachment)?;
+        restored_counts.attachments += 1;
+    }
+
     info!(
-        "Restore complete: {} protocols, {} doses, {} literature",
-        restored_counts.protocols, restored_counts.dose_logs, restored_counts.literature
+        "Restore complete: {} protocols, {} doses, {} literature, {} body metrics, {} journal entries, {} attachments",
+        restored_counts.protocols,
+        restored_counts.dose_logs,
+        restored_counts.literature,
+        restored_counts.body_metrics,
+        restored_counts.journal_entries,
+        restored_counts.attachments
     );
 
     Ok(RestoreResult {
@@ -101,25 +266,233 @@ pub async fn restore_from_backup(
 pub async fn preview_backup(
     file_path: String,
     password: Option<String>,
-) -> Result<BackupPreview, String> {
+) -> Result<BackupPreview, CommandError> {
     info!("Previewing backup: {}", file_path);
 
     let backup_data = read_backup_file(&file_path, password.as_deref())
-        .map_err(|e| format!("Failed to read backup file: {}", e))?;
+        .map_err(|e| CommandError::with_context(e, "Failed to read backup file"))?;
 
+    let tables = BackupTable::ALL
+        .iter()
+        .map(|&table| table_summary(&backup_data, table))
+        .collect();
     Ok(BackupPreview {
+        tables,
         metadata: backup_data.metadata,
         protocols_count: backup_data.protocols.len(),
         dose_logs_count: backup_data.dose_logs.len(),
         literature_count: backup_data.literature.len(),
+        body_metrics_count: backup_data.body_metrics.len(),
+        journal_entries_count: backup_data.journal_entries.len(),
+        attachments_count: backup_data.attachments.len(),
+    })
+}
+
+/// Restore a backup into a scratch database and check nothing was lost
+///
+/// The scratch database has a throwaway key and is deleted afterwards, so
+/// the app's own data is never touched. `file_path` must be a backup the
+/// app saved, e.g. from the backup history.
+pub(crate) fn deep_verify_backup_file(file_path: &str, password: Option<&str>) -> Result<BackupVerification> {
+    let backup_data = decode_backup_file(Path::new(file_path), password)?;
+
+    let dir = std::env::temp_dir().join(format!("peptrack-verify-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).context("Failed to create scratch directory")?;
+    let checked = restore_into_scratch_database(&dir, backup_data);
+    if let Err(e) = std::fs::remove_dir_all(&dir) {
+        warn!("Failed to remove scratch database {}: {}", dir.display(), e);
+    }
+    let (integrity_problems, mismatches) = checked?;
+
+    Ok(BackupVerification {
+        verified_at: OffsetDateTime::now_utc().format(&Rfc3339)?,
+        location: file_path.to_string(),
+        verified: integrity_problems.is_empty() && mismatches.is_empty(),
+        integrity_problems,
+        mismatches,
+        error: None,
     })
 }
 
+/// Integrity problems and count mismatches of `backup_data` restored into a
+/// new database in `dir`
+fn restore_into_scratch_database(dir: &Path, backup_data: BackupData) -> Result<(Vec<String>, Vec<CountMismatch>)> {
+    let expected: Vec<(BackupTable, usize)> = BackupTable::ALL
+        .iter()
+        .map(|&table| (table, expected_count(&backup_data, table)))
+        .collect();
+
+    let mut key = vec![0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    let storage = StorageManager::new(StorageConfig {
+        data_dir: Some(dir.to_path_buf()),
+        db_file_name: None,
+        key_provider: Arc::new(StaticKeyProvider::new(key)?),
+    })?;
+    storage.initialize().context("Failed to create scratch database")?;
+    storage.transaction(|batch| write_backup_records(batch, backup_data))?;
+
+    let integrity_problems = storage.integrity_check()?;
+    let mut mismatches = Vec::new();
+    for (table, expected) in expected {
+        let restored = restored_count(&storage, table)?;
+        if restored != expected {
+            mismatches.push(CountMismatch { table, expected, restored });
+        }
+    }
+    Ok((integrity_problems, mismatches))
+}
+
 // Helper functions
 
-fn validate_backup_path(file_path: &str) -> Result<std::path::PathBuf> {
-    use std::path::Path;
+/// Records of `table` the backup says it holds
+fn expected_count(backup: &BackupData, table: BackupTable) -> usize {
+    match table {
+        BackupTable::Protocols => backup.metadata.protocols_count,
+        BackupTable::DoseLogs => backup.metadata.doses_count,
+        BackupTable::Literature => backup.metadata.literature_count,
+        // Not counted in the metadata, so every record in the file
+        table => record_values(backup, table).len(),
+    }
+}
+
+/// Records of `table` in `storage`
+fn restored_count(storage: &StorageManager, table: BackupTable) -> Result<usize> {
+    Ok(match table {
+        BackupTable::Protocols => storage.list_protocols()?.len(),
+        BackupTable::DoseLogs => storage.list_dose_logs()?.len(),
+        BackupTable::Literature => storage.list_literature()?.len(),
+        BackupTable::BodyMetrics => storage.list_body_metrics()?.len(),
+        BackupTable::JournalEntries => storage.list_journal_entries()?.len(),
+        BackupTable::Attachments => storage.list_all_attachments()?.len(),
+    })
+}
+
+/// The raw records of `table`
+fn record_values(backup: &BackupData, table: BackupTable) -> Vec<&serde_json::Value> {
+    match table {
+        BackupTable::Protocols => backup.protocols.iter().collect(),
+        BackupTable::DoseLogs => backup.dose_logs.iter().collect(),
+        BackupTable::Literature => backup.literature.iter().collect(),
+        BackupTable::BodyMetrics => backup.body_metrics.iter().collect(),
+        BackupTable::JournalEntries => backup.journal_entries.iter().collect(),
+        BackupTable::Attachments => backup.attachments.iter().map(|a| &a.attachment).collect(),
+    }
+}
+
+/// Keep only the tables and records in `selection`
+fn select_records(backup: &mut BackupData, selection: &[TableSelection]) {
+    let keep = |table: BackupTable, value: &serde_json::Value| {
+        selection.iter().filter(|s| s.table == table).any(|s| match &s.ids {
+            None => true,
+            Some(ids) => value
+                .get("id")
+                .and_then(|id| id.as_str())
+                .is_some_and(|id| ids.iter().any(|wanted| wanted == id)),
+        })
+    };
+    backup.protocols.retain(|v| keep(BackupTable::Protocols, v));
+    backup.dose_logs.retain(|v| keep(BackupTable::DoseLogs, v));
+    backup.literature.retain(|v| keep(BackupTable::Literature, v));
+    backup.body_metrics.retain(|v| keep(BackupTable::BodyMetrics, v));
+    backup.journal_entries.retain(|v| keep(BackupTable::JournalEntries, v));
+    backup.attachments.retain(|a| keep(BackupTable::Attachments, &a.attachment));
+}
+
+/// A readable record, with the date it's listed by
+struct RecordInfo {
+    id: String,
+    label: String,
+    date: Option<OffsetDateTime>,
+}
+
+fn parse_records<T: serde::de::DeserializeOwned>(
+    values: Vec<&serde_json::Value>,
+    describe: impl Fn(T) -> RecordInfo,
+) -> Vec<RecordInfo> {
+    values
+        .into_iter()
+        .filter_map(|value| T::deserialize(value).ok())
+        .map(describe)
+        .collect()
+}
 
+fn record_summaries(backup: &BackupData, table: BackupTable) -> Vec<RecordInfo> {
+    let values = record_values(backup, table);
+    match table {
+        BackupTable::Protocols => parse_records(values, |p: peptrack_core::PeptideProtocol| RecordInfo {
+            label: format!("{} ({})", p.name, p.peptide_name),
+            date: Some(p.updated_at),
+            id: p.id,
+        }),
+        BackupTable::DoseLogs => parse_records(values, |d: peptrack_core::DoseLog| RecordInfo {
+            label: format!("{} mg, {}", d.amount_mg, d.site),
+            date: Some(d.logged_at),
+            id: d.id,
+        }),
+        BackupTable::Literature => parse_records(values, |l: peptrack_core::LiteratureEntry| RecordInfo {
+            label: l.title,
+            date: Some(l.indexed_at),
+            id: l.id,
+        }),
+        BackupTable::BodyMetrics => parse_records(values, |m: peptrack_core::BodyMetric| RecordInfo {
+            label: match m.weight_kg {
+                Some(weight) => format!("{} kg", weight),
+                None => "Body metrics".to_string(),
+            },
+            date: Some(m.date),
+            id: m.id,
+        }),
+        BackupTable::JournalEntries => parse_records(values, |e: peptrack_core::JournalEntry| RecordInfo {
+            label: e.title.unwrap_or_else(|| "Journal entry".to_string()),
+            date: Some(e.entry_date),
+            id: e.id,
+        }),
+        BackupTable::Attachments => parse_records(values, |a: peptrack_core::Attachment| RecordInfo {
+            label: a.file_name,
+            date: Some(a.created_at),
+            id: a.id,
+        }),
+    }
+}
+
+fn table_summary(backup: &BackupData, table: BackupTable) -> TableSummary {
+    let records = record_summaries(backup, table);
+    let dates = records.iter().filter_map(|record| record.date);
+    let format = |date: OffsetDateTime| date.format(&Rfc3339).ok();
+    TableSummary {
+        table,
+        count: record_values(backup, table).len(),
+        unreadable: record_values(backup, table).len() - records.len(),
+        earliest: dates.clone().min().and_then(format),
+        latest: dates.max().and_then(format),
+    }
+}
+
+/// Store a backed-up attachment, regenerating the thumbnail for photos
+fn restore_attachment(batch: &StorageTransaction<'_>, backup_attachment: BackupAttachment) -> Result<()> {
+    let mut attachment: peptrack_core::Attachment =
+        serde_json::from_value(backup_attachment.attachment)
+            .context("Failed to deserialize attachment")?;
+    let data = STANDARD
+        .decode(backup_attachment.data_base64)
+        .context("Invalid attachment data")?;
+
+    let thumbnail = if attachment.is_image() {
+        peptrack_core::generate_thumbnail(&data).ok()
+    } else {
+        None
+    };
+    attachment.has_thumbnail = thumbnail.is_some();
+
+    batch.add_attachment(
+        &attachment,
+        &data,
+        thumbnail.as_ref().map(|thumbnail| thumbnail.jpeg.as_slice()),
+    )
+}
+
+fn validate_backup_path(file_path: &str) -> Result<std::path::PathBuf> {
     let path = Path::new(file_path);
 
     // Resolve to canonical path to prevent path traversal
@@ -158,15 +531,18 @@ fn validate_backup_path(file_path: &str) -> Result<std::path::PathBuf> {
     Ok(canonical)
 }
 
-fn read_backup_file(file_path: &str, password: Option<&str>) -> Result<BackupData> {
+pub(crate) fn read_backup_file(file_path: &str, password: Option<&str>) -> Result<BackupData> {
     // Validate path to prevent arbitrary file reads
     let validated_path = validate_backup_path(file_path)?;
+    decode_backup_file(&validated_path, password)
+}
 
-    let data =
-        std::fs::read(&validated_path).with_context(|| format!("Failed to read file: {}", validated_path.display()))?;
+/// Read a backup file the app wrote itself, wherever it was saved
+fn decode_backup_file(path: &Path, password: Option<&str>) -> Result<BackupData> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read file: {}", path.display()))?;
 
     // Try to detect if compressed
-    let is_gzipped = file_path.ends_with(".gz") || is_gzip_data(&data);
+    let is_gzipped = path.extension().is_some_and(|extension| extension == "gz") || is_gzip_data(&data);
 
     let json = if is_gzipped {
         let mut decoder = GzDecoder::new(&data[..]);
@@ -216,6 +592,74 @@ pub struct RestoreCounts {
     pub protocols: usize,
     pub dose_logs: usize,
     pub literature: usize,
+    pub body_metrics: usize,
+    pub journal_entries: usize,
+    pub attachments: usize,
+    /// Records that couldn't be read and were left out
+    pub skipped: usize,
+}
+
+/// Outcome of restoring a backup into a scratch database
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupVerification {
+    /// RFC 3339
+    pub verified_at: String,
+    /// The backup file that was restored
+    pub location: String,
+    /// Restored intact and with every record
+    pub verified: bool,
+    /// What `PRAGMA integrity_check` found wrong with the restored database
+    pub integrity_problems: Vec<String>,
+    /// Tables that restored a different number of records than the backup holds
+    pub mismatches: Vec<CountMismatch>,
+    /// Why the backup couldn't be restored at all
+    pub error: Option<String>,
+}
+
+impl BackupVerification {
+    pub fn failed(location: String, error: &anyhow::Error) -> Self {
+        Self {
+            verified_at: OffsetDateTime::now_utc()
+                .format(&Rfc3339)
+                .unwrap_or_default(),
+            location,
+            verified: false,
+            integrity_problems: Vec::new(),
+            mismatches: Vec::new(),
+            error: Some(format!("{:#}", error)),
+        }
+    }
+}
+
+/// A table whose restored record count differs from the backup's
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CountMismatch {
+    pub table: BackupTable,
+    pub expected: usize,
+    pub restored: usize,
+}
+
+/// Records of one table in a backup
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableSummary {
+    pub table: BackupTable,
+    pub count: usize,
+    /// Records that can't be read and won't be restored
+    pub unreadable: usize,
+    /// Oldest and newest record dates (RFC 3339)
+    pub earliest: Option<String>,
+    pub latest: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupRecordSummary {
+    pub id: String,
+    pub label: String,
+    pub date: Option<String>,
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -225,11 +669,16 @@ pub struct BackupPreview {
     pub protocols_count: usize,
     pub dose_logs_count: usize,
     pub literature_count: usize,
+    pub body_metrics_count: usize,
+    pub journal_entries_count: usize,
+    pub attachments_count: usize,
+    pub tables: Vec<TableSummary>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::commands::backup::BackupMetadata;
     use std::io::Write;
 
     #[test]
@@ -399,4 +848,90 @@ mod tests {
             "Best compression should be <= default"
         );
     }
+
+    #[test]
+    fn tables_are_summarized_and_selected_by_id() {
+        let protocol = |id: &str, updated_at: &str| {
+            let mut protocol = peptrack_core::PeptideProtocol::new("Healing", "BPC-157");
+            protocol.id = id.to_string();
+            protocol.updated_at = OffsetDateTime::parse(updated_at, &Rfc3339).unwrap();
+            serde_json::to_value(protocol).unwrap()
+        };
+        let mut backup = BackupData {
+            metadata: BackupMetadata {
+                export_date: "2025-03-01T00:00:00Z".to_string(),
+                protocols_count: 3,
+                doses_count: 0,
+                literature_count: 0,
+                app_version: "0.1.0".to_string(),
+                anonymized: false,
+            },
+            protocols: vec![
+                protocol("a", "2025-01-05T00:00:00Z"),
+                protocol("b", "2025-02-10T00:00:00Z"),
+                serde_json::json!({ "id": "broken" }),
+            ],
+            dose_logs: vec![],
+            literature: vec![],
+            attachments: vec![],
+            body_metrics: vec![],
+            dose_schedules: vec![],
+            journal_entries: vec![],
+        };
+
+        let summary = table_summary(&backup, BackupTable::Protocols);
+        assert_eq!((summary.count, summary.unreadable), (3, 1));
+        assert_eq!(summary.earliest.as_deref(), Some("2025-01-05T00:00:00Z"));
+        assert_eq!(summary.latest.as_deref(), Some("2025-02-10T00:00:00Z"));
+        let empty = table_summary(&backup, BackupTable::DoseLogs);
+        assert_eq!(empty.count, 0);
+        assert!(empty.earliest.is_none());
+
+        let selection = [TableSelection {
+            table: BackupTable::Protocols,
+            ids: Some(vec!["b".to_string()]),
+        }];
+        select_records(&mut backup, &selection);
+        assert_eq!(backup.protocols.len(), 1);
+        assert_eq!(backup.protocols[0]["id"], "b");
+    }
+
+    #[test]
+    fn deep_verify_reports_records_that_did_not_restore() {
+        let protocol = serde_json::to_value(peptrack_core::PeptideProtocol::new("Healing", "BPC-157")).unwrap();
+        let backup = BackupData {
+            metadata: BackupMetadata {
+                export_date: "2025-03-01T00:00:00Z".to_string(),
+                protocols_count: 2,
+                doses_count: 0,
+                literature_count: 0,
+                app_version: "0.1.0".to_string(),
+                anonymized: false,
+            },
+            protocols: vec![protocol, serde_json::json!({ "id": "broken" })],
+            dose_logs: vec![],
+            literature: vec![],
+            attachments: vec![],
+            body_metrics: vec![],
+            dose_schedules: vec![],
+            journal_entries: vec![],
+        };
+        let dir = std::env::temp_dir().join(format!("peptrack-verify-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("backup.json");
+        std::fs::write(&path, serde_json::to_string(&backup).unwrap()).unwrap();
+
+        let verification = deep_verify_backup_file(path.to_str().unwrap(), None).unwrap();
+        assert!(!verification.verified);
+        assert!(verification.integrity_problems.is_empty());
+        assert_eq!(
+            verification.mismatches,
+            vec![CountMismatch {
+                table: BackupTable::Protocols,
+                expected: 2,
+                restored: 1,
+            }]
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
@@ -0,0 +1,305 @@
+use std::sync::Arc;
+
+use peptrack_core::models::{Alert, AlertSeverity, AlertType, LiteratureEntry, SummaryHistory};
+use peptrack_core::StorageManager;
+use peptrack_literature::{EditorialStatus, RetractionChecker};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use tauri_plugin_notification::NotificationExt;
+use time::{Duration, OffsetDateTime};
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// How often the background job runs
+const CHECK_INTERVAL_SECS: u64 = 24 * 60 * 60;
+/// Days before a paper is checked again
+const RECHECK_AFTER_DAYS: i64 = 7;
+/// Papers checked per run unless a limit is given
+const DEFAULT_BATCH: usize = 50;
+
+/// Keeps the background job and a manual check from raising the same alert
+/// twice
+static CHECK_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Outcome of a retraction check
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetractionCheckSummary {
+    pub checked: usize,
+    /// Papers newly found to be retracted
+    pub retracted: usize,
+    /// Papers newly found to have a correction
+    pub corrected: usize,
+    /// Saved AI summaries that were annotated
+    pub summaries_annotated: usize,
+    /// Papers that couldn't be looked up; retried next run
+    pub failed: usize,
+    pub alerts: Vec<Alert>,
+}
+
+fn build_alert(entry: &LiteratureEntry, retracted: bool, citing: usize) -> Alert {
+    let (alert_type, severity, title, what) = if retracted {
+        (
+            AlertType::Retraction,
+            AlertSeverity::Critical,
+            format!("Retracted: {}", entry.title),
+            "A retraction notice was published",
+        )
+    } else {
+        (
+            AlertType::Erratum,
+            AlertSeverity::Warning,
+            format!("Correction published: {}", entry.title),
+            "A correction was published",
+        )
+    };
+
+    let mut message = format!("{} for \"{}\"", what, entry.title);
+    if let Some(doi) = &entry.notice_doi {
+        message.push_str(&format!(" (notice: https://doi.org/{})", doi));
+    }
+    message.push('.');
+    if citing > 0 {
+        message.push_str(&format!(
+            " {} saved AI summar{} cite{} it and {} been flagged.",
+            citing,
+            if citing == 1 { "y" } else { "ies" },
+            if citing == 1 { "s" } else { "" },
+            if citing == 1 { "has" } else { "have" },
+        ));
+    }
+
+    let mut alert = Alert::new(alert_type, severity, title, message);
+    alert.related_id = Some(entry.id.clone());
+    alert.related_type = Some("literature".to_string());
+    alert.references = vec![entry.id.clone()];
+    alert
+}
+
+fn summary_notice(entry: &LiteratureEntry, retracted: bool) -> String {
+    if retracted {
+        format!("Cites \"{}\", which has been retracted", entry.title)
+    } else {
+        format!(
+            "Cites \"{}\", which has a published correction",
+            entry.title
+        )
+    }
+}
+
+/// Apply `status` to `entry`; returns whether it was newly retracted and
+/// newly corrected
+fn apply_status(entry: &mut LiteratureEntry, status: EditorialStatus) -> (bool, bool) {
+    let newly_retracted = status.retracted && !entry.retracted;
+    let newly_corrected = status.corrected && !entry.corrected;
+    entry.retracted |= status.retracted;
+    entry.corrected |= status.corrected;
+    if status.notice_doi.is_some() && (newly_retracted || newly_corrected) {
+        entry.notice_doi = status.notice_doi;
+    }
+    (newly_retracted, newly_corrected)
+}
+
+/// Flag saved summaries citing `entry`; returns how many changed
+fn annotate_citing_summaries(
+    storage: &StorageManager,
+    summaries: &[SummaryHistory],
+    entry: &LiteratureEntry,
+    retracted: bool,
+) -> usize {
+    let notice = summary_notice(entry, retracted);
+    summaries
+        .iter()
+        .filter(|summary| summary.cites(entry))
+        .filter(
+            |summary| match storage.annotate_summary(&summary.id, &notice) {
+                Ok(changed) => changed,
+                Err(e) => {
+                    warn!("Failed to annotate summary {}: {:#}", summary.id, e);
+                    false
+                }
+            },
+        )
+        .count()
+}
+
+/// Check up to `limit` cached papers not checked in the last week
+async fn run_check(state: &AppState, limit: usize) -> Result<RetractionCheckSummary, CommandError> {
+    let _guard = CHECK_LOCK.lock().await;
+    let now = OffsetDateTime::now_utc();
+    let checked_before = now - Duration::days(RECHECK_AFTER_DAYS);
+    let entries = state
+        .db
+        .run(move |storage| storage.list_literature_for_retraction_check(checked_before, limit))
+        .await
+        .map_err(|e| {
+            error!("Failed to list literature for retraction check: {:#}", e);
+            CommandError::with_context(e, "Failed to list literature")
+        })?;
+
+    let checker = RetractionChecker::new();
+    let mut summaries: Option<Arc<Vec<SummaryHistory>>> = None;
+    let mut result = RetractionCheckSummary {
+        checked: entries.len(),
+        ..Default::default()
+    };
+
+    for mut entry in entries {
+        let status = match checker.check(&entry).await {
+            Ok(status) => status,
+            Err(e) => {
+                warn!("Retraction check failed for \"{}\": {:#}", entry.title, e);
+                result.failed += 1;
+                continue;
+            }
+        };
+
+        let (newly_retracted, newly_corrected) = apply_status(&mut entry, status);
+        entry.retraction_checked_at = Some(now);
+        let saved = entry.clone();
+        state
+            .db
+            .run(move |storage| storage.cache_literature(&saved))
+            .await
+            .map_err(|e| {
+                error!("Failed to save retraction status: {:#}", e);
+                CommandError::with_context(e, "Failed to save literature entry")
+            })?;
+        if !newly_retracted && !newly_corrected {
+            continue;
+        }
+
+        if summaries.is_none() {
+            summaries = Some(Arc::new(
+                state
+                    .db
+                    .run(|storage| storage.list_summary_history(None))
+                    .await
+                    .unwrap_or_else(|e| {
+                        warn!("Failed to load summaries to annotate: {:#}", e);
+                        Vec::new()
+                    }),
+            ));
+        }
+        let (loaded, cited) = (summaries.clone().unwrap_or_default(), entry.clone());
+        let citing = state
+            .db
+            .run(move |storage| {
+                Ok(annotate_citing_summaries(storage, &loaded, &cited, newly_retracted))
+            })
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Failed to annotate summaries citing \"{}\": {:#}", entry.title, e);
+                0
+            });
+        result.summaries_annotated += citing;
+        if newly_retracted {
+            result.retracted += 1;
+        } else {
+            result.corrected += 1;
+        }
+
+        let alert = build_alert(&entry, newly_retracted, citing);
+        let raised = alert.clone();
+        let delivery = state
+            .db
+            .run(move |storage| storage.raise_alert(&raised))
+            .await
+            .map_err(|e| {
+                error!("Failed to create retraction alert: {:#}", e);
+                CommandError::with_context(e, "Failed to create alert")
+            })?;
+        if let Some(delivery) = delivery {
+            state.notifier.alert(&alert, delivery);
+            result.alerts.push(alert);
+        }
+    }
+
+    info!(
+        "Retraction check: {} checked, {} retracted, {} corrected, {} failed",
+        result.checked, result.retracted, result.corrected, result.failed
+    );
+    Ok(result)
+}
+
+/// Check cached papers for retractions once a day, notifying about new ones
+pub async fn run_retraction_check_loop(app: AppHandle, state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(CHECK_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        if state.key_provider.is_locked() {
+            continue;
+        }
+
+        match run_check(&state, DEFAULT_BATCH).await {
+            Ok(result) => {
+                for alert in result.alerts {
+                    app.notification()
+                        .builder()
+                        .title(&alert.title)
+                        .body(&alert.message)
+                        .show()
+                        .ok();
+                }
+            }
+            Err(e) => warn!("Retraction check failed: {}", e),
+        }
+    }
+}
+
+// ========== Retraction Commands ==========
+
+/// Check cached papers for retractions and corrections now
+///
+/// Papers checked in the last week are skipped.
+#[tauri::command]
+pub async fn check_literature_retractions(
+    state: State<'_, Arc<AppState>>,
+    limit: Option<usize>,
+) -> Result<RetractionCheckSummary, CommandError> {
+    run_check(&state, limit.unwrap_or(DEFAULT_BATCH)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_status_reports_only_new_flags() {
+        let mut entry = LiteratureEntry::new("crossref", "Paper");
+        let status = EditorialStatus {
+            retracted: true,
+            corrected: false,
+            notice_doi: Some("10.1000/notice".into()),
+        };
+
+        assert_eq!(apply_status(&mut entry, status.clone()), (true, false));
+        assert!(entry.retracted);
+        assert_eq!(entry.notice_doi.as_deref(), Some("10.1000/notice"));
+        assert_eq!(apply_status(&mut entry, status), (false, false));
+    }
+
+    #[test]
+    fn test_build_alert() {
+        let mut entry = LiteratureEntry::new("crossref", "Paper");
+        entry.notice_doi = Some("10.1000/notice".into());
+
+        let retraction = build_alert(&entry, true, 2);
+        assert_eq!(retraction.alert_type, AlertType::Retraction);
+        assert_eq!(retraction.severity, AlertSeverity::Critical);
+        assert!(retraction
+            .message
+            .contains("https://doi.org/10.1000/notice"));
+        assert!(retraction
+            .message
+            .ends_with("2 saved AI summaries cite it and have been flagged."));
+
+        let erratum = build_alert(&entry, false, 0);
+        assert_eq!(erratum.alert_type, AlertType::Erratum);
+        assert_eq!(erratum.severity, AlertSeverity::Warning);
+        assert!(erratum.message.ends_with('.'));
+    }
+}
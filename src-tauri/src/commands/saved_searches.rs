@@ -0,0 +1,458 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use peptrack_core::models::{Alert, AlertSeverity, AlertType, LiteratureEntry, SavedSearch};
+use peptrack_literature::{LiteratureResult, RelevanceContext};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use tauri_plugin_notification::NotificationExt;
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::commands::literature::{protocol_peptide_names, search_sources};
+use crate::error::{CommandError, ErrorKind};
+use crate::state::AppState;
+
+/// How often the background job looks for due saved searches
+const CHECK_INTERVAL_SECS: u64 = 60 * 60;
+/// Most new titles listed in an alert message
+const MAX_TITLES_IN_ALERT: usize = 5;
+const MAX_RESULTS_LIMIT: usize = 100;
+
+/// Keeps the background job and a manual run from processing the same
+/// results twice
+static RUN_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Fields of a saved search the user can set
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearchPayload {
+    pub name: String,
+    pub query: String,
+    pub sources: Option<Vec<String>>,
+    pub max_results: Option<usize>,
+    pub interval_days: Option<u32>,
+    pub enabled: Option<bool>,
+}
+
+impl SavedSearchPayload {
+    fn apply(self, search: &mut SavedSearch) -> Result<(), CommandError> {
+        let name = self.name.trim();
+        let query = self.query.trim();
+        if name.is_empty() {
+            return Err(CommandError::invalid_input("Name is required"));
+        }
+        if query.is_empty() {
+            return Err(CommandError::invalid_input("Query is required"));
+        }
+        if self.interval_days == Some(0) {
+            return Err(CommandError::invalid_input(
+                "Interval must be at least one day",
+            ));
+        }
+        if matches!(self.max_results, Some(n) if n == 0 || n > MAX_RESULTS_LIMIT) {
+            return Err(CommandError::invalid_input(format!(
+                "Max results must be between 1 and {}",
+                MAX_RESULTS_LIMIT
+            )));
+        }
+        if matches!(&self.sources, Some(sources) if sources.is_empty()) {
+            return Err(CommandError::invalid_input(
+                "At least one source is required",
+            ));
+        }
+
+        // A different query finds different papers, so start over
+        if search.query != query {
+            search.seen_keys.clear();
+            search.last_run_at = None;
+            search.last_new_count = 0;
+        }
+        search.name = name.to_string();
+        search.query = query.to_string();
+        if let Some(sources) = self.sources {
+            search.sources = sources;
+        }
+        if let Some(max_results) = self.max_results {
+            search.max_results = max_results;
+        }
+        if let Some(interval_days) = self.interval_days {
+            search.interval_days = interval_days;
+        }
+        if let Some(enabled) = self.enabled {
+            search.enabled = enabled;
+        }
+        search.updated_at = OffsetDateTime::now_utc();
+        Ok(())
+    }
+}
+
+/// Outcome of running a saved search
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearchRun {
+    pub search: SavedSearch,
+    /// Results not found by any earlier run, now cached
+    pub new_entries: Vec<LiteratureEntry>,
+    /// Alert raised for the new results, if any
+    pub alert: Option<Alert>,
+    /// The app is offline; the search runs when the connection returns
+    pub queued: bool,
+}
+
+/// Results whose dedup key isn't in `seen`, each paper once
+fn unseen_results(
+    results: Vec<LiteratureResult>,
+    seen: &[String],
+) -> Vec<(String, LiteratureResult)> {
+    let mut keys: HashSet<String> = seen.iter().cloned().collect();
+    results
+        .into_iter()
+        .filter_map(|result| {
+            let key = result.dedup_key();
+            keys.insert(key.clone()).then_some((key, result))
+        })
+        .collect()
+}
+
+fn build_alert(search: &SavedSearch, entries: &[LiteratureEntry]) -> Alert {
+    let title = format!(
+        "{} new paper{} for \"{}\"",
+        entries.len(),
+        if entries.len() == 1 { "" } else { "s" },
+        search.name
+    );
+    let mut lines: Vec<String> = entries
+        .iter()
+        .take(MAX_TITLES_IN_ALERT)
+        .map(|entry| format!("• {}", entry.title))
+        .collect();
+    if entries.len() > MAX_TITLES_IN_ALERT {
+        lines.push(format!("…and {} more", entries.len() - MAX_TITLES_IN_ALERT));
+    }
+
+    let mut alert = Alert::new(
+        AlertType::NewLiterature,
+        AlertSeverity::Info,
+        title,
+        lines.join("\n"),
+    );
+    alert.related_id = Some(search.id.clone());
+    alert.related_type = Some("saved_search".to_string());
+    alert.references = entries.iter().map(|entry| entry.id.clone()).collect();
+    alert
+}
+
+/// Re-run `search` and cache the results it hasn't seen before
+///
+/// The first run only records what the search currently finds; later runs
+/// raise an alert listing the new papers.
+async fn run_search(
+    state: &AppState,
+    mut search: SavedSearch,
+) -> Result<SavedSearchRun, CommandError> {
+    let _guard = RUN_LOCK.lock().await;
+    // Another run may have finished while this one waited
+    let search_id = search.id.clone();
+    if let Some(current) = state
+        .db
+        .run(move |storage| storage.get_saved_search(&search_id))
+        .await
+        .map_err(CommandError::from)?
+    {
+        search.seen_keys = current.seen_keys;
+        search.last_run_at = current.last_run_at;
+    }
+
+    // Runs look for new papers, so they never reuse a cached response
+    let results = search_sources(state, &search.query, &search.sources, search.max_results, true).await?;
+    if results.is_empty() {
+        let message = if state.connectivity.is_online() {
+            format!("No literature source could be searched for \"{}\"", search.name)
+        } else {
+            format!("\"{}\" can't be searched while offline", search.name)
+        };
+        return Err(CommandError::new(ErrorKind::Network, message));
+    }
+
+    let now = OffsetDateTime::now_utc();
+    let peptides = protocol_peptide_names(state).await;
+    let relevance = RelevanceContext::new(&search.query, &peptides, now);
+    let unseen = unseen_results(
+        results
+            .into_iter()
+            .flat_map(|(_, results)| results)
+            .collect(),
+        &search.seen_keys,
+    );
+
+    let mut new_entries = Vec::with_capacity(unseen.len());
+    for (key, result) in unseen {
+        search.seen_keys.push(key);
+        new_entries.push(relevance.to_entry(&result));
+    }
+
+    let first_run = search.last_run_at.is_none();
+    search.last_run_at = Some(now);
+    search.last_new_count = new_entries.len();
+    let (cached, saved) = (new_entries.clone(), search.clone());
+    state
+        .db
+        .run(move |storage| {
+            for entry in &cached {
+                if let Err(e) = storage.cache_literature(entry) {
+                    warn!("Failed to cache literature entry: {:#}", e);
+                }
+            }
+            storage.upsert_saved_search(&saved)
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to save search run: {:#}", e);
+            CommandError::with_context(e, "Failed to save search run")
+        })?;
+
+    let alert = if first_run || new_entries.is_empty() {
+        None
+    } else {
+        let alert = build_alert(&search, &new_entries);
+        let raised = alert.clone();
+        let delivery = state
+            .db
+            .run(move |storage| storage.raise_alert(&raised))
+            .await
+            .map_err(|e| {
+                error!("Failed to create new literature alert: {:#}", e);
+                CommandError::with_context(e, "Failed to create alert")
+            })?;
+        delivery.map(|delivery| {
+            state.notifier.alert(&alert, delivery);
+            alert
+        })
+    };
+
+    info!(
+        "Saved search \"{}\" found {} new results",
+        search.name,
+        new_entries.len()
+    );
+    Ok(SavedSearchRun {
+        search,
+        new_entries,
+        alert,
+        queued: false,
+    })
+}
+
+/// Run due saved searches now and then every hour, notifying about new papers
+///
+/// Nothing runs while offline; due searches and those queued by
+/// `run_saved_search` run as soon as the connection returns.
+pub async fn run_saved_search_loop(app: AppHandle, state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(CHECK_INTERVAL_SECS));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = state.connectivity.reconnected() => info!("Back online; running saved searches"),
+        }
+        if state.key_provider.is_locked() || !state.connectivity.check().await {
+            continue;
+        }
+
+        let due = state
+            .db
+            .run(|storage| storage.due_saved_searches(OffsetDateTime::now_utc()))
+            .await;
+        let mut due = match due {
+            Ok(due) => due,
+            Err(e) => {
+                warn!("Failed to load saved searches: {:#}", e);
+                continue;
+            }
+        };
+        for search_id in state.connectivity.take_queued_searches() {
+            if due.iter().any(|search| search.id == search_id) {
+                continue;
+            }
+            match state.db.run(move |storage| storage.get_saved_search(&search_id)).await {
+                Ok(Some(search)) => due.push(search),
+                Ok(None) => {}
+                Err(e) => warn!("Failed to load queued saved search: {:#}", e),
+            }
+        }
+        for search in due {
+            let name = search.name.clone();
+            match run_search(&state, search).await {
+                Ok(SavedSearchRun {
+                    alert: Some(alert), ..
+                }) => {
+                    app.notification()
+                        .builder()
+                        .title(&alert.title)
+                        .body(&alert.message)
+                        .show()
+                        .ok();
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Saved search \"{}\" failed: {}", name, e),
+            }
+        }
+    }
+}
+
+async fn load_search(state: &AppState, search_id: &str) -> Result<SavedSearch, CommandError> {
+    let search_id = search_id.to_string();
+    state
+        .db
+        .run(move |storage| storage.get_saved_search(&search_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to load saved search: {:#}", e);
+            CommandError::with_context(e, "Failed to load saved search")
+        })?
+        .ok_or_else(|| CommandError::not_found("Saved search not found"))
+}
+
+// ========== Saved Search Commands ==========
+
+#[tauri::command]
+pub async fn list_saved_searches(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<SavedSearch>, CommandError> {
+    state
+        .db
+        .run(|storage| storage.list_saved_searches())
+        .await
+        .map_err(|e| {
+            error!("Failed to list saved searches: {:#}", e);
+            CommandError::with_context(e, "Failed to list saved searches")
+        })
+}
+
+/// Save a query to be re-run in the background
+#[tauri::command]
+pub async fn create_saved_search(
+    state: State<'_, Arc<AppState>>,
+    payload: SavedSearchPayload,
+) -> Result<SavedSearch, CommandError> {
+    let mut search = SavedSearch::new(payload.name.clone(), payload.query.clone());
+    payload.apply(&mut search)?;
+    state
+        .db
+        .run(move |storage| storage.upsert_saved_search(&search).map(|_| search))
+        .await
+        .map_err(|e| {
+            error!("Failed to create saved search: {:#}", e);
+            CommandError::with_context(e, "Failed to create saved search")
+        })
+}
+
+/// Update a saved search; changing the query forgets the results seen so far
+#[tauri::command]
+pub async fn update_saved_search(
+    state: State<'_, Arc<AppState>>,
+    search_id: String,
+    payload: SavedSearchPayload,
+) -> Result<SavedSearch, CommandError> {
+    let mut search = load_search(&state, &search_id).await?;
+    payload.apply(&mut search)?;
+    state
+        .db
+        .run(move |storage| storage.upsert_saved_search(&search).map(|_| search))
+        .await
+        .map_err(|e| {
+            error!("Failed to update saved search: {:#}", e);
+            CommandError::with_context(e, "Failed to update saved search")
+        })
+}
+
+#[tauri::command]
+pub async fn delete_saved_search(
+    state: State<'_, Arc<AppState>>,
+    search_id: String,
+) -> Result<(), CommandError> {
+    load_search(&state, &search_id).await?;
+    state
+        .db
+        .run(move |storage| storage.delete_saved_search(&search_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to delete saved search: {:#}", e);
+            CommandError::with_context(e, "Failed to delete saved search")
+        })
+}
+
+/// Run a saved search now instead of waiting for the background job
+///
+/// While offline the search is queued instead, and runs in the background
+/// when the connection returns.
+#[tauri::command]
+pub async fn run_saved_search(
+    state: State<'_, Arc<AppState>>,
+    search_id: String,
+) -> Result<SavedSearchRun, CommandError> {
+    let search = load_search(&state, &search_id).await?;
+    if !state.connectivity.check().await {
+        info!("Offline; queued saved search \"{}\"", search.name);
+        state.connectivity.queue_search(&search.id);
+        return Ok(SavedSearchRun {
+            search,
+            new_entries: Vec::new(),
+            alert: None,
+            queued: true,
+        });
+    }
+    run_search(&state, search).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(title: &str, doi: Option<&str>) -> LiteratureResult {
+        LiteratureResult {
+            source: "pubmed".to_string(),
+            title: title.to_string(),
+            url: None,
+            doi: doi.map(String::from),
+            authors: None,
+            published_date: None,
+            journal: None,
+            abstract_text: None,
+            citation_count: None,
+            publication_types: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_unseen_results_skips_seen_and_duplicates() {
+        let seen = vec!["doi:10.1000/old".to_string()];
+        let results = vec![
+            result("Old paper", Some("10.1000/OLD")),
+            result("New paper", Some("10.1000/new")),
+            // Same paper from a second source
+            result("New paper (OpenAlex)", Some("https://doi.org/10.1000/new")),
+            result("No DOI", None),
+        ];
+
+        let unseen = unseen_results(results, &seen);
+        let keys: Vec<&str> = unseen.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["doi:10.1000/new", "title:no doi"]);
+    }
+
+    #[test]
+    fn test_build_alert_lists_titles() {
+        let search = SavedSearch::new("Tendon", "BPC-157 tendon");
+        let entries: Vec<LiteratureEntry> = (1..=7)
+            .map(|i| LiteratureEntry::new("pubmed".to_string(), format!("Paper {}", i)))
+            .collect();
+
+        let alert = build_alert(&search, &entries);
+        assert_eq!(alert.title, "7 new papers for \"Tendon\"");
+        assert_eq!(alert.message.lines().count(), MAX_TITLES_IN_ALERT + 1);
+        assert!(alert.message.ends_with("…and 2 more"));
+        assert_eq!(alert.references.len(), 7);
+        assert_eq!(alert.related_id.as_deref(), Some(search.id.as_str()));
+    }
+}
@@ -246,9 +246,18 @@ fn calculate_next_backup(frequency: &BackupFrequency) -> String {
 
 async fn perform_local_backup(state: &AppState) -> Result<String> {
     // Get backup data
-    let protocols = state.storage.list_protocols()?;
-    let doses = state.storage.list_dose_logs()?;
-    let literature = state.storage.list_literature()?;
+    let protocols = state
+        .db
+        .run(|storage| storage.list_protocols())
+        .await?;
+    let doses = state
+        .db
+        .run(|storage| storage.list_dose_logs())
+        .await?;
+    let literature = state
+        .db
+        .run(|storage| storage.list_literature())
+        .await?;
 
     // Create backup structure
     use crate::commands::backup::{BackupData, BackupMetadata};
@@ -300,9 +309,18 @@ async fn perform_drive_backup(state: &AppState) -> Result<String> {
     use crate::commands::backup::{BackupData, BackupMetadata};
     use crate::commands::drive;
 
-    let protocols = state.storage.list_protocols()?;
-    let doses = state.storage.list_dose_logs()?;
-    let literature = state.storage.list_literature()?;
+    let protocols = state
+        .db
+        .run(|storage| storage.list_protocols())
+        .await?;
+    let doses = state
+        .db
+        .run(|storage| storage.list_dose_logs())
+        .await?;
+    let literature = state
+        .db
+        .run(|storage| storage.list_literature())
+        .await?;
 
     let metadata = BackupMetadata {
         export_date: OffsetDateTime::now_utc().to_string(),
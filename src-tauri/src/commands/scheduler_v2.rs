@@ -2,16 +2,31 @@ use anyhow::{Context, Result};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use peptrack_core::models::{Alert, AlertSeverity, AlertType};
+use peptrack_core::{NotificationEvent, Setting};
 use serde::{Deserialize, Serialize};
 use std::io::{Read as _, Write as _};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tauri::{AppHandle, State};
 use tauri_plugin_notification::NotificationExt;
+use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
 use tokio::sync::{Mutex, RwLock};
 use tokio::task::JoinHandle;
 use tracing::{error, info, warn};
 
+use crate::commands::backup_journal::{BackupJournal, InFlightBackup};
+use crate::commands::backup::{
+    collect_backup_attachments, collect_backup_schedules, AttachmentBackupOptions, BackupData,
+    BackupMetadata,
+};
+use crate::commands::forecast::create_forecast_alerts;
+use crate::commands::restore::{deep_verify_backup_file, BackupVerification};
+use crate::commands::network_folder::{self, NetworkFolderSettings};
+use crate::commands::settings::{notify_setting_changed, save_setting};
+use crate::error::CommandError;
+use crate::events::AppEvent;
 use crate::state::AppState;
 
 /// Backup frequency options
@@ -34,6 +49,72 @@ pub enum BackupDestination {
     Local,
     GoogleDrive,
     Dropbox,
+    /// A mounted share, see [`BackupSchedule::network_folder`]
+    NetworkFolder,
+}
+
+impl BackupDestination {
+    /// Stable identifier, used as the related id of overdue alerts
+    fn key(&self) -> &'static str {
+        match self {
+            BackupDestination::Local => "local",
+            BackupDestination::GoogleDrive => "googleDrive",
+            BackupDestination::Dropbox => "dropbox",
+            BackupDestination::NetworkFolder => "networkFolder",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            BackupDestination::Local => "Local",
+            BackupDestination::GoogleDrive => "Google Drive",
+            BackupDestination::Dropbox => "Dropbox",
+            BackupDestination::NetworkFolder => "Network folder",
+        }
+    }
+
+    /// Needs the internet, so it's skipped while offline
+    fn is_cloud(&self) -> bool {
+        matches!(self, BackupDestination::GoogleDrive | BackupDestination::Dropbox)
+    }
+}
+
+/// How one destination of a backup fared
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DestinationResult {
+    pub destination: BackupDestination,
+    pub success: bool,
+    pub error_message: Option<String>,
+    pub size_bytes: Option<u64>,
+    /// File path or Drive file id of the backup
+    pub location: Option<String>,
+}
+
+/// Backup health of one destination across runs
+///
+/// Timestamps are RFC 3339.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DestinationStatus {
+    pub destination: BackupDestination,
+    pub last_success: Option<String>,
+    pub last_failure: Option<String>,
+    pub last_error: Option<String>,
+    /// When the current run of failures began; cleared by a success
+    pub failing_since: Option<String>,
+}
+
+impl DestinationStatus {
+    fn new(destination: BackupDestination) -> Self {
+        Self {
+            destination,
+            last_success: None,
+            last_failure: None,
+            last_error: None,
+            failing_since: None,
+        }
+    }
 }
 
 /// Backup history entry
@@ -46,10 +127,19 @@ pub struct BackupHistoryEntry {
     pub error_message: Option<String>,
     pub size_bytes: Option<u64>,
     pub compressed: bool,
+    /// How each destination fared; empty for backups that never got that far
+    #[serde(default)]
+    pub destination_results: Vec<DestinationResult>,
+    /// Cloud destinations left out because the app was offline
+    #[serde(default)]
+    pub skipped_offline: Vec<BackupDestination>,
+    /// Outcome of the last deep verify, see [`deep_verify_backup`]
+    #[serde(default)]
+    pub verification: Option<BackupVerification>,
 }
 
 /// Cleanup settings for old backups
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CleanupSettings {
     pub enabled: bool,
@@ -82,6 +172,24 @@ pub struct BackupSchedule {
     pub compress: bool,
     pub cleanup_settings: CleanupSettings,
     pub max_retries: u32,
+    #[serde(default)]
+    pub attachments: AttachmentBackupOptions,
+    /// Folder for local backups; Downloads (or Documents) when unset
+    #[serde(default)]
+    pub backup_dir: Option<String>,
+    /// Used by the network folder destination
+    #[serde(default)]
+    pub network_folder: Option<NetworkFolderSettings>,
+    /// Alert when a destination has gone this many days without a successful backup
+    #[serde(default = "default_overdue_after_days")]
+    pub overdue_after_days: u32,
+    /// Kept by the scheduler; updates from the frontend don't change it
+    #[serde(default)]
+    pub destination_status: Vec<DestinationStatus>,
+}
+
+fn default_overdue_after_days() -> u32 {
+    7
 }
 
 impl Default for BackupSchedule {
@@ -96,8 +204,147 @@ impl Default for BackupSchedule {
             compress: true,
             cleanup_settings: CleanupSettings::default(),
             max_retries: 3,
+            attachments: AttachmentBackupOptions::default(),
+            backup_dir: None,
+            network_folder: None,
+            overdue_after_days: default_overdue_after_days(),
+            destination_status: Vec::new(),
+        }
+    }
+}
+
+fn timestamp(at: OffsetDateTime) -> String {
+    at.format(&Rfc3339).unwrap_or_else(|_| at.to_string())
+}
+
+fn parse_timestamp(value: Option<&String>) -> Option<OffsetDateTime> {
+    value.and_then(|value| OffsetDateTime::parse(value, &Rfc3339).ok())
+}
+
+/// Where local backups go when no folder is configured
+fn default_backup_dir() -> Result<std::path::PathBuf> {
+    dirs::download_dir()
+        .or_else(dirs::document_dir)
+        .context("Could not determine download directory")
+}
+
+impl BackupSchedule {
+    /// Folder local backups are written to and cleaned up in
+    pub(crate) fn local_backup_dir(&self) -> Result<std::path::PathBuf> {
+        match &self.backup_dir {
+            Some(dir) => Ok(std::path::PathBuf::from(dir)),
+            None => default_backup_dir(),
         }
     }
+
+    /// Record how each destination fared in a backup that ended at `at`
+    fn record_results(&mut self, results: &[DestinationResult], at: OffsetDateTime) {
+        for result in results {
+            let status = match self
+                .destination_status
+                .iter()
+                .position(|status| status.destination == result.destination)
+            {
+                Some(index) => &mut self.destination_status[index],
+                None => {
+                    self.destination_status
+                        .push(DestinationStatus::new(result.destination.clone()));
+                    self.destination_status.last_mut().unwrap()
+                }
+            };
+            if result.success {
+                status.last_success = Some(timestamp(at));
+                status.failing_since = None;
+            } else {
+                status.last_failure = Some(timestamp(at));
+                status.last_error = result.error_message.clone();
+                if status.failing_since.is_none() {
+                    status.failing_since = Some(timestamp(at));
+                }
+            }
+        }
+    }
+
+    /// Destinations that haven't had a successful backup in `overdue_after_days`
+    ///
+    /// A destination that has never succeeded is overdue once it has been
+    /// failing that long. Destinations that haven't been tried yet aren't.
+    fn overdue_destinations(&self, now: OffsetDateTime) -> Vec<&DestinationStatus> {
+        let limit = time::Duration::days(i64::from(self.overdue_after_days));
+        self.destination_status
+            .iter()
+            .filter(|status| self.destinations.contains(&status.destination))
+            .filter(|status| {
+                let since = parse_timestamp(status.last_success.as_ref())
+                    .or_else(|| parse_timestamp(status.failing_since.as_ref()));
+                since.is_some_and(|since| now - since >= limit)
+            })
+            .collect()
+    }
+
+    /// An alert for each overdue destination
+    fn overdue_alerts(&self, now: OffsetDateTime) -> Vec<Alert> {
+        self.overdue_destinations(now)
+            .into_iter()
+            .map(|status| {
+                let label = status.destination.label();
+                let mut message = match parse_timestamp(status.last_success.as_ref()) {
+                    Some(last) => format!(
+                        "No successful {} backup in {} days.",
+                        label,
+                        (now - last).whole_days()
+                    ),
+                    None => format!("{} backups have never succeeded.", label),
+                };
+                if let Some(error) = &status.last_error {
+                    message.push_str(&format!(" Last error: {}", error));
+                }
+                let mut alert = Alert::new(
+                    AlertType::BackupOverdue,
+                    AlertSeverity::Warning,
+                    &format!("{} backups overdue", label),
+                    &message,
+                );
+                alert.related_id = Some(status.destination.key().to_string());
+                alert.related_type = Some("backup_destination".to_string());
+                alert
+            })
+            .collect()
+    }
+}
+
+impl Setting for BackupSchedule {
+    const KEY: &'static str = "backup.schedule";
+    const LEGACY_FILE: Option<&'static str> = Some("backup_schedule.json");
+
+    fn validate(&self) -> Result<(), String> {
+        if let BackupFrequency::DailyAt { hour } = self.frequency {
+            if hour > 23 {
+                return Err("Daily backup hour must be between 0 and 23".to_string());
+            }
+        }
+        if self.enabled && self.destinations.is_empty() {
+            return Err("Choose at least one backup destination".to_string());
+        }
+        if self.cleanup_settings.keep_last_n == Some(0) {
+            return Err("Keep at least one backup".to_string());
+        }
+        if let Some(dir) = &self.backup_dir {
+            if dir.trim().is_empty() || !std::path::Path::new(dir).is_absolute() {
+                return Err("The backup folder must be an absolute path".to_string());
+            }
+        }
+        if self.destinations.contains(&BackupDestination::NetworkFolder) {
+            match &self.network_folder {
+                Some(folder) => folder.validate()?,
+                None => return Err("Choose a network folder".to_string()),
+            }
+        }
+        if self.overdue_after_days == 0 {
+            return Err("Overdue backup alerts need at least one day".to_string());
+        }
+        Ok(())
+    }
 }
 
 /// Backup progress for real-time updates
@@ -110,6 +357,47 @@ pub struct BackupProgress {
     pub failed_steps: Vec<String>,
 }
 
+/// Backup health at a glance, for the dashboard
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupStatus {
+    pub enabled: bool,
+    pub last_backup: Option<String>,
+    pub next_backup: Option<String>,
+    /// Whether the most recent backup succeeded; `None` before the first one
+    pub last_succeeded: Option<bool>,
+    pub is_running: bool,
+    /// Destinations without a successful backup in `overdue_after_days`
+    pub overdue: Vec<BackupDestination>,
+}
+
+/// How a backup started at exit ended
+#[derive(Debug, Clone, PartialEq)]
+pub enum CloseBackupOutcome {
+    Completed(String),
+    Failed(String),
+    TimedOut,
+    /// The user chose to quit without waiting
+    Cancelled,
+}
+
+/// Wait for `backup` unless `timeout` passes or `cancel` completes first,
+/// in which case `backup` is dropped unfinished
+async fn race_close_backup(
+    backup: impl std::future::Future<Output = Result<String>>,
+    cancel: impl std::future::Future<Output = ()>,
+    timeout: std::time::Duration,
+) -> CloseBackupOutcome {
+    tokio::select! {
+        result = backup => match result {
+            Ok(message) => CloseBackupOutcome::Completed(message),
+            Err(e) => CloseBackupOutcome::Failed(e.to_string()),
+        },
+        _ = tokio::time::sleep(timeout) => CloseBackupOutcome::TimedOut,
+        _ = cancel => CloseBackupOutcome::Cancelled,
+    }
+}
+
 /// Scheduler state for managing background tasks
 #[derive(Clone)]
 pub struct SchedulerState {
@@ -119,6 +407,9 @@ pub struct SchedulerState {
     task_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
     backup_lock: Arc<Mutex<()>>,
     app_handle: Arc<Mutex<Option<AppHandle>>>,
+    /// Whether the schedule has been read from storage; it can't be while
+    /// the database is locked
+    schedule_loaded: Arc<AtomicBool>,
 }
 
 impl Default for SchedulerState {
@@ -127,9 +418,10 @@ impl Default for SchedulerState {
     }
 }
 
-const SCHEDULE_FILENAME: &str = "backup_schedule.json";
 const HISTORY_FILENAME: &str = "backup_history.json";
 const MAX_HISTORY_ENTRIES: usize = 100;
+/// How often the scheduler re-runs the inventory forecast for alerts
+const FORECAST_CHECK_INTERVAL_HOURS: i64 = 6;
 
 impl SchedulerState {
     pub fn new() -> Self {
@@ -145,6 +437,7 @@ impl SchedulerState {
             task_handle: Arc::new(Mutex::new(None)),
             backup_lock: Arc::new(Mutex::new(())),
             app_handle: Arc::new(Mutex::new(None)),
+            schedule_loaded: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -164,17 +457,130 @@ impl SchedulerState {
         }
     }
 
-    /// Load schedule from disk on startup
-    pub async fn load_from_disk(&self) -> Result<()> {
-        // Load schedule
-        match load_schedule_from_disk().await {
-            Ok(schedule) => {
-                *self.schedule.write().await = schedule;
-                info!("Loaded backup schedule from disk");
-            }
-            Err(e) => {
-                warn!("Failed to load backup schedule: {:#}", e);
-            }
+    /// Folder local backups are written to
+    pub async fn local_backup_dir(&self) -> Result<std::path::PathBuf> {
+        self.schedule.read().await.local_backup_dir()
+    }
+
+    /// Whether a backup should run before the app exits
+    pub async fn backup_on_close(&self) -> bool {
+        self.schedule.read().await.backup_on_close
+    }
+
+    pub async fn progress(&self) -> BackupProgress {
+        self.progress.read().await.clone()
+    }
+
+    pub async fn status(&self) -> BackupStatus {
+        let schedule = self.schedule.read().await;
+        BackupStatus {
+            enabled: schedule.enabled,
+            last_backup: schedule.last_backup.clone(),
+            next_backup: schedule.next_backup.clone(),
+            last_succeeded: self.history.read().await.first().map(|entry| entry.success),
+            is_running: self.progress.read().await.is_running,
+            overdue: schedule
+                .overdue_destinations(OffsetDateTime::now_utc())
+                .into_iter()
+                .map(|status| status.destination.clone())
+                .collect(),
+        }
+    }
+
+    /// Run the backup-on-close, giving up after `timeout` or when `cancel`
+    /// completes
+    ///
+    /// Waits for a backup that is already running instead of starting a
+    /// second one. An abandoned backup is recorded in the history as failed.
+    pub async fn run_close_backup(
+        &self,
+        app_state: &AppState,
+        cancel: impl std::future::Future<Output = ()>,
+        timeout: std::time::Duration,
+    ) -> CloseBackupOutcome {
+        let backup = async {
+            let _guard = self.backup_lock.lock().await;
+            perform_scheduled_backup_with_retry(
+                app_state,
+                &self.schedule,
+                &self.history,
+                &self.progress,
+                self,
+            )
+            .await
+        };
+
+        let outcome = race_close_backup(backup, cancel, timeout).await;
+        if !matches!(outcome, CloseBackupOutcome::TimedOut | CloseBackupOutcome::Cancelled) {
+            return outcome;
+        }
+
+        // The backup future was dropped mid-way; leave the state consistent
+        {
+            let mut progress = self.progress.write().await;
+            progress.is_running = false;
+            progress.current_step.clear();
+        }
+        if let Err(e) = BackupJournal::open().and_then(|journal| journal.recover_interrupted()) {
+            warn!("Failed to clean up abandoned backup: {:#}", e);
+        }
+        let schedule = self.schedule.read().await.clone();
+        let reason = match outcome {
+            CloseBackupOutcome::TimedOut => "Backup on close timed out",
+            _ => "Backup on close was cancelled",
+        };
+        add_history_entry(
+            &self.history,
+            BackupHistoryEntry {
+                timestamp: OffsetDateTime::now_utc().to_string(),
+                destinations: schedule.destinations,
+                success: false,
+                error_message: Some(reason.to_string()),
+                size_bytes: None,
+                compressed: schedule.compress,
+                destination_results: Vec::new(),
+                skipped_offline: Vec::new(),
+                verification: None,
+            },
+        )
+        .await;
+
+        outcome
+    }
+
+    /// Read the saved schedule, unless it was already loaded
+    ///
+    /// Fails while the database is locked; the scheduler keeps trying.
+    async fn load_schedule(&self, app_state: &AppState) -> Result<()> {
+        if self.schedule_loaded.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        let schedule: BackupSchedule = app_state
+            .db
+            .run(|storage| storage.load_setting_or_default())
+            .await?;
+        *self.schedule.write().await = schedule;
+        self.schedule_loaded.store(true, Ordering::SeqCst);
+        info!("Loaded backup schedule");
+        Ok(())
+    }
+
+    /// Save the schedule after the scheduler changed it
+    async fn persist_schedule(&self, app_state: &AppState, schedule: &BackupSchedule) {
+        let saved = schedule.clone();
+        if let Err(e) = app_state.db.run(move |storage| storage.save_setting(&saved)).await {
+            warn!("Failed to save backup schedule: {:#}", e);
+            return;
+        }
+        if let Some(handle) = self.app_handle.lock().await.as_ref() {
+            notify_setting_changed(handle, BackupSchedule::KEY);
+        }
+    }
+
+    /// Load the schedule and backup history on startup
+    pub async fn load(&self, app_state: &AppState) -> Result<()> {
+        if let Err(e) = self.load_schedule(app_state).await {
+            warn!("Failed to load backup schedule: {:#}", e);
         }
 
         // Load history
@@ -188,9 +594,41 @@ impl SchedulerState {
             }
         }
 
+        self.record_interrupted_backups().await;
         Ok(())
     }
 
+    /// Clean up backups a crash or forced quit cut short, and record them as failed
+    async fn record_interrupted_backups(&self) {
+        let interrupted = match BackupJournal::open().and_then(|journal| journal.recover_interrupted()) {
+            Ok(interrupted) => interrupted,
+            Err(e) => {
+                warn!("Failed to check for interrupted backups: {:#}", e);
+                return;
+            }
+        };
+        for entry in interrupted {
+            warn!("Backup started at {} was interrupted", entry.started_at);
+            add_history_entry(
+                &self.history,
+                BackupHistoryEntry {
+                    timestamp: entry.started_at,
+                    destinations: entry.destinations,
+                    success: false,
+                    error_message: Some(
+                        "Interrupted: PepTrack closed before the backup finished".to_string(),
+                    ),
+                    size_bytes: None,
+                    compressed: entry.compressed,
+                    destination_results: Vec::new(),
+                    skipped_offline: Vec::new(),
+                    verification: None,
+                },
+            )
+            .await;
+        }
+    }
+
     /// Start the background scheduler task
     pub async fn start_scheduler(&self, app_state: Arc<AppState>) {
         let schedule_arc = self.schedule.clone();
@@ -202,7 +640,41 @@ impl SchedulerState {
         let handle = tokio::spawn(async move {
             info!("Background backup scheduler started");
 
+            let mut last_forecast_check: Option<OffsetDateTime> = None;
+
             loop {
+                if let Err(e) = notif_state.load_schedule(&app_state).await {
+                    tracing::debug!("Backup schedule not loaded yet: {:#}", e);
+                }
+
+                // Raise low stock and expiry alerts regardless of backup settings
+                let forecast_due = last_forecast_check.is_none_or(|last| {
+                    OffsetDateTime::now_utc() - last >= time::Duration::hours(FORECAST_CHECK_INTERVAL_HOURS)
+                });
+                if forecast_due {
+                    last_forecast_check = Some(OffsetDateTime::now_utc());
+                    match create_forecast_alerts(&app_state).await {
+                        Ok(alerts) => {
+                            for alert in alerts {
+                                notif_state.send_notification(&alert.title, &alert.message).await;
+                            }
+                        }
+                        Err(e) => warn!("Inventory forecast check failed: {:#}", e),
+                    }
+
+                    let schedule = schedule_arc.read().await.clone();
+                    if schedule.enabled {
+                        match create_overdue_backup_alerts(&app_state, &schedule).await {
+                            Ok(alerts) => {
+                                for alert in alerts {
+                                    notif_state.send_notification(&alert.title, &alert.message).await;
+                                }
+                            }
+                            Err(e) => warn!("Overdue backup check failed: {:#}", e),
+                        }
+                    }
+                }
+
                 // Check if enabled
                 let schedule = schedule_arc.read().await.clone();
 
@@ -221,7 +693,15 @@ impl SchedulerState {
                         Ok(next_backup_time) => {
                             let now = OffsetDateTime::now_utc();
 
-                            if now >= next_backup_time {
+                            // Nothing can be backed up offline; wait for the
+                            // connection instead of failing every cycle
+                            let cloud_only = schedule.destinations.iter().all(BackupDestination::is_cloud);
+                            let waiting_offline = now >= next_backup_time
+                                && cloud_only
+                                && !app_state.connectivity.check().await;
+                            if waiting_offline {
+                                tracing::debug!("Offline; scheduled backup waits for the connection");
+                            } else if now >= next_backup_time {
                                 info!("Scheduled backup triggered");
 
                                 // Try to acquire lock (non-blocking)
@@ -273,7 +753,7 @@ impl SchedulerState {
 #[tauri::command]
 pub async fn get_backup_schedule(
     state: State<'_, SchedulerState>,
-) -> Result<BackupSchedule, String> {
+) -> Result<BackupSchedule, CommandError> {
     let schedule = state.schedule.read().await.clone();
     Ok(schedule)
 }
@@ -282,16 +762,75 @@ pub async fn get_backup_schedule(
 #[tauri::command]
 pub async fn get_backup_history(
     state: State<'_, SchedulerState>,
-) -> Result<Vec<BackupHistoryEntry>, String> {
+) -> Result<Vec<BackupHistoryEntry>, CommandError> {
     let history = state.history.read().await.clone();
     Ok(history)
 }
 
+/// Deep verify a backup from the history
+///
+/// Restores the backup's file into a scratch database, runs a full integrity
+/// check on it and compares each table's record count with the backup's,
+/// then records the outcome on the history entry. Only backups saved to a
+/// file, locally or in the network folder, can be verified.
+#[tauri::command]
+pub async fn deep_verify_backup(
+    state: State<'_, SchedulerState>,
+    timestamp: String,
+    password: Option<String>,
+) -> Result<BackupHistoryEntry, CommandError> {
+    let location = {
+        let history = state.history.read().await;
+        let entry = history
+            .iter()
+            .find(|entry| entry.timestamp == timestamp)
+            .ok_or_else(|| CommandError::not_found("Backup not found in history"))?;
+        entry
+            .destination_results
+            .iter()
+            .filter(|result| result.success && !result.destination.is_cloud())
+            .find_map(|result| result.location.clone())
+            .ok_or_else(|| CommandError::invalid_input("This backup has no saved file to verify"))?
+    };
+
+    info!("Deep verifying backup: {}", location);
+    let file_path = location.clone();
+    let verification =
+        tokio::task::spawn_blocking(move || deep_verify_backup_file(&file_path, password.as_deref()))
+            .await
+            .map_err(|e| CommandError::with_context(e, "Backup verification stopped unexpectedly"))?
+            .unwrap_or_else(|e| {
+                warn!("Failed to restore backup {} for verification: {:#}", location, e);
+                BackupVerification::failed(location, &e)
+            });
+    if verification.verified {
+        info!("Backup verified: {}", verification.location);
+    } else {
+        warn!(
+            "Backup failed verification: {} integrity problems, {} count mismatches",
+            verification.integrity_problems.len(),
+            verification.mismatches.len()
+        );
+    }
+
+    let mut history = state.history.write().await;
+    let entry = history
+        .iter_mut()
+        .find(|entry| entry.timestamp == timestamp)
+        .ok_or_else(|| CommandError::not_found("Backup not found in history"))?;
+    entry.verification = Some(verification);
+    let entry = entry.clone();
+    if let Err(e) = save_history_to_disk(&history).await {
+        error!("Failed to save backup history: {:#}", e);
+    }
+    Ok(entry)
+}
+
 /// Gets current backup progress
 #[tauri::command]
 pub async fn get_backup_progress(
     state: State<'_, SchedulerState>,
-) -> Result<BackupProgress, String> {
+) -> Result<BackupProgress, CommandError> {
     let progress = state.progress.read().await.clone();
     Ok(progress)
 }
@@ -299,9 +838,23 @@ pub async fn get_backup_progress(
 /// Updates the backup schedule
 #[tauri::command]
 pub async fn update_backup_schedule(
+    app: AppHandle,
     state: State<'_, SchedulerState>,
+    app_state: State<'_, std::sync::Arc<AppState>>,
+    schedule: BackupSchedule,
+) -> Result<BackupSchedule, CommandError> {
+    apply_backup_schedule(&app, &state, &app_state, schedule).await
+}
+
+/// Save `schedule` and make it the scheduler's current schedule
+///
+/// Destination health is kept from the current schedule.
+pub(crate) async fn apply_backup_schedule(
+    app: &AppHandle,
+    state: &SchedulerState,
+    app_state: &AppState,
     schedule: BackupSchedule,
-) -> Result<BackupSchedule, String> {
+) -> Result<BackupSchedule, CommandError> {
     info!(
         "Updating backup schedule: enabled={}, frequency={:?}, destinations={:?}",
         schedule.enabled, schedule.frequency, schedule.destinations
@@ -309,37 +862,65 @@ pub async fn update_backup_schedule(
 
     // Calculate next backup time if enabled
     let mut updated_schedule = schedule.clone();
+    updated_schedule.destination_status = state.schedule.read().await.destination_status.clone();
+    updated_schedule.backup_dir = schedule
+        .backup_dir
+        .map(|dir| dir.trim().to_string())
+        .filter(|dir| !dir.is_empty());
+    if let Some(dir) = &updated_schedule.backup_dir {
+        prepare_backup_dir(std::path::Path::new(dir))?;
+    }
     if updated_schedule.enabled {
         updated_schedule.next_backup = Some(calculate_next_backup(&schedule.frequency));
     } else {
         updated_schedule.next_backup = None;
     }
 
+    save_setting(app, app_state, &updated_schedule).await?;
     *state.schedule.write().await = updated_schedule.clone();
-
-    // Persist to disk
-    if let Err(e) = save_schedule_to_disk(&updated_schedule).await {
-        warn!("Failed to save backup schedule: {:#}", e);
-        return Err(format!("Failed to save schedule: {}", e));
-    }
+    state.schedule_loaded.store(true, Ordering::SeqCst);
 
     info!("Backup schedule updated successfully");
     Ok(updated_schedule)
 }
 
+/// Create the backup folder if it's missing and check it can be written to
+fn prepare_backup_dir(dir: &std::path::Path) -> Result<(), CommandError> {
+    if !dir.is_absolute() {
+        return Err(CommandError::invalid_input(
+            "The backup folder must be an absolute path",
+        ));
+    }
+    if dir.exists() && !dir.is_dir() {
+        return Err(CommandError::invalid_input(format!(
+            "{} is a file, not a folder",
+            dir.display()
+        )));
+    }
+    std::fs::create_dir_all(dir).map_err(|e| {
+        error!("Failed to create backup folder {}: {}", dir.display(), e);
+        CommandError::invalid_input(format!("Couldn't create {}: {}", dir.display(), e))
+    })?;
+    let status = network_folder::check_folder(dir);
+    match status.error {
+        Some(error) => Err(CommandError::invalid_input(error)),
+        None => Ok(()),
+    }
+}
+
 /// Manually triggers a backup
 #[tauri::command]
 pub async fn trigger_manual_backup(
     scheduler_state: State<'_, SchedulerState>,
     app_state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     info!("Manual backup triggered");
 
     // Try to acquire lock
     let _guard = scheduler_state
         .backup_lock
         .try_lock()
-        .map_err(|_| "A backup is already in progress".to_string())?;
+        .map_err(|_| CommandError::busy("A backup is already in progress"))?;
 
     let result = perform_scheduled_backup_with_retry(
         &app_state,
@@ -361,7 +942,7 @@ pub async fn trigger_manual_backup(
             Ok(msg)
         }
         Err(e) => {
-            let error_msg = format!("Backup failed: {}", e);
+            let error_msg = CommandError::with_context(e, "Backup failed");
             scheduler_state
                 .send_notification(
                     "❌ Backup Failed",
@@ -398,54 +979,102 @@ fn calculate_next_backup(frequency: &BackupFrequency) -> String {
     next.to_string()
 }
 
+/// Raise an alert for each destination whose backups are overdue
+///
+/// Alerts are skipped when an undismissed one already exists for the
+/// destination. Returns only newly created alerts.
+async fn create_overdue_backup_alerts(
+    app_state: &AppState,
+    schedule: &BackupSchedule,
+) -> Result<Vec<Alert>> {
+    let alerts = schedule.overdue_alerts(OffsetDateTime::now_utc());
+    if alerts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let created = app_state
+        .db
+        .run(move |storage| {
+            let existing = storage.list_alerts(false).context("Failed to list alerts")?;
+            let mut created = Vec::new();
+            for alert in alerts {
+                let duplicate = existing.iter().any(|a| {
+                    a.alert_type == alert.alert_type && a.related_id == alert.related_id && !a.is_dismissed
+                });
+                if duplicate {
+                    continue;
+                }
+                if let Some(delivery) = storage.raise_alert(&alert).context("Failed to create alert")? {
+                    created.push((alert, delivery));
+                }
+            }
+            Ok(created)
+        })
+        .await?;
+
+    let mut alerts = Vec::with_capacity(created.len());
+    for (alert, delivery) in created {
+        warn!("{}: {}", alert.title, alert.message);
+        app_state.notifier.alert(&alert, delivery);
+        alerts.push(alert);
+    }
+    Ok(alerts)
+}
+
+/// Back up to every destination, retrying only the ones that failed
+///
+/// The attempt fails if any destination still fails after the last retry;
+/// the history entry and each destination's status record which ones did.
 async fn perform_scheduled_backup_with_retry(
     app_state: &AppState,
     schedule_arc: &Arc<RwLock<BackupSchedule>>,
     history_arc: &Arc<RwLock<Vec<BackupHistoryEntry>>>,
     progress_arc: &Arc<RwLock<BackupProgress>>,
-    _notif_state: &SchedulerState,
+    notif_state: &SchedulerState,
 ) -> Result<String> {
     let schedule = schedule_arc.read().await.clone();
-    let max_retries = schedule.max_retries;
+    let max_retries = schedule.max_retries.max(1);
     let compress = schedule.compress;
 
+    let mut pending = schedule.destinations.clone();
+    let mut results: Vec<DestinationResult> = Vec::new();
+    let mut skipped_offline: Vec<BackupDestination> = Vec::new();
     let mut last_error = None;
 
     for attempt in 1..=max_retries {
+        // Cloud uploads can't succeed offline, so don't spend attempts on them
+        if pending.iter().any(BackupDestination::is_cloud) && !app_state.connectivity.check().await {
+            for destination in pending.iter().filter(|destination| destination.is_cloud()) {
+                info!("Offline; skipping {} backup", destination.label());
+                results.retain(|r| &r.destination != destination);
+                skipped_offline.push(destination.clone());
+            }
+            pending.retain(|destination| !destination.is_cloud());
+        }
+        if pending.is_empty() {
+            break;
+        }
         if attempt > 1 {
-            info!("Retry attempt {} of {}", attempt, max_retries);
+            info!("Retry attempt {} of {} for {:?}", attempt, max_retries, pending);
             // Exponential backoff
             let wait_secs = 2u64.pow(attempt - 1);
             tokio::time::sleep(tokio::time::Duration::from_secs(wait_secs)).await;
         }
 
-        match perform_single_backup(app_state, &schedule, progress_arc, compress).await {
-            Ok(result) => {
-                // Success! Record history
-                let entry = BackupHistoryEntry {
-                    timestamp: OffsetDateTime::now_utc().to_string(),
-                    destinations: schedule.destinations.clone(),
-                    success: true,
-                    error_message: None,
-                    size_bytes: Some(result.size_bytes),
-                    compressed: compress,
-                };
-
-                add_history_entry(history_arc, entry).await;
-
-                // Update schedule
-                let mut sched = schedule_arc.write().await;
-                sched.last_backup = Some(OffsetDateTime::now_utc().to_string());
-                if sched.enabled {
-                    sched.next_backup = Some(calculate_next_backup(&sched.frequency));
+        match perform_single_backup(app_state, &schedule, &pending, progress_arc, compress).await {
+            Ok(attempt_results) => {
+                for result in attempt_results {
+                    results.retain(|r| r.destination != result.destination);
+                    results.push(result);
+                }
+                pending.retain(|destination| {
+                    results
+                        .iter()
+                        .any(|r| &r.destination == destination && !r.success)
+                });
+                if !pending.is_empty() {
+                    error!("Backup attempt {} failed for {:?}", attempt, pending);
                 }
-                save_schedule_to_disk(&sched).await.ok();
-
-                // Clear progress
-                let mut progress = progress_arc.write().await;
-                progress.is_running = false;
-
-                return Ok(result.message);
             }
             Err(e) => {
                 error!("Backup attempt {} failed: {:#}", attempt, e);
@@ -453,34 +1082,160 @@ async fn perform_scheduled_backup_with_retry(
             }
         }
     }
+    results.sort_by_key(|r| schedule.destinations.iter().position(|d| d == &r.destination));
+
+    progress_arc.write().await.is_running = false;
+
+    let now = OffsetDateTime::now_utc();
+    let succeeded: Vec<&DestinationResult> = results.iter().filter(|r| r.success).collect();
+    let failed: Vec<&DestinationResult> = results.iter().filter(|r| !r.success).collect();
+    let success = pending.is_empty() && !results.is_empty();
+    let skipped_labels = skipped_offline
+        .iter()
+        .map(|destination| destination.label())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    // Update schedule
+    {
+        let mut sched = schedule_arc.write().await;
+        sched.record_results(&results, now);
+        if success {
+            sched.last_backup = Some(now.to_string());
+        }
+        // Move on if anything was backed up; a total failure retries next cycle
+        if sched.enabled && !succeeded.is_empty() {
+            sched.next_backup = Some(calculate_next_backup(&sched.frequency));
+        }
+        notif_state.persist_schedule(app_state, &sched).await;
+    }
 
-    // All retries failed
-    let error = last_error.unwrap();
+    let error_message = if !failed.is_empty() {
+        Some(
+            failed
+                .iter()
+                .map(|r| {
+                    format!(
+                        "{}: {}",
+                        r.destination.label(),
+                        r.error_message.as_deref().unwrap_or("failed")
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    } else if results.is_empty() && !skipped_offline.is_empty() {
+        Some(format!("Offline; skipped {}", skipped_labels))
+    } else if !success {
+        Some(
+            last_error
+                .as_ref()
+                .map(|e| e.to_string())
+                .unwrap_or_else(|| "Backup failed".to_string()),
+        )
+    } else {
+        None
+    };
     let entry = BackupHistoryEntry {
-        timestamp: OffsetDateTime::now_utc().to_string(),
+        timestamp: now.to_string(),
         destinations: schedule.destinations.clone(),
-        success: false,
-        error_message: Some(error.to_string()),
-        size_bytes: None,
+        success,
+        error_message: error_message.clone(),
+        size_bytes: (!succeeded.is_empty()).then(|| succeeded.iter().filter_map(|r| r.size_bytes).sum()),
         compressed: compress,
+        destination_results: results.clone(),
+        skipped_offline: skipped_offline.clone(),
+        verification: None,
     };
-
+    app_state.events.emit(AppEvent::BackupCompleted(entry.clone()));
     add_history_entry(history_arc, entry).await;
 
-    Err(error)
+    let mut message = succeeded
+        .iter()
+        .map(|r| format!("{}: {}", r.destination.label(), r.location.as_deref().unwrap_or_default()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if !skipped_offline.is_empty() {
+        message.push_str(&format!(" (skipped while offline: {})", skipped_labels));
+    }
+    match error_message {
+        None => {
+            app_state
+                .notifier
+                .notify(NotificationEvent::backup_succeeded(message.clone()));
+            Ok(message)
+        }
+        Some(error) => {
+            app_state.notifier.notify(NotificationEvent::backup_failed(format!(
+                "Backup failed after {} attempt(s): {}",
+                max_retries, error
+            )));
+            Err(anyhow::anyhow!(error))
+        }
+    }
 }
 
-struct BackupResult {
-    message: String,
-    size_bytes: u64,
+/// Run one backup attempt to `destinations`, journaled so that a crash
+/// part-way is cleaned up on the next start
+async fn perform_single_backup(
+    app_state: &AppState,
+    schedule: &BackupSchedule,
+    destinations: &[BackupDestination],
+    progress_arc: &Arc<RwLock<BackupProgress>>,
+    compress: bool,
+) -> Result<Vec<DestinationResult>> {
+    let journal = BackupJournal::open()?;
+    let backup = journal
+        .begin(destinations, compress)
+        .context("Failed to journal backup")?;
+    let results =
+        run_backup_steps(app_state, schedule, destinations, progress_arc, compress, &backup).await;
+    backup.finish();
+    Ok(results)
 }
 
-async fn perform_single_backup(
+/// Back up to one destination, returning where the backup went and its size
+async fn backup_to_destination(
+    app_state: &AppState,
+    destination: &BackupDestination,
+    schedule: &BackupSchedule,
+    compress: bool,
+    backup: &InFlightBackup<'_>,
+) -> Result<(String, u64)> {
+    match destination {
+        BackupDestination::Local => {
+            perform_local_backup(app_state, compress, schedule, backup).await
+        }
+        BackupDestination::GoogleDrive => {
+            if !check_drive_connection(app_state).await? {
+                anyhow::bail!("Google Drive not connected");
+            }
+            perform_drive_backup(app_state, compress, &schedule.attachments).await
+        }
+        BackupDestination::NetworkFolder => {
+            let folder = schedule
+                .network_folder
+                .as_ref()
+                .context("No network folder is set up")?;
+            perform_network_backup(app_state, compress, &schedule.attachments, folder, backup).await
+        }
+        BackupDestination::Dropbox => {
+            // TODO: Implement Dropbox backup
+            warn!("Dropbox backup not yet implemented");
+            anyhow::bail!("Dropbox backups are not implemented yet")
+        }
+    }
+}
+
+/// Try every destination, even after one fails
+async fn run_backup_steps(
     app_state: &AppState,
     schedule: &BackupSchedule,
+    destinations: &[BackupDestination],
     progress_arc: &Arc<RwLock<BackupProgress>>,
     compress: bool,
-) -> Result<BackupResult> {
+    backup: &InFlightBackup<'_>,
+) -> Vec<DestinationResult> {
     // Update progress
     {
         let mut progress = progress_arc.write().await;
@@ -491,77 +1246,41 @@ async fn perform_single_backup(
     }
 
     let mut results = Vec::new();
-    let mut total_size = 0u64;
 
-    for destination in &schedule.destinations {
+    for destination in destinations {
+        let label = destination.label();
         // Update progress
         {
             let mut progress = progress_arc.write().await;
-            progress.current_step = format!("Backing up to {:?}...", destination);
+            progress.current_step = format!("Backing up to {}...", label);
         }
 
-        match destination {
-            BackupDestination::Local => match perform_local_backup(app_state, compress).await {
-                Ok((path, size)) => {
-                    info!("Local backup successful: {}", path);
-                    results.push(format!("Local: {}", path));
-                    total_size += size;
-
-                    let mut progress = progress_arc.write().await;
-                    progress
-                        .completed_steps
-                        .push(format!("Local backup: {}", path));
-                }
-                Err(e) => {
-                    error!("Local backup failed: {:#}", e);
-                    let mut progress = progress_arc.write().await;
-                    progress.failed_steps.push(format!("Local backup: {}", e));
-                    return Err(e);
-                }
-            },
-            BackupDestination::GoogleDrive => {
-                // Check Drive connection first
-                match check_drive_connection(app_state).await {
-                    Ok(true) => match perform_drive_backup(app_state, compress).await {
-                        Ok((file_id, size)) => {
-                            info!("Google Drive backup successful: {}", file_id);
-                            results.push(format!("Drive: {}", file_id));
-                            total_size += size;
-
-                            let mut progress = progress_arc.write().await;
-                            progress
-                                .completed_steps
-                                .push(format!("Drive backup: {}", file_id));
-                        }
-                        Err(e) => {
-                            error!("Google Drive backup failed: {:#}", e);
-                            let mut progress = progress_arc.write().await;
-                            progress.failed_steps.push(format!("Drive backup: {}", e));
-                            return Err(e);
-                        }
-                    },
-                    Ok(false) => {
-                        let err = anyhow::anyhow!("Google Drive not connected");
-                        let mut progress = progress_arc.write().await;
-                        progress
-                            .failed_steps
-                            .push("Drive backup: Not connected".to_string());
-                        return Err(err);
-                    }
-                    Err(e) => {
-                        let mut progress = progress_arc.write().await;
-                        progress.failed_steps.push(format!("Drive backup: {}", e));
-                        return Err(e);
-                    }
-                }
-            }
-            BackupDestination::Dropbox => {
-                // TODO: Implement Dropbox backup
-                warn!("Dropbox backup not yet implemented");
+        match backup_to_destination(app_state, destination, schedule, compress, backup).await {
+            Ok((location, size)) => {
+                info!("{} backup successful: {}", label, location);
                 let mut progress = progress_arc.write().await;
                 progress
-                    .failed_steps
-                    .push("Dropbox backup: Not implemented".to_string());
+                    .completed_steps
+                    .push(format!("{} backup: {}", label, location));
+                results.push(DestinationResult {
+                    destination: destination.clone(),
+                    success: true,
+                    error_message: None,
+                    size_bytes: Some(size),
+                    location: Some(location),
+                });
+            }
+            Err(e) => {
+                error!("{} backup failed: {:#}", label, e);
+                let mut progress = progress_arc.write().await;
+                progress.failed_steps.push(format!("{} backup: {}", label, e));
+                results.push(DestinationResult {
+                    destination: destination.clone(),
+                    success: false,
+                    error_message: Some(e.to_string()),
+                    size_bytes: None,
+                    location: None,
+                });
             }
         }
     }
@@ -571,7 +1290,11 @@ async fn perform_single_backup(
         let mut progress = progress_arc.write().await;
         progress.current_step = "Cleaning up old backups...".to_string();
 
-        if let Err(e) = perform_cleanup(&schedule.cleanup_settings).await {
+        let cleaned = schedule
+            .local_backup_dir()
+            .and_then(|dir| list_backups(&dir))
+            .and_then(|backups| delete_old_backups(backups, &schedule.cleanup_settings));
+        if let Err(e) = cleaned {
             warn!("Cleanup failed: {:#}", e);
             progress.failed_steps.push(format!("Cleanup: {}", e));
         } else {
@@ -581,106 +1304,171 @@ async fn perform_single_backup(
         }
     }
 
-    Ok(BackupResult {
-        message: results.join(", "),
-        size_bytes: total_size,
-    })
-}
+    // The network folder has its own cleanup rules
+    let network_cleanup = schedule.network_folder.as_ref().filter(|folder| {
+        folder.cleanup_settings.enabled && destinations.contains(&BackupDestination::NetworkFolder)
+    });
+    if let Some(folder) = network_cleanup {
+        let mut progress = progress_arc.write().await;
+        progress.current_step = "Cleaning up old network folder backups...".to_string();
 
-async fn perform_local_backup(state: &AppState, compress: bool) -> Result<(String, u64)> {
-    use crate::commands::backup::{BackupData, BackupMetadata};
+        let cleaned = list_backups(&folder.dir())
+            .and_then(|backups| delete_old_backups(backups, &folder.cleanup_settings));
+        match cleaned {
+            Ok(()) => progress
+                .completed_steps
+                .push("Network folder cleanup completed".to_string()),
+            Err(e) => {
+                warn!("Network folder cleanup failed: {:#}", e);
+                progress.failed_steps.push(format!("Network folder cleanup: {}", e));
+            }
+        }
+    }
 
-    let protocols = state.storage.list_protocols()?;
-    let doses = state.storage.list_dose_logs()?;
-    let literature = state.storage.list_literature()?;
+    results
+}
 
-    let metadata = BackupMetadata {
-        export_date: OffsetDateTime::now_utc().to_string(),
-        protocols_count: protocols.len(),
-        doses_count: doses.len(),
-        literature_count: literature.len(),
-        app_version: env!("CARGO_PKG_VERSION").to_string(),
-    };
+/// Everything a scheduled backup contains, read on the blocking thread pool
+async fn load_backup_data(state: &AppState, attachments: &AttachmentBackupOptions) -> Result<BackupData> {
+    let attachments = attachments.clone();
+    state
+        .db
+        .run(move |storage| {
+            let protocols = storage.list_protocols()?;
+            let doses = storage.list_dose_logs()?;
+            let literature = storage.list_literature()?;
+
+            let metadata = BackupMetadata {
+                export_date: OffsetDateTime::now_utc().to_string(),
+                protocols_count: protocols.len(),
+                doses_count: doses.len(),
+                literature_count: literature.len(),
+                app_version: env!("CARGO_PKG_VERSION").to_string(),
+                anonymized: false,
+            };
 
-    let backup = BackupData {
-        metadata,
-        protocols: protocols
-            .into_iter()
-            .map(|p| serde_json::to_value(p).unwrap_or_default())
-            .collect(),
-        dose_logs: doses
-            .into_iter()
-            .map(|d| serde_json::to_value(d).unwrap_or_default())
-            .collect(),
-        literature: literature
-            .into_iter()
-            .map(|l| serde_json::to_value(l).unwrap_or_default())
-            .collect(),
-    };
+            Ok(BackupData {
+                metadata,
+                protocols: protocols
+                    .into_iter()
+                    .map(|p| serde_json::to_value(p).unwrap_or_default())
+                    .collect(),
+                dose_logs: doses
+                    .into_iter()
+                    .map(|d| serde_json::to_value(d).unwrap_or_default())
+                    .collect(),
+                literature: literature
+                    .into_iter()
+                    .map(|l| serde_json::to_value(l).unwrap_or_default())
+                    .collect(),
+                attachments: collect_backup_attachments(storage, &attachments)?,
+                body_metrics: storage
+                    .list_body_metrics()?
+                    .into_iter()
+                    .map(|m| serde_json::to_value(m).unwrap_or_default())
+                    .collect(),
+                dose_schedules: collect_backup_schedules(storage)?,
+                journal_entries: storage
+                    .list_journal_entries()?
+                    .into_iter()
+                    .map(|e| serde_json::to_value(e).unwrap_or_default())
+                    .collect(),
+            })
+        })
+        .await
+}
+
+/// A backup file's name and contents, compressed if asked to
+async fn encode_backup(
+    state: &AppState,
+    compress: bool,
+    attachments: &AttachmentBackupOptions,
+) -> Result<(String, Vec<u8>)> {
+    let backup = load_backup_data(state, attachments).await?;
 
     let timestamp = OffsetDateTime::now_utc()
         .format(&time::format_description::parse("[year]-[month]-[day]_[hour]-[minute]").unwrap())
         .unwrap_or_else(|_| "backup".to_string());
 
-    let default_path = dirs::download_dir()
-        .or_else(dirs::document_dir)
-        .context("Could not determine download directory")?;
-
     let json = serde_json::to_string_pretty(&backup)?;
 
-    let (filename, final_data, size) = if compress {
+    if compress {
         let filename = format!("peptrack_backup_{}.json.gz", timestamp);
         let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
         encoder.write_all(json.as_bytes())?;
-        let compressed = encoder.finish()?;
-        let size = compressed.len() as u64;
-        (filename, compressed, size)
+        Ok((filename, encoder.finish()?))
     } else {
         let filename = format!("peptrack_backup_{}.json", timestamp);
-        let size = json.len() as u64;
-        (filename, json.into_bytes(), size)
-    };
-
-    let full_path = default_path.join(&filename);
-    std::fs::write(&full_path, final_data)?;
+        Ok((filename, json.into_bytes()))
+    }
+}
 
-    // Verify backup
-    verify_backup(&full_path, compress)?;
+/// Write a backup file into `dir`, verified before it takes its final name
+fn write_backup_file(
+    dir: &std::path::Path,
+    filename: &str,
+    data: Vec<u8>,
+    compress: bool,
+    in_flight: &InFlightBackup<'_>,
+) -> Result<(String, u64)> {
+    let size = data.len() as u64;
+
+    // Write under a temporary name, so an unfinished file never looks like a backup
+    let full_path = dir.join(filename);
+    let partial_path = dir.join(format!("{}.partial", filename));
+    in_flight.add_partial_file(&partial_path)?;
+    let written = std::fs::write(&partial_path, data)
+        .map_err(anyhow::Error::from)
+        .and_then(|()| verify_backup(&partial_path, compress));
+    if let Err(e) = written {
+        std::fs::remove_file(&partial_path).ok();
+        return Err(e);
+    }
+    std::fs::rename(&partial_path, &full_path).context("Failed to save backup file")?;
 
     Ok((full_path.to_string_lossy().to_string(), size))
 }
 
-async fn perform_drive_backup(state: &AppState, compress: bool) -> Result<(String, u64)> {
-    use crate::commands::backup::{BackupData, BackupMetadata};
-    use crate::commands::drive;
+async fn perform_local_backup(
+    state: &AppState,
+    compress: bool,
+    schedule: &BackupSchedule,
+    in_flight: &InFlightBackup<'_>,
+) -> Result<(String, u64)> {
+    let dir = schedule.local_backup_dir()?;
+    // A configured folder may have been removed since it was saved
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create backup folder {}", dir.display()))?;
+
+    let (filename, data) = encode_backup(state, compress, &schedule.attachments).await?;
+    write_backup_file(&dir, &filename, data, compress, in_flight)
+}
 
-    let protocols = state.storage.list_protocols()?;
-    let doses = state.storage.list_dose_logs()?;
-    let literature = state.storage.list_literature()?;
+/// Back up to a network folder, after checking it's mounted and has room
+async fn perform_network_backup(
+    state: &AppState,
+    compress: bool,
+    attachments: &AttachmentBackupOptions,
+    folder: &NetworkFolderSettings,
+    in_flight: &InFlightBackup<'_>,
+) -> Result<(String, u64)> {
+    let dir = folder.dir();
+    // Fail fast if the share isn't mounted, before gathering the backup
+    network_folder::ensure_ready(&dir, 0)?;
+
+    let (filename, data) = encode_backup(state, compress, attachments).await?;
+    network_folder::ensure_ready(&dir, data.len() as u64)?;
+    write_backup_file(&dir, &filename, data, compress, in_flight)
+}
 
-    let metadata = BackupMetadata {
-        export_date: OffsetDateTime::now_utc().to_string(),
-        protocols_count: protocols.len(),
-        doses_count: doses.len(),
-        literature_count: literature.len(),
-        app_version: env!("CARGO_PKG_VERSION").to_string(),
-    };
+async fn perform_drive_backup(
+    state: &AppState,
+    compress: bool,
+    attachments: &AttachmentBackupOptions,
+) -> Result<(String, u64)> {
+    use crate::commands::drive;
 
-    let backup = BackupData {
-        metadata,
-        protocols: protocols
-            .into_iter()
-            .map(|p| serde_json::to_value(p).unwrap_or_default())
-            .collect(),
-        dose_logs: doses
-            .into_iter()
-            .map(|d| serde_json::to_value(d).unwrap_or_default())
-            .collect(),
-        literature: literature
-            .into_iter()
-            .map(|l| serde_json::to_value(l).unwrap_or_default())
-            .collect(),
-    };
+    let backup = load_backup_data(state, attachments).await?;
 
     let timestamp = OffsetDateTime::now_utc()
         .format(&time::format_description::parse("[year]-[month]-[day]_[hour]-[minute]").unwrap())
@@ -708,9 +1496,21 @@ async fn perform_drive_backup(state: &AppState, compress: bool) -> Result<(Strin
         .await
         .context("Google Drive not connected")?;
 
-    let client = reqwest::Client::new();
+    let client = peptrack_literature::http::client();
+
+    // Don't start an upload that can't fit; an unreadable quota isn't fatal
+    match drive::fetch_storage_info(&client, &tokens.access_token).await {
+        Ok(storage) if !storage.fits(size) => anyhow::bail!(
+            "Not enough Google Drive space: the backup needs {} bytes but only {} are free",
+            size,
+            storage.remaining_bytes.unwrap_or(0)
+        ),
+        Ok(_) => {}
+        Err(e) => warn!("Couldn't check Google Drive storage before uploading: {:#}", e),
+    }
+
     let folder_id =
-        drive::get_or_create_folder_internal(&client, &tokens.access_token, "PepTrack Backups")
+        drive::get_or_create_folder_internal(&client, &tokens.access_token, drive::BACKUP_FOLDER_NAME)
             .await
             .context("Failed to create/get Drive folder")?;
 
@@ -752,13 +1552,12 @@ fn verify_backup(path: &std::path::Path, compressed: bool) -> Result<()> {
     Ok(())
 }
 
-async fn perform_cleanup(settings: &CleanupSettings) -> Result<()> {
-    let download_dir = dirs::download_dir()
-        .or_else(dirs::document_dir)
-        .context("Could not determine download directory")?;
-
+/// Backup files in `dir`, newest first, with when they were written
+///
+/// Only files directly in `dir` are listed; links and subfolders are left out.
+pub(crate) fn list_backups(dir: &std::path::Path) -> Result<Vec<(std::path::PathBuf, std::time::SystemTime)>> {
     // Find all peptrack backup files
-    let entries = std::fs::read_dir(&download_dir)?;
+    let entries = std::fs::read_dir(dir)?;
     let mut backups: Vec<(std::path::PathBuf, std::time::SystemTime)> = Vec::new();
 
     for entry in entries.flatten() {
@@ -767,7 +1566,11 @@ async fn perform_cleanup(settings: &CleanupSettings) -> Result<()> {
             if name.starts_with("peptrack_backup_")
                 && (name.ends_with(".json") || name.ends_with(".json.gz"))
             {
+                // Doesn't follow links, so cleanup never reaches outside `dir`
                 if let Ok(metadata) = entry.metadata() {
+                    if !metadata.is_file() {
+                        continue;
+                    }
                     if let Ok(modified) = metadata.modified() {
                         backups.push((path, modified));
                     }
@@ -778,7 +1581,14 @@ async fn perform_cleanup(settings: &CleanupSettings) -> Result<()> {
 
     // Sort by modification time (newest first)
     backups.sort_by(|a, b| b.1.cmp(&a.1));
+    Ok(backups)
+}
 
+/// Delete the `backups` (newest first) that `settings` no longer keeps
+fn delete_old_backups(
+    backups: Vec<(std::path::PathBuf, std::time::SystemTime)>,
+    settings: &CleanupSettings,
+) -> Result<()> {
     let mut to_delete = Vec::new();
 
     // Apply keep_last_n rule
@@ -824,30 +1634,6 @@ async fn add_history_entry(
     save_history_to_disk(&history).await.ok();
 }
 
-async fn save_schedule_to_disk(schedule: &BackupSchedule) -> Result<()> {
-    let data_dir = dirs::data_dir()
-        .context("Unable to determine data directory")?
-        .join("PepTrack");
-    std::fs::create_dir_all(&data_dir)?;
-
-    let schedule_file = data_dir.join(SCHEDULE_FILENAME);
-    let json = serde_json::to_string_pretty(schedule)?;
-    std::fs::write(&schedule_file, json).context("Failed to save backup schedule")?;
-
-    Ok(())
-}
-
-async fn load_schedule_from_disk() -> Result<BackupSchedule> {
-    let data_dir = dirs::data_dir()
-        .context("Unable to determine data directory")?
-        .join("PepTrack");
-    let schedule_file = data_dir.join(SCHEDULE_FILENAME);
-
-    let json = std::fs::read_to_string(&schedule_file).context("Backup schedule not found")?;
-    let schedule: BackupSchedule = serde_json::from_str(&json)?;
-    Ok(schedule)
-}
-
 async fn save_history_to_disk(history: &[BackupHistoryEntry]) -> Result<()> {
     let data_dir = dirs::data_dir()
         .context("Unable to determine data directory")?
@@ -871,3 +1657,214 @@ async fn load_history_from_disk() -> Result<Vec<BackupHistoryEntry>> {
     let history: Vec<BackupHistoryEntry> = serde_json::from_str(&json)?;
     Ok(history)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::Notify;
+
+    fn result(destination: BackupDestination, error: Option<&str>) -> DestinationResult {
+        DestinationResult {
+            destination,
+            success: error.is_none(),
+            error_message: error.map(str::to_string),
+            size_bytes: None,
+            location: None,
+        }
+    }
+
+    #[test]
+    fn failing_destination_is_overdue_despite_local_successes() {
+        let mut schedule = BackupSchedule {
+            enabled: true,
+            destinations: vec![BackupDestination::Local, BackupDestination::GoogleDrive],
+            ..BackupSchedule::default()
+        };
+        let start = OffsetDateTime::now_utc() - time::Duration::days(10);
+
+        schedule.record_results(
+            &[
+                result(BackupDestination::Local, None),
+                result(BackupDestination::GoogleDrive, None),
+            ],
+            start,
+        );
+        for day in 1..=9 {
+            schedule.record_results(
+                &[
+                    result(BackupDestination::Local, None),
+                    result(BackupDestination::GoogleDrive, Some("Google Drive not connected")),
+                ],
+                start + time::Duration::days(day),
+            );
+        }
+
+        let drive = &schedule.destination_status[1];
+        assert_eq!(drive.failing_since, Some(timestamp(start + time::Duration::days(1))));
+        assert_eq!(drive.last_error.as_deref(), Some("Google Drive not connected"));
+
+        let alerts = schedule.overdue_alerts(start + time::Duration::days(10));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].alert_type, AlertType::BackupOverdue);
+        assert_eq!(alerts[0].related_id.as_deref(), Some("googleDrive"));
+        assert!(alerts[0].message.contains("10 days"));
+
+        // Not overdue before the limit, nor once it succeeds again
+        assert!(schedule.overdue_alerts(start + time::Duration::days(6)).is_empty());
+        let now = start + time::Duration::days(10);
+        schedule.record_results(&[result(BackupDestination::GoogleDrive, None)], now);
+        assert!(schedule.destination_status[1].failing_since.is_none());
+        assert!(schedule.overdue_alerts(now).is_empty());
+    }
+
+    #[test]
+    fn never_successful_destination_is_overdue_once_failing_long_enough() {
+        let mut schedule = BackupSchedule {
+            destinations: vec![BackupDestination::Dropbox],
+            overdue_after_days: 3,
+            ..BackupSchedule::default()
+        };
+        let start = OffsetDateTime::now_utc();
+        assert!(schedule.overdue_alerts(start + time::Duration::days(30)).is_empty());
+
+        schedule.record_results(&[result(BackupDestination::Dropbox, Some("not implemented"))], start);
+        assert!(schedule.overdue_alerts(start + time::Duration::days(2)).is_empty());
+        let alerts = schedule.overdue_alerts(start + time::Duration::days(3));
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].message.contains("never succeeded"));
+
+        // Removed destinations no longer raise alerts
+        schedule.destinations = vec![BackupDestination::Local];
+        assert!(schedule.overdue_alerts(start + time::Duration::days(3)).is_empty());
+    }
+
+    #[test]
+    fn backup_dir_must_be_an_absolute_folder() {
+        let dir = std::env::temp_dir().join(format!("peptrack-backup-dir-{}", std::process::id()));
+        let with_dir = |backup_dir: Option<&str>| BackupSchedule {
+            backup_dir: backup_dir.map(str::to_string),
+            ..BackupSchedule::default()
+        };
+
+        assert!(with_dir(None).validate().is_ok());
+        assert!(with_dir(Some(&dir.to_string_lossy())).validate().is_ok());
+        for relative in ["", "   ", "backups", "./backups/peptrack"] {
+            assert!(with_dir(Some(relative)).validate().is_err(), "{:?} was allowed", relative);
+        }
+
+        assert!(prepare_backup_dir(std::path::Path::new("backups")).is_err());
+        let nested = dir.join("nested");
+        prepare_backup_dir(&nested).unwrap();
+        assert!(nested.is_dir());
+        let file = dir.join("not-a-folder");
+        std::fs::write(&file, b"").unwrap();
+        assert!(prepare_backup_dir(&file).is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn cleanup_only_touches_backup_files_in_the_folder() {
+        let root = std::env::temp_dir().join(format!("peptrack-cleanup-{}", std::process::id()));
+        let dir = root.join("backups");
+        std::fs::create_dir_all(dir.join("peptrack_backup_folder.json")).unwrap();
+
+        let now = std::time::SystemTime::now();
+        let write = |path: std::path::PathBuf, age_days: u64| {
+            let file = std::fs::File::create(&path).unwrap();
+            file.set_modified(now - std::time::Duration::from_secs(age_days * 86400))
+                .unwrap();
+            path
+        };
+        let newest = write(dir.join("peptrack_backup_2.json.gz"), 1);
+        let oldest = write(dir.join("peptrack_backup_1.json"), 2);
+        let unrelated = write(dir.join("notes.json"), 30);
+        let outside = write(root.join("peptrack_backup_outside.json"), 30);
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&outside, dir.join("peptrack_backup_link.json")).unwrap();
+
+        let backups = list_backups(&dir).unwrap();
+        let paths: Vec<_> = backups.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(paths, vec![newest.clone(), oldest.clone()]);
+
+        let settings = CleanupSettings {
+            enabled: true,
+            keep_last_n: Some(1),
+            older_than_days: None,
+        };
+        delete_old_backups(backups, &settings).unwrap();
+        assert!(newest.exists());
+        assert!(!oldest.exists());
+        assert!(unrelated.exists());
+        assert!(outside.exists());
+        assert!(dir.join("peptrack_backup_folder.json").is_dir());
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    /// Sets its flag when dropped, to tell an abandoned backup from one
+    /// still running
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn slow_backup(
+        dropped: &Arc<AtomicBool>,
+    ) -> impl std::future::Future<Output = Result<String>> {
+        let flag = DropFlag(dropped.clone());
+        async move {
+            let _flag = flag;
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            Ok("Backup complete".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn slow_close_backup_is_abandoned_after_the_timeout() {
+        let dropped = Arc::new(AtomicBool::new(false));
+        let started = std::time::Instant::now();
+
+        let outcome = race_close_backup(
+            slow_backup(&dropped),
+            std::future::pending(),
+            std::time::Duration::from_millis(50),
+        )
+        .await;
+        assert_eq!(outcome, CloseBackupOutcome::TimedOut);
+        assert!(dropped.load(Ordering::SeqCst));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+        // A backup that finishes in time reports how it went
+        let outcome = race_close_backup(
+            async { Err::<String, _>(anyhow::anyhow!("Disk full")) },
+            std::future::pending(),
+            std::time::Duration::from_secs(5),
+        )
+        .await;
+        assert_eq!(outcome, CloseBackupOutcome::Failed("Disk full".to_string()));
+    }
+
+    #[tokio::test]
+    async fn cancelling_the_close_backup_lets_the_app_close() {
+        let dropped = Arc::new(AtomicBool::new(false));
+        let cancel = Arc::new(Notify::new());
+        let closer = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            closer.notify_one();
+        });
+
+        let outcome = race_close_backup(
+            slow_backup(&dropped),
+            cancel.notified(),
+            std::time::Duration::from_secs(60),
+        )
+        .await;
+        assert_eq!(outcome, CloseBackupOutcome::Cancelled);
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+}
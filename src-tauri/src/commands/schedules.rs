@@ -1,9 +1,19 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
 use anyhow::Result;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use peptrack_core::{DoseLog, DoseSkip, DoseStatsFilter, PeptideProtocol, StorageManager};
 use tauri::{AppHandle, State};
-use time::{OffsetDateTime, Time};
-use tracing::info;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{Date, Duration, OffsetDateTime, Time};
+use tracing::{error, info, warn};
 
+use crate::commands::doses::{dose_view, DoseLogView};
+use crate::commands::interactions::run_interaction_check;
+use crate::commands::preferences::load_unit_preferences;
+use crate::error::CommandError;
 use crate::state::AppState;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,17 +31,91 @@ pub struct DoseSchedule {
     pub notes: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// Stepped dosing; `amount_mg` is the current phase's amount when set
+    #[serde(default)]
+    pub titration: Option<Titration>,
+    #[serde(default)]
+    pub titration_status: Option<TitrationStatus>,
+    /// When a snoozed reminder is due again, RFC3339
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<String>,
+}
+
+/// One step of a titrated schedule, e.g. 0.25 mg for 28 days
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TitrationPhase {
+    pub amount_mg: f32,
+    /// Ignored for the last phase, which continues until changed
+    pub duration_days: u32,
+}
+
+/// Stepped dosing phases, stored as JSON on the schedule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Titration {
+    pub phases: Vec<TitrationPhase>,
+    /// Phase the schedule was on as of `phase_started`
+    pub current_phase: usize,
+    /// Day `current_phase` began (YYYY-MM-DD)
+    pub phase_started: String,
+    /// Move to the next phase when a phase's days are up; otherwise phases
+    /// only change through `advance_titration_phase`
+    pub auto_advance: bool,
+}
+
+/// The next titration step and when it is due
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TitrationStep {
+    pub phase: usize,
+    pub amount_mg: f32,
+    /// YYYY-MM-DD; with auto-advance off this is when the step is due, not
+    /// when it happens
+    pub starts_on: String,
+    pub days_until: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TitrationStatus {
+    /// Zero-based index of the phase in effect today
+    pub phase: usize,
+    pub phase_count: usize,
+    pub amount_mg: f32,
+    pub phase_started: String,
+    /// None on the last phase
+    pub next_step: Option<TitrationStep>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TitrationPayload {
+    pub phases: Vec<TitrationPhase>,
+    /// First day of `current_phase` (YYYY-MM-DD); today when not set
+    pub start_date: Option<String>,
+    #[serde(default)]
+    pub current_phase: usize,
+    #[serde(default = "default_auto_advance")]
+    pub auto_advance: bool,
+}
+
+fn default_auto_advance() -> bool {
+    true
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateSchedulePayload {
     pub protocol_id: String,
+    /// Not needed for titrated schedules, which take the phase's amount
+    #[serde(default)]
     pub amount_mg: f32,
     pub site: Option<String>,
     pub time_of_day: String,
     pub days_of_week: Vec<u8>,
     pub notes: Option<String>,
+    pub titration: Option<TitrationPayload>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -44,10 +128,130 @@ pub struct UpdateSchedulePayload {
     pub days_of_week: Option<Vec<u8>>,
     pub enabled: Option<bool>,
     pub notes: Option<String>,
+    /// `null` turns titration off; leaving it out keeps the current phases
+    #[serde(default, deserialize_with = "present")]
+    pub titration: Option<Option<TitrationPayload>>,
+}
+
+/// Tell a field set to `null` apart from one that was left out
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+const DATE_FORMAT: &[time::format_description::FormatItem<'static>] =
+    format_description!("[year]-[month]-[day]");
+
+fn format_date(date: Date) -> String {
+    date.format(DATE_FORMAT).unwrap_or_else(|_| date.to_string())
+}
+
+impl Titration {
+    fn from_payload(payload: TitrationPayload, today: Date) -> Result<Self, CommandError> {
+        if payload.phases.is_empty() {
+            return Err(CommandError::invalid_input("A titration needs at least one phase"));
+        }
+        if payload
+            .phases
+            .iter()
+            .any(|phase| !phase.amount_mg.is_finite() || phase.amount_mg <= 0.0)
+        {
+            return Err(CommandError::invalid_input("Phase amounts must be greater than zero"));
+        }
+        let last = payload.phases.len() - 1;
+        if payload.phases[..last].iter().any(|phase| phase.duration_days == 0) {
+            return Err(CommandError::invalid_input("Every phase but the last needs a duration"));
+        }
+        if payload.current_phase > last {
+            return Err(CommandError::invalid_input("Current phase is past the last phase"));
+        }
+        let start = match payload.start_date.as_deref() {
+            Some(date) => Date::parse(date, DATE_FORMAT)
+                .map_err(|e| CommandError::with_context(e, "Invalid start date. Use YYYY-MM-DD"))?,
+            None => today,
+        };
+
+        Ok(Self {
+            phases: payload.phases,
+            current_phase: payload.current_phase,
+            phase_started: format_date(start),
+            auto_advance: payload.auto_advance,
+        })
+    }
+
+    /// The phase in effect on `today` and the day it began
+    fn phase_on(&self, today: Date) -> (usize, Date) {
+        let mut phase = self.current_phase.min(self.phases.len().saturating_sub(1));
+        let mut started = Date::parse(&self.phase_started, DATE_FORMAT).unwrap_or(today);
+        if self.auto_advance {
+            while phase + 1 < self.phases.len() {
+                let next = started + Duration::days(self.phases[phase].duration_days.into());
+                if next > today {
+                    break;
+                }
+                phase += 1;
+                started = next;
+            }
+        }
+        (phase, started)
+    }
+
+    pub fn status(&self, today: Date) -> Option<TitrationStatus> {
+        let (phase, started) = self.phase_on(today);
+        let current = self.phases.get(phase)?;
+        let next_step = self.phases.get(phase + 1).map(|next| {
+            let starts_on = started + Duration::days(current.duration_days.into());
+            TitrationStep {
+                phase: phase + 1,
+                amount_mg: next.amount_mg,
+                starts_on: format_date(starts_on),
+                days_until: (starts_on - today).whole_days(),
+            }
+        });
+
+        Some(TitrationStatus {
+            phase,
+            phase_count: self.phases.len(),
+            amount_mg: current.amount_mg,
+            phase_started: format_date(started),
+            next_step,
+        })
+    }
+
+    /// Move to the phase after the one in effect on `today`, starting today
+    fn advance(&mut self, today: Date) -> Result<(), CommandError> {
+        let (phase, _) = self.phase_on(today);
+        if phase + 1 >= self.phases.len() {
+            return Err(CommandError::invalid_input("Already on the last titration phase"));
+        }
+        self.current_phase = phase + 1;
+        self.phase_started = format_date(today);
+        Ok(())
+    }
+}
+
+fn load_titration(conn: &rusqlite::Connection, schedule_id: &str) -> Result<Option<Titration>> {
+    let column: Option<Option<String>> = rusqlite::OptionalExtension::optional(conn.query_row(
+        "SELECT titration FROM dose_schedules WHERE id = ?1",
+        [schedule_id],
+        |row| row.get(0),
+    ))?;
+    Ok(parse_titration(column.flatten()))
+}
+
+fn parse_titration(column: Option<String>) -> Option<Titration> {
+    column.and_then(|json| {
+        serde_json::from_str(&json)
+            .map_err(|e| warn!("Ignoring unreadable titration: {}", e))
+            .ok()
+    })
 }
 
 /// Create the schedules table if it doesn't exist
-fn ensure_schedules_table(storage: &peptrack_core::StorageManager) -> Result<()> {
+pub(crate) fn ensure_schedules_table(storage: &peptrack_core::StorageManager) -> Result<()> {
     let conn = storage.connection()?;
     conn.execute(
         r#"
@@ -62,73 +266,170 @@ fn ensure_schedules_table(storage: &peptrack_core::StorageManager) -> Result<()>
             notes TEXT,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL,
+            titration TEXT,
             FOREIGN KEY (protocol_id) REFERENCES protocols(id)
         )
         "#,
         [],
     )?;
+
+    // Tables created before titration support lack the column
+    let has_titration: bool = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('dose_schedules') WHERE name = 'titration'",
+        [],
+        |row| row.get::<_, i64>(0).map(|count| count > 0),
+    )?;
+    if !has_titration {
+        conn.execute("ALTER TABLE dose_schedules ADD COLUMN titration TEXT", [])?;
+    }
     Ok(())
 }
 
+/// Returns the IDs of protocols that have at least one enabled dose schedule
+pub(crate) fn enabled_schedule_protocol_ids(
+    storage: &peptrack_core::StorageManager,
+) -> Result<Vec<String>> {
+    ensure_schedules_table(storage)?;
+
+    let conn = storage.connection()?;
+    let mut stmt =
+        conn.prepare("SELECT DISTINCT protocol_id FROM dose_schedules WHERE enabled = 1")?;
+    let ids = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ids)
+}
+
+/// Dose amount and weekdays of an enabled schedule, used to project usage
+#[derive(Debug, Clone)]
+pub(crate) struct ScheduledUsage {
+    pub protocol_id: String,
+    pub amount_mg: f32,
+    pub days_of_week: Vec<u8>,
+}
+
+/// Returns the amount and weekdays of every enabled dose schedule
+///
+/// Titrated schedules use today's phase amount.
+pub(crate) fn enabled_schedule_usage(
+    storage: &peptrack_core::StorageManager,
+) -> Result<Vec<ScheduledUsage>> {
+    ensure_schedules_table(storage)?;
+
+    let today = OffsetDateTime::now_utc().date();
+    let conn = storage.connection()?;
+    let mut stmt = conn.prepare(
+        "SELECT protocol_id, amount_mg, days_of_week, titration FROM dose_schedules WHERE enabled = 1",
+    )?;
+    let schedules = stmt
+        .query_map([], |row| {
+            let days_str: String = row.get(2)?;
+            let titration = parse_titration(row.get(3)?);
+            Ok(ScheduledUsage {
+                protocol_id: row.get(0)?,
+                amount_mg: titration
+                    .and_then(|titration| titration.status(today))
+                    .map_or(row.get(1)?, |status| status.amount_mg),
+                days_of_week: serde_json::from_str(&days_str).unwrap_or_default(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(schedules)
+}
+
+/// Drop cached dashboard stats computed from schedules
+///
+/// Schedules are written outside `StorageManager`, so its mutations can't do
+/// this for us. A failure only leaves the stats stale until they expire.
+fn invalidate_schedule_stats(storage: &StorageManager) {
+    if let Err(e) = storage.invalidate_stats("dose_schedules") {
+        warn!("Failed to invalidate stats after schedule change: {:#}", e);
+    }
+}
+
 #[tauri::command]
 pub async fn create_dose_schedule(
     state: State<'_, std::sync::Arc<AppState>>,
     payload: CreateSchedulePayload,
-) -> Result<DoseSchedule, String> {
+) -> Result<DoseSchedule, CommandError> {
     info!("Creating dose schedule for protocol {}", payload.protocol_id);
+    let schedule =
+        state.on_storage(move |storage| insert_dose_schedule(storage, payload)).await?;
+
+    // Activating a schedule may create a new combination with other active protocols
+    if let Err(e) = run_interaction_check(&state, None).await {
+        warn!("Interaction check after creating schedule failed: {:#}", e);
+    }
+
+    Ok(schedule)
+}
+
+/// Validates and stores a new, enabled schedule
+///
+/// Callers run the interaction check once they are done adding schedules.
+pub(crate) fn insert_dose_schedule(
+    storage: &StorageManager,
+    payload: CreateSchedulePayload,
+) -> Result<DoseSchedule, CommandError> {
+    ensure_schedules_table(storage)
+        .map_err(|e| CommandError::with_context(e, "Database error"))?;
+
+    // Get protocol details
+    let protocol = storage
+        .get_protocol(&payload.protocol_id)
+        .map_err(|e| CommandError::with_context(e, "Failed to get protocol"))?
+        .ok_or_else(|| {
+            CommandError::not_found(format!("Protocol not found: {}", payload.protocol_id))
+        })?;
+    let schedule = new_dose_schedule(&protocol, payload)?;
 
-    ensure_schedules_table(&state.storage).map_err(|e| format!("Database error: {}", e))?;
+    let conn = storage.connection()
+        .map_err(|e| CommandError::with_context(e, "Failed to get database connection"))?;
+    write_dose_schedule(&conn, &schedule)
+        .map_err(|e| CommandError::with_context(e, "Failed to create schedule"))?;
+    // Other writes wait for the connection
+    drop(conn);
+    invalidate_schedule_stats(storage);
 
+    Ok(schedule)
+}
+
+/// Validates a new, enabled schedule for `protocol` without storing it
+pub(crate) fn new_dose_schedule(
+    protocol: &PeptideProtocol,
+    payload: CreateSchedulePayload,
+) -> Result<DoseSchedule, CommandError> {
     // Validate time format
     if !is_valid_time_format(&payload.time_of_day) {
-        return Err("Invalid time format. Use HH:MM (24-hour)".to_string());
+        return Err(CommandError::invalid_input("Invalid time format. Use HH:MM (24-hour)"));
     }
 
     // Validate days of week
     if payload.days_of_week.is_empty() || payload.days_of_week.iter().any(|&d| d > 6) {
-        return Err("Invalid days of week. Use 0-6 (Sunday-Saturday)".to_string());
+        return Err(CommandError::invalid_input("Invalid days of week. Use 0-6 (Sunday-Saturday)"));
     }
 
-    // Get protocol details
-    let protocol = state
-        .storage
-        .get_protocol(&payload.protocol_id)
-        .map_err(|e| format!("Failed to get protocol: {}", e))?
-        .ok_or_else(|| format!("Protocol not found: {}", payload.protocol_id))?;
-
-    let id = uuid::Uuid::new_v4().to_string();
     let now = OffsetDateTime::now_utc();
     let now_str = now.unix_timestamp().to_string();
-    let days_json = serde_json::to_string(&payload.days_of_week)
-        .map_err(|e| format!("Failed to serialize days: {}", e))?;
 
-    let conn = state.storage.connection()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-    conn.execute(
-        r#"
-        INSERT INTO dose_schedules (id, protocol_id, amount_mg, site, time_of_day, days_of_week, enabled, notes, created_at, updated_at)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7, ?8, ?9)
-        "#,
-        rusqlite::params![
-            &id,
-            &payload.protocol_id,
-            payload.amount_mg,
-            &payload.site,
-            &payload.time_of_day,
-            &days_json,
-            &payload.notes,
-            &now_str,
-            &now_str,
-        ],
-    )
-    .map_err(|e| format!("Failed to create schedule: {}", e))?;
+    let titration = payload
+        .titration
+        .map(|titration| Titration::from_payload(titration, now.date()))
+        .transpose()?;
+    let titration_status = titration.as_ref().and_then(|titration| titration.status(now.date()));
+    let amount_mg = titration_status.as_ref().map_or(payload.amount_mg, |status| status.amount_mg);
+    if !amount_mg.is_finite() || amount_mg <= 0.0 {
+        return Err(CommandError::invalid_input("Dose amount must be greater than zero"));
+    }
 
     Ok(DoseSchedule {
-        id,
-        protocol_id: payload.protocol_id,
-        protocol_name: protocol.name,
-        peptide_name: protocol.peptide_name,
-        amount_mg: payload.amount_mg,
+        id: uuid::Uuid::new_v4().to_string(),
+        protocol_id: protocol.id.clone(),
+        protocol_name: protocol.name.clone(),
+        peptide_name: protocol.peptide_name.clone(),
+        amount_mg,
         site: payload.site,
         time_of_day: payload.time_of_day,
         days_of_week: payload.days_of_week,
@@ -136,28 +437,66 @@ pub async fn create_dose_schedule(
         notes: payload.notes,
         created_at: now_str.clone(),
         updated_at: now_str,
+        titration,
+        titration_status,
+        snoozed_until: None,
     })
 }
 
+/// Insert a schedule from [`new_dose_schedule`]
+///
+/// Takes any connection, so it can be part of a larger transaction; the
+/// caller invalidates schedule stats.
+pub(crate) fn write_dose_schedule(conn: &rusqlite::Connection, schedule: &DoseSchedule) -> Result<()> {
+    let days_json = serde_json::to_string(&schedule.days_of_week)?;
+    let titration_json = schedule.titration.as_ref().map(serde_json::to_string).transpose()?;
+    conn.execute(
+        r#"
+        INSERT INTO dose_schedules (id, protocol_id, amount_mg, site, time_of_day, days_of_week, enabled, notes, created_at, updated_at, titration)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7, ?8, ?9, ?10)
+        "#,
+        rusqlite::params![
+            &schedule.id,
+            &schedule.protocol_id,
+            schedule.amount_mg,
+            &schedule.site,
+            &schedule.time_of_day,
+            &days_json,
+            &schedule.notes,
+            &schedule.created_at,
+            &schedule.updated_at,
+            &titration_json,
+        ],
+    )?;
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn list_dose_schedules(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<Vec<DoseSchedule>, String> {
-    ensure_schedules_table(&state.storage).map_err(|e| format!("Database error: {}", e))?;
+) -> Result<Vec<DoseSchedule>, CommandError> {
+    state.on_storage(load_dose_schedules).await
+}
 
-    let conn = state.storage.connection()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+/// Loads every dose schedule with its protocol's names, ordered by time of day
+pub(crate) fn load_dose_schedules(
+    storage: &peptrack_core::StorageManager,
+) -> Result<Vec<DoseSchedule>, CommandError> {
+    ensure_schedules_table(storage).map_err(|e| CommandError::with_context(e, "Database error"))?;
+
+    let conn = storage.connection()
+        .map_err(|e| CommandError::with_context(e, "Failed to get database connection"))?;
     let mut stmt = conn
         .prepare(
             r#"
         SELECT
             id, protocol_id, amount_mg, site, time_of_day,
-            days_of_week, enabled, notes, created_at, updated_at
+            days_of_week, enabled, notes, created_at, updated_at, titration
         FROM dose_schedules
         ORDER BY time_of_day ASC
         "#,
         )
-        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+        .map_err(|e| CommandError::with_context(e, "Failed to prepare query"))?;
 
     let schedule_rows: Vec<_> = stmt
         .query_map([], |row| {
@@ -175,17 +514,22 @@ pub async fn list_dose_schedules(
                 row.get::<_, Option<String>>(7)?,  // notes
                 row.get::<_, String>(8)?,  // created_at
                 row.get::<_, String>(9)?,  // updated_at
+                parse_titration(row.get(10)?),
             ))
         })
-        .map_err(|e| format!("Failed to query schedules: {}", e))?
+        .map_err(|e| CommandError::with_context(e, "Failed to query schedules"))?
         .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect schedules: {}", e))?;
+        .map_err(|e| CommandError::with_context(e, "Failed to collect schedules"))?;
+    // Let writes through while the protocols load
+    drop(stmt);
+    drop(conn);
 
     // Fetch protocol details for each schedule
+    let today = OffsetDateTime::now_utc().date();
     let mut schedules = Vec::new();
-    for (id, protocol_id, amount_mg, site, time_of_day, days_of_week, enabled, notes, created_at, updated_at) in schedule_rows {
-        let protocol = state.storage.get_protocol(&protocol_id)
-            .map_err(|e| format!("Failed to get protocol: {}", e))?;
+    for (id, protocol_id, amount_mg, site, time_of_day, days_of_week, enabled, notes, created_at, updated_at, titration) in schedule_rows {
+        let protocol = storage.get_protocol(&protocol_id)
+            .map_err(|e| CommandError::with_context(e, "Failed to get protocol"))?;
 
         let (protocol_name, peptide_name) = if let Some(p) = protocol {
             (p.name, p.peptide_name)
@@ -193,12 +537,13 @@ pub async fn list_dose_schedules(
             ("Unknown".to_string(), "Unknown".to_string())
         };
 
+        let titration_status = titration.as_ref().and_then(|titration| titration.status(today));
         schedules.push(DoseSchedule {
             id,
             protocol_id,
             protocol_name,
             peptide_name,
-            amount_mg,
+            amount_mg: titration_status.as_ref().map_or(amount_mg, |status| status.amount_mg),
             site,
             time_of_day,
             days_of_week,
@@ -206,6 +551,9 @@ pub async fn list_dose_schedules(
             notes,
             created_at,
             updated_at,
+            titration,
+            titration_status,
+            snoozed_until: None,
         });
     }
 
@@ -216,35 +564,47 @@ pub async fn list_dose_schedules(
 pub async fn update_dose_schedule(
     state: State<'_, std::sync::Arc<AppState>>,
     payload: UpdateSchedulePayload,
-) -> Result<DoseSchedule, String> {
+) -> Result<DoseSchedule, CommandError> {
     info!("Updating dose schedule {}", payload.id);
 
-    ensure_schedules_table(&state.storage).map_err(|e| format!("Database error: {}", e))?;
-
     // Validate time if provided
     if let Some(ref time) = payload.time_of_day {
         if !is_valid_time_format(time) {
-            return Err("Invalid time format. Use HH:MM (24-hour)".to_string());
+            return Err(CommandError::invalid_input("Invalid time format. Use HH:MM (24-hour)"));
         }
     }
 
     // Validate days if provided
     if let Some(ref days) = payload.days_of_week {
         if days.is_empty() || days.iter().any(|&d| d > 6) {
-            return Err("Invalid days of week. Use 0-6 (Sunday-Saturday)".to_string());
+            return Err(CommandError::invalid_input(
+                "Invalid days of week. Use 0-6 (Sunday-Saturday)",
+            ));
         }
     }
 
-    // Perform the update in a scope that drops the connection before await
-    {
-        let conn = state.storage.connection()
-            .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    let today = OffsetDateTime::now_utc().date();
+    let titration = match payload.titration {
+        Some(Some(titration)) => Some(Some(Titration::from_payload(titration, today)?)),
+        Some(None) => Some(None),
+        None => None,
+    };
+
+    let schedule_id = payload.id.clone();
+    let enabled = payload.enabled;
+    state.on_storage(move |storage| {
+        ensure_schedules_table(storage)
+            .map_err(|e| CommandError::with_context(e, "Database error"))?;
+
+        let conn = storage.connection()
+            .map_err(|e| CommandError::with_context(e, "Failed to get database connection"))?;
         let now = OffsetDateTime::now_utc().unix_timestamp().to_string();
 
         // Build SQL for each field individually to avoid dyn ToSql
         let mut sql_parts = Vec::new();
 
-        if let Some(amount) = payload.amount_mg {
+        // A titration sets the amount itself
+        if let (Some(amount), None | Some(None)) = (payload.amount_mg, &titration) {
             sql_parts.push(format!("amount_mg = {}", amount));
         }
         if let Some(ref site) = payload.site {
@@ -263,6 +623,27 @@ pub async fn update_dose_schedule(
         if let Some(ref notes) = payload.notes {
             sql_parts.push(format!("notes = '{}'", notes.replace('\'', "''")));
         }
+        match titration {
+            Some(Some(ref titration)) => {
+                let titration_json = serde_json::to_string(titration).unwrap();
+                sql_parts.push(format!("titration = '{}'", titration_json.replace('\'', "''")));
+                if let Some(status) = titration.status(today) {
+                    sql_parts.push(format!("amount_mg = {}", status.amount_mg));
+                }
+            }
+            Some(None) => {
+                // Stay on the phase the titration had reached
+                if payload.amount_mg.is_none() {
+                    let previous = load_titration(&conn, &payload.id)
+                        .map_err(|e| CommandError::with_context(e, "Failed to load titration"))?;
+                    if let Some(status) = previous.and_then(|titration| titration.status(today)) {
+                        sql_parts.push(format!("amount_mg = {}", status.amount_mg));
+                    }
+                }
+                sql_parts.push("titration = NULL".to_string());
+            }
+            None => {}
+        }
 
         if !sql_parts.is_empty() {
             sql_parts.push(format!("updated_at = '{}'", now));
@@ -272,42 +653,150 @@ pub async fn update_dose_schedule(
                 payload.id.replace('\'', "''")
             );
             conn.execute(&sql, [])
-                .map_err(|e| format!("Failed to update schedule: {}", e))?;
+                .map_err(|e| CommandError::with_context(e, "Failed to update schedule"))?;
+            // Other writes wait for the connection
+            drop(conn);
+            invalidate_schedule_stats(storage);
+        }
+        Ok(())
+    })
+    .await?;
+
+    if enabled == Some(true) {
+        if let Err(e) = run_interaction_check(&state, None).await {
+            warn!("Interaction check after enabling schedule failed: {:#}", e);
         }
-    } // Connection dropped here
+    }
 
     // Fetch and return updated schedule
     list_dose_schedules(state)
         .await?
         .into_iter()
-        .find(|s| s.id == payload.id)
-        .ok_or_else(|| "Schedule not found after update".to_string())
+        .find(|s| s.id == schedule_id)
+        .ok_or_else(|| CommandError::not_found("Schedule not found after update"))
 }
 
 #[tauri::command]
 pub async fn delete_dose_schedule(
     state: State<'_, std::sync::Arc<AppState>>,
     schedule_id: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     info!("Deleting dose schedule {}", schedule_id);
 
-    ensure_schedules_table(&state.storage).map_err(|e| format!("Database error: {}", e))?;
+    state.on_storage(move |storage| {
+        ensure_schedules_table(storage)
+            .map_err(|e| CommandError::with_context(e, "Database error"))?;
 
-    let conn = state.storage.connection()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-    conn.execute("DELETE FROM dose_schedules WHERE id = ?1", [&schedule_id])
-        .map_err(|e| format!("Failed to delete schedule: {}", e))?;
+        let conn = storage.connection()
+            .map_err(|e| CommandError::with_context(e, "Failed to get database connection"))?;
+        conn.execute("DELETE FROM dose_schedules WHERE id = ?1", [&schedule_id])
+            .map_err(|e| CommandError::with_context(e, "Failed to delete schedule"))?;
+        drop(conn);
+        invalidate_schedule_stats(storage);
+        Ok(())
+    })
+    .await
+}
 
-    Ok(())
+/// Move a titrated schedule on to its next phase, starting today
+///
+/// Needed for schedules that don't auto-advance, or to step up early.
+#[tauri::command]
+pub async fn advance_titration_phase(
+    state: State<'_, std::sync::Arc<AppState>>,
+    schedule_id: String,
+) -> Result<DoseSchedule, CommandError> {
+    info!("Advancing titration for dose schedule {}", schedule_id);
+
+    state.on_storage(move |storage| {
+        ensure_schedules_table(storage)
+            .map_err(|e| CommandError::with_context(e, "Database error"))?;
+
+        {
+            let conn = storage.connection()
+                .map_err(|e| CommandError::with_context(e, "Failed to get database connection"))?;
+            let mut titration = load_titration(&conn, &schedule_id)
+                .map_err(|e| CommandError::with_context(e, "Failed to load titration"))?
+                .ok_or_else(|| CommandError::not_found("Schedule has no titration"))?;
+
+            let now = OffsetDateTime::now_utc();
+            titration.advance(now.date())?;
+            let amount_mg = titration
+                .status(now.date())
+                .map(|status| status.amount_mg)
+                .ok_or_else(|| CommandError::invalid_input("Titration has no phases"))?;
+            let titration_json = serde_json::to_string(&titration)
+                .map_err(|e| CommandError::with_context(e, "Failed to serialize titration"))?;
+
+            conn.execute(
+                "UPDATE dose_schedules SET titration = ?1, amount_mg = ?2, updated_at = ?3 WHERE id = ?4",
+                rusqlite::params![titration_json, amount_mg, now.unix_timestamp().to_string(), schedule_id],
+            )
+            .map_err(|e| CommandError::with_context(e, "Failed to update schedule"))?;
+        }
+        invalidate_schedule_stats(storage);
+
+        load_dose_schedules(storage)?
+            .into_iter()
+            .find(|s| s.id == schedule_id)
+            .ok_or_else(|| CommandError::not_found("Schedule not found after update"))
+    })
+    .await
 }
 
+/// Minutes a reminder is put off by when no other time is given
+pub const DEFAULT_SNOOZE_MINUTES: u32 = 10;
+/// How long a reminder stays pending, before its dose or after a snooze ends
+const REMINDER_WINDOW_MINUTES: i64 = 15;
+
+/// Snoozed reminders by schedule id, with when each is due again
+///
+/// Kept in memory only; a restart brings snoozed reminders back.
+#[derive(Clone, Default)]
+pub struct ReminderSnoozes(Arc<Mutex<HashMap<String, OffsetDateTime>>>);
+
+impl ReminderSnoozes {
+    fn snooze(&self, schedule_id: &str, until: OffsetDateTime) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).insert(schedule_id.to_string(), until);
+    }
+
+    fn clear(&self, schedule_id: &str) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).remove(schedule_id);
+    }
+
+    /// When the schedule's reminder is due again, dropping snoozes that ended
+    /// more than a reminder window ago
+    fn until(&self, schedule_id: &str, now: OffsetDateTime) -> Option<OffsetDateTime> {
+        let mut snoozes = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        snoozes.retain(|_, until| now - *until < Duration::minutes(REMINDER_WINDOW_MINUTES));
+        snoozes.get(schedule_id).copied()
+    }
+}
+
+/// A reminder that was put off
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnoozedReminder {
+    pub schedule_id: String,
+    pub protocol_name: String,
+    /// RFC3339
+    pub dose_at: String,
+    /// When the reminder shows again, RFC3339
+    pub remind_at: String,
+}
+
+/// Schedules due in the next 15 minutes
+///
+/// Titrated schedules carry the current phase's amount, and their
+/// `titrationStatus` says when the next step is due. Snoozed reminders are
+/// left out until their snooze ends, then pending for another 15 minutes
+/// with `snoozedUntil` set.
 #[tauri::command]
 pub async fn get_pending_dose_reminders(
     state: State<'_, std::sync::Arc<AppState>>,
+    snoozes: State<'_, ReminderSnoozes>,
     _app: AppHandle,
-) -> Result<Vec<DoseSchedule>, String> {
-    ensure_schedules_table(&state.storage).map_err(|e| format!("Database error: {}", e))?;
-
+) -> Result<Vec<DoseSchedule>, CommandError> {
     let schedules = list_dose_schedules(state).await?;
     let now = OffsetDateTime::now_utc();
     let current_time = now.time();
@@ -316,35 +805,268 @@ pub async fn get_pending_dose_reminders(
     // Filter schedules that should trigger now
     let pending: Vec<DoseSchedule> = schedules
         .into_iter()
-        .filter(|s| {
-            if !s.enabled {
-                return false;
+        .filter_map(|mut s| {
+            if let Some(until) = snoozes.until(&s.id, now) {
+                if now < until {
+                    return None;
+                }
+                s.snoozed_until = until.format(&Rfc3339).ok();
+                return Some(s);
             }
+            is_reminder_due(&s, current_weekday, current_time).then_some(s)
+        })
+        .collect();
+
+    Ok(pending)
+}
+
+fn is_reminder_due(s: &DoseSchedule, current_weekday: u8, current_time: Time) -> bool {
+    if !s.enabled {
+        return false;
+    }
+
+    // Check if today is a scheduled day
+    if !s.days_of_week.contains(&current_weekday) {
+        return false;
+    }
+
+    // Parse schedule time
+    if let Some(schedule_time) = parse_time(&s.time_of_day) {
+        // Within 15 minute window
+        let diff_minutes = time_diff_minutes(current_time, schedule_time);
+        (0..=REMINDER_WINDOW_MINUTES as i32).contains(&diff_minutes)
+    } else {
+        false
+    }
+}
+
+/// The enabled schedule whose reminder comes up next, with its dose time
+///
+/// A reminder that is showing now counts as next.
+pub(crate) fn next_reminder(
+    schedules: &[DoseSchedule],
+    now: OffsetDateTime,
+) -> Option<(&DoseSchedule, OffsetDateTime)> {
+    schedules
+        .iter()
+        .filter(|s| s.enabled)
+        .filter_map(|s| {
+            let time = parse_time(&s.time_of_day)?;
+            (0..=7)
+                .map(|days| (now.date() + Duration::days(days)).with_time(time).assume_utc())
+                .find(|at| {
+                    *at >= now
+                        && s.days_of_week.contains(&at.weekday().number_days_from_sunday())
+                })
+                .map(|at| (s, at))
+        })
+        .min_by_key(|(_, at)| *at)
+}
+
+/// Puts off the next reminder until `minutes` after its dose time
+///
+/// Used by the tray as well as the `snooze_next_dose_reminder` command.
+pub(crate) async fn snooze_next(
+    state: &AppState,
+    snoozes: &ReminderSnoozes,
+    minutes: Option<u32>,
+) -> Result<SnoozedReminder, CommandError> {
+    let minutes = minutes.unwrap_or(DEFAULT_SNOOZE_MINUTES);
+    if !(1..=24 * 60).contains(&minutes) {
+        return Err(CommandError::invalid_input("Snooze for between 1 minute and 24 hours"));
+    }
+    let schedules = state.on_storage(load_dose_schedules).await?;
+    let now = OffsetDateTime::now_utc();
+    let (schedule, dose_at) = next_reminder(&schedules, now)
+        .ok_or_else(|| CommandError::not_found("No dose reminders are coming up"))?;
+    let remind_at = dose_at + Duration::minutes(minutes.into());
+    snoozes.snooze(&schedule.id, remind_at);
+    info!("Snoozed the {} reminder for {} minutes", schedule.protocol_name, minutes);
+
+    let format = |at: OffsetDateTime| {
+        at.format(&Rfc3339).map_err(|e| CommandError::with_context(e, "Failed to format time"))
+    };
+    Ok(SnoozedReminder {
+        schedule_id: schedule.id.clone(),
+        protocol_name: schedule.protocol_name.clone(),
+        dose_at: format(dose_at)?,
+        remind_at: format(remind_at)?,
+    })
+}
+
+/// What was done with a dose reminder, from its notification buttons
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReminderAction {
+    /// Log the scheduled dose now
+    Taken,
+    /// Show the reminder again in `snoozeMinutes`
+    Snooze,
+    /// Record the dose as skipped on purpose
+    Skip,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReminderResponsePayload {
+    pub schedule_id: String,
+    pub action: ReminderAction,
+    /// Defaults to [`DEFAULT_SNOOZE_MINUTES`]
+    #[serde(default)]
+    pub snooze_minutes: Option<u32>,
+}
+
+/// The outcome of a reminder action; only the field for the action is set
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReminderResponse {
+    pub dose: Option<DoseLogView>,
+    /// RFC3339
+    pub snoozed_until: Option<String>,
+    pub skip: Option<DoseSkip>,
+}
+
+/// The schedule's dose time closest to `now`, from yesterday to tomorrow
+fn nearest_occurrence(schedule: &DoseSchedule, now: OffsetDateTime) -> Option<OffsetDateTime> {
+    let time = parse_time(&schedule.time_of_day)?;
+    (-1..=1)
+        .map(|days| (now.date() + Duration::days(days)).with_time(time).assume_utc())
+        .filter(|at| schedule.days_of_week.contains(&at.weekday().number_days_from_sunday()))
+        .min_by_key(|at| (*at - now).abs())
+}
 
-            // Check if today is a scheduled day
-            if !s.days_of_week.contains(&current_weekday) {
-                return false;
+/// Handles the Taken, Snooze and Skip buttons of a dose reminder
+///
+/// Taken logs the schedule's current amount at its site, Snooze puts the
+/// reminder off from now, and Skip records the day's dose as skipped so
+/// adherence stats can count it. Taken and Skip also end any snooze.
+#[tauri::command]
+pub async fn respond_to_dose_reminder(
+    state: State<'_, std::sync::Arc<AppState>>,
+    snoozes: State<'_, ReminderSnoozes>,
+    payload: ReminderResponsePayload,
+) -> Result<ReminderResponse, CommandError> {
+    let schedule = state.on_storage(load_dose_schedules)
+        .await?
+        .into_iter()
+        .find(|schedule| schedule.id == payload.schedule_id)
+        .ok_or_else(|| CommandError::not_found(format!("Schedule {} not found", payload.schedule_id)))?;
+    let now = OffsetDateTime::now_utc();
+
+    match payload.action {
+        ReminderAction::Snooze => {
+            let minutes = payload.snooze_minutes.unwrap_or(DEFAULT_SNOOZE_MINUTES);
+            if !(1..=24 * 60).contains(&minutes) {
+                return Err(CommandError::invalid_input("Snooze for between 1 minute and 24 hours"));
             }
+            let until = now + Duration::minutes(minutes.into());
+            snoozes.snooze(&schedule.id, until);
+            info!("Snoozed the {} reminder for {} minutes", schedule.protocol_name, minutes);
+            Ok(ReminderResponse {
+                snoozed_until: Some(
+                    until
+                        .format(&Rfc3339)
+                        .map_err(|e| CommandError::with_context(e, "Failed to format time"))?,
+                ),
+                ..Default::default()
+            })
+        }
+        ReminderAction::Taken => {
+            snoozes.clear(&schedule.id);
+            let site = schedule.site.clone().filter(|site| !site.trim().is_empty());
+            let mut log = DoseLog::new(
+                schedule.protocol_id.as_str(),
+                site.as_deref().unwrap_or("Unspecified"),
+                schedule.amount_mg,
+            );
+            log.schedule_id = Some(schedule.id.clone());
+            let log = state
+                .db
+                .run(move |storage| storage.append_dose_log(&log).map(|_| log))
+                .await
+                .map_err(|e| {
+                    error!("Failed to log dose from reminder: {:#}", e);
+                    CommandError::with_context(e, "Failed to log dose")
+                })?;
+            info!("Logged the {} dose from its reminder", schedule.protocol_name);
 
-            // Parse schedule time
-            if let Some(schedule_time) = parse_time(&s.time_of_day) {
-                // Within 15 minute window
-                let diff_minutes = time_diff_minutes(current_time, schedule_time);
-                (0..=15).contains(&diff_minutes)
-            } else {
-                false
+            let preferences = load_unit_preferences(&state).await?;
+            Ok(ReminderResponse {
+                dose: Some(dose_view(&preferences, Some(&schedule.peptide_name), log)),
+                ..Default::default()
+            })
+        }
+        ReminderAction::Skip => {
+            snoozes.clear(&schedule.id);
+            let scheduled_on = nearest_occurrence(&schedule, now).map_or(now.date(), |at| at.date());
+            let skip = DoseSkip::new(schedule.protocol_id.as_str(), schedule.id.as_str(), scheduled_on);
+            let recorded = skip.clone();
+            let inserted = state
+                .db
+                .run(move |storage| storage.record_dose_skip(&recorded))
+                .await
+                .map_err(|e| {
+                    error!("Failed to record skipped dose: {:#}", e);
+                    CommandError::with_context(e, "Failed to record skipped dose")
+                })?;
+            if !inserted {
+                return Err(CommandError::invalid_input(format!(
+                    "The {} dose on {} was already skipped",
+                    schedule.protocol_name, scheduled_on
+                )));
             }
+            info!("Skipped the {} dose due {}", schedule.protocol_name, scheduled_on);
+            Ok(ReminderResponse {
+                skip: Some(skip),
+                ..Default::default()
+            })
+        }
+    }
+}
+
+/// Doses skipped from reminders, oldest first; dates are ISO days and both
+/// ends are included
+#[tauri::command]
+pub async fn list_dose_skips(
+    state: State<'_, std::sync::Arc<AppState>>,
+    protocol_id: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<Vec<DoseSkip>, CommandError> {
+    let parse = |date: Option<String>| {
+        date.map(|date| {
+            Date::parse(&date, DATE_FORMAT)
+                .map_err(|_| CommandError::invalid_input(format!("Invalid date {}", date)))
         })
-        .collect();
+        .transpose()
+    };
+    let filter = DoseStatsFilter {
+        protocol_id,
+        since: parse(start_date)?,
+        until: parse(end_date)?,
+        ..Default::default()
+    };
+    state
+        .db
+        .run(move |storage| storage.list_dose_skips(&filter))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to list skipped doses"))
+}
 
-    Ok(pending)
+#[tauri::command]
+pub async fn snooze_next_dose_reminder(
+    state: State<'_, std::sync::Arc<AppState>>,
+    snoozes: State<'_, ReminderSnoozes>,
+    minutes: Option<u32>,
+) -> Result<SnoozedReminder, CommandError> {
+    snooze_next(&state, &snoozes, minutes).await
 }
 
 fn is_valid_time_format(time_str: &str) -> bool {
     time_str.len() == 5 && time_str.chars().nth(2) == Some(':')
 }
 
-fn parse_time(time_str: &str) -> Option<Time> {
+pub(crate) fn parse_time(time_str: &str) -> Option<Time> {
     let parts: Vec<&str> = time_str.split(':').collect();
     if parts.len() != 2 {
         return None;
@@ -361,3 +1083,142 @@ fn time_diff_minutes(current: Time, target: Time) -> i32 {
     let target_minutes = target.hour() as i32 * 60 + target.minute() as i32;
     target_minutes - current_minutes
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    fn semaglutide(auto_advance: bool) -> Titration {
+        let payload: TitrationPayload = serde_json::from_value(serde_json::json!({
+            "phases": [
+                {"amountMg": 0.25, "durationDays": 28},
+                {"amountMg": 0.5, "durationDays": 28},
+                {"amountMg": 1.0, "durationDays": 0}
+            ],
+            "startDate": "2025-01-06",
+            "autoAdvance": auto_advance
+        }))
+        .unwrap();
+        Titration::from_payload(payload, date!(2025 - 01 - 01)).unwrap()
+    }
+
+    #[test]
+    fn test_titration_auto_advances_through_phases() {
+        let titration = semaglutide(true);
+
+        let status = titration.status(date!(2025 - 01 - 20)).unwrap();
+        assert_eq!(status.phase, 0);
+        assert_eq!(status.amount_mg, 0.25);
+        let next = status.next_step.unwrap();
+        assert_eq!(next.amount_mg, 0.5);
+        assert_eq!(next.starts_on, "2025-02-03");
+        assert_eq!(next.days_until, 14);
+
+        let status = titration.status(date!(2025 - 03 - 03)).unwrap();
+        assert_eq!(status.phase, 2);
+        assert_eq!(status.amount_mg, 1.0);
+        assert_eq!(status.phase_started, "2025-03-03");
+        assert!(status.next_step.is_none());
+    }
+
+    #[test]
+    fn test_manual_titration_waits_for_advance() {
+        let mut titration = semaglutide(false);
+
+        let status = titration.status(date!(2025 - 02 - 10)).unwrap();
+        assert_eq!(status.phase, 0);
+        assert_eq!(status.next_step.unwrap().days_until, -7);
+
+        titration.advance(date!(2025 - 02 - 10)).unwrap();
+        let status = titration.status(date!(2025 - 02 - 10)).unwrap();
+        assert_eq!(status.amount_mg, 0.5);
+        assert_eq!(status.next_step.unwrap().starts_on, "2025-03-10");
+
+        titration.advance(date!(2025 - 03 - 10)).unwrap();
+        assert!(titration.advance(date!(2025 - 04 - 10)).is_err());
+    }
+
+    #[test]
+    fn test_titration_rejects_phase_without_duration() {
+        let payload: TitrationPayload = serde_json::from_value(serde_json::json!({
+            "phases": [{"amountMg": 0.25, "durationDays": 0}, {"amountMg": 0.5, "durationDays": 0}]
+        }))
+        .unwrap();
+        assert!(Titration::from_payload(payload, date!(2025 - 01 - 01)).is_err());
+    }
+
+    fn schedule(id: &str, time_of_day: &str, days_of_week: Vec<u8>) -> DoseSchedule {
+        DoseSchedule {
+            id: id.to_string(),
+            protocol_id: "protocol-1".to_string(),
+            protocol_name: "Healing".to_string(),
+            peptide_name: "BPC-157".to_string(),
+            amount_mg: 0.25,
+            site: None,
+            time_of_day: time_of_day.to_string(),
+            days_of_week,
+            enabled: true,
+            notes: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            titration: None,
+            titration_status: None,
+            snoozed_until: None,
+        }
+    }
+
+    #[test]
+    fn test_next_reminder_finds_soonest_scheduled_dose() {
+        use time::macros::datetime;
+
+        // Wednesday
+        let now = datetime!(2025-01-08 09:00 UTC);
+        let mut disabled = schedule("disabled", "09:30", vec![3]);
+        disabled.enabled = false;
+        let schedules = vec![
+            disabled,
+            schedule("morning", "08:00", vec![0, 1, 2, 3, 4, 5, 6]),
+            schedule("friday", "10:00", vec![5]),
+        ];
+
+        let (next, at) = next_reminder(&schedules, now).unwrap();
+        assert_eq!(next.id, "morning");
+        assert_eq!(at, datetime!(2025-01-09 08:00 UTC));
+
+        let (next, at) = next_reminder(&schedules[2..], now).unwrap();
+        assert_eq!(next.id, "friday");
+        assert_eq!(at, datetime!(2025-01-10 10:00 UTC));
+        assert!(next_reminder(&schedules[..1], now).is_none());
+    }
+
+    #[test]
+    fn test_nearest_occurrence_picks_the_closest_scheduled_day() {
+        use time::macros::datetime;
+
+        // Wednesday, just after midnight: last night's dose is closest
+        let now = datetime!(2025-01-08 00:30 UTC);
+        let nightly = schedule("nightly", "23:00", vec![0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(nearest_occurrence(&nightly, now), Some(datetime!(2025-01-07 23:00 UTC)));
+
+        // Only Thursdays
+        let thursday = schedule("thursday", "08:00", vec![4]);
+        assert_eq!(nearest_occurrence(&thursday, now), Some(datetime!(2025-01-09 08:00 UTC)));
+        let monday = schedule("monday", "08:00", vec![1]);
+        assert_eq!(nearest_occurrence(&monday, now), None);
+    }
+
+    #[test]
+    fn test_snoozes_expire_after_reminder_window() {
+        use time::macros::datetime;
+
+        let snoozes = ReminderSnoozes::default();
+        let until = datetime!(2025-01-08 09:10 UTC);
+        snoozes.snooze("morning", until);
+
+        assert_eq!(snoozes.until("morning", datetime!(2025-01-08 09:00 UTC)), Some(until));
+        assert_eq!(snoozes.until("morning", datetime!(2025-01-08 09:20 UTC)), Some(until));
+        assert_eq!(snoozes.until("morning", datetime!(2025-01-08 09:25 UTC)), None);
+        assert_eq!(snoozes.until("morning", datetime!(2025-01-08 09:00 UTC)), None);
+    }
+}
@@ -0,0 +1,939 @@
+//! Supplier page scraping
+//!
+//! Pages are fetched politely: [`ScrapeThrottle`] checks each site's
+//! `robots.txt` (cached for a day) and spaces requests to one site by the
+//! configured delay, or the site's `Crawl-delay` when that's longer, using
+//! the user agent from [`ScrapingSettings`].
+//!
+//! Some suppliers render prices with JavaScript, so the fetched HTML has
+//! none. Builds with the `js-render` feature can load such pages in a hidden
+//! webview instead ([`PageRenderer`]) when the static HTML yields no prices
+//! and [`ScrapingSettings::render_javascript`] is on.
+//!
+//! Only public HTTP(S) hosts are scraped ([`validate_scraping_url`]), so a
+//! saved URL or a page the renderer navigates to can't reach the user's own
+//! machine or network. Fetches check every redirect the same way, and the
+//! address actually connected to before reading the body, as a public name
+//! can resolve to a private address.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, OnceLock};
+
+use peptrack_core::scraping::MAX_DOMAIN_DELAY_SECS;
+use peptrack_core::{RobotsTxt, ScrapingProfile, ScrapingSettings};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+use crate::commands::settings::{load_setting_or_default, save_setting};
+use crate::commands::suppliers::{fetch_page, is_out_of_stock, PriceMatch};
+use crate::error::{CommandError, ErrorKind};
+use crate::state::AppState;
+
+/// How long a site's `robots.txt` is trusted before it's fetched again
+const ROBOTS_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// Only this much of a `robots.txt` is read, as RFC 9309 allows
+const MAX_ROBOTS_BYTES: usize = 500 * 1024;
+/// Redirects followed before a fetch gives up, as many as reqwest's default
+const MAX_REDIRECTS: usize = 10;
+
+/// Per-site `robots.txt` rules and request pacing, shared by every scrape
+#[derive(Default)]
+pub struct ScrapeThrottle {
+    robots: Mutex<HashMap<String, CachedRobots>>,
+    /// When each site may next be requested
+    next_request: Mutex<HashMap<String, Instant>>,
+}
+
+struct CachedRobots {
+    rules: Arc<RobotsTxt>,
+    fetched_at: Instant,
+}
+
+impl ScrapeThrottle {
+    /// Wait until `url`'s site may be requested again, then claim the slot
+    pub async fn wait_turn(&self, url: &url::Url, delay: Duration) {
+        let site = site_key(url);
+        let wait = {
+            let mut next_request = self.next_request.lock().await;
+            let now = Instant::now();
+            let start = next_request.get(&site).copied().unwrap_or(now).max(now);
+            next_request.insert(site.clone(), start + delay);
+            start - now
+        };
+        if !wait.is_zero() {
+            debug!("Waiting {:?} before requesting {}", wait, site);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// The `robots.txt` rules of `url`'s site, fetched if not cached
+    ///
+    /// A missing `robots.txt` allows everything; one that can't be fetched
+    /// is an error, so the page isn't scraped without knowing the rules.
+    pub async fn robots_for(
+        &self,
+        settings: &ScrapingSettings,
+        url: &url::Url,
+    ) -> Result<Arc<RobotsTxt>, CommandError> {
+        let site = site_key(url);
+        if let Some(cached) = self.robots.lock().await.get(&site) {
+            if cached.fetched_at.elapsed() < ROBOTS_MAX_AGE {
+                return Ok(cached.rules.clone());
+            }
+        }
+
+        let mut robots_url = url.clone();
+        robots_url.set_path("/robots.txt");
+        robots_url.set_query(None);
+        robots_url.set_fragment(None);
+        self.wait_turn(url, Duration::from_secs(settings.domain_delay_secs as u64)).await;
+
+        let client = scraping_client(settings)?;
+        let response = client.get(robots_url).send().await.map_err(|e| {
+            warn!("Failed to fetch robots.txt of {}: {:#}", site, e);
+            CommandError::new(ErrorKind::Network, format!("Couldn't read robots.txt of {}", site))
+        })?;
+        check_remote_addr(&response)?;
+
+        let status = response.status();
+        let rules = if status.is_success() {
+            let body = response.bytes().await.map_err(|e| {
+                CommandError::with_context(e, "Failed to read robots.txt")
+            })?;
+            let body = &body[..body.len().min(MAX_ROBOTS_BYTES)];
+            RobotsTxt::parse(&String::from_utf8_lossy(body))
+        } else if status.is_client_error() {
+            RobotsTxt::allow_all()
+        } else {
+            warn!("robots.txt of {} returned {}", site, status);
+            return Err(CommandError::new(
+                ErrorKind::Network,
+                format!("Couldn't read robots.txt of {} ({})", site, status),
+            ));
+        };
+
+        let rules = Arc::new(rules);
+        self.robots.lock().await.insert(
+            site,
+            CachedRobots {
+                rules: rules.clone(),
+                fetched_at: Instant::now(),
+            },
+        );
+        Ok(rules)
+    }
+}
+
+/// Scheme, host and port of `url`; robots.txt and pacing apply per site
+fn site_key(url: &url::Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+/// Path and query of `url`, as robots.txt rules are matched against
+fn robots_path(url: &url::Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// Parse `url_str` and check it may be scraped: HTTP or HTTPS on a public host
+pub(crate) fn validate_scraping_url(url_str: &str) -> Result<url::Url, CommandError> {
+    let url = url::Url::parse(url_str)
+        .map_err(|_| CommandError::invalid_input("Invalid URL format"))?;
+    check_public_url(&url)?;
+    Ok(url)
+}
+
+/// HTTP client for scraping, following redirects only to public hosts
+pub(crate) fn scraping_client(settings: &ScrapingSettings) -> Result<reqwest::Client, CommandError> {
+    peptrack_literature::http::client_builder(settings.user_agent.trim())
+        .redirect(reqwest::redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("Too many redirects")
+            } else if check_public_url(attempt.url()).is_err() {
+                attempt.error("Redirected to a private/internal address")
+            } else {
+                attempt.follow()
+            }
+        }))
+        .build()
+        .map_err(|e| CommandError::with_context(e, "Failed to create HTTP client"))
+}
+
+/// Reject a response whose connection went to a non-public address
+///
+/// Through a proxy the connection is to the proxy, which resolves the name
+/// itself, so then only the URL checks apply.
+pub(crate) fn check_remote_addr(response: &reqwest::Response) -> Result<(), CommandError> {
+    if peptrack_literature::http::settings().proxy().is_some() {
+        return Ok(());
+    }
+    match response.remote_addr() {
+        Some(addr) if !is_public_ip(addr.ip()) => {
+            warn!("{} resolved to non-public address {}", response.url(), addr.ip());
+            Err(CommandError::new(
+                ErrorKind::PermissionDenied,
+                "Access to private/internal addresses is not allowed for security reasons",
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reject URLs that aren't HTTP(S) or point at a loopback, private,
+/// link-local or otherwise non-public host
+fn check_public_url(url: &url::Url) -> Result<(), CommandError> {
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(CommandError::invalid_input("Only HTTP and HTTPS URLs are allowed"));
+    }
+
+    let public = match url.host() {
+        Some(url::Host::Domain(domain)) => is_public_domain(domain),
+        Some(url::Host::Ipv4(ip)) => is_public_ip(IpAddr::V4(ip)),
+        Some(url::Host::Ipv6(ip)) => is_public_ip(IpAddr::V6(ip)),
+        None => false,
+    };
+    if !public {
+        return Err(CommandError::new(
+            ErrorKind::PermissionDenied,
+            "Access to private/internal addresses is not allowed for security reasons",
+        ));
+    }
+    Ok(())
+}
+
+/// Names that resolve to this machine or the local network
+fn is_public_domain(domain: &str) -> bool {
+    let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+    !(domain == "localhost" || domain.ends_with(".localhost") || domain.ends_with(".local"))
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || first == 0
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (first == 100 && second & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_ip(IpAddr::V4(mapped)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    // Unique local, fc00::/7
+                    || first & 0xfe00 == 0xfc00
+                    // Link-local, fe80::/10
+                    || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Check `url` may be scraped and wait for its site's turn
+pub(crate) async fn prepare_request(
+    state: &AppState,
+    settings: &ScrapingSettings,
+    url: &url::Url,
+) -> Result<(), CommandError> {
+    let mut delay = Duration::from_secs(settings.domain_delay_secs as u64);
+    if settings.respect_robots_txt {
+        let robots = state.scrape_throttle.robots_for(settings, url).await?;
+        if !robots.is_allowed(settings.product_token(), &robots_path(url)) {
+            info!("robots.txt disallows scraping {}", url);
+            return Err(CommandError::new(
+                ErrorKind::PermissionDenied,
+                format!("{}'s robots.txt doesn't allow scraping this page", site_key(url)),
+            ));
+        }
+        if let Some(crawl_delay) = robots.crawl_delay(settings.product_token()) {
+            let crawl_delay = Duration::from_secs_f64(crawl_delay.clamp(0.0, MAX_DOMAIN_DELAY_SECS as f64));
+            delay = delay.max(crawl_delay);
+        }
+    }
+    state.scrape_throttle.wait_turn(url, delay).await;
+    Ok(())
+}
+
+/// Where a render window may navigate once its page is open
+///
+/// Every target must be a public host. When robots.txt is respected, it
+/// must also be a page of the rendered site its rules allow; other sites'
+/// rules aren't known, so navigating to them is refused.
+#[cfg_attr(not(feature = "js-render"), allow(dead_code))]
+pub(crate) struct NavigationPolicy {
+    site: String,
+    /// The rendered site's rules and our product token
+    robots: Option<(Arc<RobotsTxt>, String)>,
+}
+
+#[cfg_attr(not(feature = "js-render"), allow(dead_code))]
+impl NavigationPolicy {
+    pub(crate) fn allows(&self, target: &url::Url) -> bool {
+        if check_public_url(target).is_err() {
+            return false;
+        }
+        match self.robots {
+            Some((ref robots, ref product_token)) => {
+                site_key(target) == self.site
+                    && robots.is_allowed(product_token, &robots_path(target))
+            }
+            None => true,
+        }
+    }
+}
+
+/// Loads pages in a hidden webview so prices rendered by JavaScript can be
+/// scraped; a no-op without the `js-render` feature
+#[derive(Default)]
+pub struct PageRenderer {
+    /// Set once the app is running
+    app: OnceLock<AppHandle>,
+    /// Pages are rendered one at a time
+    busy: Mutex<()>,
+}
+
+impl PageRenderer {
+    pub fn attach(&self, app: AppHandle) {
+        self.app.set(app).ok();
+    }
+
+    /// Whether this build can render pages
+    pub fn is_available() -> bool {
+        cfg!(feature = "js-render")
+    }
+
+    /// The HTML of `url` after its scripts ran, following only the
+    /// navigations `navigation` allows
+    pub async fn render(
+        &self,
+        url: &url::Url,
+        user_agent: &str,
+        navigation: NavigationPolicy,
+    ) -> anyhow::Result<String> {
+        let app = self
+            .app
+            .get()
+            .ok_or_else(|| anyhow::anyhow!("The app isn't running yet"))?;
+        let _busy = self.busy.lock().await;
+        renderer::render(app, url, user_agent, navigation).await
+    }
+}
+
+/// The page at `url` rendered by a hidden webview, when the settings and
+/// this build allow it; failures are logged and give None
+pub(crate) async fn render_page(state: &AppState, url: &url::Url) -> Option<String> {
+    let settings: ScrapingSettings = load_setting_or_default(state).await;
+    if !settings.render_javascript || !PageRenderer::is_available() {
+        return None;
+    }
+    if let Err(e) = prepare_request(state, &settings, url).await {
+        warn!("Not rendering {}: {}", url, e);
+        return None;
+    }
+    let robots = if settings.respect_robots_txt {
+        // Cached by prepare_request
+        match state.scrape_throttle.robots_for(&settings, url).await {
+            Ok(robots) => Some((robots, settings.product_token().to_string())),
+            Err(e) => {
+                warn!("Not rendering {}: {}", url, e);
+                return None;
+            }
+        }
+    } else {
+        None
+    };
+    let navigation = NavigationPolicy {
+        site: site_key(url),
+        robots,
+    };
+    info!("Rendering {} to find prices added by JavaScript", url);
+    match state.page_renderer.render(url, settings.user_agent.trim(), navigation).await {
+        Ok(html) => Some(html),
+        Err(e) => {
+            warn!("Failed to render {}: {:#}", url, e);
+            None
+        }
+    }
+}
+
+#[cfg(feature = "js-render")]
+mod renderer {
+    //! The rendered HTML is passed back through navigations: an
+    //! initialization script sends it in chunks as navigations to
+    //! [`RESULT_HOST`], which are cancelled and collected, asking for the
+    //! next chunk once one arrives. The page runs in its own incognito
+    //! window that no capability covers, so it can't call app commands.
+
+    use anyhow::{anyhow, bail, Result};
+    use tauri::webview::PageLoadEvent;
+    use tracing::warn;
+    use tauri::{AppHandle, WebviewUrl, WebviewWindowBuilder};
+    use tokio::sync::{mpsc, Notify};
+    use tokio::time::{sleep, timeout, Duration};
+
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use super::{parse_rendered_chunk, NavigationPolicy};
+
+    const RESULT_HOST: &str = "peptrack-render.invalid";
+    /// Most the page may take to load
+    const LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+    /// Time given to the page's scripts after it loaded
+    const SCRIPT_SETTLE: Duration = Duration::from_secs(3);
+    const CHUNK_TIMEOUT: Duration = Duration::from_secs(10);
+    /// Characters per chunk, before URL encoding
+    const CHUNK_CHARS: usize = 16 * 1024;
+    /// Longer pages are cut off; prices are rarely past this
+    const MAX_HTML_CHARS: usize = 4 * 1024 * 1024;
+
+    static NEXT_WINDOW: AtomicU64 = AtomicU64::new(0);
+
+    fn script() -> String {
+        format!(
+            r#"window.__peptrackSend = function (i) {{
+  if (window.__peptrackHtml === undefined) {{
+    // Surrogates are dropped so a chunk never splits a character in two
+    window.__peptrackHtml = document.documentElement.outerHTML
+      .replace(/[\uD800-\uDFFF]/g, "")
+      .slice(0, {max});
+  }}
+  var html = window.__peptrackHtml;
+  var n = Math.max(1, Math.ceil(html.length / {chunk}));
+  var part = html.slice(i * {chunk}, (i + 1) * {chunk});
+  window.location.href = "https://{host}/?i=" + i + "&n=" + n + "&d=" + encodeURIComponent(part);
+}};"#,
+            max = MAX_HTML_CHARS,
+            chunk = CHUNK_CHARS,
+            host = RESULT_HOST,
+        )
+    }
+
+    pub async fn render(
+        app: &AppHandle,
+        url: &url::Url,
+        user_agent: &str,
+        navigation: NavigationPolicy,
+    ) -> Result<String> {
+        let (chunk_tx, mut chunks) = mpsc::unbounded_channel();
+        let loaded = Arc::new(Notify::new());
+        let on_load = loaded.clone();
+        let label = format!("scrape-render-{}", NEXT_WINDOW.fetch_add(1, Ordering::SeqCst));
+
+        let window = WebviewWindowBuilder::new(app, label, WebviewUrl::External(url.clone()))
+            .visible(false)
+            .incognito(true)
+            .user_agent(user_agent)
+            .initialization_script(script())
+            .on_navigation(move |target| {
+                if target.host_str() == Some(RESULT_HOST) {
+                    chunk_tx.send(target.clone()).ok();
+                    return false;
+                }
+                if !navigation.allows(target) {
+                    warn!("Render window blocked from navigating to {}", target);
+                    return false;
+                }
+                true
+            })
+            .on_page_load(move |_, payload| {
+                if payload.event() == PageLoadEvent::Finished {
+                    on_load.notify_one();
+                }
+            })
+            .build()?;
+
+        let result = async {
+            timeout(LOAD_TIMEOUT, loaded.notified())
+                .await
+                .map_err(|_| anyhow!("Page didn't load within {:?}", LOAD_TIMEOUT))?;
+            sleep(SCRIPT_SETTLE).await;
+
+            let mut html = String::new();
+            let mut index = 0;
+            loop {
+                window.eval(format!("window.__peptrackSend({})", index))?;
+                let chunk = timeout(CHUNK_TIMEOUT, chunks.recv())
+                    .await
+                    .map_err(|_| anyhow!("Rendered page wasn't returned"))?
+                    .ok_or_else(|| anyhow!("Render window closed"))?;
+                let (i, n, data) = parse_rendered_chunk(&chunk)?;
+                if i != index {
+                    bail!("Expected part {} of the rendered page, got {}", index, i);
+                }
+                html.push_str(&data);
+                index += 1;
+                if index >= n {
+                    return Ok(html);
+                }
+            }
+        }
+        .await;
+
+        if let Err(e) = window.destroy() {
+            tracing::warn!("Failed to close render window: {}", e);
+        }
+        result
+    }
+}
+
+#[cfg(not(feature = "js-render"))]
+mod renderer {
+    use anyhow::{bail, Result};
+    use tauri::AppHandle;
+
+    use super::NavigationPolicy;
+
+    pub async fn render(
+        _app: &AppHandle,
+        _url: &url::Url,
+        _user_agent: &str,
+        _navigation: NavigationPolicy,
+    ) -> Result<String> {
+        bail!("This build of PepTrack can't render JavaScript pages")
+    }
+}
+
+/// Index, count and text of a render window's chunk navigation
+#[cfg_attr(not(feature = "js-render"), allow(dead_code))]
+fn parse_rendered_chunk(url: &url::Url) -> anyhow::Result<(usize, usize, String)> {
+    let (mut i, mut n, mut data) = (None, None, String::new());
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "i" => i = value.parse().ok(),
+            "n" => n = value.parse().ok(),
+            "d" => data = value.into_owned(),
+            _ => {}
+        }
+    }
+    match (i, n) {
+        (Some(i), Some(n)) => Ok((i, n, data)),
+        _ => anyhow::bail!("Malformed rendered page part"),
+    }
+}
+
+/// Gets the scraper's user agent, robots.txt, delay and crawl budget settings
+#[tauri::command]
+pub async fn get_scraping_settings(
+    state: State<'_, Arc<AppState>>,
+) -> Result<ScrapingSettings, CommandError> {
+    Ok(load_setting_or_default(&state).await)
+}
+
+/// Saves the scraper settings
+#[tauri::command]
+pub async fn update_scraping_settings(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    settings: ScrapingSettings,
+) -> Result<ScrapingSettings, CommandError> {
+    let settings = ScrapingSettings {
+        user_agent: settings.user_agent.trim().to_string(),
+        ..settings
+    };
+    if settings.render_javascript && !PageRenderer::is_available() {
+        return Err(CommandError::invalid_input(
+            "This build of PepTrack can't render JavaScript pages",
+        ));
+    }
+    save_setting(&app, &state, &settings).await?;
+    info!("Scraping settings updated: {:?}", settings);
+    Ok(settings)
+}
+
+/// Structured data extracted from a page with a scraping profile
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileExtraction {
+    /// Text of every element matched by the price selector
+    pub price_texts: Vec<String>,
+    /// Text of every element matched by the size selector
+    pub size_texts: Vec<String>,
+    /// Text of the first element matched by the stock selector
+    pub stock_text: Option<String>,
+    /// Prices that could be normalized to a per-mg value
+    pub matches: Vec<PriceMatch>,
+    /// Stock status, if the stock selector matched an element
+    pub in_stock: Option<bool>,
+}
+
+/// Validate every selector in a profile so bad profiles are rejected on save
+pub fn validate_profile(profile: &ScrapingProfile) -> Result<(), CommandError> {
+    parse_selector("price", &profile.price_selector)?;
+    if let Some(ref selector) = profile.size_selector {
+        parse_selector("size", selector)?;
+    }
+    if let Some(ref selector) = profile.stock_selector {
+        parse_selector("stock", selector)?;
+    }
+    Ok(())
+}
+
+fn parse_selector(kind: &str, selector: &str) -> Result<Selector, CommandError> {
+    if selector.trim().is_empty() {
+        return Err(CommandError::invalid_input(format!("The {} selector cannot be empty", kind)));
+    }
+    Selector::parse(selector).map_err(|e| {
+        CommandError::invalid_input(format!("Invalid {} selector '{}': {}", kind, selector, e))
+    })
+}
+
+fn element_text(element: ElementRef<'_>) -> String {
+    let text: Vec<&str> = element.text().map(str::trim).filter(|t| !t.is_empty()).collect();
+    text.join(" ")
+}
+
+fn select_texts(document: &Html, selector: &Selector) -> Vec<String> {
+    document
+        .select(selector)
+        .map(element_text)
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+/// Parse a money amount such as "$1,249.99" or "USD 45"
+fn parse_price(text: &str) -> Option<f32> {
+    let price_re = Regex::new(r"(\d{1,3}(?:,\d{3})+|\d+)(?:\.(\d{1,2}))?").unwrap();
+    let cap = price_re.captures(text)?;
+    let whole = cap.get(1)?.as_str().replace(',', "");
+    let value = match cap.get(2) {
+        Some(cents) => format!("{}.{}", whole, cents.as_str()),
+        None => whole,
+    };
+    value.parse().ok()
+}
+
+/// Parse a vial size in mg, converting mcg where needed
+fn parse_size_mg(text: &str) -> Option<f32> {
+    let size_re = Regex::new(r"(?i)(\d+(?:\.\d+)?)\s*(mg|mcg|µg|ug)\b").unwrap();
+    let cap = size_re.captures(text)?;
+    let amount: f32 = cap.get(1)?.as_str().parse().ok()?;
+    let unit = cap.get(2)?.as_str().to_lowercase();
+    let mg = if unit == "mg" { amount } else { amount / 1000.0 };
+    (mg > 0.0).then_some(mg)
+}
+
+fn is_per_mg_text(text: &str) -> bool {
+    let lower = text.to_lowercase();
+    lower.contains("/mg") || lower.contains("/ mg") || lower.contains("per mg")
+}
+
+/// Extract prices, sizes and stock status from HTML using a scraping profile
+///
+/// Price elements are paired with size elements by position; when there is a
+/// single size element it applies to every price. Prices that cannot be
+/// normalized to $/mg are reported in `price_texts` but not in `matches`.
+pub fn extract_with_profile(html: &str, profile: &ScrapingProfile) -> Result<ProfileExtraction, CommandError> {
+    let price_selector = parse_selector("price", &profile.price_selector)?;
+    let size_selector = profile
+        .size_selector
+        .as_deref()
+        .map(|s| parse_selector("size", s))
+        .transpose()?;
+    let stock_selector = profile
+        .stock_selector
+        .as_deref()
+        .map(|s| parse_selector("stock", s))
+        .transpose()?;
+
+    let document = Html::parse_document(html);
+
+    let price_texts = select_texts(&document, &price_selector);
+    let size_texts = size_selector
+        .as_ref()
+        .map(|selector| select_texts(&document, selector))
+        .unwrap_or_default();
+    let stock_text = stock_selector
+        .as_ref()
+        .and_then(|selector| select_texts(&document, selector).into_iter().next());
+
+    let mut matches = Vec::new();
+    for (index, price_text) in price_texts.iter().enumerate() {
+        let Some(price) = parse_price(price_text) else {
+            continue;
+        };
+
+        if is_per_mg_text(price_text) {
+            matches.push(PriceMatch {
+                price_per_mg: price,
+                context: price_text.clone(),
+                pattern_type: "selector_per_mg".to_string(),
+            });
+            continue;
+        }
+
+        // Prefer the size element at the same position, then a single shared
+        // size element, then a size mentioned in the price text itself
+        let size_text = size_texts
+            .get(index)
+            .or(if size_texts.len() == 1 { size_texts.first() } else { None });
+        let size_mg = size_text
+            .and_then(|text| parse_size_mg(text))
+            .or_else(|| parse_size_mg(price_text));
+
+        if let Some(mg) = size_mg {
+            matches.push(PriceMatch {
+                price_per_mg: price / mg,
+                context: match size_text {
+                    Some(size) => format!("{} ({})", price_text, size),
+                    None => price_text.clone(),
+                },
+                pattern_type: "selector_vial_price".to_string(),
+            });
+        }
+    }
+
+    matches.sort_by(|a, b| a.price_per_mg.partial_cmp(&b.price_per_mg).unwrap());
+    matches.dedup_by(|a, b| (a.price_per_mg - b.price_per_mg).abs() < 0.01);
+
+    let in_stock = stock_text.as_deref().map(|text| match profile.out_of_stock_text {
+        Some(ref marker) if !marker.trim().is_empty() => {
+            !text.to_lowercase().contains(&marker.to_lowercase())
+        }
+        _ => !is_out_of_stock(text),
+    });
+
+    Ok(ProfileExtraction {
+        price_texts,
+        size_texts,
+        stock_text,
+        matches,
+        in_stock,
+    })
+}
+
+/// Preview what a scraping profile extracts from a page without saving anything
+#[tauri::command]
+pub async fn preview_scraping_profile(
+    state: State<'_, Arc<AppState>>,
+    url: String,
+    profile: ScrapingProfile,
+) -> Result<ProfileExtraction, CommandError> {
+    info!("Previewing scraping profile on: {}", url);
+
+    validate_profile(&profile)?;
+    let html = fetch_page(&state, &url).await?;
+
+    let mut extraction = extract_with_profile(&html, &profile).map_err(|e| {
+        error!("Scraping profile preview failed: {}", e);
+        e
+    })?;
+    if extraction.matches.is_empty() {
+        if let Ok(parsed) = url::Url::parse(&url) {
+            if let Some(rendered) = render_page(&state, &parsed).await {
+                extraction = extract_with_profile(&rendered, &profile)?;
+            }
+        }
+    }
+    Ok(extraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRODUCT_HTML: &str = r#"
+        <div class="product">
+            <span class="size">5mg</span><span class="price">$45.00</span>
+            <span class="size">10 mg</span><span class="price">$80.00</span>
+            <p class="availability">In stock</p>
+        </div>
+    "#;
+
+    fn profile() -> ScrapingProfile {
+        ScrapingProfile {
+            price_selector: ".price".to_string(),
+            size_selector: Some(".size".to_string()),
+            stock_selector: Some(".availability".to_string()),
+            out_of_stock_text: None,
+        }
+    }
+
+    /// Answer one request on a loopback port with `response`
+    async fn serve_once(response: &'static str) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await;
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_redirects_and_addresses_must_be_public() {
+        let client = scraping_client(&ScrapingSettings::default()).unwrap();
+
+        let url = serve_once("HTTP/1.1 302 Found\r\nLocation: http://10.0.0.1/\r\nContent-Length: 0\r\n\r\n").await;
+        let error = client.get(url).send().await.unwrap_err();
+        assert!(error.is_redirect());
+
+        // As if a public name had resolved to this machine
+        let url = serve_once("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await;
+        let response = client.get(url).send().await.unwrap();
+        assert!(check_remote_addr(&response).is_err());
+    }
+
+    #[test]
+    fn test_extract_pairs_prices_with_sizes() {
+        let extraction = extract_with_profile(PRODUCT_HTML, &profile()).unwrap();
+
+        assert_eq!(extraction.price_texts, vec!["$45.00", "$80.00"]);
+        assert_eq!(extraction.matches.len(), 2);
+        assert!((extraction.matches[0].price_per_mg - 8.0).abs() < 0.01);
+        assert!((extraction.matches[1].price_per_mg - 9.0).abs() < 0.01);
+        assert_eq!(extraction.in_stock, Some(true));
+    }
+
+    #[test]
+    fn test_extract_honors_custom_out_of_stock_text() {
+        let html = r#"<span class="price">$2.50/mg</span><div class="stock">Backordered</div>"#;
+        let custom = ScrapingProfile {
+            price_selector: ".price".to_string(),
+            size_selector: None,
+            stock_selector: Some(".stock".to_string()),
+            out_of_stock_text: Some("backordered".to_string()),
+        };
+
+        let extraction = extract_with_profile(html, &custom).unwrap();
+        assert_eq!(extraction.matches[0].pattern_type, "selector_per_mg");
+        assert_eq!(extraction.in_stock, Some(false));
+    }
+
+    #[test]
+    fn test_invalid_selector_is_rejected() {
+        let mut bad = profile();
+        bad.price_selector = "div[".to_string();
+
+        assert!(validate_profile(&bad).is_err());
+        assert!(extract_with_profile(PRODUCT_HTML, &bad).is_err());
+    }
+
+    #[test]
+    fn test_parse_price_and_size() {
+        assert_eq!(parse_price("$1,249.99"), Some(1249.99));
+        assert_eq!(parse_price("USD 45"), Some(45.0));
+        assert_eq!(parse_size_mg("500mcg vial"), Some(0.5));
+        assert_eq!(parse_size_mg("no size"), None);
+    }
+
+    #[test]
+    fn test_robots_rules_apply_per_site() {
+        let url = url::Url::parse("https://shop.example:8443/products/bpc?size=5mg#reviews").unwrap();
+        assert_eq!(site_key(&url), "https://shop.example:8443");
+        assert_eq!(robots_path(&url), "/products/bpc?size=5mg");
+    }
+
+    #[test]
+    fn test_scraping_urls_must_be_public() {
+        assert!(validate_scraping_url("https://shop.example/products/bpc").is_ok());
+        assert!(validate_scraping_url("http://93.184.216.34/").is_ok());
+        assert!(validate_scraping_url("http://[2606:4700::1111]/").is_ok());
+
+        for url in [
+            "not a url",
+            "ftp://shop.example/prices",
+            "file:///etc/passwd",
+            "http://localhost:8080/",
+            "http://LOCALHOST./",
+            "http://admin.localhost/",
+            "http://printer.local/",
+            "http://127.0.0.1/",
+            "http://127.1/",
+            "http://2130706433/",
+            "http://0.0.0.0/",
+            "http://10.1.2.3/",
+            "http://172.31.255.255/",
+            "http://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1/",
+            "http://[::1]/",
+            "http://[::]/",
+            "http://[fd00::1]/",
+            "http://[fe80::1]/",
+            "http://[::ffff:127.0.0.1]/",
+        ] {
+            assert!(validate_scraping_url(url).is_err(), "{} was allowed", url);
+        }
+        // Private ranges are matched exactly, not by prefix
+        assert!(validate_scraping_url("http://172.32.0.1/").is_ok());
+        assert!(validate_scraping_url("https://10.example.com/").is_ok());
+    }
+
+    #[test]
+    fn test_render_navigation_is_checked() {
+        let url = |s: &str| url::Url::parse(s).unwrap();
+        let robots = RobotsTxt::parse("User-agent: *\nDisallow: /account\n");
+        let policy = NavigationPolicy {
+            site: "https://shop.example".into(),
+            robots: Some((Arc::new(robots), "PepTrack".into())),
+        };
+        assert!(policy.allows(&url("https://shop.example/products/bpc?size=5")));
+        assert!(!policy.allows(&url("https://shop.example/account/orders")));
+        assert!(!policy.allows(&url("https://other.example/products")));
+        assert!(!policy.allows(&url("http://192.168.0.1/")));
+
+        let policy = NavigationPolicy {
+            site: "https://shop.example".into(),
+            robots: None,
+        };
+        assert!(policy.allows(&url("https://other.example/products")));
+        assert!(!policy.allows(&url("http://localhost/")));
+        assert!(!policy.allows(&url("http://[::1]:9000/")));
+    }
+
+    #[test]
+    fn test_rendered_chunks_are_parsed() {
+        let chunk = url::Url::parse(
+            "https://peptrack-render.invalid/?i=1&n=3&d=%3Cp%20class%3D%22price%22%3E%2445%26amp%3B%3C%2Fp%3E",
+        )
+        .unwrap();
+        let (i, n, data) = parse_rendered_chunk(&chunk).unwrap();
+        assert_eq!((i, n), (1, 3));
+        assert_eq!(data, "<p class=\"price\">$45&amp;</p>");
+
+        // An empty page still comes back as one empty part
+        let empty = url::Url::parse("https://peptrack-render.invalid/?i=0&n=1&d=").unwrap();
+        assert_eq!(parse_rendered_chunk(&empty).unwrap(), (0, 1, String::new()));
+
+        for malformed in ["?n=1&d=x", "?i=0&d=x", "?i=-1&n=1", "?i=a&n=1"] {
+            let url = url::Url::parse(&format!("https://peptrack-render.invalid/{}", malformed))
+                .unwrap();
+            assert!(parse_rendered_chunk(&url).is_err(), "{}", malformed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_to_one_site_are_spaced() {
+        let throttle = ScrapeThrottle::default();
+        let shop = url::Url::parse("https://shop.example/a").unwrap();
+        let other = url::Url::parse("https://other.example/a").unwrap();
+        let delay = Duration::from_millis(200);
+
+        let start = Instant::now();
+        throttle.wait_turn(&shop, delay).await;
+        throttle.wait_turn(&other, delay).await;
+        assert!(start.elapsed() < delay);
+        throttle.wait_turn(&shop, delay).await;
+        assert!(start.elapsed() >= delay);
+    }
+}
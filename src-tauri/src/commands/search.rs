@@ -0,0 +1,202 @@
+use anyhow::Result;
+use peptrack_core::{SearchEntityType, SearchHit, StorageManager};
+use serde::Serialize;
+use tauri::State;
+use tracing::{error, info};
+
+use crate::error::CommandError;
+use crate::state::AppState;
+
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+/// Longest snippet of notes or message text shown under a result
+const SUBTITLE_MAX_CHARS: usize = 120;
+
+/// A search match with enough detail to display it and open the right screen
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalSearchResult {
+    pub entity_type: SearchEntityType,
+    pub entity_id: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    /// Protocol the record belongs to, for dose logs and inventory
+    pub protocol_id: Option<String>,
+    pub date: Option<String>,
+}
+
+fn snippet(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= SUBTITLE_MAX_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(SUBTITLE_MAX_CHARS).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Load the record behind a hit; `None` if it no longer exists
+fn describe_hit(storage: &StorageManager, hit: SearchHit) -> Result<Option<GlobalSearchResult>> {
+    let result = match hit.entity_type {
+        SearchEntityType::Protocol => storage.get_protocol(&hit.entity_id)?.map(|protocol| {
+            GlobalSearchResult {
+                entity_type: hit.entity_type,
+                title: protocol.name,
+                subtitle: Some(protocol.peptide_name),
+                protocol_id: Some(protocol.id),
+                date: None,
+                entity_id: hit.entity_id,
+            }
+        }),
+        SearchEntityType::DoseLog => storage.get_dose_log(&hit.entity_id)?.map(|log| {
+            GlobalSearchResult {
+                entity_type: hit.entity_type,
+                title: format!("{} mg dose ({})", log.amount_mg, log.site),
+                subtitle: log.notes.as_deref().map(snippet),
+                protocol_id: Some(log.protocol_id),
+                date: Some(log.logged_at.date().to_string()),
+                entity_id: hit.entity_id,
+            }
+        }),
+        SearchEntityType::Supplier => storage.get_supplier(&hit.entity_id)?.map(|supplier| {
+            GlobalSearchResult {
+                entity_type: hit.entity_type,
+                title: supplier.name,
+                subtitle: supplier.website,
+                protocol_id: None,
+                date: None,
+                entity_id: hit.entity_id,
+            }
+        }),
+        SearchEntityType::InventoryItem => storage.get_inventory_item(&hit.entity_id)?.map(|item| {
+            let identifiers: Vec<String> = [
+                item.vial_number.map(|v| format!("Vial {}", v)),
+                item.batch_number.map(|b| format!("Batch {}", b)),
+                item.lot_number.map(|l| format!("Lot {}", l)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+
+            GlobalSearchResult {
+                entity_type: hit.entity_type,
+                title: if identifiers.is_empty() {
+                    "Inventory item".to_string()
+                } else {
+                    identifiers.join(" · ")
+                },
+                subtitle: item.notes.as_deref().map(snippet),
+                protocol_id: Some(item.protocol_id),
+                date: item.purchase_date.map(|date| date.date().to_string()),
+                entity_id: hit.entity_id,
+            }
+        }),
+        SearchEntityType::Summary => storage.get_summary(&hit.entity_id)?.map(|summary| {
+            GlobalSearchResult {
+                entity_type: hit.entity_type,
+                title: summary.title,
+                subtitle: Some(snippet(&summary.summary_output)),
+                protocol_id: None,
+                date: Some(summary.created_at.date().to_string()),
+                entity_id: hit.entity_id,
+            }
+        }),
+        SearchEntityType::Alert => storage.get_alert(&hit.entity_id)?.map(|alert| {
+            GlobalSearchResult {
+                entity_type: hit.entity_type,
+                title: alert.title,
+                subtitle: Some(snippet(&alert.message)),
+                protocol_id: None,
+                date: Some(alert.created_at.date().to_string()),
+                entity_id: hit.entity_id,
+            }
+        }),
+        SearchEntityType::JournalEntry => storage.get_journal_entry(&hit.entity_id)?.map(|entry| {
+            let preview = snippet(&peptrack_core::strip_links(&entry.body));
+            GlobalSearchResult {
+                entity_type: hit.entity_type,
+                title: entry.title.unwrap_or_else(|| preview.clone()),
+                subtitle: Some(preview),
+                protocol_id: None,
+                date: Some(entry.entry_date.date().to_string()),
+                entity_id: hit.entity_id,
+            }
+        }),
+    };
+    Ok(result)
+}
+
+// ========== Search Commands ==========
+
+/// Search protocols, dose notes, suppliers, inventory lot/batch numbers,
+/// summaries, alerts and journal entries
+///
+/// Every word must match the start of a word in the record. Words starting
+/// with `#` (or `tag:`) only match protocol and journal tags. `entity_types` limits the
+/// kinds of record returned.
+#[tauri::command]
+pub async fn global_search(
+    state: State<'_, std::sync::Arc<AppState>>,
+    query: String,
+    entity_types: Option<Vec<SearchEntityType>>,
+    limit: Option<usize>,
+) -> Result<Vec<GlobalSearchResult>, CommandError> {
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT).max(1);
+
+    state
+        .db
+        .run(move |storage| {
+            let hits = storage.search(&query, entity_types.as_deref(), limit)?;
+            let mut results = Vec::with_capacity(hits.len());
+            for hit in hits {
+                match describe_hit(storage, hit) {
+                    Ok(Some(result)) => results.push(result),
+                    Ok(None) => {}
+                    Err(e) => error!("Failed to load search result: {:#}", e),
+                }
+            }
+            Ok(results)
+        })
+        .await
+        .map_err(|e| {
+            error!("Search failed: {:#}", e);
+            CommandError::with_context(e, "Search failed")
+        })
+}
+
+/// Rebuild the search index from scratch
+#[tauri::command]
+pub async fn rebuild_search_index(
+    state: State<'_, std::sync::Arc<AppState>>,
+) -> Result<usize, CommandError> {
+    let count = state
+        .db
+        .run(|storage| storage.rebuild_search_index())
+        .await
+        .map_err(|e| {
+            error!("Failed to rebuild search index: {:#}", e);
+            CommandError::with_context(e, "Failed to rebuild search index")
+        })?;
+
+    info!("Rebuilt search index with {} records", count);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_truncates_long_text() {
+        assert_eq!(snippet("  short note "), "short note");
+
+        let long = "word ".repeat(40);
+        let cut = snippet(&long);
+        assert!(cut.ends_with('…'));
+        assert!(cut.chars().count() <= SUBTITLE_MAX_CHARS + 1);
+    }
+
+    #[test]
+    fn test_entity_type_serializes_for_routing() {
+        let json = serde_json::to_string(&SearchEntityType::InventoryItem).unwrap();
+        assert_eq!(json, r#""inventory_item""#);
+    }
+}
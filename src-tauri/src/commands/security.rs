@@ -0,0 +1,660 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use peptrack_core::{
+    change_passphrase, create_recovery_code, generate_key, recover_storage, rotate_storage_key,
+    unlock_storage, BiometricKeyProvider, KdfParams, KeyProvider, KeyRotation, KeyRotationProgress,
+    PassphraseKeyProvider, RecoveryCodeStatus, StorageManager,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+use tokio::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::commands::connectivity::apply_http_settings;
+use crate::error::CommandError;
+use crate::state::{self, app_data_dir, AppState, KeyLocation};
+
+const SETTINGS_FILENAME: &str = "security.json";
+const BIOMETRIC_SETTINGS_FILENAME: &str = "biometric.json";
+/// How often the auto-lock timer is checked
+const AUTO_LOCK_CHECK_SECS: u64 = 30;
+/// Emitted to the frontend when the database locks itself
+pub const DATABASE_LOCKED_EVENT: &str = "database-locked";
+/// Emitted with a [`KeyRotationProgress`] while data is re-encrypted
+pub const KEY_ROTATION_PROGRESS_EVENT: &str = "key-rotation-progress";
+/// Emit progress every this many values, plus once at the end
+const PROGRESS_EVENT_STEP: usize = 100;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoLockSettings {
+    /// Lock after this many minutes without activity; `None` never locks
+    pub timeout_minutes: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BiometricSettings {
+    /// Require Touch ID / Windows Hello before the stored key is used
+    pub enabled: bool,
+    /// Skip the prompt when unlocking again within this many minutes;
+    /// `None` prompts once per app session
+    pub grace_period_minutes: Option<u32>,
+}
+
+impl BiometricSettings {
+    pub fn grace_period(&self) -> Option<Duration> {
+        self.grace_period_minutes
+            .map(|minutes| Duration::from_secs(u64::from(minutes) * 60))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockStatus {
+    pub passphrase_enabled: bool,
+    pub biometric_enabled: bool,
+    pub biometric_available: bool,
+    pub locked: bool,
+    /// Locked with no passphrase or biometric unlock to open it: the stored
+    /// key was lost and only the recovery code can
+    pub recovery_required: bool,
+    pub recovery_code: RecoveryCodeStatus,
+    pub auto_lock_minutes: Option<u32>,
+}
+
+/// Where to keep the key after rotating it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum KeyRotationTarget {
+    /// The Keychain on macOS when available, otherwise the key file
+    StoredKey {
+        /// Required when moving off a passphrase
+        current_passphrase: Option<String>,
+    },
+    /// A key derived from a passphrase
+    Passphrase {
+        /// Required when a passphrase is already set
+        current_passphrase: Option<String>,
+        new_passphrase: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyRotationResult {
+    pub reencrypted: usize,
+    pub key_location: KeyLocation,
+    /// Replaces the recovery code, which no longer works; shown only here
+    pub recovery_code: Option<String>,
+}
+
+/// Load the saved auto-lock settings, falling back to the defaults
+pub fn load_settings() -> AutoLockSettings {
+    load_from_disk(SETTINGS_FILENAME).unwrap_or_else(|e| {
+        warn!("Using default auto-lock settings: {:#}", e);
+        AutoLockSettings::default()
+    })
+}
+
+/// Load the saved biometric unlock settings, falling back to the defaults
+pub fn load_biometric_settings() -> BiometricSettings {
+    load_from_disk(BIOMETRIC_SETTINGS_FILENAME).unwrap_or_else(|e| {
+        warn!("Using default biometric settings: {:#}", e);
+        BiometricSettings::default()
+    })
+}
+
+/// Whether the key can be released again after locking
+fn can_lock(state: &AppState) -> bool {
+    state.key_provider.is_configured() || state.biometric.is_some()
+}
+
+/// Lock the database once it has been idle longer than the auto-lock timeout
+pub async fn run_auto_lock_loop(app: AppHandle, state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(AUTO_LOCK_CHECK_SECS));
+    loop {
+        interval.tick().await;
+
+        let Some(minutes) = load_settings().timeout_minutes else {
+            continue;
+        };
+        if !can_lock(&state) || state.key_provider.is_locked() {
+            continue;
+        }
+
+        let idle = state.last_activity.lock().await.elapsed();
+        if idle < Duration::from_secs(u64::from(minutes) * 60) {
+            continue;
+        }
+
+        // The biometric session is kept, so unlocking within its grace
+        // period doesn't prompt again
+        state.key_provider.forget_key();
+        info!("Database auto-locked after {} minutes idle", minutes);
+        if let Err(e) = app.emit(DATABASE_LOCKED_EVENT, ()) {
+            warn!("Failed to notify frontend of auto-lock: {}", e);
+        }
+    }
+}
+
+// ========== Security Commands ==========
+
+/// Whether a passphrase is set and the database is currently locked
+#[tauri::command]
+pub async fn get_lock_status(state: State<'_, Arc<AppState>>) -> Result<LockStatus, CommandError> {
+    let recovery = state
+        .db
+        .run(|storage| storage.key_recovery())
+        .await
+        .map_err(|e| {
+            error!("Failed to read recovery code: {:#}", e);
+            CommandError::with_context(e, "Failed to read lock status")
+        })?;
+
+    let passphrase_enabled = state.key_provider.is_configured();
+    let locked = state.key_provider.is_locked();
+    Ok(LockStatus {
+        passphrase_enabled,
+        biometric_enabled: state.biometric.is_some(),
+        biometric_available: BiometricKeyProvider::is_available(),
+        locked,
+        recovery_required: locked && !passphrase_enabled && state.biometric.is_none(),
+        recovery_code: RecoveryCodeStatus {
+            configured: recovery.is_some(),
+            created_at: recovery.map(|recovery| recovery.created_at),
+        },
+        auto_lock_minutes: load_settings().timeout_minutes,
+    })
+}
+
+/// Unlock the database with its passphrase
+#[tauri::command]
+pub async fn unlock_database(
+    state: State<'_, Arc<AppState>>,
+    passphrase: String,
+) -> Result<(), CommandError> {
+    // Argon2id derivation takes a second or so, so it runs off the runtime
+    let key_provider = state.key_provider.clone();
+    state
+        .db
+        .run(move |storage| {
+            unlock_storage(storage, &key_provider, &passphrase)?;
+            // Network settings couldn't be read while locked
+            apply_http_settings(storage);
+            Ok(())
+        })
+        .await
+        .map_err(|e| {
+            warn!("Failed to unlock database: {:#}", e);
+            CommandError::with_context(e, "Failed to unlock")
+        })?;
+
+    *state.last_activity.lock().await = Instant::now();
+    Ok(())
+}
+
+/// Unlock the database with Touch ID / Windows Hello
+#[tauri::command]
+pub async fn unlock_database_biometric(
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), CommandError> {
+    let biometric = state
+        .biometric
+        .clone()
+        .ok_or_else(|| CommandError::conflict("Biometric unlock is not enabled"))?;
+
+    // Waits for the Touch ID / Windows Hello prompt, so it mustn't hold up a runtime worker
+    let key = tokio::task::spawn_blocking(move || biometric.key_material())
+        .await
+        .map_err(|e| CommandError::with_context(e, "Biometric unlock stopped unexpectedly"))?
+        .map_err(|e| {
+            warn!("Biometric unlock failed: {:#}", e);
+            CommandError::with_context(e, "Failed to unlock")
+        })?;
+    state.key_provider.unlock_with_key(key);
+
+    let verified = state.db.run(|storage| storage.verify_key()).await;
+    if matches!(verified, Ok(false)) {
+        state.key_provider.forget_key();
+        warn!("Stored encryption key doesn't open the database");
+        return Err(CommandError::conflict(
+            "The stored key no longer opens the database; unlock with your recovery code",
+        ));
+    }
+
+    state
+        .db
+        .run(|storage| {
+            storage.initialize()?;
+            apply_http_settings(storage);
+            Ok(())
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to open database after unlock: {:#}", e);
+            CommandError::with_context(e, "Failed to open database")
+        })?;
+
+    *state.last_activity.lock().await = Instant::now();
+    info!("Database unlocked with biometrics");
+    Ok(())
+}
+
+/// Unlock the database with its recovery code, when the stored key or
+/// passphrase has been lost
+///
+/// The recovered key is saved to the Keychain or key file in place of the
+/// lost one, and any passphrase is removed; set a new one afterwards to
+/// keep the key off disk again.
+#[tauri::command]
+pub async fn recover_with_code(
+    state: State<'_, Arc<AppState>>,
+    code: String,
+) -> Result<KeyLocation, CommandError> {
+    let key_provider = state.key_provider.clone();
+    let key_location = state
+        .db
+        .run(move |storage| {
+            let key_location = recover_and_store_key(storage, &key_provider, &code)?;
+            apply_http_settings(storage);
+            Ok(key_location)
+        })
+        .await
+        .map_err(|e| {
+            warn!("Failed to recover database: {:#}", e);
+            CommandError::with_context(e, "Failed to recover")
+        })?;
+
+    *state.last_activity.lock().await = Instant::now();
+    info!("Database recovered; key now kept in {:?}", key_location);
+    Ok(key_location)
+}
+
+/// Create a new recovery code, replacing any earlier one
+///
+/// The code is only returned here, so the user must write it down. Rotating
+/// the encryption key or changing the passphrase replaces it with a new one.
+#[tauri::command]
+pub async fn generate_recovery_code(
+    state: State<'_, Arc<AppState>>,
+    current_passphrase: Option<String>,
+) -> Result<String, CommandError> {
+    let passphrase = if state.key_provider.is_configured() {
+        Some(
+            current_passphrase
+                .ok_or_else(|| CommandError::invalid_input("Enter the current passphrase"))?,
+        )
+    } else {
+        None
+    };
+
+    let key_provider = state.key_provider.clone();
+    state
+        .on_storage(move |storage| {
+            if let Some(passphrase) = passphrase {
+                key_provider
+                    .verify_passphrase(&passphrase)
+                    .map_err(|e| CommandError::with_context(e, "Failed to create recovery code"))?;
+            }
+            create_recovery_code(storage, &key_provider, KdfParams::default()).map_err(|e| {
+                error!("Failed to create recovery code: {:#}", e);
+                CommandError::with_context(e, "Failed to create recovery code")
+            })
+        })
+        .await
+}
+
+/// Lock the database now
+#[tauri::command]
+pub async fn lock_database(state: State<'_, Arc<AppState>>) -> Result<(), CommandError> {
+    if !can_lock(&state) {
+        return Err(CommandError::conflict(
+            "Set a passphrase or enable biometric unlock first",
+        ));
+    }
+
+    state.key_provider.forget_key();
+    if let Some(biometric) = &state.biometric {
+        biometric.end_session();
+    }
+    Ok(())
+}
+
+/// Set or change the database passphrase, re-encrypting all data
+///
+/// Once a passphrase is set, the stored key file (and Keychain item on
+/// macOS) is removed so the key only exists while unlocked. A recovery code,
+/// if one was set up, is replaced by the one returned.
+#[tauri::command]
+pub async fn set_database_passphrase(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    current_passphrase: Option<String>,
+    new_passphrase: String,
+) -> Result<KeyRotationResult, CommandError> {
+    let key_provider = state.key_provider.clone();
+    let rotation = state
+        .db
+        .run(move |storage| {
+            switch_to_passphrase(
+                &app,
+                storage,
+                &key_provider,
+                current_passphrase.as_deref(),
+                &new_passphrase,
+            )
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to set database passphrase: {:#}", e);
+            CommandError::with_context(e, "Failed to set passphrase")
+        })?;
+
+    *state.last_activity.lock().await = Instant::now();
+    Ok(KeyRotationResult {
+        reencrypted: rotation.reencrypted,
+        key_location: KeyLocation::Passphrase,
+        recovery_code: rotation.recovery_code,
+    })
+}
+
+/// Re-encrypt all data under a new key
+///
+/// Use this if the current key may have been exposed, or to move the key
+/// between the Keychain/key file and a passphrase. Progress is reported
+/// through `key-rotation-progress` events. A recovery code, if one was set
+/// up, is replaced by the one returned.
+#[tauri::command]
+pub async fn rotate_encryption_key(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    target: KeyRotationTarget,
+) -> Result<KeyRotationResult, CommandError> {
+    // Re-encrypting every value can take minutes on a large database
+    let key_provider = state.key_provider.clone();
+    let result = state
+        .db
+        .run(move |storage| match target {
+            KeyRotationTarget::StoredKey { current_passphrase } => {
+                rotate_to_stored_key(&app, storage, &key_provider, current_passphrase.as_deref())
+            }
+            KeyRotationTarget::Passphrase {
+                current_passphrase,
+                new_passphrase,
+            } => switch_to_passphrase(
+                &app,
+                storage,
+                &key_provider,
+                current_passphrase.as_deref(),
+                &new_passphrase,
+            )
+            .map(|rotation| KeyRotationResult {
+                reencrypted: rotation.reencrypted,
+                key_location: KeyLocation::Passphrase,
+                recovery_code: rotation.recovery_code,
+            }),
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to rotate encryption key: {:#}", e);
+            CommandError::with_context(e, "Failed to rotate key")
+        })?;
+
+    *state.last_activity.lock().await = Instant::now();
+    info!(
+        "Encryption key rotated; now kept in {:?}",
+        result.key_location
+    );
+    Ok(result)
+}
+
+/// Reset the auto-lock timer; called by the frontend on user input
+#[tauri::command]
+pub async fn record_activity(state: State<'_, Arc<AppState>>) -> Result<(), CommandError> {
+    *state.last_activity.lock().await = Instant::now();
+    Ok(())
+}
+
+/// Gets the auto-lock timeout
+#[tauri::command]
+pub async fn get_auto_lock_settings() -> Result<AutoLockSettings, CommandError> {
+    Ok(load_settings())
+}
+
+/// Saves the auto-lock timeout
+#[tauri::command]
+pub async fn update_auto_lock_settings(settings: AutoLockSettings) -> Result<(), CommandError> {
+    if settings.timeout_minutes == Some(0) {
+        return Err(CommandError::invalid_input(
+            "Auto-lock timeout must be at least 1 minute",
+        ));
+    }
+
+    save_to_disk(SETTINGS_FILENAME, &settings).map_err(|e| {
+        error!("Failed to save auto-lock settings: {:#}", e);
+        CommandError::with_context(e, "Failed to save settings")
+    })?;
+
+    info!("Auto-lock settings updated: {:?}", settings);
+    Ok(())
+}
+
+/// Gets the biometric unlock settings
+#[tauri::command]
+pub async fn get_biometric_settings() -> Result<BiometricSettings, CommandError> {
+    Ok(load_biometric_settings())
+}
+
+/// Saves the biometric unlock settings; they apply from the next launch
+#[tauri::command]
+pub async fn update_biometric_settings(
+    state: State<'_, Arc<AppState>>,
+    settings: BiometricSettings,
+) -> Result<(), CommandError> {
+    if settings.enabled {
+        if !BiometricKeyProvider::is_available() {
+            return Err(CommandError::invalid_input(
+                "Touch ID or Windows Hello is not available on this device",
+            ));
+        }
+        if state.key_provider.is_configured() {
+            return Err(CommandError::conflict(
+                "Biometric unlock can't be used while the database is protected by a passphrase",
+            ));
+        }
+    }
+
+    save_to_disk(BIOMETRIC_SETTINGS_FILENAME, &settings).map_err(|e| {
+        error!("Failed to save biometric settings: {:#}", e);
+        CommandError::with_context(e, "Failed to save settings")
+    })?;
+
+    info!("Biometric settings updated: {:?}", settings);
+    Ok(())
+}
+
+fn emit_progress(app: &AppHandle) -> impl FnMut(KeyRotationProgress) + '_ {
+    move |progress| {
+        if progress.processed % PROGRESS_EVENT_STEP == 0 || progress.processed == progress.total {
+            if let Err(e) = app.emit(KEY_ROTATION_PROGRESS_EVENT, progress) {
+                warn!("Failed to report key rotation progress: {}", e);
+            }
+        }
+    }
+}
+
+fn switch_to_passphrase(
+    app: &AppHandle,
+    storage: &StorageManager,
+    key_provider: &PassphraseKeyProvider,
+    current_passphrase: Option<&str>,
+    new_passphrase: &str,
+) -> Result<KeyRotation> {
+    let had_passphrase = key_provider.is_configured();
+
+    let rotation = change_passphrase(
+        storage,
+        key_provider,
+        current_passphrase,
+        new_passphrase,
+        KdfParams::default(),
+        emit_progress(app),
+    )?;
+
+    if !had_passphrase {
+        remove_stored_key();
+    }
+    Ok(rotation)
+}
+
+fn rotate_to_stored_key(
+    app: &AppHandle,
+    storage: &StorageManager,
+    key_provider: &PassphraseKeyProvider,
+    current_passphrase: Option<&str>,
+) -> Result<KeyRotationResult> {
+    if key_provider.is_configured() {
+        let passphrase = current_passphrase.context("Enter the current passphrase")?;
+        key_provider.verify_passphrase(passphrase)?;
+    }
+
+    let data_dir = app_data_dir()?;
+    let new_key = generate_key()?;
+    state::write_pending_key(&data_dir, &new_key)?;
+
+    let rotation = match rotate_storage_key(
+        storage,
+        key_provider,
+        new_key.clone(),
+        emit_progress(app),
+    ) {
+        Ok(rotation) => rotation,
+        Err(e) => {
+            let _ = state::remove_pending_key(&data_dir);
+            return Err(e);
+        }
+    };
+
+    // Until these succeed the pending key is kept, and recovered on next start
+    let key_location = state::store_key(&data_dir, &new_key)?;
+    if key_provider.is_configured() {
+        key_provider.remove_passphrase()?;
+    }
+    state::remove_pending_key(&data_dir)?;
+
+    Ok(KeyRotationResult {
+        reencrypted: rotation.reencrypted,
+        key_location,
+        recovery_code: rotation.recovery_code,
+    })
+}
+
+fn recover_and_store_key(
+    storage: &StorageManager,
+    key_provider: &PassphraseKeyProvider,
+    code: &str,
+) -> Result<KeyLocation> {
+    let key = recover_storage(storage, key_provider, code)?;
+    let key_location = state::store_key(&app_data_dir()?, &key)?;
+    if key_provider.is_configured() {
+        key_provider.remove_passphrase()?;
+    }
+    Ok(key_location)
+}
+
+/// Best-effort removal of the key that was in use before the passphrase
+pub(crate) fn remove_stored_key() {
+    if let Ok(data_dir) = app_data_dir() {
+        let key_file = data_dir.join(state::KEY_FILE_NAME);
+        if key_file.exists() {
+            match std::fs::remove_file(&key_file) {
+                Ok(()) => info!("Removed stored encryption key file"),
+                Err(e) => warn!("Failed to remove stored encryption key file: {}", e),
+            }
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    match peptrack_core::KeychainKeyProvider::new()
+        .and_then(|provider| provider.delete_from_keychain())
+    {
+        Ok(()) => info!("Removed encryption key from Keychain"),
+        Err(e) => warn!("Failed to remove encryption key from Keychain: {:#}", e),
+    }
+}
+
+fn save_to_disk<T: Serialize>(file_name: &str, settings: &T) -> Result<()> {
+    let data_dir = app_data_dir()?;
+    std::fs::create_dir_all(&data_dir)?;
+
+    let settings_file = data_dir.join(file_name);
+    let json = serde_json::to_string_pretty(settings)?;
+    std::fs::write(&settings_file, json)
+        .with_context(|| format!("Failed to save {}", file_name))?;
+
+    Ok(())
+}
+
+fn load_from_disk<T: DeserializeOwned + Default>(file_name: &str) -> Result<T> {
+    let data_dir = app_data_dir()?;
+    let settings_file = data_dir.join(file_name);
+
+    if !settings_file.exists() {
+        return Ok(T::default());
+    }
+    let json = std::fs::read_to_string(&settings_file)
+        .with_context(|| format!("Failed to read {}", file_name))?;
+    let settings: T = serde_json::from_str(&json)?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_lock_settings_default_to_never() {
+        let settings: AutoLockSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings.timeout_minutes, None);
+
+        let settings: AutoLockSettings = serde_json::from_str(r#"{"timeoutMinutes": 15}"#).unwrap();
+        assert_eq!(settings.timeout_minutes, Some(15));
+    }
+
+    #[test]
+    fn test_biometric_grace_period() {
+        let settings: BiometricSettings =
+            serde_json::from_str(r#"{"enabled": true, "gracePeriodMinutes": 5}"#).unwrap();
+        assert_eq!(settings.grace_period(), Some(Duration::from_secs(300)));
+        assert_eq!(BiometricSettings::default().grace_period(), None);
+    }
+
+    #[test]
+    fn test_rotation_target_deserialization() {
+        let target: KeyRotationTarget =
+            serde_json::from_str(r#"{"kind": "stored_key", "currentPassphrase": null}"#).unwrap();
+        assert!(matches!(
+            target,
+            KeyRotationTarget::StoredKey {
+                current_passphrase: None
+            }
+        ));
+
+        let target: KeyRotationTarget = serde_json::from_str(
+            r#"{"kind": "passphrase", "newPassphrase": "correct horse battery"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            target,
+            KeyRotationTarget::Passphrase { current_passphrase: None, ref new_passphrase }
+                if new_passphrase == "correct horse battery"
+        ));
+    }
+}
@@ -0,0 +1,83 @@
+//! Loading and saving typed settings from commands
+//!
+//! Values live in the core `settings` table (see [`peptrack_core::settings`]).
+//! Every save emits [`SETTINGS_CHANGED_EVENT`] with the setting's key, so
+//! open views can reload it.
+
+use peptrack_core::Setting;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tracing::{error, warn};
+
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Emitted with a [`SettingsChanged`] after a setting is saved
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsChanged {
+    pub key: String,
+}
+
+/// Saved value of `T`, or its default when it was never saved
+pub(crate) async fn load_setting<T>(state: &AppState) -> Result<T, CommandError>
+where
+    T: Setting + Default + Send + 'static,
+{
+    state
+        .db
+        .run(|storage| storage.load_setting_or_default())
+        .await
+        .map_err(|e| {
+            error!("Failed to load {} setting: {:#}", T::KEY, e);
+            CommandError::with_context(e, "Failed to load settings")
+        })
+}
+
+/// Saved value of `T`, falling back to its default if it can't be read
+pub(crate) async fn load_setting_or_default<T>(state: &AppState) -> T
+where
+    T: Setting + Default + Send + 'static,
+{
+    state
+        .db
+        .run(|storage| storage.load_setting_or_default())
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Using default {} setting: {:#}", T::KEY, e);
+            T::default()
+        })
+}
+
+/// Validate and save `value`, then tell the frontend it changed
+pub(crate) async fn save_setting<T>(
+    app: &AppHandle,
+    state: &AppState,
+    value: &T,
+) -> Result<(), CommandError>
+where
+    T: Setting + Clone + Send + 'static,
+{
+    value.validate().map_err(CommandError::invalid_input)?;
+    let value = value.clone();
+    state
+        .db
+        .run(move |storage| storage.save_setting(&value))
+        .await
+        .map_err(|e| {
+            error!("Failed to save {} setting: {:#}", T::KEY, e);
+            CommandError::with_context(e, "Failed to save settings")
+        })?;
+    notify_setting_changed(app, T::KEY);
+    Ok(())
+}
+
+/// Tell the frontend the setting `key` changed
+pub(crate) fn notify_setting_changed(app: &AppHandle, key: &str) {
+    let payload = SettingsChanged { key: key.to_string() };
+    if let Err(e) = app.emit(SETTINGS_CHANGED_EVENT, payload) {
+        warn!("Failed to notify frontend of {} change: {}", key, e);
+    }
+}
@@ -1,9 +1,13 @@
 use anyhow::Result;
 use peptrack_core::models::SideEffect;
+use peptrack_core::UndoAction;
 use serde::Deserialize;
 use tauri::State;
 use time::OffsetDateTime;
 
+use crate::commands::dates::parse_datetime;
+use crate::commands::undo::{records, snapshot};
+use crate::error::CommandError;
 use crate::state::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -24,10 +28,9 @@ pub struct SideEffectPayload {
 pub async fn log_side_effect(
     state: State<'_, std::sync::Arc<AppState>>,
     payload: SideEffectPayload,
-) -> Result<SideEffect, String> {
+) -> Result<SideEffect, CommandError> {
     // Parse the date string
-    let date = OffsetDateTime::parse(&payload.date, &time::format_description::well_known::Rfc3339)
-        .map_err(|e| format!("Invalid date format: {}", e))?;
+    let date = parse_datetime(&payload.date)?;
 
     let mut effect = SideEffect::new(date, &payload.severity, &payload.symptom);
     effect.protocol_id = payload.protocol_id;
@@ -38,22 +41,22 @@ pub async fn log_side_effect(
     effect.updated_at = OffsetDateTime::now_utc();
 
     state
-        .storage
-        .upsert_side_effect(&effect)
-        .map_err(|err| err.to_string())?;
-
-    Ok(effect)
+        .db
+        .run(move |storage| storage.upsert_side_effect(&effect).map(|_| effect))
+        .await
+        .map_err(CommandError::from)
 }
 
 /// List all side effects
 #[tauri::command]
 pub async fn list_side_effects(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<Vec<SideEffect>, String> {
+) -> Result<Vec<SideEffect>, CommandError> {
     state
-        .storage
-        .list_side_effects()
-        .map_err(|err| err.to_string())
+        .db
+        .run(|storage| storage.list_side_effects())
+        .await
+        .map_err(CommandError::from)
 }
 
 /// Get a specific side effect by ID
@@ -61,11 +64,12 @@ pub async fn list_side_effects(
 pub async fn get_side_effect(
     state: State<'_, std::sync::Arc<AppState>>,
     effect_id: String,
-) -> Result<Option<SideEffect>, String> {
+) -> Result<Option<SideEffect>, CommandError> {
     state
-        .storage
-        .get_side_effect(&effect_id)
-        .map_err(|err| err.to_string())
+        .db
+        .run(move |storage| storage.get_side_effect(&effect_id))
+        .await
+        .map_err(CommandError::from)
 }
 
 /// List side effects for a specific protocol
@@ -73,11 +77,12 @@ pub async fn get_side_effect(
 pub async fn list_side_effects_by_protocol(
     state: State<'_, std::sync::Arc<AppState>>,
     protocol_id: String,
-) -> Result<Vec<SideEffect>, String> {
+) -> Result<Vec<SideEffect>, CommandError> {
     state
-        .storage
-        .list_side_effects_by_protocol(&protocol_id)
-        .map_err(|err| err.to_string())
+        .db
+        .run(move |storage| storage.list_side_effects_by_protocol(&protocol_id))
+        .await
+        .map_err(CommandError::from)
 }
 
 /// Update an existing side effect
@@ -86,13 +91,14 @@ pub async fn update_side_effect(
     state: State<'_, std::sync::Arc<AppState>>,
     effect_id: String,
     payload: SideEffectPayload,
-) -> Result<SideEffect, String> {
+) -> Result<SideEffect, CommandError> {
     // Get existing effect
     let mut effect = state
-        .storage
-        .get_side_effect(&effect_id)
-        .map_err(|err| err.to_string())?
-        .ok_or_else(|| "Side effect not found".to_string())?;
+        .db
+        .run(move |storage| storage.get_side_effect(&effect_id))
+        .await
+        .map_err(CommandError::from)?
+        .ok_or_else(|| CommandError::not_found("Side effect not found"))?;
 
     // Update fields
     if let Ok(date) =
@@ -112,11 +118,10 @@ pub async fn update_side_effect(
     effect.updated_at = OffsetDateTime::now_utc();
 
     state
-        .storage
-        .upsert_side_effect(&effect)
-        .map_err(|err| err.to_string())?;
-
-    Ok(effect)
+        .db
+        .run(move |storage| storage.upsert_side_effect(&effect).map(|_| effect))
+        .await
+        .map_err(CommandError::from)
 }
 
 /// Toggle the resolved status of a side effect
@@ -125,11 +130,12 @@ pub async fn toggle_side_effect_resolved(
     state: State<'_, std::sync::Arc<AppState>>,
     effect_id: String,
     resolved: bool,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     state
-        .storage
-        .update_side_effect_resolved(&effect_id, resolved)
-        .map_err(|err| err.to_string())
+        .db
+        .run(move |storage| storage.update_side_effect_resolved(&effect_id, resolved))
+        .await
+        .map_err(CommandError::from)
 }
 
 /// Delete a specific side effect
@@ -137,11 +143,18 @@ pub async fn toggle_side_effect_resolved(
 pub async fn delete_side_effect(
     state: State<'_, std::sync::Arc<AppState>>,
     effect_id: String,
-) -> Result<(), String> {
-    state
-        .storage
-        .delete_side_effect(&effect_id)
-        .map_err(|err| err.to_string())
+) -> Result<(), CommandError> {
+    let before = state
+        .db
+        .run(move |storage| {
+            let before =
+                snapshot(std::slice::from_ref(&effect_id), |id| storage.get_side_effect(id));
+            storage.delete_side_effect(&effect_id).map(|_| before)
+        })
+        .await
+        .map_err(CommandError::from)?;
+    state.undo.push("Delete 1 side effect", UndoAction::RestoreSideEffects(before));
+    Ok(())
 }
 
 /// Bulk delete multiple side effects
@@ -149,9 +162,18 @@ pub async fn delete_side_effect(
 pub async fn bulk_delete_side_effects(
     state: State<'_, std::sync::Arc<AppState>>,
     effect_ids: Vec<String>,
-) -> Result<usize, String> {
-    state
-        .storage
-        .bulk_delete_side_effects(&effect_ids)
-        .map_err(|err| err.to_string())
+) -> Result<usize, CommandError> {
+    let (deleted, before) = state
+        .db
+        .run(move |storage| {
+            let before = snapshot(&effect_ids, |id| storage.get_side_effect(id));
+            Ok((storage.bulk_delete_side_effects(&effect_ids)?, before))
+        })
+        .await
+        .map_err(CommandError::from)?;
+    state.undo.push(
+        format!("Delete {}", records(deleted, "side effect")),
+        UndoAction::RestoreSideEffects(before),
+    );
+    Ok(deleted)
 }
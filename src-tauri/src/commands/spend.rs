@@ -0,0 +1,707 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use peptrack_core::models::PriceHistory;
+use peptrack_core::{
+    CurrencyConverter, DoseLog, InventoryItem, Order, OrderStatus, PeptideProtocol, Redactor,
+    StorageManager, Supplier,
+};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime};
+use tracing::{error, info};
+
+use crate::commands::currency::resolve_currency;
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Days of dose history used to project monthly cost
+const PROJECTION_WINDOW_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlySpend {
+    /// Month in YYYY-MM format
+    pub month: String,
+    /// Money spent on vials purchased in this month
+    pub purchase_spend: f32,
+    /// Value of the peptide actually dosed in this month
+    pub consumption_cost: f32,
+    pub mg_used: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolSpend {
+    pub protocol_id: String,
+    pub protocol_name: String,
+    pub peptide_name: String,
+    pub purchase_spend: f32,
+    pub mg_purchased: f32,
+    pub mg_used: f32,
+    pub consumption_cost: f32,
+    /// Purchase spend divided by mg actually dosed
+    pub cost_per_mg_used: Option<f32>,
+    pub projected_monthly_cost: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderSpend {
+    pub order_id: String,
+    pub supplier_id: String,
+    pub supplier_name: String,
+    pub order_date: String,
+    pub status: OrderStatus,
+    /// Items plus shipping, in the report currency
+    pub total: f32,
+    pub original_total: f32,
+    pub original_currency: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendReport {
+    pub currency: String,
+    pub generated_at: String,
+    pub months: Vec<MonthlySpend>,
+    pub protocols: Vec<ProtocolSpend>,
+    /// Orders placed in the period, newest first (cancelled orders excluded)
+    pub orders: Vec<OrderSpend>,
+    /// Shipping paid on orders, included in `total_spend`
+    pub shipping_spend: f32,
+    pub total_spend: f32,
+    pub total_mg_used: f32,
+    pub cost_per_mg_used: Option<f32>,
+    pub projected_monthly_cost: f32,
+    /// Currencies that were skipped because no exchange rate is configured
+    pub missing_rates: Vec<String>,
+}
+
+/// Which table of the spend report to export
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SpendCsvKind {
+    #[default]
+    Monthly,
+    Protocols,
+    Orders,
+}
+
+/// Everything the report is computed from, loaded up front so the
+/// calculation itself stays pure
+struct SpendInputs<'a> {
+    protocols: &'a [PeptideProtocol],
+    inventory: &'a [InventoryItem],
+    doses: &'a [DoseLog],
+    orders: &'a [Order],
+    suppliers: &'a [Supplier],
+    /// Latest supplier price for inventory items without a recorded cost, by item ID
+    item_prices: &'a HashMap<String, PriceHistory>,
+    /// Latest price across suppliers, by lowercase peptide name
+    peptide_prices: &'a HashMap<String, PriceHistory>,
+}
+
+fn month_key(date: OffsetDateTime) -> String {
+    format!("{:04}-{:02}", date.year(), date.month() as u8)
+}
+
+fn month_entry(months: &mut BTreeMap<String, MonthlySpend>, date: OffsetDateTime) -> &mut MonthlySpend {
+    months
+        .entry(month_key(date))
+        .or_insert_with(|| MonthlySpend {
+            month: month_key(date),
+            purchase_spend: 0.0,
+            consumption_cost: 0.0,
+            mg_used: 0.0,
+        })
+}
+
+fn status_label(status: &OrderStatus) -> &'static str {
+    match status {
+        OrderStatus::Pending => "pending",
+        OrderStatus::Shipped => "shipped",
+        OrderStatus::Delivered => "delivered",
+        OrderStatus::Cancelled => "cancelled",
+    }
+}
+
+#[derive(Default)]
+struct ProtocolTotals {
+    purchase_spend: f32,
+    mg_purchased: f32,
+    mg_used: f32,
+    consumption_cost: f32,
+    recent_mg_used: f32,
+}
+
+fn build_spend_report(
+    inputs: &SpendInputs<'_>,
+    converter: &CurrencyConverter,
+    currency: &str,
+    since: OffsetDateTime,
+    now: OffsetDateTime,
+) -> SpendReport {
+    let mut missing_rates: Vec<String> = Vec::new();
+    let mut convert = |amount: f32, from: &str| -> Option<f32> {
+        match converter.convert(amount as f64, from, currency) {
+            Ok(value) => Some(value as f32),
+            Err(_) => {
+                if !missing_rates.iter().any(|c| c == from) {
+                    missing_rates.push(from.to_string());
+                }
+                None
+            }
+        }
+    };
+
+    let mut months: BTreeMap<String, MonthlySpend> = BTreeMap::new();
+    let mut totals: HashMap<&str, ProtocolTotals> = HashMap::new();
+    // Weighted cost per mg for each protocol, in the report currency
+    let mut cost_basis: HashMap<&str, (f32, f32)> = HashMap::new();
+    // Vials received from an order are counted through the order instead
+    let ordered_items: HashSet<&str> = inputs
+        .orders
+        .iter()
+        .flat_map(|order| order.inventory_item_ids.iter().map(String::as_str))
+        .collect();
+
+    for item in inputs.inventory {
+        let unit_cost = match item.cost_per_mg {
+            Some(cost) => convert(cost, &item.currency),
+            None => inputs
+                .item_prices
+                .get(&item.id)
+                .and_then(|price| convert(price.cost_per_mg, &price.currency)),
+        };
+        let Some(unit_cost) = unit_cost else {
+            continue;
+        };
+
+        let quantity = item.quantity_mg.unwrap_or(0.0);
+        let basis = cost_basis.entry(item.protocol_id.as_str()).or_default();
+        basis.0 += unit_cost * quantity.max(1.0);
+        basis.1 += quantity.max(1.0);
+
+        let purchased_at = item.purchase_date.unwrap_or(item.created_at);
+        if purchased_at < since || quantity <= 0.0 || ordered_items.contains(item.id.as_str()) {
+            continue;
+        }
+
+        let spend = unit_cost * quantity;
+        let entry = totals.entry(item.protocol_id.as_str()).or_default();
+        entry.purchase_spend += spend;
+        entry.mg_purchased += quantity;
+
+        month_entry(&mut months, purchased_at).purchase_spend += spend;
+    }
+
+    let supplier_names: HashMap<&str, &str> = inputs
+        .suppliers
+        .iter()
+        .map(|s| (s.id.as_str(), s.name.as_str()))
+        .collect();
+
+    let mut orders: Vec<OrderSpend> = Vec::new();
+    let mut shipping_spend = 0.0;
+    for order in inputs.orders {
+        if order.status == OrderStatus::Cancelled || order.order_date < since {
+            continue;
+        }
+        let Some(total) = convert(order.total(), &order.currency) else {
+            continue;
+        };
+
+        for line in &order.items {
+            let spend = convert(line.unit_price * line.quantity as f32, &order.currency).unwrap_or(0.0);
+            let entry = totals.entry(line.protocol_id.as_str()).or_default();
+            entry.purchase_spend += spend;
+            entry.mg_purchased += line.vial_size_mg.unwrap_or(0.0) * line.quantity as f32;
+        }
+        shipping_spend += convert(order.shipping_cost.unwrap_or(0.0), &order.currency).unwrap_or(0.0);
+        month_entry(&mut months, order.order_date).purchase_spend += total;
+
+        orders.push(OrderSpend {
+            order_id: order.id.clone(),
+            supplier_id: order.supplier_id.clone(),
+            supplier_name: supplier_names
+                .get(order.supplier_id.as_str())
+                .copied()
+                .unwrap_or("Unknown")
+                .to_string(),
+            order_date: order.order_date.to_string(),
+            status: order.status.clone(),
+            total,
+            original_total: order.total(),
+            original_currency: order.currency.clone(),
+        });
+    }
+    orders.sort_by(|a, b| b.order_date.cmp(&a.order_date));
+
+    let protocols_by_id: HashMap<&str, &PeptideProtocol> = inputs
+        .protocols
+        .iter()
+        .map(|p| (p.id.as_str(), p))
+        .collect();
+
+    let mut unit_cost_for = |protocol_id: &str| -> Option<f32> {
+        if let Some((weighted, mg)) = cost_basis.get(protocol_id) {
+            if *mg > 0.0 {
+                return Some(weighted / mg);
+            }
+        }
+        let protocol = protocols_by_id.get(protocol_id)?;
+        let price = inputs
+            .peptide_prices
+            .get(&protocol.peptide_name.to_lowercase())?;
+        convert(price.cost_per_mg, &price.currency)
+    };
+
+    let recent_cutoff = now - Duration::days(PROJECTION_WINDOW_DAYS);
+    for dose in inputs.doses {
+        let unit_cost = unit_cost_for(&dose.protocol_id);
+        let entry = totals.entry(dose.protocol_id.as_str()).or_default();
+
+        if dose.logged_at >= recent_cutoff {
+            entry.recent_mg_used += dose.amount_mg;
+        }
+        if dose.logged_at < since {
+            continue;
+        }
+
+        let cost = unit_cost.unwrap_or(0.0) * dose.amount_mg;
+        entry.mg_used += dose.amount_mg;
+        entry.consumption_cost += cost;
+
+        let month = month_entry(&mut months, dose.logged_at);
+        month.consumption_cost += cost;
+        month.mg_used += dose.amount_mg;
+    }
+
+    let mut protocols: Vec<ProtocolSpend> = Vec::new();
+    for (protocol_id, total) in &totals {
+        let Some(protocol) = protocols_by_id.get(protocol_id) else {
+            continue;
+        };
+
+        let projected_mg = total.recent_mg_used / PROJECTION_WINDOW_DAYS as f32 * 30.0;
+        let projected_monthly_cost = unit_cost_for(protocol_id).unwrap_or(0.0) * projected_mg;
+
+        protocols.push(ProtocolSpend {
+            protocol_id: protocol.id.clone(),
+            protocol_name: protocol.name.clone(),
+            peptide_name: protocol.peptide_name.clone(),
+            purchase_spend: total.purchase_spend,
+            mg_purchased: total.mg_purchased,
+            mg_used: total.mg_used,
+            consumption_cost: total.consumption_cost,
+            cost_per_mg_used: (total.mg_used > 0.0 && total.purchase_spend > 0.0)
+                .then(|| total.purchase_spend / total.mg_used),
+            projected_monthly_cost,
+        });
+    }
+    protocols.sort_by(|a, b| b.purchase_spend.total_cmp(&a.purchase_spend));
+
+    let total_spend: f32 = protocols.iter().map(|p| p.purchase_spend).sum::<f32>() + shipping_spend;
+    let total_mg_used: f32 = protocols.iter().map(|p| p.mg_used).sum();
+
+    SpendReport {
+        currency: currency.to_string(),
+        generated_at: now.format(&Rfc3339).unwrap_or_else(|_| now.to_string()),
+        months: months.into_values().collect(),
+        projected_monthly_cost: protocols.iter().map(|p| p.projected_monthly_cost).sum(),
+        cost_per_mg_used: (total_mg_used > 0.0 && total_spend > 0.0)
+            .then(|| total_spend / total_mg_used),
+        protocols,
+        orders,
+        shipping_spend,
+        total_spend,
+        total_mg_used,
+        missing_rates,
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn spend_report_csv(report: &SpendReport, kind: SpendCsvKind) -> String {
+    let mut csv = String::new();
+
+    match kind {
+        SpendCsvKind::Monthly => {
+            csv.push_str(&format!(
+                "month,purchase_spend_{0},consumption_cost_{0},mg_used\n",
+                report.currency.to_lowercase()
+            ));
+            for month in &report.months {
+                csv.push_str(&format!(
+                    "{},{:.2},{:.2},{:.3}\n",
+                    month.month, month.purchase_spend, month.consumption_cost, month.mg_used
+                ));
+            }
+        }
+        SpendCsvKind::Protocols => {
+            csv.push_str(&format!(
+                "protocol,peptide,purchase_spend_{0},mg_purchased,mg_used,consumption_cost_{0},cost_per_mg_used,projected_monthly_cost_{0}\n",
+                report.currency.to_lowercase()
+            ));
+            for protocol in &report.protocols {
+                csv.push_str(&format!(
+                    "{},{},{:.2},{:.3},{:.3},{:.2},{},{:.2}\n",
+                    csv_field(&protocol.protocol_name),
+                    csv_field(&protocol.peptide_name),
+                    protocol.purchase_spend,
+                    protocol.mg_purchased,
+                    protocol.mg_used,
+                    protocol.consumption_cost,
+                    protocol
+                        .cost_per_mg_used
+                        .map(|cost| format!("{:.4}", cost))
+                        .unwrap_or_default(),
+                    protocol.projected_monthly_cost,
+                ));
+            }
+        }
+        SpendCsvKind::Orders => {
+            csv.push_str(&format!(
+                "order_date,supplier,status,total_{},original_total,original_currency\n",
+                report.currency.to_lowercase()
+            ));
+            for order in &report.orders {
+                csv.push_str(&format!(
+                    "{},{},{},{:.2},{:.2},{}\n",
+                    csv_field(&order.order_date),
+                    csv_field(&order.supplier_name),
+                    status_label(&order.status),
+                    order.total,
+                    order.original_total,
+                    order.original_currency,
+                ));
+            }
+        }
+    }
+
+    csv
+}
+
+pub(crate) fn load_spend_report(
+    storage: &StorageManager,
+    months: Option<u32>,
+    currency: Option<String>,
+) -> Result<SpendReport, CommandError> {
+    let currency = resolve_currency(currency, None)?;
+    let now = OffsetDateTime::now_utc();
+    let since = now - Duration::days(months.unwrap_or(12).max(1) as i64 * 31);
+
+    let load_error = |what: &str, e: anyhow::Error| {
+        error!("Failed to load {} for spend report: {:#}", what, e);
+        CommandError::with_context(e, format!("Failed to load {}", what))
+    };
+
+    let protocols = storage.list_protocols().map_err(|e| load_error("protocols", e))?;
+    let inventory = storage.list_inventory().map_err(|e| load_error("inventory", e))?;
+    let doses = storage.list_dose_logs().map_err(|e| load_error("dose logs", e))?;
+    let suppliers = storage.list_suppliers().map_err(|e| load_error("suppliers", e))?;
+    let rates = storage.list_exchange_rates().map_err(|e| load_error("exchange rates", e))?;
+    let orders = storage.list_orders().map_err(|e| load_error("orders", e))?;
+
+    let protocol_peptides: HashMap<&str, &str> = protocols
+        .iter()
+        .map(|p| (p.id.as_str(), p.peptide_name.as_str()))
+        .collect();
+
+    // Fall back to scraped or manually recorded prices for vials without a cost
+    let mut item_prices = HashMap::new();
+    for item in inventory.iter().filter(|item| item.cost_per_mg.is_none()) {
+        let (Some(supplier_id), Some(peptide)) =
+            (item.supplier_id.as_deref(), protocol_peptides.get(item.protocol_id.as_str()))
+        else {
+            continue;
+        };
+        if let Some(price) = storage
+            .get_latest_price(supplier_id, peptide)
+            .map_err(|e| load_error("price history", e))?
+        {
+            item_prices.insert(item.id.clone(), price);
+        }
+    }
+
+    let mut peptide_prices: HashMap<String, PriceHistory> = HashMap::new();
+    for peptide in protocols.iter().map(|p| p.peptide_name.as_str()) {
+        for supplier in &suppliers {
+            if let Some(price) = storage
+                .get_latest_price(&supplier.id, peptide)
+                .map_err(|e| load_error("price history", e))?
+            {
+                let key = peptide.to_lowercase();
+                let newer = peptide_prices
+                    .get(&key)
+                    .is_none_or(|existing| price.recorded_at > existing.recorded_at);
+                if newer {
+                    peptide_prices.insert(key, price);
+                }
+            }
+        }
+    }
+
+    let inputs = SpendInputs {
+        protocols: &protocols,
+        inventory: &inventory,
+        doses: &doses,
+        orders: &orders,
+        suppliers: &suppliers,
+        item_prices: &item_prices,
+        peptide_prices: &peptide_prices,
+    };
+
+    Ok(build_spend_report(
+        &inputs,
+        &CurrencyConverter::new(&rates),
+        &currency,
+        since,
+        now,
+    ))
+}
+
+/// [`load_spend_report`] off the async runtime
+async fn spend_report(
+    state: &AppState,
+    months: Option<u32>,
+    currency: Option<String>,
+) -> Result<SpendReport, CommandError> {
+    state
+        .db
+        .run(move |storage| Ok(load_spend_report(storage, months, currency)))
+        .await
+        .map_err(CommandError::from)?
+}
+
+// ========== Spend Report Commands ==========
+
+/// Builds a spend report covering the last `months` months (default 12)
+#[tauri::command]
+pub async fn get_spend_report(
+    state: State<'_, std::sync::Arc<AppState>>,
+    months: Option<u32>,
+    currency: Option<String>,
+) -> Result<SpendReport, CommandError> {
+    info!("Building spend report ({} months)", months.unwrap_or(12));
+    spend_report(&state, months, currency).await
+}
+
+/// Replace protocol and supplier names with pseudonyms for sharing
+fn anonymize_spend_report(report: &mut SpendReport) {
+    let redactor = Redactor::new();
+    for protocol in &mut report.protocols {
+        protocol.protocol_name = redactor.pseudonym(&protocol.protocol_name);
+    }
+    for order in &mut report.orders {
+        order.supplier_name = redactor.pseudonym(&order.supplier_name);
+    }
+}
+
+/// Exports the spend report as CSV text that the user can save
+///
+/// With `anonymize`, protocol and supplier names are replaced with pseudonyms.
+#[tauri::command]
+pub async fn export_spend_report_csv(
+    state: State<'_, std::sync::Arc<AppState>>,
+    months: Option<u32>,
+    currency: Option<String>,
+    kind: Option<SpendCsvKind>,
+    anonymize: Option<bool>,
+) -> Result<String, CommandError> {
+    let mut report = spend_report(&state, months, currency).await?;
+    if anonymize.unwrap_or(false) {
+        anonymize_spend_report(&mut report);
+    }
+    Ok(spend_report_csv(&report, kind.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn days_ago(days: i64) -> OffsetDateTime {
+        OffsetDateTime::now_utc() - Duration::days(days)
+    }
+
+    #[test]
+    fn test_build_spend_report_joins_purchases_and_doses() {
+        let protocol = PeptideProtocol::new("Healing", "BPC-157");
+
+        let mut vial = InventoryItem::new(protocol.id.as_str());
+        vial.cost_per_mg = Some(5.0);
+        vial.quantity_mg = Some(10.0);
+        vial.purchase_date = Some(days_ago(5));
+
+        let mut dose = DoseLog::new(protocol.id.as_str(), "abdomen", 0.5);
+        dose.logged_at = days_ago(1);
+        let mut second = DoseLog::new(protocol.id.as_str(), "abdomen", 0.5);
+        second.logged_at = days_ago(2);
+
+        let protocols = vec![protocol];
+        let inventory = vec![vial];
+        let doses = vec![dose, second];
+        let empty = HashMap::new();
+        let inputs = SpendInputs {
+            protocols: &protocols,
+            inventory: &inventory,
+            doses: &doses,
+            orders: &[],
+            suppliers: &[],
+            item_prices: &empty,
+            peptide_prices: &empty,
+        };
+
+        let now = OffsetDateTime::now_utc();
+        let report = build_spend_report(
+            &inputs,
+            &CurrencyConverter::default(),
+            "USD",
+            now - Duration::days(365),
+            now,
+        );
+
+        assert_eq!(report.total_spend, 50.0);
+        assert_eq!(report.total_mg_used, 1.0);
+        assert_eq!(report.cost_per_mg_used, Some(50.0));
+        assert_eq!(report.protocols[0].consumption_cost, 5.0);
+        assert!((report.protocols[0].projected_monthly_cost - 5.0).abs() < 0.001);
+        assert!(report.missing_rates.is_empty());
+    }
+
+    #[test]
+    fn test_build_spend_report_reports_missing_rates() {
+        let protocol = PeptideProtocol::new("Healing", "BPC-157");
+        let mut vial = InventoryItem::new(protocol.id.as_str());
+        vial.cost_per_mg = Some(5.0);
+        vial.quantity_mg = Some(10.0);
+        vial.currency = "EUR".to_string();
+
+        let protocols = vec![protocol];
+        let inventory = vec![vial];
+        let empty = HashMap::new();
+        let inputs = SpendInputs {
+            protocols: &protocols,
+            inventory: &inventory,
+            doses: &[],
+            orders: &[],
+            suppliers: &[],
+            item_prices: &empty,
+            peptide_prices: &empty,
+        };
+
+        let now = OffsetDateTime::now_utc();
+        let report = build_spend_report(
+            &inputs,
+            &CurrencyConverter::default(),
+            "USD",
+            now - Duration::days(365),
+            now,
+        );
+
+        assert_eq!(report.total_spend, 0.0);
+        assert_eq!(report.missing_rates, vec!["EUR".to_string()]);
+    }
+
+    #[test]
+    fn test_build_spend_report_counts_orders_once() {
+        let protocol = PeptideProtocol::new("Healing", "BPC-157");
+        let supplier = Supplier::new("Acme Peptides");
+
+        let mut order = Order::new(
+            supplier.id.as_str(),
+            vec![peptrack_core::OrderItem {
+                protocol_id: protocol.id.clone(),
+                quantity: 2,
+                vial_size_mg: Some(5.0),
+                unit_price: 40.0,
+            }],
+        );
+        order.shipping_cost = Some(10.0);
+        order.order_date = days_ago(3);
+        order.status = OrderStatus::Delivered;
+
+        // The vial received from the order must not be counted a second time
+        let mut received = InventoryItem::new(protocol.id.as_str());
+        received.cost_per_mg = Some(8.0);
+        received.quantity_mg = Some(5.0);
+        received.purchase_date = Some(days_ago(3));
+        order.inventory_item_ids = vec![received.id.clone()];
+
+        let mut cancelled = Order::new(supplier.id.as_str(), order.items.clone());
+        cancelled.status = OrderStatus::Cancelled;
+
+        let protocols = vec![protocol];
+        let inventory = vec![received];
+        let orders = vec![order, cancelled];
+        let suppliers = vec![supplier];
+        let empty = HashMap::new();
+        let inputs = SpendInputs {
+            protocols: &protocols,
+            inventory: &inventory,
+            doses: &[],
+            orders: &orders,
+            suppliers: &suppliers,
+            item_prices: &empty,
+            peptide_prices: &empty,
+        };
+
+        let now = OffsetDateTime::now_utc();
+        let report = build_spend_report(
+            &inputs,
+            &CurrencyConverter::default(),
+            "USD",
+            now - Duration::days(365),
+            now,
+        );
+
+        assert_eq!(report.total_spend, 90.0);
+        assert_eq!(report.shipping_spend, 10.0);
+        assert_eq!(report.protocols[0].purchase_spend, 80.0);
+        assert_eq!(report.protocols[0].mg_purchased, 10.0);
+        assert_eq!(report.orders.len(), 1);
+        assert_eq!(report.orders[0].supplier_name, "Acme Peptides");
+
+        let csv = spend_report_csv(&report, SpendCsvKind::Orders);
+        assert!(csv.lines().nth(1).unwrap().ends_with(",Acme Peptides,delivered,90.00,90.00,USD"));
+    }
+
+    #[test]
+    fn test_spend_report_csv_escapes_fields() {
+        let report = SpendReport {
+            currency: "USD".to_string(),
+            generated_at: String::new(),
+            months: vec![],
+            protocols: vec![ProtocolSpend {
+                protocol_id: "p1".to_string(),
+                protocol_name: "AM, fasted".to_string(),
+                peptide_name: "BPC-157".to_string(),
+                purchase_spend: 50.0,
+                mg_purchased: 10.0,
+                mg_used: 1.0,
+                consumption_cost: 5.0,
+                cost_per_mg_used: Some(50.0),
+                projected_monthly_cost: 5.0,
+            }],
+            orders: vec![],
+            shipping_spend: 0.0,
+            total_spend: 50.0,
+            total_mg_used: 1.0,
+            cost_per_mg_used: Some(50.0),
+            projected_monthly_cost: 5.0,
+            missing_rates: vec![],
+        };
+
+        let csv = spend_report_csv(&report, SpendCsvKind::Protocols);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("protocol,peptide,purchase_spend_usd"));
+        assert_eq!(lines[1], "\"AM, fasted\",BPC-157,50.00,10.000,1.000,5.00,50.0000,5.00");
+    }
+}
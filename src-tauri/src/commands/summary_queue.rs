@@ -0,0 +1,462 @@
+//! Background queue for summarizing many cached papers
+//!
+//! Entries are summarized one at a time, since each summary runs a local AI
+//! CLI. A failed summary is put back at the end of the queue and tried up to
+//! [`MAX_ATTEMPTS`] times; entries without an abstract fail straight away.
+//! Finished summaries are saved to the summary history. The queue lives in
+//! memory, so entries still waiting when the app quits must be queued again.
+
+use std::sync::Arc;
+
+use peptrack_core::models::SummaryHistory;
+use peptrack_local_ai::{LocalAiClient, SummarizeRequest, SummaryFormat};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::sync::{Mutex, Notify};
+use tracing::{error, info, warn};
+
+use crate::commands::ai::usage_record;
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// Emitted with a [`SummaryQueueItem`] whenever an item starts or finishes
+pub const SUMMARY_QUEUE_PROGRESS_EVENT: &str = "summary-queue-progress";
+
+/// Tries per entry before it's marked failed
+const MAX_ATTEMPTS: u32 = 3;
+/// Pause before the next item after a failure that will be retried
+const RETRY_DELAY_SECS: u64 = 30;
+/// How often a locked database is checked again
+const LOCKED_POLL_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueItemStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryQueueItem {
+    pub id: String,
+    pub entry_id: String,
+    pub title: String,
+    pub format: SummaryFormat,
+    pub status: QueueItemStatus,
+    pub attempts: u32,
+    /// Error from the last attempt
+    pub error: Option<String>,
+    /// Saved summary, once done
+    pub summary_id: Option<String>,
+    /// RFC3339 timestamp
+    pub enqueued_at: String,
+    pub finished_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryQueueStatus {
+    pub items: Vec<SummaryQueueItem>,
+    pub pending: usize,
+    pub running: usize,
+    pub done: usize,
+    pub failed: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnqueueSummariesPayload {
+    pub entry_ids: Vec<String>,
+    pub format: Option<SummaryFormat>,
+}
+
+/// Why summarizing an item failed
+#[derive(Debug)]
+struct SummaryFailure {
+    message: String,
+    /// Whether trying again might work
+    retryable: bool,
+}
+
+impl SummaryFailure {
+    fn retryable(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            retryable: true,
+        }
+    }
+
+    fn permanent(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            retryable: false,
+        }
+    }
+}
+
+fn now() -> String {
+    let now = OffsetDateTime::now_utc();
+    now.format(&Rfc3339).unwrap_or_else(|_| now.to_string())
+}
+
+/// Queue items in the order they'll be processed
+#[derive(Debug, Default)]
+struct SummaryQueue {
+    items: Vec<SummaryQueueItem>,
+}
+
+impl SummaryQueue {
+    /// Add entries that aren't already waiting or running; returns how many
+    /// were added
+    fn enqueue(&mut self, entries: Vec<(String, String)>, format: SummaryFormat) -> usize {
+        let mut added = 0;
+        for (entry_id, title) in entries {
+            let queued = self.items.iter().any(|item| {
+                item.entry_id == entry_id
+                    && matches!(item.status, QueueItemStatus::Pending | QueueItemStatus::Running)
+            });
+            if queued {
+                continue;
+            }
+            self.items.push(SummaryQueueItem {
+                id: uuid::Uuid::new_v4().to_string(),
+                entry_id,
+                title,
+                format,
+                status: QueueItemStatus::Pending,
+                attempts: 0,
+                error: None,
+                summary_id: None,
+                enqueued_at: now(),
+                finished_at: None,
+            });
+            added += 1;
+        }
+        added
+    }
+
+    /// Mark the first pending item as running and return it
+    fn start_next(&mut self) -> Option<SummaryQueueItem> {
+        let item = self
+            .items
+            .iter_mut()
+            .find(|item| item.status == QueueItemStatus::Pending)?;
+        item.status = QueueItemStatus::Running;
+        item.attempts += 1;
+        Some(item.clone())
+    }
+
+    /// Record the outcome of a running item
+    ///
+    /// A retryable failure with attempts left moves the item to the back of
+    /// the queue.
+    fn finish(&mut self, id: &str, result: Result<String, SummaryFailure>) -> Option<SummaryQueueItem> {
+        let index = self.items.iter().position(|item| item.id == id)?;
+        let mut item = self.items.remove(index);
+        match result {
+            Ok(summary_id) => {
+                item.status = QueueItemStatus::Done;
+                item.summary_id = Some(summary_id);
+                item.error = None;
+                item.finished_at = Some(now());
+            }
+            Err(failure) => {
+                item.error = Some(failure.message);
+                if failure.retryable && item.attempts < MAX_ATTEMPTS {
+                    item.status = QueueItemStatus::Pending;
+                } else {
+                    item.status = QueueItemStatus::Failed;
+                    item.finished_at = Some(now());
+                }
+            }
+        }
+        if item.status == QueueItemStatus::Pending {
+            self.items.push(item.clone());
+        } else {
+            self.items.insert(index, item.clone());
+        }
+        Some(item)
+    }
+
+    /// Queue failed items again with fresh attempts; returns how many
+    fn retry_failed(&mut self) -> usize {
+        let mut retried = 0;
+        for item in self.items.iter_mut().filter(|item| item.status == QueueItemStatus::Failed) {
+            item.status = QueueItemStatus::Pending;
+            item.attempts = 0;
+            item.finished_at = None;
+            retried += 1;
+        }
+        retried
+    }
+
+    /// Drop done and failed items
+    fn clear_finished(&mut self) {
+        self.items
+            .retain(|item| matches!(item.status, QueueItemStatus::Pending | QueueItemStatus::Running));
+    }
+
+    fn status(&self) -> SummaryQueueStatus {
+        let count = |status| self.items.iter().filter(|item| item.status == status).count();
+        SummaryQueueStatus {
+            items: self.items.clone(),
+            pending: count(QueueItemStatus::Pending),
+            running: count(QueueItemStatus::Running),
+            done: count(QueueItemStatus::Done),
+            failed: count(QueueItemStatus::Failed),
+        }
+    }
+}
+
+/// Summary queue shared by the commands and the background worker
+#[derive(Clone, Default)]
+pub struct SummaryQueueState {
+    queue: Arc<Mutex<SummaryQueue>>,
+    /// Wakes the worker when items are queued
+    wake: Arc<Notify>,
+}
+
+impl SummaryQueueState {
+    async fn status(&self) -> SummaryQueueStatus {
+        self.queue.lock().await.status()
+    }
+}
+
+/// Summarize one entry's abstract and save it to the summary history
+async fn summarize_entry(state: &AppState, item: &SummaryQueueItem) -> Result<String, SummaryFailure> {
+    let entry_id = item.entry_id.clone();
+    let entry = state
+        .db
+        .run(move |storage| storage.get_literature(&entry_id))
+        .await
+        .map_err(|e| SummaryFailure::retryable(format!("Failed to load literature entry: {:#}", e)))?
+        .ok_or_else(|| SummaryFailure::permanent("Literature entry no longer exists"))?;
+    let content = entry
+        .summary
+        .as_deref()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .ok_or_else(|| SummaryFailure::permanent("Entry has no abstract to summarize"))?;
+
+    let response = state
+        .ai_client
+        .summarize(SummarizeRequest {
+            title: entry.title.clone(),
+            content: content.to_string(),
+            format: item.format,
+        })
+        .await
+        .map_err(|e| SummaryFailure::retryable(format!("AI summarization failed: {}", e)))?;
+
+    let mut summary = SummaryHistory::new(
+        entry.title.clone(),
+        content.to_string(),
+        response.raw_output,
+        format!("{:?}", item.format),
+        format!("{:?}", response.provider),
+    );
+    summary.references = vec![entry.id.clone()];
+    summary.source_id = entry.id.clone();
+    summary.usage = Some(usage_record(response.usage));
+    let summary_id = summary.id.clone();
+    state
+        .db
+        .run(move |storage| storage.save_summary(&summary))
+        .await
+        .map_err(|e| SummaryFailure::retryable(format!("Failed to save summary: {:#}", e)))?;
+
+    Ok(summary_id)
+}
+
+fn emit_progress(app: &AppHandle, item: &SummaryQueueItem) {
+    if let Err(e) = app.emit(SUMMARY_QUEUE_PROGRESS_EVENT, item) {
+        warn!("Failed to report summary queue progress: {}", e);
+    }
+}
+
+/// Work through the summary queue, waiting for new items when it's empty
+pub async fn run_summary_queue_loop(app: AppHandle, state: Arc<AppState>, queue: SummaryQueueState) {
+    loop {
+        if state.key_provider.is_locked() {
+            tokio::time::sleep(tokio::time::Duration::from_secs(LOCKED_POLL_SECS)).await;
+            continue;
+        }
+
+        let Some(item) = queue.queue.lock().await.start_next() else {
+            queue.wake.notified().await;
+            continue;
+        };
+        emit_progress(&app, &item);
+
+        let result = summarize_entry(&state, &item).await;
+        let Some(item) = queue.queue.lock().await.finish(&item.id, result) else {
+            // Cleared while running
+            continue;
+        };
+        emit_progress(&app, &item);
+
+        match item.status {
+            QueueItemStatus::Done => info!("Summarized \"{}\"", item.title),
+            QueueItemStatus::Pending => {
+                warn!(
+                    "Summarizing \"{}\" failed, will retry: {}",
+                    item.title,
+                    item.error.as_deref().unwrap_or_default()
+                );
+                tokio::time::sleep(tokio::time::Duration::from_secs(RETRY_DELAY_SECS)).await;
+            }
+            _ => warn!(
+                "Gave up summarizing \"{}\": {}",
+                item.title,
+                item.error.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+// ========== Summary Queue Commands ==========
+
+/// Queue cached literature entries to be summarized in the background
+///
+/// Entries already waiting or running are skipped. Progress is reported
+/// with [`SUMMARY_QUEUE_PROGRESS_EVENT`].
+#[tauri::command]
+pub async fn enqueue_summaries(
+    state: State<'_, Arc<AppState>>,
+    queue: State<'_, SummaryQueueState>,
+    payload: EnqueueSummariesPayload,
+) -> Result<SummaryQueueStatus, CommandError> {
+    if payload.entry_ids.is_empty() {
+        return Err(CommandError::invalid_input("Select at least one paper to summarize"));
+    }
+
+    let mut entries = Vec::new();
+    for entry_id in payload.entry_ids {
+        let id = entry_id.clone();
+        let entry = state
+            .db
+            .run(move |storage| storage.get_literature(&id))
+            .await
+            .map_err(|e| {
+                error!("Failed to load literature entry {}: {:#}", entry_id, e);
+                CommandError::with_context(e, "Failed to load literature entry")
+            })?
+            .ok_or_else(|| CommandError::not_found(format!("Literature entry {} not found", entry_id)))?;
+        entries.push((entry.id, entry.title));
+    }
+
+    let added = queue
+        .queue
+        .lock()
+        .await
+        .enqueue(entries, payload.format.unwrap_or(SummaryFormat::Markdown));
+    info!("Queued {} papers for summarization", added);
+    queue.wake.notify_one();
+
+    Ok(queue.status().await)
+}
+
+/// Items in the summary queue with counts per status
+#[tauri::command]
+pub async fn get_summary_queue_status(
+    queue: State<'_, SummaryQueueState>,
+) -> Result<SummaryQueueStatus, CommandError> {
+    Ok(queue.status().await)
+}
+
+/// Queue failed items again
+#[tauri::command]
+pub async fn retry_failed_summaries(
+    queue: State<'_, SummaryQueueState>,
+) -> Result<SummaryQueueStatus, CommandError> {
+    let retried = queue.queue.lock().await.retry_failed();
+    if retried > 0 {
+        info!("Retrying {} failed summaries", retried);
+        queue.wake.notify_one();
+    }
+    Ok(queue.status().await)
+}
+
+/// Remove done and failed items from the queue
+#[tauri::command]
+pub async fn clear_finished_summaries(
+    queue: State<'_, SummaryQueueState>,
+) -> Result<SummaryQueueStatus, CommandError> {
+    queue.queue.lock().await.clear_finished();
+    Ok(queue.status().await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(ids: &[&str]) -> Vec<(String, String)> {
+        ids.iter()
+            .map(|id| (id.to_string(), format!("Paper {}", id)))
+            .collect()
+    }
+
+    #[test]
+    fn test_enqueue_skips_entries_already_queued() {
+        let mut queue = SummaryQueue::default();
+        assert_eq!(queue.enqueue(entries(&["a", "b"]), SummaryFormat::Markdown), 2);
+        let running = queue.start_next().unwrap();
+        assert_eq!(running.entry_id, "a");
+        assert_eq!(queue.enqueue(entries(&["a", "b", "c"]), SummaryFormat::Markdown), 1);
+
+        queue.finish(&running.id, Ok("summary-a".into()));
+        // Done entries can be summarized again
+        assert_eq!(queue.enqueue(entries(&["a"]), SummaryFormat::Markdown), 1);
+        let status = queue.status();
+        assert_eq!((status.pending, status.running, status.done), (3, 0, 1));
+    }
+
+    #[test]
+    fn test_failures_retry_at_the_back_then_give_up() {
+        let mut queue = SummaryQueue::default();
+        queue.enqueue(entries(&["a", "b"]), SummaryFormat::Markdown);
+
+        let item = queue.start_next().unwrap();
+        let item = queue.finish(&item.id, Err(SummaryFailure::retryable("timeout"))).unwrap();
+        assert_eq!(item.status, QueueItemStatus::Pending);
+        let item = queue.start_next().unwrap();
+        assert_eq!(item.entry_id, "b");
+        queue.finish(&item.id, Ok("summary-b".into()));
+
+        let failed = loop {
+            let item = queue.start_next().unwrap();
+            assert_eq!(item.entry_id, "a");
+            let item = queue.finish(&item.id, Err(SummaryFailure::retryable("timeout"))).unwrap();
+            if item.status != QueueItemStatus::Pending {
+                break item;
+            }
+        };
+        assert_eq!(failed.status, QueueItemStatus::Failed);
+        assert_eq!(failed.attempts, MAX_ATTEMPTS);
+        assert_eq!(failed.error.as_deref(), Some("timeout"));
+
+        assert_eq!(queue.retry_failed(), 1);
+        let status = queue.status();
+        assert_eq!((status.pending, status.done, status.failed), (1, 1, 0));
+    }
+
+    #[test]
+    fn test_permanent_failures_are_not_retried() {
+        let mut queue = SummaryQueue::default();
+        queue.enqueue(entries(&["a"]), SummaryFormat::Json);
+        let item = queue.start_next().unwrap();
+        let item = queue
+            .finish(&item.id, Err(SummaryFailure::permanent("Entry has no abstract to summarize")))
+            .unwrap();
+        assert_eq!(item.status, QueueItemStatus::Failed);
+        assert!(queue.start_next().is_none());
+
+        queue.clear_finished();
+        assert!(queue.status().items.is_empty());
+    }
+}
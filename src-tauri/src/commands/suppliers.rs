@@ -1,10 +1,22 @@
-use peptrack_core::{InventoryItem, Supplier, VialStatus};
+use peptrack_core::{
+    InventoryItem, ScrapingProfile, ScrapingSettings, Supplier, SupplierDeletion, SupplierProduct, SupplierReview, UndoAction, VialStatus,
+};
 use serde::{Deserialize, Serialize};
 use tauri::State;
 use time::OffsetDateTime;
 use tracing::{error, info, warn};
 use regex::Regex;
 
+use crate::commands::currency::resolve_currency;
+use crate::commands::dates::parse_datetime;
+use crate::commands::defaults::reconstituted_stability_days;
+use crate::commands::scraping::{
+    check_remote_addr, extract_with_profile, prepare_request, render_page, scraping_client,
+    validate_profile, validate_scraping_url,
+};
+use crate::commands::settings::load_setting_or_default;
+use crate::commands::undo::{records, snapshot};
+use crate::error::CommandError;
 use crate::state::AppState;
 
 // ========== Supplier Commands ==========
@@ -13,7 +25,7 @@ use crate::state::AppState;
 pub async fn create_supplier(
     state: State<'_, std::sync::Arc<AppState>>,
     payload: CreateSupplierPayload,
-) -> Result<Supplier, String> {
+) -> Result<Supplier, CommandError> {
     info!("Creating supplier: {}", payload.name);
 
     let mut supplier = Supplier::new(&payload.name);
@@ -21,34 +33,55 @@ pub async fn create_supplier(
     supplier.contact_phone = payload.contact_phone;
     supplier.website = payload.website;
     supplier.notes = payload.notes;
+    if let Some(product_urls) = payload.product_urls {
+        validate_product_urls(&product_urls)?;
+        supplier.product_urls = product_urls;
+    }
+    if let Some(profile) = payload.scraping_profile {
+        validate_profile(&profile)?;
+        supplier.scraping_profile = Some(profile);
+    }
+    if payload.currency.is_some() {
+        supplier.currency = Some(resolve_currency(payload.currency, None)?);
+    }
 
-    state.storage.upsert_supplier(&supplier).map_err(|e| {
-        error!("Failed to create supplier: {:#}", e);
-        format!("Failed to create supplier: {}", e)
-    })?;
-
-    Ok(supplier)
+    state
+        .db
+        .run(move |storage| storage.upsert_supplier(&supplier).map(|_| supplier))
+        .await
+        .map_err(|e| {
+            error!("Failed to create supplier: {:#}", e);
+            CommandError::with_context(e, "Failed to create supplier")
+        })
 }
 
 #[tauri::command]
 pub async fn list_suppliers(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<Vec<Supplier>, String> {
-    state.storage.list_suppliers().map_err(|e| {
-        error!("Failed to list suppliers: {:#}", e);
-        format!("Failed to list suppliers: {}", e)
-    })
+) -> Result<Vec<Supplier>, CommandError> {
+    state
+        .db
+        .run(|storage| storage.list_suppliers())
+        .await
+        .map_err(|e| {
+            error!("Failed to list suppliers: {:#}", e);
+            CommandError::with_context(e, "Failed to list suppliers")
+        })
 }
 
 #[tauri::command]
 pub async fn get_supplier(
     state: State<'_, std::sync::Arc<AppState>>,
     supplier_id: String,
-) -> Result<Option<Supplier>, String> {
-    state.storage.get_supplier(&supplier_id).map_err(|e| {
-        error!("Failed to get supplier: {:#}", e);
-        format!("Failed to get supplier: {}", e)
-    })
+) -> Result<Option<Supplier>, CommandError> {
+    state
+        .db
+        .run(move |storage| storage.get_supplier(&supplier_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to get supplier: {:#}", e);
+            CommandError::with_context(e, "Failed to get supplier")
+        })
 }
 
 #[tauri::command]
@@ -56,14 +89,10 @@ pub async fn update_supplier(
     state: State<'_, std::sync::Arc<AppState>>,
     supplier_id: String,
     payload: UpdateSupplierPayload,
-) -> Result<Supplier, String> {
+) -> Result<Supplier, CommandError> {
     info!("Updating supplier: {}", supplier_id);
 
-    let mut supplier = state
-        .storage
-        .get_supplier(&supplier_id)
-        .map_err(|e| format!("Failed to fetch supplier: {}", e))?
-        .ok_or_else(|| "Supplier not found".to_string())?;
+    let mut supplier = fetch_supplier(&state, &supplier_id).await?;
 
     if let Some(name) = payload.name {
         supplier.name = name;
@@ -72,108 +101,284 @@ pub async fn update_supplier(
     supplier.contact_phone = payload.contact_phone.or(supplier.contact_phone);
     supplier.website = payload.website.or(supplier.website);
     supplier.notes = payload.notes.or(supplier.notes);
+    if let Some(product_urls) = payload.product_urls {
+        validate_product_urls(&product_urls)?;
+        supplier.product_urls = product_urls;
+    }
+    if let Some(profile) = payload.scraping_profile {
+        validate_profile(&profile)?;
+        supplier.scraping_profile = Some(profile);
+    }
+    if payload.currency.is_some() {
+        supplier.currency = Some(resolve_currency(payload.currency, None)?);
+    }
     supplier.updated_at = OffsetDateTime::now_utc();
 
-    state.storage.upsert_supplier(&supplier).map_err(|e| {
-        error!("Failed to update supplier: {:#}", e);
-        format!("Failed to update supplier: {}", e)
-    })?;
-
-    Ok(supplier)
+    state
+        .db
+        .run(move |storage| storage.upsert_supplier(&supplier).map(|_| supplier))
+        .await
+        .map_err(|e| {
+            error!("Failed to update supplier: {:#}", e);
+            CommandError::with_context(e, "Failed to update supplier")
+        })
 }
 
 #[tauri::command]
 pub async fn delete_supplier(
     state: State<'_, std::sync::Arc<AppState>>,
     supplier_id: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     info!("Deleting supplier: {}", supplier_id);
 
-    state.storage.delete_supplier(&supplier_id).map_err(|e| {
-        error!("Failed to delete supplier: {:#}", e);
-        format!("Failed to delete supplier: {}", e)
-    })
+    state
+        .db
+        .run(move |storage| storage.delete_supplier(&supplier_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to delete supplier: {:#}", e);
+            CommandError::with_context(e, "Failed to delete supplier")
+        })
 }
 
-/// Validate URL to prevent SSRF attacks
-fn validate_scraping_url(url_str: &str) -> Result<url::Url, String> {
-    let url = url::Url::parse(url_str)
-        .map_err(|_| "Invalid URL format".to_string())?;
+/// Rate a supplier, for one order or in general
+///
+/// Reviewing an order again replaces its earlier review.
+#[tauri::command]
+pub async fn record_supplier_review(
+    state: State<'_, std::sync::Arc<AppState>>,
+    supplier_id: String,
+    payload: SupplierReviewPayload,
+) -> Result<Supplier, CommandError> {
+    let mut supplier = fetch_supplier(&state, &supplier_id).await?;
+
+    if let Some(order_id) = payload.order_id.clone() {
+        let order = state
+            .db
+            .run(move |storage| storage.get_order(&order_id))
+            .await
+            .map_err(|e| CommandError::with_context(e, "Failed to fetch order"))?
+            .ok_or_else(|| CommandError::not_found("Order not found"))?;
+        if order.supplier_id != supplier.id {
+            return Err(CommandError::invalid_input(format!(
+                "That order wasn't placed with {}",
+                supplier.name
+            )));
+        }
+    }
+
+    let mut review = SupplierReview::new(payload.shipping_speed, payload.product_quality, payload.communication);
+    review.order_id = payload.order_id;
+    review.notes = payload.notes.filter(|notes| !notes.trim().is_empty());
+    review.validate().map_err(CommandError::invalid_input)?;
+
+    supplier.add_review(review);
+    supplier.updated_at = OffsetDateTime::now_utc();
+    let supplier = state
+        .db
+        .run(move |storage| storage.upsert_supplier(&supplier).map(|_| supplier))
+        .await
+        .map_err(|e| {
+            error!("Failed to save supplier review: {:#}", e);
+            CommandError::with_context(e, "Failed to save supplier review")
+        })?;
 
-    // Only allow HTTP/HTTPS
-    if url.scheme() != "http" && url.scheme() != "https" {
-        return Err("Only HTTP and HTTPS URLs are allowed".to_string());
+    info!("Recorded review for supplier: {}", supplier.name);
+    Ok(supplier)
+}
+
+#[tauri::command]
+pub async fn delete_supplier_review(
+    state: State<'_, std::sync::Arc<AppState>>,
+    supplier_id: String,
+    review_id: String,
+) -> Result<Supplier, CommandError> {
+    let mut supplier = fetch_supplier(&state, &supplier_id).await?;
+
+    let before = supplier.reviews.len();
+    supplier.reviews.retain(|review| review.id != review_id);
+    if supplier.reviews.len() == before {
+        return Err(CommandError::not_found("Review not found"));
     }
+    supplier.updated_at = OffsetDateTime::now_utc();
+    state
+        .db
+        .run(move |storage| storage.upsert_supplier(&supplier).map(|_| supplier))
+        .await
+        .map_err(|e| {
+            error!("Failed to delete supplier review: {:#}", e);
+            CommandError::with_context(e, "Failed to delete supplier review")
+        })
+}
+
+async fn fetch_supplier(state: &AppState, supplier_id: &str) -> Result<Supplier, CommandError> {
+    let supplier_id = supplier_id.to_string();
+    state
+        .db
+        .run(move |storage| storage.get_supplier(&supplier_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch supplier"))?
+        .ok_or_else(|| CommandError::not_found("Supplier not found"))
+}
+
+/// Delete several suppliers with their price history in one transaction
+///
+/// Inventory bought from them is kept without a supplier.
+#[tauri::command]
+pub async fn bulk_delete_suppliers(
+    state: State<'_, std::sync::Arc<AppState>>,
+    supplier_ids: Vec<String>,
+) -> Result<SupplierDeletion, CommandError> {
+    info!("Deleting {} suppliers", supplier_ids.len());
+
+    state
+        .db
+        .run(move |storage| storage.bulk_delete_suppliers(&supplier_ids))
+        .await
+        .map_err(|e| {
+            error!("Failed to delete suppliers: {:#}", e);
+            CommandError::with_context(e, "Failed to delete suppliers")
+        })
+}
 
-    // Block localhost and private IP ranges to prevent SSRF
-    if let Some(host) = url.host_str() {
-        let host_lower = host.to_lowercase();
-
-        if host_lower == "localhost"
-            || host_lower == "127.0.0.1"
-            || host_lower.starts_with("192.168.")
-            || host_lower.starts_with("10.")
-            || host_lower.starts_with("172.16.")
-            || host_lower.starts_with("172.17.")
-            || host_lower.starts_with("172.18.")
-            || host_lower.starts_with("172.19.")
-            || host_lower.starts_with("172.20.")
-            || host_lower.starts_with("172.21.")
-            || host_lower.starts_with("172.22.")
-            || host_lower.starts_with("172.23.")
-            || host_lower.starts_with("172.24.")
-            || host_lower.starts_with("172.25.")
-            || host_lower.starts_with("172.26.")
-            || host_lower.starts_with("172.27.")
-            || host_lower.starts_with("172.28.")
-            || host_lower.starts_with("172.29.")
-            || host_lower.starts_with("172.30.")
-            || host_lower.starts_with("172.31.")
-            || host_lower == "169.254.169.254"  // AWS/Cloud metadata
-            || host_lower.starts_with("[::1]")   // IPv6 localhost
-            || host_lower.starts_with("fe80:")   // IPv6 link-local
-            || host_lower.starts_with("fc00:")   // IPv6 unique local
-        {
-            return Err("Access to private/internal addresses is not allowed for security reasons".to_string());
+/// Validate saved product URLs before they are stored for re-scraping
+fn validate_product_urls(products: &[SupplierProduct]) -> Result<(), CommandError> {
+    for product in products {
+        if product.peptide_name.trim().is_empty() {
+            return Err(CommandError::invalid_input("Product URLs must specify a peptide name"));
         }
+        validate_scraping_url(&product.url)?;
     }
+    Ok(())
+}
 
-    Ok(url)
+/// Result of scraping a single product page
+pub(crate) struct ScrapeOutcome {
+    pub matches: Vec<PriceMatch>,
+    pub in_stock: bool,
 }
 
 /// Scrape a website for peptide prices
+///
+/// When `supplier_id` refers to a supplier with a scraping profile, its CSS
+/// selectors are used first and the regex patterns are only a fallback.
 #[tauri::command]
 pub async fn scrape_supplier_website(
+    state: State<'_, std::sync::Arc<AppState>>,
     url: String,
     peptide_name: Option<String>,
-) -> Result<Vec<PriceMatch>, String> {
-    info!("Scraping URL: {} for peptide: {:?}", url, peptide_name);
+    supplier_id: Option<String>,
+) -> Result<Vec<PriceMatch>, CommandError> {
+    let profile = match supplier_id {
+        Some(id) => state
+            .db
+            .run(move |storage| storage.get_supplier(&id))
+            .await
+            .map_err(|e| CommandError::with_context(e, "Failed to fetch supplier"))?
+            .and_then(|supplier| supplier.scraping_profile),
+        None => None,
+    };
+
+    scrape_prices(&state, &url, peptide_name.as_deref(), profile.as_ref())
+        .await
+        .map(|outcome| outcome.matches)
+}
 
+/// Fetch the HTML of a page after validating the URL, honoring the site's
+/// robots.txt and request delay
+pub(crate) async fn fetch_page(state: &AppState, url: &str) -> Result<String, CommandError> {
     // Validate URL to prevent SSRF attacks
-    let validated_url = validate_scraping_url(&url)?;
+    let validated_url = validate_scraping_url(url)?;
+
+    let settings: ScrapingSettings = load_setting_or_default(state).await;
+    prepare_request(state, &settings, &validated_url).await?;
 
     // Fetch the webpage
-    let response = reqwest::get(validated_url).await.map_err(|e| {
+    let client = scraping_client(&settings)?;
+    let response = client.get(validated_url).send().await.map_err(|e| {
         error!("Failed to fetch URL: {:#}", e);
-        format!("Failed to fetch webpage: {}", e)
+        CommandError::with_context(e, "Failed to fetch webpage")
     })?;
+    check_remote_addr(&response)?;
 
-    let html = response.text().await.map_err(|e| {
+    response.text().await.map_err(|e| {
         error!("Failed to read response: {:#}", e);
-        format!("Failed to read webpage content: {}", e)
-    })?;
+        CommandError::with_context(e, "Failed to read webpage content")
+    })
+}
+
+/// Fetch a page and extract price matches and stock status
+///
+/// When the HTML has no prices, the page is rendered with its JavaScript
+/// and scraped again, if enabled; see [`render_page`].
+pub(crate) async fn scrape_prices(
+    state: &AppState,
+    url: &str,
+    peptide_name: Option<&str>,
+    profile: Option<&ScrapingProfile>,
+) -> Result<ScrapeOutcome, CommandError> {
+    info!("Scraping URL: {} for peptide: {:?}", url, peptide_name);
+
+    let html = fetch_page(state, url).await?;
+    let mut outcome = extract_outcome(url, &html, peptide_name, profile)?;
+
+    if outcome.matches.is_empty() {
+        if let Some(rendered) = render_page(state, &validate_scraping_url(url)?).await {
+            outcome = extract_outcome(url, &rendered, peptide_name, profile)?;
+        }
+    }
+
+    if outcome.matches.is_empty() {
+        warn!("No prices found on URL: {}", url);
+    } else {
+        info!("Found {} price matches", outcome.matches.len());
+    }
+
+    Ok(outcome)
+}
+
+/// Price matches and stock status in a page's HTML, using the scraping
+/// profile first and the patterns as a fallback
+fn extract_outcome(
+    url: &str,
+    html: &str,
+    peptide_name: Option<&str>,
+    profile: Option<&ScrapingProfile>,
+) -> Result<ScrapeOutcome, CommandError> {
+    let mut matches = Vec::new();
+    let mut in_stock = None;
+
+    if let Some(profile) = profile {
+        let extraction = extract_with_profile(html, profile)?;
+        if extraction.matches.is_empty() {
+            warn!("Scraping profile matched no prices on {}, falling back to patterns", url);
+        }
+        matches = extraction.matches;
+        in_stock = extraction.in_stock;
+    }
+
+    if matches.is_empty() {
+        matches = extract_price_matches(html, peptide_name);
+    }
+
+    Ok(ScrapeOutcome {
+        in_stock: in_stock.unwrap_or_else(|| !is_out_of_stock(html)),
+        matches,
+    })
+}
 
-    // Extract prices using multiple patterns
+/// Extract prices from page HTML using multiple patterns
+fn extract_price_matches(html: &str, peptide_name: Option<&str>) -> Vec<PriceMatch> {
     let mut matches = Vec::new();
 
     // Pattern 1: $X.XX/mg or $X.XX per mg
     let price_per_mg_re = Regex::new(r"\$?(\d+(?:\.\d{1,2})?)\s*(?:/|per)\s*mg").unwrap();
-    for cap in price_per_mg_re.captures_iter(&html) {
+    for cap in price_per_mg_re.captures_iter(html) {
         if let Some(price_str) = cap.get(1) {
             if let Ok(price) = price_str.as_str().parse::<f32>() {
                 matches.push(PriceMatch {
                     price_per_mg: price,
-                    context: extract_context(&html, cap.get(0).unwrap().start(), 100),
+                    context: extract_context(html, cap.get(0).unwrap().start(), 100),
                     pattern_type: "per_mg".to_string(),
                 });
             }
@@ -182,14 +387,14 @@ pub async fn scrape_supplier_website(
 
     // Pattern 2: XXmg for $YY or XXmg - $YY
     let vial_price_re = Regex::new(r"(\d+(?:\.\d+)?)\s*mg\s*(?:for|-|:)?\s*\$(\d+(?:\.\d{1,2})?)").unwrap();
-    for cap in vial_price_re.captures_iter(&html) {
+    for cap in vial_price_re.captures_iter(html) {
         if let (Some(mg_str), Some(price_str)) = (cap.get(1), cap.get(2)) {
             if let (Ok(mg), Ok(total_price)) = (mg_str.as_str().parse::<f32>(), price_str.as_str().parse::<f32>()) {
                 if mg > 0.0 {
                     let price_per_mg = total_price / mg;
                     matches.push(PriceMatch {
                         price_per_mg,
-                        context: extract_context(&html, cap.get(0).unwrap().start(), 100),
+                        context: extract_context(html, cap.get(0).unwrap().start(), 100),
                         pattern_type: "vial_price".to_string(),
                     });
                 }
@@ -198,23 +403,22 @@ pub async fn scrape_supplier_website(
     }
 
     // Pattern 3: Generic price mentions near peptide names
-    if let Some(ref peptide) = peptide_name {
+    if let Some(peptide) = peptide_name {
         // Prevent ReDoS by limiting peptide name length
         if peptide.len() > 100 {
             warn!("Peptide name too long for regex search: {} chars", peptide.len());
-            return Ok(matches);
-        }
-
-        let peptide_pattern = format!(r"(?i){}\s*(?:\w+\s*){{0,10}}\$(\d+(?:\.\d{{1,2}})?)", regex::escape(peptide));
-        if let Ok(peptide_re) = Regex::new(&peptide_pattern) {
-            for cap in peptide_re.captures_iter(&html) {
-                if let Some(price_str) = cap.get(1) {
-                    if let Ok(price) = price_str.as_str().parse::<f32>() {
-                        matches.push(PriceMatch {
-                            price_per_mg: price,
-                            context: extract_context(&html, cap.get(0).unwrap().start(), 150),
-                            pattern_type: "peptide_mention".to_string(),
-                        });
+        } else {
+            let peptide_pattern = format!(r"(?i){}\s*(?:\w+\s*){{0,10}}\$(\d+(?:\.\d{{1,2}})?)", regex::escape(peptide));
+            if let Ok(peptide_re) = Regex::new(&peptide_pattern) {
+                for cap in peptide_re.captures_iter(html) {
+                    if let Some(price_str) = cap.get(1) {
+                        if let Ok(price) = price_str.as_str().parse::<f32>() {
+                            matches.push(PriceMatch {
+                                price_per_mg: price,
+                                context: extract_context(html, cap.get(0).unwrap().start(), 150),
+                                pattern_type: "peptide_mention".to_string(),
+                            });
+                        }
                     }
                 }
             }
@@ -225,13 +429,15 @@ pub async fn scrape_supplier_website(
     matches.sort_by(|a, b| a.price_per_mg.partial_cmp(&b.price_per_mg).unwrap());
     matches.dedup_by(|a, b| (a.price_per_mg - b.price_per_mg).abs() < 0.01);
 
-    if matches.is_empty() {
-        warn!("No prices found on URL: {}", url);
-    } else {
-        info!("Found {} price matches", matches.len());
-    }
+    matches
+}
 
-    Ok(matches)
+/// Detect common "out of stock" markers in a product page
+pub(crate) fn is_out_of_stock(html: &str) -> bool {
+    let lower = html.to_lowercase();
+    ["out of stock", "sold out", "currently unavailable", "outofstock"]
+        .iter()
+        .any(|marker| lower.contains(marker))
 }
 
 /// Extract text context around a position in HTML (strips tags)
@@ -255,12 +461,25 @@ fn extract_context(html: &str, position: usize, radius: usize) -> String {
 pub async fn create_inventory_item(
     state: State<'_, std::sync::Arc<AppState>>,
     payload: CreateInventoryPayload,
-) -> Result<InventoryItem, String> {
+) -> Result<InventoryItem, CommandError> {
     info!(
         "Creating inventory item for protocol: {}",
         payload.protocol_id
     );
 
+    let item = new_inventory_item(payload)?;
+    state
+        .db
+        .run(move |storage| storage.upsert_inventory_item(&item).map(|_| item))
+        .await
+        .map_err(|e| {
+            error!("Failed to create inventory item: {:#}", e);
+            CommandError::with_context(e, "Failed to create inventory item")
+        })
+}
+
+/// Builds a new inventory item from `payload` without storing it
+pub(crate) fn new_inventory_item(payload: CreateInventoryPayload) -> Result<InventoryItem, CommandError> {
     let mut item = InventoryItem::new(&payload.protocol_id);
     item.supplier_id = payload.supplier_id;
     item.vial_number = payload.vial_number;
@@ -268,41 +487,41 @@ pub async fn create_inventory_item(
     item.purchase_date = payload.purchase_date;
     item.expiry_date = payload.expiry_date;
     item.cost_per_mg = payload.cost_per_mg;
+    item.currency = resolve_currency(payload.currency, None)?;
     item.quantity_mg = payload.quantity_mg;
     item.concentration_mg_ml = payload.concentration_mg_ml;
     item.batch_number = payload.batch_number;
     item.lot_number = payload.lot_number;
     item.notes = payload.notes;
-
-    state.storage.upsert_inventory_item(&item).map_err(|e| {
-        error!("Failed to create inventory item: {:#}", e);
-        format!("Failed to create inventory item: {}", e)
-    })?;
-
     Ok(item)
 }
 
 #[tauri::command]
 pub async fn list_inventory(
     state: State<'_, std::sync::Arc<AppState>>,
-) -> Result<Vec<InventoryItem>, String> {
-    state.storage.list_inventory().map_err(|e| {
-        error!("Failed to list inventory: {:#}", e);
-        format!("Failed to list inventory: {}", e)
-    })
+) -> Result<Vec<InventoryItem>, CommandError> {
+    state
+        .db
+        .run(|storage| storage.list_inventory())
+        .await
+        .map_err(|e| {
+            error!("Failed to list inventory: {:#}", e);
+            CommandError::with_context(e, "Failed to list inventory")
+        })
 }
 
 #[tauri::command]
 pub async fn list_inventory_by_protocol(
     state: State<'_, std::sync::Arc<AppState>>,
     protocol_id: String,
-) -> Result<Vec<InventoryItem>, String> {
+) -> Result<Vec<InventoryItem>, CommandError> {
     state
-        .storage
-        .list_inventory_by_protocol(&protocol_id)
+        .db
+        .run(move |storage| storage.list_inventory_by_protocol(&protocol_id))
+        .await
         .map_err(|e| {
             error!("Failed to list inventory for protocol: {:#}", e);
-            format!("Failed to list inventory: {}", e)
+            CommandError::with_context(e, "Failed to list inventory")
         })
 }
 
@@ -310,11 +529,15 @@ pub async fn list_inventory_by_protocol(
 pub async fn get_inventory_item(
     state: State<'_, std::sync::Arc<AppState>>,
     item_id: String,
-) -> Result<Option<InventoryItem>, String> {
-    state.storage.get_inventory_item(&item_id).map_err(|e| {
-        error!("Failed to get inventory item: {:#}", e);
-        format!("Failed to get inventory item: {}", e)
-    })
+) -> Result<Option<InventoryItem>, CommandError> {
+    state
+        .db
+        .run(move |storage| storage.get_inventory_item(&item_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to get inventory item: {:#}", e);
+            CommandError::with_context(e, "Failed to get inventory item")
+        })
 }
 
 #[tauri::command]
@@ -322,14 +545,10 @@ pub async fn update_inventory_item(
     state: State<'_, std::sync::Arc<AppState>>,
     item_id: String,
     payload: UpdateInventoryPayload,
-) -> Result<InventoryItem, String> {
+) -> Result<InventoryItem, CommandError> {
     info!("Updating inventory item: {}", item_id);
 
-    let mut item = state
-        .storage
-        .get_inventory_item(&item_id)
-        .map_err(|e| format!("Failed to fetch inventory item: {}", e))?
-        .ok_or_else(|| "Inventory item not found".to_string())?;
+    let mut item = fetch_inventory_item(&state, &item_id).await?;
 
     item.supplier_id = payload.supplier_id.or(item.supplier_id);
     item.vial_number = payload.vial_number.or(item.vial_number);
@@ -339,6 +558,9 @@ pub async fn update_inventory_item(
     item.purchase_date = payload.purchase_date.or(item.purchase_date);
     item.expiry_date = payload.expiry_date.or(item.expiry_date);
     item.cost_per_mg = payload.cost_per_mg.or(item.cost_per_mg);
+    if payload.currency.is_some() {
+        item.currency = resolve_currency(payload.currency, None)?;
+    }
     item.quantity_mg = payload.quantity_mg.or(item.quantity_mg);
     item.concentration_mg_ml = payload.concentration_mg_ml.or(item.concentration_mg_ml);
     item.batch_number = payload.batch_number.or(item.batch_number);
@@ -346,29 +568,208 @@ pub async fn update_inventory_item(
     item.notes = payload.notes.or(item.notes);
     item.updated_at = OffsetDateTime::now_utc();
 
-    state.storage.upsert_inventory_item(&item).map_err(|e| {
-        error!("Failed to update inventory item: {:#}", e);
-        format!("Failed to update inventory item: {}", e)
-    })?;
+    state
+        .db
+        .run(move |storage| storage.upsert_inventory_item(&item).map(|_| item))
+        .await
+        .map_err(|e| {
+            error!("Failed to update inventory item: {:#}", e);
+            CommandError::with_context(e, "Failed to update inventory item")
+        })
+}
 
-    Ok(item)
+async fn fetch_inventory_item(
+    state: &AppState,
+    item_id: &str,
+) -> Result<InventoryItem, CommandError> {
+    let item_id = item_id.to_string();
+    state
+        .db
+        .run(move |storage| storage.get_inventory_item(&item_id))
+        .await
+        .map_err(|e| CommandError::with_context(e, "Failed to fetch inventory item"))?
+        .ok_or_else(|| CommandError::not_found("Inventory item not found"))
+}
+
+/// Record mixing a vial at `reconstituted_at` (RFC3339, default now)
+///
+/// It keeps for `stability_days`, or the catalog's fridge life for the
+/// protocol's peptide when not given.
+#[tauri::command]
+pub async fn reconstitute_inventory_item(
+    state: State<'_, std::sync::Arc<AppState>>,
+    item_id: String,
+    reconstituted_at: Option<String>,
+    stability_days: Option<u32>,
+) -> Result<InventoryItem, CommandError> {
+    let reconstituted_at = match reconstituted_at {
+        Some(value) => parse_datetime(&value)?,
+        None => OffsetDateTime::now_utc(),
+    };
+    if stability_days == Some(0) {
+        return Err(CommandError::invalid_input("A reconstituted vial keeps for at least 1 day"));
+    }
+
+    let mut item = fetch_inventory_item(&state, &item_id).await?;
+    if matches!(item.vial_status, VialStatus::Empty | VialStatus::Expired) {
+        return Err(CommandError::invalid_input("Only vials still in use can be reconstituted"));
+    }
+
+    let stability_days = match stability_days {
+        Some(days) => days,
+        None => {
+            let protocol_id = item.protocol_id.clone();
+            let peptide_name = state
+                .db
+                .run(move |storage| storage.get_protocol(&protocol_id))
+                .await
+                .map_err(|e| CommandError::with_context(e, "Failed to fetch protocol"))?
+                .map(|protocol| protocol.peptide_name)
+                .unwrap_or_default();
+            reconstituted_stability_days(&peptide_name)
+        }
+    };
+    info!("Reconstituting inventory item {} ({} days)", item_id, stability_days);
+
+    item.reconstitute(reconstituted_at, stability_days);
+    item.updated_at = OffsetDateTime::now_utc();
+
+    state
+        .db
+        .run(move |storage| storage.upsert_inventory_item(&item).map(|_| item))
+        .await
+        .map_err(|e| {
+            error!("Failed to update inventory item: {:#}", e);
+            CommandError::with_context(e, "Failed to update inventory item")
+        })
 }
 
 #[tauri::command]
 pub async fn delete_inventory_item(
     state: State<'_, std::sync::Arc<AppState>>,
     item_id: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     info!("Deleting inventory item: {}", item_id);
 
-    state.storage.delete_inventory_item(&item_id).map_err(|e| {
-        error!("Failed to delete inventory item: {:#}", e);
-        format!("Failed to delete inventory item: {}", e)
-    })
+    let before = state
+        .db
+        .run(move |storage| {
+            let before =
+                snapshot(std::slice::from_ref(&item_id), |id| storage.get_inventory_item(id));
+            storage.delete_inventory_item(&item_id).map(|_| before)
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to delete inventory item: {:#}", e);
+            CommandError::with_context(e, "Failed to delete inventory item")
+        })?;
+    state.undo.push("Delete 1 inventory item", UndoAction::RestoreInventory(before));
+    Ok(())
+}
+
+/// Set the vial status of several inventory items in one transaction
+#[tauri::command]
+pub async fn bulk_update_inventory_status(
+    state: State<'_, std::sync::Arc<AppState>>,
+    item_ids: Vec<String>,
+    status: VialStatus,
+) -> Result<usize, CommandError> {
+    let (updated, before) = state
+        .db
+        .run(move |storage| {
+            let before = snapshot(&item_ids, |id| storage.get_inventory_item(id));
+            Ok((storage.bulk_update_inventory_status(&item_ids, status)?, before))
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to update inventory status: {:#}", e);
+            CommandError::with_context(e, "Failed to update inventory status")
+        })?;
+    state.undo.push(
+        format!("Change status of {}", records(updated, "inventory item")),
+        UndoAction::RestoreInventory(before),
+    );
+    Ok(updated)
+}
+
+/// Delete several inventory items in one transaction
+#[tauri::command]
+pub async fn bulk_delete_inventory_items(
+    state: State<'_, std::sync::Arc<AppState>>,
+    item_ids: Vec<String>,
+) -> Result<usize, CommandError> {
+    info!("Deleting {} inventory items", item_ids.len());
+
+    let (deleted, before) = state
+        .db
+        .run(move |storage| {
+            let before = snapshot(&item_ids, |id| storage.get_inventory_item(id));
+            Ok((storage.bulk_delete_inventory_items(&item_ids)?, before))
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to delete inventory items: {:#}", e);
+            CommandError::with_context(e, "Failed to delete inventory items")
+        })?;
+    state.undo.push(
+        format!("Delete {}", records(deleted, "inventory item")),
+        UndoAction::RestoreInventory(before),
+    );
+    Ok(deleted)
+}
+
+/// Set the supplier of several inventory items, or clear it with `None`
+#[tauri::command]
+pub async fn bulk_assign_supplier(
+    state: State<'_, std::sync::Arc<AppState>>,
+    item_ids: Vec<String>,
+    supplier_id: Option<String>,
+) -> Result<usize, CommandError> {
+    if let Some(supplier_id) = supplier_id.clone() {
+        let supplier = state
+            .db
+            .run(move |storage| storage.get_supplier(&supplier_id))
+            .await
+            .map_err(|e| {
+                error!("Failed to fetch supplier: {:#}", e);
+                CommandError::with_context(e, "Failed to fetch supplier")
+            })?;
+        if supplier.is_none() {
+            return Err(CommandError::not_found("Supplier not found"));
+        }
+    }
+
+    let (assigned, before) = state
+        .db
+        .run(move |storage| {
+            let before = snapshot(&item_ids, |id| storage.get_inventory_item(id));
+            Ok((storage.bulk_assign_supplier(&item_ids, supplier_id.as_deref())?, before))
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to assign supplier: {:#}", e);
+            CommandError::with_context(e, "Failed to assign supplier")
+        })?;
+    state.undo.push(
+        format!("Change supplier of {}", records(assigned, "inventory item")),
+        UndoAction::RestoreInventory(before),
+    );
+    Ok(assigned)
 }
 
 // ========== Payload Structs ==========
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupplierReviewPayload {
+    /// Order being reviewed; none for a general review
+    pub order_id: Option<String>,
+    pub shipping_speed: u8,
+    pub product_quality: u8,
+    pub communication: u8,
+    pub notes: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PriceMatch {
@@ -385,6 +786,9 @@ pub struct CreateSupplierPayload {
     pub contact_phone: Option<String>,
     pub website: Option<String>,
     pub notes: Option<String>,
+    pub product_urls: Option<Vec<SupplierProduct>>,
+    pub scraping_profile: Option<ScrapingProfile>,
+    pub currency: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -395,6 +799,9 @@ pub struct UpdateSupplierPayload {
     pub contact_phone: Option<String>,
     pub website: Option<String>,
     pub notes: Option<String>,
+    pub product_urls: Option<Vec<SupplierProduct>>,
+    pub scraping_profile: Option<ScrapingProfile>,
+    pub currency: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -407,6 +814,7 @@ pub struct CreateInventoryPayload {
     pub purchase_date: Option<OffsetDateTime>,
     pub expiry_date: Option<OffsetDateTime>,
     pub cost_per_mg: Option<f32>,
+    pub currency: Option<String>,
     pub quantity_mg: Option<f32>,
     pub concentration_mg_ml: Option<f32>,
     pub batch_number: Option<String>,
@@ -423,6 +831,7 @@ pub struct UpdateInventoryPayload {
     pub purchase_date: Option<OffsetDateTime>,
     pub expiry_date: Option<OffsetDateTime>,
     pub cost_per_mg: Option<f32>,
+    pub currency: Option<String>,
     pub quantity_mg: Option<f32>,
     pub concentration_mg_ml: Option<f32>,
     pub batch_number: Option<String>,
@@ -472,4 +881,16 @@ mod tests {
         assert_eq!(payload.cost_per_mg, Some(1.25));
         assert_eq!(payload.quantity_mg, Some(10.0));
     }
+
+    #[test]
+    fn test_extract_price_matches_and_stock_status() {
+        let html = "<div>BPC-157 10mg for $45.00</div><span>$3.50/mg</span>";
+
+        let matches = extract_price_matches(html, Some("BPC-157"));
+        assert!(matches.iter().any(|m| m.pattern_type == "per_mg" && (m.price_per_mg - 3.5).abs() < 0.01));
+        assert!(matches.iter().any(|m| m.pattern_type == "vial_price" && (m.price_per_mg - 4.5).abs() < 0.01));
+
+        assert!(!is_out_of_stock(html));
+        assert!(is_out_of_stock("<button disabled>Sold Out</button>"));
+    }
 }
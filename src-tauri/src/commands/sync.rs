@@ -0,0 +1,547 @@
+//! End-to-end encrypted sync through a self-hosted relay
+//!
+//! See [`peptrack_core::sync`] for how change sets are laid out on the
+//! relay. The relay secret (a bearer token, or an S3 secret access key) and
+//! the sync key are kept with [`peptrack_core::store_credential`], in the
+//! Keychain on macOS; neither is ever uploaded.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use peptrack_core::sync::{
+    self, amz_date, change_set_key, head_key, open_blob, s3_authorization, seal_blob, sha256_hex,
+    S3Credentials, DEVICES_KEY, MAX_CHANGES_PER_SET,
+};
+use peptrack_core::{
+    delete_credential, load_credential, store_credential, ChangeSet, EnvelopeEncryption, Setting,
+    SyncChange, SyncCursor, SyncDevice, SyncRelay, SyncSettings,
+};
+use reqwest::{Method, StatusCode};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::commands::settings::notify_setting_changed;
+use crate::error::CommandError;
+use crate::state::{app_data_dir, AppState};
+
+/// Credential account of the relay's bearer token or S3 secret key
+pub(crate) const RELAY_SECRET_ACCOUNT: &str = "sync-relay-secret";
+/// Credential account of the key relay blobs are sealed with
+pub(crate) const SYNC_KEY_ACCOUNT: &str = "sync-key";
+/// How often the background job syncs while sync is on
+const SYNC_INTERVAL_SECS: u64 = 15 * 60;
+
+/// Keeps the background job and `sync_now` from uploading the same change
+/// set number twice
+static SYNC_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Sync settings, with what's stored outside them
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub settings: SyncSettings,
+    pub secret_saved: bool,
+    pub sync_key_saved: bool,
+    /// How far sync has got with each device, this one included
+    pub cursors: Vec<SyncCursor>,
+}
+
+/// What one sync did
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub uploaded_change_sets: usize,
+    pub uploaded_changes: usize,
+    pub downloaded_change_sets: usize,
+    pub saved: usize,
+    pub deleted: usize,
+    /// Downloaded changes already applied, or older than the copy here
+    pub skipped: usize,
+    /// Other devices registered with the relay
+    pub devices: usize,
+}
+
+/// Blob storage on the relay
+struct RelayClient {
+    relay: SyncRelay,
+    secret: Option<String>,
+    http: reqwest::Client,
+}
+
+impl RelayClient {
+    fn new(relay: SyncRelay) -> Result<Self> {
+        let secret = load_credential(&app_data_dir()?, RELAY_SECRET_ACCOUNT)?;
+        if matches!(relay, SyncRelay::S3 { .. }) && secret.is_none() {
+            return Err(anyhow!("No S3 secret access key saved for the sync relay"));
+        }
+        Ok(Self {
+            relay,
+            secret,
+            http: peptrack_literature::http::client(),
+        })
+    }
+
+    /// The blob stored under `key`, `None` if there is none
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let response = self.request(Method::GET, key, Vec::new())?.send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = response
+            .error_for_status()
+            .with_context(|| format!("Relay refused to read {}", key))?;
+        Ok(Some(response.bytes().await?.to_vec()))
+    }
+
+    async fn put(&self, key: &str, blob: Vec<u8>) -> Result<()> {
+        self.request(Method::PUT, key, blob)?
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Relay refused to store {}", key))?;
+        Ok(())
+    }
+
+    fn request(&self, method: Method, key: &str, body: Vec<u8>) -> Result<reqwest::RequestBuilder> {
+        match &self.relay {
+            SyncRelay::Http { url } => {
+                let mut request = self
+                    .http
+                    .request(method, format!("{}/{}", url.trim().trim_end_matches('/'), key))
+                    .body(body);
+                if let Some(token) = self.secret.as_deref().filter(|token| !token.is_empty()) {
+                    request = request.bearer_auth(token);
+                }
+                Ok(request)
+            }
+            SyncRelay::S3 {
+                endpoint,
+                bucket,
+                region,
+                access_key_id,
+            } => {
+                let endpoint = url::Url::parse(endpoint.trim()).context("Invalid S3 endpoint")?;
+                let host = match (endpoint.host_str(), endpoint.port()) {
+                    (Some(host), Some(port)) => format!("{}:{}", host, port),
+                    (Some(host), None) => host.to_string(),
+                    (None, _) => return Err(anyhow!("S3 endpoint has no host")),
+                };
+                let path = format!("/{}/{}", bucket.trim(), key);
+                let payload_sha256 = sha256_hex(&body);
+                let date = amz_date(OffsetDateTime::now_utc());
+                let credentials = S3Credentials {
+                    access_key_id: access_key_id.trim(),
+                    secret_access_key: self.secret.as_deref().unwrap_or_default(),
+                    region: region.trim(),
+                };
+                let authorization = s3_authorization(
+                    &credentials,
+                    method.as_str(),
+                    &path,
+                    &[
+                        ("host", host.as_str()),
+                        ("x-amz-content-sha256", payload_sha256.as_str()),
+                        ("x-amz-date", date.as_str()),
+                    ],
+                    &payload_sha256,
+                    &date,
+                );
+                Ok(self
+                    .http
+                    .request(method, format!("{}://{}{}", endpoint.scheme(), host, path))
+                    .header("x-amz-content-sha256", payload_sha256)
+                    .header("x-amz-date", date)
+                    .header("authorization", authorization)
+                    .body(body))
+            }
+        }
+    }
+}
+
+fn load_sync_encryption() -> Result<EnvelopeEncryption> {
+    let key = load_credential(&app_data_dir()?, SYNC_KEY_ACCOUNT)?
+        .ok_or_else(|| anyhow!("No sync key saved; register this device again"))?;
+    sync::sync_encryption(&key)
+}
+
+async fn load_devices(relay: &RelayClient, encryption: &EnvelopeEncryption) -> Result<Vec<SyncDevice>> {
+    match relay.get(DEVICES_KEY).await? {
+        Some(blob) => open_blob(encryption, &blob),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Upload `changes` as this device's next change set
+async fn upload_change_set(
+    relay: &RelayClient,
+    encryption: &EnvelopeEncryption,
+    cursor: &mut SyncCursor,
+    changes: Vec<SyncChange>,
+) -> Result<()> {
+    let change_set = ChangeSet {
+        device_id: cursor.device_id.clone(),
+        number: cursor.change_sets + 1,
+        created_at: OffsetDateTime::now_utc(),
+        changes,
+    };
+    relay
+        .put(
+            &change_set_key(&change_set.device_id, change_set.number),
+            seal_blob(encryption, &change_set)?,
+        )
+        .await?;
+    relay
+        .put(&head_key(&change_set.device_id), seal_blob(encryption, &change_set.number)?)
+        .await?;
+    cursor.change_sets = change_set.number;
+    Ok(())
+}
+
+/// The saved sync settings, read on the blocking pool
+async fn load_sync_settings(state: &AppState) -> Result<SyncSettings> {
+    state.db.run(|storage| storage.load_setting_or_default()).await
+}
+
+/// [`load_sync_settings`] for commands
+async fn sync_settings(state: &AppState) -> Result<SyncSettings, CommandError> {
+    load_sync_settings(state).await.map_err(|e| {
+        error!("Failed to load sync settings: {:#}", e);
+        CommandError::with_context(e, "Failed to load settings")
+    })
+}
+
+/// Save `settings` on the blocking pool and tell the frontend they changed
+async fn save_sync_settings(app: &AppHandle, state: &AppState, settings: SyncSettings) -> Result<(), CommandError> {
+    state
+        .db
+        .run(move |storage| storage.save_setting(&settings))
+        .await
+        .map_err(|e| {
+            error!("Failed to save sync settings: {:#}", e);
+            CommandError::with_context(e, "Failed to save settings")
+        })?;
+    notify_setting_changed(app, SyncSettings::KEY);
+    Ok(())
+}
+
+/// Save `cursor` on the blocking pool
+async fn save_cursor(state: &AppState, cursor: &SyncCursor) -> Result<()> {
+    let cursor = cursor.clone();
+    state.db.run(move |storage| storage.save_sync_cursor(&cursor)).await
+}
+
+/// Upload what changed here since the last upload, or a snapshot the first
+/// time and whenever the audit log was pruned past the last upload
+async fn upload_changes(
+    state: &AppState,
+    relay: &RelayClient,
+    encryption: &EnvelopeEncryption,
+    device_id: &str,
+    report: &mut SyncReport,
+) -> Result<()> {
+    let device = device_id.to_string();
+    let (mut cursor, pruned) = state
+        .db
+        .run(move |storage| {
+            let cursor = storage.sync_cursor(&device)?;
+            let pruned = storage.audit_log_pruned_after(cursor.audit_seq)?;
+            Ok((cursor, pruned))
+        })
+        .await?;
+    if cursor.change_sets == 0 || pruned {
+        let (seq, snapshot) = state
+            .db
+            .run(|storage| Ok((storage.last_audit_seq()?, storage.sync_snapshot()?)))
+            .await?;
+        // Only move the cursor once all of it is up, so a failed upload
+        // starts the snapshot again
+        let mut uploaded = cursor.clone();
+        for changes in snapshot.chunks(MAX_CHANGES_PER_SET) {
+            upload_change_set(relay, encryption, &mut uploaded, changes.to_vec()).await?;
+            report.uploaded_change_sets += 1;
+            report.uploaded_changes += changes.len();
+        }
+        cursor.change_sets = uploaded.change_sets;
+        cursor.audit_seq = seq;
+        cursor.synced_at = Some(OffsetDateTime::now_utc());
+        return save_cursor(state, &cursor).await;
+    }
+
+    loop {
+        let after = cursor.audit_seq;
+        let (changes, seq) = state
+            .db
+            .run(move |storage| storage.pending_sync_changes(after, MAX_CHANGES_PER_SET))
+            .await?;
+        if seq == after {
+            break;
+        }
+        if !changes.is_empty() {
+            report.uploaded_change_sets += 1;
+            report.uploaded_changes += changes.len();
+            upload_change_set(relay, encryption, &mut cursor, changes).await?;
+        }
+        cursor.audit_seq = seq;
+        cursor.synced_at = Some(OffsetDateTime::now_utc());
+        save_cursor(state, &cursor).await?;
+    }
+    Ok(())
+}
+
+/// Apply the change sets other devices uploaded since the last download
+async fn download_changes(
+    state: &AppState,
+    relay: &RelayClient,
+    encryption: &EnvelopeEncryption,
+    device_id: &str,
+    report: &mut SyncReport,
+) -> Result<()> {
+    for device in load_devices(relay, encryption).await? {
+        if device.id == device_id {
+            continue;
+        }
+        report.devices += 1;
+
+        let head: u64 = match relay.get(&head_key(&device.id)).await? {
+            Some(blob) => open_blob(encryption, &blob)?,
+            None => continue,
+        };
+        let device_id = device.id.clone();
+        let mut cursor = state.db.run(move |storage| storage.sync_cursor(&device_id)).await?;
+        while cursor.change_sets < head {
+            let number = cursor.change_sets + 1;
+            let blob = relay
+                .get(&change_set_key(&device.id, number))
+                .await?
+                .with_context(|| format!("Change set {} from {} is missing on the relay", number, device.name))?;
+            let change_set: ChangeSet = open_blob(encryption, &blob)?;
+            let applied = state
+                .db
+                .run(move |storage| storage.apply_sync_changes(&change_set.changes))
+                .await
+                .with_context(|| format!("Failed to apply change set {} from {}", number, device.name))?;
+
+            report.downloaded_change_sets += 1;
+            report.saved += applied.saved;
+            report.deleted += applied.deleted;
+            report.skipped += applied.skipped;
+            cursor.change_sets = number;
+            cursor.synced_at = Some(OffsetDateTime::now_utc());
+            save_cursor(state, &cursor).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Upload this device's changes, then apply everyone else's
+async fn sync_with_relay(state: &AppState) -> Result<(SyncSettings, SyncReport)> {
+    let _guard = SYNC_LOCK.lock().await;
+    let mut settings = load_sync_settings(state).await?;
+    let (Some(relay), Some(device_id)) = (settings.relay.clone(), settings.device_id.clone()) else {
+        return Err(anyhow!("Register this device with a sync relay first"));
+    };
+    let relay = RelayClient::new(relay)?;
+    let encryption = load_sync_encryption()?;
+
+    let mut report = SyncReport::default();
+    upload_changes(state, &relay, &encryption, &device_id, &mut report).await?;
+    download_changes(state, &relay, &encryption, &device_id, &mut report).await?;
+
+    settings.last_synced = Some(OffsetDateTime::now_utc());
+    let saved = settings.clone();
+    state.db.run(move |storage| storage.save_setting(&saved)).await?;
+    info!(
+        "Synced: uploaded {} changes, saved {} and deleted {} from {} devices",
+        report.uploaded_changes, report.saved, report.deleted, report.devices
+    );
+    Ok((settings, report))
+}
+
+/// Sync every 15 minutes while sync is on
+pub async fn run_sync_loop(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(SYNC_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        if state.key_provider.is_locked() || !state.connectivity.is_online() {
+            continue;
+        }
+        let enabled = load_sync_settings(&state)
+            .await
+            .is_ok_and(|settings| settings.enabled);
+        if !enabled {
+            continue;
+        }
+
+        if let Err(e) = sync_with_relay(&state).await {
+            // Try again at the next interval
+            warn!("Sync failed: {:#}", e);
+        }
+    }
+}
+
+async fn sync_status(state: &AppState) -> Result<SyncStatus, CommandError> {
+    let (settings, cursors) = state
+        .db
+        .run(|storage| Ok((storage.load_setting_or_default::<SyncSettings>()?, storage.list_sync_cursors()?)))
+        .await
+        .map_err(|e| {
+            error!("Failed to load sync status: {:#}", e);
+            CommandError::with_context(e, "Failed to load sync status")
+        })?;
+    let saved = |account| {
+        app_data_dir()
+            .and_then(|dir| load_credential(&dir, account))
+            .is_ok_and(|secret| secret.is_some())
+    };
+    Ok(SyncStatus {
+        settings,
+        secret_saved: saved(RELAY_SECRET_ACCOUNT),
+        sync_key_saved: saved(SYNC_KEY_ACCOUNT),
+        cursors,
+    })
+}
+
+/// Save `secret` under `account`; an empty one removes it
+fn save_credential(account: &str, secret: &str) -> Result<()> {
+    let dir = app_data_dir()?;
+    if secret.is_empty() {
+        delete_credential(&dir, account)
+    } else {
+        store_credential(&dir, account, secret)
+    }
+}
+
+// ========== Sync Commands ==========
+
+/// Gets the sync settings and how far sync has got with each device
+#[tauri::command]
+pub async fn get_sync_status(state: State<'_, Arc<AppState>>) -> Result<SyncStatus, CommandError> {
+    sync_status(&state).await
+}
+
+/// Register this device with a relay and turn sync on
+///
+/// `secret` replaces the stored relay secret when given; an empty string
+/// removes it. Give the `sync_key` shown on another device to join its
+/// devices; without one the saved key is kept, or a new one generated for
+/// the first device.
+#[tauri::command]
+pub async fn register_sync_device(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    relay: SyncRelay,
+    device_name: String,
+    secret: Option<String>,
+    sync_key: Option<String>,
+) -> Result<SyncStatus, CommandError> {
+    let device_name = device_name.trim().to_string();
+    if device_name.is_empty() {
+        return Err(CommandError::invalid_input("Name this device"));
+    }
+    let current = sync_settings(&state).await?;
+    let device_id = current
+        .device_id
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let settings = SyncSettings {
+        enabled: true,
+        relay: Some(relay.clone()),
+        device_id: Some(device_id.clone()),
+        device_name: device_name.clone(),
+        last_synced: current.last_synced,
+    };
+    settings.validate().map_err(CommandError::invalid_input)?;
+
+    if let Some(sync_key) = sync_key.as_deref().map(str::trim).filter(|key| !key.is_empty()) {
+        sync::sync_encryption(sync_key).map_err(|e| CommandError::with_context(e, "Invalid sync key"))?;
+        save_credential(SYNC_KEY_ACCOUNT, sync_key)
+            .map_err(|e| CommandError::with_context(e, "Failed to save sync key"))?;
+    } else if app_data_dir()
+        .and_then(|dir| load_credential(&dir, SYNC_KEY_ACCOUNT))
+        .map_err(|e| CommandError::with_context(e, "Failed to read sync key"))?
+        .is_none()
+    {
+        sync::generate_sync_key()
+            .and_then(|key| save_credential(SYNC_KEY_ACCOUNT, &key))
+            .map_err(|e| CommandError::with_context(e, "Failed to create sync key"))?;
+    }
+    if let Some(secret) = secret {
+        save_credential(RELAY_SECRET_ACCOUNT, &secret)
+            .map_err(|e| CommandError::with_context(e, "Failed to save relay secret"))?;
+    }
+
+    let registered = async {
+        let client = RelayClient::new(relay)?;
+        let encryption = load_sync_encryption()?;
+        let mut devices = load_devices(&client, &encryption).await?;
+        match devices.iter_mut().find(|device| device.id == device_id) {
+            Some(device) => device.name = device_name.clone(),
+            None => devices.push(SyncDevice {
+                id: device_id.clone(),
+                name: device_name.clone(),
+                registered_at: OffsetDateTime::now_utc(),
+            }),
+        }
+        client.put(DEVICES_KEY, seal_blob(&encryption, &devices)?).await
+    };
+    registered.await.map_err(|e| {
+        error!("Failed to register with sync relay: {:#}", e);
+        CommandError::with_context(e, "Failed to register with the sync relay")
+    })?;
+
+    save_sync_settings(&app, &state, settings).await?;
+    info!("Registered device {} for sync", device_id);
+    sync_status(&state).await
+}
+
+/// Turn sync on or off; the device stays registered
+#[tauri::command]
+pub async fn set_sync_enabled(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    enabled: bool,
+) -> Result<SyncStatus, CommandError> {
+    let mut settings = sync_settings(&state).await?;
+    settings.enabled = enabled;
+    save_sync_settings(&app, &state, settings).await?;
+    sync_status(&state).await
+}
+
+/// The sync key, to enter when registering another device
+#[tauri::command]
+pub async fn get_sync_key() -> Result<String, CommandError> {
+    app_data_dir()
+        .and_then(|dir| load_credential(&dir, SYNC_KEY_ACCOUNT))
+        .map_err(|e| CommandError::with_context(e, "Failed to read sync key"))?
+        .ok_or_else(|| CommandError::not_found("No sync key saved; register this device first"))
+}
+
+/// Devices registered with the relay
+#[tauri::command]
+pub async fn list_sync_devices(state: State<'_, Arc<AppState>>) -> Result<Vec<SyncDevice>, CommandError> {
+    let settings = sync_settings(&state).await?;
+    let relay = settings
+        .relay
+        .ok_or_else(|| CommandError::invalid_input("No sync relay set up"))?;
+    let devices = async {
+        let client = RelayClient::new(relay)?;
+        load_devices(&client, &load_sync_encryption()?).await
+    };
+    devices.await.map_err(|e| {
+        error!("Failed to list sync devices: {:#}", e);
+        CommandError::with_context(e, "Failed to list sync devices")
+    })
+}
+
+/// Sync with the relay now instead of waiting for the background job
+#[tauri::command]
+pub async fn sync_now(app: AppHandle, state: State<'_, Arc<AppState>>) -> Result<SyncReport, CommandError> {
+    let (_, report) = sync_with_relay(&state).await.map_err(|e| {
+        error!("Sync failed: {:#}", e);
+        CommandError::with_context(e, "Sync failed")
+    })?;
+    notify_setting_changed(&app, SyncSettings::KEY);
+    Ok(report)
+}
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use peptrack_core::{TrashEntityType, TrashItem, TrashSettings, UndoAction};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use time::format_description::well_known::Rfc3339;
+use tracing::{error, info, warn};
+
+use crate::commands::settings::{load_setting_or_default, save_setting};
+use crate::commands::undo::{records, trash_noun};
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// How often expired records are purged while the app is running
+const PURGE_INTERVAL_SECS: u64 = 6 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashListItem {
+    pub entity_type: TrashEntityType,
+    pub entity_id: String,
+    pub label: String,
+    pub protocol_id: Option<String>,
+    pub deleted_at: String,
+    /// When the record will be permanently deleted
+    pub purge_at: String,
+}
+
+impl TrashListItem {
+    fn new(item: TrashItem, retention_days: u32) -> Self {
+        let format = |date: time::OffsetDateTime| date.format(&Rfc3339).unwrap_or_else(|_| date.to_string());
+        let purge_at = item.deleted_at + time::Duration::days(retention_days.into());
+
+        Self {
+            entity_type: item.entity_type,
+            entity_id: item.entity_id,
+            label: item.label,
+            protocol_id: item.protocol_id,
+            deleted_at: format(item.deleted_at),
+            purge_at: format(purge_at),
+        }
+    }
+}
+
+/// Load the saved trash settings, falling back to the defaults
+pub async fn load_settings(state: &AppState) -> TrashSettings {
+    load_setting_or_default(state).await
+}
+
+/// Purge expired records now and then every few hours
+pub async fn run_purge_loop(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(PURGE_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        let retention_days = load_settings(&state).await.retention_days;
+        if let Err(e) = state.db.run(move |storage| storage.purge_trash(retention_days)).await {
+            warn!("Trash purge failed: {:#}", e);
+        }
+    }
+}
+
+/// Move records to the trash instead of deleting them
+pub(crate) async fn move_to_trash(
+    state: &AppState,
+    entity_type: TrashEntityType,
+    ids: &[String],
+) -> Result<usize, CommandError> {
+    let trashed = ids.to_vec();
+    let moved = state
+        .db
+        .run(move |storage| storage.move_to_trash(entity_type, &trashed))
+        .await
+        .map_err(|e| {
+            error!("Failed to move records to trash: {:#}", e);
+            CommandError::with_context(e, "Failed to move to trash")
+        })?;
+    if moved > 0 {
+        state.undo.push(
+            format!("Delete {}", records(moved, trash_noun(entity_type))),
+            UndoAction::RestoreFromTrash { entity_type, ids: ids.to_vec() },
+        );
+    }
+    Ok(moved)
+}
+
+// ========== Trash Commands ==========
+
+/// List everything in the trash, most recently deleted first
+#[tauri::command]
+pub async fn list_trash(state: State<'_, Arc<AppState>>) -> Result<Vec<TrashListItem>, CommandError> {
+    let items = state
+        .db
+        .run(|storage| storage.list_trash())
+        .await
+        .map_err(|e| {
+            error!("Failed to list trash: {:#}", e);
+            CommandError::with_context(e, "Failed to list trash")
+        })?;
+
+    let retention_days = load_settings(&state).await.retention_days;
+    Ok(items
+        .into_iter()
+        .map(|item| TrashListItem::new(item, retention_days))
+        .collect())
+}
+
+/// Restore records from the trash; returns how many were restored
+#[tauri::command]
+pub async fn restore_from_trash(
+    state: State<'_, Arc<AppState>>,
+    entity_type: TrashEntityType,
+    ids: Vec<String>,
+) -> Result<usize, CommandError> {
+    let restored = state
+        .db
+        .run(move |storage| storage.restore_from_trash(entity_type, &ids))
+        .await
+        .map_err(|e| {
+            error!("Failed to restore from trash: {:#}", e);
+            CommandError::with_context(e, "Failed to restore")
+        })?;
+
+    info!("Restored {} records from the trash", restored);
+    Ok(restored)
+}
+
+/// Permanently delete everything in the trash
+#[tauri::command]
+pub async fn empty_trash(state: State<'_, Arc<AppState>>) -> Result<usize, CommandError> {
+    state
+        .db
+        .run(|storage| storage.purge_trash(0))
+        .await
+        .map_err(|e| {
+            error!("Failed to empty trash: {:#}", e);
+            CommandError::with_context(e, "Failed to empty trash")
+        })
+}
+
+/// Gets how long records stay in the trash
+#[tauri::command]
+pub async fn get_trash_settings(state: State<'_, Arc<AppState>>) -> Result<TrashSettings, CommandError> {
+    Ok(load_settings(&state).await)
+}
+
+/// Saves how long records stay in the trash and purges anything now expired
+#[tauri::command]
+pub async fn update_trash_settings(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    settings: TrashSettings,
+) -> Result<usize, CommandError> {
+    save_setting(&app, &state, &settings).await?;
+
+    state
+        .db
+        .run(move |storage| storage.purge_trash(settings.retention_days))
+        .await
+        .map_err(|e| {
+            error!("Failed to purge trash: {:#}", e);
+            CommandError::with_context(e, "Failed to purge trash")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_list_item_reports_purge_date() {
+        let item = TrashItem {
+            entity_type: TrashEntityType::DoseLog,
+            entity_id: "dose-1".to_string(),
+            label: "0.25 mg dose".to_string(),
+            protocol_id: Some("protocol-1".to_string()),
+            deleted_at: datetime!(2024-03-01 12:00 UTC),
+        };
+
+        let listed = TrashListItem::new(item, 30);
+        assert_eq!(listed.deleted_at, "2024-03-01T12:00:00Z");
+        assert_eq!(listed.purge_at, "2024-03-31T12:00:00Z");
+    }
+
+    #[test]
+    fn test_entity_type_deserialization() {
+        let entity_type: TrashEntityType = serde_json::from_str(r#""body_metric""#).unwrap();
+        assert_eq!(entity_type, TrashEntityType::BodyMetric);
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use peptrack_core::{TrashEntityType, UndoSummary};
+use serde::Serialize;
+use tauri::State;
+use time::format_description::well_known::Rfc3339;
+use tracing::{error, info, warn};
+
+use crate::error::CommandError;
+use crate::state::AppState;
+
+/// A change that can be undone
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoListItem {
+    pub id: String,
+    /// What was done, e.g. "Delete 3 dose logs"
+    pub label: String,
+    /// Records undoing it puts back
+    pub records: usize,
+    /// RFC3339
+    pub performed_at: String,
+}
+
+impl From<UndoSummary> for UndoListItem {
+    fn from(summary: UndoSummary) -> Self {
+        Self {
+            id: summary.id,
+            label: summary.label,
+            records: summary.records,
+            performed_at: summary
+                .performed_at
+                .format(&Rfc3339)
+                .unwrap_or_else(|_| summary.performed_at.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoResult {
+    /// The change that was undone
+    pub label: String,
+    pub restored: usize,
+}
+
+/// Current copies of the records with `ids`, to save again on undo
+///
+/// Records that can't be read are left out, so the change still goes ahead.
+pub(crate) fn snapshot<T>(ids: &[String], get: impl Fn(&str) -> anyhow::Result<Option<T>>) -> Vec<T> {
+    ids.iter()
+        .filter_map(|id| {
+            get(id).unwrap_or_else(|e| {
+                warn!("Failed to keep {} for undo: {:#}", id, e);
+                None
+            })
+        })
+        .collect()
+}
+
+/// `count` of `noun`, e.g. "1 dose log" or "3 dose logs"
+pub(crate) fn records(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+pub(crate) fn trash_noun(entity_type: TrashEntityType) -> &'static str {
+    match entity_type {
+        TrashEntityType::Protocol => "protocol",
+        TrashEntityType::DoseLog => "dose log",
+        TrashEntityType::BodyMetric => "body metric",
+    }
+}
+
+// ========== Undo Commands ==========
+
+/// Changes that can be undone, most recent first
+#[tauri::command]
+pub async fn list_undo_operations(state: State<'_, Arc<AppState>>) -> Result<Vec<UndoListItem>, CommandError> {
+    Ok(state.undo.summaries().into_iter().map(UndoListItem::from).collect())
+}
+
+/// Undo the most recent delete or bulk edit made this session
+///
+/// If it can't be undone, it stays on the stack so it can be retried.
+#[tauri::command]
+pub async fn undo_last_operation(state: State<'_, Arc<AppState>>) -> Result<UndoResult, CommandError> {
+    let operation = state
+        .undo
+        .pop()
+        .ok_or_else(|| CommandError::not_found("Nothing to undo"))?;
+
+    let action = operation.action.clone();
+    match state.db.run(move |storage| storage.undo(&action)).await {
+        Ok(restored) => {
+            info!("Undid '{}', restoring {} records", operation.label, restored);
+            Ok(UndoResult { label: operation.label, restored })
+        }
+        Err(e) => {
+            error!("Failed to undo '{}': {:#}", operation.label, e);
+            let label = operation.label.clone();
+            state.undo.push_operation(operation);
+            Err(CommandError::with_context(e, format!("Failed to undo {}", label)))
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! Read-only viewer for shared exports
+//!
+//! Opens a backup file in a session held only in memory, so a coach or
+//! practitioner can look through protocols, adherence and body metrics
+//! without the app touching the live database. The session needs no
+//! database key; an encrypted export only needs its own passphrase.
+//! Nothing here writes to the export or to storage.
+
+use peptrack_core::backup::BackupData;
+use peptrack_core::{BodyMetric, DoseLog, PeptideProtocol};
+use peptrack_reports::{ReportInput, ReportSummary, ScheduledDoses};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tauri::State;
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::commands::dates::parse_date;
+use crate::commands::restore::read_backup_file;
+use crate::error::CommandError;
+
+/// The export currently open in the viewer, if any
+#[derive(Default)]
+pub struct ViewerState {
+    session: Mutex<Option<ViewerSession>>,
+}
+
+pub struct ViewerSession {
+    file_name: String,
+    export_date: String,
+    app_version: String,
+    anonymized: bool,
+    protocols: Vec<PeptideProtocol>,
+    doses: Vec<DoseLog>,
+    body_metrics: Vec<BodyMetric>,
+    schedules: Vec<ScheduledDoses>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewerSessionInfo {
+    pub file_name: String,
+    pub export_date: String,
+    pub app_version: String,
+    /// Names are pseudonyms and notes were removed
+    pub anonymized: bool,
+    pub protocols_count: usize,
+    pub dose_logs_count: usize,
+    pub body_metrics_count: usize,
+    /// False for exports made before schedules were included, which can't
+    /// show adherence
+    pub has_schedules: bool,
+    pub first_dose_date: Option<String>,
+    pub last_dose_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewerProtocolRow {
+    pub name: String,
+    pub peptide_name: String,
+    pub dose_count: usize,
+    pub total_mg: f32,
+    pub last_dose: Option<String>,
+    pub scheduled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewerAdherenceRow {
+    pub protocol_name: String,
+    pub expected: usize,
+    pub logged: usize,
+    pub percent: f32,
+}
+
+/// Dose and adherence figures for a date range of the open export
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewerSummary {
+    pub start_date: String,
+    pub end_date: String,
+    pub total_doses: usize,
+    pub overall_adherence: Option<f32>,
+    pub protocols: Vec<ViewerProtocolRow>,
+    pub adherence: Vec<ViewerAdherenceRow>,
+}
+
+/// Deserialize backup records, skipping ones this version can't read
+fn parse_records<T: DeserializeOwned>(values: Vec<serde_json::Value>, kind: &str) -> Vec<T> {
+    values
+        .into_iter()
+        .filter_map(|value| {
+            serde_json::from_value(value)
+                .map_err(|e| warn!("Skipping unreadable {} in export: {:#}", kind, e))
+                .ok()
+        })
+        .collect()
+}
+
+impl ViewerSession {
+    fn from_backup(file_name: String, backup: BackupData) -> Self {
+        let mut doses: Vec<DoseLog> = parse_records(backup.dose_logs, "dose log");
+        doses.sort_by_key(|dose| dose.logged_at);
+        let mut body_metrics: Vec<BodyMetric> = parse_records(backup.body_metrics, "body metric");
+        body_metrics.sort_by_key(|metric| metric.date);
+
+        Self {
+            file_name,
+            export_date: backup.metadata.export_date,
+            app_version: backup.metadata.app_version,
+            anonymized: backup.metadata.anonymized,
+            protocols: parse_records(backup.protocols, "protocol"),
+            doses,
+            body_metrics,
+            schedules: backup
+                .dose_schedules
+                .into_iter()
+                .map(|schedule| ScheduledDoses {
+                    protocol_id: schedule.protocol_id,
+                    amount_mg: schedule.amount_mg,
+                    days_of_week: schedule.days_of_week,
+                })
+                .collect(),
+        }
+    }
+
+    fn info(&self) -> ViewerSessionInfo {
+        ViewerSessionInfo {
+            file_name: self.file_name.clone(),
+            export_date: self.export_date.clone(),
+            app_version: self.app_version.clone(),
+            anonymized: self.anonymized,
+            protocols_count: self.protocols.len(),
+            dose_logs_count: self.doses.len(),
+            body_metrics_count: self.body_metrics.len(),
+            has_schedules: !self.schedules.is_empty(),
+            first_dose_date: self.doses.first().map(|dose| dose.logged_at.date().to_string()),
+            last_dose_date: self.doses.last().map(|dose| dose.logged_at.date().to_string()),
+        }
+    }
+
+    fn summary(&self, start: time::Date, end: time::Date, now: OffsetDateTime) -> ViewerSummary {
+        let input = ReportInput {
+            start,
+            end,
+            protocols: &self.protocols,
+            doses: &self.doses,
+            schedules: &self.schedules,
+            body_metrics: &self.body_metrics,
+            side_effects: &[],
+            inventory: &[],
+        };
+        let summary = ReportSummary::build(&input, now);
+
+        ViewerSummary {
+            start_date: summary.start.to_string(),
+            end_date: summary.end.to_string(),
+            total_doses: summary.total_doses,
+            overall_adherence: summary.overall_adherence,
+            protocols: summary
+                .protocols
+                .into_iter()
+                .map(|protocol| ViewerProtocolRow {
+                    name: protocol.name,
+                    peptide_name: protocol.peptide_name,
+                    dose_count: protocol.dose_count,
+                    total_mg: protocol.total_mg,
+                    last_dose: protocol.last_dose.map(|date| date.to_string()),
+                    scheduled: protocol.scheduled,
+                })
+                .collect(),
+            adherence: summary
+                .adherence
+                .into_iter()
+                .map(|row| ViewerAdherenceRow {
+                    protocol_name: row.protocol_name,
+                    expected: row.expected,
+                    logged: row.logged,
+                    percent: row.percent,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn no_session() -> CommandError {
+    CommandError::not_found("No export is open in the viewer")
+}
+
+// ========== Viewer Commands ==========
+
+/// Open an export in a read-only session, replacing any open one
+///
+/// `password` is the export's own passphrase; the main database can stay
+/// locked.
+#[tauri::command]
+pub async fn open_viewer_session(
+    viewer: State<'_, ViewerState>,
+    file_path: String,
+    password: Option<String>,
+) -> Result<ViewerSessionInfo, CommandError> {
+    let backup = read_backup_file(&file_path, password.as_deref())
+        .map_err(|e| CommandError::with_context(e, "Failed to open export"))?;
+
+    let file_name = std::path::Path::new(&file_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| file_path.clone());
+    let session = ViewerSession::from_backup(file_name, backup);
+    let info = session.info();
+    info!(
+        "Opened {} in the viewer: {} protocols, {} doses, {} body metrics",
+        info.file_name, info.protocols_count, info.dose_logs_count, info.body_metrics_count
+    );
+
+    *viewer.session.lock().await = Some(session);
+    Ok(info)
+}
+
+/// The open export, or `None` when the viewer is closed
+#[tauri::command]
+pub async fn get_viewer_session(
+    viewer: State<'_, ViewerState>,
+) -> Result<Option<ViewerSessionInfo>, CommandError> {
+    Ok(viewer.session.lock().await.as_ref().map(ViewerSession::info))
+}
+
+/// Close the viewer and drop the export's data from memory
+#[tauri::command]
+pub async fn close_viewer_session(viewer: State<'_, ViewerState>) -> Result<(), CommandError> {
+    viewer.session.lock().await.take();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_viewer_protocols(
+    viewer: State<'_, ViewerState>,
+) -> Result<Vec<PeptideProtocol>, CommandError> {
+    let session = viewer.session.lock().await;
+    let session = session.as_ref().ok_or_else(no_session)?;
+    Ok(session.protocols.clone())
+}
+
+/// Dose logs in the export, oldest first
+#[tauri::command]
+pub async fn list_viewer_dose_logs(
+    viewer: State<'_, ViewerState>,
+) -> Result<Vec<DoseLog>, CommandError> {
+    let session = viewer.session.lock().await;
+    let session = session.as_ref().ok_or_else(no_session)?;
+    Ok(session.doses.clone())
+}
+
+/// Body metrics in the export, oldest first
+#[tauri::command]
+pub async fn list_viewer_body_metrics(
+    viewer: State<'_, ViewerState>,
+) -> Result<Vec<BodyMetric>, CommandError> {
+    let session = viewer.session.lock().await;
+    let session = session.as_ref().ok_or_else(no_session)?;
+    Ok(session.body_metrics.clone())
+}
+
+/// Dose counts and adherence between two RFC3339 dates, both days included
+#[tauri::command]
+pub async fn get_viewer_summary(
+    viewer: State<'_, ViewerState>,
+    start_date: String,
+    end_date: String,
+) -> Result<ViewerSummary, CommandError> {
+    let start = parse_date(&start_date)?;
+    let end = parse_date(&end_date)?;
+    if start > end {
+        return Err(CommandError::invalid_input(
+            "Start date must not be after the end date",
+        ));
+    }
+
+    let session = viewer.session.lock().await;
+    let session = session.as_ref().ok_or_else(no_session)?;
+    Ok(session.summary(start, end, OffsetDateTime::now_utc()))
+}
+
+#[cfg(test)]
+mod tests {
+    use peptrack_core::backup::{BackupMetadata, BackupSchedule};
+    use time::macros::{date, datetime};
+
+    use super::*;
+
+    #[test]
+    fn session_reads_export_and_works_out_adherence() {
+        let protocol = PeptideProtocol::new("Healing", "BPC-157");
+        let mut doses = Vec::new();
+        for logged_at in [datetime!(2025-03-05 08:00 UTC), datetime!(2025-03-03 08:00 UTC)] {
+            let mut dose = DoseLog::new(protocol.id.as_str(), "abdomen", 0.25);
+            dose.logged_at = logged_at;
+            doses.push(serde_json::to_value(dose).unwrap());
+        }
+        let mut metric = BodyMetric::new(datetime!(2025-03-04 07:00 UTC));
+        metric.weight_kg = Some(80.5);
+
+        let backup = BackupData {
+            metadata: BackupMetadata {
+                export_date: "2025-03-08T10:00:00Z".to_string(),
+                protocols_count: 1,
+                doses_count: 2,
+                literature_count: 0,
+                app_version: "0.1.0".to_string(),
+                anonymized: false,
+            },
+            protocols: vec![serde_json::to_value(&protocol).unwrap()],
+            dose_logs: doses,
+            literature: vec![],
+            attachments: vec![],
+            body_metrics: vec![serde_json::to_value(&metric).unwrap(), serde_json::json!({"id": 7})],
+            dose_schedules: vec![BackupSchedule {
+                protocol_id: protocol.id.clone(),
+                amount_mg: 0.25,
+                // Mondays, Wednesdays and Fridays
+                days_of_week: vec![1, 3, 5],
+            }],
+            journal_entries: vec![],
+        };
+
+        let session = ViewerSession::from_backup("export.json".to_string(), backup);
+        let info = session.info();
+        assert_eq!(info.body_metrics_count, 1);
+        assert!(info.has_schedules);
+        assert_eq!(info.first_dose_date.as_deref(), Some("2025-03-03"));
+
+        let summary = session.summary(
+            date!(2025 - 03 - 03),
+            date!(2025 - 03 - 09),
+            datetime!(2025-03-10 12:00 UTC),
+        );
+        assert_eq!(summary.total_doses, 2);
+        assert_eq!(summary.protocols[0].dose_count, 2);
+        assert_eq!(summary.adherence[0].expected, 3);
+        assert_eq!(summary.adherence[0].logged, 2);
+    }
+}
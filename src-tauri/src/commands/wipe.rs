@@ -0,0 +1,303 @@
+//! Factory reset: removing all user data from this machine
+//!
+//! Wiping takes two calls. [`request_secure_wipe`] hands out a short-lived
+//! token, and [`secure_wipe_database`] only runs with that token, so a
+//! single stray call can't erase anything.
+
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use peptrack_core::settings::MIGRATED_SUFFIX;
+use rand::RngCore;
+use serde::Serialize;
+use tauri::State;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::commands::security::remove_stored_key;
+use crate::error::CommandError;
+use crate::logging::LOG_DIR_NAME;
+use crate::state::{self, app_data_dir, AppState, CREDENTIAL_ACCOUNTS};
+
+/// How long a wipe token stays valid
+const WIPE_TOKEN_TTL: Duration = Duration::from_secs(120);
+
+/// The wipe token waiting to be confirmed, if any
+#[derive(Default)]
+pub struct SecureWipeState {
+    pending: Mutex<Option<PendingWipe>>,
+}
+
+struct PendingWipe {
+    token: String,
+    expires_at: Instant,
+}
+
+impl SecureWipeState {
+    /// Issue a new token, replacing any earlier one
+    async fn issue(&self) -> String {
+        let mut bytes = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let token = hex::encode(bytes);
+        *self.pending.lock().await = Some(PendingWipe {
+            token: token.clone(),
+            expires_at: Instant::now() + WIPE_TOKEN_TTL,
+        });
+        token
+    }
+
+    /// Whether `token` is the current, unexpired token; it's used up either way
+    async fn redeem(&self, token: &str) -> bool {
+        match self.pending.lock().await.take() {
+            Some(pending) => pending.token == token && Instant::now() < pending.expires_at,
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecureWipeToken {
+    pub token: String,
+    pub expires_in_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecureWipeResult {
+    pub rows_deleted: usize,
+    /// Config, token, key and log files removed, relative to the data
+    /// directory
+    pub files_removed: Vec<String>,
+}
+
+/// Whether `name` is a config or token file, including the `.migrated`
+/// copies left when settings moved into the database and the `.pending`
+/// passphrase config of an unfinished passphrase change
+fn is_config_file(name: &str) -> bool {
+    name.ends_with(".json") || name.ends_with(".json.pending") || name.ends_with(MIGRATED_SUFFIX)
+}
+
+/// Config and token files in `data_dir`, the key files and the log files
+fn ancillary_files(data_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(data_dir).context("Failed to read data directory")? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_file() && is_config_file(&name) {
+            files.push(path);
+        }
+    }
+    for name in [state::KEY_FILE_NAME, state::PENDING_KEY_FILE_NAME] {
+        let path = data_dir.join(name);
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+
+    let log_dir = data_dir.join(LOG_DIR_NAME);
+    if log_dir.is_dir() {
+        for entry in std::fs::read_dir(&log_dir).context("Failed to read log directory")? {
+            let path = entry?.path();
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Overwrite `path` with zeros before removing it
+fn shred_file(path: &Path) -> Result<()> {
+    let len = std::fs::metadata(path)?.len();
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+    file.write_all(&vec![0u8; len as usize])?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+/// Remove the files and Keychain items kept outside the database
+fn remove_ancillary_data() -> Result<Vec<String>> {
+    let data_dir = app_data_dir()?;
+    remove_stored_key();
+    remove_ancillary_data_in(&data_dir)
+}
+
+/// Delete every stored credential, then shred the ancillary files in
+/// `data_dir` and remove the emptied log directory
+fn remove_ancillary_data_in(data_dir: &Path) -> Result<Vec<String>> {
+    for account in CREDENTIAL_ACCOUNTS {
+        if let Err(e) = peptrack_core::delete_credential(data_dir, account) {
+            warn!("Failed to delete stored {} credential: {:#}", account, e);
+        }
+    }
+
+    let mut removed = Vec::new();
+    for path in ancillary_files(data_dir)? {
+        shred_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        removed.push(
+            path.strip_prefix(data_dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string(),
+        );
+    }
+
+    let log_dir = data_dir.join(LOG_DIR_NAME);
+    if let Err(e) = std::fs::remove_dir(&log_dir) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove log directory: {}", e);
+        }
+    }
+    Ok(removed)
+}
+
+// ========== Wipe Commands ==========
+
+/// Start a secure wipe; pass the returned token to [`secure_wipe_database`]
+#[tauri::command]
+pub async fn request_secure_wipe(
+    wipe: State<'_, SecureWipeState>,
+) -> Result<SecureWipeToken, CommandError> {
+    info!("Secure wipe requested");
+    Ok(SecureWipeToken {
+        token: wipe.issue().await,
+        expires_in_secs: WIPE_TOKEN_TTL.as_secs(),
+    })
+}
+
+/// Permanently delete all user data
+///
+/// Every row is deleted from the database and the freed space, WAL
+/// included, is overwritten, and migration snapshots and recovery copies
+/// are deleted. Config, token and log files are shredded, including copies
+/// left by settings migrations, and the encryption key and every stored
+/// credential are removed, from the Keychain too.
+/// The database stays locked afterwards; the app should be restarted, which
+/// sets it up again with a new key.
+///
+/// Works while the database is locked, so a forgotten passphrase can be
+/// recovered from by starting over.
+#[tauri::command]
+pub async fn secure_wipe_database(
+    state: State<'_, Arc<AppState>>,
+    wipe: State<'_, SecureWipeState>,
+    token: String,
+) -> Result<SecureWipeResult, CommandError> {
+    if !wipe.redeem(&token).await {
+        return Err(CommandError::invalid_input(
+            "Wipe token is invalid or expired; request a new one",
+        ));
+    }
+
+    info!("Securely wiping all user data");
+    let rows_deleted = state.db.run(|storage| storage.secure_wipe()).await.map_err(|e| {
+        error!("Failed to wipe database: {:#}", e);
+        CommandError::with_context(e, "Failed to wipe database")
+    })?;
+    if let Err(e) = state.db.run(|storage| storage.delete_migration_snapshots()).await {
+        warn!("Failed to remove migration snapshots during wipe: {:#}", e);
+    }
+    if let Err(e) = state.db.run(|storage| storage.delete_recovery_files()).await {
+        warn!("Failed to remove recovery files during wipe: {:#}", e);
+    }
+
+    state.key_provider.forget_key();
+    if let Some(biometric) = &state.biometric {
+        biometric.end_session();
+    }
+
+    let files_removed = remove_ancillary_data().map_err(|e| {
+        error!("Failed to remove config files during wipe: {:#}", e);
+        CommandError::with_context(e, "Database wiped, but some files could not be removed")
+    })?;
+
+    info!(
+        "Secure wipe complete: {} rows, {} files",
+        rows_deleted,
+        files_removed.len()
+    );
+    Ok(SecureWipeResult {
+        rows_deleted,
+        files_removed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn wipe_token_is_single_use() {
+        let wipe = SecureWipeState::default();
+        let token = wipe.issue().await;
+
+        assert!(!wipe.redeem("not-the-token").await);
+        // A wrong guess uses the token up
+        assert!(!wipe.redeem(&token).await);
+
+        let token = wipe.issue().await;
+        assert!(wipe.redeem(&token).await);
+        assert!(!wipe.redeem(&token).await);
+    }
+
+    #[test]
+    fn ancillary_files_are_config_and_key_files() {
+        let dir = std::env::temp_dir().join(format!("peptrack-wipe-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(LOG_DIR_NAME)).unwrap();
+        for name in [
+            "drive_tokens.json",
+            "drive_tokens.json.migrated",
+            "passphrase.json.pending",
+            state::KEY_FILE_NAME,
+            "logs/peptrack.2026-10-17.jsonl",
+            "peptrack.sqlite",
+            "notes.txt",
+        ] {
+            std::fs::write(dir.join(name), b"secret").unwrap();
+        }
+        // On macOS credentials live in the Keychain, which tests leave alone
+        #[cfg(not(target_os = "macos"))]
+        for account in CREDENTIAL_ACCOUNTS {
+            peptrack_core::store_credential(&dir, account, "secret").unwrap();
+        }
+
+        let files = ancillary_files(&dir).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        #[cfg(not(target_os = "macos"))]
+        assert!(names.contains(&"credentials.json".to_string()));
+        for name in [
+            "drive_tokens.json",
+            "drive_tokens.json.migrated",
+            "passphrase.json.pending",
+            state::KEY_FILE_NAME,
+            "peptrack.2026-10-17.jsonl",
+        ] {
+            assert!(names.contains(&name.to_string()), "{} not collected", name);
+        }
+        assert!(!names.contains(&"peptrack.sqlite".to_string()));
+
+        let removed = remove_ancillary_data_in(&dir).unwrap();
+        let log_file = Path::new(LOG_DIR_NAME).join("peptrack.2026-10-17.jsonl");
+        assert!(removed.contains(&log_file.to_string_lossy().to_string()));
+        for account in CREDENTIAL_ACCOUNTS {
+            assert_eq!(peptrack_core::load_credential(&dir, account).unwrap(), None);
+        }
+        let mut left: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["notes.txt", "peptrack.sqlite"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
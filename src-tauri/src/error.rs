@@ -0,0 +1,247 @@
+//! Errors returned by Tauri commands
+//!
+//! Commands return [`CommandError`] so the frontend can tell "not found"
+//! from "database locked" from "network down" without parsing messages.
+//! Errors from storage, HTTP and the filesystem are categorized by looking
+//! through the anyhow error chain.
+
+use std::fmt;
+
+use peptrack_core::{DatabaseLocked, MigrationFailed};
+use rusqlite::ErrorCode;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+/// Broad category of a command failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    NotFound,
+    /// The request itself was wrong, e.g. a missing field or malformed file
+    InvalidInput,
+    /// Conflicts with existing data, e.g. a duplicate or a referenced record
+    Conflict,
+    /// The database is locked until the passphrase is entered
+    Locked,
+    /// Another operation is holding the database or a resource
+    Busy,
+    /// The database file or an encrypted value is damaged
+    Corrupted,
+    /// A schema upgrade failed; the database was rolled back to its snapshot
+    MigrationFailed,
+    Network,
+    PermissionDenied,
+    Internal,
+}
+
+impl ErrorKind {
+    /// Whether trying the same request again later may succeed
+    pub fn is_retryable(self) -> bool {
+        matches!(self, ErrorKind::Busy | ErrorKind::Network)
+    }
+}
+
+/// Error returned to the frontend as `{ kind, message, retryable, details }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandError {
+    pub kind: ErrorKind,
+    /// Shown to the user
+    pub message: String,
+    /// Full cause chain, for logs and bug reports
+    pub details: Option<String>,
+}
+
+impl CommandError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidInput, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Conflict, message)
+    }
+
+    pub fn busy(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Busy, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Internal, message)
+    }
+
+    /// Categorize `err` and prefix its message with `context`,
+    /// e.g. "Failed to save protocol: database disk image is malformed"
+    pub fn with_context(err: impl Into<anyhow::Error>, context: impl fmt::Display) -> Self {
+        let err = err.into();
+        let mut error = Self::from(err);
+        error.message = format!("{}: {}", context, error.message);
+        error
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.kind.is_retryable()
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CommandError", 4)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("message", &self.message)?;
+        state.serialize_field("retryable", &self.is_retryable())?;
+        state.serialize_field("details", &self.details)?;
+        state.end()
+    }
+}
+
+impl From<anyhow::Error> for CommandError {
+    fn from(err: anyhow::Error) -> Self {
+        let message = err.to_string();
+        let chain = format!("{:#}", err);
+        Self {
+            kind: classify(&err),
+            message,
+            details: (err.chain().count() > 1).then_some(chain),
+        }
+    }
+}
+
+/// Category of the first cause in the chain that is recognized
+fn classify(err: &anyhow::Error) -> ErrorKind {
+    err.chain()
+        .find_map(|cause| {
+            if cause.is::<DatabaseLocked>() {
+                return Some(ErrorKind::Locked);
+            }
+            if cause.is::<MigrationFailed>() {
+                return Some(ErrorKind::MigrationFailed);
+            }
+            if let Some(e) = cause.downcast_ref::<rusqlite::Error>() {
+                return classify_sqlite(e);
+            }
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                return Some(classify_http(e));
+            }
+            if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+                return classify_io(e);
+            }
+            if let Some(e) = cause.downcast_ref::<serde_json::Error>() {
+                return (!e.is_io()).then_some(ErrorKind::InvalidInput);
+            }
+            if cause.is::<time::error::Parse>() || cause.is::<base64::DecodeError>() {
+                return Some(ErrorKind::InvalidInput);
+            }
+            None
+        })
+        .unwrap_or(ErrorKind::Internal)
+}
+
+fn classify_sqlite(err: &rusqlite::Error) -> Option<ErrorKind> {
+    match err {
+        rusqlite::Error::QueryReturnedNoRows => Some(ErrorKind::NotFound),
+        rusqlite::Error::SqliteFailure(failure, _) => Some(match failure.code {
+            ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => ErrorKind::Busy,
+            ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase => ErrorKind::Corrupted,
+            ErrorCode::ConstraintViolation => ErrorKind::Conflict,
+            ErrorCode::PermissionDenied | ErrorCode::ReadOnly => ErrorKind::PermissionDenied,
+            _ => ErrorKind::Internal,
+        }),
+        _ => None,
+    }
+}
+
+fn classify_http(err: &reqwest::Error) -> ErrorKind {
+    match err.status().map(|status| status.as_u16()) {
+        Some(404) => ErrorKind::NotFound,
+        Some(401 | 403) => ErrorKind::PermissionDenied,
+        Some(400 | 422) => ErrorKind::InvalidInput,
+        _ => ErrorKind::Network,
+    }
+}
+
+fn classify_io(err: &std::io::Error) -> Option<ErrorKind> {
+    use std::io::ErrorKind as Io;
+    match err.kind() {
+        Io::NotFound => Some(ErrorKind::NotFound),
+        Io::PermissionDenied => Some(ErrorKind::PermissionDenied),
+        Io::TimedOut | Io::ConnectionRefused | Io::ConnectionReset | Io::ConnectionAborted => {
+            Some(ErrorKind::Network)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    fn sqlite_error(code: i32) -> rusqlite::Error {
+        rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(code), None)
+    }
+
+    #[test]
+    fn test_storage_errors_are_categorized() {
+        let corrupt = anyhow::Error::new(sqlite_error(rusqlite::ffi::SQLITE_CORRUPT))
+            .context("Failed to list protocols");
+        let error = CommandError::with_context(corrupt, "Failed to load protocols");
+        assert_eq!(error.kind, ErrorKind::Corrupted);
+        assert!(error.message.starts_with("Failed to load protocols: Failed to list protocols"));
+        assert!(error.details.is_some());
+
+        let busy = CommandError::from(anyhow::Error::new(sqlite_error(rusqlite::ffi::SQLITE_BUSY)));
+        assert_eq!(busy.kind, ErrorKind::Busy);
+        assert!(busy.is_retryable());
+
+        let locked: anyhow::Result<()> = Err(DatabaseLocked.into());
+        let locked = CommandError::from(locked.context("Failed to open connection").unwrap_err());
+        assert_eq!(locked.kind, ErrorKind::Locked);
+        assert!(!locked.is_retryable());
+
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+        assert_eq!(CommandError::with_context(missing, "Failed to read").kind, ErrorKind::NotFound);
+
+        let migration: anyhow::Result<()> = Err(MigrationFailed {
+            snapshot_id: "snap".into(),
+            restored: true,
+            reason: "duplicate column".into(),
+        }
+        .into());
+        let migration = CommandError::from(migration.context("Failed to initialize").unwrap_err());
+        assert_eq!(migration.kind, ErrorKind::MigrationFailed);
+
+        assert_eq!(CommandError::from(anyhow::anyhow!("boom")).kind, ErrorKind::Internal);
+    }
+
+    #[test]
+    fn test_serializes_with_retryable_flag() {
+        let json = serde_json::to_value(CommandError::busy("Try again")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "busy",
+                "message": "Try again",
+                "retryable": true,
+                "details": null,
+            })
+        );
+    }
+}
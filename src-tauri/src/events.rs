@@ -0,0 +1,113 @@
+//! Events pushed to the frontend
+//!
+//! Background jobs change data while the UI isn't looking: the scheduler
+//! backs up, the price monitor queues scraped prices, and both raise alerts.
+//! [`EventBus`] emits an [`AppEvent`] as a Tauri event for each of these so
+//! open windows can update without polling. Alerts come from
+//! [`StorageManager`] itself, so every alert is announced however it was
+//! created; jobs emit their own events when they finish.
+//!
+//! | Event              | Payload                |
+//! |--------------------|------------------------|
+//! | `alert:created`    | [`Alert`]              |
+//! | `inventory:low`    | [`InventoryLow`]       |
+//! | `backup:completed` | [`BackupHistoryEntry`] |
+//! | `prices:checked`   | [`PriceCheckSummary`]  |
+
+use std::sync::{Arc, OnceLock};
+
+use peptrack_core::models::{Alert, AlertSeverity, AlertType};
+use peptrack_core::{StorageEvent, StorageManager};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tracing::warn;
+
+use crate::commands::price_monitor::PriceCheckSummary;
+use crate::commands::scheduler_v2::BackupHistoryEntry;
+
+pub const ALERT_CREATED_EVENT: &str = "alert:created";
+pub const INVENTORY_LOW_EVENT: &str = "inventory:low";
+pub const BACKUP_COMPLETED_EVENT: &str = "backup:completed";
+pub const PRICES_CHECKED_EVENT: &str = "prices:checked";
+
+/// A vial predicted to run out, from its low stock alert
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryLow {
+    pub alert_id: String,
+    pub inventory_id: Option<String>,
+    pub severity: AlertSeverity,
+    pub title: String,
+    pub message: String,
+}
+
+impl From<&Alert> for InventoryLow {
+    fn from(alert: &Alert) -> Self {
+        Self {
+            alert_id: alert.id.clone(),
+            inventory_id: alert.related_id.clone(),
+            severity: alert.severity.clone(),
+            title: alert.title.clone(),
+            message: alert.message.clone(),
+        }
+    }
+}
+
+/// An event the frontend can listen for; serializes as its payload
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum AppEvent {
+    AlertCreated(Alert),
+    InventoryLow(InventoryLow),
+    BackupCompleted(BackupHistoryEntry),
+    PricesChecked(PriceCheckSummary),
+}
+
+impl AppEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppEvent::AlertCreated(_) => ALERT_CREATED_EVENT,
+            AppEvent::InventoryLow(_) => INVENTORY_LOW_EVENT,
+            AppEvent::BackupCompleted(_) => BACKUP_COMPLETED_EVENT,
+            AppEvent::PricesChecked(_) => PRICES_CHECKED_EVENT,
+        }
+    }
+}
+
+/// Emits [`AppEvent`]s once the app is running; events before then are
+/// dropped, as no window is listening yet
+#[derive(Default)]
+pub struct EventBus {
+    app: OnceLock<AppHandle>,
+}
+
+impl EventBus {
+    /// Start emitting events, including those from `storage`
+    pub fn attach(self: &Arc<Self>, app: AppHandle, storage: &StorageManager) {
+        if self.app.set(app).is_err() {
+            return;
+        }
+        let bus = Arc::clone(self);
+        storage.subscribe(Arc::new(move |event| bus.storage_event(event)));
+    }
+
+    pub fn emit(&self, event: AppEvent) {
+        let Some(app) = self.app.get() else {
+            return;
+        };
+        if let Err(e) = app.emit(event.name(), &event) {
+            warn!("Failed to emit {} event: {}", event.name(), e);
+        }
+    }
+
+    fn storage_event(&self, event: &StorageEvent) {
+        match event {
+            StorageEvent::AlertCreated(alert) => {
+                self.emit(AppEvent::AlertCreated(alert.clone()));
+                if alert.alert_type == AlertType::LowStock {
+                    self.emit(AppEvent::InventoryLow(InventoryLow::from(alert)));
+                }
+            }
+        }
+    }
+}
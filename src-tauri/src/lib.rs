@@ -1,50 +1,187 @@
 mod commands;
+mod error;
+mod events;
+mod logging;
+mod shutdown;
 mod state;
+#[cfg(desktop)]
+mod tray;
 
+use peptrack_core::{AuditRetention, LiteratureRetention};
 use tauri::Manager;
 use tracing::info;
 
 use commands::{
-    ai::{check_ai_availability, summarize_text},
+    active_levels::get_active_levels,
+    ai::{check_ai_availability, check_ai_health, summarize_corpus, summarize_text},
     analytics::{
         add_price_history, check_inventory_and_create_alerts, clear_all_alerts, compare_prices, create_alert, delete_summary,
-        dismiss_alert, get_latest_price, list_alerts, list_price_history, list_summary_history,
-        mark_alert_read, predict_inventory_depletion, save_summary,
+        diff_summaries, dismiss_alert, dismiss_alerts, export_summaries_markdown, get_ai_usage_stats,
+        get_alert_preferences, get_latest_price, get_price_trend, list_alert_threads, list_alerts, list_price_history,
+        list_summaries_for_source, list_summary_history, mark_alert_read, mute_alerts, predict_inventory_depletion,
+        rank_suppliers, save_summary, unmute_alerts, update_alert_routing,
     },
-    backup::{export_backup_data, get_backup_file_path},
+    attachments::{
+        add_attachment, delete_attachment, get_attachment, get_attachment_thumbnail,
+        list_attachments, save_attachment_to_file,
+    },
+    audit::{get_audit_retention, list_audit_log, prune_audit_log, update_audit_retention},
+    backup::{export_analytics_dataset, export_backup_data, get_backup_file_path},
     body_metrics::{bulk_delete_body_metrics, delete_body_metric, get_body_metric, list_body_metrics, log_body_metric, update_body_metric},
+    calendar::{
+        export_dose_schedule_ics, get_calendar_feed_status, regenerate_calendar_feed_token,
+        update_calendar_feed_settings, CalendarFeedState,
+    },
+    clinical_trials::{
+        delete_clinical_trial, link_clinical_trial, list_clinical_trials, search_clinical_trials,
+        unlink_clinical_trial,
+    },
+    connectivity::{check_connectivity, get_connectivity_status, get_http_settings, update_http_settings},
+    currency::{delete_exchange_rate, fetch_exchange_rates, list_exchange_rates, set_exchange_rate},
+    dashboard::{get_dashboard, get_dashboard_stats},
+    data_import::{
+        commit_import, delete_import_profile, list_import_columns, list_import_profiles, preview_import,
+        save_import_profile,
+    },
     defaults::{get_default_peptides, populate_default_peptides},
-    doses::{bulk_delete_doses, delete_dose_log, list_dose_logs, list_dose_logs_for_protocol, log_dose},
+    diagnostics::{export_diagnostics_bundle, get_recent_logs},
+    doses::{
+        bulk_delete_doses, bulk_log_doses, delete_dose_log, delete_dose_preset, get_dose_stats,
+        list_dose_log_corrections, list_dose_logs, list_dose_logs_for_protocol, list_dose_presets, log_dose,
+        log_dose_from_preset, log_last_dose_again, save_dose_preset, update_dose_log,
+    },
     side_effects::{bulk_delete_side_effects, delete_side_effect, get_side_effect, list_side_effects, list_side_effects_by_protocol, log_side_effect, toggle_side_effect_resolved, update_side_effect},
     drive::{
-        check_drive_status, complete_drive_oauth, disconnect_drive, start_drive_oauth,
-        upload_to_drive, OAuthState,
+        check_drive_status, complete_drive_oauth, disconnect_drive, get_drive_storage_info,
+        start_drive_oauth, upload_to_drive, OAuthState,
+    },
+    email_digest::{
+        get_email_digest_settings, send_email_digest_now, update_email_digest_settings,
+    },
+    forecast::{get_expiry_calendar, get_inventory_forecast},
+    goals::{create_goal, delete_goal, get_goal, list_goals, update_goal},
+    health::{
+        checkpoint_database, compact_database, find_orphaned_records, get_cascade_policies,
+        get_database_health, get_database_stats, list_maintenance_runs, list_migration_snapshots,
+        get_tamper_evidence_enabled, optimize_database, repair_orphaned_records,
+        restore_migration_snapshot, run_database_maintenance, set_tamper_evidence,
+        update_cascade_policies, verify_database_integrity, verify_tamper_chain,
+    },
+    health_bridge::{get_health_bridge_status, sync_health_bridge, update_health_bridge_settings},
+    health_import::{
+        get_health_import_mapping, import_health_data, preview_health_import,
+        update_health_import_mapping,
+    },
+    interactions::{check_protocol_interactions, find_protocol_interactions},
+    journal::{
+        create_journal_entry, delete_journal_entry, get_journal_entry, get_journal_entry_links,
+        list_journal_backlinks, list_journal_entries, update_journal_entry,
+    },
+    lab_results::{
+        delete_lab_result, get_lab_correlation, get_lab_result, get_lab_trend, list_lab_markers,
+        list_lab_results, log_lab_result, update_lab_result,
+    },
+    labels::{generate_vial_label, resolve_vial_qr},
+    literature::{
+        clear_response_cache, enrich_literature, get_literature_retention, get_paper_full_text,
+        get_response_cache_settings, get_response_cache_stats, list_literature, list_literature_tags,
+        open_external_url,
+        prune_literature_cache, search_cached_literature, search_literature, set_literature_pinned,
+        update_literature_reading, update_literature_retention, update_response_cache_settings,
+    },
+    literature_qa::ask_literature,
+    network_folder::check_network_folder,
+    notifications::{
+        get_notification_settings, test_notification_channel, update_notification_settings,
+    },
+    onboarding::{bootstrap_profile, get_setup_status, reset_setup_status},
+    orders::{create_order, delete_order, get_order, list_orders, update_order},
+    paper_pdfs::{download_paper_pdf, get_paper_pdf, remove_paper_pdf},
+    peptide_follows::{
+        cache_followed_work, check_peptide_follow, dismiss_followed_work, follow_peptide,
+        list_peptide_follows, unfollow_peptide,
+    },
+    preferences::{get_unit_preferences, update_unit_preferences},
+    price_monitor::{
+        accept_price_observation, get_price_monitor_settings, list_price_observations,
+        reject_price_observation, trigger_price_check, update_price_monitor_settings,
+        PriceMonitorState,
+    },
+    protocol_sharing::{export_protocol_package, import_protocol_package},
+    protocols::{add_protocol_tag, bulk_add_tag_to_protocols, bulk_delete_protocols, bulk_toggle_favorite_protocols, create_protocol_bundle, delete_protocol, list_protocols, remove_protocol_tag, save_protocol, toggle_protocol_favorite, update_protocol_tags},
+    recovery::{
+        apply_salvaged_database, get_recovery_status, recover_from_backup, salvage_database,
+        RecoveryState,
+    },
+    reports::{export_fhir_bundle, generate_report_pdf},
+    restore::{list_backup_records, preview_backup, restore_from_backup},
+    retractions::check_literature_retractions,
+    saved_searches::{
+        create_saved_search, delete_saved_search, list_saved_searches, run_saved_search,
+        update_saved_search,
     },
-    health::{checkpoint_database, get_database_health, get_database_stats, optimize_database, verify_database_integrity},
-    literature::{list_literature, open_external_url, search_cached_literature, search_literature},
-    protocols::{add_protocol_tag, bulk_add_tag_to_protocols, bulk_delete_protocols, bulk_toggle_favorite_protocols, delete_protocol, list_protocols, remove_protocol_tag, save_protocol, toggle_protocol_favorite, update_protocol_tags},
-    restore::{preview_backup, restore_from_backup},
     schedules::{
-        create_dose_schedule, delete_dose_schedule, get_pending_dose_reminders,
-        list_dose_schedules, update_dose_schedule,
+        advance_titration_phase, create_dose_schedule, delete_dose_schedule,
+        get_pending_dose_reminders, list_dose_schedules, list_dose_skips, respond_to_dose_reminder,
+        snooze_next_dose_reminder, update_dose_schedule, ReminderSnoozes,
+    },
+    scraping::{get_scraping_settings, preview_scraping_profile, update_scraping_settings},
+    search::{global_search, rebuild_search_index},
+    security::{
+        generate_recovery_code, get_auto_lock_settings, get_biometric_settings, get_lock_status,
+        lock_database, record_activity, recover_with_code, rotate_encryption_key,
+        set_database_passphrase, unlock_database, unlock_database_biometric,
+        update_auto_lock_settings, update_biometric_settings,
     },
     scheduler_v2::{
-        get_backup_history, get_backup_progress, get_backup_schedule, trigger_manual_backup,
-        update_backup_schedule, SchedulerState,
+        deep_verify_backup, get_backup_history, get_backup_progress, get_backup_schedule,
+        trigger_manual_backup, update_backup_schedule, SchedulerState,
+    },
+    spend::{export_spend_report_csv, get_spend_report},
+    sync::{get_sync_key, get_sync_status, list_sync_devices, register_sync_device, set_sync_enabled, sync_now},
+    summary_queue::{
+        clear_finished_summaries, enqueue_summaries, get_summary_queue_status, retry_failed_summaries,
+        SummaryQueueState,
     },
     suppliers::{
-        create_inventory_item, create_supplier, delete_inventory_item, delete_supplier,
-        get_inventory_item, get_supplier, list_inventory, list_inventory_by_protocol,
-        list_suppliers, scrape_supplier_website, update_inventory_item, update_supplier,
+        bulk_assign_supplier, bulk_delete_inventory_items, bulk_delete_suppliers, bulk_update_inventory_status,
+        create_inventory_item, create_supplier, delete_inventory_item, delete_supplier, delete_supplier_review,
+        get_inventory_item, get_supplier, list_inventory, list_inventory_by_protocol, list_suppliers,
+        reconstitute_inventory_item, record_supplier_review, scrape_supplier_website, update_inventory_item,
+        update_supplier,
     },
+    trash::{
+        empty_trash, get_trash_settings, list_trash, restore_from_trash, update_trash_settings,
+    },
+    undo::{list_undo_operations, undo_last_operation},
+    viewer::{
+        close_viewer_session, get_viewer_session, get_viewer_summary, list_viewer_body_metrics,
+        list_viewer_dose_logs, list_viewer_protocols, open_viewer_session, ViewerState,
+    },
+    wipe::{request_secure_wipe, secure_wipe_database, SecureWipeState},
 };
+use shutdown::ShutdownState;
 use state::build_state;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    // Held until the app exits so buffered log lines are written
+    let _log_guard = match logging::init() {
+        Ok(guard) => Some(guard),
+        Err(err) => {
+            eprintln!("Failed to set up log files: {err:#}");
+            None
+        }
+    };
+
+    let builder = tauri::Builder::default()
         .plugin(tauri_plugin_notification::init())
-        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_dialog::init());
+    // HealthKit / Health Connect sync for mobile builds
+    #[cfg(feature = "health-bridge")]
+    let builder = builder.plugin(peptrack_health_bridge::init());
+
+    builder
         .setup(|app| {
             if cfg!(debug_assertions) {
                 app.handle().plugin(
@@ -62,11 +199,20 @@ pub fn run() {
             })?;
 
             let scheduler_state = SchedulerState::new();
+            let price_monitor_state = PriceMonitorState::new();
+            let calendar_feed_state = CalendarFeedState::new();
             let state_arc = std::sync::Arc::new(state);
+            state_arc.notifier.attach(app.handle().clone());
+            state_arc.page_renderer.attach(app.handle().clone());
+            // Setup runs before the async runtime takes over, so it uses the
+            // blocking storage handle directly
+            let storage = state_arc.db.blocking();
+            state_arc.events.attach(app.handle().clone(), storage);
 
             // Run database health check on startup
             info!("Running startup database health check...");
-            match state_arc.storage.health_check() {
+            let mut corruption = None;
+            match storage.health_check() {
                 Ok(report) if report.is_healthy => {
                     info!(
                         "✓ Database health check: OK ({:.2} MB, WAL: {}, FK: {})",
@@ -80,8 +226,8 @@ pub fn run() {
                         "✗ Database corruption detected: {}",
                         report.integrity_result
                     );
-                    tracing::error!("Please restore from a backup or contact support");
-                    // Continue loading but warn user
+                    // Continue loading; the app offers salvage or a backup restore
+                    corruption = Some(report.integrity_result);
                 }
                 Err(e) => {
                     tracing::warn!("Health check failed: {:#}", e);
@@ -89,6 +235,88 @@ pub fn run() {
                 }
             }
 
+            // Drop audit entries outside the retention window
+            if let Err(e) = storage
+                .load_setting_or_default::<AuditRetention>()
+                .and_then(|retention| storage.prune_audit_log(&retention))
+            {
+                tracing::warn!("Audit log pruning failed: {:#}", e);
+            }
+
+            // Drop cached literature outside the retention window
+            if let Err(e) = storage
+                .load_setting_or_default::<LiteratureRetention>()
+                .and_then(|retention| storage.prune_literature_cache(&retention))
+            {
+                tracing::warn!("Literature cache pruning failed: {:#}", e);
+            }
+
+            // Route requests through the configured proxy and CA certificates
+            commands::connectivity::apply_http_settings(storage);
+
+            // Purge records that have been in the trash past the retention period
+            tauri::async_runtime::spawn(commands::trash::run_purge_loop(state_arc.clone()));
+
+            // Vacuum and checkpoint the database weekly when its stats call for it
+            tauri::async_runtime::spawn(commands::health::run_maintenance_loop(state_arc.clone()));
+
+            // Track whether the internet is reachable
+            tauri::async_runtime::spawn(commands::connectivity::run_connectivity_loop(
+                app.handle().clone(),
+                state_arc.clone(),
+            ));
+
+            // Re-run saved literature searches and report new papers
+            tauri::async_runtime::spawn(commands::saved_searches::run_saved_search_loop(
+                app.handle().clone(),
+                state_arc.clone(),
+            ));
+
+            // Check followed peptides for newly published works
+            tauri::async_runtime::spawn(commands::peptide_follows::run_peptide_follow_loop(
+                app.handle().clone(),
+                state_arc.clone(),
+            ));
+
+            // Email the daily or weekly digest when it is due
+            tauri::async_runtime::spawn(commands::email_digest::run_email_digest_loop(
+                state_arc.clone(),
+            ));
+
+            // Exchange encrypted changes with other devices through the sync relay
+            tauri::async_runtime::spawn(commands::sync::run_sync_loop(state_arc.clone()));
+
+            // Flag cached papers that have since been retracted or corrected
+            tauri::async_runtime::spawn(commands::retractions::run_retraction_check_loop(
+                app.handle().clone(),
+                state_arc.clone(),
+            ));
+
+            // Summarize papers queued for batch summarization
+            let summary_queue_state = SummaryQueueState::default();
+            tauri::async_runtime::spawn(commands::summary_queue::run_summary_queue_loop(
+                app.handle().clone(),
+                state_arc.clone(),
+                summary_queue_state.clone(),
+            ));
+
+            // Warm up the AI CLIs and cache which provider is working
+            let health_state = state_arc.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                let report = health_state
+                    .ai_client
+                    .health_check(peptrack_local_ai::health::DEFAULT_HEALTH_TIMEOUT)
+                    .await;
+                info!("AI provider warm-up done, active: {:?}", report.active_provider());
+            });
+
+            // Lock the database after the configured idle time
+            tauri::async_runtime::spawn(commands::security::run_auto_lock_loop(
+                app.handle().clone(),
+                state_arc.clone(),
+            ));
+
             // Store app handle for notifications
             let scheduler_clone_handle = scheduler_state.clone();
             let app_handle = app.handle().clone();
@@ -96,10 +324,11 @@ pub fn run() {
                 scheduler_clone_handle.set_app_handle(app_handle).await;
             });
 
-            // Load schedule from disk
+            // Load the backup schedule and history
             let scheduler_clone = scheduler_state.clone();
+            let scheduler_app_state = state_arc.clone();
             tauri::async_runtime::spawn(async move {
-                if let Err(e) = scheduler_clone.load_from_disk().await {
+                if let Err(e) = scheduler_clone.load(&scheduler_app_state).await {
                     eprintln!("Failed to load backup schedule: {:#}", e);
                 }
             });
@@ -113,15 +342,63 @@ pub fn run() {
                 scheduler_clone2.start_scheduler(state_clone).await;
             });
 
+            // Start background price monitor
+            let monitor_clone = price_monitor_state.clone();
+            let monitor_handle = app.handle().clone();
+            let monitor_state_clone = state_arc.clone();
+            tauri::async_runtime::spawn(async move {
+                monitor_clone.set_app_handle(monitor_handle).await;
+                tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                monitor_clone.start_monitor(monitor_state_clone).await;
+            });
+
+            // Start the calendar feed server if it was enabled
+            let feed_clone = calendar_feed_state.clone();
+            let feed_state_clone = state_arc.clone();
+            tauri::async_runtime::spawn(async move {
+                // Its settings are in the database, so wait for the unlock
+                while feed_state_clone.key_provider.is_locked() {
+                    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                }
+                if let Err(e) = feed_clone.restart(feed_state_clone).await {
+                    tracing::warn!("Calendar feed failed to start: {:#}", e);
+                }
+            });
+
             app.manage(state_arc);
             app.manage(OAuthState::default());
+            app.manage(ViewerState::default());
             app.manage(scheduler_state);
+            app.manage(price_monitor_state);
+            app.manage(calendar_feed_state);
+            app.manage(summary_queue_state);
+            app.manage(SecureWipeState::default());
+            app.manage(RecoveryState::new(corruption));
+            app.manage(ShutdownState::default());
+            app.manage(ReminderSnoozes::default());
+
+            // Tray quick actions and global shortcuts; the app runs without them
+            #[cfg(desktop)]
+            if let Err(e) = tray::init(app) {
+                tracing::warn!("Failed to set up the tray icon: {}", e);
+            }
             info!("PepTrack initialized");
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // Closing the main window runs the backup-on-close first
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if window.label() == "main" && shutdown::intercept_exit(window.app_handle()) {
+                    api.prevent_close();
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             list_protocols,
             save_protocol,
+            create_protocol_bundle,
+            export_protocol_package,
+            import_protocol_package,
             toggle_protocol_favorite,
             update_protocol_tags,
             add_protocol_tag,
@@ -131,14 +408,66 @@ pub fn run() {
             bulk_add_tag_to_protocols,
             bulk_toggle_favorite_protocols,
             check_ai_availability,
+            check_ai_health,
             summarize_text,
+            summarize_corpus,
+            enqueue_summaries,
+            get_summary_queue_status,
+            retry_failed_summaries,
+            clear_finished_summaries,
             list_literature,
+            list_literature_tags,
+            set_literature_pinned,
+            update_literature_reading,
+            get_literature_retention,
+            update_literature_retention,
+            prune_literature_cache,
+            get_response_cache_settings,
+            update_response_cache_settings,
+            get_response_cache_stats,
+            clear_response_cache,
+            enrich_literature,
+            get_paper_full_text,
+            ask_literature,
+            check_literature_retractions,
+            search_clinical_trials,
+            list_clinical_trials,
+            link_clinical_trial,
+            unlink_clinical_trial,
+            delete_clinical_trial,
+            download_paper_pdf,
+            get_paper_pdf,
+            remove_paper_pdf,
+            list_peptide_follows,
+            follow_peptide,
+            unfollow_peptide,
+            check_peptide_follow,
+            cache_followed_work,
+            dismiss_followed_work,
+            list_saved_searches,
+            create_saved_search,
+            update_saved_search,
+            delete_saved_search,
+            run_saved_search,
+            get_connectivity_status,
+            check_connectivity,
+            get_http_settings,
+            update_http_settings,
             open_external_url,
             search_cached_literature,
             search_literature,
             log_dose,
+            bulk_log_doses,
+            update_dose_log,
+            list_dose_presets,
+            save_dose_preset,
+            delete_dose_preset,
+            log_dose_from_preset,
+            log_last_dose_again,
+            list_dose_log_corrections,
             list_dose_logs,
             list_dose_logs_for_protocol,
+            get_dose_stats,
             delete_dose_log,
             bulk_delete_doses,
             // Body metrics commands
@@ -148,6 +477,22 @@ pub fn run() {
             update_body_metric,
             delete_body_metric,
             bulk_delete_body_metrics,
+            // Health data import commands
+            get_health_import_mapping,
+            update_health_import_mapping,
+            preview_health_import,
+            import_health_data,
+            // Import from other trackers
+            list_import_columns,
+            list_import_profiles,
+            save_import_profile,
+            delete_import_profile,
+            preview_import,
+            commit_import,
+            // Health bridge commands
+            get_health_bridge_status,
+            update_health_bridge_settings,
+            sync_health_bridge,
             // Side effects commands
             log_side_effect,
             list_side_effects,
@@ -157,65 +502,254 @@ pub fn run() {
             toggle_side_effect_resolved,
             delete_side_effect,
             bulk_delete_side_effects,
+            // Lab result commands
+            log_lab_result,
+            list_lab_results,
+            get_lab_result,
+            update_lab_result,
+            delete_lab_result,
+            list_lab_markers,
+            get_lab_trend,
+            get_lab_correlation,
+            // Journal commands
+            create_journal_entry,
+            list_journal_entries,
+            get_journal_entry,
+            update_journal_entry,
+            delete_journal_entry,
+            get_journal_entry_links,
+            list_journal_backlinks,
+            // Goal commands
+            create_goal,
+            list_goals,
+            get_goal,
+            update_goal,
+            delete_goal,
+            // Unit preference commands
+            get_unit_preferences,
+            update_unit_preferences,
+            // Trash commands
+            list_trash,
+            restore_from_trash,
+            empty_trash,
+            list_undo_operations,
+            undo_last_operation,
+            get_trash_settings,
+            update_trash_settings,
+            // Search commands
+            global_search,
+            rebuild_search_index,
+            // Security commands
+            get_lock_status,
+            unlock_database,
+            unlock_database_biometric,
+            recover_with_code,
+            generate_recovery_code,
+            lock_database,
+            set_database_passphrase,
+            rotate_encryption_key,
+            record_activity,
+            get_auto_lock_settings,
+            update_auto_lock_settings,
+            get_biometric_settings,
+            update_biometric_settings,
             export_backup_data,
+            export_analytics_dataset,
             get_backup_file_path,
             start_drive_oauth,
             complete_drive_oauth,
             check_drive_status,
             disconnect_drive,
             upload_to_drive,
+            get_drive_storage_info,
             get_backup_schedule,
             get_backup_history,
+            deep_verify_backup,
             get_backup_progress,
             update_backup_schedule,
             trigger_manual_backup,
+            check_network_folder,
             restore_from_backup,
             preview_backup,
+            list_backup_records,
+            // Sync commands
+            get_sync_status,
+            register_sync_device,
+            set_sync_enabled,
+            get_sync_key,
+            list_sync_devices,
+            sync_now,
+            // Read-only viewer commands
+            open_viewer_session,
+            get_viewer_session,
+            close_viewer_session,
+            list_viewer_protocols,
+            list_viewer_dose_logs,
+            list_viewer_body_metrics,
+            get_viewer_summary,
+            // Calendar commands
+            export_dose_schedule_ics,
+            get_calendar_feed_status,
+            update_calendar_feed_settings,
+            regenerate_calendar_feed_token,
+            // Report commands
+            generate_report_pdf,
+            export_fhir_bundle,
             // Supplier commands
             create_supplier,
             list_suppliers,
             get_supplier,
             update_supplier,
             delete_supplier,
+            bulk_delete_suppliers,
+            record_supplier_review,
+            delete_supplier_review,
             scrape_supplier_website,
+            preview_scraping_profile,
+            get_scraping_settings,
+            update_scraping_settings,
+            // Price monitor commands
+            get_price_monitor_settings,
+            update_price_monitor_settings,
+            trigger_price_check,
+            list_price_observations,
+            accept_price_observation,
+            reject_price_observation,
+            // Notification channel commands
+            get_notification_settings,
+            update_notification_settings,
+            test_notification_channel,
+            // Email digest commands
+            get_email_digest_settings,
+            update_email_digest_settings,
+            send_email_digest_now,
             // Inventory commands
             create_inventory_item,
             list_inventory,
             list_inventory_by_protocol,
             get_inventory_item,
             update_inventory_item,
+            reconstitute_inventory_item,
             delete_inventory_item,
+            bulk_update_inventory_status,
+            bulk_delete_inventory_items,
+            bulk_assign_supplier,
+            // Order commands
+            create_order,
+            list_orders,
+            get_order,
+            update_order,
+            delete_order,
+            // Attachment commands
+            add_attachment,
+            list_attachments,
+            get_attachment,
+            get_attachment_thumbnail,
+            save_attachment_to_file,
+            delete_attachment,
+            // Audit log commands
+            list_audit_log,
+            get_audit_retention,
+            update_audit_retention,
+            prune_audit_log,
             // Analytics commands
             add_price_history,
             list_price_history,
             get_latest_price,
             compare_prices,
+            get_price_trend,
+            rank_suppliers,
+            // Currency commands
+            list_exchange_rates,
+            set_exchange_rate,
+            delete_exchange_rate,
+            fetch_exchange_rates,
             create_alert,
             list_alerts,
             mark_alert_read,
             dismiss_alert,
             clear_all_alerts,
+            list_alert_threads,
+            dismiss_alerts,
+            get_alert_preferences,
+            update_alert_routing,
+            mute_alerts,
+            unmute_alerts,
             save_summary,
             list_summary_history,
+            list_summaries_for_source,
+            diff_summaries,
+            export_summaries_markdown,
+            get_ai_usage_stats,
             delete_summary,
             predict_inventory_depletion,
             check_inventory_and_create_alerts,
+            get_inventory_forecast,
+            get_expiry_calendar,
+            generate_vial_label,
+            resolve_vial_qr,
+            get_spend_report,
+            get_dashboard_stats,
+            get_dashboard,
+            export_spend_report_csv,
+            get_active_levels,
+            // Interaction commands
+            find_protocol_interactions,
+            check_protocol_interactions,
             // Dose schedule commands
             create_dose_schedule,
             list_dose_schedules,
             update_dose_schedule,
             delete_dose_schedule,
             get_pending_dose_reminders,
+            snooze_next_dose_reminder,
+            respond_to_dose_reminder,
+            list_dose_skips,
+            advance_titration_phase,
             // Health & diagnostics commands
             get_database_health,
             verify_database_integrity,
             optimize_database,
+            compact_database,
+            list_migration_snapshots,
+            restore_migration_snapshot,
             checkpoint_database,
             get_database_stats,
+            run_database_maintenance,
+            list_maintenance_runs,
+            find_orphaned_records,
+            repair_orphaned_records,
+            get_cascade_policies,
+            update_cascade_policies,
+            get_tamper_evidence_enabled,
+            set_tamper_evidence,
+            verify_tamper_chain,
+            // Logs & diagnostics
+            get_recent_logs,
+            export_diagnostics_bundle,
+            // Corruption recovery commands
+            get_recovery_status,
+            salvage_database,
+            apply_salvaged_database,
+            recover_from_backup,
+            // Factory reset commands
+            request_secure_wipe,
+            secure_wipe_database,
             // Default peptides
             get_default_peptides,
-            populate_default_peptides
+            populate_default_peptides,
+            // Onboarding
+            get_setup_status,
+            bootstrap_profile,
+            reset_setup_status
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { api, .. } = &event {
+                if shutdown::intercept_exit(app) {
+                    api.prevent_exit();
+                }
+            }
+        });
 }
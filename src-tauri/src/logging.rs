@@ -0,0 +1,214 @@
+//! Log files
+//!
+//! Every build, release included, writes its logs as JSON lines to `logs/`
+//! in the data directory. A new file is started each day and the newest
+//! [`MAX_LOG_FILES`] are kept. Debug builds also log to the console through
+//! the log plugin.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::level_filters::LevelFilter;
+use tracing::Level;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Layer;
+
+pub const LOG_DIR_NAME: &str = "logs";
+const LOG_FILE_PREFIX: &str = "peptrack";
+const LOG_FILE_SUFFIX: &str = "jsonl";
+/// Daily log files kept; older ones are deleted as new ones are started
+pub const MAX_LOG_FILES: usize = 7;
+
+/// One line of a log file
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    /// Module the entry was logged from, e.g. `app_lib::commands::backup`
+    pub target: String,
+    pub message: String,
+    /// Structured fields besides the message
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub fields: Map<String, Value>,
+}
+
+/// Which entries [`recent_logs`] returns
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    /// Least severe level to include; everything when `None`
+    pub level: Option<Level>,
+    /// Part of the module path, e.g. `backup`
+    pub module: Option<String>,
+}
+
+impl LogFilter {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        if let Some(min) = self.level {
+            match entry.level.parse::<Level>() {
+                // More verbose levels compare greater
+                Ok(level) if level <= min => {}
+                _ => return false,
+            }
+        }
+        match &self.module {
+            Some(module) => entry.target.contains(module.as_str()),
+            None => true,
+        }
+    }
+}
+
+pub fn log_dir() -> Result<PathBuf> {
+    Ok(dirs::data_dir()
+        .context("Unable to determine data directory")?
+        .join("PepTrack")
+        .join(LOG_DIR_NAME))
+}
+
+/// Start writing log files
+///
+/// Lines are written on a background thread; keep the returned guard alive
+/// until the app exits so the last of them are flushed.
+pub fn init() -> Result<WorkerGuard> {
+    let dir = log_dir()?;
+    std::fs::create_dir_all(&dir).context("Failed to create log directory")?;
+
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)
+        .context("Failed to open log file")?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    let file_layer = tracing_subscriber::fmt::layer()
+        .json()
+        .with_current_span(false)
+        .with_span_list(false)
+        .with_writer(writer)
+        .with_filter(LevelFilter::INFO);
+    // Not `try_init`, which would also claim the `log` facade the log plugin uses
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(file_layer))
+        .context("Failed to install log subscriber")?;
+    Ok(guard)
+}
+
+fn parse_line(line: &str) -> Option<LogEntry> {
+    let Value::Object(mut line) = serde_json::from_str(line).ok()? else {
+        return None;
+    };
+    let mut fields = match line.remove("fields") {
+        Some(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    };
+    let mut text = |key: &str| match line.remove(key) {
+        Some(Value::String(value)) => value,
+        _ => String::new(),
+    };
+    let (timestamp, level, target) = (text("timestamp"), text("level"), text("target"));
+    let message = match fields.remove("message") {
+        Some(Value::String(message)) => message,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    Some(LogEntry {
+        timestamp,
+        level,
+        target,
+        message,
+        fields,
+    })
+}
+
+/// Log files in `dir`, newest first
+fn log_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).context("Failed to read log directory")? {
+        let path = entry?.path();
+        let is_log = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(LOG_FILE_PREFIX) && name.ends_with(LOG_FILE_SUFFIX));
+        if is_log {
+            files.push(path);
+        }
+    }
+    // File names end in the date, so they sort by age
+    files.sort_by(|a, b| b.cmp(a));
+    Ok(files)
+}
+
+/// Up to `limit` entries from the log files in `dir` that match `filter`, newest first
+pub fn recent_logs(dir: &Path, filter: &LogFilter, limit: usize) -> Result<Vec<LogEntry>> {
+    let mut entries = Vec::new();
+    if limit == 0 {
+        return Ok(entries);
+    }
+    for path in log_files(dir)? {
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        for entry in contents.lines().rev().filter_map(parse_line) {
+            if filter.matches(&entry) {
+                entries.push(entry);
+                if entries.len() == limit {
+                    return Ok(entries);
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINES: &str = r#"{"timestamp":"2025-01-01T10:00:00Z","level":"INFO","fields":{"message":"Starting"},"target":"app_lib"}
+{"timestamp":"2025-01-01T10:00:01Z","level":"WARN","fields":{"message":"Backup slow","attempt":2},"target":"app_lib::commands::scheduler_v2"}
+not json
+{"timestamp":"2025-01-01T10:00:02Z","level":"ERROR","fields":{"message":"Backup failed"},"target":"app_lib::commands::backup"}
+"#;
+
+    fn log_dir_with_lines() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("peptrack-logs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("peptrack.2025-01-01.jsonl"), LINES).unwrap();
+        std::fs::write(dir.join("other.txt"), "ignored").unwrap();
+        dir
+    }
+
+    #[test]
+    fn recent_logs_are_newest_first_and_filtered() {
+        let dir = log_dir_with_lines();
+
+        let all = recent_logs(&dir, &LogFilter::default(), 10).unwrap();
+        let messages: Vec<_> = all.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, vec!["Backup failed", "Backup slow", "Starting"]);
+        assert_eq!(all[1].fields.get("attempt"), Some(&Value::from(2)));
+
+        let warnings = LogFilter {
+            level: Some(Level::WARN),
+            module: None,
+        };
+        assert_eq!(recent_logs(&dir, &warnings, 10).unwrap().len(), 2);
+
+        let backup = LogFilter {
+            level: None,
+            module: Some("backup".into()),
+        };
+        let entries = recent_logs(&dir, &backup, 10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].level, "ERROR");
+
+        assert_eq!(recent_logs(&dir, &LogFilter::default(), 1).unwrap().len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+//! Graceful shutdown
+//!
+//! Closing the main window or quitting the app is held back while the
+//! backup-on-close runs. A small progress window shows what is happening;
+//! closing it skips the backup and quits straight away.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::commands::scheduler_v2::{CloseBackupOutcome, SchedulerState};
+use crate::state::AppState;
+
+const PROGRESS_WINDOW_LABEL: &str = "close-backup";
+/// Longest the app waits for the backup before quitting anyway
+const CLOSE_BACKUP_TIMEOUT: Duration = Duration::from_secs(120);
+const PROGRESS_POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+#[derive(Default)]
+pub struct ShutdownState {
+    /// Set once the backup has finished or been skipped
+    exit_approved: AtomicBool,
+    in_progress: AtomicBool,
+}
+
+/// Decide whether an exit or main window close must wait
+///
+/// Returns true when the caller should prevent the exit; the app then quits
+/// on its own once the backup-on-close is done.
+pub fn intercept_exit(app: &AppHandle) -> bool {
+    let shutdown = app.state::<ShutdownState>();
+    if shutdown.exit_approved.load(Ordering::SeqCst) {
+        return false;
+    }
+    if !shutdown.in_progress.swap(true, Ordering::SeqCst) {
+        tauri::async_runtime::spawn(graceful_shutdown(app.clone()));
+    }
+    true
+}
+
+async fn graceful_shutdown(app: AppHandle) {
+    let scheduler = app.state::<SchedulerState>().inner().clone();
+    let app_state = app.state::<Arc<AppState>>().inner().clone();
+
+    if !scheduler.backup_on_close().await {
+        return exit(&app);
+    }
+    if app_state.key_provider.is_locked() {
+        warn!("Skipping backup on close because the database is locked");
+        return exit(&app);
+    }
+
+    info!("Running backup before exit");
+    if let Some(main) = app.get_webview_window("main") {
+        main.hide().ok();
+    }
+
+    let cancel = Arc::new(Notify::new());
+    let window = match open_progress_window(&app, cancel.clone()) {
+        Ok(window) => Some(window),
+        Err(e) => {
+            warn!("Failed to open backup progress window: {}", e);
+            None
+        }
+    };
+    let progress_task = window.clone().map(|window| {
+        let scheduler = scheduler.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let progress = scheduler.progress().await;
+                if let Ok(json) = serde_json::to_string(&progress) {
+                    let script = format!(
+                        "window.showBackupProgress && window.showBackupProgress({})",
+                        json
+                    );
+                    window.eval(&script).ok();
+                }
+                tokio::time::sleep(PROGRESS_POLL_INTERVAL).await;
+            }
+        })
+    });
+
+    let outcome = scheduler
+        .run_close_backup(&app_state, cancel.notified(), CLOSE_BACKUP_TIMEOUT)
+        .await;
+    match &outcome {
+        CloseBackupOutcome::Completed(message) => info!("Backup on close complete: {}", message),
+        CloseBackupOutcome::Failed(e) => warn!("Backup on close failed: {}", e),
+        CloseBackupOutcome::TimedOut => warn!(
+            "Backup on close timed out after {}s",
+            CLOSE_BACKUP_TIMEOUT.as_secs()
+        ),
+        CloseBackupOutcome::Cancelled => info!("Backup on close skipped by the user"),
+    }
+
+    if let Some(task) = progress_task {
+        task.abort();
+    }
+    if let Some(window) = window {
+        window.destroy().ok();
+    }
+    exit(&app);
+}
+
+fn exit(app: &AppHandle) {
+    app.state::<ShutdownState>()
+        .exit_approved
+        .store(true, Ordering::SeqCst);
+    app.exit(0);
+}
+
+/// Small always-on-top window; closing it cancels the backup
+fn open_progress_window(app: &AppHandle, cancel: Arc<Notify>) -> tauri::Result<WebviewWindow> {
+    let window = WebviewWindowBuilder::new(
+        app,
+        PROGRESS_WINDOW_LABEL,
+        WebviewUrl::App("close-backup.html".into()),
+    )
+    .title("Backing up PepTrack")
+    .inner_size(420.0, 180.0)
+    .resizable(false)
+    .minimizable(false)
+    .always_on_top(true)
+    .center()
+    .build()?;
+
+    window.on_window_event(move |event| {
+        if let WindowEvent::CloseRequested { .. } = event {
+            cancel.notify_one();
+        }
+    });
+    Ok(window)
+}
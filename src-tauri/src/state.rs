@@ -3,43 +3,156 @@ use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use dirs::data_dir;
-use peptrack_core::{KeyProvider, StaticKeyProvider, StorageConfig, StorageManager};
+use peptrack_core::{
+    AsyncStorage, BiometricKeyProvider, KeyMaterial, KeyProvider, PassphraseKeyProvider,
+    StaticKeyProvider, StorageConfig, StorageManager, UndoStack,
+};
 use peptrack_local_ai::{AiClientConfig, LocalAiOrchestrator};
 use rand::rngs::OsRng;
 use rand::RngCore;
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
 use tracing::{info, warn};
 
+use crate::commands::connectivity::Connectivity;
+use crate::commands::email_digest::SMTP_PASSWORD_ACCOUNT;
+use crate::commands::notifications::Notifier;
+use crate::commands::analytics::PriceComparisonCache;
+use crate::commands::scraping::{PageRenderer, ScrapeThrottle};
+use crate::commands::sync::{RELAY_SECRET_ACCOUNT, SYNC_KEY_ACCOUNT};
+use crate::error::CommandError;
+use crate::events::EventBus;
+
 #[cfg(target_os = "macos")]
 use peptrack_core::{migrate_file_key_to_keychain, KeychainKeyProvider};
 
+pub const KEY_FILE_NAME: &str = "peptrack.key";
+/// New key written here before a rotation starts, so it can't be lost if the
+/// app stops before the key is saved to its final location
+pub const PENDING_KEY_FILE_NAME: &str = "peptrack.key.pending";
+/// Every account the app keeps a credential under with
+/// [`peptrack_core::store_credential`]; a secure wipe deletes them all
+pub(crate) const CREDENTIAL_ACCOUNTS: [&str; 3] =
+    [SMTP_PASSWORD_ACCOUNT, SYNC_KEY_ACCOUNT, RELAY_SECRET_ACCOUNT];
+
+/// Where the database key is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyLocation {
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    Keychain,
+    File,
+    Passphrase,
+}
+
 #[derive(Clone)]
 pub struct AppState {
-    pub storage: Arc<StorageManager>,
+    /// Storage for commands and background tasks, which runs each call on
+    /// the blocking thread pool instead of a runtime worker
+    pub db: AsyncStorage,
     pub ai_client: Arc<LocalAiOrchestrator>,
+    /// Holds the database key; locked until the passphrase is entered when
+    /// one is set
+    pub key_provider: Arc<PassphraseKeyProvider>,
+    /// Last user activity, for auto-lock
+    pub last_activity: Arc<Mutex<Instant>>,
+    /// Releases the stored key after Touch ID / Windows Hello, when enabled
+    pub biometric: Option<Arc<BiometricKeyProvider>>,
+    /// Posts alerts and backup results to the configured webhooks
+    pub notifier: Arc<Notifier>,
+    /// Whether the internet is reachable
+    pub connectivity: Arc<Connectivity>,
+    /// robots.txt rules and request pacing for supplier scraping
+    pub scrape_throttle: Arc<ScrapeThrottle>,
+    /// Renders supplier pages that add prices with JavaScript
+    pub page_renderer: Arc<PageRenderer>,
+    /// Recent `compare_prices` results
+    pub price_comparisons: Arc<PriceComparisonCache>,
+    /// Tells the frontend about changes made in the background
+    pub events: Arc<EventBus>,
+    /// How to reverse this session's deletes and bulk edits
+    pub undo: Arc<UndoStack>,
+}
+
+impl AppState {
+    /// Run a command helper against storage on the blocking thread pool
+    ///
+    /// For helpers that fail with a [`CommandError`]; plain storage calls go
+    /// through [`AsyncStorage::run`].
+    pub async fn on_storage<T, F>(&self, f: F) -> Result<T, CommandError>
+    where
+        F: FnOnce(&StorageManager) -> Result<T, CommandError> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.db.run(move |storage| Ok(f(storage))).await.map_err(CommandError::from)?
+    }
 }
 
 pub fn build_state() -> Result<AppState> {
     let data_dir = resolve_data_dir()?;
 
-    // Attempt to migrate file key to Keychain on macOS (non-blocking)
-    #[cfg(target_os = "macos")]
-    attempt_keychain_migration(&data_dir);
-
-    // Select key provider: prefer Keychain on macOS, fallback to file-based
-    let key_provider: Arc<dyn KeyProvider> = select_key_provider(&data_dir)?;
-
+    let key_provider = Arc::new(PassphraseKeyProvider::new(&data_dir));
     let storage = StorageManager::new(StorageConfig {
-        data_dir: Some(data_dir),
+        data_dir: Some(data_dir.clone()),
         db_file_name: None,
-        key_provider,
+        key_provider: key_provider.clone(),
     })?;
-    storage.initialize()?;
+
+    let mut biometric = None;
+    if recover_pending_key(&data_dir, &storage, &key_provider)? {
+        info!("Recovered encryption key from an interrupted key rotation");
+    } else if key_provider.is_configured() {
+        info!("Database is protected by a passphrase; waiting for unlock");
+    } else {
+        // Attempt to migrate file key to Keychain on macOS (non-blocking)
+        #[cfg(target_os = "macos")]
+        attempt_keychain_migration(&data_dir);
+
+        // Select key provider: prefer Keychain on macOS, fallback to file-based
+        let stored_key: Arc<dyn KeyProvider> = select_key_provider(&data_dir)?;
+
+        let settings = crate::commands::security::load_biometric_settings();
+        if settings.enabled && BiometricKeyProvider::is_available() {
+            info!("Biometric unlock enabled; waiting for unlock");
+            biometric = Some(Arc::new(BiometricKeyProvider::new(
+                stored_key,
+                settings.grace_period(),
+            )));
+        } else {
+            if settings.enabled {
+                warn!("Biometric unlock is enabled but unavailable on this device; skipping it");
+            }
+            key_provider.unlock_with_key(stored_key.key_material()?);
+            if !storage.verify_key()? {
+                // The Keychain item or key file was lost and a new key made in its place
+                warn!("Stored encryption key doesn't open the database; waiting for recovery code");
+                key_provider.forget_key();
+            }
+        }
+    }
+
+    if !key_provider.is_locked() {
+        storage.initialize()?;
+    }
 
     let ai_client = LocalAiOrchestrator::detect(AiClientConfig::default());
 
+    let db = AsyncStorage::new(Arc::new(storage));
+    let notifier = Arc::new(Notifier::new(db.clone()));
     Ok(AppState {
-        storage: Arc::new(storage),
+        db,
         ai_client: Arc::new(ai_client),
+        key_provider,
+        last_activity: Arc::new(Mutex::new(Instant::now())),
+        biometric,
+        notifier,
+        connectivity: Arc::new(Connectivity::default()),
+        scrape_throttle: Arc::new(ScrapeThrottle::default()),
+        page_renderer: Arc::new(PageRenderer::default()),
+        price_comparisons: Arc::new(PriceComparisonCache::default()),
+        events: Arc::new(EventBus::default()),
+        undo: Arc::new(UndoStack::default()),
     })
 }
 
@@ -87,7 +200,7 @@ fn select_key_provider(data_dir: &Path) -> Result<Arc<dyn KeyProvider>> {
 /// The key file is kept as a backup even after successful migration.
 #[cfg(target_os = "macos")]
 fn attempt_keychain_migration(data_dir: &Path) {
-    let key_file = data_dir.join("peptrack.key");
+    let key_file = data_dir.join(KEY_FILE_NAME);
 
     if !key_file.exists() {
         return;
@@ -107,15 +220,22 @@ fn attempt_keychain_migration(data_dir: &Path) {
     }
 }
 
+/// The PepTrack folder in the OS data directory, where the database, key
+/// file and settings files are kept
+pub(crate) fn app_data_dir() -> Result<PathBuf> {
+    Ok(data_dir()
+        .context("Unable to determine OS data directory")?
+        .join("PepTrack"))
+}
+
 fn resolve_data_dir() -> Result<PathBuf> {
-    let mut dir = data_dir().context("Unable to determine OS data directory")?;
-    dir.push("PepTrack");
+    let dir = app_data_dir()?;
     std::fs::create_dir_all(&dir).context("Unable to create PepTrack data dir")?;
     Ok(dir)
 }
 
 fn ensure_key_material(dir: &Path) -> Result<Vec<u8>> {
-    let key_path = dir.join("peptrack.key");
+    let key_path = dir.join(KEY_FILE_NAME);
     if let Ok(raw) = std::fs::read_to_string(&key_path) {
         let bytes = hex::decode(raw.trim()).context("Stored encryption key is corrupted")?;
         return Ok(bytes);
@@ -126,3 +246,77 @@ fn ensure_key_material(dir: &Path) -> Result<Vec<u8>> {
     std::fs::write(&key_path, hex::encode(&bytes)).context("Unable to persist encryption key")?;
     Ok(bytes)
 }
+
+/// Save a rotated key where [`select_key_provider`] will find it
+///
+/// On macOS this is the Keychain when available, keeping any file backup in
+/// step; otherwise the key file.
+pub fn store_key(data_dir: &Path, key: &KeyMaterial) -> Result<KeyLocation> {
+    #[cfg(target_os = "macos")]
+    match KeychainKeyProvider::new().and_then(|provider| provider.replace_key(key)) {
+        Ok(()) => {
+            if data_dir.join(KEY_FILE_NAME).exists() {
+                write_key_file(&data_dir.join(KEY_FILE_NAME), key)?;
+            }
+            return Ok(KeyLocation::Keychain);
+        }
+        Err(err) => warn!("Keychain unavailable, saving rotated key to file: {err:#}"),
+    }
+
+    write_key_file(&data_dir.join(KEY_FILE_NAME), key)?;
+    Ok(KeyLocation::File)
+}
+
+/// Write the key for a rotation that's about to start
+pub fn write_pending_key(data_dir: &Path, key: &KeyMaterial) -> Result<()> {
+    write_key_file(&data_dir.join(PENDING_KEY_FILE_NAME), key)
+}
+
+pub fn remove_pending_key(data_dir: &Path) -> Result<()> {
+    let path = data_dir.join(PENDING_KEY_FILE_NAME);
+    if path.exists() {
+        std::fs::remove_file(&path).context("Unable to remove pending encryption key")?;
+    }
+    Ok(())
+}
+
+/// Finish a key rotation that was interrupted before its key was saved
+///
+/// Returns `true` if the pending key opens the database and is now in use.
+fn recover_pending_key(
+    data_dir: &Path,
+    storage: &StorageManager,
+    key_provider: &PassphraseKeyProvider,
+) -> Result<bool> {
+    let pending_path = data_dir.join(PENDING_KEY_FILE_NAME);
+    let Ok(raw) = std::fs::read_to_string(&pending_path) else {
+        return Ok(false);
+    };
+
+    let key = KeyMaterial::new(hex::decode(raw.trim()).context("Pending encryption key is corrupted")?)?;
+    key_provider.unlock_with_key(key.clone());
+
+    if !storage.verify_key()? {
+        // The rotation never committed; the old key is still the right one
+        info!("Discarding key from a rotation that did not complete");
+        if key_provider.is_configured() {
+            key_provider.lock()?;
+        }
+        remove_pending_key(data_dir)?;
+        return Ok(false);
+    }
+
+    store_key(data_dir, &key)?;
+    if key_provider.is_configured() {
+        key_provider.remove_passphrase()?;
+    }
+    remove_pending_key(data_dir)?;
+    Ok(true)
+}
+
+fn write_key_file(path: &Path, key: &KeyMaterial) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, hex::encode(key.to_key_bytes()?)).context("Unable to persist encryption key")?;
+    std::fs::rename(&tmp, path).context("Unable to persist encryption key")?;
+    Ok(())
+}
@@ -0,0 +1,188 @@
+//! Tray icon and global shortcuts
+//!
+//! The tray menu runs the everyday actions without opening the window: log
+//! the last dose again, snooze the next reminder and back up now. The same
+//! commands back them, so the results match doing it in the app. Each
+//! outcome is shown as a system notification and emitted as
+//! [`QUICK_ACTION_EVENT`] so an open window can refresh.
+
+use std::sync::Arc;
+
+use peptrack_core::DoseUnit;
+use serde::Serialize;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{App, AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+use tauri_plugin_notification::NotificationExt;
+use tracing::{info, warn};
+
+use crate::commands::doses::repeat_last_dose;
+use crate::commands::scheduler_v2::{trigger_manual_backup, SchedulerState};
+use crate::commands::schedules::{snooze_next, ReminderSnoozes};
+use crate::state::AppState;
+
+/// Emitted with a [`QuickActionResult`] after a tray or shortcut action
+pub const QUICK_ACTION_EVENT: &str = "quick-action";
+
+const LOG_LAST_DOSE_ID: &str = "log-last-dose";
+const SNOOZE_REMINDER_ID: &str = "snooze-reminder";
+const BACKUP_NOW_ID: &str = "backup-now";
+const SHOW_WINDOW_ID: &str = "show-window";
+const QUIT_ID: &str = "quit";
+
+#[cfg(target_os = "macos")]
+const PRIMARY_MODIFIER: Modifiers = Modifiers::SUPER;
+#[cfg(not(target_os = "macos"))]
+const PRIMARY_MODIFIER: Modifiers = Modifiers::CONTROL;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum QuickAction {
+    LogLastDose,
+    SnoozeReminder,
+    BackupNow,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickActionResult {
+    pub action: QuickAction,
+    pub success: bool,
+    pub message: String,
+}
+
+/// Cmd/Ctrl + Alt + L logs the last dose again
+fn log_last_dose_shortcut() -> Shortcut {
+    Shortcut::new(Some(PRIMARY_MODIFIER | Modifiers::ALT), Code::KeyL)
+}
+
+/// Cmd/Ctrl + Alt + P brings the window to the front
+fn show_window_shortcut() -> Shortcut {
+    Shortcut::new(Some(PRIMARY_MODIFIER | Modifiers::ALT), Code::KeyP)
+}
+
+/// Adds the tray icon and registers the global shortcuts
+pub fn init(app: &App) -> tauri::Result<()> {
+    let menu = Menu::with_items(
+        app,
+        &[
+            &MenuItem::with_id(app, LOG_LAST_DOSE_ID, "Log last dose again", true, None::<&str>)?,
+            &MenuItem::with_id(app, SNOOZE_REMINDER_ID, "Snooze next reminder", true, None::<&str>)?,
+            &MenuItem::with_id(app, BACKUP_NOW_ID, "Back up now", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, SHOW_WINDOW_ID, "Show PepTrack", true, None::<&str>)?,
+            &MenuItem::with_id(app, QUIT_ID, "Quit", true, None::<&str>)?,
+        ],
+    )?;
+
+    let mut tray = TrayIconBuilder::with_id("main")
+        .tooltip("PepTrack")
+        .menu(&menu)
+        .show_menu_on_left_click(true)
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+
+    // A shortcut taken by another app shouldn't stop PepTrack from starting
+    let shortcuts = tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, shortcut, event| {
+            if event.state() != ShortcutState::Pressed {
+                return;
+            }
+            if *shortcut == log_last_dose_shortcut() {
+                run_quick_action(app, QuickAction::LogLastDose);
+            } else if *shortcut == show_window_shortcut() {
+                show_main_window(app);
+            }
+        })
+        .build();
+    if let Err(e) = app.handle().plugin(shortcuts) {
+        warn!("Failed to set up global shortcuts: {}", e);
+        return Ok(());
+    }
+    for shortcut in [log_last_dose_shortcut(), show_window_shortcut()] {
+        if let Err(e) = app.global_shortcut().register(shortcut) {
+            warn!("Failed to register global shortcut {}: {}", shortcut, e);
+        }
+    }
+    Ok(())
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        LOG_LAST_DOSE_ID => run_quick_action(app, QuickAction::LogLastDose),
+        SNOOZE_REMINDER_ID => run_quick_action(app, QuickAction::SnoozeReminder),
+        BACKUP_NOW_ID => run_quick_action(app, QuickAction::BackupNow),
+        SHOW_WINDOW_ID => show_main_window(app),
+        // Goes through the backup-on-close like closing the window
+        QUIT_ID => app.exit(0),
+        _ => {}
+    }
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        window.show().ok();
+        window.unminimize().ok();
+        window.set_focus().ok();
+    }
+}
+
+fn run_quick_action(app: &AppHandle, action: QuickAction) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let outcome = perform(&app, action).await;
+        let result = QuickActionResult {
+            action,
+            success: outcome.is_ok(),
+            message: outcome.unwrap_or_else(|e| e),
+        };
+        info!("Quick action {:?}: {}", action, result.message);
+
+        // Backups already report how they went
+        if action != QuickAction::BackupNow {
+            let title = if result.success { "PepTrack" } else { "PepTrack: action failed" };
+            app.notification().builder().title(title).body(&result.message).show().ok();
+        }
+        if let Err(e) = app.emit(QUICK_ACTION_EVENT, &result) {
+            warn!("Failed to report quick action: {}", e);
+        }
+    });
+}
+
+/// Runs the action and describes how it went
+async fn perform(app: &AppHandle, action: QuickAction) -> Result<String, String> {
+    let state = app.state::<Arc<AppState>>();
+    match action {
+        QuickAction::LogLastDose => {
+            let dose = repeat_last_dose(&state).await.map_err(|e| e.message)?;
+            Ok(format!(
+                "Logged {} {} at {}",
+                dose.display_amount,
+                unit_label(dose.display_unit),
+                dose.log.site
+            ))
+        }
+        QuickAction::SnoozeReminder => {
+            let snoozed = snooze_next(&state, &app.state::<ReminderSnoozes>(), None)
+                .await
+                .map_err(|e| e.message)?;
+            Ok(format!("Snoozed the {} reminder", snoozed.protocol_name))
+        }
+        QuickAction::BackupNow => trigger_manual_backup(app.state::<SchedulerState>(), state)
+            .await
+            .map(|_| "Backup complete".to_string())
+            .map_err(|e| e.message),
+    }
+}
+
+fn unit_label(unit: DoseUnit) -> &'static str {
+    match unit {
+        DoseUnit::Mg => "mg",
+        DoseUnit::Mcg => "mcg",
+        DoseUnit::Iu => "IU",
+    }
+}